@@ -0,0 +1,235 @@
+use std::borrow::{Borrow, BorrowMut};
+use std::ops::{Deref, DerefMut};
+
+use collab::preclude::types::text::YChange;
+use collab::preclude::{
+  Array, ArrayRef, Collab, Delta, In, Map, MapExt, MapPrelim, MapRef, Out, ReadTxn, Text, TextRef,
+  TransactionMut,
+};
+use collab_entity::define::{CHAT, CHAT_ID, CHAT_MESSAGES};
+use collab_entity::CollabType;
+
+use crate::error::ChatError;
+use crate::message::{
+  message_content_from_map, message_from_map, message_id_from_map, set_message_metadata,
+  ChatMessage,
+};
+
+/// A conversation. The messages are stored in an array in the order they were sent.
+pub struct Chat {
+  collab: Collab,
+  body: ChatBody,
+}
+
+impl Chat {
+  /// Opens an existing chat. Returns [ChatError::NoRequiredData] if the collab is not a chat.
+  pub fn open(collab: Collab) -> Result<Self, ChatError> {
+    CollabType::Chat.validate_require_data(&collab)?;
+    let body = ChatBody::try_open(&collab).ok_or(ChatError::NoRequiredData)?;
+    Ok(Self { collab, body })
+  }
+
+  pub fn create(mut collab: Collab, chat_id: &str) -> Result<Self, ChatError> {
+    let body = ChatBody::new(&mut collab, chat_id);
+    Ok(Self { collab, body })
+  }
+
+  pub fn close(&self) {
+    self.collab.remove_all_plugins();
+  }
+
+  pub fn get_chat_id(&self) -> String {
+    let txn = self.collab.transact();
+    self.body.get_chat_id(&txn).unwrap_or_default()
+  }
+
+  /// Appends the message to the chat. The id of the message must be unique in the chat.
+  pub fn add_message(&mut self, message: ChatMessage) -> Result<(), ChatError> {
+    let mut txn = self.collab.transact_mut();
+    self.body.add_message(&mut txn, message)
+  }
+
+  pub fn get_message(&self, message_id: &str) -> Option<ChatMessage> {
+    let txn = self.collab.transact();
+    let (_, map) = self.body.find_message(&txn, message_id)?;
+    message_from_map(&txn, &map)
+  }
+
+  /// Returns all the messages, in the order they were sent.
+  pub fn get_all_messages(&self) -> Vec<ChatMessage> {
+    let txn = self.collab.transact();
+    self.body.get_all_messages(&txn)
+  }
+
+  pub fn messages_count(&self) -> u32 {
+    let txn = self.collab.transact();
+    self.body.messages.len(&txn)
+  }
+
+  /// Appends the text to the content of the message. It's used to stream the answer of the AI.
+  pub fn append_message_content(&mut self, message_id: &str, text: &str) -> Result<(), ChatError> {
+    let mut txn = self.collab.transact_mut();
+    let content = self.body.message_content(&txn, message_id)?;
+    content.push(&mut txn, text);
+    Ok(())
+  }
+
+  /// Applies the delta to the content of the message.
+  pub fn apply_message_delta(
+    &mut self,
+    message_id: &str,
+    delta: Vec<Delta<In>>,
+  ) -> Result<(), ChatError> {
+    let mut txn = self.collab.transact_mut();
+    let content = self.body.message_content(&txn, message_id)?;
+    content.apply_delta(&mut txn, delta);
+    Ok(())
+  }
+
+  /// Returns the content of the message as a delta.
+  pub fn get_message_delta(&self, message_id: &str) -> Result<Vec<Delta>, ChatError> {
+    let txn = self.collab.transact();
+    let content = self.body.message_content(&txn, message_id)?;
+    Ok(
+      content
+        .diff(&txn, YChange::identity)
+        .into_iter()
+        .map(|diff| Delta::Inserted(diff.insert, diff.attributes))
+        .collect(),
+    )
+  }
+
+  /// Sets the metadata value of the message, or removes it if `value` is None.
+  pub fn set_message_metadata(
+    &mut self,
+    message_id: &str,
+    key: &str,
+    value: Option<String>,
+  ) -> Result<(), ChatError> {
+    let mut txn = self.collab.transact_mut();
+    let (_, map) = self
+      .body
+      .find_message(&txn, message_id)
+      .ok_or_else(|| ChatError::MessageNotFound(message_id.to_string()))?;
+    set_message_metadata(&mut txn, &map, key, value);
+    Ok(())
+  }
+
+  /// Removes the message and returns it.
+  pub fn remove_message(&mut self, message_id: &str) -> Option<ChatMessage> {
+    let mut txn = self.collab.transact_mut();
+    self.body.remove_message(&mut txn, message_id)
+  }
+}
+
+impl Deref for Chat {
+  type Target = Collab;
+
+  fn deref(&self) -> &Self::Target {
+    &self.collab
+  }
+}
+
+impl DerefMut for Chat {
+  fn deref_mut(&mut self) -> &mut Self::Target {
+    &mut self.collab
+  }
+}
+
+impl Borrow<Collab> for Chat {
+  #[inline]
+  fn borrow(&self) -> &Collab {
+    &self.collab
+  }
+}
+
+impl BorrowMut<Collab> for Chat {
+  fn borrow_mut(&mut self) -> &mut Collab {
+    &mut self.collab
+  }
+}
+
+pub struct ChatBody {
+  container: MapRef,
+  messages: ArrayRef,
+}
+
+impl ChatBody {
+  pub fn new(collab: &mut Collab, chat_id: &str) -> Self {
+    let mut txn = collab.context.transact_mut();
+    let container = collab.data.get_or_init_map(&mut txn, CHAT);
+    container.insert(&mut txn, CHAT_ID, chat_id);
+    let messages = container.get_or_init(&mut txn, CHAT_MESSAGES);
+    Self {
+      container,
+      messages,
+    }
+  }
+
+  pub fn try_open(collab: &Collab) -> Option<Self> {
+    let txn = collab.context.transact();
+    let container: MapRef = collab.data.get_with_txn(&txn, CHAT)?;
+    let messages = container.get_with_txn(&txn, CHAT_MESSAGES)?;
+    Some(Self {
+      container,
+      messages,
+    })
+  }
+
+  pub fn get_chat_id<T: ReadTxn>(&self, txn: &T) -> Option<String> {
+    self.container.get_with_txn(txn, CHAT_ID)
+  }
+
+  pub fn add_message(
+    &self,
+    txn: &mut TransactionMut,
+    message: ChatMessage,
+  ) -> Result<(), ChatError> {
+    if message.id.is_empty() {
+      return Err(ChatError::MessageIdIsEmpty);
+    }
+    if self.find_message(txn, &message.id).is_some() {
+      return Err(ChatError::MessageAlreadyExists(message.id));
+    }
+    self.messages.push_back(txn, MapPrelim::from(message));
+    Ok(())
+  }
+
+  pub fn get_all_messages<T: ReadTxn>(&self, txn: &T) -> Vec<ChatMessage> {
+    self
+      .messages
+      .iter(txn)
+      .flat_map(|value| match value {
+        Out::YMap(map) => message_from_map(txn, &map),
+        _ => None,
+      })
+      .collect()
+  }
+
+  pub fn remove_message(&self, txn: &mut TransactionMut, message_id: &str) -> Option<ChatMessage> {
+    let (index, map) = self.find_message(txn, message_id)?;
+    let message = message_from_map(txn, &map);
+    self.messages.remove(txn, index);
+    message
+  }
+
+  fn find_message<T: ReadTxn>(&self, txn: &T, message_id: &str) -> Option<(u32, MapRef)> {
+    self
+      .messages
+      .iter(txn)
+      .enumerate()
+      .find_map(|(index, value)| match value {
+        Out::YMap(map) if message_id_from_map(txn, &map).as_deref() == Some(message_id) => {
+          Some((index as u32, map))
+        },
+        _ => None,
+      })
+  }
+
+  fn message_content<T: ReadTxn>(&self, txn: &T, message_id: &str) -> Result<TextRef, ChatError> {
+    self
+      .find_message(txn, message_id)
+      .and_then(|(_, map)| message_content_from_map(txn, &map))
+      .ok_or_else(|| ChatError::MessageNotFound(message_id.to_string()))
+  }
+}
@@ -0,0 +1,27 @@
+use collab_entity::CollabValidateError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ChatError {
+  #[error(transparent)]
+  Internal(#[from] anyhow::Error),
+
+  #[error("Lack of chat required data")]
+  NoRequiredData,
+
+  #[error("The message id is empty")]
+  MessageIdIsEmpty,
+
+  #[error("The message already exists: {0}")]
+  MessageAlreadyExists(String),
+
+  #[error("The message is not found: {0}")]
+  MessageNotFound(String),
+}
+
+impl From<CollabValidateError> for ChatError {
+  fn from(error: CollabValidateError) -> Self {
+    match error {
+      CollabValidateError::NoRequiredData(_) => ChatError::NoRequiredData,
+    }
+  }
+}
@@ -0,0 +1,9 @@
+mod chat;
+mod error;
+mod message;
+
+pub mod core {
+  pub use crate::chat::*;
+  pub use crate::error::*;
+  pub use crate::message::*;
+}
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+use collab::preclude::{
+  Any, GetString, In, Map, MapExt, MapPrelim, MapRef, Out, ReadTxn, TextPrelim, TextRef,
+  TransactionMut,
+};
+use serde::{Deserialize, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
+
+const MESSAGE_ID: &str = "id";
+const MESSAGE_AUTHOR_UID: &str = "author_uid";
+const MESSAGE_AUTHOR_TYPE: &str = "author_type";
+const MESSAGE_CONTENT: &str = "content";
+const MESSAGE_CREATED_AT: &str = "created_at";
+const MESSAGE_METADATA: &str = "metadata";
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum ChatAuthorType {
+  Human = 0,
+  AI = 1,
+  System = 2,
+}
+
+impl From<i64> for ChatAuthorType {
+  fn from(value: i64) -> Self {
+    match value {
+      1 => ChatAuthorType::AI,
+      2 => ChatAuthorType::System,
+      _ => ChatAuthorType::Human,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ChatAuthor {
+  /// The uid of the user who wrote the message. It's 0 for the messages that are not written by
+  /// a user.
+  pub uid: i64,
+  pub author_type: ChatAuthorType,
+}
+
+impl ChatAuthor {
+  pub fn human(uid: i64) -> Self {
+    Self {
+      uid,
+      author_type: ChatAuthorType::Human,
+    }
+  }
+
+  pub fn ai() -> Self {
+    Self {
+      uid: 0,
+      author_type: ChatAuthorType::AI,
+    }
+  }
+}
+
+/// A message of the chat.
+///
+/// The content is stored as a text, so it can be edited with deltas and the answer of the AI can
+/// be streamed into it while it is generated.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+  pub id: String,
+  pub author: ChatAuthor,
+  pub content: String,
+  pub created_at: i64,
+  #[serde(default)]
+  pub metadata: HashMap<String, String>,
+}
+
+impl ChatMessage {
+  pub fn new(id: String, author: ChatAuthor, content: String) -> Self {
+    Self {
+      id,
+      author,
+      content,
+      created_at: chrono::Utc::now().timestamp(),
+      metadata: Default::default(),
+    }
+  }
+
+  pub fn with_metadata(mut self, key: &str, value: String) -> Self {
+    self.metadata.insert(key.to_string(), value);
+    self
+  }
+}
+
+impl From<ChatMessage> for MapPrelim {
+  fn from(message: ChatMessage) -> Self {
+    MapPrelim::from([
+      (MESSAGE_ID, In::from(message.id)),
+      (MESSAGE_AUTHOR_UID, Any::BigInt(message.author.uid).into()),
+      (
+        MESSAGE_AUTHOR_TYPE,
+        Any::BigInt(message.author.author_type as i64).into(),
+      ),
+      (MESSAGE_CONTENT, TextPrelim::new(message.content).into()),
+      (MESSAGE_CREATED_AT, Any::BigInt(message.created_at).into()),
+      (
+        MESSAGE_METADATA,
+        MapPrelim::from_iter(
+          message
+            .metadata
+            .into_iter()
+            .map(|(key, value)| (key, Any::from(value))),
+        )
+        .into(),
+      ),
+    ])
+  }
+}
+
+pub(crate) fn message_id_from_map<T: ReadTxn>(txn: &T, map: &MapRef) -> Option<String> {
+  map.get_with_txn(txn, MESSAGE_ID)
+}
+
+pub(crate) fn message_from_map<T: ReadTxn>(txn: &T, map: &MapRef) -> Option<ChatMessage> {
+  let id = message_id_from_map(txn, map)?;
+  let author = ChatAuthor {
+    uid: map
+      .get_with_txn(txn, MESSAGE_AUTHOR_UID)
+      .unwrap_or_default(),
+    author_type: map
+      .get_with_txn::<_, i64>(txn, MESSAGE_AUTHOR_TYPE)
+      .map(ChatAuthorType::from)
+      .unwrap_or(ChatAuthorType::Human),
+  };
+  let content = message_content_from_map(txn, map)
+    .map(|text| text.get_string(txn))
+    .unwrap_or_default();
+  let metadata = map
+    .get_with_txn::<_, MapRef>(txn, MESSAGE_METADATA)
+    .map(|metadata| {
+      metadata
+        .iter(txn)
+        .flat_map(|(key, value)| match value {
+          Out::Any(Any::String(value)) => Some((key.to_string(), value.to_string())),
+          _ => None,
+        })
+        .collect()
+    })
+    .unwrap_or_default();
+  Some(ChatMessage {
+    id,
+    author,
+    content,
+    created_at: map
+      .get_with_txn(txn, MESSAGE_CREATED_AT)
+      .unwrap_or_default(),
+    metadata,
+  })
+}
+
+pub(crate) fn message_content_from_map<T: ReadTxn>(txn: &T, map: &MapRef) -> Option<TextRef> {
+  map.get_with_txn(txn, MESSAGE_CONTENT)
+}
+
+/// Sets the metadata value of the message, or removes it if `value` is None.
+pub(crate) fn set_message_metadata(
+  txn: &mut TransactionMut,
+  map: &MapRef,
+  key: &str,
+  value: Option<String>,
+) {
+  let metadata = map.get_or_init_map(txn, MESSAGE_METADATA);
+  match value {
+    None => {
+      metadata.remove(txn, key);
+    },
+    Some(value) => {
+      metadata.insert(txn, key, value);
+    },
+  }
+}
@@ -0,0 +1,137 @@
+use collab::core::collab::DataSource;
+use collab::core::origin::CollabOrigin;
+use collab::preclude::{Collab, Delta, In};
+use collab_chat::core::{Chat, ChatAuthor, ChatAuthorType, ChatError, ChatMessage};
+use collab_entity::CollabType;
+
+fn create_chat(chat_id: &str) -> Chat {
+  let collab = Collab::new_with_origin(CollabOrigin::Empty, chat_id, vec![], false);
+  Chat::create(collab, chat_id).unwrap()
+}
+
+#[test]
+fn add_chat_message_test() {
+  let mut chat = create_chat("c1");
+  assert_eq!(chat.get_chat_id(), "c1");
+  chat
+    .add_message(ChatMessage::new(
+      "m1".to_string(),
+      ChatAuthor::human(1),
+      "What is a CRDT?".to_string(),
+    ))
+    .unwrap();
+  chat
+    .add_message(
+      ChatMessage::new("m2".to_string(), ChatAuthor::ai(), "".to_string())
+        .with_metadata("model", "gpt-4o".to_string()),
+    )
+    .unwrap();
+
+  let messages = chat.get_all_messages();
+  assert_eq!(messages.len(), 2);
+  assert_eq!(messages[0].content, "What is a CRDT?");
+  assert_eq!(messages[0].author, ChatAuthor::human(1));
+  assert_eq!(messages[1].author.author_type, ChatAuthorType::AI);
+  assert_eq!(messages[1].metadata.get("model").unwrap(), "gpt-4o");
+
+  assert!(matches!(
+    chat.add_message(ChatMessage::new(
+      "m1".to_string(),
+      ChatAuthor::ai(),
+      "".to_string()
+    )),
+    Err(ChatError::MessageAlreadyExists(_))
+  ));
+  assert!(matches!(
+    chat.add_message(ChatMessage::new(
+      "".to_string(),
+      ChatAuthor::ai(),
+      "".to_string()
+    )),
+    Err(ChatError::MessageIdIsEmpty)
+  ));
+}
+
+#[test]
+fn stream_chat_message_content_test() {
+  let mut chat = create_chat("c1");
+  chat
+    .add_message(ChatMessage::new(
+      "m1".to_string(),
+      ChatAuthor::ai(),
+      "".to_string(),
+    ))
+    .unwrap();
+  for chunk in ["A CRDT ", "is a data ", "structure."] {
+    chat.append_message_content("m1", chunk).unwrap();
+  }
+  assert_eq!(
+    chat.get_message("m1").unwrap().content,
+    "A CRDT is a data structure."
+  );
+
+  chat
+    .apply_message_delta(
+      "m1",
+      vec![
+        Delta::Retain(2, None),
+        Delta::Deleted(4),
+        Delta::Inserted(In::from("conflict-free type"), None),
+      ],
+    )
+    .unwrap();
+  assert_eq!(
+    chat.get_message("m1").unwrap().content,
+    "A conflict-free type is a data structure."
+  );
+  assert_eq!(chat.get_message_delta("m1").unwrap().len(), 1);
+
+  chat
+    .set_message_metadata("m1", "finish_reason", Some("stop".to_string()))
+    .unwrap();
+  assert_eq!(
+    chat
+      .get_message("m1")
+      .unwrap()
+      .metadata
+      .get("finish_reason")
+      .unwrap(),
+    "stop"
+  );
+  assert!(matches!(
+    chat.append_message_content("m2", "text"),
+    Err(ChatError::MessageNotFound(_))
+  ));
+
+  assert!(chat.remove_message("m1").is_some());
+  assert_eq!(chat.messages_count(), 0);
+}
+
+#[test]
+fn open_chat_test() {
+  let mut chat = create_chat("c1");
+  chat
+    .add_message(ChatMessage::new(
+      "m1".to_string(),
+      ChatAuthor::human(1),
+      "hello".to_string(),
+    ))
+    .unwrap();
+  let encoded = chat
+    .encode_collab_v1(|collab| CollabType::Chat.validate_require_data(collab))
+    .unwrap();
+
+  let collab = Collab::new_with_source(
+    CollabOrigin::Empty,
+    "c1",
+    DataSource::DocStateV1(encoded.doc_state.to_vec()),
+    vec![],
+    false,
+  )
+  .unwrap();
+  let chat = Chat::open(collab).unwrap();
+  assert_eq!(chat.get_message("m1").unwrap().content, "hello");
+
+  let collab = Collab::new_with_origin(CollabOrigin::Empty, "c2", vec![], false);
+  assert!(matches!(Chat::open(collab), Err(ChatError::NoRequiredData)));
+}
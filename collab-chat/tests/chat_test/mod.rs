@@ -0,0 +1 @@
+mod message_test;
@@ -0,0 +1 @@
+mod chat_test;
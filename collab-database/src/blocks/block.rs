@@ -3,14 +3,19 @@ use std::collections::HashMap;
 
 use collab_entity::CollabType;
 
+use crate::blocks::BlockCollab;
+use crate::database::stamp_unset_timestamps;
 use crate::error::DatabaseError;
+use crate::fields::CellRules;
 use crate::rows::{
-  default_database_row_data, meta_id_from_row_id, Cell, DatabaseRow, Row, RowChangeSender,
-  RowDetail, RowId, RowMeta, RowMetaKey, RowMetaUpdate, RowUpdate,
+  database_row_data_with_meta, meta_id_from_row_id, subscribe_derived_cells, Cell, DatabaseRow,
+  Row, RowChangeSender, RowDetail, RowId, RowMeta, RowMetaKey, RowMetaUpdate, RowUpdate,
 };
 use crate::views::RowOrder;
 use crate::workspace_database::DatabaseCollabService;
+use crate::workspace_database::{estimate_collab_size, next_access_tick, RowUsage};
 
+use collab::core::clock::ClockProvider;
 use collab::lock::RwLock;
 use collab::preclude::Collab;
 use futures::future::join_all;
@@ -27,38 +32,116 @@ pub enum BlockEvent {
   DidFetchRow(Vec<RowDetail>),
 }
 
+pub type BlockId = u64;
+
 /// Each [Block] contains a list of [DatabaseRow]s. Each [DatabaseRow] represents a row in the database.
-/// Currently, we only use one [Block] to manage all the rows in the database. In the future, we
-/// might want to split the rows into multiple [Block]s to improve performance.
+/// The rows of a database are sharded across multiple [Block]s by the [BlockMap](super::BlockMap),
+/// so a single block never caches more rows than its capacity.
 #[derive(Clone)]
 pub struct Block {
+  pub id: BlockId,
   database_id: String,
   collab_service: Arc<dyn DatabaseCollabService>,
   pub row_mem_cache: Arc<DashMap<RowId, Arc<RwLock<DatabaseRow>>>>,
+  row_usage: Arc<DashMap<RowId, RowUsage>>,
+  /// Keeps the ids of the rows assigned to the block, see [BlockCollab]. None until the blocks
+  /// are loaded by [crate::blocks::BlockMap::load].
+  collab: Option<BlockCollab>,
   pub notifier: Arc<Sender<BlockEvent>>,
   row_change_tx: Option<RowChangeSender>,
+  clock: Arc<dyn ClockProvider>,
+  cell_rules: Arc<CellRules>,
 }
 
 impl Block {
   pub fn new(
+    id: BlockId,
     database_id: String,
     collab_service: Arc<dyn DatabaseCollabService>,
     row_change_tx: Option<RowChangeSender>,
+    notifier: Arc<Sender<BlockEvent>>,
+    clock: Arc<dyn ClockProvider>,
+    cell_rules: Arc<CellRules>,
   ) -> Block {
-    let (notifier, _) = broadcast::channel(1000);
     Self {
+      id,
       database_id,
       collab_service,
       row_mem_cache: Arc::new(Default::default()),
-      notifier: Arc::new(notifier),
+      row_usage: Arc::new(Default::default()),
+      collab: None,
+      notifier,
       row_change_tx,
+      clock,
+      cell_rules,
     }
   }
 
+  pub fn with_collab(mut self, collab: BlockCollab) -> Self {
+    self.collab = Some(collab);
+    self
+  }
+
+  pub fn collab(&self) -> Option<&BlockCollab> {
+    self.collab.as_ref()
+  }
+
+  /// Return the number of rows cached in the block.
+  pub fn len(&self) -> usize {
+    self.row_mem_cache.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.row_mem_cache.is_empty()
+  }
+
   pub fn subscribe_event(&self) -> broadcast::Receiver<BlockEvent> {
     self.notifier.subscribe()
   }
 
+  /// Keep the row in memory, with its estimated size, see
+  /// [crate::workspace_database::DatabaseMemoryBudget].
+  fn cache_row(&self, row_id: RowId, database_row: DatabaseRow) -> Arc<RwLock<DatabaseRow>> {
+    let usage = RowUsage {
+      last_used: next_access_tick(),
+      bytes: estimate_collab_size(&database_row.collab),
+    };
+    let database_row = Arc::new(RwLock::from(database_row));
+    subscribe_derived_cells(&database_row);
+    self.row_usage.insert(row_id.clone(), usage);
+    self.row_mem_cache.insert(row_id, database_row.clone());
+    database_row
+  }
+
+  /// Mark the row as the most recently used one.
+  pub(crate) fn touch(&self, row_id: &RowId) {
+    if let Some(mut usage) = self.row_usage.get_mut(row_id) {
+      usage.last_used = next_access_tick();
+    }
+  }
+
+  /// Return the usage of the loaded rows.
+  pub(crate) fn row_usage(&self) -> Vec<(RowId, RowUsage)> {
+    self
+      .row_usage
+      .iter()
+      .map(|entry| (entry.key().clone(), *entry.value()))
+      .collect()
+  }
+
+  /// Remove the row from memory if it's not used outside of the block. It's loaded again the
+  /// next time it's accessed. Return true if the row is evicted.
+  pub(crate) fn evict_row(&self, row_id: &RowId) -> bool {
+    let is_evicted = self
+      .row_mem_cache
+      .remove_if(row_id, |_, row| Arc::strong_count(row) == 1)
+      .is_some();
+    if is_evicted {
+      self.row_usage.remove(row_id);
+    }
+    is_evicted
+  }
+
   pub async fn batch_load_rows(&self, row_ids: Vec<RowId>) -> Result<(), DatabaseError> {
     let cloned_notifier = self.notifier.clone();
     let mut row_on_disk_details = vec![];
@@ -74,10 +157,11 @@ impl Block {
         self.collab_service.clone(),
       ) {
         Ok(row_collab) => {
+          let row_collab = row_collab
+            .with_clock(self.clock.clone())
+            .with_cell_rules(self.cell_rules.clone());
           if let Some(row_detail) = RowDetail::from_collab(&row_collab) {
-            self
-              .row_mem_cache
-              .insert(row_id.clone(), Arc::new(RwLock::from(row_collab)));
+            self.cache_row(row_id.clone(), row_collab);
             row_on_disk_details.push(row_detail);
           }
         },
@@ -106,8 +190,25 @@ impl Block {
     row_orders
   }
 
+  /// Create the row, stamping its created and modified times with the clock of the database if
+  /// they are unset.
   pub async fn create_new_row<T: Into<Row>>(&self, row: T) -> Result<RowOrder, DatabaseError> {
-    let row = row.into();
+    self.create_new_row_with_meta(row, None).await
+  }
+
+  /// Same as [Block::create_new_row], with the meta of the row written in its initial state.
+  pub async fn create_new_row_with_meta<T: Into<Row>>(
+    &self,
+    row: T,
+    meta: Option<RowMeta>,
+  ) -> Result<RowOrder, DatabaseError> {
+    let mut row = row.into();
+    self.cell_rules.derive_cells(&mut row.cells);
+    stamp_unset_timestamps(
+      &mut row.created_at,
+      &mut row.modified_at,
+      self.clock.as_ref(),
+    );
     let row_id = row.id.clone();
     let row_order = RowOrder {
       id: row.id.clone(),
@@ -122,7 +223,7 @@ impl Block {
       }
     }
 
-    let encoded_collab = default_database_row_data(&row_id, row);
+    let encoded_collab = database_row_data_with_meta(&row_id, row, meta);
     let collab = self
       .collab_service
       .build_collab(
@@ -137,33 +238,36 @@ impl Block {
       collab,
       self.row_change_tx.clone(),
       self.collab_service.clone(),
-    )?;
+    )?
+    .with_clock(self.clock.clone())
+    .with_cell_rules(self.cell_rules.clone());
 
-    let database_row = Arc::new(RwLock::from(database_row));
     if let Some(persistence) = self.collab_service.persistence() {
-      if let Ok(encoded_collab) = database_row.write().await.encoded_collab() {
+      if let Ok(encoded_collab) = database_row.encoded_collab() {
         persistence.save_collab(&row_id, encoded_collab)?;
       }
     }
-    self.row_mem_cache.insert(row_id, database_row);
+    self.cache_row(row_id, database_row);
     Ok(row_order)
   }
 
   pub async fn get_database_row(&self, row_id: &RowId) -> Option<Arc<RwLock<DatabaseRow>>> {
+    self.touch(row_id);
     self
       .row_mem_cache
       .get(row_id)
       .map(|entry| entry.value().clone())
   }
 
+  /// The rows are loaded again if they were evicted, see [Block::evict_row].
   pub async fn get_row_meta(&self, row_id: &RowId) -> Option<RowMeta> {
-    let database_row = self.get_database_row(row_id).await?;
+    let database_row = self.get_or_init_database_row(row_id).await.ok()?;
     let read_guard = database_row.read().await;
     read_guard.get_row_meta()
   }
 
   pub async fn get_cell(&self, row_id: &RowId, field_id: &str) -> Option<Cell> {
-    let database_row = self.get_database_row(row_id).await?;
+    let database_row = self.get_or_init_database_row(row_id).await.ok()?;
     let read_guard = database_row.read().await;
     read_guard.get_cell(field_id)
   }
@@ -197,6 +301,7 @@ impl Block {
 
   pub fn delete_row(&self, row_id: &RowId) -> Option<Arc<RwLock<DatabaseRow>>> {
     let row = self.row_mem_cache.remove(row_id).map(|(_, row)| row);
+    self.row_usage.remove(row_id);
     if let Some(persistence) = self.collab_service.persistence() {
       if let Err(err) = persistence.delete_collab(row_id) {
         error!("Can't delete the row from disk: {:?}", err);
@@ -209,7 +314,7 @@ impl Block {
   where
     F: FnOnce(RowUpdate),
   {
-    match self.get_database_row(&row_id).await {
+    match self.get_or_init_database_row(&row_id).await.ok() {
       None => {
         error!(
           "fail to update row. the database row is not created: {:?}",
@@ -225,6 +330,9 @@ impl Block {
           if let Some((_, row_data)) = self.row_mem_cache.remove(&row_id) {
             self.row_mem_cache.insert(new_row_id.clone(), row_data);
           };
+          if let Some((_, usage)) = self.row_usage.remove(&row_id) {
+            self.row_usage.insert(new_row_id.clone(), usage);
+          }
         }
       },
     }
@@ -234,8 +342,7 @@ impl Block {
   where
     F: FnOnce(RowMetaUpdate),
   {
-    let database_row = self.row_mem_cache.get(row_id);
-    match database_row {
+    match self.get_or_init_database_row(row_id).await.ok() {
       None => {
         trace!(
           "fail to update row meta. the row is not in the cache: {:?}",
@@ -254,6 +361,7 @@ impl Block {
     &self,
     row_id: &RowId,
   ) -> Result<Arc<RwLock<DatabaseRow>>, DatabaseError> {
+    self.touch(row_id);
     let value = self
       .row_mem_cache
       .get(row_id)
@@ -315,6 +423,7 @@ impl Block {
     // Initialize final database rows by combining cached and newly fetched rows
     let mut database_rows = Vec::with_capacity(row_ids.len());
     for row_id in row_ids {
+      self.touch(&row_id);
       if let Some(cached_row) = self.row_mem_cache.get(&row_id) {
         database_rows.push(cached_row.value().clone());
       } else if let Some(new_row) = uncached_rows.get(&row_id) {
@@ -325,6 +434,48 @@ impl Block {
     Ok(database_rows)
   }
 
+  /// Return the rows that exist, in the order of the given ids, without caching the rows that
+  /// are not loaded yet.
+  pub async fn read_rows(&self, row_ids: &[RowId]) -> Result<Vec<Row>, DatabaseError> {
+    let uncached_row_ids: Vec<String> = row_ids
+      .iter()
+      .filter(|id| !self.row_mem_cache.contains_key(id))
+      .map(|id| id.to_string())
+      .collect();
+    let encoded_collab_by_id = self
+      .collab_service
+      .get_collabs(uncached_row_ids, CollabType::DatabaseRow)
+      .await?;
+
+    let mut rows = Vec::with_capacity(row_ids.len());
+    for row_id in row_ids {
+      self.touch(row_id);
+      let cached_row = self
+        .row_mem_cache
+        .get(row_id)
+        .map(|entry| entry.value().clone());
+      let row = match cached_row {
+        Some(database_row) => database_row.read().await.get_row(),
+        None => match encoded_collab_by_id.get(row_id.as_str()) {
+          None => continue,
+          Some(encoded_collab) => {
+            let collab = self
+              .collab_service
+              .build_collab(
+                row_id,
+                CollabType::DatabaseRow,
+                Some((encoded_collab.clone(), false)),
+              )
+              .await?;
+            DatabaseRow::open(row_id.clone(), collab, None, self.collab_service.clone())?.get_row()
+          },
+        },
+      };
+      rows.push(row.unwrap_or_else(|| Row::empty(row_id.clone(), &self.database_id)));
+    }
+    Ok(rows)
+  }
+
   pub async fn init_database_row(
     &self,
     row_id: RowId,
@@ -347,10 +498,11 @@ impl Block {
       collab,
       self.row_change_tx.clone(),
       self.collab_service.clone(),
-    )?;
+    )?
+    .with_clock(self.clock.clone())
+    .with_cell_rules(self.cell_rules.clone());
     let row_details = RowDetail::from_collab(&database_row);
-    let database_row = Arc::new(RwLock::from(database_row));
-    self.row_mem_cache.insert(row_id, database_row.clone());
+    let database_row = self.cache_row(row_id, database_row);
     if let Some(row_detail) = row_details {
       let _ = self
         .notifier
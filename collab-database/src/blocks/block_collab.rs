@@ -0,0 +1,95 @@
+use std::sync::{Arc, Mutex};
+
+use collab::preclude::{Collab, Map, MapExt, MapRef, ReadTxn};
+use collab_entity::define::DATABASE_BLOCK_ROWS;
+use uuid::Uuid;
+
+use crate::blocks::BlockId;
+use crate::rows::RowId;
+
+/// The number of blocks of the database, only kept by the first block.
+const NUM_OF_BLOCKS: &str = "num_of_blocks";
+
+/// Return the object id of the collab of the block. The id is derived from the database id, so
+/// the blocks of a database are found without an index.
+pub fn block_collab_id(database_id: &str, block_id: BlockId) -> String {
+  let name = format!("block:{}", block_id);
+  match Uuid::parse_str(database_id) {
+    Ok(database_id) => Uuid::new_v5(&database_id, name.as_bytes()).to_string(),
+    Err(_) => format!("{}:{}", database_id, name),
+  }
+}
+
+/// The [CollabType::DatabaseBlock](collab_entity::CollabType::DatabaseBlock) collab of a
+/// [crate::blocks::Block]. It keeps the ids of the rows assigned to the block in
+/// `{ rows: { row_id: true } }`, so the assignment of the rows survives a restart, and the update
+/// history of a block only grows with its own rows.
+#[derive(Clone)]
+pub struct BlockCollab {
+  collab: Arc<Mutex<Collab>>,
+}
+
+impl BlockCollab {
+  /// Wrap the collab of the block. The map of the rows is created the first time the block is
+  /// opened, so an empty block passes the validation of its collab type.
+  pub fn new(mut collab: Collab) -> Self {
+    let has_rows = rows_map(&collab, &collab.transact()).is_some();
+    if !has_rows {
+      let collab = &mut collab;
+      let mut txn = collab.context.transact_mut();
+      collab.data.get_or_init_map(&mut txn, DATABASE_BLOCK_ROWS);
+    }
+    Self {
+      collab: Arc::new(Mutex::new(collab)),
+    }
+  }
+
+  pub fn row_ids(&self) -> Vec<RowId> {
+    let collab = self.collab.lock().unwrap();
+    let txn = collab.transact();
+    match rows_map(&collab, &txn) {
+      None => vec![],
+      Some(rows) => rows
+        .keys(&txn)
+        .map(|key| RowId::from(key.to_string()))
+        .collect(),
+    }
+  }
+
+  pub fn insert_row(&self, row_id: &RowId) {
+    let mut collab = self.collab.lock().unwrap();
+    let collab = &mut *collab;
+    let mut txn = collab.context.transact_mut();
+    let rows = collab.data.get_or_init_map(&mut txn, DATABASE_BLOCK_ROWS);
+    rows.insert(&mut txn, row_id.as_str(), true);
+  }
+
+  pub fn remove_row(&self, row_id: &RowId) {
+    let mut collab = self.collab.lock().unwrap();
+    let collab = &mut *collab;
+    let mut txn = collab.context.transact_mut();
+    let rows = collab.data.get_or_init_map(&mut txn, DATABASE_BLOCK_ROWS);
+    rows.remove(&mut txn, row_id.as_str());
+  }
+
+  /// Return the number of blocks recorded by the first block.
+  pub fn num_of_blocks(&self) -> Option<usize> {
+    let collab = self.collab.lock().unwrap();
+    let txn = collab.transact();
+    let num_of_blocks: i64 = collab.data.get_with_txn(&txn, NUM_OF_BLOCKS)?;
+    usize::try_from(num_of_blocks).ok()
+  }
+
+  pub fn set_num_of_blocks(&self, num_of_blocks: usize) {
+    let mut collab = self.collab.lock().unwrap();
+    let collab = &mut *collab;
+    let mut txn = collab.context.transact_mut();
+    collab
+      .data
+      .insert(&mut txn, NUM_OF_BLOCKS, num_of_blocks as i64);
+  }
+}
+
+fn rows_map<T: ReadTxn>(collab: &Collab, txn: &T) -> Option<MapRef> {
+  collab.data.get_with_txn(txn, DATABASE_BLOCK_ROWS)
+}
@@ -0,0 +1,557 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock as SyncRwLock};
+
+use collab::core::clock::ClockProvider;
+use collab::lock::RwLock;
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use tokio::sync::broadcast;
+use tracing::{error, instrument, trace};
+
+use crate::blocks::{block_collab_id, Block, BlockCollab, BlockEvent, BlockId};
+use crate::error::DatabaseError;
+use crate::fields::CellRules;
+use crate::rows::{
+  Cell, DatabaseRow, Row, RowChangeSender, RowId, RowMeta, RowMetaUpdate, RowUpdate,
+};
+use crate::views::RowOrder;
+use crate::workspace_database::DatabaseCollabService;
+use crate::workspace_database::RowUsage;
+use collab_entity::CollabType;
+
+/// The default number of rows of a [Block].
+pub const DEFAULT_BLOCK_CAPACITY: usize = 10_000;
+
+/// Shards the rows of a database across [Block]s. A row is assigned to a block the first time it's
+/// created or loaded, and all the calls for that row are routed to its block afterwards.
+///
+/// New rows go to the last block until it holds `capacity` rows, then a new block is opened. The
+/// room left by a row removed from a full block is not reused.
+///
+/// Once loaded with [BlockMap::load], each block is backed by its own [BlockCollab] that keeps
+/// the ids of its rows, so the assignment survives a restart.
+///
+/// A database created before the blocks has no block collab. Its first load opens an empty first
+/// block, and its rows are assigned to the blocks as they are loaded, in the order they are loaded,
+/// like new rows. The assignment is written to the block collabs at that point, so the migration
+/// happens once, row by row, and needs no separate step. Until a row is loaded, it's read from the
+/// open block, see [BlockMap::read_rows].
+pub struct BlockMap {
+  database_id: String,
+  collab_service: Arc<dyn DatabaseCollabService>,
+  row_change_tx: Option<RowChangeSender>,
+  capacity: usize,
+  clock: Arc<dyn ClockProvider>,
+  cell_rules: Arc<CellRules>,
+  notifier: Arc<broadcast::Sender<BlockEvent>>,
+  shards: SyncRwLock<BlockShards>,
+  row_blocks: DashMap<RowId, BlockId>,
+}
+
+struct BlockShards {
+  /// The id of a block is its index.
+  blocks: Vec<Block>,
+  /// The number of rows assigned to the last block.
+  open_block_len: usize,
+}
+
+impl BlockMap {
+  pub fn new(
+    database_id: String,
+    collab_service: Arc<dyn DatabaseCollabService>,
+    row_change_tx: Option<RowChangeSender>,
+    capacity: usize,
+    clock: Arc<dyn ClockProvider>,
+    cell_rules: Arc<CellRules>,
+  ) -> Self {
+    let (notifier, _) = broadcast::channel(1000);
+    let notifier = Arc::new(notifier);
+    let block = Block::new(
+      0,
+      database_id.clone(),
+      collab_service.clone(),
+      row_change_tx.clone(),
+      notifier.clone(),
+      clock.clone(),
+      cell_rules.clone(),
+    );
+    Self {
+      database_id,
+      collab_service,
+      row_change_tx,
+      capacity: capacity.max(1),
+      clock,
+      cell_rules,
+      notifier,
+      shards: SyncRwLock::new(BlockShards {
+        blocks: vec![block],
+        open_block_len: 0,
+      }),
+      row_blocks: DashMap::new(),
+    }
+  }
+
+  pub fn capacity(&self) -> usize {
+    self.capacity
+  }
+
+  /// Return the number of blocks, including the block that receives the new rows.
+  pub fn num_blocks(&self) -> usize {
+    self.shards.read().unwrap().blocks.len()
+  }
+
+  /// Return the id of the block the row is assigned to, or None if the row is not created or
+  /// loaded yet.
+  pub fn block_id_of_row(&self, row_id: &RowId) -> Option<BlockId> {
+    self.row_blocks.get(row_id).map(|entry| *entry.value())
+  }
+
+  pub fn subscribe_event(&self) -> broadcast::Receiver<BlockEvent> {
+    self.notifier.subscribe()
+  }
+
+  /// Open the collabs of the blocks and route the rows to the blocks they were assigned to. It's
+  /// called once, before any row is routed. For a database without block collabs, it creates the
+  /// collab of the first block, see the migration in [BlockMap].
+  pub async fn load(&self) -> Result<(), DatabaseError> {
+    let first = self.build_block_collab(0).await?;
+    let num_of_blocks = first.num_of_blocks().unwrap_or(1).max(1);
+    let mut collabs = vec![first];
+    for block_id in 1..num_of_blocks {
+      collabs.push(self.build_block_collab(block_id as BlockId).await?);
+    }
+
+    let mut shards = self.shards.write().unwrap();
+    let mut blocks = Vec::with_capacity(collabs.len());
+    let mut open_block_len = 0;
+    for (block_id, collab) in collabs.into_iter().enumerate() {
+      let block_id = block_id as BlockId;
+      let row_ids = collab.row_ids();
+      open_block_len = row_ids.len();
+      for row_id in row_ids {
+        self.row_blocks.entry(row_id).or_insert(block_id);
+      }
+      blocks.push(self.new_block(block_id).with_collab(collab));
+    }
+    trace!(
+      "load {} blocks of database {}",
+      blocks.len(),
+      self.database_id
+    );
+    shards.blocks = blocks;
+    shards.open_block_len = open_block_len;
+    Ok(())
+  }
+
+  async fn build_block_collab(&self, block_id: BlockId) -> Result<BlockCollab, DatabaseError> {
+    let collab = self
+      .collab_service
+      .build_collab(
+        &block_collab_id(&self.database_id, block_id),
+        CollabType::DatabaseBlock,
+        None,
+      )
+      .await?;
+    Ok(BlockCollab::new(collab))
+  }
+
+  fn new_block(&self, block_id: BlockId) -> Block {
+    Block::new(
+      block_id,
+      self.database_id.clone(),
+      self.collab_service.clone(),
+      self.row_change_tx.clone(),
+      self.notifier.clone(),
+      self.clock.clone(),
+      self.cell_rules.clone(),
+    )
+  }
+
+  pub async fn create_rows<T>(&self, rows: Vec<T>) -> Vec<RowOrder>
+  where
+    T: Into<Row> + Send,
+  {
+    let mut row_orders = Vec::with_capacity(rows.len());
+    for row in rows {
+      if let Ok(row_order) = self.create_new_row(row).await {
+        row_orders.push(row_order);
+      }
+    }
+    row_orders
+  }
+
+  pub async fn create_new_row<T: Into<Row>>(&self, row: T) -> Result<RowOrder, DatabaseError> {
+    self.create_new_row_with_meta(row, None).await
+  }
+
+  /// Same as [Self::create_new_row], with the meta of the row written in its initial state.
+  pub async fn create_new_row_with_meta<T: Into<Row>>(
+    &self,
+    row: T,
+    meta: Option<RowMeta>,
+  ) -> Result<RowOrder, DatabaseError> {
+    let row = row.into();
+    let row_id = row.id.clone();
+    let (block, is_new) = self.route_or_assign(&row_id).await?;
+    let result = block.create_new_row_with_meta(row, meta).await;
+    self.commit_assignment(&block, &row_id, is_new, result.is_ok());
+    result
+  }
+
+  /// Return the row if it's loaded. Use [Self::get_or_init_database_row] to load it.
+  pub fn get_cached_row(&self, row_id: &RowId) -> Option<Arc<RwLock<DatabaseRow>>> {
+    let block = self.route(row_id)?;
+    block.touch(row_id);
+    block
+      .row_mem_cache
+      .get(row_id)
+      .map(|entry| entry.value().clone())
+  }
+
+  /// Return the row if it was created or loaded, loading it again if it was evicted from memory.
+  pub async fn get_or_reload_database_row(
+    &self,
+    row_id: &RowId,
+  ) -> Option<Arc<RwLock<DatabaseRow>>> {
+    self
+      .route(row_id)?
+      .get_or_init_database_row(row_id)
+      .await
+      .ok()
+  }
+
+  /// Return the usage of the loaded rows of all the blocks.
+  pub(crate) fn row_usage(&self) -> Vec<(RowId, RowUsage)> {
+    self
+      .blocks()
+      .iter()
+      .flat_map(|block| block.row_usage())
+      .collect()
+  }
+
+  /// Return the estimated size in bytes of the loaded rows of all the blocks.
+  pub(crate) fn cached_row_bytes(&self) -> usize {
+    self.row_usage().iter().map(|(_, usage)| usage.bytes).sum()
+  }
+
+  /// Return the last time a row of the blocks was used, see
+  /// [crate::workspace_database::DatabaseMemoryBudget].
+  pub(crate) fn last_used(&self) -> u64 {
+    self
+      .row_usage()
+      .iter()
+      .map(|(_, usage)| usage.last_used)
+      .max()
+      .unwrap_or(0)
+  }
+
+  /// Remove the row from memory if it's not in use. It keeps its block and it's loaded again the
+  /// next time it's accessed. Return true if the row is evicted.
+  pub(crate) fn evict_row(&self, row_id: &RowId) -> bool {
+    self
+      .route(row_id)
+      .is_some_and(|block| block.evict_row(row_id))
+  }
+
+  /// Return the ids of the loaded rows of all the blocks.
+  pub fn cached_row_ids(&self) -> Vec<RowId> {
+    self
+      .blocks()
+      .iter()
+      .flat_map(|block| {
+        block
+          .row_mem_cache
+          .iter()
+          .map(|entry| entry.key().clone())
+          .collect::<Vec<_>>()
+      })
+      .collect()
+  }
+
+  /// Return the number of loaded rows of all the blocks.
+  pub fn cached_row_count(&self) -> usize {
+    self.blocks().iter().map(|block| block.len()).sum()
+  }
+
+  /// Return the loaded rows of all the blocks.
+  pub fn cached_rows(&self) -> Vec<Arc<RwLock<DatabaseRow>>> {
+    self
+      .blocks()
+      .iter()
+      .flat_map(|block| {
+        block
+          .row_mem_cache
+          .iter()
+          .map(|entry| entry.value().clone())
+          .collect::<Vec<_>>()
+      })
+      .collect()
+  }
+
+  pub async fn get_database_row(&self, row_id: &RowId) -> Option<Arc<RwLock<DatabaseRow>>> {
+    self.get_cached_row(row_id)
+  }
+
+  pub async fn get_row_meta(&self, row_id: &RowId) -> Option<RowMeta> {
+    self.route(row_id)?.get_row_meta(row_id).await
+  }
+
+  pub async fn get_cell(&self, row_id: &RowId, field_id: &str) -> Option<Cell> {
+    self.route(row_id)?.get_cell(row_id, field_id).await
+  }
+
+  pub fn get_row_document_id(&self, row_id: &RowId) -> Option<String> {
+    self.open_block().get_row_document_id(row_id)
+  }
+
+  /// Return the rows of the row orders that exist, loading the rows that are not loaded yet.
+  #[instrument(level = "debug", skip_all)]
+  pub async fn get_rows_from_row_orders(&self, row_orders: &[RowOrder]) -> Vec<Row> {
+    let mut rows = Vec::new();
+
+    let row_ids: Vec<RowId> = row_orders.iter().map(|order| order.id.clone()).collect();
+    if let Ok(database_rows) = self.init_database_rows(row_ids).await {
+      for database_row in database_rows {
+        let read_guard = database_row.read().await;
+        let row_id = read_guard.row_id.clone();
+        let row = read_guard
+          .get_row()
+          .unwrap_or_else(|| Row::empty(row_id, &self.database_id));
+        rows.push(row);
+      }
+    }
+
+    rows
+  }
+
+  pub fn delete_row(&self, row_id: &RowId) -> Option<Arc<RwLock<DatabaseRow>>> {
+    // A row that is not loaded is still removed from the disk.
+    let block = self.route(row_id).unwrap_or_else(|| self.open_block());
+    let row = block.delete_row(row_id);
+    self.unassign(row_id);
+    row
+  }
+
+  pub async fn update_row<F>(&self, row_id: RowId, f: F)
+  where
+    F: FnOnce(RowUpdate),
+  {
+    let database_row = match self.route(&row_id) {
+      None => None,
+      Some(block) => block
+        .get_or_init_database_row(&row_id)
+        .await
+        .ok()
+        .map(|row| (block, row)),
+    };
+    let (mut block, database_row) = match database_row {
+      None => {
+        error!(
+          "fail to update row. the database row is not created: {:?}",
+          row_id
+        );
+        return;
+      },
+      Some(value) => value,
+    };
+    block.update_row(row_id.clone(), f).await;
+
+    // if row_id is updated, the row is routed with its new id
+    let new_row_id = database_row.read().await.row_id.clone();
+    if new_row_id != row_id {
+      self.row_blocks.remove(&row_id);
+      self.row_blocks.insert(new_row_id.clone(), block.id);
+      if let Some(collab) = block.collab() {
+        collab.remove_row(&row_id);
+        collab.insert_row(&new_row_id);
+      }
+    }
+  }
+
+  pub async fn update_row_meta<F>(&self, row_id: &RowId, f: F)
+  where
+    F: FnOnce(RowMetaUpdate),
+  {
+    match self.route(row_id) {
+      None => {
+        trace!(
+          "fail to update row meta. the row is not in the cache: {:?}",
+          row_id
+        )
+      },
+      Some(mut block) => block.update_row_meta(row_id, f).await,
+    }
+  }
+
+  /// Get the [DatabaseRow] from the cache of its block. If the row is not in the cache,
+  /// initialize it.
+  #[instrument(level = "debug", skip_all)]
+  pub async fn get_or_init_database_row(
+    &self,
+    row_id: &RowId,
+  ) -> Result<Arc<RwLock<DatabaseRow>>, DatabaseError> {
+    let (block, is_new) = self.route_or_assign(row_id).await?;
+    let result = block.get_or_init_database_row(row_id).await;
+    self.commit_assignment(&block, row_id, is_new, result.is_ok());
+    result
+  }
+
+  /// Initialize the rows that are not loaded yet. Return the rows that exist, in the order of the
+  /// given ids.
+  pub async fn init_database_rows(
+    &self,
+    row_ids: Vec<RowId>,
+  ) -> Result<Vec<Arc<RwLock<DatabaseRow>>>, DatabaseError> {
+    let mut row_ids_by_block: BTreeMap<BlockId, (Block, Vec<(RowId, bool)>)> = BTreeMap::new();
+    for row_id in &row_ids {
+      let (block, is_new) = self.route_or_assign(row_id).await?;
+      row_ids_by_block
+        .entry(block.id)
+        .or_insert_with(|| (block, vec![]))
+        .1
+        .push((row_id.clone(), is_new));
+    }
+
+    let mut rows_by_id = HashMap::with_capacity(row_ids.len());
+    for (block, block_row_ids) in row_ids_by_block.into_values() {
+      let result = block
+        .init_database_rows(block_row_ids.iter().map(|(id, _)| id.clone()).collect())
+        .await;
+      for (row_id, is_new) in block_row_ids {
+        let row = block
+          .row_mem_cache
+          .get(&row_id)
+          .map(|row| row.value().clone());
+        // A row that doesn't exist doesn't take a place in the block.
+        self.commit_assignment(&block, &row_id, is_new, row.is_some());
+        if let Some(row) = row {
+          rows_by_id.insert(row_id, row);
+        }
+      }
+      result?;
+    }
+
+    Ok(
+      row_ids
+        .iter()
+        .filter_map(|row_id| rows_by_id.get(row_id).cloned())
+        .collect(),
+    )
+  }
+
+  /// Return the rows that exist, in the order of the given ids. The rows that are not loaded yet
+  /// are read without being cached or assigned to a block.
+  pub async fn read_rows(&self, row_ids: &[RowId]) -> Result<Vec<Row>, DatabaseError> {
+    let mut row_ids_by_block: BTreeMap<BlockId, (Block, Vec<RowId>)> = BTreeMap::new();
+    for row_id in row_ids {
+      let block = self.route(row_id).unwrap_or_else(|| self.open_block());
+      row_ids_by_block
+        .entry(block.id)
+        .or_insert_with(|| (block, vec![]))
+        .1
+        .push(row_id.clone());
+    }
+
+    let mut rows_by_id = HashMap::with_capacity(row_ids.len());
+    for (block, block_row_ids) in row_ids_by_block.into_values() {
+      for row in block.read_rows(&block_row_ids).await? {
+        rows_by_id.insert(row.id.clone(), row);
+      }
+    }
+    Ok(
+      row_ids
+        .iter()
+        .filter_map(|row_id| rows_by_id.remove(row_id))
+        .collect(),
+    )
+  }
+
+  fn blocks(&self) -> Vec<Block> {
+    self.shards.read().unwrap().blocks.clone()
+  }
+
+  fn open_block(&self) -> Block {
+    let shards = self.shards.read().unwrap();
+    shards.blocks[shards.blocks.len() - 1].clone()
+  }
+
+  fn route(&self, row_id: &RowId) -> Option<Block> {
+    let block_id = self.block_id_of_row(row_id)?;
+    let shards = self.shards.read().unwrap();
+    shards.blocks.get(block_id as usize).cloned()
+  }
+
+  /// Return the block of the row, and true if the row was not assigned yet. A row that is not
+  /// assigned yet is assigned to the last block, opening a new block if the last one is full. The
+  /// assignment is kept in memory until it's committed with [Self::commit_assignment].
+  async fn route_or_assign(&self, row_id: &RowId) -> Result<(Block, bool), DatabaseError> {
+    if let Some(block) = self.route(row_id) {
+      return Ok((block, false));
+    }
+
+    // The collab of the next block is opened before taking the lock, if the last block is full.
+    let next_block_collab = {
+      let shards = self.shards.read().unwrap();
+      let is_loaded = shards.blocks[0].collab().is_some();
+      (is_loaded && shards.open_block_len >= self.capacity).then_some(shards.blocks.len())
+    };
+    let next_block_collab = match next_block_collab {
+      None => None,
+      Some(block_id) => {
+        let block_id = block_id as BlockId;
+        Some((block_id, self.build_block_collab(block_id).await?))
+      },
+    };
+
+    let mut shards = self.shards.write().unwrap();
+    match self.row_blocks.entry(row_id.clone()) {
+      Entry::Occupied(entry) => Ok((shards.blocks[*entry.get() as usize].clone(), false)),
+      Entry::Vacant(entry) => {
+        if shards.open_block_len >= self.capacity {
+          let block_id = shards.blocks.len() as BlockId;
+          let mut block = self.new_block(block_id);
+          if let Some((_, collab)) = next_block_collab.filter(|(id, _)| *id == block_id) {
+            block = block.with_collab(collab);
+          }
+          trace!("open block {} of database {}", block.id, self.database_id);
+          shards.blocks.push(block);
+          shards.open_block_len = 0;
+          if let Some(collab) = shards.blocks[0].collab() {
+            collab.set_num_of_blocks(shards.blocks.len());
+          }
+        }
+        shards.open_block_len += 1;
+        let block = shards.blocks[shards.blocks.len() - 1].clone();
+        entry.insert(block.id);
+        Ok((block, true))
+      },
+    }
+  }
+
+  /// Write the new assignment of the row to the collab of its block if the row exists, or undo it
+  /// otherwise.
+  fn commit_assignment(&self, block: &Block, row_id: &RowId, is_new: bool, is_existing: bool) {
+    if !is_new {
+      return;
+    }
+    if is_existing {
+      if let Some(collab) = block.collab() {
+        collab.insert_row(row_id);
+      }
+    } else {
+      self.unassign(row_id);
+    }
+  }
+
+  fn unassign(&self, row_id: &RowId) {
+    let mut shards = self.shards.write().unwrap();
+    if let Some((_, block_id)) = self.row_blocks.remove(row_id) {
+      if block_id as usize == shards.blocks.len() - 1 {
+        shards.open_block_len = shards.open_block_len.saturating_sub(1);
+      }
+      if let Some(collab) = shards.blocks[block_id as usize].collab() {
+        collab.remove_row(row_id);
+      }
+    }
+  }
+}
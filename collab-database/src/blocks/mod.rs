@@ -1,3 +1,7 @@
 pub use block::*;
+pub use block_collab::*;
+pub use block_map::*;
 
 mod block;
+mod block_collab;
+mod block_map;
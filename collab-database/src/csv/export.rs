@@ -0,0 +1,198 @@
+use std::io;
+
+use collab::util::AnyMapExt;
+use futures::StreamExt;
+
+use crate::database::Database;
+use crate::entity::FieldType;
+use crate::error::DatabaseError;
+use crate::fields::checkbox_type_option::{
+  is_checkbox_checked, CHECKBOX_CHECKED, CHECKBOX_UNCHECKED,
+};
+use crate::fields::formula_type_option::FormulaTypeOption;
+use crate::fields::timestamp_type_option::TimestampTypeOption;
+use crate::fields::{stringify_type_option, Field, StringifyTypeOption};
+use crate::rows::{Cell, Row};
+use crate::template::entity::CELL_DATA;
+
+/// The options of [Database::export_csv].
+#[derive(Debug, Clone)]
+pub struct CSVExportOptions {
+  /// Write the names of the fields as the first record.
+  pub include_header: bool,
+  /// Export the rows hidden in the view, see [Database::hide_row].
+  pub include_hidden_rows: bool,
+  /// Export only these fields, in the order of the view. All the fields are exported if None.
+  pub field_ids: Option<Vec<String>>,
+  pub delimiter: u8,
+}
+
+impl Default for CSVExportOptions {
+  fn default() -> Self {
+    Self {
+      include_header: true,
+      include_hidden_rows: false,
+      field_ids: None,
+      delimiter: b',',
+    }
+  }
+}
+
+impl CSVExportOptions {
+  pub fn with_header(mut self, include_header: bool) -> Self {
+    self.include_header = include_header;
+    self
+  }
+
+  pub fn with_hidden_rows(mut self, include_hidden_rows: bool) -> Self {
+    self.include_hidden_rows = include_hidden_rows;
+    self
+  }
+
+  pub fn with_field_ids(mut self, field_ids: Vec<String>) -> Self {
+    self.field_ids = Some(field_ids);
+    self
+  }
+
+  pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+    self.delimiter = delimiter;
+    self
+  }
+}
+
+/// Turns the cells of a field into the text of a CSV record.
+struct CSVColumn {
+  field_id: String,
+  field_type: FieldType,
+  stringify: Option<Box<dyn StringifyTypeOption>>,
+  formula: Option<FormulaTypeOption>,
+}
+
+impl CSVColumn {
+  fn new(field: &Field) -> Self {
+    let field_type = FieldType::from(field.field_type);
+    let type_option = field.get_any_type_option(field_type.type_id());
+    let stringify = match field_type {
+      // The timestamps are read from the row, not from a cell.
+      FieldType::CreatedTime | FieldType::LastEditedTime => Some(Box::new(
+        type_option
+          .map(TimestampTypeOption::from)
+          .unwrap_or_else(|| TimestampTypeOption::new(field_type.clone())),
+      )
+        as Box<dyn StringifyTypeOption>),
+      _ => type_option.and_then(|type_option| stringify_type_option(type_option, &field_type)),
+    };
+    let formula = (field_type == FieldType::Formula).then(|| {
+      field
+        .get_type_option::<FormulaTypeOption>(FieldType::Formula.type_id())
+        .unwrap_or_default()
+    });
+    Self {
+      field_id: field.id.clone(),
+      field_type,
+      stringify,
+      formula,
+    }
+  }
+
+  fn stringify(&self, row: &Row) -> String {
+    let timestamp = match self.field_type {
+      FieldType::CreatedTime => Some(row.created_at),
+      FieldType::LastEditedTime => Some(row.modified_at),
+      _ => None,
+    };
+    if let Some(timestamp) = timestamp {
+      let text = timestamp.to_string();
+      return match &self.stringify {
+        Some(stringify) => stringify.stringify_text(&text),
+        None => text,
+      };
+    }
+
+    let computed: Option<Cell> = self
+      .formula
+      .as_ref()
+      .map(|formula| formula.compute_cell(&row.cells));
+    let text = match computed.as_ref().or_else(|| row.cells.get(&self.field_id)) {
+      None => "".to_string(),
+      Some(cell) => match &self.stringify {
+        Some(stringify) => stringify.stringify_cell(cell),
+        None => cell.get_as::<String>(CELL_DATA).unwrap_or_default(),
+      },
+    };
+    if self.field_type == FieldType::Checkbox {
+      return stringify_checkbox(&text).to_string();
+    }
+    text
+  }
+}
+
+impl Database {
+  /// Export the rows of the view as CSV, in the order of the fields and the rows of the view.
+  /// The cells are written as they are shown, for example the names of the select options
+  /// instead of their ids and the formatted dates instead of the timestamps.
+  pub async fn export_csv(
+    &self,
+    view_id: &str,
+    options: CSVExportOptions,
+  ) -> Result<String, DatabaseError> {
+    let mut buffer = vec![];
+    self
+      .export_csv_to_writer(view_id, options, &mut buffer)
+      .await?;
+    String::from_utf8(buffer).map_err(|err| DatabaseError::Internal(err.into()))
+  }
+
+  /// Same as [Database::export_csv] but the records are written to the writer one row at a time.
+  pub async fn export_csv_to_writer<W: io::Write>(
+    &self,
+    view_id: &str,
+    options: CSVExportOptions,
+    writer: W,
+  ) -> Result<(), DatabaseError> {
+    if self.get_view(view_id).is_none() {
+      return Err(DatabaseError::DatabaseViewNotExist);
+    }
+    let fields = self.get_fields_in_view(view_id, options.field_ids.clone());
+    let columns = fields.iter().map(CSVColumn::new).collect::<Vec<_>>();
+    let mut writer = ::csv::WriterBuilder::new()
+      .delimiter(options.delimiter)
+      .from_writer(writer);
+    if options.include_header {
+      writer
+        .write_record(fields.iter().map(|field| field.name.as_str()))
+        .map_err(csv_error)?;
+    }
+
+    let mut row_orders = self.get_row_orders_for_view(view_id);
+    if !options.include_hidden_rows {
+      let hidden_rows = self.get_hidden_rows(view_id);
+      row_orders.retain(|row_order| !hidden_rows.contains(&row_order.id));
+    }
+    let mut rows = Box::pin(self.get_rows_from_row_orders(&row_orders, None).await);
+    while let Some(row) = rows.next().await {
+      let row = row?;
+      writer
+        .write_record(columns.iter().map(|column| column.stringify(&row)))
+        .map_err(csv_error)?;
+    }
+    writer
+      .flush()
+      .map_err(|err| DatabaseError::Internal(err.into()))?;
+    Ok(())
+  }
+}
+
+/// The checked cells are all exported as `Yes`, see [is_checkbox_checked]. An empty cell is
+/// unchecked.
+fn stringify_checkbox(text: &str) -> &'static str {
+  if is_checkbox_checked(text) {
+    CHECKBOX_CHECKED
+  } else {
+    CHECKBOX_UNCHECKED
+  }
+}
+
+fn csv_error(err: ::csv::Error) -> DatabaseError {
+  DatabaseError::Internal(err.into())
+}
@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+use std::io;
+
+use crate::entity::{CreateDatabaseParams, FieldType};
+use crate::error::DatabaseError;
+use crate::template::csv::CSVTemplate;
+
+/// The options of [import_csv].
+#[derive(Debug, Clone)]
+pub struct CSVImportOptions {
+  /// Detect the field type of each column from its cells. The columns are imported as text if
+  /// false.
+  pub auto_field_type: bool,
+  /// The field types of the columns, by header. They take precedence over the detected ones.
+  pub field_types: HashMap<String, FieldType>,
+}
+
+impl Default for CSVImportOptions {
+  fn default() -> Self {
+    Self {
+      auto_field_type: true,
+      field_types: HashMap::new(),
+    }
+  }
+}
+
+impl CSVImportOptions {
+  pub fn with_auto_field_type(mut self, auto_field_type: bool) -> Self {
+    self.auto_field_type = auto_field_type;
+    self
+  }
+
+  pub fn with_field_type(mut self, header: &str, field_type: FieldType) -> Self {
+    self.field_types.insert(header.to_string(), field_type);
+    self
+  }
+}
+
+/// Parse the CSV into the params of a new database. The first record is the header, each
+/// header becomes a field and the first one is the primary field. Each following record becomes
+/// a row.
+pub async fn import_csv(
+  reader: impl io::Read,
+  options: CSVImportOptions,
+) -> Result<CreateDatabaseParams, DatabaseError> {
+  let mut template = CSVTemplate::try_from_reader(reader, options.auto_field_type, None)?;
+  for (header, field_type) in options.field_types {
+    if !template.set_field_type(&header, field_type) {
+      return Err(DatabaseError::InvalidCSV(format!(
+        "No column named {}",
+        header
+      )));
+    }
+  }
+  let template = template.try_into_database_template(None).await?;
+  Ok(template.into_params())
+}
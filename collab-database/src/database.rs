@@ -1,49 +1,90 @@
 use std::borrow::{Borrow, BorrowMut};
-use std::collections::HashMap;
-use std::fmt::Debug;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Debug, Display};
+use std::io;
 use std::ops::{Deref, DerefMut};
 
-use crate::blocks::{Block, BlockEvent};
+use crate::blocks::{BlockEvent, BlockMap, DEFAULT_BLOCK_CAPACITY};
+use crate::database_awareness::{
+  database_presences, subscribe_presence_change, DatabaseAwarenessState, DatabaseAwarenessUser,
+  DatabasePresenceChangeReceiver, EditingCell,
+};
+use crate::database_backlink::{relation_field_ids, row_relations, spawn_backlink_task};
+use crate::database_journal::{observe_journal_changes, spawn_journal_task, DatabaseJournal};
+use crate::database_search::{indexed_fields, row_index_content, spawn_search_index_task};
 use crate::database_state::DatabaseNotify;
+use crate::database_view_rows::{
+  spawn_view_rows_task, RowsChanged, ViewRowsQuery, ViewRowsSnapshot,
+};
 use crate::error::DatabaseError;
+use crate::fields::checklist_type_option::{
+  apply_checklist_changes, checklist_from_cell, ChecklistCellChange,
+};
+use crate::fields::formula_type_option::FormulaTypeOption;
+use crate::fields::media_type_option::{
+  apply_media_files, MediaCellData, MediaFile, MediaUploadState,
+};
+use crate::fields::relation_type_option::{
+  apply_relation_changes, PendingUnlink, PendingUnlinks, RelationCellData, RelationTypeOption,
+};
+use crate::fields::select_type_option::{
+  SelectOptionAudit, SelectOptionCleanup, SelectOptionCleanupSummary, SelectOptionIds,
+  SelectOptionUsage, SelectTypeOption,
+};
+use crate::fields::url_type_option::{URLCellData, URLMetadataProvider};
 use crate::fields::{
-  stringify_type_option, Field, FieldChangeReceiver, FieldMap, FieldUpdate, StringifyTypeOption,
+  stringify_type_option, ChecklistCellData, Field, FieldChangeReceiver, FieldMap,
+  FieldTypeConversion, FieldTypeConversionSummary, FieldTypeConverter, FieldUpdate,
+  StringifyTypeOption,
 };
 use crate::meta::MetaMap;
+use crate::record_span;
 use crate::rows::{
-  meta_id_from_row_id, CreateRowParams, CreateRowParamsValidator, DatabaseRow, Row, RowCell,
-  RowChangeReceiver, RowDetail, RowId, RowMeta, RowMetaKey, RowMetaUpdate, RowUpdate,
+  database_row_document_id_from_row_id, meta_id_from_row_id, render_row_document,
+  stringify_row_cell, Cell, Cells, CreateRowParams, CreateRowParamsValidator, DatabaseRow, Row,
+  RowCell, RowChangeReceiver, RowCreationHook, RowCreationHooks, RowDetail, RowDocument, RowId,
+  RowMeta, RowMetaKey, RowMetaUpdate, RowPage, RowPages, RowUpdate, CELL_FIELD_TYPE, CREATED_AT,
+  LAST_MODIFIED,
 };
 use crate::util::encoded_collab;
 use crate::views::define::DATABASE_VIEW_ROW_ORDERS;
 use crate::views::{
-  CalculationMap, DatabaseLayout, DatabaseViewUpdate, DatabaseViews, FieldOrder,
-  FieldSettingsByFieldIdMap, FieldSettingsMap, FilterMap, GroupSettingMap, LayoutSetting,
-  OrderArray, OrderObjectPosition, RowOrder, RowOrderArray, SortMap, ViewChangeReceiver,
+  fill_filter_map, find_filter_node, subscribe_calculation_change, CalculationChangeReceiver,
+  CalculationMap, DatabaseLayout, DatabaseViewUpdate, DatabaseViews, DuplicateRowPosition,
+  FieldOrder, FieldSettingsByFieldIdMap, FieldSettingsMap, FilterMap, FilterNode, FilterType,
+  GroupSettingMap, LayoutSetting, OrderArray, OrderObjectPosition, RowOrder, RowOrderArray,
+  SortMap, ViewChangeReceiver, FILTER_CHILDREN, FILTER_TYPE,
 };
 use crate::workspace_database::{
   DatabaseCollabService, DatabaseMeta, NoPersistenceDatabaseCollabService,
 };
 
 use crate::entity::{
-  CreateDatabaseParams, CreateViewParams, CreateViewParamsValidator, DatabaseView,
-  DatabaseViewMeta, EncodedCollabInfo, EncodedDatabase, FieldType,
+  CreateDatabaseParams, CreateDatabaseParamsValidator, CreateViewParams, CreateViewParamsValidator,
+  DatabaseView, DatabaseViewMeta, EncodeCursor, EncodedCollabInfo, EncodedDatabase, FieldType,
 };
 use crate::template::entity::DatabaseTemplate;
 
+use anyhow::anyhow;
+use collab::core::clock::{system_clock, ClockProvider};
+use collab::core::collab_backlink::{BacklinkRegistry, BacklinkType, LinkSource};
+use collab::core::collab_search::{IndexedContent, WorkspaceSearchIndex};
 use collab::core::origin::CollabOrigin;
 use collab::entity::EncodedCollab;
 use collab::lock::RwLock;
+use collab::preclude::block::ClientID;
 use collab::preclude::{
-  Any, Array, Collab, FillRef, JsonValue, Map, MapExt, MapPrelim, MapRef, ReadTxn, ToJson,
-  TransactionMut, YrsValue,
+  Any, Array, ArrayRef, Collab, FillRef, JsonValue, Map, MapExt, MapPrelim, MapRef, ReadTxn,
+  Subscription, ToJson, TransactionMut, YrsValue,
 };
 use collab::util::{AnyExt, ArrayExt};
+use collab_document::blocks::DocumentData;
+use collab_document::document::DocumentBody;
 use collab_entity::define::{DATABASE, DATABASE_ID, DATABASE_METAS};
 use collab_entity::CollabType;
 
-use futures::stream::StreamExt;
-use futures::{stream, Stream};
+use futures::stream::{StreamExt, TryStreamExt};
+use futures::{stream, Sink, SinkExt, Stream};
 use nanoid::nanoid;
 use rayon::iter::IntoParallelRefIterator;
 use rayon::iter::ParallelIterator;
@@ -51,6 +92,7 @@ use rayon::iter::ParallelIterator;
 use futures::future::join_all;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
 pub use tokio_stream::wrappers::WatchStream;
 use tokio_util::sync::CancellationToken;
 use tracing::{error, info, instrument, trace};
@@ -60,6 +102,10 @@ pub struct Database {
   pub collab: Collab,
   pub body: DatabaseBody,
   pub collab_service: Arc<dyn DatabaseCollabService>,
+  #[allow(dead_code)]
+  presence_subscription: Option<Subscription>,
+  #[allow(dead_code)]
+  calculation_subscription: Option<Subscription>,
 }
 impl Drop for Database {
   fn drop(&mut self) {
@@ -70,10 +116,22 @@ impl Drop for Database {
 
 const FIELDS: &str = "fields";
 const VIEWS: &str = "views";
+/// The number of rows loaded at a time by [Database::export_rows_jsonl].
+pub const EXPORT_ROWS_BATCH_SIZE: usize = 100;
 
 pub struct DatabaseContext {
   pub collab_service: Arc<dyn DatabaseCollabService>,
   pub notifier: DatabaseNotify,
+  /// The maximum number of rows of a block, see [BlockMap].
+  pub block_capacity: usize,
+  /// Stamps the created and modified times of the rows, cells, fields and views.
+  pub clock: Arc<dyn ClockProvider>,
+  /// Contribute the cells of the fields to the new rows.
+  pub row_creation_hooks: RowCreationHooks,
+  /// Checks the params of [Database::create_with_view].
+  pub create_database_validator: CreateDatabaseParamsValidator,
+  /// Receives the links back to the removed rows that are left in the other databases.
+  pub pending_unlinks: PendingUnlinks,
 }
 
 impl DatabaseContext {
@@ -81,8 +139,41 @@ impl DatabaseContext {
     Self {
       collab_service,
       notifier: DatabaseNotify::default(),
+      block_capacity: DEFAULT_BLOCK_CAPACITY,
+      clock: system_clock(),
+      row_creation_hooks: RowCreationHooks::default(),
+      create_database_validator: CreateDatabaseParamsValidator::default(),
+      pending_unlinks: PendingUnlinks::default(),
     }
   }
+
+  pub fn with_block_capacity(mut self, block_capacity: usize) -> Self {
+    self.block_capacity = block_capacity;
+    self
+  }
+
+  pub fn with_clock(mut self, clock: Arc<dyn ClockProvider>) -> Self {
+    self.clock = clock;
+    self
+  }
+
+  pub fn with_row_creation_hooks(mut self, row_creation_hooks: RowCreationHooks) -> Self {
+    self.row_creation_hooks = row_creation_hooks;
+    self
+  }
+
+  pub fn with_create_database_validator(
+    mut self,
+    validator: CreateDatabaseParamsValidator,
+  ) -> Self {
+    self.create_database_validator = validator;
+    self
+  }
+
+  pub fn with_pending_unlinks(mut self, pending_unlinks: PendingUnlinks) -> Self {
+    self.pending_unlinks = pending_unlinks;
+    self
+  }
 }
 
 pub async fn default_database_data(database_id: &str) -> Result<EncodedCollab, DatabaseError> {
@@ -110,11 +201,8 @@ impl Database {
       .await?;
     let collab_service = context.collab_service.clone();
     let (body, collab) = DatabaseBody::open(collab, context)?;
-    Ok(Self {
-      collab,
-      body,
-      collab_service,
-    })
+    body.blocks.load().await?;
+    Ok(Self::new(collab, body, collab_service))
   }
 
   pub async fn create(
@@ -140,11 +228,28 @@ impl Database {
     let collab_service = context.collab_service.clone();
     let (body, collab) =
       DatabaseBody::create(collab, database_id.to_string(), context, rows, fields).await?;
-    Ok(Self {
+    Ok(Self::new(collab, body, collab_service))
+  }
+
+  fn new(
+    collab: Collab,
+    body: DatabaseBody,
+    collab_service: Arc<dyn DatabaseCollabService>,
+  ) -> Self {
+    let presence_subscription = body.notifier.as_ref().map(|notifier| {
+      subscribe_presence_change(collab.get_awareness(), notifier.presence_change_tx.clone())
+    });
+    let calculation_subscription = body.notifier.as_ref().map(|notifier| {
+      let txn = collab.transact();
+      subscribe_calculation_change(&txn, &body.views, notifier.calculation_change_tx.clone())
+    });
+    Self {
       collab,
       body,
       collab_service,
-    })
+      presence_subscription,
+      calculation_subscription,
+    }
   }
 
   pub async fn create_with_template<T>(template: T) -> Result<Self, DatabaseError>
@@ -161,20 +266,22 @@ impl Database {
     .map_err(|e| DatabaseError::Internal(e.into()))??
     .into_params();
 
-    let context = DatabaseContext {
-      collab_service: Arc::new(NoPersistenceDatabaseCollabService),
-      notifier: Default::default(),
-    };
+    let context = DatabaseContext::new(Arc::new(NoPersistenceDatabaseCollabService));
     Self::create_with_view(params, context).await
   }
 
   /// Create a new database with the given [CreateDatabaseParams]
   /// The method will set the inline view id to the given view_id
   /// from the [CreateDatabaseParams].
+  /// The params are checked by the [DatabaseContext::create_database_validator] first.
   pub async fn create_with_view(
     params: CreateDatabaseParams,
     context: DatabaseContext,
   ) -> Result<Self, DatabaseError> {
+    let params = context
+      .create_database_validator
+      .validate(params)
+      .map_err(DatabaseError::InvalidCreateDatabaseParams)?;
     // Get or create empty database with the given database_id
     let CreateDatabaseParams {
       database_id,
@@ -208,6 +315,18 @@ impl Database {
     .map_err(|e| DatabaseError::Internal(e.into()))?
   }
 
+  #[cfg_attr(
+    feature = "trace_spans",
+    instrument(
+      level = "debug",
+      skip_all,
+      fields(
+        database_id = %self.collab.object_id(),
+        row_count = tracing::field::Empty,
+        payload_size = tracing::field::Empty,
+      )
+    )
+  )]
   pub async fn encode_database_collabs(&self) -> Result<EncodedDatabase, DatabaseError> {
     let database_id = self.collab.object_id().to_string();
     let encoded_database_collab = EncodedCollabInfo {
@@ -246,12 +365,129 @@ impl Database {
       tokio::task::yield_now().await;
     }
 
+    record_span!(
+      row_count = encoded_row_collabs.len(),
+      payload_size = encoded_database_collab.encoded_collab.doc_state.len()
+        + encoded_row_collabs
+          .iter()
+          .map(|info| info.encoded_collab.doc_state.len())
+          .sum::<usize>(),
+    );
     Ok(EncodedDatabase {
       encoded_database_collab,
       encoded_row_collabs,
     })
   }
 
+  /// Encodes the database collab followed by its rows, in the order of the inline view, and sends
+  /// them to the sink one at a time. Unlike [Self::encode_database_collabs], the encoded rows are
+  /// never collected, and the rows that are not loaded are opened only for the time of their
+  /// encoding, so the memory stays bounded for giant databases.
+  ///
+  /// The encoding starts after the given cursor, use [EncodeCursor::after] on the last collab that
+  /// was processed to resume. Returns the cursor of the last collab that was sent.
+  #[cfg_attr(
+    feature = "trace_spans",
+    instrument(
+      level = "debug",
+      skip_all,
+      fields(
+        database_id = %self.collab.object_id(),
+        row_count = tracing::field::Empty,
+        payload_size = tracing::field::Empty,
+      )
+    )
+  )]
+  pub async fn encode_streaming<S>(
+    &self,
+    cursor: EncodeCursor,
+    sink: &mut S,
+  ) -> Result<EncodeCursor, DatabaseError>
+  where
+    S: Sink<EncodedCollabInfo> + Unpin,
+    S::Error: Display,
+  {
+    let database_id = self.collab.object_id().to_string();
+    let row_orders = self.get_all_row_orders().await;
+    let mut cursor = cursor;
+    #[cfg(feature = "trace_spans")]
+    let (mut row_count, mut payload_size) = (0, 0);
+    let start = match &cursor.last_object_id {
+      None => {
+        let encoded_database_collab = EncodedCollabInfo {
+          object_id: database_id,
+          collab_type: CollabType::Database,
+          encoded_collab: encoded_collab(&self.collab, &CollabType::Database)?,
+        };
+        let next_cursor = EncodeCursor::after(&encoded_database_collab);
+        #[cfg(feature = "trace_spans")]
+        {
+          payload_size += encoded_database_collab.encoded_collab.doc_state.len();
+        }
+        send_encoded_collab(sink, encoded_database_collab).await?;
+        cursor = next_cursor;
+        0
+      },
+      Some(object_id) if *object_id == database_id => 0,
+      Some(object_id) => {
+        row_orders
+          .iter()
+          .position(|row_order| row_order.id.as_str() == object_id)
+          .ok_or_else(|| DatabaseError::DatabaseRowNotFound {
+            row_id: RowId::from(object_id.clone()),
+            reason: "the row of the cursor is not in the database".to_string(),
+          })?
+          + 1
+      },
+    };
+
+    for (index, row_order) in row_orders[start..].iter().enumerate() {
+      if let Some(encoded_row_collab) = self.encode_row_collab(&row_order.id).await {
+        let next_cursor = EncodeCursor::after(&encoded_row_collab);
+        #[cfg(feature = "trace_spans")]
+        {
+          row_count += 1;
+          payload_size += encoded_row_collab.encoded_collab.doc_state.len();
+        }
+        send_encoded_collab(sink, encoded_row_collab).await?;
+        cursor = next_cursor;
+      }
+
+      // Yield to the runtime after processing each chunk
+      if (index + 1) % 20 == 0 {
+        tokio::task::yield_now().await;
+      }
+    }
+    record_span!(row_count = row_count, payload_size = payload_size);
+    Ok(cursor)
+  }
+
+  /// Encodes the collab of the row. A row that is not loaded is not added to the cache.
+  async fn encode_row_collab(&self, row_id: &RowId) -> Option<EncodedCollabInfo> {
+    let encoded_collab = match self.body.blocks.get_cached_row(row_id) {
+      Some(database_row) => {
+        let read_guard = database_row.read().await;
+        encoded_collab(&read_guard.collab, &CollabType::DatabaseRow).ok()?
+      },
+      None => {
+        let collab = self
+          .collab_service
+          .build_collab(row_id, CollabType::DatabaseRow, None)
+          .await
+          .ok()?;
+        CollabType::DatabaseRow
+          .validate_require_data(&collab)
+          .ok()?;
+        encoded_collab(&collab, &CollabType::DatabaseRow).ok()?
+      },
+    };
+    Some(EncodedCollabInfo {
+      object_id: row_id.to_string(),
+      collab_type: CollabType::DatabaseRow,
+      encoded_collab,
+    })
+  }
+
   #[instrument(level = "info", skip_all, err)]
   pub fn write_to_disk(&self) -> Result<(), DatabaseError> {
     if let Some(persistence) = self.collab_service.persistence() {
@@ -259,13 +495,7 @@ impl Database {
       let mut encode_collabs = vec![];
       encode_collabs.push((self.collab.object_id().to_string(), database_encoded));
 
-      let rows = self
-        .body
-        .block
-        .row_mem_cache
-        .iter()
-        .map(|entry| entry.value().clone())
-        .collect::<Vec<_>>();
+      let rows = self.body.blocks.cached_rows();
 
       info!("[Database]: encode {} database rows", rows.len());
       let row_encodings = rows
@@ -315,7 +545,193 @@ impl Database {
   }
 
   pub fn subscribe_block_event(&self) -> tokio::sync::broadcast::Receiver<BlockEvent> {
-    self.body.block.subscribe_event()
+    self.body.blocks.subscribe_event()
+  }
+
+  /// Subscribe to the changes of the calculations of the views, whether they are local or remote.
+  pub fn subscribe_calculation_change(&self) -> Option<CalculationChangeReceiver> {
+    self
+      .body
+      .notifier
+      .as_ref()
+      .map(|notifier| notifier.calculation_change_tx.subscribe())
+  }
+
+  /// Subscribe to the presence of the remote users, see [Database::set_editing_cell].
+  pub fn subscribe_presence_change(&self) -> Option<DatabasePresenceChangeReceiver> {
+    self
+      .body
+      .notifier
+      .as_ref()
+      .map(|notifier| notifier.presence_change_tx.subscribe())
+  }
+
+  /// Set the local state of the awareness. It will override the previous state.
+  pub fn set_awareness_local_state(&mut self, state: DatabaseAwarenessState) {
+    if let Err(e) = self.collab.get_mut_awareness().set_local_state(state) {
+      error!("Failed to serialize DatabaseAwarenessState: {}", e);
+    }
+  }
+
+  pub fn get_awareness_local_state(&self) -> Option<DatabaseAwarenessState> {
+    self.collab.get_awareness().local_state()
+  }
+
+  /// Clean the local state of the awareness. It should be called when the database is closed.
+  pub fn clean_awareness_local_state(&mut self) {
+    self.collab.get_mut_awareness().clean_local_state()
+  }
+
+  /// Shares the cell the local user is focused on with the other users, or that the user stopped
+  /// editing when `cell` is None.
+  pub fn set_editing_cell(&mut self, user: DatabaseAwarenessUser, cell: Option<EditingCell>) {
+    let mut state = self
+      .get_awareness_local_state()
+      .unwrap_or_else(|| DatabaseAwarenessState::new(1, user.clone()));
+    state.user = user;
+    state.editing = cell;
+    state.timestamp = self.body.clock.timestamp();
+    self.set_awareness_local_state(state);
+  }
+
+  /// Return the states of the other users, keyed by client id.
+  pub fn get_remote_presences(&self) -> HashMap<ClientID, DatabaseAwarenessState> {
+    let awareness = self.collab.get_awareness();
+    let local_client_id = awareness.client_id();
+    let mut presences = database_presences(awareness);
+    presences.remove(&local_client_id);
+    presences
+  }
+
+  /// Adds the links of the relation cells of the rows to the registry and keeps them up to date
+  /// while the database is open. The fields are read once, so the relation fields created after
+  /// subscribing are not tracked.
+  pub async fn subscribe_backlinks(&self, registry: BacklinkRegistry) {
+    let database_id = self.get_database_id();
+    let relation_field_ids = relation_field_ids(self.get_all_fields());
+    registry.remove_object_links(&database_id, BacklinkType::Relation);
+    for row in self.collect_all_rows().await.into_iter().flatten() {
+      registry.set_links(
+        LinkSource::new(&database_id, &row.id),
+        BacklinkType::Relation,
+        row_relations(&row, &relation_field_ids),
+      );
+    }
+    let (row_change_rx, view_change_rx) =
+      match (self.subscribe_row_change(), self.subscribe_view_change()) {
+        (Some(row_change_rx), Some(view_change_rx)) => (row_change_rx, view_change_rx),
+        _ => return,
+      };
+    spawn_backlink_task(
+      database_id,
+      self.get_inline_view_id(),
+      relation_field_ids,
+      Arc::downgrade(&self.body.blocks),
+      row_change_rx,
+      view_change_rx,
+      registry,
+    );
+  }
+
+  /// Returns the text of the rows of the database, as they are added to a
+  /// [WorkspaceSearchIndex]. Only the cells of the text, url and select fields are indexed.
+  pub async fn index_content(&self) -> Vec<IndexedContent> {
+    let database_id = self.get_database_id();
+    let fields = indexed_fields(self.get_all_fields());
+    self
+      .collect_all_rows()
+      .await
+      .into_iter()
+      .flatten()
+      .flat_map(|row| row_index_content(&database_id, &row, &fields))
+      .collect()
+  }
+
+  /// Adds the rows to the index and keeps them up to date while the database is open. The fields
+  /// are read once, so the fields created after subscribing are not indexed.
+  pub async fn subscribe_search_index(&self, index: WorkspaceSearchIndex) {
+    let database_id = self.get_database_id();
+    index.index_object(&database_id, self.index_content().await);
+    let (row_change_rx, view_change_rx) =
+      match (self.subscribe_row_change(), self.subscribe_view_change()) {
+        (Some(row_change_rx), Some(view_change_rx)) => (row_change_rx, view_change_rx),
+        _ => return,
+      };
+    spawn_search_index_task(
+      database_id,
+      self.get_inline_view_id(),
+      indexed_fields(self.get_all_fields()),
+      Arc::downgrade(&self.body.blocks),
+      row_change_rx,
+      view_change_rx,
+      index,
+    );
+  }
+
+  /// Start recording the operations on the database in a [DatabaseJournal] that keeps the
+  /// last `capacity` entries. Return None if the changes of the database are not observed.
+  pub fn start_journal(&self, capacity: usize) -> Option<DatabaseJournal> {
+    let row_change_rx = self.subscribe_row_change()?;
+    let view_change_rx = self.subscribe_view_change()?;
+    let inline_view_id = self.get_inline_view_id();
+    let row_ids = self
+      .get_row_orders_for_view(&inline_view_id)
+      .into_iter()
+      .map(|row_order| row_order.id)
+      .collect();
+    let journal = DatabaseJournal::new(capacity, self.body.clock.clone());
+    spawn_journal_task(
+      &journal,
+      inline_view_id,
+      row_ids,
+      row_change_rx,
+      view_change_rx,
+    );
+    let txn = self.collab.transact();
+    let field_map: MapRef = self.body.root.get_with_txn(&txn, FIELDS)?;
+    observe_journal_changes(
+      &journal,
+      self.collab.origin().clone(),
+      &field_map,
+      &self.body.views,
+    );
+    Some(journal)
+  }
+
+  /// Subscribe to the visible rows of the view. The first [RowsChanged] inserts the rows that are
+  /// visible when subscribing, the next ones contain the changes of the visible rows caused by
+  /// the cell changes and the rows inserted, removed or moved in the view.
+  ///
+  /// The query is read when the rows change, so the caller subscribes again when the filters or
+  /// the sorts of the view change.
+  pub async fn subscribe_view_rows<Q: ViewRowsQuery>(
+    &self,
+    view_id: &str,
+    query: Q,
+  ) -> Option<impl Stream<Item = RowsChanged>> {
+    let row_orders = self.get_row_orders_for_view(view_id);
+    let row_change_rx = self.subscribe_row_change()?;
+    let view_change_rx = self.subscribe_view_change()?;
+    let rows = self
+      .get_rows_from_row_orders(&row_orders, None)
+      .await
+      .filter_map(|result| async { result.ok() })
+      .collect::<Vec<_>>()
+      .await;
+    let snapshot = ViewRowsSnapshot {
+      row_orders,
+      hidden_rows: self.get_hidden_rows(view_id),
+      rows,
+    };
+    let rx = spawn_view_rows_task(
+      view_id.to_string(),
+      snapshot,
+      query,
+      Arc::downgrade(&self.body.blocks),
+      row_change_rx,
+      view_change_rx,
+    );
+    Some(UnboundedReceiverStream::new(rx))
   }
 
   /// Return all field orders without order
@@ -356,9 +772,23 @@ impl Database {
   /// This row will be inserted to the end of rows of each view that
   /// reference the given database. Return the row order if the row is
   /// created successfully. Otherwise, return None.
+  #[cfg_attr(
+    feature = "trace_spans",
+    instrument(
+      level = "debug",
+      skip_all,
+      fields(
+        database_id = %self.collab.object_id(),
+        row_id = %params.id,
+        cell_count = params.cells.len(),
+      )
+    )
+  )]
   pub async fn create_row(&mut self, params: CreateRowParams) -> Result<RowOrder, DatabaseError> {
-    let params = CreateRowParamsValidator::validate(params)?;
-    let row_order = self.body.block.create_new_row(params).await?;
+    let mut params = CreateRowParamsValidator::validate(params, self.body.clock.as_ref())?;
+    self.validate_row_cells(&mut params)?;
+    self.apply_row_creation_hooks(&mut params);
+    let row_order = self.body.blocks.create_new_row(params).await?;
     let mut txn = self.collab.transact_mut();
     self
       .body
@@ -369,6 +799,14 @@ impl Database {
     Ok(row_order)
   }
 
+  #[cfg_attr(
+    feature = "trace_spans",
+    instrument(
+      level = "debug",
+      skip_all,
+      fields(database_id = %self.collab.object_id(), view_id = %view_id)
+    )
+  )]
   pub fn update_database_view<F>(&mut self, view_id: &str, f: F)
   where
     F: FnOnce(DatabaseViewUpdate),
@@ -377,6 +815,26 @@ impl Database {
     self.body.views.update_database_view(&mut txn, view_id, f);
   }
 
+  /// Hide the row in the view without deleting it. The hidden rows are not visible in
+  /// [Database::subscribe_view_rows].
+  pub fn hide_row(&mut self, view_id: &str, row_id: &RowId) {
+    self.update_database_view(view_id, |update| {
+      update.hide_row(row_id);
+    });
+  }
+
+  pub fn unhide_row(&mut self, view_id: &str, row_id: &RowId) {
+    self.update_database_view(view_id, |update| {
+      update.unhide_row(row_id);
+    });
+  }
+
+  /// Return the rows hidden in the view.
+  pub fn get_hidden_rows(&self, view_id: &str) -> HashSet<RowId> {
+    let txn = self.collab.transact();
+    self.body.views.get_hidden_rows(&txn, view_id)
+  }
+
   pub fn contains_row(&self, view_id: &str, row_id: &RowId) -> bool {
     let txn = self.collab.transact();
     if let Some(YrsValue::YMap(view)) = self.body.views.get(&txn, view_id) {
@@ -392,11 +850,27 @@ impl Database {
   /// Create a new row from the given view.
   /// This row will be inserted into corresponding [Block]. The [RowOrder] of this row will
   /// be inserted to each view.
+  #[cfg_attr(
+    feature = "trace_spans",
+    instrument(
+      level = "debug",
+      skip_all,
+      fields(
+        database_id = %self.collab.object_id(),
+        view_id = %view_id,
+        row_id = %params.id,
+        cell_count = params.cells.len(),
+      )
+    )
+  )]
   pub async fn create_row_in_view(
     &mut self,
     view_id: &str,
     params: CreateRowParams,
   ) -> Result<(usize, RowOrder), DatabaseError> {
+    let mut params = CreateRowParamsValidator::validate(params, self.body.clock.as_ref())?;
+    self.validate_row_cells(&mut params)?;
+    self.apply_row_creation_hooks(&mut params);
     let row_position = params.row_position.clone();
     let row_order = self.body.create_row(params).await?;
 
@@ -414,19 +888,51 @@ impl Database {
     Ok((index, row_order))
   }
 
+  /// Set the [RowCreationHook] of the field type, replacing the previous one.
+  pub fn register_row_creation_hook<T: RowCreationHook + 'static>(
+    &mut self,
+    field_type: FieldType,
+    hook: T,
+  ) {
+    self.body.row_creation_hooks.register(field_type, hook);
+  }
+
+  /// Validate the email and phone cells of the new row, see [crate::fields::CellRules].
+  fn validate_row_cells(&self, params: &mut CreateRowParams) -> Result<(), DatabaseError> {
+    let validators = self.body.fields.cell_rules().validators();
+    params.cells = validators.validate_cells(std::mem::take(&mut params.cells))?;
+    Ok(())
+  }
+
+  /// Add the cells contributed by the [RowCreationHooks] to the new row.
+  fn apply_row_creation_hooks(&mut self, params: &mut CreateRowParams) {
+    let mut txn = self.collab.transact_mut();
+    let fields = self.body.fields.get_all_fields(&txn);
+    let inline_view_id = self.body.get_inline_view_id(&txn);
+    let num_of_rows = self.body.views.get_row_orders(&txn, &inline_view_id).len();
+    let metas = &self.body.metas;
+    self
+      .body
+      .row_creation_hooks
+      .apply(&fields, params, num_of_rows, || {
+        metas.next_row_number(&mut txn, num_of_rows)
+      });
+  }
+
   /// Remove the row
   /// The [RowOrder] of each view representing this row will be removed.
   pub async fn remove_row(&mut self, row_id: &RowId) -> Option<Row> {
     {
       let mut txn = self.collab.transact_mut();
       self.body.views.update_all_views(&mut txn, |_, update| {
-        update.remove_row_order(row_id);
+        update.remove_row_order(row_id).unhide_row(row_id);
       });
     };
 
-    let row = self.body.block.delete_row(row_id)?;
-    let read_guard = row.read().await;
-    read_guard.get_row()
+    let row = self.body.blocks.delete_row(row_id)?;
+    let row = row.read().await.get_row()?;
+    self.unlink_removed_rows(std::slice::from_ref(&row)).await;
+    Some(row)
   }
 
   pub async fn move_row(&mut self, from_row_id: &str, to_row_id: &str) {
@@ -436,33 +942,196 @@ impl Database {
     });
   }
 
+  #[cfg_attr(
+    feature = "trace_spans",
+    instrument(
+      level = "debug",
+      skip_all,
+      fields(database_id = %self.collab.object_id(), row_count = row_ids.len())
+    )
+  )]
   pub async fn remove_rows(&mut self, row_ids: &[RowId]) -> Vec<Row> {
     {
       let mut txn = self.collab.transact_mut();
       self.body.views.update_all_views(&mut txn, |_, mut update| {
         for row_id in row_ids {
-          update = update.remove_row_order(row_id);
+          update = update.remove_row_order(row_id).unhide_row(row_id);
         }
       });
     };
 
     let mut rows = vec![];
     for row_id in row_ids {
-      if let Some(database_row) = self.body.block.delete_row(row_id) {
+      if let Some(database_row) = self.body.blocks.delete_row(row_id) {
         if let Some(row) = database_row.read().await.get_row() {
           rows.push(row);
         }
       }
     }
+    self.unlink_removed_rows(&rows).await;
     rows
   }
 
-  /// Update the row
+  /// Unlink the removed rows from the reciprocal cells of the rows they link to, see
+  /// [RelationTypeOption::reciprocal_field_id]. The links in the other databases are queued in
+  /// the [PendingUnlinks] of the [DatabaseContext].
+  async fn unlink_removed_rows(&mut self, rows: &[Row]) {
+    let database_id = self.get_database_id();
+    for (field_id, related_database_id, reciprocal_field_id) in self.get_reciprocal_fields() {
+      for row in rows {
+        let linked_row_ids = row
+          .cells
+          .get(&field_id)
+          .map(|cell| RelationCellData::from(cell).row_ids)
+          .unwrap_or_default();
+        for linked_row_id in linked_row_ids {
+          if rows.iter().any(|removed| removed.id == linked_row_id) {
+            continue;
+          }
+          if related_database_id == database_id {
+            self
+              .update_relation_links(
+                &linked_row_id,
+                &reciprocal_field_id,
+                &[],
+                std::slice::from_ref(&row.id),
+              )
+              .await;
+          } else {
+            self.body.pending_unlinks.push(PendingUnlink {
+              database_id: related_database_id.clone(),
+              field_id: reciprocal_field_id.clone(),
+              row_id: linked_row_id,
+              unlinked_row_id: row.id.clone(),
+              link: false,
+            });
+          }
+        }
+      }
+    }
+  }
+
+  /// Return the relation fields that have a reciprocal field, with the id of the related
+  /// database and the id of the reciprocal field.
+  fn get_reciprocal_fields(&self) -> Vec<(String, String, String)> {
+    self
+      .get_all_fields()
+      .into_iter()
+      .filter(|field| FieldType::from(field.field_type) == FieldType::Relation)
+      .filter_map(|field| {
+        let type_option =
+          field.get_type_option::<RelationTypeOption>(FieldType::Relation.type_id())?;
+        let reciprocal_field_id = type_option.reciprocal_field_id?;
+        Some((field.id, type_option.database_id, reciprocal_field_id))
+      })
+      .collect()
+  }
+
+  /// Update the row. The progress cells derived from a checklist field are refreshed in the same
+  /// transaction, see [crate::fields::CellRules].
+  #[cfg_attr(
+    feature = "trace_spans",
+    instrument(
+      level = "debug",
+      skip_all,
+      fields(database_id = %self.collab.object_id(), row_id = %row_id)
+    )
+  )]
   pub async fn update_row<F>(&mut self, row_id: RowId, f: F)
   where
     F: FnOnce(RowUpdate),
   {
-    self.body.block.update_row(row_id, f).await;
+    self.body.blocks.update_row(row_id, f).await;
+  }
+
+  /// Apply the changes to the options of the checklist cell in one transaction of the row, and
+  /// return the updated checklist. Unlike writing the whole cell, the options are changed one by
+  /// one, so the checkmarks and the options edited concurrently by the other clients are kept.
+  pub async fn update_checklist_cell(
+    &mut self,
+    row_id: &RowId,
+    field_id: &str,
+    changes: Vec<ChecklistCellChange>,
+  ) -> Result<ChecklistCellData, DatabaseError> {
+    let field = self
+      .get_field(field_id)
+      .ok_or_else(|| DatabaseError::NoRequiredData(format!("field {}", field_id)))?;
+    if FieldType::from(field.field_type) != FieldType::Checklist {
+      return Err(DatabaseError::NoRequiredData(format!(
+        "field {} is not a checklist field",
+        field_id
+      )));
+    }
+    if self.get_or_init_database_row(row_id).await.is_none() {
+      return Err(DatabaseError::DatabaseRowNotFound {
+        row_id: row_id.clone(),
+        reason: "the row is not exist in local disk".to_string(),
+      });
+    }
+    self
+      .update_row(row_id.clone(), |update| {
+        update.update_cells(|cells| {
+          cells.update_cell(field_id, |txn, cell_map| {
+            cell_map.insert(
+              txn,
+              CELL_FIELD_TYPE,
+              Any::BigInt(FieldType::Checklist.into()),
+            );
+            apply_checklist_changes(txn, cell_map, changes);
+          });
+        });
+      })
+      .await;
+    let cell = self.get_cell(field_id, row_id).await.cell;
+    Ok(
+      cell
+        .map(|cell| checklist_from_cell(&cell))
+        .unwrap_or_default(),
+    )
+  }
+
+  /// Add and remove the links of the relation cell of the row one by one, in one transaction of
+  /// the row, so the links edited concurrently by the other clients are kept. Return false if
+  /// the row doesn't exist.
+  pub async fn update_relation_links(
+    &mut self,
+    row_id: &RowId,
+    field_id: &str,
+    linked_row_ids: &[RowId],
+    unlinked_row_ids: &[RowId],
+  ) -> bool {
+    if self.get_or_init_database_row(row_id).await.is_none() {
+      return false;
+    }
+    let data = self
+      .get_cell(field_id, row_id)
+      .await
+      .cell
+      .map(|cell| RelationCellData::from(&cell))
+      .unwrap_or_default();
+    let changed = linked_row_ids
+      .iter()
+      .any(|linked_row_id| !data.row_ids.contains(linked_row_id))
+      || unlinked_row_ids
+        .iter()
+        .any(|unlinked_row_id| data.row_ids.contains(unlinked_row_id));
+    if changed {
+      self
+        .update_row(row_id.clone(), |update| {
+          update.update_cells(|cells| {
+            cells.update_cell(field_id, |txn, cell_map| {
+              cell_map.insert(
+                txn,
+                CELL_FIELD_TYPE,
+                Any::BigInt(FieldType::Relation.into()),
+              );
+              apply_relation_changes(txn, cell_map, linked_row_ids, unlinked_row_ids);
+            });
+          });
+        })
+        .await;
+    }
+    true
   }
 
   /// Update the meta of the row
@@ -470,7 +1139,7 @@ impl Database {
   where
     F: FnOnce(RowMetaUpdate),
   {
-    self.body.block.update_row_meta(row_id, f).await;
+    self.body.blocks.update_row_meta(row_id, f).await;
   }
 
   /// Return the index of the row in the given view.
@@ -480,22 +1149,26 @@ impl Database {
     self.body.index_of_row(&txn, view_id, row_id)
   }
 
-  /// Return the [Row] with the given row id.
+  /// Return the [Row] with the given row id. The cells of the [FieldType::Formula] fields are
+  /// computed from the other cells of the row.
   pub async fn get_row(&self, row_id: &RowId) -> Row {
-    let row = self.body.block.get_database_row(row_id).await;
+    let row = self.body.blocks.get_or_reload_database_row(row_id).await;
+    let row = match row {
+      None => return Row::empty(row_id.clone(), &self.get_database_id()),
+      Some(row) => row.read().await.get_row(),
+    };
     match row {
       None => Row::empty(row_id.clone(), &self.get_database_id()),
-      Some(row) => row
-        .read()
-        .await
-        .get_row()
-        .unwrap_or_else(|| Row::empty(row_id.clone(), &self.get_database_id())),
+      Some(mut row) => {
+        compute_formula_cells(&self.get_formula_type_options(), &mut row);
+        row
+      },
     }
   }
 
   /// Return the [RowMeta] with the given row id.
   pub async fn get_row_meta(&self, row_id: &RowId) -> Option<RowMeta> {
-    self.body.block.get_row_meta(row_id).await
+    self.body.blocks.get_row_meta(row_id).await
   }
 
   pub fn get_stringify_type_option(&self, field_id: &str) -> Option<Box<dyn StringifyTypeOption>> {
@@ -508,7 +1181,7 @@ impl Database {
 
   #[instrument(level = "debug", skip_all)]
   pub async fn get_or_init_database_row(&self, row_id: &RowId) -> Option<Arc<RwLock<DatabaseRow>>> {
-    self.body.block.get_or_init_database_row(row_id).await.ok()
+    self.body.blocks.get_or_init_database_row(row_id).await.ok()
   }
 
   pub fn init_database_rows<'a, T: Into<RowId> + Send + 'a>(
@@ -537,7 +1210,7 @@ impl Database {
             }
           }
 
-          self.body.block.init_database_rows(chunk).await
+          self.body.blocks.init_database_rows(chunk).await
         }
       })
       .filter_map(|result| async {
@@ -555,14 +1228,14 @@ impl Database {
   /// Return None if the row is not initialized.
   /// Use [Self::get_or_init_database_row] to initialize the row.
   pub async fn get_database_row(&self, row_id: &RowId) -> Option<Arc<RwLock<DatabaseRow>>> {
-    self.body.block.get_database_row(row_id).await
+    self.body.blocks.get_database_row(row_id).await
   }
 
   #[instrument(level = "debug", skip_all)]
   pub async fn get_row_detail(&self, row_id: &RowId) -> Option<RowDetail> {
     let database_row = self
       .body
-      .block
+      .blocks
       .get_or_init_database_row(row_id)
       .await
       .ok()?;
@@ -572,22 +1245,157 @@ impl Database {
   }
 
   pub fn get_row_document_id(&self, row_id: &RowId) -> Option<String> {
-    self.body.block.get_row_document_id(row_id)
+    self.body.blocks.get_row_document_id(row_id)
+  }
+
+  /// Converts the row into a standalone document, used to open the row as a page. The document
+  /// starts with the properties of the row, followed by the content of the row document if it
+  /// isn't empty. The name of the view is the cell of the primary field. The row is left unchanged.
+  ///
+  /// The view of the document isn't created here, the caller inserts it into the folder with the
+  /// document id as its view id.
+  pub async fn convert_row_to_document(
+    &self,
+    row_id: &RowId,
+  ) -> Result<RowDocument, DatabaseError> {
+    let row_detail =
+      self
+        .get_row_detail(row_id)
+        .await
+        .ok_or_else(|| DatabaseError::DatabaseRowNotFound {
+          row_id: row_id.clone(),
+          reason: "the row can't be converted to a document".to_string(),
+        })?;
+    let body = if row_detail.meta.is_document_empty {
+      None
+    } else {
+      self.get_row_document_data(&row_detail.document_id).await?
+    };
+
+    let fields = self.get_fields_in_view(&self.get_inline_view_id(), None);
+    let name = fields
+      .iter()
+      .find(|field| field.is_primary)
+      .and_then(|field| stringify_row_cell(&row_detail.row, field))
+      .unwrap_or_default();
+    let document_id = Uuid::new_v4().to_string();
+    let data = render_row_document(&document_id, &row_detail.row, &fields, body);
+    Ok(RowDocument {
+      document_id,
+      data,
+      name,
+      icon: row_detail.meta.icon_url,
+    })
+  }
+
+  async fn get_row_document_data(
+    &self,
+    document_id: &str,
+  ) -> Result<Option<DocumentData>, DatabaseError> {
+    let encoded_collab = match self
+      .collab_service
+      .get_collabs(vec![document_id.to_string()], CollabType::Document)
+      .await?
+      .remove(document_id)
+    {
+      None => return Ok(None),
+      Some(encoded_collab) => encoded_collab,
+    };
+    let collab = Collab::new_with_source(
+      CollabOrigin::Empty,
+      document_id,
+      encoded_collab.into(),
+      vec![],
+      false,
+    )
+    .map_err(|err| DatabaseError::Internal(err.into()))?;
+    let txn = collab.transact();
+    Ok(DocumentBody::from_collab(&collab).and_then(|body| body.get_document_data(&txn).ok()))
   }
 
   /// Return a list of [Row] for the given view.
-  /// The rows here are ordered by [RowOrder]s of the view.
+  /// The rows here are ordered by [RowOrder]s of the view. The rows hidden in the view, see
+  /// [Database::hide_row], are not returned.
   pub async fn get_rows_for_view(
     &self,
     view_id: &str,
     cancel_token: Option<CancellationToken>,
   ) -> impl Stream<Item = Result<Row, DatabaseError>> + '_ {
-    let row_orders = self.get_row_orders_for_view(view_id);
+    let row_orders = self.get_visible_row_orders_for_view(view_id);
     self
       .get_rows_from_row_orders(&row_orders, cancel_token)
       .await
   }
 
+  /// Return at most `limit` rows of the view, starting at `offset` in the order of the view.
+  /// The hidden rows of the view are left out before the range is taken, like
+  /// [Self::get_rows_for_view]. Only the rows of the range are loaded.
+  pub async fn get_rows_in_range(
+    &self,
+    view_id: &str,
+    offset: usize,
+    limit: usize,
+  ) -> Result<Vec<Row>, DatabaseError> {
+    let row_orders = self
+      .get_visible_row_orders_for_view(view_id)
+      .into_iter()
+      .skip(offset)
+      .take(limit)
+      .collect::<Vec<_>>();
+    self
+      .get_rows_from_row_orders(&row_orders, None)
+      .await
+      .try_collect()
+      .await
+  }
+
+  /// Return at most `limit` rows of the view that come after the row of the cursor, or the first
+  /// rows if the cursor is None. The hidden rows of the view are left out. Fails if the row of
+  /// the cursor is no longer in the view, but not if it was hidden since.
+  pub async fn get_rows_after(
+    &self,
+    view_id: &str,
+    cursor: Option<&RowId>,
+    limit: usize,
+  ) -> Result<RowPage, DatabaseError> {
+    let hidden_rows = self.get_hidden_rows(view_id);
+    let mut row_orders = self.get_row_orders_for_view(view_id);
+    let start = match cursor {
+      None => 0,
+      Some(row_id) => {
+        let index = row_orders
+          .iter()
+          .position(|row_order| &row_order.id == row_id)
+          .ok_or_else(|| DatabaseError::DatabaseRowNotFound {
+            row_id: row_id.clone(),
+            reason: "the row of the cursor is not in the view".to_string(),
+          })?;
+        index + 1
+      },
+    };
+    row_orders.drain(..start);
+    row_orders.retain(|row_order| !hidden_rows.contains(&row_order.id));
+    let end = limit.min(row_orders.len());
+    let page_row_orders = &row_orders[..end];
+    let rows: Vec<Row> = self
+      .get_rows_from_row_orders(page_row_orders, None)
+      .await
+      .try_collect()
+      .await?;
+    let next_cursor = if end < row_orders.len() {
+      page_row_orders.last().map(|row_order| row_order.id.clone())
+    } else {
+      None
+    };
+    Ok(RowPage { rows, next_cursor })
+  }
+
+  /// Iterate over the rows of the view, `page_size` rows at a time, without the hidden rows of
+  /// the view.
+  pub fn paginate_rows(&self, view_id: &str, page_size: usize) -> RowPages<'_> {
+    RowPages::new(self, view_id, page_size)
+  }
+
   pub async fn get_row_order_at_index(&self, view_id: &str, index: u32) -> Option<RowOrder> {
     let txn = self.collab.transact();
     self.body.views.get_row_order_at_index(&txn, view_id, index)
@@ -598,45 +1406,237 @@ impl Database {
     self.body.views.get_row_orders(&txn, view_id)
   }
 
+  /// Same as [Self::get_row_orders_for_view], without the hidden rows of the view.
+  fn get_visible_row_orders_for_view(&self, view_id: &str) -> Vec<RowOrder> {
+    let hidden_rows = self.get_hidden_rows(view_id);
+    let mut row_orders = self.get_row_orders_for_view(view_id);
+    row_orders.retain(|row_order| !hidden_rows.contains(&row_order.id));
+    row_orders
+  }
+
   pub fn get_row_index(&self, view_id: &str, row_id: &RowId) -> Option<usize> {
     let txn = self.collab.transact();
     self.body.index_of_row(&txn, view_id, row_id)
   }
 
-  /// Return a list of [Row] for the given view.
-  /// The rows here is ordered by the [RowOrder] of the view.
-  pub async fn get_rows_from_row_orders<'a>(
-    &'a self,
-    row_orders: &[RowOrder],
-    cancel_token: Option<CancellationToken>,
-  ) -> impl Stream<Item = Result<Row, DatabaseError>> + 'a {
-    let row_ids = row_orders.iter().map(|order| order.id.clone()).collect();
-    let rows_stream = self.init_database_rows(row_ids, cancel_token);
-    let database_id = self.get_database_id();
-    rows_stream.then(move |result| {
-      let database_id = database_id.clone();
-      async move {
-        let row = result?;
-        let read_guard = row.read().await;
-        let row_id = read_guard.row_id.clone();
-        let row = read_guard
-          .get_row()
-          .unwrap_or_else(|| Row::empty(row_id, &database_id));
-        Ok(row)
-      }
-    })
+  /// Return a list of [Row] for the given view.
+  /// The rows here is ordered by the [RowOrder] of the view. The cells of the
+  /// [FieldType::Formula] fields are computed from the other cells of each row.
+  pub async fn get_rows_from_row_orders<'a>(
+    &'a self,
+    row_orders: &[RowOrder],
+    cancel_token: Option<CancellationToken>,
+  ) -> impl Stream<Item = Result<Row, DatabaseError>> + 'a {
+    let row_ids = row_orders.iter().map(|order| order.id.clone()).collect();
+    let rows_stream = self.init_database_rows(row_ids, cancel_token);
+    let database_id = self.get_database_id();
+    let formulas = Arc::new(self.get_formula_type_options());
+    rows_stream.then(move |result| {
+      let database_id = database_id.clone();
+      let formulas = formulas.clone();
+      async move {
+        let row = result?;
+        let read_guard = row.read().await;
+        let row_id = read_guard.row_id.clone();
+        let row = match read_guard.get_row() {
+          None => Row::empty(row_id, &database_id),
+          Some(mut row) => {
+            compute_formula_cells(&formulas, &mut row);
+            row
+          },
+        };
+        Ok(row)
+      }
+    })
+  }
+
+  /// Return a list of [RowCell] for the given view and field. The cells of a
+  /// [FieldType::Formula] field are computed from the other cells of each row.
+  pub async fn get_cells_for_field(&self, view_id: &str, field_id: &str) -> Vec<RowCell> {
+    match self.get_formula_type_option(field_id) {
+      None => {
+        let txn = self.collab.transact();
+        self.body.get_cells_for_field(&txn, view_id, field_id).await
+      },
+      Some(formula) => {
+        let row_orders = self.get_row_orders_for_view(view_id);
+        self
+          .body
+          .blocks
+          .get_rows_from_row_orders(&row_orders)
+          .await
+          .into_iter()
+          .map(|row| RowCell::new(row.id, Some(formula.compute_cell(&row.cells))))
+          .collect()
+      },
+    }
+  }
+
+  /// Return the [RowCell] with the given row id and field id.
+  /// The cells of a [FieldType::Formula] field are not stored, they are computed from the other
+  /// cells of the row.
+  pub async fn get_cell(&self, field_id: &str, row_id: &RowId) -> RowCell {
+    if self.get_formula_type_option(field_id).is_some() {
+      let row = self.get_row(row_id).await;
+      return RowCell::new(row_id.clone(), row.cells.get(field_id).cloned());
+    }
+    let cell = self.body.blocks.get_cell(row_id, field_id).await;
+    RowCell::new(row_id.clone(), cell)
+  }
+
+  /// Fetch the metadata of the link of the URL cell with the provider and store it in the cell.
+  /// The metadata is dropped if the link changed while it was fetched. Return the updated cell
+  /// data, or None if the cell is empty or its link changed.
+  pub async fn enrich_url_cell(
+    &self,
+    row_id: &RowId,
+    field_id: &str,
+    provider: &dyn URLMetadataProvider,
+  ) -> Result<Option<URLCellData>, DatabaseError> {
+    let url = match self.body.blocks.get_cell(row_id, field_id).await {
+      Some(cell) => URLCellData::from(&cell).data,
+      None => return Ok(None),
+    };
+    if url.trim().is_empty() {
+      return Ok(None);
+    }
+    let metadata = provider.fetch_metadata(&url).await?;
+
+    let database_row = self.get_or_init_database_row(row_id).await.ok_or_else(|| {
+      DatabaseError::DatabaseRowNotFound {
+        row_id: row_id.clone(),
+        reason: "the row of the url cell is not found".to_string(),
+      }
+    })?;
+    let mut database_row = database_row.write().await;
+    let cell_data = database_row
+      .get_row()
+      .and_then(|row| row.cells.get(field_id).map(URLCellData::from));
+    let cell_data = match cell_data {
+      Some(cell_data) if cell_data.data == url => {
+        cell_data.with_metadata(metadata, self.body.clock.timestamp())
+      },
+      _ => return Ok(None),
+    };
+    database_row.update(|update| {
+      update.update_cells(|cells| {
+        cells.insert_cell(field_id, Cell::from(cell_data.clone()));
+      });
+    });
+    Ok(Some(cell_data))
+  }
+
+  /// Update the files of the media cell with the closure. The cell is read and written while
+  /// the row is locked, and only the files changed by the closure are written, see
+  /// [apply_media_files], so the files changed by the other clients are kept. The cell is left
+  /// untouched if the closure didn't change its files. Return the updated cell data.
+  pub async fn update_media_cell<F>(
+    &self,
+    row_id: &RowId,
+    field_id: &str,
+    f: F,
+  ) -> Result<MediaCellData, DatabaseError>
+  where
+    F: FnOnce(&mut MediaCellData),
+  {
+    match self.get_field(field_id) {
+      Some(field) if FieldType::from(field.field_type) == FieldType::Media => {},
+      _ => {
+        return Err(DatabaseError::NoRequiredData(format!(
+          "media field {}",
+          field_id
+        )))
+      },
+    }
+    let database_row = self.get_or_init_database_row(row_id).await.ok_or_else(|| {
+      DatabaseError::DatabaseRowNotFound {
+        row_id: row_id.clone(),
+        reason: "the row of the media cell is not found".to_string(),
+      }
+    })?;
+    let mut database_row = database_row.write().await;
+    let old_cell_data = database_row
+      .get_row()
+      .and_then(|row| row.cells.get(field_id).map(MediaCellData::from))
+      .unwrap_or_default();
+    let mut cell_data = old_cell_data.clone();
+    f(&mut cell_data);
+    if cell_data == old_cell_data {
+      return Ok(cell_data);
+    }
+    database_row.update(|update| {
+      update.update_cells(|cells| {
+        cells.update_cell(field_id, |txn, cell_map| {
+          cell_map.insert(txn, CELL_FIELD_TYPE, Any::BigInt(FieldType::Media.into()));
+          apply_media_files(txn, cell_map, &cell_data.files);
+        });
+      });
+    });
+    Ok(cell_data)
+  }
+
+  /// Append the file to the media cell, see [MediaCellData::add_file]. An uploaded file without
+  /// an upload time is stamped with the current time.
+  pub async fn add_media_file(
+    &self,
+    row_id: &RowId,
+    field_id: &str,
+    mut file: MediaFile,
+  ) -> Result<MediaCellData, DatabaseError> {
+    if file.upload_state == MediaUploadState::Uploaded && file.uploaded_at.is_none() {
+      file.uploaded_at = Some(self.body.clock.timestamp());
+    }
+    self
+      .update_media_cell(row_id, field_id, |data| data.add_file(file))
+      .await
+  }
+
+  /// Remove the file from the media cell. Return the removed file, or None if the cell doesn't
+  /// have it.
+  pub async fn remove_media_file(
+    &self,
+    row_id: &RowId,
+    field_id: &str,
+    file_id: &str,
+  ) -> Result<Option<MediaFile>, DatabaseError> {
+    let mut removed = None;
+    self
+      .update_media_cell(row_id, field_id, |data| removed = data.remove_file(file_id))
+      .await?;
+    Ok(removed)
   }
 
-  /// Return a list of [RowCell] for the given view and field.
-  pub async fn get_cells_for_field(&self, view_id: &str, field_id: &str) -> Vec<RowCell> {
-    let txn = self.collab.transact();
-    self.body.get_cells_for_field(&txn, view_id, field_id).await
+  /// Move the file of the media cell to the index, see [MediaCellData::move_file].
+  pub async fn move_media_file(
+    &self,
+    row_id: &RowId,
+    field_id: &str,
+    file_id: &str,
+    to_index: usize,
+  ) -> Result<MediaCellData, DatabaseError> {
+    let mut found = false;
+    let cell_data = self
+      .update_media_cell(row_id, field_id, |data| {
+        found = data.move_file(file_id, to_index)
+      })
+      .await?;
+    if !found {
+      return Err(DatabaseError::RecordNotFound);
+    }
+    Ok(cell_data)
   }
 
-  /// Return the [RowCell] with the given row id and field id.
-  pub async fn get_cell(&self, field_id: &str, row_id: &RowId) -> RowCell {
-    let cell = self.body.block.get_cell(row_id, field_id).await;
-    RowCell::new(row_id.clone(), cell)
+  fn get_formula_type_option(&self, field_id: &str) -> Option<FormulaTypeOption> {
+    formula_type_option(&self.get_field(field_id)?)
+  }
+
+  /// Return the type options of the [FieldType::Formula] fields, with their field ids.
+  fn get_formula_type_options(&self) -> Vec<(String, FormulaTypeOption)> {
+    self
+      .get_all_fields()
+      .into_iter()
+      .filter_map(|field| formula_type_option(&field).map(|formula| (field.id, formula)))
+      .collect()
   }
 
   pub fn index_of_field(&self, view_id: &str, field_id: &str) -> Option<usize> {
@@ -679,6 +1679,55 @@ impl Database {
     );
   }
 
+  /// Move the field to the position in the field orders of the given view, or of all the views
+  /// if `view_id` is None. The views are updated in a single transaction, so the view observer
+  /// sends a single [crate::views::DatabaseViewChange::DidMoveFieldOrder]. A view that doesn't
+  /// contain the field is left unchanged, and the field is moved to the end of a view that doesn't
+  /// contain the field of the position. Return the ids of the updated views.
+  pub fn move_field(
+    &mut self,
+    field_id: &str,
+    position: OrderObjectPosition,
+    view_id: Option<&str>,
+  ) -> Vec<String> {
+    let is_self_position = match &position {
+      OrderObjectPosition::Before(id) | OrderObjectPosition::After(id) => id == field_id,
+      OrderObjectPosition::Start | OrderObjectPosition::End => false,
+    };
+    if is_self_position {
+      return vec![];
+    }
+
+    let mut txn = self.collab.transact_mut();
+    let view_ids = self
+      .body
+      .views
+      .get_all_views_meta(&txn)
+      .into_iter()
+      .map(|meta| meta.id)
+      .filter(|id| view_id.map_or(true, |view_id| view_id == id))
+      .filter(|id| {
+        self
+          .body
+          .views
+          .get_field_orders(&txn, id)
+          .iter()
+          .any(|order| order.id == field_id)
+      })
+      .collect::<Vec<_>>();
+    for id in &view_ids {
+      self
+        .body
+        .views
+        .update_database_view(&mut txn, id, |update| {
+          update
+            .remove_field_order(field_id)
+            .insert_field_order(FieldOrder::new(field_id.to_string()), &position);
+        });
+    }
+    view_ids
+  }
+
   pub fn create_field_with_mut(
     &mut self,
     view_id: &str,
@@ -714,7 +1763,8 @@ impl Database {
       .update_all_views(&mut txn, |_view_id, update| {
         update
           .remove_field_order(field_id)
-          .remove_field_setting(field_id);
+          .remove_field_setting(field_id)
+          .remove_calculations_for_field(field_id);
       });
     self.body.fields.delete_field(&mut txn, field_id);
   }
@@ -812,6 +1862,9 @@ impl Database {
       });
   }
 
+  /// Move the sort to the position of the target sort, the sorts in between shift by one. So a
+  /// sort moved forward lands after the target. It used to land in front of the target, which
+  /// left two adjacent sorts unchanged.
   pub fn move_sort(&mut self, view_id: &str, from_sort_id: &str, to_sort_id: &str) {
     let mut txn = self.collab.transact_mut();
     self
@@ -821,6 +1874,7 @@ impl Database {
         update.update_sorts(|txn, sort_update| {
           if let Some(from) = sort_update.index_by_id(txn, from_sort_id) {
             if let Some(to) = sort_update.index_by_id(txn, to_sort_id) {
+              let to = if from < to { to + 1 } else { to };
               sort_update.move_to(txn, from, to);
             }
           }
@@ -828,6 +1882,53 @@ impl Database {
       });
   }
 
+  /// Insert the sort at the given priority, 0 being applied first. If a sort with the same id
+  /// exists, it's replaced and moved to the priority. The index is clamped to the number of
+  /// sorts.
+  pub fn insert_sort_at(&mut self, view_id: &str, sort: impl Into<SortMap>, index: u32) {
+    let mut txn = self.collab.transact_mut();
+    self
+      .body
+      .views
+      .update_database_view(&mut txn, view_id, |update| {
+        update.update_sorts(|txn, sort_update| {
+          let sort = sort.into();
+          if let Some(Any::String(sort_id)) = sort.get("id") {
+            if let Some(i) = sort_update.index_by_id(txn, sort_id) {
+              sort_update.remove(txn, i);
+            }
+          }
+          let index = index.min(sort_update.len(txn));
+          let map_ref = sort_update.insert(txn, index, MapPrelim::default());
+          Any::from(sort).fill(txn, &map_ref).unwrap();
+        });
+      });
+  }
+
+  /// Reorder the sorts of the view in one transaction. The given sorts come first, in the given
+  /// order, and the other sorts keep their relative order after them. Unknown ids are ignored.
+  pub fn reorder_sorts(&mut self, view_id: &str, sort_ids: &[&str]) {
+    let mut txn = self.collab.transact_mut();
+    self
+      .body
+      .views
+      .update_database_view(&mut txn, view_id, |update| {
+        update.update_sorts(|txn, sort_update| {
+          let mut target = 0;
+          for sort_id in sort_ids {
+            if let Some(from) = sort_update.index_by_id(txn, sort_id) {
+              if from > target {
+                sort_update.move_to(txn, from, target);
+              }
+              if from >= target {
+                target += 1;
+              }
+            }
+          }
+        });
+      });
+  }
+
   pub fn get_all_sorts<T>(&self, view_id: &str) -> Vec<T>
   where
     T: TryFrom<SortMap>,
@@ -1026,7 +2127,77 @@ impl Database {
           let map: MapRef = filter_update.upsert(txn, filter_id);
           let mut filter_map = map.to_json(txn).into_map().unwrap();
           f(&mut filter_map);
-          Any::from(filter_map).fill(txn, &map).unwrap();
+          fill_filter_map(txn, &map, filter_map);
+        });
+      });
+  }
+
+  /// Update a node of the filter trees of the view, see [FilterNode], at any depth. Only the
+  /// node itself is given to the function, without its children, so the children can be edited
+  /// at the same time by the other clients. Does nothing if the node doesn't exist.
+  pub fn update_filter_node(
+    &mut self,
+    view_id: &str,
+    node_id: &str,
+    f: impl FnOnce(&mut FilterMap),
+  ) {
+    let mut txn = self.collab.transact_mut();
+    self
+      .body
+      .views
+      .update_database_view(&mut txn, view_id, |view_update| {
+        view_update.update_filters(|txn, filter_update| {
+          if let Some((_, _, map_ref)) = find_filter_node(txn, &filter_update, node_id) {
+            let mut filter_map = map_ref.to_json(txn).into_map().unwrap();
+            filter_map.remove(FILTER_CHILDREN);
+            let keys = filter_map.keys().cloned().collect::<Vec<_>>();
+            f(&mut filter_map);
+            for key in keys {
+              if !filter_map.contains_key(&key) {
+                map_ref.remove(txn, &key);
+              }
+            }
+            fill_filter_map(txn, &map_ref, filter_map);
+          }
+        });
+      });
+  }
+
+  /// Add a child to the AND or OR group with the given id, at any depth of the filter trees of
+  /// the view. Does nothing if the group doesn't exist or is a condition.
+  pub fn insert_filter_node(&mut self, view_id: &str, group_id: &str, node: FilterNode) {
+    let mut txn = self.collab.transact_mut();
+    self
+      .body
+      .views
+      .update_database_view(&mut txn, view_id, |view_update| {
+        view_update.update_filters(|txn, filter_update| {
+          if let Some((_, _, map_ref)) = find_filter_node(txn, &filter_update, group_id) {
+            let filter_type = map_ref
+              .get_with_txn::<_, i64>(txn, FILTER_TYPE)
+              .map(FilterType::from)
+              .unwrap_or_default();
+            if filter_type != FilterType::Data {
+              let children: ArrayRef = map_ref.get_or_init(txn, FILTER_CHILDREN);
+              let child_ref = children.push_back(txn, MapPrelim::default());
+              fill_filter_map(txn, &child_ref, node.into());
+            }
+          }
+        });
+      });
+  }
+
+  /// Remove the node with the given id from the filter trees of the view, at any depth.
+  pub fn remove_filter_node(&mut self, view_id: &str, node_id: &str) {
+    let mut txn = self.collab.transact_mut();
+    self
+      .body
+      .views
+      .update_database_view(&mut txn, view_id, |view_update| {
+        view_update.update_filters(|txn, filter_update| {
+          if let Some((array_ref, index, _)) = find_filter_node(txn, &filter_update, node_id) {
+            array_ref.remove(txn, index);
+          }
         });
       });
   }
@@ -1056,10 +2227,10 @@ impl Database {
           let filter = filter.into();
           if let Some(Any::String(filter_id)) = filter.get("id") {
             let map_ref: MapRef = filter_update.upsert(txn, filter_id);
-            Any::from(filter).fill(txn, &map_ref).unwrap();
+            fill_filter_map(txn, &map_ref, filter);
           } else {
             let map_ref = filter_update.push_back(txn, MapPrelim::default());
-            Any::from(filter).fill(txn, &map_ref).unwrap();
+            fill_filter_map(txn, &map_ref, filter);
           }
         });
       });
@@ -1224,6 +2395,18 @@ impl Database {
   }
 
   /// Create a linked view to existing database
+  #[cfg_attr(
+    feature = "trace_spans",
+    instrument(
+      level = "debug",
+      skip_all,
+      fields(
+        database_id = %self.collab.object_id(),
+        view_id = %params.view_id,
+        row_count = tracing::field::Empty,
+      )
+    )
+  )]
   pub fn create_linked_view(&mut self, params: CreateViewParams) -> Result<(), DatabaseError> {
     let mut txn = self.collab.transact_mut();
     let inline_view_id = self.body.get_inline_view_id(&txn);
@@ -1234,6 +2417,7 @@ impl Database {
       row_orders.len(),
       field_orders.len()
     );
+    record_span!(row_count = row_orders.len());
 
     self
       .body
@@ -1246,7 +2430,7 @@ impl Database {
   pub fn duplicate_linked_view(&mut self, view_id: &str) -> Option<DatabaseView> {
     let mut txn = self.collab.transact_mut();
     let view = self.body.views.get_view(&txn, view_id)?;
-    let timestamp = timestamp();
+    let timestamp = self.body.clock.timestamp();
     let duplicated_view = DatabaseView {
       id: gen_database_view_id(),
       name: format!("{}-copy", view.name),
@@ -1262,28 +2446,148 @@ impl Database {
     Some(duplicated_view)
   }
 
-  /// Duplicate the row, and insert it after the original row.
-  pub async fn duplicate_row(&self, row_id: &RowId) -> Option<CreateRowParams> {
-    let database_id = self.get_database_id();
-    let row = self
+  /// Duplicate the row with a new [RowId], and insert it before or after the source row in all
+  /// the views. The cells and the meta of the row are copied, except the cells unique to each
+  /// row, like the created time and the auto numbers, which are created again, see
+  /// [RowCreationHook::is_unique_per_row]. The rows linked by the two-way relations are linked
+  /// back to the duplicate.
+  ///
+  /// The row document is copied first, and an error is returned if it can't be read from the
+  /// [DatabaseCollabService] or written to its persistence, before anything is created. The
+  /// row is created with its meta in its initial state, and the row orders are inserted in one
+  /// transaction once the row is created, so the views never reference a row that doesn't exist.
+  pub async fn duplicate_row(
+    &mut self,
+    row_id: &RowId,
+    position: DuplicateRowPosition,
+  ) -> Result<RowDetail, DatabaseError> {
+    let source_row = match self.body.blocks.get_or_reload_database_row(row_id).await {
+      None => None,
+      Some(row) => row.read().await.get_row(),
+    }
+    .ok_or_else(|| DatabaseError::DatabaseRowNotFound {
+      row_id: row_id.clone(),
+      reason: "the row can't be duplicated".to_string(),
+    })?;
+    // The rows whose id is not a uuid have no meta.
+    let source_meta = self.get_row_meta(row_id).await;
+    let new_row_id = gen_row_id();
+    let has_document = source_meta
+      .as_ref()
+      .is_some_and(|meta| !meta.is_document_empty);
+    if has_document {
+      if let Some(document_id) = self.get_row_document_id(row_id) {
+        let new_document_id = database_row_document_id_from_row_id(&new_row_id);
+        self
+          .copy_row_document(&document_id, &new_document_id)
+          .await?;
+      }
+    }
+
+    let timestamp = self.body.clock.timestamp();
+    let mut cells = source_row.cells;
+    self
       .body
-      .block
-      .get_database_row(row_id)
-      .await?
-      .read()
-      .await
-      .get_row()?;
-    let timestamp = timestamp();
-    Some(CreateRowParams {
-      id: gen_row_id(),
-      database_id,
-      cells: row.cells,
-      height: row.height,
-      visibility: row.visibility,
-      row_position: OrderObjectPosition::After(row.id.into()),
+      .row_creation_hooks
+      .remove_unique_cells(&self.get_all_fields(), &mut cells);
+    for cell in cells.values_mut() {
+      if cell.contains_key(CREATED_AT) {
+        cell.insert(CREATED_AT.into(), Any::BigInt(timestamp));
+      }
+      if cell.contains_key(LAST_MODIFIED) {
+        cell.insert(LAST_MODIFIED.into(), Any::BigInt(timestamp));
+      }
+    }
+    let row_position = position.order_position(row_id);
+    let mut params = CreateRowParams {
+      id: new_row_id,
+      database_id: self.get_database_id(),
+      cells,
+      height: source_row.height,
+      visibility: source_row.visibility,
+      row_position: row_position.clone(),
       created_at: timestamp,
       modified_at: timestamp,
-    })
+    };
+    self.apply_row_creation_hooks(&mut params);
+    let cells = params.cells.clone();
+    let row_order = self
+      .body
+      .blocks
+      .create_new_row_with_meta(params, source_meta)
+      .await?;
+
+    {
+      let mut txn = self.collab.transact_mut();
+      self
+        .body
+        .views
+        .update_all_views(&mut txn, |_view_id, update| {
+          update.insert_row_order(&row_order, &row_position);
+        });
+    }
+    self.link_duplicated_row(&row_order.id, &cells).await;
+    self
+      .get_row_detail(&row_order.id)
+      .await
+      .ok_or_else(|| DatabaseError::DatabaseRowNotFound {
+        row_id: row_order.id,
+        reason: "the duplicated row is not created".to_string(),
+      })
+  }
+
+  /// Link the duplicated row back from the reciprocal cells of the rows it links to, see
+  /// [RelationTypeOption::reciprocal_field_id]. The links in the other databases are queued in
+  /// the [PendingUnlinks] of the [DatabaseContext].
+  async fn link_duplicated_row(&mut self, row_id: &RowId, cells: &Cells) {
+    let database_id = self.get_database_id();
+    for (field_id, related_database_id, reciprocal_field_id) in self.get_reciprocal_fields() {
+      let linked_row_ids = cells
+        .get(&field_id)
+        .map(|cell| RelationCellData::from(cell).row_ids)
+        .unwrap_or_default();
+      for linked_row_id in linked_row_ids {
+        if related_database_id == database_id {
+          self
+            .update_relation_links(
+              &linked_row_id,
+              &reciprocal_field_id,
+              std::slice::from_ref(row_id),
+              &[],
+            )
+            .await;
+        } else {
+          self.body.pending_unlinks.push(PendingUnlink {
+            database_id: related_database_id.clone(),
+            field_id: reciprocal_field_id.clone(),
+            row_id: linked_row_id,
+            unlinked_row_id: row_id.clone(),
+            link: true,
+          });
+        }
+      }
+    }
+  }
+
+  /// Copy the row document. Return an error if it can't be read or written.
+  async fn copy_row_document(
+    &self,
+    document_id: &str,
+    new_document_id: &str,
+  ) -> Result<(), DatabaseError> {
+    let persistence = self.collab_service.persistence().ok_or_else(|| {
+      DatabaseError::NoRequiredData(format!(
+        "the persistence to copy the row document {}",
+        document_id
+      ))
+    })?;
+    let encoded_collab = self
+      .collab_service
+      .get_collabs(vec![document_id.to_string()], CollabType::Document)
+      .await?
+      .remove(document_id)
+      .ok_or_else(|| DatabaseError::NoRequiredData(format!("row document {}", document_id)))?;
+    persistence.save_collab(new_document_id, encoded_collab)
   }
 
   pub fn duplicate_field(
@@ -1375,6 +2679,36 @@ impl Database {
     rows_stream.collect::<Vec<_>>().await
   }
 
+  /// Write all the rows of the database to the writer as JSON Lines, one serialized [Row] per
+  /// line in the order of the inline view. The rows are read [EXPORT_ROWS_BATCH_SIZE] at a
+  /// time and the rows that are not loaded yet are not cached, so the whole database is never
+  /// held in memory. A batch that fails to load returns its error instead of being skipped, so an
+  /// export never silently misses rows. Return the number of written rows.
+  pub async fn export_rows_jsonl<W: io::Write>(
+    &self,
+    mut writer: W,
+  ) -> Result<usize, DatabaseError> {
+    let row_ids = self
+      .get_inline_row_orders()
+      .into_iter()
+      .map(|row_order| row_order.id)
+      .collect::<Vec<_>>();
+    let mut count = 0;
+    for row_ids in row_ids.chunks(EXPORT_ROWS_BATCH_SIZE) {
+      for row in self.body.blocks.read_rows(row_ids).await? {
+        serde_json::to_writer(&mut writer, &row)?;
+        writer
+          .write_all(b"\n")
+          .map_err(|err| DatabaseError::Internal(err.into()))?;
+        count += 1;
+      }
+    }
+    writer
+      .flush()
+      .map_err(|err| DatabaseError::Internal(err.into()))?;
+    Ok(count)
+  }
+
   pub async fn get_all_row_orders(&self) -> Vec<RowOrder> {
     let txn = self.collab.transact();
     let inline_view_id = self.body.get_inline_view_id(&txn);
@@ -1395,6 +2729,14 @@ impl Database {
 
   /// Delete a view from the database. If the view is the inline view it will clear all
   /// the linked views as well. Otherwise, just delete the view with given view id.
+  #[cfg_attr(
+    feature = "trace_spans",
+    instrument(
+      level = "debug",
+      skip_all,
+      fields(database_id = %self.collab.object_id(), view_id = %view_id)
+    )
+  )]
   pub fn delete_view(&mut self, view_id: &str) -> Vec<String> {
     // TODO(nathan): delete the database from workspace database
     let mut txn = self.collab.transact_mut();
@@ -1418,6 +2760,211 @@ impl Database {
     self.body.fields.insert_field(&mut txn, field);
   }
 
+  /// Switch the type of the field and rewrite its cells with the converter, usually a
+  /// [BuiltinFieldTypeConverter](crate::fields::BuiltinFieldTypeConverter). All the rows are
+  /// loaded and locked before any cell is written, then the cells and the field are written
+  /// without an await point in between, so the conversion is never left half done. The rows that
+  /// can't be loaded are counted in [FieldTypeConversionSummary::skipped].
+  pub async fn switch_field_type(
+    &mut self,
+    field_id: &str,
+    new_type: FieldType,
+    converter: &dyn FieldTypeConverter,
+  ) -> Result<FieldTypeConversionSummary, DatabaseError> {
+    let field = self
+      .get_field(field_id)
+      .ok_or_else(|| DatabaseError::NoRequiredData(format!("field {}", field_id)))?;
+    let mut summary = FieldTypeConversionSummary::default();
+    if FieldType::from(field.field_type) == new_type {
+      return Ok(summary);
+    }
+
+    let mut database_rows = vec![];
+    for row_order in self.get_inline_row_orders() {
+      match self.get_or_init_database_row(&row_order.id).await {
+        None => summary.skipped += 1,
+        Some(database_row) => database_rows.push(database_row),
+      }
+    }
+    let mut locked_rows = Vec::with_capacity(database_rows.len());
+    for database_row in &database_rows {
+      locked_rows.push(database_row.write().await);
+    }
+
+    let mut conversion = FieldTypeConversion::new(&field, new_type.clone());
+    for database_row in locked_rows.iter_mut() {
+      let cell = match database_row
+        .get_row()
+        .and_then(|row| row.cells.get(field_id).cloned())
+      {
+        None => continue,
+        Some(cell) => cell,
+      };
+      let new_cell = converter
+        .convert_cell(&mut conversion, &cell)
+        .map(|mut new_cell| {
+          // Keep the creation time of the cell, a converted cell is not a new one.
+          if let Some(created_at) = cell.get(CREATED_AT) {
+            new_cell.insert(CREATED_AT.into(), created_at.clone());
+          }
+          new_cell
+        });
+      match &new_cell {
+        Some(_) => summary.converted += 1,
+        None => summary.cleared += 1,
+      }
+      database_row.update(|update| {
+        update.update_cells(|cells| match new_cell {
+          Some(new_cell) => {
+            cells.clear(field_id).insert_cell(field_id, new_cell);
+          },
+          None => {
+            cells.clear(field_id);
+          },
+        });
+      });
+    }
+
+    let type_option = conversion.into_type_option();
+    let timestamp = self.body.clock.timestamp();
+    self.update_field(field_id, |update| {
+      update
+        .set_field_type(new_type.clone().into())
+        .set_type_option(new_type.into(), Some(type_option))
+        .set_last_modified(timestamp);
+    });
+    Ok(summary)
+  }
+
+  /// Return the type of the select field and its type option.
+  fn get_select_type_option(
+    &self,
+    field_id: &str,
+  ) -> Result<(FieldType, SelectTypeOption), DatabaseError> {
+    let field = self
+      .get_field(field_id)
+      .ok_or_else(|| DatabaseError::NoRequiredData(format!("field {}", field_id)))?;
+    let field_type = FieldType::from(field.field_type);
+    if !matches!(field_type, FieldType::SingleSelect | FieldType::MultiSelect) {
+      return Err(DatabaseError::NoRequiredData(format!(
+        "field {} is not a select field",
+        field_id
+      )));
+    }
+    let type_option = field
+      .get_type_option::<SelectTypeOption>(field_type.type_id())
+      .unwrap_or_default();
+    Ok((field_type, type_option))
+  }
+
+  /// Count the cells that select each option of the select field, and find the option ids that
+  /// are selected by the cells but missing from the type option. Imports and old clients can
+  /// leave such ids, that render blank.
+  pub async fn audit_select_options(
+    &self,
+    field_id: &str,
+  ) -> Result<SelectOptionAudit, DatabaseError> {
+    let (_, type_option) = self.get_select_type_option(field_id)?;
+    let mut audit = SelectOptionAudit {
+      field_id: field_id.to_string(),
+      ..Default::default()
+    };
+    let mut num_of_cells = HashMap::<String, usize>::new();
+    let inline_view_id = self.get_inline_view_id();
+    for row_cell in self.get_cells_for_field(&inline_view_id, field_id).await {
+      let ids = match &row_cell.cell {
+        None => continue,
+        Some(cell) => SelectOptionIds::from(cell).into_inner(),
+      };
+      for id in ids {
+        if type_option.options.iter().any(|option| option.id == id) {
+          *num_of_cells.entry(id).or_default() += 1;
+        } else {
+          let row_ids = audit.orphaned_option_ids.entry(id).or_default();
+          if !row_ids.contains(&row_cell.row_id) {
+            row_ids.push(row_cell.row_id.clone());
+          }
+        }
+      }
+    }
+    audit.usages = type_option
+      .options
+      .into_iter()
+      .map(|option| SelectOptionUsage {
+        num_of_cells: num_of_cells.get(&option.id).copied().unwrap_or(0),
+        option,
+      })
+      .collect();
+    Ok(audit)
+  }
+
+  /// Remove the orphaned option ids from the cells of the select field, and the options that no
+  /// cell selects, as asked by the [SelectOptionCleanup]. See [Self::audit_select_options].
+  pub async fn cleanup_select_options(
+    &mut self,
+    field_id: &str,
+    cleanup: SelectOptionCleanup,
+  ) -> Result<SelectOptionCleanupSummary, DatabaseError> {
+    let (field_type, mut type_option) = self.get_select_type_option(field_id)?;
+    let audit = self.audit_select_options(field_id).await?;
+    let mut summary = SelectOptionCleanupSummary::default();
+
+    if cleanup.remove_orphaned_ids {
+      let row_ids = audit
+        .orphaned_option_ids
+        .values()
+        .flatten()
+        .cloned()
+        .collect::<HashSet<RowId>>();
+      for row_id in row_ids {
+        let database_row = match self.get_or_init_database_row(&row_id).await {
+          None => continue,
+          Some(database_row) => database_row,
+        };
+        let mut database_row = database_row.write().await;
+        let ids = match database_row.get_cell(field_id) {
+          None => continue,
+          Some(cell) => SelectOptionIds::from(&cell).into_inner(),
+        };
+        let ids = SelectOptionIds::from(
+          ids
+            .into_iter()
+            .filter(|id| !audit.orphaned_option_ids.contains_key(id))
+            .collect::<Vec<_>>(),
+        );
+        let cell = ids.to_cell_data(field_type.clone());
+        database_row.update(|update| {
+          update.update_cells(|cells| {
+            cells.insert_cell(field_id, cell);
+          });
+        });
+        summary.cleaned_cells += 1;
+      }
+    }
+
+    if cleanup.remove_unused_options {
+      let default_option_id = type_option.default_option_id().map(str::to_string);
+      summary.removed_option_ids = audit
+        .unused_options()
+        .into_iter()
+        .filter(|option| Some(&option.id) != default_option_id.as_ref())
+        .map(|option| option.id.clone())
+        .collect();
+      if !summary.removed_option_ids.is_empty() {
+        type_option
+          .options
+          .retain(|option| !summary.removed_option_ids.contains(&option.id));
+        let timestamp = self.body.clock.timestamp();
+        self.update_field(field_id, |update| {
+          update
+            .set_type_option(field_type.into(), Some(type_option.into()))
+            .set_last_modified(timestamp);
+        });
+      }
+    }
+    Ok(summary)
+  }
+
   pub fn update_field<F>(&mut self, field_id: &str, f: F)
   where
     F: FnOnce(FieldUpdate),
@@ -1454,6 +3001,20 @@ impl BorrowMut<Collab> for Database {
   }
 }
 
+async fn send_encoded_collab<S>(
+  sink: &mut S,
+  encoded_collab: EncodedCollabInfo,
+) -> Result<(), DatabaseError>
+where
+  S: Sink<EncodedCollabInfo> + Unpin,
+  S::Error: Display,
+{
+  sink
+    .send(encoded_collab)
+    .await
+    .map_err(|err| DatabaseError::Internal(anyhow!("Failed to send the encoded collab: {}", err)))
+}
+
 pub fn gen_database_id() -> String {
   uuid::Uuid::new_v4().to_string()
 }
@@ -1499,8 +3060,43 @@ pub fn gen_option_id() -> String {
   nanoid!(4)
 }
 
+/// Stamp the timestamps that were left unset, 0, with the clock.
+pub(crate) fn stamp_unset_timestamps(
+  created_at: &mut i64,
+  modified_at: &mut i64,
+  clock: &dyn ClockProvider,
+) {
+  if *created_at == 0 {
+    *created_at = clock.timestamp();
+  }
+  if *modified_at == 0 {
+    *modified_at = *created_at;
+  }
+}
+
+fn formula_type_option(field: &Field) -> Option<FormulaTypeOption> {
+  if FieldType::from(field.field_type) != FieldType::Formula {
+    return None;
+  }
+  Some(
+    field
+      .get_type_option::<FormulaTypeOption>(FieldType::Formula.type_id())
+      .unwrap_or_default(),
+  )
+}
+
+/// Set the cells of the formula fields of the row, computed from its other cells.
+fn compute_formula_cells(formulas: &[(String, FormulaTypeOption)], row: &mut Row) {
+  for (field_id, formula) in formulas {
+    let cell = formula.compute_cell(&row.cells);
+    row.cells.insert(field_id.clone(), cell);
+  }
+}
+
+/// The time of the [system_clock], for the values built outside of a database. A database stamps
+/// its rows and views with the clock of its [DatabaseContext].
 pub fn timestamp() -> i64 {
-  chrono::Utc::now().timestamp()
+  system_clock().timestamp()
 }
 
 /// DatabaseData contains all the data of a database.
@@ -1629,15 +3225,25 @@ pub struct DatabaseBody {
   pub metas: Arc<MetaMap>,
   /// It used to keep track of the blocks. Each block contains a list of [Row]s
   /// A database rows will be stored in multiple blocks.
-  pub block: Block,
+  pub blocks: Arc<BlockMap>,
   pub notifier: Option<DatabaseNotify>,
+  pub clock: Arc<dyn ClockProvider>,
+  pub row_creation_hooks: RowCreationHooks,
+  pub pending_unlinks: PendingUnlinks,
 }
 
 impl DatabaseBody {
   fn open(collab: Collab, context: DatabaseContext) -> Result<(Self, Collab), DatabaseError> {
     CollabType::Database.validate_require_data(&collab)?;
-    let body = Self::from_collab(&collab, context.collab_service)
-      .ok_or_else(|| DatabaseError::NoRequiredData("Can not open database".to_string()))?;
+    let mut body = Self::from_collab_with_block_capacity(
+      &collab,
+      context.collab_service,
+      context.block_capacity,
+      context.clock,
+    )
+    .ok_or_else(|| DatabaseError::NoRequiredData("Can not open database".to_string()))?;
+    body.row_creation_hooks = context.row_creation_hooks;
+    body.pending_unlinks = context.pending_unlinks;
     Ok((body, collab))
   }
 
@@ -1656,20 +3262,26 @@ impl DatabaseBody {
     let views: MapRef = root.get_or_init(&mut txn, VIEWS); // { DATABASE: { FIELDS: {:}, VIEWS: {:} } }
     let metas: MapRef = root.get_or_init(&mut txn, DATABASE_METAS); // { DATABASE: { FIELDS: {:},  VIEWS: {:}, METAS: {:} } }
 
-    let fields = FieldMap::new(fields, Some(context.notifier.field_change_tx.clone()));
-    let views = DatabaseViews::new(origin, views, Some(context.notifier.view_change_tx.clone()));
-    let block = Block::new(
+    let fields = FieldMap::new(fields, Some(context.notifier.field_change_tx.clone()))
+      .with_clock(context.clock.clone());
+    let views = DatabaseViews::new(origin, views, Some(context.notifier.view_change_tx.clone()))
+      .with_clock(context.clock.clone());
+    let blocks = BlockMap::new(
       database_id.clone(),
       context.collab_service.clone(),
       Some(context.notifier.row_change_tx.clone()),
+      context.block_capacity,
+      context.clock.clone(),
+      fields.cell_rules(),
     );
+    blocks.load().await?;
 
     let database_id_uuid = Uuid::parse_str(&database_id)
       .map_err(|_| DatabaseError::InvalidDatabaseID("database_id is not a valid UUID"))?;
     let inline_view_id = database_inline_view_id(&database_id_uuid);
 
     // create rows
-    let row_orders = block.create_rows(new_rows).await;
+    let row_orders = blocks.create_rows(new_rows).await;
 
     // create field orders
     let field_orders: Vec<FieldOrder> = new_fields.iter().map(FieldOrder::from).collect();
@@ -1686,6 +3298,8 @@ impl DatabaseBody {
       DatabaseLayout::Grid,
     );
     inline_view.is_inline = true;
+    inline_view.created_at = context.clock.timestamp();
+    inline_view.modified_at = inline_view.created_at;
     inline_view.row_orders = row_orders.clone();
     inline_view.field_orders = field_orders.clone();
     views.insert_view(&mut txn, inline_view);
@@ -1699,8 +3313,11 @@ impl DatabaseBody {
       views: views.into(),
       fields: fields.into(),
       metas: metas.into(),
-      block,
+      blocks: blocks.into(),
       notifier: Some(context.notifier),
+      clock: context.clock,
+      row_creation_hooks: context.row_creation_hooks,
+      pending_unlinks: context.pending_unlinks,
     };
     Ok((body, collab))
   }
@@ -1712,6 +3329,20 @@ impl DatabaseBody {
   pub fn from_collab(
     collab: &Collab,
     collab_service: Arc<dyn DatabaseCollabService>,
+  ) -> Option<Self> {
+    Self::from_collab_with_block_capacity(
+      collab,
+      collab_service,
+      DEFAULT_BLOCK_CAPACITY,
+      system_clock(),
+    )
+  }
+
+  fn from_collab_with_block_capacity(
+    collab: &Collab,
+    collab_service: Arc<dyn DatabaseCollabService>,
+    block_capacity: usize,
+    clock: Arc<dyn ClockProvider>,
   ) -> Option<Self> {
     let txn = collab.context.transact();
     let root: MapRef = collab.data.get_with_txn(&txn, DATABASE)?;
@@ -1720,17 +3351,28 @@ impl DatabaseBody {
     let views: MapRef = root.get_with_txn(&txn, VIEWS)?; // { DATABASE: { FIELDS: {:}, VIEWS: {:} } }
     let metas: MapRef = root.get_with_txn(&txn, DATABASE_METAS)?; // { DATABASE: { FIELDS: {:},  VIEWS: {:}, METAS: {:} } }
 
-    let fields = FieldMap::new(fields, None);
-    let views = DatabaseViews::new(CollabOrigin::Empty, views, None);
+    let fields = FieldMap::new(fields, None).with_clock(clock.clone());
+    fields.load_cell_rules(&txn);
+    let views = DatabaseViews::new(CollabOrigin::Empty, views, None).with_clock(clock.clone());
     let metas = MetaMap::new(metas);
-    let block = Block::new(database_id, collab_service, None);
+    let blocks = BlockMap::new(
+      database_id,
+      collab_service,
+      None,
+      block_capacity,
+      clock.clone(),
+      fields.cell_rules(),
+    );
     Some(Self {
       root,
       views: views.into(),
       fields: fields.into(),
       metas: metas.into(),
-      block,
+      blocks: blocks.into(),
       notifier: None,
+      clock,
+      row_creation_hooks: RowCreationHooks::default(),
+      pending_unlinks: PendingUnlinks::default(),
     })
   }
 
@@ -1783,7 +3425,7 @@ impl DatabaseBody {
   /// This row will be inserted into corresponding [Block]. The [RowOrder] of this row will
   /// be inserted to each view.
   pub async fn create_row(&self, params: CreateRowParams) -> Result<RowOrder, DatabaseError> {
-    let row_order = self.block.create_new_row(params).await?;
+    let row_order = self.blocks.create_new_row(params).await?;
     Ok(row_order)
   }
 
@@ -1846,10 +3488,13 @@ impl DatabaseBody {
     field_id: &str,
   ) -> Vec<RowCell> {
     let row_orders = self.views.get_row_orders(txn, view_id);
-    let rows = self.block.get_rows_from_row_orders(&row_orders).await;
+    let rows = self.blocks.get_rows_from_row_orders(&row_orders).await;
     rows
       .into_iter()
-      .map(|row| RowCell::new(row.id, row.cells.get(field_id).cloned()))
+      .map(|row| {
+        let cell = row.get_cell(field_id).cloned();
+        RowCell::new(row.id, cell)
+      })
       .collect()
   }
   /// Get all fields in the database
@@ -1945,7 +3590,12 @@ impl DatabaseBody {
     field_orders: Vec<FieldOrder>,
     row_orders: Vec<RowOrder>,
   ) -> Result<(), DatabaseError> {
-    let params = CreateViewParamsValidator::validate(params)?;
+    let mut params = CreateViewParamsValidator::validate(params)?;
+    stamp_unset_timestamps(
+      &mut params.created_at,
+      &mut params.modified_at,
+      self.clock.as_ref(),
+    );
     let database_id = self.get_database_id(txn);
     let view = DatabaseView {
       id: params.view_id,
@@ -1962,6 +3612,8 @@ impl DatabaseBody {
       created_at: params.created_at,
       modified_at: params.modified_at,
       is_inline: false,
+      hidden_rows: Default::default(),
+      calculations: params.calculations,
     };
     // tracing::trace!("create linked view with params {:?}", params);
     self.views.insert_view(txn, view);
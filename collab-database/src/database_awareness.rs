@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use collab::core::awareness::Awareness;
+use collab::preclude::block::ClientID;
+use collab::preclude::Subscription;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::rows::RowId;
+
+pub type DatabasePresenceChangeSender = broadcast::Sender<DatabasePresenceChange>;
+pub type DatabasePresenceChangeReceiver = broadcast::Receiver<DatabasePresenceChange>;
+
+/// The awareness state of a user who opened the database, shared with the other users while they
+/// are connected. It's not persisted.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DatabaseAwarenessState {
+  pub version: i64,
+  pub user: DatabaseAwarenessUser,
+  /// The cell the user is focused on, None if the user doesn't edit any cell.
+  pub editing: Option<EditingCell>,
+  /// An optional json string with additional information, for example the color of the user.
+  pub metadata: Option<String>,
+  /// The last time the state was updated, in seconds.
+  pub timestamp: i64,
+}
+
+impl DatabaseAwarenessState {
+  pub fn new(version: i64, user: DatabaseAwarenessUser) -> Self {
+    Self {
+      version,
+      user,
+      editing: None,
+      metadata: None,
+      timestamp: 0,
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DatabaseAwarenessUser {
+  pub uid: i64,
+  pub device_id: String,
+}
+
+/// The row, and optionally the field, a user is focused on in a view.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct EditingCell {
+  pub view_id: String,
+  pub row_id: RowId,
+  /// None if the whole row is selected.
+  pub field_id: Option<String>,
+}
+
+impl EditingCell {
+  pub fn new(view_id: String, row_id: RowId, field_id: Option<String>) -> Self {
+    Self {
+      view_id,
+      row_id,
+      field_id,
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DatabasePresenceChange {
+  DidJoin {
+    client_id: ClientID,
+    state: DatabaseAwarenessState,
+  },
+  DidUpdate {
+    client_id: ClientID,
+    state: DatabaseAwarenessState,
+  },
+  /// The user left. `state` is the last known state of the user.
+  DidLeave {
+    client_id: ClientID,
+    state: Option<DatabaseAwarenessState>,
+  },
+}
+
+/// Returns the states of all the users, including the local one, keyed by client id.
+pub fn database_presences(awareness: &Awareness) -> HashMap<ClientID, DatabaseAwarenessState> {
+  awareness
+    .iter()
+    .flat_map(|(client_id, state)| {
+      let state = serde_json::from_str::<DatabaseAwarenessState>(state.data.as_deref()?).ok()?;
+      Some((client_id, state))
+    })
+    .collect()
+}
+
+pub(crate) fn subscribe_presence_change(
+  awareness: &Awareness,
+  change_tx: DatabasePresenceChangeSender,
+) -> Subscription {
+  // The state of a user is gone when they leave, so the last known states are kept to be sent
+  // along with the leave event. The changes of the local user are not sent.
+  let states = Arc::new(Mutex::new(database_presences(awareness)));
+  awareness.on_change(move |awareness, event, _| {
+    let mut states = match states.lock() {
+      Ok(states) => states,
+      Err(_) => return,
+    };
+    let local_client_id = awareness.client_id();
+    let changed = event
+      .added()
+      .iter()
+      .chain(event.updated())
+      .filter(|client_id| **client_id != local_client_id);
+    for &client_id in changed {
+      let state = match awareness.state::<DatabaseAwarenessState>(client_id) {
+        Some(state) => state,
+        None => continue,
+      };
+      let change = match states.insert(client_id, state.clone()) {
+        None => DatabasePresenceChange::DidJoin { client_id, state },
+        Some(_) => DatabasePresenceChange::DidUpdate { client_id, state },
+      };
+      let _ = change_tx.send(change);
+    }
+    for &client_id in event
+      .removed()
+      .iter()
+      .filter(|client_id| **client_id != local_client_id)
+    {
+      let state = states.remove(&client_id);
+      let _ = change_tx.send(DatabasePresenceChange::DidLeave { client_id, state });
+    }
+  })
+}
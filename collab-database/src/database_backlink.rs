@@ -0,0 +1,58 @@
+use std::sync::Weak;
+
+use collab::core::collab_backlink::{BacklinkRegistry, BacklinkType, LinkSource};
+
+use crate::blocks::BlockMap;
+use crate::entity::FieldType;
+use crate::fields::relation_type_option::RelationCellData;
+use crate::fields::Field;
+use crate::rows::{spawn_row_watcher, Row, RowChangeReceiver};
+use crate::views::ViewChangeReceiver;
+
+pub(crate) fn relation_field_ids(fields: Vec<Field>) -> Vec<String> {
+  fields
+    .into_iter()
+    .filter(|field| FieldType::from(field.field_type) == FieldType::Relation)
+    .map(|field| field.id)
+    .collect()
+}
+
+/// Returns the ids of the rows linked by the relation cells of the row, see [RelationCellData].
+pub(crate) fn row_relations(row: &Row, relation_field_ids: &[String]) -> Vec<String> {
+  relation_field_ids
+    .iter()
+    .filter_map(|field_id| row.cells.get(field_id))
+    .flat_map(|cell| RelationCellData::from(cell).row_ids)
+    .map(|row_id| row_id.to_string())
+    .collect()
+}
+
+/// Keeps the relations of the rows up to date in the registry until the database is dropped.
+pub(crate) fn spawn_backlink_task(
+  database_id: String,
+  inline_view_id: String,
+  relation_field_ids: Vec<String>,
+  rows: Weak<BlockMap>,
+  row_change_rx: RowChangeReceiver,
+  view_change_rx: ViewChangeReceiver,
+  registry: BacklinkRegistry,
+) {
+  spawn_row_watcher(
+    inline_view_id,
+    rows,
+    row_change_rx,
+    view_change_rx,
+    move |rows| {
+      for (row_id, row) in rows {
+        let relations = row
+          .map(|row| row_relations(&row, &relation_field_ids))
+          .unwrap_or_default();
+        registry.set_links(
+          LinkSource::new(&database_id, row_id),
+          BacklinkType::Relation,
+          relations,
+        );
+      }
+    },
+  );
+}
@@ -0,0 +1,282 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use collab::util::AnyMapExt;
+use futures::StreamExt;
+use sha2::{Digest, Sha256};
+
+use crate::database::Database;
+use crate::entity::FieldType;
+use crate::error::DatabaseError;
+use crate::fields::{Field, TypeOptionData};
+use crate::rows::{new_cell_builder, Cell, Cells, Row, RowId};
+use crate::template::entity::CELL_DATA;
+
+/// The key of the computed cell that holds the hash of the source cells it was computed from.
+/// The cell is stale when the hash of the current source cells is different.
+pub const COMPUTE_SOURCE_HASH: &str = "source_hash";
+
+/// The field types whose cells are computed by a [ComputeCellProvider].
+pub fn is_computed_field_type(field_type: &FieldType) -> bool {
+  matches!(field_type, FieldType::Summary | FieldType::Translate)
+}
+
+/// A request to compute the cell of a [FieldType::Summary] or [FieldType::Translate] field.
+#[derive(Debug, Clone)]
+pub struct ComputeCellRequest {
+  pub row_id: RowId,
+  pub field_id: String,
+  pub field_type: FieldType,
+  /// The type option of the field, for example the language of a translation.
+  pub type_option: Option<TypeOptionData>,
+  /// The cells of the row the computed cell is made of, by field id. The cells of the other
+  /// computed fields are left out.
+  pub source: Cells,
+  pub source_hash: String,
+}
+
+/// Computes the text of the cells of a field type, usually by calling an AI service. It's
+/// implemented by the application and registered in the [ComputeCellHooks].
+#[async_trait]
+pub trait ComputeCellProvider: Send + Sync {
+  async fn compute(&self, request: &ComputeCellRequest) -> Result<String, DatabaseError>;
+}
+
+/// The [ComputeCellProvider] of each computed field type.
+#[derive(Clone, Default)]
+pub struct ComputeCellHooks {
+  providers: HashMap<FieldType, Arc<dyn ComputeCellProvider>>,
+}
+
+impl ComputeCellHooks {
+  /// Register the provider of the field type, replacing the previous one. Only
+  /// [FieldType::Summary] and [FieldType::Translate] are computed.
+  pub fn register(
+    &mut self,
+    field_type: FieldType,
+    provider: Arc<dyn ComputeCellProvider>,
+  ) -> Result<(), DatabaseError> {
+    if !is_computed_field_type(&field_type) {
+      return Err(DatabaseError::NoRequiredData(format!(
+        "a computed field type, got {:?}",
+        field_type
+      )));
+    }
+    self.providers.insert(field_type, provider);
+    Ok(())
+  }
+
+  pub fn get(&self, field_type: &FieldType) -> Option<Arc<dyn ComputeCellProvider>> {
+    self.providers.get(field_type).cloned()
+  }
+}
+
+/// The result of a request run by [ComputeCellQueue::run].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ComputeCellOutcome {
+  /// The computed cell was written to the row.
+  Updated { row_id: RowId, field_id: String },
+  /// The source cells changed while the cell was computed. The result was dropped and the cell
+  /// was queued again.
+  Outdated { row_id: RowId, field_id: String },
+  Failed {
+    row_id: RowId,
+    field_id: String,
+    reason: String,
+  },
+}
+
+/// Return the source cells of the computed field in the row.
+pub fn compute_source_cells(fields: &[Field], row: &Row) -> Cells {
+  let mut source = Cells::new();
+  for field in fields {
+    if is_computed_field_type(&FieldType::from(field.field_type)) {
+      continue;
+    }
+    if let Some(cell) = row.cells.get(&field.id) {
+      source.insert(field.id.clone(), cell.clone());
+    }
+  }
+  source
+}
+
+/// Return the hash of the data of the source cells. The hash doesn't depend on the order of the
+/// cells or on their other keys, like the last modified time.
+pub fn compute_source_hash(source: &Cells) -> String {
+  let mut field_ids = source.keys().collect::<Vec<_>>();
+  field_ids.sort();
+  let mut hasher = Sha256::new();
+  for field_id in field_ids {
+    let data = source
+      .get(field_id)
+      .and_then(|cell| cell.get(CELL_DATA))
+      .map(|data| data.to_string())
+      .unwrap_or_default();
+    hasher.update(field_id.as_bytes());
+    hasher.update([0]);
+    hasher.update(data.as_bytes());
+    hasher.update([0]);
+  }
+  format!("{:x}", hasher.finalize())
+}
+
+/// Return true if the computed cell was not computed from the given source cells. A row without
+/// any source data has nothing to compute, so its cell is never stale.
+pub fn is_computed_cell_stale(cell: Option<&Cell>, source: &Cells) -> bool {
+  let has_data = source.values().any(|cell| {
+    cell
+      .get_as::<String>(CELL_DATA)
+      .map_or(false, |data| !data.is_empty())
+  });
+  if !has_data {
+    return false;
+  }
+  let computed_hash = cell.and_then(|cell| cell.get_as::<String>(COMPUTE_SOURCE_HASH));
+  computed_hash.as_deref() != Some(compute_source_hash(source).as_str())
+}
+
+/// Queues the stale cells of the computed fields and computes them with the registered
+/// providers. The queue is driven by the caller: [ComputeCellQueue::enqueue_row] after a row
+/// changed, or [ComputeCellQueue::enqueue_stale_cells] to scan the whole database, then
+/// [ComputeCellQueue::run].
+pub struct ComputeCellQueue {
+  hooks: ComputeCellHooks,
+  pending: VecDeque<ComputeCellRequest>,
+  queued: HashSet<(RowId, String)>,
+}
+
+impl ComputeCellQueue {
+  pub fn new(hooks: ComputeCellHooks) -> Self {
+    Self {
+      hooks,
+      pending: VecDeque::new(),
+      queued: HashSet::new(),
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.pending.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.pending.is_empty()
+  }
+
+  /// Queue the stale cells of all the rows of the database. Return the number of queued cells.
+  pub async fn enqueue_stale_cells(&mut self, database: &Database) -> usize {
+    let fields = database.get_all_fields();
+    let len = self.pending.len();
+    let mut rows = Box::pin(database.get_all_rows(None).await);
+    while let Some(row) = rows.next().await {
+      if let Ok(row) = row {
+        self.enqueue_stale_cells_of_row(&fields, &row);
+      }
+    }
+    self.pending.len() - len
+  }
+
+  /// Queue the stale cells of the row. Return the number of queued cells.
+  pub async fn enqueue_row(&mut self, database: &Database, row_id: &RowId) -> usize {
+    let fields = database.get_all_fields();
+    let row = database.get_row(row_id).await;
+    let len = self.pending.len();
+    self.enqueue_stale_cells_of_row(&fields, &row);
+    self.pending.len() - len
+  }
+
+  /// Compute the queued cells one by one and write each result to its row. A result is only
+  /// written if the source cells didn't change while it was computed, the check and the write
+  /// happen while the row is locked. The cells queued again because they were outdated are left
+  /// for the next run.
+  pub async fn run(&mut self, database: &Database) -> Vec<ComputeCellOutcome> {
+    let mut outcomes = vec![];
+    for _ in 0..self.pending.len() {
+      let request = match self.pending.pop_front() {
+        None => break,
+        Some(request) => request,
+      };
+      self
+        .queued
+        .remove(&(request.row_id.clone(), request.field_id.clone()));
+      let outcome = self.compute(database, &request).await;
+      if matches!(outcome, ComputeCellOutcome::Outdated { .. }) {
+        self.enqueue_row(database, &request.row_id).await;
+      }
+      outcomes.push(outcome);
+    }
+    outcomes
+  }
+
+  async fn compute(&self, database: &Database, request: &ComputeCellRequest) -> ComputeCellOutcome {
+    let failed = |reason: String| ComputeCellOutcome::Failed {
+      row_id: request.row_id.clone(),
+      field_id: request.field_id.clone(),
+      reason,
+    };
+    let provider = match self.hooks.get(&request.field_type) {
+      None => return failed(format!("no provider for {:?}", request.field_type)),
+      Some(provider) => provider,
+    };
+    let text = match provider.compute(request).await {
+      Ok(text) => text,
+      Err(err) => return failed(err.to_string()),
+    };
+
+    let database_row = match database.get_or_init_database_row(&request.row_id).await {
+      None => return failed("the row is not found".to_string()),
+      Some(database_row) => database_row,
+    };
+    let mut database_row = database_row.write().await;
+    let fields = database.get_all_fields();
+    let source = database_row
+      .get_row()
+      .map(|row| compute_source_cells(&fields, &row))
+      .unwrap_or_default();
+    if compute_source_hash(&source) != request.source_hash {
+      return ComputeCellOutcome::Outdated {
+        row_id: request.row_id.clone(),
+        field_id: request.field_id.clone(),
+      };
+    }
+
+    let mut cell = new_cell_builder(request.field_type.clone());
+    cell.insert(CELL_DATA.into(), text.into());
+    cell.insert(
+      COMPUTE_SOURCE_HASH.into(),
+      request.source_hash.clone().into(),
+    );
+    database_row.update(|update| {
+      update.update_cells(|cells| {
+        cells.insert_cell(&request.field_id, cell);
+      });
+    });
+    ComputeCellOutcome::Updated {
+      row_id: request.row_id.clone(),
+      field_id: request.field_id.clone(),
+    }
+  }
+
+  fn enqueue_stale_cells_of_row(&mut self, fields: &[Field], row: &Row) {
+    let source = compute_source_cells(fields, row);
+    for field in fields {
+      let field_type = FieldType::from(field.field_type);
+      if !is_computed_field_type(&field_type) || self.hooks.get(&field_type).is_none() {
+        continue;
+      }
+      let key = (row.id.clone(), field.id.clone());
+      if self.queued.contains(&key) || !is_computed_cell_stale(row.cells.get(&field.id), &source) {
+        continue;
+      }
+      self.queued.insert(key);
+      self.pending.push_back(ComputeCellRequest {
+        row_id: row.id.clone(),
+        field_id: field.id.clone(),
+        type_option: field.get_any_type_option(field_type.type_id()),
+        field_type,
+        source: source.clone(),
+        source_hash: compute_source_hash(&source),
+      });
+    }
+  }
+}
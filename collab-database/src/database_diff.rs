@@ -0,0 +1,348 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use collab::core::collab::DataSource;
+use collab::core::origin::CollabOrigin;
+use collab::entity::EncodedCollab;
+use collab::preclude::Collab;
+use collab_entity::CollabType;
+
+use crate::database::DatabaseBody;
+use crate::entity::{DatabaseView, FieldType};
+use crate::error::DatabaseError;
+use crate::fields::Field;
+use crate::rows::{RowDetail, RowId};
+use crate::views::DatabaseLayout;
+use crate::workspace_database::NoPersistenceDatabaseCollabService;
+
+/// The changes between two versions of a database, for example two snapshots. The fields and the
+/// views are sorted by id, the rows keep their order in the views.
+///
+/// The rows are separate collabs, so the edits of their cells are not part of this diff, see
+/// [RowDiff].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DatabaseDiff {
+  pub inserted_fields: Vec<DatabaseFieldSummary>,
+  pub deleted_fields: Vec<DatabaseFieldSummary>,
+  pub updated_fields: Vec<DatabaseFieldChange>,
+  pub inserted_views: Vec<DatabaseViewSummary>,
+  pub deleted_views: Vec<DatabaseViewSummary>,
+  pub updated_views: Vec<DatabaseViewChange>,
+  pub inserted_rows: Vec<RowId>,
+  pub deleted_rows: Vec<RowId>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatabaseFieldSummary {
+  pub field_id: String,
+  pub name: String,
+  pub field_type: FieldType,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatabaseFieldChange {
+  pub field_id: String,
+  pub name: String,
+  /// The previous name if the field was renamed.
+  pub old_name: Option<String>,
+  pub type_changed: bool,
+  pub type_options_changed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatabaseViewSummary {
+  pub view_id: String,
+  pub name: String,
+  pub layout: DatabaseLayout,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatabaseViewChange {
+  pub view_id: String,
+  pub name: String,
+  /// The previous name if the view was renamed.
+  pub old_name: Option<String>,
+  /// The layout or the layout settings changed.
+  pub layout_changed: bool,
+  pub filters_changed: bool,
+  pub sorts_changed: bool,
+  pub groups_changed: bool,
+  /// The fields were reordered, shown, hidden or resized.
+  pub field_settings_changed: bool,
+  /// Rows were added to the view, removed from it or reordered.
+  pub rows_changed: bool,
+}
+
+/// The changes between two versions of a database row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowDiff {
+  pub row_id: RowId,
+  /// The ids of the fields of the new cells.
+  pub inserted_cells: Vec<String>,
+  /// The ids of the fields of the removed cells.
+  pub deleted_cells: Vec<String>,
+  /// The ids of the fields of the edited cells.
+  pub updated_cells: Vec<String>,
+  /// The icon or the cover of the row changed.
+  pub meta_changed: bool,
+}
+
+impl DatabaseDiff {
+  pub fn new(
+    older_fields: &[Field],
+    older_views: &[DatabaseView],
+    newer_fields: &[Field],
+    newer_views: &[DatabaseView],
+  ) -> Self {
+    let mut diff = DatabaseDiff::default();
+
+    let (inserted, deleted, updated) = diff_by_id(older_fields, newer_fields, |field| &field.id);
+    diff.inserted_fields = inserted.into_iter().map(Into::into).collect();
+    diff.deleted_fields = deleted.into_iter().map(Into::into).collect();
+    diff.updated_fields = updated
+      .into_iter()
+      .filter(|(old, new)| old != new)
+      .map(|(old, new)| DatabaseFieldChange {
+        field_id: new.id.clone(),
+        name: new.name.clone(),
+        old_name: (old.name != new.name).then(|| old.name.clone()),
+        type_changed: old.field_type != new.field_type,
+        type_options_changed: old.type_options != new.type_options,
+      })
+      .collect();
+
+    let (inserted, deleted, updated) = diff_by_id(older_views, newer_views, |view| &view.id);
+    diff.inserted_views = inserted.into_iter().map(Into::into).collect();
+    diff.deleted_views = deleted.into_iter().map(Into::into).collect();
+    diff.updated_views = updated
+      .into_iter()
+      .map(|(old, new)| DatabaseViewChange {
+        view_id: new.id.clone(),
+        name: new.name.clone(),
+        old_name: (old.name != new.name).then(|| old.name.clone()),
+        layout_changed: old.layout != new.layout || old.layout_settings != new.layout_settings,
+        filters_changed: old.filters != new.filters,
+        sorts_changed: old.sorts != new.sorts,
+        groups_changed: old.group_settings != new.group_settings,
+        field_settings_changed: old.field_orders != new.field_orders
+          || old.field_settings != new.field_settings,
+        rows_changed: old.row_orders != new.row_orders,
+      })
+      .filter(DatabaseViewChange::has_changes)
+      .collect();
+
+    let older_rows = row_ids_of_views(older_views);
+    let newer_rows = row_ids_of_views(newer_views);
+    let older_row_set = older_rows.iter().collect::<HashSet<_>>();
+    let newer_row_set = newer_rows.iter().collect::<HashSet<_>>();
+    diff.inserted_rows = newer_rows
+      .iter()
+      .filter(|row_id| !older_row_set.contains(row_id))
+      .cloned()
+      .collect();
+    diff.deleted_rows = older_rows
+      .iter()
+      .filter(|row_id| !newer_row_set.contains(row_id))
+      .cloned()
+      .collect();
+    diff
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.inserted_fields.is_empty()
+      && self.deleted_fields.is_empty()
+      && self.updated_fields.is_empty()
+      && self.inserted_views.is_empty()
+      && self.deleted_views.is_empty()
+      && self.updated_views.is_empty()
+      && self.inserted_rows.is_empty()
+      && self.deleted_rows.is_empty()
+  }
+}
+
+impl DatabaseViewChange {
+  fn has_changes(&self) -> bool {
+    self.old_name.is_some()
+      || self.layout_changed
+      || self.filters_changed
+      || self.sorts_changed
+      || self.groups_changed
+      || self.field_settings_changed
+      || self.rows_changed
+  }
+}
+
+impl RowDiff {
+  pub fn new(older: &RowDetail, newer: &RowDetail) -> Self {
+    let older_cells = &older.row.cells;
+    let newer_cells = &newer.row.cells;
+    let mut inserted_cells = vec![];
+    let mut updated_cells = vec![];
+    for (field_id, cell) in newer_cells {
+      match older_cells.get(field_id) {
+        None => inserted_cells.push(field_id.clone()),
+        Some(old_cell) if old_cell != cell => updated_cells.push(field_id.clone()),
+        Some(_) => {},
+      }
+    }
+    let mut deleted_cells = older_cells
+      .keys()
+      .filter(|field_id| !newer_cells.contains_key(*field_id))
+      .cloned()
+      .collect::<Vec<_>>();
+    inserted_cells.sort();
+    updated_cells.sort();
+    deleted_cells.sort();
+
+    let meta_changed = older.meta.icon_url != newer.meta.icon_url
+      || serde_json::to_value(&older.meta.cover).ok()
+        != serde_json::to_value(&newer.meta.cover).ok();
+    Self {
+      row_id: newer.row.id.clone(),
+      inserted_cells,
+      deleted_cells,
+      updated_cells,
+      meta_changed,
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.inserted_cells.is_empty()
+      && self.deleted_cells.is_empty()
+      && self.updated_cells.is_empty()
+      && !self.meta_changed
+  }
+}
+
+impl From<&Field> for DatabaseFieldSummary {
+  fn from(field: &Field) -> Self {
+    Self {
+      field_id: field.id.clone(),
+      name: field.name.clone(),
+      field_type: FieldType::from(field.field_type),
+    }
+  }
+}
+
+impl From<&DatabaseView> for DatabaseViewSummary {
+  fn from(view: &DatabaseView) -> Self {
+    Self {
+      view_id: view.id.clone(),
+      name: view.name.clone(),
+      layout: view.layout,
+    }
+  }
+}
+
+/// Decodes both versions of the database and returns the changes from `older` to `newer`.
+pub fn diff_database_snapshots(
+  older: &EncodedCollab,
+  newer: &EncodedCollab,
+) -> Result<DatabaseDiff, DatabaseError> {
+  let (older_fields, older_views) = database_from_encoded_collab(older)?;
+  let (newer_fields, newer_views) = database_from_encoded_collab(newer)?;
+  Ok(DatabaseDiff::new(
+    &older_fields,
+    &older_views,
+    &newer_fields,
+    &newer_views,
+  ))
+}
+
+/// Decodes both versions of the database row and returns the changes from `older` to `newer`.
+pub fn diff_row_snapshots(
+  older: &EncodedCollab,
+  newer: &EncodedCollab,
+) -> Result<RowDiff, DatabaseError> {
+  let older = row_from_encoded_collab(older)?;
+  let newer = row_from_encoded_collab(newer)?;
+  Ok(RowDiff::new(&older, &newer))
+}
+
+fn collab_from_encoded_collab(
+  encoded_collab: &EncodedCollab,
+  collab_type: CollabType,
+) -> Result<Collab, DatabaseError> {
+  let collab = Collab::new_with_source(
+    CollabOrigin::Empty,
+    "",
+    DataSource::from(encoded_collab.clone()),
+    vec![],
+    false,
+  )
+  .map_err(|err| DatabaseError::Internal(err.into()))?;
+  collab_type.validate_require_data(&collab)?;
+  Ok(collab)
+}
+
+fn database_from_encoded_collab(
+  encoded_collab: &EncodedCollab,
+) -> Result<(Vec<Field>, Vec<DatabaseView>), DatabaseError> {
+  let collab = collab_from_encoded_collab(encoded_collab, CollabType::Database)?;
+  let body = DatabaseBody::from_collab(&collab, Arc::new(NoPersistenceDatabaseCollabService))
+    .ok_or_else(|| DatabaseError::NoRequiredData("Can not open database".to_string()))?;
+  let txn = collab.transact();
+  let fields = body.fields.get_all_fields(&txn);
+  let mut views = body.views.get_all_views(&txn);
+  // The inline view holds all the rows, so its order comes first.
+  let inline_view_id = body.try_get_inline_view_id(&txn);
+  views.sort_by_key(|view| Some(&view.id) != inline_view_id.as_ref());
+  Ok((fields, views))
+}
+
+fn row_from_encoded_collab(encoded_collab: &EncodedCollab) -> Result<RowDetail, DatabaseError> {
+  let collab = collab_from_encoded_collab(encoded_collab, CollabType::DatabaseRow)?;
+  RowDetail::from_collab(&collab)
+    .ok_or_else(|| DatabaseError::NoRequiredData("Can not open database row".to_string()))
+}
+
+/// Return the items of `newer` that are not in `older`, the items of `older` that are not in
+/// `newer` and the pairs of items that are in both, sorted by id.
+#[allow(clippy::type_complexity)]
+fn diff_by_id<'a, T, F>(
+  older: &'a [T],
+  newer: &'a [T],
+  id: F,
+) -> (Vec<&'a T>, Vec<&'a T>, Vec<(&'a T, &'a T)>)
+where
+  F: Fn(&T) -> &String,
+{
+  let older_by_id = older
+    .iter()
+    .map(|item| (id(item), item))
+    .collect::<HashMap<_, _>>();
+  let newer_by_id = newer
+    .iter()
+    .map(|item| (id(item), item))
+    .collect::<HashMap<_, _>>();
+
+  let mut inserted = vec![];
+  let mut updated = vec![];
+  for (item_id, new_item) in &newer_by_id {
+    match older_by_id.get(item_id) {
+      None => inserted.push(*new_item),
+      Some(old_item) => updated.push((*old_item, *new_item)),
+    }
+  }
+  let mut deleted = older_by_id
+    .iter()
+    .filter(|(item_id, _)| !newer_by_id.contains_key(*item_id))
+    .map(|(_, item)| *item)
+    .collect::<Vec<_>>();
+
+  inserted.sort_by(|a, b| id(a).cmp(id(b)));
+  deleted.sort_by(|a, b| id(a).cmp(id(b)));
+  updated.sort_by(|a, b| id(a.1).cmp(id(b.1)));
+  (inserted, deleted, updated)
+}
+
+/// Return the ids of the rows of the views, in the order they appear in the views.
+fn row_ids_of_views(views: &[DatabaseView]) -> Vec<RowId> {
+  let mut seen = HashSet::new();
+  views
+    .iter()
+    .flat_map(|view| view.row_orders.iter())
+    .filter(|row_order| seen.insert(row_order.id.clone()))
+    .map(|row_order| row_order.id.clone())
+    .collect()
+}
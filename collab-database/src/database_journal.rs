@@ -0,0 +1,506 @@
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, Mutex, Weak};
+
+use collab::core::clock::ClockProvider;
+use collab::core::collab_webhook::WebhookEvent;
+use collab::core::origin::CollabOrigin;
+use collab::preclude::{
+  DeepObservable, EntryChange, Event, MapExt, MapRef, PathSegment, Subscription, TransactionMut,
+};
+use collab::util::AnyMapExt;
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::StreamExt;
+
+use crate::database_view_rows::apply_row_order_delta;
+use crate::fields::field_last_modified_from_map_ref;
+use crate::rows::{RowChange, RowChangeReceiver, RowId, LAST_MODIFIED};
+use crate::views::define::{
+  DATABASE_VIEW_FIELD_ORDERS, DATABASE_VIEW_FILTERS, DATABASE_VIEW_GROUPS,
+  DATABASE_VIEW_HIDDEN_ROWS, DATABASE_VIEW_LAYOUT, DATABASE_VIEW_SORTS, VIEW_LAYOUT_SETTINGS,
+};
+use crate::views::{view_modified_at_from_map_ref, DatabaseViewChange, ViewChangeReceiver};
+
+/// The setting of a view changed by a [JournalOperation::ViewSettingChanged].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ViewSetting {
+  Layout,
+  Filter,
+  Sort,
+  Group,
+  FieldOrder,
+  HiddenRows,
+}
+
+/// A high level operation on a database, recorded by a [DatabaseJournal].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JournalOperation {
+  RowCreated {
+    row_id: RowId,
+  },
+  RowDeleted {
+    row_id: RowId,
+  },
+  CellUpdated {
+    row_id: RowId,
+    field_id: String,
+  },
+  FieldCreated {
+    field_id: String,
+  },
+  FieldUpdated {
+    field_id: String,
+  },
+  FieldDeleted {
+    field_id: String,
+  },
+  ViewSettingChanged {
+    view_id: String,
+    setting: ViewSetting,
+  },
+}
+
+/// Where the operation of a [JournalEntry] was made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalOrigin {
+  /// Made by this client.
+  Local,
+  /// Received from another client with the sync.
+  Remote,
+  /// The change events of the operation don't tell where it was made.
+  #[default]
+  Unknown,
+}
+
+impl JournalOrigin {
+  fn from_change(is_local_change: bool) -> Self {
+    if is_local_change {
+      JournalOrigin::Local
+    } else {
+      JournalOrigin::Remote
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct JournalEntry {
+  pub operation: JournalOperation,
+  /// The time, in seconds, when the operation was made. The cells, the fields and the views
+  /// carry the time of their last change, the other operations are stamped when they are
+  /// observed.
+  pub timestamp: i64,
+  pub origin: JournalOrigin,
+}
+
+/// The number of entries a subscriber of the journal can fall behind before it misses some.
+pub const JOURNAL_SUBSCRIBER_CAPACITY: usize = 100;
+
+/// Records the operations on a database in a ring buffer, so the oldest entries are dropped once
+/// the journal is full. It's started with [crate::database::Database::start_journal] and
+/// records until the database or the journal is dropped. The entries can also be recorded by
+/// the application with [DatabaseJournal::record].
+#[derive(Clone)]
+pub struct DatabaseJournal {
+  entries: Arc<Mutex<VecDeque<JournalEntry>>>,
+  capacity: usize,
+  clock: Arc<dyn ClockProvider>,
+  entry_tx: broadcast::Sender<JournalEntry>,
+  /// The observers of the fields and the views of the database, see [observe_journal_changes].
+  subscriptions: Arc<Mutex<Vec<Subscription>>>,
+}
+
+impl DatabaseJournal {
+  pub fn new(capacity: usize, clock: Arc<dyn ClockProvider>) -> Self {
+    let (entry_tx, _) = broadcast::channel(JOURNAL_SUBSCRIBER_CAPACITY);
+    Self {
+      entries: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+      capacity,
+      clock,
+      entry_tx,
+      subscriptions: Default::default(),
+    }
+  }
+
+  /// Subscribe to the entries as they are recorded, for example to forward them to the
+  /// `EventEmitter` of collab-plugins with its `forward_broadcast` method.
+  /// A subscriber that falls behind by more than [JOURNAL_SUBSCRIBER_CAPACITY] entries gets a
+  /// `Lagged` error and misses the oldest ones. They may still be in the journal, see
+  /// [DatabaseJournal::entries_since].
+  pub fn subscribe(&self) -> broadcast::Receiver<JournalEntry> {
+    self.entry_tx.subscribe()
+  }
+
+  pub fn capacity(&self) -> usize {
+    self.capacity
+  }
+
+  pub fn record(&self, operation: JournalOperation, origin: JournalOrigin) {
+    let entry = JournalEntry {
+      operation,
+      timestamp: self.clock.timestamp(),
+      origin,
+    };
+    push_entry(&self.entries, self.capacity, &self.entry_tx, entry);
+  }
+
+  /// Return all the entries, oldest first.
+  pub fn entries(&self) -> Vec<JournalEntry> {
+    self.entries.lock().unwrap().iter().cloned().collect()
+  }
+
+  /// Return the entries recorded at or after the timestamp, oldest first.
+  pub fn entries_since(&self, timestamp: i64) -> Vec<JournalEntry> {
+    self.entries_between(timestamp, i64::MAX)
+  }
+
+  /// Return the entries recorded between the timestamps, both included, oldest first.
+  pub fn entries_between(&self, start: i64, end: i64) -> Vec<JournalEntry> {
+    self
+      .entries
+      .lock()
+      .unwrap()
+      .iter()
+      .filter(|entry| entry.timestamp >= start && entry.timestamp <= end)
+      .cloned()
+      .collect()
+  }
+
+  pub fn len(&self) -> usize {
+    self.entries.lock().unwrap().len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.entries.lock().unwrap().is_empty()
+  }
+
+  pub fn clear(&self) {
+    self.entries.lock().unwrap().clear();
+  }
+}
+
+fn push_entry(
+  entries: &Mutex<VecDeque<JournalEntry>>,
+  capacity: usize,
+  entry_tx: &broadcast::Sender<JournalEntry>,
+  entry: JournalEntry,
+) {
+  if capacity > 0 {
+    let mut entries = entries.lock().unwrap();
+    while entries.len() >= capacity {
+      entries.pop_front();
+    }
+    entries.push_back(entry.clone());
+  }
+  let _ = entry_tx.send(entry);
+}
+
+impl JournalOperation {
+  /// The name of the operation, like `row_created`.
+  pub fn name(&self) -> &'static str {
+    match self {
+      JournalOperation::RowCreated { .. } => "row_created",
+      JournalOperation::RowDeleted { .. } => "row_deleted",
+      JournalOperation::CellUpdated { .. } => "cell_updated",
+      JournalOperation::FieldCreated { .. } => "field_created",
+      JournalOperation::FieldUpdated { .. } => "field_updated",
+      JournalOperation::FieldDeleted { .. } => "field_deleted",
+      JournalOperation::ViewSettingChanged { .. } => "view_setting_changed",
+    }
+  }
+}
+
+impl WebhookEvent for JournalEntry {
+  fn event_type(&self) -> String {
+    format!("database.{}", self.operation.name())
+  }
+
+  fn data(&self) -> serde_json::Value {
+    serde_json::to_value(self).unwrap_or_default()
+  }
+}
+
+/// Records the cells updated and the rows created or deleted in the database until the journal
+/// or the database is dropped. The rows are created and deleted when they are inserted into or
+/// removed from the inline view, so a row moved in the view is not recorded.
+pub(crate) fn spawn_journal_task(
+  journal: &DatabaseJournal,
+  inline_view_id: String,
+  inline_row_ids: Vec<RowId>,
+  row_change_rx: RowChangeReceiver,
+  view_change_rx: ViewChangeReceiver,
+) {
+  let row_changes = BroadcastStream::new(row_change_rx).filter_map(|change| change.ok());
+  let view_changes = BroadcastStream::new(view_change_rx).filter_map(|change| change.ok());
+  let mut events = row_changes
+    .map(JournalEvent::Row)
+    .merge(view_changes.map(JournalEvent::View));
+
+  let recorder = JournalRecorder::from(journal);
+  let mut row_ids = inline_row_ids;
+  tokio::spawn(async move {
+    while let Some(event) = events.next().await {
+      let entries = match recorder.entries.upgrade() {
+        None => break,
+        Some(entries) => entries,
+      };
+      match event {
+        JournalEvent::Row(RowChange::DidUpdateCell {
+          row_id,
+          field_id,
+          value,
+          is_local_change,
+        }) => {
+          // A cleared cell has no modification time.
+          let timestamp = value
+            .get_as::<i64>(LAST_MODIFIED)
+            .unwrap_or_else(|| recorder.clock.timestamp());
+          recorder.push(
+            &entries,
+            JournalEntry {
+              operation: JournalOperation::CellUpdated { row_id, field_id },
+              timestamp,
+              origin: JournalOrigin::from_change(is_local_change),
+            },
+          );
+        },
+        JournalEvent::View(DatabaseViewChange::DidUpdateRowOrders {
+          database_view_id,
+          is_local_change,
+          insert_row_orders,
+          delete_row_indexes,
+        }) if database_view_id == inline_view_id => {
+          let new_row_ids = apply_row_order_delta(&row_ids, insert_row_orders, &delete_row_indexes);
+          let timestamp = recorder.clock.timestamp();
+          for operation in row_order_operations(&row_ids, &new_row_ids) {
+            recorder.push(
+              &entries,
+              JournalEntry {
+                operation,
+                timestamp,
+                origin: JournalOrigin::from_change(is_local_change),
+              },
+            );
+          }
+          row_ids = new_row_ids;
+        },
+        JournalEvent::Row(_) | JournalEvent::View(_) => {},
+      }
+    }
+  });
+}
+
+/// Records the fields and the settings of the views changed by the transactions of the database
+/// until the journal is dropped. The origin of an entry is the origin of the transaction and its
+/// time is the modification time of the field or of the view.
+pub(crate) fn observe_journal_changes(
+  journal: &DatabaseJournal,
+  origin: CollabOrigin,
+  field_map: &MapRef,
+  view_map: &MapRef,
+) {
+  let field_subscription = {
+    let recorder = JournalRecorder::from(journal);
+    let origin = origin.clone();
+    let fields = field_map.clone();
+    field_map.observe_deep(move |txn, events| {
+      if let Some(entries) = recorder.entries.upgrade() {
+        let origin = JournalOrigin::from_change(CollabOrigin::from(txn) == origin);
+        for (operation, timestamp) in field_operations(txn, &fields, events.iter()) {
+          let timestamp = timestamp.unwrap_or_else(|| recorder.clock.timestamp());
+          let entry = JournalEntry {
+            operation,
+            timestamp,
+            origin,
+          };
+          recorder.push(&entries, entry);
+        }
+      }
+    })
+  };
+  let view_subscription = {
+    let recorder = JournalRecorder::from(journal);
+    let views = view_map.clone();
+    view_map.observe_deep(move |txn, events| {
+      if let Some(entries) = recorder.entries.upgrade() {
+        let origin = JournalOrigin::from_change(CollabOrigin::from(txn) == origin);
+        for (operation, timestamp) in view_operations(txn, &views, events.iter()) {
+          let timestamp = timestamp.unwrap_or_else(|| recorder.clock.timestamp());
+          let entry = JournalEntry {
+            operation,
+            timestamp,
+            origin,
+          };
+          recorder.push(&entries, entry);
+        }
+      }
+    })
+  };
+  journal
+    .subscriptions
+    .lock()
+    .unwrap()
+    .extend([field_subscription, view_subscription]);
+}
+
+enum JournalEvent {
+  Row(RowChange),
+  View(DatabaseViewChange),
+}
+
+/// Pushes the entries into a journal without keeping it alive.
+struct JournalRecorder {
+  entries: Weak<Mutex<VecDeque<JournalEntry>>>,
+  capacity: usize,
+  clock: Arc<dyn ClockProvider>,
+  entry_tx: broadcast::Sender<JournalEntry>,
+}
+
+impl From<&DatabaseJournal> for JournalRecorder {
+  fn from(journal: &DatabaseJournal) -> Self {
+    Self {
+      entries: Arc::downgrade(&journal.entries),
+      capacity: journal.capacity,
+      clock: journal.clock.clone(),
+      entry_tx: journal.entry_tx.clone(),
+    }
+  }
+}
+
+impl JournalRecorder {
+  fn push(&self, entries: &Mutex<VecDeque<JournalEntry>>, entry: JournalEntry) {
+    push_entry(entries, self.capacity, &self.entry_tx, entry);
+  }
+}
+
+/// Return the fields created, updated or deleted by the events of the field map, with their
+/// modification time. The deleted fields have no modification time.
+fn field_operations<'a>(
+  txn: &TransactionMut,
+  fields: &MapRef,
+  events: impl Iterator<Item = &'a Event>,
+) -> Vec<(JournalOperation, Option<i64>)> {
+  let last_modified = |field_id: &str| {
+    fields
+      .get_with_txn::<_, MapRef>(txn, field_id)
+      .and_then(|map_ref| field_last_modified_from_map_ref(&map_ref, txn))
+  };
+  let mut operations = vec![];
+  let mut updated = HashSet::new();
+  for event in events {
+    match event.path().front() {
+      // A change inside a field.
+      Some(PathSegment::Key(field_id)) => {
+        if updated.insert(field_id.to_string()) {
+          let field_id = field_id.to_string();
+          let timestamp = last_modified(&field_id);
+          operations.push((JournalOperation::FieldUpdated { field_id }, timestamp));
+        }
+      },
+      _ => {
+        if let Event::Map(map_event) = event {
+          for (key, change) in map_event.keys(txn).iter() {
+            let field_id = key.to_string();
+            let operation = match change {
+              EntryChange::Inserted(_) => JournalOperation::FieldCreated {
+                field_id: field_id.clone(),
+              },
+              EntryChange::Updated(_, _) if updated.insert(field_id.clone()) => {
+                JournalOperation::FieldUpdated {
+                  field_id: field_id.clone(),
+                }
+              },
+              EntryChange::Updated(_, _) => continue,
+              EntryChange::Removed(_) => {
+                operations.push((JournalOperation::FieldDeleted { field_id }, None));
+                continue;
+              },
+            };
+            operations.push((operation, last_modified(&field_id)));
+          }
+        }
+      },
+    }
+  }
+  operations
+}
+
+/// Return the rows created and deleted by a change of the row orders of the inline view.
+fn row_order_operations(old: &[RowId], new: &[RowId]) -> Vec<JournalOperation> {
+  let old_row_ids = old.iter().collect::<HashSet<_>>();
+  let new_row_ids = new.iter().collect::<HashSet<_>>();
+  let deleted = old
+    .iter()
+    .filter(|row_id| !new_row_ids.contains(row_id))
+    .map(|row_id| JournalOperation::RowDeleted {
+      row_id: row_id.clone(),
+    });
+  let created = new
+    .iter()
+    .filter(|row_id| !old_row_ids.contains(row_id))
+    .map(|row_id| JournalOperation::RowCreated {
+      row_id: row_id.clone(),
+    });
+  deleted.chain(created).collect()
+}
+
+/// Return the settings of the views changed by the events of the view map, with the modification
+/// time of the view. The views created and deleted and their row orders are not settings.
+fn view_operations<'a>(
+  txn: &TransactionMut,
+  views: &MapRef,
+  events: impl Iterator<Item = &'a Event>,
+) -> Vec<(JournalOperation, Option<i64>)> {
+  let mut changed = vec![];
+  for event in events {
+    let path = event.path();
+    let view_id = match path.front() {
+      Some(PathSegment::Key(view_id)) => view_id.to_string(),
+      _ => continue,
+    };
+    match path.get(1) {
+      // A change inside a setting of the view.
+      Some(PathSegment::Key(key)) => {
+        changed.extend(view_setting(key).map(|s| (view_id.clone(), s)))
+      },
+      // The keys of the view itself.
+      _ => {
+        if let Event::Map(map_event) = event {
+          for key in map_event.keys(txn).keys() {
+            changed.extend(view_setting(key).map(|s| (view_id.clone(), s)));
+          }
+        }
+      },
+    }
+  }
+
+  let mut seen = HashSet::new();
+  changed
+    .into_iter()
+    .filter(|change| seen.insert(change.clone()))
+    .map(|(view_id, setting)| {
+      let timestamp = views
+        .get_with_txn::<_, MapRef>(txn, &view_id)
+        .and_then(|map_ref| view_modified_at_from_map_ref(&map_ref, txn));
+      (
+        JournalOperation::ViewSettingChanged { view_id, setting },
+        timestamp,
+      )
+    })
+    .collect()
+}
+
+fn view_setting(key: &str) -> Option<ViewSetting> {
+  match key {
+    DATABASE_VIEW_LAYOUT | VIEW_LAYOUT_SETTINGS => Some(ViewSetting::Layout),
+    DATABASE_VIEW_FILTERS => Some(ViewSetting::Filter),
+    DATABASE_VIEW_SORTS => Some(ViewSetting::Sort),
+    DATABASE_VIEW_GROUPS => Some(ViewSetting::Group),
+    DATABASE_VIEW_FIELD_ORDERS => Some(ViewSetting::FieldOrder),
+    DATABASE_VIEW_HIDDEN_ROWS => Some(ViewSetting::HiddenRows),
+    _ => None,
+  }
+}
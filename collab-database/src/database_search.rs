@@ -0,0 +1,115 @@
+use std::sync::Weak;
+
+use collab::core::collab_search::{
+  IndexChange, IndexContentType, IndexedContent, WorkspaceSearchIndex,
+};
+use collab::util::AnyMapExt;
+
+use crate::blocks::BlockMap;
+use crate::entity::FieldType;
+use crate::fields::{stringify_type_option, Field, TypeOptionData};
+use crate::rows::{spawn_row_watcher, Cell, Row, RowChangeReceiver};
+use crate::template::entity::CELL_DATA;
+use crate::views::ViewChangeReceiver;
+
+/// A field whose cells are added to the search index. Only the fields that hold text are indexed.
+#[derive(Clone)]
+pub(crate) struct IndexedField {
+  field_id: String,
+  field_type: FieldType,
+  type_option: Option<TypeOptionData>,
+}
+
+impl IndexedField {
+  fn stringify_cell(&self, cell: &Cell) -> String {
+    match self
+      .type_option
+      .clone()
+      .and_then(|type_option| stringify_type_option(type_option, &self.field_type))
+    {
+      Some(stringify) => stringify.stringify_cell(cell),
+      None => cell.get_as::<String>(CELL_DATA).unwrap_or_default(),
+    }
+  }
+}
+
+pub(crate) fn indexed_fields(fields: Vec<Field>) -> Vec<IndexedField> {
+  fields
+    .into_iter()
+    .flat_map(|field| {
+      let field_type = FieldType::from(field.field_type);
+      match field_type {
+        FieldType::RichText | FieldType::URL | FieldType::SingleSelect | FieldType::MultiSelect => {
+          Some(IndexedField {
+            type_option: field.get_any_type_option(field_type.type_id()),
+            field_id: field.id,
+            field_type,
+          })
+        },
+        _ => None,
+      }
+    })
+    .collect()
+}
+
+/// Returns the text of the indexed cells of the row, one cell per line, in the order of the fields.
+pub(crate) fn row_index_content(
+  database_id: &str,
+  row: &Row,
+  fields: &[IndexedField],
+) -> Option<IndexedContent> {
+  let text = fields
+    .iter()
+    .flat_map(|field| {
+      let text = field.stringify_cell(row.cells.get(&field.field_id)?);
+      if text.trim().is_empty() {
+        None
+      } else {
+        Some(text)
+      }
+    })
+    .collect::<Vec<_>>()
+    .join("\n");
+  if text.is_empty() {
+    return None;
+  }
+  Some(IndexedContent::new(
+    database_id,
+    &row.id,
+    text,
+    IndexContentType::DatabaseRow,
+  ))
+}
+
+/// Keeps the rows of the database up to date in the index until the database is dropped.
+pub(crate) fn spawn_search_index_task(
+  database_id: String,
+  inline_view_id: String,
+  fields: Vec<IndexedField>,
+  rows: Weak<BlockMap>,
+  row_change_rx: RowChangeReceiver,
+  view_change_rx: ViewChangeReceiver,
+  index: WorkspaceSearchIndex,
+) {
+  spawn_row_watcher(
+    inline_view_id,
+    rows,
+    row_change_rx,
+    view_change_rx,
+    move |rows| {
+      let changes = rows
+        .into_iter()
+        .map(|(row_id, row)| {
+          match row.and_then(|row| row_index_content(&database_id, &row, &fields)) {
+            Some(content) => IndexChange::Upsert(content),
+            None => IndexChange::Remove {
+              object_id: database_id.clone(),
+              id: row_id.to_string(),
+            },
+          }
+        })
+        .collect();
+      index.apply_changes(changes);
+    },
+  );
+}
@@ -1,13 +1,16 @@
+use crate::database_awareness::DatabasePresenceChangeSender;
 use crate::fields::FieldChangeSender;
 use tokio::sync::broadcast;
 
 use crate::rows::RowChangeSender;
-use crate::views::ViewChangeSender;
+use crate::views::{CalculationChangeSender, ViewChangeSender};
 
 pub struct DatabaseNotify {
   pub view_change_tx: ViewChangeSender,
   pub row_change_tx: RowChangeSender,
   pub field_change_tx: FieldChangeSender,
+  pub presence_change_tx: DatabasePresenceChangeSender,
+  pub calculation_change_tx: CalculationChangeSender,
 }
 
 impl Default for DatabaseNotify {
@@ -15,10 +18,14 @@ impl Default for DatabaseNotify {
     let (view_change_tx, _) = broadcast::channel(100);
     let (row_change_tx, _) = broadcast::channel(100);
     let (field_change_tx, _) = broadcast::channel(100);
+    let (presence_change_tx, _) = broadcast::channel(100);
+    let (calculation_change_tx, _) = broadcast::channel(100);
     Self {
       view_change_tx,
       row_change_tx,
       field_change_tx,
+      presence_change_tx,
+      calculation_change_tx,
     }
   }
 }
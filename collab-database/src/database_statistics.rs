@@ -0,0 +1,154 @@
+use collab::util::AnyMapExt;
+use futures::StreamExt;
+use tokio_util::sync::CancellationToken;
+
+use crate::database::Database;
+use crate::entity::FieldType;
+use crate::fields::{parse_number, Field};
+use crate::rows::Cell;
+use crate::template::entity::CELL_DATA;
+
+/// Finds the field that has the same meaning in several databases, for example the "Cost" field
+/// of each project database. The fields are matched by name, ignoring the case and the spaces
+/// around the name, and optionally by field type.
+#[derive(Clone, Debug)]
+pub struct FieldMatcher {
+  pub name: String,
+  pub field_type: Option<FieldType>,
+}
+
+impl FieldMatcher {
+  pub fn by_name(name: &str) -> Self {
+    Self {
+      name: name.trim().to_lowercase(),
+      field_type: None,
+    }
+  }
+
+  pub fn with_field_type(mut self, field_type: FieldType) -> Self {
+    self.field_type = Some(field_type);
+    self
+  }
+
+  pub fn matches(&self, field: &Field) -> bool {
+    if let Some(field_type) = &self.field_type {
+      if field.field_type != i64::from(field_type.clone()) {
+        return false;
+      }
+    }
+    field.name.trim().to_lowercase() == self.name
+  }
+}
+
+/// Aggregated values of a field. The cells that don't hold a number are counted in
+/// `num_of_rows` only.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FieldStatistics {
+  pub num_of_rows: usize,
+  pub num_of_values: usize,
+  pub sum: f64,
+  pub min: Option<f64>,
+  pub max: Option<f64>,
+}
+
+impl FieldStatistics {
+  pub fn average(&self) -> Option<f64> {
+    if self.num_of_values == 0 {
+      None
+    } else {
+      Some(self.sum / self.num_of_values as f64)
+    }
+  }
+
+  pub fn num_of_empty(&self) -> usize {
+    self.num_of_rows - self.num_of_values
+  }
+
+  pub fn merge(&mut self, other: &FieldStatistics) {
+    self.num_of_rows += other.num_of_rows;
+    self.num_of_values += other.num_of_values;
+    self.sum += other.sum;
+    self.min = min_value(self.min, other.min);
+    self.max = max_value(self.max, other.max);
+  }
+
+  fn add_cell(&mut self, cell: Option<&Cell>) {
+    self.num_of_rows += 1;
+    let value = cell
+      .and_then(|cell| cell.get_as::<String>(CELL_DATA))
+      .and_then(|text| parse_number(&text));
+    if let Some(value) = value {
+      self.num_of_values += 1;
+      self.sum += value;
+      self.min = min_value(self.min, Some(value));
+      self.max = max_value(self.max, Some(value));
+    }
+  }
+}
+
+/// The statistics of the matched field of one database.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DatabaseFieldStatistics {
+  pub database_id: String,
+  pub field_id: String,
+  pub statistics: FieldStatistics,
+}
+
+/// The statistics of a field across several databases, see
+/// [WorkspaceDatabaseManager::get_field_statistics](crate::workspace_database::WorkspaceDatabaseManager::get_field_statistics).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CrossDatabaseFieldStatistics {
+  /// The statistics of all the databases together.
+  pub total: FieldStatistics,
+  /// The statistics of each database that has the field.
+  pub databases: Vec<DatabaseFieldStatistics>,
+  /// The databases that don't have the field or that can't be opened.
+  pub skipped: Vec<String>,
+}
+
+impl CrossDatabaseFieldStatistics {
+  pub(crate) fn add(&mut self, statistics: DatabaseFieldStatistics) {
+    self.total.merge(&statistics.statistics);
+    self.databases.push(statistics);
+  }
+}
+
+/// Compute the statistics of the field that matches in the given database. The rows are read one
+/// by one with [Database::get_all_rows], so a large database is not loaded at once. Return None
+/// if the database has no matching field.
+pub async fn database_field_statistics(
+  database: &Database,
+  matcher: &FieldMatcher,
+  cancel_token: Option<CancellationToken>,
+) -> Option<DatabaseFieldStatistics> {
+  let field = database
+    .get_all_fields()
+    .into_iter()
+    .find(|field| matcher.matches(field))?;
+  let mut statistics = FieldStatistics::default();
+  let mut rows = Box::pin(database.get_all_rows(cancel_token).await);
+  while let Some(row) = rows.next().await {
+    if let Ok(row) = row {
+      statistics.add_cell(row.cells.get(&field.id));
+    }
+  }
+  Some(DatabaseFieldStatistics {
+    database_id: database.get_database_id(),
+    field_id: field.id,
+    statistics,
+  })
+}
+
+fn min_value(a: Option<f64>, b: Option<f64>) -> Option<f64> {
+  match (a, b) {
+    (Some(a), Some(b)) => Some(a.min(b)),
+    (a, b) => a.or(b),
+  }
+}
+
+fn max_value(a: Option<f64>, b: Option<f64>) -> Option<f64> {
+  match (a, b) {
+    (Some(a), Some(b)) => Some(a.max(b)),
+    (a, b) => a.or(b),
+  }
+}
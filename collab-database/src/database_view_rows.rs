@@ -0,0 +1,380 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::sync::Weak;
+
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::StreamExt;
+
+use crate::blocks::BlockMap;
+use crate::rows::{Row, RowChange, RowChangeReceiver, RowId};
+use crate::views::{DatabaseViewChange, RowOrder, ViewChangeReceiver};
+
+/// Decides which rows of a view are visible and in which order. The filters and the sorts of a
+/// view are evaluated by the application, which implements this trait to subscribe to the
+/// visible rows with [crate::database::Database::subscribe_view_rows].
+///
+/// The rows that compare equal keep the order of the view.
+pub trait ViewRowsQuery: Send + Sync + 'static {
+  fn is_visible(&self, _row: &Row) -> bool {
+    true
+  }
+
+  fn compare(&self, _left: &Row, _right: &Row) -> Ordering {
+    Ordering::Equal
+  }
+}
+
+/// Shows all the rows in the order of the view.
+pub struct AllRows;
+
+impl ViewRowsQuery for AllRows {}
+
+/// The changes of the visible rows of a view. Applying the changes in order to the previous
+/// visible rows gives the current visible rows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowsChanged {
+  pub view_id: String,
+  pub changes: Vec<VisibleRowChange>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VisibleRowChange {
+  Inserted {
+    index: usize,
+    row: Row,
+  },
+  Removed {
+    index: usize,
+    row_id: RowId,
+  },
+  /// The row is removed at `from`, then inserted at `to`.
+  Moved {
+    row_id: RowId,
+    from: usize,
+    to: usize,
+  },
+  /// The cells of a visible row changed. Sent after the row is moved if its position changed.
+  Updated {
+    index: usize,
+    row: Row,
+  },
+}
+
+/// The rows of the view when subscribing.
+pub(crate) struct ViewRowsSnapshot {
+  pub row_orders: Vec<RowOrder>,
+  pub hidden_rows: HashSet<RowId>,
+  pub rows: Vec<Row>,
+}
+
+enum ViewRowsEvent {
+  Row(RowChange),
+  View(DatabaseViewChange),
+  Lagged,
+}
+
+/// Sends the visible rows of the view as the first [RowsChanged], then the changes of the visible
+/// rows until the database is dropped or the receiver is dropped. The hidden rows of the view are
+/// never visible, whatever the query returns.
+pub(crate) fn spawn_view_rows_task<Q: ViewRowsQuery>(
+  view_id: String,
+  snapshot: ViewRowsSnapshot,
+  query: Q,
+  rows: Weak<BlockMap>,
+  row_change_rx: RowChangeReceiver,
+  view_change_rx: ViewChangeReceiver,
+) -> mpsc::UnboundedReceiver<RowsChanged> {
+  let (tx, rx) = mpsc::unbounded_channel();
+  let row_changes = BroadcastStream::new(row_change_rx).map(|change| match change {
+    Ok(change) => ViewRowsEvent::Row(change),
+    Err(_) => ViewRowsEvent::Lagged,
+  });
+  let view_changes = BroadcastStream::new(view_change_rx).map(|change| match change {
+    Ok(change) => ViewRowsEvent::View(change),
+    Err(_) => ViewRowsEvent::Lagged,
+  });
+  let mut events = row_changes.merge(view_changes);
+  let mut view_rows = ViewRows {
+    row_ids: snapshot
+      .row_orders
+      .into_iter()
+      .map(|row_order| row_order.id)
+      .collect(),
+    rows: snapshot
+      .rows
+      .into_iter()
+      .map(|row| (row.id.clone(), row))
+      .collect(),
+    hidden_rows: snapshot.hidden_rows,
+    visible: vec![],
+    query,
+  };
+
+  tokio::spawn(async move {
+    let changes = view_rows.refresh(&HashSet::new());
+    if tx
+      .send(RowsChanged {
+        view_id: view_id.clone(),
+        changes,
+      })
+      .is_err()
+    {
+      return;
+    }
+
+    while let Some(event) = events.next().await {
+      let rows = match rows.upgrade() {
+        None => break,
+        Some(rows) => rows,
+      };
+      let updated_row_ids = match event {
+        ViewRowsEvent::Row(RowChange::DidUpdateCell { row_id, .. }) => {
+          if !view_rows.rows.contains_key(&row_id) {
+            continue;
+          }
+          HashSet::from([row_id])
+        },
+        ViewRowsEvent::Row(_) => continue,
+        ViewRowsEvent::View(DatabaseViewChange::DidUpdateRowOrders {
+          database_view_id,
+          insert_row_orders,
+          delete_row_indexes,
+          ..
+        }) if database_view_id == view_id => {
+          view_rows.row_ids =
+            apply_row_order_delta(&view_rows.row_ids, insert_row_orders, &delete_row_indexes);
+          let row_ids = view_rows.row_ids.iter().collect::<HashSet<_>>();
+          view_rows.rows.retain(|row_id, _| row_ids.contains(row_id));
+          HashSet::new()
+        },
+        ViewRowsEvent::View(DatabaseViewChange::DidUpdateHiddenRows {
+          view_id: hidden_view_id,
+          hidden_rows,
+        }) if hidden_view_id == view_id => {
+          view_rows.hidden_rows = hidden_rows;
+          HashSet::new()
+        },
+        ViewRowsEvent::View(_) => continue,
+        // Some changes were missed, so all the rows are read again.
+        ViewRowsEvent::Lagged => view_rows.row_ids.iter().cloned().collect(),
+      };
+
+      // Read the rows that changed and the rows that were inserted into the view.
+      let row_ids = view_rows
+        .row_ids
+        .iter()
+        .filter(|row_id| updated_row_ids.contains(row_id) || !view_rows.rows.contains_key(row_id))
+        .cloned()
+        .collect::<Vec<_>>();
+      for row_id in row_ids {
+        let row = match rows.get_or_init_database_row(&row_id).await {
+          Ok(database_row) => database_row.read().await.get_row(),
+          Err(_) => None,
+        };
+        match row {
+          None => view_rows.rows.remove(&row_id),
+          Some(row) => view_rows.rows.insert(row_id, row),
+        };
+      }
+
+      let changes = view_rows.refresh(&updated_row_ids);
+      if changes.is_empty() {
+        continue;
+      }
+      if tx
+        .send(RowsChanged {
+          view_id: view_id.clone(),
+          changes,
+        })
+        .is_err()
+      {
+        break;
+      }
+    }
+  });
+  rx
+}
+
+struct ViewRows<Q> {
+  /// The ids of the rows in the order of the view.
+  row_ids: Vec<RowId>,
+  /// The loaded rows of the view.
+  rows: HashMap<RowId, Row>,
+  hidden_rows: HashSet<RowId>,
+  /// The ids of the visible rows, in display order.
+  visible: Vec<RowId>,
+  query: Q,
+}
+
+impl<Q: ViewRowsQuery> ViewRows<Q> {
+  /// Evaluates the query and returns the changes from the previous visible rows. The rows in
+  /// `updated_row_ids` that stay visible are returned as updated.
+  fn refresh(&mut self, updated_row_ids: &HashSet<RowId>) -> Vec<VisibleRowChange> {
+    let mut visible = self
+      .row_ids
+      .iter()
+      .filter(|row_id| !self.hidden_rows.contains(*row_id))
+      .filter_map(|row_id| self.rows.get(row_id))
+      .filter(|row| self.query.is_visible(row))
+      .collect::<Vec<_>>();
+    // The sort is stable, so the rows that compare equal keep the order of the view.
+    visible.sort_by(|left, right| self.query.compare(left, right));
+    let visible = visible
+      .into_iter()
+      .map(|row| row.id.clone())
+      .collect::<Vec<_>>();
+
+    let changes = diff_visible_rows(&self.visible, &visible, &self.rows, updated_row_ids);
+    self.visible = visible;
+    changes
+  }
+}
+
+/// Returns the changes that turn `old` into `new`. The rows that keep their relative order are
+/// not moved, so moving one row gives one [VisibleRowChange::Moved].
+fn diff_visible_rows(
+  old: &[RowId],
+  new: &[RowId],
+  rows: &HashMap<RowId, Row>,
+  updated_row_ids: &HashSet<RowId>,
+) -> Vec<VisibleRowChange> {
+  let new_row_ids = new.iter().collect::<HashSet<_>>();
+  let mut changes = vec![];
+  let mut current = old.to_vec();
+  for index in (0..current.len()).rev() {
+    if !new_row_ids.contains(&current[index]) {
+      let row_id = current.remove(index);
+      changes.push(VisibleRowChange::Removed { index, row_id });
+    }
+  }
+
+  let old_indexes = current
+    .iter()
+    .enumerate()
+    .map(|(index, row_id)| (row_id.clone(), index))
+    .collect::<HashMap<_, _>>();
+  let kept = longest_increasing_subsequence(
+    &new
+      .iter()
+      .filter_map(|row_id| old_indexes.get(row_id).copied())
+      .collect::<Vec<_>>(),
+  )
+  .into_iter()
+  .map(|index| current[index].clone())
+  .collect::<HashSet<_>>();
+
+  for (target, row_id) in new.iter().enumerate() {
+    if kept.contains(row_id) {
+      continue;
+    }
+    // The row is placed right after the row that precedes it in the new order, which is already
+    // in place.
+    let from = current.iter().position(|id| id == row_id);
+    if let Some(from) = from {
+      current.remove(from);
+    }
+    let to = match target {
+      0 => 0,
+      _ => current
+        .iter()
+        .position(|id| id == &new[target - 1])
+        .map(|index| index + 1)
+        .unwrap_or(current.len()),
+    };
+    current.insert(to, row_id.clone());
+    match from {
+      Some(from) => changes.push(VisibleRowChange::Moved {
+        row_id: row_id.clone(),
+        from,
+        to,
+      }),
+      None => {
+        if let Some(row) = rows.get(row_id) {
+          changes.push(VisibleRowChange::Inserted {
+            index: to,
+            row: row.clone(),
+          });
+        }
+      },
+    }
+  }
+
+  for (index, row_id) in current.iter().enumerate() {
+    if updated_row_ids.contains(row_id) && old_indexes.contains_key(row_id) {
+      if let Some(row) = rows.get(row_id) {
+        changes.push(VisibleRowChange::Updated {
+          index,
+          row: row.clone(),
+        });
+      }
+    }
+  }
+  changes
+}
+
+/// Returns the indexes, into `values`, of one of the longest strictly increasing subsequences.
+fn longest_increasing_subsequence(values: &[usize]) -> Vec<usize> {
+  // `tails[len]` is the index of the smallest last value of the subsequences of length len + 1.
+  let mut tails: Vec<usize> = vec![];
+  let mut previous = vec![None; values.len()];
+  for (index, value) in values.iter().enumerate() {
+    let len = tails.partition_point(|&tail| values[tail] < *value);
+    if len > 0 {
+      previous[index] = Some(tails[len - 1]);
+    }
+    if len == tails.len() {
+      tails.push(index);
+    } else {
+      tails[len] = index;
+    }
+  }
+
+  let mut subsequence = vec![];
+  let mut next = tails.last().copied();
+  while let Some(index) = next {
+    subsequence.push(values[index]);
+    next = previous[index];
+  }
+  subsequence.reverse();
+  subsequence
+}
+
+/// Applies a [DatabaseViewChange::DidUpdateRowOrders] to the row ids of the view. The indexes of
+/// the change refer to the positions in the array that contains both the removed and the
+/// inserted rows.
+pub(crate) fn apply_row_order_delta(
+  row_ids: &[RowId],
+  insert_row_orders: Vec<(RowOrder, u32)>,
+  delete_row_indexes: &[u32],
+) -> Vec<RowId> {
+  let mut inserted = insert_row_orders
+    .into_iter()
+    .map(|(row_order, index)| (index as usize, row_order.id))
+    .collect::<HashMap<_, _>>();
+  let deleted = delete_row_indexes
+    .iter()
+    .map(|index| *index as usize)
+    .collect::<HashSet<_>>();
+  let mut existing = row_ids.iter();
+  let mut result = Vec::with_capacity(row_ids.len() + inserted.len());
+  let mut index = 0;
+  loop {
+    let row_id = match inserted.remove(&index) {
+      Some(row_id) => row_id,
+      None => match existing.next() {
+        Some(row_id) => row_id.clone(),
+        None => break,
+      },
+    };
+    if !deleted.contains(&index) {
+      result.push(row_id);
+    }
+    index += 1;
+  }
+  // The inserts past the end of the array are appended.
+  let mut remaining = inserted.into_iter().collect::<Vec<_>>();
+  remaining.sort_by_key(|(index, _)| *index);
+  result.extend(remaining.into_iter().map(|(_, row_id)| row_id));
+  result
+}
@@ -0,0 +1,463 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+
+use collab::core::collab_backlink::{BacklinkRegistry, BacklinkType, LinkSource};
+use collab::lock::RwLock;
+use collab::util::AnyMapExt;
+use collab_document::blocks::Block;
+use collab_document::document::Document;
+use collab_document::document_block_type::BlockTypeChange;
+use collab_document::importer::define::{BlockType, CHECKED_FIELD, LEVEL_FIELD};
+use futures::StreamExt;
+use serde_json::Value;
+use tokio_stream::wrappers::BroadcastStream;
+
+use crate::database::{gen_row_id, Database};
+use crate::entity::FieldType;
+use crate::error::DatabaseError;
+use crate::fields::checkbox_type_option::{
+  is_checkbox_checked, CHECKBOX_CHECKED, CHECKBOX_UNCHECKED,
+};
+use crate::rows::{new_cell_builder, Cell, Cells, CreateRowParams, Row, RowChange, RowId};
+use crate::template::entity::CELL_DATA;
+
+/// A todo block of a document.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DocumentTask {
+  pub block_id: String,
+  pub text: String,
+  pub checked: bool,
+}
+
+/// Returns the todo blocks of the document, in the order they appear.
+///
+/// When `heading_id` is set, only the todo blocks under the heading are returned, that is the
+/// blocks that follow it up to the next heading of the same or a higher level, and their
+/// children.
+pub fn extract_document_tasks(
+  document: &Document,
+  heading_id: Option<&str>,
+) -> Result<Vec<DocumentTask>, DatabaseError> {
+  let root_ids = match heading_id {
+    None => {
+      let page_id = document
+        .get_page_id()
+        .ok_or_else(|| DatabaseError::NoRequiredData("the page of the document".to_string()))?;
+      document.get_block_children_ids(&page_id)
+    },
+    Some(heading_id) => blocks_under_heading(document, heading_id)?,
+  };
+
+  let mut tasks = vec![];
+  let mut stack = root_ids.into_iter().rev().collect::<Vec<_>>();
+  while let Some(block_id) = stack.pop() {
+    let block = match document.get_block(&block_id) {
+      None => continue,
+      Some(block) => block,
+    };
+    if block.ty == BlockType::TodoList.as_str() {
+      tasks.push(DocumentTask {
+        text: document
+          .get_plain_text_from_block(&block_id)
+          .unwrap_or_default(),
+        checked: is_block_checked(&block.data),
+        block_id: block_id.clone(),
+      });
+    }
+    stack.extend(document.get_block_children_ids(&block_id).into_iter().rev());
+  }
+  Ok(tasks)
+}
+
+fn blocks_under_heading(
+  document: &Document,
+  heading_id: &str,
+) -> Result<Vec<String>, DatabaseError> {
+  let heading = document
+    .get_block(heading_id)
+    .filter(|block| block.ty == BlockType::Heading.as_str())
+    .ok_or_else(|| DatabaseError::NoRequiredData(format!("the heading {}", heading_id)))?;
+  let level = heading_level(&heading.data);
+
+  let mut block_ids = document.get_block_children_ids(heading_id);
+  let siblings = document.get_block_children_ids(&heading.parent);
+  for sibling_id in siblings
+    .into_iter()
+    .skip_while(|sibling_id| sibling_id != heading_id)
+    .skip(1)
+  {
+    let is_next_section = document.get_block(&sibling_id).map_or(false, |block| {
+      block.ty == BlockType::Heading.as_str() && heading_level(&block.data) <= level
+    });
+    if is_next_section {
+      break;
+    }
+    block_ids.push(sibling_id);
+  }
+  Ok(block_ids)
+}
+
+fn heading_level(data: &HashMap<String, Value>) -> i64 {
+  data.get(LEVEL_FIELD).and_then(Value::as_i64).unwrap_or(1)
+}
+
+fn is_block_checked(data: &HashMap<String, Value>) -> bool {
+  data
+    .get(CHECKED_FIELD)
+    .and_then(Value::as_bool)
+    .unwrap_or(false)
+}
+
+fn is_cell_checked(cell: Option<&Cell>) -> bool {
+  cell
+    .and_then(|cell| cell.get_as::<String>(CELL_DATA))
+    .map_or(false, |data| is_checkbox_checked(&data))
+}
+
+fn checkbox_cell(checked: bool) -> Cell {
+  let mut cell = new_cell_builder(FieldType::Checkbox);
+  let data = if checked {
+    CHECKBOX_CHECKED
+  } else {
+    CHECKBOX_UNCHECKED
+  };
+  cell.insert(CELL_DATA.to_string(), data.into());
+  cell
+}
+
+fn text_cell(text: &str) -> Cell {
+  let mut cell = new_cell_builder(FieldType::RichText);
+  cell.insert(CELL_DATA.to_string(), text.into());
+  cell
+}
+
+/// The fields of the database that hold the tasks.
+#[derive(Debug, Clone)]
+pub struct TaskFields {
+  /// A text field that holds the text of the todo block.
+  pub text_field_id: String,
+  /// A checkbox field that holds the checked state of the todo block.
+  pub checked_field_id: String,
+  /// A text field that holds the source block of the task, used to restore the links with
+  /// [DocumentTaskSync::restore_links].
+  pub source_field_id: Option<String>,
+}
+
+/// Turns the todo blocks of a document into rows of a database and keeps the checked state of
+/// each block and the checkbox cell of its row in sync.
+///
+/// A block and its row are linked in the [BacklinkRegistry] by a [BacklinkType::Task] link from
+/// the block to the row. Once [DocumentTaskSync::subscribe] is called, the changes of either
+/// collab are copied to the other one. [DocumentTaskSync::sync_from_document] and
+/// [DocumentTaskSync::sync_from_database] copy all the linked tasks at once, for example when the
+/// collabs are opened.
+#[derive(Clone)]
+pub struct DocumentTaskSync {
+  document_id: String,
+  database_id: String,
+  fields: TaskFields,
+  registry: BacklinkRegistry,
+}
+
+impl DocumentTaskSync {
+  pub fn new(
+    document_id: impl ToString,
+    database_id: impl ToString,
+    fields: TaskFields,
+    registry: BacklinkRegistry,
+  ) -> Self {
+    Self {
+      document_id: document_id.to_string(),
+      database_id: database_id.to_string(),
+      fields,
+      registry,
+    }
+  }
+
+  /// Creates one row per task and links the rows to their blocks. Returns the ids of the created
+  /// rows, in the order of the tasks.
+  pub async fn create_rows(
+    &self,
+    database: &mut Database,
+    tasks: Vec<DocumentTask>,
+  ) -> Result<Vec<RowId>, DatabaseError> {
+    let mut row_ids = vec![];
+    for task in tasks {
+      let row_id = gen_row_id();
+      let mut cells = Cells::from([
+        (self.fields.text_field_id.clone(), text_cell(&task.text)),
+        (
+          self.fields.checked_field_id.clone(),
+          checkbox_cell(task.checked),
+        ),
+      ]);
+      if let Some(source_field_id) = &self.fields.source_field_id {
+        cells.insert(
+          source_field_id.clone(),
+          text_cell(&source_text(&self.document_id, &task.block_id)),
+        );
+      }
+      database
+        .create_row(
+          CreateRowParams::new(row_id.clone(), self.database_id.clone()).with_cells(cells),
+        )
+        .await?;
+      self.registry.set_links(
+        LinkSource::new(&self.document_id, &task.block_id),
+        BacklinkType::Task,
+        [&row_id],
+      );
+      row_ids.push(row_id);
+    }
+    Ok(row_ids)
+  }
+
+  /// Adds the links of the rows whose source cell points to a block of the document. Used when
+  /// the database is opened, since the registry only lives in memory.
+  pub async fn restore_links(&self, database: &Database) {
+    let source_field_id = match &self.fields.source_field_id {
+      None => return,
+      Some(source_field_id) => source_field_id,
+    };
+    for row in database.collect_all_rows().await.into_iter().flatten() {
+      let source = row
+        .cells
+        .get(source_field_id)
+        .and_then(|cell| cell.get_as::<String>(CELL_DATA));
+      let block_id = match source
+        .as_deref()
+        .and_then(|source| source.split_once('/'))
+        .filter(|(document_id, _)| *document_id == self.document_id)
+      {
+        None => continue,
+        Some((_, block_id)) => block_id.to_string(),
+      };
+      self.registry.set_links(
+        LinkSource::new(&self.document_id, block_id),
+        BacklinkType::Task,
+        [&row.id],
+      );
+    }
+  }
+
+  /// Copies the checked state of the linked blocks to the checkbox cells of their rows. Returns
+  /// the ids of the updated rows.
+  pub async fn sync_from_document(
+    &self,
+    document: &Document,
+    database: &mut Database,
+  ) -> Vec<RowId> {
+    let mut updated_row_ids = vec![];
+    for (block_id, row) in self.linked_rows(database).await {
+      let checked = match document.get_block(&block_id) {
+        None => continue,
+        Some(block) => is_block_checked(&block.data),
+      };
+      if self.update_row_checked(database, &row, checked).await {
+        updated_row_ids.push(row.id);
+      }
+    }
+    updated_row_ids
+  }
+
+  /// Copies the checkbox cells of the linked rows to the checked state of their blocks. Returns
+  /// the ids of the updated blocks.
+  pub async fn sync_from_database(
+    &self,
+    database: &Database,
+    document: &mut Document,
+  ) -> Result<Vec<String>, DatabaseError> {
+    let mut updated_block_ids = vec![];
+    for (block_id, row) in self.linked_rows(database).await {
+      let checked = is_cell_checked(row.cells.get(&self.fields.checked_field_id));
+      if update_block_checked(document, &block_id, checked)? {
+        updated_block_ids.push(block_id);
+      }
+    }
+    Ok(updated_block_ids)
+  }
+
+  /// Keeps the linked blocks and rows in sync until the document or the database is dropped.
+  /// Checking a todo block updates the checkbox cell of its row, and updating the checkbox cell
+  /// of a row updates the checked state of its block, whether the change is local or remote.
+  ///
+  /// The document is subscribed to the changes of its todo blocks with
+  /// [Document::subscribe_blocks_of_type].
+  pub async fn subscribe(
+    &self,
+    document: &Arc<RwLock<Document>>,
+    database: &Arc<RwLock<Database>>,
+  ) {
+    let (_, block_changes) = document
+      .write()
+      .await
+      .subscribe_blocks_of_type(BlockType::TodoList.as_str());
+    let row_change_rx = database.read().await.subscribe_row_change();
+
+    let sync = self.clone();
+    let weak_database = Arc::downgrade(database);
+    tokio::spawn(async move {
+      let mut block_changes = Box::pin(block_changes);
+      while let Some(change) = block_changes.next().await {
+        let block = match change {
+          BlockTypeChange::Inserted(block) | BlockTypeChange::Updated(block) => block,
+          BlockTypeChange::Removed { .. } => continue,
+        };
+        match weak_database.upgrade() {
+          None => break,
+          Some(database) => sync.did_update_block(&block, &database).await,
+        }
+      }
+    });
+
+    let row_change_rx = match row_change_rx {
+      None => return,
+      Some(row_change_rx) => row_change_rx,
+    };
+    let sync = self.clone();
+    let weak_document = Arc::downgrade(document);
+    let weak_database = Arc::downgrade(database);
+    tokio::spawn(async move {
+      let mut row_changes = BroadcastStream::new(row_change_rx);
+      while let Some(change) = row_changes.next().await {
+        let row_id = match change {
+          Ok(RowChange::DidUpdateCell {
+            row_id, field_id, ..
+          }) if field_id == sync.fields.checked_field_id => Some(row_id),
+          Ok(_) => continue,
+          // Some changes were missed, so all the linked rows are copied again.
+          Err(_) => None,
+        };
+        if sync
+          .did_update_row(row_id, &weak_document, &weak_database)
+          .await
+          .is_none()
+        {
+          break;
+        }
+      }
+    });
+  }
+
+  /// Copies the checked state of the block to the checkbox cells of its rows.
+  async fn did_update_block(&self, block: &Block, database: &RwLock<Database>) {
+    let checked = is_block_checked(&block.data);
+    let source = LinkSource::new(&self.document_id, &block.id);
+    for link in self.registry.links_from(&self.document_id) {
+      if link.link_type != BacklinkType::Task || link.source != source {
+        continue;
+      }
+      let mut database = database.write().await;
+      let row = database.get_row(&RowId::from(link.target_id)).await;
+      if row.is_empty() || row.database_id != self.database_id {
+        continue;
+      }
+      self.update_row_checked(&mut database, &row, checked).await;
+    }
+  }
+
+  /// Copies the checkbox cell of the row to the checked state of its blocks, or the checkbox
+  /// cells of all the linked rows when the row is None. Returns None once the document or the
+  /// database is dropped.
+  async fn did_update_row(
+    &self,
+    row_id: Option<RowId>,
+    document: &Weak<RwLock<Document>>,
+    database: &Weak<RwLock<Database>>,
+  ) -> Option<()> {
+    let (document, database) = (document.upgrade()?, database.upgrade()?);
+    let row_id = match row_id {
+      Some(row_id) => row_id,
+      None => {
+        let database = database.read().await;
+        if let Err(err) = self
+          .sync_from_database(&database, &mut *document.write().await)
+          .await
+        {
+          tracing::error!("Failed to sync the tasks of the document: {}", err);
+        }
+        return Some(());
+      },
+    };
+    let block_ids = self
+      .registry
+      .backlinks_for(&row_id)
+      .into_iter()
+      .filter(|link| {
+        link.link_type == BacklinkType::Task && link.source.object_id == self.document_id
+      })
+      .map(|link| link.source.id)
+      .collect::<Vec<_>>();
+    if block_ids.is_empty() {
+      return Some(());
+    }
+    let row = database.read().await.get_row(&row_id).await;
+    if row.is_empty() || row.database_id != self.database_id {
+      return Some(());
+    }
+    let checked = is_cell_checked(row.cells.get(&self.fields.checked_field_id));
+    let mut document = document.write().await;
+    for block_id in block_ids {
+      if let Err(err) = update_block_checked(&mut document, &block_id, checked) {
+        tracing::error!("Failed to sync the task of the block {}: {}", block_id, err);
+      }
+    }
+    Some(())
+  }
+
+  /// Sets the checkbox cell of the row. Returns false if the cell was already in this state.
+  async fn update_row_checked(&self, database: &mut Database, row: &Row, checked: bool) -> bool {
+    if is_cell_checked(row.cells.get(&self.fields.checked_field_id)) == checked {
+      return false;
+    }
+    database
+      .update_row(row.id.clone(), |update| {
+        update.update_cells(|cells| {
+          cells.insert_cell(&self.fields.checked_field_id, checkbox_cell(checked));
+        });
+      })
+      .await;
+    true
+  }
+
+  /// Returns the blocks of the document linked to a row of the database, with their rows.
+  async fn linked_rows(&self, database: &Database) -> Vec<(String, Row)> {
+    let mut rows = vec![];
+    for link in self.registry.links_from(&self.document_id) {
+      if link.link_type != BacklinkType::Task {
+        continue;
+      }
+      let row = database.get_row(&RowId::from(link.target_id)).await;
+      if row.is_empty() || row.database_id != self.database_id {
+        continue;
+      }
+      rows.push((link.source.id, row));
+    }
+    rows
+  }
+}
+
+/// Sets the checked state of the block. Returns false if the block doesn't exist or was already
+/// in this state.
+fn update_block_checked(
+  document: &mut Document,
+  block_id: &str,
+  checked: bool,
+) -> Result<bool, DatabaseError> {
+  let mut data = match document.get_block(block_id) {
+    None => return Ok(false),
+    Some(block) => block.data,
+  };
+  if is_block_checked(&data) == checked {
+    return Ok(false);
+  }
+  data.insert(CHECKED_FIELD.to_string(), Value::Bool(checked));
+  document
+    .update_block(block_id, data)
+    .map_err(|err| DatabaseError::Internal(err.into()))?;
+  Ok(true)
+}
+
+fn source_text(document_id: &str, block_id: &str) -> String {
+  format!("{}/{}", document_id, block_id)
+}
@@ -2,17 +2,20 @@
 use crate::database::{gen_database_id, gen_database_view_id, gen_row_id, timestamp, DatabaseData};
 use crate::error::DatabaseError;
 use crate::fields::Field;
-use crate::rows::CreateRowParams;
+use crate::rows::{CreateRowParams, RowId};
+use crate::template::variable::{resolve_map, resolve_placeholders};
 use crate::views::{
-  DatabaseLayout, FieldOrder, FieldSettingsByFieldIdMap, FieldSettingsMap, FilterMap,
-  GroupSettingMap, LayoutSetting, LayoutSettings, OrderObjectPosition, RowOrder, SortMap,
+  CalculationMap, DatabaseLayout, FieldOrder, FieldSettingsByFieldIdMap, FieldSettingsMap,
+  FilterMap, FilterNode, GroupSettingMap, LayoutSetting, LayoutSettings, OrderObjectPosition,
+  RowOrder, SortMap, FILTER_FIELD_ID, GROUP_FIELD_ID, SORT_FIELD_ID,
 };
 
 use collab::entity::EncodedCollab;
 use collab_entity::CollabType;
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
 use tracing::error;
 use yrs::{Any, Out};
 
@@ -35,6 +38,23 @@ pub struct EncodedCollabInfo {
   pub encoded_collab: EncodedCollab,
 }
 
+/// The position of [Database::encode_streaming](crate::database::Database::encode_streaming) in
+/// the collabs of a database. Passing the cursor of the last collab that was processed resumes the
+/// encoding right after it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncodeCursor {
+  /// The object id of the last collab that was encoded. None if nothing was encoded yet.
+  pub last_object_id: Option<String>,
+}
+
+impl EncodeCursor {
+  pub fn after(collab: &EncodedCollabInfo) -> Self {
+    Self {
+      last_object_id: Some(collab.object_id.clone()),
+    }
+  }
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct DatabaseView {
   pub id: String,
@@ -52,6 +72,13 @@ pub struct DatabaseView {
   pub modified_at: i64,
   #[serde(default)]
   pub is_inline: bool,
+  /// The rows tucked away by the user in this view, see
+  /// [crate::views::DatabaseViewUpdate::hide_row].
+  #[serde(default)]
+  pub hidden_rows: HashSet<RowId>,
+  /// The calculations shown in the footers of the fields, see [crate::views::CalculationType].
+  #[serde(default)]
+  pub calculations: Vec<CalculationMap>,
 }
 
 impl DatabaseView {
@@ -67,6 +94,17 @@ impl DatabaseView {
       ..Default::default()
     }
   }
+
+  /// Add a filter, or a group of filters, to the view.
+  pub fn with_filter(mut self, filter: FilterNode) -> Self {
+    self.filters.push(filter.into());
+    self
+  }
+
+  /// Return the filters of the view as trees. The trees are combined with AND.
+  pub fn filter_nodes(&self) -> Vec<FilterNode> {
+    self.filters.iter().cloned().map(FilterNode::from).collect()
+  }
 }
 
 /// A meta of [DatabaseView]
@@ -88,6 +126,8 @@ pub struct CreateViewParams {
   pub group_settings: Vec<GroupSettingMap>,
   pub sorts: Vec<SortMap>,
   pub field_settings: FieldSettingsByFieldIdMap,
+  #[serde(default)]
+  pub calculations: Vec<CalculationMap>,
   pub created_at: i64,
   pub modified_at: i64,
 
@@ -165,6 +205,7 @@ impl From<DatabaseView> for CreateViewParams {
       group_settings: view.group_settings,
       sorts: view.sorts,
       field_settings: view.field_settings,
+      calculations: view.calculations,
       ..Default::default()
     }
   }
@@ -186,6 +227,286 @@ impl CreateViewParamsValidator {
   }
 }
 
+/// A problem of a [CreateDatabaseParams] found by [CreateDatabaseParamsValidator].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CreateDatabaseViolation {
+  EmptyDatabaseId,
+  EmptyFieldId,
+  DuplicateFieldId(String),
+  /// Only reported when [CreateDatabaseParamsValidator::require_primary_field] is set, like
+  /// [CreateDatabaseViolation::MultiplePrimaryFields].
+  MissingPrimaryField,
+  MultiplePrimaryFields(Vec<String>),
+  EmptyRowId,
+  DuplicateRowId(RowId),
+  RowOfOtherDatabase(RowId),
+  EmptyViewId,
+  DuplicateViewId(String),
+  ViewOfOtherDatabase(String),
+  UnknownFilterField {
+    view_id: String,
+    field_id: String,
+  },
+  UnknownSortField {
+    view_id: String,
+    field_id: String,
+  },
+  UnknownGroupField {
+    view_id: String,
+    field_id: String,
+  },
+  /// The view has a different number of `deps_fields` and `deps_field_setting`.
+  MismatchedDepsFields {
+    view_id: String,
+  },
+  /// The calendar view has no date field, neither in the fields of the database nor in its
+  /// `deps_fields`. Only reported when [CreateDatabaseParamsValidator::check_layout_fields] is
+  /// set.
+  MissingCalendarDateField {
+    view_id: String,
+  },
+}
+
+impl Display for CreateDatabaseViolation {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::EmptyDatabaseId => write!(f, "database_id is empty"),
+      Self::EmptyFieldId => write!(f, "a field has no id"),
+      Self::DuplicateFieldId(field_id) => write!(f, "field {} is duplicated", field_id),
+      Self::MissingPrimaryField => write!(f, "no primary field"),
+      Self::MultiplePrimaryFields(field_ids) => {
+        write!(f, "multiple primary fields: {}", field_ids.join(", "))
+      },
+      Self::EmptyRowId => write!(f, "a row has no id"),
+      Self::DuplicateRowId(row_id) => write!(f, "row {} is duplicated", row_id),
+      Self::RowOfOtherDatabase(row_id) => write!(f, "row {} belongs to another database", row_id),
+      Self::EmptyViewId => write!(f, "a view has no id"),
+      Self::DuplicateViewId(view_id) => write!(f, "view {} is duplicated", view_id),
+      Self::ViewOfOtherDatabase(view_id) => {
+        write!(f, "view {} belongs to another database", view_id)
+      },
+      Self::UnknownFilterField { view_id, field_id } => {
+        write!(f, "view {} filters unknown field {}", view_id, field_id)
+      },
+      Self::UnknownSortField { view_id, field_id } => {
+        write!(f, "view {} sorts by unknown field {}", view_id, field_id)
+      },
+      Self::UnknownGroupField { view_id, field_id } => {
+        write!(f, "view {} groups by unknown field {}", view_id, field_id)
+      },
+      Self::MismatchedDepsFields { view_id } => write!(
+        f,
+        "view {} has a different number of deps fields and deps field settings",
+        view_id
+      ),
+      Self::MissingCalendarDateField { view_id } => {
+        write!(f, "calendar view {} has no date field", view_id)
+      },
+    }
+  }
+}
+
+/// Checks a [CreateDatabaseParams] before the database is created, see
+/// [crate::database::DatabaseContext::with_create_database_validator]. All the violations are
+/// returned at once instead of creating a database with dangling references.
+#[derive(Debug, Clone)]
+pub struct CreateDatabaseParamsValidator {
+  /// Require exactly one primary field. Off by default, the databases built by code often have
+  /// no primary field, or several.
+  pub require_primary_field: bool,
+  /// Require the fields needed by the layouts of the views, like the date field of a calendar.
+  /// Off by default.
+  pub check_layout_fields: bool,
+  /// Set the database id of the views and the rows that have an empty one, instead of reporting
+  /// them as belonging to another database. On by default.
+  pub fill_database_ids: bool,
+}
+
+impl Default for CreateDatabaseParamsValidator {
+  fn default() -> Self {
+    Self {
+      require_primary_field: false,
+      check_layout_fields: false,
+      fill_database_ids: true,
+    }
+  }
+}
+
+impl CreateDatabaseParamsValidator {
+  pub fn with_require_primary_field(mut self, require_primary_field: bool) -> Self {
+    self.require_primary_field = require_primary_field;
+    self
+  }
+
+  pub fn with_check_layout_fields(mut self, check_layout_fields: bool) -> Self {
+    self.check_layout_fields = check_layout_fields;
+    self
+  }
+
+  pub fn with_fill_database_ids(mut self, fill_database_ids: bool) -> Self {
+    self.fill_database_ids = fill_database_ids;
+    self
+  }
+
+  /// Return the params with their default values filled, or all their violations.
+  pub fn validate(
+    &self,
+    mut params: CreateDatabaseParams,
+  ) -> Result<CreateDatabaseParams, Vec<CreateDatabaseViolation>> {
+    if self.fill_database_ids {
+      for view in params.views.iter_mut() {
+        if view.database_id.is_empty() {
+          view.database_id = params.database_id.clone();
+        }
+      }
+      for row in params.rows.iter_mut() {
+        if row.database_id.is_empty() {
+          row.database_id = params.database_id.clone();
+        }
+      }
+    }
+    let violations = self.violations(&params);
+    if violations.is_empty() {
+      Ok(params)
+    } else {
+      Err(violations)
+    }
+  }
+
+  pub fn violations(&self, params: &CreateDatabaseParams) -> Vec<CreateDatabaseViolation> {
+    let mut violations = vec![];
+    if params.database_id.is_empty() {
+      violations.push(CreateDatabaseViolation::EmptyDatabaseId);
+    }
+
+    // The deps fields of the views are created along with the fields of the database.
+    let fields = params
+      .fields
+      .iter()
+      .chain(params.views.iter().flat_map(|view| view.deps_fields.iter()))
+      .collect::<Vec<_>>();
+    let mut field_ids = HashSet::new();
+    for field in fields.iter() {
+      if field.id.is_empty() {
+        violations.push(CreateDatabaseViolation::EmptyFieldId);
+      } else if !field_ids.insert(field.id.as_str()) {
+        violations.push(CreateDatabaseViolation::DuplicateFieldId(field.id.clone()));
+      }
+    }
+    if self.require_primary_field {
+      let primary_field_ids = fields
+        .iter()
+        .filter(|field| field.is_primary)
+        .map(|field| field.id.clone())
+        .collect::<Vec<_>>();
+      match primary_field_ids.len() {
+        0 => violations.push(CreateDatabaseViolation::MissingPrimaryField),
+        1 => {},
+        _ => violations.push(CreateDatabaseViolation::MultiplePrimaryFields(
+          primary_field_ids,
+        )),
+      }
+    }
+
+    let mut row_ids = HashSet::new();
+    for row in params.rows.iter() {
+      if row.id.is_empty() {
+        violations.push(CreateDatabaseViolation::EmptyRowId);
+      } else if !row_ids.insert(&row.id) {
+        violations.push(CreateDatabaseViolation::DuplicateRowId(row.id.clone()));
+      }
+      if row.database_id != params.database_id {
+        violations.push(CreateDatabaseViolation::RowOfOtherDatabase(row.id.clone()));
+      }
+    }
+
+    let mut view_ids = HashSet::new();
+    for view in params.views.iter() {
+      if view.view_id.is_empty() {
+        violations.push(CreateDatabaseViolation::EmptyViewId);
+      } else if !view_ids.insert(view.view_id.as_str()) {
+        violations.push(CreateDatabaseViolation::DuplicateViewId(
+          view.view_id.clone(),
+        ));
+      }
+      if view.database_id != params.database_id {
+        violations.push(CreateDatabaseViolation::ViewOfOtherDatabase(
+          view.view_id.clone(),
+        ));
+      }
+      self.check_view_references(view, &field_ids, &mut violations);
+      if view.deps_fields.len() != view.deps_field_setting.len() {
+        violations.push(CreateDatabaseViolation::MismatchedDepsFields {
+          view_id: view.view_id.clone(),
+        });
+      }
+      if self.check_layout_fields
+        && view.layout == DatabaseLayout::Calendar
+        && !fields.iter().any(|field| {
+          matches!(
+            FieldType::from(field.field_type),
+            FieldType::DateTime | FieldType::CreatedTime | FieldType::LastEditedTime
+          )
+        })
+      {
+        violations.push(CreateDatabaseViolation::MissingCalendarDateField {
+          view_id: view.view_id.clone(),
+        });
+      }
+    }
+    violations
+  }
+
+  fn check_view_references(
+    &self,
+    view: &CreateViewParams,
+    field_ids: &HashSet<&str>,
+    violations: &mut Vec<CreateDatabaseViolation>,
+  ) {
+    let unknown_field_id = |map: &HashMap<String, Any>, key: &str| match map.get(key) {
+      Some(Any::String(field_id)) if !field_ids.contains(field_id.as_ref()) => {
+        Some(field_id.to_string())
+      },
+      _ => None,
+    };
+    let mut filters = view
+      .filters
+      .iter()
+      .cloned()
+      .map(FilterNode::from)
+      .collect::<Vec<_>>();
+    while let Some(filter) = filters.pop() {
+      match filter {
+        FilterNode::Data(filter) => {
+          if let Some(field_id) = unknown_field_id(&filter, FILTER_FIELD_ID) {
+            violations.push(CreateDatabaseViolation::UnknownFilterField {
+              view_id: view.view_id.clone(),
+              field_id,
+            });
+          }
+        },
+        group => filters.extend(group.children().iter().cloned()),
+      }
+    }
+    for sort in view.sorts.iter() {
+      if let Some(field_id) = unknown_field_id(sort, SORT_FIELD_ID) {
+        violations.push(CreateDatabaseViolation::UnknownSortField {
+          view_id: view.view_id.clone(),
+          field_id,
+        });
+      }
+    }
+    for group in view.group_settings.iter() {
+      if let Some(field_id) = unknown_field_id(group, GROUP_FIELD_ID) {
+        violations.push(CreateDatabaseViolation::UnknownGroupField {
+          view_id: view.view_id.clone(),
+          field_id,
+        });
+      }
+    }
+  }
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CreateDatabaseParams {
   pub database_id: String,
@@ -198,23 +519,22 @@ impl CreateDatabaseParams {
   /// This function creates a converts a `CreateDatabaseParams` that can be used to create a new
   /// database with the same data inside the given `DatabaseData` struct containing all the
   /// data of a database. The internal `database_id`, the database views' `view_id`s and the rows'
-  /// `row_id`s will all be regenerated.
+  /// `row_id`s will all be regenerated. The created and modified times of the rows and the views
+  /// are left unset, the new database stamps them with its clock.
   pub fn from_database_data(
     data: DatabaseData,
     database_view_id: &str,
     new_database_view_id: &str,
   ) -> Self {
     let database_id = gen_database_id();
-    let timestamp = timestamp();
-
     let create_row_params = data
       .rows
       .into_iter()
       .map(|row| CreateRowParams {
         id: gen_row_id(),
         database_id: database_id.clone(),
-        created_at: timestamp,
-        modified_at: timestamp,
+        created_at: 0,
+        modified_at: 0,
         cells: row.cells,
         height: row.height,
         visibility: row.visibility,
@@ -239,8 +559,8 @@ impl CreateDatabaseParams {
         group_settings: view.group_settings,
         sorts: view.sorts,
         field_settings: view.field_settings,
-        created_at: timestamp,
-        modified_at: timestamp,
+        created_at: 0,
+        modified_at: 0,
         ..Default::default()
       })
       .collect();
@@ -252,6 +572,31 @@ impl CreateDatabaseParams {
       views: create_view_params,
     }
   }
+
+  /// Replace the `{{name}}` placeholders of the field names, the type options, the cells and the
+  /// view names with the given variables, so one template can be instantiated with the names of
+  /// each user, see [resolve_placeholders]. The placeholders without a variable are kept as they
+  /// are.
+  pub fn resolve_variables(mut self, variables: &HashMap<String, String>) -> Self {
+    if variables.is_empty() {
+      return self;
+    }
+    for field in self.fields.iter_mut() {
+      field.name = resolve_placeholders(&field.name, variables);
+      for type_option in field.type_options.values_mut() {
+        *type_option = resolve_map(type_option, variables);
+      }
+    }
+    for row in self.rows.iter_mut() {
+      for cell in row.cells.values_mut() {
+        *cell = resolve_map(cell, variables);
+      }
+    }
+    for view in self.views.iter_mut() {
+      view.name = resolve_placeholders(&view.name, variables);
+    }
+    self
+  }
 }
 
 #[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize_repr, Deserialize_repr)]
@@ -272,6 +617,14 @@ pub enum FieldType {
   Translate = 12,
   Time = 13,
   Media = 14,
+  Formula = 15,
+  Rollup = 16,
+  Rating = 17,
+  Progress = 18,
+  Location = 19,
+  Email = 20,
+  Phone = 21,
+  Lookup = 22,
 }
 
 impl FieldType {
@@ -315,6 +668,14 @@ impl From<i64> for FieldType {
       12 => FieldType::Translate,
       13 => FieldType::Time,
       14 => FieldType::Media,
+      15 => FieldType::Formula,
+      16 => FieldType::Rollup,
+      17 => FieldType::Rating,
+      18 => FieldType::Progress,
+      19 => FieldType::Location,
+      20 => FieldType::Email,
+      21 => FieldType::Phone,
+      22 => FieldType::Lookup,
       _ => {
         error!("Unknown field type: {}, fallback to text", index);
         FieldType::RichText
@@ -1,4 +1,9 @@
+use std::fmt::Display;
+
+use crate::entity::CreateDatabaseViolation;
 use crate::rows::RowId;
+use collab::error::{anyhow_error_code, ClassifiedError, ErrorCode, ErrorContext};
+use collab_document::error::DocumentError;
 use collab_entity::CollabValidateError;
 
 #[derive(Debug, thiserror::Error)]
@@ -21,6 +26,12 @@ pub enum DatabaseError {
   #[error("The database view is not existing")]
   DatabaseViewNotExist,
 
+  #[error(
+    "The params to create the database are invalid: {}",
+    .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+  )]
+  InvalidCreateDatabaseParams(Vec<CreateDatabaseViolation>),
+
   #[error(transparent)]
   SerdeJson(#[from] serde_json::Error),
 
@@ -42,16 +53,31 @@ pub enum DatabaseError {
   #[error("Invalid CSV:{0}")]
   InvalidCSV(String),
 
+  #[error("Invalid email: {0}")]
+  InvalidEmail(String),
+
+  #[error("Invalid phone number: {0}")]
+  InvalidPhoneNumber(String),
+
   #[error("Import data failed: {0}")]
   ImportData(String),
 
   #[error("Internal failure: {0}")]
   Internal(#[from] anyhow::Error),
+
+  #[error("{context}: {source}")]
+  Context {
+    context: String,
+    source: Box<DatabaseError>,
+  },
 }
 
 impl DatabaseError {
   pub fn is_no_required_data(&self) -> bool {
-    matches!(self, DatabaseError::NoRequiredData(_))
+    match self {
+      DatabaseError::Context { source, .. } => source.is_no_required_data(),
+      _ => matches!(self, DatabaseError::NoRequiredData(_)),
+    }
   }
 }
 
@@ -62,3 +88,45 @@ impl From<CollabValidateError> for DatabaseError {
     }
   }
 }
+
+impl ClassifiedError for DatabaseError {
+  fn code(&self) -> ErrorCode {
+    match self {
+      DatabaseError::InvalidDatabaseID(_)
+      | DatabaseError::InvalidViewID(_)
+      | DatabaseError::InvalidRowID(_)
+      | DatabaseError::UuidError(_) => ErrorCode::InvalidId,
+      DatabaseError::DatabaseNotExist => ErrorCode::DatabaseNotExist,
+      DatabaseError::DatabaseRowNotFound { .. } => ErrorCode::DatabaseRowNotFound,
+      DatabaseError::DatabaseViewNotExist => ErrorCode::DatabaseViewNotExist,
+      DatabaseError::SerdeJson(_)
+      | DatabaseError::InvalidCSV(_)
+      | DatabaseError::InvalidEmail(_)
+      | DatabaseError::InvalidPhoneNumber(_)
+      | DatabaseError::InvalidCreateDatabaseParams(_) => ErrorCode::InvalidData,
+      DatabaseError::NoRequiredData(_) => ErrorCode::NoRequiredData,
+      DatabaseError::RecordAlreadyExist => ErrorCode::RecordAlreadyExist,
+      DatabaseError::RecordNotFound => ErrorCode::RecordNotFound,
+      DatabaseError::ActionCancelled => ErrorCode::Cancelled,
+      DatabaseError::ImportData(_) => ErrorCode::ImportData,
+      DatabaseError::Internal(err) => {
+        // The database wraps the errors of the document it depends on.
+        if let Some(err) = err.downcast_ref::<DocumentError>() {
+          err.code()
+        } else {
+          anyhow_error_code(err)
+        }
+      },
+      DatabaseError::Context { source, .. } => source.code(),
+    }
+  }
+}
+
+impl ErrorContext for DatabaseError {
+  fn context<C: Display>(self, context: C) -> Self {
+    DatabaseError::Context {
+      context: context.to_string(),
+      source: Box::new(self),
+    }
+  }
+}
@@ -0,0 +1,126 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use collab::preclude::{DeepObservable, Event, MapExt, MapRef, PathSegment, ReadTxn, Subscription};
+use dashmap::DashMap;
+
+use crate::fields::contact_type_option::ContactValidators;
+use crate::fields::progress_type_option::ProgressDerivation;
+use crate::fields::{field_from_map_ref, Field};
+use crate::rows::{cell_from_map_ref, Cell, Cells};
+
+/// The rules applied to the cells written to the rows of a database: the email and phone cells
+/// are validated, see [ContactValidators], and the progress cells derived from a checklist are
+/// refreshed, see [ProgressDerivation].
+///
+/// The rules are kept by field and updated by the [crate::fields::FieldMap] when its fields
+/// change, so writing a row doesn't read the fields of the database.
+#[derive(Debug, Default)]
+pub struct CellRules {
+  validators: Arc<ContactValidators>,
+  /// The derivations of the progress fields, by the id of the progress field.
+  derivations: DashMap<String, ProgressDerivation>,
+}
+
+impl CellRules {
+  pub fn from_fields(fields: &[Field]) -> Self {
+    let rules = Self::default();
+    rules.set_fields(fields);
+    rules
+  }
+
+  pub fn set_fields(&self, fields: &[Field]) {
+    for field in fields {
+      self.set_field(field);
+    }
+  }
+
+  /// Replace the rules of the field.
+  pub fn set_field(&self, field: &Field) {
+    self.validators.set_field(field);
+    match ProgressDerivation::from_field(field) {
+      None => {
+        self.derivations.remove(&field.id);
+      },
+      Some(derivation) => {
+        self.derivations.insert(field.id.clone(), derivation);
+      },
+    }
+  }
+
+  pub fn remove_field(&self, field_id: &str) {
+    self.validators.remove_field(field_id);
+    self.derivations.remove(field_id);
+  }
+
+  pub fn validators(&self) -> Arc<ContactValidators> {
+    self.validators.clone()
+  }
+
+  pub fn has_derivations(&self) -> bool {
+    !self.derivations.is_empty()
+  }
+
+  /// Add the progress cells derived from the checklist cells to the cells of a new row.
+  pub fn derive_cells(&self, cells: &mut Cells) {
+    for derivation in self.derivations.iter() {
+      let cell = derivation.derive_cell(
+        cells.get(&derivation.checklist_field_id),
+        cells.get(&derivation.progress_field_id),
+      );
+      if let Some(cell) = cell {
+        cells.insert(derivation.progress_field_id.clone(), cell);
+      }
+    }
+  }
+
+  /// Return the progress cells of the row that are out of date with its checklist cells, by the
+  /// id of their field.
+  pub fn outdated_derived_cells<T: ReadTxn>(
+    &self,
+    txn: &T,
+    row_data: &MapRef,
+  ) -> Vec<(String, Cell)> {
+    self
+      .derivations
+      .iter()
+      .filter_map(|derivation| {
+        let checklist_cell = cell_from_map_ref(row_data, txn, &derivation.checklist_field_id);
+        let progress_cell = cell_from_map_ref(row_data, txn, &derivation.progress_field_id);
+        derivation
+          .derive_cell(checklist_cell.as_ref(), progress_cell.as_ref())
+          .map(|cell| (derivation.progress_field_id.clone(), cell))
+      })
+      .collect()
+  }
+}
+
+/// Keep the rules up to date with the fields of the map, whether they are changed locally or by
+/// the remote. Only the fields that changed are read again.
+pub(crate) fn subscribe_cell_rules(field_map: &MapRef, rules: Arc<CellRules>) -> Subscription {
+  let fields = field_map.clone();
+  field_map.observe_deep(move |txn, events| {
+    let mut field_ids = HashSet::new();
+    for event in events.iter() {
+      match event.path().front() {
+        Some(PathSegment::Key(field_id)) => {
+          field_ids.insert(field_id.to_string());
+        },
+        _ => {
+          if let Event::Map(map_event) = event {
+            field_ids.extend(map_event.keys(txn).keys().map(|key| key.to_string()));
+          }
+        },
+      }
+    }
+    for field_id in field_ids {
+      let field = fields
+        .get_with_txn::<_, MapRef>(txn, &field_id)
+        .and_then(|map_ref| field_from_map_ref(&map_ref, txn));
+      match field {
+        None => rules.remove_field(&field_id),
+        Some(field) => rules.set_field(&field),
+      }
+    }
+  })
+}
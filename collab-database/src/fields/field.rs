@@ -163,6 +163,11 @@ pub fn field_from_value<T: ReadTxn>(value: YrsValue, txn: &T) -> Option<Field> {
   field_from_map_ref(&map_ref, txn)
 }
 
+/// Get the time the field was last modified from a [MapRef]
+pub fn field_last_modified_from_map_ref<T: ReadTxn>(map_ref: &MapRef, txn: &T) -> Option<i64> {
+  map_ref.get_with_txn(txn, LAST_MODIFIED)
+}
+
 /// Get field from a [MapRef]
 pub fn field_from_map_ref<T: ReadTxn>(map_ref: &MapRef, txn: &T) -> Option<Field> {
   let id: String = map_ref.get_with_txn(txn, FIELD_ID)?;
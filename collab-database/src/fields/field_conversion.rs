@@ -0,0 +1,290 @@
+use collab::util::AnyMapExt;
+
+use crate::entity::FieldType;
+use crate::fields::checkbox_type_option::{
+  is_checkbox_checked, CheckboxTypeOption, CHECKBOX_CHECKED, CHECKBOX_UNCHECKED,
+};
+use crate::fields::contact_type_option::{
+  EmailCellData, EmailTypeOption, PhoneCellData, PhoneTypeOption,
+};
+use crate::fields::date_type_option::{DateCellData, DateTypeOption};
+use crate::fields::location_type_option::{LocationCellData, LocationTypeOption};
+use crate::fields::number_type_option::{NumberCellFormat, NumberFormat, NumberTypeOption};
+use crate::fields::progress_type_option::{parse_progress, ProgressTypeOption};
+use crate::fields::rating_type_option::RatingTypeOption;
+use crate::fields::select_type_option::{
+  MultiSelectTypeOption, SelectOption, SelectOptionColor, SelectOptionIds, SelectTypeOption,
+  SingleSelectTypeOption,
+};
+use crate::fields::text_type_option::RichTextTypeOption;
+use crate::fields::time_type_option::{TimeCellData, TimeTypeOption};
+use crate::fields::url_type_option::{URLCellData, URLTypeOption};
+use crate::fields::{stringify_type_option, Field, StringifyTypeOption, TypeOptionData};
+use crate::rows::{new_cell_builder, Cell};
+use crate::template::date_parse::cast_string_to_timestamp;
+use crate::template::entity::CELL_DATA;
+
+/// Converts the cells of a field whose type is switched with
+/// [crate::database::Database::switch_field_type]. A custom converter can handle some types
+/// itself and fall back to [BuiltinFieldTypeConverter] for the others.
+pub trait FieldTypeConverter: Send + Sync {
+  /// Return the cell converted to [FieldTypeConversion::new_type], or None to clear the cell.
+  fn convert_cell(&self, conversion: &mut FieldTypeConversion, cell: &Cell) -> Option<Cell>;
+}
+
+/// The built-in conversions. A cell is turned into its text, then parsed in the new type: a
+/// number, a rating, a progress or a location is parsed from the text, an email or a phone number
+/// is trimmed if it's valid and kept as it is otherwise, the options of a select cell are joined
+/// with commas, and the options of the text are found or created in a select field. The cells that
+/// can't be parsed, and the cells of the types without a text like a checklist or a relation, are
+/// cleared.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BuiltinFieldTypeConverter;
+
+impl FieldTypeConverter for BuiltinFieldTypeConverter {
+  fn convert_cell(&self, conversion: &mut FieldTypeConversion, cell: &Cell) -> Option<Cell> {
+    let old_type = conversion.old_type.clone();
+    let new_type = conversion.new_type.clone();
+    let raw_data = cell.get_as::<String>(CELL_DATA).unwrap_or_default();
+    let text = conversion.old_text(cell);
+    let string_cell = |data: String| {
+      let mut cell = new_cell_builder(new_type.clone());
+      cell.insert(CELL_DATA.into(), data.into());
+      cell
+    };
+    match &new_type {
+      FieldType::RichText => non_empty(text).map(string_cell),
+      FieldType::URL => non_empty(text).map(|text| Cell::from(URLCellData::new(&text))),
+      FieldType::Number => {
+        let number = match old_type {
+          FieldType::Checkbox => Some(
+            if is_checkbox_checked(&raw_data) {
+              "1"
+            } else {
+              "0"
+            }
+            .to_string(),
+          ),
+          FieldType::Time | FieldType::DateTime => non_empty(raw_data),
+          _ => NumberCellFormat::from_format_str(text.trim(), &NumberFormat::Num)
+            .ok()
+            .filter(|number| !number.is_empty())
+            .map(|number| number.to_unformatted_string()),
+        };
+        number.map(string_cell)
+      },
+      FieldType::Checkbox => non_empty(text).map(|text| {
+        let checked = match text.trim().parse::<f64>() {
+          Ok(number) => number != 0.0,
+          Err(_) => is_checkbox_checked(&text),
+        };
+        let data = if checked {
+          CHECKBOX_CHECKED
+        } else {
+          CHECKBOX_UNCHECKED
+        };
+        string_cell(data.to_string())
+      }),
+      FieldType::DateTime => {
+        let timestamp = match old_type {
+          FieldType::Number => raw_data.trim().parse::<i64>().ok(),
+          _ => cast_string_to_timestamp(text.trim()),
+        };
+        timestamp.map(|timestamp| Cell::from(&DateCellData::from_timestamp(timestamp)))
+      },
+      FieldType::Time => {
+        let seconds = match old_type {
+          FieldType::Number => raw_data
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .map(|s| s.round() as i64),
+          _ => TimeTypeOption::parse_duration(&text),
+        };
+        seconds.map(|seconds| Cell::from(&TimeCellData::new(seconds)))
+      },
+      FieldType::Rating => {
+        let rating = match old_type {
+          FieldType::Number => raw_data.trim().parse::<f64>().ok(),
+          _ => text.trim().parse::<f64>().ok(),
+        };
+        let type_option = RatingTypeOption::from(conversion.new_type_option.clone());
+        rating
+          .filter(|rating| rating.is_finite())
+          .map(|rating| type_option.rating_cell(rating.round() as i64))
+      },
+      FieldType::Progress => {
+        let progress = match old_type {
+          FieldType::Number => parse_progress(&raw_data),
+          _ => parse_progress(&text),
+        };
+        let type_option = ProgressTypeOption::from(conversion.new_type_option.clone());
+        progress.map(|progress| type_option.progress_cell(progress))
+      },
+      FieldType::Location => LocationCellData::parse(&text).map(|location| Cell::from(&location)),
+      // The invalid emails and phone numbers are kept as they are, for the user to fix them.
+      FieldType::Email => non_empty(text).map(|text| {
+        EmailTypeOption::from(conversion.new_type_option.clone())
+          .email_cell(&text)
+          .unwrap_or_else(|_| Cell::from(&EmailCellData { email: text }))
+      }),
+      FieldType::Phone => non_empty(text).map(|text| {
+        PhoneTypeOption::from(conversion.new_type_option.clone())
+          .phone_cell(&text)
+          .unwrap_or_else(|_| Cell::from(&PhoneCellData { phone: text }))
+      }),
+      FieldType::SingleSelect | FieldType::MultiSelect => {
+        let mut names = text
+          .split(',')
+          .map(str::trim)
+          .filter(|name| !name.is_empty())
+          .collect::<Vec<_>>();
+        if new_type == FieldType::SingleSelect {
+          names.truncate(1);
+        }
+        if names.is_empty() {
+          return None;
+        }
+        let ids = names
+          .into_iter()
+          .map(|name| conversion.select_option_id(name))
+          .collect::<Vec<_>>();
+        Some(SelectOptionIds::from(ids).to_cell_data(new_type))
+      },
+      FieldType::Checklist
+      | FieldType::LastEditedTime
+      | FieldType::CreatedTime
+      | FieldType::Relation
+      | FieldType::Summary
+      | FieldType::Translate
+      | FieldType::Media
+      | FieldType::Formula
+      | FieldType::Rollup
+      | FieldType::Lookup => None,
+    }
+  }
+}
+
+/// The state of a switch of the type of a field, passed to the [FieldTypeConverter] for each
+/// cell.
+pub struct FieldTypeConversion {
+  pub old_type: FieldType,
+  pub new_type: FieldType,
+  /// The type option of the field in its old type.
+  pub old_type_option: Option<TypeOptionData>,
+  /// The type option of the field in its new type, written to the field once all the cells are
+  /// converted.
+  pub new_type_option: TypeOptionData,
+  stringify: Option<Box<dyn StringifyTypeOption>>,
+  select_type_option: Option<SelectTypeOption>,
+}
+
+impl FieldTypeConversion {
+  /// Start the conversion of the field to the new type. The type option the field had in the new
+  /// type is kept, and a select field switched to the other select type keeps its options.
+  pub fn new(field: &Field, new_type: FieldType) -> Self {
+    let old_type = FieldType::from(field.field_type);
+    let old_type_option = field.get_any_type_option(old_type.type_id());
+    let stringify = old_type_option
+      .clone()
+      .or_else(|| default_type_option_data(&old_type))
+      .and_then(|type_option| stringify_type_option(type_option, &old_type));
+    let new_type_option = field
+      .get_any_type_option(new_type.type_id())
+      .or_else(|| match (&old_type, &new_type) {
+        (
+          FieldType::SingleSelect | FieldType::MultiSelect,
+          FieldType::SingleSelect | FieldType::MultiSelect,
+        ) => old_type_option.clone(),
+        _ => None,
+      })
+      .or_else(|| default_type_option_data(&new_type))
+      .unwrap_or_default();
+    let select_type_option = match new_type {
+      FieldType::SingleSelect | FieldType::MultiSelect => {
+        Some(SelectTypeOption::from(new_type_option.clone()))
+      },
+      _ => None,
+    };
+    Self {
+      old_type,
+      new_type,
+      old_type_option,
+      new_type_option,
+      stringify,
+      select_type_option,
+    }
+  }
+
+  /// Return the text of the cell in the old type, like it's shown to the user.
+  pub fn old_text(&self, cell: &Cell) -> String {
+    match &self.stringify {
+      Some(stringify) => stringify.stringify_cell(cell),
+      None => "".to_string(),
+    }
+  }
+
+  /// Return the id of the option of the select field with the name, creating the option if the
+  /// field doesn't have it. The names are compared ignoring the case.
+  pub fn select_option_id(&mut self, name: &str) -> String {
+    let type_option = self.select_type_option.get_or_insert_with(Default::default);
+    if let Some(option) = type_option
+      .options
+      .iter()
+      .find(|option| option.name.to_lowercase() == name.to_lowercase())
+    {
+      return option.id.clone();
+    }
+    let color = SelectOptionColor::from(type_option.options.len() % 8);
+    let option = SelectOption::with_color(name, color);
+    let id = option.id.clone();
+    type_option.options.push(option);
+    id
+  }
+
+  /// Return the type option of the field in its new type, with the select options created by the
+  /// conversion.
+  pub fn into_type_option(self) -> TypeOptionData {
+    match self.select_type_option {
+      Some(select_type_option) => select_type_option.into(),
+      None => self.new_type_option,
+    }
+  }
+}
+
+/// The number of cells rewritten by [crate::database::Database::switch_field_type].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FieldTypeConversionSummary {
+  pub converted: usize,
+  /// The cells that couldn't be converted to the new type.
+  pub cleared: usize,
+  /// The rows that couldn't be loaded, so their cells are not converted.
+  pub skipped: usize,
+}
+
+fn non_empty(text: String) -> Option<String> {
+  if text.trim().is_empty() {
+    None
+  } else {
+    Some(text)
+  }
+}
+
+fn default_type_option_data(field_type: &FieldType) -> Option<TypeOptionData> {
+  match field_type {
+    FieldType::RichText => Some(RichTextTypeOption.into()),
+    FieldType::Number => Some(NumberTypeOption::default().into()),
+    FieldType::DateTime => Some(DateTypeOption::new().into()),
+    FieldType::SingleSelect => Some(SingleSelectTypeOption::default().into()),
+    FieldType::MultiSelect => Some(MultiSelectTypeOption::default().into()),
+    FieldType::Checkbox => Some(CheckboxTypeOption.into()),
+    FieldType::URL => Some(URLTypeOption::default().into()),
+    FieldType::Time => Some(TimeTypeOption::default().into()),
+    FieldType::Rating => Some(RatingTypeOption::default().into()),
+    FieldType::Progress => Some(ProgressTypeOption::default().into()),
+    FieldType::Location => Some(LocationTypeOption::default().into()),
+    FieldType::Email => Some(EmailTypeOption::default().into()),
+    FieldType::Phone => Some(PhoneTypeOption::default().into()),
+    _ => None,
+  }
+}
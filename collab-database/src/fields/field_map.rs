@@ -1,9 +1,12 @@
+use std::sync::Arc;
+
+use collab::core::clock::{system_clock, ClockProvider};
 use collab::preclude::{Map, MapExt, MapRef, ReadTxn, Subscription, TransactionMut};
 
-use crate::database::timestamp;
 use crate::fields::{
   field_from_map_ref, field_from_value, field_id_from_value, primary_field_id_from_value,
-  subscribe_field_change, Field, FieldBuilder, FieldChangeSender, FieldUpdate,
+  subscribe_cell_rules, subscribe_field_change, CellRules, Field, FieldBuilder, FieldChangeSender,
+  FieldUpdate,
 };
 use crate::views::FieldOrder;
 
@@ -12,27 +15,53 @@ pub struct FieldMap {
   container: MapRef,
   #[allow(dead_code)]
   subscription: Option<Subscription>,
+  clock: Arc<dyn ClockProvider>,
+  cell_rules: Arc<CellRules>,
+  #[allow(dead_code)]
+  cell_rules_subscription: Subscription,
 }
 
 impl FieldMap {
   pub fn new(mut container: MapRef, field_change_tx: Option<FieldChangeSender>) -> Self {
     let subscription = field_change_tx.map(|tx| subscribe_field_change(&mut container, tx));
+    let cell_rules = Arc::new(CellRules::default());
+    let cell_rules_subscription = subscribe_cell_rules(&container, cell_rules.clone());
     Self {
       container,
       subscription,
+      clock: system_clock(),
+      cell_rules,
+      cell_rules_subscription,
     }
   }
 
+  /// Return the rules of the cells of the fields, see [CellRules]. They follow the changes of the
+  /// fields once loaded with [FieldMap::load_cell_rules].
+  pub fn cell_rules(&self) -> Arc<CellRules> {
+    self.cell_rules.clone()
+  }
+
+  pub fn load_cell_rules<T: ReadTxn>(&self, txn: &T) {
+    self.cell_rules.set_fields(&self.get_all_fields(txn));
+  }
+
+  /// Set the clock that stamps the created and modified times of the fields.
+  pub fn with_clock(mut self, clock: Arc<dyn ClockProvider>) -> Self {
+    self.clock = clock;
+    self
+  }
+
   /// Insert a field into the map with a transaction
   pub fn insert_field(&self, txn: &mut TransactionMut, field: Field) {
     let map_ref: MapRef = self.container.get_or_init(txn, field.id.as_str());
+    let timestamp = self.clock.timestamp();
     FieldBuilder::new(&field.id, txn, map_ref)
       .update(|update| {
         update
           .set_name(field.name)
           .set_icon(field.icon)
-          .set_created_at(timestamp())
-          .set_last_modified(timestamp())
+          .set_created_at(timestamp)
+          .set_last_modified(timestamp)
           .set_primary(field.is_primary)
           .set_field_type(field.field_type)
           .set_type_options(field.type_options);
@@ -113,7 +142,7 @@ impl FieldMap {
   {
     let map_ref: MapRef = self.container.get_or_init(txn, field_id);
     let mut update = FieldUpdate::new(field_id, txn, &map_ref);
-    update = update.set_last_modified(timestamp());
+    update = update.set_last_modified(self.clock.timestamp());
     f(update);
   }
 
@@ -1,11 +1,18 @@
+mod cell_rules;
 mod field;
+mod field_conversion;
 mod field_id;
 mod field_map;
 mod field_observer;
+mod number_util;
 mod type_option;
 
+pub use crate::template::chect_list_parse::ChecklistCellData;
+pub use cell_rules::*;
 pub use field::*;
+pub use field_conversion::*;
 pub use field_id::*;
 pub use field_map::*;
 pub use field_observer::*;
+pub(crate) use number_util::*;
 pub use type_option::*;
@@ -0,0 +1,22 @@
+//! The numbers read from and written to the text of the cells, shared by the formulas, the
+//! rollups, the calculations and the statistics of the fields.
+
+/// Parse the text of a cell as a number. The thousands separators are ignored, and the text that
+/// isn't a finite number returns None.
+pub(crate) fn parse_number(text: &str) -> Option<f64> {
+  let text = text.trim().replace(',', "");
+  if text.is_empty() {
+    return None;
+  }
+  text.parse::<f64>().ok().filter(|value| value.is_finite())
+}
+
+/// Format the value without the rounding noise of the floating point operations, so
+/// `0.1 + 0.2` is shown as 0.3.
+pub(crate) fn format_number(value: f64) -> String {
+  let rounded = (value * 1e10).round() / 1e10;
+  if rounded == 0.0 {
+    return "0".to_string();
+  }
+  rounded.to_string()
+}
@@ -1,6 +1,11 @@
 use crate::fields::{StringifyTypeOption, TypeOptionData, TypeOptionDataBuilder};
 use serde::{Deserialize, Serialize};
 
+/// The text of a checked checkbox cell.
+pub const CHECKBOX_CHECKED: &str = "Yes";
+/// The text of an unchecked checkbox cell.
+pub const CHECKBOX_UNCHECKED: &str = "No";
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct CheckboxTypeOption;
 
@@ -27,3 +32,19 @@ impl From<TypeOptionData> for CheckboxTypeOption {
     CheckboxTypeOption
   }
 }
+
+/// Parses the text of a checkbox cell. The cells may hold `Yes`, `true`, `1` or `checked`, in
+/// any case, for a checked checkbox, and `No`, `false`, `0`, `unchecked` or nothing for an
+/// unchecked one. Returns None for any other text.
+pub fn parse_checkbox(text: &str) -> Option<bool> {
+  match text.trim().to_lowercase().as_str() {
+    "yes" | "true" | "1" | "checked" => Some(true),
+    "" | "no" | "false" | "0" | "unchecked" => Some(false),
+    _ => None,
+  }
+}
+
+/// Returns true if the text of a checkbox cell is checked. See [parse_checkbox].
+pub fn is_checkbox_checked(text: &str) -> bool {
+  parse_checkbox(text).unwrap_or(false)
+}
@@ -0,0 +1,306 @@
+use collab::preclude::{Any, Map, MapRef, ToJson, TransactionMut};
+use collab::util::{AnyExt, AnyMapExt};
+use sha2::{Digest, Sha256};
+
+use crate::entity::FieldType;
+use crate::fields::select_type_option::SelectOption;
+use crate::fields::ChecklistCellData;
+use crate::rows::{new_cell_builder, Cell};
+use crate::template::entity::CELL_DATA;
+
+/// The keys of the options of a checklist cell. Each option is stored in its own keys of the
+/// cell, suffixed by the id of the option, so the clients that edit different options, or the
+/// name and the checkmark of the same option, don't overwrite each other.
+const CHECKLIST_OPTION: &str = "checklist_option:";
+const CHECKLIST_CHECKED: &str = "checklist_checked:";
+const CHECKLIST_POSITION: &str = "checklist_position:";
+/// The fingerprint of the [CELL_DATA] written along with the keys of the options. The keys of
+/// the options are only read while it matches the [CELL_DATA] of the cell, so a cell written
+/// as a whole afterwards, by a client that doesn't know these keys for example, is read from
+/// its [CELL_DATA] again.
+const CHECKLIST_SPLIT: &str = "checklist_split";
+
+/// A change of a single option of a checklist cell, see
+/// [crate::database::Database::update_checklist_cell]. The changes of an option that doesn't
+/// exist are ignored.
+#[derive(Debug, Clone)]
+pub enum ChecklistCellChange {
+  Toggle {
+    option_id: String,
+  },
+  SetChecked {
+    option_id: String,
+    checked: bool,
+  },
+  /// Insert the option at the index, or at the end if the index is None.
+  Insert {
+    option: SelectOption,
+    index: Option<usize>,
+  },
+  Remove {
+    option_id: String,
+  },
+  Move {
+    option_id: String,
+    to_index: usize,
+  },
+  Rename {
+    option_id: String,
+    name: String,
+  },
+}
+
+struct ChecklistOption {
+  option: SelectOption,
+  position: f64,
+  checked: bool,
+}
+
+/// Return the options of the checklist cell, in their order. The cells written as a whole are
+/// read from their [CELL_DATA].
+pub fn checklist_from_cell(cell: &Cell) -> ChecklistCellData {
+  if !is_split(cell) {
+    return cell
+      .get_as::<String>(CELL_DATA)
+      .and_then(|data| serde_json::from_str::<ChecklistCellData>(&data).ok())
+      .unwrap_or_default();
+  }
+  checklist_data(checklist_options(cell))
+}
+
+/// Return the cell of the checklist, with the keys of each option.
+pub fn checklist_cell(data: &ChecklistCellData) -> Cell {
+  let mut cell = new_cell_builder(FieldType::Checklist);
+  let cell_data = checklist_cell_data(data);
+  cell.insert(CHECKLIST_SPLIT.into(), fingerprint(&cell_data).into());
+  cell.insert(CELL_DATA.into(), cell_data.into());
+  for (index, option) in data.options.iter().enumerate() {
+    let checked = data.selected_option_ids.contains(&option.id);
+    for (key, value) in option_entries(option, index as f64, checked) {
+      cell.insert(key, value);
+    }
+  }
+  cell
+}
+
+/// Apply the changes to the checklist cell in place. The [CELL_DATA] of the cell is rewritten
+/// with all the options, for the clients that read the checklist from it. It is the only key
+/// these clients see, so their concurrent changes are still last-writer-wins.
+pub fn apply_checklist_changes(
+  txn: &mut TransactionMut,
+  cell_map: &MapRef,
+  changes: Vec<ChecklistCellChange>,
+) {
+  let cell = cell_map.to_json(txn).into_map().unwrap_or_default();
+  if !is_split(&cell) {
+    // Split the checklist written as a whole into the keys of its options. The keys left by
+    // a previous split are stale.
+    let data = checklist_from_cell(&cell);
+    clear_checklist_keys(txn, cell_map);
+    for (index, option) in data.options.iter().enumerate() {
+      let checked = data.selected_option_ids.contains(&option.id);
+      for (key, value) in option_entries(option, index as f64, checked) {
+        cell_map.insert(txn, key, value);
+      }
+    }
+  }
+  let mut options = checklist_options(&cell_map.to_json(txn).into_map().unwrap_or_default());
+
+  for change in changes {
+    match change {
+      ChecklistCellChange::Toggle { option_id } => {
+        if let Some(option) = options
+          .iter_mut()
+          .find(|option| option.option.id == option_id)
+        {
+          option.checked = !option.checked;
+          cell_map.insert(txn, checked_key(&option_id), Any::Bool(option.checked));
+        }
+      },
+      ChecklistCellChange::SetChecked { option_id, checked } => {
+        if let Some(option) = options
+          .iter_mut()
+          .find(|option| option.option.id == option_id)
+        {
+          option.checked = checked;
+          cell_map.insert(txn, checked_key(&option_id), Any::Bool(checked));
+        }
+      },
+      ChecklistCellChange::Insert { option, index } => {
+        if options.iter().any(|other| other.option.id == option.id) {
+          continue;
+        }
+        let index = index.unwrap_or(options.len()).min(options.len());
+        let position = position_at(&options, index);
+        for (key, value) in option_entries(&option, position, false) {
+          cell_map.insert(txn, key, value);
+        }
+        options.insert(
+          index,
+          ChecklistOption {
+            option,
+            position,
+            checked: false,
+          },
+        );
+      },
+      ChecklistCellChange::Remove { option_id } => {
+        if let Some(index) = options
+          .iter()
+          .position(|option| option.option.id == option_id)
+        {
+          options.remove(index);
+          cell_map.remove(txn, &option_key(&option_id));
+          cell_map.remove(txn, &checked_key(&option_id));
+          cell_map.remove(txn, &position_key(&option_id));
+        }
+      },
+      ChecklistCellChange::Move {
+        option_id,
+        to_index,
+      } => {
+        if let Some(index) = options
+          .iter()
+          .position(|option| option.option.id == option_id)
+        {
+          let mut option = options.remove(index);
+          let to_index = to_index.min(options.len());
+          option.position = position_at(&options, to_index);
+          cell_map.insert(txn, position_key(&option_id), Any::Number(option.position));
+          options.insert(to_index, option);
+        }
+      },
+      ChecklistCellChange::Rename { option_id, name } => {
+        if let Some(option) = options
+          .iter_mut()
+          .find(|option| option.option.id == option_id)
+        {
+          option.option.name = name;
+          cell_map.insert(txn, option_key(&option_id), option_value(&option.option));
+        }
+      },
+    }
+  }
+
+  let options = checklist_options(&cell_map.to_json(txn).into_map().unwrap_or_default());
+  let cell_data = checklist_cell_data(&checklist_data(options));
+  cell_map.insert(txn, CHECKLIST_SPLIT, fingerprint(&cell_data));
+  cell_map.insert(txn, CELL_DATA, cell_data);
+}
+
+/// Remove the keys of the options of a checklist cell. Called before the cell is written as a
+/// whole, so the options that are not in the new cell don't come back.
+pub(crate) fn clear_checklist_keys(txn: &mut TransactionMut, cell_map: &MapRef) {
+  let keys = cell_map
+    .keys(txn)
+    .filter(|key| {
+      *key == CHECKLIST_SPLIT
+        || key.starts_with(CHECKLIST_OPTION)
+        || key.starts_with(CHECKLIST_CHECKED)
+        || key.starts_with(CHECKLIST_POSITION)
+    })
+    .map(|key| key.to_string())
+    .collect::<Vec<_>>();
+  for key in keys {
+    cell_map.remove(txn, &key);
+  }
+}
+
+fn is_split(cell: &Cell) -> bool {
+  match (cell.get(CHECKLIST_SPLIT), cell.get(CELL_DATA)) {
+    (Some(Any::String(split)), Some(Any::String(cell_data))) => {
+      split.as_ref() == fingerprint(cell_data)
+    },
+    _ => false,
+  }
+}
+
+fn fingerprint(cell_data: &str) -> String {
+  format!("{:x}", Sha256::digest(cell_data.as_bytes()))
+}
+
+fn checklist_data(options: Vec<ChecklistOption>) -> ChecklistCellData {
+  ChecklistCellData {
+    selected_option_ids: options
+      .iter()
+      .filter(|option| option.checked)
+      .map(|option| option.option.id.clone())
+      .collect(),
+    options: options.into_iter().map(|option| option.option).collect(),
+  }
+}
+
+fn checklist_options(cell: &Cell) -> Vec<ChecklistOption> {
+  let mut options = cell
+    .iter()
+    .filter_map(|(key, value)| {
+      let option_id = key.strip_prefix(CHECKLIST_OPTION)?;
+      let option = match value {
+        Any::String(value) => serde_json::from_str::<SelectOption>(value).ok()?,
+        _ => return None,
+      };
+      let position = match cell.get(&position_key(option_id)) {
+        Some(Any::Number(position)) => *position,
+        Some(Any::BigInt(position)) => *position as f64,
+        _ => f64::MAX,
+      };
+      let checked = matches!(cell.get(&checked_key(option_id)), Some(Any::Bool(true)));
+      Some(ChecklistOption {
+        option,
+        position,
+        checked,
+      })
+    })
+    .collect::<Vec<_>>();
+  // Two options inserted concurrently at the same position are ordered by their id.
+  options.sort_by(|left, right| {
+    left
+      .position
+      .total_cmp(&right.position)
+      .then_with(|| left.option.id.cmp(&right.option.id))
+  });
+  options
+}
+
+/// Return the position of an option inserted at the index, between its neighbours.
+fn position_at(options: &[ChecklistOption], index: usize) -> f64 {
+  let before = index
+    .checked_sub(1)
+    .and_then(|index| options.get(index))
+    .map(|option| option.position);
+  let after = options.get(index).map(|option| option.position);
+  match (before, after) {
+    (None, None) => 0.0,
+    (Some(before), None) => before + 1.0,
+    (None, Some(after)) => after - 1.0,
+    (Some(before), Some(after)) => (before + after) / 2.0,
+  }
+}
+
+fn option_entries(option: &SelectOption, position: f64, checked: bool) -> [(String, Any); 3] {
+  [
+    (option_key(&option.id), option_value(option)),
+    (position_key(&option.id), Any::Number(position)),
+    (checked_key(&option.id), Any::Bool(checked)),
+  ]
+}
+
+fn option_value(option: &SelectOption) -> Any {
+  Any::from(serde_json::to_string(option).unwrap_or_default())
+}
+
+fn checklist_cell_data(data: &ChecklistCellData) -> String {
+  serde_json::to_string(data).unwrap_or_default()
+}
+
+fn option_key(option_id: &str) -> String {
+  format!("{}{}", CHECKLIST_OPTION, option_id)
+}
+
+fn checked_key(option_id: &str) -> String {
+  format!("{}{}", CHECKLIST_CHECKED, option_id)
+}
+
+fn position_key(option_id: &str) -> String {
+  format!("{}{}", CHECKLIST_POSITION, option_id)
+}
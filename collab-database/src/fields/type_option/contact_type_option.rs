@@ -0,0 +1,341 @@
+use crate::entity::FieldType;
+use crate::error::DatabaseError;
+use crate::fields::{Field, StringifyTypeOption, TypeOptionData, TypeOptionDataBuilder};
+use crate::rows::{new_cell_builder, Cell, Cells};
+use crate::template::entity::CELL_DATA;
+use collab::preclude::Any;
+use collab::util::AnyMapExt;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
+use yrs::encoding::serde::from_any;
+
+const MAX_EMAIL_LEN: usize = 254;
+const MAX_EMAIL_LOCAL_PART_LEN: usize = 64;
+/// The max number of digits of an E.164 phone number.
+const MAX_PHONE_DIGITS: usize = 15;
+const MIN_STRICT_PHONE_DIGITS: usize = 7;
+const MIN_LENIENT_PHONE_DIGITS: usize = 3;
+
+/// How strictly the email and phone cells are validated before being written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum ValidationStrictness {
+  /// Only reject the values that can't be an email or a phone number, like a text without `@`.
+  #[default]
+  Lenient = 0,
+  /// Reject the emails that are not valid addresses, and the phone numbers that are not in the
+  /// international E.164 format, like `+33 1 23 45 67 89`.
+  Strict = 1,
+}
+
+/// The type option of an email field. The cells are validated by [EmailTypeOption::email_cell].
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EmailTypeOption {
+  #[serde(default)]
+  pub strictness: ValidationStrictness,
+}
+
+impl EmailTypeOption {
+  pub fn new(strictness: ValidationStrictness) -> Self {
+    Self { strictness }
+  }
+
+  /// Return the trimmed email, or [DatabaseError::InvalidEmail] if it's not valid for the
+  /// strictness of the field.
+  pub fn validate(&self, email: &str) -> Result<String, DatabaseError> {
+    let email = email.trim();
+    let invalid = |reason: &str| DatabaseError::InvalidEmail(format!("{}: {}", email, reason));
+    if email.chars().any(char::is_whitespace) {
+      return Err(invalid("contains whitespace"));
+    }
+    let (local, domain) = match email.rsplit_once('@') {
+      Some((local, domain)) if !local.is_empty() && !domain.is_empty() => (local, domain),
+      _ => return Err(invalid("expected an address like name@example.com")),
+    };
+    if self.strictness == ValidationStrictness::Strict {
+      if email.len() > MAX_EMAIL_LEN || local.len() > MAX_EMAIL_LOCAL_PART_LEN {
+        return Err(invalid("too long"));
+      }
+      if !is_valid_local_part(local) {
+        return Err(invalid("invalid name before @"));
+      }
+      if !is_valid_domain(domain) {
+        return Err(invalid("invalid domain"));
+      }
+    }
+    Ok(email.to_string())
+  }
+
+  /// Return the cell of the email, or an error if it's not valid.
+  pub fn email_cell(&self, email: &str) -> Result<Cell, DatabaseError> {
+    let email = self.validate(email)?;
+    Ok(Cell::from(&EmailCellData { email }))
+  }
+}
+
+fn is_valid_local_part(local: &str) -> bool {
+  !local.starts_with('.')
+    && !local.ends_with('.')
+    && !local.contains("..")
+    && local
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+/=?^_`{|}~.-".contains(c))
+}
+
+fn is_valid_domain(domain: &str) -> bool {
+  let labels = domain.split('.').collect::<Vec<_>>();
+  let is_valid_label = |label: &&str| {
+    !label.is_empty()
+      && label.len() <= 63
+      && !label.starts_with('-')
+      && !label.ends_with('-')
+      && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+  };
+  let tld = labels.last().copied().unwrap_or_default();
+  labels.len() >= 2
+    && labels.iter().all(is_valid_label)
+    && tld.len() >= 2
+    && tld.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+impl StringifyTypeOption for EmailTypeOption {
+  fn stringify_text(&self, text: &str) -> String {
+    text.trim().to_string()
+  }
+}
+
+impl From<TypeOptionData> for EmailTypeOption {
+  fn from(data: TypeOptionData) -> Self {
+    from_any(&Any::from(data)).unwrap_or_default()
+  }
+}
+
+impl From<EmailTypeOption> for TypeOptionData {
+  fn from(data: EmailTypeOption) -> Self {
+    TypeOptionDataBuilder::from([("strictness".into(), Any::BigInt(data.strictness as i64))])
+  }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EmailCellData {
+  pub email: String,
+}
+
+impl From<&Cell> for EmailCellData {
+  fn from(cell: &Cell) -> Self {
+    Self {
+      email: cell.get_as::<String>(CELL_DATA).unwrap_or_default(),
+    }
+  }
+}
+
+impl From<&EmailCellData> for Cell {
+  fn from(data: &EmailCellData) -> Self {
+    let mut cell = new_cell_builder(FieldType::Email);
+    cell.insert(CELL_DATA.into(), data.email.clone().into());
+    cell
+  }
+}
+
+/// The type option of a phone field. The cells are validated by [PhoneTypeOption::phone_cell].
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PhoneTypeOption {
+  #[serde(default)]
+  pub strictness: ValidationStrictness,
+}
+
+impl PhoneTypeOption {
+  pub fn new(strictness: ValidationStrictness) -> Self {
+    Self { strictness }
+  }
+
+  /// Return the trimmed phone number, or [DatabaseError::InvalidPhoneNumber] if it's not valid
+  /// for the strictness of the field. The digits can be separated by spaces, dots, dashes and
+  /// parentheses.
+  pub fn validate(&self, phone: &str) -> Result<String, DatabaseError> {
+    let phone = phone.trim();
+    let invalid =
+      |reason: &str| DatabaseError::InvalidPhoneNumber(format!("{}: {}", phone, reason));
+    let digits = phone.strip_prefix('+').unwrap_or(phone);
+    if !digits
+      .chars()
+      .all(|c| c.is_ascii_digit() || " .-()".contains(c))
+    {
+      return Err(invalid("only digits and separators are allowed"));
+    }
+    let num_of_digits = digits.chars().filter(char::is_ascii_digit).count();
+    let min_digits = match self.strictness {
+      ValidationStrictness::Lenient => MIN_LENIENT_PHONE_DIGITS,
+      ValidationStrictness::Strict => MIN_STRICT_PHONE_DIGITS,
+    };
+    if !(min_digits..=MAX_PHONE_DIGITS).contains(&num_of_digits) {
+      return Err(invalid(&format!(
+        "expected {} to {} digits",
+        min_digits, MAX_PHONE_DIGITS
+      )));
+    }
+    if self.strictness == ValidationStrictness::Strict && !phone.starts_with('+') {
+      return Err(invalid("expected an international number starting with +"));
+    }
+    Ok(phone.to_string())
+  }
+
+  /// Return the cell of the phone number, or an error if it's not valid.
+  pub fn phone_cell(&self, phone: &str) -> Result<Cell, DatabaseError> {
+    let phone = self.validate(phone)?;
+    Ok(Cell::from(&PhoneCellData { phone }))
+  }
+}
+
+impl StringifyTypeOption for PhoneTypeOption {
+  fn stringify_text(&self, text: &str) -> String {
+    text.trim().to_string()
+  }
+}
+
+impl From<TypeOptionData> for PhoneTypeOption {
+  fn from(data: TypeOptionData) -> Self {
+    from_any(&Any::from(data)).unwrap_or_default()
+  }
+}
+
+impl From<PhoneTypeOption> for TypeOptionData {
+  fn from(data: PhoneTypeOption) -> Self {
+    TypeOptionDataBuilder::from([("strictness".into(), Any::BigInt(data.strictness as i64))])
+  }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PhoneCellData {
+  pub phone: String,
+}
+
+impl PhoneCellData {
+  /// Return the number with only its digits and the leading `+`, used to dial it.
+  pub fn dial_string(&self) -> String {
+    let digits = self
+      .phone
+      .chars()
+      .filter(char::is_ascii_digit)
+      .collect::<String>();
+    if self.phone.trim_start().starts_with('+') {
+      format!("+{}", digits)
+    } else {
+      digits
+    }
+  }
+}
+
+impl From<&Cell> for PhoneCellData {
+  fn from(cell: &Cell) -> Self {
+    Self {
+      phone: cell.get_as::<String>(CELL_DATA).unwrap_or_default(),
+    }
+  }
+}
+
+impl From<&PhoneCellData> for Cell {
+  fn from(data: &PhoneCellData) -> Self {
+    let mut cell = new_cell_builder(FieldType::Phone);
+    cell.insert(CELL_DATA.into(), data.phone.clone().into());
+    cell
+  }
+}
+
+#[derive(Clone, Debug)]
+enum ContactValidator {
+  Email(EmailTypeOption),
+  Phone(PhoneTypeOption),
+}
+
+impl ContactValidator {
+  fn from_field(field: &Field) -> Option<Self> {
+    let field_type = FieldType::from(field.field_type);
+    let validator = match field_type {
+      FieldType::Email => ContactValidator::Email(
+        field
+          .get_type_option::<EmailTypeOption>(field_type.type_id())
+          .unwrap_or_default(),
+      ),
+      FieldType::Phone => ContactValidator::Phone(
+        field
+          .get_type_option::<PhoneTypeOption>(field_type.type_id())
+          .unwrap_or_default(),
+      ),
+      _ => return None,
+    };
+    Some(validator)
+  }
+}
+
+/// Validates the cells written to the email and phone fields of a database, by
+/// [crate::database::Database::create_row] and [crate::rows::DatabaseRow::update]. The validators
+/// of a database are kept up to date with its fields, see [crate::fields::CellRules].
+#[derive(Clone, Debug, Default)]
+pub struct ContactValidators {
+  validators: DashMap<String, ContactValidator>,
+}
+
+impl ContactValidators {
+  /// Return the validators of the email and phone fields.
+  pub fn from_fields(fields: &[Field]) -> Self {
+    let validators = Self::default();
+    for field in fields {
+      validators.set_field(field);
+    }
+    validators
+  }
+
+  /// Replace the validator of the field, or remove it if the field is not an email or a phone
+  /// field anymore.
+  pub fn set_field(&self, field: &Field) {
+    match ContactValidator::from_field(field) {
+      None => self.remove_field(&field.id),
+      Some(validator) => {
+        self.validators.insert(field.id.clone(), validator);
+      },
+    }
+  }
+
+  pub fn remove_field(&self, field_id: &str) {
+    self.validators.remove(field_id);
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.validators.is_empty()
+  }
+
+  /// Return the cell to write to the field. The value of an email or a phone cell is validated
+  /// and trimmed, an empty value clears it. The cells of the other fields are kept as they are.
+  pub fn validate(&self, field_id: &str, mut cell: Cell) -> Result<Cell, DatabaseError> {
+    let validator = match self.validators.get(field_id) {
+      None => return Ok(cell),
+      Some(validator) => validator.clone(),
+    };
+    let value = cell.get_as::<String>(CELL_DATA).unwrap_or_default();
+    if value.trim().is_empty() {
+      return Ok(cell);
+    }
+    let value = match validator {
+      ContactValidator::Email(type_option) => type_option.validate(&value)?,
+      ContactValidator::Phone(type_option) => type_option.validate(&value)?,
+    };
+    cell.insert(CELL_DATA.into(), value.into());
+    Ok(cell)
+  }
+
+  /// Validate the cells of a new row, see [ContactValidators::validate].
+  pub fn validate_cells(&self, cells: Cells) -> Result<Cells, DatabaseError> {
+    if self.is_empty() {
+      return Ok(cells);
+    }
+    cells
+      .into_iter()
+      .map(|(field_id, cell)| {
+        let cell = self.validate(&field_id, cell)?;
+        Ok((field_id, cell))
+      })
+      .collect()
+  }
+}
@@ -1,6 +1,7 @@
 use crate::entity::FieldType;
 use crate::error::DatabaseError;
 
+pub use crate::fields::time_type_option::TimeTypeOption;
 use crate::fields::{StringifyTypeOption, TypeOptionData, TypeOptionDataBuilder};
 use crate::rows::{new_cell_builder, Cell};
 use crate::template::entity::CELL_DATA;
@@ -17,26 +18,6 @@ pub use strum_macros::EnumIter;
 use tracing::error;
 use yrs::Any;
 
-#[derive(Clone, Debug, Serialize, Deserialize, Default)]
-pub struct TimeTypeOption;
-impl StringifyTypeOption for TimeTypeOption {
-  fn stringify_text(&self, text: &str) -> String {
-    text.to_string()
-  }
-}
-
-impl From<TypeOptionData> for TimeTypeOption {
-  fn from(_data: TypeOptionData) -> Self {
-    Self
-  }
-}
-
-impl From<TimeTypeOption> for TypeOptionData {
-  fn from(_data: TimeTypeOption) -> Self {
-    TypeOptionDataBuilder::new()
-  }
-}
-
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct DateTypeOption {
   pub date_format: DateFormat,
@@ -0,0 +1,436 @@
+use std::collections::HashSet;
+
+use crate::entity::FieldType;
+use crate::fields::checkbox_type_option::parse_checkbox;
+use crate::fields::{
+  format_number, parse_number, StringifyTypeOption, TypeOptionData, TypeOptionDataBuilder,
+};
+use crate::rows::{new_cell_builder, Cell, Cells};
+use crate::template::entity::CELL_DATA;
+use collab::preclude::Any;
+use collab::util::AnyMapExt;
+use serde::{Deserialize, Serialize};
+use yrs::encoding::serde::from_any;
+
+/// The key of the cell that holds the reason why the formula couldn't be computed.
+pub const FORMULA_ERROR: &str = "error";
+
+/// The maximum nesting of a formula: parentheses, function calls, signs and chained operators.
+/// The expression is synced with the other clients, so a deeper formula is rejected instead of
+/// overflowing the stack of every client that reads it.
+pub const MAX_FORMULA_DEPTH: usize = 128;
+
+/// The type option of a field whose cells are computed from the other cells of the row.
+///
+/// The expression references the other fields by id inside braces, for example
+/// `{price_field_id} * {quantity_field_id}`. It supports numbers, `+`, `-`, `*`, `/`,
+/// parentheses and the functions `abs(x)`, `round(x)`, `round(x, digits)`, `min(..)` and
+/// `max(..)`. An empty cell is read as 0 and a checkbox cell as 1 or 0.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct FormulaTypeOption {
+  #[serde(default)]
+  pub expression: String,
+}
+
+impl FormulaTypeOption {
+  pub fn new(expression: &str) -> Self {
+    Self {
+      expression: expression.to_string(),
+    }
+  }
+
+  pub fn parse(&self) -> Result<FormulaExpr, FormulaError> {
+    FormulaParser::new(&self.expression).parse()
+  }
+
+  /// Compute the value of the formula with the cells of a row.
+  pub fn evaluate(&self, cells: &Cells) -> Result<f64, FormulaError> {
+    self.parse()?.evaluate(cells)
+  }
+
+  /// Compute the cell of the formula with the cells of a row. When the formula can't be computed,
+  /// the cell is empty and [FORMULA_ERROR] holds the reason.
+  pub fn compute_cell(&self, cells: &Cells) -> Cell {
+    let mut cell = new_cell_builder(FieldType::Formula);
+    match self.evaluate(cells) {
+      Ok(value) => {
+        cell.insert(CELL_DATA.into(), format_number(value).into());
+      },
+      Err(err) => {
+        cell.insert(CELL_DATA.into(), "".into());
+        cell.insert(FORMULA_ERROR.into(), err.to_string().into());
+      },
+    }
+    cell
+  }
+}
+
+impl StringifyTypeOption for FormulaTypeOption {
+  fn stringify_text(&self, text: &str) -> String {
+    text.to_string()
+  }
+}
+
+impl From<TypeOptionData> for FormulaTypeOption {
+  fn from(data: TypeOptionData) -> Self {
+    from_any(&Any::from(data)).unwrap_or_default()
+  }
+}
+
+impl From<FormulaTypeOption> for TypeOptionData {
+  fn from(data: FormulaTypeOption) -> Self {
+    TypeOptionDataBuilder::from([("expression".into(), data.expression.into())])
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum FormulaError {
+  #[error("Invalid formula at {position}: {reason}")]
+  Parse { position: usize, reason: String },
+
+  #[error("Unknown function: {0}")]
+  UnknownFunction(String),
+
+  #[error("Wrong number of arguments for {0}")]
+  WrongArguments(String),
+
+  #[error("The cell of {0} is not a number")]
+  NotANumber(String),
+
+  #[error("Division by zero")]
+  DivisionByZero,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormulaOperator {
+  Add,
+  Subtract,
+  Multiply,
+  Divide,
+}
+
+/// A parsed formula.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormulaExpr {
+  Number(f64),
+  Field(String),
+  Negate(Box<FormulaExpr>),
+  Binary {
+    operator: FormulaOperator,
+    left: Box<FormulaExpr>,
+    right: Box<FormulaExpr>,
+  },
+  Function {
+    name: String,
+    args: Vec<FormulaExpr>,
+  },
+}
+
+impl FormulaExpr {
+  /// Return the ids of the fields used by the formula. The formula must be computed again when
+  /// one of these cells changes.
+  pub fn referenced_fields(&self) -> HashSet<String> {
+    let mut field_ids = HashSet::new();
+    self.collect_fields(&mut field_ids);
+    field_ids
+  }
+
+  pub fn evaluate(&self, cells: &Cells) -> Result<f64, FormulaError> {
+    let value = match self {
+      FormulaExpr::Number(value) => *value,
+      FormulaExpr::Field(field_id) => field_value(cells, field_id)?,
+      FormulaExpr::Negate(expr) => -expr.evaluate(cells)?,
+      FormulaExpr::Binary {
+        operator,
+        left,
+        right,
+      } => {
+        let left = left.evaluate(cells)?;
+        let right = right.evaluate(cells)?;
+        match operator {
+          FormulaOperator::Add => left + right,
+          FormulaOperator::Subtract => left - right,
+          FormulaOperator::Multiply => left * right,
+          FormulaOperator::Divide => {
+            if right == 0.0 {
+              return Err(FormulaError::DivisionByZero);
+            }
+            left / right
+          },
+        }
+      },
+      FormulaExpr::Function { name, args } => {
+        let args = args
+          .iter()
+          .map(|arg| arg.evaluate(cells))
+          .collect::<Result<Vec<_>, _>>()?;
+        call_function(name, &args)?
+      },
+    };
+    Ok(value)
+  }
+
+  fn collect_fields(&self, field_ids: &mut HashSet<String>) {
+    match self {
+      FormulaExpr::Number(_) => {},
+      FormulaExpr::Field(field_id) => {
+        field_ids.insert(field_id.clone());
+      },
+      FormulaExpr::Negate(expr) => expr.collect_fields(field_ids),
+      FormulaExpr::Binary { left, right, .. } => {
+        left.collect_fields(field_ids);
+        right.collect_fields(field_ids);
+      },
+      FormulaExpr::Function { args, .. } => {
+        args.iter().for_each(|arg| arg.collect_fields(field_ids));
+      },
+    }
+  }
+}
+
+fn field_value(cells: &Cells, field_id: &str) -> Result<f64, FormulaError> {
+  let text = cells
+    .get(field_id)
+    .and_then(|cell| cell.get_as::<String>(CELL_DATA))
+    .unwrap_or_default();
+  match parse_checkbox(&text) {
+    Some(checked) => Ok(if checked { 1.0 } else { 0.0 }),
+    None => parse_number(&text).ok_or_else(|| FormulaError::NotANumber(field_id.to_string())),
+  }
+}
+
+fn call_function(name: &str, args: &[f64]) -> Result<f64, FormulaError> {
+  let arity_error = || FormulaError::WrongArguments(name.to_string());
+  match name {
+    "abs" => match args {
+      [value] => Ok(value.abs()),
+      _ => Err(arity_error()),
+    },
+    "round" => match args {
+      [value] => Ok(value.round()),
+      [value, digits] => {
+        let factor = 10f64.powi(*digits as i32);
+        Ok((value * factor).round() / factor)
+      },
+      _ => Err(arity_error()),
+    },
+    "min" | "max" if args.is_empty() => Err(arity_error()),
+    "min" => Ok(args.iter().cloned().fold(f64::INFINITY, f64::min)),
+    "max" => Ok(args.iter().cloned().fold(f64::NEG_INFINITY, f64::max)),
+    _ => Err(FormulaError::UnknownFunction(name.to_string())),
+  }
+}
+
+struct FormulaParser {
+  chars: Vec<char>,
+  position: usize,
+  /// The current nesting, bounded by [MAX_FORMULA_DEPTH].
+  depth: usize,
+}
+
+impl FormulaParser {
+  fn new(expression: &str) -> Self {
+    Self {
+      chars: expression.chars().collect(),
+      position: 0,
+      depth: 0,
+    }
+  }
+
+  fn parse(mut self) -> Result<FormulaExpr, FormulaError> {
+    let expr = self.parse_sum()?;
+    self.skip_whitespace();
+    if self.position < self.chars.len() {
+      return Err(self.error("unexpected character"));
+    }
+    Ok(expr)
+  }
+
+  fn parse_sum(&mut self) -> Result<FormulaExpr, FormulaError> {
+    // Each operator nests the previous operations one level deeper.
+    let depth = self.depth;
+    let mut left = self.parse_product()?;
+    loop {
+      let operator = match self.peek() {
+        Some('+') => FormulaOperator::Add,
+        Some('-') => FormulaOperator::Subtract,
+        _ => {
+          self.depth = depth;
+          return Ok(left);
+        },
+      };
+      self.position += 1;
+      self.enter()?;
+      let right = self.parse_product()?;
+      left = FormulaExpr::Binary {
+        operator,
+        left: Box::new(left),
+        right: Box::new(right),
+      };
+    }
+  }
+
+  fn parse_product(&mut self) -> Result<FormulaExpr, FormulaError> {
+    // Each operator nests the previous operations one level deeper.
+    let depth = self.depth;
+    let mut left = self.parse_unary()?;
+    loop {
+      let operator = match self.peek() {
+        Some('*') => FormulaOperator::Multiply,
+        Some('/') => FormulaOperator::Divide,
+        _ => {
+          self.depth = depth;
+          return Ok(left);
+        },
+      };
+      self.position += 1;
+      self.enter()?;
+      let right = self.parse_unary()?;
+      left = FormulaExpr::Binary {
+        operator,
+        left: Box::new(left),
+        right: Box::new(right),
+      };
+    }
+  }
+
+  fn parse_unary(&mut self) -> Result<FormulaExpr, FormulaError> {
+    match self.peek() {
+      Some('-') => {
+        self.position += 1;
+        self.enter()?;
+        let expr = self.parse_unary()?;
+        self.leave();
+        Ok(FormulaExpr::Negate(Box::new(expr)))
+      },
+      Some('+') => {
+        self.position += 1;
+        self.enter()?;
+        let expr = self.parse_unary()?;
+        self.leave();
+        Ok(expr)
+      },
+      _ => self.parse_primary(),
+    }
+  }
+
+  fn parse_primary(&mut self) -> Result<FormulaExpr, FormulaError> {
+    match self.peek() {
+      Some('(') => {
+        self.position += 1;
+        self.enter()?;
+        let expr = self.parse_sum()?;
+        self.leave();
+        self.expect(')')?;
+        Ok(expr)
+      },
+      Some('{') => {
+        self.position += 1;
+        let start = self.position;
+        while self.position < self.chars.len() && self.chars[self.position] != '}' {
+          self.position += 1;
+        }
+        let field_id: String = self.chars[start..self.position].iter().collect();
+        self.expect('}')?;
+        let field_id = field_id.trim();
+        if field_id.is_empty() {
+          return Err(self.error("empty field reference"));
+        }
+        Ok(FormulaExpr::Field(field_id.to_string()))
+      },
+      Some(c) if c.is_ascii_digit() || c == '.' => self.parse_number(),
+      Some(c) if c.is_ascii_alphabetic() => self.parse_function(),
+      Some(_) => Err(self.error("unexpected character")),
+      None => Err(self.error("unexpected end of formula")),
+    }
+  }
+
+  fn parse_number(&mut self) -> Result<FormulaExpr, FormulaError> {
+    let start = self.position;
+    while self.position < self.chars.len()
+      && (self.chars[self.position].is_ascii_digit() || self.chars[self.position] == '.')
+    {
+      self.position += 1;
+    }
+    let text: String = self.chars[start..self.position].iter().collect();
+    text
+      .parse::<f64>()
+      .map(FormulaExpr::Number)
+      .map_err(|_| FormulaError::Parse {
+        position: start,
+        reason: format!("invalid number {}", text),
+      })
+  }
+
+  fn parse_function(&mut self) -> Result<FormulaExpr, FormulaError> {
+    let start = self.position;
+    while self.position < self.chars.len() && self.chars[self.position].is_ascii_alphanumeric() {
+      self.position += 1;
+    }
+    let name: String = self.chars[start..self.position].iter().collect();
+    self.expect('(')?;
+    let mut args = vec![];
+    if self.peek() == Some(')') {
+      self.position += 1;
+    } else {
+      self.enter()?;
+      loop {
+        args.push(self.parse_sum()?);
+        match self.peek() {
+          Some(',') => self.position += 1,
+          Some(')') => {
+            self.position += 1;
+            break;
+          },
+          _ => return Err(self.error("expected , or )")),
+        }
+      }
+      self.leave();
+    }
+    Ok(FormulaExpr::Function {
+      name: name.to_lowercase(),
+      args,
+    })
+  }
+
+  /// Go one level deeper, fails if the formula is nested deeper than [MAX_FORMULA_DEPTH].
+  fn enter(&mut self) -> Result<(), FormulaError> {
+    self.depth += 1;
+    if self.depth > MAX_FORMULA_DEPTH {
+      return Err(self.error("the formula is nested too deeply"));
+    }
+    Ok(())
+  }
+
+  fn leave(&mut self) {
+    self.depth -= 1;
+  }
+
+  fn expect(&mut self, expected: char) -> Result<(), FormulaError> {
+    if self.peek() == Some(expected) {
+      self.position += 1;
+      Ok(())
+    } else {
+      Err(self.error(&format!("expected {}", expected)))
+    }
+  }
+
+  /// Return the next character that is not a whitespace.
+  fn peek(&mut self) -> Option<char> {
+    self.skip_whitespace();
+    self.chars.get(self.position).cloned()
+  }
+
+  fn skip_whitespace(&mut self) {
+    while self.position < self.chars.len() && self.chars[self.position].is_whitespace() {
+      self.position += 1;
+    }
+  }
+
+  fn error(&self, reason: &str) -> FormulaError {
+    FormulaError::Parse {
+      position: self.position,
+      reason: reason.to_string(),
+    }
+  }
+}
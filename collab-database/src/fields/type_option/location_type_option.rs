@@ -0,0 +1,275 @@
+use crate::entity::FieldType;
+use crate::fields::{StringifyTypeOption, TypeOptionData, TypeOptionDataBuilder};
+use crate::rows::{new_cell_builder, Cell};
+use crate::template::entity::CELL_DATA;
+use crate::views::FilterMap;
+use collab::preclude::Any;
+use collab::util::AnyMapExt;
+use serde::{Deserialize, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
+use yrs::encoding::serde::from_any;
+
+/// The mean radius of the Earth, used to compute the distances between two locations.
+const EARTH_RADIUS_KM: f64 = 6371.0088;
+const KM_PER_MILE: f64 = 1.609344;
+
+const LOCATION_LATITUDE: &str = "latitude";
+const LOCATION_LONGITUDE: &str = "longitude";
+const LOCATION_ADDRESS: &str = "address";
+const LOCATION_FILTER_CONDITION: &str = "condition";
+const LOCATION_FILTER_CONTENT: &str = "content";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum DistanceUnit {
+  #[default]
+  Kilometers = 0,
+  Miles = 1,
+}
+
+impl DistanceUnit {
+  pub fn from_km(&self, km: f64) -> f64 {
+    match self {
+      DistanceUnit::Kilometers => km,
+      DistanceUnit::Miles => km / KM_PER_MILE,
+    }
+  }
+
+  pub fn symbol(&self) -> &'static str {
+    match self {
+      DistanceUnit::Kilometers => "km",
+      DistanceUnit::Miles => "mi",
+    }
+  }
+}
+
+/// The type option of a location field. The cells hold a latitude and a longitude, with an
+/// optional address, see [LocationCellData].
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LocationTypeOption {
+  /// The unit of the distances of the filters.
+  #[serde(default)]
+  pub distance_unit: DistanceUnit,
+}
+
+impl LocationTypeOption {
+  pub fn new(distance_unit: DistanceUnit) -> Self {
+    Self { distance_unit }
+  }
+
+  /// Return the cell of the location, None if the coordinates are out of range.
+  pub fn location_cell(
+    &self,
+    latitude: f64,
+    longitude: f64,
+    address: Option<String>,
+  ) -> Option<Cell> {
+    let location = LocationCellData::new(latitude, longitude)?;
+    Some(Cell::from(&location.with_address(address)))
+  }
+
+  /// Return the location of the cell, None if the cell is empty.
+  pub fn location_of(&self, cell: Option<&Cell>) -> Option<LocationCellData> {
+    cell.and_then(LocationCellData::from_cell)
+  }
+
+  /// Return the distance between the two locations in the unit of the field.
+  pub fn distance_between(&self, from: &LocationCellData, to: &LocationCellData) -> f64 {
+    self.distance_unit.from_km(from.distance_km(to))
+  }
+
+  /// Return true if the location of the cell matches the filter.
+  pub fn matches_filter(&self, filter: &LocationFilter, cell: Option<&Cell>) -> bool {
+    let location = self.location_of(cell);
+    match (filter.condition, location, &filter.center) {
+      (LocationFilterCondition::IsEmpty, location, _) => location.is_none(),
+      (LocationFilterCondition::IsNotEmpty, location, _) => location.is_some(),
+      (_, None, _) | (_, _, None) => false,
+      (LocationFilterCondition::WithinDistance, Some(location), Some(center)) => {
+        self.distance_between(center, &location) <= filter.distance
+      },
+      (LocationFilterCondition::FartherThan, Some(location), Some(center)) => {
+        self.distance_between(center, &location) > filter.distance
+      },
+    }
+  }
+}
+
+impl StringifyTypeOption for LocationTypeOption {
+  /// The address of the location if it has one, otherwise its coordinates.
+  fn stringify_cell(&self, cell: &Cell) -> String {
+    match LocationCellData::from_cell(cell) {
+      None => "".to_string(),
+      Some(location) => match location.address {
+        Some(address) if !address.is_empty() => address,
+        _ => location.coordinates(),
+      },
+    }
+  }
+
+  fn stringify_text(&self, text: &str) -> String {
+    match LocationCellData::parse(text) {
+      None => "".to_string(),
+      Some(location) => location.coordinates(),
+    }
+  }
+}
+
+impl From<TypeOptionData> for LocationTypeOption {
+  fn from(data: TypeOptionData) -> Self {
+    from_any(&Any::from(data)).unwrap_or_default()
+  }
+}
+
+impl From<LocationTypeOption> for TypeOptionData {
+  fn from(data: LocationTypeOption) -> Self {
+    TypeOptionDataBuilder::from([(
+      "distance_unit".into(),
+      Any::BigInt(data.distance_unit as i64),
+    )])
+  }
+}
+
+/// The data of a location cell. The coordinates are also stored as the `lat,lng` text of the
+/// cell, so the other field types can read it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocationCellData {
+  pub latitude: f64,
+  pub longitude: f64,
+  pub address: Option<String>,
+}
+
+impl LocationCellData {
+  /// Return None if the latitude isn't between -90 and 90 or the longitude between -180 and 180.
+  pub fn new(latitude: f64, longitude: f64) -> Option<Self> {
+    let is_valid = (-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude);
+    is_valid.then_some(Self {
+      latitude,
+      longitude,
+      address: None,
+    })
+  }
+
+  pub fn with_address(mut self, address: Option<String>) -> Self {
+    self.address = address.filter(|address| !address.trim().is_empty());
+    self
+  }
+
+  /// Parse coordinates like `48.8584, 2.2945`.
+  pub fn parse(text: &str) -> Option<Self> {
+    let (latitude, longitude) = text.split_once(',')?;
+    let latitude = latitude.trim().parse::<f64>().ok()?;
+    let longitude = longitude.trim().parse::<f64>().ok()?;
+    Self::new(latitude, longitude)
+  }
+
+  pub fn from_cell(cell: &Cell) -> Option<Self> {
+    let number = |key: &str| match cell.get(key) {
+      Some(Any::Number(value)) => Some(*value),
+      Some(Any::BigInt(value)) => Some(*value as f64),
+      _ => None,
+    };
+    let location = match (number(LOCATION_LATITUDE), number(LOCATION_LONGITUDE)) {
+      (Some(latitude), Some(longitude)) => Self::new(latitude, longitude),
+      _ => cell
+        .get_as::<String>(CELL_DATA)
+        .and_then(|text| Self::parse(&text)),
+    }?;
+    Some(location.with_address(cell.get_as::<String>(LOCATION_ADDRESS)))
+  }
+
+  pub fn coordinates(&self) -> String {
+    format!("{}, {}", self.latitude, self.longitude)
+  }
+
+  /// The great-circle distance to the other location, in kilometers.
+  pub fn distance_km(&self, other: &LocationCellData) -> f64 {
+    let (lat1, lat2) = (self.latitude.to_radians(), other.latitude.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lng = (other.longitude - self.longitude).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lng / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().min(1.0).asin()
+  }
+}
+
+impl From<&LocationCellData> for Cell {
+  fn from(data: &LocationCellData) -> Self {
+    let mut cell = new_cell_builder(FieldType::Location);
+    cell.insert(
+      CELL_DATA.into(),
+      format!("{},{}", data.latitude, data.longitude).into(),
+    );
+    cell.insert(LOCATION_LATITUDE.into(), Any::Number(data.latitude));
+    cell.insert(LOCATION_LONGITUDE.into(), Any::Number(data.longitude));
+    if let Some(address) = &data.address {
+      cell.insert(LOCATION_ADDRESS.into(), address.clone().into());
+    }
+    cell
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum LocationFilterCondition {
+  #[default]
+  WithinDistance = 0,
+  FartherThan = 1,
+  IsEmpty = 2,
+  IsNotEmpty = 3,
+}
+
+impl From<i64> for LocationFilterCondition {
+  fn from(value: i64) -> Self {
+    match value {
+      1 => LocationFilterCondition::FartherThan,
+      2 => LocationFilterCondition::IsEmpty,
+      3 => LocationFilterCondition::IsNotEmpty,
+      _ => LocationFilterCondition::WithinDistance,
+    }
+  }
+}
+
+/// A condition on the distance between the location of a cell and a center, in the unit of the
+/// field, see [LocationTypeOption::matches_filter]. It's read from the `condition` of a
+/// [FilterMap] and the `lat,lng,distance` of its `content`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LocationFilter {
+  pub condition: LocationFilterCondition,
+  pub center: Option<LocationCellData>,
+  pub distance: f64,
+}
+
+impl LocationFilter {
+  pub fn new(condition: LocationFilterCondition, center: LocationCellData, distance: f64) -> Self {
+    Self {
+      condition,
+      center: Some(center),
+      distance,
+    }
+  }
+}
+
+impl From<&FilterMap> for LocationFilter {
+  fn from(filter: &FilterMap) -> Self {
+    let condition = match filter.get(LOCATION_FILTER_CONDITION) {
+      Some(Any::BigInt(condition)) => LocationFilterCondition::from(*condition),
+      Some(Any::Number(condition)) => LocationFilterCondition::from(*condition as i64),
+      _ => LocationFilterCondition::default(),
+    };
+    let content = filter
+      .get_as::<String>(LOCATION_FILTER_CONTENT)
+      .unwrap_or_default();
+    let (center, distance) = match content.rsplit_once(',') {
+      Some((center, distance)) => (
+        LocationCellData::parse(center),
+        distance.trim().parse::<f64>().unwrap_or_default(),
+      ),
+      None => (None, 0.0),
+    };
+    Self {
+      condition,
+      center,
+      distance,
+    }
+  }
+}
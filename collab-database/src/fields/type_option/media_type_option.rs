@@ -5,11 +5,11 @@ use crate::rows::{new_cell_builder, Cell};
 use crate::template::entity::CELL_DATA;
 use collab::util::AnyMapExt;
 use serde::{Deserialize, Deserializer, Serialize};
-use serde_repr::Serialize_repr;
+use serde_repr::{Deserialize_repr, Serialize_repr};
 use std::fmt::{Display, Formatter};
 use std::path::Path;
 use std::sync::Arc;
-use yrs::Any;
+use yrs::{Any, Array, ArrayPrelim, ArrayRef, Map, MapRef, Out, ReadTxn, TransactionMut};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MediaTypeOption {
@@ -59,11 +59,65 @@ impl From<MediaTypeOption> for TypeOptionData {
   }
 }
 
-#[derive(Clone, Debug, Default, Serialize)]
+/// The files of a media cell. The order of the files is the order in which they are shown.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
 pub struct MediaCellData {
   pub files: Vec<MediaFile>,
 }
 
+impl MediaCellData {
+  pub fn get_file(&self, file_id: &str) -> Option<&MediaFile> {
+    self.files.iter().find(|file| file.id == file_id)
+  }
+
+  /// Append the file at the end of the list. A file with the same id is replaced in place.
+  pub fn add_file(&mut self, file: MediaFile) {
+    match self.files.iter_mut().find(|f| f.id == file.id) {
+      Some(existing) => *existing = file,
+      None => self.files.push(file),
+    }
+  }
+
+  /// Insert the file at the index, or at the end if the index is out of bounds.
+  pub fn insert_file(&mut self, index: usize, file: MediaFile) {
+    self.files.retain(|f| f.id != file.id);
+    let index = index.min(self.files.len());
+    self.files.insert(index, file);
+  }
+
+  /// Update the file with the given id. Return false if there is no such file.
+  pub fn update_file<F>(&mut self, file_id: &str, f: F) -> bool
+  where
+    F: FnOnce(&mut MediaFile),
+  {
+    match self.files.iter_mut().find(|file| file.id == file_id) {
+      None => false,
+      Some(file) => {
+        f(file);
+        true
+      },
+    }
+  }
+
+  pub fn remove_file(&mut self, file_id: &str) -> Option<MediaFile> {
+    let index = self.files.iter().position(|file| file.id == file_id)?;
+    Some(self.files.remove(index))
+  }
+
+  /// Move the file to the index. The index is the position of the file after the move and is
+  /// clamped to the last position. Return false if there is no such file.
+  pub fn move_file(&mut self, file_id: &str, to_index: usize) -> bool {
+    match self.remove_file(file_id) {
+      None => false,
+      Some(file) => {
+        let to_index = to_index.min(self.files.len());
+        self.files.insert(to_index, file);
+        true
+      },
+    }
+  }
+}
+
 impl From<MediaCellData> for Any {
   fn from(data: MediaCellData) -> Self {
     Any::Array(Arc::from(
@@ -71,7 +125,7 @@ impl From<MediaCellData> for Any {
         .files
         .clone()
         .into_iter()
-        .map(|file| Any::String(Arc::from(serde_json::to_string(&file).unwrap_or_default())))
+        .map(|file| media_file_entry(&file))
         .collect::<Vec<_>>(),
     ))
   }
@@ -99,6 +153,55 @@ impl TryFrom<Any> for MediaCellData {
     }
   }
 }
+/// Write the files into the [CELL_DATA] array of the media cell one entry at a time. Only the
+/// files that were removed, changed or moved are written, so the other files keep the changes
+/// made concurrently by the other clients.
+pub(crate) fn apply_media_files(txn: &mut TransactionMut, cell_map: &MapRef, files: &[MediaFile]) {
+  let array_ref = match cell_map.get(txn, CELL_DATA) {
+    Some(Out::YArray(array_ref)) => array_ref,
+    // The files written as a whole by the older versions are written again entry by entry.
+    _ => cell_map.insert(txn, CELL_DATA, ArrayPrelim::default()),
+  };
+  for index in (0..array_ref.len(txn)).rev() {
+    let removed = media_file_at(txn, &array_ref, index)
+      .map_or(true, |file| files.iter().all(|f| f.id != file.id));
+    if removed {
+      array_ref.remove(txn, index);
+    }
+  }
+  for (index, file) in files.iter().enumerate() {
+    let index = index as u32;
+    let current = (index..array_ref.len(txn))
+      .find(|i| media_file_at(txn, &array_ref, *i).is_some_and(|current| current.id == file.id));
+    match current {
+      Some(current) if current == index => {
+        if media_file_at(txn, &array_ref, index).as_ref() == Some(file) {
+          continue;
+        }
+        array_ref.remove(txn, index);
+      },
+      Some(current) => array_ref.remove(txn, current),
+      None => {},
+    }
+    array_ref.insert(txn, index, media_file_entry(file));
+  }
+  let len = array_ref.len(txn);
+  if len > files.len() as u32 {
+    array_ref.remove_range(txn, files.len() as u32, len - files.len() as u32);
+  }
+}
+
+fn media_file_at<T: ReadTxn>(txn: &T, array_ref: &ArrayRef, index: u32) -> Option<MediaFile> {
+  match array_ref.get(txn, index)? {
+    Out::Any(Any::String(file)) => serde_json::from_str(&file).ok(),
+    _ => None,
+  }
+}
+
+fn media_file_entry(file: &MediaFile) -> Any {
+  Any::String(Arc::from(serde_json::to_string(file).unwrap_or_default()))
+}
+
 impl From<&Cell> for MediaCellData {
   fn from(cell: &Cell) -> Self {
     cell.get_as::<MediaCellData>(CELL_DATA).unwrap_or_default()
@@ -146,6 +249,19 @@ pub struct MediaFile {
   pub url: String,
   pub upload_type: MediaUploadType,
   pub file_type: MediaFileType,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub mime: Option<String>,
+  /// The size of the file in bytes.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub size: Option<u64>,
+  /// The size of an image or a video in pixels.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub dimensions: Option<MediaDimensions>,
+  #[serde(default)]
+  pub upload_state: MediaUploadState,
+  /// The time, in seconds, when the upload of the file finished.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub uploaded_at: Option<i64>,
 }
 
 impl MediaFile {
@@ -161,16 +277,43 @@ impl MediaFile {
       url,
       upload_type,
       file_type,
+      mime: None,
+      size: None,
+      dimensions: None,
+      upload_state: MediaUploadState::default(),
+      uploaded_at: None,
     }
   }
 
+  pub fn with_mime(mut self, mime: &str) -> Self {
+    self.mime = Some(mime.to_string());
+    self
+  }
+
+  pub fn with_size(mut self, size: u64) -> Self {
+    self.size = Some(size);
+    self
+  }
+
+  pub fn with_dimensions(mut self, width: u32, height: u32) -> Self {
+    self.dimensions = Some(MediaDimensions { width, height });
+    self
+  }
+
+  pub fn with_upload_state(mut self, upload_state: MediaUploadState) -> Self {
+    self.upload_state = upload_state;
+    self
+  }
+
+  pub fn with_uploaded_at(mut self, uploaded_at: i64) -> Self {
+    self.uploaded_at = Some(uploaded_at);
+    self
+  }
+
   pub fn rename(&self, new_name: String) -> Self {
     Self {
-      id: self.id.clone(),
       name: new_name,
-      url: self.url.clone(),
-      upload_type: self.upload_type.clone(),
-      file_type: self.file_type.clone(),
+      ..self.clone()
     }
   }
 }
@@ -185,6 +328,24 @@ impl Display for MediaFile {
   }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MediaDimensions {
+  pub width: u32,
+  pub height: u32,
+}
+
+/// The state of the upload of a file. The files created before the state was stored are
+/// considered uploaded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum MediaUploadState {
+  #[default]
+  Uploaded = 0,
+  Pending = 1,
+  Uploading = 2,
+  Failed = 3,
+}
+
 #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize_repr)]
 #[repr(u8)]
 pub enum MediaFileType {
@@ -352,6 +513,11 @@ mod tests {
       url: "http://example.com/file".to_string(),
       upload_type: MediaUploadType::Cloud,
       file_type: MediaFileType::Image,
+      mime: None,
+      size: None,
+      dimensions: None,
+      upload_state: MediaUploadState::Uploaded,
+      uploaded_at: Some(1_700_000_000),
     };
 
     // Serialize the MediaFile to a JSON string
@@ -360,4 +526,86 @@ mod tests {
     let deserialized: MediaFile = serde_json::from_str(&serialized).unwrap();
     assert_eq!(media_file, deserialized);
   }
+
+  #[test]
+  fn deserialize_media_file_without_metadata() {
+    let json =
+      r#"{"id":"1","name":"a.png","url":"http://example.com/a.png","upload_type":2,"file_type":1}"#;
+    let file: MediaFile = serde_json::from_str(json).unwrap();
+    assert_eq!(file.mime, None);
+    assert_eq!(file.size, None);
+    assert_eq!(file.dimensions, None);
+    assert_eq!(file.upload_state, MediaUploadState::Uploaded);
+    assert_eq!(file.uploaded_at, None);
+  }
+
+  #[test]
+  fn media_file_metadata_roundtrip_through_cell() {
+    let file = MediaFile::new(
+      "a.png".to_string(),
+      "http://example.com/a.png".to_string(),
+      MediaUploadType::Cloud,
+      MediaFileType::Image,
+    )
+    .with_mime("image/png")
+    .with_size(1024)
+    .with_dimensions(640, 480)
+    .with_upload_state(MediaUploadState::Uploading);
+    let data = MediaCellData {
+      files: vec![file.clone()],
+    };
+    let cell = Cell::from(data);
+    let data = MediaCellData::from(&cell);
+    assert_eq!(data.files, vec![file.clone()]);
+    assert_eq!(data.files[0].rename("b.png".to_string()).size, Some(1024));
+  }
+
+  fn file(id: &str) -> MediaFile {
+    MediaFile {
+      id: id.to_string(),
+      name: format!("{}.png", id),
+      ..Default::default()
+    }
+  }
+
+  fn file_ids(data: &MediaCellData) -> Vec<&str> {
+    data.files.iter().map(|file| file.id.as_str()).collect()
+  }
+
+  #[test]
+  fn move_and_remove_media_files() {
+    let mut data = MediaCellData::default();
+    data.add_file(file("a"));
+    data.add_file(file("b"));
+    data.add_file(file("c"));
+    assert_eq!(file_ids(&data), vec!["a", "b", "c"]);
+
+    assert!(data.move_file("a", 2));
+    assert_eq!(file_ids(&data), vec!["b", "c", "a"]);
+    assert!(data.move_file("a", 0));
+    assert_eq!(file_ids(&data), vec!["a", "b", "c"]);
+    assert!(data.move_file("b", 100));
+    assert_eq!(file_ids(&data), vec!["a", "c", "b"]);
+    assert!(!data.move_file("d", 0));
+
+    data.insert_file(1, file("d"));
+    assert_eq!(file_ids(&data), vec!["a", "d", "c", "b"]);
+
+    assert_eq!(
+      data.remove_file("c").map(|file| file.id),
+      Some("c".to_string())
+    );
+    assert!(data.remove_file("c").is_none());
+    assert_eq!(file_ids(&data), vec!["a", "d", "b"]);
+
+    assert!(data.update_file("d", |file| file.upload_state = MediaUploadState::Failed));
+    assert_eq!(
+      data.get_file("d").unwrap().upload_state,
+      MediaUploadState::Failed
+    );
+
+    data.add_file(file("a").with_size(1));
+    assert_eq!(file_ids(&data), vec!["a", "d", "b"]);
+    assert_eq!(data.get_file("a").unwrap().size, Some(1));
+  }
 }
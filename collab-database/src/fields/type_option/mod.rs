@@ -1,9 +1,17 @@
 pub mod checkbox_type_option;
+pub mod checklist_type_option;
+pub mod contact_type_option;
 pub mod date_type_option;
+pub mod formula_type_option;
+pub mod location_type_option;
 pub mod media_type_option;
 pub mod number_type_option;
+pub mod progress_type_option;
+pub mod rating_type_option;
+pub mod relation_type_option;
 pub mod select_type_option;
 pub mod text_type_option;
+pub mod time_type_option;
 pub mod timestamp_type_option;
 pub mod url_type_option;
 
@@ -11,9 +19,15 @@ use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
 
 use crate::entity::FieldType;
+use crate::fields::contact_type_option::{EmailTypeOption, PhoneTypeOption};
 use crate::fields::date_type_option::{DateTypeOption, TimeTypeOption};
+use crate::fields::formula_type_option::FormulaTypeOption;
+use crate::fields::location_type_option::LocationTypeOption;
 use crate::fields::media_type_option::MediaTypeOption;
 use crate::fields::number_type_option::NumberTypeOption;
+use crate::fields::progress_type_option::ProgressTypeOption;
+use crate::fields::rating_type_option::RatingTypeOption;
+use crate::fields::relation_type_option::RollupTypeOption;
 use crate::fields::select_type_option::{MultiSelectTypeOption, SingleSelectTypeOption};
 use crate::fields::type_option::checkbox_type_option::CheckboxTypeOption;
 use crate::fields::type_option::text_type_option::RichTextTypeOption;
@@ -134,12 +148,20 @@ pub fn stringify_type_option(
     FieldType::URL => Some(Box::new(URLTypeOption::from(type_option_data))),
     FieldType::Time => Some(Box::new(TimeTypeOption::from(type_option_data))),
     FieldType::Media => Some(Box::new(MediaTypeOption::from(type_option_data))),
+    FieldType::Formula => Some(Box::new(FormulaTypeOption::from(type_option_data))),
+    FieldType::Rollup => Some(Box::new(RollupTypeOption::from(type_option_data))),
+    FieldType::Rating => Some(Box::new(RatingTypeOption::from(type_option_data))),
+    FieldType::Progress => Some(Box::new(ProgressTypeOption::from(type_option_data))),
+    FieldType::Location => Some(Box::new(LocationTypeOption::from(type_option_data))),
+    FieldType::Email => Some(Box::new(EmailTypeOption::from(type_option_data))),
+    FieldType::Phone => Some(Box::new(PhoneTypeOption::from(type_option_data))),
 
     FieldType::Checklist
     | FieldType::LastEditedTime
     | FieldType::CreatedTime
     | FieldType::Relation
     | FieldType::Summary
-    | FieldType::Translate => None,
+    | FieldType::Translate
+    | FieldType::Lookup => None,
   }
 }
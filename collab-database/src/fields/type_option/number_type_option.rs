@@ -3,14 +3,18 @@
 use crate::error::DatabaseError;
 use crate::fields::number_type_option::number_currency::Currency;
 use crate::fields::{StringifyTypeOption, TypeOptionData, TypeOptionDataBuilder};
+use crate::rows::Cell;
+use crate::template::entity::CELL_DATA;
 
 use collab::preclude::Any;
+use collab::util::AnyMapExt;
 
 use fancy_regex::Regex;
 use lazy_static::lazy_static;
 use rust_decimal::Decimal;
-use rusty_money::{define_currency_set, Money};
+use rusty_money::{define_currency_set, FormattableCurrency, Locale, Money};
 use serde::{Deserialize, Deserializer, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
 use std::str::FromStr;
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
@@ -26,6 +30,29 @@ pub struct NumberTypeOption {
   pub symbol: String,
   #[serde(default)]
   pub name: String,
+  /// The ISO 4217 code shown after the amount instead of the symbol of the format, like
+  /// `1,234.50 USD`.
+  #[serde(default)]
+  pub currency_code: String,
+  /// The character that groups the digits by three in [NumberTypeOption::format_number], like
+  /// `.` in `1.234,5`. The numbers are only grouped when it's set, and the currencies then use
+  /// the separators of their format.
+  #[serde(default)]
+  pub group_separator: String,
+  /// The character that separates the decimals in [NumberTypeOption::format_number], `.` when
+  /// it's empty.
+  #[serde(default)]
+  pub decimal_separator: String,
+  /// When set, the numbers are rounded to [NumberTypeOption::scale] decimals and always shown
+  /// with that many decimals.
+  #[serde(default)]
+  pub rounding: Option<NumberRounding>,
+  /// A unit written before the number, like `~`. The spaces are part of the unit.
+  #[serde(default)]
+  pub prefix: String,
+  /// A unit written after the number, like ` kg`.
+  #[serde(default)]
+  pub suffix: String,
 }
 
 impl Default for NumberTypeOption {
@@ -37,6 +64,12 @@ impl Default for NumberTypeOption {
       scale: 0,
       symbol,
       name: "Number".to_string(),
+      currency_code: String::new(),
+      group_separator: String::new(),
+      decimal_separator: String::new(),
+      rounding: None,
+      prefix: String::new(),
+      suffix: String::new(),
     }
   }
 }
@@ -49,12 +82,21 @@ impl From<TypeOptionData> for NumberTypeOption {
 
 impl From<NumberTypeOption> for TypeOptionData {
   fn from(data: NumberTypeOption) -> Self {
-    TypeOptionDataBuilder::from([
+    let mut type_option = TypeOptionDataBuilder::from([
       ("format".into(), Any::BigInt(data.format.value())),
       ("scale".into(), Any::BigInt(data.scale as i64)),
       ("name".into(), data.name.into()),
       ("symbol".into(), data.symbol.into()),
-    ])
+      ("currency_code".into(), data.currency_code.into()),
+      ("group_separator".into(), data.group_separator.into()),
+      ("decimal_separator".into(), data.decimal_separator.into()),
+      ("prefix".into(), data.prefix.into()),
+      ("suffix".into(), data.suffix.into()),
+    ]);
+    if let Some(rounding) = data.rounding {
+      type_option.insert("rounding".into(), Any::BigInt(rounding as i64));
+    }
+    type_option
   }
 }
 
@@ -125,6 +167,176 @@ impl NumberTypeOption {
     self.format = format;
     self.symbol = format.symbol();
   }
+
+  /// Return the text shown for the number cell. Every platform shows the cells with it, so a
+  /// number looks the same everywhere.
+  pub fn format_cell(&self, cell: &Cell) -> String {
+    match cell.get_as::<String>(CELL_DATA) {
+      None => "".to_string(),
+      Some(text) => self.format_number(&text),
+    }
+  }
+
+  /// Format the number typed by the user or stored in a cell with the format, the currency code,
+  /// the separators, the rounding and the units of the type option. Return an empty string if the
+  /// text is not a number.
+  ///
+  /// The currencies are rounded to two decimals unless a [NumberTypeOption::rounding] is set, and
+  /// the plain numbers are only grouped when a [NumberTypeOption::group_separator] is set.
+  pub fn format_number(&self, text: &str) -> String {
+    let decimal = match self.format_cell_data(text) {
+      Ok(cell_data) => match cell_data.decimal() {
+        Some(decimal) => *decimal,
+        None => return "".to_string(),
+      },
+      Err(_) => return "".to_string(),
+    };
+    let currency = self.format.currency();
+    let decimal = match self.rounding {
+      Some(rounding) => {
+        let mut decimal = decimal.round_dp_with_strategy(self.scale, rounding.strategy());
+        decimal.rescale(self.scale);
+        decimal
+      },
+      None if self.format == NumberFormat::Num => decimal,
+      None => decimal.round_dp_with_strategy(
+        currency.exponent(),
+        rust_decimal::RoundingStrategy::MidpointNearestEven,
+      ),
+    };
+
+    let separators = match (self.separators(), self.format) {
+      (Some(separators), _) => separators,
+      (None, NumberFormat::Num) => NumberSeparators::ungrouped(),
+      (None, _) => NumberSeparators::from(currency.locale()),
+    };
+    let amount = separators.format(&decimal.abs().to_string());
+    let sign = if decimal.is_sign_negative() && !decimal.is_zero() {
+      "-"
+    } else {
+      ""
+    };
+    let (before, after) = if !self.currency_code.is_empty() {
+      ("".to_string(), format!(" {}", self.currency_code))
+    } else {
+      match self.format {
+        NumberFormat::Num => ("".to_string(), "".to_string()),
+        _ if currency.symbol_first() => (currency.symbol().to_string(), "".to_string()),
+        _ => ("".to_string(), currency.symbol().to_string()),
+      }
+    };
+    format!(
+      "{}{}{}{}{}{}",
+      self.prefix, sign, before, amount, after, self.suffix
+    )
+  }
+
+  /// Return the separators set on the type option, if any.
+  fn separators(&self) -> Option<NumberSeparators> {
+    let group = self.group_separator.chars().next()?;
+    let decimal = self.decimal_separator.chars().next().unwrap_or('.');
+    Some(NumberSeparators::new(group, decimal))
+  }
+}
+
+/// How a number is rounded to the [NumberTypeOption::scale] decimals.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum NumberRounding {
+  /// `2.5` is rounded to `3` and `-2.5` to `-3`.
+  #[default]
+  HalfUp = 0,
+  /// `2.5` is rounded to `2` and `3.5` to `4`.
+  HalfEven = 1,
+  /// Toward zero, `2.9` is rounded to `2`.
+  Down = 2,
+  /// Away from zero, `2.1` is rounded to `3`.
+  Up = 3,
+  Floor = 4,
+  Ceiling = 5,
+}
+
+impl NumberRounding {
+  fn strategy(&self) -> rust_decimal::RoundingStrategy {
+    use rust_decimal::RoundingStrategy;
+    match self {
+      NumberRounding::HalfUp => RoundingStrategy::MidpointAwayFromZero,
+      NumberRounding::HalfEven => RoundingStrategy::MidpointNearestEven,
+      NumberRounding::Down => RoundingStrategy::ToZero,
+      NumberRounding::Up => RoundingStrategy::AwayFromZero,
+      NumberRounding::Floor => RoundingStrategy::ToNegativeInfinity,
+      NumberRounding::Ceiling => RoundingStrategy::ToPositiveInfinity,
+    }
+  }
+}
+
+/// The characters that group the digits and separate the decimals of a number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct NumberSeparators {
+  group: Option<char>,
+  decimal: char,
+  /// The digits are grouped by three, then by two after the first group, like `1,00,000`.
+  indian_grouping: bool,
+}
+
+impl NumberSeparators {
+  fn new(group: char, decimal: char) -> Self {
+    Self {
+      group: Some(group),
+      decimal,
+      indian_grouping: false,
+    }
+  }
+
+  fn ungrouped() -> Self {
+    Self {
+      group: None,
+      decimal: '.',
+      indian_grouping: false,
+    }
+  }
+
+  /// Format the digits of a positive number written like `1234.5`.
+  fn format(&self, number: &str) -> String {
+    let (integer, fraction) = match number.split_once('.') {
+      Some((integer, fraction)) => (integer, Some(fraction)),
+      None => (number, None),
+    };
+    let mut result = String::new();
+    let digits = integer.chars().collect::<Vec<_>>();
+    for (index, digit) in digits.iter().enumerate() {
+      let remaining = digits.len() - index;
+      let is_group_start = match self.indian_grouping {
+        false => remaining % 3 == 0,
+        true => remaining == 3 || (remaining > 3 && (remaining - 3) % 2 == 0),
+      };
+      if let Some(group) = self.group {
+        if index > 0 && is_group_start {
+          result.push(group);
+        }
+      }
+      result.push(*digit);
+    }
+    if let Some(fraction) = fraction {
+      result.push(self.decimal);
+      result.push_str(fraction);
+    }
+    result
+  }
+}
+
+impl From<Locale> for NumberSeparators {
+  fn from(locale: Locale) -> Self {
+    match locale {
+      Locale::EnUs => Self::new(',', '.'),
+      Locale::EnIn => Self {
+        indian_grouping: true,
+        ..Self::new(',', '.')
+      },
+      Locale::EnEu => Self::new('.', ','),
+      Locale::EnBy => Self::new(' ', ','),
+    }
+  }
 }
 
 fn number_format_from_i64<'de, D>(deserializer: D) -> Result<NumberFormat, D::Error>
@@ -796,6 +1008,75 @@ mod tests {
     assert_number(&type_option, "1234.56", "€1.234,56");
   }
 
+  #[test]
+  fn explicit_separators_test() {
+    let mut type_option = NumberTypeOption::new();
+    assert_eq!(type_option.format_number("1234567.5"), "1234567.5");
+    type_option.group_separator = ".".to_string();
+    type_option.decimal_separator = ",".to_string();
+    assert_eq!(type_option.format_number("1234567.5"), "1.234.567,5");
+    assert_eq!(type_option.format_number("-1234.5"), "-1.234,5");
+    type_option.group_separator = "'".to_string();
+    type_option.decimal_separator = "".to_string();
+    assert_eq!(type_option.format_number("1234.5"), "1'234.5");
+    // The stringified text keeps the separators of the format.
+    assert_number(&type_option, "1234.5", "1234.5");
+
+    let mut type_option = NumberTypeOption::new();
+    type_option.set_format(NumberFormat::EUR);
+    assert_eq!(type_option.format_number("1234.5"), "€1.234,5");
+  }
+
+  #[test]
+  fn currency_code_and_units_test() {
+    let mut type_option = NumberTypeOption::new();
+    type_option.set_format(NumberFormat::USD);
+    type_option.currency_code = "USD".to_string();
+    type_option.scale = 2;
+    type_option.rounding = Some(NumberRounding::HalfUp);
+    assert_eq!(type_option.format_number("1234.5"), "1,234.50 USD");
+    assert_eq!(type_option.format_number("-0.005"), "-0.01 USD");
+
+    let mut type_option = NumberTypeOption::new();
+    type_option.prefix = "~".to_string();
+    type_option.suffix = " kg".to_string();
+    assert_eq!(type_option.format_number("72.25"), "~72.25 kg");
+    assert_eq!(type_option.format_number("abc"), "");
+
+    let data = TypeOptionData::from(type_option.clone());
+    let type_option = NumberTypeOption::from(data);
+    assert_eq!(type_option.suffix, " kg");
+    assert_eq!(type_option.rounding, None);
+  }
+
+  #[test]
+  fn rounding_mode_test() {
+    let mut type_option = NumberTypeOption::new();
+    type_option.scale = 0;
+    let round = |type_option: &mut NumberTypeOption, rounding, text| {
+      type_option.rounding = Some(rounding);
+      type_option.format_number(text)
+    };
+    assert_eq!(round(&mut type_option, NumberRounding::HalfUp, "2.5"), "3");
+    assert_eq!(
+      round(&mut type_option, NumberRounding::HalfEven, "2.5"),
+      "2"
+    );
+    assert_eq!(round(&mut type_option, NumberRounding::Down, "2.9"), "2");
+    assert_eq!(round(&mut type_option, NumberRounding::Up, "2.1"), "3");
+    assert_eq!(round(&mut type_option, NumberRounding::Floor, "-2.1"), "-3");
+    assert_eq!(
+      round(&mut type_option, NumberRounding::Ceiling, "-2.9"),
+      "-2"
+    );
+
+    let mut cell = crate::rows::new_cell_builder(crate::entity::FieldType::Number);
+    cell.insert(CELL_DATA.into(), "7.75".into());
+    type_option.scale = 1;
+    type_option.rounding = Some(NumberRounding::HalfEven);
+    assert_eq!(type_option.format_cell(&cell), "7.8");
+  }
+
   fn assert_number(type_option: &NumberTypeOption, input_str: &str, expected_str: &str) {
     let output = type_option.stringify_text(input_str);
     assert_eq!(output, expected_str.to_owned());
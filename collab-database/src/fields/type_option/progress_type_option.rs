@@ -0,0 +1,174 @@
+use crate::entity::FieldType;
+use crate::fields::checklist_type_option::checklist_from_cell;
+use crate::fields::{Field, StringifyTypeOption, TypeOptionData, TypeOptionDataBuilder};
+use crate::rows::{new_cell_builder, Cell};
+use crate::template::entity::CELL_DATA;
+use collab::preclude::Any;
+use serde::{Deserialize, Serialize};
+use yrs::encoding::serde::from_any;
+
+pub const MAX_PROGRESS: i64 = 100;
+
+/// The type option of a progress field. The cells hold a percentage from 0 to [MAX_PROGRESS].
+///
+/// When [ProgressTypeOption::checklist_field_id] is set, the progress is derived from the
+/// checklist cell of the same row: the percentage of its options that are selected. The derived
+/// cell is refreshed in the transaction that creates the row or updates the checklist cell, and
+/// after the remote updates of the row, see [crate::fields::CellRules].
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ProgressTypeOption {
+  #[serde(default)]
+  pub checklist_field_id: Option<String>,
+}
+
+impl ProgressTypeOption {
+  pub fn derived_from(checklist_field_id: &str) -> Self {
+    Self {
+      checklist_field_id: Some(checklist_field_id.to_string()),
+    }
+  }
+
+  /// Return the cell with the progress kept between 0 and [MAX_PROGRESS].
+  pub fn progress_cell(&self, progress: i64) -> Cell {
+    Cell::from(&ProgressCellData::new(progress))
+  }
+
+  /// Return the progress of the cell, None if the cell is empty.
+  pub fn progress_of(&self, cell: Option<&Cell>) -> Option<i64> {
+    cell.and_then(|cell| ProgressCellData::from(cell).progress)
+  }
+
+  /// Return the percentage of the selected options of the checklist cell. A checklist without
+  /// options has no progress.
+  pub fn progress_of_checklist(&self, checklist_cell: Option<&Cell>) -> Option<i64> {
+    let checklist = checklist_from_cell(checklist_cell?);
+    if checklist.options.is_empty() {
+      return None;
+    }
+    let selected = checklist
+      .options
+      .iter()
+      .filter(|option| checklist.selected_option_ids.contains(&option.id))
+      .count();
+    let progress = selected as f64 * MAX_PROGRESS as f64 / checklist.options.len() as f64;
+    Some(progress.round() as i64)
+  }
+}
+
+impl StringifyTypeOption for ProgressTypeOption {
+  fn stringify_text(&self, text: &str) -> String {
+    match parse_progress(text) {
+      Some(progress) => format!("{}%", progress),
+      None => "".to_string(),
+    }
+  }
+}
+
+impl From<TypeOptionData> for ProgressTypeOption {
+  fn from(data: TypeOptionData) -> Self {
+    from_any(&Any::from(data)).unwrap_or_default()
+  }
+}
+
+impl From<ProgressTypeOption> for TypeOptionData {
+  fn from(data: ProgressTypeOption) -> Self {
+    let mut type_option = TypeOptionDataBuilder::new();
+    if let Some(checklist_field_id) = data.checklist_field_id {
+      type_option.insert("checklist_field_id".into(), checklist_field_id.into());
+    }
+    type_option
+  }
+}
+
+/// Parse a progress like `40` or `40%`, kept between 0 and [MAX_PROGRESS].
+pub fn parse_progress(text: &str) -> Option<i64> {
+  let text = text.trim();
+  let text = text.strip_suffix('%').unwrap_or(text).trim();
+  text
+    .parse::<f64>()
+    .ok()
+    .filter(|progress| progress.is_finite())
+    .map(|progress| (progress.round() as i64).clamp(0, MAX_PROGRESS))
+}
+
+/// The data of a progress cell. It's stored as a string like the number cells.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProgressCellData {
+  pub progress: Option<i64>,
+}
+
+impl ProgressCellData {
+  pub fn new(progress: i64) -> Self {
+    Self {
+      progress: Some(progress.clamp(0, MAX_PROGRESS)),
+    }
+  }
+}
+
+impl From<&Cell> for ProgressCellData {
+  fn from(cell: &Cell) -> Self {
+    let progress = match cell.get(CELL_DATA) {
+      Some(Any::String(text)) => parse_progress(text),
+      Some(Any::BigInt(progress)) => Some((*progress).clamp(0, MAX_PROGRESS)),
+      Some(Any::Number(progress)) => Some((progress.round() as i64).clamp(0, MAX_PROGRESS)),
+      _ => None,
+    };
+    Self { progress }
+  }
+}
+
+impl From<&ProgressCellData> for Cell {
+  fn from(data: &ProgressCellData) -> Self {
+    let mut cell = new_cell_builder(FieldType::Progress);
+    let text = data
+      .progress
+      .map(|progress| progress.to_string())
+      .unwrap_or_default();
+    cell.insert(CELL_DATA.into(), text.into());
+    cell
+  }
+}
+
+/// A progress field derived from a checklist field of the same row.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProgressDerivation {
+  pub progress_field_id: String,
+  pub checklist_field_id: String,
+  pub type_option: ProgressTypeOption,
+}
+
+impl ProgressDerivation {
+  /// Return the derivations of the progress fields that are derived from a checklist field.
+  pub fn from_fields(fields: &[Field]) -> Vec<Self> {
+    fields.iter().filter_map(Self::from_field).collect()
+  }
+
+  /// Return the derivation of the field, or None if it's not a progress field derived from a
+  /// checklist field.
+  pub fn from_field(field: &Field) -> Option<Self> {
+    if FieldType::from(field.field_type) != FieldType::Progress {
+      return None;
+    }
+    let type_option = field.get_type_option::<ProgressTypeOption>(FieldType::Progress.type_id())?;
+    let checklist_field_id = type_option.checklist_field_id.clone()?;
+    Some(Self {
+      progress_field_id: field.id.clone(),
+      checklist_field_id,
+      type_option,
+    })
+  }
+
+  /// Return the progress cell derived from the checklist cell, or None if the progress cell is
+  /// already up to date.
+  pub fn derive_cell(
+    &self,
+    checklist_cell: Option<&Cell>,
+    progress_cell: Option<&Cell>,
+  ) -> Option<Cell> {
+    let progress = self.type_option.progress_of_checklist(checklist_cell);
+    if progress == self.type_option.progress_of(progress_cell) {
+      return None;
+    }
+    Some(Cell::from(&ProgressCellData { progress }))
+  }
+}
@@ -0,0 +1,248 @@
+use std::cmp::Ordering;
+
+use crate::entity::FieldType;
+use crate::fields::{StringifyTypeOption, TypeOptionData, TypeOptionDataBuilder};
+use crate::rows::{new_cell_builder, Cell};
+use crate::template::entity::CELL_DATA;
+use crate::views::FilterMap;
+use collab::preclude::Any;
+use serde::{Deserialize, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
+use yrs::encoding::serde::from_any;
+
+pub const DEFAULT_MAX_RATING: i64 = 5;
+/// The most icons a rating field can show.
+pub const MAX_RATING_LIMIT: i64 = 10;
+
+const RATING_FILTER_CONDITION: &str = "condition";
+const RATING_FILTER_CONTENT: &str = "content";
+
+/// The icon repeated to show a rating.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum RatingIcon {
+  #[default]
+  Star = 0,
+  Heart = 1,
+  ThumbsUp = 2,
+  Fire = 3,
+}
+
+impl RatingIcon {
+  pub fn symbol(&self) -> &'static str {
+    match self {
+      RatingIcon::Star => "⭐",
+      RatingIcon::Heart => "❤️",
+      RatingIcon::ThumbsUp => "👍",
+      RatingIcon::Fire => "🔥",
+    }
+  }
+}
+
+/// The type option of a rating field. The cells hold a rating from 0 to [RatingTypeOption::max],
+/// shown as that many icons.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RatingTypeOption {
+  #[serde(default = "default_max_rating")]
+  pub max: i64,
+  #[serde(default)]
+  pub icon: RatingIcon,
+}
+
+fn default_max_rating() -> i64 {
+  DEFAULT_MAX_RATING
+}
+
+impl Default for RatingTypeOption {
+  fn default() -> Self {
+    Self {
+      max: DEFAULT_MAX_RATING,
+      icon: RatingIcon::default(),
+    }
+  }
+}
+
+impl RatingTypeOption {
+  /// The max is kept between 1 and [MAX_RATING_LIMIT].
+  pub fn new(max: i64, icon: RatingIcon) -> Self {
+    Self {
+      max: max.clamp(1, MAX_RATING_LIMIT),
+      icon,
+    }
+  }
+
+  /// Return the rating kept between 0 and the max of the field.
+  pub fn clamp(&self, rating: i64) -> i64 {
+    rating.clamp(0, self.max.max(1))
+  }
+
+  /// Return the cell with the rating kept between 0 and the max of the field.
+  pub fn rating_cell(&self, rating: i64) -> Cell {
+    Cell::from(&RatingCellData::new(self.clamp(rating)))
+  }
+
+  /// Return the rating of the cell, None if the cell is empty. A cell rated above the max of the
+  /// field, for example after the max was lowered, counts as the max.
+  pub fn rating_of(&self, cell: Option<&Cell>) -> Option<i64> {
+    cell
+      .and_then(|cell| RatingCellData::from(cell).rating)
+      .map(|rating| self.clamp(rating))
+  }
+
+  /// Show the rating as its icons, like `⭐⭐⭐`.
+  pub fn format_icons(&self, rating: i64) -> String {
+    self.icon.symbol().repeat(self.clamp(rating) as usize)
+  }
+
+  /// Compare the ratings of two cells to sort the rows in ascending order. The empty cells are
+  /// after the rated ones, so they are kept at the end when the order is reversed by the caller.
+  pub fn compare_cells(&self, left: Option<&Cell>, right: Option<&Cell>) -> Ordering {
+    match (self.rating_of(left), self.rating_of(right)) {
+      (Some(left), Some(right)) => left.cmp(&right),
+      (Some(_), None) => Ordering::Less,
+      (None, Some(_)) => Ordering::Greater,
+      (None, None) => Ordering::Equal,
+    }
+  }
+
+  /// Return true if the rating of the cell matches the filter.
+  pub fn matches_filter(&self, filter: &RatingFilter, cell: Option<&Cell>) -> bool {
+    let value = filter.value;
+    match (filter.condition, self.rating_of(cell)) {
+      (RatingFilterCondition::IsEmpty, rating) => rating.is_none(),
+      (RatingFilterCondition::IsNotEmpty, rating) => rating.is_some(),
+      (RatingFilterCondition::IsNot, rating) => rating != Some(value),
+      (_, None) => false,
+      (RatingFilterCondition::Is, Some(rating)) => rating == value,
+      (RatingFilterCondition::GreaterThan, Some(rating)) => rating > value,
+      (RatingFilterCondition::LessThan, Some(rating)) => rating < value,
+      (RatingFilterCondition::GreaterThanOrEqualTo, Some(rating)) => rating >= value,
+      (RatingFilterCondition::LessThanOrEqualTo, Some(rating)) => rating <= value,
+    }
+  }
+}
+
+impl StringifyTypeOption for RatingTypeOption {
+  fn stringify_text(&self, text: &str) -> String {
+    match text.trim().parse::<i64>() {
+      Ok(rating) => self.clamp(rating).to_string(),
+      Err(_) => "".to_string(),
+    }
+  }
+}
+
+impl From<TypeOptionData> for RatingTypeOption {
+  /// The max written by another client is kept between 1 and [MAX_RATING_LIMIT] too.
+  fn from(data: TypeOptionData) -> Self {
+    let type_option: Self = from_any(&Any::from(data)).unwrap_or_default();
+    Self::new(type_option.max, type_option.icon)
+  }
+}
+
+impl From<RatingTypeOption> for TypeOptionData {
+  fn from(data: RatingTypeOption) -> Self {
+    TypeOptionDataBuilder::from([
+      ("max".into(), Any::BigInt(data.max)),
+      ("icon".into(), Any::BigInt(data.icon as i64)),
+    ])
+  }
+}
+
+/// The data of a rating cell. It's stored as a string like the number cells.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RatingCellData {
+  pub rating: Option<i64>,
+}
+
+impl RatingCellData {
+  pub fn new(rating: i64) -> Self {
+    Self {
+      rating: Some(rating),
+    }
+  }
+}
+
+impl From<&Cell> for RatingCellData {
+  fn from(cell: &Cell) -> Self {
+    let rating = match cell.get(CELL_DATA) {
+      Some(Any::String(text)) => text.trim().parse::<i64>().ok(),
+      Some(Any::BigInt(rating)) => Some(*rating),
+      Some(Any::Number(rating)) => Some(rating.round() as i64),
+      _ => None,
+    };
+    Self { rating }
+  }
+}
+
+impl From<&RatingCellData> for Cell {
+  fn from(data: &RatingCellData) -> Self {
+    let mut cell = new_cell_builder(FieldType::Rating);
+    let text = data
+      .rating
+      .map(|rating| rating.to_string())
+      .unwrap_or_default();
+    cell.insert(CELL_DATA.into(), text.into());
+    cell
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum RatingFilterCondition {
+  #[default]
+  Is = 0,
+  IsNot = 1,
+  GreaterThan = 2,
+  LessThan = 3,
+  GreaterThanOrEqualTo = 4,
+  LessThanOrEqualTo = 5,
+  IsEmpty = 6,
+  IsNotEmpty = 7,
+}
+
+impl From<i64> for RatingFilterCondition {
+  fn from(value: i64) -> Self {
+    match value {
+      1 => RatingFilterCondition::IsNot,
+      2 => RatingFilterCondition::GreaterThan,
+      3 => RatingFilterCondition::LessThan,
+      4 => RatingFilterCondition::GreaterThanOrEqualTo,
+      5 => RatingFilterCondition::LessThanOrEqualTo,
+      6 => RatingFilterCondition::IsEmpty,
+      7 => RatingFilterCondition::IsNotEmpty,
+      _ => RatingFilterCondition::Is,
+    }
+  }
+}
+
+/// A condition on the rating of a cell, see [RatingTypeOption::matches_filter]. It's read from
+/// the `condition` of a [FilterMap] and the rating from its `content`, like the filters of the
+/// other fields.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RatingFilter {
+  pub condition: RatingFilterCondition,
+  pub value: i64,
+}
+
+impl RatingFilter {
+  pub fn new(condition: RatingFilterCondition, value: i64) -> Self {
+    Self { condition, value }
+  }
+}
+
+impl From<&FilterMap> for RatingFilter {
+  fn from(filter: &FilterMap) -> Self {
+    let condition = match filter.get(RATING_FILTER_CONDITION) {
+      Some(Any::BigInt(condition)) => RatingFilterCondition::from(*condition),
+      Some(Any::Number(condition)) => RatingFilterCondition::from(*condition as i64),
+      _ => RatingFilterCondition::default(),
+    };
+    let value = match filter.get(RATING_FILTER_CONTENT) {
+      Some(Any::String(text)) => text.trim().parse::<i64>().unwrap_or_default(),
+      Some(Any::BigInt(value)) => *value,
+      Some(Any::Number(value)) => value.round() as i64,
+      _ => 0,
+    };
+    Self { condition, value }
+  }
+}
@@ -0,0 +1,437 @@
+use crate::entity::FieldType;
+use crate::fields::{format_number, parse_number};
+use crate::fields::{StringifyTypeOption, TypeOptionData, TypeOptionDataBuilder};
+use crate::rows::{new_cell_builder, Cell, RowId};
+use crate::template::entity::CELL_DATA;
+use collab::preclude::{Any, Map, MapRef, ToJson, TransactionMut};
+use collab::util::AnyExt;
+use serde::{Deserialize, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, Mutex};
+use yrs::encoding::serde::from_any;
+
+/// The keys of the links of a relation cell. Each link is stored in its own key of the cell,
+/// suffixed by the id of the linked row, with its position as value, so the clients that link
+/// or unlink different rows don't overwrite each other.
+const RELATION_LINK: &str = "relation_link:";
+/// The fingerprint of the [CELL_DATA] written along with the keys of the links. The keys of the
+/// links are only read while it matches the [CELL_DATA] of the cell, so a cell written as a
+/// whole afterwards is read from its [CELL_DATA] again.
+const RELATION_SPLIT: &str = "relation_split";
+
+/// The type option of a relation field. The cells of the field link to the rows of the database
+/// with the given id.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct RelationTypeOption {
+  #[serde(default)]
+  pub database_id: String,
+  /// The relation field of the related database that links back to the rows of this field. When
+  /// it's set, the links written by
+  /// [WorkspaceDatabaseManager::update_relation_cell](crate::workspace_database::WorkspaceDatabaseManager::update_relation_cell)
+  /// are mirrored into the cells of that field.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub reciprocal_field_id: Option<String>,
+}
+
+impl RelationTypeOption {
+  pub fn new(database_id: &str) -> Self {
+    Self {
+      database_id: database_id.to_string(),
+      reciprocal_field_id: None,
+    }
+  }
+
+  pub fn with_reciprocal_field(mut self, field_id: &str) -> Self {
+    self.reciprocal_field_id = Some(field_id.to_string());
+    self
+  }
+}
+
+impl From<TypeOptionData> for RelationTypeOption {
+  fn from(data: TypeOptionData) -> Self {
+    from_any(&Any::from(data)).unwrap_or_default()
+  }
+}
+
+impl From<RelationTypeOption> for TypeOptionData {
+  fn from(data: RelationTypeOption) -> Self {
+    let mut type_option =
+      TypeOptionDataBuilder::from([("database_id".into(), data.database_id.into())]);
+    if let Some(field_id) = data.reciprocal_field_id {
+      type_option.insert("reciprocal_field_id".into(), field_id.into());
+    }
+    type_option
+  }
+}
+
+/// The data of a relation cell is the list of the linked row ids.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RelationCellData {
+  pub row_ids: Vec<RowId>,
+}
+
+impl RelationCellData {
+  /// Add the link to the row, return false if it's already linked.
+  pub fn link(&mut self, row_id: &RowId) -> bool {
+    if self.row_ids.contains(row_id) {
+      return false;
+    }
+    self.row_ids.push(row_id.clone());
+    true
+  }
+
+  /// Remove the link to the row, return false if it's not linked.
+  pub fn unlink(&mut self, row_id: &RowId) -> bool {
+    let len = self.row_ids.len();
+    self.row_ids.retain(|linked_row_id| linked_row_id != row_id);
+    self.row_ids.len() != len
+  }
+}
+
+impl From<&Cell> for RelationCellData {
+  fn from(cell: &Cell) -> Self {
+    if is_split(cell) {
+      return Self {
+        row_ids: relation_links(cell)
+          .into_iter()
+          .map(|(row_id, _)| row_id)
+          .collect(),
+      };
+    }
+    Self {
+      row_ids: cell_data_row_ids(cell),
+    }
+  }
+}
+
+impl From<&RelationCellData> for Cell {
+  fn from(data: &RelationCellData) -> Self {
+    let mut cell = new_cell_builder(FieldType::Relation);
+    cell.insert(CELL_DATA.into(), row_ids_data(&data.row_ids));
+    cell
+  }
+}
+
+/// A link back to a removed row, left in the reciprocal cell of a row of another database. See
+/// [PendingUnlinks].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingUnlink {
+  pub database_id: String,
+  pub field_id: String,
+  pub row_id: RowId,
+  pub unlinked_row_id: RowId,
+  /// Link the row back instead of unlinking it, like the duplicate of a row that links to a row
+  /// of another database.
+  pub link: bool,
+}
+
+/// The links back to the removed rows that are left in the other databases. A database only
+/// unlinks its removed rows from its own reciprocal cells; the links in the other databases are
+/// queued here, as are the links back to its duplicated rows, and applied by the
+/// [WorkspaceDatabaseManager](crate::workspace_database::WorkspaceDatabaseManager) that shares
+/// the queue with all the databases it opens.
+#[derive(Debug, Clone, Default)]
+pub struct PendingUnlinks(Arc<Mutex<Vec<PendingUnlink>>>);
+
+impl PendingUnlinks {
+  pub fn push(&self, unlink: PendingUnlink) {
+    self.0.lock().unwrap().push(unlink);
+  }
+
+  pub fn take(&self) -> Vec<PendingUnlink> {
+    std::mem::take(&mut *self.0.lock().unwrap())
+  }
+
+  pub fn extend(&self, unlinks: impl IntoIterator<Item = PendingUnlink>) {
+    self.0.lock().unwrap().extend(unlinks);
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.0.lock().unwrap().is_empty()
+  }
+}
+
+/// Link and unlink the rows in the relation cell in place, and return true if a link was added or
+/// removed. The [CELL_DATA] of the cell is rewritten with all the links, for the clients that
+/// read the links from it.
+pub fn apply_relation_changes(
+  txn: &mut TransactionMut,
+  cell_map: &MapRef,
+  linked_row_ids: &[RowId],
+  unlinked_row_ids: &[RowId],
+) -> bool {
+  let cell = cell_map.to_json(txn).into_map().unwrap_or_default();
+  if !is_split(&cell) {
+    // Split the links written as a whole into their own keys. The keys left by a previous split
+    // are stale.
+    clear_relation_keys(txn, cell_map);
+    for (index, row_id) in cell_data_row_ids(&cell).iter().enumerate() {
+      cell_map.insert(txn, link_key(row_id), Any::Number(index as f64));
+    }
+  }
+  let mut links = relation_links(&cell_map.to_json(txn).into_map().unwrap_or_default());
+
+  let mut changed = false;
+  for row_id in linked_row_ids {
+    if links
+      .iter()
+      .any(|(linked_row_id, _)| linked_row_id == row_id)
+    {
+      continue;
+    }
+    let position = links
+      .last()
+      .map(|(_, position)| position + 1.0)
+      .unwrap_or_default();
+    cell_map.insert(txn, link_key(row_id), Any::Number(position));
+    links.push((row_id.clone(), position));
+    changed = true;
+  }
+  for row_id in unlinked_row_ids {
+    if let Some(index) = links
+      .iter()
+      .position(|(linked_row_id, _)| linked_row_id == row_id)
+    {
+      links.remove(index);
+      cell_map.remove(txn, &link_key(row_id));
+      changed = true;
+    }
+  }
+
+  let row_ids = links
+    .into_iter()
+    .map(|(row_id, _)| row_id)
+    .collect::<Vec<_>>();
+  cell_map.insert(txn, RELATION_SPLIT, fingerprint(&row_ids));
+  cell_map.insert(txn, CELL_DATA, row_ids_data(&row_ids));
+  changed
+}
+
+/// Remove the keys of the links of a relation cell. Called before the cell is written as a whole,
+/// so the links that are not in the new cell don't come back.
+pub(crate) fn clear_relation_keys(txn: &mut TransactionMut, cell_map: &MapRef) {
+  let keys = cell_map
+    .keys(txn)
+    .filter(|key| *key == RELATION_SPLIT || key.starts_with(RELATION_LINK))
+    .map(|key| key.to_string())
+    .collect::<Vec<_>>();
+  for key in keys {
+    cell_map.remove(txn, &key);
+  }
+}
+
+fn is_split(cell: &Cell) -> bool {
+  match cell.get(RELATION_SPLIT) {
+    Some(Any::String(split)) => split.as_ref() == fingerprint(&cell_data_row_ids(cell)),
+    _ => false,
+  }
+}
+
+fn fingerprint(row_ids: &[RowId]) -> String {
+  let mut hasher = Sha256::new();
+  for row_id in row_ids {
+    hasher.update(row_id.as_bytes());
+    hasher.update([0]);
+  }
+  format!("{:x}", hasher.finalize())
+}
+
+fn link_key(row_id: &RowId) -> String {
+  format!("{}{}", RELATION_LINK, row_id)
+}
+
+/// Return the linked rows with their positions, in their order. Two rows linked concurrently at
+/// the same position are ordered by their id.
+fn relation_links(cell: &Cell) -> Vec<(RowId, f64)> {
+  let mut links = cell
+    .iter()
+    .filter_map(|(key, value)| {
+      let row_id = key.strip_prefix(RELATION_LINK)?;
+      let position = match value {
+        Any::Number(position) => *position,
+        Any::BigInt(position) => *position as f64,
+        _ => f64::MAX,
+      };
+      Some((RowId::from(row_id.to_string()), position))
+    })
+    .collect::<Vec<_>>();
+  links.sort_by(|left, right| {
+    left
+      .1
+      .total_cmp(&right.1)
+      .then_with(|| left.0.as_str().cmp(right.0.as_str()))
+  });
+  links
+}
+
+fn cell_data_row_ids(cell: &Cell) -> Vec<RowId> {
+  match cell.get(CELL_DATA) {
+    Some(Any::Array(row_ids)) => row_ids
+      .iter()
+      .filter_map(|row_id| match row_id {
+        Any::String(row_id) => Some(RowId::from(row_id.to_string())),
+        _ => None,
+      })
+      .collect(),
+    _ => vec![],
+  }
+}
+
+fn row_ids_data(row_ids: &[RowId]) -> Any {
+  let row_ids = row_ids
+    .iter()
+    .map(|row_id| Any::from(row_id.to_string()))
+    .collect::<Vec<_>>();
+  Any::Array(Arc::from(row_ids))
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum RollupAggregation {
+  /// The number of linked rows.
+  #[default]
+  Count = 0,
+  Sum = 1,
+  Min = 2,
+  Max = 3,
+  Average = 4,
+}
+
+impl From<i64> for RollupAggregation {
+  fn from(value: i64) -> Self {
+    match value {
+      1 => RollupAggregation::Sum,
+      2 => RollupAggregation::Min,
+      3 => RollupAggregation::Max,
+      4 => RollupAggregation::Average,
+      _ => RollupAggregation::Count,
+    }
+  }
+}
+
+/// The type option of a rollup field. The cell of a row aggregates the `target_field_id` cells of
+/// the rows linked by its `relation_field_id` cell. The cells are not stored, they are computed
+/// by [WorkspaceDatabaseManager::resolve_rollup_cell](crate::workspace_database::WorkspaceDatabaseManager::resolve_rollup_cell).
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct RollupTypeOption {
+  #[serde(default)]
+  pub relation_field_id: String,
+  #[serde(default)]
+  pub target_field_id: String,
+  #[serde(default)]
+  pub aggregation: RollupAggregation,
+}
+
+impl RollupTypeOption {
+  pub fn new(
+    relation_field_id: &str,
+    target_field_id: &str,
+    aggregation: RollupAggregation,
+  ) -> Self {
+    Self {
+      relation_field_id: relation_field_id.to_string(),
+      target_field_id: target_field_id.to_string(),
+      aggregation,
+    }
+  }
+
+  /// Aggregate the target cells of the linked rows. The cells that don't hold a number are
+  /// counted by [RollupAggregation::Count] only. Return None when there is no number to
+  /// aggregate.
+  pub fn aggregate(&self, cells: &[Option<Cell>]) -> Option<f64> {
+    let values = cells
+      .iter()
+      .flatten()
+      .filter_map(|cell| match cell.get(CELL_DATA) {
+        Some(Any::String(text)) => parse_number(text),
+        _ => None,
+      })
+      .collect::<Vec<_>>();
+    match self.aggregation {
+      RollupAggregation::Count => Some(cells.len() as f64),
+      _ if values.is_empty() => None,
+      RollupAggregation::Sum => Some(values.iter().sum()),
+      RollupAggregation::Min => values.iter().cloned().reduce(f64::min),
+      RollupAggregation::Max => values.iter().cloned().reduce(f64::max),
+      RollupAggregation::Average => Some(values.iter().sum::<f64>() / values.len() as f64),
+    }
+  }
+
+  /// Return the rollup cell of the target cells of the linked rows. The cell is empty when there
+  /// is no number to aggregate.
+  pub fn compute_cell(&self, cells: &[Option<Cell>]) -> Cell {
+    let mut cell = new_cell_builder(FieldType::Rollup);
+    let data = self.aggregate(cells).map(format_number).unwrap_or_default();
+    cell.insert(CELL_DATA.into(), data.into());
+    cell
+  }
+}
+
+impl StringifyTypeOption for RollupTypeOption {
+  fn stringify_text(&self, text: &str) -> String {
+    text.to_string()
+  }
+}
+
+impl From<TypeOptionData> for RollupTypeOption {
+  fn from(data: TypeOptionData) -> Self {
+    from_any(&Any::from(data)).unwrap_or_default()
+  }
+}
+
+impl From<RollupTypeOption> for TypeOptionData {
+  fn from(data: RollupTypeOption) -> Self {
+    TypeOptionDataBuilder::from([
+      ("relation_field_id".into(), data.relation_field_id.into()),
+      ("target_field_id".into(), data.target_field_id.into()),
+      ("aggregation".into(), Any::BigInt(data.aggregation as i64)),
+    ])
+  }
+}
+
+/// The type option of a lookup field. The cell of a row lists the `target_field_id` cells of the
+/// rows linked by its `relation_field_id` cell, as they are. Like the rollup cells, the cells are
+/// not stored, they are resolved by
+/// [WorkspaceDatabaseManager::resolve_lookup_values](crate::workspace_database::WorkspaceDatabaseManager::resolve_lookup_values).
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct LookupTypeOption {
+  #[serde(default)]
+  pub relation_field_id: String,
+  #[serde(default)]
+  pub target_field_id: String,
+}
+
+impl LookupTypeOption {
+  pub fn new(relation_field_id: &str, target_field_id: &str) -> Self {
+    Self {
+      relation_field_id: relation_field_id.to_string(),
+      target_field_id: target_field_id.to_string(),
+    }
+  }
+}
+
+impl From<TypeOptionData> for LookupTypeOption {
+  fn from(data: TypeOptionData) -> Self {
+    from_any(&Any::from(data)).unwrap_or_default()
+  }
+}
+
+impl From<LookupTypeOption> for TypeOptionData {
+  fn from(data: LookupTypeOption) -> Self {
+    TypeOptionDataBuilder::from([
+      ("relation_field_id".into(), data.relation_field_id.into()),
+      ("target_field_id".into(), data.target_field_id.into()),
+    ])
+  }
+}
+
+/// The value of a lookup cell pulled from one of the linked rows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LookupValue {
+  pub row_id: RowId,
+  /// The target cell of the linked row, None if the row or its cell doesn't exist.
+  pub cell: Option<Cell>,
+  /// The cell stringified by the type option of the target field.
+  pub text: String,
+}
@@ -2,10 +2,11 @@ use crate::database::gen_option_id;
 
 use crate::error::DatabaseError;
 use crate::fields::{StringifyTypeOption, TypeOptionData, TypeOptionDataBuilder};
-use crate::rows::{new_cell_builder, Cell};
+use crate::rows::{new_cell_builder, Cell, RowId};
 use crate::template::entity::CELL_DATA;
 use collab::util::AnyMapExt;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::ops::{Deref, DerefMut};
 use std::str::FromStr;
 
@@ -13,6 +14,9 @@ use std::str::FromStr;
 pub struct SelectTypeOption {
   pub options: Vec<SelectOption>,
   pub disable_color: bool,
+  /// The option selected in the new rows, see [crate::rows::DefaultSelectOptionHook].
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  default_option_id: Option<String>,
 }
 
 impl StringifyTypeOption for SelectTypeOption {
@@ -37,6 +41,29 @@ impl StringifyTypeOption for SelectTypeOption {
 }
 
 impl SelectTypeOption {
+  pub fn new(options: Vec<SelectOption>, disable_color: bool) -> Self {
+    Self {
+      options,
+      disable_color,
+      default_option_id: None,
+    }
+  }
+
+  /// Select the option in the new rows.
+  pub fn with_default_option_id(mut self, option_id: impl Into<String>) -> Self {
+    self.default_option_id = Some(option_id.into());
+    self
+  }
+
+  pub fn set_default_option_id(&mut self, option_id: Option<String>) {
+    self.default_option_id = option_id;
+  }
+
+  /// Return the id of the option selected in the new rows.
+  pub fn default_option_id(&self) -> Option<&str> {
+    self.default_option_id.as_deref()
+  }
+
   pub fn to_json_string(&self) -> String {
     serde_json::to_string(self).unwrap()
   }
@@ -255,3 +282,55 @@ impl std::ops::DerefMut for SelectOptionIds {
     &mut self.0
   }
 }
+
+/// The number of cells that select an option of a select field.
+#[derive(Clone, Debug)]
+pub struct SelectOptionUsage {
+  pub option: SelectOption,
+  pub num_of_cells: usize,
+}
+
+/// The usage of the options of a select field, see
+/// [crate::database::Database::audit_select_options].
+#[derive(Clone, Debug, Default)]
+pub struct SelectOptionAudit {
+  pub field_id: String,
+  /// The options of the type option, in their order, with the number of cells that select them.
+  pub usages: Vec<SelectOptionUsage>,
+  /// The option ids selected by the cells but missing from the type option, with the rows of
+  /// these cells. The cells render them blank.
+  pub orphaned_option_ids: BTreeMap<String, Vec<RowId>>,
+}
+
+impl SelectOptionAudit {
+  /// Return the options that no cell selects.
+  pub fn unused_options(&self) -> Vec<&SelectOption> {
+    self
+      .usages
+      .iter()
+      .filter(|usage| usage.num_of_cells == 0)
+      .map(|usage| &usage.option)
+      .collect()
+  }
+
+  pub fn has_orphaned_option_ids(&self) -> bool {
+    !self.orphaned_option_ids.is_empty()
+  }
+}
+
+/// What [crate::database::Database::cleanup_select_options] removes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SelectOptionCleanup {
+  /// Remove the orphaned option ids from the cells.
+  pub remove_orphaned_ids: bool,
+  /// Remove the options that no cell selects, except the default option of the field.
+  pub remove_unused_options: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SelectOptionCleanupSummary {
+  /// The number of cells whose orphaned option ids were removed.
+  pub cleaned_cells: usize,
+  /// The ids of the options removed from the type option.
+  pub removed_option_ids: Vec<String>,
+}
@@ -0,0 +1,166 @@
+use crate::entity::FieldType;
+use crate::fields::{StringifyTypeOption, TypeOptionData, TypeOptionDataBuilder};
+use crate::rows::{new_cell_builder, Cell};
+use crate::template::entity::CELL_DATA;
+use crate::views::CalculationType;
+use collab::preclude::Any;
+use serde::{Deserialize, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
+use yrs::encoding::serde::from_any;
+
+/// How a duration is shown.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum DurationFormat {
+  /// `1:30`
+  #[default]
+  HoursMinutes = 0,
+  /// `1:30:15`
+  HoursMinutesSeconds = 1,
+  /// `1.5`
+  DecimalHours = 2,
+}
+
+/// The type option of a time field. The cells hold a duration in seconds, used to track the time
+/// spent on a task.
+#[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq)]
+pub struct TimeTypeOption {
+  #[serde(default)]
+  pub duration_format: DurationFormat,
+}
+
+impl TimeTypeOption {
+  pub fn new(duration_format: DurationFormat) -> Self {
+    Self { duration_format }
+  }
+
+  pub fn format_duration(&self, seconds: i64) -> String {
+    let sign = if seconds < 0 { "-" } else { "" };
+    let total = seconds.unsigned_abs();
+    let (hours, minutes, seconds) = (total / 3600, total % 3600 / 60, total % 60);
+    match self.duration_format {
+      DurationFormat::HoursMinutes => format!("{}{}:{:02}", sign, hours, minutes),
+      DurationFormat::HoursMinutesSeconds => {
+        format!("{}{}:{:02}:{:02}", sign, hours, minutes, seconds)
+      },
+      DurationFormat::DecimalHours => {
+        let hours = (total as f64 / 3600.0 * 100.0).round() / 100.0;
+        format!("{}{}", sign, hours)
+      },
+    }
+  }
+
+  /// Parse a duration typed by the user, `h:mm`, `h:mm:ss` or a number of hours like `1.5`.
+  pub fn parse_duration(text: &str) -> Option<i64> {
+    let text = text.trim();
+    let (sign, text) = match text.strip_prefix('-') {
+      Some(text) => (-1, text),
+      None => (1, text),
+    };
+    if text.contains(':') {
+      let parts = text
+        .split(':')
+        .map(|part| part.trim().parse::<u32>().ok())
+        .collect::<Option<Vec<_>>>()?;
+      let seconds = match parts.as_slice() {
+        [hours, minutes] if *minutes < 60 => *hours as i64 * 3600 + *minutes as i64 * 60,
+        [hours, minutes, seconds] if *minutes < 60 && *seconds < 60 => {
+          *hours as i64 * 3600 + *minutes as i64 * 60 + *seconds as i64
+        },
+        _ => return None,
+      };
+      return Some(sign * seconds);
+    }
+    let hours = text.parse::<f64>().ok().filter(|hours| hours.is_finite())?;
+    Some(sign * (hours * 3600.0).round() as i64)
+  }
+
+  /// Compute the calculation of the durations of the cells. The counts are numbers, the other
+  /// calculations are formatted as a duration.
+  pub fn calculate(&self, calculation_type: CalculationType, cells: &[Option<Cell>]) -> String {
+    let values = cells
+      .iter()
+      .map(|cell| {
+        cell
+          .as_ref()
+          .and_then(|cell| TimeCellData::from(cell).seconds)
+          .map(|seconds| seconds.to_string())
+      })
+      .collect::<Vec<_>>();
+    let value = calculation_type.calculate(&values);
+    match calculation_type {
+      CalculationType::Count | CalculationType::CountEmpty | CalculationType::CountNonEmpty => {
+        value
+      },
+      _ => match value.parse::<f64>() {
+        Ok(seconds) => self.format_duration(seconds.round() as i64),
+        Err(_) => value,
+      },
+    }
+  }
+}
+
+impl StringifyTypeOption for TimeTypeOption {
+  /// The text that isn't a number of seconds, like the free text of the older time cells, is
+  /// returned as it is.
+  fn stringify_text(&self, text: &str) -> String {
+    match text.trim().parse::<i64>() {
+      Ok(seconds) => self.format_duration(seconds),
+      Err(_) => text.to_string(),
+    }
+  }
+}
+
+impl From<TypeOptionData> for TimeTypeOption {
+  fn from(data: TypeOptionData) -> Self {
+    from_any(&Any::from(data)).unwrap_or_default()
+  }
+}
+
+impl From<TimeTypeOption> for TypeOptionData {
+  fn from(data: TimeTypeOption) -> Self {
+    TypeOptionDataBuilder::from([(
+      "duration_format".into(),
+      Any::BigInt(data.duration_format as i64),
+    )])
+  }
+}
+
+/// The data of a time cell, a duration in seconds. It's stored as a string like the number
+/// cells.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimeCellData {
+  pub seconds: Option<i64>,
+}
+
+impl TimeCellData {
+  pub fn new(seconds: i64) -> Self {
+    Self {
+      seconds: Some(seconds),
+    }
+  }
+}
+
+impl From<&Cell> for TimeCellData {
+  fn from(cell: &Cell) -> Self {
+    let seconds = match cell.get(CELL_DATA) {
+      Some(Any::String(text)) => text.trim().parse::<i64>().ok(),
+      Some(Any::BigInt(seconds)) => Some(*seconds),
+      Some(Any::Number(seconds)) => Some(seconds.round() as i64),
+      _ => None,
+    };
+    Self { seconds }
+  }
+}
+
+impl From<&TimeCellData> for Cell {
+  fn from(data: &TimeCellData) -> Self {
+    let mut cell = new_cell_builder(FieldType::Time);
+    let text = data
+      .seconds
+      .map(|seconds| seconds.to_string())
+      .unwrap_or_default();
+    cell.insert(CELL_DATA.into(), text.into());
+    cell
+  }
+}
@@ -1,3 +1,5 @@
+use async_trait::async_trait;
+
 use crate::entity::FieldType;
 use crate::error::DatabaseError;
 use crate::fields::{StringifyTypeOption, TypeOptionData, TypeOptionDataBuilder};
@@ -37,9 +39,39 @@ impl From<URLTypeOption> for TypeOptionData {
   }
 }
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+/// The keys of the metadata of the link stored in a URL cell, see [URLCellData].
+pub const URL_TITLE: &str = "title";
+pub const URL_FAVICON: &str = "favicon";
+pub const URL_LAST_VERIFIED_AT: &str = "last_verified_at";
+
+/// The metadata fetched from the page of a link, used to render a preview of the link.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct URLMetadata {
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub title: Option<String>,
+  /// The url of the icon of the page.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub favicon: Option<String>,
+}
+
+/// Fetches the [URLMetadata] of a link. It's implemented by the application, the metadata is
+/// stored in the cell by [crate::database::Database::enrich_url_cell] so the other clients get
+/// it with the sync instead of fetching it again.
+#[async_trait]
+pub trait URLMetadataProvider: Send + Sync {
+  async fn fetch_metadata(&self, url: &str) -> Result<URLMetadata, DatabaseError>;
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct URLCellData {
   pub data: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub title: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub favicon: Option<String>,
+  /// The timestamp, in seconds, of the last time the metadata was fetched.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub last_verified_at: Option<i64>,
 }
 
 impl AsRef<str> for URLCellData {
@@ -52,6 +84,35 @@ impl URLCellData {
   pub fn new(s: &str) -> Self {
     Self {
       data: s.to_string(),
+      ..Default::default()
+    }
+  }
+
+  pub fn with_metadata(mut self, metadata: URLMetadata, verified_at: i64) -> Self {
+    self.title = metadata.title;
+    self.favicon = metadata.favicon;
+    self.last_verified_at = Some(verified_at);
+    self
+  }
+
+  /// Return the metadata of the link, None if it was never fetched.
+  pub fn metadata(&self) -> Option<URLMetadata> {
+    self.last_verified_at?;
+    Some(URLMetadata {
+      title: self.title.clone(),
+      favicon: self.favicon.clone(),
+    })
+  }
+
+  /// Return true if the metadata of the link was never fetched or was fetched more than
+  /// `max_age` seconds before `now`. An empty cell has nothing to fetch.
+  pub fn needs_enrichment(&self, now: i64, max_age: i64) -> bool {
+    if self.data.trim().is_empty() {
+      return false;
+    }
+    match self.last_verified_at {
+      None => true,
+      Some(verified_at) => now - verified_at > max_age,
     }
   }
 
@@ -64,14 +125,31 @@ impl From<&Cell> for URLCellData {
   fn from(cell: &Cell) -> Self {
     Self {
       data: cell.get_as(CELL_DATA).unwrap_or_default(),
+      title: cell.get_as(URL_TITLE),
+      favicon: cell.get_as(URL_FAVICON),
+      last_verified_at: cell.get_as(URL_LAST_VERIFIED_AT),
     }
   }
 }
 
 impl From<URLCellData> for Cell {
+  /// The metadata keys are always written, so the metadata of the previous link is cleared when
+  /// the link of an existing cell is replaced.
   fn from(data: URLCellData) -> Self {
     let mut cell = new_cell_builder(FieldType::URL);
     cell.insert(CELL_DATA.into(), data.data.into());
+    cell.insert(
+      URL_TITLE.into(),
+      data.title.map(Any::from).unwrap_or(Any::Null),
+    );
+    cell.insert(
+      URL_FAVICON.into(),
+      data.favicon.map(Any::from).unwrap_or(Any::Null),
+    );
+    cell.insert(
+      URL_LAST_VERIFIED_AT.into(),
+      data.last_verified_at.map(Any::BigInt).unwrap_or(Any::Null),
+    );
     cell
   }
 }
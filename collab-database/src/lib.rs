@@ -1,4 +1,6 @@
 pub mod database;
+pub mod database_compute;
+pub mod database_journal;
 pub mod fields;
 pub mod meta;
 pub mod rows;
@@ -8,8 +10,18 @@ pub mod workspace_database;
 #[macro_use]
 mod macros;
 pub mod blocks;
+pub mod csv;
+pub mod database_awareness;
+mod database_backlink;
+pub mod database_diff;
+mod database_search;
 pub mod database_state;
+pub mod database_statistics;
+pub mod database_view_rows;
+pub mod document_task;
 pub mod entity;
 pub mod error;
 pub mod template;
+#[cfg(feature = "test_utils")]
+pub mod test_utils;
 pub mod util;
@@ -224,3 +224,22 @@ macro_rules! impl_array_update {
     }
   };
 }
+
+/// Records the values of the fields of the current span. The fields must be declared by the
+/// span, usually as `tracing::field::Empty`. The definition is chosen by the `trace_spans`
+/// feature of this crate, so the crates that call the macro don't need the feature.
+#[cfg(feature = "trace_spans")]
+#[macro_export]
+macro_rules! record_span {
+  ($($field: ident = $value: expr),+ $(,)?) => {{
+    let span = tracing::Span::current();
+    $(span.record(stringify!($field), $value);)+
+  }};
+}
+
+/// Expands to nothing without the `trace_spans` feature, the values are not evaluated.
+#[cfg(not(feature = "trace_spans"))]
+#[macro_export]
+macro_rules! record_span {
+  ($($field: ident = $value: expr),+ $(,)?) => {{}};
+}
@@ -1,8 +1,11 @@
-use collab::preclude::{Any, Map, MapRef, ReadTxn, TransactionMut};
+use collab::preclude::{Any, Map, MapExt, MapRef, ReadTxn, TransactionMut};
 use collab_entity::define::DATABASE_INLINE_VIEW;
 use std::ops::Deref;
 use tracing::error;
 
+/// The number of the last row numbered by [MetaMap::next_row_number].
+const ROW_NUMBER: &str = "row_number";
+
 pub struct MetaMap {
   container: MapRef,
 }
@@ -34,6 +37,19 @@ impl MetaMap {
       },
     }
   }
+
+  /// Return the number of the next row, in the order the rows are created. The first number
+  /// follows the `num_of_rows` rows that existed before the rows were numbered, and the numbers
+  /// of the removed rows are not reused.
+  pub(crate) fn next_row_number(&self, txn: &mut TransactionMut, num_of_rows: usize) -> i64 {
+    let last = self
+      .container
+      .get_with_txn::<_, i64>(txn, ROW_NUMBER)
+      .unwrap_or(num_of_rows as i64);
+    let next = last + 1;
+    self.container.insert(txn, ROW_NUMBER, next);
+    next
+  }
 }
 
 impl Deref for MetaMap {
@@ -1,10 +1,16 @@
 use std::collections::HashMap;
 use std::ops::Deref;
 
+use std::sync::Arc;
+
+use collab::core::clock::{system_clock, ClockProvider};
 use collab::preclude::{Any, FillRef, Map, MapRef, TransactionMut};
 use collab::util::AnyMapExt;
+use tracing::warn;
 
-use crate::database::timestamp;
+use crate::fields::checklist_type_option::clear_checklist_keys;
+use crate::fields::contact_type_option::ContactValidators;
+use crate::fields::relation_type_option::clear_relation_keys;
 use crate::rows::{RowId, CREATED_AT, LAST_MODIFIED};
 use crate::template::entity::CELL_DATA;
 
@@ -13,21 +19,54 @@ pub type Cells = HashMap<String, Cell>;
 pub struct CellsUpdate<'a, 'b> {
   map_ref: &'a MapRef,
   txn: &'a mut TransactionMut<'b>,
+  clock: Arc<dyn ClockProvider>,
+  validators: Arc<ContactValidators>,
 }
 
 impl<'a, 'b> CellsUpdate<'a, 'b> {
   pub fn new(txn: &'a mut TransactionMut<'b>, map_ref: &'a MapRef) -> Self {
-    Self { map_ref, txn }
+    Self {
+      map_ref,
+      txn,
+      clock: system_clock(),
+      validators: Default::default(),
+    }
+  }
+
+  /// Set the clock that stamps the created and modified times of the cells.
+  pub fn with_clock(mut self, clock: Arc<dyn ClockProvider>) -> Self {
+    self.clock = clock;
+    self
+  }
+
+  /// Set the validators of the cells inserted with [CellsUpdate::insert_cell].
+  pub fn with_validators(mut self, validators: Arc<ContactValidators>) -> Self {
+    self.validators = validators;
+    self
   }
 
+  /// Insert the cell, replacing the existing one. An email or a phone cell that is not valid for
+  /// its field is not written, see [ContactValidators].
   pub fn insert_cell(self, key: &str, cell: Cell) -> Self {
+    let cell = match self.validators.validate(key, cell) {
+      Ok(cell) => cell,
+      Err(err) => {
+        warn!("skip the invalid cell of the field {}: {}", key, err);
+        return self;
+      },
+    };
     let cell_map_ref: MapRef = self.map_ref.get_or_init(self.txn, key);
+    let timestamp = self.clock.timestamp();
     if cell_map_ref.get(self.txn, CREATED_AT).is_none() {
-      cell_map_ref.insert(self.txn, CREATED_AT, Any::BigInt(timestamp()));
+      cell_map_ref.insert(self.txn, CREATED_AT, Any::BigInt(timestamp));
     }
 
+    // The cell is written as a whole, so the options of a checklist and the links of a relation
+    // stored in their own keys are replaced by the ones of the new cell.
+    clear_checklist_keys(self.txn, &cell_map_ref);
+    clear_relation_keys(self.txn, &cell_map_ref);
     Any::from(cell).fill(self.txn, &cell_map_ref).unwrap();
-    cell_map_ref.insert(self.txn, LAST_MODIFIED, Any::BigInt(timestamp()));
+    cell_map_ref.insert(self.txn, LAST_MODIFIED, Any::BigInt(timestamp));
     self
   }
 
@@ -38,6 +77,22 @@ impl<'a, 'b> CellsUpdate<'a, 'b> {
     self.insert_cell(key, cell)
   }
 
+  /// Modify the keys of the cell in place. Unlike [Self::insert_cell], the keys that are not
+  /// modified keep the changes made concurrently by the other clients.
+  pub fn update_cell<F>(self, key: &str, f: F) -> Self
+  where
+    F: FnOnce(&mut TransactionMut, &MapRef),
+  {
+    let cell_map_ref: MapRef = self.map_ref.get_or_init(self.txn, key);
+    let timestamp = self.clock.timestamp();
+    if cell_map_ref.get(self.txn, CREATED_AT).is_none() {
+      cell_map_ref.insert(self.txn, CREATED_AT, Any::BigInt(timestamp));
+    }
+    f(self.txn, &cell_map_ref);
+    cell_map_ref.insert(self.txn, LAST_MODIFIED, Any::BigInt(timestamp));
+    self
+  }
+
   pub fn clear(self, key: &str) -> Self {
     let cell_map_ref: MapRef = self.map_ref.get_or_init(self.txn, key);
     cell_map_ref.clear(self.txn);
@@ -1,13 +1,70 @@
-use collab::preclude::Any;
+use collab::preclude::encoding::serde::from_any;
+use collab::preclude::{
+  Any, Array, ArrayRef, DeepObservable, EntryChange, Event, Map, MapExt, MapPrelim, MapRef,
+  PathSegment, ReadTxn, ToJson, TransactionMut, YrsValue,
+};
 use collab::util::deserialize_i64_from_numeric;
+use collab_entity::define::DATABASE_ROW_DATA;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::rows::{row_from_map_ref, RowChange, RowChangeSender, RowId};
+
+/// The key of the map of the comments in the row collab. The key of an entry is the id of the
+/// comment and its value a map with the fields of the [RowComment].
+/// The map is created by the first write, the rows without comments don't have it.
+pub const ROW_COMMENTS: &str = "comments";
+/// The array the comments were stored in before [ROW_COMMENTS]. Its comments are moved to the
+/// map by the first write.
+const LEGACY_ROW_COMMENTS: &str = "comment";
+
+const COMMENT_ID: &str = "id";
+const COMMENT_UID: &str = "uid";
+const COMMENT_CONTENT: &str = "content";
+const COMMENT_CREATED_AT: &str = "created_at";
+const COMMENT_UPDATED_AT: &str = "updated_at";
+const COMMENT_REPLY_TO: &str = "reply_to";
+const COMMENT_RESOLVED: &str = "resolved";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RowComment {
-  uid: i64,
-  content: String,
+  /// Empty for the comments of the legacy array, see [LEGACY_ROW_COMMENTS].
+  #[serde(default)]
+  pub id: String,
+  /// The user that wrote the comment.
   #[serde(deserialize_with = "deserialize_i64_from_numeric")]
-  created_at: i64,
+  pub uid: i64,
+  pub content: String,
+  #[serde(deserialize_with = "deserialize_i64_from_numeric")]
+  pub created_at: i64,
+  /// The last time the content was edited, or the creation time if it never was.
+  #[serde(default, deserialize_with = "deserialize_i64_from_numeric")]
+  pub updated_at: i64,
+  /// The id of the comment this one replies to. A reply can't be replied to, the replies of a
+  /// thread all point to its first comment.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub reply_to: Option<String>,
+  /// Only the first comment of a thread is resolved.
+  #[serde(default)]
+  pub resolved: bool,
+}
+
+impl RowComment {
+  pub fn new(uid: i64, content: String, created_at: i64) -> Self {
+    Self {
+      id: uuid::Uuid::new_v4().to_string(),
+      uid,
+      content,
+      created_at,
+      updated_at: created_at,
+      reply_to: None,
+      resolved: false,
+    }
+  }
+
+  pub fn with_reply_to(mut self, reply_to: String) -> Self {
+    self.reply_to = Some(reply_to);
+    self
+  }
 }
 
 impl TryFrom<Any> for RowComment {
@@ -27,3 +84,281 @@ impl From<RowComment> for Any {
     Any::from_json(&json).unwrap()
   }
 }
+
+/// A change of the comments of a row, local or remote, sent with
+/// [RowChange::DidChangeRowComment].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RowCommentChange {
+  Created(RowComment),
+  /// The content of the comment was edited or its thread was resolved.
+  Updated(RowComment),
+  Deleted {
+    comment_id: String,
+  },
+}
+
+/// The comments of a row. Each comment is a map, so editing a comment and resolving it at the
+/// same time on two clients keeps both changes.
+#[derive(Clone)]
+pub struct RowComments {
+  row_id: RowId,
+  /// The root map of the row collab, the parent of the [ROW_COMMENTS] map.
+  root: MapRef,
+}
+
+impl RowComments {
+  pub fn new(row_id: RowId, root: MapRef) -> Self {
+    Self { row_id, root }
+  }
+
+  pub fn insert_comment_with_txn(&self, txn: &mut TransactionMut, comment: RowComment) {
+    let container = self.get_or_init_container(txn);
+    insert_comment(txn, &container, comment);
+  }
+
+  pub fn get_comment_with_txn<T: ReadTxn>(&self, txn: &T, comment_id: &str) -> Option<RowComment> {
+    match self.container(txn) {
+      Some(container) => {
+        let map_ref: MapRef = container.get_with_txn(txn, comment_id)?;
+        comment_from_map_ref(txn, &map_ref)
+      },
+      None => self
+        .legacy_comments(txn)
+        .into_iter()
+        .find(|comment| comment.id == comment_id),
+    }
+  }
+
+  /// Return all the comments, the oldest first.
+  pub fn get_all_comments_with_txn<T: ReadTxn>(&self, txn: &T) -> Vec<RowComment> {
+    let mut comments = match self.container(txn) {
+      Some(container) => container
+        .iter(txn)
+        .filter_map(|(_, value)| match value {
+          YrsValue::YMap(map_ref) => comment_from_map_ref(txn, &map_ref),
+          _ => None,
+        })
+        .collect::<Vec<_>>(),
+      None => self.legacy_comments(txn),
+    };
+    comments.sort_by(|a, b| (a.created_at, &a.id).cmp(&(b.created_at, &b.id)));
+    comments
+  }
+
+  /// Replace the content of the comment. Return false if the comment is not found.
+  pub fn update_content_with_txn(
+    &self,
+    txn: &mut TransactionMut,
+    comment_id: &str,
+    content: String,
+    updated_at: i64,
+  ) -> bool {
+    match self.get_comment_map_mut(txn, comment_id) {
+      None => false,
+      Some(map_ref) => {
+        map_ref.insert(txn, COMMENT_CONTENT, content);
+        map_ref.insert(txn, COMMENT_UPDATED_AT, Any::BigInt(updated_at));
+        true
+      },
+    }
+  }
+
+  /// Return false if the comment is not found.
+  pub fn set_resolved_with_txn(
+    &self,
+    txn: &mut TransactionMut,
+    comment_id: &str,
+    resolved: bool,
+  ) -> bool {
+    match self.get_comment_map_mut(txn, comment_id) {
+      None => false,
+      Some(map_ref) => {
+        map_ref.insert(txn, COMMENT_RESOLVED, resolved);
+        true
+      },
+    }
+  }
+
+  /// Remove the comment and its replies. Return the ids of the removed comments.
+  pub fn remove_comment_with_txn(&self, txn: &mut TransactionMut, comment_id: &str) -> Vec<String> {
+    if self.get_comment_map_mut(txn, comment_id).is_none() {
+      return vec![];
+    }
+    let mut removed = vec![comment_id.to_string()];
+    removed.extend(
+      self
+        .get_all_comments_with_txn(txn)
+        .into_iter()
+        .filter(|comment| comment.reply_to.as_deref() == Some(comment_id))
+        .map(|comment| comment.id),
+    );
+    if let Some(container) = self.container(txn) {
+      for id in &removed {
+        container.remove(txn, id);
+      }
+    }
+    removed
+  }
+
+  fn container<T: ReadTxn>(&self, txn: &T) -> Option<MapRef> {
+    self.root.get_with_txn(txn, ROW_COMMENTS)
+  }
+
+  /// Return the map of the comment, moving the legacy comments to the [ROW_COMMENTS] map first
+  /// if the comment is one of them.
+  fn get_comment_map_mut(&self, txn: &mut TransactionMut, comment_id: &str) -> Option<MapRef> {
+    let container = match self.container(txn) {
+      Some(container) => container,
+      None => {
+        self.get_comment_with_txn(txn, comment_id)?;
+        self.get_or_init_container(txn)
+      },
+    };
+    container.get_with_txn(txn, comment_id)
+  }
+
+  fn get_or_init_container(&self, txn: &mut TransactionMut) -> MapRef {
+    if let Some(container) = self.container(txn) {
+      return container;
+    }
+    let legacy_comments = self.legacy_comments(txn);
+    let container: MapRef = self.root.insert(txn, ROW_COMMENTS, MapPrelim::default());
+    for comment in legacy_comments {
+      insert_comment(txn, &container, comment);
+    }
+    self.root.remove(txn, LEGACY_ROW_COMMENTS);
+    container
+  }
+
+  /// The legacy comments don't have an id, it's derived from their position so that the clients
+  /// that move them to the [ROW_COMMENTS] map at the same time agree on it.
+  fn legacy_comments<T: ReadTxn>(&self, txn: &T) -> Vec<RowComment> {
+    let array: ArrayRef = match self.root.get_with_txn(txn, LEGACY_ROW_COMMENTS) {
+      Some(array) => array,
+      None => return vec![],
+    };
+    array
+      .iter(txn)
+      .enumerate()
+      .filter_map(|(index, value)| {
+        let mut comment = RowComment::try_from(value.to_json(txn)).ok()?;
+        if comment.id.is_empty() {
+          comment.id = format!("{}-{}", self.row_id, index);
+        }
+        if comment.updated_at == 0 {
+          comment.updated_at = comment.created_at;
+        }
+        Some(comment)
+      })
+      .collect()
+  }
+}
+
+fn insert_comment(txn: &mut TransactionMut, container: &MapRef, comment: RowComment) {
+  let map_ref: MapRef = container.insert(txn, comment.id.as_str(), MapPrelim::default());
+  map_ref.insert(txn, COMMENT_ID, comment.id);
+  map_ref.insert(txn, COMMENT_UID, Any::BigInt(comment.uid));
+  map_ref.insert(txn, COMMENT_CONTENT, comment.content);
+  map_ref.insert(txn, COMMENT_CREATED_AT, Any::BigInt(comment.created_at));
+  map_ref.insert(txn, COMMENT_UPDATED_AT, Any::BigInt(comment.updated_at));
+  if let Some(reply_to) = comment.reply_to {
+    map_ref.insert(txn, COMMENT_REPLY_TO, reply_to);
+  }
+  map_ref.insert(txn, COMMENT_RESOLVED, comment.resolved);
+}
+
+fn comment_from_map_ref<T: ReadTxn>(txn: &T, map_ref: &MapRef) -> Option<RowComment> {
+  from_any(&map_ref.to_json(txn)).ok()
+}
+
+fn comments_from_map_ref<T: ReadTxn>(txn: &T, map_ref: &MapRef) -> Vec<RowComment> {
+  map_ref
+    .iter(txn)
+    .filter_map(|(_, value)| match value {
+      YrsValue::YMap(map_ref) => comment_from_map_ref(txn, &map_ref),
+      _ => None,
+    })
+    .collect()
+}
+
+/// Send the changes of the comments of the row to the change_tx, whether they are local or
+/// remote. Each change is sent with [RowChange::DidChangeRowComment], and the row is sent once
+/// per transaction with [RowChange::DidUpdateRowComment].
+pub(crate) fn subscribe_row_comment_change(
+  row_id: RowId,
+  comments: &RowComments,
+  change_tx: RowChangeSender,
+) {
+  // The comments map may not exist yet, so the root map is observed.
+  let root = comments.root.clone();
+  comments
+    .root
+    .observe_deep_with("row_comments", move |txn, events| {
+      let mut changes = vec![];
+      for event in events.iter() {
+        let map_event = match event {
+          Event::Map(map_event) => map_event,
+          _ => continue,
+        };
+        let path = event.path();
+        let mut segments = path.iter();
+        match (segments.next(), segments.next(), segments.next()) {
+          // The comments map was created, by the first comment or the migration of the legacy
+          // comments.
+          (None, _, _) => {
+            if let Some(EntryChange::Inserted(YrsValue::YMap(map_ref))) =
+              map_event.keys(txn).get(ROW_COMMENTS)
+            {
+              changes.extend(
+                comments_from_map_ref(txn, map_ref)
+                  .into_iter()
+                  .map(RowCommentChange::Created),
+              );
+            }
+          },
+          // An entry of the comments map was inserted or removed.
+          (Some(PathSegment::Key(key)), None, _) if key.as_ref() == ROW_COMMENTS => {
+            for (key, entry_change) in map_event.keys(txn).iter() {
+              match entry_change {
+                EntryChange::Inserted(YrsValue::YMap(map_ref)) => {
+                  if let Some(comment) = comment_from_map_ref(txn, map_ref) {
+                    changes.push(RowCommentChange::Created(comment));
+                  }
+                },
+                EntryChange::Removed(_) => changes.push(RowCommentChange::Deleted {
+                  comment_id: key.to_string(),
+                }),
+                _ => {},
+              }
+            }
+          },
+          // A field of a comment was updated.
+          (Some(PathSegment::Key(key)), Some(PathSegment::Key(_)), None)
+            if key.as_ref() == ROW_COMMENTS =>
+          {
+            if let YrsValue::YMap(map_ref) = event.target() {
+              if let Some(comment) = comment_from_map_ref(txn, &map_ref) {
+                changes.push(RowCommentChange::Updated(comment));
+              }
+            }
+          },
+          _ => {},
+        }
+      }
+      if changes.is_empty() {
+        return;
+      }
+      for change in changes {
+        let _ = change_tx.send(RowChange::DidChangeRowComment {
+          row_id: row_id.clone(),
+          change,
+        });
+      }
+      let row = root
+        .get_with_txn::<_, MapRef>(txn, DATABASE_ROW_DATA)
+        .and_then(|data| row_from_map_ref(&data, txn));
+      if let Some(row) = row {
+        let _ = change_tx.send(RowChange::DidUpdateRowComment { row });
+      }
+    });
+}
@@ -1,12 +1,20 @@
 pub use cell::*;
 pub use comment::*;
 pub use row::*;
+pub use row_creation_hook::*;
+pub use row_document::*;
 pub use row_id::*;
 pub use row_meta::*;
 pub use row_observer::*;
+pub use row_page::*;
+pub(crate) use row_watcher::*;
 mod cell;
 mod comment;
 mod row;
+mod row_creation_hook;
+mod row_document;
 mod row_id;
 mod row_meta;
 mod row_observer;
+mod row_page;
+mod row_watcher;
@@ -1,28 +1,33 @@
 use collab::preclude::{
-  Any, ArrayRef, Collab, FillRef, Map, MapExt, MapRef, ReadTxn, ToJson, TransactionMut, YrsValue,
+  Any, Collab, DeepObservable, FillRef, Map, MapExt, MapRef, ReadTxn, ToJson, TransactionMut,
+  YrsValue,
 };
 use std::borrow::{Borrow, BorrowMut};
 use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
+use collab::core::clock::{system_clock, ClockProvider};
+use collab::lock::RwLock;
 use collab::preclude::encoding::serde::from_any;
 use collab::util::AnyExt;
 use collab_entity::define::DATABASE_ROW_DATA;
 use collab_entity::CollabType;
 
-use crate::database::timestamp;
+use crate::database::{stamp_unset_timestamps, timestamp};
 
 use crate::error::DatabaseError;
+use crate::fields::contact_type_option::ContactValidators;
+use crate::fields::CellRules;
 use crate::rows::{
-  subscribe_row_data_change, Cell, Cells, CellsUpdate, RowChangeSender, RowId, RowMeta,
-  RowMetaUpdate,
+  subscribe_row_comment_change, subscribe_row_data_change, Cell, Cells, CellsUpdate,
+  RowChangeSender, RowComment, RowComments, RowId, RowMeta, RowMetaUpdate,
 };
 
 use crate::util::encoded_collab;
-use crate::views::{OrderObjectPosition, RowOrder};
+use crate::views::{FieldOrder, OrderObjectPosition, RowOrder};
 use crate::workspace_database::DatabaseCollabService;
 use crate::{impl_bool_update, impl_i32_update, impl_i64_update};
 use collab::core::origin::CollabOrigin;
@@ -34,7 +39,6 @@ use uuid::Uuid;
 pub type BlockId = i64;
 
 const META: &str = "meta";
-const COMMENT: &str = "comment";
 pub const LAST_MODIFIED: &str = "last_modified";
 pub const CREATED_AT: &str = "created_at";
 
@@ -43,11 +47,31 @@ pub struct DatabaseRow {
   pub collab: Collab,
   pub body: DatabaseRowBody,
   collab_service: Arc<dyn DatabaseCollabService>,
+  clock: Arc<dyn ClockProvider>,
+  cell_rules: Arc<CellRules>,
 }
 
 pub fn default_database_row_data(row_id: &RowId, row: Row) -> EncodedCollab {
+  database_row_data_with_meta(row_id, row, None)
+}
+
+/// Same as [default_database_row_data], with the meta of the row in the initial state, so the row
+/// is never seen without its meta. The meta of a row whose id is not a uuid is ignored.
+pub(crate) fn database_row_data_with_meta(
+  row_id: &RowId,
+  row: Row,
+  meta: Option<RowMeta>,
+) -> EncodedCollab {
   let mut collab = Collab::new_with_origin(CollabOrigin::Empty, row_id, vec![], false);
-  let _ = DatabaseRowBody::create(row_id.clone(), &mut collab, row);
+  let body = DatabaseRowBody::create(row_id.clone(), &mut collab, row);
+  if let (Some(meta), Ok(uuid)) = (meta, Uuid::parse_str(row_id)) {
+    let mut txn = collab.context.transact_mut();
+    RowMetaUpdate::new(&mut txn, body.meta.clone(), uuid)
+      .insert_icon_if_not_none(meta.icon_url)
+      .insert_cover_if_not_none(meta.cover)
+      .update_is_document_empty(meta.is_document_empty)
+      .update_attachment_count(meta.attachment_count);
+  }
   collab
     .encode_collab_v1(|_collab| Ok::<_, DatabaseError>(()))
     .unwrap()
@@ -69,13 +93,21 @@ impl DatabaseRow {
   ) -> Result<Self, DatabaseError> {
     let body = DatabaseRowBody::open(row_id.clone(), &mut collab)?;
     if let Some(change_tx) = change_tx {
-      subscribe_row_data_change(row_id.clone(), &body.data, change_tx);
+      subscribe_row_data_change(
+        collab.origin().clone(),
+        row_id.clone(),
+        &body.data,
+        change_tx.clone(),
+      );
+      subscribe_row_comment_change(row_id.clone(), &body.comments, change_tx);
     }
     Ok(Self {
       row_id,
       collab,
       body,
       collab_service,
+      clock: system_clock(),
+      cell_rules: Default::default(),
     })
   }
 
@@ -88,16 +120,36 @@ impl DatabaseRow {
   ) -> Self {
     let body = DatabaseRowBody::create(row_id.clone(), &mut collab, row);
     if let Some(change_tx) = change_tx {
-      subscribe_row_data_change(row_id.clone(), &body.data, change_tx);
+      subscribe_row_data_change(
+        collab.origin().clone(),
+        row_id.clone(),
+        &body.data,
+        change_tx.clone(),
+      );
+      subscribe_row_comment_change(row_id.clone(), &body.comments, change_tx);
     }
     Self {
       row_id,
       collab,
       body,
       collab_service,
+      clock: system_clock(),
+      cell_rules: Default::default(),
     }
   }
 
+  /// Set the clock that stamps the cells updated with [DatabaseRow::update].
+  pub fn with_clock(mut self, clock: Arc<dyn ClockProvider>) -> Self {
+    self.clock = clock;
+    self
+  }
+
+  /// Set the rules of the cells updated with [DatabaseRow::update], see [CellRules].
+  pub fn with_cell_rules(mut self, cell_rules: Arc<CellRules>) -> Self {
+    self.cell_rules = cell_rules;
+    self
+  }
+
   pub fn encoded_collab(&self) -> Result<EncodedCollab, DatabaseError> {
     let row_encoded = encoded_collab(&self.collab, &CollabType::DatabaseRow)?;
     Ok(row_encoded)
@@ -152,6 +204,8 @@ impl DatabaseRow {
     cell_from_map_ref(&self.body.data, &txn, field_id)
   }
 
+  /// Update the row, then refresh the progress cells derived from its checklist cells in the same
+  /// transaction. The email and phone cells are checked by the validators, see [CellRules].
   pub fn update<F>(&mut self, f: F)
   where
     F: FnOnce(RowUpdate),
@@ -159,9 +213,22 @@ impl DatabaseRow {
     let data = self.body.data.clone();
     let meta = self.body.meta.clone();
     let mut txn = self.collab.transact_mut();
-    let update = RowUpdate::new(&mut txn, data.clone(), meta);
+    let update = RowUpdate::new(&mut txn, data.clone(), meta)
+      .with_clock(self.clock.clone())
+      .with_validators(self.cell_rules.validators());
     f(update);
 
+    let derived_cells = self.cell_rules.outdated_derived_cells(&txn, &data);
+    if !derived_cells.is_empty() {
+      RowUpdate::new(&mut txn, data.clone(), self.body.meta.clone())
+        .with_clock(self.clock.clone())
+        .update_cells(|mut cells| {
+          for (field_id, cell) in derived_cells {
+            cells = cells.insert_cell(&field_id, cell);
+          }
+        });
+    }
+
     // updates the row_id in case it has changed
     if let Some(row_id) = row_id_from_map_ref(&txn, &data) {
       self.body.row_id = row_id.clone();
@@ -184,6 +251,81 @@ impl DatabaseRow {
     }
   }
 
+  /// Return the comments of the row, the oldest first.
+  pub fn get_comments(&self) -> Vec<RowComment> {
+    let txn = self.collab.transact();
+    self.body.comments.get_all_comments_with_txn(&txn)
+  }
+
+  pub fn get_comment(&self, comment_id: &str) -> Option<RowComment> {
+    let txn = self.collab.transact();
+    self.body.comments.get_comment_with_txn(&txn, comment_id)
+  }
+
+  /// Add a comment written by the user. A reply to a reply is added to the thread of the comment
+  /// it replies to.
+  pub fn create_comment(
+    &mut self,
+    uid: i64,
+    content: String,
+    reply_to: Option<&str>,
+  ) -> Result<RowComment, DatabaseError> {
+    let comments = self.body.comments.clone();
+    let mut comment = RowComment::new(uid, content, self.clock.timestamp());
+    let mut txn = self.collab.transact_mut();
+    if let Some(reply_to) = reply_to {
+      let parent = comments
+        .get_comment_with_txn(&txn, reply_to)
+        .ok_or(DatabaseError::RecordNotFound)?;
+      comment = comment.with_reply_to(parent.reply_to.unwrap_or(parent.id));
+    }
+    comments.insert_comment_with_txn(&mut txn, comment.clone());
+    Ok(comment)
+  }
+
+  pub fn edit_comment(
+    &mut self,
+    comment_id: &str,
+    content: String,
+  ) -> Result<RowComment, DatabaseError> {
+    let comments = self.body.comments.clone();
+    let updated_at = self.clock.timestamp();
+    let mut txn = self.collab.transact_mut();
+    if !comments.update_content_with_txn(&mut txn, comment_id, content, updated_at) {
+      return Err(DatabaseError::RecordNotFound);
+    }
+    comments
+      .get_comment_with_txn(&txn, comment_id)
+      .ok_or(DatabaseError::RecordNotFound)
+  }
+
+  /// Resolve or reopen the thread of the comment. Return the first comment of the thread.
+  pub fn resolve_comment(
+    &mut self,
+    comment_id: &str,
+    resolved: bool,
+  ) -> Result<RowComment, DatabaseError> {
+    let comments = self.body.comments.clone();
+    let mut txn = self.collab.transact_mut();
+    let comment = comments
+      .get_comment_with_txn(&txn, comment_id)
+      .ok_or(DatabaseError::RecordNotFound)?;
+    let thread_id = comment.reply_to.unwrap_or(comment.id);
+    if !comments.set_resolved_with_txn(&mut txn, &thread_id, resolved) {
+      return Err(DatabaseError::RecordNotFound);
+    }
+    comments
+      .get_comment_with_txn(&txn, &thread_id)
+      .ok_or(DatabaseError::RecordNotFound)
+  }
+
+  /// Delete the comment and its replies. Return the ids of the deleted comments.
+  pub fn delete_comment(&mut self, comment_id: &str) -> Vec<String> {
+    let comments = self.body.comments.clone();
+    let mut txn = self.collab.transact_mut();
+    comments.remove_comment_with_txn(&mut txn, comment_id)
+  }
+
   pub fn delete(&self) {
     match self.collab_service.persistence() {
       None => {
@@ -225,13 +367,50 @@ impl BorrowMut<Collab> for DatabaseRow {
   }
 }
 
+/// Refresh the progress cells of the row when a change made outside of [DatabaseRow::update],
+/// like a remote update, leaves them out of date with its checklist cells, see [CellRules]. The
+/// cells can't be written while the change is observed, so they are refreshed by a new task.
+pub(crate) fn subscribe_derived_cells(database_row: &Arc<RwLock<DatabaseRow>>) {
+  let row = match database_row.try_read() {
+    Ok(row) => row,
+    Err(_) => return,
+  };
+  let data = row.body.data.clone();
+  let cell_rules = row.cell_rules.clone();
+  let weak_row = Arc::downgrade(database_row);
+  let is_outdated = {
+    let txn = row.collab.transact();
+    !cell_rules.outdated_derived_cells(&txn, &data).is_empty()
+  };
+  if is_outdated {
+    refresh_derived_cells(weak_row.clone());
+  }
+  row
+    .body
+    .data
+    .observe_deep_with("derived_cells", move |txn, _| {
+      if cell_rules.has_derivations() && !cell_rules.outdated_derived_cells(txn, &data).is_empty() {
+        refresh_derived_cells(weak_row.clone());
+      }
+    });
+}
+
+fn refresh_derived_cells(database_row: Weak<RwLock<DatabaseRow>>) {
+  if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+    runtime.spawn(async move {
+      if let Some(database_row) = database_row.upgrade() {
+        database_row.write().await.update(|_| {});
+      }
+    });
+  }
+}
+
 pub struct DatabaseRowBody {
   row_id: RowId,
   data: MapRef,
   #[allow(dead_code)]
   meta: MapRef,
-  #[allow(dead_code)]
-  comments: ArrayRef,
+  comments: RowComments,
 }
 
 impl DatabaseRowBody {
@@ -248,7 +427,6 @@ impl DatabaseRowBody {
     let mut txn = collab.context.transact_mut();
     let data: MapRef = collab.data.get_or_init(&mut txn, DATABASE_ROW_DATA);
     let meta: MapRef = collab.data.get_or_init(&mut txn, META);
-    let comments: ArrayRef = collab.data.get_or_init(&mut txn, COMMENT);
     if let Some(row) = row {
       RowBuilder::new(&mut txn, data.clone(), meta.clone())
         .update(|update| {
@@ -264,6 +442,7 @@ impl DatabaseRowBody {
         .done();
     }
 
+    let comments = RowComments::new(row_id.clone(), collab.data.clone());
     DatabaseRowBody {
       row_id,
       data,
@@ -336,6 +515,10 @@ impl DatabaseRowBody {
   pub fn get_meta(&self) -> &MapRef {
     &self.meta
   }
+
+  pub fn get_comments(&self) -> &RowComments {
+    &self.comments
+  }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -450,6 +633,21 @@ impl Row {
     self.cells.is_empty()
   }
 
+  /// Return the cell of the given field. The [Cells] are keyed by field id, so the lookup doesn't
+  /// go through the yrs map of the row.
+  pub fn get_cell(&self, field_id: &str) -> Option<&Cell> {
+    self.cells.get(field_id)
+  }
+
+  /// Return the cells in the order of the given fields, for example the [FieldOrder]s of a grid
+  /// view. A field without a cell in this row gets `None`.
+  pub fn cells_in_field_order(&self, field_orders: &[FieldOrder]) -> Vec<Option<&Cell>> {
+    field_orders
+      .iter()
+      .map(|field_order| self.get_cell(&field_order.id))
+      .collect()
+  }
+
   pub fn document_id(&self) -> String {
     meta_id_from_meta_type(self.id.as_str(), RowMetaKey::DocumentId)
   }
@@ -514,6 +712,8 @@ pub struct RowUpdate<'a, 'b> {
   map_ref: MapRef,
   meta_ref: MapRef,
   txn: &'a mut TransactionMut<'b>,
+  clock: Arc<dyn ClockProvider>,
+  validators: Arc<ContactValidators>,
 }
 
 impl<'a, 'b> RowUpdate<'a, 'b> {
@@ -522,9 +722,23 @@ impl<'a, 'b> RowUpdate<'a, 'b> {
       map_ref,
       txn,
       meta_ref,
+      clock: system_clock(),
+      validators: Default::default(),
     }
   }
 
+  /// Set the clock that stamps the cells updated with [RowUpdate::update_cells].
+  pub fn with_clock(mut self, clock: Arc<dyn ClockProvider>) -> Self {
+    self.clock = clock;
+    self
+  }
+
+  /// Set the validators of the cells updated with [RowUpdate::update_cells].
+  pub fn with_validators(mut self, validators: Arc<ContactValidators>) -> Self {
+    self.validators = validators;
+    self
+  }
+
   impl_bool_update!(set_visibility, set_visibility_if_not_none, ROW_VISIBILITY);
   impl_i32_update!(set_height, set_height_at_if_not_none, ROW_HEIGHT);
   impl_i64_update!(set_created_at, set_created_at_if_not_none, CREATED_AT);
@@ -592,7 +806,9 @@ impl<'a, 'b> RowUpdate<'a, 'b> {
     F: FnOnce(CellsUpdate),
   {
     let cell_map: MapRef = self.map_ref.get_or_init(self.txn, ROW_CELLS);
-    let update = CellsUpdate::new(self.txn, &cell_map);
+    let update = CellsUpdate::new(self.txn, &cell_map)
+      .with_clock(self.clock.clone())
+      .with_validators(self.validators.clone());
     f(update);
     self
   }
@@ -673,26 +889,24 @@ pub struct CreateRowParams {
 pub(crate) struct CreateRowParamsValidator;
 
 impl CreateRowParamsValidator {
-  pub(crate) fn validate(mut params: CreateRowParams) -> Result<CreateRowParams, DatabaseError> {
+  pub(crate) fn validate(
+    mut params: CreateRowParams,
+    clock: &dyn ClockProvider,
+  ) -> Result<CreateRowParams, DatabaseError> {
     if params.id.is_empty() {
       return Err(DatabaseError::InvalidRowID("row_id is empty"));
     }
 
-    let timestamp = timestamp();
-    if params.created_at == 0 {
-      params.created_at = timestamp;
-    }
-    if params.modified_at == 0 {
-      params.modified_at = timestamp;
-    }
+    stamp_unset_timestamps(&mut params.created_at, &mut params.modified_at, clock);
 
     Ok(params)
   }
 }
 
 impl CreateRowParams {
+  /// The created and modified times are left unset, the database stamps them with its clock
+  /// when the row is created.
   pub fn new<T: Into<RowId>>(id: T, database_id: String) -> Self {
-    let timestamp = timestamp();
     Self {
       id: id.into(),
       database_id,
@@ -700,8 +914,8 @@ impl CreateRowParams {
       height: 60,
       visibility: true,
       row_position: OrderObjectPosition::default(),
-      created_at: timestamp,
-      modified_at: timestamp,
+      created_at: 0,
+      modified_at: 0,
     }
   }
 
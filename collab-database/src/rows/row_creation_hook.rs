@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use collab::preclude::Any;
+
+use crate::entity::FieldType;
+use crate::fields::select_type_option::SelectTypeOption;
+use crate::fields::Field;
+use crate::rows::{new_cell_builder, Cell, Cells, CreateRowParams};
+use crate::template::entity::CELL_DATA;
+
+/// What a [RowCreationHook] knows about the row being created.
+pub struct RowCreationContext<'a> {
+  pub field: &'a Field,
+  pub row: &'a CreateRowParams,
+  /// The number of rows of the database before the row is created.
+  pub num_of_rows: usize,
+  /// The number of the row in the order the rows of the database are created, see
+  /// [AutoNumberHook].
+  pub row_number: i64,
+}
+
+/// Contributes the cell of a field to the new rows, like the creation time of a created time
+/// field. Implement it to stamp the cells of a field type when the row is created instead of
+/// updating the row afterwards.
+pub trait RowCreationHook: Send + Sync {
+  /// Return the cell of the field for the new row, or None to leave it empty. It's only called
+  /// for the fields that have no cell in the [CreateRowParams].
+  fn create_cell(&self, context: &RowCreationContext) -> Option<Cell>;
+
+  /// Return true if the cell is unique to each row, like its creation time or its number. The
+  /// duplicate of a row gets a new cell from the hook instead of the cell of the source row.
+  fn is_unique_per_row(&self) -> bool {
+    false
+  }
+}
+
+/// Fills the cell of a [FieldType::CreatedTime] field with the creation time of the row.
+pub struct CreatedTimeHook;
+
+impl RowCreationHook for CreatedTimeHook {
+  fn create_cell(&self, context: &RowCreationContext) -> Option<Cell> {
+    let mut cell = new_cell_builder(FieldType::CreatedTime);
+    cell.insert(CELL_DATA.into(), context.row.created_at.to_string().into());
+    Some(cell)
+  }
+
+  fn is_unique_per_row(&self) -> bool {
+    true
+  }
+}
+
+/// Numbers the new rows in the order they are created. Unlike the position of the row, the number
+/// of a row is kept when the rows before it are removed, and the numbers of the removed rows are
+/// not reused. The rows that existed before the first numbered row are counted.
+///
+/// It's not a default hook, register it for the field type of the numbered field:
+/// `database.register_row_creation_hook(FieldType::Number, AutoNumberHook)`.
+pub struct AutoNumberHook;
+
+impl RowCreationHook for AutoNumberHook {
+  fn create_cell(&self, context: &RowCreationContext) -> Option<Cell> {
+    let mut cell = new_cell_builder(FieldType::from(context.field.field_type));
+    cell.insert(CELL_DATA.into(), context.row_number.to_string().into());
+    Some(cell)
+  }
+
+  fn is_unique_per_row(&self) -> bool {
+    true
+  }
+}
+
+/// Selects the [SelectTypeOption::default_option_id] of a select field, if it has one.
+pub struct DefaultSelectOptionHook;
+
+impl RowCreationHook for DefaultSelectOptionHook {
+  fn create_cell(&self, context: &RowCreationContext) -> Option<Cell> {
+    let field_type = FieldType::from(context.field.field_type);
+    let type_option = context
+      .field
+      .get_type_option::<SelectTypeOption>(field_type.type_id())?;
+    let option_id = type_option.default_option_id()?.to_string();
+    // An option that was removed from the field is not selected.
+    if !type_option
+      .options
+      .iter()
+      .any(|option| option.id == option_id)
+    {
+      return None;
+    }
+    let mut cell = new_cell_builder(field_type);
+    cell.insert(CELL_DATA.into(), Any::from(option_id));
+    Some(cell)
+  }
+}
+
+/// The [RowCreationHook]s of the field types, run by [crate::database::Database::create_row] and
+/// [crate::database::Database::create_row_in_view] before the row is created, so the cells are
+/// part of the initial state of the row.
+///
+/// The default hooks stamp the created time fields and select the default option of the select
+/// fields.
+#[derive(Clone)]
+pub struct RowCreationHooks {
+  hooks: HashMap<FieldType, Arc<dyn RowCreationHook>>,
+}
+
+impl RowCreationHooks {
+  /// Return the hooks without the default ones.
+  pub fn empty() -> Self {
+    Self {
+      hooks: HashMap::new(),
+    }
+  }
+
+  /// Set the hook of the field type, replacing the previous one.
+  pub fn register<T: RowCreationHook + 'static>(&mut self, field_type: FieldType, hook: T) {
+    self.hooks.insert(field_type, Arc::new(hook));
+  }
+
+  pub fn unregister(&mut self, field_type: &FieldType) {
+    self.hooks.remove(field_type);
+  }
+
+  /// Remove the cells of the fields whose hook is [RowCreationHook::is_unique_per_row], so
+  /// [Self::apply] creates them again for the duplicate of a row.
+  pub fn remove_unique_cells(&self, fields: &[Field], cells: &mut Cells) {
+    for field in fields {
+      let is_unique = self
+        .hooks
+        .get(&FieldType::from(field.field_type))
+        .is_some_and(|hook| hook.is_unique_per_row());
+      if is_unique {
+        cells.remove(&field.id);
+      }
+    }
+  }
+
+  /// Add the cells contributed by the hooks to the row, keeping the cells it already has.
+  /// `next_row_number` is only called if a hook is run, see [RowCreationContext::row_number].
+  pub fn apply(
+    &self,
+    fields: &[Field],
+    row: &mut CreateRowParams,
+    num_of_rows: usize,
+    next_row_number: impl FnOnce() -> i64,
+  ) {
+    if self.hooks.is_empty() {
+      return;
+    }
+    let mut next_row_number = Some(next_row_number);
+    let mut row_number = 0;
+    let mut cells = vec![];
+    for field in fields {
+      if row.cells.contains_key(&field.id) {
+        continue;
+      }
+      let hook = match self.hooks.get(&FieldType::from(field.field_type)) {
+        None => continue,
+        Some(hook) => hook,
+      };
+      if let Some(next_row_number) = next_row_number.take() {
+        row_number = next_row_number();
+      }
+      let context = RowCreationContext {
+        field,
+        row,
+        num_of_rows,
+        row_number,
+      };
+      if let Some(cell) = hook.create_cell(&context) {
+        cells.push((field.id.clone(), cell));
+      }
+    }
+    row.cells.extend(cells);
+  }
+}
+
+impl Default for RowCreationHooks {
+  fn default() -> Self {
+    let mut hooks = Self::empty();
+    hooks.register(FieldType::CreatedTime, CreatedTimeHook);
+    hooks.register(FieldType::SingleSelect, DefaultSelectOptionHook);
+    hooks.register(FieldType::MultiSelect, DefaultSelectOptionHook);
+    hooks
+  }
+}
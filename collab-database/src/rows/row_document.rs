@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+
+use collab::util::AnyMapExt;
+use collab_document::blocks::{Block, DocumentData, DocumentMeta, EXTERNAL_TYPE_TEXT};
+use collab_document::document_data::{generate_id, page_id_from_document_id, PAGE};
+use collab_document::importer::define::BlockType;
+use serde_json::json;
+
+use crate::entity::FieldType;
+use crate::fields::{stringify_type_option, Field};
+use crate::rows::Row;
+use crate::template::entity::CELL_DATA;
+
+/// A standalone document made from a database row, see
+/// [Database::convert_row_to_document](crate::database::Database::convert_row_to_document).
+#[derive(Debug, Clone)]
+pub struct RowDocument {
+  pub document_id: String,
+  pub data: DocumentData,
+  /// The name of the document's view, the cell of the primary field.
+  pub name: String,
+  /// The icon of the document's view, the icon of the row if any.
+  pub icon: Option<String>,
+}
+
+/// Returns the text of the cell as it's displayed in the database. Returns None if the cell is
+/// empty or its field has no text representation.
+pub(crate) fn stringify_row_cell(row: &Row, field: &Field) -> Option<String> {
+  let cell = row.cells.get(&field.id)?;
+  let field_type = FieldType::from(field.field_type);
+  let text = match field
+    .get_any_type_option(field_type.type_id())
+    .and_then(|type_option| stringify_type_option(type_option, &field_type))
+  {
+    Some(stringify) => stringify.stringify_cell(cell),
+    None => cell.get_as::<String>(CELL_DATA)?,
+  };
+  if text.trim().is_empty() {
+    None
+  } else {
+    Some(text)
+  }
+}
+
+/// Renders the properties of the row as the first blocks of the document, one paragraph per
+/// non-empty cell, followed by the blocks of the row document if any.
+///
+/// The primary field is left out since its cell is the name of the document.
+pub(crate) fn render_row_document(
+  document_id: &str,
+  row: &Row,
+  fields: &[Field],
+  body: Option<DocumentData>,
+) -> DocumentData {
+  let page_id = page_id_from_document_id(document_id).unwrap_or_else(generate_id);
+  let mut data = match body {
+    Some(body) => move_body_to_page(body, &page_id),
+    None => empty_page(&page_id),
+  };
+  let page_children_id = data.blocks[&page_id].children.clone();
+  let text_map = data.meta.text_map.get_or_insert_with(HashMap::new);
+
+  let mut header_block_ids = vec![];
+  for field in fields.iter().filter(|field| !field.is_primary) {
+    let text = match stringify_row_cell(row, field) {
+      None => continue,
+      Some(text) => text,
+    };
+    let block = new_block(&page_id, BlockType::Paragraph, Some(generate_id()));
+    let delta = json!([
+      { "insert": field.name, "attributes": { "bold": true } },
+      { "insert": format!(": {}", text) },
+    ]);
+    text_map.insert(block.external_id.clone().unwrap(), delta.to_string());
+    header_block_ids.push(block.id.clone());
+    data
+      .meta
+      .children_map
+      .insert(block.children.clone(), vec![]);
+    data.blocks.insert(block.id.clone(), block);
+  }
+
+  let has_body = data
+    .meta
+    .children_map
+    .get(&page_children_id)
+    .map_or(false, |children| !children.is_empty());
+  if has_body && !header_block_ids.is_empty() {
+    let divider = new_block(&page_id, BlockType::Divider, None);
+    header_block_ids.push(divider.id.clone());
+    data
+      .meta
+      .children_map
+      .insert(divider.children.clone(), vec![]);
+    data.blocks.insert(divider.id.clone(), divider);
+  }
+  data
+    .meta
+    .children_map
+    .entry(page_children_id)
+    .or_default()
+    .splice(0..0, header_block_ids);
+  data
+}
+
+/// Replaces the page block of the row document with the page of the new document.
+fn move_body_to_page(mut body: DocumentData, page_id: &str) -> DocumentData {
+  let body_page = match body.blocks.remove(&body.page_id) {
+    None => return empty_page(page_id),
+    Some(body_page) => body_page,
+  };
+  let children = body
+    .meta
+    .children_map
+    .get(&body_page.children)
+    .cloned()
+    .unwrap_or_default();
+  for child_id in children {
+    if let Some(child) = body.blocks.get_mut(&child_id) {
+      child.parent = page_id.to_string();
+    }
+  }
+  body.blocks.insert(
+    page_id.to_string(),
+    Block {
+      id: page_id.to_string(),
+      parent: "".to_string(),
+      ..body_page
+    },
+  );
+  body.page_id = page_id.to_string();
+  body
+}
+
+fn empty_page(page_id: &str) -> DocumentData {
+  let page = Block {
+    id: page_id.to_string(),
+    ty: PAGE.to_string(),
+    parent: "".to_string(),
+    children: page_id.to_string(),
+    external_id: None,
+    external_type: None,
+    data: HashMap::new(),
+  };
+  DocumentData {
+    page_id: page_id.to_string(),
+    blocks: HashMap::from([(page_id.to_string(), page)]),
+    meta: DocumentMeta {
+      children_map: HashMap::from([(page_id.to_string(), vec![])]),
+      text_map: Some(HashMap::new()),
+    },
+  }
+}
+
+fn new_block(parent_id: &str, block_type: BlockType, text_id: Option<String>) -> Block {
+  Block {
+    id: generate_id(),
+    ty: block_type.as_str().to_string(),
+    parent: parent_id.to_string(),
+    children: generate_id(),
+    external_type: text_id.as_ref().map(|_| EXTERNAL_TYPE_TEXT.to_string()),
+    external_id: text_id,
+    data: HashMap::new(),
+  }
+}
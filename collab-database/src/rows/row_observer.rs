@@ -1,5 +1,6 @@
-use crate::rows::{Cell, Row, RowId, ROW_CELLS, ROW_HEIGHT, ROW_VISIBILITY};
+use crate::rows::{Cell, Row, RowCommentChange, RowId, ROW_CELLS, ROW_HEIGHT, ROW_VISIBILITY};
 
+use collab::core::origin::CollabOrigin;
 use collab::preclude::{DeepObservable, EntryChange, Event, MapRef, TransactionMut};
 use collab::preclude::{PathSegment, ToJson};
 use std::ops::Deref;
@@ -26,24 +27,32 @@ pub enum RowChange {
     row_id: RowId,
     field_id: String,
     value: Cell,
+    is_local_change: bool,
   },
+  /// Sent once per transaction that changes the comments of the row.
   DidUpdateRowComment {
     row: Row,
   },
+  DidChangeRowComment {
+    row_id: RowId,
+    change: RowCommentChange,
+  },
 }
 
 pub(crate) fn subscribe_row_data_change(
+  origin: CollabOrigin,
   row_id: RowId,
   row_data_map: &MapRef,
   change_tx: RowChangeSender,
 ) {
   row_data_map.observe_deep_with("change", move |txn, events| {
+    let is_local_change = CollabOrigin::from(txn) == origin;
     for event in events.iter() {
       match event {
         Event::Text(_) => {},
         Event::Array(_) => {},
         Event::Map(map_event) => {
-          handle_map_event(&row_id, &change_tx, txn, event, map_event);
+          handle_map_event(&row_id, &change_tx, txn, event, map_event, is_local_change);
         },
         Event::XmlFragment(_) => {},
         Event::XmlText(_) => {},
@@ -60,6 +69,7 @@ fn handle_map_event(
   txn: &TransactionMut,
   event: &Event,
   map_event: &MapEvent,
+  is_local_change: bool,
 ) {
   let path = RowChangePath::from(event);
   for (key, enctry_change) in map_event.keys(txn).iter() {
@@ -108,6 +118,7 @@ fn handle_map_event(
                 row_id: row_id.clone(),
                 field_id,
                 value: cell,
+                is_local_change,
               });
             }
           },
@@ -126,6 +137,7 @@ fn handle_map_event(
                   row_id: row_id.clone(),
                   field_id,
                   value: cell,
+                  is_local_change,
                 });
               }
             }
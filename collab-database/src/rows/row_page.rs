@@ -0,0 +1,61 @@
+use crate::database::Database;
+use crate::error::DatabaseError;
+use crate::rows::{Row, RowId};
+
+/// A page of the rows of a view, returned by [Database::get_rows_after].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowPage {
+  pub rows: Vec<Row>,
+  /// The cursor of the next page, None if it's the last page. The cursor is the id of the last
+  /// row of the page, so the rows inserted before it don't shift the next page.
+  pub next_cursor: Option<RowId>,
+}
+
+/// Iterates over the pages of the rows of a view. Only the rows of the requested page are
+/// loaded. See [Database::paginate_rows].
+pub struct RowPages<'a> {
+  database: &'a Database,
+  view_id: String,
+  page_size: usize,
+  cursor: Option<RowId>,
+  is_done: bool,
+}
+
+impl<'a> RowPages<'a> {
+  pub(crate) fn new(database: &'a Database, view_id: &str, page_size: usize) -> Self {
+    Self {
+      database,
+      view_id: view_id.to_string(),
+      page_size,
+      cursor: None,
+      is_done: page_size == 0,
+    }
+  }
+
+  /// The cursor of the next page, None before the first page.
+  pub fn cursor(&self) -> Option<&RowId> {
+    self.cursor.as_ref()
+  }
+
+  /// Return the rows of the next page, or None after the last page.
+  pub async fn next_page(&mut self) -> Option<Result<Vec<Row>, DatabaseError>> {
+    if self.is_done {
+      return None;
+    }
+    let page = self
+      .database
+      .get_rows_after(&self.view_id, self.cursor.as_ref(), self.page_size)
+      .await;
+    match page {
+      Ok(page) => {
+        self.is_done = page.next_cursor.is_none();
+        self.cursor = page.next_cursor;
+        Some(Ok(page.rows))
+      },
+      Err(err) => {
+        self.is_done = true;
+        Some(Err(err))
+      },
+    }
+  }
+}
@@ -0,0 +1,101 @@
+use std::collections::HashSet;
+use std::sync::Weak;
+
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::StreamExt;
+
+use crate::blocks::BlockMap;
+use crate::rows::{Row, RowChange, RowChangeReceiver, RowId};
+use crate::views::{DatabaseViewChange, ViewChangeReceiver};
+
+enum DatabaseChange {
+  Row(RowChange),
+  View(DatabaseViewChange),
+  Lagged,
+}
+
+/// Calls `on_rows_changed` with the rows that changed until the database is dropped. A removed row
+/// is passed as None.
+///
+/// A row changes when one of its cells changes or when it's inserted into the inline view. When
+/// rows are removed from the inline view, the rows that are no longer loaded are passed as removed.
+pub(crate) fn spawn_row_watcher<F>(
+  inline_view_id: String,
+  rows: Weak<BlockMap>,
+  row_change_rx: RowChangeReceiver,
+  view_change_rx: ViewChangeReceiver,
+  on_rows_changed: F,
+) where
+  F: Fn(Vec<(RowId, Option<Row>)>) + Send + 'static,
+{
+  let row_changes = BroadcastStream::new(row_change_rx).map(|change| match change {
+    Ok(change) => DatabaseChange::Row(change),
+    Err(_) => DatabaseChange::Lagged,
+  });
+  let view_changes = BroadcastStream::new(view_change_rx).map(|change| match change {
+    Ok(change) => DatabaseChange::View(change),
+    Err(_) => DatabaseChange::Lagged,
+  });
+  let mut changes = row_changes.merge(view_changes);
+  let mut known_row_ids: HashSet<RowId> = rows
+    .upgrade()
+    .map(|rows| rows.cached_row_ids().into_iter().collect())
+    .unwrap_or_default();
+  tokio::spawn(async move {
+    while let Some(change) = changes.next().await {
+      let rows = match rows.upgrade() {
+        None => break,
+        Some(rows) => rows,
+      };
+      let row_ids = match change {
+        DatabaseChange::Row(RowChange::DidUpdateCell { row_id, .. }) => vec![row_id],
+        DatabaseChange::Row(_) => continue,
+        DatabaseChange::View(DatabaseViewChange::DidUpdateRowOrders {
+          database_view_id,
+          insert_row_orders,
+          delete_row_indexes,
+          ..
+        }) if database_view_id == inline_view_id => {
+          let mut row_ids = insert_row_orders
+            .into_iter()
+            .map(|(row_order, _)| row_order.id)
+            .collect::<Vec<_>>();
+          if !delete_row_indexes.is_empty() {
+            row_ids.extend(
+              known_row_ids
+                .iter()
+                .filter(|row_id| rows.get_cached_row(row_id).is_none())
+                .cloned(),
+            );
+          }
+          row_ids
+        },
+        DatabaseChange::View(_) => continue,
+        // Some changes were missed, so all the rows are passed again.
+        DatabaseChange::Lagged => known_row_ids
+          .iter()
+          .cloned()
+          .chain(rows.cached_row_ids())
+          .collect::<HashSet<_>>()
+          .into_iter()
+          .collect(),
+      };
+
+      let mut changed_rows = vec![];
+      for row_id in row_ids {
+        let database_row = rows.get_cached_row(&row_id);
+        let row = match database_row {
+          None => None,
+          Some(database_row) => database_row.read().await.get_row(),
+        };
+        if row.is_some() {
+          known_row_ids.insert(row_id.clone());
+        } else {
+          known_row_ids.remove(&row_id);
+        }
+        changed_rows.push((row_id, row));
+      }
+      on_rows_changed(changed_rows);
+    }
+  });
+}
@@ -11,8 +11,8 @@ use crate::fields::number_type_option::NumberTypeOption;
 use crate::fields::select_type_option::SelectTypeOption;
 use crate::fields::text_type_option::RichTextTypeOption;
 use crate::fields::timestamp_type_option::TimestampTypeOption;
+use crate::fields::ChecklistCellData;
 use crate::rows::new_cell_builder;
-use crate::template::chect_list_parse::ChecklistCellData;
 use crate::template::csv::CSVResource;
 use crate::template::date_parse::replace_cells_with_timestamp;
 use crate::template::media_parse::replace_cells_with_files;
@@ -188,10 +188,7 @@ impl FieldTemplateBuilder {
     let cell_template = match field_type {
       FieldType::SingleSelect | FieldType::MultiSelect => {
         let options = build_options_from_cells(&self.cells);
-        let type_option = SelectTypeOption {
-          options,
-          disable_color: false,
-        };
+        let type_option = SelectTypeOption::new(options, false);
         let cell_template =
           replace_cells_with_options_id(self.cells, &type_option.options, SELECT_OPTION_SEPARATOR)
             .into_iter()
@@ -80,6 +80,20 @@ impl CSVTemplate {
     self.view_id = view_id;
   }
 
+  /// Use the field type for the columns with the given header instead of the detected one.
+  /// Return false if there is no such column.
+  pub fn set_field_type(&mut self, name: &str, field_type: FieldType) -> bool {
+    let mut found = false;
+    for field in self.fields.iter_mut().filter(|field| field.name == name) {
+      field.field_type = field_type.clone();
+      found = true;
+    }
+    if found {
+      filter_out_resources(&self.fields, &self.rows, &mut self.resource);
+    }
+    found
+  }
+
   pub async fn try_into_database_template(
     self,
     file_url_builder: Option<Box<dyn FileUrlBuilder>>,
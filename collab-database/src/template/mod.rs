@@ -1,8 +1,9 @@
 pub mod builder;
-mod chect_list_parse;
+pub(crate) mod chect_list_parse;
 pub mod csv;
 pub mod date_parse;
 pub mod entity;
 mod media_parse;
 pub mod option_parse;
 pub mod util;
+pub mod variable;
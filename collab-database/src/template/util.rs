@@ -1,4 +1,4 @@
-use crate::database::{timestamp, Database, DatabaseContext};
+use crate::database::{Database, DatabaseContext};
 use crate::entity::{CreateDatabaseParams, CreateViewParams};
 use crate::error::DatabaseError;
 use crate::fields::Field;
@@ -9,10 +9,7 @@ use std::sync::Arc;
 
 pub async fn database_from_template(template: DatabaseTemplate) -> Result<Database, DatabaseError> {
   let params = create_database_params_from_template(template);
-  let context = DatabaseContext {
-    collab_service: Arc::new(NoPersistenceDatabaseCollabService),
-    notifier: Default::default(),
-  };
+  let context = DatabaseContext::new(Arc::new(NoPersistenceDatabaseCollabService));
   let database = Database::create_with_view(params, context).await?;
   Ok(database)
 }
@@ -31,12 +28,12 @@ where
   Ok(params)
 }
 
+/// The created and modified times of the rows and the views are left unset, the database created
+/// from the params stamps them with its clock.
 pub(crate) fn create_database_params_from_template(
   template: DatabaseTemplate,
 ) -> CreateDatabaseParams {
   let database_id = template.database_id;
-  let timestamp = timestamp();
-
   let mut fields = vec![];
   for template_field in template.fields {
     let mut field = Field::new(
@@ -60,8 +57,8 @@ pub(crate) fn create_database_params_from_template(
       height: row_template.height,
       visibility: row_template.visibility,
       row_position: Default::default(),
-      created_at: timestamp,
-      modified_at: timestamp,
+      created_at: 0,
+      modified_at: 0,
     });
   }
 
@@ -77,8 +74,9 @@ pub(crate) fn create_database_params_from_template(
       group_settings: view_template.group_settings,
       sorts: view_template.sorts,
       field_settings: Default::default(),
-      created_at: timestamp,
-      modified_at: timestamp,
+      calculations: vec![],
+      created_at: 0,
+      modified_at: 0,
       deps_fields: vec![],
       deps_field_setting: vec![],
     });
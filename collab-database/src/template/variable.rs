@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use collab::preclude::Any;
+use serde_json::Value;
+
+/// Replace the `{{name}}` placeholders of the text with the value of the variable of the same
+/// name. The spaces around the name are ignored, so `{{ name }}` is the same placeholder. The
+/// placeholders without a variable are kept as they are.
+pub fn resolve_placeholders(text: &str, variables: &HashMap<String, String>) -> String {
+  let mut resolved = String::with_capacity(text.len());
+  let mut rest = text;
+  while let Some(start) = rest.find("{{") {
+    let end = match rest[start + 2..].find("}}") {
+      None => break,
+      Some(end) => start + 2 + end,
+    };
+    resolved.push_str(&rest[..start]);
+    match variables.get(rest[start + 2..end].trim()) {
+      Some(value) => resolved.push_str(value),
+      None => resolved.push_str(&rest[start..end + 2]),
+    }
+    rest = &rest[end + 2..];
+  }
+  resolved.push_str(rest);
+  resolved
+}
+
+/// Resolve the placeholders of all the strings of the value. A string that holds a JSON object
+/// or array, like the options of a select field, is resolved inside, so the JSON stays valid
+/// whatever the values of the variables are.
+pub(crate) fn resolve_any(value: &Any, variables: &HashMap<String, String>) -> Any {
+  match value {
+    Any::String(text) if text.contains("{{") => Any::from(resolve_text(text, variables)),
+    Any::Array(values) => Any::Array(
+      values
+        .iter()
+        .map(|value| resolve_any(value, variables))
+        .collect::<Vec<_>>()
+        .into(),
+    ),
+    Any::Map(map) => Any::Map(Arc::new(resolve_map(map, variables))),
+    value => value.clone(),
+  }
+}
+
+pub(crate) fn resolve_map(
+  map: &HashMap<String, Any>,
+  variables: &HashMap<String, String>,
+) -> HashMap<String, Any> {
+  map
+    .iter()
+    .map(|(key, value)| (key.clone(), resolve_any(value, variables)))
+    .collect()
+}
+
+fn resolve_text(text: &str, variables: &HashMap<String, String>) -> String {
+  let trimmed = text.trim_start();
+  if trimmed.starts_with('{') || trimmed.starts_with('[') {
+    if let Ok(mut json) = serde_json::from_str::<Value>(text) {
+      if json.is_object() || json.is_array() {
+        resolve_json(&mut json, variables);
+        return json.to_string();
+      }
+    }
+  }
+  resolve_placeholders(text, variables)
+}
+
+fn resolve_json(value: &mut Value, variables: &HashMap<String, String>) {
+  match value {
+    Value::String(text) => *text = resolve_placeholders(text, variables),
+    Value::Array(values) => values
+      .iter_mut()
+      .for_each(|value| resolve_json(value, variables)),
+    Value::Object(map) => map
+      .values_mut()
+      .for_each(|value| resolve_json(value, variables)),
+    _ => {},
+  }
+}
@@ -0,0 +1,203 @@
+//! Generators of random but valid databases for the tests and the fuzzers. Only available with
+//! the `test_utils` feature.
+
+use std::sync::Arc;
+
+use collab::preclude::Any;
+use collab::test_utils::SeededRng;
+
+use crate::database::{timestamp, Database, DatabaseContext};
+use crate::entity::{CreateDatabaseParams, CreateViewParams, FieldType};
+use crate::error::DatabaseError;
+use crate::fields::checkbox_type_option::CheckboxTypeOption;
+use crate::fields::date_type_option::DateTypeOption;
+use crate::fields::number_type_option::NumberTypeOption;
+use crate::fields::select_type_option::{SelectOption, SelectOptionColor, SelectTypeOption};
+use crate::fields::text_type_option::RichTextTypeOption;
+use crate::fields::Field;
+use crate::rows::{new_cell_builder, Cells, CreateRowParams, RowId};
+use crate::template::entity::CELL_DATA;
+use crate::views::DatabaseLayout;
+use crate::workspace_database::NoPersistenceDatabaseCollabService;
+
+/// The field types the generator picks from. The first field is always the primary text field.
+const FIELD_TYPES: &[FieldType] = &[
+  FieldType::RichText,
+  FieldType::Number,
+  FieldType::Checkbox,
+  FieldType::DateTime,
+  FieldType::SingleSelect,
+];
+
+const LAYOUTS: &[DatabaseLayout] = &[
+  DatabaseLayout::Grid,
+  DatabaseLayout::Board,
+  DatabaseLayout::Calendar,
+];
+
+/// Generates a database with the given number of fields, rows and views. The same seed always
+/// generates the same ids, names and cells.
+///
+/// ```ignore
+/// let database = DatabaseGenerator::new(7).with_rows(1_000).build().await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct DatabaseGenerator {
+  seed: u64,
+  num_fields: usize,
+  num_rows: usize,
+  num_views: usize,
+  empty_cell_probability: f64,
+}
+
+impl DatabaseGenerator {
+  pub fn new(seed: u64) -> Self {
+    Self {
+      seed,
+      num_fields: 5,
+      num_rows: 10,
+      num_views: 1,
+      empty_cell_probability: 0.1,
+    }
+  }
+
+  /// The number of fields, at least one for the primary field.
+  pub fn with_fields(mut self, num_fields: usize) -> Self {
+    self.num_fields = num_fields.max(1);
+    self
+  }
+
+  pub fn with_rows(mut self, num_rows: usize) -> Self {
+    self.num_rows = num_rows;
+    self
+  }
+
+  /// The number of views, at least one for the inline view.
+  pub fn with_views(mut self, num_views: usize) -> Self {
+    self.num_views = num_views.max(1);
+    self
+  }
+
+  /// The probability, between 0.0 and 1.0, that a cell is left empty.
+  pub fn with_empty_cell_probability(mut self, probability: f64) -> Self {
+    self.empty_cell_probability = probability;
+    self
+  }
+
+  pub fn generate_params(&self) -> CreateDatabaseParams {
+    let mut rng = SeededRng::new(self.seed);
+    let database_id = rng.gen_uuid();
+    let timestamp = timestamp();
+
+    let fields = (0..self.num_fields)
+      .map(|index| generate_field(&mut rng, index))
+      .collect::<Vec<_>>();
+
+    let rows = (0..self.num_rows)
+      .map(|_| {
+        let mut cells = Cells::new();
+        for field in &fields {
+          if rng.gen_bool(self.empty_cell_probability) {
+            continue;
+          }
+          let field_type = FieldType::from(field.field_type);
+          let mut cell = new_cell_builder(field_type.clone());
+          cell.insert(
+            CELL_DATA.to_string(),
+            Any::from(generate_cell_data(&mut rng, field, &field_type)),
+          );
+          cells.insert(field.id.clone(), cell);
+        }
+        let mut params =
+          CreateRowParams::new(RowId::from(rng.gen_uuid()), database_id.clone()).with_cells(cells);
+        params.created_at = timestamp;
+        params.modified_at = timestamp;
+        params
+      })
+      .collect();
+
+    let views = (0..self.num_views)
+      .map(|index| CreateViewParams {
+        database_id: database_id.clone(),
+        view_id: rng.gen_uuid(),
+        name: format!("{} {}", rng.gen_word(), index),
+        layout: if index == 0 {
+          DatabaseLayout::Grid
+        } else {
+          *rng.choose(LAYOUTS)
+        },
+        created_at: timestamp,
+        modified_at: timestamp,
+        ..Default::default()
+      })
+      .collect();
+
+    CreateDatabaseParams {
+      database_id,
+      fields,
+      rows,
+      views,
+    }
+  }
+
+  /// Creates the generated database in memory.
+  pub async fn build(&self) -> Result<Database, DatabaseError> {
+    let context = DatabaseContext::new(Arc::new(NoPersistenceDatabaseCollabService));
+    Database::create_with_view(self.generate_params(), context).await
+  }
+}
+
+fn generate_field(rng: &mut SeededRng, index: usize) -> Field {
+  let field_type = if index == 0 {
+    FieldType::RichText
+  } else {
+    rng.choose(FIELD_TYPES).clone()
+  };
+  let field = Field::new(
+    rng.gen_uuid(),
+    format!("{} {}", rng.gen_word(), index),
+    field_type.clone() as i64,
+    index == 0,
+  );
+  let type_id = field_type.type_id();
+  match field_type {
+    FieldType::Number => field.with_type_option_data(type_id, NumberTypeOption::default().into()),
+    FieldType::Checkbox => field.with_type_option_data(type_id, CheckboxTypeOption.into()),
+    FieldType::DateTime => field.with_type_option_data(type_id, DateTypeOption::new().into()),
+    FieldType::SingleSelect => {
+      let options = (0..rng.gen_range(2..6))
+        .map(|_| SelectOption {
+          id: rng.gen_uuid(),
+          name: rng.gen_word().to_string(),
+          color: SelectOptionColor::default(),
+        })
+        .collect();
+      let type_option = SelectTypeOption::new(options, false);
+      field.with_type_option_data(type_id, type_option.into())
+    },
+    _ => field.with_type_option_data(type_id, RichTextTypeOption.into()),
+  }
+}
+
+fn generate_cell_data(rng: &mut SeededRng, field: &Field, field_type: &FieldType) -> String {
+  match field_type {
+    FieldType::Number => rng.gen_range(0..10_000).to_string(),
+    FieldType::Checkbox => if rng.gen_bool(0.5) { "Yes" } else { "No" }.to_string(),
+    // Between 2020-01-01 and 2030-01-01
+    FieldType::DateTime => (1_577_836_800 + rng.gen_range(0..315_532_800) as i64).to_string(),
+    FieldType::SingleSelect => field
+      .get_type_option::<SelectTypeOption>(field_type.type_id())
+      .and_then(|type_option| {
+        let index = rng.gen_range(0..type_option.options.len());
+        type_option
+          .options
+          .get(index)
+          .map(|option| option.id.clone())
+      })
+      .unwrap_or_default(),
+    _ => {
+      let num_words = rng.gen_range(1..8);
+      rng.gen_sentence(num_words)
+    },
+  }
+}
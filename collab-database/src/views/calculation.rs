@@ -1,6 +1,83 @@
 use collab::preclude::Any;
+use serde_repr::{Deserialize_repr, Serialize_repr};
 use std::collections::HashMap;
 
+use crate::fields::{format_number, parse_number};
+
 pub type CalculationArray = Vec<Any>;
 pub type CalculationMap = HashMap<String, Any>;
 pub type CalculationMapBuilder = HashMap<String, Any>;
+
+pub const CALCULATION_ID: &str = "id";
+pub const CALCULATION_FIELD_ID: &str = "field_id";
+pub const CALCULATION_TYPE: &str = "calculation_type";
+pub const CALCULATION_VALUE: &str = "value";
+
+/// The calculation shown in the footer of a field in a grid.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum CalculationType {
+  /// The number of rows.
+  #[default]
+  Count = 0,
+  Sum = 1,
+  Average = 2,
+  Median = 3,
+  Min = 4,
+  Max = 5,
+  CountEmpty = 6,
+  CountNonEmpty = 7,
+}
+
+impl From<i64> for CalculationType {
+  fn from(value: i64) -> Self {
+    match value {
+      1 => CalculationType::Sum,
+      2 => CalculationType::Average,
+      3 => CalculationType::Median,
+      4 => CalculationType::Min,
+      5 => CalculationType::Max,
+      6 => CalculationType::CountEmpty,
+      7 => CalculationType::CountNonEmpty,
+      _ => CalculationType::Count,
+    }
+  }
+}
+
+impl CalculationType {
+  /// Compute the value of the calculation from the data of the cells of the field, one per row
+  /// of the view. The numeric calculations skip the cells that are not numbers and return an
+  /// empty value if there is no number.
+  pub fn calculate(&self, values: &[Option<String>]) -> String {
+    let is_empty = |value: &Option<String>| value.as_deref().map_or(true, |v| v.trim().is_empty());
+    let mut numbers = values
+      .iter()
+      .flatten()
+      .filter_map(|value| parse_number(value))
+      .collect::<Vec<_>>();
+    let number = match self {
+      CalculationType::Count => return values.len().to_string(),
+      CalculationType::CountEmpty => {
+        return values.iter().filter(|v| is_empty(v)).count().to_string()
+      },
+      CalculationType::CountNonEmpty => {
+        return values.iter().filter(|v| !is_empty(v)).count().to_string()
+      },
+      _ if numbers.is_empty() => return "".to_string(),
+      CalculationType::Sum => numbers.iter().sum(),
+      CalculationType::Average => numbers.iter().sum::<f64>() / numbers.len() as f64,
+      CalculationType::Median => {
+        numbers.sort_by(|a, b| a.total_cmp(b));
+        let middle = numbers.len() / 2;
+        if numbers.len() % 2 == 0 {
+          (numbers[middle - 1] + numbers[middle]) / 2.0
+        } else {
+          numbers[middle]
+        }
+      },
+      CalculationType::Min => numbers.iter().cloned().fold(f64::INFINITY, f64::min),
+      CalculationType::Max => numbers.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+    };
+    format_number(number)
+  }
+}
@@ -0,0 +1,136 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use collab::preclude::{Any, Map, MapExt};
+use collab::preclude::{DeepObservable, Event, MapRef, PathSegment, ReadTxn, Subscription};
+use tokio::sync::broadcast;
+
+use crate::views::define::VIEW_CALCULATIONS;
+use crate::views::{calculations_from_map_ref, CalculationMap};
+
+pub type CalculationChangeSender = broadcast::Sender<CalculationChanged>;
+pub type CalculationChangeReceiver = broadcast::Receiver<CalculationChanged>;
+
+/// The calculation of a field in a view changed, for example the sum shown in the footer of a
+/// grid was recomputed after a cell changed. `old` is None when the calculation is created and
+/// `new` is None when it's removed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalculationChanged {
+  pub view_id: String,
+  pub field_id: String,
+  pub old: Option<CalculationMap>,
+  pub new: Option<CalculationMap>,
+}
+
+/// The calculations of each view, keyed by view id and field id.
+type ViewCalculations = HashMap<String, HashMap<String, CalculationMap>>;
+
+pub(crate) fn subscribe_calculation_change<T: ReadTxn>(
+  txn: &T,
+  view_map: &MapRef,
+  change_tx: CalculationChangeSender,
+) -> Subscription {
+  // The removed values are not in the events, so the last known calculations are kept to be
+  // sent as the old values.
+  let calculations: ViewCalculations = view_map
+    .keys(txn)
+    .map(|view_id| {
+      let calculations = view_calculations(txn, view_map, view_id);
+      (view_id.to_string(), calculations)
+    })
+    .collect();
+  let calculations = Mutex::new(calculations);
+  let cloned_view_map = view_map.clone();
+  view_map.observe_deep(move |txn, events| {
+    let mut view_ids = HashSet::new();
+    for event in events.iter() {
+      let path = event.path();
+      match (path.front(), path.get(1)) {
+        (Some(PathSegment::Key(view_id)), Some(PathSegment::Key(key)))
+          if key.as_ref() == VIEW_CALCULATIONS =>
+        {
+          view_ids.insert(view_id.to_string());
+        },
+        // The calculations of the view were created.
+        (Some(PathSegment::Key(view_id)), None) => {
+          if let Event::Map(map_event) = event {
+            if map_event.keys(txn).contains_key(VIEW_CALCULATIONS) {
+              view_ids.insert(view_id.to_string());
+            }
+          }
+        },
+        // A view was created or deleted.
+        (None, _) => {
+          if let Event::Map(map_event) = event {
+            view_ids.extend(
+              map_event
+                .keys(txn)
+                .keys()
+                .map(|view_id| view_id.to_string()),
+            );
+          }
+        },
+        _ => {},
+      }
+    }
+    if view_ids.is_empty() {
+      return;
+    }
+
+    let mut calculations = match calculations.lock() {
+      Ok(calculations) => calculations,
+      Err(_) => return,
+    };
+    for view_id in view_ids {
+      let new_calculations = view_calculations(txn, &cloned_view_map, &view_id);
+      let old_calculations = calculations.remove(&view_id).unwrap_or_default();
+      for change in diff_calculations(&view_id, &old_calculations, &new_calculations) {
+        let _ = change_tx.send(change);
+      }
+      if !new_calculations.is_empty() {
+        calculations.insert(view_id, new_calculations);
+      }
+    }
+  })
+}
+
+fn view_calculations<T: ReadTxn>(
+  txn: &T,
+  view_map: &MapRef,
+  view_id: &str,
+) -> HashMap<String, CalculationMap> {
+  view_map
+    .get_with_txn::<_, MapRef>(txn, view_id)
+    .map(|map_ref| calculations_from_map_ref(txn, &map_ref))
+    .unwrap_or_default()
+    .into_iter()
+    .filter_map(|calculation| match calculation.get("field_id") {
+      Some(Any::String(field_id)) => Some((field_id.to_string(), calculation)),
+      _ => None,
+    })
+    .collect()
+}
+
+fn diff_calculations(
+  view_id: &str,
+  old: &HashMap<String, CalculationMap>,
+  new: &HashMap<String, CalculationMap>,
+) -> Vec<CalculationChanged> {
+  old
+    .keys()
+    .chain(new.keys().filter(|field_id| !old.contains_key(*field_id)))
+    .filter_map(|field_id| {
+      let old = old.get(field_id);
+      let new = new.get(field_id);
+      if old == new {
+        return None;
+      }
+      Some(CalculationChanged {
+        view_id: view_id.to_string(),
+        field_id: field_id.clone(),
+        old: old.cloned(),
+        new: new.cloned(),
+      })
+    })
+    .collect()
+}
@@ -0,0 +1,322 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use chrono::{Datelike, Duration, FixedOffset, Months, NaiveDate, NaiveTime, TimeZone};
+use chrono_tz::Tz;
+use collab::preclude::Any;
+use collab::util::AnyMapExt;
+use serde_repr::{Deserialize_repr, Serialize_repr};
+
+use crate::fields::date_type_option::DateCellData;
+use crate::rows::{Row, RowId};
+use crate::views::GroupSettingMap;
+
+pub const GROUP_DATE_BUCKET: &str = "date_bucket";
+pub const GROUP_WEEK_START: &str = "week_start";
+pub const GROUP_UTC_OFFSET: &str = "utc_offset";
+pub const GROUP_TIMEZONE_ID: &str = "timezone_id";
+/// The id of the bucket of the rows without a date.
+pub const NO_DATE_BUCKET_ID: &str = "no_date";
+
+/// How the dates of a date field are split into groups.
+#[derive(Debug, PartialEq, Copy, Eq, Hash, Clone, Default, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum DateBucketStrategy {
+  /// Today, yesterday, tomorrow, this week, last week, next week, earlier and later.
+  #[default]
+  Relative = 0,
+  Day = 1,
+  Week = 2,
+  Month = 3,
+  Quarter = 4,
+  Year = 5,
+}
+
+impl From<i64> for DateBucketStrategy {
+  fn from(value: i64) -> Self {
+    match value {
+      1 => DateBucketStrategy::Day,
+      2 => DateBucketStrategy::Week,
+      3 => DateBucketStrategy::Month,
+      4 => DateBucketStrategy::Quarter,
+      5 => DateBucketStrategy::Year,
+      _ => DateBucketStrategy::Relative,
+    }
+  }
+}
+
+#[derive(Debug, PartialEq, Copy, Eq, Hash, Clone, Default, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum WeekStart {
+  #[default]
+  Monday = 0,
+  Sunday = 1,
+}
+
+impl From<i64> for WeekStart {
+  fn from(value: i64) -> Self {
+    match value {
+      1 => WeekStart::Sunday,
+      _ => WeekStart::Monday,
+    }
+  }
+}
+
+/// A group of dates. `start` is inclusive and `end` is exclusive, both are timestamps in
+/// seconds. They are None for the unbounded buckets, like the earlier dates of the relative
+/// strategy or the rows without a date.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DateBucket {
+  pub id: String,
+  pub start: Option<i64>,
+  pub end: Option<i64>,
+}
+
+impl DateBucket {
+  pub fn no_date() -> Self {
+    Self {
+      id: NO_DATE_BUCKET_ID.to_string(),
+      start: None,
+      end: None,
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateRowGroup {
+  pub bucket: DateBucket,
+  pub row_ids: Vec<RowId>,
+}
+
+/// The rule used to group the rows by a date field. It's stored in the [GroupSettingMap] of the
+/// group, so every client computes the same buckets. The first day of the week and the time zone
+/// are part of the rule instead of being read from the locale of each device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DateGroupRule {
+  pub strategy: DateBucketStrategy,
+  pub week_start: WeekStart,
+  /// The offset of the time zone in seconds east of UTC, used when there is no [Self::timezone].
+  pub utc_offset: i32,
+  /// The time zone of the dates. Its offset is resolved for each date, so the dates on both
+  /// sides of a daylight saving time change are grouped by their own local day.
+  pub timezone: Option<Tz>,
+}
+
+impl DateGroupRule {
+  pub fn new(strategy: DateBucketStrategy) -> Self {
+    Self {
+      strategy,
+      ..Default::default()
+    }
+  }
+
+  pub fn with_week_start(mut self, week_start: WeekStart) -> Self {
+    self.week_start = week_start;
+    self
+  }
+
+  pub fn with_utc_offset(mut self, utc_offset: i32) -> Self {
+    self.utc_offset = utc_offset;
+    self
+  }
+
+  pub fn with_timezone(mut self, timezone: Tz) -> Self {
+    self.timezone = Some(timezone);
+    self
+  }
+
+  /// Return the rule stored in the group setting, or None if the group is not a date group.
+  pub fn from_group_setting(setting: &GroupSettingMap) -> Option<Self> {
+    let strategy: i64 = setting.get_as(GROUP_DATE_BUCKET)?;
+    let week_start: i64 = setting.get_as(GROUP_WEEK_START).unwrap_or_default();
+    let utc_offset: i64 = setting.get_as(GROUP_UTC_OFFSET).unwrap_or_default();
+    let timezone = setting
+      .get_as::<String>(GROUP_TIMEZONE_ID)
+      .and_then(|timezone_id| Tz::from_str(&timezone_id).ok());
+    Some(Self {
+      strategy: DateBucketStrategy::from(strategy),
+      week_start: WeekStart::from(week_start),
+      utc_offset: utc_offset as i32,
+      timezone,
+    })
+  }
+
+  pub fn fill_group_setting(&self, setting: &mut GroupSettingMap) {
+    setting.insert(
+      GROUP_DATE_BUCKET.to_string(),
+      Any::BigInt(self.strategy as i64),
+    );
+    setting.insert(
+      GROUP_WEEK_START.to_string(),
+      Any::BigInt(self.week_start as i64),
+    );
+    setting.insert(
+      GROUP_UTC_OFFSET.to_string(),
+      Any::BigInt(self.utc_offset as i64),
+    );
+    match self.timezone {
+      None => setting.remove(GROUP_TIMEZONE_ID),
+      Some(timezone) => setting.insert(GROUP_TIMEZONE_ID.to_string(), Any::from(timezone.name())),
+    };
+  }
+
+  /// Return the bucket of the timestamp. `now` is only used by the relative strategy. The range
+  /// of the relative buckets of a week covers the whole week, so it includes the days of the
+  /// today, yesterday and tomorrow buckets, which take precedence.
+  pub fn bucket(&self, timestamp: i64, now: i64) -> DateBucket {
+    let date = match self.local_date(timestamp) {
+      None => return DateBucket::no_date(),
+      Some(date) => date,
+    };
+    match self.strategy {
+      DateBucketStrategy::Day => self.make_bucket(
+        format!("day:{}", date.format("%Y-%m-%d")),
+        date,
+        date + Duration::days(1),
+      ),
+      DateBucketStrategy::Week => {
+        let start = self.start_of_week(date);
+        self.make_bucket(
+          format!("week:{}", start.format("%Y-%m-%d")),
+          start,
+          start + Duration::days(7),
+        )
+      },
+      DateBucketStrategy::Month => {
+        let start = date.with_day(1).unwrap_or(date);
+        self.make_bucket(
+          format!("month:{}", start.format("%Y-%m")),
+          start,
+          start + Months::new(1),
+        )
+      },
+      DateBucketStrategy::Quarter => {
+        let quarter = date.month0() / 3;
+        let start = NaiveDate::from_ymd_opt(date.year(), quarter * 3 + 1, 1).unwrap_or(date);
+        self.make_bucket(
+          format!("quarter:{}-Q{}", date.year(), quarter + 1),
+          start,
+          start + Months::new(3),
+        )
+      },
+      DateBucketStrategy::Year => {
+        let start = NaiveDate::from_ymd_opt(date.year(), 1, 1).unwrap_or(date);
+        self.make_bucket(
+          format!("year:{}", date.year()),
+          start,
+          start + Months::new(12),
+        )
+      },
+      DateBucketStrategy::Relative => match self.local_date(now) {
+        None => DateBucket::no_date(),
+        Some(today) => self.relative_bucket(date, today),
+      },
+    }
+  }
+
+  /// Group the rows by the date of the field, keeping the order of the rows inside each group.
+  /// The groups are sorted by date and the group of the rows without a date comes first.
+  pub fn group_rows(&self, rows: &[Row], field_id: &str, now: i64) -> Vec<DateRowGroup> {
+    let mut groups: Vec<DateRowGroup> = vec![];
+    let mut index_by_id: HashMap<String, usize> = HashMap::new();
+    for row in rows {
+      let bucket = match row
+        .cells
+        .get(field_id)
+        .and_then(|cell| DateCellData::from(cell).timestamp)
+      {
+        None => DateBucket::no_date(),
+        Some(timestamp) => self.bucket(timestamp, now),
+      };
+      let index = *index_by_id.entry(bucket.id.clone()).or_insert_with(|| {
+        groups.push(DateRowGroup {
+          bucket,
+          row_ids: vec![],
+        });
+        groups.len() - 1
+      });
+      groups[index].row_ids.push(row.id.clone());
+    }
+    groups.sort_by_key(|group| {
+      (
+        group.bucket.id != NO_DATE_BUCKET_ID,
+        group.bucket.start.unwrap_or(i64::MIN),
+      )
+    });
+    groups
+  }
+
+  fn relative_bucket(&self, date: NaiveDate, today: NaiveDate) -> DateBucket {
+    let this_week = self.start_of_week(today);
+    let last_week = this_week - Duration::days(7);
+    let next_week = this_week + Duration::days(7);
+    let after_next_week = next_week + Duration::days(7);
+    match (date - today).num_days() {
+      0 => self.make_bucket("today".to_string(), date, date + Duration::days(1)),
+      -1 => self.make_bucket("yesterday".to_string(), date, date + Duration::days(1)),
+      1 => self.make_bucket("tomorrow".to_string(), date, date + Duration::days(1)),
+      _ if date < last_week => DateBucket {
+        id: "earlier".to_string(),
+        start: None,
+        end: Some(self.timestamp_of(last_week)),
+      },
+      _ if date < this_week => self.make_bucket("last_week".to_string(), last_week, this_week),
+      _ if date < next_week => self.make_bucket("this_week".to_string(), this_week, next_week),
+      _ if date < after_next_week => {
+        self.make_bucket("next_week".to_string(), next_week, after_next_week)
+      },
+      _ => DateBucket {
+        id: "later".to_string(),
+        start: Some(self.timestamp_of(after_next_week)),
+        end: None,
+      },
+    }
+  }
+
+  fn make_bucket(&self, id: String, start: NaiveDate, end: NaiveDate) -> DateBucket {
+    DateBucket {
+      id,
+      start: Some(self.timestamp_of(start)),
+      end: Some(self.timestamp_of(end)),
+    }
+  }
+
+  fn start_of_week(&self, date: NaiveDate) -> NaiveDate {
+    let days = match self.week_start {
+      WeekStart::Monday => date.weekday().num_days_from_monday(),
+      WeekStart::Sunday => date.weekday().num_days_from_sunday(),
+    };
+    date - Duration::days(days as i64)
+  }
+
+  fn local_date(&self, timestamp: i64) -> Option<NaiveDate> {
+    match self.timezone {
+      Some(timezone) => Some(timezone.timestamp_opt(timestamp, 0).single()?.date_naive()),
+      None => {
+        let offset = FixedOffset::east_opt(self.utc_offset)?;
+        Some(offset.timestamp_opt(timestamp, 0).single()?.date_naive())
+      },
+    }
+  }
+
+  /// The timestamp of the midnight of the date in the time zone of the rule.
+  fn timestamp_of(&self, date: NaiveDate) -> i64 {
+    let midnight = date.and_time(NaiveTime::MIN);
+    let timezone = match self.timezone {
+      None => return midnight.and_utc().timestamp() - self.utc_offset as i64,
+      Some(timezone) => timezone,
+    };
+    // The day starts at one o'clock in the time zones that skip the midnight when the clocks
+    // are moved forward.
+    timezone
+      .from_local_datetime(&midnight)
+      .earliest()
+      .or_else(|| {
+        timezone
+          .from_local_datetime(&(midnight + Duration::hours(1)))
+          .earliest()
+      })
+      .map(|date_time| date_time.timestamp())
+      .unwrap_or_else(|| midnight.and_utc().timestamp())
+  }
+}
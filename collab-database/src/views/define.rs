@@ -13,3 +13,4 @@ pub const VIEW_CREATE_AT: &str = "created_at";
 pub const VIEW_MODIFY_AT: &str = "modified_at";
 pub const IS_INLINE: &str = "is_inline";
 pub const VIEW_CALCULATIONS: &str = "calculations";
+pub const DATABASE_VIEW_HIDDEN_ROWS: &str = "hidden_rows";
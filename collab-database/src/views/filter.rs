@@ -1,6 +1,248 @@
-use collab::preclude::Any;
+use collab::preclude::{
+  Any, Array, ArrayPrelim, ArrayRef, FillRef, Map, MapExt, MapPrelim, MapRef, ReadTxn,
+  TransactionMut,
+};
+use collab::util::{AnyMapExt, ArrayExt};
+use serde_repr::{Deserialize_repr, Serialize_repr};
 use std::collections::HashMap;
+use std::sync::Arc;
 
 pub type FilterArray = Vec<Any>;
 pub type FilterMap = HashMap<String, Any>;
 pub type FilterMapBuilder = HashMap<String, Any>;
+
+pub const FILTER_ID: &str = "id";
+pub const FILTER_TYPE: &str = "filter_type";
+pub const FILTER_CHILDREN: &str = "children";
+/// The field a condition applies to.
+pub const FILTER_FIELD_ID: &str = "field_id";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum FilterType {
+  And = 0,
+  Or = 1,
+  /// A condition on a field. The filters without a [FILTER_TYPE] are conditions.
+  #[default]
+  Data = 2,
+}
+
+impl From<i64> for FilterType {
+  fn from(value: i64) -> Self {
+    match value {
+      0 => FilterType::And,
+      1 => FilterType::Or,
+      _ => FilterType::Data,
+    }
+  }
+}
+
+/// A node of a filter tree. The filters of a view are the children of an implicit AND group, and
+/// each group can hold conditions and other groups, so `(status = done OR archived) AND
+/// assignee = me` is stored as an OR group next to the assignee condition.
+///
+/// A group is stored as a [FilterMap] with its [FILTER_TYPE] and its [FILTER_CHILDREN], so it
+/// can be used everywhere a flat filter is used, like [crate::database::Database::insert_filter]
+/// or [crate::database::Database::get_all_filters]. The children are stored as an array of
+/// maps, so each node can be edited on its own, see
+/// [crate::database::Database::update_filter_node].
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterNode {
+  And {
+    id: String,
+    children: Vec<FilterNode>,
+  },
+  Or {
+    id: String,
+    children: Vec<FilterNode>,
+  },
+  /// A condition on a field. The content of the map is defined by the application.
+  Data(FilterMap),
+}
+
+impl FilterNode {
+  pub fn and(id: &str, children: Vec<FilterNode>) -> Self {
+    FilterNode::And {
+      id: id.to_string(),
+      children,
+    }
+  }
+
+  pub fn or(id: &str, children: Vec<FilterNode>) -> Self {
+    FilterNode::Or {
+      id: id.to_string(),
+      children,
+    }
+  }
+
+  pub fn data(filter: impl Into<FilterMap>) -> Self {
+    FilterNode::Data(filter.into())
+  }
+
+  /// Add a child to the group. Does nothing if the node is a condition.
+  pub fn with_child(mut self, child: FilterNode) -> Self {
+    if let Some(children) = self.children_mut() {
+      children.push(child);
+    }
+    self
+  }
+
+  pub fn id(&self) -> Option<&str> {
+    match self {
+      FilterNode::And { id, .. } | FilterNode::Or { id, .. } => Some(id),
+      FilterNode::Data(filter) => match filter.get(FILTER_ID) {
+        Some(Any::String(id)) => Some(id),
+        _ => None,
+      },
+    }
+  }
+
+  pub fn filter_type(&self) -> FilterType {
+    match self {
+      FilterNode::And { .. } => FilterType::And,
+      FilterNode::Or { .. } => FilterType::Or,
+      FilterNode::Data(_) => FilterType::Data,
+    }
+  }
+
+  pub fn children(&self) -> &[FilterNode] {
+    match self {
+      FilterNode::And { children, .. } | FilterNode::Or { children, .. } => children,
+      FilterNode::Data(_) => &[],
+    }
+  }
+
+  pub fn children_mut(&mut self) -> Option<&mut Vec<FilterNode>> {
+    match self {
+      FilterNode::And { children, .. } | FilterNode::Or { children, .. } => Some(children),
+      FilterNode::Data(_) => None,
+    }
+  }
+
+  /// Find the node with the given id in the tree, including the node itself.
+  pub fn find(&self, id: &str) -> Option<&FilterNode> {
+    if self.id() == Some(id) {
+      return Some(self);
+    }
+    self.children().iter().find_map(|child| child.find(id))
+  }
+
+  pub fn find_mut(&mut self, id: &str) -> Option<&mut FilterNode> {
+    if self.id() == Some(id) {
+      return Some(self);
+    }
+    self
+      .children_mut()?
+      .iter_mut()
+      .find_map(|child| child.find_mut(id))
+  }
+
+  /// Remove the descendant with the given id and return it.
+  pub fn remove(&mut self, id: &str) -> Option<FilterNode> {
+    let children = self.children_mut()?;
+    if let Some(index) = children.iter().position(|child| child.id() == Some(id)) {
+      return Some(children.remove(index));
+    }
+    children.iter_mut().find_map(|child| child.remove(id))
+  }
+
+  /// Return true if the conditions of the tree match. `matches` tells whether one condition
+  /// matches. An empty group doesn't filter anything out.
+  pub fn evaluate<F>(&self, matches: &F) -> bool
+  where
+    F: Fn(&FilterMap) -> bool,
+  {
+    match self {
+      FilterNode::Data(filter) => matches(filter),
+      FilterNode::And { children, .. } => children.iter().all(|child| child.evaluate(matches)),
+      FilterNode::Or { children, .. } => {
+        children.is_empty() || children.iter().any(|child| child.evaluate(matches))
+      },
+    }
+  }
+}
+
+impl From<FilterMap> for FilterNode {
+  fn from(filter: FilterMap) -> Self {
+    let filter_type = filter
+      .get_as::<i64>(FILTER_TYPE)
+      .map(FilterType::from)
+      .unwrap_or_default();
+    let id = || filter.get_as::<String>(FILTER_ID).unwrap_or_default();
+    let children = || match filter.get(FILTER_CHILDREN) {
+      Some(Any::Array(children)) => children
+        .iter()
+        .filter_map(|child| match child {
+          Any::Map(child) => Some(FilterNode::from((**child).clone())),
+          _ => None,
+        })
+        .collect(),
+      _ => vec![],
+    };
+    match filter_type {
+      FilterType::And => FilterNode::And {
+        id: id(),
+        children: children(),
+      },
+      FilterType::Or => FilterNode::Or {
+        id: id(),
+        children: children(),
+      },
+      FilterType::Data => FilterNode::Data(filter),
+    }
+  }
+}
+
+impl From<FilterNode> for FilterMap {
+  fn from(node: FilterNode) -> Self {
+    let filter_type = node.filter_type();
+    match node {
+      FilterNode::Data(filter) => filter,
+      FilterNode::And { id, children } | FilterNode::Or { id, children } => {
+        let children = children
+          .into_iter()
+          .map(|child| Any::Map(Arc::new(FilterMap::from(child))))
+          .collect::<Vec<_>>();
+        FilterMapBuilder::from([
+          (FILTER_ID.into(), id.into()),
+          (FILTER_TYPE.into(), Any::BigInt(filter_type as i64)),
+          (FILTER_CHILDREN.into(), Any::Array(Arc::from(children))),
+        ])
+      },
+    }
+  }
+}
+
+/// Write the filter into the map. The children of a group are written as an array holding one
+/// map per child, recursively. The children are left as they are if the filter has none.
+pub(crate) fn fill_filter_map(txn: &mut TransactionMut, map_ref: &MapRef, mut filter: FilterMap) {
+  let children = filter.remove(FILTER_CHILDREN);
+  Any::from(filter).fill(txn, map_ref).unwrap();
+  if let Some(Any::Array(children)) = children {
+    let array_ref: ArrayRef = map_ref.insert(txn, FILTER_CHILDREN, ArrayPrelim::default());
+    for child in children.iter() {
+      if let Any::Map(child) = child {
+        let child_ref = array_ref.push_back(txn, MapPrelim::default());
+        fill_filter_map(txn, &child_ref, (**child).clone());
+      }
+    }
+  }
+}
+
+/// Find the node with the given id in the filters of a view, at any depth. Return the array that
+/// holds the node, the index of the node in it and the map of the node.
+pub(crate) fn find_filter_node<T: ReadTxn>(
+  txn: &T,
+  array_ref: &ArrayRef,
+  id: &str,
+) -> Option<(ArrayRef, u32, MapRef)> {
+  if let Some(index) = array_ref.index_by_id(txn, id) {
+    let map_ref = array_ref.get(txn, index)?.cast::<MapRef>().ok()?;
+    return Some((array_ref.clone(), index, map_ref));
+  }
+  array_ref
+    .iter(txn)
+    .filter_map(|value| value.cast::<MapRef>().ok())
+    .filter_map(|map_ref| map_ref.get_with_txn::<_, ArrayRef>(txn, FILTER_CHILDREN))
+    .find_map(|children| find_filter_node(txn, &children, id))
+}
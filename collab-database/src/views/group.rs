@@ -10,6 +10,8 @@ pub type GroupSettingArrayUpdate = ArrayRef;
 /// One of the key/value represents as the [GroupMap]
 pub type GroupSettingMap = HashMap<String, Any>;
 pub type GroupSettingBuilder = HashMap<String, Any>;
+/// The field the rows are grouped by.
+pub const GROUP_FIELD_ID: &str = "field_id";
 
 /// [GroupMap] contains the key/value that represents a group data.
 pub type GroupMap = HashMap<String, Any>;
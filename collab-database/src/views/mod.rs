@@ -1,4 +1,6 @@
 mod calculation;
+mod calculation_observer;
+mod date_group;
 pub mod define;
 pub mod field_order;
 mod field_settings;
@@ -12,6 +14,8 @@ mod view_map;
 mod view_observer;
 
 pub use calculation::*;
+pub use calculation_observer::*;
+pub use date_group::*;
 pub use field_order::*;
 pub use field_settings::*;
 pub use filter::*;
@@ -1,6 +1,47 @@
-use collab::preclude::Any;
+use collab::preclude::{Any, Array, ArrayRef, Map, MapRef, TransactionMut};
 use std::collections::HashMap;
 
 pub type SortArray = Vec<Any>;
 pub type SortMap = HashMap<String, Any>;
 pub type SortMapBuilder = HashMap<String, Any>;
+/// The field the rows are sorted by.
+pub const SORT_FIELD_ID: &str = "field_id";
+
+/// The precedence of the sort, 0 is applied first. It's stored in each sort and written again
+/// from the order of the sorts whenever the sorts change. The sorts are read in the order of
+/// their priority, so every client applies them in the same order even if the sorts were
+/// reordered concurrently.
+pub const SORT_PRIORITY: &str = "priority";
+
+/// Write the index of each sort of the array into its [SORT_PRIORITY]. Only the priorities that
+/// changed are written.
+pub(crate) fn update_sort_priorities(txn: &mut TransactionMut, array_ref: &ArrayRef) {
+  for index in 0..array_ref.len(txn) {
+    if let Some(map_ref) = array_ref
+      .get(txn, index)
+      .and_then(|value| value.cast::<MapRef>().ok())
+    {
+      map_ref.try_update(txn, SORT_PRIORITY, Any::BigInt(index as i64));
+    }
+  }
+}
+
+/// Order the sorts by their [SORT_PRIORITY]. A sort written before the priority was stored takes
+/// its index as its priority.
+pub(crate) fn sorts_in_priority_order(sorts: Vec<SortMap>) -> Vec<SortMap> {
+  let mut sorts = sorts
+    .into_iter()
+    .enumerate()
+    .map(|(index, mut sort)| {
+      let priority = match sort.get(SORT_PRIORITY) {
+        Some(Any::BigInt(priority)) => *priority,
+        Some(Any::Number(priority)) => *priority as i64,
+        _ => index as i64,
+      };
+      sort.insert(SORT_PRIORITY.to_string(), Any::BigInt(priority));
+      (priority, sort)
+    })
+    .collect::<Vec<_>>();
+  sorts.sort_by_key(|(priority, _)| *priority);
+  sorts.into_iter().map(|(_, sort)| sort).collect()
+}
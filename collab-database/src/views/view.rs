@@ -1,19 +1,23 @@
 use collab::preclude::{
-  Any, Array, ArrayRef, FillRef, Map, MapExt, MapRef, ReadTxn, ToJson, TransactionMut, YrsValue,
+  Any, Array, ArrayRef, FillRef, Map, MapExt, MapPrelim, MapRef, ReadTxn, ToJson, TransactionMut,
+  YrsValue,
 };
-use collab::util::AnyExt;
-use std::collections::HashMap;
+use collab::util::{AnyExt, ArrayExt};
+use std::collections::{HashMap, HashSet};
 use tracing::trace;
 
-use super::CalculationMap;
+use super::{CalculationArray, CalculationMap, CALCULATION_FIELD_ID};
 
 use crate::entity::{DatabaseView, DatabaseViewMeta};
+use crate::rows::RowId;
 
 use crate::views::define::*;
+use crate::views::filter::fill_filter_map;
 use crate::views::layout::{DatabaseLayout, LayoutSettings};
+use crate::views::sort::{sorts_in_priority_order, update_sort_priorities};
 use crate::views::{
-  FieldOrder, FieldOrderArray, FieldSettingsByFieldIdMap, FilterArray, FilterMap,
-  GroupSettingArray, GroupSettingMap, LayoutSetting, RowOrder, RowOrderArray, SortArray, SortMap,
+  FieldOrder, FieldOrderArray, FieldSettingsByFieldIdMap, FilterMap, FilterNode, GroupSettingArray,
+  GroupSettingMap, LayoutSetting, RowOrder, RowOrderArray, SortArray, SortMap,
 };
 use crate::{impl_any_update, impl_i64_update, impl_order_update, impl_str_update};
 
@@ -38,7 +42,7 @@ impl<'a, 'b> ViewBuilder<'a, 'b> {
   pub fn done(self) {}
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub enum OrderObjectPosition {
   Start,
   Before(String),
@@ -47,6 +51,24 @@ pub enum OrderObjectPosition {
   End,
 }
 
+/// Where [crate::database::Database::duplicate_row] puts the duplicate, relative to the source row
+/// in each view.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateRowPosition {
+  Before,
+  #[default]
+  After,
+}
+
+impl DuplicateRowPosition {
+  pub fn order_position(&self, source_row_id: &str) -> OrderObjectPosition {
+    match self {
+      DuplicateRowPosition::Before => OrderObjectPosition::Before(source_row_id.to_string()),
+      DuplicateRowPosition::After => OrderObjectPosition::After(source_row_id.to_string()),
+    }
+  }
+}
+
 pub struct DatabaseViewUpdate<'a, 'b> {
   map_ref: &'a MapRef,
   txn: &'a mut TransactionMut<'b>,
@@ -136,6 +158,30 @@ impl<'a, 'b> DatabaseViewUpdate<'a, 'b> {
     self
   }
 
+  /// Set calculations of the current view
+  pub fn set_calculations(mut self, calculations: Vec<CalculationMap>) -> Self {
+    let array_ref = self.get_calculations_array();
+    let calculations: CalculationArray = calculations.into_iter().map(Any::from).collect();
+    Any::from(calculations).fill(self.txn, &array_ref).unwrap();
+    self
+  }
+
+  /// Remove the calculations of the field
+  pub fn remove_calculations_for_field(self, field_id: &str) -> Self {
+    self.update_calculations(|txn, array_ref| {
+      let field_id = Any::from(field_id);
+      let indexes = array_of_maps(array_ref.clone(), txn)
+        .iter()
+        .enumerate()
+        .filter(|(_, calculation)| calculation.get(CALCULATION_FIELD_ID) == Some(&field_id))
+        .map(|(index, _)| index as u32)
+        .collect::<Vec<_>>();
+      for index in indexes.into_iter().rev() {
+        array_ref.remove(txn, index);
+      }
+    })
+  }
+
   /// Update calculations
   pub fn update_calculations<F>(mut self, f: F) -> Self
   where
@@ -146,6 +192,39 @@ impl<'a, 'b> DatabaseViewUpdate<'a, 'b> {
     self
   }
 
+  /// Hide the row in this view only. The row is not deleted and stays in the other views.
+  pub fn hide_row(mut self, row_id: &RowId) -> Self {
+    let map_ref = self.get_hidden_rows_map();
+    map_ref.insert(self.txn, row_id.as_str(), true);
+    self
+  }
+
+  pub fn unhide_row(self, row_id: &RowId) -> Self {
+    if let Some(map_ref) = self
+      .map_ref
+      .get_with_txn::<_, MapRef>(self.txn, DATABASE_VIEW_HIDDEN_ROWS)
+    {
+      map_ref.remove(self.txn, row_id.as_str());
+    }
+    self
+  }
+
+  /// Set the hidden rows of the current view
+  pub fn set_hidden_rows(mut self, hidden_rows: HashSet<RowId>) -> Self {
+    let map_ref = self.get_hidden_rows_map();
+    map_ref.clear(self.txn);
+    for row_id in hidden_rows {
+      map_ref.insert(self.txn, row_id.as_str(), true);
+    }
+    self
+  }
+
+  fn get_hidden_rows_map(&mut self) -> MapRef {
+    self
+      .map_ref
+      .get_or_init(self.txn, DATABASE_VIEW_HIDDEN_ROWS)
+  }
+
   fn get_calculations_array(&mut self) -> ArrayRef {
     self.map_ref.get_or_init(self.txn, VIEW_CALCULATIONS)
   }
@@ -153,11 +232,20 @@ impl<'a, 'b> DatabaseViewUpdate<'a, 'b> {
   /// Set filters of the current view
   pub fn set_filters(mut self, filters: Vec<FilterMap>) -> Self {
     let array_ref = self.get_filter_array();
-    let filter_array: FilterArray = filters.into_iter().map(Any::from).collect();
-    Any::from(filter_array).fill(self.txn, &array_ref).unwrap();
+    array_ref.clear(self.txn);
+    for filter in filters {
+      let map_ref = array_ref.push_back(self.txn, MapPrelim::default());
+      fill_filter_map(self.txn, &map_ref, filter);
+    }
     self
   }
 
+  /// Set filters of the current view from filter trees. The groups are stored with their
+  /// children, see [FilterNode].
+  pub fn set_filter_nodes(self, filters: Vec<FilterNode>) -> Self {
+    self.set_filters(filters.into_iter().map(FilterMap::from).collect())
+  }
+
   /// Update filters
   /// The given function, [ArrayMapUpdate], which can be used to update the filters
   pub fn update_filters<F>(mut self, f: F) -> Self
@@ -190,22 +278,26 @@ impl<'a, 'b> DatabaseViewUpdate<'a, 'b> {
     self
   }
 
-  /// Set sorts of the current view
+  /// Set sorts of the current view. The [SORT_PRIORITY](crate::views::SORT_PRIORITY) of the sorts
+  /// is their index in the given sorts.
   pub fn set_sorts(mut self, sorts: Vec<SortMap>) -> Self {
     let array_ref = self.get_sort_array();
     let sort_array: SortArray = sorts.into_iter().map(Any::from).collect();
     Any::from(sort_array).fill(self.txn, &array_ref).unwrap();
+    update_sort_priorities(self.txn, &array_ref);
     self
   }
 
   /// Update sorts
-  /// The given function, [ArrayMapUpdate], which can be used to update the sorts
+  /// The given function, [ArrayMapUpdate], which can be used to update the sorts. The
+  /// [SORT_PRIORITY](crate::views::SORT_PRIORITY) of the sorts is updated afterwards.
   pub fn update_sorts<F>(mut self, f: F) -> Self
   where
     F: FnOnce(&mut TransactionMut, ArrayRef),
   {
     let array_ref = self.get_sort_array();
-    f(self.txn, array_ref);
+    f(self.txn, array_ref.clone());
+    update_sort_priorities(self.txn, &array_ref);
     self
   }
 
@@ -275,6 +367,9 @@ impl<'a, 'b> DatabaseViewUpdate<'a, 'b> {
 pub fn view_id_from_map_ref<T: ReadTxn>(map_ref: &MapRef, txn: &T) -> String {
   map_ref.get_with_txn(txn, VIEW_ID).unwrap_or_default()
 }
+pub fn view_modified_at_from_map_ref<T: ReadTxn>(map_ref: &MapRef, txn: &T) -> Option<i64> {
+  map_ref.get_with_txn(txn, VIEW_MODIFY_AT)
+}
 pub fn view_id_from_value<T: ReadTxn>(value: YrsValue, txn: &T) -> Option<String> {
   let map_ref: MapRef = value.cast().ok()?;
   Some(view_id_from_map_ref(&map_ref, txn))
@@ -322,7 +417,7 @@ pub fn group_setting_from_map_ref<T: ReadTxn>(txn: &T, map_ref: &MapRef) -> Vec<
 pub fn sorts_from_map_ref<T: ReadTxn>(txn: &T, map_ref: &MapRef) -> Vec<SortMap> {
   map_ref
     .get_with_txn::<_, ArrayRef>(txn, DATABASE_VIEW_SORTS)
-    .map(|array_ref| array_of_maps(array_ref, txn))
+    .map(|array_ref| sorts_in_priority_order(array_of_maps(array_ref, txn)))
     .unwrap_or_default()
 }
 
@@ -361,6 +456,19 @@ pub fn field_settings_from_map_ref<T: ReadTxn>(
     .unwrap_or_default()
 }
 
+/// Return the hidden rows of the view from the map ref of the view
+pub fn hidden_rows_from_map_ref<T: ReadTxn>(txn: &T, map_ref: &MapRef) -> HashSet<RowId> {
+  map_ref
+    .get_with_txn::<_, MapRef>(txn, DATABASE_VIEW_HIDDEN_ROWS)
+    .map(|map_ref| {
+      map_ref
+        .keys(txn)
+        .map(|row_id| RowId::from(row_id.to_string()))
+        .collect()
+    })
+    .unwrap_or_default()
+}
+
 /// Creates a new view from a map ref
 pub fn view_from_map_ref<T: ReadTxn>(map_ref: &MapRef, txn: &T) -> Option<DatabaseView> {
   let id: String = map_ref.get_with_txn(txn, VIEW_ID)?;
@@ -389,7 +497,7 @@ pub fn view_from_map_ref<T: ReadTxn>(map_ref: &MapRef, txn: &T) -> Option<Databa
 
   let sorts = map_ref
     .get_with_txn::<_, ArrayRef>(txn, DATABASE_VIEW_SORTS)
-    .map(|array_ref| array_of_maps(array_ref, txn))
+    .map(|array_ref| sorts_in_priority_order(array_of_maps(array_ref, txn)))
     .unwrap_or_default();
 
   let row_orders = map_ref
@@ -416,6 +524,8 @@ pub fn view_from_map_ref<T: ReadTxn>(map_ref: &MapRef, txn: &T) -> Option<Databa
     .unwrap_or_default();
 
   let is_inline: bool = map_ref.get_with_txn(txn, IS_INLINE).unwrap_or_default();
+  let hidden_rows = hidden_rows_from_map_ref(txn, map_ref);
+  let calculations = calculations_from_map_ref(txn, map_ref);
 
   Some(DatabaseView {
     id,
@@ -432,6 +542,8 @@ pub fn view_from_map_ref<T: ReadTxn>(map_ref: &MapRef, txn: &T) -> Option<Databa
     created_at,
     modified_at,
     is_inline,
+    hidden_rows,
+    calculations,
   })
 }
 
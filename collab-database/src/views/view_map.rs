@@ -2,19 +2,22 @@ use collab::preclude::{
   Array, ArrayRef, Map, MapExt, MapPrelim, MapRef, ReadTxn, Subscription, TransactionMut,
 };
 
-use crate::database::timestamp;
 use crate::entity::{DatabaseView, DatabaseViewMeta};
 use crate::rows::RowId;
 use crate::views::define::*;
 use crate::views::{
   field_settings_from_map_ref, filters_from_map_ref, group_setting_from_map_ref,
-  layout_setting_from_map_ref, sorts_from_map_ref, subscribe_view_map_change, view_from_map_ref,
-  view_from_value, view_meta_from_value, CalculationMap, DatabaseLayout, DatabaseViewUpdate,
-  FieldOrder, FieldOrderArray, FieldSettingsByFieldIdMap, FilterMap, GroupSettingMap,
-  LayoutSetting, OrderArray, RowOrder, RowOrderArray, SortMap, ViewBuilder, ViewChangeSender,
+  hidden_rows_from_map_ref, layout_setting_from_map_ref, sorts_from_map_ref,
+  subscribe_view_map_change, view_from_map_ref, view_from_value, view_meta_from_value,
+  CalculationMap, DatabaseLayout, DatabaseViewUpdate, FieldOrder, FieldOrderArray,
+  FieldSettingsByFieldIdMap, FilterMap, GroupSettingMap, LayoutSetting, OrderArray, RowOrder,
+  RowOrderArray, SortMap, ViewBuilder, ViewChangeSender,
 };
+use collab::core::clock::{system_clock, ClockProvider};
 use collab::core::origin::CollabOrigin;
+use std::collections::HashSet;
 use std::ops::Deref;
+use std::sync::Arc;
 
 use super::{calculations_from_map_ref, view_id_from_map_ref};
 
@@ -39,6 +42,7 @@ pub struct DatabaseViews {
   container: MapRef,
   #[allow(dead_code)]
   view_map_subscription: Option<Subscription>,
+  clock: Arc<dyn ClockProvider>,
 }
 
 impl Deref for DatabaseViews {
@@ -60,9 +64,16 @@ impl DatabaseViews {
     Self {
       container,
       view_map_subscription,
+      clock: system_clock(),
     }
   }
 
+  /// Set the clock that stamps the modified time of the updated views.
+  pub fn with_clock(mut self, clock: Arc<dyn ClockProvider>) -> Self {
+    self.clock = clock;
+    self
+  }
+
   pub fn insert_view(&self, txn: &mut TransactionMut, view: DatabaseView) {
     let map_ref = self
       .container
@@ -82,6 +93,8 @@ impl DatabaseViews {
         .set_sorts(view.sorts)
         .set_field_orders(view.field_orders)
         .set_row_orders(view.row_orders)
+        .set_hidden_rows(view.hidden_rows)
+        .set_calculations(view.calculations)
         .set_is_inline(view.is_inline);
     });
   }
@@ -198,6 +211,14 @@ impl DatabaseViews {
       })?
   }
 
+  pub fn get_hidden_rows<T: ReadTxn>(&self, txn: &T, view_id: &str) -> HashSet<RowId> {
+    self
+      .container
+      .get_with_txn::<_, MapRef>(txn, view_id)
+      .map(|map_ref| hidden_rows_from_map_ref(txn, &map_ref))
+      .unwrap_or_default()
+  }
+
   pub fn get_row_orders<T: ReadTxn>(&self, txn: &T, view_id: &str) -> Vec<RowOrder> {
     self
       .container
@@ -254,7 +275,7 @@ impl DatabaseViews {
   {
     if let Some(map_ref) = self.container.get_with_txn::<_, MapRef>(txn, view_id) {
       let mut update = DatabaseViewUpdate::new(txn, &map_ref);
-      update = update.set_modified_at(timestamp());
+      update = update.set_modified_at(self.clock.timestamp());
       f(update)
     } else {
       tracing::error!(
@@ -277,7 +298,7 @@ impl DatabaseViews {
     for map_ref in map_refs {
       let view_id = view_id_from_map_ref(&map_ref, txn);
       let mut update = DatabaseViewUpdate::new(txn, &map_ref);
-      update = update.set_modified_at(timestamp());
+      update = update.set_modified_at(self.clock.timestamp());
       f(view_id, update)
     }
   }
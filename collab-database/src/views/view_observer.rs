@@ -1,15 +1,18 @@
 use crate::entity::DatabaseView;
+use crate::rows::RowId;
 use crate::views::define::*;
 use crate::views::{
-  row_order_from_value, view_from_map_ref, view_from_value, view_id_from_map_ref, DatabaseLayout,
-  FieldOrder, FilterMap, GroupMap, RowOrder, SortMap,
+  field_order_from_value, hidden_rows_from_map_ref, row_order_from_value, view_from_map_ref,
+  view_from_value, view_id_from_map_ref, DatabaseLayout, FieldOrder, FilterMap, GroupMap,
+  OrderObjectPosition, RowOrder, SortMap,
 };
 use collab::core::origin::CollabOrigin;
 use collab::preclude::array::ArrayEvent;
 use collab::preclude::map::MapEvent;
-use collab::preclude::{Change, MapRef, Subscription, ToJson, TransactionMut};
+use collab::preclude::{Array, Change, Map, MapRef, Subscription, ToJson, TransactionMut};
 use collab::preclude::{DeepObservable, EntryChange, Event, PathSegment};
 use collab::util::AnyExt;
+use std::collections::HashSet;
 use std::ops::Deref;
 use std::str::FromStr;
 use tokio::sync::broadcast;
@@ -69,6 +72,18 @@ pub enum DatabaseViewChange {
     view_id: String,
     field_order: FieldOrder,
   },
+  /// Sent once per transaction, local or remote, for all the views in which the field was moved
+  /// to the same position.
+  DidMoveFieldOrder {
+    field_id: String,
+    view_ids: Vec<String>,
+    position: OrderObjectPosition,
+  },
+  // hidden rows
+  DidUpdateHiddenRows {
+    view_id: String,
+    hidden_rows: HashSet<RowId>,
+  },
 }
 
 pub type ViewChangeSender = broadcast::Sender<DatabaseViewChange>;
@@ -82,21 +97,97 @@ pub(crate) fn subscribe_view_map_change(
   view_map.observe_deep(move |txn, events| {
     let txn_origin = CollabOrigin::from(txn);
     let is_local = txn_origin == origin;
+    let mut field_moves: Vec<(String, OrderObjectPosition, Vec<String>)> = vec![];
     for event in events.iter() {
       match event {
         Event::Text(_) => {},
         Event::Array(array_event) => {
+          if let ArrayChangeKey::FieldOrder = ArrayChangeKey::from(array_event) {
+            if let Some(view_id) = view_id_from_array_event(array_event) {
+              for (field_id, position) in moved_field_orders(txn, array_event) {
+                match field_moves
+                  .iter_mut()
+                  .find(|(id, other, _)| *id == field_id && *other == position)
+                {
+                  Some((_, _, view_ids)) => view_ids.push(view_id.clone()),
+                  None => field_moves.push((field_id, position, vec![view_id.clone()])),
+                }
+              }
+            }
+            continue;
+          }
           handle_array_event(&change_tx, txn, array_event, is_local);
         },
         Event::Map(event) => {
+          if let Some(change) = hidden_rows_change_from_event(txn, event) {
+            let _ = change_tx.send(change);
+            if event.path().len() > 1 {
+              continue;
+            }
+          }
           handle_map_event(&change_tx, txn, event, is_local);
         },
         _ => {},
       }
     }
+    for (field_id, position, mut view_ids) in field_moves {
+      view_ids.sort();
+      let _ = change_tx.send(DatabaseViewChange::DidMoveFieldOrder {
+        field_id,
+        view_ids,
+        position,
+      });
+    }
   })
 }
 
+/// Returns the fields moved by the event of the field orders of a view, with their new position.
+/// A move removes the field order and inserts it again, so the inserted field orders are moved
+/// when as many field orders are removed. Otherwise the fields were created or deleted.
+fn moved_field_orders(
+  txn: &TransactionMut,
+  array_event: &ArrayEvent,
+) -> Vec<(String, OrderObjectPosition)> {
+  let mut inserted = vec![];
+  let mut num_of_removed = 0;
+  let mut offset = 0;
+  for change in array_event.delta(txn) {
+    match change {
+      Change::Added(values) => {
+        for value in values {
+          if let Some(field_order) = field_order_from_value(value.clone(), txn) {
+            inserted.push((field_order.id, offset));
+          }
+          offset += 1;
+        }
+      },
+      Change::Removed(len) => num_of_removed += len,
+      Change::Retain(len) => offset += len,
+    }
+  }
+  if inserted.is_empty() || inserted.len() != num_of_removed as usize {
+    return vec![];
+  }
+
+  let field_ids = array_event
+    .target()
+    .iter(txn)
+    .flat_map(|value| field_order_from_value(value, txn))
+    .map(|field_order| field_order.id)
+    .collect::<Vec<_>>();
+  inserted
+    .into_iter()
+    .map(|(field_id, index)| {
+      let position = match index.checked_sub(1).and_then(|i| field_ids.get(i as usize)) {
+        None => OrderObjectPosition::Start,
+        Some(_) if index as usize + 1 == field_ids.len() => OrderObjectPosition::End,
+        Some(prev_id) => OrderObjectPosition::After(prev_id.clone()),
+      };
+      (field_id, position)
+    })
+    .collect()
+}
+
 /// Handles an array modification process consisting of retain and remove operations.
 ///
 /// # Process
@@ -176,6 +267,8 @@ fn handle_array_event(
                 change_tx.send(DatabaseViewChange::DidCreateGroupSettings { view_id, groups });
             }
           },
+          // The field orders are handled by moved_field_orders
+          ArrayChangeKey::FieldOrder => {},
           ArrayChangeKey::Unhandled(s) => {
             trace!("database view observe unknown insert: {}", s);
           },
@@ -206,6 +299,7 @@ fn handle_array_event(
                 let _ = change_tx.send(DatabaseViewChange::DidUpdateGroupSetting { view_id });
               }
             },
+            ArrayChangeKey::FieldOrder => {},
             ArrayChangeKey::Unhandled(_s) => {
               #[cfg(feature = "verbose_log")]
               trace!("database view observe unknown remove: {}", _s);
@@ -287,10 +381,44 @@ fn handle_map_event(
   }
 }
 
+/// Returns the hidden rows of the view when the event updates them. The first hidden row of a view
+/// creates the map of the hidden rows, which is an event of the view map.
+fn hidden_rows_change_from_event(
+  txn: &TransactionMut,
+  event: &MapEvent,
+) -> Option<DatabaseViewChange> {
+  let path = event.path();
+  match (path.front(), path.get(1), path.len()) {
+    (Some(PathSegment::Key(view_id)), Some(PathSegment::Key(key)), 2)
+      if key.as_ref() == DATABASE_VIEW_HIDDEN_ROWS =>
+    {
+      let hidden_rows = event
+        .target()
+        .keys(txn)
+        .map(|row_id| RowId::from(row_id.to_string()))
+        .collect();
+      Some(DatabaseViewChange::DidUpdateHiddenRows {
+        view_id: view_id.to_string(),
+        hidden_rows,
+      })
+    },
+    (Some(PathSegment::Key(view_id)), None, 1)
+      if event.keys(txn).contains_key(DATABASE_VIEW_HIDDEN_ROWS) =>
+    {
+      Some(DatabaseViewChange::DidUpdateHiddenRows {
+        view_id: view_id.to_string(),
+        hidden_rows: hidden_rows_from_map_ref(txn, event.target()),
+      })
+    },
+    _ => None,
+  }
+}
+
 #[derive(Debug)]
 enum ArrayChangeKey {
   Unhandled(String),
   RowOrder,
+  FieldOrder,
   Filter,
   Sort,
   Group,
@@ -302,6 +430,7 @@ impl From<&ArrayEvent> for ArrayChangeKey {
       Some(segment) => match segment {
         PathSegment::Key(s) => match s.as_ref() {
           DATABASE_VIEW_ROW_ORDERS => Self::RowOrder,
+          DATABASE_VIEW_FIELD_ORDERS => Self::FieldOrder,
           DATABASE_VIEW_FILTERS => Self::Filter,
           DATABASE_VIEW_SORTS => Self::Sort,
           DATABASE_VIEW_GROUPS => Self::Group,
@@ -1,6 +1,6 @@
-use crate::database::timestamp;
 use crate::error::DatabaseError;
 use anyhow::anyhow;
+use collab::core::clock::{system_clock, ClockProvider};
 use collab::core::collab::DataSource;
 use collab::core::origin::CollabOrigin;
 use collab::entity::EncodedCollab;
@@ -12,11 +12,13 @@ use collab_entity::define::WORKSPACE_DATABASES;
 use collab_entity::CollabType;
 use std::borrow::{Borrow, BorrowMut};
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
 /// Used to store list of [DatabaseMeta].
 pub struct WorkspaceDatabase {
   collab: Collab,
   body: WorkspaceDatabaseBody,
+  clock: Arc<dyn ClockProvider>,
 }
 
 pub fn default_workspace_database_data(object_id: &str) -> EncodedCollab {
@@ -31,12 +33,26 @@ impl WorkspaceDatabase {
   pub fn open(mut collab: Collab) -> Result<Self, DatabaseError> {
     CollabType::WorkspaceDatabase.validate_require_data(&collab)?;
     let body = WorkspaceDatabaseBody::open(&mut collab)?;
-    Ok(Self { body, collab })
+    Ok(Self {
+      body,
+      collab,
+      clock: system_clock(),
+    })
   }
 
   pub fn create(mut collab: Collab) -> Self {
     let body = WorkspaceDatabaseBody::create(&mut collab);
-    Self { body, collab }
+    Self {
+      body,
+      collab,
+      clock: system_clock(),
+    }
+  }
+
+  /// Set the clock that stamps the created time of the added databases.
+  pub fn with_clock(mut self, clock: Arc<dyn ClockProvider>) -> Self {
+    self.clock = clock;
+    self
   }
 
   pub fn from_collab_doc_state(
@@ -61,7 +77,7 @@ impl WorkspaceDatabase {
     let linked_views: HashSet<String> = view_ids.into_iter().collect();
     let record = DatabaseMeta {
       database_id: database_id.to_string(),
-      created_at: timestamp(),
+      created_at: self.clock.timestamp(),
       linked_views: linked_views.into_iter().collect(),
     };
     self.body.push_back(&mut txn, record);
@@ -71,12 +87,13 @@ impl WorkspaceDatabase {
     &mut self,
     view_ids_by_database_id: HashMap<String, Vec<String>>,
   ) -> TransactionMut {
+    let timestamp = self.clock.timestamp();
     let mut txn = self.collab.transact_mut();
     for (database_id, view_ids) in view_ids_by_database_id {
       let linked_views: HashSet<String> = view_ids.into_iter().collect();
       let record = DatabaseMeta {
         database_id,
-        created_at: timestamp(),
+        created_at: timestamp,
         linked_views: linked_views.into_iter().collect(),
       };
       self.body.push_back(&mut txn, record);
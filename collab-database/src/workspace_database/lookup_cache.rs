@@ -0,0 +1,220 @@
+use crate::blocks::BlockEvent;
+use crate::database::Database;
+use crate::fields::relation_type_option::LookupValue;
+use crate::fields::Field;
+use crate::rows::{RowChange, RowChangeReceiver, RowId};
+use crate::workspace_database::memory_budget::next_access_tick;
+use dashmap::DashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use tokio::sync::broadcast::error::TryRecvError;
+use tokio::sync::broadcast::Receiver;
+
+/// The default number of values kept by a [LookupCache].
+pub const LOOKUP_CACHE_CAPACITY: usize = 10_000;
+
+/// Caches the values of the lookup cells resolved by
+/// [crate::workspace_database::WorkspaceDatabaseManager::resolve_lookup_values]. A value is cached
+/// by the linked row and the target field it was pulled from, so the lookup cells linking to the
+/// same row share it.
+///
+/// The cached values of a row are invalidated by the changes sent by the row observers of its
+/// database, and by the rows fetched from the remote. A value is also dropped when its target
+/// field changed. Once the cache is full, the least recently used value is evicted.
+#[derive(Debug)]
+pub(crate) struct LookupCache {
+  entries: DashMap<LookupCacheKey, LookupCacheEntry>,
+  /// The changes of the rows of the databases the values were pulled from, by database id.
+  watchers: DashMap<String, LookupCacheWatcher>,
+  capacity: AtomicUsize,
+  hits: AtomicU64,
+  misses: AtomicU64,
+  evictions: AtomicU64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct LookupCacheKey {
+  row_id: RowId,
+  field_id: String,
+}
+
+#[derive(Debug)]
+struct LookupCacheEntry {
+  database_id: String,
+  field: Field,
+  value: LookupValue,
+  accessed_at: u64,
+}
+
+#[derive(Debug)]
+struct LookupCacheWatcher {
+  row_change_rx: Option<RowChangeReceiver>,
+  block_event_rx: Receiver<BlockEvent>,
+}
+
+/// The metrics of the [LookupCache] of a [crate::workspace_database::WorkspaceDatabaseManager].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LookupCacheMetrics {
+  pub entries: usize,
+  /// The number of values reused from the cache.
+  pub hits: u64,
+  /// The number of values that were pulled from the linked rows.
+  pub misses: u64,
+  /// The number of values evicted because the cache was full.
+  pub evictions: u64,
+}
+
+impl Default for LookupCache {
+  fn default() -> Self {
+    Self {
+      entries: DashMap::new(),
+      watchers: DashMap::new(),
+      capacity: AtomicUsize::new(LOOKUP_CACHE_CAPACITY),
+      hits: AtomicU64::new(0),
+      misses: AtomicU64::new(0),
+      evictions: AtomicU64::new(0),
+    }
+  }
+}
+
+impl LookupCache {
+  /// Set the number of values kept by the cache. The least recently used values are evicted
+  /// until the cache fits.
+  pub(crate) fn set_capacity(&self, capacity: usize) {
+    self.capacity.store(capacity, Ordering::Relaxed);
+    self.evict();
+  }
+
+  /// Listen to the changes of the rows of the database, so the values pulled from them are
+  /// invalidated. Does nothing if the database is already watched.
+  pub(crate) fn watch_database(&self, database_id: &str, database: &Database) {
+    self
+      .watchers
+      .entry(database_id.to_string())
+      .or_insert_with(|| LookupCacheWatcher {
+        row_change_rx: database.subscribe_row_change(),
+        block_event_rx: database.subscribe_block_event(),
+      });
+  }
+
+  /// Remove the values of the rows of the database that changed since the last call. When some
+  /// changes were missed, all the values pulled from the database are removed.
+  pub(crate) fn invalidate_changed_rows(&self, database_id: &str) {
+    let mut changed = vec![];
+    let mut lagged = false;
+    if let Some(mut watcher) = self.watchers.get_mut(database_id) {
+      if let Some(row_change_rx) = watcher.row_change_rx.as_mut() {
+        loop {
+          match row_change_rx.try_recv() {
+            Ok(RowChange::DidUpdateCell {
+              row_id, field_id, ..
+            }) => changed.push((row_id, Some(field_id))),
+            Ok(RowChange::DidUpdateRowComment { .. }) => {},
+            Ok(RowChange::DidUpdateVisibility { .. }) => {},
+            Ok(RowChange::DidUpdateHeight { .. }) => {},
+            Ok(RowChange::DidChangeRowComment { .. }) => {},
+            Err(TryRecvError::Lagged(_)) => lagged = true,
+            Err(TryRecvError::Empty) | Err(TryRecvError::Closed) => break,
+          }
+        }
+      }
+      loop {
+        match watcher.block_event_rx.try_recv() {
+          Ok(BlockEvent::DidFetchRow(rows)) => {
+            changed.extend(rows.into_iter().map(|row| (row.row.id, None)));
+          },
+          Err(TryRecvError::Lagged(_)) => lagged = true,
+          Err(TryRecvError::Empty) | Err(TryRecvError::Closed) => break,
+        }
+      }
+    }
+
+    if lagged {
+      self
+        .entries
+        .retain(|_, entry| entry.database_id != database_id);
+      return;
+    }
+    for (row_id, field_id) in changed {
+      match field_id {
+        Some(field_id) => {
+          self.entries.remove(&LookupCacheKey { row_id, field_id });
+        },
+        None => self.entries.retain(|key, _| key.row_id != row_id),
+      }
+    }
+  }
+
+  /// Return the cached value of the row if its target field is unchanged.
+  pub(crate) fn get(&self, field: &Field, row_id: &RowId) -> Option<LookupValue> {
+    let key = LookupCacheKey {
+      row_id: row_id.clone(),
+      field_id: field.id.clone(),
+    };
+    let value = self
+      .entries
+      .get_mut(&key)
+      .filter(|entry| entry.field == *field)
+      .map(|mut entry| {
+        entry.accessed_at = next_access_tick();
+        entry.value.clone()
+      });
+    match value {
+      None => self.misses.fetch_add(1, Ordering::Relaxed),
+      Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+    };
+    value
+  }
+
+  pub(crate) fn insert(&self, database_id: &str, field: &Field, value: LookupValue) {
+    let key = LookupCacheKey {
+      row_id: value.row_id.clone(),
+      field_id: field.id.clone(),
+    };
+    self.entries.insert(
+      key,
+      LookupCacheEntry {
+        database_id: database_id.to_string(),
+        field: field.clone(),
+        value,
+        accessed_at: next_access_tick(),
+      },
+    );
+    self.evict();
+  }
+
+  /// Evict the least recently used values until the cache fits in its capacity.
+  fn evict(&self) {
+    let capacity = self.capacity.load(Ordering::Relaxed);
+    while self.entries.len() > capacity {
+      let least_recently_used = self
+        .entries
+        .iter()
+        .min_by_key(|entry| entry.accessed_at)
+        .map(|entry| entry.key().clone());
+      match least_recently_used {
+        None => break,
+        Some(key) => {
+          self.entries.remove(&key);
+          self.evictions.fetch_add(1, Ordering::Relaxed);
+        },
+      }
+    }
+  }
+
+  /// Remove the values pulled from the rows of the database and stop watching its rows.
+  pub(crate) fn remove_database(&self, database_id: &str) {
+    self
+      .entries
+      .retain(|_, entry| entry.database_id != database_id);
+    self.watchers.remove(database_id);
+  }
+
+  pub(crate) fn metrics(&self) -> LookupCacheMetrics {
+    LookupCacheMetrics {
+      entries: self.entries.len(),
+      hits: self.hits.load(Ordering::Relaxed),
+      misses: self.misses.load(Ordering::Relaxed),
+      evictions: self.evictions.load(Ordering::Relaxed),
+    }
+  }
+}
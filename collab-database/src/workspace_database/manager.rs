@@ -1,27 +1,47 @@
 use crate::database::{try_fixing_database, Database, DatabaseContext, DatabaseData};
 
+use crate::database_statistics::{
+  database_field_statistics, CrossDatabaseFieldStatistics, FieldMatcher,
+};
+use crate::entity::FieldType;
 use crate::error::DatabaseError;
+use crate::fields::relation_type_option::{
+  LookupTypeOption, LookupValue, PendingUnlinks, RelationCellData, RelationTypeOption,
+  RollupTypeOption,
+};
+use crate::fields::stringify_type_option;
+use crate::rows::{Cell, RowId};
+use crate::template::entity::CELL_DATA;
 use crate::workspace_database::body::{DatabaseMeta, WorkspaceDatabase};
+use crate::workspace_database::lookup_cache::{LookupCache, LookupCacheMetrics};
+use crate::workspace_database::memory_budget::{
+  estimate_collab_size, DatabaseLru, DatabaseMemoryBudget, ResidentCollabMetrics,
+};
 use async_trait::async_trait;
+use collab::core::clock::{system_clock, ClockProvider};
 use collab::core::collab::DataSource;
 use collab::preclude::Collab;
 use collab_entity::CollabType;
 
 use collab::entity::EncodedCollab;
 
-use crate::entity::{CreateDatabaseParams, CreateViewParams, CreateViewParamsValidator};
+use crate::entity::{
+  CreateDatabaseParams, CreateDatabaseParamsValidator, CreateViewParams, CreateViewParamsValidator,
+};
 
 use anyhow::anyhow;
 use collab::core::collab_plugin::CollabPersistence;
 use collab::core::origin::CollabOrigin;
 use collab::error::CollabError;
 use collab::lock::RwLock;
+use collab::util::AnyMapExt;
 use dashmap::DashMap;
 use rayon::prelude::*;
 use std::borrow::{Borrow, BorrowMut};
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use tracing::{error, info};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, trace, warn};
 
 pub type EncodeCollabByOid = HashMap<String, EncodedCollab>;
 pub type DataSourceByOid = HashMap<String, DataSource>;
@@ -137,6 +157,8 @@ pub trait DatabaseCollabPersistenceService: Send + Sync + 'static {
 
   fn is_collab_exist(&self, object_id: &str) -> bool;
 
+  /// Write all the given collabs to disk. The implementation should write them atomically, so
+  /// a database is never left with only part of its collabs persisted.
   fn flush_collabs(
     &self,
     encoded_collabs: Vec<(String, EncodedCollab)>,
@@ -189,10 +211,17 @@ pub struct WorkspaceDatabaseManager {
   object_id: String,
   body: WorkspaceDatabase,
   collab_service: Arc<dyn DatabaseCollabService>,
+  clock: Arc<dyn ClockProvider>,
   /// In memory database handlers.
   /// The key is the database id. The handler will be added when the database is opened or created.
   /// and the handler will be removed when the database is deleted or closed.
   databases: DashMap<String, Arc<RwLock<Database>>>,
+  memory_budget: DatabaseMemoryBudget,
+  lru: DatabaseLru,
+  create_database_validator: CreateDatabaseParamsValidator,
+  lookup_cache: LookupCache,
+  /// The links back to the rows removed from the opened databases, left in the other databases.
+  pending_unlinks: PendingUnlinks,
 }
 
 impl WorkspaceDatabaseManager {
@@ -207,7 +236,13 @@ impl WorkspaceDatabaseManager {
       object_id: object_id.to_string(),
       body,
       collab_service,
+      clock: system_clock(),
       databases: DashMap::new(),
+      memory_budget: DatabaseMemoryBudget::unlimited(),
+      lru: DatabaseLru::default(),
+      create_database_validator: CreateDatabaseParamsValidator::default(),
+      lookup_cache: LookupCache::default(),
+      pending_unlinks: PendingUnlinks::default(),
     })
   }
 
@@ -222,10 +257,52 @@ impl WorkspaceDatabaseManager {
       object_id: object_id.to_string(),
       body,
       collab_service,
+      clock: system_clock(),
       databases: DashMap::new(),
+      memory_budget: DatabaseMemoryBudget::unlimited(),
+      lru: DatabaseLru::default(),
+      create_database_validator: CreateDatabaseParamsValidator::default(),
+      lookup_cache: LookupCache::default(),
+      pending_unlinks: PendingUnlinks::default(),
     })
   }
 
+  /// Set the clock that stamps the created and modified times of the databases opened or created
+  /// by this manager.
+  pub fn with_clock(mut self, clock: Arc<dyn ClockProvider>) -> Self {
+    self.body = self.body.with_clock(clock.clone());
+    self.clock = clock;
+    self
+  }
+
+  /// Set the budget of the databases and rows kept in memory, see [DatabaseMemoryBudget]. The
+  /// budget is enforced each time a database is got, opened or created.
+  pub fn set_memory_budget(&mut self, memory_budget: DatabaseMemoryBudget) {
+    self.memory_budget = memory_budget;
+  }
+
+  /// Set the checks of the params of [Self::create_database], see
+  /// [CreateDatabaseParamsValidator].
+  pub fn set_create_database_validator(&mut self, validator: CreateDatabaseParamsValidator) {
+    self.create_database_validator = validator;
+  }
+
+  pub fn memory_budget(&self) -> DatabaseMemoryBudget {
+    self.memory_budget
+  }
+
+  /// Return the number of databases and rows kept in memory, and the number of evicted ones.
+  pub fn resident_collab_metrics(&self) -> ResidentCollabMetrics {
+    self.lru.metrics()
+  }
+
+  fn database_context(&self) -> DatabaseContext {
+    DatabaseContext::new(self.collab_service.clone())
+      .with_clock(self.clock.clone())
+      .with_create_database_validator(self.create_database_validator.clone())
+      .with_pending_unlinks(self.pending_unlinks.clone())
+  }
+
   pub fn close(&self) {
     self.body.close();
   }
@@ -242,6 +319,62 @@ impl WorkspaceDatabaseManager {
     &self,
     database_id: &str,
   ) -> Result<Arc<RwLock<Database>>, DatabaseError> {
+    let database = self.open_database(database_id).await?;
+    self.apply_pending_unlinks().await;
+    Ok(database)
+  }
+
+  /// Remove the links back to the removed rows that the databases left in the other databases,
+  /// and add the links back to the duplicated rows, see [PendingUnlinks]. The links of a
+  /// database that is locked are kept for the next time.
+  async fn apply_pending_unlinks(&self) {
+    if self.pending_unlinks.is_empty() {
+      return;
+    }
+    let mut unlinks_by_database = HashMap::<String, Vec<_>>::new();
+    for unlink in self.pending_unlinks.take() {
+      unlinks_by_database
+        .entry(unlink.database_id.clone())
+        .or_default()
+        .push(unlink);
+    }
+    for (database_id, unlinks) in unlinks_by_database {
+      let database = match self.open_database(&database_id).await {
+        Ok(database) => database,
+        Err(DatabaseError::DatabaseNotExist) => continue,
+        Err(err) => {
+          warn!("Keep the links back to the removed rows: {}", err);
+          self.pending_unlinks.extend(unlinks);
+          continue;
+        },
+      };
+      let mut database = match database.try_write() {
+        Ok(database) => database,
+        Err(_) => {
+          self.pending_unlinks.extend(unlinks);
+          continue;
+        },
+      };
+      for unlink in unlinks {
+        let row_ids = std::slice::from_ref(&unlink.unlinked_row_id);
+        let (linked_row_ids, unlinked_row_ids) = if unlink.link {
+          (row_ids, &[][..])
+        } else {
+          (&[][..], row_ids)
+        };
+        database
+          .update_relation_links(
+            &unlink.row_id,
+            &unlink.field_id,
+            linked_row_ids,
+            unlinked_row_ids,
+          )
+          .await;
+      }
+    }
+  }
+
+  async fn open_database(&self, database_id: &str) -> Result<Arc<RwLock<Database>>, DatabaseError> {
     // Check if the database exists in the body
     if !self.body.contains(database_id) {
       return Err(DatabaseError::DatabaseNotExist);
@@ -249,39 +382,28 @@ impl WorkspaceDatabaseManager {
 
     // Check if the database is already initialized and cached
     if let Some(database) = self.databases.get(database_id).as_deref().cloned() {
+      self.lru.touch(database_id);
+      self.enforce_memory_budget();
       return Ok(database);
     }
 
     // Helper function to insert the database into the cache
-    let insert_database = |db: Database| -> Arc<RwLock<Database>> {
-      let database = Arc::new(RwLock::new(db));
-      self
-        .databases
-        .insert(database_id.to_string(), database.clone());
-      database
-    };
+    let insert_database =
+      |db: Database| -> Arc<RwLock<Database>> { self.cache_database(database_id, db) };
 
     // Try to open the database
-    let context = DatabaseContext::new(self.collab_service.clone());
+    let context = self.database_context();
     match Database::open(database_id, context).await {
       Ok(database) => Ok(insert_database(database)),
       // If the database is missing required data, try to fix it and open it again
       Err(err) => {
         if err.is_no_required_data() {
           if self
-            .fix_and_open_database(
-              database_id,
-              DatabaseContext::new(self.collab_service.clone()),
-            )
+            .fix_and_open_database(database_id, self.database_context())
             .await
             .is_ok()
           {
-            if let Ok(database) = Database::open(
-              database_id,
-              DatabaseContext::new(self.collab_service.clone()),
-            )
-            .await
-            {
+            if let Ok(database) = Database::open(database_id, self.database_context()).await {
               return Ok(insert_database(database));
             }
           }
@@ -319,19 +441,74 @@ impl WorkspaceDatabaseManager {
   ) -> Result<Arc<RwLock<Database>>, DatabaseError> {
     debug_assert!(!params.database_id.is_empty());
 
-    let context = DatabaseContext::new(self.collab_service.clone());
-    // Add a new database record.
+    let context = self.database_context();
     let mut linked_views = HashSet::new();
     linked_views.extend(params.views.iter().map(|view| view.view_id.clone()));
+    let database_id = params.database_id.clone();
+    let database = Database::create_with_view(params, context).await?;
+    // Add a new database record.
     self
       .body
-      .add_database(&params.database_id, linked_views.into_iter().collect());
-    let database_id = params.database_id.clone();
-    let database = Database::create_with_view(params, context).await.unwrap();
-    let mutex_database = RwLock::from(database);
-    let database = Arc::new(mutex_database);
-    self.databases.insert(database_id, database.clone());
-    Ok(database)
+      .add_database(&database_id, linked_views.into_iter().collect());
+    Ok(self.cache_database(&database_id, database))
+  }
+
+  /// Keep the database in memory, then close the least recently used databases that are not in
+  /// use until the [DatabaseMemoryBudget] is met. The returned database is in use, so it's kept.
+  fn cache_database(&self, database_id: &str, database: Database) -> Arc<RwLock<Database>> {
+    let bytes = estimate_collab_size(&database.collab);
+    self.lookup_cache.watch_database(database_id, &database);
+    self
+      .lru
+      .insert(database_id, bytes, database.body.blocks.clone());
+    let database = Arc::new(RwLock::new(database));
+    self
+      .databases
+      .insert(database_id.to_string(), database.clone());
+    self.enforce_memory_budget();
+    database
+  }
+
+  /// Unload the least recently used rows, then close the least recently used databases, that
+  /// are not used outside of the manager until the [DatabaseMemoryBudget] is met. Return the
+  /// number of closed databases.
+  pub fn enforce_memory_budget(&self) -> usize {
+    if self.memory_budget.is_rows_exceeded(&self.lru.resident()) {
+      for (database_id, row_id) in self.lru.least_recently_used_rows() {
+        if !self.memory_budget.is_rows_exceeded(&self.lru.resident()) {
+          break;
+        }
+        if self.lru.evict_row(&database_id, &row_id) {
+          trace!(
+            "evict row {} of database {} from memory",
+            row_id,
+            database_id
+          );
+        }
+      }
+    }
+
+    let mut num_of_evicted = 0;
+    for database_id in self.lru.least_recently_used() {
+      if !self.memory_budget.is_exceeded(&self.lru.resident()) {
+        break;
+      }
+      let is_evicted = self
+        .databases
+        .remove_if(&database_id, |_, database| Arc::strong_count(database) == 1)
+        .is_some();
+      if is_evicted {
+        let num_of_rows = self.lru.remove(&database_id);
+        self.lru.record_eviction(num_of_rows);
+        num_of_evicted += 1;
+        trace!(
+          "evict database {} with {} rows from memory",
+          database_id,
+          num_of_rows
+        );
+      }
+    }
+    num_of_evicted
   }
 
   /// Create linked view that shares the same data with the inline view's database
@@ -365,10 +542,16 @@ impl WorkspaceDatabaseManager {
       }
     }
     self.databases.remove(database_id);
+    self.lru.remove(database_id);
+    self.lookup_cache.remove_database(database_id);
   }
 
+  /// Remove the database and its rows from memory. It's opened again by
+  /// [Self::get_or_init_database].
   pub fn close_database(&self, database_id: &str) {
     let _ = self.databases.remove(database_id);
+    self.lru.remove(database_id);
+    self.lookup_cache.remove_database(database_id);
   }
 
   pub fn track_database(&mut self, database_id: &str, database_view_ids: Vec<String>) {
@@ -425,6 +608,288 @@ impl WorkspaceDatabaseManager {
     }
   }
 
+  /// Aggregate the field that matches in each of the given databases, for example the sum of
+  /// the "Cost" field of all the project databases. The rows of each database are streamed, see
+  /// [database_field_statistics]. The databases that can't be opened or that have no matching
+  /// field are listed in [CrossDatabaseFieldStatistics::skipped].
+  pub async fn get_field_statistics(
+    &self,
+    database_ids: &[String],
+    matcher: &FieldMatcher,
+    cancel_token: Option<CancellationToken>,
+  ) -> CrossDatabaseFieldStatistics {
+    let mut statistics = CrossDatabaseFieldStatistics::default();
+    for database_id in database_ids {
+      if cancel_token
+        .as_ref()
+        .map(|token| token.is_cancelled())
+        .unwrap_or(false)
+      {
+        break;
+      }
+      let database_statistics = match self.get_or_init_database(database_id).await {
+        Ok(database) => {
+          let database = database.read().await;
+          database_field_statistics(&database, matcher, cancel_token.clone()).await
+        },
+        Err(err) => {
+          error!(
+            "Open database {} for statistics failed: {}",
+            database_id, err
+          );
+          None
+        },
+      };
+      match database_statistics {
+        Some(database_statistics) => statistics.add(database_statistics),
+        None => statistics.skipped.push(database_id.clone()),
+      }
+    }
+    statistics
+  }
+
+  /// Compute the cell of a [FieldType::Rollup] field. The rows linked by the relation cell of the
+  /// row are read from the related database, then their target cells are aggregated, see
+  /// [RollupTypeOption::aggregate].
+  pub async fn resolve_rollup_cell(
+    &self,
+    database_id: &str,
+    field_id: &str,
+    row_id: &RowId,
+  ) -> Result<Cell, DatabaseError> {
+    let (type_option, related_database_id, linked_row_ids) = {
+      let database = self.get_or_init_database(database_id).await?;
+      let database = database.read().await;
+      let type_option = database
+        .get_field(field_id)
+        .filter(|field| FieldType::from(field.field_type) == FieldType::Rollup)
+        .and_then(|field| field.get_type_option::<RollupTypeOption>(FieldType::Rollup.type_id()))
+        .ok_or_else(|| DatabaseError::NoRequiredData(format!("rollup field {}", field_id)))?;
+      let (related_database_id, linked_row_ids) =
+        linked_rows(&database, &type_option.relation_field_id, row_id).await?;
+      (type_option, related_database_id, linked_row_ids)
+    };
+
+    let related_database = self.get_or_init_database(&related_database_id).await?;
+    let related_database = related_database.read().await;
+    let mut cells = Vec::with_capacity(linked_row_ids.len());
+    for linked_row_id in linked_row_ids.iter() {
+      // The links to the rows that can't be loaded, like the deleted rows, are not aggregated.
+      if related_database
+        .get_or_init_database_row(linked_row_id)
+        .await
+        .is_none()
+      {
+        continue;
+      }
+      let cell = related_database
+        .get_cell(&type_option.target_field_id, linked_row_id)
+        .await;
+      cells.push(cell.cell);
+    }
+    Ok(type_option.compute_cell(&cells))
+  }
+
+  /// Resolve the values of a [FieldType::Lookup] cell: the target cells of the rows linked by the
+  /// relation cell of the row, in the order of the links. The values are cached, and a cached
+  /// value is reused until its row changes, see [Self::lookup_cache_metrics].
+  pub async fn resolve_lookup_values(
+    &self,
+    database_id: &str,
+    field_id: &str,
+    row_id: &RowId,
+  ) -> Result<Vec<LookupValue>, DatabaseError> {
+    let (type_option, related_database_id, linked_row_ids) = {
+      let database = self.get_or_init_database(database_id).await?;
+      let database = database.read().await;
+      let type_option = database
+        .get_field(field_id)
+        .filter(|field| FieldType::from(field.field_type) == FieldType::Lookup)
+        .and_then(|field| field.get_type_option::<LookupTypeOption>(FieldType::Lookup.type_id()))
+        .ok_or_else(|| DatabaseError::NoRequiredData(format!("lookup field {}", field_id)))?;
+      let (related_database_id, linked_row_ids) =
+        linked_rows(&database, &type_option.relation_field_id, row_id).await?;
+      (type_option, related_database_id, linked_row_ids)
+    };
+
+    let related_database = self.get_or_init_database(&related_database_id).await?;
+    let related_database = related_database.read().await;
+    let target_field = related_database
+      .get_field(&type_option.target_field_id)
+      .ok_or_else(|| {
+        DatabaseError::NoRequiredData(format!("target field {}", type_option.target_field_id))
+      })?;
+    let target_field_type = FieldType::from(target_field.field_type);
+    let stringify = target_field
+      .get_any_type_option(target_field_type.type_id())
+      .and_then(|type_option| stringify_type_option(type_option, &target_field_type));
+    self
+      .lookup_cache
+      .invalidate_changed_rows(&related_database_id);
+
+    let mut values = Vec::with_capacity(linked_row_ids.len());
+    for linked_row_id in linked_row_ids {
+      if let Some(value) = self.lookup_cache.get(&target_field, &linked_row_id) {
+        values.push(value);
+        continue;
+      }
+
+      let cell = related_database
+        .get_cell(&target_field.id, &linked_row_id)
+        .await
+        .cell;
+      let text = match (&cell, &stringify) {
+        (Some(cell), Some(stringify)) => stringify.stringify_cell(cell),
+        (Some(cell), None) => cell.get_as::<String>(CELL_DATA).unwrap_or_default(),
+        (None, _) => String::new(),
+      };
+      let value = LookupValue {
+        row_id: linked_row_id,
+        cell,
+        text,
+      };
+      self
+        .lookup_cache
+        .insert(&related_database_id, &target_field, value.clone());
+      values.push(value);
+    }
+    Ok(values)
+  }
+
+  /// Return the metrics of the cache of the values resolved by [Self::resolve_lookup_values].
+  pub fn lookup_cache_metrics(&self) -> LookupCacheMetrics {
+    self.lookup_cache.metrics()
+  }
+
+  /// Set the number of values kept by the cache of [Self::resolve_lookup_values], by default
+  /// [crate::workspace_database::LOOKUP_CACHE_CAPACITY]. The least recently used values are evicted first.
+  pub fn set_lookup_cache_capacity(&self, capacity: usize) {
+    self.lookup_cache.set_capacity(capacity);
+  }
+
+  /// Set the rows linked by the relation cell of the row. When the relation field has a
+  /// [RelationTypeOption::reciprocal_field_id], the row is linked back from the cells of that
+  /// field in the newly linked rows, and unlinked from the rows that are no longer linked.
+  ///
+  /// The links are added and removed one by one, so the links edited concurrently by the other
+  /// clients are kept. Both databases are locked, and all the rows are loaded, before anything is
+  /// written, so a misconfigured reciprocal field or a missing row leaves the cells of both
+  /// databases unchanged. The linked rows that don't exist are skipped.
+  pub async fn update_relation_cell(
+    &self,
+    database_id: &str,
+    field_id: &str,
+    row_id: &RowId,
+    row_ids: Vec<RowId>,
+  ) -> Result<(), DatabaseError> {
+    let database = self.get_or_init_database(database_id).await?;
+    let type_option = relation_type_option(&*database.read().await, field_id)?;
+    let related_database = match &type_option.reciprocal_field_id {
+      None => None,
+      Some(reciprocal_field_id) => {
+        let related_database = self.get_or_init_database(&type_option.database_id).await?;
+        let reciprocal_type_option =
+          relation_type_option(&*related_database.read().await, reciprocal_field_id)?;
+        if reciprocal_type_option.database_id != database_id {
+          return Err(DatabaseError::NoRequiredData(format!(
+            "relation field {} doesn't link to database {}",
+            reciprocal_field_id, database_id
+          )));
+        }
+        Some((related_database, reciprocal_field_id.clone()))
+      },
+    };
+
+    // The databases are locked in the order of their ids, so the updates of the two sides of a
+    // relation don't wait for each other.
+    let other_database = related_database
+      .as_ref()
+      .map(|(related_database, _)| related_database)
+      .filter(|related_database| !Arc::ptr_eq(related_database, &database));
+    let (mut database, mut other_database) = match other_database {
+      None => (database.write().await, None),
+      Some(other_database) if database_id < type_option.database_id.as_str() => {
+        let database = database.write().await;
+        (database, Some(other_database.write().await))
+      },
+      Some(other_database) => {
+        let other_database = other_database.write().await;
+        (database.write().await, Some(other_database))
+      },
+    };
+
+    if database.get_or_init_database_row(row_id).await.is_none() {
+      return Err(DatabaseError::DatabaseRowNotFound {
+        row_id: row_id.clone(),
+        reason: "the row is not exist in local disk".to_string(),
+      });
+    }
+    let old_row_ids = database
+      .get_cell(field_id, row_id)
+      .await
+      .cell
+      .map(|cell| RelationCellData::from(&cell).row_ids)
+      .unwrap_or_default();
+    let mut data = RelationCellData::default();
+    for linked_row_id in row_ids {
+      data.link(&linked_row_id);
+    }
+    let linked_row_ids = data
+      .row_ids
+      .iter()
+      .filter(|linked_row_id| !old_row_ids.contains(linked_row_id))
+      .cloned()
+      .collect::<Vec<_>>();
+    let unlinked_row_ids = old_row_ids
+      .into_iter()
+      .filter(|old_row_id| !data.row_ids.contains(old_row_id))
+      .collect::<Vec<_>>();
+
+    let mut reciprocal_changes = vec![];
+    if let Some((_, reciprocal_field_id)) = &related_database {
+      let related_database = other_database.as_deref_mut().unwrap_or(&mut *database);
+      let changes = linked_row_ids
+        .iter()
+        .map(|related_row_id| (related_row_id, true))
+        .chain(
+          unlinked_row_ids
+            .iter()
+            .map(|related_row_id| (related_row_id, false)),
+        );
+      for (related_row_id, linked) in changes {
+        if related_database
+          .get_or_init_database_row(related_row_id)
+          .await
+          .is_none()
+        {
+          warn!(
+            "Skip the reciprocal link of row {} in database {}: the row is not found",
+            related_row_id, type_option.database_id
+          );
+          continue;
+        }
+        reciprocal_changes.push((reciprocal_field_id, related_row_id, linked));
+      }
+    }
+
+    database
+      .update_relation_links(row_id, field_id, &linked_row_ids, &unlinked_row_ids)
+      .await;
+    let related_database = other_database.as_deref_mut().unwrap_or(&mut *database);
+    let row_ids = std::slice::from_ref(row_id);
+    for (reciprocal_field_id, related_row_id, linked) in reciprocal_changes {
+      let (linked, unlinked) = if linked {
+        (row_ids, &[][..])
+      } else {
+        (&[][..], row_ids)
+      };
+      related_database
+        .update_relation_links(related_row_id, reciprocal_field_id, linked, unlinked)
+        .await;
+    }
+    Ok(())
+  }
+
   pub fn flush_workspace_database(&self) -> Result<(), DatabaseError> {
     let encoded_collab = self.body.encode_collab_v1()?;
     self
@@ -489,3 +954,32 @@ impl BorrowMut<Collab> for WorkspaceDatabaseManager {
     self.body.borrow_mut()
   }
 }
+
+/// Return the id of the database related by the relation field and the rows linked by the
+/// relation cell of the row.
+async fn linked_rows(
+  database: &Database,
+  relation_field_id: &str,
+  row_id: &RowId,
+) -> Result<(String, Vec<RowId>), DatabaseError> {
+  let type_option = relation_type_option(database, relation_field_id)?;
+  let linked_row_ids = database
+    .get_cell(relation_field_id, row_id)
+    .await
+    .cell
+    .map(|cell| RelationCellData::from(&cell).row_ids)
+    .unwrap_or_default();
+  Ok((type_option.database_id, linked_row_ids))
+}
+
+/// Return the type option of the relation field of the database.
+fn relation_type_option(
+  database: &Database,
+  field_id: &str,
+) -> Result<RelationTypeOption, DatabaseError> {
+  database
+    .get_field(field_id)
+    .filter(|field| FieldType::from(field.field_type) == FieldType::Relation)
+    .and_then(|field| field.get_type_option::<RelationTypeOption>(FieldType::Relation.type_id()))
+    .ok_or_else(|| DatabaseError::NoRequiredData(format!("relation field {}", field_id)))
+}
@@ -0,0 +1,225 @@
+use crate::blocks::BlockMap;
+use crate::rows::RowId;
+use collab::preclude::{Collab, ReadTxn, StateVector};
+use dashmap::DashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Limits the collabs kept in memory by a [crate::workspace_database::WorkspaceDatabaseManager].
+/// A database counts as one collab and each of its loaded rows as another one, and the size of
+/// each collab is estimated from its encoded state when it's loaded.
+///
+/// When a limit on the rows or on the size is exceeded, the least recently used rows are
+/// unloaded first, their databases stay open. Then the least recently used databases are closed
+/// with all their rows. A row or a database that is still used outside of the manager is never
+/// evicted, so the budget can be exceeded while many of them are in use.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DatabaseMemoryBudget {
+  /// The max number of open databases, None for no limit.
+  pub max_resident_databases: Option<usize>,
+  /// The max number of loaded rows of all the open databases, None for no limit.
+  pub max_resident_rows: Option<usize>,
+  /// The max estimated size in bytes of the open databases and their loaded rows, None for no
+  /// limit.
+  pub max_resident_bytes: Option<usize>,
+}
+
+impl DatabaseMemoryBudget {
+  pub fn unlimited() -> Self {
+    Self::default()
+  }
+
+  pub fn with_max_resident_databases(mut self, max: usize) -> Self {
+    self.max_resident_databases = Some(max);
+    self
+  }
+
+  pub fn with_max_resident_rows(mut self, max: usize) -> Self {
+    self.max_resident_rows = Some(max);
+    self
+  }
+
+  pub fn with_max_resident_bytes(mut self, max: usize) -> Self {
+    self.max_resident_bytes = Some(max);
+    self
+  }
+
+  pub fn is_exceeded(&self, collabs: &ResidentCollabs) -> bool {
+    self
+      .max_resident_databases
+      .is_some_and(|max| collabs.databases > max)
+      || self.is_rows_exceeded(collabs)
+  }
+
+  /// Return true if the limit on the rows or on the size is exceeded, which evicting rows can
+  /// meet.
+  pub fn is_rows_exceeded(&self, collabs: &ResidentCollabs) -> bool {
+    self.max_resident_rows.is_some_and(|max| collabs.rows > max)
+      || self
+        .max_resident_bytes
+        .is_some_and(|max| collabs.bytes > max)
+  }
+}
+
+/// The collabs that are kept in memory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResidentCollabs {
+  pub databases: usize,
+  pub rows: usize,
+  /// The estimated size in bytes of the databases and the rows.
+  pub bytes: usize,
+}
+
+/// The metrics of the collabs of a [crate::workspace_database::WorkspaceDatabaseManager].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResidentCollabMetrics {
+  pub resident: ResidentCollabs,
+  /// The number of databases closed to stay under the [DatabaseMemoryBudget].
+  pub evicted_databases: u64,
+  /// The number of rows unloaded, on their own or with the evicted databases.
+  pub evicted_rows: u64,
+}
+
+static ACCESS_TICK: AtomicU64 = AtomicU64::new(1);
+
+/// Return the tick of an access to a database or a row. The ticks of all the databases are
+/// comparable, so the least recently used rows are found across the databases.
+pub(crate) fn next_access_tick() -> u64 {
+  ACCESS_TICK.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Estimate the size in bytes of the collab from its encoded state.
+pub(crate) fn estimate_collab_size(collab: &Collab) -> usize {
+  collab
+    .transact()
+    .encode_state_as_update_v1(&StateVector::default())
+    .len()
+}
+
+/// The last access and the estimated size of a loaded row.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RowUsage {
+  pub(crate) last_used: u64,
+  pub(crate) bytes: usize,
+}
+
+/// Keeps track of the last time each open database was used, with its rows.
+#[derive(Default)]
+pub(crate) struct DatabaseLru {
+  databases: DashMap<String, ResidentDatabase>,
+  evicted_databases: AtomicU64,
+  evicted_rows: AtomicU64,
+}
+
+struct ResidentDatabase {
+  last_used: u64,
+  bytes: usize,
+  blocks: Arc<BlockMap>,
+}
+
+impl ResidentDatabase {
+  /// A database is used when itself or one of its rows is accessed.
+  fn last_used(&self) -> u64 {
+    self.last_used.max(self.blocks.last_used())
+  }
+}
+
+impl DatabaseLru {
+  pub(crate) fn insert(&self, database_id: &str, bytes: usize, blocks: Arc<BlockMap>) {
+    let resident = ResidentDatabase {
+      last_used: next_access_tick(),
+      bytes,
+      blocks,
+    };
+    self.databases.insert(database_id.to_string(), resident);
+  }
+
+  pub(crate) fn touch(&self, database_id: &str) {
+    if let Some(mut resident) = self.databases.get_mut(database_id) {
+      resident.last_used = next_access_tick();
+    }
+  }
+
+  pub(crate) fn remove(&self, database_id: &str) -> usize {
+    self
+      .databases
+      .remove(database_id)
+      .map(|(_, resident)| resident.blocks.cached_row_count())
+      .unwrap_or(0)
+  }
+
+  /// Return the ids of the databases, from the least to the most recently used.
+  pub(crate) fn least_recently_used(&self) -> Vec<String> {
+    let mut database_ids = self
+      .databases
+      .iter()
+      .map(|entry| (entry.value().last_used(), entry.key().clone()))
+      .collect::<Vec<_>>();
+    database_ids.sort();
+    database_ids.into_iter().map(|(_, id)| id).collect()
+  }
+
+  /// Return the loaded rows of all the databases, with the ids of their databases, from the
+  /// least to the most recently used.
+  pub(crate) fn least_recently_used_rows(&self) -> Vec<(String, RowId)> {
+    let mut rows = self
+      .databases
+      .iter()
+      .flat_map(|entry| {
+        let database_id = entry.key().clone();
+        entry
+          .value()
+          .blocks
+          .row_usage()
+          .into_iter()
+          .map(move |(row_id, usage)| (usage.last_used, database_id.clone(), row_id))
+      })
+      .collect::<Vec<_>>();
+    rows.sort_by_key(|(last_used, _, _)| *last_used);
+    rows
+      .into_iter()
+      .map(|(_, database_id, row_id)| (database_id, row_id))
+      .collect()
+  }
+
+  /// Remove the row from memory if it's not in use, see [BlockMap::evict_row].
+  pub(crate) fn evict_row(&self, database_id: &str, row_id: &RowId) -> bool {
+    let blocks = match self.databases.get(database_id) {
+      None => return false,
+      Some(resident) => resident.blocks.clone(),
+    };
+    let is_evicted = blocks.evict_row(row_id);
+    if is_evicted {
+      self.evicted_rows.fetch_add(1, Ordering::Relaxed);
+    }
+    is_evicted
+  }
+
+  pub(crate) fn resident(&self) -> ResidentCollabs {
+    self
+      .databases
+      .iter()
+      .fold(ResidentCollabs::default(), |mut collabs, entry| {
+        let resident = entry.value();
+        collabs.databases += 1;
+        collabs.rows += resident.blocks.cached_row_count();
+        collabs.bytes += resident.bytes + resident.blocks.cached_row_bytes();
+        collabs
+      })
+  }
+
+  pub(crate) fn record_eviction(&self, num_of_rows: usize) {
+    self.evicted_databases.fetch_add(1, Ordering::Relaxed);
+    self
+      .evicted_rows
+      .fetch_add(num_of_rows as u64, Ordering::Relaxed);
+  }
+
+  pub(crate) fn metrics(&self) -> ResidentCollabMetrics {
+    ResidentCollabMetrics {
+      resident: self.resident(),
+      evicted_databases: self.evicted_databases.load(Ordering::Relaxed),
+      evicted_rows: self.evicted_rows.load(Ordering::Relaxed),
+    }
+  }
+}
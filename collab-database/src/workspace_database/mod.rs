@@ -1,7 +1,11 @@
 pub use body::*;
+pub use lookup_cache::*;
 pub use manager::*;
+pub use memory_budget::*;
 pub use relation::*;
 
 mod body;
+mod lookup_cache;
 mod manager;
+mod memory_budget;
 mod relation;
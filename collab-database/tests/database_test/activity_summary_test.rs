@@ -0,0 +1,94 @@
+use collab::core::origin::CollabOrigin;
+use collab::preclude::updates::decoder::Decode;
+use collab::preclude::{Collab, ReadTxn, StateVector, Update};
+use collab_database::fields::Field;
+use collab_database::views::OrderObjectPosition;
+use collab_entity::activity::{summarize_activity, ActivityChange, ActivityKind, ActivityTarget};
+use collab_entity::CollabType;
+
+use crate::database_test::helper::{
+  create_database_with_default_data, default_field_settings_by_layout,
+};
+
+#[tokio::test]
+async fn summarize_database_activity_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database_with_default_data(1, &database_id).await;
+  // The copy of the database of a user that was away.
+  let away_collab = || {
+    let mut collab = Collab::new_with_origin(CollabOrigin::Empty, &database_id, vec![], false);
+    let doc_state = database_test
+      .collab
+      .transact()
+      .encode_state_as_update_v1(&StateVector::default());
+    collab
+      .apply_update(Update::decode_v1(&doc_state).unwrap())
+      .unwrap();
+    collab
+  };
+  let mut collab = away_collab();
+  let mut other_collab = away_collab();
+  let since = database_test.collab.transact().state_vector();
+
+  database_test.create_field(
+    None,
+    Field::new("f4".to_string(), "number field".to_string(), 1, false),
+    &OrderObjectPosition::default(),
+    default_field_settings_by_layout(),
+  );
+  let until = database_test.collab.transact().state_vector();
+  let first_update = database_test
+    .collab
+    .transact()
+    .encode_state_as_update_v1(&since);
+  // The row was created before the user went away.
+  let removed_row_id = database_test.pre_define_row_ids[1].clone();
+  database_test.remove_row(&removed_row_id).await.unwrap();
+  database_test.create_field(
+    None,
+    Field::new("f5".to_string(), "date field".to_string(), 2, false),
+    &OrderObjectPosition::default(),
+    default_field_settings_by_layout(),
+  );
+  let second_update = database_test
+    .collab
+    .transact()
+    .encode_state_as_update_v1(&until);
+  let updates = vec![first_update, second_update];
+
+  // Only the changes made up to the second state vector are summarized.
+  let summary =
+    summarize_activity(&mut collab, &CollabType::Database, &updates, Some(&until)).unwrap();
+  assert_eq!(summary.since, since);
+  assert_eq!(summary.until, until);
+  assert_eq!(summary.insertions_by_client.len(), 1);
+  assert!(summary.changes.contains(&ActivityChange {
+    target: ActivityTarget::Field("f4".to_string()),
+    kind: ActivityKind::Inserted,
+  }));
+  assert!(!summary
+    .changes
+    .iter()
+    .any(|change| change.target == ActivityTarget::Row(removed_row_id.to_string())));
+
+  let summary =
+    summarize_activity(&mut other_collab, &CollabType::Database, &updates, None).unwrap();
+  let inserted_field_ids = summary
+    .changes
+    .iter()
+    .filter_map(|change| match (&change.target, change.kind) {
+      (ActivityTarget::Field(field_id), ActivityKind::Inserted) => Some(field_id.as_str()),
+      _ => None,
+    })
+    .collect::<Vec<_>>();
+  assert_eq!(inserted_field_ids, vec!["f4", "f5"]);
+  assert!(summary.changes.contains(&ActivityChange {
+    target: ActivityTarget::Row(removed_row_id.to_string()),
+    kind: ActivityKind::Deleted,
+  }));
+
+  // Nothing changed once all the updates were applied.
+  let summary =
+    summarize_activity(&mut other_collab, &CollabType::Database, &updates, None).unwrap();
+  assert!(summary.is_empty());
+}
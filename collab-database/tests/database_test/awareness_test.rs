@@ -0,0 +1,67 @@
+use collab_database::database::Database;
+use collab_database::database_awareness::{
+  DatabaseAwarenessUser, DatabasePresenceChange, EditingCell,
+};
+use collab_database::rows::RowId;
+
+use crate::database_test::helper::create_database;
+
+/// Sends the local state of `from` to `to`, including a removed state.
+fn sync_awareness(from: &Database, to: &Database) {
+  let awareness = from.collab.get_awareness();
+  let update = awareness
+    .update_with_clients([awareness.client_id()])
+    .unwrap();
+  to.collab.get_awareness().apply_update(update).unwrap();
+}
+
+fn user(uid: i64) -> DatabaseAwarenessUser {
+  DatabaseAwarenessUser {
+    uid,
+    device_id: uid.to_string(),
+  }
+}
+
+#[tokio::test]
+async fn editing_cell_presence_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut local = create_database(1, &database_id);
+  let remote = create_database(2, &database_id);
+  let mut presence_rx = remote.subscribe_presence_change().unwrap();
+
+  let row_id = RowId::from(uuid::Uuid::new_v4().to_string());
+  let cell = EditingCell::new("v1".to_string(), row_id.clone(), Some("f1".to_string()));
+  local.set_editing_cell(user(1), Some(cell.clone()));
+  assert_eq!(
+    local.get_awareness_local_state().unwrap().editing,
+    Some(cell.clone())
+  );
+  assert!(local.get_remote_presences().is_empty());
+
+  sync_awareness(&local, &remote);
+  match presence_rx.try_recv().unwrap() {
+    DatabasePresenceChange::DidJoin { state, .. } => {
+      assert_eq!(state.user, user(1));
+      assert_eq!(state.editing, Some(cell));
+    },
+    change => panic!("unexpected change: {:?}", change),
+  }
+  let presences = remote.get_remote_presences();
+  assert_eq!(presences.len(), 1);
+
+  let cell = EditingCell::new("v1".to_string(), row_id, None);
+  local.set_editing_cell(user(1), Some(cell.clone()));
+  sync_awareness(&local, &remote);
+  match presence_rx.try_recv().unwrap() {
+    DatabasePresenceChange::DidUpdate { state, .. } => assert_eq!(state.editing, Some(cell)),
+    change => panic!("unexpected change: {:?}", change),
+  }
+
+  local.clean_awareness_local_state();
+  sync_awareness(&local, &remote);
+  match presence_rx.try_recv().unwrap() {
+    DatabasePresenceChange::DidLeave { state, .. } => assert_eq!(state.unwrap().user, user(1)),
+    change => panic!("unexpected change: {:?}", change),
+  }
+  assert!(remote.get_remote_presences().is_empty());
+}
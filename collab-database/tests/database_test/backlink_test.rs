@@ -0,0 +1,75 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use collab::core::collab_backlink::{BacklinkRegistry, BacklinkType, LinkSource};
+use collab::preclude::Any;
+use collab_database::database::gen_row_id;
+use collab_database::entity::FieldType;
+use collab_database::fields::Field;
+use collab_database::rows::{new_cell_builder, Cell, CreateRowParams, RowId};
+use collab_database::template::entity::CELL_DATA;
+use tokio::time::{sleep, timeout};
+
+use crate::database_test::helper::DatabaseTestBuilder;
+
+fn relation_cell(row_ids: &[&RowId]) -> Cell {
+  let mut cell = new_cell_builder(FieldType::Relation);
+  let row_ids = row_ids
+    .iter()
+    .map(|row_id| Any::from(row_id.to_string()))
+    .collect::<Vec<_>>();
+  cell.insert(CELL_DATA.into(), Any::Array(Arc::from(row_ids)));
+  cell
+}
+
+async fn wait_for_backlinks(registry: &BacklinkRegistry, object_id: &str, expected: usize) {
+  timeout(Duration::from_secs(5), async {
+    while registry.backlinks_for(object_id).len() != expected {
+      sleep(Duration::from_millis(50)).await;
+    }
+  })
+  .await
+  .unwrap_or_else(|_| panic!("backlinks of {} timeout", object_id));
+}
+
+#[tokio::test]
+async fn register_relation_cells_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let linked_row_1 = gen_row_id();
+  let linked_row_2 = gen_row_id();
+  let row_1 = gen_row_id();
+  let mut database_test = DatabaseTestBuilder::new(1, &database_id)
+    .with_field(Field::new(
+      "relation".to_string(),
+      "Tasks".to_string(),
+      FieldType::Relation.into(),
+      false,
+    ))
+    .with_row(
+      CreateRowParams::new(row_1.clone(), database_id.clone())
+        .with_cells([("relation".to_string(), relation_cell(&[&linked_row_1]))].into()),
+    )
+    .build()
+    .await;
+
+  let registry = BacklinkRegistry::new();
+  database_test.subscribe_backlinks(registry.clone()).await;
+  let backlinks = registry.backlinks_for(&linked_row_1);
+  assert_eq!(backlinks.len(), 1);
+  assert_eq!(backlinks[0].source, LinkSource::new(&database_id, &row_1));
+  assert_eq!(backlinks[0].link_type, BacklinkType::Relation);
+
+  database_test
+    .update_row(row_1.clone(), |row| {
+      row.update_cells(|cells| {
+        cells.insert_cell("relation", relation_cell(&[&linked_row_1, &linked_row_2]));
+      });
+    })
+    .await;
+  wait_for_backlinks(&registry, &linked_row_2, 1).await;
+  assert_eq!(registry.links_from(&database_id).len(), 2);
+
+  database_test.remove_row(&row_1).await;
+  wait_for_backlinks(&registry, &linked_row_1, 0).await;
+  assert!(registry.links_from(&database_id).is_empty());
+}
@@ -1,6 +1,11 @@
-use collab_database::rows::CreateRowParams;
+use collab_database::rows::{CreateRowParams, RowId};
 
-use crate::database_test::helper::create_database;
+use collab_database::blocks::block_collab_id;
+
+use crate::database_test::helper::{
+  create_database, create_database_with_block_capacity, create_database_with_db_and_block_capacity,
+  restore_database_from_db,
+};
 
 #[tokio::test]
 async fn create_rows_test() {
@@ -15,3 +20,113 @@ async fn create_rows_test() {
   let rows = database_test.get_rows_for_view("v1").await;
   assert_eq!(rows.len(), 100);
 }
+
+#[tokio::test]
+async fn create_rows_across_blocks_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database_with_block_capacity(1, &database_id, 10);
+  let mut row_ids = vec![];
+  for i in 0..25 {
+    let row_id = RowId::from(format!("row-{}", i));
+    database_test
+      .create_row_in_view(
+        "v1",
+        CreateRowParams::new(row_id.clone(), database_id.clone()),
+      )
+      .await
+      .unwrap();
+    row_ids.push(row_id);
+  }
+
+  let blocks = &database_test.body.blocks;
+  assert_eq!(blocks.num_blocks(), 3);
+  assert_eq!(blocks.block_id_of_row(&row_ids[0]), Some(0));
+  assert_eq!(blocks.block_id_of_row(&row_ids[9]), Some(0));
+  assert_eq!(blocks.block_id_of_row(&row_ids[10]), Some(1));
+  assert_eq!(blocks.block_id_of_row(&row_ids[24]), Some(2));
+
+  let row = database_test.get_row(&row_ids[24]).await;
+  assert_eq!(row.id, row_ids[24]);
+  let rows = database_test.get_rows_for_view("v1").await;
+  assert_eq!(
+    rows.into_iter().map(|row| row.id).collect::<Vec<_>>(),
+    row_ids
+  );
+}
+
+#[tokio::test]
+async fn remove_and_update_rows_across_blocks_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database_with_block_capacity(1, &database_id, 2);
+  let mut row_ids = vec![];
+  for _ in 0..5 {
+    let row_id = RowId::from(uuid::Uuid::new_v4().to_string());
+    database_test
+      .create_row(CreateRowParams::new(row_id.clone(), database_id.clone()))
+      .await
+      .unwrap();
+    row_ids.push(row_id);
+  }
+  assert_eq!(database_test.body.blocks.num_blocks(), 3);
+
+  let row = database_test.remove_row(&row_ids[1]).await.unwrap();
+  assert_eq!(row.id, row_ids[1]);
+  assert!(database_test
+    .body
+    .blocks
+    .block_id_of_row(&row_ids[1])
+    .is_none());
+  assert_eq!(database_test.get_rows_for_view("v1").await.len(), 4);
+
+  // The row keeps its block when its id changes.
+  let new_row_id = RowId::from(uuid::Uuid::new_v4().to_string());
+  database_test
+    .update_row(row_ids[3].clone(), |row_update| {
+      row_update.set_row_id(new_row_id.clone());
+    })
+    .await;
+  let blocks = &database_test.body.blocks;
+  assert!(blocks.block_id_of_row(&row_ids[3]).is_none());
+  assert_eq!(blocks.block_id_of_row(&new_row_id), Some(1));
+  assert_eq!(database_test.get_row(&new_row_id).await.id, new_row_id);
+}
+
+#[tokio::test]
+async fn restore_row_blocks_test() {
+  let workspace_id = uuid::Uuid::new_v4().to_string();
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let (db, mut database_test) =
+    create_database_with_db_and_block_capacity(1, &workspace_id, &database_id, 2).await;
+  let mut row_ids = vec![];
+  for _ in 0..5 {
+    let row_id = RowId::from(uuid::Uuid::new_v4().to_string());
+    database_test
+      .create_row(CreateRowParams::new(row_id.clone(), database_id.clone()))
+      .await
+      .unwrap();
+    row_ids.push(row_id);
+  }
+  database_test.remove_row(&row_ids[1]).await.unwrap();
+  drop(database_test);
+
+  // The rows are routed to the blocks they were assigned to, before they're loaded.
+  let database_test = restore_database_from_db(1, &workspace_id, &database_id, db).await;
+  let blocks = &database_test.body.blocks;
+  assert_eq!(blocks.num_blocks(), 3);
+  assert_eq!(blocks.block_id_of_row(&row_ids[0]), Some(0));
+  assert!(blocks.block_id_of_row(&row_ids[1]).is_none());
+  assert_eq!(blocks.block_id_of_row(&row_ids[2]), Some(1));
+  assert_eq!(blocks.block_id_of_row(&row_ids[3]), Some(1));
+  assert_eq!(blocks.block_id_of_row(&row_ids[4]), Some(2));
+  assert_eq!(blocks.cached_row_count(), 0);
+  assert_eq!(database_test.get_rows_for_view("v1").await.len(), 4);
+}
+
+#[test]
+fn block_collab_id_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let first = block_collab_id(&database_id, 0);
+  assert!(uuid::Uuid::parse_str(&first).is_ok());
+  assert_eq!(first, block_collab_id(&database_id, 0));
+  assert_ne!(first, block_collab_id(&database_id, 1));
+}
@@ -0,0 +1,125 @@
+use collab::preclude::Any;
+use collab_database::entity::CreateViewParams;
+use collab_database::views::{
+  CalculationChanged, CalculationMap, CalculationType, DatabaseLayout, CALCULATION_FIELD_ID,
+};
+
+use crate::database_test::helper::create_database;
+
+fn sum_calculation(id: &str, field_id: &str, value: &str) -> CalculationMap {
+  CalculationMap::from([
+    ("id".to_string(), Any::from(id)),
+    ("field_id".to_string(), Any::from(field_id)),
+    ("calculation_type".to_string(), Any::from(1)),
+    ("value".to_string(), Any::from(value)),
+  ])
+}
+
+#[tokio::test]
+async fn calculation_change_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database(1, &database_id);
+  let mut calculation_rx = database_test.subscribe_calculation_change().unwrap();
+
+  database_test.update_calculation("v1", sum_calculation("c1", "f1", "10"));
+  assert_eq!(
+    calculation_rx.try_recv().unwrap(),
+    CalculationChanged {
+      view_id: "v1".to_string(),
+      field_id: "f1".to_string(),
+      old: None,
+      new: Some(sum_calculation("c1", "f1", "10")),
+    }
+  );
+
+  // The footer is recomputed after a cell changed.
+  database_test.update_calculation("v1", sum_calculation("c1", "f1", "15"));
+  let change = calculation_rx.try_recv().unwrap();
+  assert_eq!(change.old, Some(sum_calculation("c1", "f1", "10")));
+  assert_eq!(change.new, Some(sum_calculation("c1", "f1", "15")));
+
+  database_test.update_calculation("v1", sum_calculation("c2", "f2", "1"));
+  let change = calculation_rx.try_recv().unwrap();
+  assert_eq!(change.field_id, "f2");
+  assert!(change.old.is_none());
+
+  database_test.remove_calculation("v1", "c1");
+  assert_eq!(
+    calculation_rx.try_recv().unwrap(),
+    CalculationChanged {
+      view_id: "v1".to_string(),
+      field_id: "f1".to_string(),
+      old: Some(sum_calculation("c1", "f1", "15")),
+      new: None,
+    }
+  );
+  assert!(calculation_rx.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn view_calculations_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database(1, &database_id);
+  database_test.update_calculation("v1", sum_calculation("c1", "f1", "10"));
+  database_test.update_calculation("v1", sum_calculation("c2", "f2", "3"));
+
+  let view = database_test.get_view("v1").unwrap();
+  assert_eq!(
+    view.calculations,
+    vec![
+      sum_calculation("c1", "f1", "10"),
+      sum_calculation("c2", "f2", "3")
+    ]
+  );
+
+  let duplicated_view = database_test.duplicate_linked_view("v1").unwrap();
+  let duplicated_view = database_test.get_view(&duplicated_view.id).unwrap();
+  assert_eq!(duplicated_view.calculations, view.calculations);
+
+  let params = CreateViewParams {
+    database_id,
+    view_id: "v2".to_string(),
+    layout: DatabaseLayout::Grid,
+    calculations: vec![sum_calculation("c3", "f1", "1")],
+    ..Default::default()
+  };
+  database_test.create_linked_view(params).unwrap();
+  assert_eq!(
+    database_test.get_view("v2").unwrap().calculations,
+    vec![sum_calculation("c3", "f1", "1")]
+  );
+
+  // The calculations of a deleted field are removed from all the views
+  database_test.delete_field("f1");
+  for view in database_test.get_all_views() {
+    assert!(view
+      .calculations
+      .iter()
+      .all(|calculation| calculation.get(CALCULATION_FIELD_ID) != Some(&Any::from("f1"))));
+  }
+  assert_eq!(database_test.get_view("v1").unwrap().calculations.len(), 1);
+}
+
+#[test]
+fn calculate_test() {
+  let values = ["1", "4", "", "abc", "2.5"]
+    .iter()
+    .map(|value| Some(value.to_string()))
+    .chain([None])
+    .collect::<Vec<_>>();
+  let calculate = |calculation_type: CalculationType| calculation_type.calculate(&values);
+  assert_eq!(calculate(CalculationType::Count), "6");
+  assert_eq!(calculate(CalculationType::CountEmpty), "2");
+  assert_eq!(calculate(CalculationType::CountNonEmpty), "4");
+  assert_eq!(calculate(CalculationType::Sum), "7.5");
+  assert_eq!(calculate(CalculationType::Average), "2.5");
+  assert_eq!(calculate(CalculationType::Median), "2.5");
+  assert_eq!(calculate(CalculationType::Min), "1");
+  assert_eq!(calculate(CalculationType::Max), "4");
+  assert_eq!(CalculationType::Median.calculate(&[]), "");
+  assert_eq!(
+    CalculationType::Median.calculate(&[Some("1".to_string()), Some("2".to_string())]),
+    "1.5"
+  );
+  assert_eq!(CalculationType::from(1), CalculationType::Sum);
+}
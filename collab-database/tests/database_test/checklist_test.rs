@@ -0,0 +1,257 @@
+use collab::preclude::updates::decoder::Decode;
+use collab::preclude::{Any, Doc, FillRef, ReadTxn, StateVector, ToJson, Transact, Update};
+use collab::util::{AnyExt, AnyMapExt};
+use collab_database::entity::FieldType;
+use collab_database::fields::checklist_type_option::{
+  apply_checklist_changes, checklist_cell, checklist_from_cell, ChecklistCellChange,
+};
+use collab_database::fields::progress_type_option::{ProgressCellData, ProgressTypeOption};
+use collab_database::fields::select_type_option::SelectOption;
+use collab_database::fields::ChecklistCellData;
+use collab_database::fields::Field;
+use collab_database::rows::{new_cell_builder, Cell, CreateRowParams, RowId};
+use collab_database::template::entity::CELL_DATA;
+use collab_database::views::OrderObjectPosition;
+
+use crate::database_test::helper::{create_database, default_field_settings_by_layout};
+
+fn legacy_checklist_cell(data: &ChecklistCellData) -> Cell {
+  let mut cell = new_cell_builder(FieldType::Checklist);
+  cell.insert(
+    CELL_DATA.into(),
+    serde_json::to_string(data).unwrap().into(),
+  );
+  cell
+}
+
+fn checklist_data(names: &[&str], selected: &[&str]) -> ChecklistCellData {
+  let names = names.iter().map(|name| name.to_string()).collect();
+  let selected = selected.iter().map(|name| name.to_string()).collect();
+  ChecklistCellData::from((names, selected))
+}
+
+fn option_names(data: &ChecklistCellData) -> Vec<String> {
+  data
+    .options
+    .iter()
+    .map(|option| option.name.clone())
+    .collect()
+}
+
+fn selected_names(data: &ChecklistCellData) -> Vec<String> {
+  data
+    .options
+    .iter()
+    .filter(|option| data.selected_option_ids.contains(&option.id))
+    .map(|option| option.name.clone())
+    .collect()
+}
+
+#[tokio::test]
+async fn update_checklist_cell_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database(1, &database_id);
+  let checklist_field = Field::new(
+    "tasks".to_string(),
+    "Tasks".to_string(),
+    FieldType::Checklist.into(),
+    false,
+  );
+  let progress_field = Field::new(
+    "progress".to_string(),
+    "Progress".to_string(),
+    FieldType::Progress.into(),
+    false,
+  )
+  .with_type_option_data(
+    FieldType::Progress.type_id(),
+    ProgressTypeOption::derived_from("tasks").into(),
+  );
+  for field in [checklist_field, progress_field] {
+    database_test.create_field(
+      None,
+      field,
+      &OrderObjectPosition::default(),
+      default_field_settings_by_layout(),
+    );
+  }
+
+  let data = checklist_data(&["a", "b", "c"], &["a"]);
+  let ids = data
+    .options
+    .iter()
+    .map(|option| option.id.clone())
+    .collect::<Vec<_>>();
+  let row_id = RowId::from(1);
+  let row = CreateRowParams::new(1, database_id.clone())
+    .with_cells([("tasks".to_string(), legacy_checklist_cell(&data))].into());
+  database_test.create_row(row).await.unwrap();
+
+  // The checklist written as a whole is split into its options on the first change.
+  let data = database_test
+    .update_checklist_cell(
+      &row_id,
+      "tasks",
+      vec![
+        ChecklistCellChange::Toggle {
+          option_id: ids[1].clone(),
+        },
+        ChecklistCellChange::Rename {
+          option_id: ids[2].clone(),
+          name: "C".to_string(),
+        },
+      ],
+    )
+    .await
+    .unwrap();
+  assert_eq!(option_names(&data), vec!["a", "b", "C"]);
+  assert_eq!(selected_names(&data), vec!["a", "b"]);
+
+  let progress = database_test
+    .get_cell("progress", &row_id)
+    .await
+    .cell
+    .unwrap();
+  assert_eq!(ProgressCellData::from(&progress).progress, Some(67));
+
+  let d = SelectOption::new("d");
+  let data = database_test
+    .update_checklist_cell(
+      &row_id,
+      "tasks",
+      vec![
+        ChecklistCellChange::Insert {
+          option: d.clone(),
+          index: Some(1),
+        },
+        ChecklistCellChange::Move {
+          option_id: ids[0].clone(),
+          to_index: 3,
+        },
+        ChecklistCellChange::Remove {
+          option_id: ids[1].clone(),
+        },
+        ChecklistCellChange::SetChecked {
+          option_id: d.id.clone(),
+          checked: true,
+        },
+      ],
+    )
+    .await
+    .unwrap();
+  assert_eq!(option_names(&data), vec!["d", "C", "a"]);
+  assert_eq!(selected_names(&data), vec!["d", "a"]);
+
+  // The cell data is kept in sync for the clients that read the checklist as a whole.
+  let cell = database_test.get_cell("tasks", &row_id).await.cell.unwrap();
+  let legacy: ChecklistCellData =
+    serde_json::from_str(&cell.get_as::<String>(CELL_DATA).unwrap()).unwrap();
+  assert_eq!(option_names(&legacy), option_names(&data));
+  assert_eq!(legacy.selected_option_ids, data.selected_option_ids);
+
+  let error = database_test
+    .update_checklist_cell(&row_id, "progress", vec![])
+    .await;
+  assert!(error.is_err());
+}
+
+#[test]
+fn concurrent_checklist_changes_test() {
+  let data = checklist_data(&["a", "b"], &[]);
+  let (a, b) = (data.options[0].id.clone(), data.options[1].id.clone());
+
+  let doc_1 = Doc::with_client_id(1);
+  let cell_1 = doc_1.get_or_insert_map("cell");
+  Any::from(checklist_cell(&data))
+    .fill(&mut doc_1.transact_mut(), &cell_1)
+    .unwrap();
+  let doc_2 = Doc::with_client_id(2);
+  let cell_2 = doc_2.get_or_insert_map("cell");
+  let update = doc_1
+    .transact()
+    .encode_state_as_update_v1(&StateVector::default());
+  doc_2
+    .transact_mut()
+    .apply_update(Update::decode_v1(&update).unwrap())
+    .unwrap();
+
+  // One client checks an option while the other one renames it and checks another one.
+  apply_checklist_changes(
+    &mut doc_1.transact_mut(),
+    &cell_1,
+    vec![ChecklistCellChange::Toggle {
+      option_id: a.clone(),
+    }],
+  );
+  apply_checklist_changes(
+    &mut doc_2.transact_mut(),
+    &cell_2,
+    vec![
+      ChecklistCellChange::Rename {
+        option_id: a,
+        name: "A".to_string(),
+      },
+      ChecklistCellChange::Toggle { option_id: b },
+    ],
+  );
+  let update_1 = doc_1
+    .transact()
+    .encode_state_as_update_v1(&StateVector::default());
+  let update_2 = doc_2
+    .transact()
+    .encode_state_as_update_v1(&StateVector::default());
+  doc_1
+    .transact_mut()
+    .apply_update(Update::decode_v1(&update_2).unwrap())
+    .unwrap();
+  doc_2
+    .transact_mut()
+    .apply_update(Update::decode_v1(&update_1).unwrap())
+    .unwrap();
+
+  for (doc, cell) in [(&doc_1, &cell_1), (&doc_2, &cell_2)] {
+    let cell = cell.to_json(&doc.transact()).into_map().unwrap();
+    let data = checklist_from_cell(&cell);
+    assert_eq!(option_names(&data), vec!["A", "b"]);
+    assert_eq!(selected_names(&data), vec!["A", "b"]);
+  }
+}
+
+#[test]
+fn whole_checklist_write_after_split_test() {
+  let data = checklist_data(&["a", "b"], &[]);
+  let a = data.options[0].id.clone();
+  let doc = Doc::new();
+  let cell_map = doc.get_or_insert_map("cell");
+  Any::from(checklist_cell(&data))
+    .fill(&mut doc.transact_mut(), &cell_map)
+    .unwrap();
+  apply_checklist_changes(
+    &mut doc.transact_mut(),
+    &cell_map,
+    vec![ChecklistCellChange::Toggle { option_id: a }],
+  );
+
+  // A client that doesn't know the keys of the options merges the whole cell into the map.
+  let legacy = checklist_data(&["c"], &[]);
+  Any::from(legacy_checklist_cell(&legacy))
+    .fill(&mut doc.transact_mut(), &cell_map)
+    .unwrap();
+  let cell = cell_map.to_json(&doc.transact()).into_map().unwrap();
+  let data = checklist_from_cell(&cell);
+  assert_eq!(option_names(&data), vec!["c"]);
+  assert!(data.selected_option_ids.is_empty());
+
+  // The options left by the previous split don't come back.
+  apply_checklist_changes(
+    &mut doc.transact_mut(),
+    &cell_map,
+    vec![ChecklistCellChange::Toggle {
+      option_id: legacy.options[0].id.clone(),
+    }],
+  );
+  let cell = cell_map.to_json(&doc.transact()).into_map().unwrap();
+  let data = checklist_from_cell(&cell);
+  assert_eq!(option_names(&data), vec!["c"]);
+  assert_eq!(selected_names(&data), vec!["c"]);
+}
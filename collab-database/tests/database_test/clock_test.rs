@@ -0,0 +1,159 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use collab::core::clock::ManualClock;
+use collab::util::AnyMapExt;
+use collab_database::database::{Database, DatabaseContext};
+use collab_database::entity::{CreateDatabaseParams, CreateViewParams, FieldType};
+use collab_database::error::DatabaseError;
+use collab_database::fields::url_type_option::{URLCellData, URLMetadata, URLMetadataProvider};
+use collab_database::fields::Field;
+use collab_database::rows::{Cell, CreateRowParams, RowId, CREATED_AT, LAST_MODIFIED};
+use collab_database::views::DuplicateRowPosition;
+use collab_database::workspace_database::NoPersistenceDatabaseCollabService;
+
+use crate::helper::TestTextCell;
+
+const NOW: i64 = 1_700_000_000;
+
+async fn create_database_with_clock(clock: Arc<ManualClock>) -> Database {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let context =
+    DatabaseContext::new(Arc::new(NoPersistenceDatabaseCollabService)).with_clock(clock);
+  let params = CreateDatabaseParams {
+    database_id: database_id.clone(),
+    views: vec![CreateViewParams {
+      database_id,
+      view_id: "v1".to_string(),
+      name: "my first database view".to_string(),
+      ..Default::default()
+    }],
+    ..Default::default()
+  };
+  Database::create_with_view(params, context).await.unwrap()
+}
+
+#[tokio::test]
+async fn database_rows_use_injected_clock_test() {
+  let clock = Arc::new(ManualClock::new(NOW));
+  let mut database = create_database_with_clock(clock.clone()).await;
+  let inline_view = database.get_view(&database.get_inline_view_id()).unwrap();
+  assert_eq!(inline_view.created_at, NOW);
+
+  let row_id = RowId::from(uuid::Uuid::new_v4().to_string());
+  let params = CreateRowParams::new(row_id.clone(), database.get_database_id());
+  database.create_row(params).await.unwrap();
+  let row = database.get_row(&row_id).await;
+  assert_eq!(row.created_at, NOW);
+  assert_eq!(row.modified_at, NOW);
+
+  clock.advance(60);
+  database
+    .update_row(row_id.clone(), |row_update| {
+      row_update.update_cells(|cells_update| {
+        cells_update.insert("f1", TestTextCell::from("hello"));
+      });
+    })
+    .await;
+  clock.advance(60);
+  database
+    .update_row(row_id.clone(), |row_update| {
+      row_update.update_cells(|cells_update| {
+        cells_update.insert("f1", TestTextCell::from("hello world"));
+      });
+    })
+    .await;
+  let cell = database.get_cell("f1", &row_id).await.cell.unwrap();
+  assert_eq!(cell.get_as::<i64>(CREATED_AT), Some(NOW + 60));
+  assert_eq!(cell.get_as::<i64>(LAST_MODIFIED), Some(NOW + 120));
+
+  let duplicated = database
+    .duplicate_row(&row_id, DuplicateRowPosition::After)
+    .await
+    .unwrap();
+  assert_eq!(duplicated.row.created_at, NOW + 120);
+}
+
+#[tokio::test]
+async fn database_views_use_injected_clock_test() {
+  let clock = Arc::new(ManualClock::new(NOW));
+  let mut database = create_database_with_clock(clock.clone()).await;
+
+  clock.set(NOW + 3600);
+  database.update_database_view("v1", |update| {
+    update.set_name("renamed view");
+  });
+  let view = database.get_view("v1").unwrap();
+  assert_eq!(view.modified_at, NOW + 3600);
+
+  let duplicated = database.duplicate_linked_view("v1").unwrap();
+  assert_eq!(duplicated.created_at, NOW + 3600);
+  assert_eq!(duplicated.modified_at, NOW + 3600);
+}
+
+#[tokio::test]
+async fn database_params_use_injected_clock_test() {
+  let clock = Arc::new(ManualClock::new(NOW));
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let row_id = RowId::from(uuid::Uuid::new_v4().to_string());
+  let context =
+    DatabaseContext::new(Arc::new(NoPersistenceDatabaseCollabService)).with_clock(clock);
+  let params = CreateDatabaseParams {
+    database_id: database_id.clone(),
+    rows: vec![CreateRowParams::new(row_id.clone(), database_id.clone())],
+    views: vec![CreateViewParams {
+      database_id,
+      view_id: "v1".to_string(),
+      name: "my first database view".to_string(),
+      ..Default::default()
+    }],
+    ..Default::default()
+  };
+  let database = Database::create_with_view(params, context).await.unwrap();
+
+  let view = database.get_view("v1").unwrap();
+  assert_eq!(view.created_at, NOW);
+  assert_eq!(view.modified_at, NOW);
+  let row = database.get_row(&row_id).await;
+  assert_eq!(row.created_at, NOW);
+  assert_eq!(row.modified_at, NOW);
+}
+
+struct TitleProvider;
+
+#[async_trait]
+impl URLMetadataProvider for TitleProvider {
+  async fn fetch_metadata(&self, url: &str) -> Result<URLMetadata, DatabaseError> {
+    Ok(URLMetadata {
+      title: Some(url.to_string()),
+      favicon: None,
+    })
+  }
+}
+
+#[tokio::test]
+async fn url_enrichment_uses_injected_clock_test() {
+  let clock = Arc::new(ManualClock::new(NOW));
+  let mut database = create_database_with_clock(clock.clone()).await;
+  let field = Field::new(
+    "url".to_string(),
+    "Link".to_string(),
+    FieldType::URL.into(),
+    false,
+  );
+  database.create_field(None, field, &Default::default(), Default::default());
+  let row_id = RowId::from(uuid::Uuid::new_v4().to_string());
+  let cell = Cell::from(URLCellData::new("https://appflowy.io"));
+  let params = CreateRowParams::new(row_id.clone(), database.get_database_id())
+    .with_cells([("url".to_string(), cell)].into_iter().collect());
+  database.create_row(params).await.unwrap();
+
+  clock.advance(60);
+  let cell_data = database
+    .enrich_url_cell(&row_id, "url", &TitleProvider)
+    .await
+    .unwrap()
+    .unwrap();
+  assert_eq!(cell_data.last_verified_at, Some(NOW + 60));
+}
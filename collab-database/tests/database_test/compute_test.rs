@@ -0,0 +1,141 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use collab::util::AnyMapExt;
+use collab_database::database_compute::{
+  ComputeCellHooks, ComputeCellOutcome, ComputeCellProvider, ComputeCellQueue, ComputeCellRequest,
+  COMPUTE_SOURCE_HASH,
+};
+use collab_database::entity::FieldType;
+use collab_database::error::DatabaseError;
+use collab_database::fields::Field;
+use collab_database::rows::{CreateRowParams, RowId};
+use collab_database::template::entity::CELL_DATA;
+use collab_database::views::OrderObjectPosition;
+
+use crate::database_test::helper::{
+  create_database, default_field_settings_by_layout, DatabaseTest,
+};
+use crate::helper::TestTextCell;
+
+#[derive(Default)]
+struct UppercaseSummary {
+  calls: AtomicUsize,
+}
+
+#[async_trait]
+impl ComputeCellProvider for UppercaseSummary {
+  async fn compute(&self, request: &ComputeCellRequest) -> Result<String, DatabaseError> {
+    self.calls.fetch_add(1, Ordering::SeqCst);
+    let mut values = request
+      .source
+      .values()
+      .filter_map(|cell| cell.get_as::<String>(CELL_DATA))
+      .collect::<Vec<_>>();
+    values.sort();
+    Ok(values.join(" ").to_uppercase())
+  }
+}
+
+async fn create_database_with_summary_field() -> (DatabaseTest, RowId) {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database(1, &database_id);
+  for (field_id, field_type) in [
+    ("name", FieldType::RichText),
+    ("summary", FieldType::Summary),
+    ("translate", FieldType::Translate),
+  ] {
+    database_test.create_field(
+      None,
+      Field::new(
+        field_id.to_string(),
+        field_id.to_string(),
+        field_type.into(),
+        false,
+      ),
+      &OrderObjectPosition::default(),
+      default_field_settings_by_layout(),
+    );
+  }
+
+  let row_id = RowId::from(uuid::Uuid::new_v4().to_string());
+  let mut row = CreateRowParams::new(row_id.clone(), database_id.clone());
+  row
+    .cells
+    .insert("name".to_string(), TestTextCell::from("apple").into());
+  database_test.create_row(row).await.unwrap();
+  // A row without any source data is never computed
+  let empty_row = CreateRowParams::new(uuid::Uuid::new_v4().to_string(), database_id);
+  database_test.create_row(empty_row).await.unwrap();
+  (database_test, row_id)
+}
+
+async fn summary_cell(database_test: &DatabaseTest, row_id: &RowId) -> Option<String> {
+  database_test
+    .get_cell("summary", row_id)
+    .await
+    .cell
+    .and_then(|cell| cell.get_as::<String>(CELL_DATA))
+}
+
+#[tokio::test]
+async fn compute_stale_summary_cells_test() {
+  let (mut database_test, row_id) = create_database_with_summary_field().await;
+  let provider = Arc::new(UppercaseSummary::default());
+  let mut hooks = ComputeCellHooks::default();
+  hooks
+    .register(FieldType::Summary, provider.clone())
+    .unwrap();
+  let mut queue = ComputeCellQueue::new(hooks);
+
+  // The translate field has no provider, so only the summary cell is queued
+  assert_eq!(queue.enqueue_stale_cells(&database_test).await, 1);
+  assert_eq!(queue.enqueue_row(&database_test, &row_id).await, 0);
+  let outcomes = queue.run(&database_test).await;
+  assert_eq!(
+    outcomes,
+    vec![ComputeCellOutcome::Updated {
+      row_id: row_id.clone(),
+      field_id: "summary".to_string(),
+    }]
+  );
+  assert!(queue.is_empty());
+  assert_eq!(
+    summary_cell(&database_test, &row_id).await.as_deref(),
+    Some("APPLE")
+  );
+  let cell = database_test
+    .get_cell("summary", &row_id)
+    .await
+    .cell
+    .unwrap();
+  assert!(cell.get_as::<String>(COMPUTE_SOURCE_HASH).is_some());
+
+  // The computed cell is up to date
+  assert_eq!(queue.enqueue_stale_cells(&database_test).await, 0);
+
+  // Changing a source cell makes the computed cell stale
+  database_test
+    .update_row(row_id.clone(), |row| {
+      row.update_cells(|cells| {
+        cells.insert("name", TestTextCell::from("banana"));
+      });
+    })
+    .await;
+  assert_eq!(queue.enqueue_row(&database_test, &row_id).await, 1);
+  queue.run(&database_test).await;
+  assert_eq!(
+    summary_cell(&database_test, &row_id).await.as_deref(),
+    Some("BANANA")
+  );
+  assert_eq!(provider.calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn register_provider_for_non_computed_field_test() {
+  let mut hooks = ComputeCellHooks::default();
+  let result = hooks.register(FieldType::RichText, Arc::new(UppercaseSummary::default()));
+  assert!(result.is_err());
+  assert!(hooks.get(&FieldType::RichText).is_none());
+}
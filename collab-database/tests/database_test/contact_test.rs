@@ -0,0 +1,210 @@
+use collab_database::entity::FieldType;
+use collab_database::error::DatabaseError;
+use collab_database::fields::contact_type_option::{
+  EmailCellData, EmailTypeOption, PhoneCellData, PhoneTypeOption, ValidationStrictness,
+};
+use collab_database::fields::text_type_option::RichTextTypeOption;
+use collab_database::fields::{BuiltinFieldTypeConverter, Field, TypeOptionData};
+use collab_database::rows::{new_cell_builder, Cell, CreateRowParams};
+use collab_database::template::entity::CELL_DATA;
+use collab_database::views::OrderObjectPosition;
+
+use crate::database_test::helper::{create_database, default_field_settings_by_layout};
+
+#[test]
+fn validate_email_test() {
+  let lenient = EmailTypeOption::default();
+  let strict = EmailTypeOption::new(ValidationStrictness::Strict);
+  assert_eq!(
+    lenient.validate(" me@example.com ").unwrap(),
+    "me@example.com"
+  );
+  assert_eq!(lenient.validate("me@localhost").unwrap(), "me@localhost");
+  assert!(strict.validate("me@localhost").is_err());
+  assert!(strict.validate("first..last@example.com").is_err());
+  assert!(strict.validate("me@-example.com").is_err());
+  assert_eq!(
+    strict.validate("first.last+tag@mail.example.org").unwrap(),
+    "first.last+tag@mail.example.org"
+  );
+
+  for email in [
+    "",
+    "example.com",
+    "@example.com",
+    "me@",
+    "my name@example.com",
+  ] {
+    assert!(
+      matches!(
+        lenient.email_cell(email),
+        Err(DatabaseError::InvalidEmail(_))
+      ),
+      "{}",
+      email
+    );
+  }
+
+  let cell = strict.email_cell("me@example.com").unwrap();
+  assert_eq!(EmailCellData::from(&cell).email, "me@example.com");
+  let data = TypeOptionData::from(strict.clone());
+  assert_eq!(EmailTypeOption::from(data), strict);
+}
+
+#[test]
+fn validate_phone_test() {
+  let lenient = PhoneTypeOption::default();
+  let strict = PhoneTypeOption::new(ValidationStrictness::Strict);
+  assert_eq!(
+    lenient.validate(" (555) 123-4567 ").unwrap(),
+    "(555) 123-4567"
+  );
+  assert_eq!(lenient.validate("112").unwrap(), "112");
+  assert!(strict.validate("(555) 123-4567").is_err());
+  assert!(strict.validate("+1 555").is_err());
+  assert_eq!(
+    strict.validate("+33 1 23 45 67 89").unwrap(),
+    "+33 1 23 45 67 89"
+  );
+
+  for phone in ["", "12", "call me", "+1 555 123 4567 890 123", "555+1234"] {
+    assert!(
+      matches!(
+        lenient.phone_cell(phone),
+        Err(DatabaseError::InvalidPhoneNumber(_))
+      ),
+      "{}",
+      phone
+    );
+  }
+
+  let cell = strict.phone_cell("+1 (555) 123-4567").unwrap();
+  let data = PhoneCellData::from(&cell);
+  assert_eq!(data.phone, "+1 (555) 123-4567");
+  assert_eq!(data.dial_string(), "+15551234567");
+  let type_option = TypeOptionData::from(strict.clone());
+  assert_eq!(PhoneTypeOption::from(type_option), strict);
+  assert_eq!(
+    PhoneTypeOption::from(TypeOptionData::new()),
+    PhoneTypeOption::default()
+  );
+}
+
+#[tokio::test]
+async fn convert_text_to_email_field_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database(1, &database_id);
+  let field = Field::new(
+    "contact".to_string(),
+    "Contact".to_string(),
+    FieldType::RichText.into(),
+    false,
+  )
+  .with_type_option_data(FieldType::RichText.type_id(), RichTextTypeOption.into())
+  .with_type_option_data(
+    FieldType::Email.type_id(),
+    EmailTypeOption::new(ValidationStrictness::Strict).into(),
+  );
+  database_test.create_field(
+    None,
+    field,
+    &OrderObjectPosition::default(),
+    default_field_settings_by_layout(),
+  );
+
+  let mut row_ids = vec![];
+  for text in ["me@example.com", "me@localhost", "call me"] {
+    let mut cell = new_cell_builder(FieldType::RichText);
+    cell.insert(CELL_DATA.into(), text.into());
+    let row_id = uuid::Uuid::new_v4().to_string();
+    let row = CreateRowParams::new(row_id.clone(), database_id.clone())
+      .with_cells([("contact".to_string(), cell)].into_iter().collect());
+    database_test.create_row(row).await.unwrap();
+    row_ids.push(row_id);
+  }
+
+  // The invalid emails are kept as they are, for the user to fix them.
+  let summary = database_test
+    .switch_field_type("contact", FieldType::Email, &BuiltinFieldTypeConverter)
+    .await
+    .unwrap();
+  assert_eq!(summary.converted, 3);
+  assert_eq!(summary.cleared, 0);
+  let mut emails = vec![];
+  for row_id in row_ids {
+    let cell: Option<Cell> = database_test.get_cell("contact", &row_id.into()).await.cell;
+    emails.push(
+      cell
+        .map(|cell| EmailCellData::from(&cell).email)
+        .unwrap_or_default(),
+    );
+  }
+  assert_eq!(emails, vec!["me@example.com", "me@localhost", "call me"]);
+}
+
+#[tokio::test]
+async fn validate_contact_cells_on_write_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database(1, &database_id);
+  let field = Field::new(
+    "email".to_string(),
+    "Email".to_string(),
+    FieldType::Email.into(),
+    false,
+  )
+  .with_type_option_data(
+    FieldType::Email.type_id(),
+    EmailTypeOption::new(ValidationStrictness::Strict).into(),
+  );
+  database_test.create_field(
+    None,
+    field,
+    &OrderObjectPosition::default(),
+    default_field_settings_by_layout(),
+  );
+  let email_cell = |email: &str| {
+    Cell::from(&EmailCellData {
+      email: email.to_string(),
+    })
+  };
+
+  // A new row with an invalid email is rejected.
+  let row_id = uuid::Uuid::new_v4().to_string();
+  let params = CreateRowParams::new(row_id.clone(), database_id.clone()).with_cells(
+    [("email".to_string(), email_cell("me@localhost"))]
+      .into_iter()
+      .collect(),
+  );
+  assert!(database_test.create_row(params).await.is_err());
+
+  let params = CreateRowParams::new(row_id.clone(), database_id.clone()).with_cells(
+    [("email".to_string(), email_cell(" me@example.com "))]
+      .into_iter()
+      .collect(),
+  );
+  database_test.create_row(params).await.unwrap();
+  let email = |cell: Option<Cell>| cell.map(|cell| EmailCellData::from(&cell).email);
+  assert_eq!(
+    email(
+      database_test
+        .get_cell("email", &row_id.clone().into())
+        .await
+        .cell
+    )
+    .as_deref(),
+    Some("me@example.com")
+  );
+
+  // An invalid email is not written over the valid one.
+  database_test
+    .update_row(row_id.clone().into(), |row_update| {
+      row_update.update_cells(|cells_update| {
+        cells_update.insert_cell("email", email_cell("call me"));
+      });
+    })
+    .await;
+  assert_eq!(
+    email(database_test.get_cell("email", &row_id.into()).await.cell).as_deref(),
+    Some("me@example.com")
+  );
+}
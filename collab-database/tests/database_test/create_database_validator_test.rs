@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use collab::preclude::Any;
+use collab_database::database::{Database, DatabaseContext};
+use collab_database::entity::{
+  CreateDatabaseParams, CreateDatabaseParamsValidator, CreateDatabaseViolation, CreateViewParams,
+  FieldType,
+};
+use collab_database::error::DatabaseError;
+use collab_database::fields::Field;
+use collab_database::rows::{CreateRowParams, RowId};
+use collab_database::views::{
+  DatabaseLayout, FilterNode, FILTER_FIELD_ID, GROUP_FIELD_ID, SORT_FIELD_ID,
+};
+use collab_database::workspace_database::NoPersistenceDatabaseCollabService;
+
+fn field(id: &str, field_type: FieldType, is_primary: bool) -> Field {
+  Field::new(
+    id.to_string(),
+    id.to_string(),
+    field_type.into(),
+    is_primary,
+  )
+}
+
+fn field_ref(key: &str, field_id: &str) -> HashMap<String, Any> {
+  HashMap::from([(key.to_string(), Any::from(field_id))])
+}
+
+fn view(view_id: &str, layout: DatabaseLayout) -> CreateViewParams {
+  CreateViewParams::new(
+    "d1".to_string(),
+    view_id.to_string(),
+    view_id.to_string(),
+    layout,
+  )
+}
+
+#[tokio::test]
+async fn create_database_params_violations_test() {
+  let mut grid = view("v1", DatabaseLayout::Grid);
+  grid.filters = vec![FilterNode::and(
+    "and",
+    vec![
+      FilterNode::data(field_ref(FILTER_FIELD_ID, "f1")),
+      FilterNode::data(field_ref(FILTER_FIELD_ID, "missing_filter_field")),
+    ],
+  )
+  .into()];
+  grid.sorts = vec![field_ref(SORT_FIELD_ID, "missing_sort_field")];
+  grid.group_settings = vec![field_ref(GROUP_FIELD_ID, "f2")];
+  let mut board = view("v1", DatabaseLayout::Board);
+  board.deps_fields = vec![field("f2", FieldType::SingleSelect, false)];
+  let params = CreateDatabaseParams {
+    database_id: "d1".to_string(),
+    fields: vec![
+      field("f1", FieldType::RichText, true),
+      field("f3", FieldType::Number, false),
+    ],
+    rows: vec![
+      CreateRowParams::new(1, "d1".to_string()),
+      CreateRowParams::new(1, "d1".to_string()),
+      CreateRowParams::new(2, "d2".to_string()),
+      CreateRowParams::new(3, String::new()),
+    ],
+    views: vec![grid, board],
+  };
+
+  let violations = CreateDatabaseParamsValidator::default().violations(&params);
+  assert_eq!(
+    violations,
+    vec![
+      CreateDatabaseViolation::DuplicateRowId(RowId::from(1)),
+      CreateDatabaseViolation::RowOfOtherDatabase(RowId::from(2)),
+      CreateDatabaseViolation::RowOfOtherDatabase(RowId::from(3)),
+      CreateDatabaseViolation::UnknownFilterField {
+        view_id: "v1".to_string(),
+        field_id: "missing_filter_field".to_string(),
+      },
+      CreateDatabaseViolation::UnknownSortField {
+        view_id: "v1".to_string(),
+        field_id: "missing_sort_field".to_string(),
+      },
+      CreateDatabaseViolation::DuplicateViewId("v1".to_string()),
+      CreateDatabaseViolation::MismatchedDepsFields {
+        view_id: "v1".to_string(),
+      },
+    ]
+  );
+
+  // The rows without a database id are filled with the id of the database.
+  let violations = CreateDatabaseParamsValidator::default()
+    .validate(params.clone())
+    .unwrap_err();
+  assert!(!violations.contains(&CreateDatabaseViolation::RowOfOtherDatabase(RowId::from(3))));
+  let violations = CreateDatabaseParamsValidator::default()
+    .with_fill_database_ids(false)
+    .validate(params)
+    .unwrap_err();
+  assert!(violations.contains(&CreateDatabaseViolation::RowOfOtherDatabase(RowId::from(3))));
+}
+
+#[tokio::test]
+async fn create_database_params_configurable_constraints_test() {
+  let params = CreateDatabaseParams {
+    database_id: "d1".to_string(),
+    fields: vec![
+      field("f1", FieldType::RichText, false),
+      field("f2", FieldType::Number, false),
+    ],
+    rows: vec![],
+    views: vec![view("v1", DatabaseLayout::Calendar)],
+  };
+  assert!(CreateDatabaseParamsValidator::default()
+    .violations(&params)
+    .is_empty());
+
+  let validator = CreateDatabaseParamsValidator::default()
+    .with_require_primary_field(true)
+    .with_check_layout_fields(true);
+  assert_eq!(
+    validator.violations(&params),
+    vec![
+      CreateDatabaseViolation::MissingPrimaryField,
+      CreateDatabaseViolation::MissingCalendarDateField {
+        view_id: "v1".to_string(),
+      },
+    ]
+  );
+
+  // The date field of the calendar can come with the view.
+  let mut params = params;
+  params.fields[0].is_primary = true;
+  params.views[0] = view("v1", DatabaseLayout::Calendar).with_deps_fields(
+    vec![field("date", FieldType::DateTime, false)],
+    vec![HashMap::new()],
+  );
+  assert!(validator.violations(&params).is_empty());
+}
+
+#[tokio::test]
+async fn create_database_with_invalid_params_test() {
+  let mut grid = view("v1", DatabaseLayout::Grid);
+  grid.sorts = vec![field_ref(SORT_FIELD_ID, "missing")];
+  let params = CreateDatabaseParams {
+    database_id: "d1".to_string(),
+    fields: vec![field("f1", FieldType::RichText, true)],
+    rows: vec![],
+    views: vec![grid],
+  };
+  let result = Database::create_with_view(
+    params,
+    DatabaseContext::new(Arc::new(NoPersistenceDatabaseCollabService)),
+  )
+  .await;
+  match result {
+    Err(DatabaseError::InvalidCreateDatabaseParams(violations)) => assert_eq!(
+      violations,
+      vec![CreateDatabaseViolation::UnknownSortField {
+        view_id: "v1".to_string(),
+        field_id: "missing".to_string(),
+      }]
+    ),
+    _ => panic!("the database should not be created"),
+  }
+}
@@ -0,0 +1,161 @@
+use collab_database::csv::CSVExportOptions;
+use collab_database::entity::FieldType;
+use collab_database::fields::checkbox_type_option::CheckboxTypeOption;
+use collab_database::fields::date_type_option::{DateCellData, DateTypeOption};
+use collab_database::fields::select_type_option::{
+  MultiSelectTypeOption, SelectOption, SelectOptionIds, SelectTypeOption,
+};
+use collab_database::fields::text_type_option::RichTextTypeOption;
+use collab_database::fields::{Field, StringifyTypeOption, TypeOptionData};
+use collab_database::rows::{new_cell_builder, Cell, CreateRowParams, RowId};
+use collab_database::template::entity::CELL_DATA;
+use collab_database::views::OrderObjectPosition;
+
+use crate::database_test::helper::{
+  create_database, default_field_settings_by_layout, DatabaseTest,
+};
+
+const TIMESTAMP: i64 = 1_700_000_000;
+
+fn create_field(
+  database_test: &mut DatabaseTest,
+  id: &str,
+  name: &str,
+  field_type: FieldType,
+  type_option: TypeOptionData,
+) {
+  let field = Field::new(
+    id.to_string(),
+    name.to_string(),
+    field_type.clone().into(),
+    false,
+  )
+  .with_type_option_data(field_type.type_id(), type_option);
+  database_test.create_field(
+    None,
+    field,
+    &OrderObjectPosition::default(),
+    default_field_settings_by_layout(),
+  );
+}
+
+fn text_cell(text: &str) -> Cell {
+  let mut cell = new_cell_builder(FieldType::RichText);
+  cell.insert(CELL_DATA.into(), text.into());
+  cell
+}
+
+async fn create_export_database() -> (DatabaseTest, Vec<RowId>) {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database(1, &database_id);
+  let options = vec![SelectOption::new("Rust"), SelectOption::new("Dart")];
+  let tags = MultiSelectTypeOption(SelectTypeOption::new(options.clone(), false));
+  create_field(
+    &mut database_test,
+    "name",
+    "Name",
+    FieldType::RichText,
+    RichTextTypeOption.into(),
+  );
+  create_field(
+    &mut database_test,
+    "tags",
+    "Tags",
+    FieldType::MultiSelect,
+    tags.into(),
+  );
+  create_field(
+    &mut database_test,
+    "done",
+    "Done",
+    FieldType::Checkbox,
+    CheckboxTypeOption.into(),
+  );
+  create_field(
+    &mut database_test,
+    "due",
+    "Due",
+    FieldType::DateTime,
+    DateTypeOption::new().into(),
+  );
+
+  let option_ids = SelectOptionIds::from(
+    options
+      .iter()
+      .map(|option| option.id.clone())
+      .collect::<Vec<_>>(),
+  );
+  let rows = vec![
+    vec![
+      ("name", text_cell("Collab, the CRDT")),
+      ("tags", option_ids.to_cell_data(FieldType::MultiSelect)),
+      ("done", text_cell("true")),
+      ("due", Cell::from(&DateCellData::from_timestamp(TIMESTAMP))),
+    ],
+    vec![("name", text_cell("Docs"))],
+  ];
+  let mut row_ids = vec![];
+  for cells in rows {
+    let row_id = RowId::from(uuid::Uuid::new_v4().to_string());
+    let params = CreateRowParams::new(row_id.clone(), database_id.clone()).with_cells(
+      cells
+        .into_iter()
+        .map(|(field_id, cell)| (field_id.to_string(), cell))
+        .collect(),
+    );
+    database_test.create_row(params).await.unwrap();
+    row_ids.push(row_id);
+  }
+  (database_test, row_ids)
+}
+
+#[tokio::test]
+async fn export_view_to_csv_test() {
+  let (database_test, _) = create_export_database().await;
+  let csv = database_test
+    .export_csv("v1", CSVExportOptions::default())
+    .await
+    .unwrap();
+
+  let due =
+    DateTypeOption::new().stringify_cell(&Cell::from(&DateCellData::from_timestamp(TIMESTAMP)));
+  let expected = format!(
+    "Name,Tags,Done,Due\n\"Collab, the CRDT\",\"Rust, Dart\",Yes,\"{}\"\nDocs,,No,\n",
+    due
+  );
+  assert_eq!(csv, expected);
+}
+
+#[tokio::test]
+async fn export_csv_options_test() {
+  let (mut database_test, row_ids) = create_export_database().await;
+  database_test.hide_row("v1", &row_ids[0]);
+
+  let options = CSVExportOptions::default()
+    .with_header(false)
+    .with_field_ids(vec!["done".to_string(), "name".to_string()]);
+  let csv = database_test
+    .export_csv("v1", options.clone())
+    .await
+    .unwrap();
+  assert_eq!(csv, "Docs,No\n");
+
+  let mut buffer = vec![];
+  database_test
+    .export_csv_to_writer(
+      "v1",
+      options.with_hidden_rows(true).with_delimiter(b';'),
+      &mut buffer,
+    )
+    .await
+    .unwrap();
+  assert_eq!(
+    String::from_utf8(buffer).unwrap(),
+    "Collab, the CRDT;Yes\nDocs;No\n"
+  );
+
+  assert!(database_test
+    .export_csv("unknown", CSVExportOptions::default())
+    .await
+    .is_err());
+}
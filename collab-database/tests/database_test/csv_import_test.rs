@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use collab_database::csv::{import_csv, CSVExportOptions, CSVImportOptions};
+use collab_database::database::{Database, DatabaseContext};
+use collab_database::entity::FieldType;
+use collab_database::workspace_database::NoPersistenceDatabaseCollabService;
+
+const CSV: &str = "Name,Count,Done,Tag\nCollab,1,Yes,Rust\nDocs,2,No,Dart\nSync,3,Yes,Rust\n";
+
+#[tokio::test]
+async fn import_csv_test() {
+  let params = import_csv(CSV.as_bytes(), CSVImportOptions::default())
+    .await
+    .unwrap();
+  let fields = params
+    .fields
+    .iter()
+    .map(|field| {
+      (
+        field.name.as_str(),
+        FieldType::from(field.field_type),
+        field.is_primary,
+      )
+    })
+    .collect::<Vec<_>>();
+  assert_eq!(
+    fields,
+    vec![
+      ("Name", FieldType::RichText, true),
+      ("Count", FieldType::Number, false),
+      ("Done", FieldType::Checkbox, false),
+      ("Tag", FieldType::SingleSelect, false),
+    ]
+  );
+  assert_eq!(params.rows.len(), 3);
+  assert_eq!(params.rows[0].cells.len(), 4);
+
+  let database_id = params.database_id.clone();
+  let context = DatabaseContext::new(Arc::new(NoPersistenceDatabaseCollabService));
+  let database = Database::create_with_view(params, context).await.unwrap();
+  assert_eq!(database.get_database_id(), database_id);
+  let csv = database
+    .export_csv(&database.get_inline_view_id(), CSVExportOptions::default())
+    .await
+    .unwrap();
+  assert_eq!(csv, CSV);
+}
+
+#[tokio::test]
+async fn import_csv_with_field_type_hints_test() {
+  let options = CSVImportOptions::default()
+    .with_auto_field_type(false)
+    .with_field_type("Count", FieldType::Number);
+  let params = import_csv(CSV.as_bytes(), options).await.unwrap();
+  let field_types = params
+    .fields
+    .iter()
+    .map(|field| FieldType::from(field.field_type))
+    .collect::<Vec<_>>();
+  assert_eq!(
+    field_types,
+    vec![
+      FieldType::RichText,
+      FieldType::Number,
+      FieldType::RichText,
+      FieldType::RichText,
+    ]
+  );
+
+  let options = CSVImportOptions::default().with_field_type("Unknown", FieldType::Number);
+  assert!(import_csv(CSV.as_bytes(), options).await.is_err());
+}
@@ -0,0 +1,135 @@
+use collab::entity::EncodedCollab;
+use collab_database::database_diff::{
+  diff_database_snapshots, diff_row_snapshots, DatabaseFieldSummary, DatabaseViewSummary,
+};
+use collab_database::entity::{CreateViewParams, FieldType};
+use collab_database::fields::Field;
+use collab_database::rows::{Cells, CreateRowParams, RowId};
+use collab_database::views::{DatabaseLayout, OrderObjectPosition};
+
+use crate::database_test::helper::{
+  create_database_with_default_data, default_field_settings_by_layout, DatabaseTest,
+};
+use crate::helper::TestTextCell;
+
+#[tokio::test]
+async fn diff_database_snapshots_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database_with_default_data(1, &database_id).await;
+  let older = database_test
+    .encode_database_collabs()
+    .await
+    .unwrap()
+    .encoded_database_collab
+    .encoded_collab;
+
+  database_test.create_field(
+    None,
+    Field::new("f4".to_string(), "number field".to_string(), 1, false),
+    &OrderObjectPosition::default(),
+    default_field_settings_by_layout(),
+  );
+  database_test.update_field("f1", |field_update| {
+    field_update.set_name("renamed text field");
+  });
+  database_test
+    .create_linked_view(CreateViewParams {
+      database_id: database_id.clone(),
+      view_id: "v2".to_string(),
+      name: "my board".to_string(),
+      layout: DatabaseLayout::Board,
+      ..Default::default()
+    })
+    .unwrap();
+  let removed_row_id = database_test.pre_define_row_ids[1].clone();
+  database_test.remove_row(&removed_row_id).await.unwrap();
+  let newer = database_test
+    .encode_database_collabs()
+    .await
+    .unwrap()
+    .encoded_database_collab
+    .encoded_collab;
+
+  let diff = diff_database_snapshots(&older, &newer).unwrap();
+  assert_eq!(
+    diff.inserted_fields,
+    vec![DatabaseFieldSummary {
+      field_id: "f4".to_string(),
+      name: "number field".to_string(),
+      field_type: FieldType::Number,
+    }]
+  );
+  assert!(diff.deleted_fields.is_empty());
+  assert_eq!(diff.updated_fields.len(), 1);
+  assert_eq!(diff.updated_fields[0].field_id, "f1");
+  assert_eq!(diff.updated_fields[0].name, "renamed text field");
+  assert_eq!(
+    diff.updated_fields[0].old_name.as_deref(),
+    Some("text field")
+  );
+  assert!(!diff.updated_fields[0].type_changed);
+
+  assert_eq!(
+    diff.inserted_views,
+    vec![DatabaseViewSummary {
+      view_id: "v2".to_string(),
+      name: "my board".to_string(),
+      layout: DatabaseLayout::Board,
+    }]
+  );
+  let v1 = diff
+    .updated_views
+    .iter()
+    .find(|view| view.view_id == "v1")
+    .unwrap();
+  assert!(v1.rows_changed);
+  assert!(!v1.layout_changed);
+  assert!(diff.inserted_rows.is_empty());
+  assert_eq!(diff.deleted_rows, vec![removed_row_id]);
+
+  let diff = diff_database_snapshots(&newer, &newer).unwrap();
+  assert!(diff.is_empty());
+}
+
+#[tokio::test]
+async fn diff_database_row_snapshots_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database_with_default_data(1, &database_id).await;
+  let row_id = RowId::from(uuid::Uuid::new_v4().to_string());
+  let params =
+    CreateRowParams::new(row_id.clone(), database_id.clone()).with_cells(Cells::from([(
+      "f1".into(),
+      TestTextCell::from("1f1cell").into(),
+    )]));
+  database_test.create_row(params).await.unwrap();
+  let older = encode_row(&database_test, &row_id).await;
+
+  database_test
+    .update_row(row_id.clone(), |row_update| {
+      row_update.update_cells(|cells_update| {
+        cells_update
+          .insert("f1", TestTextCell("hello world".to_string()))
+          .insert("f3", TestTextCell("new cell".to_string()));
+      });
+    })
+    .await;
+  let newer = encode_row(&database_test, &row_id).await;
+
+  let diff = diff_row_snapshots(&older, &newer).unwrap();
+  assert_eq!(diff.row_id, row_id);
+  assert_eq!(diff.inserted_cells, vec!["f3".to_string()]);
+  assert!(diff.deleted_cells.is_empty());
+  assert_eq!(diff.updated_cells, vec!["f1".to_string()]);
+}
+
+async fn encode_row(database_test: &DatabaseTest, row_id: &RowId) -> EncodedCollab {
+  database_test
+    .encode_database_collabs()
+    .await
+    .unwrap()
+    .encoded_row_collabs
+    .into_iter()
+    .find(|info| info.object_id == row_id.as_str())
+    .unwrap()
+    .encoded_collab
+}
@@ -0,0 +1,266 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use collab::core::collab_backlink::{BacklinkRegistry, BacklinkType, LinkSource};
+use collab::lock::RwLock;
+use collab_database::document_task::{
+  extract_document_tasks, DocumentTask, DocumentTaskSync, TaskFields,
+};
+use collab_database::entity::FieldType;
+use collab_database::fields::Field;
+use collab_database::rows::{new_cell_builder, RowId};
+use collab_database::template::entity::CELL_DATA;
+use collab_document::document::Document;
+use collab_document::importer::md_importer::MDImporter;
+use tokio::time::sleep;
+use uuid::Uuid;
+
+use crate::database_test::helper::{DatabaseTest, DatabaseTestBuilder};
+use crate::helper::TestTextCell;
+
+const MARKDOWN: &str = "# Sprint\n\n- [ ] Write the doc\n- [x] Review the PR\n\n## Notes\n\n- [ ] Book the room\n\n# Later\n\n- [ ] Ship it";
+
+fn create_document() -> Document {
+  let document_id = Uuid::new_v4().to_string();
+  let data = MDImporter::new(None)
+    .import(&document_id, MARKDOWN.to_string())
+    .unwrap();
+  Document::create(&document_id, data).unwrap()
+}
+
+fn find_block(document: &Document, text: &str) -> String {
+  document
+    .get_all_block_ids()
+    .into_iter()
+    .find(|block_id| document.get_plain_text_from_block(block_id).as_deref() == Some(text))
+    .unwrap()
+}
+
+async fn create_task_database(database_id: &str) -> DatabaseTest {
+  DatabaseTestBuilder::new(1, database_id)
+    .with_field(Field::new(
+      "name".to_string(),
+      "Name".to_string(),
+      FieldType::RichText.into(),
+      true,
+    ))
+    .with_field(Field::new(
+      "done".to_string(),
+      "Done".to_string(),
+      FieldType::Checkbox.into(),
+      false,
+    ))
+    .with_field(Field::new(
+      "source".to_string(),
+      "Source".to_string(),
+      FieldType::RichText.into(),
+      false,
+    ))
+    .build()
+    .await
+}
+
+fn task_fields() -> TaskFields {
+  TaskFields {
+    text_field_id: "name".to_string(),
+    checked_field_id: "done".to_string(),
+    source_field_id: Some("source".to_string()),
+  }
+}
+
+async fn cell_text(database_test: &DatabaseTest, row_id: &RowId, field_id: &str) -> String {
+  TestTextCell::from(database_test.get_row(row_id).await.cells[field_id].clone()).0
+}
+
+#[tokio::test]
+async fn extract_document_tasks_test() {
+  let document = create_document();
+  let texts = |tasks: Vec<DocumentTask>| {
+    tasks
+      .into_iter()
+      .map(|task| (task.text, task.checked))
+      .collect::<Vec<_>>()
+  };
+
+  let tasks = extract_document_tasks(&document, None).unwrap();
+  assert_eq!(
+    texts(tasks),
+    vec![
+      ("Write the doc".to_string(), false),
+      ("Review the PR".to_string(), true),
+      ("Book the room".to_string(), false),
+      ("Ship it".to_string(), false),
+    ]
+  );
+
+  // The tasks under a heading stop at the next heading of the same level.
+  let sprint = find_block(&document, "Sprint");
+  let tasks = extract_document_tasks(&document, Some(&sprint)).unwrap();
+  assert_eq!(tasks.len(), 3);
+  let notes = find_block(&document, "Notes");
+  let tasks = extract_document_tasks(&document, Some(&notes)).unwrap();
+  assert_eq!(texts(tasks), vec![("Book the room".to_string(), false)]);
+
+  let write = find_block(&document, "Write the doc");
+  assert!(extract_document_tasks(&document, Some(&write)).is_err());
+}
+
+#[tokio::test]
+async fn create_task_rows_test() {
+  let database_id = Uuid::new_v4().to_string();
+  let mut database_test = create_task_database(&database_id).await;
+  let document = create_document();
+  let document_id = document.object_id().to_string();
+  let registry = BacklinkRegistry::new();
+  let sync = DocumentTaskSync::new(&document_id, &database_id, task_fields(), registry.clone());
+
+  let sprint = find_block(&document, "Sprint");
+  let tasks = extract_document_tasks(&document, Some(&sprint)).unwrap();
+  let row_ids = sync.create_rows(&mut database_test, tasks).await.unwrap();
+  assert_eq!(row_ids.len(), 3);
+  assert_eq!(
+    cell_text(&database_test, &row_ids[1], "name").await,
+    "Review the PR"
+  );
+  assert_eq!(cell_text(&database_test, &row_ids[1], "done").await, "Yes");
+  assert_eq!(cell_text(&database_test, &row_ids[0], "done").await, "No");
+
+  let review = find_block(&document, "Review the PR");
+  assert_eq!(
+    cell_text(&database_test, &row_ids[1], "source").await,
+    format!("{}/{}", document_id, review)
+  );
+  let backlinks = registry.backlinks_for(&row_ids[1]);
+  assert_eq!(backlinks.len(), 1);
+  assert_eq!(backlinks[0].source, LinkSource::new(&document_id, &review));
+  assert_eq!(backlinks[0].link_type, BacklinkType::Task);
+
+  // The links are restored from the source cells.
+  let restored_registry = BacklinkRegistry::new();
+  DocumentTaskSync::new(
+    &document_id,
+    &database_id,
+    task_fields(),
+    restored_registry.clone(),
+  )
+  .restore_links(&database_test)
+  .await;
+  assert_eq!(
+    restored_registry.links_from(&document_id),
+    registry.links_from(&document_id)
+  );
+}
+
+#[tokio::test]
+async fn sync_task_checked_state_test() {
+  let database_id = Uuid::new_v4().to_string();
+  let mut database_test = create_task_database(&database_id).await;
+  let mut document = create_document();
+  let document_id = document.object_id().to_string();
+  let registry = BacklinkRegistry::new();
+  let sync = DocumentTaskSync::new(&document_id, &database_id, task_fields(), registry.clone());
+  // A mention in a todo block is kept when the block is linked to its row.
+  let write = find_block(&document, "Write the doc");
+  registry.set_links(
+    LinkSource::new(&document_id, &write),
+    BacklinkType::Mention,
+    ["page"],
+  );
+  let tasks = extract_document_tasks(&document, None).unwrap();
+  let row_ids = sync.create_rows(&mut database_test, tasks).await.unwrap();
+  assert_eq!(registry.backlinks_for("page").len(), 1);
+  assert_eq!(registry.links_from(&document_id).len(), 5);
+  assert!(sync
+    .sync_from_document(&document, &mut database_test)
+    .await
+    .is_empty());
+
+  // Check a todo block in the document.
+  let mut data = document.get_block(&write).unwrap().data;
+  data.insert("checked".to_string(), true.into());
+  document.update_block(&write, data).unwrap();
+  let updated = sync.sync_from_document(&document, &mut database_test).await;
+  assert_eq!(updated, vec![row_ids[0].clone()]);
+  assert_eq!(cell_text(&database_test, &row_ids[0], "done").await, "Yes");
+
+  // Uncheck a row in the database.
+  let mut cell = new_cell_builder(FieldType::Checkbox);
+  cell.insert(CELL_DATA.to_string(), "No".into());
+  database_test
+    .update_row(row_ids[1].clone(), |row| {
+      row.update_cells(|cells| {
+        cells.insert_cell("done", cell);
+      });
+    })
+    .await;
+  let updated = sync
+    .sync_from_database(&database_test, &mut document)
+    .await
+    .unwrap();
+  let review = find_block(&document, "Review the PR");
+  assert_eq!(updated, vec![review.clone()]);
+  assert_eq!(
+    document.get_block(&review).unwrap().data["checked"],
+    serde_json::json!(false)
+  );
+  // The text of the block is kept.
+  assert_eq!(
+    document.get_plain_text_from_block(&review).unwrap(),
+    "Review the PR"
+  );
+}
+
+#[tokio::test]
+async fn subscribe_task_sync_test() {
+  let database_id = Uuid::new_v4().to_string();
+  let mut database_test = create_task_database(&database_id).await;
+  let document = create_document();
+  let document_id = document.object_id().to_string();
+  let registry = BacklinkRegistry::new();
+  let sync = DocumentTaskSync::new(&document_id, &database_id, task_fields(), registry.clone());
+  let tasks = extract_document_tasks(&document, None).unwrap();
+  let row_ids = sync.create_rows(&mut database_test, tasks).await.unwrap();
+  let write = find_block(&document, "Write the doc");
+  let review = find_block(&document, "Review the PR");
+  let document = Arc::new(RwLock::new(document));
+  let database = Arc::new(RwLock::new(database_test.database));
+
+  // Subscribing the document to the registry keeps the task links.
+  document.write().await.subscribe_backlinks(registry.clone());
+  assert_eq!(registry.links_from(&document_id).len(), 4);
+  sync.subscribe(&document, &database).await;
+
+  // Check a todo block in the document.
+  {
+    let mut document = document.write().await;
+    let mut data = document.get_block(&write).unwrap().data;
+    data.insert("checked".to_string(), true.into());
+    document.update_block(&write, data).unwrap();
+  }
+  sleep(Duration::from_millis(300)).await;
+  let row = database.read().await.get_row(&row_ids[0]).await;
+  assert_eq!(TestTextCell::from(row.cells["done"].clone()).0, "Yes");
+
+  // Uncheck a row in the database.
+  let mut cell = new_cell_builder(FieldType::Checkbox);
+  cell.insert(CELL_DATA.to_string(), "No".into());
+  database
+    .write()
+    .await
+    .update_row(row_ids[1].clone(), |row| {
+      row.update_cells(|cells| {
+        cells.insert_cell("done", cell);
+      });
+    })
+    .await;
+  sleep(Duration::from_millis(300)).await;
+  let document = document.read().await;
+  assert_eq!(
+    document.get_block(&review).unwrap().data["checked"],
+    serde_json::json!(false)
+  );
+  assert_eq!(
+    document.get_block(&write).unwrap().data["checked"],
+    serde_json::json!(true)
+  );
+}
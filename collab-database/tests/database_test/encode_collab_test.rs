@@ -1,7 +1,13 @@
-use crate::database_test::helper::create_database_with_default_data;
+use crate::database_test::helper::{
+  create_database_with_db, create_database_with_default_data, restore_database_from_db,
+};
 use assert_json_diff::assert_json_eq;
 use collab::core::origin::CollabOrigin;
 use collab::preclude::Collab;
+use collab_database::entity::{EncodeCursor, EncodedCollabInfo};
+use collab_database::rows::CreateRowParams;
+use collab_entity::CollabType;
+use uuid::Uuid;
 
 #[tokio::test]
 async fn encode_database_collab_test() {
@@ -32,3 +38,121 @@ async fn encode_database_collab_test() {
     assert_json_eq!(json, expected_json);
   }
 }
+
+#[tokio::test]
+async fn encode_streaming_database_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let database_test = create_database_with_default_data(1, &database_id).await;
+
+  let mut sink: Vec<EncodedCollabInfo> = vec![];
+  let cursor = database_test
+    .encode_streaming(EncodeCursor::default(), &mut sink)
+    .await
+    .unwrap();
+
+  let expected_object_ids = database_test
+    .encode_database_collabs()
+    .await
+    .unwrap()
+    .into_collabs()
+    .into_iter()
+    .map(|collab| collab.object_id)
+    .collect::<Vec<_>>();
+  assert_eq!(
+    sink
+      .iter()
+      .map(|collab| collab.object_id.clone())
+      .collect::<Vec<_>>(),
+    expected_object_ids
+  );
+  assert_eq!(sink[0].collab_type, CollabType::Database);
+  assert!(sink[1..]
+    .iter()
+    .all(|collab| collab.collab_type == CollabType::DatabaseRow));
+  assert_eq!(cursor, EncodeCursor::after(&sink[3]));
+}
+
+#[tokio::test]
+async fn resume_encode_streaming_database_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let database_test = create_database_with_default_data(1, &database_id).await;
+  let row_ids = &database_test.pre_define_row_ids;
+
+  // Resume after the database collab
+  let mut sink: Vec<EncodedCollabInfo> = vec![];
+  let cursor = EncodeCursor {
+    last_object_id: Some(database_id.clone()),
+  };
+  database_test
+    .encode_streaming(cursor, &mut sink)
+    .await
+    .unwrap();
+  assert_eq!(sink.len(), 3);
+
+  // Resume after the first row
+  let mut sink: Vec<EncodedCollabInfo> = vec![];
+  let cursor = EncodeCursor {
+    last_object_id: Some(row_ids[0].to_string()),
+  };
+  let cursor = database_test
+    .encode_streaming(cursor, &mut sink)
+    .await
+    .unwrap();
+  assert_eq!(
+    sink
+      .iter()
+      .map(|collab| collab.object_id.clone())
+      .collect::<Vec<_>>(),
+    vec![row_ids[1].to_string(), row_ids[2].to_string()]
+  );
+
+  // Nothing is left after the last row
+  let mut sink: Vec<EncodedCollabInfo> = vec![];
+  let last_cursor = database_test
+    .encode_streaming(cursor.clone(), &mut sink)
+    .await
+    .unwrap();
+  assert!(sink.is_empty());
+  assert_eq!(last_cursor, cursor);
+
+  let cursor = EncodeCursor {
+    last_object_id: Some(Uuid::new_v4().to_string()),
+  };
+  assert!(database_test
+    .encode_streaming(cursor, &mut sink)
+    .await
+    .is_err());
+}
+
+#[tokio::test]
+async fn encode_streaming_does_not_load_rows_test() {
+  let workspace_id = Uuid::new_v4().to_string();
+  let database_id = Uuid::new_v4().to_string();
+  let (db, mut database_test) = create_database_with_db(1, &workspace_id, &database_id).await;
+  let row_1 = CreateRowParams::new(Uuid::new_v4().to_string(), database_id.clone());
+  let row_2 = CreateRowParams::new(Uuid::new_v4().to_string(), database_id.clone());
+  database_test.create_row(row_1.clone()).await.unwrap();
+  database_test.create_row(row_2.clone()).await.unwrap();
+  drop(database_test);
+
+  let database_test = restore_database_from_db(1, &workspace_id, &database_id, db).await;
+  let mut sink: Vec<EncodedCollabInfo> = vec![];
+  database_test
+    .encode_streaming(EncodeCursor::default(), &mut sink)
+    .await
+    .unwrap();
+  assert_eq!(sink.len(), 3);
+  assert!(database_test.get_database_row(&row_1.id).await.is_none());
+  assert!(database_test.get_database_row(&row_2.id).await.is_none());
+
+  let collab = Collab::new_with_source(
+    CollabOrigin::Empty,
+    &row_2.id,
+    sink.pop().unwrap().encoded_collab.into(),
+    vec![],
+    false,
+  )
+  .unwrap();
+  let row_json = collab.to_json_value();
+  assert_eq!(row_json["data"]["id"], row_2.id.to_string());
+}
@@ -0,0 +1,204 @@
+use collab::util::AnyMapExt;
+use collab_database::entity::FieldType;
+use collab_database::fields::select_type_option::{
+  MultiSelectTypeOption, SelectOption, SelectOptionIds, SelectTypeOption,
+};
+use collab_database::fields::text_type_option::RichTextTypeOption;
+use collab_database::fields::{
+  BuiltinFieldTypeConverter, Field, FieldTypeConversion, FieldTypeConversionSummary,
+  FieldTypeConverter, TypeOptionData,
+};
+use collab_database::rows::{new_cell_builder, Cell, CreateRowParams, RowId};
+use collab_database::template::entity::CELL_DATA;
+use collab_database::views::OrderObjectPosition;
+
+use crate::database_test::helper::{
+  create_database, default_field_settings_by_layout, DatabaseTest,
+};
+
+fn text_cell(text: &str) -> Cell {
+  let mut cell = new_cell_builder(FieldType::RichText);
+  cell.insert(CELL_DATA.into(), text.into());
+  cell
+}
+
+/// Create a database with a field of the type and a row for each cell.
+async fn create_conversion_database(
+  field_type: FieldType,
+  type_option: TypeOptionData,
+  cells: Vec<Cell>,
+) -> (DatabaseTest, Vec<RowId>) {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database(1, &database_id);
+  let field = Field::new(
+    "f".to_string(),
+    "F".to_string(),
+    field_type.clone().into(),
+    false,
+  )
+  .with_type_option_data(field_type.type_id(), type_option);
+  database_test.create_field(
+    None,
+    field,
+    &OrderObjectPosition::default(),
+    default_field_settings_by_layout(),
+  );
+  let mut row_ids = vec![];
+  for cell in cells {
+    let row_id = RowId::from(uuid::Uuid::new_v4().to_string());
+    let row = CreateRowParams::new(row_id.clone(), database_id.clone())
+      .with_cells([("f".to_string(), cell)].into_iter().collect());
+    database_test.create_row(row).await.unwrap();
+    row_ids.push(row_id);
+  }
+  (database_test, row_ids)
+}
+
+async fn cell_data(database_test: &DatabaseTest, row_id: &RowId) -> Option<String> {
+  let cell = database_test.get_cell("f", row_id).await.cell?;
+  cell.get_as::<String>(CELL_DATA)
+}
+
+#[tokio::test]
+async fn switch_text_field_to_number_test() {
+  let cells = vec![text_cell("12.5"), text_cell("$1,000"), text_cell("abc")];
+  let (mut database_test, row_ids) =
+    create_conversion_database(FieldType::RichText, RichTextTypeOption.into(), cells).await;
+  let summary = database_test
+    .switch_field_type("f", FieldType::Number, &BuiltinFieldTypeConverter)
+    .await
+    .unwrap();
+  assert_eq!(
+    summary,
+    FieldTypeConversionSummary {
+      converted: 2,
+      cleared: 1,
+      skipped: 0,
+    }
+  );
+
+  let field = database_test.get_field("f").unwrap();
+  assert_eq!(FieldType::from(field.field_type), FieldType::Number);
+  assert!(field
+    .get_any_type_option(FieldType::Number.type_id())
+    .is_some());
+  assert_eq!(
+    cell_data(&database_test, &row_ids[0]).await.as_deref(),
+    Some("12.5")
+  );
+  assert_eq!(
+    cell_data(&database_test, &row_ids[1]).await.as_deref(),
+    Some("1000")
+  );
+  assert_eq!(cell_data(&database_test, &row_ids[2]).await, None);
+
+  // Switching to the same type does nothing.
+  let summary = database_test
+    .switch_field_type("f", FieldType::Number, &BuiltinFieldTypeConverter)
+    .await
+    .unwrap();
+  assert_eq!(summary, FieldTypeConversionSummary::default());
+}
+
+#[tokio::test]
+async fn switch_select_field_to_text_and_back_test() {
+  let options = vec![SelectOption::new("Rust"), SelectOption::new("Dart")];
+  let type_option = MultiSelectTypeOption(SelectTypeOption::new(options.clone(), false));
+  let ids = options
+    .iter()
+    .map(|option| option.id.clone())
+    .collect::<Vec<_>>();
+  let cells = vec![
+    SelectOptionIds::from(ids.clone()).to_cell_data(FieldType::MultiSelect),
+    SelectOptionIds::from(vec![ids[1].clone()]).to_cell_data(FieldType::MultiSelect),
+  ];
+  let (mut database_test, row_ids) =
+    create_conversion_database(FieldType::MultiSelect, type_option.into(), cells).await;
+
+  database_test
+    .switch_field_type("f", FieldType::RichText, &BuiltinFieldTypeConverter)
+    .await
+    .unwrap();
+  assert_eq!(
+    cell_data(&database_test, &row_ids[0]).await.as_deref(),
+    Some("Rust, Dart")
+  );
+  database_test
+    .update_row(row_ids[1].clone(), |row| {
+      row.update_cells(|cells| {
+        cells.insert_cell("f", text_cell("dart, Go"));
+      });
+    })
+    .await;
+
+  // The options of the field are kept, and the new names become options.
+  database_test
+    .switch_field_type("f", FieldType::MultiSelect, &BuiltinFieldTypeConverter)
+    .await
+    .unwrap();
+  let field = database_test.get_field("f").unwrap();
+  let type_option = field
+    .get_type_option::<MultiSelectTypeOption>(FieldType::MultiSelect.type_id())
+    .unwrap();
+  let names = type_option
+    .options
+    .iter()
+    .map(|option| option.name.as_str())
+    .collect::<Vec<_>>();
+  assert_eq!(names, vec!["Rust", "Dart", "Go"]);
+  assert_eq!(
+    cell_data(&database_test, &row_ids[0]).await,
+    Some(ids.join(","))
+  );
+  let go_id = type_option.options[2].id.clone();
+  assert_eq!(
+    cell_data(&database_test, &row_ids[1]).await,
+    Some(format!("{},{}", ids[1], go_id))
+  );
+}
+
+/// Turns a checkbox into a text, and leaves the other conversions to the built-in converter.
+struct EmojiConverter;
+
+impl FieldTypeConverter for EmojiConverter {
+  fn convert_cell(&self, conversion: &mut FieldTypeConversion, cell: &Cell) -> Option<Cell> {
+    if conversion.old_type == FieldType::Checkbox && conversion.new_type == FieldType::RichText {
+      let checked = cell.get_as::<String>(CELL_DATA).as_deref() == Some("Yes");
+      return Some(text_cell(if checked { "✅" } else { "❌" }));
+    }
+    BuiltinFieldTypeConverter.convert_cell(conversion, cell)
+  }
+}
+
+#[tokio::test]
+async fn switch_field_type_with_custom_converter_test() {
+  let cells = vec![text_cell("yes"), text_cell("0"), text_cell("")];
+  let (mut database_test, row_ids) =
+    create_conversion_database(FieldType::RichText, RichTextTypeOption.into(), cells).await;
+  let summary = database_test
+    .switch_field_type("f", FieldType::Checkbox, &EmojiConverter)
+    .await
+    .unwrap();
+  assert_eq!(summary.converted, 2);
+  assert_eq!(
+    cell_data(&database_test, &row_ids[0]).await.as_deref(),
+    Some("Yes")
+  );
+  assert_eq!(
+    cell_data(&database_test, &row_ids[1]).await.as_deref(),
+    Some("No")
+  );
+
+  database_test
+    .switch_field_type("f", FieldType::RichText, &EmojiConverter)
+    .await
+    .unwrap();
+  assert_eq!(
+    cell_data(&database_test, &row_ids[0]).await.as_deref(),
+    Some("✅")
+  );
+  assert_eq!(
+    cell_data(&database_test, &row_ids[1]).await.as_deref(),
+    Some("❌")
+  );
+}
@@ -1,7 +1,9 @@
 use crate::database_test::helper::{
   create_database, create_database_with_default_data, default_field_settings_by_layout,
+  DatabaseTest,
 };
 use collab_database::entity::CreateViewParams;
+use collab_database::views::DatabaseViewChange;
 use collab_database::{fields::Field, views::OrderObjectPosition};
 
 #[tokio::test]
@@ -257,6 +259,73 @@ async fn move_field_test() {
   assert_eq!(view_2.field_orders[2].id, "f2");
 }
 
+#[tokio::test]
+async fn move_field_in_all_views_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database(1, &database_id);
+  let params = CreateViewParams {
+    database_id: database_id.clone(),
+    view_id: "v2".to_string(),
+    ..Default::default()
+  };
+  database_test.create_linked_view(params).unwrap();
+  for i in 0..3 {
+    database_test.create_field(
+      None,
+      Field::new(format!("f{}", i), format!("text field {}", i), 0, true),
+      &OrderObjectPosition::default(),
+      default_field_settings_by_layout(),
+    );
+  }
+  let field_ids = |database_test: &DatabaseTest, view_id: &str| {
+    database_test
+      .get_view(view_id)
+      .unwrap()
+      .field_orders
+      .into_iter()
+      .map(|order| order.id)
+      .collect::<Vec<_>>()
+  };
+
+  let mut view_change_rx = database_test.subscribe_view_change().unwrap();
+  let view_ids = database_test.move_field("f2", OrderObjectPosition::Start, None);
+  // The linked views and the inline view.
+  assert_eq!(view_ids.len(), 3);
+  assert!(view_ids.contains(&"v1".to_string()) && view_ids.contains(&"v2".to_string()));
+  assert_eq!(field_ids(&database_test, "v1"), vec!["f2", "f0", "f1"]);
+  assert_eq!(field_ids(&database_test, "v2"), vec!["f2", "f0", "f1"]);
+
+  let mut move_events = vec![];
+  while let Ok(change) = view_change_rx.try_recv() {
+    if let DatabaseViewChange::DidMoveFieldOrder {
+      field_id, view_ids, ..
+    } = change
+    {
+      move_events.push((field_id, view_ids));
+    }
+  }
+  // The observer sends a single change for all the views, including the remote moves.
+  let mut view_ids = view_ids;
+  view_ids.sort();
+  assert_eq!(move_events, vec![("f2".to_string(), view_ids)]);
+
+  let view_ids = database_test.move_field(
+    "f2",
+    OrderObjectPosition::After("f1".to_string()),
+    Some("v2"),
+  );
+  assert_eq!(view_ids, vec!["v2".to_string()]);
+  assert_eq!(field_ids(&database_test, "v1"), vec!["f2", "f0", "f1"]);
+  assert_eq!(field_ids(&database_test, "v2"), vec!["f0", "f1", "f2"]);
+
+  let view_ids =
+    database_test.move_field("f2", OrderObjectPosition::Before("f2".to_string()), None);
+  assert!(view_ids.is_empty());
+  assert!(database_test
+    .move_field("f10", OrderObjectPosition::Start, None)
+    .is_empty());
+}
+
 #[tokio::test]
 async fn move_field_to_out_of_index_test() {
   let database_id = uuid::Uuid::new_v4().to_string();
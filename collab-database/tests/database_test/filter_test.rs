@@ -1,3 +1,7 @@
+use collab::preclude::Any;
+use collab_database::entity::CreateViewParams;
+use collab_database::views::{DatabaseLayout, FilterMap, FilterNode, FilterType, FILTER_TYPE};
+
 use crate::database_test::helper::{create_database_with_default_data, DatabaseTest};
 use crate::helper::{TestFieldType, TestFilter, FILTER_CONTENT};
 
@@ -76,6 +80,155 @@ async fn remove_database_view_filter_test() {
   assert!(filter_1.is_none());
 }
 
+#[tokio::test]
+async fn insert_nested_filter_group_test() {
+  let mut database_test =
+    create_database_with_default_data(1, &uuid::Uuid::new_v4().to_string()).await;
+  // (status = done OR archived) AND assignee = me
+  let tree = FilterNode::and(
+    "root",
+    vec![
+      FilterNode::or(
+        "status_group",
+        vec![
+          FilterNode::data(test_filter("done")),
+          FilterNode::data(test_filter("archived")),
+        ],
+      ),
+      FilterNode::data(test_filter("me")),
+    ],
+  );
+  database_test.insert_filter("v1", tree.clone());
+
+  let filter = database_test
+    .get_filter::<FilterNode>("v1", "root")
+    .unwrap();
+  assert_eq!(filter, tree);
+  assert_eq!(filter.children()[0].filter_type(), FilterType::Or);
+
+  let matches = |contents: &[&str]| {
+    filter.evaluate(&|condition: &FilterMap| {
+      let condition = TestFilter::try_from(condition.clone()).unwrap();
+      contents.contains(&condition.content.as_str())
+    })
+  };
+  assert!(matches(&["done", "me"]));
+  assert!(matches(&["archived", "me"]));
+  assert!(!matches(&["done", "archived"]));
+  assert!(!matches(&["me"]));
+}
+
+#[tokio::test]
+async fn update_nested_filter_group_test() {
+  let mut database_test =
+    create_database_with_default_data(1, &uuid::Uuid::new_v4().to_string()).await;
+  let tree = FilterNode::or("group", vec![FilterNode::data(test_filter("done"))]);
+  database_test.insert_filter("v1", tree);
+
+  let mut filter = database_test
+    .get_filter::<FilterNode>("v1", "group")
+    .unwrap();
+  let removed = filter.remove("done").unwrap();
+  assert_eq!(removed.id(), Some("done"));
+  let filter = filter.with_child(FilterNode::and(
+    "nested",
+    vec![FilterNode::data(test_filter("me"))],
+  ));
+  database_test.insert_filter("v1", filter);
+
+  let filter = database_test
+    .get_filter::<FilterNode>("v1", "group")
+    .unwrap();
+  assert_eq!(filter.children().len(), 1);
+  assert!(filter.find("done").is_none());
+  assert_eq!(
+    filter.find("me").map(|node| node.filter_type()),
+    Some(FilterType::Data)
+  );
+}
+
+#[tokio::test]
+async fn update_filter_node_test() {
+  let mut database_test =
+    create_database_with_default_data(1, &uuid::Uuid::new_v4().to_string()).await;
+  let tree = FilterNode::and(
+    "root",
+    vec![FilterNode::or(
+      "status_group",
+      vec![FilterNode::data(test_filter("done"))],
+    )],
+  );
+  database_test.insert_filter("v1", tree);
+
+  database_test.update_filter_node("v1", "done", |filter| {
+    filter.insert(FILTER_CONTENT.into(), "archived".into());
+  });
+  database_test.insert_filter_node("v1", "status_group", FilterNode::data(test_filter("me")));
+  // A condition can't hold children.
+  database_test.insert_filter_node("v1", "done", FilterNode::data(test_filter("other")));
+  database_test.update_filter_node("v1", "status_group", |filter| {
+    filter.insert(FILTER_TYPE.into(), Any::BigInt(FilterType::And as i64));
+  });
+
+  let filter = database_test
+    .get_filter::<FilterNode>("v1", "root")
+    .unwrap();
+  let group = filter.find("status_group").unwrap();
+  assert_eq!(group.filter_type(), FilterType::And);
+  assert_eq!(group.children().len(), 2);
+  assert!(filter.find("other").is_none());
+  let FilterNode::Data(done) = filter.find("done").unwrap() else {
+    panic!("done is a condition");
+  };
+  let done = TestFilter::try_from(done.clone()).unwrap();
+  assert_eq!(done.content, "archived");
+
+  database_test.remove_filter_node("v1", "me");
+  let filter = database_test
+    .get_filter::<FilterNode>("v1", "root")
+    .unwrap();
+  assert!(filter.find("me").is_none());
+  assert_eq!(filter.find("status_group").unwrap().children().len(), 1);
+}
+
+#[tokio::test]
+async fn create_database_view_with_nested_filter_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database_with_default_data(1, &database_id).await;
+  let view = collab_database::entity::DatabaseView::default()
+    .with_filter(FilterNode::or(
+      "group",
+      vec![
+        FilterNode::data(test_filter("done")),
+        FilterNode::data(test_filter("archived")),
+      ],
+    ))
+    .with_filter(FilterNode::data(test_filter("me")));
+  let params = CreateViewParams {
+    database_id,
+    view_id: "v2".to_string(),
+    name: "nested filters".to_string(),
+    filters: view.filters.clone(),
+    layout: DatabaseLayout::Grid,
+    ..Default::default()
+  };
+  database_test.create_linked_view(params).unwrap();
+
+  let filters = database_test.get_view("v2").unwrap().filter_nodes();
+  assert_eq!(filters, view.filter_nodes());
+  assert_eq!(filters[0].children().len(), 2);
+}
+
+fn test_filter(content: &str) -> TestFilter {
+  TestFilter {
+    id: content.to_string(),
+    field_id: "f1".to_string(),
+    field_type: TestFieldType::RichText,
+    condition: 0,
+    content: content.to_string(),
+  }
+}
+
 async fn create_database_with_two_filters() -> DatabaseTest {
   let database_id = uuid::Uuid::new_v4();
   let mut database_test = create_database_with_default_data(1, &database_id.to_string()).await;
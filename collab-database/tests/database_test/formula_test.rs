@@ -0,0 +1,171 @@
+use collab::util::AnyMapExt;
+use collab_database::entity::FieldType;
+use collab_database::fields::formula_type_option::{
+  FormulaError, FormulaTypeOption, FORMULA_ERROR, MAX_FORMULA_DEPTH,
+};
+use collab_database::fields::Field;
+use collab_database::rows::{Cells, CreateRowParams};
+use collab_database::template::entity::CELL_DATA;
+use collab_database::views::OrderObjectPosition;
+use std::collections::HashSet;
+
+use crate::database_test::helper::{create_database, default_field_settings_by_layout};
+use crate::helper::TestTextCell;
+
+fn cells(values: &[(&str, &str)]) -> Cells {
+  values
+    .iter()
+    .map(|(field_id, value)| (field_id.to_string(), TestTextCell::from(*value).into()))
+    .collect()
+}
+
+#[test]
+fn evaluate_formula_test() {
+  let cells = cells(&[("price", "2.5"), ("quantity", "4"), ("done", "Yes")]);
+  let evaluate = |expression: &str| FormulaTypeOption::new(expression).evaluate(&cells);
+  assert_eq!(evaluate("{price} * {quantity}"), Ok(10.0));
+  assert_eq!(evaluate("1 + 2 * 3"), Ok(7.0));
+  assert_eq!(evaluate("(1 + 2) * 3"), Ok(9.0));
+  assert_eq!(evaluate("-{price} + {done}"), Ok(-1.5));
+  assert_eq!(evaluate("{missing} + 1"), Ok(1.0));
+  assert_eq!(evaluate("round({price} / 3, 2)"), Ok(0.83));
+  assert_eq!(evaluate("max({price}, {quantity}, 3)"), Ok(4.0));
+  assert_eq!(evaluate("abs(min(-2, 1))"), Ok(2.0));
+  assert_eq!(evaluate("{price} / 0"), Err(FormulaError::DivisionByZero));
+  assert_eq!(
+    evaluate("sqrt(4)"),
+    Err(FormulaError::UnknownFunction("sqrt".to_string()))
+  );
+  assert!(matches!(
+    evaluate("{price} *"),
+    Err(FormulaError::Parse { .. })
+  ));
+  assert!(matches!(
+    evaluate("(1 + 2"),
+    Err(FormulaError::Parse { .. })
+  ));
+
+  let text_cells = self::cells(&[("name", "apple")]);
+  assert_eq!(
+    FormulaTypeOption::new("{name} + 1").evaluate(&text_cells),
+    Err(FormulaError::NotANumber("name".to_string()))
+  );
+
+  let expr = FormulaTypeOption::new("{price} * {quantity} + {price}")
+    .parse()
+    .unwrap();
+  assert_eq!(
+    expr.referenced_fields(),
+    HashSet::from(["price".to_string(), "quantity".to_string()])
+  );
+}
+
+#[test]
+fn reject_deeply_nested_formula_test() {
+  let cells = Cells::new();
+  let nested = |depth: usize| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+  let evaluate = |expression: &str| FormulaTypeOption::new(expression).evaluate(&cells);
+  assert_eq!(evaluate(&nested(MAX_FORMULA_DEPTH)), Ok(1.0));
+  assert!(matches!(
+    evaluate(&nested(100_000)),
+    Err(FormulaError::Parse { .. })
+  ));
+  assert!(matches!(
+    evaluate(&format!("{}1", "-".repeat(100_000))),
+    Err(FormulaError::Parse { .. })
+  ));
+  assert!(matches!(
+    evaluate(&vec!["1"; 100_000].join(" + ")),
+    Err(FormulaError::Parse { .. })
+  ));
+  assert_eq!(
+    evaluate(&vec!["1"; MAX_FORMULA_DEPTH].join(" + ")),
+    Ok(128.0)
+  );
+}
+
+#[tokio::test]
+async fn compute_formula_cell_on_read_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database(1, &database_id);
+  for field_id in ["price", "quantity"] {
+    database_test.create_field(
+      None,
+      Field::new(
+        field_id.to_string(),
+        field_id.to_string(),
+        FieldType::Number.into(),
+        false,
+      ),
+      &OrderObjectPosition::default(),
+      default_field_settings_by_layout(),
+    );
+  }
+  let total_field = Field::new(
+    "total".to_string(),
+    "Total".to_string(),
+    FieldType::Formula.into(),
+    false,
+  )
+  .with_type_option_data(
+    FieldType::Formula.type_id(),
+    FormulaTypeOption::new("{price} * {quantity}").into(),
+  );
+  database_test.create_field(
+    None,
+    total_field,
+    &OrderObjectPosition::default(),
+    default_field_settings_by_layout(),
+  );
+
+  let row_id = uuid::Uuid::new_v4().to_string();
+  let row = CreateRowParams::new(row_id.clone(), database_id.clone())
+    .with_cells(cells(&[("price", "0.1"), ("quantity", "3")]));
+  database_test.create_row(row).await.unwrap();
+
+  let cell = database_test
+    .get_cell("total", &row_id.clone().into())
+    .await
+    .cell
+    .unwrap();
+  assert_eq!(cell.get_as::<String>(CELL_DATA).unwrap(), "0.3");
+  // The formula is computed by every read path.
+  let row = database_test.get_row(&row_id.clone().into()).await;
+  assert_eq!(
+    row.cells["total"].get_as::<String>(CELL_DATA).unwrap(),
+    "0.3"
+  );
+  let cells = database_test.get_cells_for_field("v1", "total").await;
+  assert_eq!(
+    cells[0]
+      .cell
+      .as_ref()
+      .unwrap()
+      .get_as::<String>(CELL_DATA)
+      .unwrap(),
+    "0.3"
+  );
+  let rows = database_test.get_rows_for_view("v1").await;
+  assert_eq!(
+    rows[0].cells["total"].get_as::<String>(CELL_DATA).unwrap(),
+    "0.3"
+  );
+
+  database_test
+    .update_row(row_id.clone().into(), |row| {
+      row.update_cells(|cells| {
+        cells.insert("quantity", TestTextCell::from("ten"));
+      });
+    })
+    .await;
+  let cell = database_test
+    .get_cell("total", &row_id.into())
+    .await
+    .cell
+    .unwrap();
+  assert_eq!(cell.get_as::<String>(CELL_DATA).unwrap(), "");
+  assert_eq!(
+    cell.get_as::<String>(FORMULA_ERROR).unwrap(),
+    "The cell of quantity is not a number"
+  );
+}
@@ -0,0 +1,34 @@
+use collab::test_utils::assert_json_eq_ignoring;
+use collab_database::entity::FieldType;
+use collab_database::test_utils::DatabaseGenerator;
+
+#[tokio::test]
+async fn generate_database_test() {
+  let generator = DatabaseGenerator::new(7)
+    .with_fields(6)
+    .with_rows(30)
+    .with_views(3);
+  let database = generator.build().await.unwrap();
+
+  let fields = database.get_fields_in_view(&database.get_inline_view_id(), None);
+  assert_eq!(fields.len(), 6);
+  assert!(fields[0].is_primary);
+  assert_eq!(FieldType::from(fields[0].field_type), FieldType::RichText);
+  assert_eq!(database.get_all_views().len(), 3);
+  assert_eq!(database.get_all_row_orders().await.len(), 30);
+}
+
+#[tokio::test]
+async fn generate_same_database_with_same_seed_test() {
+  let database_1 = DatabaseGenerator::new(7).build().await.unwrap();
+  let database_2 = DatabaseGenerator::new(7).build().await.unwrap();
+  assert_json_eq_ignoring(
+    &database_1.to_json_value().await,
+    &database_2.to_json_value().await,
+    &["created_at", "modified_at", "last_modified"],
+  );
+
+  let params_1 = DatabaseGenerator::new(7).generate_params();
+  let params_2 = DatabaseGenerator::new(8).generate_params();
+  assert_ne!(params_1.database_id, params_2.database_id);
+}
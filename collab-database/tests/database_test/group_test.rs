@@ -1,7 +1,12 @@
 use collab::preclude::Any;
 use collab::util::{AnyExt, AnyMapExt};
 use collab_database::entity::CreateViewParams;
-use collab_database::views::{DatabaseLayout, GroupMap};
+use collab_database::fields::date_type_option::DateCellData;
+use collab_database::rows::{Cell, Row};
+use collab_database::views::{
+  DatabaseLayout, DateBucketStrategy, DateGroupRule, GroupMap, GroupSettingMap, WeekStart,
+  NO_DATE_BUCKET_ID,
+};
 
 use crate::database_test::helper::{create_database_with_default_data, DatabaseTest};
 use crate::helper::{TestGroup, TestGroupSetting, CONTENT, GROUPS};
@@ -193,6 +198,135 @@ async fn remove_database_view_group_test() {
   assert_eq!(group_settings[0].groups[0].id, "group_item2");
 }
 
+// Wednesday 2024-05-15 10:00 UTC
+const NOW: i64 = 1715767200;
+const DAY: i64 = 86400;
+
+#[test]
+fn date_bucket_test() {
+  let day = DateGroupRule::new(DateBucketStrategy::Day).bucket(NOW, NOW);
+  assert_eq!(day.id, "day:2024-05-15");
+  assert_eq!(day.start, Some(1715731200));
+  assert_eq!(day.end, Some(1715731200 + DAY));
+
+  let rule = DateGroupRule::new(DateBucketStrategy::Week);
+  assert_eq!(rule.bucket(NOW, NOW).id, "week:2024-05-13");
+  let rule = rule.with_week_start(WeekStart::Sunday);
+  assert_eq!(rule.bucket(NOW, NOW).id, "week:2024-05-12");
+
+  let month = DateGroupRule::new(DateBucketStrategy::Month).bucket(NOW, NOW);
+  assert_eq!(month.id, "month:2024-05");
+  assert_eq!(month.end, Some(1717200000));
+
+  let quarter = DateGroupRule::new(DateBucketStrategy::Quarter).bucket(NOW, NOW);
+  assert_eq!(quarter.id, "quarter:2024-Q2");
+  assert_eq!(quarter.start, Some(1711929600));
+
+  let year = DateGroupRule::new(DateBucketStrategy::Year).bucket(NOW, NOW);
+  assert_eq!(year.id, "year:2024");
+
+  // 23:30 UTC is already the next day one hour east of UTC.
+  let rule = DateGroupRule::new(DateBucketStrategy::Day).with_utc_offset(3600);
+  let bucket = rule.bucket(1715815800, NOW);
+  assert_eq!(bucket.id, "day:2024-05-16");
+  assert_eq!(bucket.start, Some(1715814000));
+}
+
+#[test]
+fn date_bucket_across_daylight_saving_time_test() {
+  // 2024-04-01 22:30 UTC is already 2024-04-02 in Paris, two hours east of UTC in the summer.
+  let rule = DateGroupRule::new(DateBucketStrategy::Day).with_timezone(chrono_tz::Europe::Paris);
+  let bucket = rule.bucket(1712010600, NOW);
+  assert_eq!(bucket.id, "day:2024-04-02");
+  assert_eq!(bucket.start, Some(1712008800));
+
+  // The week of the change to the summer time starts one hour east of UTC and ends two hours
+  // east of UTC.
+  let rule = DateGroupRule::new(DateBucketStrategy::Week).with_timezone(chrono_tz::Europe::Paris);
+  let bucket = rule.bucket(1711929600 - DAY, NOW);
+  assert_eq!(bucket.id, "week:2024-03-25");
+  assert_eq!(bucket.start, Some(1711321200));
+  assert_eq!(bucket.end, Some(1711922400));
+}
+
+#[test]
+fn relative_date_bucket_test() {
+  let rule = DateGroupRule::new(DateBucketStrategy::Relative);
+  let bucket_id = |timestamp: i64| rule.bucket(timestamp, NOW).id;
+  assert_eq!(bucket_id(NOW + 3600), "today");
+  assert_eq!(bucket_id(NOW - DAY), "yesterday");
+  assert_eq!(bucket_id(NOW + DAY), "tomorrow");
+  assert_eq!(bucket_id(NOW - 2 * DAY), "this_week");
+  assert_eq!(bucket_id(NOW - 7 * DAY), "last_week");
+  assert_eq!(bucket_id(NOW + 6 * DAY), "next_week");
+  assert_eq!(bucket_id(NOW - 14 * DAY), "earlier");
+  assert_eq!(bucket_id(NOW + 17 * DAY), "later");
+  assert_eq!(rule.bucket(NOW - 14 * DAY, NOW).start, None);
+}
+
+#[test]
+fn group_rows_by_date_test() {
+  let date_row = |id: &str, timestamp: Option<i64>| {
+    let mut row = Row::new(id.to_string(), "d1");
+    if let Some(timestamp) = timestamp {
+      let cell = Cell::from(&DateCellData::from_timestamp(timestamp));
+      row.cells.insert("date".to_string(), cell);
+    }
+    row
+  };
+  let rows = vec![
+    date_row("r1", Some(NOW + 40 * DAY)),
+    date_row("r2", Some(NOW)),
+    date_row("r3", None),
+    date_row("r4", Some(NOW - 3600)),
+  ];
+  let groups = DateGroupRule::new(DateBucketStrategy::Month).group_rows(&rows, "date", NOW);
+  let groups = groups
+    .iter()
+    .map(|group| {
+      let row_ids = group
+        .row_ids
+        .iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>();
+      (group.bucket.id.as_str(), row_ids)
+    })
+    .collect::<Vec<_>>();
+  assert_eq!(
+    groups,
+    vec![
+      (NO_DATE_BUCKET_ID, vec!["r3".to_string()]),
+      ("month:2024-05", vec!["r2".to_string(), "r4".to_string()]),
+      ("month:2024-06", vec!["r1".to_string()]),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn date_group_rule_in_group_setting_test() {
+  let database_id = uuid::Uuid::new_v4();
+  let mut database_test = create_database_with_default_data(1, &database_id.to_string()).await;
+  let rule = DateGroupRule::new(DateBucketStrategy::Week)
+    .with_week_start(WeekStart::Sunday)
+    .with_utc_offset(-5 * 3600)
+    .with_timezone(chrono_tz::America::New_York);
+  let mut setting = GroupSettingMap::from([
+    ("id".to_string(), Any::from("g1")),
+    ("field_id".to_string(), Any::from("f3")),
+  ]);
+  rule.fill_group_setting(&mut setting);
+  database_test.insert_group_setting("v1", setting);
+
+  let settings = database_test.get_all_group_setting::<GroupSettingMap>("v1");
+  assert_eq!(settings.len(), 1);
+  assert_eq!(DateGroupRule::from_group_setting(&settings[0]), Some(rule));
+
+  let settings = create_database_with_two_groups()
+    .await
+    .get_all_group_setting::<GroupSettingMap>("v1");
+  assert_eq!(DateGroupRule::from_group_setting(&settings[0]), None);
+}
+
 async fn create_database_with_two_groups() -> DatabaseTest {
   let database_id = uuid::Uuid::new_v4();
   let mut database_test = create_database_with_default_data(1, &database_id.to_string()).await;
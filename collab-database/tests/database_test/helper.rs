@@ -1,5 +1,6 @@
 use collab::core::collab::DataSource;
 use collab::preclude::{uuid_v4, CollabBuilder};
+use collab_database::blocks::DEFAULT_BLOCK_CAPACITY;
 use collab_database::database::{Database, DatabaseContext};
 use collab_database::fields::Field;
 use collab_database::rows::{Cells, CreateRowParams, DatabaseRow, Row, RowId};
@@ -57,6 +58,15 @@ impl DerefMut for DatabaseTest {
 
 /// Create a database with a single view.
 pub fn create_database(uid: i64, database_id: &str) -> DatabaseTest {
+  create_database_with_block_capacity(uid, database_id, DEFAULT_BLOCK_CAPACITY)
+}
+
+/// Create a database with a single view, whose blocks hold at most `block_capacity` rows.
+pub fn create_database_with_block_capacity(
+  uid: i64,
+  database_id: &str,
+  block_capacity: usize,
+) -> DatabaseTest {
   let workspace_id = Uuid::new_v4().to_string();
   setup_log();
   let collab_db = make_rocks_db();
@@ -66,7 +76,7 @@ pub fn create_database(uid: i64, database_id: &str) -> DatabaseTest {
     db: collab_db.clone(),
   });
 
-  let context = DatabaseContext::new(collab_service);
+  let context = DatabaseContext::new(collab_service).with_block_capacity(block_capacity);
   let params = CreateDatabaseParams {
     database_id: database_id.to_string(),
     views: vec![CreateViewParams {
@@ -115,6 +125,16 @@ pub async fn create_database_with_db(
   uid: i64,
   workspace_id: &str,
   database_id: &str,
+) -> (Arc<CollabKVDB>, DatabaseTest) {
+  create_database_with_db_and_block_capacity(uid, workspace_id, database_id, DEFAULT_BLOCK_CAPACITY)
+    .await
+}
+
+pub async fn create_database_with_db_and_block_capacity(
+  uid: i64,
+  workspace_id: &str,
+  database_id: &str,
+  block_capacity: usize,
 ) -> (Arc<CollabKVDB>, DatabaseTest) {
   setup_log();
   let collab_db = make_rocks_db();
@@ -123,7 +143,7 @@ pub async fn create_database_with_db(
     workspace_id: workspace_id.to_string(),
     db: collab_db.clone(),
   });
-  let context = DatabaseContext::new(collab_service);
+  let context = DatabaseContext::new(collab_service).with_block_capacity(block_capacity);
   let params = CreateDatabaseParams {
     database_id: database_id.to_string(),
     views: vec![CreateViewParams {
@@ -0,0 +1,288 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use collab::core::clock::ManualClock;
+use collab::core::collab_webhook::WebhookEvent;
+use collab::core::origin::CollabOrigin;
+use collab::preclude::Transact;
+use collab_database::database::{Database, DatabaseContext};
+use collab_database::database_journal::{
+  DatabaseJournal, JournalEntry, JournalOperation, JournalOrigin, ViewSetting,
+};
+use collab_database::entity::{CreateDatabaseParams, CreateViewParams};
+use collab_database::fields::Field;
+use collab_database::rows::{CreateRowParams, RowId};
+use collab_database::views::{DatabaseLayout, OrderObjectPosition};
+use collab_database::workspace_database::NoPersistenceDatabaseCollabService;
+use serde_json::json;
+
+use crate::database_test::helper::default_field_settings_by_layout;
+use crate::helper::{TestFilter, TestTextCell};
+
+const NOW: i64 = 1_700_000_000;
+
+async fn create_database_with_clock(clock: Arc<ManualClock>) -> Database {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let context =
+    DatabaseContext::new(Arc::new(NoPersistenceDatabaseCollabService)).with_clock(clock);
+  let params = CreateDatabaseParams {
+    database_id: database_id.clone(),
+    views: vec![CreateViewParams {
+      database_id,
+      view_id: "v1".to_string(),
+      ..Default::default()
+    }],
+    ..Default::default()
+  };
+  Database::create_with_view(params, context).await.unwrap()
+}
+
+/// Wait until the journal recorded the operation and return its origin.
+async fn wait_for_operation(
+  journal: &DatabaseJournal,
+  operation: &JournalOperation,
+) -> JournalOrigin {
+  wait_for_entry(journal, operation).await.origin
+}
+
+async fn wait_for_entry(journal: &DatabaseJournal, operation: &JournalOperation) -> JournalEntry {
+  for _ in 0..50 {
+    let entry = journal
+      .entries()
+      .into_iter()
+      .find(|entry| &entry.operation == operation);
+    if let Some(entry) = entry {
+      return entry;
+    }
+    tokio::time::sleep(Duration::from_millis(20)).await;
+  }
+  panic!("{:?} is not recorded", operation);
+}
+
+#[tokio::test]
+async fn journal_records_database_operations_test() {
+  let clock = Arc::new(ManualClock::new(NOW));
+  let mut database = create_database_with_clock(clock.clone()).await;
+  let journal = database.start_journal(100).unwrap();
+
+  database.create_field(
+    None,
+    Field::new("f1".to_string(), "text".to_string(), 0, false),
+    &OrderObjectPosition::default(),
+    default_field_settings_by_layout(),
+  );
+  let row_id = RowId::from(uuid::Uuid::new_v4().to_string());
+  database
+    .create_row(CreateRowParams::new(
+      row_id.clone(),
+      database.get_database_id(),
+    ))
+    .await
+    .unwrap();
+  let origin = wait_for_operation(
+    &journal,
+    &JournalOperation::RowCreated {
+      row_id: row_id.clone(),
+    },
+  )
+  .await;
+  assert_eq!(origin, JournalOrigin::Local);
+  database
+    .update_row(row_id.clone(), |row| {
+      row.update_cells(|cells| {
+        cells.insert("f1", TestTextCell::from("hello"));
+      });
+    })
+    .await;
+  wait_for_operation(
+    &journal,
+    &JournalOperation::CellUpdated {
+      row_id: row_id.clone(),
+      field_id: "f1".to_string(),
+    },
+  )
+  .await;
+
+  clock.advance(60);
+  database.insert_filter(
+    "v1",
+    TestFilter {
+      id: "filter".to_string(),
+      field_id: "f1".to_string(),
+      field_type: Default::default(),
+      condition: 0,
+      content: "hello".to_string(),
+    },
+  );
+  database.delete_field("f1");
+  database.remove_row(&row_id).await;
+  for operation in [
+    JournalOperation::FieldCreated {
+      field_id: "f1".to_string(),
+    },
+    JournalOperation::ViewSettingChanged {
+      view_id: "v1".to_string(),
+      setting: ViewSetting::Filter,
+    },
+    JournalOperation::FieldDeleted {
+      field_id: "f1".to_string(),
+    },
+    JournalOperation::RowDeleted {
+      row_id: row_id.clone(),
+    },
+  ] {
+    wait_for_operation(&journal, &operation).await;
+  }
+
+  let recent = journal.entries_since(NOW + 60);
+  assert!(recent.iter().all(|entry| entry.timestamp == NOW + 60));
+  assert!(recent
+    .iter()
+    .all(|entry| !matches!(entry.operation, JournalOperation::RowCreated { .. })));
+  assert_eq!(
+    journal.entries_between(NOW, NOW).len() + recent.len(),
+    journal.len()
+  );
+}
+
+#[tokio::test]
+async fn journal_records_origin_and_time_of_changes_test() {
+  let clock = Arc::new(ManualClock::new(NOW));
+  let mut database = create_database_with_clock(clock.clone()).await;
+  let journal = database.start_journal(100).unwrap();
+  database.create_field(
+    None,
+    Field::new("f1".to_string(), "text".to_string(), 0, false),
+    &OrderObjectPosition::default(),
+    default_field_settings_by_layout(),
+  );
+  let row_id = RowId::from(uuid::Uuid::new_v4().to_string());
+  database
+    .create_row(CreateRowParams::new(
+      row_id.clone(),
+      database.get_database_id(),
+    ))
+    .await
+    .unwrap();
+  database
+    .update_row(row_id.clone(), |row| {
+      row.update_cells(|cells| {
+        cells.insert("f1", TestTextCell::from("hello"));
+      });
+    })
+    .await;
+
+  // The cell is recorded at the time it was updated, not when the journal sees the change.
+  clock.advance(60);
+  let entry = wait_for_entry(
+    &journal,
+    &JournalOperation::CellUpdated {
+      row_id,
+      field_id: "f1".to_string(),
+    },
+  )
+  .await;
+  assert_eq!(entry.origin, JournalOrigin::Local);
+  assert_eq!(entry.timestamp, NOW);
+  let entry = wait_for_entry(
+    &journal,
+    &JournalOperation::FieldCreated {
+      field_id: "f1".to_string(),
+    },
+  )
+  .await;
+  assert_eq!(entry.origin, JournalOrigin::Local);
+  assert_eq!(entry.timestamp, NOW);
+
+  // A change made by another client keeps the origin of its transaction.
+  {
+    let mut txn = database
+      .collab
+      .get_awareness()
+      .doc()
+      .transact_mut_with(CollabOrigin::Server);
+    database.body.fields.insert_field(
+      &mut txn,
+      Field::new("f2".to_string(), "text".to_string(), 0, false),
+    );
+    database
+      .body
+      .views
+      .update_database_view(&mut txn, "v1", |update| {
+        update.set_layout_type(DatabaseLayout::Board);
+      });
+    clock.advance(60);
+  }
+  let entry = wait_for_entry(
+    &journal,
+    &JournalOperation::FieldCreated {
+      field_id: "f2".to_string(),
+    },
+  )
+  .await;
+  assert_eq!(entry.origin, JournalOrigin::Remote);
+  assert_eq!(entry.timestamp, NOW + 60);
+  let entry = wait_for_entry(
+    &journal,
+    &JournalOperation::ViewSettingChanged {
+      view_id: "v1".to_string(),
+      setting: ViewSetting::Layout,
+    },
+  )
+  .await;
+  assert_eq!(entry.origin, JournalOrigin::Remote);
+  assert_eq!(entry.timestamp, NOW + 60);
+}
+
+#[test]
+fn journal_keeps_the_latest_entries_test() {
+  let clock = Arc::new(ManualClock::new(NOW));
+  let journal = DatabaseJournal::new(2, clock.clone());
+  for i in 0..3 {
+    journal.record(
+      JournalOperation::FieldCreated {
+        field_id: format!("f{}", i),
+      },
+      JournalOrigin::Remote,
+    );
+    clock.advance(1);
+  }
+  let field_ids = journal
+    .entries()
+    .into_iter()
+    .map(|entry| match entry.operation {
+      JournalOperation::FieldCreated { field_id } => field_id,
+      _ => unreachable!(),
+    })
+    .collect::<Vec<_>>();
+  assert_eq!(field_ids, vec!["f1", "f2"]);
+  assert_eq!(journal.entries_since(NOW + 2).len(), 1);
+  assert_eq!(journal.entries_between(NOW, NOW + 1).len(), 1);
+
+  journal.clear();
+  assert!(journal.is_empty());
+}
+
+#[tokio::test]
+async fn journal_entry_webhook_event_test() {
+  let clock = Arc::new(ManualClock::new(NOW));
+  let journal = DatabaseJournal::new(10, clock);
+  let mut entry_rx = journal.subscribe();
+  journal.record(
+    JournalOperation::FieldCreated {
+      field_id: "f1".to_string(),
+    },
+    JournalOrigin::Local,
+  );
+
+  let entry = entry_rx.recv().await.unwrap();
+  assert_eq!(entry.event_type(), "database.field_created");
+  assert_eq!(
+    entry.data(),
+    json!({
+      "operation": { "type": "field_created", "field_id": "f1" },
+      "timestamp": NOW,
+      "origin": "local",
+    })
+  );
+}
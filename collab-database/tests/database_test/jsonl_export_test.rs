@@ -0,0 +1,72 @@
+use collab_database::database::EXPORT_ROWS_BATCH_SIZE;
+use collab_database::rows::{CreateRowParams, Row, RowId};
+
+use crate::database_test::helper::{
+  create_database, create_database_with_db, restore_database_from_db,
+};
+use crate::helper::TestTextCell;
+
+#[tokio::test]
+async fn export_rows_jsonl_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database(1, &database_id);
+  let row_count = EXPORT_ROWS_BATCH_SIZE + 20;
+  for i in 0..row_count {
+    let row_id = RowId::from(uuid::Uuid::new_v4().to_string());
+    let cells = [(
+      "f1".to_string(),
+      TestTextCell::from(format!("row {}", i).as_str()).into(),
+    )]
+    .into_iter()
+    .collect();
+    let params = CreateRowParams::new(row_id, database_id.clone()).with_cells(cells);
+    database_test.create_row(params).await.unwrap();
+  }
+
+  let mut buffer = vec![];
+  let count = database_test.export_rows_jsonl(&mut buffer).await.unwrap();
+  assert_eq!(count, row_count);
+
+  let text = String::from_utf8(buffer).unwrap();
+  let rows = text
+    .lines()
+    .map(|line| serde_json::from_str::<Row>(line).unwrap())
+    .collect::<Vec<_>>();
+  let expected = database_test
+    .collect_all_rows()
+    .await
+    .into_iter()
+    .map(|row| row.unwrap())
+    .collect::<Vec<_>>();
+  assert_eq!(rows, expected);
+  assert!(text.ends_with('\n'));
+}
+
+#[tokio::test]
+async fn export_empty_database_rows_jsonl_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let database_test = create_database(1, &database_id);
+  let mut buffer = vec![];
+  let count = database_test.export_rows_jsonl(&mut buffer).await.unwrap();
+  assert_eq!(count, 0);
+  assert!(buffer.is_empty());
+}
+
+#[tokio::test]
+async fn export_rows_jsonl_does_not_load_rows_test() {
+  let workspace_id = uuid::Uuid::new_v4().to_string();
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let (db, mut database_test) = create_database_with_db(1, &workspace_id, &database_id).await;
+  let row_1 = CreateRowParams::new(uuid::Uuid::new_v4().to_string(), database_id.clone());
+  let row_2 = CreateRowParams::new(uuid::Uuid::new_v4().to_string(), database_id.clone());
+  database_test.create_row(row_1.clone()).await.unwrap();
+  database_test.create_row(row_2.clone()).await.unwrap();
+  drop(database_test);
+
+  let database_test = restore_database_from_db(1, &workspace_id, &database_id, db).await;
+  let mut buffer = vec![];
+  let count = database_test.export_rows_jsonl(&mut buffer).await.unwrap();
+  assert_eq!(count, 2);
+  assert!(database_test.get_database_row(&row_1.id).await.is_none());
+  assert!(database_test.get_database_row(&row_2.id).await.is_none());
+}
@@ -0,0 +1,156 @@
+use collab::preclude::Any;
+use collab_database::entity::FieldType;
+use collab_database::fields::location_type_option::{
+  DistanceUnit, LocationCellData, LocationFilter, LocationFilterCondition, LocationTypeOption,
+};
+use collab_database::fields::text_type_option::RichTextTypeOption;
+use collab_database::fields::{
+  BuiltinFieldTypeConverter, Field, StringifyTypeOption, TypeOptionData,
+};
+use collab_database::rows::{new_cell_builder, Cell, CreateRowParams};
+use collab_database::template::entity::CELL_DATA;
+use collab_database::views::{FilterMapBuilder, OrderObjectPosition};
+
+use crate::database_test::helper::{create_database, default_field_settings_by_layout};
+
+fn paris() -> LocationCellData {
+  LocationCellData::new(48.8566, 2.3522).unwrap()
+}
+
+#[test]
+fn location_type_option_test() {
+  let type_option = LocationTypeOption::new(DistanceUnit::Miles);
+  assert!(LocationCellData::new(91.0, 0.0).is_none());
+  assert!(LocationCellData::new(0.0, -181.0).is_none());
+  assert!(type_option.location_cell(100.0, 0.0, None).is_none());
+
+  let cell = type_option
+    .location_cell(48.8566, 2.3522, Some("Paris".to_string()))
+    .unwrap();
+  let location = type_option.location_of(Some(&cell)).unwrap();
+  assert_eq!(location, paris().with_address(Some("Paris".to_string())));
+  assert_eq!(type_option.stringify_cell(&cell), "Paris");
+  assert_eq!(
+    type_option.stringify_cell(&Cell::from(&paris())),
+    "48.8566, 2.3522"
+  );
+  assert_eq!(type_option.stringify_text(" 10 ,  20.5"), "10, 20.5");
+  assert_eq!(type_option.stringify_text("nowhere"), "");
+  assert_eq!(type_option.location_of(None), None);
+
+  let data = TypeOptionData::from(type_option.clone());
+  assert_eq!(LocationTypeOption::from(data), type_option);
+  assert_eq!(
+    LocationTypeOption::from(TypeOptionData::new()),
+    LocationTypeOption::default()
+  );
+}
+
+#[test]
+fn location_distance_test() {
+  let london = LocationCellData::new(51.5074, -0.1278).unwrap();
+  let distance = paris().distance_km(&london);
+  assert!((distance - 343.5).abs() < 1.0, "{}", distance);
+  assert_eq!(paris().distance_km(&paris()), 0.0);
+
+  let miles = LocationTypeOption::new(DistanceUnit::Miles).distance_between(&paris(), &london);
+  assert!((miles - 213.5).abs() < 1.0, "{}", miles);
+}
+
+#[test]
+fn filter_location_cells_test() {
+  let type_option = LocationTypeOption::default();
+  let cells = [
+    Some((48.8606, 2.3376)),
+    Some((51.5074, -0.1278)),
+    None,
+    Some((40.7128, -74.006)),
+  ]
+  .iter()
+  .map(|location| {
+    location.and_then(|(latitude, longitude)| type_option.location_cell(latitude, longitude, None))
+  })
+  .collect::<Vec<_>>();
+  let matching = |filter: LocationFilter| {
+    cells
+      .iter()
+      .map(|cell| type_option.matches_filter(&filter, cell.as_ref()))
+      .collect::<Vec<_>>()
+  };
+  use LocationFilterCondition::*;
+  assert_eq!(
+    matching(LocationFilter::new(WithinDistance, paris(), 500.0)),
+    vec![true, true, false, false]
+  );
+  assert_eq!(
+    matching(LocationFilter::new(FartherThan, paris(), 10.0)),
+    vec![false, true, false, true]
+  );
+  assert_eq!(
+    matching(LocationFilter::new(IsEmpty, paris(), 0.0)),
+    vec![false, false, true, false]
+  );
+  assert_eq!(
+    matching(LocationFilter::new(IsNotEmpty, paris(), 0.0)),
+    vec![true, true, false, true]
+  );
+
+  let filter = FilterMapBuilder::from([
+    ("condition".into(), Any::BigInt(1)),
+    ("content".into(), "48.8566, 2.3522, 25".into()),
+  ]);
+  assert_eq!(
+    LocationFilter::from(&filter),
+    LocationFilter::new(FartherThan, paris(), 25.0)
+  );
+  // A filter without a center doesn't match the locations.
+  let filter = LocationFilter::from(&FilterMapBuilder::new());
+  assert_eq!(matching(filter), vec![false, false, false, false]);
+}
+
+#[tokio::test]
+async fn location_field_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database(1, &database_id);
+  let field = Field::new(
+    "location".to_string(),
+    "Location".to_string(),
+    FieldType::RichText.into(),
+    false,
+  )
+  .with_type_option_data(FieldType::RichText.type_id(), RichTextTypeOption.into());
+  database_test.create_field(
+    None,
+    field,
+    &OrderObjectPosition::default(),
+    default_field_settings_by_layout(),
+  );
+
+  let mut row_ids = vec![];
+  for text in ["48.8566, 2.3522", "95, 10", "home"] {
+    let mut cell = new_cell_builder(FieldType::RichText);
+    cell.insert(CELL_DATA.into(), text.into());
+    let row_id = uuid::Uuid::new_v4().to_string();
+    let row = CreateRowParams::new(row_id.clone(), database_id.clone())
+      .with_cells([("location".to_string(), cell)].into_iter().collect());
+    database_test.create_row(row).await.unwrap();
+    row_ids.push(row_id);
+  }
+
+  // Only the text with valid coordinates is converted.
+  let summary = database_test
+    .switch_field_type("location", FieldType::Location, &BuiltinFieldTypeConverter)
+    .await
+    .unwrap();
+  assert_eq!(summary.converted, 1);
+  assert_eq!(summary.cleared, 2);
+  let mut locations = vec![];
+  for row_id in row_ids {
+    let cell: Option<Cell> = database_test
+      .get_cell("location", &row_id.into())
+      .await
+      .cell;
+    locations.push(cell.and_then(|cell| LocationCellData::from_cell(&cell)));
+  }
+  assert_eq!(locations, vec![Some(paris()), None, None]);
+}
@@ -0,0 +1,229 @@
+use std::sync::Arc;
+
+use collab::core::collab::DataSource;
+use collab::core::origin::CollabOrigin;
+use collab::preclude::{Array, ArrayRef, Collab, MapExt, ReadTxn, Update};
+use collab_database::entity::FieldType;
+use collab_database::error::DatabaseError;
+use collab_database::fields::media_type_option::{
+  MediaCellData, MediaFile, MediaFileType, MediaTypeOption, MediaUploadState, MediaUploadType,
+};
+use collab_database::fields::Field;
+use collab_database::rows::{CreateRowParams, DatabaseRow, RowId};
+use collab_database::template::entity::CELL_DATA;
+use collab_database::views::OrderObjectPosition;
+use collab_database::workspace_database::NoPersistenceDatabaseCollabService;
+use yrs::updates::decoder::Decode;
+
+use crate::database_test::helper::{
+  create_database, default_field_settings_by_layout, DatabaseTest,
+};
+
+async fn create_media_database() -> (DatabaseTest, RowId) {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database(1, &database_id);
+  let field = Field::new(
+    "media".to_string(),
+    "Files".to_string(),
+    FieldType::Media.into(),
+    false,
+  )
+  .with_type_option_data(
+    FieldType::Media.type_id(),
+    MediaTypeOption::default().into(),
+  );
+  database_test.create_field(
+    None,
+    field,
+    &OrderObjectPosition::default(),
+    default_field_settings_by_layout(),
+  );
+
+  let row_id = RowId::from(uuid::Uuid::new_v4().to_string());
+  let row = CreateRowParams::new(row_id.clone(), database_id);
+  database_test.create_row(row).await.unwrap();
+  (database_test, row_id)
+}
+
+async fn get_media_cell(database_test: &DatabaseTest, row_id: &RowId) -> MediaCellData {
+  let cell = database_test.get_cell("media", row_id).await.cell.unwrap();
+  MediaCellData::from(&cell)
+}
+
+fn file_names(data: &MediaCellData) -> Vec<String> {
+  data.files.iter().map(|file| file.name.clone()).collect()
+}
+
+fn new_file(name: &str) -> MediaFile {
+  MediaFile::new(
+    name.to_string(),
+    format!("https://appflowy.io/{}", name),
+    MediaUploadType::Cloud,
+    MediaFileType::from_file(name),
+  )
+  .with_mime("image/png")
+  .with_size(1024)
+}
+
+#[tokio::test]
+async fn add_media_files_test() {
+  let (database_test, row_id) = create_media_database().await;
+  let (a, b) = tokio::join!(
+    database_test.add_media_file(&row_id, "media", new_file("a.png")),
+    database_test.add_media_file(&row_id, "media", new_file("b.png")),
+  );
+  a.unwrap();
+  b.unwrap();
+
+  // Both files are kept, the adds don't overwrite each other.
+  let data = get_media_cell(&database_test, &row_id).await;
+  let mut names = file_names(&data);
+  names.sort();
+  assert_eq!(names, vec!["a.png", "b.png"]);
+  assert!(data.files.iter().all(|file| file.uploaded_at.is_some()));
+  assert_eq!(data.files[0].size, Some(1024));
+  assert_eq!(data.files[0].mime.as_deref(), Some("image/png"));
+
+  // A file that is not uploaded yet has no upload time.
+  let file = new_file("c.png").with_upload_state(MediaUploadState::Pending);
+  let data = database_test
+    .add_media_file(&row_id, "media", file.clone())
+    .await
+    .unwrap();
+  assert_eq!(data.get_file(&file.id).unwrap().uploaded_at, None);
+}
+
+#[tokio::test]
+async fn move_and_remove_media_files_test() {
+  let (database_test, row_id) = create_media_database().await;
+  let files = vec![new_file("a.png"), new_file("b.png"), new_file("c.png")];
+  for file in &files {
+    database_test
+      .add_media_file(&row_id, "media", file.clone())
+      .await
+      .unwrap();
+  }
+
+  let data = database_test
+    .move_media_file(&row_id, "media", &files[2].id, 0)
+    .await
+    .unwrap();
+  assert_eq!(file_names(&data), vec!["c.png", "a.png", "b.png"]);
+  assert_eq!(get_media_cell(&database_test, &row_id).await, data);
+
+  let removed = database_test
+    .remove_media_file(&row_id, "media", &files[0].id)
+    .await
+    .unwrap();
+  assert_eq!(removed.map(|file| file.name), Some("a.png".to_string()));
+  let data = get_media_cell(&database_test, &row_id).await;
+  assert_eq!(file_names(&data), vec!["c.png", "b.png"]);
+
+  let removed = database_test
+    .remove_media_file(&row_id, "media", &files[0].id)
+    .await
+    .unwrap();
+  assert!(removed.is_none());
+  let result = database_test
+    .move_media_file(&row_id, "media", &files[0].id, 0)
+    .await;
+  assert!(matches!(result, Err(DatabaseError::RecordNotFound)));
+}
+
+#[tokio::test]
+async fn update_media_cell_test() {
+  let (database_test, row_id) = create_media_database().await;
+  let file = new_file("a.png").with_upload_state(MediaUploadState::Uploading);
+  database_test
+    .add_media_file(&row_id, "media", file.clone())
+    .await
+    .unwrap();
+
+  let data = database_test
+    .update_media_cell(&row_id, "media", |data| {
+      data.update_file(&file.id, |file| {
+        file.upload_state = MediaUploadState::Uploaded;
+        file.uploaded_at = Some(100);
+      });
+    })
+    .await
+    .unwrap();
+  let updated = data.get_file(&file.id).unwrap();
+  assert_eq!(updated.upload_state, MediaUploadState::Uploaded);
+  assert_eq!(updated.uploaded_at, Some(100));
+  assert_eq!(get_media_cell(&database_test, &row_id).await, data);
+
+  // Only the cells of a media field are updated.
+  let result = database_test
+    .update_media_cell(&row_id, "unknown", |data| data.add_file(new_file("b.png")))
+    .await;
+  assert!(matches!(result, Err(DatabaseError::NoRequiredData(_))));
+}
+
+#[tokio::test]
+async fn concurrent_media_file_edits_test() {
+  let (database_test, row_id) = create_media_database().await;
+  let file_a = new_file("a.png");
+  database_test
+    .add_media_file(&row_id, "media", file_a.clone())
+    .await
+    .unwrap();
+
+  let database_row = database_test
+    .get_or_init_database_row(&row_id)
+    .await
+    .unwrap();
+  let (state_vector, encoded_collab) = {
+    let row = database_row.read().await;
+    let state_vector = row.collab.transact().state_vector();
+    (state_vector, row.encoded_collab().unwrap())
+  };
+  let collab = Collab::new_with_source(
+    CollabOrigin::Empty,
+    &row_id,
+    DataSource::DocStateV1(encoded_collab.doc_state.to_vec()),
+    vec![],
+    false,
+  )
+  .unwrap();
+  let mut remote_row = DatabaseRow::open(
+    row_id.clone(),
+    collab,
+    None,
+    Arc::new(NoPersistenceDatabaseCollabService),
+  )
+  .unwrap();
+
+  // The remote appends a file while the local client renames the first one and adds another.
+  let remote_file = new_file("remote.png");
+  remote_row.update(|update| {
+    update.update_cells(|cells| {
+      cells.update_cell("media", |txn, cell_map| {
+        let files: ArrayRef = cell_map.get_with_txn(txn, CELL_DATA).unwrap();
+        files.push_back(txn, serde_json::to_string(&remote_file).unwrap());
+      });
+    });
+  });
+  database_test
+    .update_media_cell(&row_id, "media", |data| {
+      data.update_file(&file_a.id, |file| file.name = "renamed.png".to_string());
+      data.add_file(new_file("b.png"));
+    })
+    .await
+    .unwrap();
+
+  let update = remote_row
+    .collab
+    .transact()
+    .encode_state_as_update_v1(&state_vector);
+  database_row
+    .write()
+    .await
+    .collab
+    .apply_update(Update::decode_v1(&update).unwrap())
+    .unwrap();
+
+  let mut names = file_names(&get_media_cell(&database_test, &row_id).await);
+  names.sort();
+  assert_eq!(names, vec!["b.png", "remote.png", "renamed.png"]);
+}
@@ -1,17 +1,48 @@
+mod activity_summary_test;
+mod awareness_test;
+mod backlink_test;
 mod block_test;
+mod calculation_test;
 mod cell_test;
+mod checklist_test;
+mod clock_test;
+mod compute_test;
+mod contact_test;
+mod create_database_validator_test;
+mod csv_export_test;
+mod csv_import_test;
+mod database_diff_test;
+mod document_task_test;
 mod encode_collab_test;
+mod field_conversion_test;
 mod field_observe_test;
 mod field_setting_test;
 mod field_test;
 mod filter_test;
+mod formula_test;
+mod generator_test;
 mod group_test;
 pub mod helper;
+mod journal_test;
+mod jsonl_export_test;
 mod layout_test;
+mod location_test;
+mod media_test;
+mod progress_test;
+mod rating_test;
 mod restore_test;
+mod row_comment_test;
+mod row_creation_hook_test;
+mod row_document_test;
 mod row_observe_test;
+mod row_page_test;
 mod row_test;
+mod search_index_test;
+mod select_option_audit_test;
 mod sort_test;
+mod time_test;
 mod type_option_test;
+mod url_test;
 mod view_observe_test;
+mod view_rows_test;
 mod view_test;
@@ -0,0 +1,203 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use collab::core::collab::DataSource;
+use collab::core::origin::CollabOrigin;
+use collab::preclude::{Collab, ReadTxn, Update};
+use collab_database::database::Database;
+use collab_database::entity::FieldType;
+use collab_database::fields::progress_type_option::{
+  parse_progress, ProgressCellData, ProgressDerivation, ProgressTypeOption,
+};
+use collab_database::fields::ChecklistCellData;
+use collab_database::fields::{Field, TypeOptionData};
+use collab_database::rows::{new_cell_builder, Cell, CreateRowParams, DatabaseRow};
+use collab_database::template::entity::CELL_DATA;
+use collab_database::views::OrderObjectPosition;
+use collab_database::workspace_database::NoPersistenceDatabaseCollabService;
+use yrs::updates::decoder::Decode;
+
+use crate::database_test::helper::{create_database, default_field_settings_by_layout};
+
+fn checklist_cell(options: &[&str], selected: &[&str]) -> Cell {
+  let names = options.iter().map(|name| name.to_string()).collect();
+  let selected = selected.iter().map(|name| name.to_string()).collect();
+  let data = ChecklistCellData::from((names, selected));
+  let mut cell = new_cell_builder(FieldType::Checklist);
+  cell.insert(
+    CELL_DATA.into(),
+    serde_json::to_string(&data).unwrap().into(),
+  );
+  cell
+}
+
+async fn progress_of_row(database: &Database, row_id: &str) -> Option<i64> {
+  let cell = database
+    .get_cell("progress", &row_id.to_string().into())
+    .await
+    .cell;
+  cell.and_then(|cell| ProgressCellData::from(&cell).progress)
+}
+
+#[test]
+fn progress_type_option_test() {
+  assert_eq!(parse_progress("40%"), Some(40));
+  assert_eq!(parse_progress(" 12.6 "), Some(13));
+  assert_eq!(parse_progress("250"), Some(100));
+  assert_eq!(parse_progress("soon"), None);
+
+  let type_option = ProgressTypeOption::derived_from("tasks");
+  assert_eq!(
+    ProgressCellData::from(&type_option.progress_cell(-5)).progress,
+    Some(0)
+  );
+  let data = TypeOptionData::from(type_option.clone());
+  assert_eq!(ProgressTypeOption::from(data), type_option);
+  assert_eq!(
+    ProgressTypeOption::from(TypeOptionData::new()),
+    ProgressTypeOption::default()
+  );
+
+  let cell = checklist_cell(&["a", "b", "c"], &["a"]);
+  assert_eq!(type_option.progress_of_checklist(Some(&cell)), Some(33));
+  let cell = checklist_cell(&[], &[]);
+  assert_eq!(type_option.progress_of_checklist(Some(&cell)), None);
+}
+
+fn create_progress_fields(database: &mut Database) {
+  let checklist_field = Field::new(
+    "tasks".to_string(),
+    "Tasks".to_string(),
+    FieldType::Checklist.into(),
+    false,
+  );
+  let progress_field = Field::new(
+    "progress".to_string(),
+    "Progress".to_string(),
+    FieldType::Progress.into(),
+    false,
+  )
+  .with_type_option_data(
+    FieldType::Progress.type_id(),
+    ProgressTypeOption::derived_from("tasks").into(),
+  );
+  for field in [checklist_field, progress_field] {
+    database.create_field(
+      None,
+      field,
+      &OrderObjectPosition::default(),
+      default_field_settings_by_layout(),
+    );
+  }
+}
+
+#[tokio::test]
+async fn progress_derived_from_checklist_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database(1, &database_id);
+  create_progress_fields(&mut database_test);
+  assert_eq!(
+    ProgressDerivation::from_fields(&database_test.get_all_fields()).len(),
+    1
+  );
+
+  let row_id = uuid::Uuid::new_v4().to_string();
+  let row = CreateRowParams::new(row_id.clone(), database_id.clone());
+  database_test.create_row(row).await.unwrap();
+
+  // The progress cell is refreshed when the checklist cell is updated.
+  database_test
+    .update_row(row_id.clone().into(), |update| {
+      update.update_cells(|cells| {
+        cells.insert_cell("tasks", checklist_cell(&["a", "b", "c", "d"], &["a", "b"]));
+      });
+    })
+    .await;
+  assert_eq!(progress_of_row(&database_test, &row_id).await, Some(50));
+
+  database_test
+    .update_row(row_id.clone().into(), |update| {
+      update.update_cells(|cells| {
+        cells.insert_cell("tasks", checklist_cell(&["a", "b"], &["a", "b"]));
+      });
+    })
+    .await;
+  assert_eq!(progress_of_row(&database_test, &row_id).await, Some(100));
+
+  // Clearing the checklist clears the progress.
+  database_test
+    .update_row(row_id.clone().into(), |update| {
+      update.update_cells(|cells| {
+        cells.clear("tasks");
+      });
+    })
+    .await;
+  assert_eq!(progress_of_row(&database_test, &row_id).await, None);
+}
+
+#[tokio::test]
+async fn progress_derived_in_every_row_write_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database(1, &database_id);
+  create_progress_fields(&mut database_test);
+
+  // The progress of a new row is derived from its checklist cell.
+  let row_id = uuid::Uuid::new_v4().to_string();
+  let row = CreateRowParams::new(row_id.clone(), database_id.clone())
+    .with_cells([("tasks".to_string(), checklist_cell(&["a", "b"], &["a"]))].into());
+  database_test.create_row(row).await.unwrap();
+  assert_eq!(progress_of_row(&database_test, &row_id).await, Some(50));
+
+  // Updating the row directly refreshes the progress too.
+  let database_row = database_test
+    .get_or_init_database_row(&row_id.clone().into())
+    .await
+    .unwrap();
+  database_row.write().await.update(|update| {
+    update.update_cells(|cells| {
+      cells.insert_cell("tasks", checklist_cell(&["a", "b"], &[]));
+    });
+  });
+  assert_eq!(progress_of_row(&database_test, &row_id).await, Some(0));
+
+  // A remote client that doesn't derive the progress checks all the options.
+  let (state_vector, encoded_collab) = {
+    let row = database_row.read().await;
+    let state_vector = row.collab.transact().state_vector();
+    (state_vector, row.encoded_collab().unwrap())
+  };
+  let collab = Collab::new_with_source(
+    CollabOrigin::Empty,
+    &row_id,
+    DataSource::DocStateV1(encoded_collab.doc_state.to_vec()),
+    vec![],
+    false,
+  )
+  .unwrap();
+  let mut remote_row = DatabaseRow::open(
+    row_id.clone().into(),
+    collab,
+    None,
+    Arc::new(NoPersistenceDatabaseCollabService),
+  )
+  .unwrap();
+  remote_row.update(|update| {
+    update.update_cells(|cells| {
+      cells.insert_cell("tasks", checklist_cell(&["a", "b"], &["a", "b"]));
+    });
+  });
+  let update = remote_row
+    .collab
+    .transact()
+    .encode_state_as_update_v1(&state_vector);
+  database_row
+    .write()
+    .await
+    .collab
+    .apply_update(Update::decode_v1(&update).unwrap())
+    .unwrap();
+
+  // The progress is refreshed after the remote update is applied.
+  tokio::time::sleep(Duration::from_millis(100)).await;
+  assert_eq!(progress_of_row(&database_test, &row_id).await, Some(100));
+}
@@ -0,0 +1,137 @@
+use std::cmp::Ordering;
+
+use collab::preclude::Any;
+use collab_database::entity::FieldType;
+use collab_database::fields::rating_type_option::{
+  RatingCellData, RatingFilter, RatingFilterCondition, RatingIcon, RatingTypeOption,
+  MAX_RATING_LIMIT,
+};
+use collab_database::fields::text_type_option::RichTextTypeOption;
+use collab_database::fields::{BuiltinFieldTypeConverter, Field, TypeOptionData};
+use collab_database::rows::{new_cell_builder, Cell, CreateRowParams};
+use collab_database::template::entity::CELL_DATA;
+use collab_database::views::{FilterMapBuilder, OrderObjectPosition};
+
+use crate::database_test::helper::{create_database, default_field_settings_by_layout};
+
+#[test]
+fn rating_type_option_test() {
+  let type_option = RatingTypeOption::new(3, RatingIcon::Heart);
+  assert_eq!(RatingTypeOption::new(50, RatingIcon::Star).max, 10);
+  assert_eq!(type_option.clamp(7), 3);
+  assert_eq!(type_option.clamp(-1), 0);
+  assert_eq!(type_option.format_icons(2), "❤️❤️");
+
+  let cell = type_option.rating_cell(5);
+  assert_eq!(RatingCellData::from(&cell).rating, Some(3));
+  assert_eq!(type_option.rating_of(None), None);
+
+  let data = TypeOptionData::from(type_option.clone());
+  assert_eq!(RatingTypeOption::from(data), type_option);
+  assert_eq!(
+    RatingTypeOption::from(TypeOptionData::new()),
+    RatingTypeOption::default()
+  );
+
+  // The max written by another client is clamped when it's read.
+  let data = TypeOptionData::from([("max".into(), Any::BigInt(100))]);
+  assert_eq!(RatingTypeOption::from(data).max, MAX_RATING_LIMIT);
+}
+
+#[test]
+fn filter_and_sort_rating_cells_test() {
+  let type_option = RatingTypeOption::default();
+  let cells = [Some(4), Some(1), None, Some(5)]
+    .iter()
+    .map(|rating| rating.map(|rating| type_option.rating_cell(rating)))
+    .collect::<Vec<_>>();
+  let matching = |filter: RatingFilter| {
+    cells
+      .iter()
+      .map(|cell| type_option.matches_filter(&filter, cell.as_ref()))
+      .collect::<Vec<_>>()
+  };
+  use RatingFilterCondition::*;
+  assert_eq!(
+    matching(RatingFilter::new(GreaterThanOrEqualTo, 4)),
+    vec![true, false, false, true]
+  );
+  assert_eq!(
+    matching(RatingFilter::new(IsNot, 4)),
+    vec![false, true, true, true]
+  );
+  assert_eq!(
+    matching(RatingFilter::new(IsEmpty, 0)),
+    vec![false, false, true, false]
+  );
+
+  let filter = FilterMapBuilder::from([
+    ("condition".into(), Any::BigInt(3)),
+    ("content".into(), "2".into()),
+  ]);
+  assert_eq!(RatingFilter::from(&filter), RatingFilter::new(LessThan, 2));
+
+  let mut sorted = cells.clone();
+  sorted.sort_by(|left, right| type_option.compare_cells(left.as_ref(), right.as_ref()));
+  let ratings = sorted
+    .iter()
+    .map(|cell| type_option.rating_of(cell.as_ref()))
+    .collect::<Vec<_>>();
+  assert_eq!(ratings, vec![Some(1), Some(4), Some(5), None]);
+  assert_eq!(
+    type_option.compare_cells(cells[0].as_ref(), cells[0].as_ref()),
+    Ordering::Equal
+  );
+}
+
+#[tokio::test]
+async fn rating_field_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database(1, &database_id);
+  let field = Field::new(
+    "rating".to_string(),
+    "Rating".to_string(),
+    FieldType::RichText.into(),
+    false,
+  )
+  .with_type_option_data(FieldType::RichText.type_id(), RichTextTypeOption.into())
+  .with_type_option_data(
+    FieldType::Rating.type_id(),
+    RatingTypeOption::new(3, RatingIcon::Star).into(),
+  );
+  database_test.create_field(
+    None,
+    field,
+    &OrderObjectPosition::default(),
+    default_field_settings_by_layout(),
+  );
+
+  let mut row_ids = vec![];
+  for text in ["2", "8", "great"] {
+    let mut cell = new_cell_builder(FieldType::RichText);
+    cell.insert(CELL_DATA.into(), text.into());
+    let row_id = uuid::Uuid::new_v4().to_string();
+    let row = CreateRowParams::new(row_id.clone(), database_id.clone())
+      .with_cells([("rating".to_string(), cell)].into_iter().collect());
+    database_test.create_row(row).await.unwrap();
+    row_ids.push(row_id);
+  }
+
+  // The ratings are parsed from the text and kept under the max of the field.
+  let summary = database_test
+    .switch_field_type("rating", FieldType::Rating, &BuiltinFieldTypeConverter)
+    .await
+    .unwrap();
+  assert_eq!(summary.converted, 2);
+  assert_eq!(summary.cleared, 1);
+  let mut ratings = vec![];
+  for row_id in row_ids {
+    let cell: Option<Cell> = database_test.get_cell("rating", &row_id.into()).await.cell;
+    ratings.push(cell.and_then(|cell| RatingCellData::from(&cell).rating));
+  }
+  assert_eq!(ratings, vec![Some(2), Some(3), None]);
+
+  let type_option = database_test.get_stringify_type_option("rating").unwrap();
+  let cell = Cell::from(&RatingCellData::new(2));
+  assert_eq!(type_option.stringify_cell(&cell), "2");
+}
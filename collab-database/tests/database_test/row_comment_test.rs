@@ -0,0 +1,230 @@
+use std::sync::Arc;
+
+use collab::core::origin::CollabOrigin;
+use collab::preclude::updates::decoder::Decode;
+use collab::preclude::{Any, Array, ArrayPrelim, Collab, Map, ReadTxn, Update};
+use collab_database::database::gen_row_id;
+use collab_database::error::DatabaseError;
+use collab_database::rows::{
+  CreateRowParams, DatabaseRow, Row, RowChange, RowCommentChange, RowId,
+};
+use collab_database::workspace_database::NoPersistenceDatabaseCollabService;
+use tokio::sync::broadcast;
+
+use crate::database_test::helper::{create_database, wait_for_specific_event};
+
+fn create_database_row(row_id: &RowId) -> DatabaseRow {
+  let collab = Collab::new_with_origin(CollabOrigin::Empty, row_id, vec![], false);
+  DatabaseRow::create(
+    row_id.clone(),
+    collab,
+    None,
+    Row::new(row_id.clone(), "d1"),
+    Arc::new(NoPersistenceDatabaseCollabService),
+  )
+}
+
+fn open_database_row(from: &DatabaseRow) -> (DatabaseRow, broadcast::Sender<RowChange>) {
+  let (change_tx, _) = broadcast::channel(100);
+  let mut collab = Collab::new_with_origin(CollabOrigin::Empty, &from.row_id, vec![], false);
+  sync(&from.collab, &mut collab);
+  let row = DatabaseRow::open(
+    from.row_id.clone(),
+    collab,
+    Some(change_tx.clone()),
+    Arc::new(NoPersistenceDatabaseCollabService),
+  )
+  .unwrap();
+  (row, change_tx)
+}
+
+fn sync(from: &Collab, to: &mut Collab) {
+  let update = from
+    .transact()
+    .encode_state_as_update_v1(&to.transact().state_vector());
+  to.apply_update(Update::decode_v1(&update).unwrap())
+    .unwrap();
+}
+
+#[tokio::test]
+async fn create_edit_and_delete_row_comments_test() {
+  let row_id = gen_row_id();
+  let mut row = create_database_row(&row_id);
+
+  let first = row
+    .create_comment(1, "Is it done?".to_string(), None)
+    .unwrap();
+  let reply = row
+    .create_comment(2, "Almost".to_string(), Some(&first.id))
+    .unwrap();
+  // A reply to a reply is added to the same thread.
+  let second_reply = row
+    .create_comment(1, "Great".to_string(), Some(&reply.id))
+    .unwrap();
+  assert_eq!(reply.reply_to.as_deref(), Some(first.id.as_str()));
+  assert_eq!(second_reply.reply_to.as_deref(), Some(first.id.as_str()));
+  assert!(matches!(
+    row.create_comment(1, "?".to_string(), Some("unknown")),
+    Err(DatabaseError::RecordNotFound)
+  ));
+
+  let edited = row.edit_comment(&reply.id, "Done".to_string()).unwrap();
+  assert_eq!(edited.content, "Done");
+  assert_eq!(edited.uid, 2);
+  assert_eq!(row.get_comment(&reply.id).unwrap(), edited);
+
+  // Resolving a reply resolves its thread.
+  let thread = row.resolve_comment(&second_reply.id, true).unwrap();
+  assert_eq!(thread.id, first.id);
+  assert!(thread.resolved);
+  assert!(!row.get_comment(&second_reply.id).unwrap().resolved);
+
+  let comments = row.get_comments();
+  assert_eq!(comments.len(), 3);
+  assert!(comments.iter().any(|comment| comment == &edited));
+
+  let mut deleted = row.delete_comment(&first.id);
+  deleted.sort();
+  let mut expected = vec![first.id, reply.id, second_reply.id];
+  expected.sort();
+  assert_eq!(deleted, expected);
+  assert!(row.get_comments().is_empty());
+  assert!(row.delete_comment("unknown").is_empty());
+}
+
+#[tokio::test]
+async fn sync_row_comments_test() {
+  let row_id = gen_row_id();
+  let mut row = create_database_row(&row_id);
+  let comment = row.create_comment(1, "Hello".to_string(), None).unwrap();
+  let (mut remote_row, change_tx) = open_database_row(&row);
+  let change_rx = change_tx.subscribe();
+  assert_eq!(remote_row.get_comments(), vec![comment.clone()]);
+
+  // The content is edited on one client while the thread is resolved on the other.
+  row
+    .edit_comment(&comment.id, "Hello world".to_string())
+    .unwrap();
+  remote_row.resolve_comment(&comment.id, true).unwrap();
+  sync(&row.collab, &mut remote_row.collab);
+  sync(&remote_row.collab, &mut row.collab);
+
+  let comment = row.get_comment(&comment.id).unwrap();
+  assert_eq!(comment.content, "Hello world");
+  assert!(comment.resolved);
+  assert_eq!(remote_row.get_comment(&comment.id).unwrap(), comment);
+
+  let comment_id = comment.id.clone();
+  wait_for_specific_event(change_rx, |event| match event {
+    RowChange::DidChangeRowComment {
+      change: RowCommentChange::Updated(updated),
+      ..
+    } => updated == &comment,
+    _ => false,
+  })
+  .await
+  .unwrap();
+
+  let change_rx = change_tx.subscribe();
+  row.delete_comment(&comment_id);
+  sync(&row.collab, &mut remote_row.collab);
+  wait_for_specific_event(change_rx, |event| match event {
+    RowChange::DidChangeRowComment {
+      change: RowCommentChange::Deleted { comment_id: id },
+      ..
+    } => id == &comment_id,
+    _ => false,
+  })
+  .await
+  .unwrap();
+}
+
+#[tokio::test]
+async fn observe_row_comments_of_database_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database(1, &database_id);
+  let row_change_rx = database_test.subscribe_row_change().unwrap();
+  let row_id = gen_row_id();
+  database_test
+    .create_row(CreateRowParams::new(row_id.clone(), database_id))
+    .await
+    .unwrap();
+
+  let database_row = database_test
+    .get_or_init_database_row(&row_id)
+    .await
+    .unwrap();
+  let comment = database_row
+    .write()
+    .await
+    .create_comment(1, "Hello".to_string(), None)
+    .unwrap();
+  wait_for_specific_event(row_change_rx, |event| match event {
+    RowChange::DidChangeRowComment {
+      row_id: id,
+      change: RowCommentChange::Created(created),
+    } => id == &row_id && created == &comment,
+    _ => false,
+  })
+  .await
+  .unwrap();
+}
+
+#[tokio::test]
+async fn open_row_without_comments_does_not_write_test() {
+  let row_id = gen_row_id();
+  let row = create_database_row(&row_id);
+  let state_vector = row.collab.transact().state_vector();
+  let (remote_row, _) = open_database_row(&row);
+  assert!(remote_row.get_comments().is_empty());
+  assert_eq!(remote_row.collab.transact().state_vector(), state_vector);
+}
+
+#[tokio::test]
+async fn migrate_legacy_row_comments_test() {
+  let row_id = gen_row_id();
+  let mut row = create_database_row(&row_id);
+  {
+    let collab = &mut row.collab;
+    let mut txn = collab.context.transact_mut();
+    let legacy = collab
+      .data
+      .insert(&mut txn, "comment", ArrayPrelim::default());
+    legacy.push_back(
+      &mut txn,
+      Any::from_json(r#"{"uid":1,"content":"First","created_at":10}"#).unwrap(),
+    );
+    legacy.push_back(
+      &mut txn,
+      Any::from_json(r#"{"uid":2,"content":"Second","created_at":20}"#).unwrap(),
+    );
+  }
+  let (mut row, change_tx) = open_database_row(&row);
+  let change_rx = change_tx.subscribe();
+  let comments = row.get_comments();
+  assert_eq!(comments.len(), 2);
+  assert_eq!(comments[0].content, "First");
+  assert_eq!(comments[0].updated_at, 10);
+  assert_eq!(comments[1].uid, 2);
+
+  // The first write moves the legacy comments to the comments map.
+  let edited = row
+    .edit_comment(&comments[1].id, "Second!".to_string())
+    .unwrap();
+  assert_eq!(edited.id, comments[1].id);
+  assert!(row
+    .collab
+    .data
+    .get(&row.collab.transact(), "comment")
+    .is_none());
+  let migrated = row.get_comments();
+  assert_eq!(migrated[0], comments[0]);
+  assert_eq!(migrated[1], edited);
+
+  wait_for_specific_event(change_rx, |event| match event {
+    RowChange::DidUpdateRowComment { row } => row.id == row_id,
+    _ => false,
+  })
+  .await
+  .unwrap();
+}
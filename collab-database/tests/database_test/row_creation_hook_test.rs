@@ -0,0 +1,196 @@
+use collab::util::AnyMapExt;
+use collab_database::entity::FieldType;
+use collab_database::fields::number_type_option::NumberTypeOption;
+use collab_database::fields::select_type_option::{
+  SelectOption, SelectTypeOption, SingleSelectTypeOption,
+};
+use collab_database::fields::timestamp_type_option::TimestampTypeOption;
+use collab_database::fields::Field;
+use collab_database::rows::{new_cell_builder, AutoNumberHook, CreateRowParams};
+use collab_database::template::entity::CELL_DATA;
+use collab_database::views::{DuplicateRowPosition, OrderObjectPosition};
+
+use crate::database_test::helper::{
+  create_database, default_field_settings_by_layout, DatabaseTest,
+};
+
+fn create_field(database_test: &mut DatabaseTest, field: Field) {
+  database_test.create_field(
+    None,
+    field,
+    &OrderObjectPosition::default(),
+    default_field_settings_by_layout(),
+  );
+}
+
+async fn cell_data(database_test: &DatabaseTest, field_id: &str, row_id: i32) -> Option<String> {
+  let cell = database_test.get_cell(field_id, &row_id.into()).await.cell;
+  cell.and_then(|cell| cell.get_as::<String>(CELL_DATA))
+}
+
+#[tokio::test]
+async fn stamp_cells_of_new_rows_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database(1, &database_id);
+  let created_time = Field::new(
+    "created".to_string(),
+    "Created".to_string(),
+    FieldType::CreatedTime.into(),
+    false,
+  )
+  .with_type_option_data(
+    FieldType::CreatedTime.type_id(),
+    TimestampTypeOption::new(FieldType::CreatedTime).into(),
+  );
+  let options = vec![SelectOption::new("Todo"), SelectOption::new("Done")];
+  let status = Field::new(
+    "status".to_string(),
+    "Status".to_string(),
+    FieldType::SingleSelect.into(),
+    false,
+  )
+  .with_type_option_data(
+    FieldType::SingleSelect.type_id(),
+    SingleSelectTypeOption(
+      SelectTypeOption::new(options.clone(), false).with_default_option_id(options[0].id.clone()),
+    )
+    .into(),
+  );
+  create_field(&mut database_test, created_time);
+  create_field(&mut database_test, status);
+
+  let mut params = CreateRowParams::new(1, database_id.clone());
+  params.created_at = 1_700_000_000;
+  database_test.create_row(params).await.unwrap();
+  assert_eq!(
+    cell_data(&database_test, "created", 1).await.as_deref(),
+    Some("1700000000")
+  );
+  assert_eq!(
+    cell_data(&database_test, "status", 1).await,
+    Some(options[0].id.clone())
+  );
+
+  // The duplicate of a row has its own created time.
+  let duplicated = database_test
+    .duplicate_row(&1.into(), DuplicateRowPosition::After)
+    .await
+    .unwrap();
+  assert_ne!(duplicated.row.created_at, 1_700_000_000);
+  assert_eq!(
+    duplicated
+      .row
+      .cells
+      .get("created")
+      .and_then(|cell| cell.get_as::<String>(CELL_DATA)),
+    Some(duplicated.row.created_at.to_string())
+  );
+
+  // The cells given with the row are kept.
+  let mut cell = new_cell_builder(FieldType::SingleSelect);
+  cell.insert(CELL_DATA.into(), options[1].id.clone().into());
+  let params = CreateRowParams::new(2, database_id.clone())
+    .with_cells([("status".to_string(), cell)].into_iter().collect());
+  database_test
+    .create_row_in_view("v1", params)
+    .await
+    .unwrap();
+  assert_eq!(
+    cell_data(&database_test, "status", 2).await,
+    Some(options[1].id.clone())
+  );
+}
+
+#[tokio::test]
+async fn register_row_creation_hook_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database(1, &database_id);
+  let number = Field::new(
+    "number".to_string(),
+    "No.".to_string(),
+    FieldType::Number.into(),
+    false,
+  )
+  .with_type_option_data(
+    FieldType::Number.type_id(),
+    NumberTypeOption::default().into(),
+  );
+  create_field(&mut database_test, number);
+  database_test.register_row_creation_hook(FieldType::Number, AutoNumberHook);
+
+  let num_of_rows = database_test.get_inline_row_orders().len();
+  for row_id in [1, 2] {
+    let params = CreateRowParams::new(row_id, database_id.clone());
+    database_test.create_row(params).await.unwrap();
+  }
+  assert_eq!(
+    cell_data(&database_test, "number", 1).await,
+    Some((num_of_rows + 1).to_string())
+  );
+  assert_eq!(
+    cell_data(&database_test, "number", 2).await,
+    Some((num_of_rows + 2).to_string())
+  );
+
+  // The number of a removed row is not reused.
+  database_test.remove_row(&2.into()).await;
+  let params = CreateRowParams::new(3, database_id.clone());
+  database_test
+    .create_row_in_view("v1", params)
+    .await
+    .unwrap();
+  assert_eq!(
+    cell_data(&database_test, "number", 3).await,
+    Some((num_of_rows + 3).to_string())
+  );
+
+  // The duplicate of a row gets a new number.
+  let duplicated = database_test
+    .duplicate_row(&3.into(), DuplicateRowPosition::After)
+    .await
+    .unwrap();
+  assert_eq!(
+    duplicated
+      .row
+      .cells
+      .get("number")
+      .and_then(|cell| cell.get_as::<String>(CELL_DATA)),
+    Some((num_of_rows + 4).to_string())
+  );
+}
+
+#[tokio::test]
+async fn validate_row_before_running_hooks_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database(1, &database_id);
+  let created_time = Field::new(
+    "created".to_string(),
+    "Created".to_string(),
+    FieldType::CreatedTime.into(),
+    false,
+  )
+  .with_type_option_data(
+    FieldType::CreatedTime.type_id(),
+    TimestampTypeOption::new(FieldType::CreatedTime).into(),
+  );
+  create_field(&mut database_test, created_time);
+
+  // The unset creation time is stamped before the hooks fill the cells.
+  let params = CreateRowParams::new(1, database_id.clone());
+  database_test
+    .create_row_in_view("v1", params)
+    .await
+    .unwrap();
+  let row = database_test.get_row(&1.into()).await;
+  assert_ne!(row.created_at, 0);
+  assert_eq!(
+    cell_data(&database_test, "created", 1).await,
+    Some(row.created_at.to_string())
+  );
+
+  let params = CreateRowParams::new(String::new(), database_id.clone());
+  assert!(database_test
+    .create_row_in_view("v1", params)
+    .await
+    .is_err());
+}
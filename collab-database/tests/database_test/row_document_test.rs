@@ -0,0 +1,221 @@
+use collab::core::origin::CollabOrigin;
+use collab_database::database::gen_row_id;
+use collab_database::fields::Field;
+use collab_database::rows::{Cells, CreateRowParams, RowDocument};
+use collab_database::views::DuplicateRowPosition;
+use collab_document::blocks::DocumentData;
+use collab_document::document::Document;
+use collab_document::importer::md_importer::MDImporter;
+use collab_entity::CollabType;
+
+use crate::database_test::helper::{DatabaseTest, DatabaseTestBuilder};
+use crate::helper::TestTextCell;
+
+async fn create_task_database(database_id: &str) -> DatabaseTest {
+  DatabaseTestBuilder::new(1, database_id)
+    .with_field(Field::new("name".to_string(), "Name".to_string(), 0, true))
+    .with_field(Field::new(
+      "status".to_string(),
+      "Status".to_string(),
+      0,
+      false,
+    ))
+    .with_field(Field::new(
+      "notes".to_string(),
+      "Notes".to_string(),
+      0,
+      false,
+    ))
+    .build()
+    .await
+}
+
+/// Returns the plain text of the top level blocks of the document, in order.
+fn block_texts(data: &DocumentData) -> Vec<String> {
+  let text_map = data.meta.text_map.clone().unwrap_or_default();
+  data.meta.children_map[&data.blocks[&data.page_id].children]
+    .iter()
+    .map(|block_id| {
+      let block = &data.blocks[block_id];
+      let delta = block
+        .external_id
+        .as_ref()
+        .and_then(|text_id| text_map.get(text_id))
+        .map(|delta| serde_json::from_str::<Vec<serde_json::Value>>(delta).unwrap())
+        .unwrap_or_default();
+      let text = delta
+        .iter()
+        .map(|insert| insert["insert"].as_str().unwrap().to_string())
+        .collect::<String>();
+      format!("{}:{}", block.ty, text)
+    })
+    .collect()
+}
+
+#[tokio::test]
+async fn convert_row_to_document_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_task_database(&database_id).await;
+  let row_id = gen_row_id();
+  database_test
+    .create_row(
+      CreateRowParams::new(row_id.clone(), database_id.clone()).with_cells(Cells::from([
+        ("name".into(), TestTextCell::from("Write the doc").into()),
+        ("status".into(), TestTextCell::from("In progress").into()),
+      ])),
+    )
+    .await
+    .unwrap();
+  database_test
+    .update_row_meta(&row_id, |meta| {
+      meta.insert_icon("📝");
+    })
+    .await;
+
+  let RowDocument {
+    document_id,
+    data,
+    name,
+    icon,
+  } = database_test
+    .convert_row_to_document(&row_id)
+    .await
+    .unwrap();
+  assert_eq!(name, "Write the doc");
+  assert_eq!(icon.as_deref(), Some("📝"));
+  // The empty cells and the primary field are left out.
+  assert_eq!(block_texts(&data), vec!["paragraph:Status: In progress"]);
+
+  let document = Document::create(&document_id, data).unwrap();
+  assert_eq!(document.to_plain_text().unwrap(), "\nStatus: In progress");
+}
+
+#[tokio::test]
+async fn convert_row_with_document_body_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_task_database(&database_id).await;
+  let row_id = gen_row_id();
+  database_test
+    .create_row(
+      CreateRowParams::new(row_id.clone(), database_id.clone()).with_cells(Cells::from([
+        ("name".into(), TestTextCell::from("Write the doc").into()),
+        ("notes".into(), TestTextCell::from("Due friday").into()),
+      ])),
+    )
+    .await
+    .unwrap();
+
+  let row_document_id = database_test.get_row_document_id(&row_id).unwrap();
+  let body = MDImporter::new(None)
+    .import(&row_document_id, "# Plan\n\nFirst draft".to_string())
+    .unwrap();
+  let row_document = Document::create(&row_document_id, body).unwrap();
+  database_test
+    .collab_service
+    .persistence()
+    .unwrap()
+    .flush_collabs(vec![(
+      row_document_id,
+      row_document.encode_collab().unwrap(),
+    )])
+    .unwrap();
+  database_test
+    .update_row_meta(&row_id, |meta| {
+      meta.update_is_document_empty(false);
+    })
+    .await;
+
+  let row_document = database_test
+    .convert_row_to_document(&row_id)
+    .await
+    .unwrap();
+  assert_eq!(
+    block_texts(&row_document.data),
+    vec![
+      "paragraph:Notes: Due friday",
+      "divider:",
+      "heading:Plan",
+      "paragraph:First draft"
+    ]
+  );
+  let page_id = &row_document.data.page_id;
+  assert!(row_document
+    .data
+    .blocks
+    .values()
+    .filter(|block| &block.id != page_id)
+    .all(|block| row_document.data.blocks.contains_key(&block.parent)));
+
+  let document = Document::create(&row_document.document_id, row_document.data).unwrap();
+  assert_eq!(
+    document.to_plain_text().unwrap(),
+    "\nNotes: Due friday\n\nPlan\nFirst draft"
+  );
+}
+
+#[tokio::test]
+async fn convert_not_exist_row_to_document_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let database_test = create_task_database(&database_id).await;
+  assert!(database_test
+    .convert_row_to_document(&gen_row_id())
+    .await
+    .is_err());
+}
+
+#[tokio::test]
+async fn duplicate_row_with_document_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_task_database(&database_id).await;
+  let row_id = gen_row_id();
+  database_test
+    .create_row(
+      CreateRowParams::new(row_id.clone(), database_id.clone()).with_cells(Cells::from([(
+        "name".into(),
+        TestTextCell::from("Write the doc").into(),
+      )])),
+    )
+    .await
+    .unwrap();
+  let row_document_id = database_test.get_row_document_id(&row_id).unwrap();
+  let body = MDImporter::new(None)
+    .import(&row_document_id, "# Plan\n\nFirst draft".to_string())
+    .unwrap();
+  let row_document = Document::create(&row_document_id, body).unwrap();
+  database_test
+    .collab_service
+    .persistence()
+    .unwrap()
+    .flush_collabs(vec![(
+      row_document_id.clone(),
+      row_document.encode_collab().unwrap(),
+    )])
+    .unwrap();
+  database_test
+    .update_row_meta(&row_id, |meta| {
+      meta.update_is_document_empty(false);
+    })
+    .await;
+
+  let duplicated = database_test
+    .duplicate_row(&row_id, DuplicateRowPosition::After)
+    .await
+    .unwrap();
+  assert_ne!(duplicated.document_id, row_document_id);
+  assert!(!duplicated.meta.is_document_empty);
+  let encoded_collab = database_test
+    .collab_service
+    .get_collabs(vec![duplicated.document_id.clone()], CollabType::Document)
+    .await
+    .unwrap()
+    .remove(&duplicated.document_id)
+    .unwrap();
+  let document = Document::open_with_options(
+    CollabOrigin::Empty,
+    encoded_collab.into(),
+    &duplicated.document_id,
+    vec![],
+  )
+  .unwrap();
+  assert_eq!(document.to_plain_text().unwrap(), "\nPlan\nFirst draft");
+}
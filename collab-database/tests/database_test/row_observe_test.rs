@@ -79,7 +79,8 @@ async fn observer_row_cell_test() {
       row_id: _,
       field_id,
       value,
-    } => field_id == "f1" && value.get_as::<i64>("level") == Some(1),
+      is_local_change,
+    } => is_local_change && field_id == "f1" && value.get_as::<i64>("level") == Some(1),
     _ => false,
   })
   .await
@@ -114,7 +115,8 @@ async fn observer_row_cell_test() {
       row_id: _,
       field_id,
       value,
-    } => field_id == "f1" && value.get_as::<i64>("level") == Some(2),
+      is_local_change,
+    } => is_local_change && field_id == "f1" && value.get_as::<i64>("level") == Some(2),
     _ => false,
   })
   .await
@@ -0,0 +1,120 @@
+use collab_database::rows::{CreateRowParams, Row, RowId};
+use collab_database::views::OrderObjectPosition;
+
+use crate::database_test::helper::{create_database, DatabaseTest};
+
+async fn create_database_with_rows(count: usize) -> (DatabaseTest, Vec<RowId>) {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database(1, &database_id);
+  let mut row_ids = vec![];
+  for _ in 0..count {
+    let row_id = RowId::from(uuid::Uuid::new_v4().to_string());
+    let params = CreateRowParams::new(row_id.clone(), database_id.clone());
+    database_test.create_row(params).await.unwrap();
+    row_ids.push(row_id);
+  }
+  (database_test, row_ids)
+}
+
+#[tokio::test]
+async fn get_rows_in_range_test() {
+  let (database_test, row_ids) = create_database_with_rows(5).await;
+  let ids = |rows: Vec<Row>| rows.into_iter().map(|row| row.id).collect::<Vec<_>>();
+
+  let rows = database_test.get_rows_in_range("v1", 1, 2).await.unwrap();
+  assert_eq!(ids(rows), row_ids[1..3].to_vec());
+  let rows = database_test.get_rows_in_range("v1", 4, 10).await.unwrap();
+  assert_eq!(ids(rows), row_ids[4..].to_vec());
+  assert!(database_test
+    .get_rows_in_range("v1", 10, 2)
+    .await
+    .unwrap()
+    .is_empty());
+}
+
+#[tokio::test]
+async fn get_rows_after_cursor_test() {
+  let (mut database_test, row_ids) = create_database_with_rows(5).await;
+  let page = database_test.get_rows_after("v1", None, 2).await.unwrap();
+  assert_eq!(page.rows.len(), 2);
+  assert_eq!(page.next_cursor, Some(row_ids[1].clone()));
+
+  // The rows inserted before the cursor don't shift the next page.
+  let database_id = database_test.get_database_id();
+  let mut params = CreateRowParams::new(uuid::Uuid::new_v4().to_string(), database_id);
+  params.row_position = OrderObjectPosition::Start;
+  database_test.create_row(params).await.unwrap();
+  let page = database_test
+    .get_rows_after("v1", page.next_cursor.as_ref(), 2)
+    .await
+    .unwrap();
+  let page_row_ids = page
+    .rows
+    .iter()
+    .map(|row| row.id.clone())
+    .collect::<Vec<_>>();
+  assert_eq!(page_row_ids, row_ids[2..4].to_vec());
+
+  let page = database_test
+    .get_rows_after("v1", page.next_cursor.as_ref(), 2)
+    .await
+    .unwrap();
+  assert_eq!(page.rows[0].id, row_ids[4]);
+  assert_eq!(page.next_cursor, None);
+
+  let unknown = RowId::from(uuid::Uuid::new_v4().to_string());
+  assert!(database_test
+    .get_rows_after("v1", Some(&unknown), 2)
+    .await
+    .is_err());
+}
+
+#[tokio::test]
+async fn paginate_rows_test() {
+  let (database_test, row_ids) = create_database_with_rows(5).await;
+  let mut pages = database_test.paginate_rows("v1", 2);
+  let mut page_sizes = vec![];
+  let mut paginated_row_ids = vec![];
+  while let Some(rows) = pages.next_page().await {
+    let rows = rows.unwrap();
+    page_sizes.push(rows.len());
+    paginated_row_ids.extend(rows.into_iter().map(|row| row.id));
+  }
+  assert_eq!(page_sizes, vec![2, 2, 1]);
+  assert_eq!(paginated_row_ids, row_ids);
+  assert!(pages.next_page().await.is_none());
+}
+
+#[tokio::test]
+async fn hidden_rows_are_not_paginated_test() {
+  let (mut database_test, row_ids) = create_database_with_rows(5).await;
+  database_test.hide_row("v1", &row_ids[1]);
+  database_test.hide_row("v1", &row_ids[3]);
+  let ids = |rows: Vec<Row>| rows.into_iter().map(|row| row.id).collect::<Vec<_>>();
+
+  let rows = database_test.get_rows_in_range("v1", 1, 2).await.unwrap();
+  assert_eq!(ids(rows), vec![row_ids[2].clone(), row_ids[4].clone()]);
+
+  let page = database_test.get_rows_after("v1", None, 2).await.unwrap();
+  assert_eq!(ids(page.rows), vec![row_ids[0].clone(), row_ids[2].clone()]);
+  assert_eq!(page.next_cursor, Some(row_ids[2].clone()));
+
+  // A cursor on a row hidden since it was returned still works.
+  database_test.hide_row("v1", &row_ids[2]);
+  let page = database_test
+    .get_rows_after("v1", page.next_cursor.as_ref(), 2)
+    .await
+    .unwrap();
+  assert_eq!(ids(page.rows), vec![row_ids[4].clone()]);
+  assert_eq!(page.next_cursor, None);
+
+  let mut pages = database_test.paginate_rows("v1", 1);
+  let mut paginated_row_ids = vec![];
+  while let Some(rows) = pages.next_page().await {
+    paginated_row_ids.extend(ids(rows.unwrap()));
+  }
+  assert_eq!(
+    paginated_row_ids,
+    vec![row_ids[0].clone(), row_ids[4].clone()]
+  );
+}
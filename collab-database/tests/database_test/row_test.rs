@@ -4,11 +4,13 @@ use crate::database_test::helper::{
 use collab_database::database::gen_row_id;
 use collab_database::entity::{CreateViewParams, FileUploadType};
 use collab_database::rows::{
-  meta_id_from_row_id, CoverType, CreateRowParams, RowCover, RowId, RowMetaKey,
+  meta_id_from_row_id, Cells, CoverType, CreateRowParams, RowCover, RowId, RowMetaKey,
 };
-use collab_database::views::OrderObjectPosition;
+use collab_database::views::{DuplicateRowPosition, FieldOrder, OrderObjectPosition};
 use uuid::Uuid;
 
+use crate::helper::TestTextCell;
+
 #[tokio::test]
 async fn create_row_shared_by_two_view_test() {
   let database_id = uuid::Uuid::new_v4().to_string();
@@ -240,20 +242,23 @@ async fn duplicate_row_test() {
   let second_row_id = database_test.pre_define_row_ids[1].clone();
   let third_row_id = database_test.pre_define_row_ids[2].clone();
 
-  let params = database_test.duplicate_row(&second_row_id).await.unwrap();
-  let (index, row_order) = database_test
-    .create_row_in_view("v1", params)
+  let duplicated = database_test
+    .duplicate_row(&second_row_id, DuplicateRowPosition::After)
     .await
     .unwrap();
-  assert_eq!(index, 2);
+  assert_ne!(duplicated.row.id, second_row_id);
+  assert_eq!(
+    database_test.index_of_row("v1", &duplicated.row.id),
+    Some(2)
+  );
 
   let rows = database_test.get_rows_for_view("v1").await;
   assert_eq!(rows.len(), 4);
-
   assert_eq!(rows[0].id, first_row_id);
   assert_eq!(rows[1].id, second_row_id);
-  assert_eq!(rows[2].id, row_order.id);
+  assert_eq!(rows[2].id, duplicated.row.id);
   assert_eq!(rows[3].id, third_row_id);
+  assert_eq!(rows[2].cells, rows[1].cells);
 }
 
 #[tokio::test]
@@ -263,19 +268,77 @@ async fn duplicate_last_row_test() {
   let rows = database_test.get_rows_for_view("v1").await;
   assert_eq!(rows.len(), 3);
 
-  let params = database_test
-    .duplicate_row(&database_test.pre_define_row_ids[2].clone())
-    .await
-    .unwrap();
-  let (index, row_order) = database_test
-    .create_row_in_view("v1", params)
+  let last_row_id = database_test.pre_define_row_ids[2].clone();
+  let duplicated = database_test
+    .duplicate_row(&last_row_id, DuplicateRowPosition::After)
     .await
     .unwrap();
-  assert_eq!(index, 3);
+  assert_eq!(
+    database_test.index_of_row("v1", &duplicated.row.id),
+    Some(3)
+  );
 
   let rows = database_test.get_rows_for_view("v1").await;
   assert_eq!(rows.len(), 4);
-  assert_eq!(rows[3].id, row_order.id);
+  assert_eq!(rows[3].id, duplicated.row.id);
+}
+
+#[tokio::test]
+async fn duplicate_row_in_all_views_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database_with_default_data(1, &database_id).await;
+  database_test
+    .create_linked_view(CreateViewParams {
+      database_id: database_id.clone(),
+      view_id: "v2".to_string(),
+      ..Default::default()
+    })
+    .unwrap();
+  let first_row_id = database_test.pre_define_row_ids[0].clone();
+  // The meta of a row can only be set when its id is a uuid.
+  let source_row_id = gen_row_id();
+  database_test
+    .create_row(
+      CreateRowParams::new(source_row_id.clone(), database_id.clone()).with_cells(Cells::from([(
+        "f1".to_string(),
+        TestTextCell::from("launch").into(),
+      )])),
+    )
+    .await
+    .unwrap();
+  // The rows are ordered differently in the second view.
+  database_test.update_database_view("v2", |update| {
+    update.move_row_order(&source_row_id, &first_row_id);
+  });
+  database_test
+    .update_row_meta(&source_row_id, |meta| {
+      meta.insert_icon("🚀").update_attachment_count(2);
+    })
+    .await;
+
+  let duplicated = database_test
+    .duplicate_row(&source_row_id, DuplicateRowPosition::Before)
+    .await
+    .unwrap();
+  for view_id in ["v1", "v2"] {
+    let source_index = database_test.index_of_row(view_id, &source_row_id).unwrap();
+    assert_eq!(
+      database_test.index_of_row(view_id, &duplicated.row.id),
+      Some(source_index - 1)
+    );
+  }
+  assert_eq!(duplicated.meta.icon_url.as_deref(), Some("🚀"));
+  assert_eq!(duplicated.meta.attachment_count, 2);
+  assert!(duplicated.meta.is_document_empty);
+  assert_ne!(
+    duplicated.document_id,
+    database_test.get_row_document_id(&source_row_id).unwrap()
+  );
+
+  assert!(database_test
+    .duplicate_row(&gen_row_id(), DuplicateRowPosition::After)
+    .await
+    .is_err());
 }
 
 #[tokio::test]
@@ -404,3 +467,36 @@ async fn validate_row_test() {
   let row = create_row(1, &workspace_id, RowId::from(1));
   row.validate().unwrap();
 }
+
+#[tokio::test]
+async fn row_cells_in_field_order_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database(1, &database_id);
+  let row_id = gen_row_id();
+  let params = CreateRowParams::new(row_id.clone(), database_id.clone()).with_cells(Cells::from([
+    ("f1".into(), TestTextCell::from("hello").into()),
+    ("f3".into(), TestTextCell::from("world").into()),
+  ]));
+  database_test.create_row(params).await.unwrap();
+
+  let row = database_test.get_row(&row_id).await;
+  assert_eq!(
+    TestTextCell::from(row.get_cell("f1").cloned().unwrap()).0,
+    "hello"
+  );
+  assert!(row.get_cell("f2").is_none());
+
+  let field_orders = ["f3", "f2", "f1"]
+    .into_iter()
+    .map(|id| FieldOrder::new(id.to_string()))
+    .collect::<Vec<_>>();
+  let texts = row
+    .cells_in_field_order(&field_orders)
+    .into_iter()
+    .map(|cell| cell.cloned().map(|cell| TestTextCell::from(cell).0))
+    .collect::<Vec<_>>();
+  assert_eq!(
+    texts,
+    vec![Some("world".to_string()), None, Some("hello".to_string())]
+  );
+}
@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+use collab::core::collab_search::{IndexContentType, SearchQuery, WorkspaceSearchIndex};
+use collab_database::database::gen_row_id;
+use collab_database::rows::{Cells, CreateRowParams};
+use tokio::time::{sleep, timeout};
+
+use crate::database_test::helper::create_database_with_default_data;
+use crate::helper::TestTextCell;
+
+fn search_ids(index: &WorkspaceSearchIndex, query: &str) -> Vec<String> {
+  index
+    .search(&SearchQuery::new(query))
+    .into_iter()
+    .map(|content| content.id)
+    .collect()
+}
+
+async fn wait_for_search(index: &WorkspaceSearchIndex, query: &str, expected: Vec<String>) {
+  timeout(Duration::from_secs(5), async {
+    while search_ids(index, query) != expected {
+      sleep(Duration::from_millis(50)).await;
+    }
+  })
+  .await
+  .unwrap_or_else(|_| panic!("search {} timeout", query));
+}
+
+#[tokio::test]
+async fn index_database_rows_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let database_test = create_database_with_default_data(1, &database_id).await;
+  let index = WorkspaceSearchIndex::new();
+  database_test.subscribe_search_index(index.clone()).await;
+
+  let row_1 = database_test.pre_define_row_ids[0].to_string();
+  let contents = index.search(&SearchQuery::new("1F1CELL"));
+  assert_eq!(contents.len(), 1);
+  assert_eq!(contents[0].object_id, database_id);
+  assert_eq!(contents[0].id, row_1);
+  assert_eq!(contents[0].content_type, IndexContentType::DatabaseRow);
+  // Only the cells of the text fields are indexed.
+  assert!(search_ids(&index, "2f2cell").is_empty());
+  assert_eq!(
+    index.search(&SearchQuery::new("cell").with_limit(2)).len(),
+    2
+  );
+  assert!(index
+    .search(&SearchQuery::new("cell").with_content_type(IndexContentType::Document))
+    .is_empty());
+}
+
+#[tokio::test]
+async fn update_index_on_row_change_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database_with_default_data(1, &database_id).await;
+  let index = WorkspaceSearchIndex::new();
+  database_test.subscribe_search_index(index.clone()).await;
+  let row_2 = database_test.pre_define_row_ids[1].clone();
+  let row_3 = database_test.pre_define_row_ids[2].clone();
+
+  database_test
+    .update_row(row_2.clone(), |row| {
+      row.update_cells(|cells| {
+        cells.insert_cell("f1", TestTextCell::from("search index").into());
+      });
+    })
+    .await;
+  wait_for_search(&index, "index", vec![row_2.to_string()]).await;
+  assert!(search_ids(&index, "2f1cell").is_empty());
+
+  let row_4 = gen_row_id();
+  database_test
+    .create_row(
+      CreateRowParams::new(row_4.clone(), database_id.clone()).with_cells(Cells::from([(
+        "f1".into(),
+        TestTextCell::from("new row").into(),
+      )])),
+    )
+    .await
+    .unwrap();
+  wait_for_search(&index, "new row", vec![row_4.to_string()]).await;
+
+  database_test.remove_row(&row_3).await;
+  wait_for_search(&index, "3f1cell", vec![]).await;
+  assert_eq!(index.content_ids(&database_id).len(), 3);
+}
@@ -0,0 +1,125 @@
+use collab_database::entity::FieldType;
+use collab_database::fields::select_type_option::{
+  MultiSelectTypeOption, SelectOption, SelectOptionCleanup, SelectOptionCleanupSummary,
+  SelectOptionIds, SelectTypeOption,
+};
+use collab_database::fields::text_type_option::RichTextTypeOption;
+use collab_database::fields::Field;
+use collab_database::rows::{new_cell_builder, CreateRowParams, RowId};
+use collab_database::template::entity::CELL_DATA;
+use collab_database::views::OrderObjectPosition;
+
+use crate::database_test::helper::{
+  create_database, default_field_settings_by_layout, DatabaseTest,
+};
+
+async fn selected_ids(database_test: &DatabaseTest, row_id: i32) -> Vec<String> {
+  let cell = database_test.get_cell("tags", &row_id.into()).await.cell;
+  cell
+    .map(|cell| SelectOptionIds::from(&cell).into_inner())
+    .unwrap_or_default()
+}
+
+#[tokio::test]
+async fn audit_and_cleanup_select_options_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database(1, &database_id);
+  let options = vec![
+    SelectOption::new("A"),
+    SelectOption::new("B"),
+    SelectOption::new("C"),
+  ];
+  let (a, b, c) = (&options[0].id, &options[1].id, &options[2].id);
+  let field = Field::new(
+    "tags".to_string(),
+    "Tags".to_string(),
+    FieldType::MultiSelect.into(),
+    false,
+  )
+  .with_type_option_data(
+    FieldType::MultiSelect.type_id(),
+    MultiSelectTypeOption(SelectTypeOption::new(options.clone(), false)).into(),
+  );
+  database_test.create_field(
+    None,
+    field,
+    &OrderObjectPosition::default(),
+    default_field_settings_by_layout(),
+  );
+
+  let cells = [
+    format!("{},missing-x", a),
+    a.to_string(),
+    format!("missing-y,{}", b),
+  ];
+  for (index, cell_data) in cells.iter().enumerate() {
+    let mut cell = new_cell_builder(FieldType::MultiSelect);
+    cell.insert(CELL_DATA.into(), cell_data.clone().into());
+    let params = CreateRowParams::new(index as i32 + 10, database_id.clone())
+      .with_cells([("tags".to_string(), cell)].into_iter().collect());
+    database_test.create_row(params).await.unwrap();
+  }
+
+  let audit = database_test.audit_select_options("tags").await.unwrap();
+  let usages = audit
+    .usages
+    .iter()
+    .map(|usage| (usage.option.id.clone(), usage.num_of_cells))
+    .collect::<Vec<_>>();
+  assert_eq!(usages, vec![(a.clone(), 2), (b.clone(), 1), (c.clone(), 0)]);
+  assert_eq!(audit.unused_options()[0].id, *c);
+  assert_eq!(
+    audit.orphaned_option_ids.get("missing-x"),
+    Some(&vec![RowId::from(10)])
+  );
+  assert_eq!(
+    audit.orphaned_option_ids.get("missing-y"),
+    Some(&vec![RowId::from(12)])
+  );
+
+  let summary = database_test
+    .cleanup_select_options(
+      "tags",
+      SelectOptionCleanup {
+        remove_orphaned_ids: true,
+        remove_unused_options: true,
+      },
+    )
+    .await
+    .unwrap();
+  assert_eq!(
+    summary,
+    SelectOptionCleanupSummary {
+      cleaned_cells: 2,
+      removed_option_ids: vec![c.clone()],
+    }
+  );
+  assert_eq!(selected_ids(&database_test, 10).await, vec![a.clone()]);
+  assert_eq!(selected_ids(&database_test, 11).await, vec![a.clone()]);
+  assert_eq!(selected_ids(&database_test, 12).await, vec![b.clone()]);
+
+  let audit = database_test.audit_select_options("tags").await.unwrap();
+  assert!(!audit.has_orphaned_option_ids());
+  assert_eq!(audit.usages.len(), 2);
+}
+
+#[tokio::test]
+async fn audit_non_select_field_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database(1, &database_id);
+  let field = Field::new(
+    "text".to_string(),
+    "Text".to_string(),
+    FieldType::RichText.into(),
+    false,
+  )
+  .with_type_option_data(FieldType::RichText.type_id(), RichTextTypeOption.into());
+  database_test.create_field(
+    None,
+    field,
+    &OrderObjectPosition::default(),
+    default_field_settings_by_layout(),
+  );
+  assert!(database_test.audit_select_options("text").await.is_err());
+  assert!(database_test.audit_select_options("unknown").await.is_err());
+}
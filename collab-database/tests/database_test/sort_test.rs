@@ -1,7 +1,10 @@
 use crate::database_test::helper::{create_database_with_default_data, DatabaseTest};
 use crate::helper::{SortCondition, TestSort};
+use collab::preclude::{Any, Array, ArrayRef, Map, MapExt, MapRef};
+use collab::util::AnyMapExt;
 use collab_database::entity::CreateViewParams;
-use collab_database::views::DatabaseLayout;
+use collab_database::views::{DatabaseLayout, SORT_PRIORITY};
+use collab_entity::define::DATABASE;
 
 #[tokio::test]
 async fn create_database_view_with_sort_test() {
@@ -88,6 +91,105 @@ async fn reorder_database_view_sort_test() {
   assert_eq!(sorts[1].id, "s1");
 }
 
+#[tokio::test]
+async fn move_database_view_sort_forward_test() {
+  let mut database_test = create_database_with_two_sorts().await;
+  database_test.move_sort("v1", "s1", "s2");
+  assert_eq!(sort_ids(&database_test), vec!["s2", "s1"]);
+}
+
+#[tokio::test]
+async fn insert_database_view_sort_at_test() {
+  let mut database_test = create_database_with_two_sorts().await;
+  database_test.insert_sort_at("v1", test_sort("s3"), 1);
+  assert_eq!(sort_ids(&database_test), vec!["s1", "s3", "s2"]);
+
+  // Insert an existing sort moves it
+  database_test.insert_sort_at("v1", test_sort("s2"), 0);
+  assert_eq!(sort_ids(&database_test), vec!["s2", "s1", "s3"]);
+
+  database_test.insert_sort_at("v1", test_sort("s4"), 100);
+  assert_eq!(sort_ids(&database_test), vec!["s2", "s1", "s3", "s4"]);
+  assert_eq!(sort_priorities(&database_test), vec![0, 1, 2, 3]);
+}
+
+#[tokio::test]
+async fn reorder_database_view_sorts_test() {
+  let mut database_test = create_database_with_two_sorts().await;
+  database_test.insert_sort("v1", test_sort("s3"));
+  database_test.insert_sort("v1", test_sort("s4"));
+  assert_eq!(sort_priorities(&database_test), vec![0, 1, 2, 3]);
+
+  database_test.reorder_sorts("v1", &["s3", "unknown", "s1", "s3"]);
+  assert_eq!(sort_ids(&database_test), vec!["s3", "s1", "s2", "s4"]);
+  assert_eq!(sort_priorities(&database_test), vec![0, 1, 2, 3]);
+
+  database_test.remove_sort("v1", "s3");
+  assert_eq!(sort_ids(&database_test), vec!["s1", "s2", "s4"]);
+  assert_eq!(sort_priorities(&database_test), vec![0, 1, 2]);
+}
+
+#[tokio::test]
+async fn database_view_sorts_in_stored_priority_order_test() {
+  let mut database_test = create_database_with_two_sorts().await;
+  database_test.insert_sort("v1", test_sort("s3"));
+  {
+    // The priorities are stored in the sorts
+    let collab = &mut database_test.collab;
+    let data = collab.data.clone();
+    let mut txn = collab.transact_mut();
+    let sorts: ArrayRef = data
+      .get_with_path(&txn, [DATABASE, "views", "v1", "sorts"])
+      .unwrap();
+    let priority = |txn: &_, index: u32| {
+      sorts
+        .get(txn, index)
+        .unwrap()
+        .cast::<MapRef>()
+        .unwrap()
+        .get_with_txn::<_, i64>(txn, SORT_PRIORITY)
+        .unwrap()
+    };
+    assert_eq!(priority(&txn, 0), 0);
+    assert_eq!(priority(&txn, 2), 2);
+
+    // Another client moved the last sort first, which only changed the priorities
+    for (index, priority) in [(0, 1), (1, 2), (2, 0)] {
+      let map_ref = sorts.get(&txn, index).unwrap().cast::<MapRef>().unwrap();
+      map_ref.insert(&mut txn, SORT_PRIORITY, Any::BigInt(priority));
+    }
+  }
+  assert_eq!(sort_ids(&database_test), vec!["s3", "s1", "s2"]);
+  assert_eq!(sort_priorities(&database_test), vec![0, 1, 2]);
+}
+
+fn test_sort(id: &str) -> TestSort {
+  TestSort {
+    id: id.to_string(),
+    field_id: "f1".to_string(),
+    field_type: Default::default(),
+    condition: SortCondition::Ascending,
+  }
+}
+
+fn sort_ids(database_test: &DatabaseTest) -> Vec<String> {
+  database_test
+    .get_all_sorts::<TestSort>("v1")
+    .into_iter()
+    .map(|sort| sort.id)
+    .collect()
+}
+
+fn sort_priorities(database_test: &DatabaseTest) -> Vec<i64> {
+  database_test
+    .get_view("v1")
+    .unwrap()
+    .sorts
+    .iter()
+    .map(|sort| sort.get_as::<i64>(SORT_PRIORITY).unwrap())
+    .collect()
+}
+
 async fn create_database_with_two_sorts() -> DatabaseTest {
   let database_id = uuid::Uuid::new_v4();
   let mut database_test = create_database_with_default_data(1, &database_id.to_string()).await;
@@ -0,0 +1,92 @@
+use collab_database::entity::FieldType;
+use collab_database::fields::time_type_option::{DurationFormat, TimeCellData, TimeTypeOption};
+use collab_database::fields::{Field, StringifyTypeOption};
+use collab_database::rows::{Cell, CreateRowParams};
+use collab_database::views::{CalculationType, OrderObjectPosition};
+
+use crate::database_test::helper::{create_database, default_field_settings_by_layout};
+
+#[test]
+fn format_and_parse_duration_test() {
+  let seconds = 3600 + 30 * 60 + 15;
+  let format = |format: DurationFormat| TimeTypeOption::new(format).format_duration(seconds);
+  assert_eq!(format(DurationFormat::HoursMinutes), "1:30");
+  assert_eq!(format(DurationFormat::HoursMinutesSeconds), "1:30:15");
+  assert_eq!(format(DurationFormat::DecimalHours), "1.5");
+  assert_eq!(TimeTypeOption::default().format_duration(-90 * 60), "-1:30");
+
+  assert_eq!(TimeTypeOption::parse_duration("1:30"), Some(5400));
+  assert_eq!(TimeTypeOption::parse_duration("1:30:15"), Some(seconds));
+  assert_eq!(TimeTypeOption::parse_duration("1.5"), Some(5400));
+  assert_eq!(TimeTypeOption::parse_duration("-0:45"), Some(-2700));
+  assert_eq!(TimeTypeOption::parse_duration("1:75"), None);
+  assert_eq!(TimeTypeOption::parse_duration("abc"), None);
+}
+
+#[test]
+fn stringify_legacy_time_text_test() {
+  let type_option = TimeTypeOption::new(DurationFormat::HoursMinutes);
+  assert_eq!(type_option.stringify_text("5400"), "1:30");
+  // The free text of the older time cells is kept.
+  assert_eq!(type_option.stringify_text("about an hour"), "about an hour");
+  assert_eq!(type_option.stringify_text(""), "");
+}
+
+#[test]
+fn calculate_durations_test() {
+  let cells = [Some(3600), Some(1800), None]
+    .iter()
+    .map(|seconds| seconds.map(|seconds| Cell::from(&TimeCellData::new(seconds))))
+    .chain([None])
+    .collect::<Vec<_>>();
+  let type_option = TimeTypeOption::new(DurationFormat::HoursMinutes);
+  assert_eq!(type_option.calculate(CalculationType::Sum, &cells), "1:30");
+  assert_eq!(
+    type_option.calculate(CalculationType::Average, &cells),
+    "0:45"
+  );
+  assert_eq!(type_option.calculate(CalculationType::Max, &cells), "1:00");
+  assert_eq!(type_option.calculate(CalculationType::Count, &cells), "4");
+  assert_eq!(
+    type_option.calculate(CalculationType::CountNonEmpty, &cells),
+    "2"
+  );
+  assert_eq!(type_option.calculate(CalculationType::Sum, &[]), "");
+}
+
+#[tokio::test]
+async fn stringify_time_cell_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database(1, &database_id);
+  let field = Field::new(
+    "time".to_string(),
+    "Time spent".to_string(),
+    FieldType::Time.into(),
+    false,
+  )
+  .with_type_option_data(
+    FieldType::Time.type_id(),
+    TimeTypeOption::new(DurationFormat::DecimalHours).into(),
+  );
+  database_test.create_field(
+    None,
+    field,
+    &OrderObjectPosition::default(),
+    default_field_settings_by_layout(),
+  );
+
+  let row_id = uuid::Uuid::new_v4().to_string();
+  let cell = Cell::from(&TimeCellData::new(2 * 3600 + 15 * 60));
+  let row = CreateRowParams::new(row_id.clone(), database_id)
+    .with_cells([("time".to_string(), cell)].into_iter().collect());
+  database_test.create_row(row).await.unwrap();
+
+  let cell = database_test
+    .get_cell("time", &row_id.into())
+    .await
+    .cell
+    .unwrap();
+  assert_eq!(TimeCellData::from(&cell).seconds, Some(8100));
+  let type_option = database_test.get_stringify_type_option("time").unwrap();
+  assert_eq!(type_option.stringify_cell(&cell), "2.25");
+}
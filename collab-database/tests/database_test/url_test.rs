@@ -0,0 +1,117 @@
+use async_trait::async_trait;
+use collab_database::entity::FieldType;
+use collab_database::error::DatabaseError;
+use collab_database::fields::url_type_option::{
+  URLCellData, URLMetadata, URLMetadataProvider, URLTypeOption,
+};
+use collab_database::fields::Field;
+use collab_database::rows::{Cell, CreateRowParams, RowId};
+use collab_database::views::OrderObjectPosition;
+
+use crate::database_test::helper::{
+  create_database, default_field_settings_by_layout, DatabaseTest,
+};
+
+struct TestURLMetadataProvider;
+
+#[async_trait]
+impl URLMetadataProvider for TestURLMetadataProvider {
+  async fn fetch_metadata(&self, url: &str) -> Result<URLMetadata, DatabaseError> {
+    Ok(URLMetadata {
+      title: Some(format!("Title of {}", url)),
+      favicon: Some(format!("{}/favicon.ico", url)),
+    })
+  }
+}
+
+async fn create_url_database(url: &str) -> (DatabaseTest, RowId) {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database(1, &database_id);
+  let field = Field::new(
+    "url".to_string(),
+    "Link".to_string(),
+    FieldType::URL.into(),
+    false,
+  )
+  .with_type_option_data(FieldType::URL.type_id(), URLTypeOption::default().into());
+  database_test.create_field(
+    None,
+    field,
+    &OrderObjectPosition::default(),
+    default_field_settings_by_layout(),
+  );
+
+  let row_id = RowId::from(uuid::Uuid::new_v4().to_string());
+  let cell = Cell::from(URLCellData::new(url));
+  let row = CreateRowParams::new(row_id.clone(), database_id)
+    .with_cells([("url".to_string(), cell)].into_iter().collect());
+  database_test.create_row(row).await.unwrap();
+  (database_test, row_id)
+}
+
+async fn get_url_cell(database_test: &DatabaseTest, row_id: &RowId) -> URLCellData {
+  let cell = database_test.get_cell("url", row_id).await.cell.unwrap();
+  URLCellData::from(&cell)
+}
+
+#[test]
+fn url_cell_metadata_test() {
+  let metadata = URLMetadata {
+    title: Some("AppFlowy".to_string()),
+    favicon: None,
+  };
+  let cell_data = URLCellData::new("https://appflowy.io");
+  assert_eq!(cell_data.metadata(), None);
+  assert!(cell_data.needs_enrichment(100, 60));
+  assert!(!URLCellData::new(" ").needs_enrichment(100, 60));
+
+  let cell_data = cell_data.with_metadata(metadata.clone(), 100);
+  assert_eq!(cell_data.metadata(), Some(metadata));
+  assert!(!cell_data.needs_enrichment(150, 60));
+  assert!(cell_data.needs_enrichment(200, 60));
+  assert_eq!(URLCellData::from(&Cell::from(cell_data.clone())), cell_data);
+
+  // The cells written before the metadata existed are still read.
+  let json = r#"{"data":"https://appflowy.io"}"#;
+  let cell_data = serde_json::from_str::<URLCellData>(json).unwrap();
+  assert_eq!(cell_data, URLCellData::new("https://appflowy.io"));
+  assert_eq!(cell_data.to_string(), json);
+}
+
+#[tokio::test]
+async fn enrich_url_cell_test() {
+  let (mut database_test, row_id) = create_url_database("https://appflowy.io").await;
+  let cell_data = database_test
+    .enrich_url_cell(&row_id, "url", &TestURLMetadataProvider)
+    .await
+    .unwrap()
+    .unwrap();
+  assert_eq!(
+    cell_data.title.as_deref(),
+    Some("Title of https://appflowy.io")
+  );
+  assert!(cell_data.last_verified_at.is_some());
+  assert_eq!(get_url_cell(&database_test, &row_id).await, cell_data);
+
+  // Replacing the link clears the metadata of the previous link.
+  database_test
+    .update_row(row_id.clone(), |row| {
+      row.update_cells(|cells| {
+        cells.insert_cell("url", Cell::from(URLCellData::new("https://github.com")));
+      });
+    })
+    .await;
+  let cell_data = get_url_cell(&database_test, &row_id).await;
+  assert_eq!(cell_data, URLCellData::new("https://github.com"));
+}
+
+#[tokio::test]
+async fn enrich_empty_url_cell_test() {
+  let (database_test, row_id) = create_url_database("").await;
+  let cell_data = database_test
+    .enrich_url_cell(&row_id, "url", &TestURLMetadataProvider)
+    .await
+    .unwrap();
+  assert!(cell_data.is_none());
+  assert_eq!(get_url_cell(&database_test, &row_id).await.metadata(), None);
+}
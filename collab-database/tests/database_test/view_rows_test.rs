@@ -0,0 +1,237 @@
+use std::cmp::Ordering;
+use std::time::Duration;
+
+use collab_database::database::gen_row_id;
+use collab_database::database_view_rows::{AllRows, RowsChanged, ViewRowsQuery, VisibleRowChange};
+use collab_database::rows::{Cells, CreateRowParams, Row, RowId};
+use futures::{Stream, StreamExt};
+use tokio::time::timeout;
+
+use crate::database_test::helper::create_database_with_default_data;
+use crate::helper::TestTextCell;
+
+/// Shows the rows whose f1 cell contains "cell", sorted by the f1 cell in descending order.
+struct TextCellQuery;
+
+fn text_cell(row: &Row) -> String {
+  row
+    .cells
+    .get("f1")
+    .map(|cell| TestTextCell::from(cell.clone()).0)
+    .unwrap_or_default()
+}
+
+impl ViewRowsQuery for TextCellQuery {
+  fn is_visible(&self, row: &Row) -> bool {
+    text_cell(row).contains("cell")
+  }
+
+  fn compare(&self, left: &Row, right: &Row) -> Ordering {
+    text_cell(right).cmp(&text_cell(left))
+  }
+}
+
+async fn next_changes(stream: &mut (impl Stream<Item = RowsChanged> + Unpin)) -> RowsChanged {
+  timeout(Duration::from_secs(5), stream.next())
+    .await
+    .expect("rows changed timeout")
+    .expect("stream closed")
+}
+
+fn apply_changes(row_ids: &mut Vec<RowId>, changed: &RowsChanged) {
+  for change in &changed.changes {
+    match change {
+      VisibleRowChange::Inserted { index, row } => row_ids.insert(*index, row.id.clone()),
+      VisibleRowChange::Removed { index, row_id } => {
+        assert_eq!(&row_ids.remove(*index), row_id);
+      },
+      VisibleRowChange::Moved { row_id, from, to } => {
+        assert_eq!(&row_ids.remove(*from), row_id);
+        row_ids.insert(*to, row_id.clone());
+      },
+      VisibleRowChange::Updated { index, row } => assert_eq!(row_ids[*index], row.id),
+    }
+  }
+}
+
+#[tokio::test]
+async fn filtered_and_sorted_view_rows_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database_with_default_data(1, &database_id).await;
+  let row_1 = database_test.pre_define_row_ids[0].clone();
+  let row_2 = database_test.pre_define_row_ids[1].clone();
+  let row_3 = database_test.pre_define_row_ids[2].clone();
+  let mut stream = Box::pin(
+    database_test
+      .subscribe_view_rows("v1", TextCellQuery)
+      .await
+      .unwrap(),
+  );
+
+  let mut visible = vec![];
+  let changed = next_changes(&mut stream).await;
+  assert_eq!(changed.view_id, "v1");
+  apply_changes(&mut visible, &changed);
+  assert_eq!(visible, vec![row_3.clone(), row_2.clone(), row_1.clone()]);
+
+  // The row is filtered out.
+  database_test
+    .update_row(row_2.clone(), |row| {
+      row.update_cells(|cells| {
+        cells.insert_cell("f1", TestTextCell::from("hidden").into());
+      });
+    })
+    .await;
+  let changed = next_changes(&mut stream).await;
+  assert_eq!(
+    changed.changes,
+    vec![VisibleRowChange::Removed {
+      index: 1,
+      row_id: row_2.clone(),
+    }]
+  );
+  apply_changes(&mut visible, &changed);
+
+  // The row moves to the top and its new cells are sent.
+  database_test
+    .update_row(row_1.clone(), |row| {
+      row.update_cells(|cells| {
+        cells.insert_cell("f1", TestTextCell::from("9f1cell").into());
+      });
+    })
+    .await;
+  let changed = next_changes(&mut stream).await;
+  assert_eq!(changed.changes.len(), 2);
+  assert_eq!(
+    changed.changes[0],
+    VisibleRowChange::Moved {
+      row_id: row_1.clone(),
+      from: 1,
+      to: 0,
+    }
+  );
+  match &changed.changes[1] {
+    VisibleRowChange::Updated { index, row } => {
+      assert_eq!(*index, 0);
+      assert_eq!(text_cell(row), "9f1cell");
+    },
+    change => panic!("unexpected change: {:?}", change),
+  }
+  apply_changes(&mut visible, &changed);
+  assert_eq!(visible, vec![row_1.clone(), row_3.clone()]);
+
+  // The new row is inserted at its sorted position.
+  let row_4 = gen_row_id();
+  database_test
+    .create_row(
+      CreateRowParams::new(row_4.clone(), database_id.clone()).with_cells(Cells::from([(
+        "f1".into(),
+        TestTextCell::from("4f1cell").into(),
+      )])),
+    )
+    .await
+    .unwrap();
+  let changed = next_changes(&mut stream).await;
+  assert_eq!(changed.changes.len(), 1);
+  assert!(matches!(
+    &changed.changes[0],
+    VisibleRowChange::Inserted { index: 1, row } if row.id == row_4
+  ));
+  apply_changes(&mut visible, &changed);
+  assert_eq!(visible, vec![row_1, row_4, row_3]);
+}
+
+#[tokio::test]
+async fn view_rows_follow_row_orders_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database_with_default_data(1, &database_id).await;
+  let row_1 = database_test.pre_define_row_ids[0].clone();
+  let row_2 = database_test.pre_define_row_ids[1].clone();
+  let row_3 = database_test.pre_define_row_ids[2].clone();
+  let mut stream = Box::pin(
+    database_test
+      .subscribe_view_rows("v1", AllRows)
+      .await
+      .unwrap(),
+  );
+
+  let mut visible = vec![];
+  apply_changes(&mut visible, &next_changes(&mut stream).await);
+  assert_eq!(visible, vec![row_1.clone(), row_2.clone(), row_3.clone()]);
+
+  database_test.move_row(&row_1, &row_3).await;
+  let changed = next_changes(&mut stream).await;
+  assert_eq!(changed.changes.len(), 1);
+  apply_changes(&mut visible, &changed);
+  let row_ids = database_test
+    .get_row_orders_for_view("v1")
+    .into_iter()
+    .map(|row_order| row_order.id)
+    .collect::<Vec<_>>();
+  assert_eq!(visible, row_ids);
+
+  database_test.remove_row(&row_2).await;
+  let changed = next_changes(&mut stream).await;
+  apply_changes(&mut visible, &changed);
+  assert!(!visible.contains(&row_2));
+  assert_eq!(visible.len(), 2);
+}
+
+#[tokio::test]
+async fn hidden_rows_are_not_visible_test() {
+  let database_id = uuid::Uuid::new_v4().to_string();
+  let mut database_test = create_database_with_default_data(1, &database_id).await;
+  let row_1 = database_test.pre_define_row_ids[0].clone();
+  let row_2 = database_test.pre_define_row_ids[1].clone();
+  let row_3 = database_test.pre_define_row_ids[2].clone();
+  database_test.hide_row("v1", &row_1);
+  let mut stream = Box::pin(
+    database_test
+      .subscribe_view_rows("v1", AllRows)
+      .await
+      .unwrap(),
+  );
+
+  let mut visible = vec![];
+  apply_changes(&mut visible, &next_changes(&mut stream).await);
+  assert_eq!(visible, vec![row_2.clone(), row_3.clone()]);
+
+  database_test.hide_row("v1", &row_3);
+  let changed = next_changes(&mut stream).await;
+  assert_eq!(
+    changed.changes,
+    vec![VisibleRowChange::Removed {
+      index: 1,
+      row_id: row_3.clone(),
+    }]
+  );
+
+  database_test.unhide_row("v1", &row_1);
+  apply_changes(&mut visible, &changed);
+  apply_changes(&mut visible, &next_changes(&mut stream).await);
+  assert_eq!(visible, vec![row_1.clone(), row_2.clone()]);
+
+  // The row is only hidden in the view, it's not deleted.
+  assert_eq!(database_test.get_hidden_rows("v1"), [row_3.clone()].into());
+  assert!(database_test.contains_row("v1", &row_3));
+  let duplicated_view = database_test.duplicate_linked_view("v1").unwrap();
+  assert_eq!(
+    database_test.get_hidden_rows(&duplicated_view.id),
+    [row_3.clone()].into()
+  );
+
+  // The hidden rows are not listed in the rows of the view, and are no longer hidden once
+  // removed.
+  let row_ids = database_test
+    .get_rows_for_view("v1")
+    .await
+    .into_iter()
+    .map(|row| row.id)
+    .collect::<Vec<_>>();
+  assert_eq!(row_ids, vec![row_1, row_2]);
+  database_test.remove_row(&row_3).await;
+  assert!(database_test.get_hidden_rows("v1").is_empty());
+  assert!(database_test
+    .get_hidden_rows(&duplicated_view.id)
+    .is_empty());
+}
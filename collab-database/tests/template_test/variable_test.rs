@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+
+use collab::util::AnyMapExt;
+use collab_database::database::gen_database_id;
+use collab_database::entity::{CreateDatabaseParams, CreateViewParams, FieldType};
+use collab_database::fields::select_type_option::{SelectOption, SelectTypeOption};
+use collab_database::fields::Field;
+use collab_database::rows::{new_cell_builder, CreateRowParams};
+use collab_database::template::entity::CELL_DATA;
+use collab_database::template::variable::resolve_placeholders;
+
+fn variables() -> HashMap<String, String> {
+  HashMap::from([
+    ("project".to_string(), "Apollo".to_string()),
+    ("team".to_string(), "Team \"A\"".to_string()),
+  ])
+}
+
+#[test]
+fn resolve_placeholders_test() {
+  let variables = variables();
+  assert_eq!(
+    resolve_placeholders("{{project}} tasks", &variables),
+    "Apollo tasks"
+  );
+  assert_eq!(
+    resolve_placeholders("{{ project }}/{{team}}", &variables),
+    "Apollo/Team \"A\""
+  );
+  assert_eq!(
+    resolve_placeholders("{{unknown}} and {{project", &variables),
+    "{{unknown}} and {{project"
+  );
+}
+
+#[test]
+fn resolve_database_params_variables_test() {
+  let database_id = gen_database_id();
+  let mut status_field = Field::new(
+    "f2".to_string(),
+    "{{team}} status".to_string(),
+    FieldType::SingleSelect.into(),
+    false,
+  );
+  let type_option = SelectTypeOption::new(vec![SelectOption::new("{{project}} done")], false);
+  status_field
+    .type_options
+    .insert(FieldType::SingleSelect.type_id(), type_option.into());
+
+  let mut cell = new_cell_builder(FieldType::RichText);
+  cell.insert(CELL_DATA.into(), "Kick off {{project}}".into());
+  let row = CreateRowParams::new(gen_database_id(), database_id.clone())
+    .with_cells(HashMap::from([("f1".to_string(), cell)]));
+
+  let params = CreateDatabaseParams {
+    database_id: database_id.clone(),
+    fields: vec![
+      Field::new(
+        "f1".to_string(),
+        "{{project}} tasks".to_string(),
+        FieldType::RichText.into(),
+        true,
+      ),
+      status_field,
+    ],
+    rows: vec![row],
+    views: vec![CreateViewParams {
+      database_id,
+      view_id: "v1".to_string(),
+      name: "{{project}} board".to_string(),
+      ..Default::default()
+    }],
+  }
+  .resolve_variables(&variables());
+
+  assert_eq!(params.fields[0].name, "Apollo tasks");
+  assert_eq!(params.fields[1].name, "Team \"A\" status");
+  let type_option = SelectTypeOption::from(
+    params.fields[1]
+      .type_options
+      .get(&FieldType::SingleSelect.type_id())
+      .cloned()
+      .unwrap(),
+  );
+  assert_eq!(type_option.options[0].name, "Apollo done");
+
+  let cell = params.rows[0].cells.get("f1").unwrap();
+  assert_eq!(cell.get_as::<String>(CELL_DATA).unwrap(), "Kick off Apollo");
+  assert_eq!(params.views[0].name, "Apollo board");
+}
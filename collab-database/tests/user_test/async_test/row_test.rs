@@ -337,7 +337,6 @@ fn create_row_test_expected() -> Value {
     ],
     "views": [
       {
-        "created_at": 0,
         "database_id": "d2",
         "field_orders": [
           {
@@ -355,7 +354,6 @@ fn create_row_test_expected() -> Value {
         "id": "v1",
         "layout": 0,
         "layout_settings": {},
-        "modified_at": 0,
         "name": "my first database view",
         "row_orders": [
           {
@@ -27,6 +27,7 @@ use collab::core::origin::CollabOrigin;
 use collab::entity::EncodedCollab;
 use collab::lock::Mutex;
 use collab_database::entity::{CreateDatabaseParams, CreateViewParams};
+use collab_plugins::local_storage::kv::batch::CollabWriteBatch;
 use collab_plugins::local_storage::kv::doc::CollabKVAction;
 use collab_plugins::local_storage::kv::KVTransactionDB;
 use collab_plugins::local_storage::rocksdb::rocksdb_plugin::RocksdbDiskPlugin;
@@ -129,21 +130,14 @@ impl DatabaseCollabPersistenceService for TestUserDatabasePersistenceImpl {
     &self,
     encoded_collabs: Vec<(String, EncodedCollab)>,
   ) -> Result<(), DatabaseError> {
-    let write_txn = self.db.write_txn();
+    let mut batch = CollabWriteBatch::new(self.uid, &self.workspace_id);
     for (object_id, encode_collab) in encoded_collabs {
-      write_txn
-        .flush_doc(
-          self.uid,
-          &self.workspace_id,
-          &object_id,
-          encode_collab.state_vector.to_vec(),
-          encode_collab.doc_state.to_vec(),
-        )
-        .map_err(|e| DatabaseError::Internal(e.into()))?;
+      batch.flush_doc(&object_id, encode_collab);
     }
-
-    write_txn.commit_transaction().unwrap();
-    Ok(())
+    self
+      .db
+      .write_batch(&batch)
+      .map_err(|e| DatabaseError::Internal(e.into()))
   }
 }
 
@@ -0,0 +1,176 @@
+use collab_database::database::{gen_database_id, gen_database_view_id, gen_row_id};
+use collab_database::entity::{CreateDatabaseParams, CreateViewParams, FieldType};
+use collab_database::fields::relation_type_option::{
+  LookupTypeOption, RelationCellData, RelationTypeOption,
+};
+use collab_database::fields::Field;
+use collab_database::rows::{Cell, Cells, CreateRowParams, RowId};
+
+use crate::helper::TestTextCell;
+use crate::user_test::helper::{random_uid, workspace_database_test, WorkspaceDatabaseTest};
+
+fn database_params(
+  database_id: &str,
+  fields: Vec<Field>,
+  rows: Vec<Cells>,
+) -> CreateDatabaseParams {
+  CreateDatabaseParams {
+    database_id: database_id.to_string(),
+    fields,
+    rows: rows
+      .into_iter()
+      .map(|cells| CreateRowParams::new(gen_row_id(), database_id.to_string()).with_cells(cells))
+      .collect(),
+    views: vec![CreateViewParams {
+      database_id: database_id.to_string(),
+      view_id: gen_database_view_id(),
+      ..Default::default()
+    }],
+  }
+}
+
+async fn lookup_texts(
+  test: &WorkspaceDatabaseTest,
+  database_id: &str,
+  field_id: &str,
+  row_id: &RowId,
+) -> Vec<String> {
+  test
+    .resolve_lookup_values(database_id, field_id, row_id)
+    .await
+    .unwrap()
+    .into_iter()
+    .map(|value| value.text)
+    .collect()
+}
+
+#[tokio::test]
+async fn resolve_lookup_values_test() {
+  let mut test = workspace_database_test(random_uid()).await;
+  let tasks_id = gen_database_id();
+  let title_field = Field::new(
+    "title".to_string(),
+    "Title".to_string(),
+    FieldType::RichText.into(),
+    true,
+  );
+  let tasks_params = database_params(
+    &tasks_id,
+    vec![title_field],
+    ["design", "build", "ship"]
+      .iter()
+      .map(|title| Cells::from([("title".to_string(), TestTextCell::from(*title).into())]))
+      .collect(),
+  );
+  let task_ids = tasks_params
+    .rows
+    .iter()
+    .map(|row| row.id.clone())
+    .collect::<Vec<RowId>>();
+  test.create_database(tasks_params).await.unwrap();
+
+  let projects_id = gen_database_id();
+  let relation_field = Field::new(
+    "tasks".to_string(),
+    "Tasks".to_string(),
+    FieldType::Relation.into(),
+    false,
+  )
+  .with_type_option_data(
+    FieldType::Relation.type_id(),
+    RelationTypeOption::new(&tasks_id).into(),
+  );
+  let lookup_field = Field::new(
+    "titles".to_string(),
+    "Task titles".to_string(),
+    FieldType::Lookup.into(),
+    false,
+  )
+  .with_type_option_data(
+    FieldType::Lookup.type_id(),
+    LookupTypeOption::new("tasks", "title").into(),
+  );
+  let relation_cell = |row_ids: Vec<RowId>| {
+    Cells::from([(
+      "tasks".to_string(),
+      Cell::from(&RelationCellData { row_ids }),
+    )])
+  };
+  let projects_params = database_params(
+    &projects_id,
+    vec![relation_field, lookup_field],
+    vec![
+      relation_cell(vec![task_ids[1].clone(), task_ids[0].clone()]),
+      relation_cell(vec![task_ids[0].clone(), task_ids[2].clone()]),
+      Cells::new(),
+    ],
+  );
+  let project_ids = projects_params
+    .rows
+    .iter()
+    .map(|row| row.id.clone())
+    .collect::<Vec<RowId>>();
+  test.create_database(projects_params).await.unwrap();
+
+  // The values follow the order of the links.
+  assert_eq!(
+    lookup_texts(&test, &projects_id, "titles", &project_ids[0]).await,
+    vec!["build", "design"]
+  );
+  let metrics = test.lookup_cache_metrics();
+  assert_eq!((metrics.hits, metrics.misses, metrics.entries), (0, 2, 2));
+
+  // The value of the task linked by both projects is pulled once.
+  assert_eq!(
+    lookup_texts(&test, &projects_id, "titles", &project_ids[1]).await,
+    vec!["design", "ship"]
+  );
+  let metrics = test.lookup_cache_metrics();
+  assert_eq!((metrics.hits, metrics.misses, metrics.entries), (1, 3, 3));
+
+  // Modifying the task invalidates its cached value.
+  test
+    .get_or_init_database(&tasks_id)
+    .await
+    .unwrap()
+    .write()
+    .await
+    .update_row(task_ids[0].clone(), |row| {
+      row.update_cells(|cells| {
+        cells.insert_cell("title", TestTextCell::from("redesign").into());
+      });
+    })
+    .await;
+  assert_eq!(
+    lookup_texts(&test, &projects_id, "titles", &project_ids[0]).await,
+    vec!["build", "redesign"]
+  );
+  let metrics = test.lookup_cache_metrics();
+  assert_eq!((metrics.hits, metrics.misses), (2, 4));
+
+  // The row without linked rows.
+  assert!(lookup_texts(&test, &projects_id, "titles", &project_ids[2])
+    .await
+    .is_empty());
+
+  // The relation field is not a lookup field.
+  assert!(test
+    .resolve_lookup_values(&projects_id, "tasks", &project_ids[0])
+    .await
+    .is_err());
+
+  // The least recently used values are evicted once the cache is full.
+  test.set_lookup_cache_capacity(1);
+  let metrics = test.lookup_cache_metrics();
+  assert_eq!((metrics.entries, metrics.evictions), (1, 2));
+  assert_eq!(
+    lookup_texts(&test, &projects_id, "titles", &project_ids[0]).await,
+    vec!["build", "redesign"]
+  );
+  let metrics = test.lookup_cache_metrics();
+  assert_eq!((metrics.entries, metrics.evictions), (1, 4));
+
+  // Closing the related database drops the values pulled from its rows.
+  test.close_database(&tasks_id);
+  assert_eq!(test.lookup_cache_metrics().entries, 0);
+}
@@ -0,0 +1,134 @@
+use collab_database::workspace_database::DatabaseMemoryBudget;
+use futures::StreamExt;
+
+use crate::user_test::helper::{make_default_grid, random_uid, workspace_database_test};
+
+#[tokio::test]
+async fn evict_least_recently_used_database_test() {
+  let mut test = workspace_database_test(random_uid()).await;
+  test.set_memory_budget(DatabaseMemoryBudget::unlimited().with_max_resident_databases(2));
+
+  let mut database_ids = vec![];
+  for index in 0..3 {
+    let params = make_default_grid(&format!("v{}", index), "grid");
+    database_ids.push(params.database_id.clone());
+    test.create_database(params).await.unwrap();
+  }
+
+  // The first database is the least recently used one.
+  let metrics = test.resident_collab_metrics();
+  assert_eq!(metrics.resident.databases, 2);
+  assert_eq!(metrics.resident.rows, 6);
+  assert_eq!(metrics.evicted_databases, 1);
+  assert_eq!(metrics.evicted_rows, 3);
+
+  // Opening the first database again evicts the second one, not the third one that was used
+  // after it.
+  test.get_or_init_database(&database_ids[2]).await.unwrap();
+  let database = test.get_or_init_database(&database_ids[0]).await.unwrap();
+  let num_of_rows = database.read().await.get_all_rows(None).await.count().await;
+  assert_eq!(num_of_rows, 3);
+  drop(database);
+  assert_eq!(test.resident_collab_metrics().evicted_databases, 2);
+  let database = test.get_or_init_database(&database_ids[2]).await.unwrap();
+  assert_eq!(test.resident_collab_metrics().evicted_databases, 2);
+  drop(database);
+
+  test.close_database(&database_ids[0]);
+  assert_eq!(test.resident_collab_metrics().resident.databases, 1);
+}
+
+#[tokio::test]
+async fn evict_least_recently_used_rows_test() {
+  let mut test = workspace_database_test(random_uid()).await;
+  test.set_memory_budget(DatabaseMemoryBudget::unlimited().with_max_resident_rows(4));
+
+  let first = test
+    .create_database(make_default_grid("v1", "grid"))
+    .await
+    .unwrap();
+  let second = test
+    .create_database(make_default_grid("v2", "grid"))
+    .await
+    .unwrap();
+
+  // The rows of the first database are unloaded, both databases stay open.
+  let metrics = test.resident_collab_metrics();
+  assert_eq!(metrics.resident.databases, 2);
+  assert_eq!(metrics.resident.rows, 4);
+  assert_eq!(metrics.evicted_databases, 0);
+  assert_eq!(metrics.evicted_rows, 2);
+
+  // The evicted rows are loaded again when they're accessed.
+  let rows = first
+    .read()
+    .await
+    .get_all_rows(None)
+    .await
+    .collect::<Vec<_>>()
+    .await;
+  assert_eq!(rows.len(), 3);
+  assert!(rows.iter().all(|row| row.is_ok()));
+  assert_eq!(test.resident_collab_metrics().resident.rows, 6);
+
+  // The rows of the first database were used last, so the rows of the second one are evicted.
+  test.enforce_memory_budget();
+  let second = second.read().await;
+  let mut num_of_loaded_rows = 0;
+  for row_order in second.get_all_row_orders().await {
+    if second.get_database_row(&row_order.id).await.is_some() {
+      num_of_loaded_rows += 1;
+    }
+  }
+  assert_eq!(num_of_loaded_rows, 1);
+  assert_eq!(test.resident_collab_metrics().resident.rows, 4);
+}
+
+#[tokio::test]
+async fn evict_rows_over_bytes_budget_test() {
+  let mut test = workspace_database_test(random_uid()).await;
+  let database = test
+    .create_database(make_default_grid("v1", "grid"))
+    .await
+    .unwrap();
+  let resident = test.resident_collab_metrics().resident;
+  assert!(resident.bytes > 0);
+
+  // Only the rows can be evicted, the database is in use.
+  test.set_memory_budget(DatabaseMemoryBudget::unlimited().with_max_resident_bytes(1));
+  assert_eq!(test.enforce_memory_budget(), 0);
+  let metrics = test.resident_collab_metrics();
+  assert_eq!(metrics.resident.databases, 1);
+  assert_eq!(metrics.resident.rows, 0);
+  assert!(metrics.resident.bytes < resident.bytes);
+  assert_eq!(metrics.evicted_rows, 3);
+  drop(database);
+}
+
+#[tokio::test]
+async fn keep_databases_in_use_over_budget_test() {
+  let mut test = workspace_database_test(random_uid()).await;
+  test.set_memory_budget(DatabaseMemoryBudget::unlimited().with_max_resident_databases(1));
+
+  let first = test
+    .create_database(make_default_grid("v1", "grid"))
+    .await
+    .unwrap();
+  let second = test
+    .create_database(make_default_grid("v2", "grid"))
+    .await
+    .unwrap();
+
+  // Both databases are still used, so the budget can't be met.
+  let metrics = test.resident_collab_metrics();
+  assert_eq!(metrics.resident.databases, 2);
+  assert_eq!(metrics.evicted_databases, 0);
+
+  drop(first);
+  assert_eq!(test.enforce_memory_budget(), 1);
+  let metrics = test.resident_collab_metrics();
+  assert_eq!(metrics.resident.databases, 1);
+  assert_eq!(metrics.resident.rows, 3);
+  drop(second);
+  assert_eq!(test.enforce_memory_budget(), 0);
+}
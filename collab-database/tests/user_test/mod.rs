@@ -1,7 +1,12 @@
 mod cell_test;
 mod database_test;
 pub mod helper;
+mod lookup_test;
+mod memory_budget_test;
+mod reciprocal_relation_test;
 // mod relation_test;
 // mod snapshot_test;
 // mod async_test;
+mod rollup_test;
+mod statistics_test;
 mod type_option_test;
@@ -0,0 +1,293 @@
+use collab::core::origin::CollabOrigin;
+use collab::preclude::updates::decoder::Decode;
+use collab::preclude::{Collab, MapExt, MapRef, ReadTxn, ToJson, Update};
+use collab::util::AnyExt;
+use collab_database::database::{gen_database_id, gen_database_view_id, gen_row_id};
+use collab_database::entity::{CreateDatabaseParams, CreateViewParams, FieldType};
+use collab_database::fields::relation_type_option::{
+  apply_relation_changes, RelationCellData, RelationTypeOption,
+};
+use collab_database::fields::Field;
+use collab_database::rows::{CreateRowParams, RowId};
+use collab_database::views::DuplicateRowPosition;
+
+use crate::user_test::helper::{random_uid, workspace_database_test, WorkspaceDatabaseTest};
+
+fn relation_field(id: &str, type_option: RelationTypeOption) -> Field {
+  Field::new(
+    id.to_string(),
+    id.to_string(),
+    FieldType::Relation.into(),
+    false,
+  )
+  .with_type_option_data(FieldType::Relation.type_id(), type_option.into())
+}
+
+fn database_params(database_id: &str, field: Field, num_of_rows: usize) -> CreateDatabaseParams {
+  CreateDatabaseParams {
+    database_id: database_id.to_string(),
+    fields: vec![field],
+    rows: (0..num_of_rows)
+      .map(|_| CreateRowParams::new(gen_row_id(), database_id.to_string()))
+      .collect(),
+    views: vec![CreateViewParams {
+      database_id: database_id.to_string(),
+      view_id: gen_database_view_id(),
+      ..Default::default()
+    }],
+  }
+}
+
+async fn linked_row_ids(
+  test: &WorkspaceDatabaseTest,
+  database_id: &str,
+  field_id: &str,
+  row_id: &RowId,
+) -> Vec<RowId> {
+  let database = test.get_or_init_database(database_id).await.unwrap();
+  let cell = database.read().await.get_cell(field_id, row_id).await.cell;
+  cell
+    .map(|cell| RelationCellData::from(&cell).row_ids)
+    .unwrap_or_default()
+}
+
+#[tokio::test]
+async fn reciprocal_relation_test() {
+  let mut test = workspace_database_test(random_uid()).await;
+  let projects_id = gen_database_id();
+  let tasks_id = gen_database_id();
+  let projects_params = database_params(
+    &projects_id,
+    relation_field(
+      "tasks",
+      RelationTypeOption::new(&tasks_id).with_reciprocal_field("projects"),
+    ),
+    2,
+  );
+  let tasks_params = database_params(
+    &tasks_id,
+    relation_field(
+      "projects",
+      RelationTypeOption::new(&projects_id).with_reciprocal_field("tasks"),
+    ),
+    3,
+  );
+  let projects = projects_params
+    .rows
+    .iter()
+    .map(|row| row.id.clone())
+    .collect::<Vec<_>>();
+  let tasks = tasks_params
+    .rows
+    .iter()
+    .map(|row| row.id.clone())
+    .collect::<Vec<_>>();
+  test.create_database(projects_params).await.unwrap();
+  test.create_database(tasks_params).await.unwrap();
+
+  test
+    .update_relation_cell(
+      &projects_id,
+      "tasks",
+      &projects[0],
+      vec![tasks[0].clone(), tasks[1].clone()],
+    )
+    .await
+    .unwrap();
+  test
+    .update_relation_cell(&projects_id, "tasks", &projects[1], vec![tasks[1].clone()])
+    .await
+    .unwrap();
+  assert_eq!(
+    linked_row_ids(&test, &tasks_id, "projects", &tasks[0]).await,
+    vec![projects[0].clone()]
+  );
+  assert_eq!(
+    linked_row_ids(&test, &tasks_id, "projects", &tasks[1]).await,
+    vec![projects[0].clone(), projects[1].clone()]
+  );
+
+  // Unlinking the first task removes the reciprocal link, linking the third one adds it.
+  test
+    .update_relation_cell(
+      &projects_id,
+      "tasks",
+      &projects[0],
+      vec![tasks[1].clone(), tasks[2].clone()],
+    )
+    .await
+    .unwrap();
+  assert!(linked_row_ids(&test, &tasks_id, "projects", &tasks[0])
+    .await
+    .is_empty());
+  assert_eq!(
+    linked_row_ids(&test, &tasks_id, "projects", &tasks[2]).await,
+    vec![projects[0].clone()]
+  );
+
+  // The reciprocal field links back too.
+  test
+    .update_relation_cell(&tasks_id, "projects", &tasks[0], vec![projects[1].clone()])
+    .await
+    .unwrap();
+  assert_eq!(
+    linked_row_ids(&test, &projects_id, "tasks", &projects[1]).await,
+    vec![tasks[1].clone(), tasks[0].clone()]
+  );
+
+  // Removing a task unlinks it from the projects.
+  let tasks_database = test.get_or_init_database(&tasks_id).await.unwrap();
+  let row = tasks_database.write().await.remove_row(&tasks[1]).await;
+  assert!(row.is_some());
+  assert_eq!(
+    linked_row_ids(&test, &projects_id, "tasks", &projects[0]).await,
+    vec![tasks[2].clone()]
+  );
+  assert_eq!(
+    linked_row_ids(&test, &projects_id, "tasks", &projects[1]).await,
+    vec![tasks[0].clone()]
+  );
+
+  // The duplicate of a project is linked back from its tasks.
+  let projects_database = test.get_or_init_database(&projects_id).await.unwrap();
+  let duplicated = projects_database
+    .write()
+    .await
+    .duplicate_row(&projects[1], DuplicateRowPosition::After)
+    .await
+    .unwrap();
+  assert_eq!(
+    linked_row_ids(&test, &tasks_id, "projects", &tasks[0]).await,
+    vec![projects[1].clone(), duplicated.row.id]
+  );
+}
+
+#[tokio::test]
+async fn misconfigured_reciprocal_relation_test() {
+  let mut test = workspace_database_test(random_uid()).await;
+  let projects_id = gen_database_id();
+  let tasks_id = gen_database_id();
+  // The reciprocal field of the tasks links to another database.
+  let projects_params = database_params(
+    &projects_id,
+    relation_field(
+      "tasks",
+      RelationTypeOption::new(&tasks_id).with_reciprocal_field("projects"),
+    ),
+    1,
+  );
+  let tasks_params = database_params(
+    &tasks_id,
+    relation_field("projects", RelationTypeOption::new(&gen_database_id())),
+    1,
+  );
+  let project = projects_params.rows[0].id.clone();
+  let task = tasks_params.rows[0].id.clone();
+  test.create_database(projects_params).await.unwrap();
+  test.create_database(tasks_params).await.unwrap();
+
+  assert!(test
+    .update_relation_cell(&projects_id, "tasks", &project, vec![task.clone()])
+    .await
+    .is_err());
+  assert!(linked_row_ids(&test, &projects_id, "tasks", &project)
+    .await
+    .is_empty());
+  assert!(linked_row_ids(&test, &tasks_id, "projects", &task)
+    .await
+    .is_empty());
+}
+
+#[tokio::test]
+async fn remove_row_of_self_relation_test() {
+  let mut test = workspace_database_test(random_uid()).await;
+  let tasks_id = gen_database_id();
+  let tasks_params = database_params(
+    &tasks_id,
+    relation_field(
+      "blocked_by",
+      RelationTypeOption::new(&tasks_id).with_reciprocal_field("blocked_by"),
+    ),
+    3,
+  );
+  let tasks = tasks_params
+    .rows
+    .iter()
+    .map(|row| row.id.clone())
+    .collect::<Vec<_>>();
+  test.create_database(tasks_params).await.unwrap();
+
+  test
+    .update_relation_cell(
+      &tasks_id,
+      "blocked_by",
+      &tasks[0],
+      vec![tasks[1].clone(), tasks[2].clone()],
+    )
+    .await
+    .unwrap();
+  assert_eq!(
+    linked_row_ids(&test, &tasks_id, "blocked_by", &tasks[1]).await,
+    vec![tasks[0].clone()]
+  );
+
+  let database = test.get_or_init_database(&tasks_id).await.unwrap();
+  let rows = database.write().await.remove_rows(&tasks[..1]).await;
+  assert_eq!(rows.len(), 1);
+  assert!(linked_row_ids(&test, &tasks_id, "blocked_by", &tasks[1])
+    .await
+    .is_empty());
+  assert!(linked_row_ids(&test, &tasks_id, "blocked_by", &tasks[2])
+    .await
+    .is_empty());
+}
+
+#[test]
+fn concurrent_relation_links_test() {
+  let row_ids = (0..3).map(|_| gen_row_id()).collect::<Vec<_>>();
+  let new_collab = || Collab::new_with_origin(CollabOrigin::Empty, "row", vec![], false);
+  let cell_map = |collab: &mut Collab| -> MapRef {
+    let mut txn = collab.context.transact_mut();
+    collab.data.get_or_init_map(&mut txn, "cell")
+  };
+  let sync = |from: &Collab, to: &mut Collab| {
+    let update = from
+      .transact()
+      .encode_state_as_update_v1(&to.transact().state_vector());
+    to.apply_update(Update::decode_v1(&update).unwrap())
+      .unwrap();
+  };
+
+  let mut collab = new_collab();
+  let map = cell_map(&mut collab);
+  apply_relation_changes(&mut collab.context.transact_mut(), &map, &row_ids[..1], &[]);
+  let mut other_collab = new_collab();
+  sync(&collab, &mut other_collab);
+  let other_map = cell_map(&mut other_collab);
+
+  // One client links a row while the other one links another row and unlinks the first one.
+  apply_relation_changes(
+    &mut collab.context.transact_mut(),
+    &map,
+    &row_ids[1..2],
+    &[],
+  );
+  apply_relation_changes(
+    &mut other_collab.context.transact_mut(),
+    &other_map,
+    &row_ids[2..],
+    &row_ids[..1],
+  );
+  sync(&collab, &mut other_collab);
+  sync(&other_collab, &mut collab);
+
+  let cell = map
+    .to_json(&collab.transact())
+    .into_map()
+    .unwrap_or_default();
+  let mut linked_row_ids = RelationCellData::from(&cell).row_ids;
+  linked_row_ids.sort_by(|left, right| left.as_str().cmp(right.as_str()));
+  let mut expected = row_ids[1..].to_vec();
+  expected.sort_by(|left, right| left.as_str().cmp(right.as_str()));
+  assert_eq!(linked_row_ids, expected);
+}
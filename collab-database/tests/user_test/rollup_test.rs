@@ -0,0 +1,133 @@
+use collab::util::AnyMapExt;
+use collab_database::database::{gen_database_id, gen_database_view_id, gen_row_id};
+use collab_database::entity::{CreateDatabaseParams, CreateViewParams, FieldType};
+use collab_database::fields::relation_type_option::{
+  RelationCellData, RelationTypeOption, RollupAggregation, RollupTypeOption,
+};
+use collab_database::fields::Field;
+use collab_database::rows::{Cell, Cells, CreateRowParams, RowId};
+use collab_database::template::entity::CELL_DATA;
+
+use crate::helper::TestTextCell;
+use crate::user_test::helper::{random_uid, workspace_database_test};
+
+fn database_params(
+  database_id: &str,
+  fields: Vec<Field>,
+  rows: Vec<Cells>,
+) -> CreateDatabaseParams {
+  CreateDatabaseParams {
+    database_id: database_id.to_string(),
+    fields,
+    rows: rows
+      .into_iter()
+      .map(|cells| CreateRowParams::new(gen_row_id(), database_id.to_string()).with_cells(cells))
+      .collect(),
+    views: vec![CreateViewParams {
+      database_id: database_id.to_string(),
+      view_id: gen_database_view_id(),
+      ..Default::default()
+    }],
+  }
+}
+
+fn rollup_field(id: &str, aggregation: RollupAggregation) -> Field {
+  Field::new(
+    id.to_string(),
+    id.to_string(),
+    FieldType::Rollup.into(),
+    false,
+  )
+  .with_type_option_data(
+    FieldType::Rollup.type_id(),
+    RollupTypeOption::new("tasks", "hours", aggregation).into(),
+  )
+}
+
+#[tokio::test]
+async fn resolve_rollup_cell_test() {
+  let mut test = workspace_database_test(random_uid()).await;
+  let tasks_id = gen_database_id();
+  let hours_field = Field::new(
+    "hours".to_string(),
+    "Hours".to_string(),
+    FieldType::Number.into(),
+    false,
+  );
+  let tasks_params = database_params(
+    &tasks_id,
+    vec![hours_field],
+    ["3", "4.5", "", "2"]
+      .iter()
+      .map(|hours| Cells::from([("hours".to_string(), TestTextCell::from(*hours).into())]))
+      .collect(),
+  );
+  let task_ids = tasks_params
+    .rows
+    .iter()
+    .map(|row| row.id.clone())
+    .collect::<Vec<RowId>>();
+  test.create_database(tasks_params).await.unwrap();
+
+  let projects_id = gen_database_id();
+  let relation_field = Field::new(
+    "tasks".to_string(),
+    "Tasks".to_string(),
+    FieldType::Relation.into(),
+    false,
+  )
+  .with_type_option_data(
+    FieldType::Relation.type_id(),
+    RelationTypeOption::new(&tasks_id).into(),
+  );
+  // The last link is to a row that doesn't exist, it's not aggregated.
+  let relation_cell = Cell::from(&RelationCellData {
+    row_ids: [&task_ids[..3], &[gen_row_id()]].concat(),
+  });
+  let projects_params = database_params(
+    &projects_id,
+    vec![
+      relation_field,
+      rollup_field("sum", RollupAggregation::Sum),
+      rollup_field("count", RollupAggregation::Count),
+      rollup_field("average", RollupAggregation::Average),
+      rollup_field("max", RollupAggregation::Max),
+    ],
+    vec![
+      Cells::from([("tasks".to_string(), relation_cell)]),
+      Cells::new(),
+    ],
+  );
+  let project_ids = projects_params
+    .rows
+    .iter()
+    .map(|row| row.id.clone())
+    .collect::<Vec<RowId>>();
+  test.create_database(projects_params).await.unwrap();
+
+  let rollup = |field_id: &'static str, row_id: RowId| {
+    let test = &test;
+    let projects_id = projects_id.clone();
+    async move {
+      let cell = test
+        .resolve_rollup_cell(&projects_id, field_id, &row_id)
+        .await
+        .unwrap();
+      cell.get_as::<String>(CELL_DATA).unwrap()
+    }
+  };
+  assert_eq!(rollup("sum", project_ids[0].clone()).await, "7.5");
+  assert_eq!(rollup("count", project_ids[0].clone()).await, "3");
+  assert_eq!(rollup("average", project_ids[0].clone()).await, "3.75");
+  assert_eq!(rollup("max", project_ids[0].clone()).await, "4.5");
+
+  // The row without linked rows.
+  assert_eq!(rollup("count", project_ids[1].clone()).await, "0");
+  assert_eq!(rollup("sum", project_ids[1].clone()).await, "");
+
+  // The relation field is not a rollup field.
+  assert!(test
+    .resolve_rollup_cell(&projects_id, "tasks", &project_ids[0])
+    .await
+    .is_err());
+}
@@ -0,0 +1,80 @@
+use collab_database::database::{gen_database_id, gen_database_view_id, gen_field_id, gen_row_id};
+use collab_database::database_statistics::FieldMatcher;
+use collab_database::entity::{CreateDatabaseParams, CreateViewParams, FieldType};
+use collab_database::fields::Field;
+use collab_database::rows::{Cells, CreateRowParams};
+
+use crate::helper::TestTextCell;
+use crate::user_test::helper::{random_uid, workspace_database_test};
+
+fn project_database(field_name: &str, costs: &[&str]) -> CreateDatabaseParams {
+  let database_id = gen_database_id();
+  let name_field = Field::new(gen_field_id(), "Name".to_string(), 0, true);
+  let cost_field = Field::new(
+    gen_field_id(),
+    field_name.to_string(),
+    FieldType::Number.into(),
+    false,
+  );
+  let rows = costs
+    .iter()
+    .map(|cost| {
+      let mut cells = Cells::new();
+      cells.insert(cost_field.id.clone(), TestTextCell::from(*cost).into());
+      CreateRowParams::new(gen_row_id(), database_id.clone()).with_cells(cells)
+    })
+    .collect();
+  CreateDatabaseParams {
+    database_id: database_id.clone(),
+    fields: vec![name_field, cost_field],
+    rows,
+    views: vec![CreateViewParams {
+      database_id,
+      view_id: gen_database_view_id(),
+      ..Default::default()
+    }],
+  }
+}
+
+#[tokio::test]
+async fn cross_database_field_statistics_test() {
+  let mut test = workspace_database_test(random_uid()).await;
+  let params_1 = project_database("Cost", &["10", "1,200.5"]);
+  let params_2 = project_database(" cost ", &["-4", "", "n/a"]);
+  let params_3 = project_database("Budget", &["100"]);
+  let database_ids = vec![
+    params_1.database_id.clone(),
+    params_2.database_id.clone(),
+    params_3.database_id.clone(),
+    gen_database_id(),
+  ];
+  for params in [params_1, params_2, params_3] {
+    test.create_database(params).await.unwrap();
+  }
+
+  let matcher = FieldMatcher::by_name("Cost").with_field_type(FieldType::Number);
+  let statistics = test
+    .get_field_statistics(&database_ids, &matcher, None)
+    .await;
+  assert_eq!(statistics.databases.len(), 2);
+  assert_eq!(statistics.databases[0].statistics.sum, 1210.5);
+  assert_eq!(statistics.databases[1].statistics.num_of_values, 1);
+  assert_eq!(statistics.databases[1].statistics.num_of_empty(), 2);
+  assert_eq!(statistics.skipped, database_ids[2..].to_vec());
+
+  let total = statistics.total;
+  assert_eq!(total.num_of_rows, 5);
+  assert_eq!(total.num_of_values, 3);
+  assert_eq!(total.sum, 1206.5);
+  assert_eq!(total.min, Some(-4.0));
+  assert_eq!(total.max, Some(1200.5));
+  assert_eq!(total.average(), Some(1206.5 / 3.0));
+
+  // The field type must match too.
+  let matcher = FieldMatcher::by_name("Cost").with_field_type(FieldType::RichText);
+  let statistics = test
+    .get_field_statistics(&database_ids, &matcher, None)
+    .await;
+  assert!(statistics.databases.is_empty());
+  assert_eq!(statistics.total.num_of_rows, 0);
+}
@@ -0,0 +1,404 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::sync::{Arc, RwLock};
+
+use crate::blocks::Block;
+
+/// The id of the page block, the blocks and the children map of a document.
+pub(crate) type BlockTree = (String, HashMap<String, Block>, HashMap<String, Vec<String>>);
+
+/// The place of a block in the tree of a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockPosition {
+  /// The id of the parent block, None for the page block.
+  pub parent_id: Option<String>,
+  /// The index of the block in the children of its parent.
+  pub index: u32,
+  /// The number of ancestors of the block, 0 for the page block.
+  pub depth: u32,
+}
+
+/// Reads the links between the blocks of a document, so the [BlockIndex] can walk the tree.
+pub(crate) trait BlockLinks {
+  /// Return the id of the children array of the block, None if the block doesn't exist.
+  fn children_id(&self, block_id: &str) -> Option<String>;
+  /// Return the parent id of the block, None if the block doesn't exist.
+  fn parent_id(&self, block_id: &str) -> Option<String>;
+  /// Return the ids of the blocks in the children array.
+  fn children(&self, children_id: &str) -> Vec<String>;
+}
+
+impl BlockLinks for BlockTree {
+  fn children_id(&self, block_id: &str) -> Option<String> {
+    self.1.get(block_id).map(|block| block.children.clone())
+  }
+
+  fn parent_id(&self, block_id: &str) -> Option<String> {
+    self.1.get(block_id).map(|block| block.parent.clone())
+  }
+
+  fn children(&self, children_id: &str) -> Vec<String> {
+    self.2.get(children_id).cloned().unwrap_or_default()
+  }
+}
+
+/// A change of the document that may move blocks in its tree, see [BlockIndex::apply_changes].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum TreeChange {
+  /// The children array with this id changed.
+  Children(String),
+  /// The block was inserted, removed, or its children array was replaced.
+  Block(String),
+  /// The page, the blocks map or the children map were replaced.
+  Reset,
+}
+
+/// Maps the id of each block that is reachable from the page block to its [BlockPosition], so the
+/// outline, the drag and drop and the subtree operations don't scan the children arrays of the
+/// document for every block.
+///
+/// The index is built on the first lookup, then kept up to date by the observer of the document:
+/// only the children arrays that changed are read again, and only the subtrees that moved are
+/// walked. It's only rebuilt when the page, the blocks map or the children map are replaced.
+#[derive(Clone, Default)]
+pub(crate) struct BlockIndex {
+  state: Arc<RwLock<Option<TreeState>>>,
+}
+
+#[derive(Default)]
+struct TreeState {
+  page_id: String,
+  positions: HashMap<String, BlockPosition>,
+  /// The id of the children array of each indexed block, and the ids of the children that are
+  /// indexed under it.
+  nodes: HashMap<String, (String, Vec<String>)>,
+  /// The id of the indexed block of each children array.
+  owners: HashMap<String, String>,
+}
+
+impl TreeState {
+  fn new(page_id: &str, links: &impl BlockLinks) -> Self {
+    let mut state = Self {
+      page_id: page_id.to_string(),
+      ..Default::default()
+    };
+    if links.children_id(page_id).is_some() {
+      state.attach(page_id, None, 0, 0, links);
+    }
+    state
+  }
+
+  /// Index the block at the given place, then the blocks of its subtree that are not indexed yet.
+  fn attach(
+    &mut self,
+    block_id: &str,
+    parent_id: Option<&str>,
+    index: u32,
+    depth: u32,
+    links: &impl BlockLinks,
+  ) {
+    self.positions.insert(
+      block_id.to_string(),
+      BlockPosition {
+        parent_id: parent_id.map(|id| id.to_string()),
+        index,
+        depth,
+      },
+    );
+    let mut queue = VecDeque::from([(block_id.to_string(), depth)]);
+    while let Some((block_id, depth)) = queue.pop_front() {
+      let children_id = links.children_id(&block_id).unwrap_or_default();
+      let mut child_ids = vec![];
+      for (index, child_id) in links.children(&children_id).into_iter().enumerate() {
+        // A block that appears twice in the tree keeps its first position.
+        if links.children_id(&child_id).is_none() || self.positions.contains_key(&child_id) {
+          continue;
+        }
+        self.positions.insert(
+          child_id.clone(),
+          BlockPosition {
+            parent_id: Some(block_id.clone()),
+            index: index as u32,
+            depth: depth + 1,
+          },
+        );
+        child_ids.push(child_id.clone());
+        queue.push_back((child_id, depth + 1));
+      }
+      self.owners.insert(children_id.clone(), block_id.clone());
+      self.nodes.insert(block_id, (children_id, child_ids));
+    }
+  }
+
+  /// Remove the block and its subtree from the index.
+  fn detach(&mut self, block_id: &str) {
+    let mut stack = vec![block_id.to_string()];
+    while let Some(block_id) = stack.pop() {
+      self.positions.remove(&block_id);
+      if let Some((children_id, child_ids)) = self.nodes.remove(&block_id) {
+        if self.owners.get(&children_id) == Some(&block_id) {
+          self.owners.remove(&children_id);
+        }
+        stack.extend(child_ids);
+      }
+    }
+  }
+
+  fn apply_changes(&mut self, changes: &[TreeChange], links: &impl BlockLinks) {
+    // The indexed blocks whose children must be read again.
+    let mut parent_ids = BTreeSet::new();
+    for change in changes {
+      match change {
+        TreeChange::Children(children_id) => {
+          if let Some(block_id) = self.owners.get(children_id) {
+            parent_ids.insert(block_id.clone());
+          }
+        },
+        TreeChange::Block(block_id) if *block_id == self.page_id => {
+          *self = Self::new(&self.page_id, links);
+          return;
+        },
+        TreeChange::Block(block_id) => match links.parent_id(block_id) {
+          None => {
+            if let Some(parent_id) = self
+              .positions
+              .get(block_id)
+              .and_then(|position| position.parent_id.clone())
+            {
+              parent_ids.insert(parent_id);
+            }
+            self.detach(block_id);
+          },
+          Some(_) if self.positions.contains_key(block_id) => {
+            parent_ids.insert(block_id.clone());
+          },
+          Some(parent_id) => {
+            if self.positions.contains_key(&parent_id) {
+              parent_ids.insert(parent_id);
+            }
+          },
+        },
+        TreeChange::Reset => {
+          *self = Self::default();
+          return;
+        },
+      }
+    }
+
+    // The removed children are detached from all the parents before the inserted children are
+    // attached, so a block moved between two parents is attached under its new parent.
+    let mut children_of_parents = vec![];
+    for parent_id in parent_ids {
+      let Some((children_id, child_ids)) = self.nodes.get(&parent_id).cloned() else {
+        continue;
+      };
+      let new_children_id = links.children_id(&parent_id).unwrap_or_default();
+      let children = links.children(&new_children_id);
+      for child_id in child_ids {
+        if !children.contains(&child_id) {
+          self.detach(&child_id);
+        }
+      }
+      if new_children_id != children_id {
+        self.owners.remove(&children_id);
+        self
+          .owners
+          .insert(new_children_id.clone(), parent_id.clone());
+      }
+      children_of_parents.push((parent_id, new_children_id, children));
+    }
+
+    for (parent_id, children_id, children) in children_of_parents {
+      let Some(depth) = self
+        .positions
+        .get(&parent_id)
+        .map(|position| position.depth)
+      else {
+        continue;
+      };
+      let mut child_ids = vec![];
+      for (index, child_id) in children.into_iter().enumerate() {
+        if links.children_id(&child_id).is_none() {
+          continue;
+        }
+        match self.positions.get_mut(&child_id) {
+          Some(position) if position.parent_id.as_ref() == Some(&parent_id) => {
+            position.index = index as u32;
+          },
+          Some(_) => continue,
+          None => self.attach(&child_id, Some(&parent_id), index as u32, depth + 1, links),
+        }
+        child_ids.push(child_id);
+      }
+      self.nodes.insert(parent_id, (children_id, child_ids));
+    }
+  }
+}
+
+impl BlockIndex {
+  /// Updates the index with the changes of the document, if the index was built.
+  pub(crate) fn apply_changes(&self, changes: &[TreeChange], links: &impl BlockLinks) {
+    if changes.is_empty() {
+      return;
+    }
+    let mut state = self.state.write().unwrap();
+    if changes.contains(&TreeChange::Reset) {
+      *state = None;
+    } else if let Some(state) = state.as_mut() {
+      state.apply_changes(changes, links);
+    }
+  }
+
+  /// Return the position of the block, building the index first if needed. `load` returns the
+  /// tree of the document, it's only called to build the index.
+  pub(crate) fn get<F>(&self, block_id: &str, load: F) -> Option<BlockPosition>
+  where
+    F: FnOnce() -> Option<BlockTree>,
+  {
+    self.with_positions(load, |positions| positions.get(block_id).cloned())
+  }
+
+  /// Calls `f` with the up to date positions of the blocks.
+  pub(crate) fn with_positions<F, R>(
+    &self,
+    load: F,
+    f: impl FnOnce(&HashMap<String, BlockPosition>) -> R,
+  ) -> R
+  where
+    F: FnOnce() -> Option<BlockTree>,
+  {
+    if let Some(state) = self.state.read().unwrap().as_ref() {
+      return f(&state.positions);
+    }
+    let state = load()
+      .map(|tree| TreeState::new(&tree.0, &tree))
+      .unwrap_or_default();
+    let result = f(&state.positions);
+    *self.state.write().unwrap() = Some(state);
+    result
+  }
+}
+
+/// The modified time and the text id of a block, as stored in the document.
+pub(crate) type IndexedBlock = (Option<i64>, Option<String>);
+
+/// Keeps the blocks of a document ordered by their modified time, and the blocks of each text, so
+/// the recently modified blocks and the blocks stamped by a text edit are found without scanning
+/// the blocks of the document.
+///
+/// The index is built on the first lookup, then kept up to date by the observer of the document,
+/// block by block. It's only rebuilt when the whole blocks map is replaced.
+#[derive(Clone, Default)]
+pub(crate) struct ModifiedIndex {
+  state: Arc<RwLock<Option<ModifiedState>>>,
+}
+
+#[derive(Default)]
+struct ModifiedState {
+  blocks: HashMap<String, IndexedBlock>,
+  recent: BTreeSet<(Reverse<i64>, String)>,
+  blocks_of_text: HashMap<String, HashSet<String>>,
+}
+
+impl ModifiedState {
+  fn insert(&mut self, block_id: String, block: IndexedBlock) {
+    self.remove(&block_id);
+    if let Some(modified_at) = block.0 {
+      self.recent.insert((Reverse(modified_at), block_id.clone()));
+    }
+    if let Some(text_id) = &block.1 {
+      self
+        .blocks_of_text
+        .entry(text_id.clone())
+        .or_default()
+        .insert(block_id.clone());
+    }
+    self.blocks.insert(block_id, block);
+  }
+
+  fn remove(&mut self, block_id: &str) {
+    let Some((modified_at, text_id)) = self.blocks.remove(block_id) else {
+      return;
+    };
+    if let Some(modified_at) = modified_at {
+      self
+        .recent
+        .remove(&(Reverse(modified_at), block_id.to_string()));
+    }
+    if let Some(text_id) = text_id {
+      if let Some(block_ids) = self.blocks_of_text.get_mut(&text_id) {
+        block_ids.remove(block_id);
+        if block_ids.is_empty() {
+          self.blocks_of_text.remove(&text_id);
+        }
+      }
+    }
+  }
+}
+
+impl ModifiedIndex {
+  /// Drops the index, it's rebuilt on the next lookup.
+  pub(crate) fn invalidate(&self) {
+    *self.state.write().unwrap() = None;
+  }
+
+  /// Updates the block in the index, if the index was built.
+  pub(crate) fn set_block(&self, block_id: &str, block: IndexedBlock) {
+    if let Some(state) = self.state.write().unwrap().as_mut() {
+      state.insert(block_id.to_string(), block);
+    }
+  }
+
+  /// Removes the block from the index, if the index was built.
+  pub(crate) fn remove_block(&self, block_id: &str) {
+    if let Some(state) = self.state.write().unwrap().as_mut() {
+      state.remove(block_id);
+    }
+  }
+
+  /// See [crate::document::Document::get_recently_modified_blocks]. `load` returns all the blocks
+  /// of the document, it's only called to build the index.
+  pub(crate) fn recently_modified<F>(&self, limit: usize, load: F) -> Vec<(String, i64)>
+  where
+    F: FnOnce() -> Vec<(String, IndexedBlock)>,
+  {
+    self.with_state(load, |state| {
+      state
+        .recent
+        .iter()
+        .take(limit)
+        .map(|(Reverse(modified_at), block_id)| (block_id.clone(), *modified_at))
+        .collect()
+    })
+  }
+
+  /// Return the ids of the blocks whose text has the given id.
+  pub(crate) fn blocks_of_text<F>(&self, text_id: &str, load: F) -> Vec<String>
+  where
+    F: FnOnce() -> Vec<(String, IndexedBlock)>,
+  {
+    self.with_state(load, |state| {
+      state
+        .blocks_of_text
+        .get(text_id)
+        .map(|block_ids| block_ids.iter().cloned().collect())
+        .unwrap_or_default()
+    })
+  }
+
+  fn with_state<F, R>(&self, load: F, f: impl FnOnce(&ModifiedState) -> R) -> R
+  where
+    F: FnOnce() -> Vec<(String, IndexedBlock)>,
+  {
+    if let Some(state) = self.state.read().unwrap().as_ref() {
+      return f(state);
+    }
+    let mut state = ModifiedState::default();
+    for (block_id, block) in load() {
+      state.insert(block_id, block);
+    }
+    let result = f(&state);
+    *self.state.write().unwrap() = Some(state);
+    result
+  }
+}
@@ -1,7 +1,12 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
-use crate::blocks::{hashmap_to_json_str, json_str_to_hashmap, Block, ChildrenOperation};
+use crate::block_index::{IndexedBlock, ModifiedIndex};
+use crate::blocks::{
+  hashmap_to_json_str, json_str_to_hashmap, Block, BlockTimestamps, ChildrenOperation,
+};
 use crate::error::DocumentError;
+use collab::core::clock::{system_clock, ClockProvider};
 use collab::preclude::{Map, MapExt, MapRef, ReadTxn, TransactionMut};
 use serde_json::Value;
 
@@ -12,15 +17,23 @@ pub const EXTERNAL_TYPE_MAP: &str = "map";
 const ID: &str = "id";
 const TYPE: &str = "ty";
 const PARENT: &str = "parent";
-const CHILDREN: &str = "children";
+pub(crate) const CHILDREN: &str = "children";
 const DATA: &str = "data";
 const EXTERNAL_ID: &str = "external_id";
 const EXTERNAL_TYPE: &str = "external_type";
+pub(crate) const CREATED_AT: &str = "created_at";
+pub(crate) const MODIFIED_AT: &str = "modified_at";
+/// The text edits made within this number of seconds of the modified time of a block don't stamp
+/// it again, so typing doesn't write a timestamp for every keystroke.
+pub(crate) const MODIFIED_AT_THROTTLE_SECS: i64 = 5;
 
 /// for block operate, there has a root map, and a children map.
+#[derive(Clone)]
 pub struct BlockOperation {
   root: MapRef,
   children_operation: ChildrenOperation,
+  clock: Arc<dyn ClockProvider>,
+  modified_index: ModifiedIndex,
 }
 
 impl BlockOperation {
@@ -28,9 +41,20 @@ impl BlockOperation {
     Self {
       root,
       children_operation,
+      clock: system_clock(),
+      modified_index: ModifiedIndex::default(),
     }
   }
 
+  pub(crate) fn modified_index(&self) -> &ModifiedIndex {
+    &self.modified_index
+  }
+
+  /// Set the clock that stamps the created and modified times of the blocks.
+  pub fn set_clock(&mut self, clock: Arc<dyn ClockProvider>) {
+    self.clock = clock;
+  }
+
   /// get all blocks
   pub fn get_all_blocks<T: ReadTxn>(&self, txn: &T) -> HashMap<String, Block> {
     self
@@ -69,6 +93,9 @@ impl BlockOperation {
     map.insert(txn, DATA, json_str);
     map.insert(txn, EXTERNAL_ID, block.external_id);
     map.insert(txn, EXTERNAL_TYPE, block.external_type);
+    let now = self.clock.timestamp();
+    map.insert(txn, CREATED_AT, now);
+    map.insert(txn, MODIFIED_AT, now);
 
     // Create the children for each block.
     self
@@ -107,6 +134,18 @@ impl BlockOperation {
       .map(|map| block_from_map(txn, map))
   }
 
+  /// Return the parent id and the children id of the block, without reading its data.
+  pub(crate) fn get_block_links_with_txn<T: ReadTxn>(
+    &self,
+    txn: &T,
+    id: &str,
+  ) -> Option<(String, String)> {
+    let map = self.root.get_with_txn::<T, MapRef>(txn, id)?;
+    let parent: String = map.get_with_txn(txn, PARENT).unwrap_or_default();
+    let children: String = map.get_with_txn(txn, CHILDREN).unwrap_or_default();
+    Some((parent, children))
+  }
+
   /// Update the block with the given id.
   /// Except \`data\` and \`parent\` and \'external_id\' and \'external_type\' field, other fields can be updated.
   /// If you want to turn into other block, you should delete the block and create a new block.
@@ -141,8 +180,83 @@ impl BlockOperation {
     if let Some(external_type) = external_type {
       map.try_update(txn, EXTERNAL_TYPE, external_type);
     }
+    map.insert(txn, MODIFIED_AT, self.clock.timestamp());
     Ok(())
   }
+
+  /// Stamp the modified time of the blocks whose text has the given id, after the text was
+  /// edited. A block that was stamped less than [MODIFIED_AT_THROTTLE_SECS] ago keeps its stamp.
+  pub fn touch_blocks_of_text_with_txn(&self, txn: &mut TransactionMut, text_id: &str) {
+    let now = self.clock.timestamp();
+    // The text of a block usually has the id of the block.
+    let maps = match self.root.get_with_txn::<_, MapRef>(txn, text_id) {
+      Some(map) if map.get_with_txn::<_, String>(txn, EXTERNAL_ID).as_deref() == Some(text_id) => {
+        vec![map]
+      },
+      _ => self
+        .modified_index
+        .blocks_of_text(text_id, || self.indexed_blocks(txn))
+        .into_iter()
+        .filter_map(|block_id| self.root.get_with_txn::<_, MapRef>(txn, &block_id))
+        .collect(),
+    };
+    for map in maps {
+      let modified_at: Option<i64> = map.get_with_txn(txn, MODIFIED_AT);
+      let is_recent = modified_at
+        .map(|modified_at| (0..MODIFIED_AT_THROTTLE_SECS).contains(&(now - modified_at)))
+        .unwrap_or(false);
+      if !is_recent {
+        map.insert(txn, MODIFIED_AT, now);
+      }
+    }
+  }
+
+  /// Return the created and modified times of the block, or None if the block is not found.
+  pub fn get_block_timestamps_with_txn<T: ReadTxn>(
+    &self,
+    txn: &T,
+    id: &str,
+  ) -> Option<BlockTimestamps> {
+    let map = self.root.get_with_txn::<T, MapRef>(txn, id)?;
+    Some(block_timestamps_from_map(txn, &map))
+  }
+
+  /// See [crate::document::Document::get_recently_modified_blocks].
+  pub fn get_recently_modified_blocks_with_txn<T: ReadTxn>(
+    &self,
+    txn: &T,
+    limit: usize,
+  ) -> Vec<(String, i64)> {
+    self
+      .modified_index
+      .recently_modified(limit, || self.indexed_blocks(txn))
+  }
+
+  fn indexed_blocks<T: ReadTxn>(&self, txn: &T) -> Vec<(String, IndexedBlock)> {
+    self
+      .root
+      .iter(txn)
+      .filter_map(|(id, value)| {
+        let map = value.cast::<MapRef>().ok()?;
+        Some((id.to_string(), indexed_block_from_map(txn, &map)))
+      })
+      .collect()
+  }
+}
+
+/// Return the modified time and the text id of the block, as kept by the [ModifiedIndex].
+pub(crate) fn indexed_block_from_map<T: ReadTxn>(txn: &T, map: &MapRef) -> IndexedBlock {
+  (
+    map.get_with_txn(txn, MODIFIED_AT),
+    map.get_with_txn(txn, EXTERNAL_ID),
+  )
+}
+
+fn block_timestamps_from_map<T: ReadTxn>(txn: &T, map: &MapRef) -> BlockTimestamps {
+  BlockTimestamps {
+    created_at: map.get_with_txn(txn, CREATED_AT),
+    modified_at: map.get_with_txn(txn, MODIFIED_AT),
+  }
 }
 
 /// Build the block from the [MapRef]
@@ -2,6 +2,7 @@ use std::collections::HashMap;
 use std::hash::Hash;
 use std::ops::Deref;
 
+use collab::core::collab_webhook::WebhookEvent;
 use serde::{Deserialize, Serialize};
 use serde_json;
 use serde_json::Value;
@@ -25,6 +26,15 @@ pub struct Block {
   pub data: HashMap<String, Value>,
 }
 
+/// The times, in seconds, when a [Block] was created and last modified. They are stamped by the
+/// document when the block is inserted or updated, or its text edited, and are None for the
+/// blocks written before the times were tracked.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BlockTimestamps {
+  pub created_at: Option<i64>,
+  pub modified_at: Option<i64>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 pub struct DocumentMeta {
   /// Meta has a children map.
@@ -46,7 +56,7 @@ pub struct DocumentData {
 }
 
 /// Operate block action.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlockAction {
   /// Block action type.
   pub action: BlockActionType,
@@ -54,7 +64,7 @@ pub struct BlockAction {
   pub payload: BlockActionPayload,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlockActionPayload {
   // [Block] When action = Insert, Update, Delete or Move, block needs to be passed.
   pub block: Option<Block>,
@@ -68,7 +78,7 @@ pub struct BlockActionPayload {
   pub text_id: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum BlockActionType {
   Insert,
   Update,
@@ -95,6 +105,16 @@ impl Deref for BlockEvent {
   }
 }
 
+impl WebhookEvent for BlockEvent {
+  fn event_type(&self) -> String {
+    "document.blocks_changed".to_string()
+  }
+
+  fn data(&self) -> serde_json::Value {
+    serde_json::to_value(self).unwrap_or_default()
+  }
+}
+
 /// Block change event payload.
 #[derive(Debug, Clone, Serialize)]
 pub struct BlockEventPayload {
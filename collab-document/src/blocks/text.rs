@@ -5,6 +5,7 @@ use serde::Deserialize;
 use serde_json::json;
 use std::collections::HashMap;
 
+#[derive(Clone)]
 pub struct TextOperation {
   root: MapRef,
 }
@@ -24,6 +25,11 @@ impl TextOperation {
     self.root.remove(txn, text_id);
   }
 
+  /// get text ref with text_id, without creating it
+  pub fn get_text<T: ReadTxn>(&self, txn: &T, text_id: &str) -> Option<TextRef> {
+    self.root.get_with_txn(txn, text_id)
+  }
+
   /// get text delta with text_id
   pub fn get_delta_with_txn<T: ReadTxn>(&self, txn: &T, text_id: &str) -> Option<Vec<TextDelta>> {
     let value = self.root.get(txn, text_id)?;
@@ -1,13 +1,13 @@
 use crate::blocks::text_entities::TextDelta;
-use crate::blocks::{BlockEvent, BlockEventPayload, DeltaType};
+use crate::blocks::{Block, BlockEvent, BlockEventPayload, BlockOperation, DeltaType};
 use crate::error::DocumentError;
 use collab::preclude::text::YChange;
 use collab::preclude::{
-  Array, Delta, EntryChange, Event, Map, PathSegment, ReadTxn, Text, TextRef, TransactionMut,
-  YrsDelta, YrsValue,
+  Array, Delta, EntryChange, Event, Events, Map, PathSegment, ReadTxn, Text, TextRef,
+  TransactionMut, YrsDelta, YrsValue,
 };
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 /// block data json string to hashmap
 pub fn json_str_to_hashmap(json_str: &str) -> Result<HashMap<String, Value>, DocumentError> {
@@ -148,3 +148,67 @@ pub fn get_delta_with_text_ref<T: ReadTxn>(text_ref: &TextRef, txn: &T) -> Vec<D
 pub fn deserialize_text_delta(delta: &str) -> serde_json::Result<Vec<TextDelta>> {
   serde_json::from_str::<Vec<TextDelta>>(delta)
 }
+
+/// The key of the block map in the path of a block event.
+const BLOCKS_PATH: &str = "blocks";
+/// The key of the text map in the path of a block event.
+const TEXT_MAP_PATH: &str = "text_map";
+
+/// Returns the ids of the blocks changed by the events, with their current value, or None if the
+/// block was removed. A change of a text is mapped to the blocks that use it.
+pub(crate) fn changed_blocks_from_events(
+  object_id: &str,
+  txn: &TransactionMut,
+  events: &Events,
+  block_operation: &BlockOperation,
+) -> Vec<(String, Option<Block>)> {
+  let mut block_ids = HashSet::new();
+  let mut text_ids = HashSet::new();
+  for event in events.iter() {
+    for payload in parse_event(object_id, txn, event).iter() {
+      let path = payload
+        .path
+        .iter()
+        .map(|key| key.as_str())
+        .collect::<Vec<_>>();
+      match path.as_slice() {
+        [BLOCKS_PATH] => {
+          block_ids.insert(payload.id.clone());
+        },
+        [BLOCKS_PATH, block_id, ..] => {
+          block_ids.insert(block_id.to_string());
+        },
+        [_, TEXT_MAP_PATH] => {
+          text_ids.insert(payload.id.clone());
+        },
+        [_, TEXT_MAP_PATH, text_id, ..] => {
+          text_ids.insert(text_id.to_string());
+        },
+        _ => {},
+      }
+    }
+  }
+
+  if !text_ids.is_empty() {
+    block_ids.extend(
+      block_operation
+        .get_all_blocks(txn)
+        .into_values()
+        .filter(|block| {
+          block
+            .external_id
+            .as_ref()
+            .map_or(false, |text_id| text_ids.contains(text_id))
+        })
+        .map(|block| block.id),
+    );
+  }
+
+  block_ids
+    .into_iter()
+    .map(|block_id| {
+      let block = block_operation.get_block_with_txn(txn, &block_id);
+      (block_id, block)
+    })
+    .collect()
+}
@@ -1,5 +1,8 @@
 use anyhow::anyhow;
+use collab::core::clock::ClockProvider;
 use collab::core::collab::DataSource;
+use collab::core::collab_backlink::{BacklinkRegistry, BacklinkType};
+use collab::core::collab_search::{IndexedContent, WorkspaceSearchIndex};
 use collab::core::origin::CollabOrigin;
 use collab::entity::EncodedCollab;
 use collab::preclude::block::ClientID;
@@ -11,14 +14,30 @@ use serde_json::Value;
 use std::borrow::{Borrow, BorrowMut};
 use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
 use std::vec;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_stream::Stream;
 
+use crate::block_index::{
+  BlockIndex, BlockLinks, BlockPosition, BlockTree, ModifiedIndex, TreeChange,
+};
 use crate::blocks::{
-  deserialize_text_delta, parse_event, Block, BlockAction, BlockActionPayload, BlockActionType,
-  BlockEvent, BlockOperation, ChildrenOperation, DocumentData, DocumentMeta, TextDelta,
-  TextOperation, EXTERNAL_TYPE_TEXT,
+  deserialize_text_delta, indexed_block_from_map, parse_event, Block, BlockAction,
+  BlockActionPayload, BlockActionType, BlockEvent, BlockOperation, BlockTimestamps,
+  ChildrenOperation, DocumentData, DocumentMeta, TextDelta, TextOperation, CHILDREN,
+  EXTERNAL_TYPE_TEXT,
 };
+
+use crate::document_assets::{collect_tree_assets, DocumentAsset};
+use crate::document_attribution::{record_author, text_author_ranges, BlockAuthorRange, AUTHORS};
 use crate::document_awareness::DocumentAwarenessState;
+use crate::document_backlink::{document_mentions, mention_changes_from_events};
+use crate::document_block_type::{BlockTypeChange, BlockTypeTracker};
+use crate::document_context::extract_context;
+use crate::document_plain_text::{render_plain_text, PlainTextOptions};
+use crate::document_search::{document_index_content, index_changes_from_events};
 use crate::error::DocumentError;
 use crate::importer::define::BlockType;
 
@@ -36,10 +55,22 @@ const CHILDREN_MAP: &str = "children_map";
 /// [Block]'s yText map. And it's also in [META].
 /// The key is the text block's external_id, and the value is the text block's yText.
 const TEXT_MAP: &str = "text_map";
+/// The key of the observer that updates the search index.
+const SEARCH_INDEX_OBSERVER: &str = "search_index";
+/// The key of the observer that updates the backlink registry.
+const BACKLINK_OBSERVER: &str = "backlink";
+/// The key of the observer that updates the [BlockIndex].
+const BLOCK_INDEX_OBSERVER: &str = "block_index";
+/// The prefix of the keys of the observers of [Document::subscribe_blocks_of_type].
+const BLOCK_TYPE_OBSERVER_PREFIX: &str = "block_type";
 
 pub struct Document {
   collab: Collab,
   body: DocumentBody,
+  block_index: BlockIndex,
+  /// The keys of the observers of [Document::subscribe_blocks_of_type] and the senders of their
+  /// streams, so the observers of the dropped streams can be removed.
+  block_type_observers: Vec<(String, mpsc::UnboundedSender<BlockTypeChange>)>,
 }
 
 impl Document {
@@ -48,7 +79,7 @@ impl Document {
   pub fn open(mut collab: Collab) -> Result<Self, DocumentError> {
     CollabType::Document.validate_require_data(&collab)?;
     let body = DocumentBody::new(&mut collab, None)?;
-    Ok(Self { collab, body })
+    Ok(Self::with_block_index(collab, body))
   }
 
   /// Opening a document with given [DataSource]
@@ -65,7 +96,36 @@ impl Document {
 
   pub fn create_with_data(mut collab: Collab, data: DocumentData) -> Result<Self, DocumentError> {
     let body = DocumentBody::new(&mut collab, Some(data))?;
-    Ok(Self { collab, body })
+    Ok(Self::with_block_index(collab, body))
+  }
+
+  fn with_block_index(collab: Collab, body: DocumentBody) -> Self {
+    let block_index = BlockIndex::default();
+    let cloned_block_index = block_index.clone();
+    let block_operation = body.block_operation.clone();
+    let children_operation = body.children_operation.clone();
+    body
+      .root
+      .observe_deep_with(BLOCK_INDEX_OBSERVER, move |txn, events| {
+        let modified_index = block_operation.modified_index();
+        let mut changes = vec![];
+        for event in events.iter() {
+          update_modified_index(modified_index, txn, event);
+          tree_changes_from_event(txn, event, &mut changes);
+        }
+        let links = TxnBlockLinks {
+          txn,
+          block_operation: &block_operation,
+          children_operation: &children_operation,
+        };
+        cloned_block_index.apply_changes(&changes, &links);
+      });
+    Self {
+      collab,
+      body,
+      block_index,
+      block_type_observers: vec![],
+    }
   }
 
   pub fn create(document_id: &str, data: DocumentData) -> Result<Self, DocumentError> {
@@ -112,6 +172,109 @@ impl Document {
     });
   }
 
+  /// Returns the text of the blocks of the document, as they are added to a
+  /// [WorkspaceSearchIndex].
+  pub fn index_content(&self) -> Vec<IndexedContent> {
+    let txn = self.collab.transact();
+    document_index_content(
+      self.object_id(),
+      &txn,
+      &self.body.block_operation,
+      &self.body.text_operation,
+    )
+  }
+
+  /// Adds the text of the blocks to the index and keeps it up to date with the changes of the
+  /// document, whether they are local or remote.
+  pub fn subscribe_search_index(&mut self, index: WorkspaceSearchIndex) {
+    let object_id = self.object_id().to_string();
+    index.index_object(&object_id, self.index_content());
+    let block_operation = self.body.block_operation.clone();
+    let text_operation = self.body.text_operation.clone();
+    self
+      .body
+      .root
+      .observe_deep_with(SEARCH_INDEX_OBSERVER, move |txn, events| {
+        let changes =
+          index_changes_from_events(&object_id, txn, events, &block_operation, &text_operation);
+        index.apply_changes(changes);
+      });
+  }
+
+  /// Adds the mentions of the blocks to the registry and keeps them up to date with the changes
+  /// of the document.
+  pub fn subscribe_backlinks(&mut self, registry: BacklinkRegistry) {
+    let object_id = self.object_id().to_string();
+    registry.remove_object_links(&object_id, BacklinkType::Mention);
+    {
+      let txn = self.collab.transact();
+      for (source, mentions) in document_mentions(
+        &object_id,
+        &txn,
+        &self.body.block_operation,
+        &self.body.text_operation,
+      ) {
+        registry.set_links(source, BacklinkType::Mention, mentions);
+      }
+    }
+    let block_operation = self.body.block_operation.clone();
+    let text_operation = self.body.text_operation.clone();
+    self
+      .body
+      .root
+      .observe_deep_with(BACKLINK_OBSERVER, move |txn, events| {
+        for (source, mentions) in
+          mention_changes_from_events(&object_id, txn, events, &block_operation, &text_operation)
+        {
+          registry.set_links(source, BacklinkType::Mention, mentions);
+        }
+      });
+  }
+
+  /// Returns the blocks of the given type, keyed by id, and the stream of their changes, whether
+  /// they are local or remote. Each call has its own stream, so the same type can be subscribed
+  /// more than once.
+  pub fn subscribe_blocks_of_type(
+    &mut self,
+    block_type: &str,
+  ) -> (HashMap<String, Block>, impl Stream<Item = BlockTypeChange>) {
+    let blocks = {
+      let txn = self.collab.transact();
+      let mut blocks = self.body.block_operation.get_all_blocks(&txn);
+      blocks.retain(|_, block| block.ty == block_type);
+      blocks
+    };
+    let object_id = self.object_id().to_string();
+    let block_operation = self.body.block_operation.clone();
+    let tracker = BlockTypeTracker::new(block_type, blocks.keys());
+    let (tx, rx) = mpsc::unbounded_channel();
+    let root = &self.body.root;
+    self.block_type_observers.retain(|(key, tx)| {
+      let is_closed = tx.is_closed();
+      if is_closed {
+        root.unobserve_deep(key.as_str());
+      }
+      !is_closed
+    });
+    let key = format!(
+      "{}:{}:{}",
+      BLOCK_TYPE_OBSERVER_PREFIX,
+      block_type,
+      uuid::Uuid::new_v4()
+    );
+    let observer_tx = tx.clone();
+    root.observe_deep_with(key.as_str(), move |txn, events| {
+      if observer_tx.is_closed() {
+        return;
+      }
+      for change in tracker.changes_from_events(&object_id, txn, events, &block_operation) {
+        let _ = observer_tx.send(change);
+      }
+    });
+    self.block_type_observers.push((key, tx));
+    (blocks, UnboundedReceiverStream::new(rx))
+  }
+
   /// Get document data.
   pub fn get_document_data(&self) -> Result<DocumentData, DocumentError> {
     let txn = self.collab.transact();
@@ -138,11 +301,16 @@ impl Document {
     let delta = deserialize_text_delta(&delta).ok().unwrap_or_default();
     #[cfg(feature = "verbose_log")]
     tracing::trace!("apply_text_delta: text_id: {}, delta: {:?}", text_id, delta);
+    self.body.record_author(&mut txn);
 
     self
       .body
       .text_operation
       .apply_delta(&mut txn, text_id, delta);
+    self
+      .body
+      .block_operation
+      .touch_blocks_of_text_with_txn(&mut txn, text_id);
   }
 
   /// Apply actions to the document.
@@ -157,15 +325,45 @@ impl Document {
         BlockActionType::Update => self.body.handle_update_action(&mut txn, action.payload),
         BlockActionType::Delete => self.body.handle_delete_action(&mut txn, action.payload),
         BlockActionType::Move => self.body.handle_move_action(&mut txn, action.payload),
-        BlockActionType::InsertText | BlockActionType::ApplyTextDelta => self
-          .body
-          .handle_apply_text_delta_action(&mut txn, action.payload),
+        BlockActionType::InsertText | BlockActionType::ApplyTextDelta => {
+          self.body.record_author(&mut txn);
+          self
+            .body
+            .handle_apply_text_delta_action(&mut txn, action.payload)
+        },
       };
       result?;
     }
     Ok(())
   }
 
+  /// Set the clock that stamps the created and modified times of the blocks.
+  pub fn set_clock(&mut self, clock: Arc<dyn ClockProvider>) {
+    self.body.block_operation.set_clock(clock);
+  }
+
+  /// Return the times when the block was created and last modified, or None if the block is not
+  /// found.
+  pub fn get_block_timestamps(&self, block_id: &str) -> Option<BlockTimestamps> {
+    let txn = self.collab.transact();
+    self
+      .body
+      .block_operation
+      .get_block_timestamps_with_txn(&txn, block_id)
+  }
+
+  /// Return the ids and modified times of the `limit` most recently modified blocks, the latest
+  /// first. The blocks modified at the same time are ordered by id, and the blocks without a
+  /// modified time are left out. The first call builds the index of the modified times, the next
+  /// ones don't scan the blocks of the document.
+  pub fn get_recently_modified_blocks(&self, limit: usize) -> Vec<(String, i64)> {
+    let txn = self.collab.transact();
+    self
+      .body
+      .block_operation
+      .get_recently_modified_blocks_with_txn(&txn, limit)
+  }
+
   /// Get block with the given id.
   pub fn get_block(&self, block_id: &str) -> Option<Block> {
     let txn = self.collab.transact();
@@ -194,6 +392,49 @@ impl Document {
     }
   }
 
+  /// Return the parent, the index in the parent and the depth of the block. Return None if the
+  /// block is not in the tree of the page.
+  pub fn get_block_position(&self, block_id: &str) -> Option<BlockPosition> {
+    self.block_index.get(block_id, || self.load_block_tree())
+  }
+
+  /// Return the ids of the ancestors of the block, from its parent to the page block.
+  pub fn get_block_ancestor_ids(&self, block_id: &str) -> Vec<String> {
+    self.block_index.with_positions(
+      || self.load_block_tree(),
+      |positions| {
+        let mut ancestor_ids = vec![];
+        let mut parent_id = positions
+          .get(block_id)
+          .and_then(|position| position.parent_id.as_ref());
+        while let Some(id) = parent_id {
+          ancestor_ids.push(id.clone());
+          parent_id = positions
+            .get(id)
+            .and_then(|position| position.parent_id.as_ref());
+        }
+        ancestor_ids
+      },
+    )
+  }
+
+  /// Return true if the block is in the subtree of the given ancestor, for example to reject
+  /// dropping a block into one of its own children.
+  pub fn is_block_descendant_of(&self, block_id: &str, ancestor_id: &str) -> bool {
+    self
+      .get_block_ancestor_ids(block_id)
+      .iter()
+      .any(|id| id == ancestor_id)
+  }
+
+  fn load_block_tree(&self) -> Option<BlockTree> {
+    let txn = self.collab.transact();
+    let page_id: String = self.body.root.get_with_txn(&txn, PAGE_ID)?;
+    let blocks = self.body.block_operation.get_all_blocks(&txn);
+    let children_map = self.body.children_operation.get_all_children(&txn);
+    Some((page_id, blocks, children_map))
+  }
+
   /// Insert block to the document.
   pub fn insert_block(
     &mut self,
@@ -260,6 +501,42 @@ impl Document {
         text.join("")
       })
   }
+
+  /// Return the ranges of the text of the block with the users that wrote them, in the order of
+  /// the text. Only the text edited with [Document::apply_text_delta], [Document::apply_action]
+  /// or [Document::set_block_delta] records its user, the other ranges have no uid.
+  ///
+  /// Return an empty list if the block is not found or has no text.
+  pub fn get_block_authors(&mut self, block_id: &str) -> Vec<BlockAuthorRange> {
+    let mut txn = self.collab.transact_mut();
+    let text = self
+      .body
+      .block_operation
+      .get_block_with_txn(&txn, block_id)
+      .and_then(|block| block.external_id)
+      .and_then(|text_id| self.body.text_operation.get_text(&txn, &text_id));
+    match text {
+      Some(text) => {
+        let authors = self
+          .body
+          .root
+          .get_with_txn::<_, MapRef>(&txn, META)
+          .and_then(|meta| meta.get_with_txn::<_, MapRef>(&txn, AUTHORS));
+        text_author_ranges(&mut txn, &text, authors.as_ref())
+      },
+      None => vec![],
+    }
+  }
+
+  /// Return the files referenced by the image, file and gallery blocks of the page, in the order
+  /// of the document, for example to export or back up the document with its files.
+  pub fn collect_assets(&self) -> Vec<DocumentAsset> {
+    self
+      .load_block_tree()
+      .map(|tree| collect_tree_assets(&tree))
+      .unwrap_or_default()
+  }
+
   pub fn get_block_delta_json<T: AsRef<str>>(&self, block_id: T) -> Option<Value> {
     let delta = self.get_block_delta(block_id)?.1;
     serde_json::to_value(delta).ok()
@@ -292,6 +569,10 @@ impl Document {
           .body
           .text_operation
           .delete_text_with_txn(&mut txn, external_id);
+        self
+          .body
+          .block_operation
+          .touch_blocks_of_text_with_txn(&mut txn, external_id);
       }
     }
   }
@@ -313,10 +594,15 @@ impl Document {
         .external_id
         .as_ref()
         .ok_or(DocumentError::ExternalIdIsNotFound)?;
+      self.body.record_author(&mut txn);
       self
         .body
         .text_operation
         .set_delta(&mut txn, external_id, delta);
+      self
+        .body
+        .block_operation
+        .touch_blocks_of_text_with_txn(&mut txn, external_id);
       Ok(())
     } else {
       Err(DocumentError::BlockIsNotFound)
@@ -405,6 +691,8 @@ impl Document {
     });
   }
 
+  /// Return the text of the page block, then the text of each top level block on its own line.
+  /// Use [Document::to_plain_text_with_options] to render the nested blocks and the list markers.
   pub fn to_plain_text(&self) -> Result<String, DocumentError> {
     let page_id = self
       .get_page_id()
@@ -420,6 +708,51 @@ impl Document {
     Ok(text)
   }
 
+  /// Render the blocks of the document in the order of the document, one line per block, see
+  /// [PlainTextOptions]. Unlike [Document::to_plain_text], the text of the page block is left out.
+  pub fn to_plain_text_with_options(
+    &self,
+    options: &PlainTextOptions,
+  ) -> Result<String, DocumentError> {
+    let page_id = self
+      .get_page_id()
+      .ok_or_else(|| DocumentError::Internal(anyhow!("Page id is not found")))?;
+    let txn = self.collab.transact();
+    Ok(render_plain_text(
+      &txn,
+      &page_id,
+      &self.body.block_operation,
+      &self.body.children_operation,
+      &self.body.text_operation,
+      options,
+    ))
+  }
+
+  /// Return the plain text around the block, for example to give an AI model the context of the
+  /// block. Each block is on its own line, after a marker with its id, see
+  /// [crate::document_context::context_line]. The lines before and after the block are added in
+  /// turn, in the order of the document, as long as the text stays within `max_chars`
+  /// characters. The line of the block itself is cut if it's longer than that.
+  ///
+  /// The children of the collapsed blocks are left out, unless the block is one of them. The
+  /// context only depends on the state of the document, so all the clients build the same one.
+  pub fn extract_context(&self, block_id: &str, max_chars: usize) -> Result<String, DocumentError> {
+    let page_id = self
+      .get_page_id()
+      .ok_or_else(|| DocumentError::Internal(anyhow!("Page id is not found")))?;
+    let txn = self.collab.transact();
+    extract_context(
+      &txn,
+      &page_id,
+      block_id,
+      max_chars,
+      &self.body.block_operation,
+      &self.body.children_operation,
+      &self.body.text_operation,
+    )
+    .ok_or(DocumentError::BlockIsNotFound)
+  }
+
   // pub fn to_delta(&self) -> Result<Vec<String>, DocumentError> {
   //   let txn = self.collab.transact();
   //   let blocks = self.body.block_operation.get_all_blocks(&txn);
@@ -565,6 +898,15 @@ impl DocumentBody {
     })
   }
 
+  /// Record the user of the transaction as the author of the text it edits.
+  fn record_author(&self, txn: &mut TransactionMut) {
+    let authors = self
+      .root
+      .get_or_init_map(txn, META)
+      .get_or_init_map(txn, AUTHORS);
+    record_author(txn, &authors);
+  }
+
   fn insert_block(
     &self,
     txn: &mut TransactionMut,
@@ -839,6 +1181,9 @@ impl DocumentBody {
       if let Some(delta) = payload.delta {
         let delta = deserialize_text_delta(&delta).ok().unwrap_or_default();
         self.text_operation.apply_delta(txn, &text_id, delta);
+        self
+          .block_operation
+          .touch_blocks_of_text_with_txn(txn, &text_id);
         Ok(())
       } else {
         Err(DocumentError::TextActionParamsError)
@@ -849,6 +1194,117 @@ impl DocumentBody {
   }
 }
 
+/// Keeps the [ModifiedIndex] up to date with the blocks inserted, removed or stamped by the
+/// event, locally or remotely.
+fn update_modified_index(modified_index: &ModifiedIndex, txn: &TransactionMut, event: &Event) {
+  let Event::Map(event) = event else {
+    return;
+  };
+  let path = event.path();
+  match (path.front(), path.get(1), path.len()) {
+    (None, _, _) => {
+      if event.keys(txn).contains_key(BLOCKS) {
+        modified_index.invalidate();
+      }
+    },
+    (Some(PathSegment::Key(first)), _, 1) if first.as_ref() == BLOCKS => {
+      for (block_id, change) in event.keys(txn).iter() {
+        match change {
+          EntryChange::Inserted(Out::YMap(map)) | EntryChange::Updated(_, Out::YMap(map)) => {
+            modified_index.set_block(block_id, indexed_block_from_map(txn, map));
+          },
+          _ => modified_index.remove_block(block_id),
+        }
+      }
+    },
+    (Some(PathSegment::Key(first)), Some(PathSegment::Key(block_id)), 2)
+      if first.as_ref() == BLOCKS =>
+    {
+      modified_index.set_block(block_id, indexed_block_from_map(txn, event.target()));
+    },
+    _ => {},
+  }
+}
+
+/// Collects the changes of the event that may move blocks in the tree of the document. The text
+/// edits and the updates of the data of the blocks don't change the tree.
+fn tree_changes_from_event(txn: &TransactionMut, event: &Event, changes: &mut Vec<TreeChange>) {
+  let path = event.path();
+  let key_at = |index: usize| match path.get(index) {
+    Some(PathSegment::Key(key)) => Some(key.as_ref()),
+    _ => None,
+  };
+  match (event, key_at(0), key_at(1), path.len()) {
+    (Event::Map(event), None, _, 0) => {
+      let keys = event.keys(txn);
+      if [PAGE_ID, BLOCKS, META]
+        .iter()
+        .any(|key| keys.contains_key(*key))
+      {
+        changes.push(TreeChange::Reset);
+      }
+    },
+    (Event::Map(event), Some(META), None, 1) => {
+      if event.keys(txn).contains_key(CHILDREN_MAP) {
+        changes.push(TreeChange::Reset);
+      }
+    },
+    (Event::Map(event), Some(META), Some(CHILDREN_MAP), 2) => {
+      for children_id in event.keys(txn).keys() {
+        changes.push(TreeChange::Children(children_id.to_string()));
+      }
+    },
+    (Event::Array(_), Some(META), Some(CHILDREN_MAP), 3) => {
+      if let Some(children_id) = key_at(2) {
+        changes.push(TreeChange::Children(children_id.to_string()));
+      }
+    },
+    (Event::Map(event), Some(BLOCKS), None, 1) => {
+      for block_id in event.keys(txn).keys() {
+        changes.push(TreeChange::Block(block_id.to_string()));
+      }
+    },
+    (Event::Map(event), Some(BLOCKS), Some(block_id), 2) => {
+      if event.keys(txn).contains_key(CHILDREN) {
+        changes.push(TreeChange::Block(block_id.to_string()));
+      }
+    },
+    _ => {},
+  }
+}
+
+/// The links between the blocks of the document, read in a transaction.
+struct TxnBlockLinks<'a, T: ReadTxn> {
+  txn: &'a T,
+  block_operation: &'a BlockOperation,
+  children_operation: &'a ChildrenOperation,
+}
+
+impl<T: ReadTxn> BlockLinks for TxnBlockLinks<'_, T> {
+  fn children_id(&self, block_id: &str) -> Option<String> {
+    self
+      .block_operation
+      .get_block_links_with_txn(self.txn, block_id)
+      .map(|(_, children_id)| children_id)
+  }
+
+  fn parent_id(&self, block_id: &str) -> Option<String> {
+    self
+      .block_operation
+      .get_block_links_with_txn(self.txn, block_id)
+      .map(|(parent_id, _)| parent_id)
+  }
+
+  fn children(&self, children_id: &str) -> Vec<String> {
+    self
+      .children_operation
+      .get_children(self.txn, children_id)
+      .into_iter()
+      .map(|child| child.to_string(self.txn))
+      .collect()
+  }
+}
+
 /// Represents a the index content of a document.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct DocumentIndexContent {
@@ -0,0 +1,157 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::block_index::BlockTree;
+use crate::blocks::Block;
+use crate::importer::define::{BlockType, IMAGE_TYPE_FIELD, URL_FIELD};
+
+pub const NAME_FIELD: &str = "name";
+/// Where the file of a [BlockType::File] is stored, 0 on the device, 1 an external link and 2 the
+/// cloud storage of the workspace.
+pub const FILE_URL_TYPE_FIELD: &str = "url_type";
+/// The images of a [BlockType::Gallery], each a map with an [URL_FIELD] and a `type` like the
+/// image blocks.
+pub const GALLERY_IMAGES_FIELD: &str = "images";
+const GALLERY_IMAGE_TYPE_FIELD: &str = "type";
+
+/// Where the file of a [DocumentAsset] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetUploadState {
+  /// A file on the device that is not uploaded yet. Its url is a local path.
+  Local,
+  /// A file stored in the cloud storage of the workspace.
+  Uploaded,
+  /// A link to a file outside the workspace.
+  External,
+}
+
+/// A file referenced by a block of a document, see [crate::document::Document::collect_assets].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DocumentAsset {
+  pub block_id: String,
+  pub block_type: String,
+  /// The url of the file, or its path on the device if it's [AssetUploadState::Local].
+  pub url: String,
+  pub name: Option<String>,
+  pub upload_state: AssetUploadState,
+}
+
+/// Return the assets of the blocks of the tree, in the order of the document. The blocks that
+/// are not reachable from the page block are left out.
+pub(crate) fn collect_tree_assets(tree: &BlockTree) -> Vec<DocumentAsset> {
+  let (page_id, blocks, children_map) = tree;
+  let mut assets = vec![];
+  let mut visited = HashSet::new();
+  let mut stack = vec![page_id.as_str()];
+  while let Some(block_id) = stack.pop() {
+    let block = match blocks.get(block_id) {
+      Some(block) if visited.insert(block_id) => block,
+      _ => continue,
+    };
+    assets.extend(block_assets(block));
+    if let Some(children) = children_map.get(&block.children) {
+      stack.extend(children.iter().rev().map(String::as_str));
+    }
+  }
+  assets
+}
+
+/// Return the assets referenced by the data of the block.
+pub fn block_assets(block: &Block) -> Vec<DocumentAsset> {
+  let asset =
+    |url: &str, name: Option<String>, upload_state: Option<AssetUploadState>| DocumentAsset {
+      block_id: block.id.clone(),
+      block_type: block.ty.clone(),
+      url: url.to_string(),
+      name,
+      upload_state: upload_state.unwrap_or_else(|| upload_state_from_url(url)),
+    };
+  let name = || {
+    block
+      .data
+      .get(NAME_FIELD)
+      .and_then(Value::as_str)
+      .map(String::from)
+  };
+  match BlockType::from_block_ty(&block.ty) {
+    BlockType::Image => match url_of(&block.data) {
+      Some(url) => {
+        let state = image_upload_state(block.data.get(IMAGE_TYPE_FIELD));
+        vec![asset(url, name(), state)]
+      },
+      None => vec![],
+    },
+    BlockType::File => match url_of(&block.data) {
+      Some(url) => {
+        let state = file_upload_state(block.data.get(FILE_URL_TYPE_FIELD));
+        vec![asset(url, name(), state)]
+      },
+      None => vec![],
+    },
+    BlockType::Gallery => block
+      .data
+      .get(GALLERY_IMAGES_FIELD)
+      .and_then(Value::as_array)
+      .into_iter()
+      .flatten()
+      .filter_map(|image| {
+        let image = image.as_object()?;
+        let url = image
+          .get(URL_FIELD)
+          .and_then(Value::as_str)
+          .filter(|url| !url.is_empty())?;
+        let state = image_upload_state(image.get(GALLERY_IMAGE_TYPE_FIELD));
+        Some(asset(url, None, state))
+      })
+      .collect(),
+    _ => vec![],
+  }
+}
+
+fn url_of(data: &HashMap<String, Value>) -> Option<&str> {
+  data
+    .get(URL_FIELD)
+    .and_then(Value::as_str)
+    .filter(|url| !url.is_empty())
+}
+
+fn type_of(value: Option<&Value>) -> Option<i64> {
+  match value? {
+    Value::Number(number) => number.as_i64(),
+    Value::String(text) => text.parse().ok(),
+    _ => None,
+  }
+}
+
+/// The image type is 0 for a file on the device, 1 for the cloud storage and 2 for an external
+/// link.
+fn image_upload_state(value: Option<&Value>) -> Option<AssetUploadState> {
+  match type_of(value)? {
+    0 => Some(AssetUploadState::Local),
+    1 => Some(AssetUploadState::Uploaded),
+    2 => Some(AssetUploadState::External),
+    _ => None,
+  }
+}
+
+fn file_upload_state(value: Option<&Value>) -> Option<AssetUploadState> {
+  match type_of(value)? {
+    0 => Some(AssetUploadState::Local),
+    1 => Some(AssetUploadState::External),
+    2 => Some(AssetUploadState::Uploaded),
+    _ => None,
+  }
+}
+
+/// The blocks written without a type are links if their url has a scheme, local files
+/// otherwise.
+fn upload_state_from_url(url: &str) -> AssetUploadState {
+  if url.starts_with("http://") || url.starts_with("https://") {
+    AssetUploadState::External
+  } else {
+    AssetUploadState::Local
+  }
+}
@@ -0,0 +1,82 @@
+use collab::core::origin::CollabOrigin;
+use collab::preclude::block::ClientID;
+use collab::preclude::text::YChange;
+use collab::preclude::*;
+
+/// The key of the map, in the meta of the document, that links the client ids of the edits to
+/// the users that made them. The key of an entry is the client id and its value the uid.
+pub(crate) const AUTHORS: &str = "authors";
+
+/// A range of the text of a block inserted by the same client. The offsets are in the units of
+/// the text, like the indexes of a delta, and the end is excluded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockAuthorRange {
+  pub start: u32,
+  pub end: u32,
+  pub client_id: ClientID,
+  /// The user of the client, or None if the client didn't record it, for example because it
+  /// edited the document with a version that didn't track the authors.
+  pub uid: Option<i64>,
+}
+
+/// Link the client of the transaction to the user of its origin, so the text it inserts can be
+/// attributed. Does nothing if the link is already recorded or the origin is not a client.
+pub(crate) fn record_author(txn: &mut TransactionMut, authors: &MapRef) {
+  let uid = match CollabOrigin::from(&*txn).client_user_id() {
+    Some(uid) => uid,
+    None => return,
+  };
+  let client_id = txn.doc().client_id().to_string();
+  let recorded: Option<i64> = authors.get_with_txn(txn, &client_id);
+  if recorded != Some(uid) {
+    authors.insert(txn, client_id, uid);
+  }
+}
+
+/// Return the ranges of the text grouped by the client that inserted them. Every character keeps
+/// the id of the client that inserted it, so a formatted range is still attributed to its
+/// writer, and a replaced range to the client that replaced it.
+///
+/// The text is read as a delta against an empty snapshot, so each chunk of the delta is the
+/// content of one item and carries the id of the client that inserted it.
+pub(crate) fn text_author_ranges(
+  txn: &mut TransactionMut,
+  text: &TextRef,
+  authors: Option<&MapRef>,
+) -> Vec<BlockAuthorRange> {
+  let snapshot = txn.snapshot();
+  let offset_kind = txn.doc().offset_kind();
+  let chunks = text.diff_range(
+    txn,
+    Some(&snapshot),
+    Some(&Snapshot::default()),
+    YChange::identity,
+  );
+  let mut ranges: Vec<BlockAuthorRange> = vec![];
+  let mut index = 0;
+  for chunk in chunks {
+    let len = match &chunk.insert {
+      Out::Any(Any::String(text)) => match offset_kind {
+        OffsetKind::Bytes => text.len() as u32,
+        OffsetKind::Utf16 => text.encode_utf16().count() as u32,
+      },
+      _ => 1,
+    };
+    let start = index;
+    index += len;
+    let client_id = match chunk.ychange {
+      Some(change) => change.id.client,
+      None => continue,
+    };
+    match ranges.last_mut() {
+      Some(range) if range.client_id == client_id && range.end == start => range.end = index,
+      _ => ranges.push(BlockAuthorRange {
+        start,
+        end: index,
+        client_id,
+        uid: authors.and_then(|authors| authors.get_with_txn(txn, &client_id.to_string())),
+      }),
+    }
+  }
+  ranges
+}
@@ -0,0 +1,72 @@
+use collab::core::collab_backlink::LinkSource;
+use collab::preclude::{Any, Events, ReadTxn, TransactionMut};
+
+use crate::blocks::{changed_blocks_from_events, Block, BlockOperation, TextDelta, TextOperation};
+
+/// Returns the mentions of every block that mentions other objects.
+pub(crate) fn document_mentions<T: ReadTxn>(
+  object_id: &str,
+  txn: &T,
+  block_operation: &BlockOperation,
+  text_operation: &TextOperation,
+) -> Vec<(LinkSource, Vec<String>)> {
+  block_operation
+    .get_all_blocks(txn)
+    .into_values()
+    .flat_map(|block| {
+      let mentions = block_mentions(txn, &block, text_operation);
+      if mentions.is_empty() {
+        None
+      } else {
+        Some((LinkSource::new(object_id, block.id), mentions))
+      }
+    })
+    .collect()
+}
+
+/// Returns the mentions of the blocks changed by the events. The mentions of a removed block are
+/// empty.
+pub(crate) fn mention_changes_from_events(
+  object_id: &str,
+  txn: &TransactionMut,
+  events: &Events,
+  block_operation: &BlockOperation,
+  text_operation: &TextOperation,
+) -> Vec<(LinkSource, Vec<String>)> {
+  changed_blocks_from_events(object_id, txn, events, block_operation)
+    .into_iter()
+    .map(|(block_id, block)| {
+      let mentions = block
+        .map(|block| block_mentions(txn, &block, text_operation))
+        .unwrap_or_default();
+      (LinkSource::new(object_id, block_id), mentions)
+    })
+    .collect()
+}
+
+/// Returns the ids of the objects mentioned in the text of the block. A mention of a database row
+/// has a `row_id`, the other mentions point to the `page_id`.
+fn block_mentions<T: ReadTxn>(
+  txn: &T,
+  block: &Block,
+  text_operation: &TextOperation,
+) -> Vec<String> {
+  let deltas = block
+    .external_id
+    .as_ref()
+    .and_then(|text_id| text_operation.get_delta_with_txn(txn, text_id))
+    .unwrap_or_default();
+  deltas
+    .iter()
+    .filter_map(|delta| match delta {
+      TextDelta::Inserted(_, Some(attrs)) => match attrs.get("mention") {
+        Some(Any::Map(mention)) => mention
+          .get("row_id")
+          .or_else(|| mention.get("page_id"))
+          .map(|id| id.to_string()),
+        _ => None,
+      },
+      _ => None,
+    })
+    .collect()
+}
@@ -0,0 +1,65 @@
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use collab::preclude::{Events, TransactionMut};
+
+use crate::blocks::{changed_blocks_from_events, Block, BlockOperation};
+
+/// A change of the blocks of a type, see [crate::document::Document::subscribe_blocks_of_type].
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockTypeChange {
+  /// The block was inserted, or its type was changed to the subscribed type.
+  Inserted(Block),
+  /// The data, the text or the parent of the block changed.
+  Updated(Block),
+  /// The block was deleted, or its type was changed to another type.
+  Removed { block_id: String },
+}
+
+/// Keeps the ids of the blocks of one type and turns the events of the document into the changes
+/// of these blocks.
+pub(crate) struct BlockTypeTracker {
+  block_type: String,
+  block_ids: Mutex<HashSet<String>>,
+}
+
+impl BlockTypeTracker {
+  pub(crate) fn new<'a>(block_type: &str, block_ids: impl IntoIterator<Item = &'a String>) -> Self {
+    Self {
+      block_type: block_type.to_string(),
+      block_ids: Mutex::new(block_ids.into_iter().cloned().collect()),
+    }
+  }
+
+  pub(crate) fn changes_from_events(
+    &self,
+    object_id: &str,
+    txn: &TransactionMut,
+    events: &Events,
+    block_operation: &BlockOperation,
+  ) -> Vec<BlockTypeChange> {
+    let mut tracked_ids = match self.block_ids.lock() {
+      Ok(tracked_ids) => tracked_ids,
+      Err(_) => return vec![],
+    };
+    changed_blocks_from_events(object_id, txn, events, block_operation)
+      .into_iter()
+      .filter_map(|(block_id, block)| {
+        let block = block.filter(|block| block.ty == self.block_type);
+        let was_tracked = tracked_ids.contains(&block_id);
+        match block {
+          Some(block) if was_tracked => Some(BlockTypeChange::Updated(block)),
+          Some(block) => {
+            tracked_ids.insert(block_id);
+            Some(BlockTypeChange::Inserted(block))
+          },
+          None if was_tracked => {
+            tracked_ids.remove(&block_id);
+            Some(BlockTypeChange::Removed { block_id })
+          },
+          None => None,
+        }
+      })
+      .collect()
+  }
+}
@@ -0,0 +1,152 @@
+use std::collections::HashSet;
+
+use collab::preclude::ReadTxn;
+
+use crate::blocks::{BlockOperation, ChildrenOperation, TextOperation};
+use crate::document_plain_text::block_plain_text;
+use crate::importer::define::COLLAPSED_FIELD;
+
+/// Return the line of the block in the context, the text of the block after a marker with its id.
+pub fn context_line(block_id: &str, text: &str) -> String {
+  format!("[{}] {}", block_id, text)
+}
+
+/// Build the context centered on the block, see [crate::document::Document::extract_context].
+/// Return None if the block is not in the page.
+pub(crate) fn extract_context<T: ReadTxn>(
+  txn: &T,
+  page_id: &str,
+  block_id: &str,
+  max_chars: usize,
+  block_operation: &BlockOperation,
+  children_operation: &ChildrenOperation,
+  text_operation: &TextOperation,
+) -> Option<String> {
+  let mut block_ids = vec![];
+  let mut visited = HashSet::from([page_id.to_string()]);
+  visible_block_ids(
+    txn,
+    page_id,
+    block_id,
+    block_operation,
+    children_operation,
+    &mut visited,
+    &mut block_ids,
+  );
+  let target = block_ids.iter().position(|id| id == block_id)?;
+  let texts = block_ids
+    .iter()
+    .map(|id| {
+      block_operation
+        .get_block_with_txn(txn, id)
+        .map(|block| block_plain_text(txn, text_operation, &block))
+        .unwrap_or_default()
+    })
+    .collect::<Vec<_>>();
+  let lines = block_ids
+    .iter()
+    .zip(texts.iter())
+    .map(|(id, text)| context_line(id, text))
+    .collect::<Vec<_>>();
+
+  // The line of the block is always kept, with its whole marker. Only its text is cut to the
+  // budget if it's too long.
+  let target_line = cut_context_line(block_id, &texts[target], max_chars);
+  let mut used = target_line.chars().count();
+  let (mut start, mut end) = (target, target + 1);
+  let (mut before_done, mut after_done) = (false, false);
+  // Add the lines before and after the block in turn, until the next one doesn't fit. Each line
+  // takes a line break on top of its characters.
+  while !(before_done && after_done) {
+    if !before_done {
+      match start.checked_sub(1) {
+        Some(prev) if used + lines[prev].chars().count() < max_chars => {
+          used += lines[prev].chars().count() + 1;
+          start = prev;
+        },
+        _ => before_done = true,
+      }
+    }
+    if !after_done {
+      match lines.get(end) {
+        Some(line) if used + line.chars().count() < max_chars => {
+          used += line.chars().count() + 1;
+          end += 1;
+        },
+        _ => after_done = true,
+      }
+    }
+  }
+
+  let mut context = lines[start..target].to_vec();
+  context.push(target_line);
+  context.extend_from_slice(&lines[target + 1..end]);
+  Some(context.join("\n"))
+}
+
+/// Return the line of the block with its whole marker, and as much of its text as fits in the
+/// budget.
+fn cut_context_line(block_id: &str, text: &str, max_chars: usize) -> String {
+  let marker = format!("[{}]", block_id);
+  let budget = max_chars.saturating_sub(marker.chars().count() + 1);
+  if budget == 0 {
+    return marker;
+  }
+  format!(
+    "{} {}",
+    marker,
+    text.chars().take(budget).collect::<String>()
+  )
+}
+
+/// Collect the ids of the blocks under the parent in the order of the document. The children of
+/// the collapsed blocks are left out, unless the target block is one of them. A block that
+/// appears twice in the tree, or in its own children, is only visited once.
+fn visible_block_ids<T: ReadTxn>(
+  txn: &T,
+  parent_id: &str,
+  target_id: &str,
+  block_operation: &BlockOperation,
+  children_operation: &ChildrenOperation,
+  visited: &mut HashSet<String>,
+  block_ids: &mut Vec<String>,
+) -> bool {
+  let parent = match block_operation.get_block_with_txn(txn, parent_id) {
+    None => return false,
+    Some(parent) => parent,
+  };
+  let mut contains_target = false;
+  for child in children_operation.get_children(txn, &parent.children) {
+    let block_id = child.to_string(txn);
+    if !visited.insert(block_id.clone()) {
+      continue;
+    }
+    let collapsed = block_operation
+      .get_block_with_txn(txn, &block_id)
+      .and_then(|block| {
+        block
+          .data
+          .get(COLLAPSED_FIELD)
+          .and_then(|value| value.as_bool())
+      })
+      .unwrap_or(false);
+    contains_target |= block_id == target_id;
+    block_ids.push(block_id.clone());
+
+    let len = block_ids.len();
+    let children_contain_target = visible_block_ids(
+      txn,
+      &block_id,
+      target_id,
+      block_operation,
+      children_operation,
+      visited,
+      block_ids,
+    );
+    if collapsed && !children_contain_target {
+      block_ids.truncate(len);
+    }
+    contains_target |= children_contain_target;
+  }
+  contains_target
+}
@@ -0,0 +1,149 @@
+use std::collections::HashSet;
+
+use collab::core::collab::DataSource;
+use collab::core::origin::CollabOrigin;
+use collab::entity::EncodedCollab;
+use collab::preclude::Collab;
+
+use crate::blocks::{Block, DocumentData};
+use crate::document::Document;
+use crate::error::DocumentError;
+
+/// The changes between two versions of a document, for example two snapshots. The blocks are
+/// listed in the order they appear in the document.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DocumentDiff {
+  pub inserted_blocks: Vec<BlockChange>,
+  pub deleted_blocks: Vec<BlockChange>,
+  pub updated_blocks: Vec<BlockUpdate>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockChange {
+  pub block_id: String,
+  pub ty: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockUpdate {
+  pub block_id: String,
+  pub ty: String,
+  /// The type or the data of the block changed.
+  pub data_changed: bool,
+  /// The text delta of the block changed.
+  pub text_changed: bool,
+  /// The block moved to another parent, or its children were added, removed or reordered.
+  pub moved: bool,
+}
+
+impl DocumentDiff {
+  pub fn new(older: &DocumentData, newer: &DocumentData) -> Self {
+    let mut diff = DocumentDiff::default();
+    for block_id in blocks_in_document_order(newer) {
+      let new_block = &newer.blocks[&block_id];
+      match older.blocks.get(&block_id) {
+        None => diff.inserted_blocks.push(BlockChange::from(new_block)),
+        Some(old_block) => {
+          let update = BlockUpdate {
+            block_id,
+            ty: new_block.ty.clone(),
+            data_changed: old_block.ty != new_block.ty || old_block.data != new_block.data,
+            text_changed: block_text(older, old_block) != block_text(newer, new_block),
+            moved: old_block.parent != new_block.parent
+              || block_children(older, old_block) != block_children(newer, new_block),
+          };
+          if update.data_changed || update.text_changed || update.moved {
+            diff.updated_blocks.push(update);
+          }
+        },
+      }
+    }
+    for block_id in blocks_in_document_order(older) {
+      if !newer.blocks.contains_key(&block_id) {
+        diff
+          .deleted_blocks
+          .push(BlockChange::from(&older.blocks[&block_id]));
+      }
+    }
+    diff
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.inserted_blocks.is_empty()
+      && self.deleted_blocks.is_empty()
+      && self.updated_blocks.is_empty()
+  }
+}
+
+impl From<&Block> for BlockChange {
+  fn from(block: &Block) -> Self {
+    Self {
+      block_id: block.id.clone(),
+      ty: block.ty.clone(),
+    }
+  }
+}
+
+/// Decodes both versions of the document and returns the changes from `older` to `newer`.
+pub fn diff_document_snapshots(
+  older: &EncodedCollab,
+  newer: &EncodedCollab,
+) -> Result<DocumentDiff, DocumentError> {
+  let older = document_data_from_encoded_collab(older)?;
+  let newer = document_data_from_encoded_collab(newer)?;
+  Ok(DocumentDiff::new(&older, &newer))
+}
+
+fn document_data_from_encoded_collab(
+  encoded_collab: &EncodedCollab,
+) -> Result<DocumentData, DocumentError> {
+  let collab = Collab::new_with_source(
+    CollabOrigin::Empty,
+    "",
+    DataSource::from(encoded_collab.clone()),
+    vec![],
+    false,
+  )?;
+  Document::open(collab)?.get_document_data()
+}
+
+fn block_text<'a>(data: &'a DocumentData, block: &Block) -> Option<&'a String> {
+  let text_id = block.external_id.as_ref()?;
+  data.meta.text_map.as_ref()?.get(text_id)
+}
+
+/// A block without children may have no entry in the children map.
+fn block_children<'a>(data: &'a DocumentData, block: &Block) -> &'a [String] {
+  data
+    .meta
+    .children_map
+    .get(&block.children)
+    .map(Vec::as_slice)
+    .unwrap_or_default()
+}
+
+/// Return the ids of the blocks reachable from the page, depth first, followed by the blocks that
+/// are not attached to the page.
+fn blocks_in_document_order(data: &DocumentData) -> Vec<String> {
+  let mut visited = HashSet::new();
+  let mut block_ids = Vec::with_capacity(data.blocks.len());
+  let mut stack = vec![data.page_id.clone()];
+  while let Some(block_id) = stack.pop() {
+    let block = match data.blocks.get(&block_id) {
+      Some(block) if visited.insert(block_id.clone()) => block,
+      _ => continue,
+    };
+    block_ids.push(block_id);
+    stack.extend(block_children(data, block).iter().rev().cloned());
+  }
+
+  let mut detached = data
+    .blocks
+    .keys()
+    .filter(|block_id| !visited.contains(*block_id))
+    .cloned()
+    .collect::<Vec<_>>();
+  detached.sort();
+  block_ids.extend(detached);
+  block_ids
+}
@@ -0,0 +1,249 @@
+use std::collections::HashSet;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+use collab::preclude::ReadTxn;
+
+use crate::blocks::{Block, BlockOperation, ChildrenOperation, TextDelta, TextOperation};
+use crate::importer::define::{BlockType, CHECKED_FIELD, START_NUMBER_FIELD};
+
+/// The indentation of a nested block, for each level below the top level blocks.
+pub const PLAIN_TEXT_INDENT: &str = "  ";
+
+/// How a block is rendered, returned by the [PlainTextBlockRenderer] of the [PlainTextOptions].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlainTextBlock {
+  /// Render the block with the options.
+  Default,
+  /// Replace the line of the block, the marker and the indentation included. The children are
+  /// rendered as usual.
+  Line(String),
+  /// Leave out the block and its children.
+  Skip,
+}
+
+/// The block being rendered, passed to the [PlainTextBlockRenderer].
+pub struct PlainTextBlockContext<'a> {
+  pub block: &'a Block,
+  /// The text of the block, without marker and indentation.
+  pub text: &'a str,
+  /// The depth of the block, 1 for the top level blocks.
+  pub depth: usize,
+}
+
+pub type PlainTextBlockRenderer =
+  Arc<dyn Fn(&PlainTextBlockContext) -> PlainTextBlock + Send + Sync>;
+
+/// The options of [crate::document::Document::to_plain_text_with_options]. The default options
+/// render the text of every block on its own line, without markers or indentation.
+#[derive(Clone, Default)]
+pub struct PlainTextOptions {
+  /// Start the lines of the bulleted lists with `- ` and the lines of the numbered lists with
+  /// their number.
+  pub include_list_markers: bool,
+  /// Indent the nested blocks with [PLAIN_TEXT_INDENT] for each level.
+  pub indent_nested_blocks: bool,
+  /// Start the lines of the todo lists with `[x] ` or `[ ] `.
+  pub render_todo_checkboxes: bool,
+  /// The blocks nested deeper than the depth are left out, the top level blocks have a depth of
+  /// 1. None for no limit.
+  pub max_depth: Option<usize>,
+  block_renderer: Option<PlainTextBlockRenderer>,
+}
+
+impl Debug for PlainTextOptions {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("PlainTextOptions")
+      .field("include_list_markers", &self.include_list_markers)
+      .field("indent_nested_blocks", &self.indent_nested_blocks)
+      .field("render_todo_checkboxes", &self.render_todo_checkboxes)
+      .field("max_depth", &self.max_depth)
+      .field("block_renderer", &self.block_renderer.is_some())
+      .finish()
+  }
+}
+
+impl PlainTextOptions {
+  /// The options that keep the structure of the document readable, for example when copying it
+  /// to the clipboard.
+  pub fn structured() -> Self {
+    Self {
+      include_list_markers: true,
+      indent_nested_blocks: true,
+      render_todo_checkboxes: true,
+      ..Default::default()
+    }
+  }
+
+  pub fn with_list_markers(mut self, include_list_markers: bool) -> Self {
+    self.include_list_markers = include_list_markers;
+    self
+  }
+
+  pub fn with_indent_nested_blocks(mut self, indent_nested_blocks: bool) -> Self {
+    self.indent_nested_blocks = indent_nested_blocks;
+    self
+  }
+
+  pub fn with_todo_checkboxes(mut self, render_todo_checkboxes: bool) -> Self {
+    self.render_todo_checkboxes = render_todo_checkboxes;
+    self
+  }
+
+  pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+    self.max_depth = Some(max_depth);
+    self
+  }
+
+  /// Set the function called for each block before it's rendered, to override how the block is
+  /// rendered.
+  pub fn with_block_renderer<F>(mut self, renderer: F) -> Self
+  where
+    F: Fn(&PlainTextBlockContext) -> PlainTextBlock + Send + Sync + 'static,
+  {
+    self.block_renderer = Some(Arc::new(renderer));
+    self
+  }
+}
+
+/// Render the blocks nested under the page, in the order of the document, one line per block.
+pub(crate) fn render_plain_text<T: ReadTxn>(
+  txn: &T,
+  page_id: &str,
+  block_operation: &BlockOperation,
+  children_operation: &ChildrenOperation,
+  text_operation: &TextOperation,
+  options: &PlainTextOptions,
+) -> String {
+  let renderer = PlainTextRenderer {
+    txn,
+    block_operation,
+    children_operation,
+    text_operation,
+    options,
+  };
+  let mut lines = vec![];
+  let mut visited = HashSet::from([page_id.to_string()]);
+  renderer.render_children(page_id, 1, &mut visited, &mut lines);
+  lines.join("\n")
+}
+
+struct PlainTextRenderer<'a, T> {
+  txn: &'a T,
+  block_operation: &'a BlockOperation,
+  children_operation: &'a ChildrenOperation,
+  text_operation: &'a TextOperation,
+  options: &'a PlainTextOptions,
+}
+
+impl<'a, T: ReadTxn> PlainTextRenderer<'a, T> {
+  /// Render the children of the parent. A block that appears twice in the tree, or in its own
+  /// children, is only rendered once.
+  fn render_children(
+    &self,
+    parent_id: &str,
+    depth: usize,
+    visited: &mut HashSet<String>,
+    lines: &mut Vec<String>,
+  ) {
+    if self
+      .options
+      .max_depth
+      .is_some_and(|max_depth| depth > max_depth)
+    {
+      return;
+    }
+    let parent = match self.block_operation.get_block_with_txn(self.txn, parent_id) {
+      None => return,
+      Some(parent) => parent,
+    };
+    // The number of the current item of a numbered list, reset by the other blocks.
+    let mut number = None;
+    for child in self
+      .children_operation
+      .get_children(self.txn, &parent.children)
+    {
+      let block_id = child.to_string(self.txn);
+      if !visited.insert(block_id.clone()) {
+        continue;
+      }
+      let block = match self.block_operation.get_block_with_txn(self.txn, &block_id) {
+        None => continue,
+        Some(block) => block,
+      };
+      number = match BlockType::from_block_ty(&block.ty) {
+        BlockType::NumberedList => Some(match number {
+          None => block
+            .data
+            .get(START_NUMBER_FIELD)
+            .and_then(|number| number.as_i64())
+            .unwrap_or(1),
+          Some(number) => number + 1,
+        }),
+        _ => None,
+      };
+
+      let text = block_plain_text(self.txn, self.text_operation, &block);
+      let rendering = match &self.options.block_renderer {
+        None => PlainTextBlock::Default,
+        Some(renderer) => renderer(&PlainTextBlockContext {
+          block: &block,
+          text: &text,
+          depth,
+        }),
+      };
+      match rendering {
+        PlainTextBlock::Skip => continue,
+        PlainTextBlock::Line(line) => lines.push(line),
+        PlainTextBlock::Default => lines.push(self.block_line(&block, &text, depth, number)),
+      }
+      self.render_children(&block.id, depth + 1, visited, lines);
+    }
+  }
+
+  fn block_line(&self, block: &Block, text: &str, depth: usize, number: Option<i64>) -> String {
+    let mut line = String::new();
+    if self.options.indent_nested_blocks {
+      line.push_str(&PLAIN_TEXT_INDENT.repeat(depth - 1));
+    }
+    match BlockType::from_block_ty(&block.ty) {
+      BlockType::BulletedList if self.options.include_list_markers => line.push_str("- "),
+      BlockType::NumberedList if self.options.include_list_markers => {
+        line.push_str(&format!("{}. ", number.unwrap_or(1)));
+      },
+      BlockType::TodoList if self.options.render_todo_checkboxes => {
+        let checked = block
+          .data
+          .get(CHECKED_FIELD)
+          .and_then(|checked| checked.as_bool())
+          .unwrap_or(false);
+        line.push_str(if checked { "[x] " } else { "[ ] " });
+      },
+      _ => {},
+    }
+    line.push_str(text);
+    line
+  }
+}
+
+/// Return the text of the block without its formatting, empty if the block has no text.
+pub(crate) fn block_plain_text<T: ReadTxn>(
+  txn: &T,
+  text_operation: &TextOperation,
+  block: &Block,
+) -> String {
+  block
+    .external_id
+    .as_ref()
+    .and_then(|text_id| text_operation.get_delta_with_txn(txn, text_id))
+    .map(|delta| {
+      delta
+        .iter()
+        .filter_map(|delta| match delta {
+          TextDelta::Inserted(text, _) => Some(text.as_str()),
+          _ => None,
+        })
+        .collect()
+    })
+    .unwrap_or_default()
+}
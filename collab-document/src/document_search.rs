@@ -0,0 +1,66 @@
+use collab::core::collab_search::{IndexChange, IndexContentType, IndexedContent};
+use collab::preclude::{Events, ReadTxn, TransactionMut};
+
+use crate::blocks::{changed_blocks_from_events, Block, BlockOperation, TextDelta, TextOperation};
+
+/// Returns the text of every block that has a non-empty text.
+pub(crate) fn document_index_content<T: ReadTxn>(
+  object_id: &str,
+  txn: &T,
+  block_operation: &BlockOperation,
+  text_operation: &TextOperation,
+) -> Vec<IndexedContent> {
+  block_operation
+    .get_all_blocks(txn)
+    .into_values()
+    .flat_map(|block| block_index_content(object_id, txn, &block, text_operation))
+    .collect()
+}
+
+/// Converts the events of the document into the changes of the blocks whose text changed.
+pub(crate) fn index_changes_from_events(
+  object_id: &str,
+  txn: &TransactionMut,
+  events: &Events,
+  block_operation: &BlockOperation,
+  text_operation: &TextOperation,
+) -> Vec<IndexChange> {
+  changed_blocks_from_events(object_id, txn, events, block_operation)
+    .into_iter()
+    .map(|(block_id, block)| {
+      block
+        .and_then(|block| block_index_content(object_id, txn, &block, text_operation))
+        .map(IndexChange::Upsert)
+        .unwrap_or_else(|| IndexChange::Remove {
+          object_id: object_id.to_string(),
+          id: block_id,
+        })
+    })
+    .collect()
+}
+
+fn block_index_content<T: ReadTxn>(
+  object_id: &str,
+  txn: &T,
+  block: &Block,
+  text_operation: &TextOperation,
+) -> Option<IndexedContent> {
+  let text_id = block.external_id.as_ref()?;
+  let text = text_operation
+    .get_delta_with_txn(txn, text_id)?
+    .into_iter()
+    .filter_map(|delta| match delta {
+      TextDelta::Inserted(text, _) => Some(text),
+      _ => None,
+    })
+    .collect::<String>();
+  if text.trim().is_empty() {
+    return None;
+  }
+  Some(IndexedContent::new(
+    object_id,
+    &block.id,
+    text,
+    IndexContentType::Document,
+  ))
+}
@@ -1,3 +1,6 @@
+use std::fmt::Display;
+
+use collab::error::{anyhow_error_code, ClassifiedError, ErrorCode, ErrorContext};
 use collab_entity::CollabValidateError;
 
 #[derive(Debug, thiserror::Error)]
@@ -46,6 +49,12 @@ pub enum DocumentError {
 
   #[error("Unable to parse markdown to document data")]
   ParseMarkdownError,
+
+  #[error("{context}: {source}")]
+  Context {
+    context: String,
+    source: Box<DocumentError>,
+  },
 }
 
 impl From<CollabValidateError> for DocumentError {
@@ -55,3 +64,35 @@ impl From<CollabValidateError> for DocumentError {
     }
   }
 }
+
+impl ClassifiedError for DocumentError {
+  fn code(&self) -> ErrorCode {
+    match self {
+      DocumentError::Internal(err) => anyhow_error_code(err),
+      DocumentError::CollabError(err) => err.code(),
+      DocumentError::BlockAlreadyExists => ErrorCode::BlockAlreadyExists,
+      DocumentError::BlockIsNotFound
+      | DocumentError::ParentIsNotFound
+      | DocumentError::ExternalIdIsNotFound => ErrorCode::BlockNotFound,
+      DocumentError::BlockCreateError
+      | DocumentError::CreateRootBlockError
+      | DocumentError::DeleteBlockError
+      | DocumentError::TextActionParamsError => ErrorCode::InvalidBlockOperation,
+      DocumentError::PageIdIsEmpty | DocumentError::NoRequiredData => ErrorCode::NoRequiredData,
+      DocumentError::ConvertDataError => ErrorCode::InvalidData,
+      DocumentError::ParseDocumentError | DocumentError::ParseMarkdownError => {
+        ErrorCode::ParseDocument
+      },
+      DocumentError::Context { source, .. } => source.code(),
+    }
+  }
+}
+
+impl ErrorContext for DocumentError {
+  fn context<C: Display>(self, context: C) -> Self {
+    DocumentError::Context {
+      context: context.to_string(),
+      source: Box::new(self),
+    }
+  }
+}
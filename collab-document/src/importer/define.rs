@@ -11,6 +11,8 @@ pub enum BlockType {
   NumberedList,
   BulletedList,
   Image,
+  File,
+  Gallery,
   LinkPreview,
   Code,
   MathEquation,
@@ -32,6 +34,8 @@ impl BlockType {
       BlockType::NumberedList => "numbered_list",
       BlockType::BulletedList => "bulleted_list",
       BlockType::Image => "image",
+      BlockType::File => "file",
+      BlockType::Gallery => "multi_image",
       BlockType::LinkPreview => "link_preview",
       BlockType::Code => "code",
       BlockType::MathEquation => "math_equation",
@@ -53,6 +57,8 @@ impl BlockType {
       "numbered_list" => BlockType::NumberedList,
       "bulleted_list" => BlockType::BulletedList,
       "image" => BlockType::Image,
+      "file" => BlockType::File,
+      "multi_image" => BlockType::Gallery,
       "link_preview" => BlockType::LinkPreview,
       "code" => BlockType::Code,
       "math_equation" => BlockType::MathEquation,
@@ -133,4 +139,7 @@ pub const COL_POSITION_FIELD: &str = "colPosition";
 pub const CHECKED_FIELD: &str = "checked";
 pub const START_NUMBER_FIELD: &str = "number";
 
+// Toggle Keys
+pub const COLLAPSED_FIELD: &str = "collapsed";
+
 pub const ALIGN_FIELD: &str = "align";
@@ -1,6 +1,17 @@
+pub mod block_index;
 pub mod blocks;
 pub mod document;
+pub mod document_assets;
+pub mod document_attribution;
 pub mod document_awareness;
+mod document_backlink;
+pub mod document_block_type;
+pub mod document_context;
 pub mod document_data;
+pub mod document_diff;
+pub mod document_plain_text;
+mod document_search;
 pub mod error;
 pub mod importer;
+#[cfg(feature = "test_utils")]
+pub mod test_utils;
@@ -0,0 +1,138 @@
+//! Generators of random but valid documents for the tests and the fuzzers. Only available with
+//! the `test_utils` feature.
+
+use std::collections::HashMap;
+
+use collab::test_utils::SeededRng;
+use serde_json::json;
+
+use crate::blocks::{Block, DocumentData, DocumentMeta};
+use crate::document::Document;
+use crate::document_data::{PAGE, PARAGRAPH_BLOCK_TYPE};
+use crate::error::DocumentError;
+
+/// The types of the generated text blocks, with the data they carry.
+const BLOCK_TYPES: &[&str] = &[
+  PARAGRAPH_BLOCK_TYPE,
+  "heading",
+  "todo_list",
+  "bulleted_list",
+  "numbered_list",
+  "quote",
+];
+
+/// Generates a document with the given number of text blocks, nested up to the given depth under
+/// the page. The same seed always generates the same ids, blocks and texts.
+///
+/// ```ignore
+/// let document = DocumentGenerator::new(7).with_blocks(500).build("document_id")?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct DocumentGenerator {
+  seed: u64,
+  num_blocks: usize,
+  max_depth: usize,
+  max_words: usize,
+}
+
+impl DocumentGenerator {
+  pub fn new(seed: u64) -> Self {
+    Self {
+      seed,
+      num_blocks: 10,
+      max_depth: 3,
+      max_words: 12,
+    }
+  }
+
+  pub fn with_blocks(mut self, num_blocks: usize) -> Self {
+    self.num_blocks = num_blocks;
+    self
+  }
+
+  /// The maximum depth of the blocks, 1 means that all the blocks are children of the page.
+  pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+    self.max_depth = max_depth.max(1);
+    self
+  }
+
+  /// The maximum number of words of the text of a block.
+  pub fn with_max_words(mut self, max_words: usize) -> Self {
+    self.max_words = max_words.max(1);
+    self
+  }
+
+  pub fn generate_data(&self) -> DocumentData {
+    let mut rng = SeededRng::new(self.seed);
+    let page_id = rng.gen_uuid();
+    let mut blocks = HashMap::new();
+    let mut children_map = HashMap::new();
+    let mut text_map = HashMap::new();
+
+    blocks.insert(
+      page_id.clone(),
+      Block {
+        id: page_id.clone(),
+        ty: PAGE.to_string(),
+        parent: "".to_string(),
+        children: page_id.clone(),
+        external_id: None,
+        external_type: None,
+        data: HashMap::new(),
+      },
+    );
+    children_map.insert(page_id.clone(), vec![]);
+
+    // The blocks that can have children, with their depth and the id of their children.
+    let mut parents = vec![(page_id.clone(), 0, page_id.clone())];
+    for _ in 0..self.num_blocks {
+      let (parent_id, depth, children_id) = rng.choose(&parents).clone();
+      let block_id = rng.gen_uuid();
+      let block_children_id = rng.gen_uuid();
+      let text_id = rng.gen_uuid();
+      let ty = *rng.choose(BLOCK_TYPES);
+      let data = match ty {
+        "heading" => HashMap::from([("level".to_string(), json!(rng.gen_range(1..4)))]),
+        "todo_list" => HashMap::from([("checked".to_string(), json!(rng.gen_bool(0.5)))]),
+        _ => HashMap::new(),
+      };
+      let num_words = rng.gen_range(1..self.max_words + 1);
+      let delta = json!([{ "insert": rng.gen_sentence(num_words) }]);
+
+      blocks.insert(
+        block_id.clone(),
+        Block {
+          id: block_id.clone(),
+          ty: ty.to_string(),
+          parent: parent_id,
+          children: block_children_id.clone(),
+          external_id: Some(text_id.clone()),
+          external_type: Some("text".to_string()),
+          data,
+        },
+      );
+      if let Some(children) = children_map.get_mut(&children_id) {
+        children.push(block_id.clone());
+      }
+      children_map.insert(block_children_id.clone(), vec![]);
+      text_map.insert(text_id, delta.to_string());
+      if depth + 1 < self.max_depth {
+        parents.push((block_id, depth + 1, block_children_id));
+      }
+    }
+
+    DocumentData {
+      page_id,
+      blocks,
+      meta: DocumentMeta {
+        children_map,
+        text_map: Some(text_map),
+      },
+    }
+  }
+
+  /// Creates the generated document in memory.
+  pub fn build(&self, document_id: &str) -> Result<Document, DocumentError> {
+    Document::create(document_id, self.generate_data())
+  }
+}
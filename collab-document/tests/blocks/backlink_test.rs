@@ -0,0 +1,54 @@
+use collab::core::collab_backlink::{BacklinkRegistry, BacklinkType, LinkSource};
+use serde_json::json;
+
+use crate::blocks::block_test_core::BlockTestCore;
+
+fn mention_delta(mention: serde_json::Value) -> String {
+  json!([
+    { "insert": "Linked to " },
+    { "insert": "$", "attributes": { "mention": mention } }
+  ])
+  .to_string()
+}
+
+#[test]
+fn register_block_mentions_test() {
+  let mut test = BlockTestCore::new();
+  let page = test.get_page();
+  let block = test.insert_text_block("".to_string(), &page.id, None);
+  let text_id = block.external_id.clone().unwrap();
+  test.document.apply_text_delta(
+    &text_id,
+    mention_delta(json!({ "type": "page", "page_id": "view_1" })),
+  );
+
+  let registry = BacklinkRegistry::new();
+  test.document.subscribe_backlinks(registry.clone());
+  let backlinks = registry.backlinks_for("view_1");
+  assert_eq!(backlinks.len(), 1);
+  assert_eq!(backlinks[0].source, LinkSource::new("1", &block.id));
+  assert_eq!(backlinks[0].link_type, BacklinkType::Mention);
+
+  // A row mention links to the row instead of the database view.
+  let row_block = test.insert_text_block("".to_string(), &page.id, Some(block.id.clone()));
+  test.document.apply_text_delta(
+    row_block.external_id.as_ref().unwrap(),
+    mention_delta(json!({ "type": "page", "page_id": "view_2", "row_id": "row_1" })),
+  );
+  assert_eq!(
+    registry.backlinks_for("row_1")[0].source,
+    LinkSource::new("1", &row_block.id)
+  );
+  assert!(registry.backlinks_for("view_2").is_empty());
+  assert_eq!(registry.links_from("1").len(), 2);
+
+  test.document.apply_text_delta(
+    &text_id,
+    json!([{ "retain": 10 }, { "delete": 1 }]).to_string(),
+  );
+  assert!(registry.backlinks_for("view_1").is_empty());
+
+  test.delete_block(&row_block.id);
+  assert!(registry.backlinks_for("row_1").is_empty());
+  assert!(registry.links_from("1").is_empty());
+}
@@ -1,3 +1,5 @@
+mod backlink_test;
 mod block_test;
 mod block_test_core;
+mod search_index_test;
 mod text_test;
@@ -0,0 +1,61 @@
+use collab::core::collab_search::{IndexContentType, SearchQuery, WorkspaceSearchIndex};
+use serde_json::json;
+
+use crate::blocks::block_test_core::BlockTestCore;
+
+fn search_ids(index: &WorkspaceSearchIndex, query: &str) -> Vec<String> {
+  index
+    .search(&SearchQuery::new(query))
+    .into_iter()
+    .map(|content| content.id)
+    .collect()
+}
+
+#[test]
+fn index_existing_blocks_test() {
+  let mut test = BlockTestCore::new();
+  let page = test.get_page();
+  let block = test.insert_text_block("Hello World".to_string(), &page.id, None);
+
+  let index = WorkspaceSearchIndex::new();
+  test.document.subscribe_search_index(index.clone());
+
+  let contents = index.search(&SearchQuery::new("hello"));
+  assert_eq!(contents.len(), 1);
+  assert_eq!(contents[0].object_id, "1");
+  assert_eq!(contents[0].id, block.id);
+  assert_eq!(contents[0].text, "Hello World");
+  assert_eq!(contents[0].content_type, IndexContentType::Document);
+  assert!(search_ids(&index, "goodbye").is_empty());
+}
+
+#[test]
+fn update_index_on_block_change_test() {
+  let mut test = BlockTestCore::new();
+  let index = WorkspaceSearchIndex::new();
+  test.document.subscribe_search_index(index.clone());
+  let page = test.get_page();
+
+  let first = test.insert_text_block("Search index".to_string(), &page.id, None);
+  let second = test.insert_text_block(
+    "Index of the workspace".to_string(),
+    &page.id,
+    Some(first.id.clone()),
+  );
+  // The block that contains the whole query comes first.
+  assert_eq!(
+    search_ids(&index, "index workspace"),
+    vec![second.id.clone()]
+  );
+  assert_eq!(search_ids(&index, "search index"), vec![first.id.clone()]);
+  assert_eq!(search_ids(&index, "index").len(), 2);
+
+  let text_id = first.external_id.clone().unwrap();
+  let delta = json!([{ "retain": 12 }, { "insert": " subsystem" }]).to_string();
+  test.document.apply_text_delta(&text_id, delta);
+  assert_eq!(search_ids(&index, "subsystem"), vec![first.id.clone()]);
+
+  test.delete_block(&first.id);
+  assert!(search_ids(&index, "subsystem").is_empty());
+  assert_eq!(search_ids(&index, "index"), vec![second.id]);
+}
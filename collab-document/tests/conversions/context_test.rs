@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+
+use collab_document::blocks::{Block, DocumentData, DocumentMeta};
+use collab_document::document::Document;
+use collab_document::error::DocumentError;
+use serde_json::json;
+
+/// The blocks of the document: (id, parent id, type, text, collapsed).
+const BLOCKS: [(&str, &str, &str, &str, bool); 7] = [
+  ("intro", "page", "paragraph", "Welcome", false),
+  ("steps", "page", "toggle_list", "Steps", true),
+  ("step_1", "steps", "paragraph", "Open the app", false),
+  ("step_2", "steps", "paragraph", "Sign in", false),
+  ("notes", "page", "bulleted_list", "Notes", false),
+  ("note_1", "notes", "paragraph", "Keep it short", false),
+  ("outro", "page", "paragraph", "Thanks", false),
+];
+
+fn create_document() -> Document {
+  create_document_with(|_| {})
+}
+
+/// Create the document, after `edit` changed its children map.
+fn create_document_with(edit: impl FnOnce(&mut HashMap<String, Vec<String>>)) -> Document {
+  let mut blocks = HashMap::new();
+  let mut children_map = HashMap::<String, Vec<String>>::new();
+  let mut text_map = HashMap::new();
+  blocks.insert(
+    "page".to_string(),
+    Block {
+      id: "page".to_string(),
+      ty: "page".to_string(),
+      parent: "".to_string(),
+      children: "page_children".to_string(),
+      data: HashMap::new(),
+      external_id: None,
+      external_type: None,
+    },
+  );
+  children_map.insert("page_children".to_string(), vec![]);
+  for (id, parent, ty, text, collapsed) in BLOCKS {
+    let mut data = HashMap::new();
+    if collapsed {
+      data.insert("collapsed".to_string(), json!(true));
+    }
+    blocks.insert(
+      id.to_string(),
+      Block {
+        id: id.to_string(),
+        ty: ty.to_string(),
+        parent: parent.to_string(),
+        children: format!("{}_children", id),
+        data,
+        external_id: Some(format!("{}_text", id)),
+        external_type: Some("text".to_string()),
+      },
+    );
+    children_map.insert(format!("{}_children", id), vec![]);
+    children_map
+      .get_mut(&format!("{}_children", parent))
+      .unwrap()
+      .push(id.to_string());
+    text_map.insert(
+      format!("{}_text", id),
+      json!([{ "insert": text }]).to_string(),
+    );
+  }
+  edit(&mut children_map);
+  let data = DocumentData {
+    page_id: "page".to_string(),
+    blocks,
+    meta: DocumentMeta {
+      children_map,
+      text_map: Some(text_map),
+    },
+  };
+  Document::create("context_document", data).unwrap()
+}
+
+#[test]
+fn extract_whole_context_test() {
+  let document = create_document();
+  // The steps are collapsed.
+  assert_eq!(
+    document.extract_context("notes", 1000).unwrap(),
+    "[intro] Welcome\n[steps] Steps\n[notes] Notes\n[note_1] Keep it short\n[outro] Thanks"
+  );
+}
+
+#[test]
+fn extract_context_within_budget_test() {
+  let document = create_document();
+  // "[notes] Notes" is 13 characters, "[steps] Steps" takes 14 with its line break and
+  // "[note_1] Keep it short" takes 23.
+  assert_eq!(
+    document.extract_context("notes", 50).unwrap(),
+    "[steps] Steps\n[notes] Notes\n[note_1] Keep it short"
+  );
+  // The line after the block doesn't fit, the lines before it still do.
+  assert_eq!(
+    document.extract_context("notes", 45).unwrap(),
+    "[intro] Welcome\n[steps] Steps\n[notes] Notes"
+  );
+  // The text of the block is cut to the budget, its marker is kept.
+  assert_eq!(document.extract_context("notes", 10).unwrap(), "[notes] No");
+  assert_eq!(document.extract_context("notes", 5).unwrap(), "[notes]");
+
+  // The context is the same each time it's built.
+  assert_eq!(
+    document.extract_context("note_1", 60).unwrap(),
+    document.extract_context("note_1", 60).unwrap()
+  );
+}
+
+#[test]
+fn extract_context_in_collapsed_section_test() {
+  let document = create_document();
+  assert_eq!(
+    document.extract_context("step_2", 1000).unwrap(),
+    "[intro] Welcome\n[steps] Steps\n[step_1] Open the app\n[step_2] Sign in\n[notes] Notes\n\
+     [note_1] Keep it short\n[outro] Thanks"
+  );
+
+  assert!(matches!(
+    document.extract_context("unknown", 1000),
+    Err(DocumentError::BlockIsNotFound)
+  ));
+}
+
+#[test]
+fn extract_context_with_cyclic_children_test() {
+  // The notes are one of their own children.
+  let document = create_document_with(|children_map| {
+    children_map
+      .get_mut("note_1_children")
+      .unwrap()
+      .push("notes".to_string());
+  });
+  assert_eq!(
+    document.extract_context("note_1", 1000).unwrap(),
+    "[intro] Welcome\n[steps] Steps\n[notes] Notes\n[note_1] Keep it short\n[outro] Thanks"
+  );
+}
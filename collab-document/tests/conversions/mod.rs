@@ -1 +1,2 @@
+mod context_test;
 mod plain_text_test;
@@ -1,5 +1,11 @@
+use std::collections::HashMap;
+
+use collab_document::blocks::{DocumentData, DocumentMeta};
+use collab_document::document_plain_text::{PlainTextBlock, PlainTextOptions};
+use collab_document::importer::md_importer::MDImporter;
 use collab_document::{blocks::Block, document::Document};
 use nanoid::nanoid;
+use serde_json::json;
 
 use crate::util::DocumentTest;
 
@@ -55,3 +61,105 @@ fn insert_paragraphs(document: &mut Document, paragraphs: Vec<String>) {
     document.apply_text_delta(&text_id, format!(r#"[{{"insert": "{}"}}]"#, paragraph));
   }
 }
+
+fn markdown_document(markdown: &str) -> Document {
+  let document_id = nanoid!(6);
+  let data = MDImporter::new(None)
+    .import(&document_id, markdown.to_string())
+    .unwrap();
+  Document::create(&document_id, data).unwrap()
+}
+
+const MARKDOWN: &str = "# Plan\n\n- Design\n  - Sketch\n    - Paper\n- Build\n\n1. First\n2. Second\n\n- [x] Done\n- [ ] Todo";
+
+#[test]
+fn plain_text_with_default_options_test() {
+  let document = markdown_document(MARKDOWN);
+  let plain_text = document
+    .to_plain_text_with_options(&PlainTextOptions::default())
+    .unwrap();
+  assert_eq!(
+    plain_text,
+    "Plan\nDesign\nSketch\nPaper\nBuild\nFirst\nSecond\nDone\nTodo"
+  );
+}
+
+#[test]
+fn plain_text_with_structured_options_test() {
+  let document = markdown_document(MARKDOWN);
+  let plain_text = document
+    .to_plain_text_with_options(&PlainTextOptions::structured())
+    .unwrap();
+  assert_eq!(
+    plain_text,
+    "Plan\n- Design\n  - Sketch\n    - Paper\n- Build\n1. First\n2. Second\n[x] Done\n[ ] Todo"
+  );
+
+  let plain_text = document
+    .to_plain_text_with_options(&PlainTextOptions::structured().with_max_depth(2))
+    .unwrap();
+  assert_eq!(
+    plain_text,
+    "Plan\n- Design\n  - Sketch\n- Build\n1. First\n2. Second\n[x] Done\n[ ] Todo"
+  );
+}
+
+#[test]
+fn plain_text_with_block_renderer_test() {
+  let document = markdown_document(MARKDOWN);
+  let options = PlainTextOptions::default()
+    .with_list_markers(true)
+    .with_block_renderer(|context| match context.block.ty.as_str() {
+      "heading" => PlainTextBlock::Line(format!("## {}", context.text.to_uppercase())),
+      "todo_list" => PlainTextBlock::Skip,
+      _ if context.text == "Sketch" => PlainTextBlock::Skip,
+      _ => PlainTextBlock::Default,
+    });
+  let plain_text = document.to_plain_text_with_options(&options).unwrap();
+  assert_eq!(
+    plain_text,
+    "## PLAN\n- Design\n- Build\n1. First\n2. Second"
+  );
+}
+
+#[test]
+fn plain_text_with_cyclic_children_test() {
+  let block = |id: &str, parent: &str, text_id: Option<&str>| Block {
+    id: id.to_string(),
+    ty: if parent.is_empty() {
+      "page"
+    } else {
+      "paragraph"
+    }
+    .to_string(),
+    parent: parent.to_string(),
+    children: format!("{}_children", id),
+    data: HashMap::new(),
+    external_id: text_id.map(str::to_string),
+    external_type: text_id.map(|_| "text".to_string()),
+  };
+  // The paragraph is one of its own children.
+  let data = DocumentData {
+    page_id: "page".to_string(),
+    blocks: HashMap::from([
+      ("page".to_string(), block("page", "", None)),
+      ("a".to_string(), block("a", "page", Some("a_text"))),
+    ]),
+    meta: DocumentMeta {
+      children_map: HashMap::from([
+        ("page_children".to_string(), vec!["a".to_string()]),
+        ("a_children".to_string(), vec!["a".to_string()]),
+      ]),
+      text_map: Some(HashMap::from([(
+        "a_text".to_string(),
+        json!([{ "insert": "Hello" }]).to_string(),
+      )])),
+    },
+  };
+  let document = Document::create("cyclic_document", data).unwrap();
+  let options = PlainTextOptions::structured();
+  assert_eq!(
+    document.to_plain_text_with_options(&options).unwrap(),
+    "Hello"
+  );
+}
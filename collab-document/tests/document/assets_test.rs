@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+
+use collab_document::blocks::Block;
+use collab_document::document::Document;
+use collab_document::document_assets::{AssetUploadState, DocumentAsset};
+use collab_document::test_utils::DocumentGenerator;
+use serde_json::{json, Value};
+
+fn insert_block(
+  document: &mut Document,
+  id: &str,
+  ty: &str,
+  parent_id: &str,
+  prev_id: Option<&str>,
+  data: Value,
+) {
+  let data: HashMap<String, Value> = serde_json::from_value(data).unwrap();
+  let block = Block {
+    id: id.to_string(),
+    ty: ty.to_string(),
+    parent: parent_id.to_string(),
+    children: format!("{}-children", id),
+    external_id: None,
+    external_type: None,
+    data,
+  };
+  document
+    .insert_block(block, prev_id.map(String::from))
+    .unwrap();
+}
+
+fn asset(block_id: &str, block_type: &str, url: &str, state: AssetUploadState) -> DocumentAsset {
+  DocumentAsset {
+    block_id: block_id.to_string(),
+    block_type: block_type.to_string(),
+    url: url.to_string(),
+    name: None,
+    upload_state: state,
+  }
+}
+
+#[test]
+fn collect_assets_test() {
+  let mut document = DocumentGenerator::new(1).with_blocks(0).build("1").unwrap();
+  let page_id = document.get_page_id().unwrap();
+  insert_block(
+    &mut document,
+    "image",
+    "image",
+    &page_id,
+    None,
+    json!({ "url": "https://appflowy.io/a.png", "image_type": 1 }),
+  );
+  insert_block(
+    &mut document,
+    "paragraph",
+    "paragraph",
+    &page_id,
+    Some("image"),
+    json!({}),
+  );
+  // A file nested in the paragraph comes before the blocks after the paragraph.
+  insert_block(
+    &mut document,
+    "file",
+    "file",
+    "paragraph",
+    None,
+    json!({ "url": "/tmp/report.pdf", "name": "report.pdf", "url_type": 0 }),
+  );
+  insert_block(
+    &mut document,
+    "gallery",
+    "multi_image",
+    &page_id,
+    Some("paragraph"),
+    json!({ "images": [
+      { "url": "https://example.com/b.png", "type": 2 },
+      { "url": "" },
+      { "url": "c.png" },
+    ] }),
+  );
+  insert_block(
+    &mut document,
+    "empty_image",
+    "image",
+    &page_id,
+    Some("gallery"),
+    json!({ "url": "" }),
+  );
+  insert_block(
+    &mut document,
+    "link",
+    "image",
+    &page_id,
+    Some("empty_image"),
+    json!({ "url": "https://example.com/d.png" }),
+  );
+
+  let mut file = asset("file", "file", "/tmp/report.pdf", AssetUploadState::Local);
+  file.name = Some("report.pdf".to_string());
+  assert_eq!(
+    document.collect_assets(),
+    vec![
+      asset(
+        "image",
+        "image",
+        "https://appflowy.io/a.png",
+        AssetUploadState::Uploaded
+      ),
+      file,
+      asset(
+        "gallery",
+        "multi_image",
+        "https://example.com/b.png",
+        AssetUploadState::External
+      ),
+      asset("gallery", "multi_image", "c.png", AssetUploadState::Local),
+      asset(
+        "link",
+        "image",
+        "https://example.com/d.png",
+        AssetUploadState::External
+      ),
+    ]
+  );
+
+  // The assets of a deleted block are gone.
+  document.delete_block("paragraph").unwrap();
+  let block_ids = document
+    .collect_assets()
+    .into_iter()
+    .map(|asset| asset.block_id)
+    .collect::<Vec<_>>();
+  assert_eq!(block_ids, vec!["image", "gallery", "gallery", "link"]);
+}
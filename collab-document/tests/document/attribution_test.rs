@@ -0,0 +1,89 @@
+use collab::core::collab::DataSource;
+use collab::core::origin::{CollabClient, CollabOrigin};
+use collab::preclude::updates::decoder::Decode;
+use collab::preclude::{Collab, ReadTxn, Update};
+use collab_document::document::Document;
+use collab_document::document_attribution::BlockAuthorRange;
+
+use crate::util::{get_document_data, insert_block_for_page, DocumentTest};
+
+fn open_as(document: &Document, uid: i64) -> Document {
+  let doc_state = document.encode_collab().unwrap().doc_state.to_vec();
+  let collab = Collab::new_with_source(
+    CollabOrigin::Client(CollabClient::new(uid, uid.to_string())),
+    document.object_id(),
+    DataSource::DocStateV1(doc_state),
+    vec![],
+    false,
+  )
+  .unwrap();
+  Document::open(collab).unwrap()
+}
+
+fn sync(from: &Document, to: &mut Document) {
+  let state_vector = to.transact().state_vector();
+  let update = from.transact().encode_state_as_update_v1(&state_vector);
+  to.apply_update(Update::decode_v1(&update).unwrap())
+    .unwrap();
+}
+
+/// Return the id of the text block and the id of its text.
+fn text_block(document: &Document) -> (String, String) {
+  let (_, blocks, _) = get_document_data(document);
+  let block = blocks
+    .values()
+    .find(|block| block.external_id.is_some())
+    .unwrap();
+  (block.id.clone(), block.external_id.clone().unwrap())
+}
+
+#[test]
+fn block_authors_of_concurrent_edits_test() {
+  let mut test = DocumentTest::new(1, "1");
+  let (block_id, text_id) = text_block(&test.document);
+  test
+    .document
+    .apply_text_delta(&text_id, r#"[{"insert": "Hello"}]"#.to_string());
+
+  let mut other = open_as(&test.document, 2);
+  other.apply_text_delta(
+    &text_id,
+    r#"[{"retain": 5}, {"insert": " World"}]"#.to_string(),
+  );
+  sync(&other, &mut test.document);
+
+  let expected = vec![
+    BlockAuthorRange {
+      start: 0,
+      end: 5,
+      client_id: test.document.client_id(),
+      uid: Some(1),
+    },
+    BlockAuthorRange {
+      start: 5,
+      end: 11,
+      client_id: other.client_id(),
+      uid: Some(2),
+    },
+  ];
+  assert_eq!(test.document.get_block_authors(&block_id), expected);
+  assert_eq!(other.get_block_authors(&block_id), expected);
+
+  // Formatting the text doesn't change its author.
+  test.document.apply_text_delta(
+    &text_id,
+    r#"[{"retain": 6}, {"retain": 5, "attributes": {"bold": true}}]"#.to_string(),
+  );
+  assert_eq!(test.document.get_block_authors(&block_id), expected);
+}
+
+#[test]
+fn block_authors_without_text_test() {
+  let mut test = DocumentTest::new(1, "1");
+  insert_block_for_page(&mut test.document, "block_1".to_string());
+  assert!(test.document.get_block_authors("block_1").is_empty());
+  assert!(test.document.get_block_authors("unknown").is_empty());
+
+  let (block_id, _) = text_block(&test.document);
+  assert!(test.document.get_block_authors(&block_id).is_empty());
+}
@@ -0,0 +1,102 @@
+use collab::core::origin::CollabOrigin;
+use collab::preclude::Collab;
+use collab_document::block_index::BlockPosition;
+use collab_document::blocks::Block;
+use collab_document::document::Document;
+use collab_document::test_utils::DocumentGenerator;
+use yrs::updates::decoder::Decode;
+use yrs::{ReadTxn, StateVector, Update};
+
+fn insert_block(document: &mut Document, id: &str, parent_id: &str, prev_id: Option<&str>) {
+  let block = Block {
+    id: id.to_string(),
+    ty: "paragraph".to_string(),
+    parent: parent_id.to_string(),
+    children: format!("{}-children", id),
+    external_id: None,
+    external_type: None,
+    data: Default::default(),
+  };
+  document
+    .insert_block(block, prev_id.map(|id| id.to_string()))
+    .unwrap();
+}
+
+fn position(parent_id: &str, index: u32, depth: u32) -> Option<BlockPosition> {
+  Some(BlockPosition {
+    parent_id: Some(parent_id.to_string()),
+    index,
+    depth,
+  })
+}
+
+#[test]
+fn block_position_test() {
+  let mut document = DocumentGenerator::new(1).with_blocks(0).build("1").unwrap();
+  let page_id = document.get_page_id().unwrap();
+  insert_block(&mut document, "a", &page_id, None);
+  insert_block(&mut document, "b", &page_id, Some("a"));
+  insert_block(&mut document, "c", "a", None);
+  insert_block(&mut document, "d", "c", None);
+
+  assert_eq!(
+    document.get_block_position(&page_id),
+    Some(BlockPosition {
+      parent_id: None,
+      index: 0,
+      depth: 0
+    })
+  );
+  assert_eq!(document.get_block_position("a"), position(&page_id, 0, 1));
+  assert_eq!(document.get_block_position("b"), position(&page_id, 1, 1));
+  assert_eq!(document.get_block_position("d"), position("c", 0, 3));
+  assert_eq!(
+    document.get_block_ancestor_ids("d"),
+    vec!["c".to_string(), "a".to_string(), page_id.clone()]
+  );
+  assert!(document.is_block_descendant_of("d", "a"));
+  assert!(!document.is_block_descendant_of("a", "d"));
+  assert!(document.get_block_position("unknown").is_none());
+
+  // The index follows the moves and the deletes.
+  document
+    .move_block("c", Some("b".to_string()), None)
+    .unwrap();
+  assert_eq!(document.get_block_position("c"), position("b", 0, 2));
+  assert_eq!(document.get_block_position("d"), position("c", 0, 3));
+  assert!(document.is_block_descendant_of("d", "b"));
+
+  document.delete_block("a").unwrap();
+  assert!(document.get_block_position("a").is_none());
+  assert_eq!(document.get_block_position("b"), position(&page_id, 0, 1));
+
+  // The subtree of a moved block follows the depth of its new parent.
+  document
+    .move_block("c", Some(page_id.clone()), Some("b".to_string()))
+    .unwrap();
+  assert_eq!(document.get_block_position("c"), position(&page_id, 1, 1));
+  assert_eq!(document.get_block_position("d"), position("c", 0, 2));
+}
+
+#[test]
+fn block_position_follows_remote_changes_test() {
+  let mut document = DocumentGenerator::new(1).with_blocks(0).build("1").unwrap();
+  let page_id = document.get_page_id().unwrap();
+  insert_block(&mut document, "a", &page_id, None);
+  assert_eq!(document.get_block_position("a"), position(&page_id, 0, 1));
+
+  let encoded = document.encode_collab().unwrap();
+  let collab =
+    Collab::new_with_source(CollabOrigin::Empty, "1", encoded.into(), vec![], false).unwrap();
+  let mut remote = Document::open(collab).unwrap();
+  insert_block(&mut remote, "b", &page_id, None);
+  let update = remote
+    .transact()
+    .encode_state_as_update_v1(&StateVector::default());
+  document
+    .apply_update(Update::decode_v1(&update).unwrap())
+    .unwrap();
+
+  assert_eq!(document.get_block_position("b"), position(&page_id, 0, 1));
+  assert_eq!(document.get_block_position("a"), position(&page_id, 1, 1));
+}
@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use collab::core::clock::ManualClock;
+use collab_document::blocks::BlockTimestamps;
+use serde_json::json;
+
+use crate::util::{get_document_data, insert_block_for_page, DocumentTest};
+
+// Later than the creation of the blocks of the test document, stamped with the system clock.
+const NOW: i64 = 4_000_000_000;
+
+#[test]
+fn stamp_block_timestamps_test() {
+  let mut test = DocumentTest::new(1, "1");
+  let clock = Arc::new(ManualClock::new(NOW));
+  test.document.set_clock(clock.clone());
+  let (_, blocks, _) = get_document_data(&test.document);
+  let text_block = blocks
+    .values()
+    .find(|block| block.external_id.is_some())
+    .unwrap()
+    .clone();
+
+  insert_block_for_page(&mut test.document, "block_1".to_string());
+  assert_eq!(
+    test.document.get_block_timestamps("block_1"),
+    Some(BlockTimestamps {
+      created_at: Some(NOW),
+      modified_at: Some(NOW),
+    })
+  );
+  assert_eq!(test.document.get_block_timestamps("unknown"), None);
+
+  clock.advance(10);
+  test
+    .document
+    .update_block("block_1", HashMap::from([("level".to_string(), json!(1))]))
+    .unwrap();
+  assert_eq!(
+    test.document.get_block_timestamps("block_1"),
+    Some(BlockTimestamps {
+      created_at: Some(NOW),
+      modified_at: Some(NOW + 10),
+    })
+  );
+
+  // Editing the text of a block modifies the block.
+  clock.advance(10);
+  test.document.apply_text_delta(
+    text_block.external_id.as_ref().unwrap(),
+    r#"[{"insert": "Hello"}]"#.to_string(),
+  );
+  let timestamps = test.document.get_block_timestamps(&text_block.id).unwrap();
+  assert_eq!(timestamps.modified_at, Some(NOW + 20));
+
+  let recent = test.document.get_recently_modified_blocks(2);
+  assert_eq!(
+    recent,
+    vec![
+      (text_block.id.clone(), NOW + 20),
+      ("block_1".to_string(), NOW + 10)
+    ]
+  );
+  assert_eq!(test.document.get_recently_modified_blocks(1).len(), 1);
+
+  // Typing right after the last stamp doesn't stamp the block again.
+  clock.advance(2);
+  test.document.apply_text_delta(
+    text_block.external_id.as_ref().unwrap(),
+    r#"[{"retain": 5}, {"insert": " World"}]"#.to_string(),
+  );
+  let timestamps = test.document.get_block_timestamps(&text_block.id).unwrap();
+  assert_eq!(timestamps.modified_at, Some(NOW + 20));
+
+  // The recently modified blocks follow the later stamps.
+  clock.advance(10);
+  test
+    .document
+    .update_block("block_1", HashMap::from([("level".to_string(), json!(2))]))
+    .unwrap();
+  assert_eq!(
+    test.document.get_recently_modified_blocks(2),
+    vec![
+      ("block_1".to_string(), NOW + 32),
+      (text_block.id.clone(), NOW + 20)
+    ]
+  );
+}
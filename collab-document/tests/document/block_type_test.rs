@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+
+use collab::core::origin::CollabOrigin;
+use collab::preclude::Collab;
+use collab_document::blocks::Block;
+use collab_document::document::Document;
+use collab_document::document_block_type::BlockTypeChange;
+use collab_document::test_utils::DocumentGenerator;
+use futures::{FutureExt, Stream, StreamExt};
+use serde_json::json;
+use yrs::updates::decoder::Decode;
+use yrs::{ReadTxn, StateVector, Update};
+
+fn insert_block(document: &mut Document, id: &str, ty: &str, parent_id: &str) {
+  let block = Block {
+    id: id.to_string(),
+    ty: ty.to_string(),
+    parent: parent_id.to_string(),
+    children: format!("{}-children", id),
+    external_id: None,
+    external_type: None,
+    data: Default::default(),
+  };
+  document.insert_block(block, None).unwrap();
+}
+
+/// Returns the changes that were sent so far.
+fn sent_changes(
+  stream: &mut (impl Stream<Item = BlockTypeChange> + Unpin),
+) -> Vec<BlockTypeChange> {
+  let mut changes = vec![];
+  while let Some(Some(change)) = stream.next().now_or_never() {
+    changes.push(change);
+  }
+  changes
+}
+
+#[test]
+fn subscribe_blocks_of_type_test() {
+  let mut document = DocumentGenerator::new(1).with_blocks(0).build("1").unwrap();
+  let page_id = document.get_page_id().unwrap();
+  insert_block(&mut document, "todo_1", "todo_list", &page_id);
+  insert_block(&mut document, "paragraph_1", "paragraph", &page_id);
+
+  let (blocks, stream) = document.subscribe_blocks_of_type("todo_list");
+  let mut stream = Box::pin(stream);
+  assert_eq!(blocks.keys().collect::<Vec<_>>(), vec!["todo_1"]);
+
+  insert_block(&mut document, "todo_2", "todo_list", &page_id);
+  insert_block(&mut document, "paragraph_2", "paragraph", &page_id);
+  let changes = sent_changes(&mut stream);
+  assert_eq!(changes.len(), 1);
+  assert!(matches!(&changes[0], BlockTypeChange::Inserted(block) if block.id == "todo_2"));
+
+  document
+    .update_block(
+      "todo_1",
+      HashMap::from([("checked".to_string(), json!(true))]),
+    )
+    .unwrap();
+  document
+    .update_block(
+      "paragraph_1",
+      HashMap::from([("level".to_string(), json!(1))]),
+    )
+    .unwrap();
+  let changes = sent_changes(&mut stream);
+  assert_eq!(changes.len(), 1);
+  match &changes[0] {
+    BlockTypeChange::Updated(block) => {
+      assert_eq!(block.id, "todo_1");
+      assert_eq!(block.data.get("checked"), Some(&json!(true)));
+    },
+    change => panic!("unexpected change: {:?}", change),
+  }
+
+  document.delete_block("todo_2").unwrap();
+  document.delete_block("paragraph_2").unwrap();
+  assert_eq!(
+    sent_changes(&mut stream),
+    vec![BlockTypeChange::Removed {
+      block_id: "todo_2".to_string()
+    }]
+  );
+}
+
+#[test]
+fn blocks_of_type_follow_remote_changes_test() {
+  let mut document = DocumentGenerator::new(1).with_blocks(0).build("1").unwrap();
+  let page_id = document.get_page_id().unwrap();
+  let encoded = document.encode_collab().unwrap();
+  let collab =
+    Collab::new_with_source(CollabOrigin::Empty, "1", encoded.into(), vec![], false).unwrap();
+  let mut remote = Document::open(collab).unwrap();
+  let (blocks, stream) = document.subscribe_blocks_of_type("todo_list");
+  let mut stream = Box::pin(stream);
+  assert!(blocks.is_empty());
+
+  insert_block(&mut remote, "todo_1", "todo_list", &page_id);
+  let update = remote
+    .transact()
+    .encode_state_as_update_v1(&StateVector::default());
+  document
+    .apply_update(Update::decode_v1(&update).unwrap())
+    .unwrap();
+
+  let changes = sent_changes(&mut stream);
+  assert_eq!(changes.len(), 1);
+  assert!(matches!(&changes[0], BlockTypeChange::Inserted(block) if block.id == "todo_1"));
+
+  // Subscribing again keeps the previous stream.
+  let (blocks, other_stream) = document.subscribe_blocks_of_type("todo_list");
+  let mut other_stream = Box::pin(other_stream);
+  assert_eq!(blocks.len(), 1);
+  insert_block(&mut document, "todo_2", "todo_list", &page_id);
+  for stream in [&mut stream, &mut other_stream] {
+    let changes = sent_changes(stream);
+    assert_eq!(changes.len(), 1);
+    assert!(matches!(&changes[0], BlockTypeChange::Inserted(block) if block.id == "todo_2"));
+  }
+}
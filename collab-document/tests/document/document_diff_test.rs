@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+
+use crate::util::{get_document_data, insert_block_for_page, DocumentTest};
+use collab_document::blocks::Block;
+use collab_document::document_diff::{diff_document_snapshots, BlockChange};
+use serde_json::json;
+
+#[test]
+fn diff_inserted_blocks_test() {
+  let mut test = DocumentTest::new(1, "1");
+  let (page_id, _, _) = get_document_data(&test.document);
+  let older = test.document.encode_collab().unwrap();
+
+  insert_block_for_page(&mut test.document, "block_1".to_string());
+  let block = Block {
+    id: "block_2".to_string(),
+    ty: "heading".to_string(),
+    parent: page_id.clone(),
+    children: "".to_string(),
+    external_id: Some("text_2".to_string()),
+    external_type: Some("text".to_string()),
+    data: Default::default(),
+  };
+  test
+    .document
+    .insert_block(block, Some("block_1".to_string()))
+    .unwrap();
+  test
+    .document
+    .apply_text_delta("text_2", r#"[{"insert": "Hello"}]"#.to_string());
+  let newer = test.document.encode_collab().unwrap();
+
+  let diff = diff_document_snapshots(&older, &newer).unwrap();
+  assert_eq!(
+    diff.inserted_blocks,
+    vec![
+      BlockChange {
+        block_id: "block_1".to_string(),
+        ty: "paragraph".to_string(),
+      },
+      BlockChange {
+        block_id: "block_2".to_string(),
+        ty: "heading".to_string(),
+      },
+    ]
+  );
+  assert!(diff.deleted_blocks.is_empty());
+  // The children of the page changed
+  assert_eq!(diff.updated_blocks.len(), 1);
+  assert_eq!(diff.updated_blocks[0].block_id, page_id);
+  assert!(diff.updated_blocks[0].moved);
+
+  let diff = diff_document_snapshots(&newer, &newer).unwrap();
+  assert!(diff.is_empty());
+}
+
+#[test]
+fn diff_updated_and_deleted_blocks_test() {
+  let mut test = DocumentTest::new(1, "1");
+  let (page_id, _, _) = get_document_data(&test.document);
+  insert_block_for_page(&mut test.document, "block_1".to_string());
+  insert_block_for_page(&mut test.document, "block_2".to_string());
+  let block = Block {
+    id: "block_3".to_string(),
+    ty: "paragraph".to_string(),
+    parent: page_id,
+    children: "".to_string(),
+    external_id: Some("text_3".to_string()),
+    external_type: Some("text".to_string()),
+    data: Default::default(),
+  };
+  test.document.insert_block(block, None).unwrap();
+  test
+    .document
+    .apply_text_delta("text_3", r#"[{"insert": "Hello"}]"#.to_string());
+  let older = test.document.encode_collab().unwrap();
+
+  let data = HashMap::from([("checked".to_string(), json!(true))]);
+  test.document.update_block("block_1", data).unwrap();
+  test.document.apply_text_delta(
+    "text_3",
+    r#"[{"retain": 5}, {"insert": " World"}]"#.to_string(),
+  );
+  test.document.delete_block("block_2").unwrap();
+  let newer = test.document.encode_collab().unwrap();
+
+  let diff = diff_document_snapshots(&older, &newer).unwrap();
+  assert!(diff.inserted_blocks.is_empty());
+  assert_eq!(
+    diff.deleted_blocks,
+    vec![BlockChange {
+      block_id: "block_2".to_string(),
+      ty: "paragraph".to_string(),
+    }]
+  );
+
+  let updates = diff
+    .updated_blocks
+    .iter()
+    .map(|update| (update.block_id.as_str(), update))
+    .collect::<HashMap<_, _>>();
+  let block_1 = updates["block_1"];
+  assert!(block_1.data_changed && !block_1.text_changed && !block_1.moved);
+  let block_3 = updates["block_3"];
+  assert!(!block_3.data_changed && block_3.text_changed && !block_3.moved);
+}
@@ -0,0 +1,29 @@
+use collab_document::test_utils::DocumentGenerator;
+
+#[test]
+fn generate_document_test() {
+  let generator = DocumentGenerator::new(7).with_blocks(50).with_max_depth(2);
+  let document = generator.build("document_1").unwrap();
+  let data = document.get_document_data().unwrap();
+
+  // The page and the generated blocks
+  assert_eq!(data.blocks.len(), 51);
+  for block in data.blocks.values() {
+    if block.id == data.page_id {
+      continue;
+    }
+    let parent = &data.blocks[&block.parent];
+    assert!(parent.id == data.page_id || parent.parent == data.page_id);
+    assert!(data.meta.children_map[&parent.children].contains(&block.id));
+  }
+}
+
+#[test]
+fn generate_same_document_with_same_seed_test() {
+  let data_1 = DocumentGenerator::new(7).generate_data();
+  let data_2 = DocumentGenerator::new(7).generate_data();
+  assert_eq!(data_1, data_2);
+
+  let data_3 = DocumentGenerator::new(8).generate_data();
+  assert_ne!(data_1.page_id, data_3.page_id);
+}
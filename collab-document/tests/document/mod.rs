@@ -1,5 +1,12 @@
+mod assets_test;
+mod attribution_test;
 mod awareness_test;
+mod block_index_test;
+mod block_timestamps_test;
+mod block_type_test;
 mod document_data_test;
+mod document_diff_test;
 mod document_test;
+mod generator_test;
 mod redo_undo_test;
 mod restore_test;
@@ -0,0 +1,331 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use collab::error::CollabError;
+use collab::preclude::block::ClientID;
+use collab::preclude::updates::decoder::Decode;
+use collab::preclude::{
+  Any, Collab, DeepObservable, EntryChange, Event, Map, MapRef, Out, PathSegment, ReadTxn,
+  StateVector, ToJson, TransactionMut, Update,
+};
+
+use crate::define::{DATABASE, DATABASE_ROW_DATA, DOCUMENT_ROOT, FOLDER};
+use crate::CollabType;
+
+/// What changed in a collab between two of its state vectors, for example since a user last
+/// opened it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActivitySummary {
+  pub since: StateVector,
+  pub until: StateVector,
+  /// The number of elements inserted by each client between the two state vectors. A text
+  /// insertion counts each of its characters.
+  pub insertions_by_client: BTreeMap<ClientID, u32>,
+  /// The changes, by the part of the collab they were made to. A part that was inserted and
+  /// then updated is only reported as inserted.
+  pub changes: Vec<ActivityChange>,
+}
+
+impl ActivitySummary {
+  pub fn is_empty(&self) -> bool {
+    self.insertions_by_client.is_empty() && self.changes.is_empty()
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActivityChange {
+  pub target: ActivityTarget,
+  pub kind: ActivityKind,
+}
+
+/// The part of a collab that was changed, typed by the kind of the collab.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ActivityTarget {
+  /// A field of a database, by its id.
+  Field(String),
+  /// A view of a database, by its id.
+  DatabaseView(String),
+  /// A row added to or removed from the views of a database, by its id.
+  Row(String),
+  /// A cell of a database row, by the id of its field.
+  Cell(String),
+  /// The height, the visibility or the meta of a database row.
+  RowMeta,
+  /// A comment of a database row, by its id.
+  RowComment(String),
+  /// A block of a document, by its id.
+  Block(String),
+  /// A text of a document, by its id.
+  Text(String),
+  /// A view of a folder, by its id.
+  FolderView(String),
+  /// A change to any other part of the collab, by its path from the root.
+  Other(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActivityKind {
+  Inserted,
+  Updated,
+  Deleted,
+}
+
+/// Apply the updates made after the state vector of the collab, `since`, to the collab and
+/// summarize what they changed. The updates are applied in order, up to the `until` state vector
+/// if there is one: once the collab reached it, or an update goes past it, the remaining updates
+/// are left out. The state vectors don't change with the deletions, so an update that only
+/// deletes content is left out once the collab reached `until`.
+///
+/// The changes are decoded from the updates themselves, so the content inserted before `since`
+/// and deleted after it is reported as deleted.
+pub fn summarize_activity(
+  collab: &mut Collab,
+  collab_type: &CollabType,
+  updates: &[Vec<u8>],
+  until: Option<&StateVector>,
+) -> Result<ActivitySummary, CollabError> {
+  let since = collab.transact().state_vector();
+  let rows_before = row_ids_of_views(collab, collab_type);
+
+  let (tx, rx) = std::sync::mpsc::channel();
+  let observed_type = collab_type.clone();
+  let subscription = collab.data.observe_deep(move |txn, events| {
+    for event in events.iter() {
+      for change in changes_of_event(&observed_type, txn, event) {
+        let _ = tx.send(change);
+      }
+    }
+  });
+  let result = (|| {
+    for update in updates {
+      if let Some(until) = until {
+        if is_covered(until, &collab.transact().state_vector()) {
+          break;
+        }
+      }
+      let update = Update::decode_v1(update)?;
+      if let Some(until) = until {
+        if !is_covered(&update.state_vector(), until) {
+          break;
+        }
+      }
+      collab.apply_update(update)?;
+    }
+    Ok::<_, CollabError>(())
+  })();
+  drop(subscription);
+  result?;
+
+  let mut changes = BTreeMap::new();
+  for (target, kind) in rx.try_iter() {
+    merge_change(&mut changes, target, kind);
+  }
+  let rows_after = row_ids_of_views(collab, collab_type);
+  for row_id in rows_after.difference(&rows_before) {
+    merge_change(
+      &mut changes,
+      ActivityTarget::Row(row_id.clone()),
+      ActivityKind::Inserted,
+    );
+  }
+  for row_id in rows_before.difference(&rows_after) {
+    merge_change(
+      &mut changes,
+      ActivityTarget::Row(row_id.clone()),
+      ActivityKind::Deleted,
+    );
+  }
+
+  let until = collab.transact().state_vector();
+  let mut insertions_by_client = BTreeMap::new();
+  for (client_id, clock) in until.iter() {
+    let inserted = clock.saturating_sub(since.get(client_id));
+    if inserted > 0 {
+      insertions_by_client.insert(*client_id, inserted);
+    }
+  }
+  Ok(ActivitySummary {
+    since,
+    until,
+    insertions_by_client,
+    changes: changes
+      .into_iter()
+      .map(|(target, kind)| ActivityChange { target, kind })
+      .collect(),
+  })
+}
+
+/// Return true if every clock of the state vector is covered by the other one.
+fn is_covered(state_vector: &StateVector, other: &StateVector) -> bool {
+  state_vector
+    .iter()
+    .all(|(client_id, clock)| *clock <= other.get(client_id))
+}
+
+/// Combine the change with the previous change of the same target. A target that was inserted
+/// and then deleted didn't change.
+fn merge_change(
+  changes: &mut BTreeMap<ActivityTarget, ActivityKind>,
+  target: ActivityTarget,
+  kind: ActivityKind,
+) {
+  let merged = match (changes.get(&target), kind) {
+    (None, kind) => Some(kind),
+    (Some(ActivityKind::Inserted), ActivityKind::Deleted) => None,
+    (Some(ActivityKind::Inserted), _) => Some(ActivityKind::Inserted),
+    (Some(ActivityKind::Deleted), ActivityKind::Inserted) => Some(ActivityKind::Updated),
+    (Some(_), kind) => Some(kind),
+  };
+  match merged {
+    None => {
+      changes.remove(&target);
+    },
+    Some(kind) => {
+      changes.insert(target, kind);
+    },
+  }
+}
+
+fn changes_of_event(
+  collab_type: &CollabType,
+  txn: &TransactionMut,
+  event: &Event,
+) -> Vec<(ActivityTarget, ActivityKind)> {
+  let path = event
+    .path()
+    .into_iter()
+    .map(|segment| match segment {
+      PathSegment::Key(key) => key.to_string(),
+      PathSegment::Index(index) => index.to_string(),
+    })
+    .collect::<Vec<_>>();
+  let mut changes = vec![];
+  match event {
+    Event::Map(map_event) => {
+      for (key, entry_change) in map_event.keys(txn).iter() {
+        let mut entry_path = path.clone();
+        entry_path.push(key.to_string());
+        match entry_change {
+          EntryChange::Inserted(value) => {
+            inserted_changes(collab_type, txn, entry_path, value, &mut changes);
+          },
+          EntryChange::Updated(_, _) => {
+            changes.extend(classify(collab_type, &entry_path, ActivityKind::Updated));
+          },
+          EntryChange::Removed(_) => {
+            changes.extend(classify(collab_type, &entry_path, ActivityKind::Deleted));
+          },
+        }
+      }
+    },
+    _ => changes.extend(classify(collab_type, &path, ActivityKind::Updated)),
+  }
+  changes
+}
+
+/// The nested types inserted with a map entry don't have events of their own, so the entries of
+/// an inserted map that isn't a known part of the collab are reported one by one.
+fn inserted_changes(
+  collab_type: &CollabType,
+  txn: &TransactionMut,
+  path: Vec<String>,
+  value: &Out,
+  changes: &mut Vec<(ActivityTarget, ActivityKind)>,
+) {
+  match classify(collab_type, &path, ActivityKind::Inserted) {
+    Some((ActivityTarget::Other(_), _)) => match value {
+      Out::YMap(map_ref) => {
+        for (key, value) in map_ref.iter(txn) {
+          let mut child_path = path.clone();
+          child_path.push(key.to_string());
+          inserted_changes(collab_type, txn, child_path, &value, changes);
+        }
+      },
+      Out::Any(Any::Map(map)) => {
+        for key in map.keys() {
+          let mut child_path = path.clone();
+          child_path.push(key.to_string());
+          changes.extend(classify(collab_type, &child_path, ActivityKind::Inserted));
+        }
+      },
+      _ => changes.extend(classify(collab_type, &path, ActivityKind::Inserted)),
+    },
+    change => changes.extend(change),
+  }
+}
+
+/// Return the part of the collab at the path, or None if the changes at the path are reported in
+/// another way, like the row orders of the database views.
+fn classify(
+  collab_type: &CollabType,
+  path: &[String],
+  kind: ActivityKind,
+) -> Option<(ActivityTarget, ActivityKind)> {
+  let path = path.iter().map(String::as_str).collect::<Vec<_>>();
+  let (target, rest) = match (collab_type, path.as_slice()) {
+    (CollabType::Database, [DATABASE, "fields", id, rest @ ..]) => {
+      (ActivityTarget::Field(id.to_string()), rest)
+    },
+    (CollabType::Database, [DATABASE, "views", _, "row_orders", ..]) => return None,
+    (CollabType::Database, [DATABASE, "views", id, rest @ ..]) => {
+      (ActivityTarget::DatabaseView(id.to_string()), rest)
+    },
+    (CollabType::DatabaseRow, [DATABASE_ROW_DATA, "cells", id, rest @ ..]) => {
+      (ActivityTarget::Cell(id.to_string()), rest)
+    },
+    (CollabType::DatabaseRow, [DATABASE_ROW_DATA, ..] | ["meta", ..]) => {
+      return Some((ActivityTarget::RowMeta, ActivityKind::Updated));
+    },
+    (CollabType::DatabaseRow, ["comments", id, rest @ ..]) => {
+      (ActivityTarget::RowComment(id.to_string()), rest)
+    },
+    (CollabType::Document, [DOCUMENT_ROOT, "blocks", id, rest @ ..]) => {
+      (ActivityTarget::Block(id.to_string()), rest)
+    },
+    (CollabType::Document, [DOCUMENT_ROOT, "meta", "text_map", id, rest @ ..]) => {
+      (ActivityTarget::Text(id.to_string()), rest)
+    },
+    (CollabType::Document, [DOCUMENT_ROOT, "meta", "children_map", ..]) => return None,
+    (CollabType::Folder, [FOLDER, "views", id, rest @ ..]) => {
+      (ActivityTarget::FolderView(id.to_string()), rest)
+    },
+    (_, path) => return Some((ActivityTarget::Other(path.join("/")), kind)),
+  };
+  if rest.is_empty() {
+    Some((target, kind))
+  } else {
+    Some((target, ActivityKind::Updated))
+  }
+}
+
+/// Return the ids of the rows of all the views of the database. Empty for the other collabs.
+fn row_ids_of_views(collab: &Collab, collab_type: &CollabType) -> BTreeSet<String> {
+  let mut row_ids = BTreeSet::new();
+  if collab_type != &CollabType::Database {
+    return row_ids;
+  }
+  let txn = collab.transact();
+  let views = collab
+    .data
+    .get(&txn, DATABASE)
+    .and_then(|database| database.cast::<MapRef>().ok())
+    .and_then(|database| database.get(&txn, "views"))
+    .and_then(|views| views.cast::<MapRef>().ok());
+  if let Some(views) = views {
+    for (_, view) in views.iter(&txn) {
+      let row_orders = view
+        .cast::<MapRef>()
+        .ok()
+        .and_then(|view| view.get(&txn, "row_orders"));
+      if let Some(Any::Array(row_orders)) = row_orders.map(|value| value.to_json(&txn)) {
+        row_ids.extend(row_orders.iter().filter_map(|row_order| match row_order {
+          Any::Map(row_order) => match row_order.get("id") {
+            Some(Any::String(id)) => Some(id.to_string()),
+            _ => None,
+          },
+          _ => None,
+        }));
+      }
+    }
+  }
+  row_ids
+}
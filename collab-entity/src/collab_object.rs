@@ -3,8 +3,9 @@ use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 
 use crate::define::{
-  DATABASE, DATABASE_ID, DATABASE_INLINE_VIEW, DATABASE_METAS, DATABASE_ROW_DATA, DATABASE_ROW_ID,
-  DOCUMENT_ROOT, FOLDER, FOLDER_META, FOLDER_WORKSPACE_ID, USER_AWARENESS, WORKSPACE_DATABASES,
+  CHAT, CHAT_ID, CHAT_MESSAGES, DATABASE, DATABASE_BLOCK_ROWS, DATABASE_ID, DATABASE_INLINE_VIEW,
+  DATABASE_METAS, DATABASE_ROW_DATA, DATABASE_ROW_ID, DOCUMENT_ROOT, FOLDER, FOLDER_META,
+  FOLDER_WORKSPACE_ID, USER_AWARENESS, WORKSPACE_DATABASES,
 };
 use crate::proto;
 use collab::preclude::{ArrayRef, Collab, MapExt, MapRef};
@@ -28,6 +29,12 @@ pub enum CollabType {
   /// No strict validation is applied when handling objects of this type(check out the [CollabType::validate_require_data]
   /// for more information), which means errors might not be caught as strictly as with known types.
   Unknown = 6,
+  /// A conversation, for example with the AI assistant. The messages are appended to an array,
+  /// so the chat history is synced like the other objects.
+  Chat = 7,
+  /// A block of the rows of a database. It keeps the ids of the rows assigned to the block, the
+  /// rows themselves are stored in their own [CollabType::DatabaseRow] objects.
+  DatabaseBlock = 8,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -128,6 +135,29 @@ impl CollabType {
           .ok_or_else(|| no_required_data_error(self, USER_AWARENESS))?;
         Ok(())
       },
+      CollabType::Chat => {
+        let chat: MapRef = collab
+          .data
+          .get_with_path(&txn, [CHAT])
+          .ok_or_else(|| no_required_data_error(self, CHAT))?;
+        let chat_id: String = chat
+          .get_with_txn(&txn, CHAT_ID)
+          .ok_or_else(|| no_required_data_error(self, CHAT_ID))?;
+        if chat_id.is_empty() {
+          return Err(no_required_data_error(self, CHAT_ID));
+        }
+        let _: ArrayRef = chat
+          .get_with_txn(&txn, CHAT_MESSAGES)
+          .ok_or_else(|| no_required_data_error(self, CHAT_MESSAGES))?;
+        Ok(())
+      },
+      CollabType::DatabaseBlock => {
+        let _: MapRef = collab
+          .data
+          .get_with_path(&txn, [DATABASE_BLOCK_ROWS])
+          .ok_or_else(|| no_required_data_error(self, DATABASE_BLOCK_ROWS))?;
+        Ok(())
+      },
       CollabType::Unknown => Ok(()),
     }
   }
@@ -140,6 +170,8 @@ impl CollabType {
       proto::collab::CollabType::Folder => CollabType::Folder,
       proto::collab::CollabType::DatabaseRow => CollabType::DatabaseRow,
       proto::collab::CollabType::UserAwareness => CollabType::UserAwareness,
+      proto::collab::CollabType::Chat => CollabType::Chat,
+      proto::collab::CollabType::DatabaseBlock => CollabType::DatabaseBlock,
     }
   }
 
@@ -152,6 +184,8 @@ impl CollabType {
       CollabType::Folder => proto::collab::CollabType::Folder,
       CollabType::DatabaseRow => proto::collab::CollabType::DatabaseRow,
       CollabType::UserAwareness => proto::collab::CollabType::UserAwareness,
+      CollabType::Chat => proto::collab::CollabType::Chat,
+      CollabType::DatabaseBlock => proto::collab::CollabType::DatabaseBlock,
     }
   }
 }
@@ -192,6 +226,8 @@ impl Display for CollabType {
       Self::DatabaseRow => f.write_str("DatabaseRow"),
       Self::Folder => f.write_str("Folder"),
       Self::UserAwareness => f.write_str("UserAwareness"),
+      Self::Chat => f.write_str("Chat"),
+      Self::DatabaseBlock => f.write_str("DatabaseBlock"),
       Self::Unknown => f.write_str("Unknown"),
     }
   }
@@ -209,6 +245,8 @@ macro_rules! impl_from_integer_for_collab_type {
               3 => CollabType::Folder,
               4 => CollabType::DatabaseRow,
               5 => CollabType::UserAwareness,
+              7 => CollabType::Chat,
+              8 => CollabType::DatabaseBlock,
               _ => CollabType::Unknown,
           }
         }
@@ -229,6 +267,8 @@ macro_rules! impl_from_collab_type_for_integer {
                 CollabType::Folder => 3,
                 CollabType::DatabaseRow => 4,
                 CollabType::UserAwareness => 5,
+                CollabType::Chat => 7,
+                CollabType::DatabaseBlock => 8,
                 CollabType::Unknown => 255,
             }
           }
@@ -251,6 +291,7 @@ pub struct CollabObject {
 }
 
 impl CollabObject {
+  /// Creates the object without validating it. Use [CollabObjectBuilder] to validate the object.
   pub fn new(
     uid: i64,
     object_id: String,
@@ -274,8 +315,267 @@ impl CollabObject {
   }
 }
 
+/// Provides the id of the device on which the app is running.
+pub trait DeviceIdProvider: Send + Sync {
+  fn device_id(&self) -> String;
+}
+
+impl<F> DeviceIdProvider for F
+where
+  F: Fn() -> String + Send + Sync,
+{
+  fn device_id(&self) -> String {
+    self()
+  }
+}
+
+/// The maximum length of an object id.
+pub const MAX_OBJECT_ID_LEN: usize = 128;
+
+#[derive(Debug, Clone, Eq, PartialEq, thiserror::Error)]
+pub enum CollabObjectError {
+  #[error("The uid is invalid: {0}")]
+  InvalidUid(i64),
+
+  #[error("The workspace id is empty")]
+  EmptyWorkspaceId,
+
+  #[error("The workspace id is not a uuid: {0}")]
+  InvalidWorkspaceId(String),
+
+  #[error("The object id is empty")]
+  EmptyObjectId,
+
+  #[error("The object id is invalid: {0}")]
+  InvalidObjectId(String),
+
+  #[error("The collab type is not set")]
+  MissingCollabType,
+
+  #[error("The collab type {collab_type} doesn't match the object: {reason}")]
+  CollabTypeMismatch {
+    collab_type: CollabType,
+    reason: String,
+  },
+
+  #[error("The device id is empty")]
+  EmptyDeviceId,
+}
+
+/// Builds a [CollabObject] and validates it, so a malformed object is rejected before it's sent
+/// to the server.
+///
+/// The rules are:
+///   1. the uid is positive
+///   2. the workspace id is a uuid
+///   3. the object id is not empty, at most [MAX_OBJECT_ID_LEN] characters long, and only contains
+///      ascii letters, digits, `-` and `_`
+///   4. the collab type is known. The object id of a [CollabType::Folder] is the workspace id
+///   5. the device id is not empty. It's taken from the [DeviceIdProvider] if it's not set
+#[derive(Default)]
+pub struct CollabObjectBuilder {
+  uid: i64,
+  object_id: String,
+  collab_type: Option<CollabType>,
+  workspace_id: String,
+  device_id: Option<String>,
+  device_id_provider: Option<Box<dyn DeviceIdProvider>>,
+  meta: HashMap<String, String>,
+}
+
+impl CollabObjectBuilder {
+  pub fn new(uid: i64, object_id: &str) -> Self {
+    Self {
+      uid,
+      object_id: object_id.to_string(),
+      ..Default::default()
+    }
+  }
+
+  pub fn with_collab_type(mut self, collab_type: CollabType) -> Self {
+    self.collab_type = Some(collab_type);
+    self
+  }
+
+  pub fn with_workspace_id(mut self, workspace_id: &str) -> Self {
+    self.workspace_id = workspace_id.to_string();
+    self
+  }
+
+  pub fn with_device_id(mut self, device_id: &str) -> Self {
+    self.device_id = Some(device_id.to_string());
+    self
+  }
+
+  pub fn with_device_id_provider<P: DeviceIdProvider + 'static>(mut self, provider: P) -> Self {
+    self.device_id_provider = Some(Box::new(provider));
+    self
+  }
+
+  pub fn with_meta(mut self, key: &str, value: String) -> Self {
+    self.meta.insert(key.to_string(), value);
+    self
+  }
+
+  pub fn build(self) -> Result<CollabObject, CollabObjectError> {
+    if self.uid <= 0 {
+      return Err(CollabObjectError::InvalidUid(self.uid));
+    }
+    validate_workspace_id(&self.workspace_id)?;
+    validate_object_id(&self.object_id)?;
+
+    let collab_type = match self.collab_type {
+      None | Some(CollabType::Unknown) => return Err(CollabObjectError::MissingCollabType),
+      Some(collab_type) => collab_type,
+    };
+    if collab_type == CollabType::Folder && self.object_id != self.workspace_id {
+      return Err(CollabObjectError::CollabTypeMismatch {
+        collab_type,
+        reason: "the object id of the folder must be the workspace id".to_string(),
+      });
+    }
+
+    let device_id = self
+      .device_id
+      .or_else(|| {
+        self
+          .device_id_provider
+          .as_ref()
+          .map(|provider| provider.device_id())
+      })
+      .unwrap_or_default();
+    if device_id.trim().is_empty() {
+      return Err(CollabObjectError::EmptyDeviceId);
+    }
+
+    Ok(CollabObject {
+      object_id: self.object_id,
+      uid: self.uid,
+      collab_type,
+      device_id,
+      workspace_id: self.workspace_id,
+      meta: self.meta,
+    })
+  }
+}
+
+fn validate_workspace_id(workspace_id: &str) -> Result<(), CollabObjectError> {
+  if workspace_id.is_empty() {
+    return Err(CollabObjectError::EmptyWorkspaceId);
+  }
+  uuid::Uuid::parse_str(workspace_id)
+    .map(|_| ())
+    .map_err(|_| CollabObjectError::InvalidWorkspaceId(workspace_id.to_string()))
+}
+
+fn validate_object_id(object_id: &str) -> Result<(), CollabObjectError> {
+  if object_id.is_empty() {
+    return Err(CollabObjectError::EmptyObjectId);
+  }
+  let is_valid = object_id.len() <= MAX_OBJECT_ID_LEN
+    && object_id
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+  if is_valid {
+    Ok(())
+  } else {
+    Err(CollabObjectError::InvalidObjectId(object_id.to_string()))
+  }
+}
+
 impl Display for CollabObject {
   fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
     f.write_fmt(format_args!("{:?}:{}]", self.collab_type, self.object_id,))
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const WORKSPACE_ID: &str = "9eebea03-3ed5-4298-86b2-a7f77856d48b";
+
+  #[test]
+  fn build_collab_object_test() {
+    let object = CollabObjectBuilder::new(1, "d1")
+      .with_collab_type(CollabType::Document)
+      .with_workspace_id(WORKSPACE_ID)
+      .with_device_id_provider(|| "device".to_string())
+      .build()
+      .unwrap();
+    assert_eq!(object.device_id, "device");
+    assert_eq!(object.workspace_id, WORKSPACE_ID);
+
+    let builder = || {
+      CollabObjectBuilder::new(1, WORKSPACE_ID)
+        .with_workspace_id(WORKSPACE_ID)
+        .with_device_id("device")
+    };
+    assert!(builder()
+      .with_collab_type(CollabType::Folder)
+      .build()
+      .is_ok());
+    assert_eq!(
+      builder().build().unwrap_err(),
+      CollabObjectError::MissingCollabType
+    );
+  }
+
+  #[test]
+  fn invalid_collab_object_test() {
+    let builder = |uid: i64, object_id: &str, workspace_id: &str| {
+      CollabObjectBuilder::new(uid, object_id)
+        .with_collab_type(CollabType::Folder)
+        .with_workspace_id(workspace_id)
+        .with_device_id("device")
+    };
+    assert_eq!(
+      builder(0, WORKSPACE_ID, WORKSPACE_ID).build().unwrap_err(),
+      CollabObjectError::InvalidUid(0)
+    );
+    assert_eq!(
+      builder(1, WORKSPACE_ID, "").build().unwrap_err(),
+      CollabObjectError::EmptyWorkspaceId
+    );
+    assert!(matches!(
+      builder(1, WORKSPACE_ID, "w1").build(),
+      Err(CollabObjectError::InvalidWorkspaceId(_))
+    ));
+    assert!(matches!(
+      builder(1, "d 1", WORKSPACE_ID).build(),
+      Err(CollabObjectError::InvalidObjectId(_))
+    ));
+    assert!(matches!(
+      builder(1, "d1", WORKSPACE_ID).build(),
+      Err(CollabObjectError::CollabTypeMismatch { .. })
+    ));
+    assert_eq!(
+      builder(1, WORKSPACE_ID, WORKSPACE_ID)
+        .with_device_id(" ")
+        .build()
+        .unwrap_err(),
+      CollabObjectError::EmptyDeviceId
+    );
+  }
+
+  #[test]
+  fn database_block_collab_type_test() {
+    assert_eq!(CollabType::from(8u8), CollabType::DatabaseBlock);
+    assert_eq!(i32::from(CollabType::DatabaseBlock), 8);
+    assert_eq!(
+      CollabType::from_proto(&CollabType::DatabaseBlock.to_proto()),
+      CollabType::DatabaseBlock
+    );
+
+    let mut collab = Collab::new(1, "b1", "device", vec![], false);
+    assert!(CollabType::DatabaseBlock
+      .validate_require_data(&collab)
+      .is_err());
+    let mut txn = collab.context.transact_mut();
+    collab.data.get_or_init_map(&mut txn, DATABASE_BLOCK_ROWS);
+    drop(txn);
+    assert!(CollabType::DatabaseBlock
+      .validate_require_data(&collab)
+      .is_ok());
+  }
+}
@@ -14,6 +14,12 @@ pub const DATABASE_METAS: &str = "metas";
 pub const DATABASE_INLINE_VIEW: &str = "iid";
 pub const DATABASE_ROW_DATA: &str = "data";
 pub const DATABASE_ROW_ID: &str = "id";
+pub const DATABASE_BLOCK_ROWS: &str = "rows";
 
 // User Awareness
 pub const USER_AWARENESS: &str = "user_awareness";
+
+// Chat
+pub const CHAT: &str = "chat";
+pub const CHAT_ID: &str = "id";
+pub const CHAT_MESSAGES: &str = "messages";
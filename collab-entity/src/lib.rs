@@ -1,5 +1,6 @@
 pub use collab_object::*;
 
+pub mod activity;
 mod collab_object;
 pub mod define;
 pub mod proto;
@@ -3,6 +3,8 @@ use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
 
 use anyhow::Result;
+use chrono::{DateTime, Days, Months};
+use collab::preclude::encoding::serde::{from_any, to_any};
 use collab::preclude::{Any, In, Map, MapExt, MapPrelim, MapRef, Out, ReadTxn, TransactionMut};
 use serde::{Deserialize, Serialize};
 use serde_repr::*;
@@ -21,6 +23,72 @@ pub struct Reminder {
   pub meta: ReminderMeta,
   /// The object_id field is used to store the id of the object that the reminder is associated with.
   pub object_id: String,
+  /// The id of the block in the object that the reminder is associated with.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub block_id: Option<String>,
+  /// None if the reminder only fires once.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub repeat: Option<RepeatRule>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize_repr, Deserialize_repr)]
+#[repr(i64)]
+pub enum RepeatFrequency {
+  Daily = 0,
+  Weekly = 1,
+  Monthly = 2,
+  Yearly = 3,
+}
+
+/// How a reminder repeats. The next occurrences are computed in UTC from the `scheduled_at` of
+/// the reminder, in seconds.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RepeatRule {
+  pub frequency: RepeatFrequency,
+  /// Repeat every `interval` days, weeks, months or years. Zero is treated as one.
+  #[serde(default = "default_repeat_interval")]
+  pub interval: u32,
+  /// The reminder doesn't fire after this time.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub until: Option<i64>,
+}
+
+fn default_repeat_interval() -> u32 {
+  1
+}
+
+impl RepeatRule {
+  pub fn new(frequency: RepeatFrequency) -> Self {
+    Self {
+      frequency,
+      interval: 1,
+      until: None,
+    }
+  }
+
+  /// Returns the first occurrence of a reminder scheduled at `scheduled_at` that is after
+  /// `after`, or None if the rule ends before it.
+  pub fn next_after(&self, scheduled_at: i64, after: i64) -> Option<i64> {
+    let interval = self.interval.max(1);
+    let start = DateTime::from_timestamp(scheduled_at, 0)?;
+    let mut next = start;
+    let mut n: u32 = 0;
+    while next.timestamp() <= after {
+      // Always compute from the start, so a month with fewer days doesn't shift the following
+      // occurrences.
+      n = n.checked_add(interval)?;
+      next = match self.frequency {
+        RepeatFrequency::Daily => start.checked_add_days(Days::new(n as u64))?,
+        RepeatFrequency::Weekly => start.checked_add_days(Days::new(n as u64 * 7))?,
+        RepeatFrequency::Monthly => start.checked_add_months(Months::new(n))?,
+        RepeatFrequency::Yearly => start.checked_add_months(Months::new(n.checked_mul(12)?))?,
+      };
+    }
+    match self.until {
+      Some(until) if next.timestamp() > until => None,
+      _ => Some(next.timestamp()),
+    }
+  }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize_repr, Deserialize_repr)]
@@ -53,9 +121,30 @@ impl Reminder {
       message: "".to_string(),
       meta: ReminderMeta::default(),
       object_id,
+      block_id: None,
+      repeat: None,
     }
   }
 
+  pub fn with_block_id(self, block_id: String) -> Self {
+    Self {
+      block_id: Some(block_id),
+      ..self
+    }
+  }
+
+  pub fn with_repeat(self, repeat: RepeatRule) -> Self {
+    Self {
+      repeat: Some(repeat),
+      ..self
+    }
+  }
+
+  /// Whether the reminder should fire at `now`.
+  pub fn is_due(&self, now: i64) -> bool {
+    !self.is_ack && self.scheduled_at <= now
+  }
+
   pub fn with_title(self, title: String) -> Self {
     Self { title, ..self }
   }
@@ -164,6 +253,8 @@ pub const REMINDER_TY: &str = "ty";
 pub const REMINDER_TITLE: &str = "title";
 pub const REMINDER_MESSAGE: &str = "message";
 pub const REMINDER_META: &str = "meta";
+pub const REMINDER_BLOCK_ID: &str = "block_id";
+pub const REMINDER_REPEAT: &str = "repeat";
 
 fn reminder_from_map<T: ReadTxn>(txn: &T, map_ref: &MapRef) -> Result<Reminder> {
   let id: String = map_ref
@@ -198,6 +289,11 @@ fn reminder_from_map<T: ReadTxn>(txn: &T, map_ref: &MapRef) -> Result<Reminder>
       _ => ReminderMeta::default(),
     })
     .unwrap_or_default();
+  let block_id: Option<String> = map_ref.get_with_txn(txn, REMINDER_BLOCK_ID);
+  let repeat = match map_ref.get(txn, REMINDER_REPEAT) {
+    Some(Out::Any(any)) => from_any::<RepeatRule>(&any).ok(),
+    _ => None,
+  };
 
   Ok(Reminder {
     id,
@@ -209,12 +305,14 @@ fn reminder_from_map<T: ReadTxn>(txn: &T, map_ref: &MapRef) -> Result<Reminder>
     title,
     message,
     meta,
+    block_id,
+    repeat,
   })
 }
 
 impl From<Reminder> for MapPrelim {
   fn from(item: Reminder) -> Self {
-    MapPrelim::from([
+    let mut map = MapPrelim::from([
       (REMINDER_ID, In::from(item.id)),
       (REMINDER_OBJECT_ID, item.object_id.into()),
       (REMINDER_SCHEDULED_AT, Any::BigInt(item.scheduled_at).into()),
@@ -224,13 +322,20 @@ impl From<Reminder> for MapPrelim {
       (REMINDER_TITLE, item.title.into()),
       (REMINDER_MESSAGE, item.message.into()),
       (REMINDER_META, Any::from(item.meta).into()),
-    ])
+    ]);
+    if let Some(block_id) = item.block_id {
+      map.insert(REMINDER_BLOCK_ID.into(), block_id.into());
+    }
+    if let Some(repeat) = item.repeat.and_then(|repeat| to_any(&repeat).ok()) {
+      map.insert(REMINDER_REPEAT.into(), repeat.into());
+    }
+    map
   }
 }
 
 #[cfg(test)]
 mod test {
-  use crate::reminder::{ObjectType, Reminder};
+  use crate::reminder::{ObjectType, Reminder, RepeatFrequency, RepeatRule};
   use collab::preclude::encoding::serde::from_any;
   use collab::preclude::{Doc, Map, MapPrelim, ToJson, Transact};
 
@@ -262,4 +367,40 @@ mod test {
       )
     );
   }
+
+  #[test]
+  fn repeat_rule_next_after_test() {
+    // 2024-01-31T09:00:00Z
+    let scheduled_at = 1706691600;
+    let daily = RepeatRule::new(RepeatFrequency::Daily);
+    assert_eq!(
+      daily.next_after(scheduled_at, scheduled_at),
+      Some(scheduled_at + 86400)
+    );
+    let weekly = RepeatRule {
+      interval: 2,
+      ..RepeatRule::new(RepeatFrequency::Weekly)
+    };
+    assert_eq!(
+      weekly.next_after(scheduled_at, scheduled_at + 86400),
+      Some(scheduled_at + 14 * 86400)
+    );
+
+    // The monthly occurrences are clamped to the end of the shorter months, but the following
+    // ones still fall on the 31st.
+    let monthly = RepeatRule::new(RepeatFrequency::Monthly);
+    // 2024-02-29T09:00:00Z
+    let feb = monthly.next_after(scheduled_at, scheduled_at).unwrap();
+    assert_eq!(feb, 1709197200);
+    // 2024-03-31T09:00:00Z
+    assert_eq!(monthly.next_after(scheduled_at, feb), Some(1711875600));
+
+    let until = RepeatRule {
+      until: Some(scheduled_at + 86400),
+      ..RepeatRule::new(RepeatFrequency::Daily)
+    };
+    assert!(until
+      .next_after(scheduled_at, scheduled_at + 86400)
+      .is_none());
+  }
 }
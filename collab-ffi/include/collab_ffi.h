@@ -0,0 +1,59 @@
+/*
+ * The C ABI of the collab engine. See the documentation of the collab-ffi crate for the
+ * ownership rules; in short, everything returned through an out pointer is owned by the caller
+ * and released with the matching *_free function.
+ */
+#ifndef COLLAB_FFI_H
+#define COLLAB_FFI_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum CollabStatus {
+  COLLAB_STATUS_OK = 0,
+  COLLAB_STATUS_NULL_POINTER = 1,
+  COLLAB_STATUS_INVALID_UTF8 = 2,
+  COLLAB_STATUS_INVALID_UPDATE = 3,
+  COLLAB_STATUS_NOT_FOUND = 4,
+  COLLAB_STATUS_INTERNAL = 5,
+  COLLAB_STATUS_PANIC = 6,
+} CollabStatus;
+
+typedef struct CollabHandle CollabHandle;
+
+typedef struct CollabBuffer {
+  uint8_t *data;
+  size_t len;
+} CollabBuffer;
+
+typedef void (*CollabUpdateCallback)(void *user_data, const uint8_t *update, size_t len);
+
+CollabStatus collab_new(const char *object_id, CollabHandle **out);
+CollabStatus collab_open(const char *object_id, const uint8_t *doc_state, size_t len,
+                         CollabHandle **out);
+void collab_free(CollabHandle *handle);
+
+CollabStatus collab_apply_update(CollabHandle *handle, const uint8_t *update, size_t len);
+CollabStatus collab_encode_doc_state(const CollabHandle *handle, CollabBuffer *out);
+CollabStatus collab_state_vector(const CollabHandle *handle, CollabBuffer *out);
+CollabStatus collab_encode_update_since(const CollabHandle *handle, const uint8_t *state_vector,
+                                        size_t len, CollabBuffer *out);
+CollabStatus collab_to_json(const CollabHandle *handle, char **out);
+
+CollabStatus collab_subscribe_update(CollabHandle *handle, CollabUpdateCallback callback,
+                                     void *user_data, uint64_t *out_id);
+CollabStatus collab_unsubscribe(CollabHandle *handle, uint64_t id);
+
+char *collab_last_error_message(void);
+void collab_buffer_free(CollabBuffer buffer);
+void collab_string_free(char *string);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
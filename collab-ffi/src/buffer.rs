@@ -0,0 +1,87 @@
+use std::ffi::{c_char, CStr, CString};
+
+use crate::error::FfiError;
+
+/// A byte buffer allocated by the library, for example an encoded update. It must be released
+/// with [collab_buffer_free].
+#[repr(C)]
+#[derive(Debug)]
+pub struct CollabBuffer {
+  pub data: *mut u8,
+  pub len: usize,
+}
+
+impl From<Vec<u8>> for CollabBuffer {
+  fn from(bytes: Vec<u8>) -> Self {
+    let bytes = bytes.into_boxed_slice();
+    let len = bytes.len();
+    Self {
+      data: Box::into_raw(bytes) as *mut u8,
+      len,
+    }
+  }
+}
+
+/// Releases a buffer returned by the library. Releasing an empty buffer is a no-op.
+///
+/// # Safety
+/// The buffer must have been returned by the library and not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn collab_buffer_free(buffer: CollabBuffer) {
+  if !buffer.data.is_null() {
+    drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+      buffer.data,
+      buffer.len,
+    )));
+  }
+}
+
+/// Releases a string returned by the library. Releasing null is a no-op.
+///
+/// # Safety
+/// The string must have been returned by the library and not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn collab_string_free(string: *mut c_char) {
+  if !string.is_null() {
+    drop(CString::from_raw(string));
+  }
+}
+
+pub(crate) unsafe fn read_str<'a>(
+  ptr: *const c_char,
+  name: &'static str,
+) -> Result<&'a str, FfiError> {
+  if ptr.is_null() {
+    return Err(FfiError::NullPointer(name));
+  }
+  CStr::from_ptr(ptr)
+    .to_str()
+    .map_err(|_| FfiError::InvalidUtf8(name))
+}
+
+/// Reads `len` bytes from `ptr`. A null pointer is only accepted for an empty slice.
+pub(crate) unsafe fn read_bytes<'a>(
+  ptr: *const u8,
+  len: usize,
+  name: &'static str,
+) -> Result<&'a [u8], FfiError> {
+  if len == 0 {
+    return Ok(&[]);
+  }
+  if ptr.is_null() {
+    return Err(FfiError::NullPointer(name));
+  }
+  Ok(std::slice::from_raw_parts(ptr, len))
+}
+
+pub(crate) unsafe fn write_out<T>(
+  out: *mut T,
+  value: T,
+  name: &'static str,
+) -> Result<(), FfiError> {
+  if out.is_null() {
+    return Err(FfiError::NullPointer(name));
+  }
+  out.write(value);
+  Ok(())
+}
@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+use std::ffi::{c_char, c_void, CString};
+
+use collab::core::collab::DataSource;
+use collab::core::origin::CollabOrigin;
+use collab::preclude::updates::decoder::Decode;
+use collab::preclude::updates::encoder::Encode;
+use collab::preclude::{Collab, ReadTxn, StateVector, Subscription, Update};
+
+use crate::buffer::{read_bytes, read_str, write_out, CollabBuffer};
+use crate::error::{ffi_call, CollabStatus, FfiError};
+
+/// An opened collab, see the ownership rules of the crate.
+pub struct CollabHandle {
+  collab: Collab,
+  subscriptions: HashMap<u64, Subscription>,
+  next_subscription_id: u64,
+}
+
+impl CollabHandle {
+  fn new(collab: Collab) -> Self {
+    Self {
+      collab,
+      subscriptions: HashMap::new(),
+      next_subscription_id: 1,
+    }
+  }
+}
+
+/// Called with every update of the collab, encoded as a yrs v1 update. The update is only valid
+/// during the call.
+pub type CollabUpdateCallback =
+  extern "C" fn(user_data: *mut c_void, update: *const u8, len: usize);
+
+/// The user data of a callback. The host promises that it can be used from the thread that
+/// changes the collab.
+struct UserData(*mut c_void);
+unsafe impl Send for UserData {}
+unsafe impl Sync for UserData {}
+
+unsafe fn handle_ref<'a>(handle: *const CollabHandle) -> Result<&'a CollabHandle, FfiError> {
+  handle.as_ref().ok_or(FfiError::NullPointer("handle"))
+}
+
+unsafe fn handle_mut<'a>(handle: *mut CollabHandle) -> Result<&'a mut CollabHandle, FfiError> {
+  handle.as_mut().ok_or(FfiError::NullPointer("handle"))
+}
+
+fn open_collab(object_id: &str, doc_state: &[u8]) -> Result<Collab, FfiError> {
+  let collab = Collab::new_with_source(
+    CollabOrigin::Empty,
+    object_id,
+    DataSource::DocStateV1(doc_state.to_vec()),
+    vec![],
+    false,
+  )?;
+  Ok(collab)
+}
+
+/// Creates an empty collab and writes its handle to `out`.
+///
+/// # Safety
+/// `object_id` must be a valid C string and `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn collab_new(
+  object_id: *const c_char,
+  out: *mut *mut CollabHandle,
+) -> CollabStatus {
+  ffi_call(|| {
+    let object_id = read_str(object_id, "object_id")?;
+    let handle = Box::new(CollabHandle::new(open_collab(object_id, &[])?));
+    write_out(out, Box::into_raw(handle), "out")
+  })
+}
+
+/// Opens the collab from its doc state, encoded as a yrs v1 update, and writes its handle to
+/// `out`.
+///
+/// # Safety
+/// `object_id` must be a valid C string, `doc_state` must point to `len` readable bytes and
+/// `out` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn collab_open(
+  object_id: *const c_char,
+  doc_state: *const u8,
+  len: usize,
+  out: *mut *mut CollabHandle,
+) -> CollabStatus {
+  ffi_call(|| {
+    let object_id = read_str(object_id, "object_id")?;
+    let doc_state = read_bytes(doc_state, len, "doc_state")?;
+    let handle = Box::new(CollabHandle::new(open_collab(object_id, doc_state)?));
+    write_out(out, Box::into_raw(handle), "out")
+  })
+}
+
+/// Releases the collab and drops all its subscriptions. Releasing null is a no-op.
+///
+/// # Safety
+/// The handle must have been returned by the library and not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn collab_free(handle: *mut CollabHandle) {
+  if !handle.is_null() {
+    drop(Box::from_raw(handle));
+  }
+}
+
+/// Applies an update, encoded with yrs v1. The update subscribers are called before it returns.
+///
+/// # Safety
+/// `handle` must be a valid handle and `update` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn collab_apply_update(
+  handle: *mut CollabHandle,
+  update: *const u8,
+  len: usize,
+) -> CollabStatus {
+  ffi_call(|| {
+    let handle = handle_mut(handle)?;
+    let update = read_bytes(update, len, "update")?;
+    let update =
+      Update::decode_v1(update).map_err(|err| FfiError::InvalidUpdate(err.to_string()))?;
+    handle.collab.apply_update(update)?;
+    Ok(())
+  })
+}
+
+/// Writes the doc state of the collab, encoded as a yrs v1 update, to `out`.
+///
+/// # Safety
+/// `handle` must be a valid handle and `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn collab_encode_doc_state(
+  handle: *const CollabHandle,
+  out: *mut CollabBuffer,
+) -> CollabStatus {
+  ffi_call(|| {
+    let handle = handle_ref(handle)?;
+    let doc_state = handle
+      .collab
+      .transact()
+      .encode_state_as_update_v1(&StateVector::default());
+    write_out(out, CollabBuffer::from(doc_state), "out")
+  })
+}
+
+/// Writes the state vector of the collab, encoded with yrs v1, to `out`.
+///
+/// # Safety
+/// `handle` must be a valid handle and `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn collab_state_vector(
+  handle: *const CollabHandle,
+  out: *mut CollabBuffer,
+) -> CollabStatus {
+  ffi_call(|| {
+    let handle = handle_ref(handle)?;
+    let state_vector = handle.collab.transact().state_vector().encode_v1();
+    write_out(out, CollabBuffer::from(state_vector), "out")
+  })
+}
+
+/// Writes the changes missing from the given state vector, encoded as a yrs v1 update, to
+/// `out`. An empty state vector returns the whole doc state.
+///
+/// # Safety
+/// `handle` must be a valid handle, `state_vector` must point to `len` readable bytes and `out`
+/// must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn collab_encode_update_since(
+  handle: *const CollabHandle,
+  state_vector: *const u8,
+  len: usize,
+  out: *mut CollabBuffer,
+) -> CollabStatus {
+  ffi_call(|| {
+    let handle = handle_ref(handle)?;
+    let state_vector = match read_bytes(state_vector, len, "state_vector")? {
+      [] => StateVector::default(),
+      bytes => {
+        StateVector::decode_v1(bytes).map_err(|err| FfiError::InvalidUpdate(err.to_string()))?
+      },
+    };
+    let update = handle
+      .collab
+      .transact()
+      .encode_state_as_update_v1(&state_vector);
+    write_out(out, CollabBuffer::from(update), "out")
+  })
+}
+
+/// Writes the data of the collab, as a JSON string, to `out`.
+///
+/// # Safety
+/// `handle` must be a valid handle and `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn collab_to_json(
+  handle: *const CollabHandle,
+  out: *mut *mut c_char,
+) -> CollabStatus {
+  ffi_call(|| {
+    let handle = handle_ref(handle)?;
+    let json = handle.collab.to_json_value().to_string();
+    // A JSON string never contains a nul byte, it's escaped.
+    let json = CString::new(json).map_err(|err| FfiError::Internal(err.to_string()))?;
+    write_out(out, json.into_raw(), "out")
+  })
+}
+
+/// Calls `callback` with every update of the collab, until the subscription is removed with
+/// [collab_unsubscribe] or the collab is released. The callback is called on the thread that
+/// changed the collab. Writes the id of the subscription to `out_id`. A null `callback` is
+/// reported as [CollabStatus::NullPointer].
+///
+/// # Safety
+/// `handle` must be a valid handle and `out_id` a valid pointer. `user_data` must stay valid
+/// until the subscription is removed.
+#[no_mangle]
+pub unsafe extern "C" fn collab_subscribe_update(
+  handle: *mut CollabHandle,
+  callback: Option<CollabUpdateCallback>,
+  user_data: *mut c_void,
+  out_id: *mut u64,
+) -> CollabStatus {
+  ffi_call(|| {
+    let handle = handle_mut(handle)?;
+    let callback = callback.ok_or(FfiError::NullPointer("callback"))?;
+    if out_id.is_null() {
+      return Err(FfiError::NullPointer("out_id"));
+    }
+    let user_data = UserData(user_data);
+    let subscription = handle
+      .collab
+      .get_awareness()
+      .doc()
+      .observe_update_v1(move |_, event| {
+        let user_data = &user_data;
+        callback(user_data.0, event.update.as_ptr(), event.update.len());
+      })
+      .map_err(|err| FfiError::Internal(err.to_string()))?;
+
+    let id = handle.next_subscription_id;
+    handle.next_subscription_id += 1;
+    handle.subscriptions.insert(id, subscription);
+    write_out(out_id, id, "out_id")
+  })
+}
+
+/// Removes a subscription added with [collab_subscribe_update].
+///
+/// # Safety
+/// `handle` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn collab_unsubscribe(handle: *mut CollabHandle, id: u64) -> CollabStatus {
+  ffi_call(|| {
+    let handle = handle_mut(handle)?;
+    handle
+      .subscriptions
+      .remove(&id)
+      .map(drop)
+      .ok_or_else(|| FfiError::NotFound(format!("subscription {}", id)))
+  })
+}
@@ -0,0 +1,98 @@
+use std::cell::RefCell;
+use std::ffi::{c_char, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use collab::error::CollabError;
+
+/// The result of a call. Any value other than `Ok` comes with an error message, see
+/// [collab_last_error_message].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum CollabStatus {
+  Ok = 0,
+  NullPointer = 1,
+  InvalidUtf8 = 2,
+  InvalidUpdate = 3,
+  NotFound = 4,
+  Internal = 5,
+  Panic = 6,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum FfiError {
+  #[error("{0} is null")]
+  NullPointer(&'static str),
+
+  #[error("{0} is not valid UTF-8")]
+  InvalidUtf8(&'static str),
+
+  #[error("Invalid update: {0}")]
+  InvalidUpdate(String),
+
+  #[error("{0} is not found")]
+  NotFound(String),
+
+  #[error(transparent)]
+  Collab(#[from] CollabError),
+
+  #[error("Internal failure: {0}")]
+  Internal(String),
+}
+
+impl FfiError {
+  fn status(&self) -> CollabStatus {
+    match self {
+      FfiError::NullPointer(_) => CollabStatus::NullPointer,
+      FfiError::InvalidUtf8(_) => CollabStatus::InvalidUtf8,
+      FfiError::InvalidUpdate(_) | FfiError::Collab(CollabError::DecodeUpdate(_)) => {
+        CollabStatus::InvalidUpdate
+      },
+      FfiError::NotFound(_) => CollabStatus::NotFound,
+      FfiError::Collab(_) | FfiError::Internal(_) => CollabStatus::Internal,
+    }
+  }
+}
+
+thread_local! {
+  static LAST_ERROR: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+  LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(message));
+}
+
+/// Runs the body of an exported function. It records the error for [collab_last_error_message]
+/// and keeps a panic from unwinding into the host.
+pub(crate) fn ffi_call<F>(f: F) -> CollabStatus
+where
+  F: FnOnce() -> Result<(), FfiError>,
+{
+  match catch_unwind(AssertUnwindSafe(f)) {
+    Ok(Ok(())) => CollabStatus::Ok,
+    Ok(Err(err)) => {
+      let status = err.status();
+      set_last_error(err.to_string());
+      status
+    },
+    Err(panic) => {
+      let message = panic
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string());
+      set_last_error(message);
+      CollabStatus::Panic
+    },
+  }
+}
+
+/// Returns the message of the last error that happened on the calling thread, or null if there
+/// is none. The message is owned by the caller and must be released with
+/// [collab_string_free](crate::collab_string_free).
+#[no_mangle]
+pub extern "C" fn collab_last_error_message() -> *mut c_char {
+  LAST_ERROR
+    .with(|last_error| last_error.borrow().clone())
+    .and_then(|message| CString::new(message).ok())
+    .map_or(std::ptr::null_mut(), CString::into_raw)
+}
@@ -0,0 +1,25 @@
+//! A C ABI over the core collab operations, used to embed the collab engine in Swift and Kotlin
+//! hosts. The matching header is `include/collab_ffi.h`.
+//!
+//! # Ownership
+//!
+//! - A [CollabHandle] returned by `collab_new` or `collab_open` is owned by the caller and must
+//!   be released with `collab_free`, exactly once. A handle is not thread safe: the host must not
+//!   call into the same handle from two threads at the same time.
+//! - A [CollabBuffer] or a string returned through an out pointer is owned by the caller and must
+//!   be released with `collab_buffer_free` or `collab_string_free`.
+//! - The pointers passed in, such as the object id or an update, are only borrowed for the
+//!   duration of the call.
+//! - The update passed to a [CollabUpdateCallback] is only valid during the callback. The host
+//!   must copy it to keep it.
+//!
+//! Every function that can fail returns a [CollabStatus]. On failure, the message of the error
+//! can be read with `collab_last_error_message` on the same thread.
+
+mod buffer;
+mod collab;
+mod error;
+
+pub use buffer::*;
+pub use collab::*;
+pub use error::*;
@@ -0,0 +1,193 @@
+use std::ffi::{c_void, CStr, CString};
+use std::ptr;
+
+use collab::core::origin::CollabOrigin;
+use collab::preclude::Collab;
+use collab_ffi::*;
+
+fn source_collab() -> Collab {
+  let mut collab = Collab::new_with_origin(CollabOrigin::Empty, "c1", vec![], false);
+  collab.insert("title", "hello");
+  collab
+}
+
+fn doc_state(collab: &Collab) -> Vec<u8> {
+  collab
+    .encode_collab_v1(|_| Ok::<_, ()>(()))
+    .unwrap()
+    .doc_state
+    .to_vec()
+}
+
+unsafe fn take_buffer(buffer: CollabBuffer) -> Vec<u8> {
+  let bytes = std::slice::from_raw_parts(buffer.data, buffer.len).to_vec();
+  collab_buffer_free(buffer);
+  bytes
+}
+
+unsafe fn take_string(string: *mut std::ffi::c_char) -> String {
+  let value = CStr::from_ptr(string).to_str().unwrap().to_string();
+  collab_string_free(string);
+  value
+}
+
+unsafe fn open(object_id: &str, doc_state: &[u8]) -> *mut CollabHandle {
+  let object_id = CString::new(object_id).unwrap();
+  let mut handle = ptr::null_mut();
+  let status = collab_open(
+    object_id.as_ptr(),
+    doc_state.as_ptr(),
+    doc_state.len(),
+    &mut handle,
+  );
+  assert_eq!(status, CollabStatus::Ok);
+  handle
+}
+
+unsafe fn to_json(handle: *const CollabHandle) -> serde_json::Value {
+  let mut json = ptr::null_mut();
+  assert_eq!(collab_to_json(handle, &mut json), CollabStatus::Ok);
+  serde_json::from_str(&take_string(json)).unwrap()
+}
+
+extern "C" fn record_update(user_data: *mut c_void, update: *const u8, len: usize) {
+  let updates = unsafe { &mut *(user_data as *mut Vec<Vec<u8>>) };
+  updates.push(unsafe { std::slice::from_raw_parts(update, len) }.to_vec());
+}
+
+#[test]
+fn open_and_encode_through_ffi_test() {
+  let source = source_collab();
+  unsafe {
+    let handle = open("c1", &doc_state(&source));
+    assert_eq!(to_json(handle)["title"], "hello");
+
+    let mut buffer = CollabBuffer {
+      data: ptr::null_mut(),
+      len: 0,
+    };
+    assert_eq!(
+      collab_encode_doc_state(handle, &mut buffer),
+      CollabStatus::Ok
+    );
+    let reopened = open("c1", &take_buffer(buffer));
+    assert_eq!(to_json(reopened)["title"], "hello");
+
+    collab_free(reopened);
+    collab_free(handle);
+  }
+}
+
+#[test]
+fn sync_updates_through_ffi_test() {
+  let mut source = source_collab();
+  unsafe {
+    let object_id = CString::new("c1").unwrap();
+    let mut handle = ptr::null_mut();
+    assert_eq!(
+      collab_new(object_id.as_ptr(), &mut handle),
+      CollabStatus::Ok
+    );
+
+    let mut updates: Vec<Vec<u8>> = vec![];
+    let mut subscription_id = 0;
+    let status = collab_subscribe_update(
+      handle,
+      Some(record_update),
+      &mut updates as *mut Vec<Vec<u8>> as *mut c_void,
+      &mut subscription_id,
+    );
+    assert_eq!(status, CollabStatus::Ok);
+
+    // Send the changes the handle is missing, based on its state vector.
+    let mut state_vector = CollabBuffer {
+      data: ptr::null_mut(),
+      len: 0,
+    };
+    assert_eq!(
+      collab_state_vector(handle, &mut state_vector),
+      CollabStatus::Ok
+    );
+    let state_vector = take_buffer(state_vector);
+    let update = {
+      use collab::preclude::updates::decoder::Decode;
+      use collab::preclude::{ReadTxn, StateVector};
+      source
+        .transact()
+        .encode_state_as_update_v1(&StateVector::decode_v1(&state_vector).unwrap())
+    };
+    let status = collab_apply_update(handle, update.as_ptr(), update.len());
+    assert_eq!(status, CollabStatus::Ok);
+    assert_eq!(to_json(handle)["title"], "hello");
+    assert_eq!(updates.len(), 1);
+
+    // The handle sends back the changes the source is missing.
+    source.insert("title", "changed");
+    let update = doc_state(&source);
+    collab_apply_update(handle, update.as_ptr(), update.len());
+    assert_eq!(to_json(handle)["title"], "changed");
+    assert_eq!(updates.len(), 2);
+
+    assert_eq!(
+      collab_unsubscribe(handle, subscription_id),
+      CollabStatus::Ok
+    );
+    assert_eq!(
+      collab_unsubscribe(handle, subscription_id),
+      CollabStatus::NotFound
+    );
+    source.insert("title", "unobserved");
+    let update = doc_state(&source);
+    collab_apply_update(handle, update.as_ptr(), update.len());
+    assert_eq!(updates.len(), 2);
+
+    let mut empty = CollabBuffer {
+      data: ptr::null_mut(),
+      len: 0,
+    };
+    assert_eq!(
+      collab_encode_update_since(handle, ptr::null(), 0, &mut empty),
+      CollabStatus::Ok
+    );
+    let reopened = open("c1", &take_buffer(empty));
+    assert_eq!(to_json(reopened)["title"], "unobserved");
+
+    collab_free(reopened);
+    collab_free(handle);
+  }
+}
+
+#[test]
+fn report_errors_through_ffi_test() {
+  unsafe {
+    let mut handle = ptr::null_mut();
+    assert_eq!(
+      collab_new(ptr::null(), &mut handle),
+      CollabStatus::NullPointer
+    );
+    assert!(handle.is_null());
+    assert_eq!(
+      take_string(collab_last_error_message()),
+      "object_id is null"
+    );
+
+    let handle = open("c1", &[]);
+    let update = [1, 2, 3];
+    let status = collab_apply_update(handle, update.as_ptr(), update.len());
+    assert_eq!(status, CollabStatus::InvalidUpdate);
+    assert!(!collab_last_error_message().is_null());
+
+    assert_eq!(
+      collab_apply_update(ptr::null_mut(), update.as_ptr(), update.len()),
+      CollabStatus::NullPointer
+    );
+
+    let mut subscription_id = 0;
+    assert_eq!(
+      collab_subscribe_update(handle, None, ptr::null_mut(), &mut subscription_id),
+      CollabStatus::NullPointer
+    );
+    assert_eq!(take_string(collab_last_error_message()), "callback is null");
+    collab_free(handle);
+  }
+}
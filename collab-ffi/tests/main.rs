@@ -0,0 +1 @@
+mod ffi_test;
@@ -42,3 +42,11 @@ impl AsRef<str> for TrashInfo {
     &self.id
   }
 }
+
+#[derive(Clone, Debug)]
+pub struct RecentViewInfo {
+  pub id: String,
+  pub name: String,
+  /// The last time the view was opened.
+  pub opened_at: i64,
+}
@@ -1,3 +1,6 @@
+use std::fmt::Display;
+
+use collab::error::{anyhow_error_code, ClassifiedError, ErrorCode, ErrorContext};
 use collab_entity::CollabValidateError;
 
 #[derive(Debug, thiserror::Error)]
@@ -10,6 +13,12 @@ pub enum FolderError {
 
   #[error("Lack of folder required data:{0}")]
   NoRequiredData(String),
+
+  #[error("{context}: {source}")]
+  Context {
+    context: String,
+    source: Box<FolderError>,
+  },
 }
 
 impl From<CollabValidateError> for FolderError {
@@ -19,3 +28,23 @@ impl From<CollabValidateError> for FolderError {
     }
   }
 }
+
+impl ClassifiedError for FolderError {
+  fn code(&self) -> ErrorCode {
+    match self {
+      FolderError::Internal(err) => anyhow_error_code(err),
+      FolderError::CollabError(err) => err.code(),
+      FolderError::NoRequiredData(_) => ErrorCode::NoRequiredData,
+      FolderError::Context { source, .. } => source.code(),
+    }
+  }
+}
+
+impl ErrorContext for FolderError {
+  fn context<C: Display>(self, context: C) -> Self {
+    FolderError::Context {
+      context: context.to_string(),
+      source: Box::new(self),
+    }
+  }
+}
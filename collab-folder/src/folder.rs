@@ -1,7 +1,8 @@
 use std::borrow::{Borrow, BorrowMut};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
+use std::time::Duration;
 
 use collab::core::collab::DataSource;
 pub use collab::core::origin::CollabOrigin;
@@ -16,11 +17,13 @@ use tracing::error;
 use crate::error::FolderError;
 use crate::folder_observe::ViewChangeSender;
 use crate::hierarchy_builder::{FlattedViews, ParentChildViews};
-use crate::section::{Section, SectionItem, SectionMap};
+use crate::section::{Section, SectionItem, SectionMap, TrashSectionItem};
 use crate::view::view_from_map_ref;
 use crate::{
-  impl_section_op, subscribe_folder_change, FolderData, ParentChildRelations, SectionChangeSender,
-  TrashInfo, View, ViewUpdate, ViewsMap, Workspace,
+  impl_section_op, subscribe_folder_change, timestamp, FolderChangeSender, FolderData,
+  MemberChangeSender, MembersMap, ParentChildRelations, RecentViewInfo, SectionChange,
+  SectionChangeSender, TrashInfo, TrashSectionChange, View, ViewNameIndex, ViewStatsIndex,
+  ViewUpdate, ViewsMap, Workspace,
 };
 
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
@@ -51,17 +54,24 @@ impl AsRef<str> for UserId {
   }
 }
 
-const VIEWS: &str = "views";
-const PARENT_CHILD_VIEW_RELATION: &str = "relation";
+pub(crate) const VIEWS: &str = "views";
+pub(crate) const PARENT_CHILD_VIEW_RELATION: &str = "relation";
+pub(crate) const PARENT_CHILD_VIEW_ORDER: &str = "relation_order";
 const CURRENT_VIEW: &str = "current_view";
 
 pub(crate) const FAVORITES_V1: &str = "favorites";
 const SECTION: &str = "section";
+const MEMBERS: &str = "members";
+
+/// The maximum number of views kept by [Folder::track_view_opened].
+pub const MAX_RECENT_VIEWS: usize = 30;
 
 #[derive(Clone)]
 pub struct FolderNotify {
   pub view_change_tx: ViewChangeSender,
   pub section_change_tx: SectionChangeSender,
+  pub member_change_tx: MemberChangeSender,
+  pub folder_change_tx: FolderChangeSender,
 }
 
 /// Represents the folder hierarchy in a workspace.
@@ -70,7 +80,7 @@ pub struct FolderNotify {
 /// such as workspaces, views, trash, favorites, meta, and relation.
 ///
 /// The folder hierarchy can be visualized as follows:
-/// Folder: [workspaces: [], views: {}, trash: [], favorites: { uid: [] }, meta: {}, relation: {}]
+/// Folder: [workspaces: [], views: {}, trash: [], favorites: { uid: [] }, meta: {}, relation: {}, relation_order: {}]
 ///
 ///
 /// # Fields
@@ -201,9 +211,11 @@ impl Folder {
     self.body.views.get_views_belong_to(&txn, parent_id)
   }
 
-  pub fn move_view(&mut self, view_id: &str, from: u32, to: u32) -> Option<Arc<View>> {
+  /// Moves the view after the `prev_view_id` within its parent, or to the first position if
+  /// `prev_view_id` is None. Use [Folder::move_nested_view] to move the view to another parent.
+  pub fn move_view(&mut self, view_id: &str, prev_view_id: Option<String>) -> Option<Arc<View>> {
     let mut txn = self.collab.transact_mut();
-    self.body.move_view(&mut txn, view_id, from, to)
+    self.body.move_view(&mut txn, view_id, prev_view_id)
   }
 
   /// Moves a nested view to a new location in the hierarchy.
@@ -235,6 +247,17 @@ impl Folder {
       .move_nested_view(&mut txn, view_id, new_parent_id, prev_view_id)
   }
 
+  /// Duplicates the view and all of its descendants with new ids. The copy is placed right after
+  /// the view, and the descendants keep their order.
+  ///
+  /// Returns the mapping from the id of each duplicated view to the id of its copy, so the
+  /// caller can duplicate the document or database of each view with the matching id. Returns
+  /// None if the view doesn't exist or is the workspace.
+  pub fn duplicate_view_recursive(&mut self, view_id: &str) -> Option<HashMap<String, String>> {
+    let mut txn = self.collab.transact_mut();
+    self.body.duplicate_view_recursive(&mut txn, view_id)
+  }
+
   pub fn set_current_view(&mut self, view_id: String) {
     let mut txn = self.collab.transact_mut();
     self.body.set_current_view(&mut txn, view_id);
@@ -253,6 +276,45 @@ impl Folder {
     self.body.views.update_view(&mut txn, view_id, f)
   }
 
+  /// Updates the JSON object stored in the extra of the view. The extra is replaced by an empty
+  /// object if it's missing or not an object.
+  pub fn update_view_extra<F>(&mut self, view_id: &str, f: F) -> Option<Arc<View>>
+  where
+    F: FnOnce(&mut serde_json::Map<String, serde_json::Value>),
+  {
+    let view = self.get_view(view_id)?;
+    let mut extra = view
+      .extra
+      .as_ref()
+      .and_then(|extra| serde_json::from_str::<serde_json::Value>(extra).ok())
+      .and_then(|extra| match extra {
+        serde_json::Value::Object(extra) => Some(extra),
+        _ => None,
+      })
+      .unwrap_or_default();
+    f(&mut extra);
+    let extra = serde_json::to_string(&extra).ok()?;
+    self.update_view(view_id, |update| update.set_extra(extra).done())
+  }
+
+  /// Sets a single value of the extra of the view, keeping the other values.
+  pub fn set_extra_value<V: Into<serde_json::Value>>(
+    &mut self,
+    view_id: &str,
+    key: &str,
+    value: V,
+  ) -> Option<Arc<View>> {
+    self.update_view_extra(view_id, |extra| {
+      extra.insert(key.to_string(), value.into());
+    })
+  }
+
+  pub fn remove_extra_value(&mut self, view_id: &str, key: &str) -> Option<Arc<View>> {
+    self.update_view_extra(view_id, |extra| {
+      extra.remove(key);
+    })
+  }
+
   pub fn delete_views<T: AsRef<str>>(&mut self, views: Vec<T>) {
     let mut txn = self.collab.transact_mut();
     self.body.views.delete_views(&mut txn, views);
@@ -270,6 +332,15 @@ impl Folder {
     remove_all_my_favorite_sections
   );
 
+  /// Move the favorite view to the `to` position of the current user's favorites.
+  pub fn move_favorite_view_id(&mut self, view_id: &str, to: u32) -> bool {
+    let mut txn = self.collab.transact_mut();
+    match self.body.section.section_op(&txn, Section::Favorite) {
+      None => false,
+      Some(op) => op.move_section_item_with_txn(&mut txn, view_id, to),
+    }
+  }
+
   // Recent
   impl_section_op!(
     Section::Recent,
@@ -322,6 +393,78 @@ impl Folder {
       .collect()
   }
 
+  /// Records that the view was opened by moving it to the front of the current user's recent
+  /// views. Only the latest [MAX_RECENT_VIEWS] views are kept.
+  pub fn track_view_opened(&mut self, view_id: &str) {
+    let mut txn = self.collab.transact_mut();
+    if self.body.views.get_view_with_txn(&txn, view_id).is_none() {
+      return;
+    }
+    if let Some(op) = self.body.section.section_op(&txn, Section::Recent) {
+      op.move_or_insert_to_front_with_txn(&mut txn, view_id, MAX_RECENT_VIEWS);
+    }
+  }
+
+  /// Returns the recent views of the current user, the most recently opened first. Views that
+  /// no longer exist are skipped.
+  pub fn get_my_recent_views(&self) -> Vec<RecentViewInfo> {
+    let txn = self.collab.transact();
+    let mut seen = HashSet::new();
+    self
+      .get_my_recent_sections()
+      .into_iter()
+      .filter(|item| seen.insert(item.id.clone()))
+      .flat_map(|item| {
+        self
+          .body
+          .views
+          .get_view_name_with_txn(&txn, &item.id)
+          .map(|name| RecentViewInfo {
+            id: item.id,
+            name,
+            opened_at: item.timestamp,
+          })
+      })
+      .collect()
+  }
+
+  /// Moves the view to the trash. The view is detached from its parent, and the parent is
+  /// recorded with the deletion time so the view can be put back by
+  /// [Folder::restore_view_from_trash]. Returns false if the view doesn't exist or is already in
+  /// the trash.
+  pub fn move_view_to_trash(&mut self, view_id: &str) -> bool {
+    let mut txn = self.collab.transact_mut();
+    self.body.move_view_to_trash(&mut txn, view_id)
+  }
+
+  /// Takes the view out of the trash and puts it back to its original position. If the original
+  /// parent no longer exists or is in the trash, the view is put back under the workspace.
+  pub fn restore_view_from_trash(&mut self, view_id: &str) -> Option<Arc<View>> {
+    let mut txn = self.collab.transact_mut();
+    self.body.restore_view_from_trash(&mut txn, view_id)
+  }
+
+  pub fn get_my_trash_items(&self) -> Vec<TrashSectionItem> {
+    let txn = self.collab.transact();
+    self
+      .body
+      .section
+      .section_op(&txn, Section::Trash)
+      .map(|op| op.get_all_trash_items(&txn))
+      .unwrap_or_default()
+  }
+
+  /// Returns the trash items that were moved to the trash more than `ttl` ago. They are the
+  /// candidates for permanent deletion.
+  pub fn expired_trash_items(&self, ttl: Duration) -> Vec<TrashSectionItem> {
+    let deadline = timestamp() - ttl.as_secs() as i64;
+    self
+      .get_my_trash_items()
+      .into_iter()
+      .filter(|item| item.timestamp <= deadline)
+      .collect()
+  }
+
   /// Inserts a new view into the specified workspace under a given parent view.
   ///
   /// # Parameters:
@@ -480,6 +623,9 @@ pub struct FolderBody {
   pub views: Arc<ViewsMap>,
   pub section: Arc<SectionMap>,
   pub meta: MapRef,
+  pub members: MembersMap,
+  pub view_name_index: ViewNameIndex,
+  pub view_stats_index: ViewStatsIndex,
   #[allow(dead_code)]
   subscription: Subscription,
   #[allow(dead_code)]
@@ -506,15 +652,30 @@ impl FolderBody {
     let mut txn = collab.context.transact_mut();
     // create the folder
     let mut folder = collab.data.get_or_init_map(&mut txn, FOLDER);
-    let subscription = subscribe_folder_change(&mut folder);
 
     // create the folder data
-    let views: MapRef = folder.get_or_init(&mut txn, VIEWS);
+    let mut views: MapRef = folder.get_or_init(&mut txn, VIEWS);
     let section: MapRef = folder.get_or_init(&mut txn, SECTION);
     let meta: MapRef = folder.get_or_init(&mut txn, FOLDER_META);
+    let members = MembersMap::new(
+      folder.get_or_init(&mut txn, MEMBERS),
+      notifier
+        .as_ref()
+        .map(|notifier| notifier.member_change_tx.clone()),
+    );
+    let mut relations: MapRef = folder.get_or_init(&mut txn, PARENT_CHILD_VIEW_RELATION);
     let parent_child_relations = Arc::new(ParentChildRelations::new(
-      folder.get_or_init(&mut txn, PARENT_CHILD_VIEW_RELATION),
+      relations.clone(),
+      folder.get_or_init(&mut txn, PARENT_CHILD_VIEW_ORDER),
     ));
+    let subscription = subscribe_folder_change(
+      &mut folder,
+      views.clone(),
+      parent_child_relations.clone(),
+      notifier
+        .as_ref()
+        .map(|notifier| notifier.folder_change_tx.clone()),
+    );
 
     let section = Arc::new(SectionMap::create(
       &mut txn,
@@ -523,8 +684,13 @@ impl FolderBody {
       notifier
         .as_ref()
         .map(|notifier| notifier.section_change_tx.clone()),
+      notifier
+        .as_ref()
+        .map(|notifier| notifier.folder_change_tx.clone()),
     ));
     let all_views = get_views_from_root(&views, &uid, &parent_child_relations, &section, &txn);
+    let view_name_index = ViewNameIndex::new(&mut views, &all_views);
+    let view_stats_index = ViewStatsIndex::new(&mut views, &mut relations);
     let views = Arc::new(ViewsMap::new(
       &uid,
       views,
@@ -566,6 +732,9 @@ impl FolderBody {
       views,
       section,
       meta,
+      members,
+      view_name_index,
+      view_stats_index,
       subscription,
       notifier,
     }
@@ -685,11 +854,15 @@ impl FolderBody {
     &self,
     txn: &mut TransactionMut,
     view_id: &str,
-    from: u32,
-    to: u32,
+    prev_view_id: Option<String>,
   ) -> Option<Arc<View>> {
     let view = self.views.get_view_with_txn(txn, view_id)?;
-    self.views.move_child(txn, &view.parent_view_id, from, to);
+    if !self
+      .views
+      .move_child_after(txn, &view.parent_view_id, view_id, prev_view_id.as_deref())
+    {
+      return None;
+    }
     Some(view)
   }
 
@@ -732,6 +905,137 @@ impl FolderBody {
     Some(view)
   }
 
+  pub fn duplicate_view_recursive(
+    &self,
+    txn: &mut TransactionMut,
+    view_id: &str,
+  ) -> Option<HashMap<String, String>> {
+    if self.get_workspace_id_with_txn(txn).as_deref() == Some(view_id) {
+      return None;
+    }
+    let views = self.get_view_recursively_with_txn(txn, view_id);
+    let root = views.first()?;
+    let index = self
+      .views
+      .get_view_with_txn(txn, &root.parent_view_id)
+      .and_then(|parent| {
+        parent
+          .children
+          .items
+          .iter()
+          .position(|child| child.id == view_id)
+      })
+      .map(|pos| pos as u32 + 1);
+
+    let id_mapping = views
+      .iter()
+      .map(|view| (view.id.clone(), uuid::Uuid::new_v4().to_string()))
+      .collect::<HashMap<_, _>>();
+    // The parent is always inserted before its children, so the children are appended in the
+    // same order as the original ones.
+    for view in views {
+      let is_root = view.id == view_id;
+      let parent_view_id = id_mapping
+        .get(&view.parent_view_id)
+        .cloned()
+        .unwrap_or(view.parent_view_id);
+      let copy = View {
+        id: id_mapping[&view.id].clone(),
+        parent_view_id,
+        children: Default::default(),
+        is_favorite: false,
+        created_by: Some(self.uid.as_i64()),
+        last_edited_by: Some(self.uid.as_i64()),
+        ..view
+      };
+      self
+        .views
+        .insert(txn, copy, if is_root { index } else { None });
+    }
+    Some(id_mapping)
+  }
+
+  pub fn move_view_to_trash(&self, txn: &mut TransactionMut, view_id: &str) -> bool {
+    let trash = match self.section.section_op(txn, Section::Trash) {
+      None => return false,
+      Some(trash) => trash,
+    };
+    if trash.contains_with_txn(txn, view_id) {
+      return false;
+    }
+    let view = match self.views.get_view_with_txn(txn, view_id) {
+      None => return false,
+      Some(view) => view,
+    };
+
+    let parent_id = view.parent_view_id.as_str();
+    let prev_view_id = self
+      .views
+      .get_view_with_txn(txn, parent_id)
+      .and_then(|parent| {
+        let pos = parent
+          .children
+          .items
+          .iter()
+          .position(|child| child.id == view_id)?;
+        pos
+          .checked_sub(1)
+          .map(|prev| parent.children.items[prev].id.clone())
+      });
+    self
+      .views
+      .dissociate_parent_child_with_txn(txn, parent_id, view_id);
+    trash.add_trash_item_with_txn(
+      txn,
+      TrashSectionItem::new(view_id.to_string(), parent_id.to_string(), prev_view_id),
+    );
+    true
+  }
+
+  pub fn restore_view_from_trash(
+    &self,
+    txn: &mut TransactionMut,
+    view_id: &str,
+  ) -> Option<Arc<View>> {
+    let trash = self.section.section_op(txn, Section::Trash)?;
+    let view = self.views.get_view_with_txn(txn, view_id)?;
+    let item = trash.take_trash_item_with_txn(txn, view_id)?;
+
+    let mut parent_view_id = view.parent_view_id.clone();
+    // The view stays in its parent if it was added by [SectionOperation::add_sections_item].
+    if let Some(original_parent_id) = item.parent_view_id {
+      let is_parent_available = self
+        .views
+        .get_view_with_txn(txn, &original_parent_id)
+        .is_some()
+        && !trash.contains_with_txn(txn, &original_parent_id);
+      parent_view_id = if is_parent_available {
+        original_parent_id
+      } else {
+        self.get_workspace_id_with_txn(txn)?
+      };
+
+      self
+        .views
+        .associate_parent_child_with_txn(txn, &parent_view_id, view_id, item.prev_view_id);
+      if parent_view_id != view.parent_view_id {
+        self
+          .views
+          .update_view_with_txn(&self.uid, txn, view_id, |update| {
+            update.set_bid(&parent_view_id).done()
+          });
+      }
+    }
+
+    trash.send_change(SectionChange::Trash(
+      TrashSectionChange::TrashItemRestored {
+        id: view_id.to_string(),
+        parent_view_id,
+      },
+    ));
+    self.views.get_view_with_txn(txn, view_id)
+  }
+
   pub fn get_current_view<T: ReadTxn>(&self, txn: &T) -> Option<String> {
     self.meta.get_with_txn(txn, CURRENT_VIEW)
   }
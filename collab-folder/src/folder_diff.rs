@@ -1,17 +1,20 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 use arc_swap::ArcSwapOption;
 
+use collab::core::collab::DataSource;
 use collab::core::origin::CollabOrigin;
 use collab::entity::EncodedCollab;
 use collab::error::CollabError;
 use collab::preclude::updates::decoder::Decode;
-use collab::preclude::{DeepObservable, EntryChange, Event, MapExt, ReadTxn, Update, YrsValue};
+use collab::preclude::{
+  Collab, DeepObservable, EntryChange, Event, MapExt, ReadTxn, Update, YrsValue,
+};
 
 use crate::error::FolderError;
 use crate::view::FOLDER_VIEW_ID;
-use crate::Folder;
+use crate::{Folder, View, ViewLayout};
 
 impl Folder {
   pub fn calculate_view_changes(
@@ -102,3 +105,128 @@ pub enum FolderViewChange {
   Updated { view_id: String },
   Deleted { view_ids: Vec<String> },
 }
+
+/// The changes between two versions of a folder, for example two snapshots. The views are sorted
+/// by id. The state of a user, like the favorites, is not compared.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FolderDiff {
+  pub inserted_views: Vec<FolderViewSummary>,
+  pub deleted_views: Vec<FolderViewSummary>,
+  pub updated_views: Vec<FolderViewUpdate>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FolderViewSummary {
+  pub view_id: String,
+  pub name: String,
+  pub layout: ViewLayout,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FolderViewUpdate {
+  pub view_id: String,
+  pub name: String,
+  /// The previous name if the view was renamed.
+  pub old_name: Option<String>,
+  /// The view moved to another parent.
+  pub moved: bool,
+  /// Children were added to the view, removed from it or reordered.
+  pub children_changed: bool,
+  /// The layout, icon, cover or extra data of the view changed.
+  pub data_changed: bool,
+}
+
+impl FolderDiff {
+  pub fn new(older: &[Arc<View>], newer: &[Arc<View>]) -> Self {
+    let older = older
+      .iter()
+      .map(|view| (view.id.as_str(), view))
+      .collect::<HashMap<_, _>>();
+    let newer = newer
+      .iter()
+      .map(|view| (view.id.as_str(), view))
+      .collect::<HashMap<_, _>>();
+
+    let mut diff = FolderDiff::default();
+    for (view_id, new_view) in &newer {
+      match older.get(view_id) {
+        None => diff
+          .inserted_views
+          .push(FolderViewSummary::from(new_view.as_ref())),
+        Some(old_view) => {
+          let update = FolderViewUpdate {
+            view_id: new_view.id.clone(),
+            name: new_view.name.clone(),
+            old_name: (old_view.name != new_view.name).then(|| old_view.name.clone()),
+            moved: old_view.parent_view_id != new_view.parent_view_id,
+            children_changed: old_view.children != new_view.children,
+            data_changed: old_view.layout != new_view.layout
+              || old_view.icon != new_view.icon
+              || old_view.cover != new_view.cover
+              || old_view.extra != new_view.extra,
+          };
+          if update.old_name.is_some()
+            || update.moved
+            || update.children_changed
+            || update.data_changed
+          {
+            diff.updated_views.push(update);
+          }
+        },
+      }
+    }
+    for (view_id, old_view) in &older {
+      if !newer.contains_key(view_id) {
+        diff
+          .deleted_views
+          .push(FolderViewSummary::from(old_view.as_ref()));
+      }
+    }
+
+    diff
+      .inserted_views
+      .sort_by(|a, b| a.view_id.cmp(&b.view_id));
+    diff.deleted_views.sort_by(|a, b| a.view_id.cmp(&b.view_id));
+    diff.updated_views.sort_by(|a, b| a.view_id.cmp(&b.view_id));
+    diff
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.inserted_views.is_empty() && self.deleted_views.is_empty() && self.updated_views.is_empty()
+  }
+}
+
+impl From<&View> for FolderViewSummary {
+  fn from(view: &View) -> Self {
+    Self {
+      view_id: view.id.clone(),
+      name: view.name.clone(),
+      layout: view.layout.clone(),
+    }
+  }
+}
+
+/// Decodes both versions of the folder and returns the changes from `older` to `newer`.
+pub fn diff_folder_snapshots(
+  older: &EncodedCollab,
+  newer: &EncodedCollab,
+) -> Result<FolderDiff, FolderError> {
+  let older = views_from_encoded_collab(older)?;
+  let newer = views_from_encoded_collab(newer)?;
+  Ok(FolderDiff::new(&older, &newer))
+}
+
+fn views_from_encoded_collab(
+  encoded_collab: &EncodedCollab,
+) -> Result<Vec<Arc<View>>, FolderError> {
+  let collab = Collab::new_with_source(
+    CollabOrigin::Empty,
+    "",
+    DataSource::from(encoded_collab.clone()),
+    vec![],
+    false,
+  )?;
+  // The views are compared without the state of a user, so any user id works.
+  let folder = Folder::open(0, collab, None)?;
+  Ok(folder.get_all_views())
+}
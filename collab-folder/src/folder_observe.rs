@@ -1,15 +1,25 @@
 use dashmap::DashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 use collab::core::collab::{IndexContent, IndexContentSender};
+use collab::preclude::array::ArrayRef;
+use collab::preclude::types::Change;
 use collab::preclude::{
-  DeepObservable, EntryChange, Event, MapRef, Subscription, ToJson, YrsValue,
+  DeepObservable, EntryChange, Event, Events, MapExt, MapRef, PathSegment, Subscription, ToJson,
+  TransactionMut, YrsValue,
 };
 use serde_json::json;
 use tokio::sync::broadcast;
 
-use crate::section::SectionMap;
-use crate::{view_from_map_ref, ParentChildRelations, UserId, View, ViewIndexContent};
+use crate::folder::{PARENT_CHILD_VIEW_ORDER, PARENT_CHILD_VIEW_RELATION, VIEWS};
+use crate::relation::{initial_order_keys, order_by_keys};
+use crate::section::{FavoriteSectionChange, SectionMap, TrashSectionChange};
+use crate::view::{FOLDER_VIEW_NAME, VIEW_PARENT_ID};
+use crate::{
+  children_from_array_ref, view_from_map_ref, view_identifier_from_value, ParentChildRelations,
+  UserId, View, ViewIndexContent,
+};
 
 #[derive(Debug, Clone)]
 pub enum ViewChange {
@@ -21,7 +31,34 @@ pub enum ViewChange {
 pub type ViewChangeSender = broadcast::Sender<ViewChange>;
 pub type ViewChangeReceiver = broadcast::Receiver<ViewChange>;
 
-pub(crate) fn subscribe_folder_change(root: &mut MapRef) -> Subscription {
+/// A change of one region of the sidebar, so the client only needs to refresh that region
+/// instead of reloading the whole hierarchy on every [ViewChange::DidUpdate].
+#[derive(Debug, Clone)]
+pub enum FolderChange {
+  FavoritesChanged(FavoriteSectionChange),
+  TrashChanged(TrashSectionChange),
+  /// The view was moved from the `from` parent to the `to` parent. Both are the same parent if
+  /// the view was only reordered among its siblings.
+  ViewMoved {
+    view_id: String,
+    from: String,
+    to: String,
+  },
+  ViewRenamed {
+    view_id: String,
+    name: String,
+  },
+}
+
+pub type FolderChangeSender = broadcast::Sender<FolderChange>;
+pub type FolderChangeReceiver = broadcast::Receiver<FolderChange>;
+
+pub(crate) fn subscribe_folder_change(
+  root: &mut MapRef,
+  views: MapRef,
+  relations: Arc<ParentChildRelations>,
+  folder_change_tx: Option<FolderChangeSender>,
+) -> Subscription {
   root.observe_deep(move |txn, events| {
     for deep_event in events.iter() {
       match deep_event {
@@ -50,9 +87,178 @@ pub(crate) fn subscribe_folder_change(root: &mut MapRef) -> Subscription {
         _ => {},
       }
     }
+
+    if let Some(folder_change_tx) = folder_change_tx.as_ref() {
+      for change in view_moves_and_renames(txn, events, &views, &relations) {
+        let _ = folder_change_tx.send(change);
+      }
+    }
   })
 }
 
+/// Returns the views renamed or moved by the transaction, whether it's local or remote.
+///
+/// A view moved to another parent is found by the change of its parent id. A view moved among
+/// its siblings is found by its new position in the array of the children, or by its new order
+/// key. The keys given to the siblings the first time they are reordered are not moves.
+fn view_moves_and_renames(
+  txn: &TransactionMut,
+  events: &Events,
+  views: &MapRef,
+  relations: &ParentChildRelations,
+) -> Vec<FolderChange> {
+  let mut changes = vec![];
+  let mut created = HashSet::new();
+  let mut moved = HashSet::new();
+  let mut inserted_children = vec![];
+  let mut order_changes = HashMap::new();
+  for event in events.iter() {
+    let path = event
+      .path()
+      .into_iter()
+      .map(|segment| match segment {
+        PathSegment::Key(key) => key.to_string(),
+        PathSegment::Index(index) => index.to_string(),
+      })
+      .collect::<Vec<_>>();
+    let path = path.iter().map(String::as_str).collect::<Vec<_>>();
+    match (event, path.as_slice()) {
+      (Event::Map(event), [VIEWS]) => {
+        for (view_id, change) in event.keys(txn).iter() {
+          if let EntryChange::Inserted(_) = change {
+            created.insert(view_id.to_string());
+          }
+        }
+      },
+      (Event::Map(event), [VIEWS, view_id]) => {
+        for (key, change) in event.keys(txn).iter() {
+          let (old, new) = match change {
+            EntryChange::Inserted(new) => (None, new),
+            EntryChange::Updated(old, new) => (Some(old), new),
+            EntryChange::Removed(_) => continue,
+          };
+          let old = old.and_then(|old| old.clone().cast::<String>().ok());
+          let new = match new.clone().cast::<String>() {
+            Ok(new) if old.as_ref() != Some(&new) => new,
+            _ => continue,
+          };
+          match key.as_ref() {
+            FOLDER_VIEW_NAME => changes.push(FolderChange::ViewRenamed {
+              view_id: view_id.to_string(),
+              name: new,
+            }),
+            VIEW_PARENT_ID => {
+              if let Some(old) = old {
+                moved.insert(view_id.to_string());
+                changes.push(FolderChange::ViewMoved {
+                  view_id: view_id.to_string(),
+                  from: old,
+                  to: new,
+                });
+              }
+            },
+            _ => {},
+          }
+        }
+      },
+      (Event::Array(event), [PARENT_CHILD_VIEW_RELATION, parent_id]) => {
+        for change in event.delta(txn) {
+          if let Change::Added(values) = change {
+            for value in values {
+              if let Some(child) = view_identifier_from_value(value.clone()) {
+                inserted_children.push((parent_id.to_string(), child.id));
+              }
+            }
+          }
+        }
+      },
+      (Event::Map(event), [PARENT_CHILD_VIEW_ORDER]) => {
+        for (view_id, change) in event.keys(txn).iter() {
+          let old_key = match change {
+            EntryChange::Inserted(_) => None,
+            EntryChange::Updated(old, _) => old.clone().cast::<String>().ok(),
+            EntryChange::Removed(_) => continue,
+          };
+          order_changes.insert(view_id.to_string(), old_key);
+        }
+      },
+      _ => {},
+    }
+  }
+
+  // The children removed and inserted again in the array, by the clients that don't use the
+  // order keys.
+  for (parent_id, view_id) in inserted_children {
+    if !created.contains(&view_id) && moved.insert(view_id.clone()) {
+      changes.push(FolderChange::ViewMoved {
+        view_id,
+        from: parent_id.clone(),
+        to: parent_id,
+      });
+    }
+  }
+
+  let mut initial_keys_by_parent = HashMap::new();
+  for (view_id, old_key) in order_changes.iter() {
+    if created.contains(view_id) || moved.contains(view_id) {
+      continue;
+    }
+    let parent_id = match views
+      .get_with_txn::<_, MapRef>(txn, view_id)
+      .and_then(|view| view.get_with_txn::<_, String>(txn, VIEW_PARENT_ID))
+    {
+      Some(parent_id) => parent_id,
+      None => continue,
+    };
+    let is_moved = match old_key {
+      Some(_) => true,
+      None => {
+        let initial_keys = initial_keys_by_parent
+          .entry(parent_id.clone())
+          .or_insert_with(|| initial_keys_of_children(txn, relations, &parent_id, &order_changes));
+        let new_key = relations.orders.get_with_txn::<_, String>(txn, view_id);
+        initial_keys.get(view_id) != new_key.as_ref()
+      },
+    };
+    if is_moved {
+      changes.push(FolderChange::ViewMoved {
+        view_id: view_id.clone(),
+        from: parent_id.clone(),
+        to: parent_id,
+      });
+    }
+  }
+  changes
+}
+
+/// Returns the keys the children of the parent would get if they were given a key before the
+/// transaction, by their id. `order_changes` are the keys the children had before the
+/// transaction, for the keys changed by the transaction.
+fn initial_keys_of_children(
+  txn: &TransactionMut,
+  relations: &ParentChildRelations,
+  parent_id: &str,
+  order_changes: &HashMap<String, Option<String>>,
+) -> HashMap<String, String> {
+  let children = match relations
+    .container
+    .get_with_txn::<_, ArrayRef>(txn, parent_id)
+  {
+    Some(array) => children_from_array_ref(txn, &array).into_inner(),
+    None => return HashMap::new(),
+  };
+  let children = order_by_keys(children, |view_id| match order_changes.get(view_id) {
+    Some(old_key) => old_key.clone(),
+    None => relations.orders.get_with_txn(txn, view_id),
+  });
+  let keys = initial_order_keys(children.iter().map(|(_, key)| key.clone()));
+  children
+    .into_iter()
+    .zip(keys)
+    .map(|((child, _), (key, _))| (child.id, key))
+    .collect()
+}
+
 pub(crate) fn subscribe_view_change(
   _uid: &UserId,
   root: &mut MapRef,
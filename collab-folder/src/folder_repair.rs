@@ -0,0 +1,216 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use collab::preclude::{ReadTxn, TransactionMut};
+
+use crate::hierarchy_builder::SpacePermission;
+use crate::section::Section;
+use crate::{timestamp, Folder, FolderBody, SpaceInfo, View, ViewLayout};
+
+/// The name of the space that receives the views re-attached by [Folder::repair_hierarchy].
+pub const RECOVERY_SPACE_NAME: &str = "Recovered";
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum HierarchyIssue {
+  /// The parent of the view doesn't exist, so the view is not reachable from the workspace.
+  MissingParent {
+    view_id: String,
+    parent_view_id: String,
+  },
+  /// The views are ancestors of themselves. Each view is the parent of the previous one, and
+  /// the first view is the parent of the last one.
+  Cycle { view_ids: Vec<String> },
+  /// The trash of the current user contains a view that no longer exists.
+  DanglingTrashItem { view_id: String },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HierarchyReport {
+  pub issues: Vec<HierarchyIssue>,
+}
+
+impl HierarchyReport {
+  pub fn is_ok(&self) -> bool {
+    self.issues.is_empty()
+  }
+}
+
+/// Returns the id of the recovery space of the workspace. The id is derived from the workspace
+/// id so that repairing on two devices at the same time doesn't create two spaces.
+pub fn recovery_space_id(workspace_id: &str) -> String {
+  format!("{}-recovery", workspace_id)
+}
+
+impl Folder {
+  /// Checks the hierarchy of the folder. The views with issues don't show up in the sidebar,
+  /// see [HierarchyIssue].
+  pub fn validate_hierarchy(&self) -> HierarchyReport {
+    let txn = self.collab.transact();
+    self.body.validate_hierarchy(&txn)
+  }
+
+  /// Repairs the issues found by [Folder::validate_hierarchy]:
+  ///   1. the views without parent are moved to the recovery space
+  ///   2. each cycle is broken by moving its first view to the recovery space
+  ///   3. the dangling trash items are removed
+  ///
+  /// The recovery space is created when needed. Returns the issues that were repaired.
+  pub fn repair_hierarchy(&mut self) -> HierarchyReport {
+    let mut txn = self.collab.transact_mut();
+    self.body.repair_hierarchy(&mut txn)
+  }
+}
+
+impl FolderBody {
+  pub fn validate_hierarchy<T: ReadTxn>(&self, txn: &T) -> HierarchyReport {
+    let mut report = HierarchyReport::default();
+    let workspace_id = match self.get_workspace_id_with_txn(txn) {
+      None => return report,
+      Some(workspace_id) => workspace_id,
+    };
+    let trash_ids = self
+      .section
+      .section_op(txn, Section::Trash)
+      .map(|op| op.get_all_trash_items(txn))
+      .unwrap_or_default()
+      .into_iter()
+      .map(|item| item.id)
+      .collect::<Vec<_>>();
+    let views = self
+      .views
+      .get_all_views(txn)
+      .into_iter()
+      .map(|view| (view.id.clone(), view))
+      .collect::<HashMap<String, Arc<View>>>();
+
+    let mut view_ids = views.keys().cloned().collect::<Vec<_>>();
+    view_ids.sort();
+    for view_id in &view_ids {
+      let view = &views[view_id];
+      // The orphan views are detached on purpose. The trashed views are put back under an
+      // existing view when they are restored.
+      if view.id == workspace_id || view.parent_view_id == view.id || trash_ids.contains(view_id) {
+        continue;
+      }
+      if view.parent_view_id != workspace_id && !views.contains_key(&view.parent_view_id) {
+        report.issues.push(HierarchyIssue::MissingParent {
+          view_id: view.id.clone(),
+          parent_view_id: view.parent_view_id.clone(),
+        });
+      }
+    }
+
+    let mut visited = HashSet::new();
+    for view_id in &view_ids {
+      if let Some(cycle) = find_cycle(&views, &workspace_id, view_id, &mut visited) {
+        report
+          .issues
+          .push(HierarchyIssue::Cycle { view_ids: cycle });
+      }
+    }
+
+    for view_id in trash_ids {
+      if !views.contains_key(&view_id) {
+        report
+          .issues
+          .push(HierarchyIssue::DanglingTrashItem { view_id });
+      }
+    }
+    report
+  }
+
+  pub fn repair_hierarchy(&self, txn: &mut TransactionMut) -> HierarchyReport {
+    let report = self.validate_hierarchy(txn);
+    let workspace_id = match self.get_workspace_id_with_txn(txn) {
+      Some(workspace_id) if !report.is_ok() => workspace_id,
+      _ => return report,
+    };
+
+    let recovery_space_id = recovery_space_id(&workspace_id);
+    let mut dangling_trash_ids = vec![];
+    for issue in &report.issues {
+      let view_id = match issue {
+        HierarchyIssue::MissingParent { view_id, .. } => view_id,
+        HierarchyIssue::Cycle { view_ids } => &view_ids[0],
+        HierarchyIssue::DanglingTrashItem { view_id } => {
+          dangling_trash_ids.push(view_id.clone());
+          continue;
+        },
+      };
+      if self
+        .views
+        .get_view_with_txn(txn, &recovery_space_id)
+        .is_none()
+      {
+        self.insert_recovery_space(txn, &workspace_id, &recovery_space_id);
+      }
+      tracing::warn!("Move view {} to the recovery space", view_id);
+      self.move_nested_view(txn, view_id, &recovery_space_id, None);
+    }
+
+    if !dangling_trash_ids.is_empty() {
+      if let Some(trash) = self.section.section_op(txn, Section::Trash) {
+        trash.delete_section_items_with_txn(txn, dangling_trash_ids);
+      }
+    }
+    report
+  }
+
+  fn insert_recovery_space(&self, txn: &mut TransactionMut, workspace_id: &str, space_id: &str) {
+    let info = SpaceInfo {
+      is_space: true,
+      space_permission: SpacePermission::PublicToAll,
+      space_created_at: timestamp(),
+      space_icon: None,
+      space_icon_color: None,
+      space_members: vec![],
+    };
+    let mut view = View::new(
+      space_id.to_string(),
+      workspace_id.to_string(),
+      RECOVERY_SPACE_NAME.to_string(),
+      ViewLayout::Document,
+      Some(self.uid.as_i64()),
+    );
+    view.extra = serde_json::to_string(&info).ok();
+    self.views.insert(txn, view, None);
+  }
+}
+
+/// Walks up the ancestors of the view and returns the cycle if one is found.
+///
+/// The views in `visited` were already walked, so walking stops when one of them is reached. It
+/// keeps the walk linear in the number of views, and reports each cycle only once.
+fn find_cycle(
+  views: &HashMap<String, Arc<View>>,
+  workspace_id: &str,
+  view_id: &str,
+  visited: &mut HashSet<String>,
+) -> Option<Vec<String>> {
+  let mut path: Vec<String> = vec![];
+  let mut current = views.get(view_id)?;
+  loop {
+    if let Some(pos) = path.iter().position(|id| id == &current.id) {
+      let mut cycle = path.split_off(pos);
+      // Start the cycle from its smallest id, so it doesn't depend on where the walk started.
+      let min = (0..cycle.len()).min_by_key(|i| &cycle[*i]).unwrap_or(0);
+      cycle.rotate_left(min);
+      visited.extend(path);
+      visited.extend(cycle.iter().cloned());
+      return Some(cycle);
+    }
+    if visited.contains(&current.id) {
+      break;
+    }
+    path.push(current.id.clone());
+    if current.parent_view_id == workspace_id || current.parent_view_id == current.id {
+      break;
+    }
+    match views.get(&current.parent_view_id) {
+      None => break,
+      Some(parent) => current = parent,
+    }
+  }
+  visited.extend(path);
+  None
+}
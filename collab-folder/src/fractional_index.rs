@@ -0,0 +1,117 @@
+//! Keys used to order the children of a view.
+//!
+//! A key represents a fraction between 0 and 1 written in base 62, without the leading "0.". A new
+//! key can always be generated between two existing keys, so moving a view only rewrites the key
+//! of that view instead of moving items in an array.
+
+const DIGITS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+const BASE: usize = DIGITS.len();
+
+fn digit_index(digit: u8) -> usize {
+  DIGITS.iter().position(|d| *d == digit).unwrap_or(0)
+}
+
+/// Returns a key that sorts after `prev` and before `next`. None means the start or the end of
+/// the list respectively.
+///
+/// If `next` doesn't sort after `prev`, which can happen when two devices generate the same key
+/// concurrently, the key is generated right after `prev`.
+pub fn key_between(prev: Option<&str>, next: Option<&str>) -> String {
+  let prev = prev.unwrap_or("");
+  match next {
+    Some(next) if next > prev => midpoint(prev.as_bytes(), Some(next.as_bytes())),
+    Some(_) => midpoint(prev.as_bytes(), Some(&[])),
+    None => midpoint(prev.as_bytes(), None),
+  }
+}
+
+/// Returns `n` keys sorted in ascending order between `prev` and `next`.
+pub fn n_keys_between(prev: Option<&str>, next: Option<&str>, n: usize) -> Vec<String> {
+  let mut keys: Vec<String> = Vec::with_capacity(n);
+  for _ in 0..n {
+    let key = key_between(keys.last().map(|key| key.as_str()).or(prev), next);
+    keys.push(key);
+  }
+  keys
+}
+
+/// `a` is less than `b` and none of them ends with the zero digit. An empty `b` means that the
+/// key must be greater than `a` and as close as possible to it.
+fn midpoint(a: &[u8], b: Option<&[u8]>) -> String {
+  if let Some(b) = b {
+    if b.is_empty() {
+      let mut key = String::from_utf8_lossy(a).to_string();
+      key.push_str(&midpoint(&[], None));
+      return key;
+    }
+
+    // Skip the common prefix. The missing digits of `a` are zeros.
+    let mut n = 0;
+    while n < b.len() && a.get(n).copied().unwrap_or(DIGITS[0]) == b[n] {
+      n += 1;
+    }
+    if n > 0 {
+      let mut key = String::from_utf8_lossy(&b[..n]).to_string();
+      key.push_str(&midpoint(a.get(n..).unwrap_or(&[]), Some(&b[n..])));
+      return key;
+    }
+  }
+
+  let digit_a = a.first().map(|d| digit_index(*d)).unwrap_or(0);
+  let digit_b = b.map(|b| digit_index(b[0])).unwrap_or(BASE);
+  if digit_b - digit_a > 1 {
+    let mid = (digit_a + digit_b + 1) / 2;
+    (DIGITS[mid] as char).to_string()
+  } else {
+    match b {
+      // The first digits are consecutive, so the first digit of `b` is a key in between.
+      Some(b) if b.len() > 1 => (b[0] as char).to_string(),
+      _ => {
+        let mut key = (DIGITS[digit_a] as char).to_string();
+        key.push_str(&midpoint(a.get(1..).unwrap_or(&[]), None));
+        key
+      },
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn key_between_test() {
+    let first = key_between(None, None);
+    let after = key_between(Some(&first), None);
+    let before = key_between(None, Some(&first));
+    assert!(before < first && first < after);
+
+    let mid = key_between(Some(&first), Some(&after));
+    assert!(first < mid && mid < after);
+
+    // Keep inserting at the same position
+    let mut next = after.clone();
+    for _ in 0..100 {
+      let key = key_between(Some(&first), Some(&next));
+      assert!(first < key && key < next, "{} {} {}", first, key, next);
+      next = key;
+    }
+  }
+
+  #[test]
+  fn key_between_equal_keys_test() {
+    let key = key_between(None, None);
+    let next = key_between(Some(&key), Some(&key));
+    assert!(next > key);
+  }
+
+  #[test]
+  fn n_keys_between_test() {
+    let keys = n_keys_between(Some("V"), Some("W"), 10);
+    assert_eq!(keys.len(), 10);
+    for window in keys.windows(2) {
+      assert!(window[0] < window[1]);
+    }
+    assert!(keys[0].as_str() > "V" && keys[9].as_str() < "W");
+  }
+}
@@ -204,6 +204,7 @@ impl NestedChildViewBuilder {
       is_favorite: self.is_favorite,
       layout: self.layout,
       icon: self.icon,
+      cover: None,
       created_by: Some(self.uid),
       last_edited_time: 0,
       children: RepeatedViewIdentifier::new(
@@ -243,12 +244,25 @@ impl ViewExtraBuilder {
     self
   }
 
+  pub fn with_space_icon(mut self, icon: &str, icon_color: Option<&str>) -> Self {
+    self.0["space_icon"] = json!(icon);
+    if let Some(icon_color) = icon_color {
+      self.0["space_icon_color"] = json!(icon_color);
+    }
+    self
+  }
+
+  pub fn with_space_members(mut self, members: Vec<i64>) -> Self {
+    self.0["space_members"] = json!(members);
+    self
+  }
+
   pub fn build(self) -> serde_json::Value {
     self.0
   }
 }
 
-#[derive(Debug, Clone, serde_repr::Serialize_repr, serde_repr::Deserialize_repr)]
+#[derive(Debug, Clone, Eq, PartialEq, serde_repr::Serialize_repr, serde_repr::Deserialize_repr)]
 #[repr(u8)]
 pub enum SpacePermission {
   PublicToAll = 0,
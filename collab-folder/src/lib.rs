@@ -2,18 +2,28 @@ pub use entities::*;
 pub use folder::*;
 pub use folder_migration::*;
 pub use folder_observe::*;
+pub use folder_repair::*;
+pub use member::*;
 pub use relation::*;
 pub use section::*;
+pub use space::*;
 // pub use trash::*;
 pub use view::*;
+pub use view_search::*;
+pub use view_stats::*;
 pub use workspace::*;
 
 mod entities;
 mod folder;
+mod fractional_index;
+mod member;
 mod relation;
 mod section;
+mod space;
 // mod trash;
 mod view;
+mod view_search;
+mod view_stats;
 mod workspace;
 
 #[macro_use]
@@ -22,4 +32,7 @@ pub mod error;
 pub mod folder_diff;
 mod folder_migration;
 mod folder_observe;
+mod folder_repair;
 pub mod hierarchy_builder;
+#[cfg(feature = "test_utils")]
+pub mod test_utils;
@@ -0,0 +1,226 @@
+use anyhow::bail;
+use collab::preclude::encoding::serde::{from_any, to_any};
+use collab::preclude::{
+  deserialize_i64_from_numeric, Any, EntryChange, Map, MapRef, Observable, ReadTxn, Subscription,
+  TransactionMut, YrsValue,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::{timestamp, Folder};
+
+#[derive(
+  Debug, Clone, Copy, Eq, PartialEq, serde_repr::Serialize_repr, serde_repr::Deserialize_repr,
+)]
+#[repr(u8)]
+pub enum MemberRole {
+  Owner = 1,
+  Member = 2,
+  Guest = 3,
+}
+
+impl MemberRole {
+  /// Whether the role allows creating and editing the views of the workspace.
+  pub fn can_edit(&self) -> bool {
+    matches!(self, MemberRole::Owner | MemberRole::Member)
+  }
+
+  /// Whether the role allows managing the members of the workspace.
+  pub fn can_manage_members(&self) -> bool {
+    matches!(self, MemberRole::Owner)
+  }
+}
+
+/// A member of the workspace. The members are stored in the folder, keyed by uid, so they can be
+/// read offline and are synced like the rest of the folder.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceMember {
+  #[serde(deserialize_with = "deserialize_i64_from_numeric")]
+  pub uid: i64,
+  pub role: MemberRole,
+  pub name: String,
+  #[serde(default)]
+  pub avatar: Option<String>,
+  #[serde(deserialize_with = "deserialize_i64_from_numeric")]
+  pub joined_at: i64,
+}
+
+impl WorkspaceMember {
+  pub fn new(uid: i64, role: MemberRole, name: String) -> Self {
+    Self {
+      uid,
+      role,
+      name,
+      avatar: None,
+      joined_at: timestamp(),
+    }
+  }
+}
+
+impl TryFrom<&Any> for WorkspaceMember {
+  type Error = anyhow::Error;
+
+  fn try_from(any: &Any) -> Result<Self, Self::Error> {
+    Ok(from_any(any)?)
+  }
+}
+
+impl From<WorkspaceMember> for Any {
+  fn from(value: WorkspaceMember) -> Self {
+    to_any(&value).unwrap()
+  }
+}
+
+impl TryFrom<&YrsValue> for WorkspaceMember {
+  type Error = anyhow::Error;
+
+  fn try_from(value: &YrsValue) -> Result<Self, Self::Error> {
+    match value {
+      YrsValue::Any(any) => WorkspaceMember::try_from(any),
+      _ => bail!("Invalid member yrs value"),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum MemberChange {
+  DidAddMember { member: WorkspaceMember },
+  DidUpdateMember { member: WorkspaceMember },
+  DidRemoveMember { uid: i64 },
+}
+
+pub type MemberChangeSender = broadcast::Sender<MemberChange>;
+pub type MemberChangeReceiver = broadcast::Receiver<MemberChange>;
+
+/// The members of the workspace, keyed by uid.
+///
+/// The changes are sent from an observer of the map, so the changes applied from remote are
+/// notified in the same way as the local ones.
+pub struct MembersMap {
+  container: MapRef,
+  #[allow(dead_code)]
+  subscription: Option<Subscription>,
+}
+
+impl MembersMap {
+  pub fn new(mut container: MapRef, change_tx: Option<MemberChangeSender>) -> Self {
+    let subscription =
+      change_tx.map(|change_tx| subscribe_member_change(&mut container, change_tx));
+    Self {
+      container,
+      subscription,
+    }
+  }
+
+  pub fn get_member<T: ReadTxn>(&self, txn: &T, uid: i64) -> Option<WorkspaceMember> {
+    let value = self.container.get(txn, &uid.to_string())?;
+    WorkspaceMember::try_from(&value).ok()
+  }
+
+  /// Returns all the members, ordered by the time they joined the workspace.
+  pub fn get_all_members<T: ReadTxn>(&self, txn: &T) -> Vec<WorkspaceMember> {
+    let mut members = self
+      .container
+      .iter(txn)
+      .flat_map(|(_, value)| WorkspaceMember::try_from(&value).ok())
+      .collect::<Vec<_>>();
+    members.sort_by_key(|member| (member.joined_at, member.uid));
+    members
+  }
+
+  /// Inserts the member, replacing the existing member with the same uid.
+  pub fn upsert_member(&self, txn: &mut TransactionMut, member: WorkspaceMember) {
+    self
+      .container
+      .insert(txn, member.uid.to_string(), Any::from(member));
+  }
+
+  pub fn update_member<F>(
+    &self,
+    txn: &mut TransactionMut,
+    uid: i64,
+    f: F,
+  ) -> Option<WorkspaceMember>
+  where
+    F: FnOnce(&mut WorkspaceMember),
+  {
+    let mut member = self.get_member(txn, uid)?;
+    f(&mut member);
+    // The uid is the key of the member, so it can't be changed.
+    member.uid = uid;
+    self.upsert_member(txn, member.clone());
+    Some(member)
+  }
+
+  pub fn remove_member(&self, txn: &mut TransactionMut, uid: i64) -> Option<WorkspaceMember> {
+    let member = self.get_member(txn, uid)?;
+    self.container.remove(txn, &uid.to_string());
+    Some(member)
+  }
+}
+
+fn subscribe_member_change(root: &mut MapRef, change_tx: MemberChangeSender) -> Subscription {
+  root.observe(move |txn, event| {
+    for (key, change) in event.keys(txn).iter() {
+      let change = match change {
+        EntryChange::Inserted(value) => match WorkspaceMember::try_from(value) {
+          Ok(member) => MemberChange::DidAddMember { member },
+          Err(_) => continue,
+        },
+        EntryChange::Updated(_, value) => match WorkspaceMember::try_from(value) {
+          Ok(member) => MemberChange::DidUpdateMember { member },
+          Err(_) => continue,
+        },
+        EntryChange::Removed(_) => match key.parse::<i64>() {
+          Ok(uid) => MemberChange::DidRemoveMember { uid },
+          Err(_) => continue,
+        },
+      };
+      let _ = change_tx.send(change);
+    }
+  })
+}
+
+impl Folder {
+  /// Adds the member to the workspace, replacing the existing member with the same uid.
+  pub fn add_member(&mut self, member: WorkspaceMember) {
+    let mut txn = self.collab.transact_mut();
+    self.body.members.upsert_member(&mut txn, member);
+  }
+
+  /// Updates the member with the given uid. Returns None if the member doesn't exist.
+  pub fn update_member<F>(&mut self, uid: i64, f: F) -> Option<WorkspaceMember>
+  where
+    F: FnOnce(&mut WorkspaceMember),
+  {
+    let mut txn = self.collab.transact_mut();
+    self.body.members.update_member(&mut txn, uid, f)
+  }
+
+  /// Removes the member with the given uid and returns it.
+  pub fn remove_member(&mut self, uid: i64) -> Option<WorkspaceMember> {
+    let mut txn = self.collab.transact_mut();
+    self.body.members.remove_member(&mut txn, uid)
+  }
+
+  pub fn get_member(&self, uid: i64) -> Option<WorkspaceMember> {
+    let txn = self.collab.transact();
+    self.body.members.get_member(&txn, uid)
+  }
+
+  /// Returns all the members of the workspace, ordered by the time they joined.
+  pub fn get_all_members(&self) -> Vec<WorkspaceMember> {
+    let txn = self.collab.transact();
+    self.body.members.get_all_members(&txn)
+  }
+
+  /// Returns the role of the member, or None if the user is not a member of the workspace.
+  pub fn get_member_role(&self, uid: i64) -> Option<MemberRole> {
+    self.get_member(uid).map(|member| member.role)
+  }
+
+  /// Returns the role of the current user.
+  pub fn get_my_role(&self) -> Option<MemberRole> {
+    self.get_member_role(self.body.uid.as_i64())
+  }
+}
@@ -1,11 +1,13 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
 
-use collab::preclude::{Any, MapExt, MapRef, YrsValue};
+use collab::preclude::{Any, Map, MapExt, MapRef, YrsValue};
 use collab::preclude::{Array, ArrayRef, ReadTxn, TransactionMut};
 use serde::{Deserialize, Serialize};
 
+use crate::fractional_index::{key_between, n_keys_between};
+
 /// Used to keep track of the view hierarchy.
 /// Parent-child relationship is stored in the map and each child is stored in an array.
 /// relation {
@@ -13,13 +15,22 @@ use serde::{Deserialize, Serialize};
 ///   parent_id: [child_id1, child_id2, ...]
 /// }
 ///
+/// Once the children of a parent have been reordered, their order is defined by the fractional
+/// keys stored in the `orders` map instead of their position in the array. Moving a child only
+/// rewrites its key, so concurrent moves on different devices never duplicate or lose the child.
+/// relation_order {
+///   child_id1: "V",
+///   child_id2: "l",
+/// }
+///
 pub struct ParentChildRelations {
-  container: MapRef,
+  pub(crate) container: MapRef,
+  pub(crate) orders: MapRef,
 }
 
 impl ParentChildRelations {
-  pub fn new(container: MapRef) -> Self {
-    Self { container }
+  pub fn new(container: MapRef, orders: MapRef) -> Self {
+    Self { container, orders }
   }
 
   /// Dissociates a parent-child relationship within a given transaction.
@@ -114,13 +125,30 @@ impl ParentChildRelations {
     }
   }
 
+  /// Moves the child after the `prev_view_id`, or to the first position if it's None.
+  /// Returns false if the child or the `prev_view_id` is not a child of the parent.
+  pub fn move_child_after_with_txn(
+    &self,
+    txn: &mut TransactionMut,
+    parent_id: &str,
+    view_id: &str,
+    prev_view_id: Option<&str>,
+  ) -> bool {
+    match self.get_children_with_txn(txn, parent_id) {
+      None => false,
+      Some(belonging_array) => {
+        belonging_array.move_child_after_with_txn(txn, view_id, prev_view_id)
+      },
+    }
+  }
+
   pub fn get_children_with_txn<T: ReadTxn>(
     &self,
     txn: &T,
     parent_id: &str,
   ) -> Option<ChildrenArray> {
     let array = self.container.get_with_txn(txn, parent_id)?;
-    Some(ChildrenArray::from_array(array))
+    Some(ChildrenArray::with_orders(array, self.orders.clone()))
   }
 
   pub fn get_or_create_children_with_txn(
@@ -132,7 +160,7 @@ impl ParentChildRelations {
       .container
       .get_with_txn(txn, parent_id)
       .unwrap_or_else(|| self.container.get_or_init_array(txn, parent_id));
-    ChildrenArray::from_array(array_ref)
+    ChildrenArray::with_orders(array_ref, self.orders.clone())
   }
 
   pub fn delete_children_with_txn(&self, txn: &mut TransactionMut, parent_id: &str, index: u32) {
@@ -155,38 +183,127 @@ impl ParentChildRelations {
 }
 
 /// Handy wrapper around an array of children.
-/// It provides methods to manipulate the array.
+/// It provides methods to manipulate the array. The indexes are the positions of the children
+/// in the order returned by [ChildrenArray::get_children_with_txn].
 #[derive(Clone)]
-pub struct ChildrenArray(ArrayRef);
+pub struct ChildrenArray {
+  array: ArrayRef,
+  /// The fractional keys of the children. None to only use the order of the array.
+  orders: Option<MapRef>,
+}
 
 impl ChildrenArray {
   pub fn from_array(array: ArrayRef) -> Self {
-    Self(array)
+    Self {
+      array,
+      orders: None,
+    }
   }
 
+  pub fn with_orders(array: ArrayRef, orders: MapRef) -> Self {
+    Self {
+      array,
+      orders: Some(orders),
+    }
+  }
+
+  /// Returns the children ordered by their keys. The children without a key, which were added
+  /// by clients that don't support the keys, are placed after them in the order of the array.
   pub fn get_children_with_txn<T: ReadTxn>(&self, txn: &T) -> RepeatedViewIdentifier {
-    children_from_array_ref(txn, &self.0)
+    let children = children_from_array_ref(txn, &self.array);
+    let orders = match &self.orders {
+      None => return children,
+      Some(orders) => orders,
+    };
+
+    RepeatedViewIdentifier::new(
+      order_by_keys(children.into_inner(), |id| orders.get_with_txn(txn, id))
+        .into_iter()
+        .map(|(child, _)| child)
+        .collect(),
+    )
   }
 
   pub fn move_child_with_txn(&self, txn: &mut TransactionMut, from: u32, to: u32) {
-    if let Some(YrsValue::Any(value)) = self.0.get(txn, from) {
-      self.0.remove(txn, from);
-      self.0.insert(txn, to, value);
+    match &self.orders {
+      None => {
+        if let Some(YrsValue::Any(value)) = self.array.get(txn, from) {
+          self.array.remove(txn, from);
+          self.array.insert(txn, to, value);
+        }
+      },
+      Some(orders) => {
+        let children = self.ensure_order_keys(txn, orders);
+        if let Some((child, _)) = children.get(from as usize) {
+          let view_id = child.id.clone();
+          self.set_position_with_txn(txn, orders, children, &view_id, to as usize);
+        }
+      },
+    }
+  }
+
+  /// Moves the child after the `prev_view_id`, or to the first position if it's None. Only the
+  /// key of the child is updated.
+  pub fn move_child_after_with_txn(
+    &self,
+    txn: &mut TransactionMut,
+    view_id: &str,
+    prev_view_id: Option<&str>,
+  ) -> bool {
+    let children = self.get_children_with_txn(txn).into_inner();
+    let others = children
+      .iter()
+      .filter(|child| child.id != view_id)
+      .collect::<Vec<_>>();
+    if others.len() == children.len() {
+      return false;
+    }
+    let index = match prev_view_id {
+      None => 0,
+      Some(prev_view_id) => match others.iter().position(|child| child.id == prev_view_id) {
+        None => return false,
+        Some(pos) => pos + 1,
+      },
+    };
+
+    match &self.orders {
+      None => {
+        let from = children
+          .iter()
+          .position(|child| child.id == view_id)
+          .unwrap();
+        self.move_child_with_txn(txn, from as u32, index as u32);
+      },
+      Some(orders) => {
+        let children = self.ensure_order_keys(txn, orders);
+        self.set_position_with_txn(txn, orders, children, view_id, index);
+      },
     }
+    true
   }
 
+  /// Removes the child at the given index. All of its copies are removed from the array.
   pub fn remove_child_with_txn(
     &self,
     txn: &mut TransactionMut,
     index: u32,
   ) -> Option<ViewIdentifier> {
-    let value = self.0.get(txn, index)?;
-    self.0.remove(txn, index);
-    view_identifier_from_value(value)
+    let child = self
+      .get_children_with_txn(txn)
+      .into_inner()
+      .into_iter()
+      .nth(index as usize)?;
+    for pos in self.array_positions(txn, &child.id).into_iter().rev() {
+      self.array.remove(txn, pos);
+    }
+    if let Some(orders) = &self.orders {
+      orders.remove(txn, &child.id);
+    }
+    Some(child)
   }
 
   pub fn insert_child_with_txn(&self, txn: &mut TransactionMut, index: u32, child: ViewIdentifier) {
-    self.0.insert(txn, index, child);
+    self.add_children_with_txn(txn, vec![child], Some(index));
   }
 
   /// Add children to the views.
@@ -199,32 +316,168 @@ impl ChildrenArray {
     children: Vec<ViewIdentifier>,
     index: Option<u32>,
   ) {
-    let mut existing_children_ids: Vec<String> = self
-      .get_children_with_txn(txn)
-      .into_inner()
-      .into_iter()
-      .map(|child_view| child_view.id)
+    let existing_children = self.get_children_with_txn(txn).into_inner();
+    let mut existing_children_ids: Vec<String> = existing_children
+      .iter()
+      .map(|child_view| child_view.id.clone())
       .collect();
 
-    let values = children.into_iter().filter(|child| {
-      let contains_child = existing_children_ids.contains(&child.id);
-      if !contains_child {
-        existing_children_ids.push(child.id.clone());
+    let values = children
+      .into_iter()
+      .filter(|child| {
+        let contains_child = existing_children_ids.contains(&child.id);
+        if !contains_child {
+          existing_children_ids.push(child.id.clone());
+        }
+        !contains_child
+      })
+      .collect::<Vec<_>>();
+    if values.is_empty() {
+      return;
+    }
+
+    let index = index
+      .map(|index| index as usize)
+      .filter(|index| *index < existing_children.len());
+
+    // Once the siblings are ordered by keys, the new children need keys too.
+    if let Some(orders) = &self.orders {
+      let orders_by_key = existing_children
+        .iter()
+        .any(|child| orders.get_with_txn::<_, String>(txn, &child.id).is_some());
+      if orders_by_key {
+        let keys = self.ensure_order_keys(txn, orders);
+        let (prev, next) = match index {
+          None => (keys.last(), None),
+          Some(index) => (index.checked_sub(1).map(|i| &keys[i]), keys.get(index)),
+        };
+        let new_keys = n_keys_between(
+          prev.map(|(_, key)| key.as_str()),
+          next.map(|(_, key)| key.as_str()),
+          values.len(),
+        );
+        for (child, key) in values.iter().zip(new_keys) {
+          orders.insert(txn, child.id.as_str(), key);
+        }
       }
-      !contains_child
+    }
+
+    // Keep the array in the same order for the clients that don't support the keys.
+    let array_index = index.and_then(|index| {
+      self
+        .array_positions(txn, &existing_children[index].id)
+        .first()
+        .copied()
     });
+    match array_index {
+      Some(array_index) => self.array.insert_range(txn, array_index, values),
+      None => {
+        for value in values {
+          self.array.push_back(txn, value);
+        }
+      },
+    }
+  }
+
+  fn array_positions<T: ReadTxn>(&self, txn: &T, view_id: &str) -> Vec<u32> {
+    self
+      .array
+      .iter(txn)
+      .enumerate()
+      .filter(|(_, value)| {
+        view_identifier_from_value(value.clone())
+          .map(|child| child.id == view_id)
+          .unwrap_or(false)
+      })
+      .map(|(pos, _)| pos as u32)
+      .collect()
+  }
 
-    if let Some(index) = index {
-      if index < self.0.len(txn) {
-        self.0.insert_range(txn, index, values);
-        return;
+  /// Assigns keys to the children that don't have one and returns all the children with their
+  /// keys, in order.
+  fn ensure_order_keys(
+    &self,
+    txn: &mut TransactionMut,
+    orders: &MapRef,
+  ) -> Vec<(ViewIdentifier, String)> {
+    let children = self.get_children_with_txn(txn).into_inner();
+    let keys = initial_order_keys(
+      children
+        .iter()
+        .map(|child| orders.get_with_txn(txn, &child.id)),
+    );
+    let mut ordered = vec![];
+    for (child, (key, is_new)) in children.into_iter().zip(keys) {
+      if is_new {
+        orders.insert(txn, child.id.as_str(), key.clone());
       }
+      ordered.push((child, key));
     }
+    ordered
+  }
 
-    for value in values {
-      self.0.push_back(txn, value);
+  /// Updates the key of the child so it's placed at the `index` among the other children.
+  fn set_position_with_txn(
+    &self,
+    txn: &mut TransactionMut,
+    orders: &MapRef,
+    children: Vec<(ViewIdentifier, String)>,
+    view_id: &str,
+    index: usize,
+  ) {
+    let others = children
+      .into_iter()
+      .filter(|(child, _)| child.id != view_id)
+      .collect::<Vec<_>>();
+    let index = index.min(others.len());
+    let prev = index.checked_sub(1).map(|i| others[i].1.as_str());
+    let next = others.get(index).map(|(_, key)| key.as_str());
+    orders.insert(txn, view_id, key_between(prev, next));
+  }
+}
+
+/// Orders the children of the array by their keys. The children without a key, which were added
+/// by clients that don't support the keys, are placed after them in the order of the array.
+pub(crate) fn order_by_keys(
+  children: Vec<ViewIdentifier>,
+  key_of: impl Fn(&str) -> Option<String>,
+) -> Vec<(ViewIdentifier, Option<String>)> {
+  let mut keyed = vec![];
+  let mut unkeyed = vec![];
+  let mut seen = HashSet::new();
+  for child in children {
+    // Concurrent inserts may add the same child twice
+    if !seen.insert(child.id.clone()) {
+      continue;
+    }
+    match key_of(&child.id) {
+      Some(key) => keyed.push((child, Some(key))),
+      None => unkeyed.push((child, None)),
     }
   }
+  keyed.sort_by(|(a, a_key), (b, b_key)| a_key.cmp(b_key).then_with(|| a.id.cmp(&b.id)));
+  keyed.extend(unkeyed);
+  keyed
+}
+
+/// Returns the keys of the ordered children and whether each key is new. The keys of the
+/// children without a key are placed after the previous key, see
+/// [ChildrenArray::ensure_order_keys].
+pub(crate) fn initial_order_keys(
+  keys: impl IntoIterator<Item = Option<String>>,
+) -> Vec<(String, bool)> {
+  let mut last_key: Option<String> = None;
+  keys
+    .into_iter()
+    .map(|key| {
+      let key = match key {
+        Some(key) => (key, false),
+        None => (key_between(last_key.as_deref(), None), true),
+      };
+      last_key = Some(key.0.clone());
+      key
+    })
+    .collect()
 }
 
 pub fn children_from_array_ref<T: ReadTxn>(
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use crate::{timestamp, UserId};
+use crate::{timestamp, FolderChange, FolderChangeSender, UserId};
 use anyhow::bail;
 use collab::preclude::encoding::serde::{from_any, to_any};
 use collab::preclude::{deserialize_i64_from_numeric, ArrayRef, MapExt};
@@ -15,6 +15,7 @@ pub struct SectionMap {
   container: MapRef,
   #[allow(dead_code)]
   change_tx: Option<SectionChangeSender>,
+  folder_change_tx: Option<FolderChangeSender>,
   #[allow(dead_code)]
   subscription: Option<Subscription>,
 }
@@ -29,6 +30,7 @@ impl SectionMap {
     uid: &UserId,
     root: MapRef,
     change_tx: Option<SectionChangeSender>,
+    folder_change_tx: Option<FolderChangeSender>,
   ) -> Self {
     for section in predefined_sections() {
       root.get_or_init_map(txn, section.as_ref());
@@ -38,6 +40,7 @@ impl SectionMap {
       uid: uid.clone(),
       container: root,
       change_tx,
+      folder_change_tx,
       subscription: None,
     }
   }
@@ -49,6 +52,7 @@ impl SectionMap {
       container,
       section,
       change_tx: self.change_tx.clone(),
+      folder_change_tx: self.folder_change_tx.clone(),
     })
   }
 
@@ -101,6 +105,7 @@ impl AsRef<str> for Section {
 #[derive(Clone, Debug)]
 pub enum SectionChange {
   Trash(TrashSectionChange),
+  Favorite(FavoriteSectionChange),
 }
 
 pub type SectionChangeSender = broadcast::Sender<SectionChange>;
@@ -108,8 +113,24 @@ pub type SectionChangeReceiver = broadcast::Receiver<SectionChange>;
 
 #[derive(Clone, Debug)]
 pub enum TrashSectionChange {
-  TrashItemAdded { ids: Vec<String> },
-  TrashItemRemoved { ids: Vec<String> },
+  TrashItemAdded {
+    ids: Vec<String>,
+  },
+  TrashItemRemoved {
+    ids: Vec<String>,
+  },
+  /// The view was taken out of the trash and put back under `parent_view_id`.
+  TrashItemRestored {
+    id: String,
+    parent_view_id: String,
+  },
+}
+
+#[derive(Clone, Debug)]
+pub enum FavoriteSectionChange {
+  FavoriteItemAdded { ids: Vec<String> },
+  FavoriteItemRemoved { ids: Vec<String> },
+  FavoriteItemMoved { id: String, from: u32, to: u32 },
 }
 
 pub type SectionsByUid = HashMap<UserId, Vec<SectionItem>>;
@@ -119,6 +140,7 @@ pub struct SectionOperation<'a> {
   container: MapRef,
   section: Section,
   change_tx: Option<SectionChangeSender>,
+  folder_change_tx: Option<FolderChangeSender>,
 }
 
 impl<'a> SectionOperation<'a> {
@@ -210,18 +232,17 @@ impl<'a> SectionOperation<'a> {
         }
       }
 
-      if let Some(change_tx) = self.change_tx.as_ref() {
-        match self.section {
-          Section::Favorite => {},
-          Section::Recent => {},
-          Section::Trash => {
-            let _ = change_tx.send(SectionChange::Trash(TrashSectionChange::TrashItemRemoved {
-              ids: ids.into_iter().map(|id| id.as_ref().to_string()).collect(),
-            }));
-          },
-          Section::Custom(_) => {},
-          Section::Private => {},
-        }
+      let ids = ids.into_iter().map(|id| id.as_ref().to_string()).collect();
+      match self.section {
+        Section::Favorite => self.send_change(SectionChange::Favorite(
+          FavoriteSectionChange::FavoriteItemRemoved { ids },
+        )),
+        Section::Trash => {
+          self.send_change(SectionChange::Trash(TrashSectionChange::TrashItemRemoved {
+            ids,
+          }))
+        },
+        Section::Recent | Section::Custom(_) | Section::Private => {},
       }
     }
   }
@@ -229,18 +250,16 @@ impl<'a> SectionOperation<'a> {
   pub fn add_sections_item(&self, txn: &mut TransactionMut, items: Vec<SectionItem>) {
     let item_ids = items.iter().map(|item| item.id.clone()).collect::<Vec<_>>();
     self.add_sections_for_user_with_txn(txn, self.uid(), items);
-    if let Some(change_tx) = self.change_tx.as_ref() {
-      match self.section {
-        Section::Favorite => {},
-        Section::Recent => {},
-        Section::Trash => {
-          let _ = change_tx.send(SectionChange::Trash(TrashSectionChange::TrashItemAdded {
-            ids: item_ids,
-          }));
-        },
-        Section::Custom(_) => {},
-        Section::Private => {},
-      }
+    match self.section {
+      Section::Favorite => self.send_change(SectionChange::Favorite(
+        FavoriteSectionChange::FavoriteItemAdded { ids: item_ids },
+      )),
+      Section::Trash => {
+        self.send_change(SectionChange::Trash(TrashSectionChange::TrashItemAdded {
+          ids: item_ids,
+        }))
+      },
+      Section::Recent | Section::Custom(_) | Section::Private => {},
     }
   }
 
@@ -257,6 +276,119 @@ impl<'a> SectionOperation<'a> {
     }
   }
 
+  /// Move the item with the given id to the `to` position of the current user's items.
+  /// Returns false if the item is not found.
+  pub fn move_section_item_with_txn(&self, txn: &mut TransactionMut, id: &str, to: u32) -> bool {
+    let array = match self
+      .container()
+      .get_with_txn::<_, ArrayRef>(txn, self.uid().as_ref())
+    {
+      None => return false,
+      Some(array) => array,
+    };
+    let items = self.get_all_section_item(txn);
+    let from = match items.iter().position(|item| item.id == id) {
+      None => return false,
+      Some(pos) => pos as u32,
+    };
+    let to = to.min(items.len() as u32 - 1);
+    if from == to {
+      return true;
+    }
+
+    let item = items[from as usize].clone();
+    array.remove(txn, from);
+    array.insert(txn, to, item);
+
+    if self.section == Section::Favorite {
+      self.send_change(SectionChange::Favorite(
+        FavoriteSectionChange::FavoriteItemMoved {
+          id: id.to_string(),
+          from,
+          to,
+        },
+      ));
+    }
+    true
+  }
+
+  /// Moves the item with the given id to the front of the current user's items, or inserts it
+  /// at the front if it doesn't exist. Only the first `cap` items are kept.
+  pub fn move_or_insert_to_front_with_txn(&self, txn: &mut TransactionMut, id: &str, cap: usize) {
+    let array = self.container().get_or_init_array(txn, self.uid().as_ref());
+    // Concurrent inserts from other devices may leave duplicated items, remove all of them.
+    let positions = self
+      .get_all_section_item(txn)
+      .iter()
+      .enumerate()
+      .filter(|(_, item)| item.id == id)
+      .map(|(pos, _)| pos as u32)
+      .collect::<Vec<_>>();
+    for pos in positions.into_iter().rev() {
+      array.remove(txn, pos);
+    }
+    array.insert(txn, 0, SectionItem::new(id.to_string()));
+
+    let len = array.len(txn);
+    if len as usize > cap {
+      array.remove_range(txn, cap as u32, len - cap as u32);
+    }
+  }
+
+  /// Returns the trash items of the current user. Only meaningful for the [Section::Trash].
+  pub fn get_all_trash_items<T: ReadTxn>(&self, txn: &T) -> Vec<TrashSectionItem> {
+    match self
+      .container()
+      .get_with_txn::<_, ArrayRef>(txn, self.uid().as_ref())
+    {
+      None => vec![],
+      Some(array) => array
+        .iter(txn)
+        .flat_map(|value| TrashSectionItem::try_from(&value).ok())
+        .collect(),
+    }
+  }
+
+  pub fn add_trash_item_with_txn(&self, txn: &mut TransactionMut, item: TrashSectionItem) {
+    let id = item.id.clone();
+    let array = self.container().get_or_init_array(txn, self.uid().as_ref());
+    array.push_back(txn, item);
+    self.send_change(SectionChange::Trash(TrashSectionChange::TrashItemAdded {
+      ids: vec![id],
+    }));
+  }
+
+  /// Removes the trash item with the given id without sending a notification.
+  pub fn take_trash_item_with_txn(
+    &self,
+    txn: &mut TransactionMut,
+    id: &str,
+  ) -> Option<TrashSectionItem> {
+    let array = self
+      .container()
+      .get_with_txn::<_, ArrayRef>(txn, self.uid().as_ref())?;
+    let (pos, item) = array.iter(txn).enumerate().find_map(|(pos, value)| {
+      let item = TrashSectionItem::try_from(&value).ok()?;
+      (item.id == id).then_some((pos, item))
+    })?;
+    array.remove(txn, pos as u32);
+    Some(item)
+  }
+
+  /// Sends the change to the section channel and, as a [FolderChange], to the folder channel.
+  pub(crate) fn send_change(&self, change: SectionChange) {
+    if let Some(folder_change_tx) = self.folder_change_tx.as_ref() {
+      let folder_change = match &change {
+        SectionChange::Trash(change) => FolderChange::TrashChanged(change.clone()),
+        SectionChange::Favorite(change) => FolderChange::FavoritesChanged(change.clone()),
+      };
+      let _ = folder_change_tx.send(folder_change);
+    }
+    if let Some(change_tx) = self.change_tx.as_ref() {
+      let _ = change_tx.send(change);
+    }
+  }
+
   pub fn clear(&self, txn: &mut TransactionMut) {
     if let Some(array) = self
       .container()
@@ -330,3 +462,50 @@ impl TryFrom<&YrsValue> for SectionItem {
     }
   }
 }
+
+/// An item of the [Section::Trash].
+///
+/// It's stored in the same array as the [SectionItem]s of the trash, so the items added by
+/// [SectionOperation::add_sections_item] can be read as [TrashSectionItem] without a parent.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TrashSectionItem {
+  pub id: String,
+  /// The time the view was moved to the trash.
+  #[serde(deserialize_with = "deserialize_i64_from_numeric")]
+  pub timestamp: i64,
+  /// The parent of the view before it was moved to the trash. None if the view was not
+  /// detached from its parent.
+  #[serde(default)]
+  pub parent_view_id: Option<String>,
+  /// The sibling that preceded the view. None if the view was the first child.
+  #[serde(default)]
+  pub prev_view_id: Option<String>,
+}
+
+impl TrashSectionItem {
+  pub fn new(id: String, parent_view_id: String, prev_view_id: Option<String>) -> Self {
+    Self {
+      id,
+      timestamp: timestamp(),
+      parent_view_id: Some(parent_view_id),
+      prev_view_id,
+    }
+  }
+}
+
+impl From<TrashSectionItem> for Any {
+  fn from(value: TrashSectionItem) -> Self {
+    to_any(&value).unwrap()
+  }
+}
+
+impl TryFrom<&YrsValue> for TrashSectionItem {
+  type Error = anyhow::Error;
+
+  fn try_from(value: &YrsValue) -> Result<Self, Self::Error> {
+    match value {
+      YrsValue::Any(any) => Ok(from_any(any)?),
+      _ => bail!("Invalid trash section yrs value"),
+    }
+  }
+}
@@ -0,0 +1,196 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use collab::preclude::ReadTxn;
+
+use crate::hierarchy_builder::{ParentChildViews, SpacePermission};
+use crate::{timestamp, Folder, SpaceInfo, View, ViewLayout, ViewsMap};
+
+/// Parameters to create a space, which is a top level view of the workspace that groups other
+/// views. A [SpacePermission::PublicToAll] space is shared with all the members of the workspace,
+/// a [SpacePermission::Private] space is only visible to its creator and `members`.
+#[derive(Debug, Clone)]
+pub struct CreateSpaceParams {
+  pub space_id: String,
+  pub name: String,
+  pub permission: SpacePermission,
+  pub icon: Option<String>,
+  pub icon_color: Option<String>,
+  pub members: Vec<i64>,
+}
+
+impl CreateSpaceParams {
+  pub fn new(name: &str, permission: SpacePermission) -> Self {
+    Self {
+      space_id: uuid::Uuid::new_v4().to_string(),
+      name: name.to_string(),
+      permission,
+      icon: None,
+      icon_color: None,
+      members: vec![],
+    }
+  }
+}
+
+impl Folder {
+  /// Creates a space at the end of the workspace's children.
+  pub fn create_space(&mut self, params: CreateSpaceParams) -> Option<Arc<View>> {
+    let workspace_id = self.get_workspace_id()?;
+    let info = SpaceInfo {
+      is_space: true,
+      space_permission: params.permission,
+      space_created_at: timestamp(),
+      space_icon: params.icon,
+      space_icon_color: params.icon_color,
+      space_members: params.members,
+    };
+    let mut view = View::new(
+      params.space_id.clone(),
+      workspace_id,
+      params.name,
+      ViewLayout::Document,
+      Some(self.body.uid.as_i64()),
+    );
+    view.extra = Some(serde_json::to_string(&info).ok()?);
+    self.insert_view(view, None);
+    self.get_view(&params.space_id)
+  }
+
+  /// Returns all the spaces of the workspace in their sidebar order.
+  pub fn get_all_spaces(&self) -> Vec<Arc<View>> {
+    let txn = self.collab.transact();
+    let workspace_id = match self.body.get_workspace_id(&txn) {
+      None => return vec![],
+      Some(workspace_id) => workspace_id,
+    };
+    self
+      .body
+      .views
+      .get_views_belong_to(&txn, &workspace_id)
+      .into_iter()
+      .filter(|view| view.is_space())
+      .collect()
+  }
+
+  /// Returns the spaces that the current user can see.
+  pub fn get_my_visible_spaces(&self) -> Vec<Arc<View>> {
+    let uid = self.body.uid.as_i64();
+    self
+      .get_all_spaces()
+      .into_iter()
+      .filter(|space| space.is_visible_to(uid))
+      .collect()
+  }
+
+  /// Updates the [SpaceInfo] of the space. The other values stored in the view's extra are kept.
+  pub fn update_space_info<F>(&mut self, space_id: &str, f: F) -> Option<Arc<View>>
+  where
+    F: FnOnce(&mut SpaceInfo),
+  {
+    let space = self.get_view(space_id)?;
+    let mut info = space.space_info().filter(|info| info.is_space)?;
+    f(&mut info);
+
+    let info = match serde_json::to_value(&info).ok()? {
+      serde_json::Value::Object(info) => info,
+      _ => return None,
+    };
+    self.update_view_extra(space_id, |extra| {
+      // SpaceInfo skips the empty values, so remove the old ones before merging.
+      for key in ["space_icon", "space_icon_color", "space_members"] {
+        extra.remove(key);
+      }
+      extra.extend(info);
+    })
+  }
+
+  /// Moves the space after the `prev_space_id`, or to the first position if it's None.
+  pub fn move_space(&mut self, space_id: &str, prev_space_id: Option<String>) -> Option<Arc<View>> {
+    let workspace_id = self.get_workspace_id()?;
+    if !self.get_view(space_id)?.is_space() {
+      return None;
+    }
+    self.move_nested_view(space_id, &workspace_id, prev_space_id)
+  }
+
+  /// Moves the view, with all of its descendants, into the space. The view is placed after the
+  /// `prev_view_id`, or at the first position if it's None.
+  pub fn move_view_to_space(
+    &mut self,
+    view_id: &str,
+    space_id: &str,
+    prev_view_id: Option<String>,
+  ) -> Option<Arc<View>> {
+    if !self.get_view(space_id)?.is_space() {
+      return None;
+    }
+    self.move_nested_view(view_id, space_id, prev_view_id)
+  }
+
+  /// Returns the space that contains the view, or None if the view is not in a space.
+  pub fn get_space_of_view(&self, view_id: &str) -> Option<Arc<View>> {
+    let txn = self.collab.transact();
+    let workspace_id = self.body.get_workspace_id(&txn)?;
+    let mut view = self.body.views.get_view(&txn, view_id)?;
+    // The number of ancestors is bounded by the number of views, which avoids looping forever
+    // on a corrupted hierarchy.
+    for _ in 0..self.body.views.get_all_views(&txn).len() {
+      if view.is_space() {
+        return Some(view);
+      }
+      if view.parent_view_id == workspace_id || view.parent_view_id == view.id {
+        return None;
+      }
+      view = self.body.views.get_view(&txn, &view.parent_view_id)?;
+    }
+    None
+  }
+
+  /// Returns the hierarchy that the current user can see: the top level views of the
+  /// workspace with their descendants, excluding at every level the private spaces of other
+  /// users and everything below them.
+  pub fn get_my_visible_hierarchy(&self) -> Vec<ParentChildViews> {
+    let uid = self.body.uid.as_i64();
+    let txn = self.collab.transact();
+    let workspace_id = match self.body.get_workspace_id(&txn) {
+      None => return vec![],
+      Some(workspace_id) => workspace_id,
+    };
+    let mut visited = HashSet::from([workspace_id.clone()]);
+    self
+      .body
+      .views
+      .get_views_belong_to(&txn, &workspace_id)
+      .into_iter()
+      .filter_map(|view| {
+        build_hierarchy(
+          &self.body.views,
+          &txn,
+          uid,
+          view.as_ref().clone(),
+          &mut visited,
+        )
+      })
+      .collect()
+  }
+}
+
+/// Returns None if the view is not visible to the user or was already visited. A cyclic
+/// parent/child relation, which concurrent moves can create, would otherwise recurse forever.
+fn build_hierarchy<T: ReadTxn>(
+  views: &ViewsMap,
+  txn: &T,
+  uid: i64,
+  view: View,
+  visited: &mut HashSet<String>,
+) -> Option<ParentChildViews> {
+  if !view.is_visible_to(uid) || !visited.insert(view.id.clone()) {
+    return None;
+  }
+  let children = views
+    .get_views_belong_to(txn, &view.id)
+    .into_iter()
+    .filter_map(|child| build_hierarchy(views, txn, uid, child.as_ref().clone(), visited))
+    .collect();
+  Some(ParentChildViews { view, children })
+}
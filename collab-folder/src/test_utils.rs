@@ -0,0 +1,117 @@
+//! Generators of random but valid folders for the tests and the fuzzers. Only available with the
+//! `test_utils` feature.
+
+use collab::core::origin::CollabOrigin;
+use collab::preclude::Collab;
+use collab::test_utils::SeededRng;
+
+use crate::hierarchy_builder::{FlattedViews, NestedChildViewBuilder, ParentChildViews};
+use crate::{Folder, FolderData, RepeatedViewIdentifier, ViewIdentifier, ViewLayout, Workspace};
+
+const LAYOUTS: &[ViewLayout] = &[
+  ViewLayout::Document,
+  ViewLayout::Grid,
+  ViewLayout::Board,
+  ViewLayout::Calendar,
+  ViewLayout::Chat,
+];
+
+/// Generates a folder whose workspace has a tree of views of the given depth. Every view has
+/// between zero and the given number of children. The same seed always generates the same ids,
+/// names and layouts.
+///
+/// ```ignore
+/// let folder = FolderGenerator::new(7).with_depth(4).with_max_children(5).build(uid);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FolderGenerator {
+  seed: u64,
+  num_spaces: usize,
+  depth: usize,
+  max_children: usize,
+}
+
+impl FolderGenerator {
+  pub fn new(seed: u64) -> Self {
+    Self {
+      seed,
+      num_spaces: 2,
+      depth: 3,
+      max_children: 3,
+    }
+  }
+
+  /// The number of views at the first level of the workspace.
+  pub fn with_spaces(mut self, num_spaces: usize) -> Self {
+    self.num_spaces = num_spaces;
+    self
+  }
+
+  /// The number of levels of views, 1 means that all the views are at the first level.
+  pub fn with_depth(mut self, depth: usize) -> Self {
+    self.depth = depth.max(1);
+    self
+  }
+
+  pub fn with_max_children(mut self, max_children: usize) -> Self {
+    self.max_children = max_children;
+    self
+  }
+
+  pub fn generate_data(&self, uid: i64) -> FolderData {
+    let mut rng = SeededRng::new(self.seed);
+    let workspace_id = rng.gen_uuid();
+    let views = (0..self.num_spaces)
+      .map(|_| self.generate_view(&mut rng, uid, workspace_id.clone(), 1))
+      .collect::<Vec<_>>();
+
+    let mut workspace = Workspace::new(workspace_id, rng.gen_sentence(2), uid);
+    workspace.child_views = RepeatedViewIdentifier::new(
+      views
+        .iter()
+        .map(|view| ViewIdentifier {
+          id: view.view.id.clone(),
+        })
+        .collect(),
+    );
+    let mut data = FolderData::new(workspace);
+    data.views = FlattedViews::flatten_views(views);
+    data.current_view = data
+      .views
+      .first()
+      .map(|view| view.id.clone())
+      .unwrap_or_default();
+    data
+  }
+
+  /// Creates the generated folder in memory.
+  pub fn build(&self, uid: i64) -> Folder {
+    let data = self.generate_data(uid);
+    let collab = Collab::new_with_origin(CollabOrigin::Empty, &data.workspace.id, vec![], false);
+    Folder::create(uid, collab, None, data)
+  }
+
+  fn generate_view(
+    &self,
+    rng: &mut SeededRng,
+    uid: i64,
+    parent_view_id: String,
+    depth: usize,
+  ) -> ParentChildViews {
+    let view_id = rng.gen_uuid();
+    let num_children = if depth < self.depth {
+      rng.gen_range(0..self.max_children + 1)
+    } else {
+      0
+    };
+    let children = (0..num_children)
+      .map(|_| self.generate_view(rng, uid, view_id.clone(), depth + 1))
+      .collect();
+    NestedChildViewBuilder::new(uid, parent_view_id)
+      .with_view_id(&view_id)
+      .with_name(&rng.gen_sentence(2))
+      .with_layout(rng.choose(LAYOUTS).clone())
+      .with_children(children)
+      .build()
+  }
+}
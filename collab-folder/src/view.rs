@@ -7,6 +7,7 @@ use collab::preclude::{
   Any, Map, MapExt, MapPrelim, MapRef, ReadTxn, Subscription, TransactionMut, YrsValue,
 };
 use dashmap::DashMap;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_repr::*;
 use tracing::{instrument, trace};
@@ -19,15 +20,20 @@ use crate::{subscribe_view_change, ParentChildRelations, RepeatedViewIdentifier,
 
 pub(crate) const FOLDER_VIEW_ID: &str = "id";
 pub(crate) const FOLDER_VIEW_NAME: &str = "name";
-const VIEW_PARENT_ID: &str = "bid";
+pub(crate) const VIEW_PARENT_ID: &str = "bid";
 const VIEW_DESC: &str = "desc";
-const VIEW_LAYOUT: &str = "layout";
-const VIEW_CREATE_AT: &str = "created_at";
+pub(crate) const VIEW_LAYOUT: &str = "layout";
+pub(crate) const VIEW_CREATE_AT: &str = "created_at";
 const VIEW_CREATED_BY: &str = "created_by";
 const VIEW_ICON: &str = "icon";
-const VIEW_LAST_EDITED_TIME: &str = "last_edited_time";
-const VIEW_LAST_EDITED_BY: &str = "last_edited_by";
+const VIEW_COVER: &str = "cover";
+pub(crate) const VIEW_LAST_EDITED_TIME: &str = "last_edited_time";
+pub(crate) const VIEW_LAST_EDITED_BY: &str = "last_edited_by";
 const VIEW_EXTRA: &str = "extra";
+/// Known keys of the [View::extra].
+pub const VIEW_EXTRA_IS_PINNED: &str = "is_pinned";
+pub const VIEW_EXTRA_FONT: &str = "font";
+pub const VIEW_EXTRA_LINE_HEIGHT: &str = "line_height";
 // const VIEW_LAST_VIEWED_TIME: &str = "last_viewed_time";
 
 pub fn timestamp() -> i64 {
@@ -87,6 +93,24 @@ impl ViewsMap {
     self.remove_cache_view(parent_id);
   }
 
+  /// Moves the child after the `prev_view_id`, or to the first position if it's None.
+  pub fn move_child_after(
+    &self,
+    txn: &mut TransactionMut,
+    parent_id: &str,
+    view_id: &str,
+    prev_view_id: Option<&str>,
+  ) -> bool {
+    let is_moved = self.parent_children_relation.move_child_after_with_txn(
+      txn,
+      parent_id,
+      view_id,
+      prev_view_id,
+    );
+    self.remove_cache_view(parent_id);
+    is_moved
+  }
+
   /// Dissociate the relationship between parent_id and view_id.
   /// Why don't we use the move method to replace dissociate_parent_child and associate_parent_child?
   /// Because the views and workspaces are stored in two separate maps, we can't directly move a view from one map to another.
@@ -319,6 +343,7 @@ impl ViewsMap {
         .set_created_at(created_at)
         .set_children(view.children)
         .set_icon(view.icon)
+        .set_cover_if_not_none(view.cover)
         .set_created_by(Some(create_by))
         .set_last_edited_time(last_edited_time)
         .set_last_edited_by(Some(last_edited_by))
@@ -443,6 +468,8 @@ pub(crate) fn view_from_map_ref<T: ReadTxn>(
     .unwrap_or_default();
 
   let icon = get_icon_from_view_map(map_ref, txn);
+  let extra: Option<String> = map_ref.get_with_txn(txn, VIEW_EXTRA);
+  let cover = get_cover_from_view_map(map_ref, txn, extra.as_deref());
   let is_favorite = section_map
     .section_op(txn, Section::Favorite)
     .map(|op| op.contains_with_txn(txn, &id))
@@ -453,7 +480,6 @@ pub(crate) fn view_from_map_ref<T: ReadTxn>(
     .get_with_txn(txn, VIEW_LAST_EDITED_TIME)
     .unwrap_or(timestamp());
   let last_edited_by = map_ref.get_with_txn(txn, VIEW_LAST_EDITED_BY);
-  let extra = map_ref.get_with_txn(txn, VIEW_EXTRA);
 
   Some(View {
     id,
@@ -463,6 +489,7 @@ pub(crate) fn view_from_map_ref<T: ReadTxn>(
     created_at,
     layout,
     icon,
+    cover,
     is_favorite,
     created_by,
     last_edited_time,
@@ -476,6 +503,30 @@ pub fn get_icon_from_view_map<T: ReadTxn>(map_ref: &MapRef, txn: &T) -> Option<V
   serde_json::from_str::<ViewIcon>(&icon_str).ok()
 }
 
+/// Returns the cover of the view. Views created before the cover was stored on its own keep it
+/// in the `extra` as `{ "cover": { "type": "0", "value": "" } }`, which is used as a fallback.
+pub fn get_cover_from_view_map<T: ReadTxn>(
+  map_ref: &MapRef,
+  txn: &T,
+  extra: Option<&str>,
+) -> Option<ViewCover> {
+  match map_ref.get_with_txn::<_, String>(txn, VIEW_COVER) {
+    Some(cover_str) => serde_json::from_str::<ViewCover>(&cover_str).ok(),
+    None => {
+      let extra = serde_json::from_str::<serde_json::Value>(extra?).ok()?;
+      let cover = extra.get("cover")?;
+      let ty = match cover.get("type")? {
+        serde_json::Value::String(ty) => ty.parse::<i64>().ok()?,
+        ty => ty.as_i64()?,
+      };
+      Some(ViewCover {
+        ty: CoverType::try_from(ty).ok()?,
+        value: cover.get("value")?.as_str()?.to_string(),
+      })
+    },
+  }
+}
+
 pub struct ViewBuilder<'a, 'b> {
   view_id: &'a str,
   map_ref: MapRef,
@@ -585,6 +636,24 @@ impl<'a, 'b, 'c> ViewUpdate<'a, 'b, 'c> {
     self
   }
 
+  /// Set the cover of the view. Passing None removes the cover.
+  pub fn set_cover(self, cover: Option<ViewCover>) -> Self {
+    let cover_str = cover
+      .and_then(|cover| serde_json::to_string(&cover).ok())
+      .unwrap_or_default();
+    self.map_ref.insert(self.txn, VIEW_COVER, cover_str);
+
+    self
+  }
+
+  pub fn set_cover_if_not_none(self, cover: Option<ViewCover>) -> Self {
+    if cover.is_some() {
+      self.set_cover(cover)
+    } else {
+      self
+    }
+  }
+
   pub fn set_private(self, is_private: bool) -> Self {
     if let Some(private_section) = self.section_map.section_op(self.txn, Section::Private) {
       if is_private {
@@ -601,7 +670,10 @@ impl<'a, 'b, 'c> ViewUpdate<'a, 'b, 'c> {
   pub fn set_favorite(self, is_favorite: bool) -> Self {
     if let Some(fav_section) = self.section_map.section_op(self.txn, Section::Favorite) {
       if is_favorite {
-        fav_section.add_sections_item(self.txn, vec![SectionItem::new(self.view_id.to_string())]);
+        // Keep the position of the view if it's already a favorite.
+        if !fav_section.contains_with_txn(self.txn, self.view_id) {
+          fav_section.add_sections_item(self.txn, vec![SectionItem::new(self.view_id.to_string())]);
+        }
       } else {
         fav_section.delete_section_items_with_txn(self.txn, vec![self.view_id.to_string()]);
       }
@@ -675,12 +747,14 @@ pub struct View {
   pub is_favorite: bool,
   pub layout: ViewLayout,
   pub icon: Option<ViewIcon>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub cover: Option<ViewCover>,
   pub created_by: Option<i64>, // user id
   pub last_edited_time: i64,
   pub last_edited_by: Option<i64>, // user id
   /// this value used to store the extra data with JSON format
   /// for document:
-  /// - cover: { type: "", value: "" }
+  /// - cover: { type: "", value: "" }, replaced by [View::cover]
   ///   - type: "0" represents normal color,
   ///           "1" represents gradient color,
   ///           "2" represents built-in image,
@@ -689,6 +763,10 @@ pub struct View {
   ///           "5" represents unsplash image
   /// - line_height_layout: "small" or "normal" or "large"
   /// - font_layout: "small", or "normal", or "large"
+  /// - is_pinned, font and line_height, see [View::is_pinned], [View::font] and
+  ///   [View::line_height]
+  ///
+  /// Use [crate::Folder::set_extra_value] to set a single value without replacing the others.
   pub extra: Option<String>,
 }
 
@@ -709,6 +787,7 @@ impl View {
       is_favorite: false,
       layout,
       icon: None,
+      cover: None,
       created_by,
       last_edited_time: 0,
       last_edited_by: None,
@@ -726,22 +805,76 @@ impl View {
       is_favorite: false,
       layout,
       icon: None,
+      cover: None,
       created_by: uid,
       last_edited_time: 0,
       last_edited_by: None,
       extra: None,
     }
   }
+  /// Returns the value stored under the `key` of the extra.
+  pub fn extra_value(&self, key: &str) -> Option<serde_json::Value> {
+    let extra = self.extra.as_ref()?;
+    let mut extra = serde_json::from_str::<serde_json::Value>(extra).ok()?;
+    extra.as_object_mut()?.remove(key)
+  }
+
+  /// Returns the value stored under the `key` of the extra, or None if it's missing or has
+  /// another type.
+  pub fn extra_value_as<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+    serde_json::from_value(self.extra_value(key)?).ok()
+  }
+
+  pub fn is_pinned(&self) -> bool {
+    self
+      .extra_value_as::<bool>(VIEW_EXTRA_IS_PINNED)
+      .unwrap_or(false)
+  }
+
+  /// The font family of the page.
+  pub fn font(&self) -> Option<String> {
+    self.extra_value_as(VIEW_EXTRA_FONT)
+  }
+
+  /// The line height of the page, as a multiple of the font size.
+  pub fn line_height(&self) -> Option<f64> {
+    self.extra_value_as(VIEW_EXTRA_LINE_HEIGHT)
+  }
+
   pub fn space_info(&self) -> Option<SpaceInfo> {
     let extra = self.extra.as_ref()?;
     serde_json::from_str::<SpaceInfo>(extra).ok()
   }
+
+  pub fn is_space(&self) -> bool {
+    self.space_info().map(|info| info.is_space).unwrap_or(false)
+  }
+
+  /// Whether the user can see this view. Only the private spaces are restricted, they are
+  /// visible to their creator and members.
+  pub fn is_visible_to(&self, uid: i64) -> bool {
+    match self.space_info() {
+      Some(info) if info.is_space && info.space_permission == SpacePermission::Private => {
+        self.created_by == Some(uid) || info.space_members.contains(&uid)
+      },
+      _ => true,
+    }
+  }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct SpaceInfo {
   pub is_space: bool,
   pub space_permission: SpacePermission,
+  #[serde(default)]
+  pub space_created_at: i64,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub space_icon: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub space_icon_color: Option<String>,
+  /// The users, besides the creator, that can see a [SpacePermission::Private] space.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub space_members: Vec<i64>,
 }
 
 /// Represents a the index of a view.
@@ -806,6 +939,44 @@ pub struct ViewIcon {
   pub value: String,
 }
 
+#[derive(Eq, PartialEq, Debug, Hash, Clone, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum CoverType {
+  Color = 0,
+  Gradient = 1,
+  BuiltInImage = 2,
+  CustomImage = 3,
+  LocalImage = 4,
+  UnsplashImage = 5,
+}
+
+impl TryFrom<i64> for CoverType {
+  type Error = anyhow::Error;
+
+  fn try_from(value: i64) -> Result<Self, Self::Error> {
+    match value {
+      0 => Ok(CoverType::Color),
+      1 => Ok(CoverType::Gradient),
+      2 => Ok(CoverType::BuiltInImage),
+      3 => Ok(CoverType::CustomImage),
+      4 => Ok(CoverType::LocalImage),
+      5 => Ok(CoverType::UnsplashImage),
+      _ => bail!("Unknown cover type {}", value),
+    }
+  }
+}
+
+/// Represents the cover displayed in the header of a view.
+///
+/// # Fields
+/// - `ty`: The type of the cover, as specified by the `CoverType` enum.
+/// - `value`: A color, a gradient name, or the url or path of the image, depending on the type.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct ViewCover {
+  pub ty: CoverType,
+  pub value: String,
+}
+
 #[derive(Eq, PartialEq, Debug, Hash, Clone, Serialize_repr, Deserialize_repr)]
 #[repr(u8)]
 pub enum ViewLayout {
@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use collab::preclude::{
+  DeepObservable, EntryChange, Event, MapExt, MapRef, ReadTxn, Subscription, YrsValue,
+};
+use dashmap::DashMap;
+
+use crate::section::Section;
+use crate::view::{FOLDER_VIEW_ID, FOLDER_VIEW_NAME};
+use crate::{Folder, View};
+
+/// How well a view name matches a search query. The variants are ordered from the best match to
+/// the worst one.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum ViewSearchMatch {
+  /// The name is the query.
+  Exact,
+  /// The name starts with the query.
+  Prefix,
+  /// One of the words of the name starts with the query.
+  WordPrefix,
+  /// The name contains the query.
+  Substring,
+  /// The characters of the query appear in the name in the same order.
+  Fuzzy,
+}
+
+#[derive(Debug, Clone)]
+pub struct ViewSearchResult {
+  pub view: Arc<View>,
+  pub matched: ViewSearchMatch,
+}
+
+/// The lowercased name of every view, keyed by view id.
+///
+/// The index is built when the folder is opened and then kept up to date by observing the views
+/// map, so searching doesn't need to read the views from the document.
+pub struct ViewNameIndex {
+  names: Arc<DashMap<String, String>>,
+  #[allow(dead_code)]
+  subscription: Subscription,
+}
+
+impl ViewNameIndex {
+  pub(crate) fn new(views: &mut MapRef, all_views: &HashMap<String, Arc<View>>) -> Self {
+    let names = Arc::new(DashMap::from_iter(
+      all_views
+        .values()
+        .map(|view| (view.id.clone(), view.name.to_lowercase())),
+    ));
+    let subscription = subscribe_view_name_change(views, names.clone());
+    Self {
+      names,
+      subscription,
+    }
+  }
+
+  /// Returns the ids of the views whose name matches the query, with the best matches first.
+  /// Views with the same kind of match are ordered by the length of their name.
+  pub fn search(&self, query: &str) -> Vec<(String, ViewSearchMatch)> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+      return vec![];
+    }
+
+    let mut matches = self
+      .names
+      .iter()
+      .flat_map(|entry| {
+        let matched = match_name(entry.value(), &query)?;
+        Some((matched, entry.value().len(), entry.key().clone()))
+      })
+      .collect::<Vec<_>>();
+    matches.sort();
+    matches
+      .into_iter()
+      .map(|(matched, _, view_id)| (view_id, matched))
+      .collect()
+  }
+}
+
+fn match_name(name: &str, query: &str) -> Option<ViewSearchMatch> {
+  if name == query {
+    Some(ViewSearchMatch::Exact)
+  } else if name.starts_with(query) {
+    Some(ViewSearchMatch::Prefix)
+  } else if name
+    .split(|c: char| !c.is_alphanumeric())
+    .any(|word| word.starts_with(query))
+  {
+    Some(ViewSearchMatch::WordPrefix)
+  } else if name.contains(query) {
+    Some(ViewSearchMatch::Substring)
+  } else {
+    let mut chars = name.chars();
+    query
+      .chars()
+      .filter(|c| !c.is_whitespace())
+      .all(|c| chars.any(|n| n == c))
+      .then_some(ViewSearchMatch::Fuzzy)
+  }
+}
+
+fn subscribe_view_name_change(
+  views: &mut MapRef,
+  names: Arc<DashMap<String, String>>,
+) -> Subscription {
+  views.observe_deep(move |txn, events| {
+    for event in events.iter() {
+      if let Event::Map(event) = event {
+        if event.path().is_empty() {
+          // A view was inserted into or removed from the views map.
+          for (view_id, change) in event.keys(txn).iter() {
+            match change {
+              EntryChange::Inserted(YrsValue::YMap(map_ref))
+              | EntryChange::Updated(_, YrsValue::YMap(map_ref)) => {
+                index_view_name(&names, map_ref, txn);
+              },
+              EntryChange::Removed(_) => {
+                names.remove(view_id.as_ref());
+              },
+              _ => {},
+            }
+          }
+        } else if event.keys(txn).contains_key(FOLDER_VIEW_NAME) {
+          index_view_name(&names, event.target(), txn);
+        }
+      }
+    }
+  })
+}
+
+fn index_view_name<T: ReadTxn>(names: &DashMap<String, String>, map_ref: &MapRef, txn: &T) {
+  if let Some(view_id) = map_ref.get_with_txn::<_, String>(txn, FOLDER_VIEW_ID) {
+    let name: String = map_ref
+      .get_with_txn(txn, FOLDER_VIEW_NAME)
+      .unwrap_or_default();
+    names.insert(view_id, name.to_lowercase());
+  }
+}
+
+impl Folder {
+  /// Searches the views by name. The search is case insensitive and returns the best matches
+  /// first, see [ViewSearchMatch]. Views in the current user's trash are skipped.
+  pub fn search_views(&self, query: &str) -> Vec<ViewSearchResult> {
+    let txn = self.collab.transact();
+    let trash = self.body.section.section_op(&txn, Section::Trash);
+    self
+      .body
+      .view_name_index
+      .search(query)
+      .into_iter()
+      .filter(|(view_id, _)| {
+        trash
+          .as_ref()
+          .map(|trash| !trash.contains_with_txn(&txn, view_id))
+          .unwrap_or(true)
+      })
+      .flat_map(|(view_id, matched)| {
+        let view = self.body.views.get_view(&txn, &view_id)?;
+        Some(ViewSearchResult { view, matched })
+      })
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn match_name_test() {
+    assert_eq!(match_name("notes", "notes"), Some(ViewSearchMatch::Exact));
+    assert_eq!(match_name("notes", "no"), Some(ViewSearchMatch::Prefix));
+    assert_eq!(
+      match_name("meeting notes", "no"),
+      Some(ViewSearchMatch::WordPrefix)
+    );
+    assert_eq!(
+      match_name("keynotes", "note"),
+      Some(ViewSearchMatch::Substring)
+    );
+    assert_eq!(
+      match_name("meeting notes", "mtn"),
+      Some(ViewSearchMatch::Fuzzy)
+    );
+    assert_eq!(match_name("meeting notes", "xyz"), None);
+  }
+}
@@ -0,0 +1,200 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use collab::preclude::{
+  DeepObservable, EntryChange, Event, MapExt, MapRef, PathSegment, ReadTxn, Subscription,
+  TransactionMut,
+};
+use dashmap::DashMap;
+
+use crate::view::{
+  VIEW_CREATE_AT, VIEW_LAST_EDITED_BY, VIEW_LAST_EDITED_TIME, VIEW_LAYOUT, VIEW_PARENT_ID,
+};
+use crate::{Folder, ParentChildRelations, ViewLayout};
+
+/// The statistics of the views under a view, see [Folder::subtree_stats].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SubtreeStats {
+  /// The number of views under the view, the view itself is not counted.
+  pub descendant_count: usize,
+  /// The number of views under the view for each layout.
+  pub count_by_layout: HashMap<ViewLayout, usize>,
+  /// The latest time, in seconds, a view of the subtree was edited, the view itself included.
+  pub last_edited_time: i64,
+  /// The view edited at [SubtreeStats::last_edited_time] and the user who edited it.
+  pub last_edited_view_id: String,
+  pub last_edited_by: Option<i64>,
+}
+
+impl SubtreeStats {
+  fn add_child(&mut self, layout: ViewLayout, child: &SubtreeStats) {
+    self.descendant_count += 1 + child.descendant_count;
+    *self.count_by_layout.entry(layout).or_default() += 1;
+    for (layout, count) in &child.count_by_layout {
+      *self.count_by_layout.entry(layout.clone()).or_default() += count;
+    }
+    if child.last_edited_time > self.last_edited_time {
+      self.last_edited_time = child.last_edited_time;
+      self.last_edited_view_id = child.last_edited_view_id.clone();
+      self.last_edited_by = child.last_edited_by;
+    }
+  }
+}
+
+/// The [SubtreeStats] of the views, computed when they are first asked for.
+///
+/// The stats of a view are made of the stats of its children, so they're kept until the view or
+/// one of its descendants changes. The observers of the views and of their children remove the
+/// stats of the changed view and of its ancestors, locally or remotely, and the next lookup only
+/// computes the stats of the views on that path again.
+pub struct ViewStatsIndex {
+  stats: Arc<DashMap<String, SubtreeStats>>,
+  #[allow(dead_code)]
+  subscriptions: Vec<Subscription>,
+}
+
+impl ViewStatsIndex {
+  pub(crate) fn new(views: &mut MapRef, relations: &mut MapRef) -> Self {
+    let stats = Arc::new(DashMap::new());
+    let views_map = views.clone();
+    let subscriptions = vec![
+      subscribe_view_stats_change(views, views_map.clone(), stats.clone(), false),
+      subscribe_view_stats_change(relations, views_map, stats.clone(), true),
+    ];
+    Self {
+      stats,
+      subscriptions,
+    }
+  }
+
+  pub(crate) fn get<T: ReadTxn>(
+    &self,
+    txn: &T,
+    views: &MapRef,
+    relations: &ParentChildRelations,
+    view_id: &str,
+  ) -> Option<SubtreeStats> {
+    let (_, stats) = self.compute(txn, views, relations, view_id, &mut HashSet::new())?;
+    Some(stats)
+  }
+
+  /// Return the layout of the view and its stats. `visiting` holds the ancestors of the view, so
+  /// a view that is its own ancestor is skipped instead of looping forever.
+  fn compute<T: ReadTxn>(
+    &self,
+    txn: &T,
+    views: &MapRef,
+    relations: &ParentChildRelations,
+    view_id: &str,
+    visiting: &mut HashSet<String>,
+  ) -> Option<(ViewLayout, SubtreeStats)> {
+    let view: MapRef = views.get_with_txn(txn, view_id)?;
+    let layout = view
+      .get_with_txn::<_, i64>(txn, VIEW_LAYOUT)
+      .and_then(|layout| layout.try_into().ok())?;
+    if let Some(stats) = self.stats.get(view_id) {
+      return Some((layout, stats.clone()));
+    }
+    if !visiting.insert(view_id.to_string()) {
+      return None;
+    }
+
+    let created_at: i64 = view.get_with_txn(txn, VIEW_CREATE_AT).unwrap_or_default();
+    let mut stats = SubtreeStats {
+      last_edited_time: view
+        .get_with_txn(txn, VIEW_LAST_EDITED_TIME)
+        .unwrap_or(created_at),
+      last_edited_view_id: view_id.to_string(),
+      last_edited_by: view.get_with_txn(txn, VIEW_LAST_EDITED_BY),
+      ..Default::default()
+    };
+    let children = relations
+      .get_children_with_txn(txn, view_id)
+      .map(|children| children.get_children_with_txn(txn))
+      .unwrap_or_default();
+    for child in children.iter() {
+      if let Some((child_layout, child_stats)) =
+        self.compute(txn, views, relations, &child.id, visiting)
+      {
+        stats.add_child(child_layout, &child_stats);
+      }
+    }
+
+    visiting.remove(view_id);
+    self.stats.insert(view_id.to_string(), stats.clone());
+    Some((layout, stats))
+  }
+}
+
+/// Removes the stats of the views changed by the events of the views map, or of the relations map
+/// if `is_relation`, and of their ancestors.
+fn subscribe_view_stats_change(
+  container: &mut MapRef,
+  views: MapRef,
+  stats: Arc<DashMap<String, SubtreeStats>>,
+  is_relation: bool,
+) -> Subscription {
+  container.observe_deep(move |txn, events| {
+    for event in events.iter() {
+      let view_ids = match event.path().front() {
+        Some(PathSegment::Key(view_id)) => vec![view_id.to_string()],
+        Some(PathSegment::Index(_)) => continue,
+        None => match event {
+          Event::Map(event) => {
+            // The parent of a removed view can't be read anymore, so all the stats are removed.
+            if !is_relation
+              && event
+                .keys(txn)
+                .values()
+                .any(|change| matches!(change, EntryChange::Removed(_)))
+            {
+              stats.clear();
+              return;
+            }
+            event.keys(txn).keys().map(|key| key.to_string()).collect()
+          },
+          _ => continue,
+        },
+      };
+      for view_id in view_ids {
+        invalidate_ancestors(txn, &views, &stats, view_id);
+      }
+    }
+  })
+}
+
+fn invalidate_ancestors(
+  txn: &TransactionMut,
+  views: &MapRef,
+  stats: &DashMap<String, SubtreeStats>,
+  view_id: String,
+) {
+  let mut visited = HashSet::new();
+  let mut next = Some(view_id);
+  while let Some(view_id) = next {
+    if !visited.insert(view_id.clone()) {
+      break;
+    }
+    stats.remove(&view_id);
+    next = views
+      .get_with_txn::<_, MapRef>(txn, &view_id)
+      .and_then(|view| view.get_with_txn::<_, String>(txn, VIEW_PARENT_ID));
+  }
+}
+
+impl Folder {
+  /// Returns the number of views under the view, by layout, and when the last of them was edited,
+  /// for example to confirm the deletion of the view with the number of pages it contains. The
+  /// stats are kept between the calls and only computed again for the views that changed, see
+  /// [ViewStatsIndex]. Returns None if the view is not found.
+  pub fn subtree_stats(&self, view_id: &str) -> Option<SubtreeStats> {
+    let txn = self.collab.transact();
+    let views = &self.body.views;
+    self.body.view_stats_index.get(
+      &txn,
+      &views.container,
+      &views.parent_children_relation,
+      view_id,
+    )
+  }
+}
@@ -53,6 +53,7 @@ impl From<Workspace> for View {
       is_favorite: false,
       layout: ViewLayout::Document,
       icon: None,
+      cover: None,
       created_by: value.created_by,
       last_edited_time: value.last_edited_time,
       last_edited_by: value.last_edited_by,
@@ -1,5 +1,9 @@
 use assert_json_diff::assert_json_include;
-use collab_folder::{timestamp, UserId};
+use collab::core::collab::DataSource;
+use collab::core::origin::CollabOrigin;
+use collab::preclude::updates::decoder::Decode;
+use collab::preclude::{ReadTxn, StateVector, Update};
+use collab_folder::{timestamp, Folder, UserId};
 use serde_json::json;
 
 use crate::util::{create_folder_with_workspace, make_test_view};
@@ -303,3 +307,133 @@ fn create_orphan_child_views_test() {
         })
   );
 }
+
+#[test]
+fn move_view_after_prev_view_test() {
+  let uid = UserId::from(1);
+  let folder_test = create_folder_with_workspace(uid.clone(), "w1");
+  let mut folder = folder_test.folder;
+  for id in ["1", "2", "3"] {
+    folder.insert_view(make_test_view(id, "w1", vec![]), None);
+  }
+
+  folder.move_view("3", None).unwrap();
+  assert_eq!(child_ids(&folder, "w1"), vec!["3", "1", "2"]);
+  folder.move_view("3", Some("1".to_string())).unwrap();
+  assert_eq!(child_ids(&folder, "w1"), vec!["1", "3", "2"]);
+  folder.move_view("1", Some("2".to_string())).unwrap();
+  assert_eq!(child_ids(&folder, "w1"), vec!["3", "2", "1"]);
+  // The prev view must be a sibling
+  assert!(folder.move_view("1", Some("4".to_string())).is_none());
+
+  // New views are inserted according to the keys of their siblings
+  folder.insert_view(make_test_view("4", "w1", vec![]), Some(1));
+  folder.insert_view(make_test_view("5", "w1", vec![]), None);
+  assert_eq!(child_ids(&folder, "w1"), vec!["3", "4", "2", "1", "5"]);
+
+  folder
+    .body
+    .views
+    .remove_child(&mut folder.collab.transact_mut(), "w1", 1);
+  assert_eq!(child_ids(&folder, "w1"), vec!["3", "2", "1", "5"]);
+}
+
+#[test]
+fn concurrent_move_views_test() {
+  let uid = UserId::from(1);
+  let folder_test = create_folder_with_workspace(uid.clone(), "w1");
+  let mut local = folder_test.folder;
+  for id in ["1", "2", "3"] {
+    local.insert_view(make_test_view(id, "w1", vec![]), None);
+  }
+  let doc_state = local.encode_collab().unwrap().doc_state.to_vec();
+  let mut remote = Folder::from_collab_doc_state(
+    uid,
+    CollabOrigin::Empty,
+    DataSource::DocStateV1(doc_state),
+    "w1",
+    vec![],
+  )
+  .unwrap();
+
+  // Both devices move the same view while offline
+  local.move_view("3", None).unwrap();
+  remote.move_view("3", Some("1".to_string())).unwrap();
+  remote.move_view("1", Some("2".to_string())).unwrap();
+
+  let local_update = encode_update(&local);
+  let remote_update = encode_update(&remote);
+  local.apply_update(remote_update).unwrap();
+  remote.apply_update(local_update).unwrap();
+
+  let local_ids = child_ids(&local, "w1");
+  assert_eq!(local_ids, child_ids(&remote, "w1"));
+  assert_eq!(local_ids.len(), 3);
+  for id in ["1", "2", "3"] {
+    assert!(local_ids.contains(&id.to_string()));
+  }
+}
+
+#[test]
+fn duplicate_view_recursive_test() {
+  let uid = UserId::from(1);
+  let folder_test = create_folder_with_workspace(uid.clone(), "w1");
+  let mut folder = folder_test.folder;
+  for id in ["1", "2"] {
+    folder.insert_view(make_test_view(id, "w1", vec![]), None);
+  }
+  for id in ["1_1", "1_2", "1_3"] {
+    let mut view = make_test_view(id, "1", vec![]);
+    view.name = format!("name {}", id);
+    folder.insert_view(view, None);
+  }
+  folder.insert_view(make_test_view("1_2_1", "1_2", vec![]), None);
+  folder.move_view("1_3", None).unwrap();
+
+  let id_mapping = folder.duplicate_view_recursive("1").unwrap();
+  assert_eq!(id_mapping.len(), 5);
+  let copy_id = id_mapping["1"].clone();
+
+  // The copy is placed right after the original view
+  assert_eq!(
+    child_ids(&folder, "w1"),
+    vec!["1".to_string(), copy_id.clone(), "2".to_string()]
+  );
+  let expected = ["1_3", "1_1", "1_2"]
+    .iter()
+    .map(|id| id_mapping[*id].clone())
+    .collect::<Vec<_>>();
+  assert_eq!(child_ids(&folder, &copy_id), expected);
+  assert_eq!(
+    child_ids(&folder, &id_mapping["1_2"]),
+    vec![id_mapping["1_2_1"].clone()]
+  );
+  assert_eq!(
+    folder.get_view(&id_mapping["1_1"]).unwrap().name,
+    "name 1_1"
+  );
+
+  // The original views are not changed
+  assert_eq!(
+    child_ids(&folder, "1"),
+    vec!["1_3".to_string(), "1_1".to_string(), "1_2".to_string()]
+  );
+  assert!(folder.duplicate_view_recursive("w1").is_none());
+  assert!(folder.duplicate_view_recursive("unknown").is_none());
+}
+
+fn child_ids(folder: &Folder, parent_id: &str) -> Vec<String> {
+  folder
+    .get_views_belong_to(parent_id)
+    .iter()
+    .map(|view| view.id.clone())
+    .collect()
+}
+
+fn encode_update(folder: &Folder) -> Update {
+  let update = folder
+    .collab
+    .transact()
+    .encode_state_as_update_v1(&StateVector::default());
+  Update::decode_v1(&update).unwrap()
+}
@@ -3,7 +3,7 @@ use crate::util::{
   unzip_history_folder_db,
 };
 use assert_json_diff::assert_json_include;
-use collab_folder::{FolderData, UserId};
+use collab_folder::{FavoriteSectionChange, FolderData, SectionChange, UserId};
 use serde_json::json;
 use uuid::Uuid;
 
@@ -76,6 +76,74 @@ fn add_favorite_view_and_then_remove_test() {
   assert!(!views[0].is_favorite);
 }
 
+#[test]
+fn reorder_favorite_views_test() {
+  let uid = UserId::from(1);
+  let folder_test = create_folder_with_workspace(uid.clone(), "w1");
+  let workspace_id = folder_test.get_workspace_id().unwrap();
+
+  let mut folder = folder_test.folder;
+  for id in ["1", "2", "3"] {
+    folder.insert_view(make_test_view(id, workspace_id.as_str(), vec![]), None);
+  }
+  folder.add_favorite_view_ids(vec!["1".to_string(), "2".to_string(), "3".to_string()]);
+  // Adding an existing favorite doesn't duplicate it
+  folder.add_favorite_view_ids(vec!["1".to_string()]);
+
+  assert!(folder.move_favorite_view_id("3", 0));
+  let ids = folder
+    .get_my_favorite_sections()
+    .into_iter()
+    .map(|item| item.id)
+    .collect::<Vec<_>>();
+  assert_eq!(ids, vec!["3", "1", "2"]);
+
+  // An out of range position moves the view to the end
+  assert!(folder.move_favorite_view_id("3", 100));
+  let ids = folder
+    .get_my_favorite_sections()
+    .into_iter()
+    .map(|item| item.id)
+    .collect::<Vec<_>>();
+  assert_eq!(ids, vec!["1", "2", "3"]);
+
+  assert!(!folder.move_favorite_view_id("4", 0));
+}
+
+#[test]
+fn favorite_change_notification_test() {
+  let uid = UserId::from(1);
+  let mut folder_test = create_folder_with_workspace(uid.clone(), "w1");
+  let workspace_id = folder_test.get_workspace_id().unwrap();
+  let mut section_rx = folder_test.section_rx.take().unwrap();
+
+  let mut folder = folder_test.folder;
+  for id in ["1", "2"] {
+    folder.insert_view(make_test_view(id, workspace_id.as_str(), vec![]), None);
+  }
+  folder.add_favorite_view_ids(vec!["1".to_string(), "2".to_string()]);
+  folder.move_favorite_view_id("2", 0);
+  folder.delete_favorite_view_ids(vec!["1".to_string()]);
+
+  let mut changes = vec![];
+  while let Ok(SectionChange::Favorite(change)) = section_rx.try_recv() {
+    changes.push(change);
+  }
+  assert_eq!(changes.len(), 4);
+  assert!(matches!(
+    &changes[0],
+    FavoriteSectionChange::FavoriteItemAdded { ids } if ids == &vec!["1".to_string()]
+  ));
+  assert!(matches!(
+    &changes[2],
+    FavoriteSectionChange::FavoriteItemMoved { id, from: 1, to: 0 } if id == "2"
+  ));
+  assert!(matches!(
+    &changes[3],
+    FavoriteSectionChange::FavoriteItemRemoved { ids } if ids == &vec!["1".to_string()]
+  ));
+}
+
 #[test]
 fn create_multiple_user_favorite_test() {
   let uid_1 = UserId::from(1);
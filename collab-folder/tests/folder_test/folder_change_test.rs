@@ -0,0 +1,124 @@
+use collab::core::collab::DataSource;
+use collab::core::origin::CollabOrigin;
+use collab::preclude::updates::decoder::Decode;
+use collab::preclude::{ReadTxn, Update};
+use collab_folder::{FavoriteSectionChange, Folder, FolderChange, TrashSectionChange, UserId};
+
+use crate::util::{create_folder_with_workspace, make_test_view};
+
+#[test]
+fn view_moved_and_renamed_change_test() {
+  let uid = UserId::from(1);
+  let mut folder_test = create_folder_with_workspace(uid.clone(), "w1");
+  let mut change_rx = folder_test.folder_change_rx.take().unwrap();
+  let mut folder = folder_test.folder;
+  for id in ["1", "2", "3"] {
+    folder.insert_view(make_test_view(id, "w1", vec![]), None);
+  }
+
+  folder.move_view("1", Some("2".to_string())).unwrap();
+  match change_rx.try_recv().unwrap() {
+    FolderChange::ViewMoved { view_id, from, to } => {
+      assert_eq!(view_id, "1");
+      assert_eq!(from, "w1");
+      assert_eq!(to, "w1");
+    },
+    change => panic!("unexpected change: {:?}", change),
+  }
+
+  folder.move_nested_view("3", "2", None).unwrap();
+  match change_rx.try_recv().unwrap() {
+    FolderChange::ViewMoved { view_id, from, to } => {
+      assert_eq!(view_id, "3");
+      assert_eq!(from, "w1");
+      assert_eq!(to, "2");
+    },
+    change => panic!("unexpected change: {:?}", change),
+  }
+
+  folder.update_view("2", |update| update.set_name("new name").done());
+  match change_rx.try_recv().unwrap() {
+    FolderChange::ViewRenamed { view_id, name } => {
+      assert_eq!(view_id, "2");
+      assert_eq!(name, "new name");
+    },
+    change => panic!("unexpected change: {:?}", change),
+  }
+
+  // Updating other properties, or setting the same name, is not a rename.
+  folder.update_view("2", |update| update.set_desc("desc").done());
+  folder.update_view("2", |update| update.set_name("new name").done());
+  assert!(change_rx.try_recv().is_err());
+}
+
+#[test]
+fn remote_view_moved_and_renamed_change_test() {
+  let uid = UserId::from(1);
+  let mut folder_test = create_folder_with_workspace(uid.clone(), "w1");
+  let mut change_rx = folder_test.folder_change_rx.take().unwrap();
+  let mut folder = folder_test.folder;
+  for id in ["1", "2"] {
+    folder.insert_view(make_test_view(id, "w1", vec![]), None);
+  }
+
+  let doc_state = folder.encode_collab().unwrap().doc_state.to_vec();
+  let mut remote = Folder::from_collab_doc_state(
+    uid,
+    CollabOrigin::Empty,
+    DataSource::DocStateV1(doc_state),
+    "w1",
+    vec![],
+  )
+  .unwrap();
+  let sv = folder.transact().state_vector();
+  remote.move_view("2", None).unwrap();
+  remote.update_view("1", |update| update.set_name("remote name").done());
+  remote.move_nested_view("1", "2", None).unwrap();
+  let update = remote.transact().encode_state_as_update_v1(&sv);
+  folder
+    .apply_update(Update::decode_v1(&update).unwrap())
+    .unwrap();
+
+  let mut changes = vec![];
+  while let Ok(change) = change_rx.try_recv() {
+    changes.push(change);
+  }
+  assert_eq!(changes.len(), 3, "{:?}", changes);
+  assert!(changes.iter().any(|change| matches!(
+    change,
+    FolderChange::ViewMoved { view_id, from, to } if view_id == "2" && from == "w1" && to == "w1"
+  )));
+  assert!(changes.iter().any(|change| matches!(
+    change,
+    FolderChange::ViewMoved { view_id, from, to } if view_id == "1" && from == "w1" && to == "2"
+  )));
+  assert!(changes.iter().any(|change| matches!(
+    change,
+    FolderChange::ViewRenamed { view_id, name } if view_id == "1" && name == "remote name"
+  )));
+}
+
+#[test]
+fn section_change_test() {
+  let uid = UserId::from(1);
+  let mut folder_test = create_folder_with_workspace(uid.clone(), "w1");
+  let mut change_rx = folder_test.folder_change_rx.take().unwrap();
+  let mut folder = folder_test.folder;
+  folder.insert_view(make_test_view("1", "w1", vec![]), None);
+
+  folder.add_favorite_view_ids(vec!["1".to_string()]);
+  match change_rx.try_recv().unwrap() {
+    FolderChange::FavoritesChanged(FavoriteSectionChange::FavoriteItemAdded { ids }) => {
+      assert_eq!(ids, vec!["1".to_string()]);
+    },
+    change => panic!("unexpected change: {:?}", change),
+  }
+
+  assert!(folder.move_view_to_trash("1"));
+  match change_rx.try_recv().unwrap() {
+    FolderChange::TrashChanged(TrashSectionChange::TrashItemAdded { ids }) => {
+      assert_eq!(ids, vec!["1".to_string()]);
+    },
+    change => panic!("unexpected change: {:?}", change),
+  }
+}
@@ -0,0 +1,32 @@
+use collab::test_utils::assert_json_eq_ignoring;
+use collab_folder::test_utils::FolderGenerator;
+
+#[test]
+fn generate_folder_test() {
+  let generator = FolderGenerator::new(7)
+    .with_spaces(3)
+    .with_depth(3)
+    .with_max_children(2);
+  let data = generator.generate_data(1);
+  let folder = generator.build(1);
+
+  let workspace_id = folder.get_workspace_id().unwrap();
+  assert_eq!(workspace_id, data.workspace.id);
+  let first_level_views = folder.get_views_belong_to(&workspace_id);
+  assert_eq!(first_level_views.len(), 3);
+  for view in &data.views {
+    assert!(folder.get_view(&view.id).is_some());
+    assert!(view.children.len() <= 2);
+  }
+}
+
+#[test]
+fn generate_same_folder_with_same_seed_test() {
+  let folder_1 = FolderGenerator::new(7).build(1);
+  let folder_2 = FolderGenerator::new(7).build(1);
+  assert_json_eq_ignoring(
+    &folder_1.to_json_value(),
+    &folder_2.to_json_value(),
+    &["created_at", "last_edited_time"],
+  );
+}
@@ -1,10 +1,17 @@
 mod child_views_test;
 mod custom_section;
 mod favorite_test;
+mod folder_change_test;
+mod generator_test;
 mod load_disk;
+mod member_test;
 mod recent_views_test;
+mod repair_test;
+mod search_test;
 mod serde_test;
+mod space_test;
 mod trash_test;
 mod util;
+mod view_stats_test;
 mod view_test;
 mod workspace_test;
@@ -0,0 +1,119 @@
+use collab::core::collab::DataSource;
+use collab::core::origin::CollabOrigin;
+use collab::preclude::updates::decoder::Decode;
+use collab::preclude::{Collab, ReadTxn, Update};
+use collab_folder::{Folder, FolderNotify, MemberChange, MemberRole, UserId, WorkspaceMember};
+
+use crate::util::create_folder_with_workspace;
+
+#[test]
+fn add_update_and_remove_member_test() {
+  let uid = UserId::from(1);
+  let folder_test = create_folder_with_workspace(uid.clone(), "w1");
+  let mut folder = folder_test.folder;
+
+  let mut owner = WorkspaceMember::new(1, MemberRole::Owner, "owner".to_string());
+  owner.joined_at = 1;
+  let mut guest = WorkspaceMember::new(2, MemberRole::Guest, "guest".to_string());
+  guest.joined_at = 2;
+  guest.avatar = Some("avatar_url".to_string());
+  folder.add_member(guest.clone());
+  folder.add_member(owner.clone());
+
+  assert_eq!(folder.get_all_members(), vec![owner.clone(), guest.clone()]);
+  assert_eq!(folder.get_member(2), Some(guest));
+  assert_eq!(folder.get_my_role(), Some(MemberRole::Owner));
+  assert!(!folder.get_member_role(2).unwrap().can_edit());
+
+  let member = folder
+    .update_member(2, |member| {
+      member.role = MemberRole::Member;
+      member.name = "member".to_string();
+    })
+    .unwrap();
+  assert_eq!(member.role, MemberRole::Member);
+  assert_eq!(folder.get_member(2).unwrap().name, "member");
+  assert!(folder.get_member_role(2).unwrap().can_edit());
+  assert!(folder.update_member(3, |_| {}).is_none());
+
+  assert_eq!(folder.remove_member(2).unwrap().uid, 2);
+  assert!(folder.get_member(2).is_none());
+  assert!(folder.remove_member(2).is_none());
+  assert_eq!(folder.get_all_members(), vec![owner]);
+}
+
+#[test]
+fn member_change_test() {
+  let uid = UserId::from(1);
+  let mut folder_test = create_folder_with_workspace(uid.clone(), "w1");
+  let mut member_rx = folder_test.member_rx.take().unwrap();
+  let mut folder = folder_test.folder;
+
+  let member = WorkspaceMember::new(2, MemberRole::Member, "member".to_string());
+  folder.add_member(member.clone());
+  assert_eq!(
+    member_rx.try_recv().unwrap(),
+    MemberChange::DidAddMember {
+      member: member.clone()
+    }
+  );
+
+  let member = folder
+    .update_member(2, |member| member.role = MemberRole::Guest)
+    .unwrap();
+  assert_eq!(
+    member_rx.try_recv().unwrap(),
+    MemberChange::DidUpdateMember { member }
+  );
+
+  folder.remove_member(2);
+  assert_eq!(
+    member_rx.try_recv().unwrap(),
+    MemberChange::DidRemoveMember { uid: 2 }
+  );
+}
+
+#[test]
+fn sync_members_between_devices_test() {
+  let uid = UserId::from(1);
+  let folder_test = create_folder_with_workspace(uid.clone(), "w1");
+  let mut folder = folder_test.folder;
+  let member = WorkspaceMember::new(2, MemberRole::Guest, "guest".to_string());
+  folder.add_member(member.clone());
+
+  let (member_tx, mut member_rx) = tokio::sync::broadcast::channel(10);
+  let (view_tx, _view_rx) = tokio::sync::broadcast::channel(10);
+  let (section_tx, _section_rx) = tokio::sync::broadcast::channel(10);
+  let (folder_change_tx, _folder_change_rx) = tokio::sync::broadcast::channel(10);
+  let notifier = FolderNotify {
+    view_change_tx: view_tx,
+    section_change_tx: section_tx,
+    member_change_tx: member_tx,
+    folder_change_tx,
+  };
+  let doc_state = folder.encode_collab().unwrap().doc_state;
+  let collab = Collab::new_with_source(
+    CollabOrigin::Empty,
+    "w1",
+    DataSource::DocStateV1(doc_state.to_vec()),
+    vec![],
+    false,
+  )
+  .unwrap();
+  let mut remote = Folder::open(2, collab, Some(notifier)).unwrap();
+  assert_eq!(remote.get_all_members(), vec![member]);
+  assert_eq!(remote.get_my_role(), Some(MemberRole::Guest));
+
+  let sv = remote.transact().state_vector();
+  folder.update_member(2, |member| member.role = MemberRole::Member);
+  let update = folder.transact().encode_state_as_update_v1(&sv);
+  remote
+    .apply_update(Update::decode_v1(&update).unwrap())
+    .unwrap();
+
+  assert_eq!(remote.get_my_role(), Some(MemberRole::Member));
+  match member_rx.try_recv().unwrap() {
+    MemberChange::DidUpdateMember { member } => assert_eq!(member.role, MemberRole::Member),
+    change => panic!("unexpected change: {:?}", change),
+  }
+}
@@ -1,5 +1,5 @@
 use assert_json_diff::assert_json_include;
-use collab_folder::{timestamp, FolderData, Section, UserId};
+use collab_folder::{timestamp, FolderData, Section, UserId, MAX_RECENT_VIEWS};
 use serde_json::json;
 
 use crate::util::{create_folder_with_data, create_folder_with_workspace, make_test_view};
@@ -193,3 +193,52 @@ fn delete_recent_test() {
   let recent = folder.get_my_recent_sections();
   assert_eq!(recent.len(), 0);
 }
+
+#[test]
+fn track_view_opened_test() {
+  let uid = UserId::from(1);
+  let folder_test = create_folder_with_workspace(uid.clone(), "w1");
+  let mut folder = folder_test.folder;
+  for id in ["v1", "v2", "v3"] {
+    folder.insert_view(make_test_view(id, "w1", vec![]), None);
+  }
+
+  folder.track_view_opened("v1");
+  folder.track_view_opened("v2");
+  folder.track_view_opened("v3");
+  folder.track_view_opened("v1");
+  // Views that don't exist are ignored
+  folder.track_view_opened("v4");
+
+  let ids = folder
+    .get_my_recent_views()
+    .into_iter()
+    .map(|view| view.id)
+    .collect::<Vec<_>>();
+  assert_eq!(ids, vec!["v1", "v3", "v2"]);
+
+  folder.delete_views(vec!["v3"]);
+  let ids = folder
+    .get_my_recent_views()
+    .into_iter()
+    .map(|view| view.id)
+    .collect::<Vec<_>>();
+  assert_eq!(ids, vec!["v1", "v2"]);
+}
+
+#[test]
+fn recent_views_are_capped_test() {
+  let uid = UserId::from(1);
+  let folder_test = create_folder_with_workspace(uid.clone(), "w1");
+  let mut folder = folder_test.folder;
+  for i in 0..MAX_RECENT_VIEWS + 5 {
+    let id = format!("v{}", i);
+    folder.insert_view(make_test_view(&id, "w1", vec![]), None);
+    folder.track_view_opened(&id);
+  }
+
+  let recent = folder.get_my_recent_views();
+  assert_eq!(recent.len(), MAX_RECENT_VIEWS);
+  assert_eq!(recent[0].id, format!("v{}", MAX_RECENT_VIEWS + 4));
+  assert_eq!(recent[MAX_RECENT_VIEWS - 1].id, "v5");
+}
@@ -0,0 +1,96 @@
+use collab::core::collab::DataSource;
+use collab::core::origin::CollabOrigin;
+use collab::preclude::updates::decoder::Decode;
+use collab::preclude::{ReadTxn, StateVector, Update};
+use collab_folder::{recovery_space_id, Folder, HierarchyIssue, UserId, RECOVERY_SPACE_NAME};
+
+use crate::util::{create_folder_with_workspace, make_test_view};
+
+#[test]
+fn repair_missing_parent_and_dangling_trash_test() {
+  let uid = UserId::from(1);
+  let folder_test = create_folder_with_workspace(uid.clone(), "w1");
+  let mut folder = folder_test.folder;
+  folder.insert_view(make_test_view("1", "w1", vec![]), None);
+  folder.insert_view(make_test_view("2", "unknown", vec![]), None);
+  folder.insert_view(make_test_view("2_1", "2", vec![]), None);
+  folder.insert_view(make_test_view("3", "w1", vec![]), None);
+  folder.add_trash_view_ids(vec!["3".to_string()]);
+  folder.delete_views(vec!["3"]);
+  assert!(!folder.validate_hierarchy().is_ok());
+
+  let report = folder.repair_hierarchy();
+  assert_eq!(
+    report.issues,
+    vec![
+      HierarchyIssue::MissingParent {
+        view_id: "2".to_string(),
+        parent_view_id: "unknown".to_string(),
+      },
+      HierarchyIssue::DanglingTrashItem {
+        view_id: "3".to_string(),
+      },
+    ]
+  );
+
+  let space_id = recovery_space_id("w1");
+  let space = folder.get_view(&space_id).unwrap();
+  assert!(space.is_space());
+  assert_eq!(space.name, RECOVERY_SPACE_NAME);
+  assert_eq!(child_ids(&folder, &space_id), vec!["2".to_string()]);
+  assert_eq!(child_ids(&folder, "2"), vec!["2_1".to_string()]);
+  assert!(folder.get_my_trash_sections().is_empty());
+  assert!(folder.validate_hierarchy().is_ok());
+}
+
+#[test]
+fn repair_cycle_created_by_concurrent_moves_test() {
+  let uid = UserId::from(1);
+  let folder_test = create_folder_with_workspace(uid.clone(), "w1");
+  let mut local = folder_test.folder;
+  for id in ["1", "2"] {
+    local.insert_view(make_test_view(id, "w1", vec![]), None);
+  }
+  let doc_state = local.encode_collab().unwrap().doc_state.to_vec();
+  let mut remote = Folder::from_collab_doc_state(
+    uid,
+    CollabOrigin::Empty,
+    DataSource::DocStateV1(doc_state),
+    "w1",
+    vec![],
+  )
+  .unwrap();
+
+  // Each device moves one view into the other one while offline
+  local.move_nested_view("1", "2", None).unwrap();
+  remote.move_nested_view("2", "1", None).unwrap();
+  let update = remote
+    .collab
+    .transact()
+    .encode_state_as_update_v1(&StateVector::default());
+  local
+    .apply_update(Update::decode_v1(&update).unwrap())
+    .unwrap();
+  assert!(child_ids(&local, "w1").is_empty());
+
+  let report = local.repair_hierarchy();
+  assert_eq!(
+    report.issues,
+    vec![HierarchyIssue::Cycle {
+      view_ids: vec!["1".to_string(), "2".to_string()],
+    }]
+  );
+  let space_id = recovery_space_id("w1");
+  assert_eq!(child_ids(&local, &space_id), vec!["1".to_string()]);
+  assert_eq!(child_ids(&local, "1"), vec!["2".to_string()]);
+  assert!(local.validate_hierarchy().is_ok());
+  assert!(local.repair_hierarchy().is_ok());
+}
+
+fn child_ids(folder: &Folder, parent_id: &str) -> Vec<String> {
+  folder
+    .get_views_belong_to(parent_id)
+    .iter()
+    .map(|view| view.id.clone())
+    .collect()
+}
@@ -0,0 +1,87 @@
+use collab::core::collab::DataSource;
+use collab::core::origin::CollabOrigin;
+use collab::preclude::updates::decoder::Decode;
+use collab::preclude::{ReadTxn, Update};
+use collab_folder::{Folder, UserId, ViewSearchMatch};
+
+use crate::util::{create_folder_with_workspace, make_test_view};
+
+fn insert_named_view(folder: &mut Folder, view_id: &str, name: &str) {
+  let mut view = make_test_view(view_id, "w1", vec![]);
+  view.name = name.to_string();
+  folder.insert_view(view, None);
+}
+
+fn search_ids(folder: &Folder, query: &str) -> Vec<String> {
+  folder
+    .search_views(query)
+    .into_iter()
+    .map(|result| result.view.id.clone())
+    .collect()
+}
+
+#[test]
+fn search_views_by_name_test() {
+  let uid = UserId::from(1);
+  let folder_test = create_folder_with_workspace(uid.clone(), "w1");
+  let mut folder = folder_test.folder;
+  insert_named_view(&mut folder, "1", "Meeting notes");
+  insert_named_view(&mut folder, "2", "Notes");
+  insert_named_view(&mut folder, "3", "Keynotes");
+  insert_named_view(&mut folder, "4", "Roadmap");
+
+  let results = folder.search_views("notes");
+  let matches = results
+    .iter()
+    .map(|result| (result.view.id.as_str(), result.matched))
+    .collect::<Vec<_>>();
+  assert_eq!(
+    matches,
+    vec![
+      ("2", ViewSearchMatch::Exact),
+      ("1", ViewSearchMatch::WordPrefix),
+      ("3", ViewSearchMatch::Substring),
+    ]
+  );
+  assert_eq!(search_ids(&folder, "RD"), vec!["4"]);
+  assert_eq!(search_ids(&folder, "mtng"), vec!["1"]);
+  assert!(search_ids(&folder, "  ").is_empty());
+}
+
+#[test]
+fn search_index_follows_folder_changes_test() {
+  let uid = UserId::from(1);
+  let folder_test = create_folder_with_workspace(uid.clone(), "w1");
+  let mut folder = folder_test.folder;
+  insert_named_view(&mut folder, "1", "Draft");
+  insert_named_view(&mut folder, "2", "Plan");
+  assert_eq!(search_ids(&folder, "draft"), vec!["1"]);
+
+  folder.update_view("1", |update| update.set_name("Journal").done());
+  assert!(search_ids(&folder, "draft").is_empty());
+  assert_eq!(search_ids(&folder, "jour"), vec!["1"]);
+
+  folder.delete_views(vec!["1"]);
+  assert!(search_ids(&folder, "jour").is_empty());
+
+  folder.add_trash_view_ids(vec!["2".to_string()]);
+  assert!(search_ids(&folder, "plan").is_empty());
+
+  // The views inserted on another device are indexed when the update is applied.
+  let doc_state = folder.encode_collab().unwrap().doc_state.to_vec();
+  let mut remote = Folder::from_collab_doc_state(
+    uid,
+    CollabOrigin::Empty,
+    DataSource::DocStateV1(doc_state),
+    "w1",
+    vec![],
+  )
+  .unwrap();
+  let sv = folder.transact().state_vector();
+  insert_named_view(&mut remote, "3", "Remote notes");
+  let update = remote.transact().encode_state_as_update_v1(&sv);
+  folder
+    .apply_update(Update::decode_v1(&update).unwrap())
+    .unwrap();
+  assert_eq!(search_ids(&folder, "remote"), vec!["3"]);
+}
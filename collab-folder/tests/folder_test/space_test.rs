@@ -0,0 +1,161 @@
+use collab_folder::hierarchy_builder::SpacePermission;
+use collab_folder::{CreateSpaceParams, SpaceInfo, UserId};
+
+use crate::util::{create_folder_with_workspace, make_test_view};
+
+#[test]
+fn create_space_test() {
+  let uid = UserId::from(1);
+  let folder_test = create_folder_with_workspace(uid.clone(), "w1");
+  let mut folder = folder_test.folder;
+  folder.insert_view(make_test_view("v1", "w1", vec![]), None);
+
+  let mut params = CreateSpaceParams::new("General", SpacePermission::PublicToAll);
+  params.icon = Some("interface_essential/home-3".to_string());
+  params.icon_color = Some("0xFFA34AFD".to_string());
+  let general = folder.create_space(params).unwrap();
+  let private = folder
+    .create_space(CreateSpaceParams::new("Private", SpacePermission::Private))
+    .unwrap();
+
+  let spaces = folder.get_all_spaces();
+  assert_eq!(spaces.len(), 2);
+  assert_eq!(spaces[0].id, general.id);
+  assert_eq!(spaces[1].id, private.id);
+
+  let info = spaces[0].space_info().unwrap();
+  assert!(info.is_space);
+  assert_eq!(info.space_permission, SpacePermission::PublicToAll);
+  assert_eq!(
+    info.space_icon.as_deref(),
+    Some("interface_essential/home-3")
+  );
+  assert_eq!(info.space_icon_color.as_deref(), Some("0xFFA34AFD"));
+  assert!(!folder.get_view("v1").unwrap().is_space());
+
+  // Reorder the spaces
+  folder.move_space(&private.id, None).unwrap();
+  let spaces = folder.get_all_spaces();
+  assert_eq!(spaces[0].id, private.id);
+  assert_eq!(spaces[1].id, general.id);
+  // A view that is not a space can't be moved as a space
+  assert!(folder.move_space("v1", None).is_none());
+}
+
+#[test]
+fn move_view_tree_between_spaces_test() {
+  let uid = UserId::from(1);
+  let folder_test = create_folder_with_workspace(uid.clone(), "w1");
+  let mut folder = folder_test.folder;
+  let shared = folder
+    .create_space(CreateSpaceParams::new(
+      "Shared",
+      SpacePermission::PublicToAll,
+    ))
+    .unwrap();
+  let private = folder
+    .create_space(CreateSpaceParams::new("Private", SpacePermission::Private))
+    .unwrap();
+  folder.insert_view(make_test_view("v1", &shared.id, vec![]), None);
+  folder.insert_view(make_test_view("v1_1", "v1", vec![]), None);
+  assert_eq!(folder.get_space_of_view("v1_1").unwrap().id, shared.id);
+
+  folder.move_view_to_space("v1", &private.id, None).unwrap();
+  assert_eq!(folder.get_space_of_view("v1_1").unwrap().id, private.id);
+  assert!(folder.get_views_belong_to(&shared.id).is_empty());
+  assert_eq!(folder.get_views_belong_to("v1")[0].id, "v1_1");
+
+  // The target must be a space
+  assert!(folder.move_view_to_space("v1", "v1_1", None).is_none());
+}
+
+#[test]
+fn private_space_visibility_test() {
+  let uid = UserId::from(1);
+  let folder_test = create_folder_with_workspace(uid.clone(), "w1");
+  let mut folder = folder_test.folder;
+  let shared = folder
+    .create_space(CreateSpaceParams::new(
+      "Shared",
+      SpacePermission::PublicToAll,
+    ))
+    .unwrap();
+  let private = folder
+    .create_space(CreateSpaceParams::new("Private", SpacePermission::Private))
+    .unwrap();
+  folder.insert_view(make_test_view("v1", &private.id, vec![]), None);
+
+  assert!(private.is_visible_to(1));
+  assert!(!private.is_visible_to(2));
+  assert!(shared.is_visible_to(2));
+
+  let hierarchy = folder.get_my_visible_hierarchy();
+  assert_eq!(hierarchy.len(), 2);
+  assert_eq!(hierarchy[1].view.id, private.id);
+  assert_eq!(hierarchy[1].children[0].view.id, "v1");
+
+  // Share the private space with another member
+  let private = folder
+    .update_space_info(&private.id, |info| info.space_members.push(2))
+    .unwrap();
+  assert!(private.is_visible_to(2));
+  assert!(!private.is_visible_to(3));
+  assert_eq!(private.space_info().unwrap().space_members, vec![2]);
+}
+
+#[test]
+fn visible_hierarchy_with_cycle_test() {
+  let uid = UserId::from(1);
+  let folder_test = create_folder_with_workspace(uid.clone(), "w1");
+  let mut folder = folder_test.folder;
+  let space = folder
+    .create_space(CreateSpaceParams::new(
+      "Shared",
+      SpacePermission::PublicToAll,
+    ))
+    .unwrap();
+  folder.insert_view(make_test_view("v1", &space.id, vec![]), None);
+  // v1_1 lists its parent as one of its children.
+  folder.insert_view(make_test_view("v1_1", "v1", vec![space.id.clone()]), None);
+
+  let hierarchy = folder.get_my_visible_hierarchy();
+  assert_eq!(hierarchy.len(), 1);
+  let v1 = &hierarchy[0].children[0];
+  assert_eq!(v1.view.id, "v1");
+  assert_eq!(v1.children[0].view.id, "v1_1");
+  assert!(v1.children[0].children.is_empty());
+}
+
+#[test]
+fn nested_private_space_visibility_test() {
+  let uid = UserId::from(1);
+  let folder_test = create_folder_with_workspace(uid.clone(), "w1");
+  let mut folder = folder_test.folder;
+  let shared = folder
+    .create_space(CreateSpaceParams::new(
+      "Shared",
+      SpacePermission::PublicToAll,
+    ))
+    .unwrap();
+  folder.insert_view(make_test_view("v1", &shared.id, vec![]), None);
+  // A private space of another user, nested in the shared space.
+  let info = SpaceInfo {
+    is_space: true,
+    space_permission: SpacePermission::Private,
+    space_created_at: 0,
+    space_icon: None,
+    space_icon_color: None,
+    space_members: vec![],
+  };
+  let mut private = make_test_view("private", "v1", vec![]);
+  private.created_by = Some(2);
+  private.extra = Some(serde_json::to_string(&info).unwrap());
+  folder.insert_view(private, None);
+  folder.insert_view(make_test_view("v2", "private", vec![]), None);
+
+  let hierarchy = folder.get_my_visible_hierarchy();
+  assert_eq!(hierarchy.len(), 1);
+  let v1 = &hierarchy[0].children[0];
+  assert_eq!(v1.view.id, "v1");
+  assert!(v1.children.is_empty());
+}
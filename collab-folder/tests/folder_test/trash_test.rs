@@ -68,7 +68,9 @@ async fn create_trash_callback_test() {
         assert_eq!(ids, vec!["1", "2"]);
       },
       TrashSectionChange::TrashItemRemoved { .. } => {},
+      TrashSectionChange::TrashItemRestored { .. } => {},
     },
+    SectionChange::Favorite(_) => {},
   }))
   .await;
 }
@@ -91,7 +93,9 @@ async fn delete_trash_view_ids_callback_test() {
       TrashSectionChange::TrashItemRemoved { ids } => {
         assert_eq!(ids, vec!["1", "2"]);
       },
+      TrashSectionChange::TrashItemRestored { .. } => {},
     },
+    SectionChange::Favorite(_) => {},
   }))
   .await;
 }
@@ -107,3 +111,93 @@ async fn timeout<F: Future>(f: F) {
     .await
     .unwrap();
 }
+
+#[test]
+fn move_view_to_trash_and_restore_test() {
+  let uid = UserId::from(1);
+  let folder_test = create_folder_with_workspace(uid.clone(), "w1");
+  let mut folder = folder_test.folder;
+  folder.insert_view(make_test_view("v1", "w1", vec![]), None);
+  folder.insert_view(make_test_view("v2", "w1", vec![]), None);
+  folder.insert_view(make_test_view("v3", "w1", vec![]), None);
+
+  assert!(folder.move_view_to_trash("v2"));
+  assert!(!folder.move_view_to_trash("v2"));
+  let children = folder.get_views_belong_to("w1");
+  assert_eq!(children.len(), 2);
+  assert!(children.iter().all(|view| view.id != "v2"));
+
+  let trash = folder.get_my_trash_items();
+  assert_eq!(trash.len(), 1);
+  assert_eq!(trash[0].id, "v2");
+  assert_eq!(trash[0].parent_view_id.as_deref(), Some("w1"));
+  assert_eq!(trash[0].prev_view_id.as_deref(), Some("v1"));
+  // The trash items are also readable as section items
+  assert_eq!(folder.get_my_trash_sections()[0].id, "v2");
+
+  let view = folder.restore_view_from_trash("v2").unwrap();
+  assert_eq!(view.parent_view_id, "w1");
+  assert!(folder.get_my_trash_items().is_empty());
+  let ids = folder
+    .get_views_belong_to("w1")
+    .iter()
+    .map(|view| view.id.clone())
+    .collect::<Vec<_>>();
+  assert_eq!(ids, vec!["v1", "v2", "v3"]);
+}
+
+#[test]
+fn restore_view_when_parent_is_trashed_test() {
+  let uid = UserId::from(1);
+  let folder_test = create_folder_with_workspace(uid.clone(), "w1");
+  let mut folder = folder_test.folder;
+  folder.insert_view(make_test_view("v1", "w1", vec![]), None);
+  folder.insert_view(make_test_view("v1_1", "v1", vec![]), None);
+
+  assert!(folder.move_view_to_trash("v1_1"));
+  assert!(folder.move_view_to_trash("v1"));
+
+  // The original parent is in the trash, so the view is put back under the workspace
+  let view = folder.restore_view_from_trash("v1_1").unwrap();
+  assert_eq!(view.parent_view_id, "w1");
+  assert_eq!(folder.get_views_belong_to("w1")[0].id, "v1_1");
+  assert!(folder.restore_view_from_trash("v1_1").is_none());
+}
+
+#[test]
+fn expired_trash_items_test() {
+  let uid = UserId::from(1);
+  let folder_test = create_folder_with_workspace(uid.clone(), "w1");
+  let mut folder = folder_test.folder;
+  folder.insert_view(make_test_view("v1", "w1", vec![]), None);
+  folder.move_view_to_trash("v1");
+
+  assert!(folder
+    .expired_trash_items(Duration::from_secs(60 * 60))
+    .is_empty());
+  let expired = folder.expired_trash_items(Duration::from_secs(0));
+  assert_eq!(expired.len(), 1);
+  assert_eq!(expired[0].id, "v1");
+}
+
+#[tokio::test]
+async fn restore_trash_callback_test() {
+  let uid = UserId::from(1);
+  let mut folder_test = create_folder_with_workspace(uid.clone(), "w1");
+  let mut section_rx = folder_test.section_rx.take().unwrap();
+  folder_test.insert_view(make_test_view("v1", "w1", vec![]), None);
+  folder_test.move_view_to_trash("v1");
+  folder_test.restore_view_from_trash("v1");
+
+  match section_rx.recv().await.unwrap() {
+    SectionChange::Trash(TrashSectionChange::TrashItemAdded { ids }) => assert_eq!(ids, vec!["v1"]),
+    change => panic!("unexpected change: {:?}", change),
+  }
+  match section_rx.recv().await.unwrap() {
+    SectionChange::Trash(TrashSectionChange::TrashItemRestored { id, parent_view_id }) => {
+      assert_eq!(id, "v1");
+      assert_eq!(parent_view_id, "w1");
+    },
+    change => panic!("unexpected change: {:?}", change),
+  }
+}
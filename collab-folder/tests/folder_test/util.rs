@@ -33,6 +33,12 @@ pub struct FolderTest {
 
   #[allow(dead_code)]
   pub(crate) section_rx: Option<SectionChangeReceiver>,
+
+  #[allow(dead_code)]
+  pub(crate) member_rx: Option<MemberChangeReceiver>,
+
+  #[allow(dead_code)]
+  pub(crate) folder_change_rx: Option<FolderChangeReceiver>,
 }
 
 pub fn create_folder(uid: UserId, workspace_id: &str) -> FolderTest {
@@ -69,9 +75,13 @@ pub fn create_folder_with_data(
 
   let (view_tx, view_rx) = tokio::sync::broadcast::channel(100);
   let (section_tx, section_rx) = tokio::sync::broadcast::channel(100);
+  let (member_tx, member_rx) = tokio::sync::broadcast::channel(100);
+  let (folder_change_tx, folder_change_rx) = tokio::sync::broadcast::channel(100);
   let context = FolderNotify {
     view_change_tx: view_tx,
     section_change_tx: section_tx,
+    member_change_tx: member_tx,
+    folder_change_tx,
   };
   let folder = Folder::create(uid, collab, Some(context), folder_data);
   FolderTest {
@@ -80,6 +90,8 @@ pub fn create_folder_with_data(
     cleaner,
     view_rx,
     section_rx: Some(section_rx),
+    member_rx: Some(member_rx),
+    folder_change_rx: Some(folder_change_rx),
   }
 }
 
@@ -113,9 +125,13 @@ pub fn open_folder_with_db(
 
   let (view_tx, view_rx) = tokio::sync::broadcast::channel(100);
   let (section_tx, section_rx) = tokio::sync::broadcast::channel(100);
+  let (member_tx, member_rx) = tokio::sync::broadcast::channel(100);
+  let (folder_change_tx, folder_change_rx) = tokio::sync::broadcast::channel(100);
   let context = FolderNotify {
     view_change_tx: view_tx,
     section_change_tx: section_tx,
+    member_change_tx: member_tx,
+    folder_change_tx,
   };
   let folder = Folder::open(uid, collab, Some(context)).unwrap();
   FolderTest {
@@ -124,6 +140,8 @@ pub fn open_folder_with_db(
     cleaner,
     view_rx,
     section_rx: Some(section_rx),
+    member_rx: Some(member_rx),
+    folder_change_rx: Some(folder_change_rx),
   }
 }
 
@@ -145,6 +163,7 @@ pub fn make_test_view(view_id: &str, parent_view_id: &str, belongings: Vec<Strin
     is_favorite: false,
     layout: ViewLayout::Document,
     icon: None,
+    cover: None,
     created_by: None,
     last_edited_time: 0,
     last_edited_by: None,
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use collab::core::collab::DataSource;
+use collab::core::origin::CollabOrigin;
+use collab::preclude::updates::decoder::Decode;
+use collab::preclude::{ReadTxn, Update};
+use collab_folder::{Folder, UserId, ViewLayout};
+
+use crate::util::{create_folder_with_workspace, make_test_view};
+
+fn insert_view(folder: &mut Folder, view_id: &str, parent_id: &str, layout: ViewLayout) {
+  let mut view = make_test_view(view_id, parent_id, vec![]);
+  view.layout = layout;
+  folder.insert_view(view, None);
+}
+
+fn set_last_edited_time(folder: &mut Folder, view_id: &str, time: i64) {
+  folder.update_view(view_id, |update| update.set_last_edited_time(time).done());
+}
+
+/// 1
+/// ├── 1_1 (grid)
+/// │   └── 1_1_1
+/// └── 1_2 (board)
+fn create_nested_views(folder: &mut Folder) {
+  insert_view(folder, "1", "w1", ViewLayout::Document);
+  insert_view(folder, "1_1", "1", ViewLayout::Grid);
+  insert_view(folder, "1_1_1", "1_1", ViewLayout::Document);
+  insert_view(folder, "1_2", "1", ViewLayout::Board);
+  // Inserting a child edits its parent, so the times are set once all the views are inserted.
+  for (view_id, time) in [("1", 100), ("1_1", 300), ("1_1_1", 200), ("1_2", 150)] {
+    set_last_edited_time(folder, view_id, time);
+  }
+}
+
+#[test]
+fn subtree_stats_test() {
+  let uid = UserId::from(1);
+  let folder_test = create_folder_with_workspace(uid, "w1");
+  let mut folder = folder_test.folder;
+  create_nested_views(&mut folder);
+
+  let stats = folder.subtree_stats("1").unwrap();
+  assert_eq!(stats.descendant_count, 3);
+  assert_eq!(
+    stats.count_by_layout,
+    HashMap::from([
+      (ViewLayout::Grid, 1),
+      (ViewLayout::Document, 1),
+      (ViewLayout::Board, 1),
+    ])
+  );
+  assert_eq!(stats.last_edited_time, 300);
+  assert_eq!(stats.last_edited_view_id, "1_1");
+
+  let stats = folder.subtree_stats("1_1_1").unwrap();
+  assert_eq!(stats.descendant_count, 0);
+  assert!(stats.count_by_layout.is_empty());
+  assert_eq!(stats.last_edited_time, 200);
+
+  assert_eq!(folder.subtree_stats("w1").unwrap().descendant_count, 4);
+
+  // The stats kept for the ancestors are updated when a view is edited.
+  set_last_edited_time(&mut folder, "1_2", 400);
+  let stats = folder.subtree_stats("1").unwrap();
+  assert_eq!(stats.last_edited_time, 400);
+  assert_eq!(stats.last_edited_view_id, "1_2");
+  assert!(folder.subtree_stats("unknown").is_none());
+}
+
+#[test]
+fn subtree_stats_follow_folder_changes_test() {
+  let uid = UserId::from(1);
+  let folder_test = create_folder_with_workspace(uid.clone(), "w1");
+  let mut folder = folder_test.folder;
+  create_nested_views(&mut folder);
+  assert_eq!(folder.subtree_stats("1").unwrap().descendant_count, 3);
+
+  // Editing a view updates the stats of its ancestors.
+  folder.update_view("1_1_1", |update| update.set_name("Notes").done());
+  let stats = folder.subtree_stats("1").unwrap();
+  assert!(stats.last_edited_time > 300);
+  assert_eq!(stats.last_edited_view_id, "1_1_1");
+  assert_eq!(stats.last_edited_by, Some(1));
+
+  insert_view(&mut folder, "1_2_1", "1_2", ViewLayout::Calendar);
+  let stats = folder.subtree_stats("1").unwrap();
+  assert_eq!(stats.descendant_count, 4);
+  assert_eq!(stats.count_by_layout.get(&ViewLayout::Calendar), Some(&1));
+
+  folder.move_nested_view("1_1", "w1", None);
+  let stats = folder.subtree_stats("1").unwrap();
+  assert_eq!(stats.descendant_count, 2);
+  assert_eq!(stats.count_by_layout.get(&ViewLayout::Grid), None);
+  assert_eq!(folder.subtree_stats("1_1").unwrap().descendant_count, 1);
+
+  folder.delete_views(vec!["1_2_1"]);
+  assert_eq!(folder.subtree_stats("1").unwrap().descendant_count, 1);
+
+  // The views inserted on another device are counted once the update is applied.
+  let doc_state = folder.encode_collab().unwrap().doc_state.to_vec();
+  let mut remote = Folder::from_collab_doc_state(
+    uid,
+    CollabOrigin::Empty,
+    DataSource::DocStateV1(doc_state),
+    "w1",
+    vec![],
+  )
+  .unwrap();
+  let sv = folder.transact().state_vector();
+  insert_view(&mut remote, "1_3", "1", ViewLayout::Chat);
+  let update = remote.transact().encode_state_as_update_v1(&sv);
+  folder
+    .apply_update(Update::decode_v1(&update).unwrap())
+    .unwrap();
+  let stats = folder.subtree_stats("1").unwrap();
+  assert_eq!(stats.descendant_count, 2);
+  assert_eq!(stats.count_by_layout.get(&ViewLayout::Chat), Some(&1));
+}
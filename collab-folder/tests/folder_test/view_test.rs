@@ -1,7 +1,10 @@
 use crate::util::{create_folder_with_workspace, make_test_view, setup_log};
 use collab::core::collab::IndexContent;
-use collab_folder::folder_diff::FolderViewChange;
-use collab_folder::{timestamp, IconType, UserId, ViewIcon, ViewIndexContent};
+use collab_folder::folder_diff::{diff_folder_snapshots, FolderViewChange, FolderViewSummary};
+use collab_folder::{
+  timestamp, CoverType, IconType, UserId, ViewCover, ViewIcon, ViewIndexContent, ViewLayout,
+  VIEW_EXTRA_FONT, VIEW_EXTRA_IS_PINNED, VIEW_EXTRA_LINE_HEIGHT,
+};
 
 #[test]
 fn create_view_test() {
@@ -160,6 +163,63 @@ fn update_view_icon_test() {
   assert!(r_view.last_edited_time >= time);
 }
 
+#[test]
+fn update_view_cover_test() {
+  let uid = UserId::from(1);
+  let folder_test = create_folder_with_workspace(uid.clone(), "w1");
+  let mut folder = folder_test.folder;
+
+  let mut view = make_test_view("v1", "w1", vec![]);
+  view.cover = Some(ViewCover {
+    ty: CoverType::Color,
+    value: "#FF0000".to_string(),
+  });
+  folder.insert_view(view, None);
+  assert_eq!(
+    folder.get_view("v1").unwrap().cover.as_ref().unwrap().value,
+    "#FF0000"
+  );
+
+  let cover = ViewCover {
+    ty: CoverType::UnsplashImage,
+    value: "https://images.unsplash.com/photo".to_string(),
+  };
+  let r_view = folder
+    .update_view("v1", |update| update.set_cover(Some(cover.clone())).done())
+    .unwrap();
+  assert_eq!(r_view.cover, Some(cover));
+
+  let r_view = folder
+    .update_view("v1", |update| update.set_cover(None).done())
+    .unwrap();
+  assert_eq!(r_view.cover, None);
+}
+
+#[test]
+fn read_cover_from_view_extra_test() {
+  let uid = UserId::from(1);
+  let folder_test = create_folder_with_workspace(uid.clone(), "w1");
+  let mut folder = folder_test.folder;
+
+  let mut view = make_test_view("v1", "w1", vec![]);
+  view.extra = Some(r#"{"cover":{"type":"2","value":"1"},"font_layout":"normal"}"#.to_string());
+  folder.insert_view(view, None);
+  let r_view = folder.get_view("v1").unwrap();
+  assert_eq!(
+    r_view.cover,
+    Some(ViewCover {
+      ty: CoverType::BuiltInImage,
+      value: "1".to_string(),
+    })
+  );
+
+  // The cover set by the update takes precedence over the one in the extra
+  let r_view = folder
+    .update_view("v1", |update| update.set_cover(None).done())
+    .unwrap();
+  assert_eq!(r_view.cover, None);
+}
+
 #[test]
 fn different_icon_ty_test() {
   let uid = UserId::from(1);
@@ -511,3 +571,90 @@ fn compare_diff_view_test() {
     view_id: "v2".to_string(),
   }));
 }
+
+#[test]
+fn diff_folder_snapshots_test() {
+  let uid = UserId::from(1);
+  let folder_test = create_folder_with_workspace(uid.clone(), "w1");
+  let mut folder = folder_test.folder;
+  folder.insert_view(make_test_view("v1", "w1", vec![]), None);
+  folder.insert_view(make_test_view("v2", "w1", vec![]), None);
+  folder.insert_view(make_test_view("v3", "w1", vec![]), None);
+  let older = folder.encode_collab().unwrap();
+
+  folder.insert_view(make_test_view("v4", "w1", vec![]), None);
+  folder.delete_views(vec!["v1"]);
+  folder.update_view("v2", |update| update.set_name("v2_updated").done());
+  folder.move_nested_view("v3", "v2", None);
+  let newer = folder.encode_collab().unwrap();
+
+  let diff = diff_folder_snapshots(&older, &newer).unwrap();
+  assert_eq!(
+    diff.inserted_views,
+    vec![FolderViewSummary {
+      view_id: "v4".to_string(),
+      name: "".to_string(),
+      layout: ViewLayout::Document,
+    }]
+  );
+  assert_eq!(diff.deleted_views.len(), 1);
+  assert_eq!(diff.deleted_views[0].view_id, "v1");
+
+  let v2 = diff
+    .updated_views
+    .iter()
+    .find(|v| v.view_id == "v2")
+    .unwrap();
+  assert_eq!(v2.name, "v2_updated");
+  assert_eq!(v2.old_name.as_deref(), Some(""));
+  assert!(v2.children_changed);
+  let v3 = diff
+    .updated_views
+    .iter()
+    .find(|v| v.view_id == "v3")
+    .unwrap();
+  assert!(v3.moved && v3.old_name.is_none());
+
+  assert!(diff_folder_snapshots(&newer, &newer).unwrap().is_empty());
+}
+
+#[test]
+fn set_view_extra_value_test() {
+  let uid = UserId::from(1);
+  let folder_test = create_folder_with_workspace(uid.clone(), "w1");
+  let mut folder = folder_test.folder;
+  let mut view = make_test_view("v1", "w1", vec![]);
+  view.extra = Some(r#"{"font_layout":"large"}"#.to_string());
+  folder.insert_view(view, None);
+
+  let view = folder.get_view("v1").unwrap();
+  assert!(!view.is_pinned());
+  assert!(view.font().is_none());
+
+  folder.set_extra_value("v1", VIEW_EXTRA_IS_PINNED, true);
+  folder.set_extra_value("v1", VIEW_EXTRA_FONT, "Poppins");
+  let view = folder
+    .set_extra_value("v1", VIEW_EXTRA_LINE_HEIGHT, 1.5)
+    .unwrap();
+  assert!(view.is_pinned());
+  assert_eq!(view.font(), Some("Poppins".to_string()));
+  assert_eq!(view.line_height(), Some(1.5));
+  // The values that are not set by the typed accessors are kept.
+  assert_eq!(
+    view.extra_value_as::<String>("font_layout"),
+    Some("large".to_string())
+  );
+
+  let view = folder.remove_extra_value("v1", VIEW_EXTRA_FONT).unwrap();
+  assert!(view.font().is_none());
+  assert!(view.is_pinned());
+
+  // A value with another type is ignored by the typed getter.
+  let view = folder
+    .set_extra_value("v1", VIEW_EXTRA_LINE_HEIGHT, "tall")
+    .unwrap();
+  assert!(view.line_height().is_none());
+  assert!(folder
+    .set_extra_value("v2", VIEW_EXTRA_IS_PINNED, true)
+    .is_none());
+}
@@ -32,6 +32,9 @@ pub enum ImporterError {
   #[error("Can not import file")]
   CannotImport,
 
+  #[error("Unsupported archive version: {0}")]
+  UnsupportedArchiveVersion(u32),
+
   #[error(transparent)]
   Internal(#[from] anyhow::Error),
 }
@@ -1,9 +1,11 @@
 use crate::error::ImporterError;
 use crate::notion::page::CollabResource;
 use crate::notion::NotionImporter;
-use crate::util::{unzip_from_path_or_memory, Either};
+use crate::pipeline::{run_import, ImportContext, ImportedWorkspace, NotionZipSource};
+use crate::util::{unzip_from_path_or_memory, upload_file_url, Either, FileId};
 use collab::entity::EncodedCollab;
 use collab_entity::CollabType;
+use std::collections::HashSet;
 use std::fmt;
 
 use futures::StreamExt;
@@ -35,6 +37,82 @@ pub async fn import_notion_zip_file(
   Ok(RepeatedImportedCollabInfo { infos })
 }
 
+/// Imports a Notion export zip as a whole workspace.
+///
+/// Unlike [import_notion_zip_file], it also returns the hierarchy of the imported pages, which
+/// is used to insert the views into the folder, and the manifest of the files that need to be
+/// uploaded.
+pub async fn import_notion_workspace(
+  uid: i64,
+  host: &str,
+  workspace_id: &str,
+  zip_file: PathBuf,
+  output_dir: PathBuf,
+) -> Result<ImportedWorkspace, ImporterError> {
+  let source = NotionZipSource {
+    zip_file,
+    output_dir,
+  };
+  run_import(&source, &ImportContext::new(uid, workspace_id, host)).await
+}
+
+/// A file referenced by an imported collab.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ImportedAsset {
+  /// The id of the collab that references the file.
+  pub object_id: String,
+  pub file_path: String,
+  pub file_id: String,
+  /// The url the file must be uploaded to. The imported collabs already reference the file by
+  /// this url.
+  pub url: String,
+  pub size: u64,
+}
+
+/// The files that need to be uploaded along with the imported collabs.
+#[derive(Debug, Clone, Default)]
+pub struct AssetManifest {
+  pub assets: Vec<ImportedAsset>,
+}
+
+impl AssetManifest {
+  pub async fn from_infos(host: &str, workspace_id: &str, infos: &[ImportedCollabInfo]) -> Self {
+    let mut seen = HashSet::new();
+    let mut assets = vec![];
+    for resource in infos.iter().flat_map(|info| info.resources.iter()) {
+      for file_path in &resource.files {
+        if !seen.insert((resource.object_id.clone(), file_path.clone())) {
+          continue;
+        }
+        let path = PathBuf::from(file_path);
+        let file_id = match FileId::from_path(&path).await {
+          Ok(file_id) => file_id,
+          Err(err) => {
+            tracing::warn!("Skip the asset {}: {}", file_path, err);
+            continue;
+          },
+        };
+        let size = tokio::fs::metadata(&path)
+          .await
+          .map(|m| m.len())
+          .unwrap_or(0);
+        assets.push(ImportedAsset {
+          url: upload_file_url(host, workspace_id, &resource.object_id, &file_id),
+          object_id: resource.object_id.clone(),
+          file_path: file_path.clone(),
+          file_id,
+          size,
+        });
+      }
+    }
+    Self { assets }
+  }
+
+  pub fn total_size(&self) -> u64 {
+    self.assets.iter().map(|asset| asset.size).sum()
+  }
+}
+
 #[derive(Debug, Clone)]
 pub struct RepeatedImportedCollabInfo {
   pub infos: Vec<ImportedCollabInfo>,
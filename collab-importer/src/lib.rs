@@ -1,6 +1,11 @@
 pub mod error;
 pub mod imported_collab;
+pub mod markdown_dir;
 pub mod notion;
+pub mod pipeline;
+pub mod row_document;
+pub mod snapshot_diff;
 mod space_view;
 pub mod util;
+pub mod workspace_archive;
 pub mod zip_tool;
@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+
+const FRONT_MATTER_DELIMITER: &str = "---";
+const FRONT_MATTER_END: &str = "...";
+
+/// The front-matter of a markdown file, the YAML block between two `---` lines at the top of
+/// the file.
+///
+/// Only the `key: value` pairs of the top level are read. A list written as `- item` lines under
+/// a key is joined with `, `, and nested maps are ignored.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct FrontMatter {
+  pub fields: HashMap<String, String>,
+}
+
+impl FrontMatter {
+  pub fn get(&self, key: &str) -> Option<&str> {
+    self.fields.get(key).map(|value| value.as_str())
+  }
+
+  pub fn title(&self) -> Option<&str> {
+    self.get("title").filter(|title| !title.is_empty())
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.fields.is_empty()
+  }
+}
+
+/// Splits the front-matter from the content of a markdown file.
+///
+/// Returns the front-matter, the content without it, and the number of lines the front-matter
+/// takes, which is used to map the line numbers of the content back to the file.
+pub fn split_front_matter(content: &str) -> (FrontMatter, &str, usize) {
+  let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+  let mut lines = content.split_inclusive('\n');
+  let mut offset = match lines.next() {
+    Some(line) if line.trim_end() == FRONT_MATTER_DELIMITER => line.len(),
+    _ => return (FrontMatter::default(), content, 0),
+  };
+
+  let mut yaml_lines = vec![];
+  for line in lines {
+    offset += line.len();
+    let trimmed = line.trim_end();
+    if trimmed == FRONT_MATTER_DELIMITER || trimmed == FRONT_MATTER_END {
+      let num_of_lines = yaml_lines.len() + 2;
+      let rest = content[offset..].trim_start_matches(['\r', '\n']);
+      let skipped_lines = content[offset..content.len() - rest.len()]
+        .matches('\n')
+        .count();
+      return (
+        parse_front_matter(&yaml_lines),
+        rest,
+        num_of_lines + skipped_lines,
+      );
+    }
+    yaml_lines.push(trimmed);
+  }
+
+  // The front-matter is not closed, so the delimiter is a thematic break.
+  (FrontMatter::default(), content, 0)
+}
+
+fn parse_front_matter(lines: &[&str]) -> FrontMatter {
+  let mut fields = HashMap::new();
+  let mut list: Option<(String, Vec<String>)> = None;
+  for line in lines {
+    if line.trim().is_empty() || line.trim_start().starts_with('#') {
+      continue;
+    }
+
+    let is_indented = line.starts_with([' ', '\t']);
+    let trimmed = line.trim();
+    if let Some(item) = trimmed.strip_prefix("- ") {
+      if let Some((_, items)) = list.as_mut() {
+        items.push(unquote(item).to_string());
+      }
+      continue;
+    }
+    if is_indented {
+      continue;
+    }
+
+    if let Some((key, items)) = list.take() {
+      fields.insert(key, items.join(", "));
+    }
+    if let Some((key, value)) = trimmed.split_once(':') {
+      let key = key.trim().to_string();
+      let value = value.trim();
+      if value.is_empty() {
+        list = Some((key, vec![]));
+      } else {
+        fields.insert(key, unquote(value).to_string());
+      }
+    }
+  }
+  if let Some((key, items)) = list {
+    fields.insert(key, items.join(", "));
+  }
+  FrontMatter { fields }
+}
+
+fn unquote(value: &str) -> &str {
+  let value = value.trim();
+  for quote in ['"', '\''] {
+    if value.len() >= 2 && value.starts_with(quote) && value.ends_with(quote) {
+      return &value[1..value.len() - 1];
+    }
+  }
+  value
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn split_front_matter_test() {
+    let content = "---\ntitle: \"Daily note\"\ntags:\n  - journal\n  - work\n---\n\n# Today\n";
+    let (front_matter, rest, num_of_lines) = split_front_matter(content);
+    assert_eq!(front_matter.title(), Some("Daily note"));
+    assert_eq!(front_matter.get("tags"), Some("journal, work"));
+    assert_eq!(rest, "# Today\n");
+    assert_eq!(num_of_lines, 7);
+  }
+
+  #[test]
+  fn no_front_matter_test() {
+    let content = "# Title\n---\n";
+    let (front_matter, rest, num_of_lines) = split_front_matter(content);
+    assert!(front_matter.is_empty());
+    assert_eq!(rest, content);
+    assert_eq!(num_of_lines, 0);
+
+    // Not closed
+    let content = "---\ntitle: a\n";
+    let (front_matter, rest, _) = split_front_matter(content);
+    assert!(front_matter.is_empty());
+    assert_eq!(rest, content);
+  }
+}
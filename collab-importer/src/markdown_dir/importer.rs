@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use collab_document::blocks::DocumentData;
+use collab_document::document::Document;
+use collab_document::document_data::default_document_data;
+use collab_document::importer::define::{BlockType, URL_FIELD};
+use collab_document::importer::md_importer::MDImporter;
+use collab_entity::CollabType;
+use collab_folder::hierarchy_builder::{
+  NestedChildViewBuilder, NestedViews, ParentChildViews, SpacePermission,
+};
+use collab_folder::ViewLayout;
+use markdown::to_mdast;
+use percent_encoding::percent_decode_str;
+use serde_json::Value;
+use tokio::fs;
+use tracing::warn;
+
+use crate::error::ImporterError;
+use crate::imported_collab::{
+  ImportType, ImportedCollab, ImportedCollabInfo, RepeatedImportedCollabInfo,
+};
+use crate::markdown_dir::front_matter::split_front_matter;
+use crate::markdown_dir::unsupported::{find_unsupported_constructs, UnsupportedConstruct};
+use crate::notion::page::CollabResource;
+use crate::space_view::create_space_view;
+use crate::util::{upload_file_url, FileId};
+
+const MARKDOWN_EXTENSIONS: [&str; 2] = ["md", "markdown"];
+
+/// Imports a folder of markdown files, such as an Obsidian vault.
+///
+/// Each markdown file becomes a document, and each folder becomes a page whose children are the
+/// files and folders it contains. A folder that has a markdown file with the same name next to
+/// it, like `Projects/` and `Projects.md`, uses that file as its content. Hidden files and
+/// folders, like `.obsidian`, and the symbolic links are skipped, so the import never reads
+/// outside of the folder.
+#[derive(Debug)]
+pub struct MarkdownDirImporter {
+  uid: i64,
+  host: String,
+  workspace_id: String,
+  path: PathBuf,
+  /// The canonical path of the folder, that contains all the imported images.
+  root: PathBuf,
+  name: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportedMarkdownDir {
+  pub name: String,
+  /// The hierarchy of the imported views, under a space named after the folder. The view ids
+  /// are the object ids of the collabs.
+  pub nested_views: NestedViews,
+  pub infos: RepeatedImportedCollabInfo,
+  /// The constructs that were imported as plain text, or dropped.
+  pub unsupported_constructs: Vec<UnsupportedConstruct>,
+}
+
+#[derive(Debug, Clone)]
+struct MarkdownPage {
+  view_id: String,
+  name: String,
+  /// None if the page is a folder without a markdown file.
+  file_path: Option<PathBuf>,
+  children: Vec<MarkdownPage>,
+}
+
+impl MarkdownDirImporter {
+  pub fn new<P: Into<PathBuf>, S: ToString>(
+    uid: i64,
+    dir_path: P,
+    workspace_id: S,
+    host: String,
+  ) -> Result<Self, ImporterError> {
+    let path = dir_path.into();
+    if !path.is_dir() {
+      return Err(ImporterError::InvalidPath(format!(
+        "Path: is not a directory: {:?}",
+        path
+      )));
+    }
+
+    let name = path
+      .file_name()
+      .and_then(|name| name.to_str())
+      .map(|name| name.to_string())
+      .unwrap_or_else(|| {
+        let now = chrono::Utc::now();
+        format!("import-{}", now.format("%Y-%m-%d %H:%M"))
+      });
+    let root = path.canonicalize()?;
+
+    Ok(Self {
+      uid,
+      host,
+      workspace_id: workspace_id.to_string(),
+      path,
+      root,
+      name,
+    })
+  }
+
+  pub async fn import(self) -> Result<ImportedMarkdownDir, ImporterError> {
+    let path = self.path.clone();
+    let pages = tokio::task::spawn_blocking(move || collect_pages(&path))
+      .await
+      .map_err(|err| ImporterError::Internal(err.into()))??;
+    if pages.is_empty() {
+      return Err(ImporterError::CannotImport);
+    }
+
+    let space_id = uuid::Uuid::new_v4().to_string();
+    let mut infos = vec![];
+    let mut unsupported_constructs = vec![];
+    let mut views = vec![];
+    for page in pages {
+      let view = self
+        .import_page(&space_id, page, &mut infos, &mut unsupported_constructs)
+        .await?;
+      views.push(view);
+    }
+
+    let (space_view, space_collab) = create_space_view(
+      self.uid,
+      &self.workspace_id,
+      &self.name,
+      &space_id,
+      views,
+      SpacePermission::PublicToAll,
+    )?;
+    let space_info = ImportedCollabInfo {
+      name: self.name.clone(),
+      imported_collabs: vec![ImportedCollab {
+        object_id: space_id.clone(),
+        collab_type: CollabType::Document,
+        encoded_collab: space_collab.encode_collab_v1(|_collab| Ok::<_, ImporterError>(()))?,
+      }],
+      resources: vec![CollabResource {
+        object_id: space_id,
+        files: vec![],
+      }],
+      import_type: ImportType::Document,
+    };
+    infos.insert(0, space_info);
+
+    Ok(ImportedMarkdownDir {
+      name: self.name,
+      nested_views: NestedViews {
+        views: vec![space_view],
+      },
+      infos: RepeatedImportedCollabInfo { infos },
+      unsupported_constructs,
+    })
+  }
+
+  #[async_recursion::async_recursion]
+  async fn import_page(
+    &self,
+    parent_id: &str,
+    page: MarkdownPage,
+    infos: &mut Vec<ImportedCollabInfo>,
+    unsupported_constructs: &mut Vec<UnsupportedConstruct>,
+  ) -> Result<ParentChildViews, ImporterError> {
+    let mut name = page.name;
+    let mut files = vec![];
+    let document_data = match &page.file_path {
+      None => default_document_data(&page.view_id),
+      Some(file_path) => {
+        let content = fs::read_to_string(file_path).await?;
+        let (front_matter, content, line_offset) = split_front_matter(&content);
+        if let Some(title) = front_matter.title() {
+          name = title.to_string();
+        }
+
+        let mut document_data = {
+          let md_importer = MDImporter::new(None);
+          let md_node = to_mdast(content, &md_importer.parse_options)
+            .map_err(ImporterError::ParseMarkdownError)?;
+          unsupported_constructs.extend(find_unsupported_constructs(&md_node).into_iter().map(
+            |(kind, line)| UnsupportedConstruct {
+              file_path: file_path.clone(),
+              kind,
+              line: line + line_offset,
+            },
+          ));
+          md_importer.import(&page.view_id, content.to_string())?
+        };
+        if let Some(parent_path) = file_path.parent() {
+          files = self
+            .replace_local_images(&page.view_id, &mut document_data, parent_path)
+            .await;
+        }
+        document_data
+      },
+    };
+
+    let document = Document::create(&page.view_id, document_data)?;
+    infos.push(ImportedCollabInfo {
+      name: name.clone(),
+      imported_collabs: vec![ImportedCollab {
+        object_id: page.view_id.clone(),
+        collab_type: CollabType::Document,
+        encoded_collab: document.encode_collab()?,
+      }],
+      resources: vec![CollabResource {
+        object_id: page.view_id.clone(),
+        files,
+      }],
+      import_type: ImportType::Document,
+    });
+
+    let mut children = vec![];
+    for child in page.children {
+      children.push(
+        self
+          .import_page(&page.view_id, child, infos, unsupported_constructs)
+          .await?,
+      );
+    }
+    Ok(
+      NestedChildViewBuilder::new(self.uid, parent_id.to_string())
+        .with_view_id(&page.view_id)
+        .with_name(&name)
+        .with_layout(ViewLayout::Document)
+        .with_children(children)
+        .build(),
+    )
+  }
+
+  /// Replaces the relative urls of the images that exist in the folder with the urls the images
+  /// will be uploaded to. Returns the paths of the images. The paths are resolved, so an image
+  /// outside of the folder, through `..` or a symbolic link, is left as is.
+  async fn replace_local_images(
+    &self,
+    view_id: &str,
+    document_data: &mut DocumentData,
+    parent_path: &Path,
+  ) -> Vec<String> {
+    let image_type = BlockType::Image.to_string();
+    let mut files = vec![];
+    for block in document_data.blocks.values_mut() {
+      if block.ty != image_type {
+        continue;
+      }
+      let image_path = match block
+        .data
+        .get(URL_FIELD)
+        .and_then(|url| url.as_str())
+        .filter(|url| !url.contains("://") && !url.starts_with("data:"))
+        .and_then(|url| percent_decode_str(url).decode_utf8().ok())
+      {
+        None => continue,
+        Some(url) => parent_path.join(url.as_ref()),
+      };
+      let image_path = match fs::canonicalize(&image_path).await {
+        Ok(image_path) => image_path,
+        Err(_) => continue,
+      };
+      if !image_path.starts_with(&self.root) {
+        warn!("Skip the image {:?} outside of the folder", image_path);
+        continue;
+      }
+      if !image_path.is_file() {
+        continue;
+      }
+      match FileId::from_path(&image_path).await {
+        Ok(file_id) => {
+          let url = upload_file_url(&self.host, &self.workspace_id, view_id, &file_id);
+          block.data.insert(URL_FIELD.to_string(), Value::String(url));
+          if let Some(path) = image_path.to_str() {
+            if !files.iter().any(|file| file == path) {
+              files.push(path.to_string());
+            }
+          }
+        },
+        Err(err) => warn!("Skip the image {:?}: {}", image_path, err),
+      }
+    }
+    files
+  }
+}
+
+/// Collects the markdown files and the folders that contain markdown files, sorted by name.
+fn collect_pages(dir: &Path) -> Result<Vec<MarkdownPage>, ImporterError> {
+  let mut entries = std::fs::read_dir(dir)?
+    .flatten()
+    .filter(|entry| {
+      entry
+        .file_type()
+        .map(|file_type| !file_type.is_symlink())
+        .unwrap_or(false)
+    })
+    .map(|entry| entry.path())
+    .filter(|path| {
+      path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| !name.starts_with('.'))
+        .unwrap_or(false)
+    })
+    .collect::<Vec<_>>();
+  entries.sort();
+
+  let mut pages = vec![];
+  let mut page_index_by_name = HashMap::new();
+  for path in entries.iter().filter(|path| is_markdown_file(path)) {
+    let name = file_stem(path);
+    page_index_by_name.insert(name.clone(), pages.len());
+    pages.push(MarkdownPage {
+      view_id: uuid::Uuid::new_v4().to_string(),
+      name,
+      file_path: Some(path.clone()),
+      children: vec![],
+    });
+  }
+
+  for path in entries.iter().filter(|path| path.is_dir()) {
+    let children = collect_pages(path)?;
+    if children.is_empty() {
+      continue;
+    }
+    let name = file_stem(path);
+    match page_index_by_name.get(&name) {
+      Some(index) => pages[*index].children.extend(children),
+      None => pages.push(MarkdownPage {
+        view_id: uuid::Uuid::new_v4().to_string(),
+        name,
+        file_path: None,
+        children,
+      }),
+    }
+  }
+  pages.sort_by(|a, b| a.name.cmp(&b.name));
+  Ok(pages)
+}
+
+fn is_markdown_file(path: &Path) -> bool {
+  path.is_file()
+    && path
+      .extension()
+      .and_then(|ext| ext.to_str())
+      .map(|ext| {
+        MARKDOWN_EXTENSIONS
+          .iter()
+          .any(|md_ext| ext.eq_ignore_ascii_case(md_ext))
+      })
+      .unwrap_or(false)
+}
+
+fn file_stem(path: &Path) -> String {
+  let file_name = if path.is_dir() {
+    path.file_name()
+  } else {
+    path.file_stem()
+  };
+  file_name
+    .map(|name| name.to_string_lossy().to_string())
+    .unwrap_or_default()
+}
@@ -0,0 +1,7 @@
+mod front_matter;
+mod importer;
+mod unsupported;
+
+pub use front_matter::*;
+pub use importer::*;
+pub use unsupported::*;
@@ -0,0 +1,110 @@
+use std::fmt::Display;
+use std::path::PathBuf;
+
+use markdown::mdast;
+
+/// A markdown construct that has no equivalent block in a document. The construct is imported as
+/// plain text, or dropped if it can't be represented as text.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum UnsupportedKind {
+  Html,
+  Footnote,
+  /// An Obsidian `[[page]]` link.
+  WikiLink,
+  /// An Obsidian `![[file]]` embed.
+  Embed,
+  /// An Obsidian `> [!note]` callout. It's imported as a quote.
+  Callout,
+}
+
+impl Display for UnsupportedKind {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      UnsupportedKind::Html => write!(f, "HTML"),
+      UnsupportedKind::Footnote => write!(f, "Footnote"),
+      UnsupportedKind::WikiLink => write!(f, "Wiki link"),
+      UnsupportedKind::Embed => write!(f, "Embed"),
+      UnsupportedKind::Callout => write!(f, "Callout"),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UnsupportedConstruct {
+  pub file_path: PathBuf,
+  pub kind: UnsupportedKind,
+  /// The 1-indexed line of the construct in the file.
+  pub line: usize,
+}
+
+impl Display for UnsupportedConstruct {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "{}:{}: {}",
+      self.file_path.display(),
+      self.line,
+      self.kind
+    )
+  }
+}
+
+/// Returns the unsupported constructs of the markdown tree, with the line they start at.
+pub(crate) fn find_unsupported_constructs(node: &mdast::Node) -> Vec<(UnsupportedKind, usize)> {
+  let mut constructs = vec![];
+  collect_unsupported_constructs(node, &mut constructs);
+  constructs
+}
+
+fn collect_unsupported_constructs(
+  node: &mdast::Node,
+  constructs: &mut Vec<(UnsupportedKind, usize)>,
+) {
+  let line = node.position().map(|p| p.start.line).unwrap_or(0);
+  match node {
+    mdast::Node::Html(_) => constructs.push((UnsupportedKind::Html, line)),
+    mdast::Node::FootnoteDefinition(_) | mdast::Node::FootnoteReference(_) => {
+      constructs.push((UnsupportedKind::Footnote, line))
+    },
+    mdast::Node::Blockquote(quote) if is_callout(quote) => {
+      constructs.push((UnsupportedKind::Callout, line))
+    },
+    mdast::Node::Text(text) => {
+      for (index, text_line) in text.value.lines().enumerate() {
+        let mut rest = text_line;
+        while let Some(start) = rest.find("[[") {
+          let is_embed = rest[..start].ends_with('!');
+          match rest[start..].find("]]") {
+            None => break,
+            Some(end) => {
+              let kind = if is_embed {
+                UnsupportedKind::Embed
+              } else {
+                UnsupportedKind::WikiLink
+              };
+              constructs.push((kind, line + index));
+              rest = &rest[start + end + 2..];
+            },
+          }
+        }
+      }
+    },
+    _ => {},
+  }
+
+  if let Some(children) = node.children() {
+    for child in children {
+      collect_unsupported_constructs(child, constructs);
+    }
+  }
+}
+
+fn is_callout(quote: &mdast::Blockquote) -> bool {
+  match quote.children.first() {
+    Some(mdast::Node::Paragraph(paragraph)) => matches!(
+      paragraph.children.first(),
+      Some(mdast::Node::Text(text)) if text.value.starts_with("[!")
+    ),
+    _ => false,
+  }
+}
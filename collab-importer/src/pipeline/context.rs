@@ -0,0 +1,116 @@
+use std::fmt::{Debug, Formatter};
+use std::path::Path;
+use std::sync::Arc;
+
+use collab_database::template::builder::FileUrlBuilder;
+use collab_entity::CollabType;
+
+use crate::imported_collab::ImportedCollabInfo;
+use crate::util::{upload_file_url, FileId};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ImportProgress {
+  Started {
+    source: String,
+  },
+  /// A collab of a page or a database was imported.
+  ImportedCollab {
+    object_id: String,
+    collab_type: CollabType,
+  },
+  /// A page or a database of the source was imported, after all of its collabs.
+  DidImport {
+    name: String,
+    num_of_collabs: usize,
+  },
+  Finished {
+    num_of_collabs: usize,
+    num_of_assets: usize,
+  },
+}
+
+pub trait ImportProgressReporter: Send + Sync {
+  fn report(&self, progress: ImportProgress);
+}
+
+impl<F> ImportProgressReporter for F
+where
+  F: Fn(ImportProgress) + Send + Sync,
+{
+  fn report(&self, progress: ImportProgress) {
+    self(progress)
+  }
+}
+
+/// The workspace the source is imported into, shared by all the importers.
+#[derive(Clone)]
+pub struct ImportContext {
+  pub uid: i64,
+  pub workspace_id: String,
+  /// The host of the server the files are uploaded to.
+  pub host: String,
+  progress_reporter: Option<Arc<dyn ImportProgressReporter>>,
+}
+
+impl ImportContext {
+  pub fn new<S: ToString>(uid: i64, workspace_id: S, host: S) -> Self {
+    Self {
+      uid,
+      workspace_id: workspace_id.to_string(),
+      host: host.to_string(),
+      progress_reporter: None,
+    }
+  }
+
+  pub fn with_progress_reporter<R: ImportProgressReporter + 'static>(
+    mut self,
+    reporter: R,
+  ) -> Self {
+    self.progress_reporter = Some(Arc::new(reporter));
+    self
+  }
+
+  /// Returns the url the file, which is referenced by the object, will be uploaded to.
+  pub fn file_url(&self, object_id: &str, file_id: &str) -> String {
+    upload_file_url(&self.host, &self.workspace_id, object_id, file_id)
+  }
+
+  pub fn report(&self, progress: ImportProgress) {
+    if let Some(reporter) = &self.progress_reporter {
+      reporter.report(progress);
+    }
+  }
+
+  /// Report each collab of the page or the database, then the page or the database itself.
+  /// Called by the importers as soon as a page or a database is imported.
+  pub fn report_imported(&self, info: &ImportedCollabInfo) {
+    for imported_collab in &info.imported_collabs {
+      self.report(ImportProgress::ImportedCollab {
+        object_id: imported_collab.object_id.clone(),
+        collab_type: imported_collab.collab_type.clone(),
+      });
+    }
+    self.report(ImportProgress::DidImport {
+      name: info.name.clone(),
+      num_of_collabs: info.imported_collabs.len(),
+    });
+  }
+}
+
+impl Debug for ImportContext {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("ImportContext")
+      .field("uid", &self.uid)
+      .field("workspace_id", &self.workspace_id)
+      .field("host", &self.host)
+      .finish()
+  }
+}
+
+#[async_trait::async_trait]
+impl FileUrlBuilder for ImportContext {
+  async fn build(&self, database_id: &str, path: &Path) -> Option<String> {
+    let file_id = FileId::from_path(&path.to_path_buf()).await.ok()?;
+    Some(self.file_url(database_id, &file_id))
+  }
+}
@@ -0,0 +1,80 @@
+mod context;
+mod source;
+
+pub use context::*;
+pub use source::*;
+
+use collab_folder::hierarchy_builder::NestedViews;
+
+use crate::error::ImporterError;
+use crate::imported_collab::{AssetManifest, ImportedCollabInfo, RepeatedImportedCollabInfo};
+
+/// Imports a source, like a Notion export or a folder of markdown files, into a workspace.
+///
+/// An importer only converts the source into collabs and views. The steps shared by all the
+/// sources, like reporting the progress and collecting the files to upload, are done by
+/// [run_import].
+///
+/// The futures are not `Send` because the Notion importer holds non `Send` streams while
+/// converting the pages.
+#[async_trait::async_trait(?Send)]
+pub trait WorkspaceImporter: Send + Sync {
+  /// The name of the source, used in the progress and the logs.
+  fn source_name(&self) -> &str;
+
+  /// Convert the source. Each page or database is reported with
+  /// [ImportContext::report_imported] as soon as it's imported.
+  async fn import(&self, context: &ImportContext) -> Result<ImportedSource, ImporterError>;
+}
+
+/// The collabs and the views converted from a source.
+#[derive(Debug, Clone)]
+pub struct ImportedSource {
+  pub name: String,
+  /// The hierarchy of the imported views. The view ids are the object ids of the collabs.
+  pub nested_views: NestedViews,
+  pub infos: Vec<ImportedCollabInfo>,
+  /// The parts of the source that could not be imported as they are.
+  pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportedWorkspace {
+  pub name: String,
+  /// The hierarchy of the imported views. The view ids are the object ids of the collabs.
+  pub nested_views: NestedViews,
+  pub infos: RepeatedImportedCollabInfo,
+  pub asset_manifest: AssetManifest,
+  pub warnings: Vec<String>,
+}
+
+/// Runs the importer and collects the files referenced by the imported collabs.
+pub async fn run_import(
+  importer: &dyn WorkspaceImporter,
+  context: &ImportContext,
+) -> Result<ImportedWorkspace, ImporterError> {
+  context.report(ImportProgress::Started {
+    source: importer.source_name().to_string(),
+  });
+  let imported = importer.import(context).await?;
+
+  let asset_manifest =
+    AssetManifest::from_infos(&context.host, &context.workspace_id, &imported.infos).await;
+  context.report(ImportProgress::Finished {
+    num_of_collabs: imported
+      .infos
+      .iter()
+      .map(|info| info.imported_collabs.len())
+      .sum(),
+    num_of_assets: asset_manifest.assets.len(),
+  });
+  Ok(ImportedWorkspace {
+    name: imported.name,
+    nested_views: imported.nested_views,
+    infos: RepeatedImportedCollabInfo {
+      infos: imported.infos,
+    },
+    asset_manifest,
+    warnings: imported.warnings,
+  })
+}
@@ -0,0 +1,182 @@
+use std::path::PathBuf;
+
+use collab_database::database::{gen_database_view_id, Database};
+use collab_database::template::csv::{CSVResource, CSVTemplate};
+use collab_folder::hierarchy_builder::{NestedChildViewBuilder, NestedViews};
+use collab_folder::ViewLayout;
+use futures::StreamExt;
+use tokio::fs;
+
+use crate::error::ImporterError;
+use crate::imported_collab::{ImportType, ImportedCollab, ImportedCollabInfo};
+use crate::markdown_dir::MarkdownDirImporter;
+use crate::notion::page::CollabResource;
+use crate::notion::NotionImporter;
+use crate::pipeline::{ImportContext, ImportedSource, WorkspaceImporter};
+use crate::util::{unzip_from_path_or_memory, Either};
+
+/// A zip file exported from Notion.
+pub struct NotionZipSource {
+  pub zip_file: PathBuf,
+  /// The folder the zip file is extracted to.
+  pub output_dir: PathBuf,
+}
+
+#[async_trait::async_trait(?Send)]
+impl WorkspaceImporter for NotionZipSource {
+  fn source_name(&self) -> &str {
+    "notion"
+  }
+
+  async fn import(&self, context: &ImportContext) -> Result<ImportedSource, ImporterError> {
+    if !self.zip_file.exists() {
+      return Err(ImporterError::FileNotFound);
+    }
+
+    let unzip_file =
+      unzip_from_path_or_memory(Either::Left(self.zip_file.clone()), self.output_dir.clone())
+        .await?;
+    let imported = NotionImporter::new(
+      context.uid,
+      &unzip_file,
+      &context.workspace_id,
+      context.host.clone(),
+    )?
+    .import()
+    .await?;
+
+    let name = imported.name.clone();
+    let nested_views = imported.build_nested_views().await;
+    let infos = imported
+      .into_collab_stream()
+      .await
+      .inspect(|info| context.report_imported(info))
+      .collect::<Vec<ImportedCollabInfo>>()
+      .await;
+    Ok(ImportedSource {
+      name,
+      nested_views,
+      infos,
+      warnings: vec![],
+    })
+  }
+}
+
+/// A folder of markdown files. See [MarkdownDirImporter].
+pub struct MarkdownDirSource {
+  pub path: PathBuf,
+}
+
+#[async_trait::async_trait(?Send)]
+impl WorkspaceImporter for MarkdownDirSource {
+  fn source_name(&self) -> &str {
+    "markdown"
+  }
+
+  async fn import(&self, context: &ImportContext) -> Result<ImportedSource, ImporterError> {
+    let imported = MarkdownDirImporter::new(
+      context.uid,
+      &self.path,
+      &context.workspace_id,
+      context.host.clone(),
+    )?
+    .import()
+    .await?;
+    for info in &imported.infos.infos {
+      context.report_imported(info);
+    }
+    Ok(ImportedSource {
+      name: imported.name,
+      nested_views: imported.nested_views,
+      infos: imported.infos.infos,
+      warnings: imported
+        .unsupported_constructs
+        .iter()
+        .map(|construct| construct.to_string())
+        .collect(),
+    })
+  }
+}
+
+/// A CSV file, imported as a grid. The first row is used as the header.
+pub struct CsvFileSource {
+  pub file_path: PathBuf,
+}
+
+#[async_trait::async_trait(?Send)]
+impl WorkspaceImporter for CsvFileSource {
+  fn source_name(&self) -> &str {
+    "csv"
+  }
+
+  async fn import(&self, context: &ImportContext) -> Result<ImportedSource, ImporterError> {
+    if !self.file_path.exists() {
+      return Err(ImporterError::FileNotFound);
+    }
+    let name = self
+      .file_path
+      .file_stem()
+      .and_then(|name| name.to_str())
+      .unwrap_or_default()
+      .to_string();
+    let content = fs::read_to_string(&self.file_path).await?;
+
+    let view_id = gen_database_view_id();
+    let csv_resource = CSVResource {
+      server_url: context.host.clone(),
+      workspace_id: context.workspace_id.clone(),
+      files: vec![],
+    };
+    let mut csv_template =
+      CSVTemplate::try_from_reader(content.as_bytes(), true, Some(csv_resource))?;
+    csv_template.reset_view_id(view_id.clone());
+    let database_template = csv_template
+      .try_into_database_template(Some(Box::new(context.clone())))
+      .await?;
+    let database = Database::create_with_template(database_template).await?;
+
+    let database_id = database.get_database_id();
+    let view_ids = database
+      .get_all_views()
+      .into_iter()
+      .map(|view| view.id)
+      .collect();
+    let imported_collabs = database
+      .encode_database_collabs()
+      .await?
+      .into_collabs()
+      .into_iter()
+      .map(|collab_info| ImportedCollab {
+        object_id: collab_info.object_id,
+        collab_type: collab_info.collab_type,
+        encoded_collab: collab_info.encoded_collab,
+      })
+      .collect();
+    let info = ImportedCollabInfo {
+      name: name.clone(),
+      imported_collabs,
+      resources: vec![CollabResource {
+        object_id: database_id.clone(),
+        files: vec![],
+      }],
+      import_type: ImportType::Database {
+        database_id,
+        view_ids,
+        row_document_ids: vec![],
+      },
+    };
+    context.report_imported(&info);
+
+    let view = NestedChildViewBuilder::new(context.uid, context.workspace_id.clone())
+      .with_view_id(&view_id)
+      .with_name(&name)
+      .with_layout(ViewLayout::Grid)
+      .build();
+    Ok(ImportedSource {
+      name,
+      nested_views: NestedViews { views: vec![view] },
+      infos: vec![info],
+      warnings: vec![],
+    })
+  }
+}
@@ -0,0 +1,19 @@
+use collab_database::rows::RowDocument;
+use collab_folder::hierarchy_builder::{NestedChildViewBuilder, ParentChildViews};
+
+/// Build the folder view of a document converted from a database row, see
+/// [Database::convert_row_to_document](collab_database::database::Database::convert_row_to_document).
+/// The id of the view is the document id.
+pub fn create_row_document_view(
+  uid: i64,
+  parent_view_id: &str,
+  row_document: &RowDocument,
+) -> ParentChildViews {
+  let mut view_builder = NestedChildViewBuilder::new(uid, parent_view_id.to_string())
+    .with_view_id(&row_document.document_id)
+    .with_name(&row_document.name);
+  if let Some(icon) = &row_document.icon {
+    view_builder = view_builder.with_icon(icon);
+  }
+  view_builder.build()
+}
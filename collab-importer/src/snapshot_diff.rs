@@ -0,0 +1,58 @@
+use anyhow::anyhow;
+use collab::entity::EncodedCollab;
+use collab_database::database_diff::{
+  diff_database_snapshots, diff_row_snapshots, DatabaseDiff, RowDiff,
+};
+use collab_document::document_diff::{diff_document_snapshots, DocumentDiff};
+use collab_entity::CollabType;
+use collab_folder::folder_diff::{diff_folder_snapshots, FolderDiff};
+
+use crate::error::ImporterError;
+
+/// The changes between two snapshots of a collab, typed by the kind of the collab. Used by the
+/// version history to explain what happened between two versions.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SnapshotDiff {
+  Document(DocumentDiff),
+  Database(DatabaseDiff),
+  DatabaseRow(RowDiff),
+  Folder(FolderDiff),
+}
+
+impl SnapshotDiff {
+  pub fn is_empty(&self) -> bool {
+    match self {
+      SnapshotDiff::Document(diff) => diff.is_empty(),
+      SnapshotDiff::Database(diff) => diff.is_empty(),
+      SnapshotDiff::DatabaseRow(diff) => diff.is_empty(),
+      SnapshotDiff::Folder(diff) => diff.is_empty(),
+    }
+  }
+}
+
+/// Decodes both snapshots as the given collab type and returns the changes from `older` to
+/// `newer`. Only documents, databases, database rows and folders can be compared.
+pub fn diff_snapshots(
+  older: &EncodedCollab,
+  newer: &EncodedCollab,
+  collab_type: &CollabType,
+) -> Result<SnapshotDiff, ImporterError> {
+  match collab_type {
+    CollabType::Document => diff_document_snapshots(older, newer)
+      .map(SnapshotDiff::Document)
+      .map_err(ImporterError::from),
+    CollabType::Database => diff_database_snapshots(older, newer)
+      .map(SnapshotDiff::Database)
+      .map_err(ImporterError::from),
+    CollabType::DatabaseRow => diff_row_snapshots(older, newer)
+      .map(SnapshotDiff::DatabaseRow)
+      .map_err(ImporterError::from),
+    CollabType::Folder => diff_folder_snapshots(older, newer)
+      .map(SnapshotDiff::Folder)
+      .map_err(|err| ImporterError::Internal(err.into())),
+    _ => Err(ImporterError::Internal(anyhow!(
+      "The snapshots of {} can't be compared",
+      collab_type
+    ))),
+  }
+}
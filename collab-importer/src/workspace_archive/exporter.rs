@@ -0,0 +1,244 @@
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use collab::core::origin::CollabOrigin;
+use collab::entity::EncodedCollab;
+use collab_database::database::{get_row_document_id, Database, DatabaseContext};
+use collab_database::workspace_database::DatabaseMeta;
+use collab_document::blocks::DocumentData;
+use collab_document::document::Document;
+use collab_entity::CollabType;
+use collab_folder::{Folder, View, ViewLayout};
+use tracing::warn;
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+use crate::error::ImporterError;
+use crate::workspace_archive::loader::{ArchiveCollabLoader, LoaderDatabaseCollabService};
+use crate::workspace_archive::manifest::{
+  collab_file_name, json_file_name, ArchiveManifest, ArchivedObject, MANIFEST_FILE,
+  WORKSPACE_ARCHIVE_VERSION,
+};
+
+/// Exports the views of a workspace, and the documents, databases and rows they reference, into
+/// a zip archive that can be restored with [crate::workspace_archive::WorkspaceArchiveSource].
+///
+/// The views in the trash are not exported. The chat views are skipped because their messages
+/// can't be restored with new ids.
+pub struct WorkspaceArchiveExporter {
+  workspace_id: String,
+  workspace_name: String,
+  views: Vec<View>,
+  database_metas: Vec<DatabaseMeta>,
+  loader: Arc<dyn ArchiveCollabLoader>,
+}
+
+impl WorkspaceArchiveExporter {
+  /// `database_metas` are the databases of the workspace, used to find the database of each
+  /// database view.
+  pub fn new(
+    folder: &Folder,
+    database_metas: Vec<DatabaseMeta>,
+    loader: Arc<dyn ArchiveCollabLoader>,
+  ) -> Result<Self, ImporterError> {
+    let workspace_id = folder
+      .get_workspace_id()
+      .ok_or_else(|| ImporterError::Internal(anyhow!("The folder has no workspace")))?;
+    let workspace_name = folder
+      .get_workspace_info(&workspace_id)
+      .map(|workspace| workspace.name)
+      .unwrap_or_default();
+    let trash = folder
+      .get_my_trash_info()
+      .into_iter()
+      .map(|trash| trash.id)
+      .collect::<HashSet<_>>();
+
+    let mut views = vec![];
+    collect_views(folder, &workspace_id, &trash, &mut views);
+    Ok(Self {
+      workspace_id,
+      workspace_name,
+      views,
+      database_metas,
+      loader,
+    })
+  }
+
+  /// Writes the archive to the file and returns its manifest.
+  pub async fn export_to_file(self, archive_path: &Path) -> Result<ArchiveManifest, ImporterError> {
+    let mut files = vec![];
+    let mut objects = vec![];
+    let mut exported_database_ids = HashSet::new();
+    for view in &self.views {
+      match view.layout {
+        ViewLayout::Document => {
+          if self.export_document(&view.id, &mut files).await? {
+            objects.push(ArchivedObject::Document {
+              object_id: view.id.clone(),
+            });
+          } else {
+            warn!("Skip the document {}: the collab is not found", view.id);
+          }
+        },
+        ViewLayout::Grid | ViewLayout::Board | ViewLayout::Calendar => {
+          let database_id = match self
+            .database_metas
+            .iter()
+            .find(|meta| meta.linked_views.contains(&view.id))
+          {
+            None => {
+              warn!("Skip the view {}: the database is not found", view.id);
+              continue;
+            },
+            Some(meta) => meta.database_id.clone(),
+          };
+          if exported_database_ids.insert(database_id.clone()) {
+            objects.push(self.export_database(&database_id, &mut files).await?);
+          }
+        },
+        ViewLayout::Chat => warn!("Skip the chat view {}", view.id),
+      }
+    }
+
+    let manifest = ArchiveManifest {
+      version: WORKSPACE_ARCHIVE_VERSION,
+      workspace_id: self.workspace_id,
+      workspace_name: self.workspace_name,
+      exported_at: chrono::Utc::now().timestamp(),
+      views: self
+        .views
+        .into_iter()
+        .filter(|view| view.layout != ViewLayout::Chat)
+        .collect(),
+      objects,
+    };
+    files.insert(
+      0,
+      (
+        MANIFEST_FILE.to_string(),
+        serde_json::to_vec_pretty(&manifest).map_err(|err| ImporterError::Internal(err.into()))?,
+      ),
+    );
+
+    let archive_path = archive_path.to_path_buf();
+    tokio::task::spawn_blocking(move || write_archive(archive_path, files))
+      .await
+      .map_err(|err| ImporterError::Internal(err.into()))??;
+    Ok(manifest)
+  }
+
+  /// Returns false if the document doesn't exist.
+  async fn export_document(
+    &self,
+    document_id: &str,
+    files: &mut Vec<(String, Vec<u8>)>,
+  ) -> Result<bool, ImporterError> {
+    let encoded_collab = match self
+      .loader
+      .load_collab(document_id, CollabType::Document)
+      .await
+    {
+      None => return Ok(false),
+      Some(encoded_collab) => encoded_collab,
+    };
+    let data = document_data_from_encoded_collab(document_id, encoded_collab.clone())?;
+    files.push((
+      collab_file_name(document_id),
+      encode_collab(&encoded_collab)?,
+    ));
+    files.push((
+      json_file_name(document_id),
+      serde_json::to_vec(&data).map_err(|err| ImporterError::Internal(err.into()))?,
+    ));
+    Ok(true)
+  }
+
+  async fn export_database(
+    &self,
+    database_id: &str,
+    files: &mut Vec<(String, Vec<u8>)>,
+  ) -> Result<ArchivedObject, ImporterError> {
+    let collab_service = LoaderDatabaseCollabService::new(self.loader.clone());
+    let database =
+      Database::open(database_id, DatabaseContext::new(Arc::new(collab_service))).await?;
+    let data = database.get_database_data().await;
+    for collab in database.encode_database_collabs().await?.into_collabs() {
+      files.push((
+        collab_file_name(&collab.object_id),
+        encode_collab(&collab.encoded_collab)?,
+      ));
+    }
+    files.push((
+      json_file_name(database_id),
+      serde_json::to_vec(&data).map_err(|err| ImporterError::Internal(err.into()))?,
+    ));
+
+    let mut row_document_ids = vec![];
+    for row in &data.rows {
+      let row_document_id = get_row_document_id(&row.id)?;
+      if self.export_document(&row_document_id, files).await? {
+        row_document_ids.push(row_document_id);
+      }
+    }
+
+    Ok(ArchivedObject::Database {
+      database_id: database_id.to_string(),
+      view_ids: data.views.iter().map(|view| view.id.clone()).collect(),
+      row_ids: data.rows.iter().map(|row| row.id.to_string()).collect(),
+      row_document_ids,
+    })
+  }
+}
+
+fn collect_views(folder: &Folder, parent_id: &str, trash: &HashSet<String>, views: &mut Vec<View>) {
+  for view in folder.get_views_belong_to(parent_id) {
+    if trash.contains(&view.id) {
+      continue;
+    }
+    views.push(view.as_ref().clone());
+    collect_views(folder, &view.id, trash, views);
+  }
+}
+
+pub(crate) fn document_data_from_encoded_collab(
+  document_id: &str,
+  encoded_collab: EncodedCollab,
+) -> Result<DocumentData, ImporterError> {
+  let document = Document::open_with_options(
+    CollabOrigin::Empty,
+    encoded_collab.into(),
+    document_id,
+    vec![],
+  )?;
+  Ok(document.get_document_data()?)
+}
+
+fn encode_collab(encoded_collab: &EncodedCollab) -> Result<Vec<u8>, ImporterError> {
+  encoded_collab
+    .encode_to_bytes()
+    .map_err(|err| ImporterError::Internal(err.into()))
+}
+
+fn write_archive(
+  archive_path: PathBuf,
+  files: Vec<(String, Vec<u8>)>,
+) -> Result<(), ImporterError> {
+  if let Some(parent) = archive_path.parent() {
+    std::fs::create_dir_all(parent)?;
+  }
+  let mut writer = ZipWriter::new(std::fs::File::create(archive_path)?);
+  for (name, content) in files {
+    writer
+      .start_file(name, FileOptions::default())
+      .map_err(|err| ImporterError::Internal(err.into()))?;
+    writer.write_all(&content)?;
+  }
+  writer
+    .finish()
+    .map_err(|err| ImporterError::Internal(err.into()))?;
+  Ok(())
+}
@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use collab::core::collab::DataSource;
+use collab::core::origin::CollabOrigin;
+use collab::entity::EncodedCollab;
+use collab::preclude::Collab;
+use collab_database::error::DatabaseError;
+use collab_database::workspace_database::{
+  DatabaseCollabPersistenceService, DatabaseCollabService, EncodeCollabByOid,
+};
+use collab_entity::CollabType;
+
+/// Loads the collabs of the workspace being exported.
+#[async_trait]
+pub trait ArchiveCollabLoader: Send + Sync + 'static {
+  /// Returns None if the collab doesn't exist.
+  async fn load_collab(&self, object_id: &str, collab_type: CollabType) -> Option<EncodedCollab>;
+}
+
+#[async_trait]
+impl ArchiveCollabLoader for HashMap<String, EncodedCollab> {
+  async fn load_collab(&self, object_id: &str, _collab_type: CollabType) -> Option<EncodedCollab> {
+    self.get(object_id).cloned()
+  }
+}
+
+/// Opens the databases with the collabs returned by the loader. Nothing is persisted.
+pub struct LoaderDatabaseCollabService {
+  loader: Arc<dyn ArchiveCollabLoader>,
+}
+
+impl LoaderDatabaseCollabService {
+  pub fn new(loader: Arc<dyn ArchiveCollabLoader>) -> Self {
+    Self { loader }
+  }
+}
+
+#[async_trait]
+impl DatabaseCollabService for LoaderDatabaseCollabService {
+  async fn build_collab(
+    &self,
+    object_id: &str,
+    object_type: CollabType,
+    encoded_collab: Option<(EncodedCollab, bool)>,
+  ) -> Result<Collab, DatabaseError> {
+    let encoded_collab = match encoded_collab {
+      Some((encoded_collab, _)) => Some(encoded_collab),
+      None => self.loader.load_collab(object_id, object_type).await,
+    };
+    match encoded_collab {
+      None => Ok(Collab::new_with_origin(
+        CollabOrigin::Empty,
+        object_id,
+        vec![],
+        false,
+      )),
+      Some(encoded_collab) => Collab::new_with_source(
+        CollabOrigin::Empty,
+        object_id,
+        DataSource::from(encoded_collab),
+        vec![],
+        false,
+      )
+      .map_err(|err| DatabaseError::Internal(err.into())),
+    }
+  }
+
+  async fn get_collabs(
+    &self,
+    object_ids: Vec<String>,
+    collab_type: CollabType,
+  ) -> Result<EncodeCollabByOid, DatabaseError> {
+    let mut collabs = HashMap::new();
+    for object_id in object_ids {
+      if let Some(encoded_collab) = self
+        .loader
+        .load_collab(&object_id, collab_type.clone())
+        .await
+      {
+        collabs.insert(object_id, encoded_collab);
+      }
+    }
+    Ok(collabs)
+  }
+
+  fn persistence(&self) -> Option<Arc<dyn DatabaseCollabPersistenceService>> {
+    None
+  }
+}
@@ -0,0 +1,65 @@
+use collab_entity::CollabType;
+use collab_folder::View;
+use serde::{Deserialize, Serialize};
+
+/// Version of the archive format. Archives with a greater version can't be restored.
+pub const WORKSPACE_ARCHIVE_VERSION: u32 = 1;
+
+pub(crate) const MANIFEST_FILE: &str = "manifest.json";
+
+/// The content of the `manifest.json` file at the root of a workspace archive.
+///
+/// Every archived collab is stored twice: encoded in `collabs/<object_id>.collab`, and as JSON in
+/// `json/<object_id>.json`. The JSON is used when the encoded collab can't be opened, for
+/// example when the archive is restored by a version that changed the encoding.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+  pub version: u32,
+  pub workspace_id: String,
+  pub workspace_name: String,
+  pub exported_at: i64,
+  /// The views of the workspace. A view comes after its parent, and the children of a view are
+  /// in the same order as in the folder.
+  pub views: Vec<View>,
+  pub objects: Vec<ArchivedObject>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ArchivedObject {
+  /// The document of a document view. The object id is the view id.
+  Document { object_id: String },
+  /// A database and its rows. The JSON of the database contains its rows, so the rows have no
+  /// JSON file of their own.
+  Database {
+    database_id: String,
+    view_ids: Vec<String>,
+    row_ids: Vec<String>,
+    /// The documents of the rows that have one.
+    row_document_ids: Vec<String>,
+  },
+}
+
+impl ArchivedObject {
+  pub fn object_id(&self) -> &str {
+    match self {
+      ArchivedObject::Document { object_id } => object_id,
+      ArchivedObject::Database { database_id, .. } => database_id,
+    }
+  }
+
+  pub fn collab_type(&self) -> CollabType {
+    match self {
+      ArchivedObject::Document { .. } => CollabType::Document,
+      ArchivedObject::Database { .. } => CollabType::Database,
+    }
+  }
+}
+
+pub(crate) fn collab_file_name(object_id: &str) -> String {
+  format!("collabs/{}.collab", object_id)
+}
+
+pub(crate) fn json_file_name(object_id: &str) -> String {
+  format!("json/{}.json", object_id)
+}
@@ -0,0 +1,9 @@
+mod exporter;
+mod loader;
+mod manifest;
+mod restore;
+
+pub use exporter::*;
+pub use loader::*;
+pub use manifest::*;
+pub use restore::*;
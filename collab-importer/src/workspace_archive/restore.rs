@@ -0,0 +1,468 @@
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use collab::entity::EncodedCollab;
+use collab_database::database::{
+  gen_database_id, gen_database_view_id, gen_row_id, get_row_document_id, Database,
+  DatabaseContext, DatabaseData,
+};
+use collab_database::entity::{CreateDatabaseParams, CreateViewParams};
+use collab_database::rows::{CreateRowParams, RowId};
+use collab_database::workspace_database::NoPersistenceDatabaseCollabService;
+use collab_document::blocks::DocumentData;
+use collab_document::document::Document;
+use collab_document::document_data::default_document_data;
+use collab_entity::CollabType;
+use collab_folder::hierarchy_builder::{NestedViews, ParentChildViews};
+use collab_folder::{RepeatedViewIdentifier, View, ViewIdentifier, ViewLayout};
+use fancy_regex::{Captures, Regex};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use zip::ZipArchive;
+
+use crate::error::ImporterError;
+use crate::imported_collab::{ImportType, ImportedCollab, ImportedCollabInfo};
+use crate::notion::page::CollabResource;
+use crate::pipeline::{ImportContext, ImportedSource, WorkspaceImporter};
+use crate::workspace_archive::exporter::document_data_from_encoded_collab;
+use crate::workspace_archive::loader::LoaderDatabaseCollabService;
+use crate::workspace_archive::manifest::{
+  collab_file_name, json_file_name, ArchiveManifest, ArchivedObject, MANIFEST_FILE,
+  WORKSPACE_ARCHIVE_VERSION,
+};
+
+const UUID_PATTERN: &str =
+  r"[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}";
+
+/// A workspace archive written by [crate::workspace_archive::WorkspaceArchiveExporter].
+///
+/// All the object ids are regenerated, so an archive can be restored more than once, into the
+/// same workspace or another one. The references between the objects, like the mentions of a
+/// page or the relations between databases, are updated to the new ids.
+pub struct WorkspaceArchiveSource {
+  pub archive_path: PathBuf,
+}
+
+#[async_trait::async_trait(?Send)]
+impl WorkspaceImporter for WorkspaceArchiveSource {
+  fn source_name(&self) -> &str {
+    "archive"
+  }
+
+  async fn import(&self, context: &ImportContext) -> Result<ImportedSource, ImporterError> {
+    if !self.archive_path.exists() {
+      return Err(ImporterError::FileNotFound);
+    }
+    let archive_path = self.archive_path.clone();
+    let mut archive = tokio::task::spawn_blocking(move || read_archive(archive_path))
+      .await
+      .map_err(|err| ImporterError::Internal(err.into()))??;
+    if archive.manifest.version > WORKSPACE_ARCHIVE_VERSION {
+      return Err(ImporterError::UnsupportedArchiveVersion(
+        archive.manifest.version,
+      ));
+    }
+
+    let id_map = IdMap::new(&archive.manifest)?;
+    let mut infos = vec![];
+    let mut warnings = vec![];
+    let objects = std::mem::take(&mut archive.manifest.objects);
+    for object in &objects {
+      let info = match object {
+        ArchivedObject::Document { object_id } => {
+          let name = archive.view_name(object_id);
+          let data = archive.document_data(object_id, &mut warnings)?;
+          restore_document(&name, &id_map.get(object_id), id_map.remap(&data)?)?
+        },
+        ArchivedObject::Database {
+          database_id,
+          row_document_ids,
+          ..
+        } => {
+          archive
+            .restore_database(database_id, row_document_ids, &id_map, &mut warnings)
+            .await?
+        },
+      };
+      context.report_imported(&info);
+      infos.push(info);
+    }
+
+    // The views whose document was not exported are restored with an empty document.
+    for view in &archive.manifest.views {
+      if view.layout == ViewLayout::Document
+        && !objects.iter().any(|object| object.object_id() == view.id)
+      {
+        let view_id = id_map.get(&view.id);
+        let info = restore_document(&view.name, &view_id, default_document_data(&view_id))?;
+        context.report_imported(&info);
+        infos.push(info);
+      }
+    }
+
+    Ok(ImportedSource {
+      name: archive.manifest.workspace_name.clone(),
+      nested_views: NestedViews {
+        views: restore_views(
+          &archive.manifest.views,
+          &archive.manifest.workspace_id,
+          &context.workspace_id,
+          &id_map,
+        ),
+      },
+      infos,
+      warnings,
+    })
+  }
+}
+
+struct WorkspaceArchive {
+  manifest: ArchiveManifest,
+  files: HashMap<String, Vec<u8>>,
+}
+
+impl WorkspaceArchive {
+  fn view_name(&self, view_id: &str) -> String {
+    self
+      .manifest
+      .views
+      .iter()
+      .find(|view| view.id == view_id)
+      .map(|view| view.name.clone())
+      .unwrap_or_default()
+  }
+
+  fn encoded_collab(&self, object_id: &str) -> Option<EncodedCollab> {
+    let bytes = self.files.get(&collab_file_name(object_id))?;
+    EncodedCollab::decode_from_bytes(bytes).ok()
+  }
+
+  fn json<T: DeserializeOwned>(&self, object_id: &str) -> Result<T, ImporterError> {
+    let bytes = self
+      .files
+      .get(&json_file_name(object_id))
+      .ok_or(ImporterError::FileNotFound)?;
+    serde_json::from_slice(bytes).map_err(|err| ImporterError::Internal(err.into()))
+  }
+
+  /// Reads the document from the encoded collab, or from the JSON if the collab can't be
+  /// opened.
+  fn document_data(
+    &self,
+    document_id: &str,
+    warnings: &mut Vec<String>,
+  ) -> Result<DocumentData, ImporterError> {
+    let data = self.encoded_collab(document_id).and_then(|encoded_collab| {
+      document_data_from_encoded_collab(document_id, encoded_collab).ok()
+    });
+    match data {
+      Some(data) => Ok(data),
+      None => {
+        warnings.push(format!("Restored the document {} from JSON", document_id));
+        self.json(document_id)
+      },
+    }
+  }
+
+  /// Same as [WorkspaceArchive::document_data], for a database and its rows.
+  async fn database_data(
+    &self,
+    database_id: &str,
+    warnings: &mut Vec<String>,
+  ) -> Result<DatabaseData, ImporterError> {
+    let collabs = self
+      .files
+      .keys()
+      .flat_map(|name| {
+        let object_id = name.strip_prefix("collabs/")?.strip_suffix(".collab")?;
+        Some((object_id.to_string(), self.encoded_collab(object_id)?))
+      })
+      .collect::<HashMap<_, _>>();
+    let collab_service = LoaderDatabaseCollabService::new(Arc::new(collabs));
+    match Database::open(database_id, DatabaseContext::new(Arc::new(collab_service))).await {
+      Ok(database) => Ok(database.get_database_data().await),
+      Err(_) => {
+        warnings.push(format!("Restored the database {} from JSON", database_id));
+        self.json(database_id)
+      },
+    }
+  }
+
+  async fn restore_database(
+    &self,
+    database_id: &str,
+    row_document_ids: &[String],
+    id_map: &IdMap,
+    warnings: &mut Vec<String>,
+  ) -> Result<ImportedCollabInfo, ImporterError> {
+    let data = id_map.remap(&self.database_data(database_id, warnings).await?)?;
+    let new_database_id = data.database_id.clone();
+    let view_ids = data.views.iter().map(|view| view.id.clone()).collect();
+    let name = data
+      .views
+      .first()
+      .map(|view| view.name.clone())
+      .unwrap_or_default();
+    let params = create_database_params(data);
+    let context = DatabaseContext::new(Arc::new(NoPersistenceDatabaseCollabService));
+    let mut database = Database::create_with_view(params, context).await?;
+
+    let mut row_documents = vec![];
+    for row_document_id in row_document_ids {
+      let new_row_document_id = id_map.get(row_document_id);
+      let data = self.document_data(row_document_id, warnings)?;
+      row_documents.push(restore_document(
+        "",
+        &new_row_document_id,
+        id_map.remap(&data)?,
+      )?);
+    }
+    for (row_id, row_document_id) in &id_map.row_ids {
+      let has_document = row_document_id
+        .as_ref()
+        .map(|id| row_document_ids.contains(id))
+        .unwrap_or(false);
+      if !has_document {
+        continue;
+      }
+      database
+        .update_row_meta(&RowId::from(id_map.get(row_id)), |meta| {
+          meta.update_is_document_empty(false);
+        })
+        .await;
+    }
+
+    let mut imported_collabs = database
+      .encode_database_collabs()
+      .await?
+      .into_collabs()
+      .into_iter()
+      .map(|collab_info| ImportedCollab {
+        object_id: collab_info.object_id,
+        collab_type: collab_info.collab_type,
+        encoded_collab: collab_info.encoded_collab,
+      })
+      .collect::<Vec<_>>();
+    let mut resources = vec![];
+    let mut new_row_document_ids = vec![];
+    for row_document in row_documents {
+      new_row_document_ids.push(row_document.imported_collabs[0].object_id.clone());
+      imported_collabs.extend(row_document.imported_collabs);
+      resources.extend(row_document.resources);
+    }
+    resources.push(CollabResource {
+      object_id: new_database_id.clone(),
+      files: vec![],
+    });
+
+    Ok(ImportedCollabInfo {
+      name,
+      imported_collabs,
+      resources,
+      import_type: ImportType::Database {
+        database_id: new_database_id,
+        view_ids,
+        row_document_ids: new_row_document_ids,
+      },
+    })
+  }
+}
+
+/// Maps the object ids of the archive to new ids.
+struct IdMap {
+  ids: HashMap<String, String>,
+  /// The rows and their documents, keyed by the old row id.
+  row_ids: HashMap<String, Option<String>>,
+  uuid_regex: Regex,
+}
+
+impl IdMap {
+  fn new(manifest: &ArchiveManifest) -> Result<Self, ImporterError> {
+    let mut ids = HashMap::new();
+    let mut row_ids = HashMap::new();
+    for view in &manifest.views {
+      ids.insert(view.id.clone(), uuid::Uuid::new_v4().to_string());
+    }
+    for object in &manifest.objects {
+      match object {
+        ArchivedObject::Document { object_id } => {
+          ids
+            .entry(object_id.clone())
+            .or_insert_with(|| uuid::Uuid::new_v4().to_string());
+        },
+        ArchivedObject::Database {
+          database_id,
+          view_ids,
+          row_ids: archived_row_ids,
+          row_document_ids,
+        } => {
+          ids.insert(database_id.clone(), gen_database_id());
+          for view_id in view_ids {
+            ids
+              .entry(view_id.clone())
+              .or_insert_with(gen_database_view_id);
+          }
+          for row_id in archived_row_ids {
+            let new_row_id = gen_row_id();
+            let row_document_id = get_row_document_id(&RowId::from(row_id.clone()))?;
+            let row_document_id = if row_document_ids.contains(&row_document_id) {
+              ids.insert(row_document_id.clone(), get_row_document_id(&new_row_id)?);
+              Some(row_document_id)
+            } else {
+              None
+            };
+            ids.insert(row_id.clone(), new_row_id.to_string());
+            row_ids.insert(row_id.clone(), row_document_id);
+          }
+        },
+      }
+    }
+    let uuid_regex = Regex::new(UUID_PATTERN).map_err(|err| ImporterError::Internal(err.into()))?;
+    Ok(Self {
+      ids,
+      row_ids,
+      uuid_regex,
+    })
+  }
+
+  /// Returns the new id of the object. The ids that are not in the archive are kept.
+  fn get(&self, id: &str) -> String {
+    self.ids.get(id).cloned().unwrap_or_else(|| id.to_string())
+  }
+
+  /// Replaces all the ids of the archive found in the value with the new ids.
+  fn remap<T: Serialize + DeserializeOwned>(&self, value: &T) -> Result<T, ImporterError> {
+    let json = serde_json::to_string(value).map_err(|err| ImporterError::Internal(err.into()))?;
+    let json = self
+      .uuid_regex
+      .replace_all(&json, |captures: &Captures| self.get(&captures[0]));
+    serde_json::from_str(&json).map_err(|err| ImporterError::Internal(err.into()))
+  }
+}
+
+fn read_archive(archive_path: PathBuf) -> Result<WorkspaceArchive, ImporterError> {
+  let mut zip = ZipArchive::new(std::fs::File::open(archive_path)?)
+    .map_err(|err| ImporterError::Internal(err.into()))?;
+  let mut files = HashMap::new();
+  for index in 0..zip.len() {
+    let mut file = zip
+      .by_index(index)
+      .map_err(|err| ImporterError::Internal(err.into()))?;
+    let mut content = vec![];
+    file.read_to_end(&mut content)?;
+    files.insert(file.name().to_string(), content);
+  }
+  let manifest = files
+    .remove(MANIFEST_FILE)
+    .ok_or_else(|| ImporterError::InvalidFileType("The archive has no manifest".to_string()))?;
+  let manifest =
+    serde_json::from_slice(&manifest).map_err(|err| ImporterError::Internal(err.into()))?;
+  Ok(WorkspaceArchive { manifest, files })
+}
+
+fn restore_document(
+  name: &str,
+  document_id: &str,
+  data: DocumentData,
+) -> Result<ImportedCollabInfo, ImporterError> {
+  let document = Document::create(document_id, data)?;
+  Ok(ImportedCollabInfo {
+    name: name.to_string(),
+    imported_collabs: vec![ImportedCollab {
+      object_id: document_id.to_string(),
+      collab_type: CollabType::Document,
+      encoded_collab: document.encode_collab()?,
+    }],
+    resources: vec![CollabResource {
+      object_id: document_id.to_string(),
+      files: vec![],
+    }],
+    import_type: ImportType::Document,
+  })
+}
+
+/// Unlike [CreateDatabaseParams::from_database_data], keeps the ids of the database, the views
+/// and the rows, which were already remapped.
+fn create_database_params(data: DatabaseData) -> CreateDatabaseParams {
+  let database_id = data.database_id;
+  let rows = data
+    .rows
+    .into_iter()
+    .map(|row| CreateRowParams {
+      id: row.id,
+      database_id: database_id.clone(),
+      cells: row.cells,
+      height: row.height,
+      visibility: row.visibility,
+      row_position: Default::default(),
+      created_at: row.created_at,
+      modified_at: row.modified_at,
+    })
+    .collect();
+  let views = data
+    .views
+    .into_iter()
+    .map(|view| CreateViewParams {
+      database_id: database_id.clone(),
+      view_id: view.id,
+      name: view.name,
+      layout: view.layout,
+      layout_settings: view.layout_settings,
+      filters: view.filters,
+      group_settings: view.group_settings,
+      sorts: view.sorts,
+      field_settings: view.field_settings,
+      created_at: view.created_at,
+      modified_at: view.modified_at,
+      ..Default::default()
+    })
+    .collect();
+  CreateDatabaseParams {
+    database_id,
+    fields: data.fields,
+    rows,
+    views,
+  }
+}
+
+/// Rebuilds the hierarchy of the views with the new ids. The top level views of the archived
+/// workspace are moved to the workspace the archive is restored into.
+fn restore_views(
+  views: &[View],
+  archived_workspace_id: &str,
+  workspace_id: &str,
+  id_map: &IdMap,
+) -> Vec<ParentChildViews> {
+  views
+    .iter()
+    .filter(|view| view.parent_view_id == archived_workspace_id)
+    .map(|view| restore_view(views, view, workspace_id, id_map))
+    .collect()
+}
+
+fn restore_view(views: &[View], view: &View, parent_id: &str, id_map: &IdMap) -> ParentChildViews {
+  let view_id = id_map.get(&view.id);
+  let children = views
+    .iter()
+    .filter(|child| child.parent_view_id == view.id)
+    .map(|child| restore_view(views, child, &view_id, id_map))
+    .collect::<Vec<_>>();
+
+  let mut restored_view = view.clone();
+  restored_view.id = view_id;
+  restored_view.parent_view_id = parent_id.to_string();
+  restored_view.children = RepeatedViewIdentifier::new(
+    children
+      .iter()
+      .map(|child| ViewIdentifier {
+        id: child.view.id.clone(),
+      })
+      .collect(),
+  );
+  ParentChildViews {
+    view: restored_view,
+    children,
+  }
+}
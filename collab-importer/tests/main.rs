@@ -1,2 +1,6 @@
+mod markdown_dir_test;
 mod notion_test;
+mod pipeline_test;
+mod snapshot_diff_test;
 mod util;
+mod workspace_archive_test;
@@ -0,0 +1,184 @@
+use std::env::temp_dir;
+use std::path::Path;
+
+use collab::core::collab::DataSource;
+use collab::core::origin::CollabOrigin;
+use collab_document::document::Document;
+use collab_document::importer::define::{BlockType, URL_FIELD};
+use collab_importer::error::ImporterError;
+use collab_importer::markdown_dir::{MarkdownDirImporter, UnsupportedKind};
+
+use crate::util::Cleaner;
+
+fn write_file(root: &Path, path: &str, content: &[u8]) {
+  let path = root.join(path);
+  std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+  std::fs::write(path, content).unwrap();
+}
+
+fn create_vault() -> (Cleaner, std::path::PathBuf) {
+  let root = temp_dir()
+    .join(uuid::Uuid::new_v4().to_string())
+    .join("vault");
+  write_file(
+    &root,
+    "Inbox.md",
+    b"---\ntitle: My inbox\ntags:\n  - todo\n---\n\n# Inbox\n\nSee [[Projects]] and ![[diagram.png]]\n\n<div>html</div>\n",
+  );
+  write_file(
+    &root,
+    "Projects.md",
+    b"# Projects\n\n![cover](assets/cover.png)\n",
+  );
+  write_file(&root, "assets/cover.png", b"png");
+  write_file(
+    &root,
+    "Projects/Roadmap.md",
+    b"> [!note]\n> Ship it[^1]\n\n[^1]: soon\n",
+  );
+  write_file(&root, "Archive/2023/Old.markdown", b"old");
+  write_file(&root, "Empty/readme.txt", b"not markdown");
+  write_file(&root, ".obsidian/Settings.md", b"hidden");
+  (Cleaner::new(root.parent().unwrap().to_path_buf()), root)
+}
+
+#[tokio::test]
+async fn import_markdown_dir_test() {
+  let (_cleaner, root) = create_vault();
+  let host = "http://test.appflowy.cloud";
+  let workspace_id = uuid::Uuid::new_v4().to_string();
+  let imported = MarkdownDirImporter::new(1, &root, &workspace_id, host.to_string())
+    .unwrap()
+    .import()
+    .await
+    .unwrap();
+
+  assert_eq!(imported.name, "vault");
+  // The space, Inbox, Projects, Roadmap, Archive, 2023 and Old
+  assert_eq!(imported.infos.len(), 7);
+
+  let views = imported.nested_views.clone().into_inner();
+  assert_eq!(views.len(), 1);
+  let space = &views[0];
+  assert_eq!(space.view.name, "vault");
+  let names = space
+    .children
+    .iter()
+    .map(|view| view.view.name.as_str())
+    .collect::<Vec<_>>();
+  assert_eq!(names, vec!["Archive", "My inbox", "Projects"]);
+
+  let archive = &space.children[0];
+  assert_eq!(archive.children[0].view.name, "2023");
+  assert_eq!(archive.children[0].children[0].view.name, "Old");
+  assert_eq!(
+    archive.children[0].children[0].view.parent_view_id,
+    archive.children[0].view.id
+  );
+
+  let projects = &space.children[2];
+  assert_eq!(projects.children.len(), 1);
+  assert_eq!(projects.children[0].view.name, "Roadmap");
+
+  // The image of the Projects page is uploaded with the page
+  let projects_info = imported
+    .infos
+    .iter()
+    .find(|info| info.imported_collabs[0].object_id == projects.view.id)
+    .unwrap();
+  assert_eq!(projects_info.resources[0].files.len(), 1);
+  assert!(projects_info.resources[0].files[0].ends_with("cover.png"));
+
+  let document = Document::open_with_options(
+    CollabOrigin::Empty,
+    DataSource::DocStateV1(
+      projects_info.imported_collabs[0]
+        .encoded_collab
+        .doc_state
+        .to_vec(),
+    ),
+    &projects.view.id,
+    vec![],
+  )
+  .unwrap();
+  let page_id = document.get_page_id().unwrap();
+  let image_url = document
+    .get_block_children_ids(&page_id)
+    .iter()
+    .filter_map(|block_id| document.get_block_data(block_id))
+    .find(|(block_type, _)| matches!(block_type, BlockType::Image))
+    .and_then(|(_, data)| data.get(URL_FIELD)?.as_str().map(|url| url.to_string()))
+    .unwrap();
+  assert!(image_url.starts_with(&format!(
+    "{host}/api/file_storage/{workspace_id}/v1/blob/{}/",
+    projects.view.id
+  )));
+
+  let mut unsupported = imported
+    .unsupported_constructs
+    .iter()
+    .map(|construct| {
+      (
+        construct.file_path.file_name().unwrap().to_str().unwrap(),
+        construct.kind,
+        construct.line,
+      )
+    })
+    .collect::<Vec<_>>();
+  unsupported.sort_by_key(|(file, _, line)| (file.to_string(), *line));
+  assert_eq!(
+    unsupported,
+    vec![
+      ("Inbox.md", UnsupportedKind::WikiLink, 9),
+      ("Inbox.md", UnsupportedKind::Embed, 9),
+      ("Inbox.md", UnsupportedKind::Html, 11),
+      ("Roadmap.md", UnsupportedKind::Callout, 1),
+      ("Roadmap.md", UnsupportedKind::Footnote, 2),
+      ("Roadmap.md", UnsupportedKind::Footnote, 4),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn import_markdown_dir_without_markdown_test() {
+  let root = temp_dir().join(uuid::Uuid::new_v4().to_string());
+  let _cleaner = Cleaner::new(root.clone());
+  write_file(&root, "notes.txt", b"text");
+
+  let result = MarkdownDirImporter::new(1, &root, "workspace_id", "".to_string())
+    .unwrap()
+    .import()
+    .await;
+  assert!(matches!(result, Err(ImporterError::CannotImport)));
+  assert!(
+    MarkdownDirImporter::new(1, root.join("notes.txt"), "workspace_id", "".to_string()).is_err()
+  );
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn import_markdown_dir_stays_in_the_folder_test() {
+  let base = temp_dir().join(uuid::Uuid::new_v4().to_string());
+  let _cleaner = Cleaner::new(base.clone());
+  let root = base.join("vault");
+  write_file(&base, "secret.png", b"png");
+  write_file(&base, "outside/Leak.md", b"leak");
+  write_file(&root, "Page.md", b"![secret](../secret.png)\n");
+  std::os::unix::fs::symlink(base.join("outside"), root.join("Linked")).unwrap();
+  std::os::unix::fs::symlink(base.join("outside/Leak.md"), root.join("Leak.md")).unwrap();
+
+  let imported = MarkdownDirImporter::new(1, &root, "workspace_id", "".to_string())
+    .unwrap()
+    .import()
+    .await
+    .unwrap();
+  // The space and Page, the symbolic links are skipped.
+  assert_eq!(imported.infos.len(), 2);
+  let page_info = imported
+    .infos
+    .iter()
+    .find(|info| info.name == "Page")
+    .unwrap();
+  // The image outside of the folder is not uploaded.
+  assert!(page_info.resources[0].files.is_empty());
+}
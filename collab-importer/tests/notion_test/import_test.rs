@@ -17,7 +17,9 @@ use collab_entity::CollabType;
 use collab_folder::hierarchy_builder::ParentChildViews;
 use collab_folder::{default_folder_data, Folder, View};
 use collab_importer::error::ImporterError;
-use collab_importer::imported_collab::{import_notion_zip_file, ImportType};
+use collab_importer::imported_collab::{
+  import_notion_workspace, import_notion_zip_file, ImportType,
+};
 use collab_importer::notion::page::NotionPage;
 use collab_importer::notion::{is_csv_contained_cached, CSVContentCache, NotionImporter};
 use collab_importer::util::{parse_csv, CSVRow};
@@ -805,3 +807,39 @@ $"#;
 
   vec![a, b, c, d]
 }
+
+#[tokio::test]
+async fn import_notion_workspace_test() {
+  let workspace_id = uuid::Uuid::new_v4().to_string();
+  let host = "http://test.appflowy.cloud";
+  let zip_file_path = PathBuf::from("./tests/asset/project&task.zip");
+  let temp_dir = temp_dir().join(uuid::Uuid::new_v4().to_string());
+  std::fs::create_dir_all(&temp_dir).unwrap();
+  let workspace = import_notion_workspace(1, host, &workspace_id, zip_file_path, temp_dir)
+    .await
+    .unwrap();
+
+  assert_eq!(workspace.name, "project&task");
+  assert_eq!(workspace.infos.len(), 4);
+  let views = workspace.nested_views.clone().into_inner();
+  assert_eq!(views.len(), 1);
+  let object_ids = workspace
+    .infos
+    .iter()
+    .flat_map(|info| info.imported_collabs.iter())
+    .map(|collab| collab.object_id.clone())
+    .collect::<Vec<_>>();
+  assert!(object_ids.contains(&views[0].view.id));
+
+  // The two files of the Projects database
+  let manifest = &workspace.asset_manifest;
+  assert_eq!(manifest.assets.len(), 2);
+  assert_eq!(manifest.total_size(), 1143952);
+  for asset in &manifest.assets {
+    assert!(asset.url.starts_with(&format!(
+      "{host}/api/file_storage/{workspace_id}/v1/blob/{}/",
+      asset.object_id
+    )));
+    assert!(asset.url.ends_with(&asset.file_id));
+  }
+}
@@ -0,0 +1,102 @@
+use std::env::temp_dir;
+use std::sync::{Arc, Mutex};
+
+use collab_folder::ViewLayout;
+use collab_importer::imported_collab::ImportType;
+use collab_importer::pipeline::{
+  run_import, CsvFileSource, ImportContext, ImportProgress, MarkdownDirSource, WorkspaceImporter,
+};
+
+use crate::util::Cleaner;
+
+fn context_with_progress(workspace_id: &str) -> (ImportContext, Arc<Mutex<Vec<ImportProgress>>>) {
+  let progress = Arc::new(Mutex::new(vec![]));
+  let cloned_progress = progress.clone();
+  let context = ImportContext::new(1, workspace_id, "http://test.appflowy.cloud")
+    .with_progress_reporter(move |p| cloned_progress.lock().unwrap().push(p));
+  (context, progress)
+}
+
+#[tokio::test]
+async fn import_csv_file_with_pipeline_test() {
+  let dir = temp_dir().join(uuid::Uuid::new_v4().to_string());
+  let _cleaner = Cleaner::new(dir.clone());
+  std::fs::create_dir_all(&dir).unwrap();
+  let file_path = dir.join("Tasks.csv");
+  std::fs::write(&file_path, "Name,Done\nWrite docs,Yes\nShip,No\n").unwrap();
+
+  let workspace_id = uuid::Uuid::new_v4().to_string();
+  let (context, progress) = context_with_progress(&workspace_id);
+  let source = CsvFileSource { file_path };
+  let workspace = run_import(&source, &context).await.unwrap();
+
+  assert_eq!(workspace.name, "Tasks");
+  assert_eq!(workspace.infos.len(), 1);
+  let views = workspace.nested_views.clone().into_inner();
+  assert_eq!(views.len(), 1);
+  assert_eq!(views[0].view.layout, ViewLayout::Grid);
+  assert_eq!(views[0].view.parent_view_id, workspace_id);
+  match &workspace.infos[0].import_type {
+    ImportType::Database { view_ids, .. } => assert!(view_ids.contains(&views[0].view.id)),
+    ImportType::Document => panic!("The CSV file should be imported as a database"),
+  }
+  // The database and its two rows
+  assert_eq!(workspace.infos[0].imported_collabs.len(), 3);
+
+  // Each collab is reported as it's imported, then the database.
+  let mut expected = vec![ImportProgress::Started {
+    source: source.source_name().to_string(),
+  }];
+  expected.extend(workspace.infos[0].imported_collabs.iter().map(|collab| {
+    ImportProgress::ImportedCollab {
+      object_id: collab.object_id.clone(),
+      collab_type: collab.collab_type.clone(),
+    }
+  }));
+  expected.push(ImportProgress::DidImport {
+    name: "Tasks".to_string(),
+    num_of_collabs: 3,
+  });
+  expected.push(ImportProgress::Finished {
+    num_of_collabs: 3,
+    num_of_assets: 0,
+  });
+  assert_eq!(progress.lock().unwrap().clone(), expected);
+}
+
+#[tokio::test]
+async fn import_markdown_dir_with_pipeline_test() {
+  let root = temp_dir().join(uuid::Uuid::new_v4().to_string());
+  let _cleaner = Cleaner::new(root.clone());
+  std::fs::create_dir_all(root.join("images")).unwrap();
+  std::fs::write(root.join("images/logo.png"), b"png").unwrap();
+  std::fs::write(
+    root.join("Home.md"),
+    "# Home\n\n![logo](images/logo.png)\n\n<br>\n",
+  )
+  .unwrap();
+
+  let workspace_id = uuid::Uuid::new_v4().to_string();
+  let (context, progress) = context_with_progress(&workspace_id);
+  let workspace = run_import(&MarkdownDirSource { path: root }, &context)
+    .await
+    .unwrap();
+
+  // The space and the Home page
+  assert_eq!(workspace.infos.len(), 2);
+  assert_eq!(workspace.asset_manifest.assets.len(), 1);
+  let asset = &workspace.asset_manifest.assets[0];
+  assert_eq!(
+    asset.url,
+    context.file_url(&asset.object_id, &asset.file_id)
+  );
+  assert_eq!(workspace.warnings.len(), 1);
+  assert!(workspace.warnings[0].ends_with("Home.md:5: HTML"));
+  assert_eq!(
+    progress.lock().unwrap().last(),
+    Some(&ImportProgress::Finished {
+      num_of_collabs: 2,
+      num_of_assets: 1
+    })
+  );
+}
@@ -0,0 +1,40 @@
+use collab_document::blocks::Block;
+use collab_document::document::Document;
+use collab_document::document_data::default_document_data;
+use collab_entity::CollabType;
+use collab_importer::snapshot_diff::{diff_snapshots, SnapshotDiff};
+
+#[test]
+fn diff_document_snapshots_test() {
+  let mut document = Document::create("d1", default_document_data("d1")).unwrap();
+  let older = document.encode_collab().unwrap();
+  let page_id = document.get_page_id().unwrap();
+  let block = Block {
+    id: "block_1".to_string(),
+    ty: "paragraph".to_string(),
+    parent: page_id,
+    children: "".to_string(),
+    external_id: None,
+    external_type: None,
+    data: Default::default(),
+  };
+  document.insert_block(block, None).unwrap();
+  let newer = document.encode_collab().unwrap();
+
+  let diff = match diff_snapshots(&older, &newer, &CollabType::Document).unwrap() {
+    SnapshotDiff::Document(diff) => diff,
+    diff => panic!("unexpected diff: {:?}", diff),
+  };
+  assert_eq!(diff.inserted_blocks.len(), 1);
+  assert_eq!(diff.inserted_blocks[0].block_id, "block_1");
+  assert!(diff_snapshots(&newer, &newer, &CollabType::Document)
+    .unwrap()
+    .is_empty());
+}
+
+#[test]
+fn diff_unsupported_snapshots_test() {
+  let document = Document::create("d1", default_document_data("d1")).unwrap();
+  let encoded = document.encode_collab().unwrap();
+  assert!(diff_snapshots(&encoded, &encoded, &CollabType::UserAwareness).is_err());
+}
@@ -14,7 +14,7 @@ use tracing_subscriber::EnvFilter;
 pub struct Cleaner(PathBuf);
 
 impl Cleaner {
-  fn new(dir: PathBuf) -> Self {
+  pub fn new(dir: PathBuf) -> Self {
     Cleaner(dir)
   }
 
@@ -0,0 +1,329 @@
+use std::collections::HashMap;
+use std::env::temp_dir;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use collab::core::origin::CollabOrigin;
+use collab::entity::EncodedCollab;
+use collab::preclude::Collab;
+use collab_database::database::{Database, DatabaseContext};
+use collab_database::workspace_database::DatabaseMeta;
+use collab_document::document::Document;
+use collab_folder::{default_folder_data, Folder};
+use collab_importer::error::ImporterError;
+use collab_importer::imported_collab::ImportType;
+use collab_importer::pipeline::{
+  run_import, CsvFileSource, ImportContext, ImportedWorkspace, MarkdownDirSource,
+};
+use collab_importer::workspace_archive::{
+  ArchivedObject, LoaderDatabaseCollabService, WorkspaceArchiveExporter, WorkspaceArchiveSource,
+  WORKSPACE_ARCHIVE_VERSION,
+};
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+use crate::util::Cleaner;
+
+struct ArchiveTest {
+  _cleaner: Cleaner,
+  dir: PathBuf,
+  workspace_id: String,
+  folder: Folder,
+  collabs: HashMap<String, EncodedCollab>,
+  database_metas: Vec<DatabaseMeta>,
+}
+
+/// Creates a workspace with the pages of a markdown folder and a grid imported from a CSV file.
+async fn create_workspace() -> ArchiveTest {
+  let dir = temp_dir().join(uuid::Uuid::new_v4().to_string());
+  let cleaner = Cleaner::new(dir.clone());
+  std::fs::create_dir_all(dir.join("notes/Projects")).unwrap();
+  std::fs::write(dir.join("notes/Home.md"), "# Home\n\nWelcome home\n").unwrap();
+  std::fs::write(dir.join("notes/Projects/Roadmap.md"), "Ship the archive\n").unwrap();
+  std::fs::write(
+    dir.join("Tasks.csv"),
+    "Name,Status\nWrite,Done\nReview,Todo\n",
+  )
+  .unwrap();
+
+  let workspace_id = uuid::Uuid::new_v4().to_string();
+  let context = ImportContext::new(1, workspace_id.as_str(), "http://test.appflowy.cloud");
+  let notes = run_import(
+    &MarkdownDirSource {
+      path: dir.join("notes"),
+    },
+    &context,
+  )
+  .await
+  .unwrap();
+  let tasks = run_import(
+    &CsvFileSource {
+      file_path: dir.join("Tasks.csv"),
+    },
+    &context,
+  )
+  .await
+  .unwrap();
+
+  let collab = Collab::new(1, &workspace_id, "1", vec![], false);
+  let mut folder = Folder::create(1, collab, None, default_folder_data(&workspace_id));
+  let mut collabs = HashMap::new();
+  let mut database_metas = vec![];
+  for workspace in [notes, tasks] {
+    folder.insert_nested_views(workspace.nested_views.clone().into_inner());
+    for info in workspace.infos.iter() {
+      if let ImportType::Database {
+        database_id,
+        view_ids,
+        ..
+      } = &info.import_type
+      {
+        database_metas.push(DatabaseMeta {
+          database_id: database_id.clone(),
+          created_at: 0,
+          linked_views: view_ids.clone(),
+        });
+      }
+      for collab in &info.imported_collabs {
+        collabs.insert(collab.object_id.clone(), collab.encoded_collab.clone());
+      }
+    }
+  }
+
+  ArchiveTest {
+    _cleaner: cleaner,
+    dir,
+    workspace_id,
+    folder,
+    collabs,
+    database_metas,
+  }
+}
+
+impl ArchiveTest {
+  async fn export(&self) -> PathBuf {
+    let archive_path = self.dir.join(format!("{}.zip", uuid::Uuid::new_v4()));
+    WorkspaceArchiveExporter::new(
+      &self.folder,
+      self.database_metas.clone(),
+      Arc::new(self.collabs.clone()),
+    )
+    .unwrap()
+    .export_to_file(&archive_path)
+    .await
+    .unwrap();
+    archive_path
+  }
+}
+
+async fn restore(archive_path: &Path, workspace_id: &str) -> ImportedWorkspace {
+  let context = ImportContext::new(1, workspace_id, "http://test.appflowy.cloud");
+  run_import(
+    &WorkspaceArchiveSource {
+      archive_path: archive_path.to_path_buf(),
+    },
+    &context,
+  )
+  .await
+  .unwrap()
+}
+
+fn restored_collabs(workspace: &ImportedWorkspace) -> HashMap<String, EncodedCollab> {
+  workspace
+    .infos
+    .iter()
+    .flat_map(|info| info.imported_collabs.iter())
+    .map(|collab| (collab.object_id.clone(), collab.encoded_collab.clone()))
+    .collect()
+}
+
+#[tokio::test]
+async fn export_and_restore_workspace_archive_test() {
+  let test = create_workspace().await;
+  let archive_path = test.export().await;
+
+  let new_workspace_id = uuid::Uuid::new_v4().to_string();
+  let restored = restore(&archive_path, &new_workspace_id).await;
+  assert!(restored.warnings.is_empty());
+
+  // The space of the notes and the grid
+  let views = restored.nested_views.clone().into_inner();
+  assert_eq!(views.len(), 2);
+  assert!(views
+    .iter()
+    .all(|view| view.view.parent_view_id == new_workspace_id));
+  let old_view_ids = test
+    .folder
+    .get_all_views()
+    .into_iter()
+    .map(|view| view.id.clone())
+    .collect::<Vec<_>>();
+  let flatten_views = restored.nested_views.flatten_views();
+  // The space, Home, Projects, Roadmap and the grid
+  assert_eq!(flatten_views.len(), 5);
+  assert!(flatten_views
+    .iter()
+    .all(|view| !old_view_ids.contains(&view.id)));
+
+  let notes = views.iter().find(|view| view.view.name == "notes").unwrap();
+  let projects = notes
+    .children
+    .iter()
+    .find(|view| view.view.name == "Projects")
+    .unwrap();
+  let roadmap = &projects.children[0];
+  assert_eq!(roadmap.view.name, "Roadmap");
+  assert_eq!(roadmap.view.parent_view_id, projects.view.id);
+
+  let collabs = restored_collabs(&restored);
+  let document = Document::open_with_options(
+    CollabOrigin::Empty,
+    collabs[&roadmap.view.id].clone().into(),
+    &roadmap.view.id,
+    vec![],
+  )
+  .unwrap();
+  assert_eq!(document.to_plain_text().unwrap().trim(), "Ship the archive");
+
+  let (database_id, view_ids) = restored
+    .infos
+    .iter()
+    .find_map(|info| match &info.import_type {
+      ImportType::Database {
+        database_id,
+        view_ids,
+        ..
+      } => Some((database_id.clone(), view_ids.clone())),
+      ImportType::Document => None,
+    })
+    .unwrap();
+  let grid = views.iter().find(|view| view.view.name == "Tasks").unwrap();
+  assert!(view_ids.contains(&grid.view.id));
+  assert!(!test
+    .database_metas
+    .iter()
+    .any(|meta| meta.database_id == database_id));
+
+  let collab_service = LoaderDatabaseCollabService::new(Arc::new(collabs));
+  let database = Database::open(&database_id, DatabaseContext::new(Arc::new(collab_service)))
+    .await
+    .unwrap();
+  let rows = database.get_database_data().await.rows;
+  assert_eq!(rows.len(), 2);
+  assert!(rows.iter().all(|row| row.database_id == database_id));
+
+  // Restoring the same archive again creates new objects
+  let restored_again = restore(&archive_path, &new_workspace_id).await;
+  let ids = restored_collabs(&restored_again);
+  assert!(restored_collabs(&restored)
+    .keys()
+    .all(|object_id| !ids.contains_key(object_id)));
+}
+
+#[tokio::test]
+async fn restore_unsupported_archive_version_test() {
+  let test = create_workspace().await;
+  let archive_path = test.dir.join("future.zip");
+  let mut writer = ZipWriter::new(std::fs::File::create(&archive_path).unwrap());
+  writer
+    .start_file("manifest.json", FileOptions::default())
+    .unwrap();
+  let manifest = serde_json::json!({
+    "version": WORKSPACE_ARCHIVE_VERSION + 1,
+    "workspace_id": test.workspace_id,
+    "workspace_name": "",
+    "exported_at": 0,
+    "views": [],
+    "objects": [],
+  });
+  writer.write_all(manifest.to_string().as_bytes()).unwrap();
+  writer.finish().unwrap();
+
+  let context = ImportContext::new(1, "workspace_id", "");
+  let result = run_import(&WorkspaceArchiveSource { archive_path }, &context).await;
+  assert!(matches!(
+    result,
+    Err(ImporterError::UnsupportedArchiveVersion(version)) if version == WORKSPACE_ARCHIVE_VERSION + 1
+  ));
+}
+
+#[tokio::test]
+async fn export_manifest_test() {
+  let test = create_workspace().await;
+  let archive_path = test.dir.join("manifest.zip");
+  let manifest = WorkspaceArchiveExporter::new(
+    &test.folder,
+    test.database_metas.clone(),
+    Arc::new(test.collabs.clone()),
+  )
+  .unwrap()
+  .export_to_file(&archive_path)
+  .await
+  .unwrap();
+
+  assert_eq!(manifest.version, WORKSPACE_ARCHIVE_VERSION);
+  assert_eq!(manifest.workspace_id, test.workspace_id);
+  // The space, Home, Projects, Roadmap and the grid
+  assert_eq!(manifest.views.len(), 5);
+  let databases = manifest
+    .objects
+    .iter()
+    .filter_map(|object| match object {
+      ArchivedObject::Database { row_ids, .. } => Some(row_ids.len()),
+      ArchivedObject::Document { .. } => None,
+    })
+    .collect::<Vec<_>>();
+  assert_eq!(databases, vec![2]);
+  assert_eq!(manifest.objects.len(), 5);
+}
+
+#[tokio::test]
+async fn restore_document_from_json_test() {
+  let test = create_workspace().await;
+  let archive_path = test.export().await;
+  let roadmap_id = test
+    .folder
+    .get_all_views()
+    .into_iter()
+    .find(|view| view.name == "Roadmap")
+    .unwrap()
+    .id
+    .clone();
+
+  // Corrupt the encoded collab of the Roadmap document
+  let mut zip = zip::ZipArchive::new(std::fs::File::open(&archive_path).unwrap()).unwrap();
+  let corrupted_path = test.dir.join("corrupted.zip");
+  let mut writer = ZipWriter::new(std::fs::File::create(&corrupted_path).unwrap());
+  for index in 0..zip.len() {
+    let mut file = zip.by_index(index).unwrap();
+    let mut content = vec![];
+    std::io::Read::read_to_end(&mut file, &mut content).unwrap();
+    if file.name() == format!("collabs/{}.collab", roadmap_id) {
+      content = vec![1, 2, 3];
+    }
+    writer
+      .start_file(file.name(), FileOptions::default())
+      .unwrap();
+    writer.write_all(&content).unwrap();
+  }
+  writer.finish().unwrap();
+
+  let restored = restore(&corrupted_path, &uuid::Uuid::new_v4().to_string()).await;
+  assert_eq!(restored.warnings.len(), 1);
+  assert!(restored.warnings[0].contains(&roadmap_id));
+  let roadmap = restored
+    .nested_views
+    .flatten_views()
+    .into_iter()
+    .find(|view| view.name == "Roadmap")
+    .unwrap();
+  let document = Document::open_with_options(
+    CollabOrigin::Empty,
+    restored_collabs(&restored)[&roadmap.id].clone().into(),
+    &roadmap.id,
+    vec![],
+  )
+  .unwrap();
+  assert_eq!(document.to_plain_text().unwrap().trim(), "Ship the archive");
+}
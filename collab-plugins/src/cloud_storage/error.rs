@@ -1,3 +1,5 @@
+use collab::error::{ClassifiedError, ErrorCode};
+
 #[derive(Debug, thiserror::Error)]
 pub enum SyncError {
   #[error("failed to deserialize message: {0}")]
@@ -12,6 +14,22 @@ pub enum SyncError {
   #[error(transparent)]
   IO(#[from] std::io::Error),
 
+  #[error("received {received} of the {total} chunks of the payload")]
+  IncompletePayload { received: usize, total: usize },
+
   #[error("Internal failure: {0}")]
   Internal(#[from] Box<dyn std::error::Error + Send + Sync>),
 }
+
+impl ClassifiedError for SyncError {
+  fn code(&self) -> ErrorCode {
+    match self {
+      SyncError::DecodingError(_) => ErrorCode::SyncDecode,
+      SyncError::SerdeError(_) => ErrorCode::InvalidData,
+      SyncError::TokioTask(_) => ErrorCode::SyncTask,
+      SyncError::IO(_) => ErrorCode::SyncIO,
+      SyncError::IncompletePayload { .. } => ErrorCode::InvalidData,
+      SyncError::Internal(_) => ErrorCode::Internal,
+    }
+  }
+}
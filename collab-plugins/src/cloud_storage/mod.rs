@@ -1,11 +1,16 @@
+pub use error::SyncError;
 pub use remote_collab::{
   RemoteCollabSnapshot, RemoteCollabState, RemoteCollabStorage, RemoteUpdateReceiver,
   RemoteUpdateSender,
 };
+pub use spawner::*;
+pub use update_size::*;
+pub use webhook::*;
 pub use yrs::merge_updates_v1;
 pub use yrs::updates::decoder::Decode;
 pub use yrs::Update as YrsUpdate;
 
+#[cfg(not(target_arch = "wasm32"))]
 pub mod postgres;
 
 mod channel;
@@ -13,3 +18,6 @@ mod error;
 mod msg;
 mod remote_collab;
 mod sink;
+mod spawner;
+mod update_size;
+mod webhook;
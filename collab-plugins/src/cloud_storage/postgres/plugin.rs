@@ -17,6 +17,8 @@ use collab_entity::CollabObject;
 
 use crate::cloud_storage::remote_collab::{RemoteCollab, RemoteCollabStorage};
 use crate::cloud_storage::sink::{SinkConfig, SinkStrategy};
+use crate::cloud_storage::spawner::Spawner;
+use crate::cloud_storage::update_size::UpdateSizePolicy;
 use crate::CollabKVDB;
 
 pub struct SupabaseDBPlugin {
@@ -28,9 +30,11 @@ pub struct SupabaseDBPlugin {
   remote_collab_storage: Arc<dyn RemoteCollabStorage>,
   pending_updates: Arc<RwLock<Vec<Vec<u8>>>>,
   is_first_sync_done: Arc<AtomicBool>,
+  spawner: Arc<dyn Spawner>,
 }
 
 impl SupabaseDBPlugin {
+  #[allow(clippy::too_many_arguments)]
   pub fn new(
     uid: i64,
     object: CollabObject,
@@ -38,6 +42,8 @@ impl SupabaseDBPlugin {
     sync_per_secs: u64,
     remote_collab_storage: Arc<dyn RemoteCollabStorage>,
     local_collab_storage: Weak<CollabKVDB>,
+    update_size: UpdateSizePolicy,
+    spawner: Arc<dyn Spawner>,
   ) -> Self {
     let pending_updates = Arc::new(RwLock::from(Vec::new()));
     let is_first_sync_done = Arc::new(AtomicBool::new(false));
@@ -46,25 +52,27 @@ impl SupabaseDBPlugin {
       .with_timeout(10)
       .with_strategy(SinkStrategy::FixInterval(Duration::from_secs(
         sync_per_secs,
-      )));
+      )))
+      .with_update_size_policy(update_size);
     let remote_collab = Arc::new(RemoteCollab::new(
       object.clone(),
       remote_collab_storage.clone(),
       config,
       local_collab.clone(),
+      spawner.clone(),
     ));
 
     // Subscribe the sync state from the remote collab
     let remote_sync_state = remote_collab.subscribe_sync_state();
     let mut remote_sync_state_stream = WatchStream::new(remote_sync_state);
     let weak_local_collab = local_collab.clone();
-    tokio::spawn(async move {
+    spawner.spawn(Box::pin(async move {
       while let Some(new_state) = remote_sync_state_stream.next().await {
         if let Some(local_collab) = weak_local_collab.upgrade() {
           local_collab.read().await.set_sync_state(new_state);
         }
       }
-    });
+    }));
 
     Self {
       uid,
@@ -75,6 +83,7 @@ impl SupabaseDBPlugin {
       is_first_sync_done,
       local_collab_storage,
       remote_collab_storage,
+      spawner,
     }
   }
 }
@@ -95,9 +104,9 @@ impl CollabPlugin for SupabaseDBPlugin {
       is_first_sync_done: Arc::downgrade(&self.is_first_sync_done),
     };
 
-    tokio::spawn(async move {
+    self.spawner.spawn(Box::pin(async move {
       let _ = Retry::spawn(retry_strategy, action).await;
-    });
+    }));
   }
 
   fn receive_local_update(&self, origin: &CollabOrigin, object_id: &str, update: &[u8]) {
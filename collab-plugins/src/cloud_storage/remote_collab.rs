@@ -14,7 +14,6 @@ use collab::preclude::Collab;
 use collab_entity::CollabObject;
 use rand::random;
 use serde::Deserialize;
-use tokio::spawn;
 use tokio::sync::mpsc::unbounded_channel;
 use tokio::sync::watch;
 use tokio_stream::wrappers::WatchStream;
@@ -28,6 +27,8 @@ use crate::cloud_storage::msg::{CollabSinkMessage, MsgId};
 use crate::cloud_storage::sink::{
   CollabSink, CollabSinkRunner, MsgIdCounter, SinkConfig, SinkState,
 };
+use crate::cloud_storage::spawner::Spawner;
+use crate::cloud_storage::update_size::{PayloadChunk, UpdateSizePolicy};
 
 /// The [RemoteCollab] is used to sync the local collab to the remote.
 pub struct RemoteCollab {
@@ -38,6 +39,7 @@ pub struct RemoteCollab {
   /// to the remote via the [RemoteCollabStorage].
   sink: Arc<CollabSink<TokioUnboundedSink<Message>, Message>>,
   sync_state: Arc<watch::Sender<SyncState>>,
+  update_size: UpdateSizePolicy,
   #[allow(dead_code)]
   is_init_sync_finish: Arc<AtomicBool>,
 }
@@ -52,11 +54,13 @@ impl RemoteCollab {
   /// Create a new remote collab.
   /// `timeout` is the time to wait for the server to ack the message.
   /// If the server does not ack the message in time, the message will be sent again.
+  /// The background tasks of the sync run on the given [Spawner].
   pub fn new(
     object: CollabObject,
     storage: Arc<dyn RemoteCollabStorage>,
     config: SinkConfig,
     local_collab: Weak<RwLock<Collab>>,
+    spawner: Arc<dyn Spawner>,
   ) -> Self {
     let is_init_sync_finish = Arc::new(AtomicBool::new(false));
     let sync_state = Arc::new(watch::channel(SyncState::InitSyncBegin).0);
@@ -70,6 +74,7 @@ impl RemoteCollab {
     let weak_storage = Arc::downgrade(&storage);
     let (notifier, notifier_rx) = watch::channel(false);
     let (sync_state_tx, sink_state_rx) = watch::channel(SinkState::Init);
+    let update_size = config.update_size;
     let collab_sink = Arc::new(CollabSink::new(
       object.uid,
       TokioUnboundedSink(sink),
@@ -77,13 +82,14 @@ impl RemoteCollab {
       sync_state_tx,
       RngMsgIdCounter::new(),
       config,
+      spawner.clone(),
     ));
 
     // spawns an asynchronous task to continuously listen to the updates stream
     // and process them as they come in.
     let cloned_is_init_sync_finish = is_init_sync_finish.clone();
     if let Some(mut collab_stream) = storage.subscribe_remote_updates(&object) {
-      spawn(async move {
+      spawner.spawn(Box::pin(async move {
         while let Some(update) = collab_stream.recv().await {
           if !cloned_is_init_sync_finish.load(std::sync::atomic::Ordering::SeqCst) {
             continue;
@@ -101,14 +107,14 @@ impl RemoteCollab {
             }
           }
         }
-      });
+      }));
     }
 
     let weak_collab_sink = Arc::downgrade(&collab_sink);
     let weak_sync_state = Arc::downgrade(&sync_state);
     let mut sink_state_stream = WatchStream::new(sink_state_rx);
     // Subscribe the sink state stream and update the sync state in the background.
-    spawn(async move {
+    spawner.spawn(Box::pin(async move {
       while let Some(collab_state) = sink_state_stream.next().await {
         if let Some(sync_state) = weak_sync_state.upgrade() {
           match collab_state {
@@ -124,78 +130,90 @@ impl RemoteCollab {
           }
         }
       }
-    });
+    }));
 
     // Spawn a task to receive updates from the [CollabSink] and send updates to
     // the remote storage.
     let cloned_is_init_sync_finish = is_init_sync_finish.clone();
-    spawn(async move {
+    let cloned_spawner = spawner.clone();
+    spawner.spawn(Box::pin(async move {
       while let Some(message) = stream.recv().await {
         if let Some(storage) = weak_storage.upgrade() {
           if !storage.is_enable() {
             // If the storage is not enable, it will wait for 300ms and try again.
-            // Return the time slice to the tokio scheduler.
-            tokio::time::sleep(Duration::from_millis(300)).await;
+            // Return the time slice to the scheduler.
+            cloned_spawner.sleep(Duration::from_millis(300)).await;
             continue;
           }
           let is_init_msg = message.is_init_msg();
           trace!("send message: {}", message);
-          match message.split() {
-            Ok((object, msg_id, payload)) => {
-              // If the message is init message, it will flush all the updates to the remote.
-              if is_init_msg {
-                tracing::trace!("send init sync {}:{}", object, msg_id);
-                match storage.send_init_sync(&object, msg_id, payload).await {
-                  Ok(_) => {
-                    if let Some(collab_sink) = weak_collab_sink.upgrade() {
-                      collab_sink.ack_msg(&object.object_id, msg_id).await;
-                      cloned_is_init_sync_finish.store(true, std::sync::atomic::Ordering::SeqCst);
-                    }
-                  },
-                  Err(e) => {
-                    tracing::error!(
-                      "send {}:{} init sync failed: {:?}",
-                      object.object_id,
-                      msg_id,
-                      e
-                    )
-                  },
-                }
-              } else {
-                tracing::trace!("send update {}:{}", object, msg_id);
-                match storage.send_update(&object, msg_id, payload).await {
-                  Ok(_) => {
-                    tracing::debug!("ack update {}:{}", object, msg_id);
-                    if let Some(collab_sink) = weak_collab_sink.upgrade() {
-                      collab_sink.ack_msg(&object.object_id, msg_id).await;
-                    }
-                  },
-                  Err(e) => tracing::error!(
-                    "send {}:{} update failed: {:?}",
-                    object.object_id,
-                    msg_id,
-                    e
-                  ),
+          let (object, msg_id, payload) = match message.split() {
+            Ok(value) => value,
+            Err(e) => {
+              tracing::error!("🔴Failed to split message: {:?}", e);
+              continue;
+            },
+          };
+          // A payload that exceeds the max update size is sent in chunks, the init sync
+          // included, if the remote can reassemble them. It flushes all the updates to the
+          // remote.
+          let result = if update_size.is_oversized(payload.len()) && storage.supports_chunks() {
+            tracing::trace!(
+              "send {}:{} in chunks, payload_len:{}",
+              object,
+              msg_id,
+              payload.len()
+            );
+            send_chunks(
+              storage.as_ref(),
+              &object,
+              msg_id,
+              &payload,
+              update_size.max_update_size,
+              is_init_msg,
+            )
+            .await
+          } else if is_init_msg {
+            tracing::trace!("send init sync {}:{}", object, msg_id);
+            storage.send_init_sync(&object, msg_id, payload).await
+          } else {
+            tracing::trace!("send update {}:{}", object, msg_id);
+            storage.send_update(&object, msg_id, payload).await
+          };
+          match result {
+            Ok(_) => {
+              tracing::debug!("ack message {}:{}", object, msg_id);
+              if let Some(collab_sink) = weak_collab_sink.upgrade() {
+                collab_sink.ack_msg(&object.object_id, msg_id).await;
+                if is_init_msg {
+                  cloned_is_init_sync_finish.store(true, std::sync::atomic::Ordering::SeqCst);
                 }
               }
             },
-            Err(e) => tracing::error!("🔴Failed to split message: {:?}", e),
+            Err(e) => tracing::error!(
+              "send {}:{} {} failed: {:?}",
+              object.object_id,
+              msg_id,
+              if is_init_msg { "init sync" } else { "update" },
+              e
+            ),
           }
         }
       }
-    });
+    }));
 
     // Spawn a task that boost the [CollabSink]
-    spawn(CollabSinkRunner::run(
+    spawner.spawn(Box::pin(CollabSinkRunner::run(
       Arc::downgrade(&collab_sink),
       notifier_rx,
-    ));
+    )));
     Self {
       object,
       collab,
       storage,
       sink: collab_sink,
       sync_state,
+      update_size,
       is_init_sync_finish,
     }
   }
@@ -391,6 +409,28 @@ pub trait RemoteCollabStorage: Send + Sync + 'static {
     update: Vec<u8>,
   ) -> Result<(), anyhow::Error>;
 
+  /// Return true if the remote reassembles the payloads sent with [Self::send_chunk]. Otherwise,
+  /// the payloads are sent as a whole whatever their size.
+  fn supports_chunks(&self) -> bool {
+    false
+  }
+
+  /// Send a chunk of a payload that exceeds the max update size of the [UpdateSizePolicy]. The
+  /// chunks of a payload are sent in order with the same id, the remote applies the payload, as
+  /// an update or as the init sync, once it received all of them. Only called when
+  /// [Self::supports_chunks] returns true.
+  async fn send_chunk(
+    &self,
+    object: &CollabObject,
+    _id: MsgId,
+    _chunk: PayloadChunk,
+  ) -> Result<(), anyhow::Error> {
+    Err(anyhow::anyhow!(
+      "the remote storage of {} doesn't support chunked payloads",
+      object.object_id
+    ))
+  }
+
   /// The init sync is used to send the initial state of the remote collab to the remote storage.
   /// The init_update contains all the missing updates of the remote collab compared to the local.
   async fn send_init_sync(
@@ -441,6 +481,19 @@ where
     (**self).send_update(object, id, update).await
   }
 
+  fn supports_chunks(&self) -> bool {
+    (**self).supports_chunks()
+  }
+
+  async fn send_chunk(
+    &self,
+    object: &CollabObject,
+    id: MsgId,
+    chunk: PayloadChunk,
+  ) -> Result<(), Error> {
+    (**self).send_chunk(object, id, chunk).await
+  }
+
   async fn send_init_sync(
     &self,
     object: &CollabObject,
@@ -455,6 +508,22 @@ where
   }
 }
 
+/// Send the payload in [PayloadChunk]s of at most `max_size` bytes, stopping at the first chunk
+/// that fails.
+async fn send_chunks(
+  storage: &dyn RemoteCollabStorage,
+  object: &CollabObject,
+  msg_id: MsgId,
+  payload: &[u8],
+  max_size: usize,
+  is_init: bool,
+) -> Result<(), Error> {
+  for chunk in PayloadChunk::split(payload, max_size, is_init) {
+    storage.send_chunk(object, msg_id, chunk).await?;
+  }
+  Ok(())
+}
+
 #[derive(Clone, Debug)]
 pub enum MessageMeta {
   Init { msg_id: MsgId },
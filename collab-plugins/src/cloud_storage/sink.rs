@@ -1,17 +1,17 @@
 use std::marker::PhantomData;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Weak};
 use std::time::Duration;
 
 use collab::lock::Mutex;
 use futures_util::SinkExt;
-use tokio::spawn;
 use tokio::sync::{mpsc, oneshot, watch};
-use tokio::time::{Instant, Interval};
 use tracing::{debug, trace};
 
 use crate::cloud_storage::error::SyncError;
 use crate::cloud_storage::msg::{CollabSinkMessage, MessageState, PendingMsgQueue};
+use crate::cloud_storage::spawner::{timeout, Spawner};
+use crate::cloud_storage::update_size::UpdateSizePolicy;
 
 pub const DEFAULT_SYNC_TIMEOUT: u64 = 2;
 #[derive(Clone, Debug)]
@@ -51,9 +51,10 @@ pub struct CollabSink<Sink, Msg> {
   #[allow(dead_code)]
   interval_runner_stop_tx: Option<mpsc::Sender<()>>,
 
-  /// Used to calculate the time interval between two messages. Only used when the sink strategy
-  /// is [SinkStrategy::FixInterval].
-  instant: Mutex<Instant>,
+  /// Set by the [IntervalRunner] when the interval elapsed, and reset when a message is sent.
+  /// Only used when the sink strategy is [SinkStrategy::FixInterval].
+  interval_elapsed: Arc<AtomicBool>,
+  spawner: Arc<dyn Spawner>,
   state_notifier: Arc<watch::Sender<SinkState>>,
 }
 
@@ -76,6 +77,7 @@ where
     sync_state_tx: watch::Sender<SinkState>,
     msg_id_counter: C,
     config: SinkConfig,
+    spawner: Arc<dyn Spawner>,
   ) -> Self
   where
     C: MsgIdCounter,
@@ -87,13 +89,14 @@ where
     let pending_msg_queue = Arc::new(Mutex::from(pending_msg_queue));
     let msg_id_counter = Arc::new(msg_id_counter);
     //
-    let instant = Mutex::from(Instant::now());
+    let interval_elapsed = Arc::new(AtomicBool::new(false));
     let mut interval_runner_stop_tx = None;
     if let SinkStrategy::FixInterval(duration) = &config.strategy {
       let weak_notifier = Arc::downgrade(&notifier);
       let (tx, rx) = mpsc::channel(1);
       interval_runner_stop_tx = Some(tx);
-      spawn(IntervalRunner::new(*duration).run(weak_notifier, rx));
+      let runner = IntervalRunner::new(*duration, interval_elapsed.clone(), spawner.clone());
+      spawner.spawn(Box::pin(runner.run(weak_notifier, rx)));
     }
     Self {
       uid,
@@ -103,8 +106,9 @@ where
       notifier,
       state_notifier,
       config,
-      instant,
+      interval_elapsed,
       interval_runner_stop_tx,
+      spawner,
     }
   }
 
@@ -171,23 +175,12 @@ where
       return Ok(());
     }
 
-    // Check the elapsed time from the last message. Return if the elapsed time is less than
-    // the fix interval.
-    if let SinkStrategy::FixInterval(duration) = &self.config.strategy {
-      let elapsed = self.instant.lock().await.elapsed();
-      // trace!(
-      //   "elapsed interval: {:?}, fix interval: {:?}",
-      //   elapsed,
-      //   duration
-      // );
-      if elapsed < *duration {
-        return Ok(());
-      }
-    }
-
-    // Reset the instant if the strategy is [SinkStrategy::FixInterval].
-    if self.config.strategy.is_fix_interval() {
-      *self.instant.lock().await = Instant::now();
+    // Return if the fix interval has not elapsed since the last message. Taking the flag resets
+    // it for the next interval.
+    if self.config.strategy.is_fix_interval()
+      && !self.interval_elapsed.swap(false, Ordering::SeqCst)
+    {
+      return Ok(());
     }
 
     self.try_send_msg_immediately().await;
@@ -238,8 +231,8 @@ where
     sender.send(collab_msg).await.ok()?;
     // Wait for the message to be acked.
     // If the message is not acked within the timeout, resend the message.
-    match tokio::time::timeout(self.config.timeout, rx).await {
-      Ok(_) => {
+    match timeout(self.spawner.as_ref(), self.config.timeout, rx).await {
+      Some(_) => {
         if let Ok(mut pending_msgs) = self.pending_msg_queue.try_lock() {
           let pending_msg = pending_msgs.pop();
           trace!(
@@ -257,7 +250,7 @@ where
         }
         self.notify()
       },
-      Err(_) => {
+      None => {
         let mut lock = self.pending_msg_queue.lock().await;
         if let Some(mut pending_msg) = lock.peek_mut() {
           pending_msg.set_state(MessageState::Timeout);
@@ -323,6 +316,8 @@ pub struct SinkConfig {
   pub max_merge_size: usize,
   /// `strategy` is the strategy to send the messages.
   pub strategy: SinkStrategy,
+  /// `update_size` limits the size of the payloads sent to the remote.
+  pub update_size: UpdateSizePolicy,
 }
 
 impl SinkConfig {
@@ -347,6 +342,11 @@ impl SinkConfig {
     self
   }
 
+  pub fn with_update_size_policy(mut self, update_size: UpdateSizePolicy) -> Self {
+    self.update_size = update_size;
+    self
+  }
+
   pub fn with_strategy(mut self, strategy: SinkStrategy) -> Self {
     if let SinkStrategy::FixInterval(duration) = strategy {
       if self.timeout < duration {
@@ -364,6 +364,7 @@ impl Default for SinkConfig {
       timeout: Duration::from_secs(DEFAULT_SYNC_TIMEOUT),
       max_merge_size: 4096,
       strategy: SinkStrategy::Asap,
+      update_size: UpdateSizePolicy::default(),
     }
   }
 }
@@ -401,30 +402,31 @@ impl MsgIdCounter for DefaultMsgIdCounter {
 }
 
 struct IntervalRunner {
-  interval: Option<Interval>,
+  duration: Duration,
+  elapsed: Arc<AtomicBool>,
+  spawner: Arc<dyn Spawner>,
 }
 
 impl IntervalRunner {
-  fn new(duration: Duration) -> Self {
+  fn new(duration: Duration, elapsed: Arc<AtomicBool>, spawner: Arc<dyn Spawner>) -> Self {
     Self {
-      interval: Some(tokio::time::interval(duration)),
+      duration,
+      elapsed,
+      spawner,
     }
   }
 }
 
 impl IntervalRunner {
-  pub async fn run(mut self, sender: Weak<watch::Sender<bool>>, mut stop_rx: mpsc::Receiver<()>) {
-    let mut interval = self
-      .interval
-      .take()
-      .expect("Interval should only take once");
+  pub async fn run(self, sender: Weak<watch::Sender<bool>>, mut stop_rx: mpsc::Receiver<()>) {
     loop {
       tokio::select! {
         _ = stop_rx.recv() => {
             break;
         },
-        _ = interval.tick() => {
+        _ = self.spawner.sleep(self.duration) => {
           if let Some(sender) = sender.upgrade() {
+            self.elapsed.store(true, Ordering::SeqCst);
             let _ = sender.send(false);
           } else {
             break;
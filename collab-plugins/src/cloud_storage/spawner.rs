@@ -0,0 +1,119 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::future::{select, Either};
+
+pub type SpawnFuture = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+/// Abstracts the async runtime used by the [RemoteCollab](super::remote_collab::RemoteCollab)
+/// and its [CollabSink](super::sink::CollabSink). The sync tasks never call into a specific
+/// runtime, so the cloud storage can run on tokio, in the browser or on any other executor that
+/// implements this trait.
+pub trait Spawner: Send + Sync + 'static {
+  /// Runs the future in the background. The future is dropped when it completes.
+  fn spawn(&self, future: SpawnFuture);
+
+  /// Returns a future that completes after the given duration.
+  fn sleep(&self, duration: Duration) -> SpawnFuture;
+}
+
+/// Waits for the future to complete, returns [None] if it takes longer than the duration.
+pub(crate) async fn timeout<F>(
+  spawner: &dyn Spawner,
+  duration: Duration,
+  future: F,
+) -> Option<F::Output>
+where
+  F: Future + Unpin,
+{
+  match select(future, spawner.sleep(duration)).await {
+    Either::Left((output, _)) => Some(output),
+    Either::Right(_) => None,
+  }
+}
+
+/// Returns the [Spawner] of the target: [TokioSpawner] on native and [WasmSpawner] in the
+/// browser.
+pub fn default_spawner() -> Arc<dyn Spawner> {
+  #[cfg(not(target_arch = "wasm32"))]
+  {
+    Arc::new(TokioSpawner)
+  }
+  #[cfg(target_arch = "wasm32")]
+  {
+    Arc::new(WasmSpawner)
+  }
+}
+
+/// Spawns the tasks on the current tokio runtime.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TokioSpawner;
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Spawner for TokioSpawner {
+  fn spawn(&self, future: SpawnFuture) {
+    tokio::spawn(future);
+  }
+
+  fn sleep(&self, duration: Duration) -> SpawnFuture {
+    Box::pin(tokio::time::sleep(duration))
+  }
+}
+
+/// Spawns the tasks on the global executor of smol.
+#[cfg(feature = "smol_runtime")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SmolSpawner;
+
+#[cfg(feature = "smol_runtime")]
+impl Spawner for SmolSpawner {
+  fn spawn(&self, future: SpawnFuture) {
+    smol::spawn(future).detach();
+  }
+
+  fn sleep(&self, duration: Duration) -> SpawnFuture {
+    Box::pin(async move {
+      smol::Timer::after(duration).await;
+    })
+  }
+}
+
+/// Spawns the tasks on the event loop of the browser with `wasm-bindgen-futures`. The timers use
+/// the `setTimeout` of the window.
+#[cfg(target_arch = "wasm32")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct WasmSpawner;
+
+#[cfg(target_arch = "wasm32")]
+impl Spawner for WasmSpawner {
+  fn spawn(&self, future: SpawnFuture) {
+    wasm_bindgen_futures::spawn_local(future);
+  }
+
+  fn sleep(&self, duration: Duration) -> SpawnFuture {
+    use wasm_bindgen::closure::Closure;
+    use wasm_bindgen::JsCast;
+
+    let (tx, rx) = futures::channel::oneshot::channel::<()>();
+    match web_sys::window() {
+      Some(window) => {
+        let callback = Closure::once_into_js(move || {
+          let _ = tx.send(());
+        });
+        if let Err(err) = window.set_timeout_with_callback_and_timeout_and_arguments_0(
+          callback.unchecked_ref(),
+          duration.as_millis().min(i32::MAX as u128) as i32,
+        ) {
+          tracing::error!("Failed to set timeout: {:?}", err);
+        }
+      },
+      None => tracing::error!("Failed to set timeout: the window is not available"),
+    }
+    Box::pin(async move {
+      let _ = rx.await;
+    })
+  }
+}
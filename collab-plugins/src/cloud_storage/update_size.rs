@@ -0,0 +1,82 @@
+/// The default max size of a payload sent to the remote, 1MB.
+pub const DEFAULT_MAX_UPDATE_SIZE: usize = 1024 * 1024;
+
+/// Limits the size of the payloads sent to the remote, so a huge update, like a giant paste,
+/// doesn't exceed the message limit of the server. A payload larger than the max size, the init
+/// sync included, is sent in [PayloadChunk]s when the [RemoteCollabStorage] supports them.
+///
+/// The updates are sent once they're committed to the local collab, so they're never dropped
+/// here. Use [Collab::transact_mut_with_max_update_size] to roll back and reject an oversized
+/// change.
+///
+/// [RemoteCollabStorage]: crate::cloud_storage::RemoteCollabStorage
+/// [Collab::transact_mut_with_max_update_size]: collab::preclude::Collab::transact_mut_with_max_update_size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UpdateSizePolicy {
+  pub max_update_size: usize,
+}
+
+impl UpdateSizePolicy {
+  pub fn new(max_update_size: usize) -> Self {
+    Self {
+      max_update_size: max_update_size.max(1),
+    }
+  }
+
+  pub fn is_oversized(&self, payload_len: usize) -> bool {
+    payload_len > self.max_update_size
+  }
+}
+
+impl Default for UpdateSizePolicy {
+  fn default() -> Self {
+    Self::new(DEFAULT_MAX_UPDATE_SIZE)
+  }
+}
+
+/// A part of a payload that was larger than the [UpdateSizePolicy::max_update_size]. The chunks
+/// of a payload share the id of its message and are sent in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PayloadChunk {
+  pub index: u32,
+  pub total: u32,
+  /// True if the payload is the init sync of the collab.
+  pub is_init: bool,
+  pub data: Vec<u8>,
+}
+
+impl PayloadChunk {
+  /// Split the payload in chunks of at most `max_size` bytes.
+  pub fn split(payload: &[u8], max_size: usize, is_init: bool) -> Vec<PayloadChunk> {
+    let chunks = payload.chunks(max_size.max(1)).collect::<Vec<_>>();
+    let total = chunks.len() as u32;
+    chunks
+      .into_iter()
+      .enumerate()
+      .map(|(index, data)| PayloadChunk {
+        index: index as u32,
+        total,
+        is_init,
+        data: data.to_vec(),
+      })
+      .collect()
+  }
+
+  /// Join the chunks of a payload, in any order. Return an error if a chunk is missing.
+  pub fn reassemble(mut chunks: Vec<PayloadChunk>) -> Result<Vec<u8>, SyncError> {
+    chunks.sort_by_key(|chunk| chunk.index);
+    let total = chunks.first().map(|chunk| chunk.total).unwrap_or(0);
+    let is_complete = chunks.len() as u32 == total
+      && chunks
+        .iter()
+        .enumerate()
+        .all(|(index, chunk)| chunk.index == index as u32 && chunk.total == total);
+    if !is_complete {
+      return Err(SyncError::IncompletePayload {
+        received: chunks.len(),
+        total: total as usize,
+      });
+    }
+    Ok(chunks.into_iter().flat_map(|chunk| chunk.data).collect())
+  }
+}
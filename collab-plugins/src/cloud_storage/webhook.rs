@@ -0,0 +1,321 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use collab::core::clock::{system_clock, ClockProvider};
+use collab::core::collab_webhook::{WebhookEvent, WebhookPayload, WEBHOOK_SCHEMA_VERSION};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, Notify};
+use tokio_stream::{Stream, StreamExt};
+
+use crate::cloud_storage::spawner::Spawner;
+
+/// Delivers the payloads, usually with an HTTP request. It's implemented by the server.
+#[async_trait]
+pub trait WebhookSink: Send + Sync + 'static {
+  async fn send(&self, payload: &WebhookPayload) -> Result<(), anyhow::Error>;
+}
+
+/// How often a payload is sent again when the sink fails. The delay doubles after each attempt,
+/// up to `max_delay`.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+  pub max_retries: u32,
+  pub initial_delay: Duration,
+  pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+  fn default() -> Self {
+    Self {
+      max_retries: 5,
+      initial_delay: Duration::from_millis(500),
+      max_delay: Duration::from_secs(30),
+    }
+  }
+}
+
+impl RetryPolicy {
+  pub fn new(max_retries: u32, initial_delay: Duration, max_delay: Duration) -> Self {
+    Self {
+      max_retries,
+      initial_delay,
+      max_delay,
+    }
+  }
+
+  /// Return the delay before the given retry, starting at 0.
+  pub fn delay(&self, retry: u32) -> Duration {
+    let factor = 2u32.saturating_pow(retry);
+    self
+      .initial_delay
+      .saturating_mul(factor)
+      .min(self.max_delay)
+  }
+}
+
+/// What the [EventEmitter] does with a new payload when its queue is full, usually because the
+/// sink is down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueueOverflow {
+  /// Drop the oldest queued payload to make room for the new one.
+  DropOldest,
+  /// Drop the new payload.
+  DropNewest,
+}
+
+/// How many payloads the [EventEmitter] keeps while they wait for their delivery.
+#[derive(Debug, Clone)]
+pub struct QueuePolicy {
+  pub capacity: usize,
+  pub overflow: QueueOverflow,
+}
+
+impl Default for QueuePolicy {
+  fn default() -> Self {
+    Self {
+      capacity: 1000,
+      overflow: QueueOverflow::DropOldest,
+    }
+  }
+}
+
+impl QueuePolicy {
+  pub fn new(capacity: usize, overflow: QueueOverflow) -> Self {
+    Self { capacity, overflow }
+  }
+}
+
+/// The event emitted by [EventEmitter::forward_broadcast] when the receiver lagged behind the
+/// sender, with the number of the events that were missed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LaggedEvent {
+  pub missed: u64,
+}
+
+impl WebhookEvent for LaggedEvent {
+  fn event_type(&self) -> String {
+    "webhook.lagged".to_string()
+  }
+
+  fn data(&self) -> serde_json::Value {
+    serde_json::json!({ "missed": self.missed })
+  }
+}
+
+/// The payloads waiting for their delivery.
+struct PayloadQueue {
+  payloads: Mutex<VecDeque<WebhookPayload>>,
+  policy: QueuePolicy,
+  notify: Notify,
+  dropped: AtomicU64,
+  closed: AtomicBool,
+}
+
+impl PayloadQueue {
+  fn push(&self, payload: WebhookPayload) {
+    let dropped = {
+      let mut payloads = self.payloads.lock().unwrap_or_else(|err| err.into_inner());
+      if payloads.len() < self.policy.capacity {
+        payloads.push_back(payload);
+        None
+      } else {
+        match self.policy.overflow {
+          QueueOverflow::DropNewest => Some(payload),
+          QueueOverflow::DropOldest => {
+            payloads.push_back(payload);
+            payloads.pop_front()
+          },
+        }
+      }
+    };
+    if let Some(dropped) = dropped {
+      self.dropped.fetch_add(1, Ordering::SeqCst);
+      tracing::warn!(
+        "drop the webhook payload {} of {}, the queue is full",
+        dropped.sequence,
+        dropped.object_id
+      );
+    }
+    self.notify.notify_one();
+  }
+
+  fn pop(&self) -> Option<WebhookPayload> {
+    self
+      .payloads
+      .lock()
+      .unwrap_or_else(|err| err.into_inner())
+      .pop_front()
+  }
+}
+
+/// Closes the queue once all the clones of the emitter are dropped, so the delivery stops after
+/// the queued payloads are delivered.
+struct QueueCloser(Arc<PayloadQueue>);
+
+impl Drop for QueueCloser {
+  fn drop(&mut self) {
+    self.0.closed.store(true, Ordering::SeqCst);
+    self.0.notify.notify_one();
+  }
+}
+
+/// Turns the change events into [WebhookPayload]s and delivers them to the sink one at a time,
+/// in the order they were emitted. A payload is dropped once the sink failed more than
+/// [RetryPolicy::max_retries] times, or when the queue is full as set by its [QueuePolicy]. A
+/// receiver can tell that payloads were dropped from the gaps in their sequence. The delivery
+/// runs on the [Spawner] and stops when all the clones of the emitter are dropped.
+#[derive(Clone)]
+pub struct EventEmitter {
+  queue: Arc<PayloadQueue>,
+  _closer: Arc<QueueCloser>,
+  sequence: Arc<AtomicU64>,
+  clock: Arc<dyn ClockProvider>,
+  spawner: Arc<dyn Spawner>,
+}
+
+impl EventEmitter {
+  pub fn new<S: WebhookSink>(
+    sink: S,
+    retry_policy: RetryPolicy,
+    spawner: Arc<dyn Spawner>,
+  ) -> Self {
+    Self::new_with_clock(sink, retry_policy, spawner, system_clock())
+  }
+
+  pub fn new_with_clock<S: WebhookSink>(
+    sink: S,
+    retry_policy: RetryPolicy,
+    spawner: Arc<dyn Spawner>,
+    clock: Arc<dyn ClockProvider>,
+  ) -> Self {
+    Self::new_with_queue_policy(sink, retry_policy, QueuePolicy::default(), spawner, clock)
+  }
+
+  pub fn new_with_queue_policy<S: WebhookSink>(
+    sink: S,
+    retry_policy: RetryPolicy,
+    queue_policy: QueuePolicy,
+    spawner: Arc<dyn Spawner>,
+    clock: Arc<dyn ClockProvider>,
+  ) -> Self {
+    let queue = Arc::new(PayloadQueue {
+      payloads: Mutex::new(VecDeque::new()),
+      policy: queue_policy,
+      notify: Notify::new(),
+      dropped: AtomicU64::new(0),
+      closed: AtomicBool::new(false),
+    });
+    let delivery_queue = queue.clone();
+    let delivery_spawner = spawner.clone();
+    spawner.spawn(Box::pin(async move {
+      loop {
+        match delivery_queue.pop() {
+          Some(payload) => deliver(&sink, &retry_policy, delivery_spawner.as_ref(), &payload).await,
+          None if delivery_queue.closed.load(Ordering::SeqCst) => break,
+          None => delivery_queue.notify.notified().await,
+        }
+      }
+    }));
+    Self {
+      _closer: Arc::new(QueueCloser(queue.clone())),
+      queue,
+      sequence: Arc::new(AtomicU64::new(0)),
+      clock,
+      spawner,
+    }
+  }
+
+  /// Queue the event for delivery. Return the payload that will be delivered, unless it's
+  /// dropped because the queue is full.
+  pub fn emit<E: WebhookEvent>(&self, object_id: &str, event: &E) -> WebhookPayload {
+    let payload = WebhookPayload {
+      schema_version: WEBHOOK_SCHEMA_VERSION,
+      sequence: self.sequence.fetch_add(1, Ordering::SeqCst),
+      event_type: event.event_type(),
+      object_id: object_id.to_string(),
+      timestamp: self.clock.timestamp(),
+      data: event.data(),
+    };
+    self.queue.push(payload.clone());
+    payload
+  }
+
+  /// Return the number of the payloads dropped because the queue was full.
+  pub fn dropped_count(&self) -> u64 {
+    self.queue.dropped.load(Ordering::SeqCst)
+  }
+
+  /// Emit the events of the stream until it ends.
+  pub fn forward<E, S>(&self, object_id: String, events: S)
+  where
+    E: WebhookEvent + Send + 'static,
+    S: Stream<Item = E> + Send + Unpin + 'static,
+  {
+    let emitter = self.clone();
+    let mut events = events;
+    self.spawner.spawn(Box::pin(async move {
+      while let Some(event) = events.next().await {
+        emitter.emit(&object_id, &event);
+      }
+    }));
+  }
+
+  /// Emit the events of the receiver until its sender is dropped. When the receiver lagged
+  /// behind, a [LaggedEvent] with the number of the missed events is emitted in their place.
+  pub fn forward_broadcast<E>(&self, object_id: String, mut events: broadcast::Receiver<E>)
+  where
+    E: WebhookEvent + Clone + Send + 'static,
+  {
+    let emitter = self.clone();
+    self.spawner.spawn(Box::pin(async move {
+      loop {
+        match events.recv().await {
+          Ok(event) => {
+            emitter.emit(&object_id, &event);
+          },
+          Err(RecvError::Lagged(missed)) => {
+            tracing::warn!("the webhook events of {} lagged by {}", object_id, missed);
+            emitter.emit(&object_id, &LaggedEvent { missed });
+          },
+          Err(RecvError::Closed) => break,
+        }
+      }
+    }));
+  }
+}
+
+async fn deliver<S: WebhookSink>(
+  sink: &S,
+  retry_policy: &RetryPolicy,
+  spawner: &dyn Spawner,
+  payload: &WebhookPayload,
+) {
+  let mut retry = 0;
+  loop {
+    match sink.send(payload).await {
+      Ok(()) => return,
+      Err(err) if retry >= retry_policy.max_retries => {
+        tracing::error!(
+          "drop the webhook payload {} of {} after {} retries: {}",
+          payload.sequence,
+          payload.object_id,
+          retry,
+          err
+        );
+        return;
+      },
+      Err(err) => {
+        tracing::warn!(
+          "fail to send the webhook payload {}, retrying: {}",
+          payload.sequence,
+          err
+        );
+        spawner.sleep(retry_policy.delay(retry)).await;
+        retry += 1;
+      },
+    }
+  }
+}
@@ -16,7 +16,7 @@ macro_rules! if_wasm {
     )*}
 }
 
-#[cfg(all(feature = "postgres_plugin", not(target_arch = "wasm32")))]
+#[cfg(feature = "postgres_plugin")]
 pub mod cloud_storage;
 pub mod connect_state;
 
@@ -0,0 +1,255 @@
+use std::io::Write;
+
+use crate::local_storage::kv::doc::{
+  get_doc_ids_of_user, get_doc_ids_of_workspace, split_workspace_and_object_id, CollabKVAction,
+};
+use crate::local_storage::kv::keys::*;
+use crate::local_storage::kv::snapshot::{get_snapshot_id, SnapshotAction};
+use crate::local_storage::kv::*;
+use serde::{Deserialize, Serialize};
+use smallvec::{smallvec, SmallVec};
+
+/// Magic bytes at the beginning of every archive file.
+pub const ARCHIVE_MAGIC: &[u8; 8] = b"COLLABKV";
+/// Version of the archive format. Archives with a greater version can't be imported.
+pub const ARCHIVE_VERSION: u32 = 1;
+/// magic + version + payload length + payload checksum
+const ARCHIVE_HEADER_LEN: usize = 8 + 4 + 8 + 4;
+
+/// All the documents, updates and snapshots of a user.
+///
+/// A record key only contains the part that follows the document id or snapshot id, which are
+/// local to a KV store. New ids are generated when importing the archive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollabArchive {
+  pub uid: i64,
+  pub created_at: i64,
+  pub objects: Vec<ArchivedObject>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedObject {
+  /// The object part of the doc id key: workspace_id + object_id, or object_id for the legacy
+  /// key format.
+  pub id_key: Vec<u8>,
+  pub object_id: String,
+  pub doc_records: Vec<ArchivedRecord>,
+  pub snapshot_records: Vec<ArchivedRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedRecord {
+  pub key: Vec<u8>,
+  pub value: Vec<u8>,
+  /// crc32 of the key and value
+  pub checksum: u32,
+}
+
+impl ArchivedRecord {
+  fn new(key: &[u8], value: &[u8]) -> Self {
+    Self {
+      key: key.to_vec(),
+      value: value.to_vec(),
+      checksum: record_checksum(key, value),
+    }
+  }
+
+  pub fn is_valid(&self) -> bool {
+    self.checksum == record_checksum(&self.key, &self.value)
+  }
+}
+
+fn record_checksum(key: &[u8], value: &[u8]) -> u32 {
+  let mut hasher = crc32fast::Hasher::new();
+  hasher.update(key);
+  hasher.update(value);
+  hasher.finalize()
+}
+
+impl CollabArchive {
+  /// Encode the archive as: magic, version, payload length, payload crc32 and the payload.
+  pub fn encode(&self) -> Result<Vec<u8>, PersistenceError> {
+    let payload = bincode::serialize(self)?;
+    let mut data = Vec::with_capacity(ARCHIVE_HEADER_LEN + payload.len());
+    data.write_all(ARCHIVE_MAGIC).unwrap();
+    data.write_all(&ARCHIVE_VERSION.to_be_bytes()).unwrap();
+    data
+      .write_all(&(payload.len() as u64).to_be_bytes())
+      .unwrap();
+    data
+      .write_all(&crc32fast::hash(&payload).to_be_bytes())
+      .unwrap();
+    data.write_all(&payload).unwrap();
+    Ok(data)
+  }
+
+  /// Decode the archive and verify the checksums of the payload and of every record.
+  pub fn decode(data: &[u8]) -> Result<Self, PersistenceError> {
+    if data.len() < ARCHIVE_HEADER_LEN || &data[0..8] != ARCHIVE_MAGIC {
+      return Err(PersistenceError::InvalidData(
+        "not a collab archive".to_string(),
+      ));
+    }
+    let version = u32::from_be_bytes(data[8..12].try_into().unwrap());
+    if version > ARCHIVE_VERSION {
+      return Err(PersistenceError::InvalidData(format!(
+        "unsupported archive version: {}",
+        version
+      )));
+    }
+    let len = u64::from_be_bytes(data[12..20].try_into().unwrap()) as usize;
+    let checksum = u32::from_be_bytes(data[20..24].try_into().unwrap());
+    let payload = &data[ARCHIVE_HEADER_LEN..];
+    if payload.len() != len || crc32fast::hash(payload) != checksum {
+      return Err(PersistenceError::InvalidData(
+        "archive checksum mismatch".to_string(),
+      ));
+    }
+
+    let archive: CollabArchive = bincode::deserialize(payload)?;
+    for object in &archive.objects {
+      let is_valid = object
+        .doc_records
+        .iter()
+        .chain(object.snapshot_records.iter())
+        .all(|record| record.is_valid());
+      if !is_valid {
+        return Err(PersistenceError::InvalidData(format!(
+          "record checksum mismatch: {}",
+          object.object_id
+        )));
+      }
+    }
+    Ok(archive)
+  }
+
+  pub fn num_of_records(&self) -> usize {
+    self
+      .objects
+      .iter()
+      .map(|object| object.doc_records.len() + object.snapshot_records.len())
+      .sum()
+  }
+}
+
+impl<'a, T> ArchiveAction<'a> for T
+where
+  T: KVStore<'a> + 'a,
+  PersistenceError: From<<Self as KVStore<'a>>::Error>,
+{
+}
+
+pub trait ArchiveAction<'a>: CollabKVAction<'a> + SnapshotAction<'a>
+where
+  PersistenceError: From<<Self as KVStore<'a>>::Error>,
+{
+  /// Collect all the documents, updates and snapshots of the given user.
+  fn export_archive_data(&self, uid: i64) -> Result<CollabArchive, PersistenceError> {
+    let ids = get_doc_ids_of_user(self, uid)?;
+    export_objects(self, uid, ids, None)
+  }
+
+  /// Collect the documents, updates and snapshots of the given workspace of the user, used to
+  /// migrate a single workspace to another store.
+  fn export_workspace_archive_data(
+    &self,
+    uid: i64,
+    workspace_id: &str,
+  ) -> Result<CollabArchive, PersistenceError> {
+    let ids = get_doc_ids_of_workspace(self, uid, workspace_id)?;
+    export_objects(self, uid, ids, Some(workspace_id))
+  }
+
+  /// Write the archive to the store. Objects that already exist are replaced by the archived
+  /// version.
+  fn import_archive_data(&self, archive: &CollabArchive) -> Result<(), PersistenceError> {
+    let uid_bytes = archive.uid.to_be_bytes();
+    for object in &archive.objects {
+      let mut id_key: SmallVec<[u8; 20]> = smallvec![DOC_SPACE, DOC_SPACE_OBJECT];
+      id_key.extend_from_slice(&uid_bytes);
+      id_key.extend_from_slice(&object.id_key);
+      id_key.push(TERMINATOR);
+      let id_key = Key(id_key);
+
+      let doc_id = match get_id_for_key(self, id_key.clone()) {
+        Some(doc_id) => {
+          let start = make_doc_start_key(doc_id);
+          let end = make_doc_end_key(doc_id);
+          remove_record_range(self, start.as_ref(), end.as_ref())?;
+          doc_id
+        },
+        None => insert_doc_id_for_key(self, id_key)?,
+      };
+      for record in &object.doc_records {
+        let mut key: SmallVec<[u8; 20]> = smallvec![DOC_SPACE, DOC_SPACE_OBJECT_KEY];
+        key.extend_from_slice(&doc_id.to_be_bytes());
+        key.extend_from_slice(&record.key);
+        insert_record(self, key.as_slice(), &record.value)?;
+      }
+
+      self.delete_all_snapshots(archive.uid, object.object_id.as_str())?;
+      if !object.snapshot_records.is_empty() {
+        let snapshot_id = self.create_snapshot_id(archive.uid, object.object_id.as_str())?;
+        for record in &object.snapshot_records {
+          let mut key: SmallVec<[u8; 20]> = smallvec![SNAPSHOT_SPACE, SNAPSHOT_SPACE_OBJECT];
+          key.extend_from_slice(&snapshot_id.to_be_bytes());
+          key.extend_from_slice(&record.key);
+          insert_record(self, key.as_slice(), &record.value)?;
+        }
+      }
+    }
+    Ok(())
+  }
+}
+
+fn export_objects<'a, S>(
+  store: &S,
+  uid: i64,
+  ids: Vec<(Vec<u8>, DocID)>,
+  workspace_id: Option<&str>,
+) -> Result<CollabArchive, PersistenceError>
+where
+  S: KVStore<'a>,
+  PersistenceError: From<<S as KVStore<'a>>::Error>,
+{
+  let mut objects = vec![];
+  for (id_key, doc_id) in ids {
+    let (_, object_id) = split_workspace_and_object_id(&id_key, workspace_id);
+
+    // The document records are stored as [DOC_SPACE, DOC_SPACE_OBJECT_KEY, doc_id, ..]
+    let start = make_doc_start_key(doc_id);
+    let end = make_doc_end_key(doc_id);
+    let doc_prefix_len = 2 + DOC_ID_LEN;
+    let doc_records = store
+      .range(start.as_ref()..end.as_ref())?
+      .map(|entry| ArchivedRecord::new(&entry.key()[doc_prefix_len..], entry.value()))
+      .collect();
+
+    // The snapshot records are stored as [SNAPSHOT_SPACE, SNAPSHOT_SPACE_OBJECT, snapshot_id, ..]
+    let mut snapshot_records = vec![];
+    if let Some(snapshot_id) = get_snapshot_id(uid, store, object_id.as_bytes()) {
+      let start = make_snapshot_update_key(snapshot_id, 0);
+      let end = make_snapshot_update_key(snapshot_id, Clock::MAX);
+      let snapshot_prefix_len = 2 + SNAPSHOT_ID_LEN;
+      for entry in store.range(start.as_ref()..=end.as_ref())? {
+        snapshot_records.push(ArchivedRecord::new(
+          &entry.key()[snapshot_prefix_len..],
+          entry.value(),
+        ));
+      }
+    }
+
+    objects.push(ArchivedObject {
+      id_key,
+      object_id,
+      doc_records,
+      snapshot_records,
+    });
+  }
+
+  Ok(CollabArchive {
+    uid,
+    created_at: chrono::Utc::now().timestamp(),
+    objects,
+  })
+}
@@ -0,0 +1,152 @@
+use crate::local_storage::kv::doc::CollabKVAction;
+use crate::local_storage::kv::*;
+use collab::entity::EncodedCollab;
+
+/// A list of writes that spans multiple objects of the same user and workspace.
+///
+/// The batch is applied within a single write transaction. If any of the operations fails, the
+/// transaction is not committed and none of the objects are changed. It's used to persist objects
+/// that only make sense together, for example, a database collab and its row collabs.
+#[derive(Debug, Clone)]
+pub struct CollabWriteBatch {
+  pub uid: i64,
+  pub workspace_id: String,
+  ops: Vec<CollabBatchOp>,
+}
+
+#[derive(Debug, Clone)]
+pub enum CollabBatchOp {
+  /// Create a new document. Fails with [PersistenceError::DocumentAlreadyExist] if the document
+  /// already exists.
+  Create {
+    object_id: String,
+    encoded_collab: EncodedCollab,
+  },
+  /// Replace the document state and remove all of its updates. The document is created if it
+  /// doesn't exist.
+  Flush {
+    object_id: String,
+    encoded_collab: EncodedCollab,
+  },
+  /// Append an update to an existing document. Fails with [PersistenceError::RecordNotFound] if
+  /// the document doesn't exist.
+  PushUpdate { object_id: String, update: Vec<u8> },
+  /// Delete the document, its updates and snapshots.
+  Delete { object_id: String },
+}
+
+/// A store that applies a [CollabWriteBatch] atomically.
+pub trait CollabBatchWriter {
+  fn write_batch(&self, batch: &CollabWriteBatch) -> Result<(), PersistenceError>;
+}
+
+impl CollabWriteBatch {
+  pub fn new(uid: i64, workspace_id: &str) -> Self {
+    Self {
+      uid,
+      workspace_id: workspace_id.to_string(),
+      ops: vec![],
+    }
+  }
+
+  pub fn create_doc(&mut self, object_id: &str, encoded_collab: EncodedCollab) -> &mut Self {
+    self.ops.push(CollabBatchOp::Create {
+      object_id: object_id.to_string(),
+      encoded_collab,
+    });
+    self
+  }
+
+  pub fn flush_doc(&mut self, object_id: &str, encoded_collab: EncodedCollab) -> &mut Self {
+    self.ops.push(CollabBatchOp::Flush {
+      object_id: object_id.to_string(),
+      encoded_collab,
+    });
+    self
+  }
+
+  pub fn push_update(&mut self, object_id: &str, update: Vec<u8>) -> &mut Self {
+    self.ops.push(CollabBatchOp::PushUpdate {
+      object_id: object_id.to_string(),
+      update,
+    });
+    self
+  }
+
+  pub fn delete_doc(&mut self, object_id: &str) -> &mut Self {
+    self.ops.push(CollabBatchOp::Delete {
+      object_id: object_id.to_string(),
+    });
+    self
+  }
+
+  pub fn ops(&self) -> &[CollabBatchOp] {
+    &self.ops
+  }
+
+  pub fn len(&self) -> usize {
+    self.ops.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.ops.is_empty()
+  }
+}
+
+impl<'a, T> CollabBatchAction<'a> for T
+where
+  T: KVStore<'a> + 'a,
+  PersistenceError: From<<Self as KVStore<'a>>::Error>,
+{
+}
+
+pub trait CollabBatchAction<'a>: CollabKVAction<'a>
+where
+  PersistenceError: From<<Self as KVStore<'a>>::Error>,
+{
+  /// Apply all the operations of the batch to the store. The caller is responsible for
+  /// committing the transaction, which must be skipped when an error is returned.
+  fn apply_batch(&self, batch: &CollabWriteBatch) -> Result<(), PersistenceError> {
+    let uid = batch.uid;
+    let workspace_id = batch.workspace_id.as_str();
+    for op in batch.ops() {
+      match op {
+        CollabBatchOp::Create {
+          object_id,
+          encoded_collab,
+        } => {
+          if self.is_exist(uid, workspace_id, object_id) {
+            tracing::warn!("🟡{:?} already exist", object_id);
+            return Err(PersistenceError::DocumentAlreadyExist);
+          }
+          self.flush_doc_with(
+            uid,
+            workspace_id,
+            object_id,
+            &encoded_collab.doc_state,
+            &encoded_collab.state_vector,
+          )?;
+        },
+        CollabBatchOp::Flush {
+          object_id,
+          encoded_collab,
+        } => {
+          self.flush_doc_with(
+            uid,
+            workspace_id,
+            object_id,
+            &encoded_collab.doc_state,
+            &encoded_collab.state_vector,
+          )?;
+        },
+        CollabBatchOp::PushUpdate { object_id, update } => {
+          self.push_update(uid, workspace_id, object_id, update)?;
+        },
+        CollabBatchOp::Delete { object_id } => {
+          self.delete_doc(uid, workspace_id, object_id)?;
+        },
+      }
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,109 @@
+use crate::local_storage::kv::batch::{CollabBatchWriter, CollabWriteBatch};
+use crate::local_storage::kv::PersistenceError;
+use collab::core::transaction::DocTransactionExtension;
+use collab::preclude::{Collab, ReadTxn};
+
+/// Receives the updates of a committed [CollabOperationGroup], in the order the operations were
+/// added to the group.
+pub trait CollabUpdateSink {
+  fn send_update(&self, object_id: &str, update: &[u8]);
+}
+
+impl<F> CollabUpdateSink for F
+where
+  F: Fn(&str, &[u8]),
+{
+  fn send_update(&self, object_id: &str, update: &[u8]) {
+    self(object_id, update)
+  }
+}
+
+/// An update that is sent to the [CollabUpdateSink] once the group is committed.
+#[derive(Debug, Clone)]
+pub struct GroupedUpdate {
+  pub object_id: String,
+  pub update: Vec<u8>,
+}
+
+/// Groups the mutations of multiple collabs that reference each other, for example, a folder view,
+/// the database it points to and the documents of the database rows.
+///
+/// The changes are persisted within a single [CollabWriteBatch] when the group is committed, so
+/// either all the collabs are written or none of them is. The updates are only sent to the sink
+/// after the batch is written, and in the order the operations were added, so a remote never
+/// receives a reference to an object that doesn't exist yet.
+///
+/// The collabs of the group must not have a disk plugin attached, otherwise the plugin persists
+/// their updates as soon as they are made. If the commit fails, the in-memory collabs contain the
+/// changes that were not persisted and should be reopened from the disk.
+#[derive(Debug, Clone)]
+pub struct CollabOperationGroup {
+  batch: CollabWriteBatch,
+  updates: Vec<GroupedUpdate>,
+}
+
+impl CollabOperationGroup {
+  pub fn new(uid: i64, workspace_id: &str) -> Self {
+    Self {
+      batch: CollabWriteBatch::new(uid, workspace_id),
+      updates: vec![],
+    }
+  }
+
+  /// Adds a collab that was created within the operation. The whole state of the collab is
+  /// persisted and sent. The commit fails if a collab with the same id already exists.
+  pub fn create_collab(&mut self, object_id: &str, collab: &Collab) -> &mut Self {
+    let encoded_collab = collab.transact().get_encoded_collab_v1();
+    self.updates.push(GroupedUpdate {
+      object_id: object_id.to_string(),
+      update: encoded_collab.doc_state.to_vec(),
+    });
+    self.batch.create_doc(object_id, encoded_collab);
+    self
+  }
+
+  /// Runs `f` on an existing collab. Only the changes made by `f` are persisted and sent. The
+  /// commit fails if the collab doesn't exist on the disk.
+  pub fn update_collab<F, T>(&mut self, object_id: &str, collab: &mut Collab, f: F) -> T
+  where
+    F: FnOnce(&mut Collab) -> T,
+  {
+    let state_vector = collab.transact().state_vector();
+    let output = f(collab);
+    let update = collab.transact().encode_state_as_update_v1(&state_vector);
+    self.updates.push(GroupedUpdate {
+      object_id: object_id.to_string(),
+      update: update.clone(),
+    });
+    self.batch.push_update(object_id, update);
+    output
+  }
+
+  /// Deletes the collab from the disk. Nothing is sent for the deleted collab.
+  pub fn delete_collab(&mut self, object_id: &str) -> &mut Self {
+    self.batch.delete_doc(object_id);
+    self
+  }
+
+  pub fn updates(&self) -> &[GroupedUpdate] {
+    &self.updates
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.batch.is_empty()
+  }
+
+  /// Writes all the changes of the group within a single transaction, then sends the updates to
+  /// the sink. Nothing is sent if the write fails.
+  pub fn commit<W, S>(self, writer: &W, sink: &S) -> Result<(), PersistenceError>
+  where
+    W: CollabBatchWriter + ?Sized,
+    S: CollabUpdateSink + ?Sized,
+  {
+    writer.write_batch(&self.batch)?;
+    for update in &self.updates {
+      sink.send_update(&update.object_id, &update.update);
+    }
+    Ok(())
+  }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::io::Write;
 use std::ops::RangeBounds;
@@ -55,6 +56,12 @@ pub trait KVStore<'a> {
 
   /// Return the entry prior to the given key
   fn next_back_entry(&self, key: &[u8]) -> Result<Option<Self::Entry>, Self::Error>;
+
+  /// Return true if the records are written with a checksum, see [insert_record]. Off by default,
+  /// because each checksum is an extra key written with its record.
+  fn record_checksums(&self) -> bool {
+    false
+  }
 }
 
 impl<T> KVStore<'static> for Arc<T>
@@ -89,6 +96,10 @@ where
   fn next_back_entry(&self, key: &[u8]) -> Result<Option<Self::Entry>, Self::Error> {
     (**self).next_back_entry(key)
   }
+
+  fn record_checksums(&self) -> bool {
+    (**self).record_checksums()
+  }
 }
 
 pub fn insert_snapshot_update<'a, K, S>(
@@ -104,7 +115,7 @@ where
 {
   let snapshot = CollabSnapshot::new(data).to_vec();
   let update_key = create_update_key(snapshot_id, store, object_id, make_snapshot_update_key)?;
-  store.insert(update_key, snapshot)?;
+  insert_record(store, update_key, snapshot)?;
   Ok(())
 }
 
@@ -125,10 +136,111 @@ where
     // So we return an error here.
     return Err(PersistenceError::DuplicateUpdateKey);
   }
-  db.insert(update_key.as_ref(), value)?;
+  insert_record(db, update_key.as_ref(), value)?;
   Ok(update_key.to_vec())
 }
 
+/// Insert the record, along with the checksum of its value if the store has
+/// [KVStore::record_checksums] on. Used for the document states, state vectors, updates and
+/// snapshots, whose checksums are checked when they are read back.
+pub fn insert_record<'a, S, K, V>(store: &S, key: K, value: V) -> Result<(), PersistenceError>
+where
+  K: AsRef<[u8]>,
+  V: AsRef<[u8]>,
+  S: KVStore<'a>,
+  PersistenceError: From<<S as KVStore<'a>>::Error>,
+{
+  if store.record_checksums() {
+    let checksum = record_checksum(value.as_ref());
+    store.insert(make_checksum_key(key.as_ref()), checksum.to_be_bytes())?;
+  }
+  store.insert(key.as_ref(), value)?;
+  Ok(())
+}
+
+/// Remove the record inserted by [insert_record] and its checksum.
+pub fn remove_record<'a, S>(store: &S, key: &[u8]) -> Result<(), PersistenceError>
+where
+  S: KVStore<'a>,
+  PersistenceError: From<<S as KVStore<'a>>::Error>,
+{
+  store.remove(key)?;
+  if store.record_checksums() {
+    store.remove(make_checksum_key(key).as_ref())?;
+  }
+  Ok(())
+}
+
+/// Remove the records in the range [from..to] and their checksums.
+pub fn remove_record_range<'a, S>(store: &S, from: &[u8], to: &[u8]) -> Result<(), PersistenceError>
+where
+  S: KVStore<'a>,
+  PersistenceError: From<<S as KVStore<'a>>::Error>,
+{
+  store.remove_range(from, to)?;
+  if store.record_checksums() {
+    store.remove_range(
+      make_checksum_key(from).as_ref(),
+      make_checksum_key(to).as_ref(),
+    )?;
+  }
+  Ok(())
+}
+
+/// Remove all the checksums written by [insert_record]. A store that doesn't write the checksums
+/// must not keep the old ones, since its records can be overwritten without updating them.
+pub fn remove_all_record_checksums<'a, S>(store: &S) -> Result<(), PersistenceError>
+where
+  S: KVStore<'a>,
+  PersistenceError: From<<S as KVStore<'a>>::Error>,
+{
+  store.remove_range(&[CHECKSUM_SPACE], &[CHECKSUM_SPACE + 1])?;
+  Ok(())
+}
+
+pub fn record_checksum(value: &[u8]) -> u32 {
+  crc32fast::hash(value)
+}
+
+/// Return the checksums of the records in the range [from..to], keyed by the key of the record.
+/// The records written before the checksums were introduced don't have one.
+pub fn get_record_checksums<'a, S>(
+  store: &S,
+  from: &[u8],
+  to: &[u8],
+) -> Result<HashMap<Vec<u8>, u32>, PersistenceError>
+where
+  S: KVStore<'a>,
+  PersistenceError: From<<S as KVStore<'a>>::Error>,
+{
+  let from = make_checksum_key(from);
+  let to = make_checksum_key(to);
+  let mut checksums = HashMap::new();
+  for entry in store.range(from.as_ref()..to.as_ref())? {
+    if let Ok(checksum) = <[u8; 4]>::try_from(entry.value()) {
+      checksums.insert(entry.key()[1..].to_vec(), u32::from_be_bytes(checksum));
+    }
+  }
+  Ok(checksums)
+}
+
+/// Return false if the record doesn't match its stored checksum. The records written before
+/// the checksums were introduced are considered valid.
+pub fn is_record_checksum_valid<'a, S>(
+  store: &S,
+  key: &[u8],
+  value: &[u8],
+) -> Result<bool, PersistenceError>
+where
+  S: KVStore<'a>,
+  PersistenceError: From<<S as KVStore<'a>>::Error>,
+{
+  match store.get(make_checksum_key(key))? {
+    None => Ok(true),
+    Some(checksum) => Ok(checksum.as_ref() == record_checksum(value).to_be_bytes()),
+  }
+}
+
 pub fn get_last_update_key<'a, S, F>(
   store: &S,
   id: OID,
@@ -1,8 +1,10 @@
+use crate::local_storage::kv::history::HistoryAction;
 use crate::local_storage::kv::keys::*;
-use crate::local_storage::kv::snapshot::SnapshotAction;
+use crate::local_storage::kv::oid::OID;
+use crate::local_storage::kv::snapshot::{get_snapshot_id, SnapshotAction};
 use crate::local_storage::kv::*;
 use smallvec::{smallvec, SmallVec};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use tracing::{error, info};
 use uuid::Uuid;
@@ -33,8 +35,9 @@ where
     let sv_key = make_state_vector_key(doc_id);
 
     info!("new doc:{:?}, doc state len:{}", object_id, doc_state.len());
-    self.insert(doc_state_key, doc_state)?;
-    self.insert(sv_key, sv)?;
+    insert_record(self, doc_state_key, doc_state)?;
+    insert_record(self, sv_key, sv)?;
+    touch_last_modified(self, doc_id)?;
 
     Ok(())
   }
@@ -59,13 +62,14 @@ where
     // Remove the updates
     let start = make_doc_start_key(doc_id);
     let end = make_doc_end_key(doc_id);
-    self.remove_range(start.as_ref(), end.as_ref())?;
+    remove_record_range(self, start.as_ref(), end.as_ref())?;
 
     let doc_state_key = make_doc_state_key(doc_id);
     let sv_key = make_state_vector_key(doc_id);
     // Insert new doc state and state vector
-    self.insert(doc_state_key, doc_state)?;
-    self.insert(sv_key, state_vector)?;
+    insert_record(self, doc_state_key, doc_state)?;
+    insert_record(self, sv_key, state_vector)?;
+    touch_last_modified(self, doc_id)?;
     Ok(())
   }
 
@@ -97,6 +101,12 @@ where
       let doc_state_key = make_doc_state_key(doc_id);
       if let Some(doc_state) = self.get(doc_state_key.as_ref())? {
         // Load the doc state
+        if !is_record_checksum_valid(self, doc_state_key.as_ref(), doc_state.as_ref())? {
+          error!("🔴{:?} doc state doesn't match its checksum", object_id);
+          return Err(PersistenceError::InvalidData(
+            "doc state doesn't match its checksum".to_string(),
+          ));
+        }
 
         match Update::decode_v1(doc_state.as_ref()) {
           Ok(update) => {
@@ -113,16 +123,17 @@ where
         let update_end = make_doc_update_key(doc_id, Clock::MAX);
 
         // Load the updates
+        let checksums = get_record_checksums(self, update_start.as_ref(), update_end.as_ref())?;
         let encoded_updates = self.range(update_start.as_ref()..update_end.as_ref())?;
         for encoded_update in encoded_updates {
           // Decode the update and apply it to the transaction. If the update is invalid, we will
           // remove the update and the following updates.
-          if let Err(e) = Update::decode_v1(encoded_update.value())
-            .map_err(PersistenceError::Yrs)
+          if let Err(e) = check_record_checksum(&checksums, &encoded_update)
+            .and_then(|_| Update::decode_v1(encoded_update.value()).map_err(PersistenceError::Yrs))
             .and_then(|update| txn.try_apply_update(update))
           {
             tracing::error!("🔴{:?} apply update error: {}", object_id, e);
-            self.remove_range(encoded_update.key().as_ref(), update_end.as_ref())?;
+            remove_record_range(self, encoded_update.key(), update_end.as_ref())?;
             break;
           }
           update_count += 1;
@@ -176,7 +187,11 @@ where
           object_id
         )))
       },
-      Some(doc_id) => insert_doc_update(self, doc_id, object_id, update.to_vec()),
+      Some(doc_id) => {
+        let update_key = insert_doc_update(self, doc_id, object_id, update.to_vec())?;
+        touch_last_modified(self, doc_id)?;
+        Ok(update_key)
+      },
     }
   }
 
@@ -190,7 +205,7 @@ where
   ) -> Result<(), PersistenceError> {
     if let Some(doc_id) = get_doc_id(uid, self, workspace_id, object_id) {
       let start = make_doc_update_key(doc_id, 0);
-      self.remove_range(start.as_ref(), end.as_ref())?;
+      remove_record_range(self, start.as_ref(), end)?;
     }
     Ok(())
   }
@@ -204,7 +219,7 @@ where
     if let Some(doc_id) = get_doc_id(uid, self, workspace_id, object_id) {
       let start = make_doc_update_key(doc_id, 0);
       let end = make_doc_update_key(doc_id, Clock::MAX);
-      self.remove_range(start.as_ref(), end.as_ref())?;
+      remove_record_range(self, start.as_ref(), end.as_ref())?;
     }
     Ok(())
   }
@@ -220,14 +235,15 @@ where
     let doc_id = get_or_create_did(uid, self, workspace_id, object_id)?;
     let start = make_doc_start_key(doc_id);
     let end = make_doc_end_key(doc_id);
-    self.remove_range(start.as_ref(), end.as_ref())?;
+    remove_record_range(self, start.as_ref(), end.as_ref())?;
 
     let doc_state_key = make_doc_state_key(doc_id);
     let sv_key = make_state_vector_key(doc_id);
 
     // Insert new doc state and state vector
-    self.insert(doc_state_key, doc_state)?;
-    self.insert(sv_key, sv)?;
+    insert_record(self, doc_state_key, doc_state)?;
+    insert_record(self, sv_key, sv)?;
+    touch_last_modified(self, doc_id)?;
     Ok(())
   }
 
@@ -271,16 +287,17 @@ where
       // Delete the updates
       let start = make_doc_start_key(did);
       let end = make_doc_end_key(did);
-      self.remove_range(start.as_ref(), end.as_ref())?;
+      remove_record_range(self, start.as_ref(), end.as_ref())?;
 
       // Delete the document state and the state vector
       let doc_state_key = make_doc_state_key(did);
       let sv_key = make_state_vector_key(did);
-      let _ = self.remove(doc_state_key.as_ref());
-      let _ = self.remove(sv_key.as_ref());
+      let _ = remove_record(self, doc_state_key.as_ref());
+      let _ = remove_record(self, sv_key.as_ref());
 
-      // Delete the snapshot
+      // Delete the snapshot and the history
       self.delete_all_snapshots(uid, object_id)?;
+      self.delete_history(uid, object_id)?;
     }
     Ok(())
   }
@@ -295,30 +312,17 @@ where
     Ok(OIDIter { iter })
   }
 
+  /// Return the ids of the objects stored in the given workspace of the user.
   fn get_all_object_ids(
     &self,
     uid: i64,
     workspace_id: &str,
   ) -> Result<impl Iterator<Item = String>, PersistenceError> {
-    let uid_bytes = uid.to_be_bytes();
-    let workspace_bytes = workspace_id.as_bytes();
-
-    // Construct the `from` key with UID and workspace_id
-    let mut from_vec: SmallVec<[u8; 24]> = smallvec![DOC_SPACE, DOC_SPACE_OBJECT];
-    from_vec.extend_from_slice(&uid_bytes);
-    from_vec.extend_from_slice(workspace_bytes);
-    let from = Key(from_vec);
-
-    // Construct the `to` key by appending 0xFF to cover the full range of keys with the same prefix
-    let to_vec: SmallVec<[u8; 24]> = smallvec![DOC_SPACE, DOC_SPACE_OBJECT_KEY];
-    let to = Key(to_vec);
-
-    let iter = self.range(from.as_ref()..to.as_ref())?;
-
-    Ok(iter.filter_map(move |entry| {
-      extract_object_id_from_key_v1(entry.key(), uid_bytes.len(), workspace_bytes.len())
-        .and_then(|object_id_bytes| String::from_utf8(object_id_bytes.to_vec()).ok())
-    }))
+    let object_ids = get_doc_ids_of_workspace(self, uid, workspace_id)?
+      .into_iter()
+      .map(|(id_key, _)| split_workspace_and_object_id(&id_key, Some(workspace_id)).1)
+      .collect::<Vec<_>>();
+    Ok(object_ids.into_iter())
   }
 
   fn get_all_workspace_ids(&self) -> Result<Vec<String>, PersistenceError> {
@@ -392,6 +396,52 @@ where
       0
     }
   }
+
+  /// Return all the objects stored for the given user, along with their number of updates,
+  /// last modified timestamp and size on disk.
+  fn list_objects(&self, uid: i64) -> Result<Vec<StoredObjectInfo>, PersistenceError> {
+    get_doc_ids_of_user(self, uid)?
+      .into_iter()
+      .map(|(id_key, doc_id)| get_stored_object_info(self, uid, &id_key, None, doc_id))
+      .collect()
+  }
+
+  /// Same as [Self::list_objects], but only for the objects of the given workspace.
+  fn list_workspace_objects(
+    &self,
+    uid: i64,
+    workspace_id: &str,
+  ) -> Result<Vec<StoredObjectInfo>, PersistenceError> {
+    get_doc_ids_of_workspace(self, uid, workspace_id)?
+      .into_iter()
+      .map(|(id_key, doc_id)| {
+        get_stored_object_info(self, uid, &id_key, Some(workspace_id), doc_id)
+      })
+      .collect()
+  }
+
+  /// Return the size in bytes of all the objects of the given workspace of the user.
+  fn workspace_size(&self, uid: i64, workspace_id: &str) -> Result<u64, PersistenceError> {
+    let objects = self.list_workspace_objects(uid, workspace_id)?;
+    Ok(objects.iter().map(|object| object.size).sum())
+  }
+
+  /// Delete all the objects of the given workspace of the user, along with their updates and
+  /// snapshots. The other workspaces are left untouched. Return the number of deleted objects.
+  fn delete_workspace(&self, uid: i64, workspace_id: &str) -> Result<usize, PersistenceError> {
+    let ids = get_doc_ids_of_workspace(self, uid, workspace_id)?;
+    for (id_key, _) in &ids {
+      let (_, object_id) = split_workspace_and_object_id(id_key, Some(workspace_id));
+      self.delete_doc(uid, workspace_id, object_id.as_str())?;
+    }
+    info!(
+      "[Client {}] deleted {} objects of workspace {}",
+      uid,
+      ids.len(),
+      workspace_id
+    );
+    Ok(ids.len())
+  }
 }
 
 impl<'a, T> CollabKVAction<'a> for T
@@ -401,6 +451,188 @@ where
 {
 }
 
+/// Summary of an object stored in the KV store.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredObjectInfo {
+  /// The workspace of the object. It's [None] for objects stored with the legacy key format,
+  /// which doesn't contain the workspace id.
+  pub workspace_id: Option<String>,
+  pub object_id: String,
+  pub update_count: usize,
+  /// Timestamp in seconds of the last write. It's [None] for objects that haven't been written
+  /// since the timestamp was introduced.
+  pub last_modified: Option<i64>,
+  /// Size in bytes of the document state, updates and snapshots.
+  pub size: u64,
+}
+
+fn check_record_checksum<E: KVEntry>(
+  checksums: &HashMap<Vec<u8>, u32>,
+  entry: &E,
+) -> Result<(), PersistenceError> {
+  match checksums.get(entry.key()) {
+    Some(checksum) if *checksum != record_checksum(entry.value()) => Err(
+      PersistenceError::InvalidData("update doesn't match its checksum".to_string()),
+    ),
+    _ => Ok(()),
+  }
+}
+
+fn touch_last_modified<'a, S>(store: &S, doc_id: DocID) -> Result<(), PersistenceError>
+where
+  S: KVStore<'a>,
+  PersistenceError: From<<S as KVStore<'a>>::Error>,
+{
+  let now = chrono::Utc::now().timestamp();
+  store.insert(make_last_modified_key(doc_id), now.to_be_bytes())?;
+  Ok(())
+}
+
+/// Return the object part of the doc id keys, that is workspace_id + object_id or only the
+/// object_id for the legacy keys, along with the doc id of each object of the given user.
+pub(crate) fn get_doc_ids_of_user<'a, S>(
+  store: &S,
+  uid: i64,
+) -> Result<Vec<(Vec<u8>, DocID)>, PersistenceError>
+where
+  S: KVStore<'a>,
+  PersistenceError: From<<S as KVStore<'a>>::Error>,
+{
+  let mut prefix: SmallVec<[u8; 20]> = smallvec![DOC_SPACE, DOC_SPACE_OBJECT];
+  prefix.extend_from_slice(&uid.to_be_bytes());
+  let prefix_len = prefix.len();
+  get_doc_ids_with_prefix(store, prefix, prefix_len)
+}
+
+/// Same as [get_doc_ids_of_user], but only for the objects of the given workspace. The keys are
+/// matched on the exact uid and workspace id prefix, so any workspace id works, not only uuids.
+/// The legacy keys don't contain the workspace id, so they are never returned.
+pub(crate) fn get_doc_ids_of_workspace<'a, S>(
+  store: &S,
+  uid: i64,
+  workspace_id: &str,
+) -> Result<Vec<(Vec<u8>, DocID)>, PersistenceError>
+where
+  S: KVStore<'a>,
+  PersistenceError: From<<S as KVStore<'a>>::Error>,
+{
+  let mut prefix: SmallVec<[u8; 20]> = smallvec![DOC_SPACE, DOC_SPACE_OBJECT];
+  prefix.extend_from_slice(&uid.to_be_bytes());
+  // The id key keeps the workspace id in front of the object id, like [get_doc_ids_of_user].
+  let id_key_start = prefix.len();
+  prefix.extend_from_slice(workspace_id.as_bytes());
+  get_doc_ids_with_prefix(store, prefix, id_key_start)
+}
+
+fn get_doc_ids_with_prefix<'a, S>(
+  store: &S,
+  prefix: SmallVec<[u8; 20]>,
+  id_key_start: usize,
+) -> Result<Vec<(Vec<u8>, DocID)>, PersistenceError>
+where
+  S: KVStore<'a>,
+  PersistenceError: From<<S as KVStore<'a>>::Error>,
+{
+  let prefix_len = prefix.len();
+  let mut to = prefix.clone();
+  to.push(TERMINATOR_HI_WATERMARK);
+
+  let ids = store
+    .range(Key(prefix).as_ref()..Key(to).as_ref())?
+    .filter_map(|entry| {
+      let key = entry.key();
+      if key.len() <= prefix_len || key[key.len() - 1] != TERMINATOR {
+        return None;
+      }
+      let doc_id = OID::from_be_bytes(entry.value().try_into().ok()?);
+      Some((key[id_key_start..key.len() - 1].to_vec(), doc_id))
+    })
+    .collect();
+  Ok(ids)
+}
+
+/// Return the summary of the object with the given id key and doc id.
+fn get_stored_object_info<'a, S>(
+  store: &S,
+  uid: i64,
+  id_key: &[u8],
+  workspace_id: Option<&str>,
+  doc_id: DocID,
+) -> Result<StoredObjectInfo, PersistenceError>
+where
+  S: KVStore<'a>,
+  PersistenceError: From<<S as KVStore<'a>>::Error>,
+{
+  let (workspace_id, object_id) = split_workspace_and_object_id(id_key, workspace_id);
+  let mut info = StoredObjectInfo {
+    workspace_id,
+    object_id,
+    update_count: 0,
+    last_modified: None,
+    size: 0,
+  };
+
+  // [DOC_SPACE, DOC_SPACE_OBJECT_KEY] + doc_id, followed by the tag byte
+  const TAG_INDEX: usize = 2 + DOC_ID_LEN;
+  let start = make_doc_start_key(doc_id);
+  let end = make_doc_end_key(doc_id);
+  for entry in store.range(start.as_ref()..end.as_ref())? {
+    let key = entry.key();
+    info.size += (key.len() + entry.value().len()) as u64;
+    match key
+      .get(TAG_INDEX)
+      .copied()
+      .unwrap_or(TERMINATOR_HI_WATERMARK)
+    {
+      DOC_UPDATE if key.len() == TAG_INDEX + CLOCK_LEN + 2 => info.update_count += 1,
+      DOC_LAST_MODIFIED => {
+        info.last_modified = entry.value().try_into().ok().map(i64::from_be_bytes);
+      },
+      _ => {},
+    }
+  }
+
+  if let Some(snapshot_id) = get_snapshot_id(uid, store, info.object_id.as_bytes()) {
+    let start = make_snapshot_update_key(snapshot_id, 0);
+    let end = make_snapshot_update_key(snapshot_id, Clock::MAX);
+    for entry in store.range(start.as_ref()..=end.as_ref())? {
+      info.size += (entry.key().len() + entry.value().len()) as u64;
+    }
+  }
+  Ok(info)
+}
+
+/// Split the object part of a doc id key into its workspace id and object id. The workspace id
+/// is stored in front of the object id without a separator, so it's split at the end of the
+/// given workspace id when the keys were read for a workspace, see [get_doc_ids_of_workspace].
+/// Otherwise the workspace id is expected to be a uuid string. The legacy key format only
+/// contains the object id.
+pub(crate) fn split_workspace_and_object_id(
+  bytes: &[u8],
+  workspace_id: Option<&str>,
+) -> (Option<String>, String) {
+  if let Some(workspace_id) = workspace_id {
+    if let Some(object_id) = bytes.strip_prefix(workspace_id.as_bytes()) {
+      return (
+        Some(workspace_id.to_string()),
+        String::from_utf8_lossy(object_id).to_string(),
+      );
+    }
+  }
+  const UUID_STR_LEN: usize = 36;
+  if bytes.len() > UUID_STR_LEN {
+    if let Ok(workspace_id) = std::str::from_utf8(&bytes[..UUID_STR_LEN]) {
+      if Uuid::parse_str(workspace_id).is_ok() {
+        return (
+          Some(workspace_id.to_string()),
+          String::from_utf8_lossy(&bytes[UUID_STR_LEN..]).to_string(),
+        );
+      }
+    }
+  }
+  (None, String::from_utf8_lossy(bytes).to_string())
+}
+
 /// Get or create a document id for the given object id.
 fn get_or_create_did<'a, K, S>(
   uid: i64,
@@ -426,7 +658,12 @@ where
   }
 }
 
-fn get_doc_id<'a, K, S>(uid: i64, store: &S, workspace_id: &K, object_id: &K) -> Option<DocID>
+pub(crate) fn get_doc_id<'a, K, S>(
+  uid: i64,
+  store: &S,
+  workspace_id: &K,
+  object_id: &K,
+) -> Option<DocID>
 where
   S: KVStore<'a>,
   K: AsRef<[u8]> + ?Sized,
@@ -1,3 +1,7 @@
+use std::fmt::Display;
+
+use collab::error::{anyhow_error_code, ClassifiedError, ErrorCode, ErrorContext};
+
 #[derive(Debug, thiserror::Error)]
 pub enum PersistenceError {
   #[cfg(not(target_arch = "wasm32"))]
@@ -50,11 +54,57 @@ pub enum PersistenceError {
 
   #[error(transparent)]
   Internal(#[from] anyhow::Error),
+
+  #[error("{context}: {source}")]
+  Context {
+    context: String,
+    source: Box<PersistenceError>,
+  },
 }
 
 impl PersistenceError {
   pub fn is_record_not_found(&self) -> bool {
-    matches!(self, PersistenceError::RecordNotFound(_))
+    match self {
+      PersistenceError::Context { source, .. } => source.is_record_not_found(),
+      _ => matches!(self, PersistenceError::RecordNotFound(_)),
+    }
+  }
+}
+
+impl ClassifiedError for PersistenceError {
+  fn code(&self) -> ErrorCode {
+    match self {
+      #[cfg(not(target_arch = "wasm32"))]
+      PersistenceError::RocksdbCorruption(_) => ErrorCode::StorageCorruption,
+      #[cfg(not(target_arch = "wasm32"))]
+      PersistenceError::RocksdbRepairFail(_) => ErrorCode::StorageRepairFailed,
+      #[cfg(not(target_arch = "wasm32"))]
+      PersistenceError::RocksdbBusy(_) => ErrorCode::StorageBusy,
+      #[cfg(not(target_arch = "wasm32"))]
+      PersistenceError::RocksdbIOError(_) => ErrorCode::StorageIO,
+      PersistenceError::Bincode(_) => ErrorCode::StorageEncoding,
+      PersistenceError::RecordNotFound(_) => ErrorCode::RecordNotFound,
+      PersistenceError::DocumentAlreadyExist => ErrorCode::RecordAlreadyExist,
+      PersistenceError::UnexpectedEmptyUpdates => ErrorCode::UnexpectedEmpty,
+      // The updates read from the store can't be decoded or applied, so they are lost.
+      PersistenceError::Yrs(_) => ErrorCode::DecodeUpdate,
+      PersistenceError::Update(_) => ErrorCode::ApplyUpdate,
+      PersistenceError::InvalidData(_) => ErrorCode::InvalidData,
+      PersistenceError::DuplicateUpdateKey => ErrorCode::DuplicateUpdateKey,
+      PersistenceError::LatestUpdateKeyNotExist => ErrorCode::LatestUpdateKeyNotExist,
+      PersistenceError::Collab(err) => err.code(),
+      PersistenceError::Internal(err) => anyhow_error_code(err),
+      PersistenceError::Context { source, .. } => source.code(),
+    }
+  }
+}
+
+impl ErrorContext for PersistenceError {
+  fn context<C: Display>(self, context: C) -> Self {
+    PersistenceError::Context {
+      context: context.to_string(),
+      source: Box::new(self),
+    }
   }
 }
 
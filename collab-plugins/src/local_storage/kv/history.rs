@@ -0,0 +1,225 @@
+use std::fmt::Debug;
+use std::ops::Range;
+
+use crate::local_storage::kv::keys::*;
+use crate::local_storage::kv::*;
+use collab::core::origin::CollabOrigin;
+use serde::{Deserialize, Serialize};
+use yrs::updates::decoder::Decode;
+use yrs::Update;
+
+impl<'a, T> HistoryAction<'a> for T
+where
+  T: KVStore<'a>,
+  PersistenceError: From<<Self as KVStore<'a>>::Error>,
+{
+}
+
+/// Keeps every update of an object along with the time it was captured and its origin, so the
+/// activity of the object can be replayed without a server. Unlike the doc updates, the history
+/// is not merged when the document is flushed.
+pub trait HistoryAction<'a>: KVStore<'a> + Sized
+where
+  PersistenceError: From<<Self as KVStore<'a>>::Error>,
+{
+  /// Record the update, captured now, in the history of the given object.
+  fn push_history<K>(
+    &self,
+    uid: i64,
+    object_id: &K,
+    origin: &CollabOrigin,
+    update: &[u8],
+  ) -> Result<(), PersistenceError>
+  where
+    K: AsRef<[u8]> + ?Sized + Debug,
+  {
+    let timestamp = chrono::Utc::now().timestamp_millis();
+    self.push_history_at(uid, object_id, timestamp, origin, update)
+  }
+
+  /// Record the update in the history of the given object. The timestamp is in milliseconds.
+  /// Updates captured within the same millisecond are kept in the order they were pushed.
+  fn push_history_at<K>(
+    &self,
+    uid: i64,
+    object_id: &K,
+    timestamp: i64,
+    origin: &CollabOrigin,
+    update: &[u8],
+  ) -> Result<(), PersistenceError>
+  where
+    K: AsRef<[u8]> + ?Sized + Debug,
+  {
+    let history_id = self.create_history_id(uid, object_id)?;
+    let seq = next_history_seq(self, history_id, timestamp)?;
+    let key = make_history_update_key(history_id, timestamp, seq);
+    let record = HistoryRecord {
+      origin: origin.clone(),
+      update: update.to_vec(),
+    };
+    tracing::trace!("New history update for object:{:?}", object_id);
+    self.insert(key, bincode::serialize(&record)?)?;
+    Ok(())
+  }
+
+  /// Return the summaries of the updates of the given object captured within the time range, in
+  /// milliseconds, ordered by time. Records that can't be decoded are skipped.
+  fn query_history<K: AsRef<[u8]> + ?Sized>(
+    &self,
+    uid: i64,
+    object_id: &K,
+    time_range: Range<i64>,
+  ) -> Result<Vec<HistoryUpdateSummary>, PersistenceError> {
+    let history_id = match get_history_id(uid, self, object_id) {
+      None => return Ok(vec![]),
+      Some(history_id) => history_id,
+    };
+    let start = make_history_update_key(history_id, time_range.start, 0);
+    let end = make_history_update_key(history_id, time_range.end, 0);
+
+    let mut summaries = vec![];
+    for entry in self.range(start.as_ref()..end.as_ref())? {
+      let (timestamp, _) = timestamp_and_seq_from_history_key(entry.key());
+      match HistoryRecord::try_from(entry.value()).and_then(|record| record.summary(timestamp)) {
+        Ok(summary) => summaries.push(summary),
+        Err(err) => tracing::warn!("🟡skip history update at {}: {}", timestamp, err),
+      }
+    }
+    Ok(summaries)
+  }
+
+  /// Return the number of updates in the history of the given object.
+  fn number_of_history_updates<K: AsRef<[u8]> + ?Sized>(&self, uid: i64, object_id: &K) -> usize {
+    match get_history_id(uid, self, object_id) {
+      None => 0,
+      Some(history_id) => {
+        let start = make_history_update_key(history_id, 0, 0);
+        let end = make_history_update_key(history_id, i64::MAX, Clock::MAX);
+        self
+          .range(start.as_ref()..=end.as_ref())
+          .map(|range| range.count())
+          .unwrap_or(0)
+      },
+    }
+  }
+
+  /// Delete the history of the given object.
+  fn delete_history<K: AsRef<[u8]> + ?Sized>(
+    &self,
+    uid: i64,
+    object_id: &K,
+  ) -> Result<(), PersistenceError> {
+    if let Some(history_id) = get_history_id(uid, self, object_id) {
+      let start = make_history_update_key(history_id, 0, 0);
+      let end = make_history_update_key(history_id, i64::MAX, Clock::MAX);
+      self.remove_range(start.as_ref(), end.as_ref())?;
+      let _ = self.remove(end.as_ref());
+    }
+    Ok(())
+  }
+
+  /// Create a history id for the given object id.
+  fn create_history_id<K: AsRef<[u8]> + ?Sized>(
+    &self,
+    uid: i64,
+    object_id: &K,
+  ) -> Result<HistoryID, PersistenceError> {
+    if let Some(history_id) = get_history_id(uid, self, object_id) {
+      Ok(history_id)
+    } else {
+      let key = make_history_id_key(&uid.to_be_bytes(), object_id.as_ref());
+      let new_history_id = insert_doc_id_for_key(self, key)?;
+      Ok(new_history_id)
+    }
+  }
+}
+
+pub fn get_history_id<'a, K, S>(uid: i64, store: &S, object_id: &K) -> Option<HistoryID>
+where
+  K: AsRef<[u8]> + ?Sized,
+  S: KVStore<'a>,
+{
+  let key = make_history_id_key(&uid.to_be_bytes(), object_id.as_ref());
+  get_id_for_key(store, key)
+}
+
+/// Return the sequence number of a new update captured at the given timestamp.
+fn next_history_seq<'a, S>(
+  store: &S,
+  history_id: HistoryID,
+  timestamp: i64,
+) -> Result<Clock, PersistenceError>
+where
+  S: KVStore<'a>,
+  PersistenceError: From<<S as KVStore<'a>>::Error>,
+{
+  let max_key = make_history_update_key(history_id, timestamp, Clock::MAX);
+  // The keys of the same timestamp only differ by the sequence number and the terminator.
+  let prefix_len = max_key.len() - 1 - CLOCK_LEN;
+  match store.next_back_entry(max_key.as_ref())? {
+    Some(entry) if entry.key().starts_with(&max_key[..prefix_len]) => {
+      let (_, seq) = timestamp_and_seq_from_history_key(entry.key());
+      Ok(seq + 1)
+    },
+    _ => Ok(0),
+  }
+}
+
+#[derive(Serialize, Deserialize)]
+struct HistoryRecord {
+  origin: CollabOrigin,
+  update: Vec<u8>,
+}
+
+impl HistoryRecord {
+  fn summary(self, timestamp: i64) -> Result<HistoryUpdateSummary, PersistenceError> {
+    let update = Update::decode_v1(&self.update)?;
+    let upper = update.state_vector();
+    let lower = update.state_vector_lower();
+    let mut clients = vec![];
+    let mut inserted = 0;
+    for (client_id, clock) in upper.iter() {
+      clients.push(*client_id);
+      inserted += clock.saturating_sub(lower.get(client_id));
+    }
+    clients.sort_unstable();
+
+    let mut deleted = 0;
+    for (_, range) in update.delete_set().iter() {
+      deleted += range.iter().map(|range| range.len() as u32).sum::<u32>();
+    }
+
+    Ok(HistoryUpdateSummary {
+      timestamp,
+      origin: self.origin,
+      size: self.update.len(),
+      clients,
+      inserted,
+      deleted,
+    })
+  }
+}
+
+impl TryFrom<&[u8]> for HistoryRecord {
+  type Error = PersistenceError;
+
+  fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+    Ok(bincode::deserialize(value)?)
+  }
+}
+
+/// The decoded summary of an update of the history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryUpdateSummary {
+  /// The time the update was captured, in milliseconds.
+  pub timestamp: i64,
+  pub origin: CollabOrigin,
+  /// The size of the encoded update, in bytes.
+  pub size: usize,
+  /// The ids of the clients that made the insertions of the update.
+  pub clients: Vec<u64>,
+  /// The number of inserted elements.
+  pub inserted: u32,
+  /// The number of deleted elements.
+  pub deleted: u32,
+}
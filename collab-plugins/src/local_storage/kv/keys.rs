@@ -17,10 +17,22 @@ use smallvec::{smallvec, SmallVec};
 //     DOC_SPACE_OBJECT_KEY     doc_id      TERMINATOR_HI_WATERMARK (state end)
 //     DOC_SPACE_OBJECT_KEY     doc_id      DOC_STATE_VEC (state vector)
 //     DOC_SPACE_OBJECT_KEY     doc_id      DOC_UPDATE clock TERMINATOR (update)
+//     DOC_SPACE_OBJECT_KEY     doc_id      DOC_SNAPSHOT_CLOCK (last update covered by a snapshot)
+//     DOC_SPACE_OBJECT_KEY     doc_id      DOC_LAST_MODIFIED (last modified timestamp)
 //
 // SNAPSHOT_SPACE
 //     SNAPSHOT_SPACE_OBJECT        object_id       TERMINATOR
 //     SNAPSHOT_SPACE_OBJECT_KEY    snapshot_id     SNAPSHOT_UPDATE(snapshot)
+//
+// HISTORY_SPACE
+//     HISTORY_SPACE_OBJECT         uid object_id   TERMINATOR
+//     HISTORY_SPACE_OBJECT_KEY     history_id      HISTORY_UPDATE timestamp seq TERMINATOR
+//
+// QUARANTINE_SPACE
+//     uid     original key
+//
+// CHECKSUM_SPACE
+//     original key (crc32 of the record)
 
 /// Prefix byte used for all of the yrs object entries.
 pub const DOC_SPACE: u8 = 1;
@@ -45,6 +57,14 @@ pub const REMOTE_DOC_STATE_VEC: u8 = 2;
 /// Tag byte within [DOC_SPACE_OBJECT_KEY] used to identify object's update entries.
 pub const DOC_UPDATE: u8 = 2;
 
+/// Tag byte within [DOC_SPACE_OBJECT_KEY] used to identify the clock of the last update that is
+/// covered by the latest snapshot of the object.
+pub const DOC_SNAPSHOT_CLOCK: u8 = 3;
+
+/// Tag byte within [DOC_SPACE_OBJECT_KEY] used to identify the timestamp of the last write to
+/// the object.
+pub const DOC_LAST_MODIFIED: u8 = 4;
+
 /// Prefix byte used for snapshot id -> [SnapshotID] mapping index key space.
 pub const SNAPSHOT_SPACE: u8 = 2;
 
@@ -57,6 +77,25 @@ pub const SNAPSHOT_UPDATE: u8 = 1;
 pub const COLLAB_SPACE: u8 = 3;
 pub const COLLAB_SPACE_OBJECT: u8 = 0;
 
+/// Prefix byte used for the records moved out by the verification of the stored objects.
+pub const QUARANTINE_SPACE: u8 = 4;
+
+/// Prefix byte used for all the history entries.
+pub const HISTORY_SPACE: u8 = 5;
+
+/// Prefix byte used for the checksums of the document and snapshot records. They are only
+/// written when [crate::local_storage::kv::KVStore::record_checksums] is on.
+pub const CHECKSUM_SPACE: u8 = 6;
+
+/// Prefix byte used for object id -> [HistoryID] mapping index key space.
+pub const HISTORY_SPACE_OBJECT: u8 = 0;
+
+/// Prefix byte used for history key space.
+pub const HISTORY_SPACE_OBJECT_KEY: u8 = 1;
+
+/// Tag byte within [HISTORY_SPACE_OBJECT_KEY] used to identify object's history entries.
+pub const HISTORY_UPDATE: u8 = 0;
+
 pub type DocID = u64;
 pub const DOC_ID_LEN: usize = 8;
 pub const DOC_STATE_KEY_LEN: usize = DOC_ID_LEN + 4;
@@ -68,6 +107,12 @@ pub const SNAPSHOT_ID_LEN: usize = 8;
 pub const SNAPSHOT_UPDATE_KEY_LEN: usize = SNAPSHOT_ID_LEN + CLOCK_LEN + 4;
 pub const SNAPSHOT_UPDATE_KEY_PREFIX_LEN: usize = SNAPSHOT_ID_LEN + 4;
 
+pub type HistoryID = u64;
+pub const HISTORY_ID_LEN: usize = 8;
+pub const TIMESTAMP_LEN: usize = 8;
+pub const HISTORY_UPDATE_KEY_LEN: usize = HISTORY_ID_LEN + TIMESTAMP_LEN + CLOCK_LEN + 4;
+pub const HISTORY_UPDATE_KEY_PREFIX_LEN: usize = HISTORY_ID_LEN + 3;
+
 pub type Clock = u32;
 pub const CLOCK_LEN: usize = 4;
 
@@ -135,6 +180,22 @@ pub fn make_remote_state_vector_key(doc_id: DocID) -> Key<DOC_STATE_KEY_LEN> {
   Key(v)
 }
 
+// [1,1,  0,0,0,0,0,0,0,0,  3]
+pub fn make_snapshot_clock_key(doc_id: DocID) -> Key<DOC_STATE_KEY_LEN> {
+  let mut v: SmallVec<[u8; DOC_STATE_KEY_LEN]> = smallvec![DOC_SPACE, DOC_SPACE_OBJECT_KEY];
+  v.write_all(&doc_id.to_be_bytes()).unwrap();
+  v.push(DOC_SNAPSHOT_CLOCK);
+  Key(v)
+}
+
+// [1,1,  0,0,0,0,0,0,0,0,  4]
+pub fn make_last_modified_key(doc_id: DocID) -> Key<DOC_STATE_KEY_LEN> {
+  let mut v: SmallVec<[u8; DOC_STATE_KEY_LEN]> = smallvec![DOC_SPACE, DOC_SPACE_OBJECT_KEY];
+  v.write_all(&doc_id.to_be_bytes()).unwrap();
+  v.push(DOC_LAST_MODIFIED);
+  Key(v)
+}
+
 // [1,1,  0,0,0,0,0,0,0,0,  2   0,0,0,0,  0]
 pub fn make_doc_update_key(doc_id: DocID, clock: Clock) -> Key<DOC_UPDATE_KEY_LEN> {
   let mut v: SmallVec<[u8; DOC_UPDATE_KEY_LEN]> = smallvec![DOC_SPACE, DOC_SPACE_OBJECT_KEY];
@@ -192,6 +253,59 @@ pub fn make_snapshot_update_key_prefix(
   Key(v)
 }
 
+// [5,0, uid,  object_id,  0]
+pub fn make_history_id_key(uid: &[u8], object_id: &[u8]) -> Key<20> {
+  let mut v: SmallVec<[u8; 20]> = smallvec![HISTORY_SPACE, HISTORY_SPACE_OBJECT];
+  v.write_all(uid).unwrap();
+  v.write_all(object_id).unwrap();
+  v.push(TERMINATOR);
+  Key(v)
+}
+
+// [5,1,  0,0,0,0,0,0,0,0,  0,  [0,0,0,0,0,0,0,0],  [0,0,0,0],  0]
+pub fn make_history_update_key(
+  history_id: HistoryID,
+  timestamp: i64,
+  seq: Clock,
+) -> Key<HISTORY_UPDATE_KEY_LEN> {
+  let mut v: SmallVec<[u8; HISTORY_UPDATE_KEY_LEN]> =
+    smallvec![HISTORY_SPACE, HISTORY_SPACE_OBJECT_KEY];
+  v.write_all(&history_id.to_be_bytes()).unwrap();
+  v.push(HISTORY_UPDATE);
+  // The timestamps before the epoch are stored as the epoch, so the keys keep the time order.
+  v.write_all(&(timestamp.max(0) as u64).to_be_bytes())
+    .unwrap();
+  v.write_all(&seq.to_be_bytes()).unwrap();
+  v.push(TERMINATOR);
+  Key(v)
+}
+
+// [5,1,  0,0,0,0,0,0,0,0,  0]
+pub fn make_history_update_key_prefix(history_id: HistoryID) -> Key<HISTORY_UPDATE_KEY_PREFIX_LEN> {
+  let mut v: SmallVec<[u8; HISTORY_UPDATE_KEY_PREFIX_LEN]> =
+    smallvec![HISTORY_SPACE, HISTORY_SPACE_OBJECT_KEY];
+  v.write_all(&history_id.to_be_bytes()).unwrap();
+  v.push(HISTORY_UPDATE);
+  Key(v)
+}
+
+// [5,1,  0,0,0,0,0,0,0,0,  0,  [0,0,0,0,0,0,0,0],  [0,0,0,0],  0]
+pub fn timestamp_and_seq_from_history_key(key: &[u8]) -> (i64, Clock) {
+  let len = key.len();
+  let seq_start = len - 1 - CLOCK_LEN;
+  let timestamp_start = seq_start - TIMESTAMP_LEN;
+  let timestamp = u64::from_be_bytes(key[timestamp_start..seq_start].try_into().unwrap());
+  let seq = Clock::from_be_bytes(key[seq_start..len - 1].try_into().unwrap());
+  (timestamp as i64, seq)
+}
+
+// [6,  original key]
+pub fn make_checksum_key(key: &[u8]) -> Key<20> {
+  let mut v: SmallVec<[u8; 20]> = smallvec![CHECKSUM_SPACE];
+  v.write_all(key).unwrap();
+  Key(v)
+}
+
 pub fn make_collab_id_key(object_id: &[u8]) -> Key<20> {
   let mut v: SmallVec<[u8; 20]> = smallvec![COLLAB_SPACE, COLLAB_SPACE_OBJECT];
   v.write_all(object_id).unwrap();
@@ -2,10 +2,16 @@ pub use db::*;
 pub use error::*;
 pub use range::*;
 
+pub mod archive;
+pub mod batch;
+pub mod coordinator;
 mod db;
 pub mod doc;
 pub mod error;
+pub mod history;
 pub mod keys;
 pub mod oid;
+pub mod prune;
 mod range;
 pub mod snapshot;
+pub mod verify;
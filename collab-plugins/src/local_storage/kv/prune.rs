@@ -0,0 +1,201 @@
+use std::fmt::Debug;
+
+use crate::local_storage::kv::doc::{get_doc_id, CollabKVAction};
+use crate::local_storage::kv::keys::*;
+use crate::local_storage::kv::snapshot::SnapshotAction;
+use crate::local_storage::kv::*;
+use crate::local_storage::UpdatePrunePolicy;
+use collab::core::collab::make_yrs_doc;
+use yrs::updates::decoder::Decode;
+use yrs::updates::encoder::Encode;
+use yrs::{ReadTxn, StateVector, Transact, Update};
+
+impl<'a, T> PruneAction<'a> for T
+where
+  T: KVStore<'a> + 'a,
+  PersistenceError: From<<Self as KVStore<'a>>::Error>,
+{
+}
+
+/// The space occupied by the updates that can be pruned.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReclaimableSpace {
+  /// Number of updates that are covered by the latest snapshot and out of the safety margin.
+  pub num_of_updates: usize,
+  /// Sum of the key and value sizes of those updates, in bytes.
+  pub bytes: u64,
+}
+
+pub trait PruneAction<'a>: CollabKVAction<'a> + SnapshotAction<'a>
+where
+  PersistenceError: From<<Self as KVStore<'a>>::Error>,
+{
+  /// Write a snapshot for the given object and remember the last update it covers. The
+  /// `snapshot_data` must be generated from a state that includes all the persisted updates.
+  ///
+  /// If the [UpdatePrunePolicy] is enabled, the covered updates are pruned right away.
+  /// Return the space that was reclaimed by the pruning.
+  fn create_snapshot_with_policy<K: AsRef<[u8]> + ?Sized + Debug>(
+    &self,
+    uid: i64,
+    workspace_id: &K,
+    object_id: &K,
+    snapshot_data: Vec<u8>,
+    policy: &UpdatePrunePolicy,
+  ) -> Result<ReclaimableSpace, PersistenceError> {
+    self.create_snapshot_with_data(uid, object_id, snapshot_data)?;
+    let doc_id = match get_doc_id(uid, self, workspace_id, object_id) {
+      None => return Ok(ReclaimableSpace::default()),
+      Some(doc_id) => doc_id,
+    };
+
+    if let Some(last_update_key) = self.get_doc_last_update_key(uid, workspace_id, object_id) {
+      self.insert(
+        make_snapshot_clock_key(doc_id),
+        clock_from_key(last_update_key.as_ref()),
+      )?;
+    }
+
+    if policy.enable {
+      self.prune_updates(uid, workspace_id, object_id, policy.safety_margin)
+    } else {
+      Ok(ReclaimableSpace::default())
+    }
+  }
+
+  /// Return the clock of the last update that is covered by the latest snapshot.
+  fn get_snapshot_clock<K: AsRef<[u8]> + ?Sized>(
+    &self,
+    uid: i64,
+    workspace_id: &K,
+    object_id: &K,
+  ) -> Option<Clock> {
+    let doc_id = get_doc_id(uid, self, workspace_id, object_id)?;
+    let value = self.get(make_snapshot_clock_key(doc_id)).ok()??;
+    let bytes: [u8; CLOCK_LEN] = value.as_ref().try_into().ok()?;
+    Some(Clock::from_be_bytes(bytes))
+  }
+
+  /// Return the space that [PruneAction::prune_updates] would reclaim with the given safety
+  /// margin.
+  fn reclaimable_space<K: AsRef<[u8]> + ?Sized + Debug>(
+    &self,
+    uid: i64,
+    workspace_id: &K,
+    object_id: &K,
+    safety_margin: u32,
+  ) -> Result<ReclaimableSpace, PersistenceError> {
+    let mut space = ReclaimableSpace::default();
+    if let Some((start, end)) = prunable_range(self, uid, workspace_id, object_id, safety_margin) {
+      for entry in self.range(start.as_ref()..end.as_ref())? {
+        space.num_of_updates += 1;
+        space.bytes += (entry.key().len() + entry.value().len()) as u64;
+      }
+    }
+    Ok(space)
+  }
+
+  /// Merge the updates that are covered by the latest snapshot into the document state and
+  /// delete them. The most recent `safety_margin` covered updates are kept.
+  ///
+  /// Nothing is pruned if the object doesn't have a snapshot yet or if the merged state can't
+  /// be integrated completely.
+  fn prune_updates<K: AsRef<[u8]> + ?Sized + Debug>(
+    &self,
+    uid: i64,
+    workspace_id: &K,
+    object_id: &K,
+    safety_margin: u32,
+  ) -> Result<ReclaimableSpace, PersistenceError> {
+    let (doc_id, (start, end)) = match get_doc_id(uid, self, workspace_id, object_id).zip(
+      prunable_range(self, uid, workspace_id, object_id, safety_margin),
+    ) {
+      None => return Ok(ReclaimableSpace::default()),
+      Some(value) => value,
+    };
+
+    let doc_state_key = make_doc_state_key(doc_id);
+    let doc_state = match self.get(doc_state_key.as_ref())? {
+      None => return Ok(ReclaimableSpace::default()),
+      Some(doc_state) => doc_state,
+    };
+
+    let mut space = ReclaimableSpace::default();
+    let doc = make_yrs_doc(true);
+    {
+      let mut txn = doc.transact_mut();
+      txn.try_apply_update(Update::decode_v1(doc_state.as_ref())?)?;
+      for entry in self.range(start.as_ref()..end.as_ref())? {
+        txn.try_apply_update(Update::decode_v1(entry.value())?)?;
+        space.num_of_updates += 1;
+        space.bytes += (entry.key().len() + entry.value().len()) as u64;
+      }
+
+      if txn.store().pending_update().is_some() || txn.store().pending_ds().is_some() {
+        tracing::warn!(
+          "🟡skip pruning {:?}, the covered updates can't be integrated",
+          object_id
+        );
+        return Ok(ReclaimableSpace::default());
+      }
+    }
+
+    if space.num_of_updates == 0 {
+      return Ok(space);
+    }
+
+    let txn = doc.transact();
+    let doc_state = txn.encode_state_as_update_v1(&StateVector::default());
+    let sv = txn.state_vector().encode_v1();
+    insert_record(self, doc_state_key, doc_state)?;
+    insert_record(self, make_state_vector_key(doc_id), sv)?;
+    remove_record_range(self, start.as_ref(), end.as_ref())?;
+
+    // The clock of the next update restarts from 1 when there is no update left, so the
+    // snapshot clock must be dropped. Otherwise, the new updates would be treated as covered.
+    if self.number_of_updates(uid, workspace_id, object_id) == 0 {
+      self.remove(make_snapshot_clock_key(doc_id).as_ref())?;
+    }
+    tracing::trace!(
+      "pruned {} updates of {:?}, {} bytes",
+      space.num_of_updates,
+      object_id,
+      space.bytes
+    );
+    Ok(space)
+  }
+}
+
+/// Return the update key range [start..end) that can be pruned.
+fn prunable_range<'a, K, S>(
+  store: &S,
+  uid: i64,
+  workspace_id: &K,
+  object_id: &K,
+  safety_margin: u32,
+) -> Option<(Key<DOC_UPDATE_KEY_LEN>, Key<DOC_UPDATE_KEY_LEN>)>
+where
+  K: AsRef<[u8]> + ?Sized + Debug,
+  S: PruneAction<'a>,
+  PersistenceError: From<<S as KVStore<'a>>::Error>,
+{
+  let doc_id = get_doc_id(uid, store, workspace_id, object_id)?;
+  let snapshot_clock = store.get_snapshot_clock(uid, workspace_id, object_id)?;
+
+  // Collect the clocks of the covered updates. The clocks are not guaranteed to be contiguous,
+  // so the safety margin is counted in number of updates instead of clock values.
+  let start = make_doc_update_key(doc_id, 0);
+  let end = make_doc_update_key(doc_id, snapshot_clock.saturating_add(1));
+  let clocks = store
+    .range(start.as_ref()..end.as_ref())
+    .ok()?
+    .map(|entry| Clock::from_be_bytes(clock_from_key(entry.key()).try_into().unwrap()))
+    .collect::<Vec<_>>();
+
+  let prunable = clocks.len().checked_sub(safety_margin as usize)?;
+  if prunable == 0 {
+    return None;
+  }
+  let end = make_doc_update_key(doc_id, clocks[prunable - 1].saturating_add(1));
+  Some((start, end))
+}
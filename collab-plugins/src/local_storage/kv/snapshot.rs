@@ -110,7 +110,7 @@ where
 
   fn delete_last_snapshot_by_snapshot_id(&self, snapshot_id: SnapshotID) {
     if let Some(last_update_key) = self.get_snapshot_last_update_key(snapshot_id) {
-      match self.remove(last_update_key.as_ref()) {
+      match remove_record(self, last_update_key.as_ref()) {
         Ok(_) => {},
         Err(e) => {
           tracing::error!("🔴delete last snapshot failed: {:?}", e);
@@ -128,7 +128,7 @@ where
     if let Some(snapshot_id) = get_snapshot_id(uid, self, object_id) {
       let start = make_snapshot_update_key(snapshot_id, 0);
       let end = make_snapshot_update_key(snapshot_id, Clock::MAX);
-      self.remove_range(start.as_ref(), end.as_ref())?;
+      remove_record_range(self, start.as_ref(), end.as_ref())?;
     }
     Ok(())
   }
@@ -0,0 +1,270 @@
+use crate::local_storage::kv::doc::{
+  get_doc_ids_of_user, split_workspace_and_object_id, CollabKVAction,
+};
+use crate::local_storage::kv::keys::*;
+use crate::local_storage::kv::snapshot::{get_snapshot_id, CollabSnapshot, SnapshotAction};
+use crate::local_storage::kv::*;
+use collab::core::collab::make_yrs_doc;
+use smallvec::{smallvec, SmallVec};
+use yrs::updates::decoder::Decode;
+use yrs::updates::encoder::Encode;
+use yrs::{ReadTxn, Transact, TransactionMut, Update};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyIssueKind {
+  /// The object doesn't have a document state.
+  MissingDocState,
+  /// The document state can't be decoded or applied.
+  InvalidDocState(String),
+  /// The stored state vector doesn't match the one computed from the document state.
+  StateVectorMismatch,
+  /// The update can't be decoded or applied.
+  InvalidUpdate(String),
+  /// Some updates depend on data that is not stored, so they can't be integrated.
+  MissingDependencies,
+  /// The snapshot can't be decoded or applied.
+  InvalidSnapshot(String),
+  /// The record doesn't match the checksum computed when it was written.
+  ChecksumMismatch,
+  /// The update comes after a bad update of the same object. It may depend on the bad update, so
+  /// it's moved out with it.
+  FollowsBadUpdate,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyIssue {
+  pub object_id: String,
+  /// The key of the bad record. Empty if the issue is not about a single record.
+  pub key: Vec<u8>,
+  pub kind: VerifyIssueKind,
+  /// Whether the record was moved to the quarantine space.
+  pub quarantined: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct VerifyReport {
+  pub uid: i64,
+  pub num_of_objects: usize,
+  pub num_of_records: usize,
+  pub issues: Vec<VerifyIssue>,
+}
+
+/// A record moved out by [VerifyAction::verify].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuarantinedRecord {
+  /// The original key of the record.
+  pub key: Vec<u8>,
+  pub value: Vec<u8>,
+}
+
+impl VerifyReport {
+  pub fn is_ok(&self) -> bool {
+    self.issues.is_empty()
+  }
+
+  pub fn num_of_quarantined(&self) -> usize {
+    self.issues.iter().filter(|issue| issue.quarantined).count()
+  }
+}
+
+impl<'a, T> VerifyAction<'a> for T
+where
+  T: KVStore<'a> + 'a,
+  PersistenceError: From<<Self as KVStore<'a>>::Error>,
+{
+}
+
+pub trait VerifyAction<'a>: CollabKVAction<'a> + SnapshotAction<'a>
+where
+  PersistenceError: From<<Self as KVStore<'a>>::Error>,
+{
+  /// Check that the stored data of every object of the given user can be restored:
+  ///   1. every record matches the checksum computed when it was written, if the record was
+  ///      written with one, see [KVStore::record_checksums]
+  ///   2. the document state and every update decode and apply cleanly
+  ///   3. the stored state vector matches the document state
+  ///   4. every snapshot decodes and applies cleanly
+  ///
+  /// The updates after the first bad update of an object are reported as
+  /// [VerifyIssueKind::FollowsBadUpdate], since they may build on it.
+  ///
+  /// When `quarantine` is true, the bad records are moved to the [QUARANTINE_SPACE] so they
+  /// don't break opening the document. The store must be a write transaction in that case.
+  fn verify(&self, uid: i64, quarantine: bool) -> Result<VerifyReport, PersistenceError> {
+    let mut report = VerifyReport {
+      uid,
+      ..Default::default()
+    };
+
+    for (id_key, doc_id) in get_doc_ids_of_user(self, uid)? {
+      let (_, object_id) = split_workspace_and_object_id(&id_key, None);
+      report.num_of_objects += 1;
+
+      let mut bad_records = vec![];
+      let doc = make_yrs_doc(true);
+      {
+        let mut txn = doc.transact_mut();
+        let doc_state_key = make_doc_state_key(doc_id);
+        match self.get(doc_state_key.as_ref())? {
+          None => report.issues.push(make_issue(
+            &object_id,
+            vec![],
+            VerifyIssueKind::MissingDocState,
+          )),
+          Some(doc_state) => {
+            report.num_of_records += 1;
+            let checked =
+              if is_record_checksum_valid(self, doc_state_key.as_ref(), doc_state.as_ref())? {
+                apply_encoded_update(&mut txn, doc_state.as_ref())
+                  .map_err(|err| VerifyIssueKind::InvalidDocState(err.to_string()))
+              } else {
+                Err(VerifyIssueKind::ChecksumMismatch)
+              };
+            match checked {
+              Ok(_) => {
+                let sv_key = make_state_vector_key(doc_id);
+                if let Some(sv) = self.get(sv_key.as_ref())? {
+                  report.num_of_records += 1;
+                  if !is_record_checksum_valid(self, sv_key.as_ref(), sv.as_ref())? {
+                    bad_records.push((sv_key.to_vec(), VerifyIssueKind::ChecksumMismatch));
+                  } else if sv.as_ref() != txn.state_vector().encode_v1().as_slice() {
+                    report.issues.push(make_issue(
+                      &object_id,
+                      sv_key.to_vec(),
+                      VerifyIssueKind::StateVectorMismatch,
+                    ));
+                  }
+                }
+              },
+              Err(kind) => bad_records.push((doc_state_key.to_vec(), kind)),
+            }
+          },
+        }
+
+        let start = make_doc_update_key(doc_id, 0);
+        let end = make_doc_update_key(doc_id, Clock::MAX);
+        let mut has_bad_update = false;
+        for entry in self.range(start.as_ref()..=end.as_ref())? {
+          report.num_of_records += 1;
+          let kind = if has_bad_update {
+            Some(VerifyIssueKind::FollowsBadUpdate)
+          } else if !is_record_checksum_valid(self, entry.key(), entry.value())? {
+            Some(VerifyIssueKind::ChecksumMismatch)
+          } else {
+            apply_encoded_update(&mut txn, entry.value())
+              .err()
+              .map(|err| VerifyIssueKind::InvalidUpdate(err.to_string()))
+          };
+          if let Some(kind) = kind {
+            has_bad_update = true;
+            bad_records.push((entry.key().to_vec(), kind));
+          }
+        }
+
+        if txn.store().pending_update().is_some() || txn.store().pending_ds().is_some() {
+          report.issues.push(make_issue(
+            &object_id,
+            vec![],
+            VerifyIssueKind::MissingDependencies,
+          ));
+        }
+      }
+
+      if let Some(snapshot_id) = get_snapshot_id(uid, self, object_id.as_bytes()) {
+        let start = make_snapshot_update_key(snapshot_id, 0);
+        let end = make_snapshot_update_key(snapshot_id, Clock::MAX);
+        for entry in self.range(start.as_ref()..=end.as_ref())? {
+          report.num_of_records += 1;
+          if !is_record_checksum_valid(self, entry.key(), entry.value())? {
+            bad_records.push((entry.key().to_vec(), VerifyIssueKind::ChecksumMismatch));
+          } else if let Err(err) = verify_snapshot(entry.value()) {
+            bad_records.push((
+              entry.key().to_vec(),
+              VerifyIssueKind::InvalidSnapshot(err.to_string()),
+            ));
+          }
+        }
+      }
+
+      for (key, kind) in bad_records {
+        let mut issue = make_issue(&object_id, key, kind);
+        if quarantine {
+          quarantine_record(self, uid, &issue.key)?;
+          issue.quarantined = true;
+        }
+        report.issues.push(issue);
+      }
+    }
+
+    if !report.is_ok() {
+      tracing::warn!(
+        "🟡verify uid:{} found {} issues, {} quarantined",
+        uid,
+        report.issues.len(),
+        report.num_of_quarantined()
+      );
+    }
+    Ok(report)
+  }
+
+  /// Return the records of the given user that were moved out by [VerifyAction::verify].
+  fn get_quarantined_records(&self, uid: i64) -> Result<Vec<QuarantinedRecord>, PersistenceError> {
+    let from = make_quarantine_key(uid, &[]);
+    let to = make_quarantine_key(uid, &[TERMINATOR_HI_WATERMARK]);
+    let prefix_len = from.len();
+    let records = self
+      .range(from.as_ref()..to.as_ref())?
+      .map(|entry| QuarantinedRecord {
+        key: entry.key()[prefix_len..].to_vec(),
+        value: entry.value().to_vec(),
+      })
+      .collect();
+    Ok(records)
+  }
+}
+
+fn make_issue(object_id: &str, key: Vec<u8>, kind: VerifyIssueKind) -> VerifyIssue {
+  VerifyIssue {
+    object_id: object_id.to_string(),
+    key,
+    kind,
+    quarantined: false,
+  }
+}
+
+fn apply_encoded_update(txn: &mut TransactionMut, data: &[u8]) -> Result<(), PersistenceError> {
+  let update = Update::decode_v1(data)?;
+  txn.try_apply_update(update)
+}
+
+fn verify_snapshot(data: &[u8]) -> Result<(), PersistenceError> {
+  let snapshot = CollabSnapshot::try_from(data)?;
+  let doc = make_yrs_doc(true);
+  let mut txn = doc.transact_mut();
+  apply_encoded_update(&mut txn, &snapshot.data)?;
+  if txn.store().pending_update().is_some() {
+    return Err(PersistenceError::InvalidData(
+      "snapshot depends on missing data".to_string(),
+    ));
+  }
+  Ok(())
+}
+
+fn make_quarantine_key(uid: i64, key: &[u8]) -> Key<20> {
+  let mut v: SmallVec<[u8; 20]> = smallvec![QUARANTINE_SPACE];
+  v.extend_from_slice(&uid.to_be_bytes());
+  v.extend_from_slice(key);
+  Key(v)
+}
+
+fn quarantine_record<'a, S>(store: &S, uid: i64, key: &[u8]) -> Result<(), PersistenceError>
+where
+  S: KVStore<'a>,
+  PersistenceError: From<<S as KVStore<'a>>::Error>,
+{
+  if let Some(value) = store.get(key)? {
+    store.insert(make_quarantine_key(uid, key), value)?;
+    remove_record(store, key)?;
+  }
+  Ok(())
+}
@@ -0,0 +1,214 @@
+use std::sync::{Arc, Mutex};
+
+use anyhow::anyhow;
+use collab::core::origin::CollabOrigin;
+use collab::lock::RwLock;
+use collab::preclude::{Collab, CollabPlugin};
+use tokio::sync::{watch, RwLockReadGuard, RwLockWriteGuard};
+use tokio::task::JoinHandle;
+use tracing::{error, trace};
+use yrs::updates::decoder::Decode;
+use yrs::Update;
+
+use crate::local_storage::kv::doc::{get_doc_id, CollabKVAction};
+use crate::local_storage::kv::keys::make_doc_state_key;
+use crate::local_storage::kv::snapshot::SnapshotAction;
+use crate::local_storage::kv::{KVStore, KVTransactionDB, PersistenceError, TransactionMutExt};
+use crate::CollabKVDB;
+
+/// Number of updates applied each time the replay task holds the write lock of the collab. The
+/// lock is released between two batches, so the readers are not blocked by a long replay.
+const REPLAY_BATCH_SIZE: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpenState {
+  /// The collab only contains the latest snapshot, the remaining updates are being replayed. A
+  /// collab stays read only if the replay failed.
+  ReadOnly,
+  /// All the updates were replayed and the collab is initialized with its plugins.
+  Writable,
+}
+
+/// A collab opened with [open_collab_from_snapshot]. It can be read right away, but it's only
+/// handed out for writing once the background replay flipped it to [OpenState::Writable].
+pub struct FastOpenCollab {
+  collab: Arc<RwLock<Collab>>,
+  state: watch::Receiver<OpenState>,
+  /// The error of the replay, set before the sender of the state is dropped.
+  replay_error: Arc<Mutex<Option<String>>>,
+  replay: JoinHandle<Result<u32, PersistenceError>>,
+}
+
+impl FastOpenCollab {
+  pub fn state(&self) -> OpenState {
+    *self.state.borrow()
+  }
+
+  pub fn is_writable(&self) -> bool {
+    self.state() == OpenState::Writable
+  }
+
+  pub fn subscribe_state(&self) -> watch::Receiver<OpenState> {
+    self.state.clone()
+  }
+
+  /// Read the collab. Before it's writable it only contains the snapshot and the updates that
+  /// were replayed so far.
+  pub async fn read(&self) -> RwLockReadGuard<'_, Collab> {
+    self.collab.read().await
+  }
+
+  /// Wait until all the updates are replayed, then lock the collab for writing. Return the error
+  /// of the replay if it failed: the collab is missing some of its updates, so it's not written.
+  pub async fn write(&self) -> Result<RwLockWriteGuard<'_, Collab>, PersistenceError> {
+    self.wait_until_writable().await?;
+    Ok(self.collab.write().await)
+  }
+
+  pub async fn wait_until_writable(&self) -> Result<(), PersistenceError> {
+    let mut state = self.state.clone();
+    // The sender is dropped without sending Writable when the replay failed.
+    match state.wait_for(|state| *state == OpenState::Writable).await {
+      Ok(_) => Ok(()),
+      Err(_) => {
+        let replay_error = self
+          .replay_error
+          .lock()
+          .unwrap()
+          .clone()
+          .unwrap_or_else(|| "the replay was aborted".to_string());
+        Err(PersistenceError::Internal(anyhow!(
+          "replay updates failed: {}",
+          replay_error
+        )))
+      },
+    }
+  }
+
+  /// Wait until all the updates are replayed and return the collab with the number of updates
+  /// that were applied on top of the snapshot.
+  pub async fn into_collab(self) -> Result<(Arc<RwLock<Collab>>, u32), PersistenceError> {
+    let num_of_updates = self
+      .replay
+      .await
+      .map_err(|err| PersistenceError::Internal(err.into()))??;
+    Ok((self.collab, num_of_updates))
+  }
+}
+
+/// Open the collab from its latest snapshot, or from its document state if it has no snapshot,
+/// and replay the updates persisted after it in a background task. Opening a heavily edited
+/// document doesn't wait for all its updates to be decoded and applied.
+///
+/// The collab is initialized with the `plugins` once the replay is done, so the replayed updates
+/// are not persisted again by the disk plugins. If the replay fails, the collab is neither
+/// initialized nor writable.
+pub fn open_collab_from_snapshot(
+  db: Arc<CollabKVDB>,
+  uid: i64,
+  workspace_id: &str,
+  origin: CollabOrigin,
+  object_id: &str,
+  plugins: Vec<Box<dyn CollabPlugin>>,
+  skip_gc: bool,
+) -> Result<FastOpenCollab, PersistenceError> {
+  let (from_snapshot, initial_state) = {
+    let read_txn = db.read_txn();
+    let doc_id = get_doc_id(uid, &read_txn, workspace_id, object_id).ok_or_else(|| {
+      PersistenceError::RecordNotFound(format!(
+        "doc with given object id: {:?} is not found",
+        object_id
+      ))
+    })?;
+    match read_txn.get_last_snapshot(uid, object_id) {
+      Some(snapshot) => (true, snapshot.data),
+      None => {
+        let doc_state = read_txn
+          .get(make_doc_state_key(doc_id).as_ref())?
+          .ok_or(PersistenceError::UnexpectedEmptyUpdates)?;
+        (false, doc_state)
+      },
+    }
+  };
+
+  let mut collab = Collab::new_with_origin(origin, object_id, plugins, skip_gc);
+  collab
+    .transact_mut()
+    .try_apply_update(Update::decode_v1(&initial_state)?)?;
+  let collab = Arc::new(RwLock::new(collab));
+  let (state_tx, state_rx) = watch::channel(OpenState::ReadOnly);
+
+  let replay_error = Arc::new(Mutex::new(None));
+  let replay = {
+    let collab = collab.clone();
+    let replay_error = replay_error.clone();
+    let workspace_id = workspace_id.to_string();
+    let object_id = object_id.to_string();
+    tokio::spawn(async move {
+      let result =
+        replay_updates(&db, uid, &workspace_id, &object_id, from_snapshot, &collab).await;
+      match &result {
+        Ok(_) => {
+          collab.write().await.initialize();
+          let _ = state_tx.send(OpenState::Writable);
+        },
+        Err(err) => {
+          error!("🔴replay updates of {} failed: {}", object_id, err);
+          *replay_error.lock().unwrap() = Some(err.to_string());
+        },
+      }
+      result
+    })
+  };
+
+  Ok(FastOpenCollab {
+    collab,
+    state: state_rx,
+    replay_error,
+    replay,
+  })
+}
+
+async fn replay_updates(
+  db: &CollabKVDB,
+  uid: i64,
+  workspace_id: &str,
+  object_id: &str,
+  from_snapshot: bool,
+  collab: &RwLock<Collab>,
+) -> Result<u32, PersistenceError> {
+  let mut encoded_updates = vec![];
+  let num_of_doc_states;
+  {
+    let read_txn = db.read_txn();
+    // The snapshot may be older than the document state, which is merged again like an update.
+    if from_snapshot {
+      if let Some(doc_id) = get_doc_id(uid, &read_txn, workspace_id, object_id) {
+        if let Some(doc_state) = read_txn.get(make_doc_state_key(doc_id).as_ref())? {
+          encoded_updates.push(doc_state);
+        }
+      }
+    }
+    num_of_doc_states = encoded_updates.len();
+    encoded_updates.extend(read_txn.get_all_updates(uid, workspace_id, object_id)?);
+  }
+
+  let mut num_of_updates = 0u32;
+  for batch in encoded_updates.chunks(REPLAY_BATCH_SIZE) {
+    let mut collab = collab.write().await;
+    let mut txn = collab.transact_mut();
+    for encoded_update in batch {
+      // Like loading the document, the updates after an invalid one are not applied.
+      Update::decode_v1(encoded_update)
+        .map_err(PersistenceError::Yrs)
+        .and_then(|update| txn.try_apply_update(update))?;
+      num_of_updates += 1;
+    }
+    drop(txn);
+    drop(collab);
+    tokio::task::yield_now().await;
+  }
+  let num_of_updates = num_of_updates - num_of_doc_states as u32;
+  trace!("replayed {} updates of {}", num_of_updates, object_id);
+  Ok(num_of_updates)
+}
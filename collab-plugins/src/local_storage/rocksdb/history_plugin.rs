@@ -0,0 +1,79 @@
+use crate::local_storage::kv::history::{HistoryAction, HistoryUpdateSummary};
+use crate::local_storage::kv::{KVTransactionDB, PersistenceError};
+use crate::CollabKVDB;
+
+use std::ops::Range;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::{Arc, Weak};
+
+use collab::core::collab_plugin::CollabPluginType;
+use collab::core::origin::CollabOrigin;
+use collab::preclude::{Collab, CollabPlugin};
+use tracing::error;
+use yrs::TransactionMut;
+
+/// Persists every update of the collab, along with the time it was captured and its origin, into
+/// the history of the object. Use [Self::query_history] or [HistoryAction::query_history] to
+/// build an activity timeline of the object.
+#[derive(Clone)]
+pub struct RocksdbHistoryPlugin {
+  uid: i64,
+  object_id: String,
+  collab_db: Weak<CollabKVDB>,
+  did_init: Arc<AtomicBool>,
+}
+
+impl RocksdbHistoryPlugin {
+  pub fn new(uid: i64, object_id: String, collab_db: Weak<CollabKVDB>) -> Self {
+    Self {
+      uid,
+      object_id,
+      collab_db,
+      did_init: Arc::new(AtomicBool::new(false)),
+    }
+  }
+
+  /// Return the summaries of the updates captured within the time range, in milliseconds.
+  pub fn query_history(
+    &self,
+    time_range: Range<i64>,
+  ) -> Result<Vec<HistoryUpdateSummary>, PersistenceError> {
+    let collab_db = self.collab_db.upgrade().ok_or_else(|| {
+      PersistenceError::RecordNotFound(format!("collab_db of {} is dropped", self.object_id))
+    })?;
+    let read_txn = collab_db.read_txn();
+    read_txn.query_history(self.uid, &self.object_id, time_range)
+  }
+}
+
+impl CollabPlugin for RocksdbHistoryPlugin {
+  fn did_init(&self, _collab: &Collab, _object_id: &str) {
+    self.did_init.store(true, SeqCst);
+  }
+
+  fn receive_update(&self, object_id: &str, txn: &TransactionMut, update: &[u8]) {
+    // The updates applied while loading the doc are already in the history
+    if !self.did_init.load(SeqCst) {
+      return;
+    }
+    match self.collab_db.upgrade() {
+      None => tracing::warn!("[History Plugin]: collab_db is dropped"),
+      Some(db) => {
+        let origin = CollabOrigin::from(txn);
+        let result =
+          db.with_write_txn(|w_db_txn| w_db_txn.push_history(self.uid, object_id, &origin, update));
+        if let Err(err) = result {
+          error!(
+            "[History Plugin]: {} save history failed: {:?}",
+            object_id, err
+          );
+        }
+      },
+    }
+  }
+
+  fn plugin_type(&self) -> CollabPluginType {
+    CollabPluginType::Other("RocksdbHistoryPlugin".to_string())
+  }
+}
@@ -3,9 +3,16 @@ use std::ops::RangeBounds;
 use std::path::Path;
 use std::sync::Arc;
 
+use collab::core::mapped_file::MappedFile;
+
+use crate::local_storage::kv::archive::{ArchiveAction, CollabArchive};
+use crate::local_storage::kv::batch::{CollabBatchAction, CollabBatchWriter, CollabWriteBatch};
 use crate::local_storage::kv::doc::CollabKVAction;
+use crate::local_storage::kv::verify::{VerifyAction, VerifyReport};
 
-use crate::local_storage::kv::{KVEntry, KVStore, KVTransactionDB, PersistenceError};
+use crate::local_storage::kv::{
+  remove_all_record_checksums, KVEntry, KVStore, KVTransactionDB, PersistenceError,
+};
 use rocksdb::Direction::Forward;
 use rocksdb::{
   DBIteratorWithThreadMode, Direction, ErrorKind, IteratorMode, Options, ReadOptions,
@@ -16,12 +23,24 @@ use rocksdb::{
 #[derive(Clone)]
 pub struct KVTransactionDBRocksdbImpl {
   db: Arc<TransactionDB>,
+  record_checksums: bool,
 }
 
 impl KVTransactionDBRocksdbImpl {
   /// Open a new RocksDB database at the given path.
   /// If the database is corrupted, try to repair it. If it cannot be repaired, return an error.
   pub fn open(path: impl AsRef<Path>) -> Result<Self, PersistenceError> {
+    Self::open_with_record_checksums(path, false)
+  }
+
+  /// Same as [Self::open]. When `record_checksums` is true, each document state, state vector,
+  /// update and snapshot is written with a checksum that is checked when it's read back, at the
+  /// cost of an extra key per record. Otherwise the checksums left by a previous session are
+  /// removed, since the records they cover may be overwritten without them.
+  pub fn open_with_record_checksums(
+    path: impl AsRef<Path>,
+    record_checksums: bool,
+  ) -> Result<Self, PersistenceError> {
     let auto_repair = false;
     let txn_db_opts = TransactionDBOptions::default();
     let mut db_opts = Options::default();
@@ -61,6 +80,12 @@ impl KVTransactionDBRocksdbImpl {
     db_opts.set_level_zero_slowdown_writes_trigger(5);
     db_opts.set_level_zero_stop_writes_trigger(10);
 
+    // compaction of the deleted keys
+    // The pruned updates and the deleted documents leave tombstones that are only dropped when
+    // their files are compacted. A file with at least 1000 deletions in any 10000 consecutive
+    // entries, or with 50% of deletions, is marked for compaction when it's written.
+    db_opts.add_compact_on_deletion_collector_factory(10_000, 1_000, 0.5);
+
     // log
     // don't set the log dir (set_db_log_dir) because it will cause the 'file name too long' error on mobile platform
     db_opts.set_recycle_log_file_num(5);
@@ -109,7 +134,14 @@ impl KVTransactionDBRocksdbImpl {
       },
     }?;
 
-    Ok(Self { db: Arc::new(db) })
+    let db = Self {
+      db: Arc::new(db),
+      record_checksums,
+    };
+    if !record_checksums {
+      db.with_write_txn(remove_all_record_checksums)?;
+    }
+    Ok(db)
   }
 
   pub async fn is_exist(
@@ -131,6 +163,84 @@ impl KVTransactionDBRocksdbImpl {
     self.with_write_txn(|txn| txn.delete_doc(uid, workspace_id, doc_id))?;
     Ok(())
   }
+
+  /// Apply the [CollabWriteBatch] within a single transaction. Either all the operations are
+  /// committed or none of them is.
+  pub fn write_batch(&self, batch: &CollabWriteBatch) -> Result<(), PersistenceError> {
+    if batch.is_empty() {
+      return Ok(());
+    }
+    self.with_write_txn(|txn| txn.apply_batch(batch))
+  }
+
+  /// Write all the documents, updates and snapshots of the given user into a single archive
+  /// file. Return the number of archived objects.
+  pub fn export_archive(
+    &self,
+    uid: i64,
+    path: impl AsRef<Path>,
+  ) -> Result<usize, PersistenceError> {
+    let archive = self.read_txn().export_archive_data(uid)?;
+    std::fs::write(path, archive.encode()?)
+      .map_err(|err| PersistenceError::Internal(err.into()))?;
+    Ok(archive.objects.len())
+  }
+
+  /// Same as [Self::export_archive], but only for the objects of the given workspace, so a single
+  /// workspace can be migrated to another store.
+  pub fn export_workspace_archive(
+    &self,
+    uid: i64,
+    workspace_id: &str,
+    path: impl AsRef<Path>,
+  ) -> Result<usize, PersistenceError> {
+    let archive = self
+      .read_txn()
+      .export_workspace_archive_data(uid, workspace_id)?;
+    std::fs::write(path, archive.encode()?)
+      .map_err(|err| PersistenceError::Internal(err.into()))?;
+    Ok(archive.objects.len())
+  }
+
+  /// Restore the archive file created by [Self::export_archive]. The archive is verified before
+  /// anything is written, and all the objects are written within a single transaction. The file
+  /// is memory-mapped, so it's not read into memory before it's decoded.
+  /// Return the number of restored objects.
+  ///
+  /// # Safety
+  ///
+  /// The file must not be modified or truncated while it's imported, see [MappedFile::map].
+  pub unsafe fn import_archive(&self, path: impl AsRef<Path>) -> Result<usize, PersistenceError> {
+    let data = MappedFile::open(path).map_err(|err| PersistenceError::Internal(err.into()))?;
+    let archive = CollabArchive::decode(&data)?;
+    self.with_write_txn(|txn| txn.import_archive_data(&archive))?;
+    Ok(archive.objects.len())
+  }
+
+  /// Return the size in bytes of the files of the database on disk.
+  pub fn disk_size(&self) -> Result<u64, PersistenceError> {
+    let entries =
+      std::fs::read_dir(self.db.path()).map_err(|err| PersistenceError::Internal(err.into()))?;
+    let mut size = 0;
+    for entry in entries.flatten() {
+      if let Ok(metadata) = entry.metadata() {
+        if metadata.is_file() {
+          size += metadata.len();
+        }
+      }
+    }
+    Ok(size)
+  }
+
+  /// Verify the persisted data of the given user. See [VerifyAction::verify] for the checks.
+  /// When `quarantine` is true, the bad records are moved out within a single transaction.
+  pub fn verify(&self, uid: i64, quarantine: bool) -> Result<VerifyReport, PersistenceError> {
+    if quarantine {
+      self.with_write_txn(|txn| txn.verify(uid, true))
+    } else {
+      self.read_txn().verify(uid, false)
+    }
+  }
 }
 
 impl KVTransactionDB for KVTransactionDBRocksdbImpl {
@@ -149,7 +259,7 @@ impl KVTransactionDB for KVTransactionDBRocksdbImpl {
     let txn = self
       .db
       .transaction_opt(&WriteOptions::default(), &txn_options);
-    RocksdbKVStoreImpl::new(txn)
+    RocksdbKVStoreImpl::new(txn).with_record_checksums(self.record_checksums)
   }
 
   fn write_txn<'a, 'b>(&'b self) -> Self::TransactionAction<'a>
@@ -160,7 +270,7 @@ impl KVTransactionDB for KVTransactionDBRocksdbImpl {
     let txn = self
       .db
       .transaction_opt(&WriteOptions::default(), &txn_options);
-    RocksdbKVStoreImpl::new(txn)
+    RocksdbKVStoreImpl::new(txn).with_record_checksums(self.record_checksums)
   }
 
   fn with_write_txn<'a, 'b, Output>(
@@ -174,9 +284,9 @@ impl KVTransactionDB for KVTransactionDBRocksdbImpl {
     let txn = self
       .db
       .transaction_opt(&WriteOptions::default(), &txn_options);
-    let store = RocksdbKVStoreImpl::new(txn);
+    let store = RocksdbKVStoreImpl::new(txn).with_record_checksums(self.record_checksums);
     let result = f(&store)?;
-    store.0.commit()?;
+    store.txn.commit()?;
     Ok(result)
   }
 
@@ -185,19 +295,36 @@ impl KVTransactionDB for KVTransactionDBRocksdbImpl {
   }
 }
 
+impl CollabBatchWriter for KVTransactionDBRocksdbImpl {
+  fn write_batch(&self, batch: &CollabWriteBatch) -> Result<(), PersistenceError> {
+    KVTransactionDBRocksdbImpl::write_batch(self, batch)
+  }
+}
+
 /// Implementation of [KVStore] for [KVTransactionDBRocksdbImpl]. This is a wrapper around [Transaction].
-// pub struct RocksKVStoreImpl<'a, DB: Send + Sync>(Transaction<'a, DB>);
-pub struct RocksdbKVStoreImpl<'a, DB: Send>(Transaction<'a, DB>);
+pub struct RocksdbKVStoreImpl<'a, DB: Send> {
+  txn: Transaction<'a, DB>,
+  record_checksums: bool,
+}
 
 unsafe impl<'a, DB: Send> Send for RocksdbKVStoreImpl<'a, DB> {}
 
 impl<'a, DB: Send + Sync> RocksdbKVStoreImpl<'a, DB> {
   pub fn new(txn: Transaction<'a, DB>) -> Self {
-    Self(txn)
+    Self {
+      txn,
+      record_checksums: false,
+    }
+  }
+
+  /// See [KVStore::record_checksums].
+  pub fn with_record_checksums(mut self, record_checksums: bool) -> Self {
+    self.record_checksums = record_checksums;
+    self
   }
 
   pub fn commit_transaction(self) -> Result<(), PersistenceError> {
-    self.0.commit()?;
+    self.txn.commit()?;
     Ok(())
   }
 }
@@ -209,7 +336,7 @@ impl<'a, DB: Send + Sync> KVStore<'a> for RocksdbKVStoreImpl<'a, DB> {
   type Error = PersistenceError;
 
   fn get<K: AsRef<[u8]>>(&self, key: K) -> Result<Option<Self::Value>, Self::Error> {
-    if let Some(value) = self.0.get(key)? {
+    if let Some(value) = self.txn.get(key)? {
       Ok(Some(value))
     } else {
       Ok(None)
@@ -217,12 +344,12 @@ impl<'a, DB: Send + Sync> KVStore<'a> for RocksdbKVStoreImpl<'a, DB> {
   }
 
   fn insert<K: AsRef<[u8]>, V: AsRef<[u8]>>(&self, key: K, value: V) -> Result<(), Self::Error> {
-    self.0.put(key, value)?;
+    self.txn.put(key, value)?;
     Ok(())
   }
 
   fn remove(&self, key: &[u8]) -> Result<(), Self::Error> {
-    self.0.delete(key)?;
+    self.txn.delete(key)?;
     Ok(())
   }
 
@@ -231,11 +358,11 @@ impl<'a, DB: Send + Sync> KVStore<'a> for RocksdbKVStoreImpl<'a, DB> {
     opt.set_iterate_lower_bound(from);
     opt.set_iterate_upper_bound(to);
     let i = self
-      .0
+      .txn
       .iterator_opt(IteratorMode::From(from, Direction::Forward), opt);
     for res in i {
       let (key, _) = res?;
-      self.0.delete(key)?;
+      self.txn.delete(key)?;
     }
     Ok(())
   }
@@ -268,7 +395,7 @@ impl<'a, DB: Send + Sync> KVStore<'a> for RocksdbKVStoreImpl<'a, DB> {
       ops::Bound::Unbounded => {},
     };
     let iterator_mode = IteratorMode::From(from, Forward);
-    let iter = self.0.iterator_opt(iterator_mode, opt);
+    let iter = self.txn.iterator_opt(iterator_mode, opt);
     Ok(RocksdbRange {
       // Safe to transmute because the lifetime of the iterator is the same as the lifetime of the
       // transaction.
@@ -284,7 +411,7 @@ impl<'a, DB: Send + Sync> KVStore<'a> for RocksdbKVStoreImpl<'a, DB> {
 
   fn next_back_entry(&self, key: &[u8]) -> Result<Option<Self::Entry>, Self::Error> {
     let opt = ReadOptions::default();
-    let mut raw = self.0.raw_iterator_opt(opt);
+    let mut raw = self.txn.raw_iterator_opt(opt);
     raw.seek_for_prev(key);
     if let Some((key, value)) = raw.item() {
       Ok(Some(RocksdbEntry::new(key.to_vec(), value.to_vec())))
@@ -292,6 +419,10 @@ impl<'a, DB: Send + Sync> KVStore<'a> for RocksdbKVStoreImpl<'a, DB> {
       Ok(None)
     }
   }
+
+  fn record_checksums(&self) -> bool {
+    self.record_checksums
+  }
 }
 
 impl<'a, DB: Send + Sync> From<Transaction<'a, DB>> for RocksdbKVStoreImpl<'a, DB> {
@@ -0,0 +1,336 @@
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use collab::core::collab::make_yrs_doc;
+use collab::core::collab_plugin::CollabPluginType;
+use collab::core::origin::CollabOrigin;
+use collab::preclude::CollabPlugin;
+use tracing::{error, info, trace};
+use yrs::{ReadTxn, StateVector, Transact};
+
+use crate::local_storage::kv::doc::{CollabKVAction, StoredObjectInfo};
+use crate::local_storage::kv::prune::{PruneAction, ReclaimableSpace};
+use crate::local_storage::kv::{KVTransactionDB, PersistenceError};
+use crate::local_storage::UpdatePrunePolicy;
+use crate::CollabKVDB;
+
+/// Controls which objects are compacted by the [MaintenanceService].
+#[derive(Clone, Debug)]
+pub struct MaintenancePolicy {
+  /// Time between two runs. Default is 1 hour.
+  pub interval: Duration,
+  /// The objects with fewer updates are skipped. Default is 100.
+  pub min_updates: usize,
+  /// The objects written during the last `idle_seconds` are skipped, they are probably being
+  /// edited. The service is also paused for `idle_seconds` after each local edit reported by a
+  /// [MaintenanceEditingPlugin]. Default is 300.
+  pub idle_seconds: i64,
+  /// Number of the most recent updates that are kept, see [UpdatePrunePolicy::safety_margin].
+  /// Default is 10.
+  pub safety_margin: u32,
+}
+
+impl MaintenancePolicy {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn interval(mut self, interval: Duration) -> Self {
+    self.interval = interval;
+    self
+  }
+
+  pub fn min_updates(mut self, min_updates: usize) -> Self {
+    self.min_updates = min_updates;
+    self
+  }
+
+  pub fn idle_seconds(mut self, idle_seconds: i64) -> Self {
+    self.idle_seconds = idle_seconds;
+    self
+  }
+
+  pub fn safety_margin(mut self, safety_margin: u32) -> Self {
+    self.safety_margin = safety_margin;
+    self
+  }
+}
+
+impl Default for MaintenancePolicy {
+  fn default() -> Self {
+    Self {
+      interval: Duration::from_secs(60 * 60),
+      min_updates: 100,
+      idle_seconds: 300,
+      safety_margin: 10,
+    }
+  }
+}
+
+/// The result of one run of the [MaintenanceService].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MaintenanceReport {
+  /// Number of objects that were visited.
+  pub num_of_objects: usize,
+  /// Number of objects that got a new snapshot and had their updates pruned.
+  pub num_of_compacted: usize,
+  /// Number of objects that failed to compact. The errors are logged.
+  pub num_of_failed: usize,
+  /// The space reclaimed by the pruned updates.
+  pub reclaimed: ReclaimableSpace,
+  /// The disk space freed during the run, from the size of the database files before and after
+  /// it. RocksDB drops the pruned updates from its files when it compacts them, in the
+  /// background, so the space can also be freed after the run.
+  pub disk_reclaimed: u64,
+  /// True if the run stopped early because the service was paused.
+  pub interrupted: bool,
+}
+
+/// Walks the stored objects of a user in a workspace, writes a snapshot of the objects that
+/// accumulated updates and prunes the updates that the snapshot covers, so long-lived installs
+/// don't replay an ever-growing list of updates when opening a document. RocksDB compacts the
+/// files holding the deleted keys, as set up by [CollabKVDB::open], and the disk space freed is
+/// reported in [MaintenanceReport::disk_reclaimed].
+///
+/// The service is opt-in, it only runs when [MaintenanceService::run_once] is called or after
+/// [MaintenanceService::spawn]. It's paused while the user is editing the collabs that have a
+/// [MaintenanceEditingPlugin].
+///
+/// ```ignore
+/// let service =
+///   MaintenanceService::new(uid, workspace_id, Arc::downgrade(&db), MaintenancePolicy::new());
+/// let _handle = service.clone().spawn(|report| info!("compaction: {:?}", report));
+/// collab.add_plugin(Box::new(service.editing_plugin()));
+/// ```
+#[derive(Clone)]
+pub struct MaintenanceService {
+  uid: i64,
+  workspace_id: String,
+  collab_db: Weak<CollabKVDB>,
+  policy: MaintenancePolicy,
+  pause_count: Arc<AtomicUsize>,
+  /// Timestamp in seconds of the last local edit, 0 if there was none.
+  last_edited_at: Arc<AtomicI64>,
+}
+
+impl MaintenanceService {
+  pub fn new(
+    uid: i64,
+    workspace_id: impl ToString,
+    collab_db: Weak<CollabKVDB>,
+    policy: MaintenancePolicy,
+  ) -> Self {
+    Self {
+      uid,
+      workspace_id: workspace_id.to_string(),
+      collab_db,
+      policy,
+      pause_count: Arc::new(AtomicUsize::new(0)),
+      last_edited_at: Arc::new(AtomicI64::new(0)),
+    }
+  }
+
+  /// Return a plugin that pauses the service while the collab is edited, that is until
+  /// [MaintenancePolicy::idle_seconds] passed since its last local update.
+  pub fn editing_plugin(&self) -> MaintenanceEditingPlugin {
+    MaintenanceEditingPlugin {
+      last_edited_at: self.last_edited_at.clone(),
+    }
+  }
+
+  /// Pause the service until the returned guard is dropped. A run in progress stops before the
+  /// next object. The service can be paused several times, it resumes when all the guards are
+  /// dropped.
+  pub fn pause(&self) -> MaintenancePauseGuard {
+    self.pause_count.fetch_add(1, Ordering::AcqRel);
+    MaintenancePauseGuard {
+      pause_count: self.pause_count.clone(),
+    }
+  }
+
+  /// Return true if the service was paused with [MaintenanceService::pause], or if a collab was
+  /// edited during the last [MaintenancePolicy::idle_seconds].
+  pub fn is_paused(&self) -> bool {
+    self.pause_count.load(Ordering::Acquire) > 0 || self.is_editing()
+  }
+
+  fn is_editing(&self) -> bool {
+    let last_edited_at = self.last_edited_at.load(Ordering::Acquire);
+    last_edited_at > 0 && chrono::Utc::now().timestamp() - last_edited_at < self.policy.idle_seconds
+  }
+
+  /// Compact the objects of the workspace that match the policy and return what was reclaimed.
+  /// The objects stored with the legacy key format, which doesn't contain the workspace id, are
+  /// compacted too.
+  pub fn run_once(&self) -> Result<MaintenanceReport, PersistenceError> {
+    let mut report = MaintenanceReport::default();
+    let collab_db = match self.collab_db.upgrade() {
+      None => return Ok(report),
+      Some(collab_db) => collab_db,
+    };
+    let disk_size_before = collab_db.disk_size()?;
+    let objects = collab_db
+      .read_txn()
+      .list_objects(self.uid)?
+      .into_iter()
+      .filter(|object| {
+        object
+          .workspace_id
+          .as_ref()
+          .map_or(true, |workspace_id| *workspace_id == self.workspace_id)
+      });
+    let now = chrono::Utc::now().timestamp();
+    for object in objects {
+      if self.is_paused() {
+        report.interrupted = true;
+        break;
+      }
+      report.num_of_objects += 1;
+      if !self.should_compact(&object, now) {
+        continue;
+      }
+
+      match self.compact_object(&collab_db, &object) {
+        Ok(reclaimed) => {
+          trace!(
+            "compacted {}, reclaimed {} bytes",
+            object.object_id,
+            reclaimed.bytes
+          );
+          report.num_of_compacted += 1;
+          report.reclaimed.num_of_updates += reclaimed.num_of_updates;
+          report.reclaimed.bytes += reclaimed.bytes;
+        },
+        Err(err) => {
+          error!("🔴failed to compact {}: {:?}", object.object_id, err);
+          report.num_of_failed += 1;
+        },
+      }
+    }
+    report.disk_reclaimed = disk_size_before.saturating_sub(collab_db.disk_size()?);
+    Ok(report)
+  }
+
+  /// Run the service every [MaintenancePolicy::interval] until the returned handle is dropped or
+  /// the database is dropped. `on_report` is called after each run. The runs are skipped while
+  /// the service is paused.
+  pub fn spawn<F>(self, on_report: F) -> MaintenanceHandle
+  where
+    F: Fn(MaintenanceReport) + Send + Sync + 'static,
+  {
+    let stopped = Arc::new(AtomicBool::new(false));
+    let cloned_stopped = stopped.clone();
+    let on_report = Arc::new(on_report);
+    tokio::spawn(async move {
+      let mut interval = tokio::time::interval(self.policy.interval);
+      // The first tick completes immediately, the first run happens after one interval.
+      interval.tick().await;
+      loop {
+        interval.tick().await;
+        if cloned_stopped.load(Ordering::Acquire) || self.collab_db.strong_count() == 0 {
+          break;
+        }
+        if self.is_paused() {
+          continue;
+        }
+        let service = self.clone();
+        let on_report = on_report.clone();
+        let result = tokio::task::spawn_blocking(move || match service.run_once() {
+          Ok(report) => {
+            info!(
+              "[Client {}] maintenance compacted {} objects, reclaimed {} bytes",
+              service.uid, report.num_of_compacted, report.reclaimed.bytes
+            );
+            on_report(report);
+          },
+          Err(err) => error!("🔴maintenance failed: {:?}", err),
+        })
+        .await;
+        if result.is_err() {
+          break;
+        }
+      }
+    });
+    MaintenanceHandle { stopped }
+  }
+
+  fn should_compact(&self, object: &StoredObjectInfo, now: i64) -> bool {
+    if object.update_count < self.policy.min_updates.max(1) {
+      return false;
+    }
+    match object.last_modified {
+      Some(last_modified) => now - last_modified >= self.policy.idle_seconds,
+      None => true,
+    }
+  }
+
+  fn compact_object(
+    &self,
+    collab_db: &CollabKVDB,
+    object: &StoredObjectInfo,
+  ) -> Result<ReclaimableSpace, PersistenceError> {
+    // The objects stored with the legacy key format are found with the workspace id too.
+    let workspace_id = self.workspace_id.as_str();
+    let object_id = object.object_id.as_str();
+    let policy = UpdatePrunePolicy::new(self.policy.safety_margin);
+    collab_db.with_write_txn(|store| {
+      let doc = make_yrs_doc(true);
+      {
+        let mut txn = doc.transact_mut();
+        store.load_doc_with_txn(self.uid, workspace_id, object_id, &mut txn)?;
+      }
+      let snapshot_data = doc
+        .transact()
+        .encode_state_as_update_v1(&StateVector::default());
+      store.create_snapshot_with_policy(self.uid, workspace_id, object_id, snapshot_data, &policy)
+    })
+  }
+}
+
+/// Pauses the [MaintenanceService] while the collab it's added to is edited, see
+/// [MaintenanceService::editing_plugin]. Only the local updates count as edits.
+#[derive(Clone)]
+pub struct MaintenanceEditingPlugin {
+  last_edited_at: Arc<AtomicI64>,
+}
+
+impl CollabPlugin for MaintenanceEditingPlugin {
+  fn receive_local_update(&self, _origin: &CollabOrigin, _object_id: &str, _update: &[u8]) {
+    self
+      .last_edited_at
+      .store(chrono::Utc::now().timestamp(), Ordering::Release);
+  }
+
+  fn plugin_type(&self) -> CollabPluginType {
+    CollabPluginType::Other("MaintenanceEditingPlugin".to_string())
+  }
+}
+
+/// Resumes the [MaintenanceService] when dropped, see [MaintenanceService::pause].
+pub struct MaintenancePauseGuard {
+  pause_count: Arc<AtomicUsize>,
+}
+
+impl Drop for MaintenancePauseGuard {
+  fn drop(&mut self) {
+    self.pause_count.fetch_sub(1, Ordering::AcqRel);
+  }
+}
+
+/// Stops the runs of a spawned [MaintenanceService] when dropped.
+pub struct MaintenanceHandle {
+  stopped: Arc<AtomicBool>,
+}
+
+impl MaintenanceHandle {
+  pub fn stop(&self) {
+    self.stopped.store(true, Ordering::Release);
+  }
+}
+
+impl Drop for MaintenanceHandle {
+  fn drop(&mut self) {
+    self.stop();
+  }
+}
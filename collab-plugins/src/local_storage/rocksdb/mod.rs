@@ -1,4 +1,7 @@
+pub mod fast_open;
+pub mod history_plugin;
 pub mod kv_impl;
+pub mod maintenance;
 pub mod rocksdb_plugin;
 // pub mod snapshot_plugin;
 pub mod util;
@@ -5,6 +5,8 @@ pub struct CollabPersistenceConfig {
   /// Generate a snapshot every N updates
   /// Default is 100. The value must be greater than 0.
   pub snapshot_per_update: u32,
+  /// Controls how the updates covered by a snapshot are pruned.
+  pub prune_policy: UpdatePrunePolicy,
 }
 
 impl CollabPersistenceConfig {
@@ -22,6 +24,11 @@ impl CollabPersistenceConfig {
     self.snapshot_per_update = snapshot_per_update;
     self
   }
+
+  pub fn prune_policy(mut self, prune_policy: UpdatePrunePolicy) -> Self {
+    self.prune_policy = prune_policy;
+    self
+  }
 }
 
 impl Default for CollabPersistenceConfig {
@@ -29,6 +36,36 @@ impl Default for CollabPersistenceConfig {
     Self {
       enable_snapshot: true,
       snapshot_per_update: 100,
+      prune_policy: UpdatePrunePolicy::default(),
+    }
+  }
+}
+
+/// Once a snapshot is written for an object, the updates it covers are merged into the document
+/// state and removed from the update list, so opening the document doesn't need to replay them.
+#[derive(Clone, Debug)]
+pub struct UpdatePrunePolicy {
+  /// Prune the updates right after a snapshot is written. Default is [false].
+  pub enable: bool,
+  /// Number of the most recent covered updates that are kept as they are.
+  /// Default is 10.
+  pub safety_margin: u32,
+}
+
+impl UpdatePrunePolicy {
+  pub fn new(safety_margin: u32) -> Self {
+    Self {
+      enable: true,
+      safety_margin,
+    }
+  }
+}
+
+impl Default for UpdatePrunePolicy {
+  fn default() -> Self {
+    Self {
+      enable: false,
+      safety_margin: 10,
     }
   }
 }
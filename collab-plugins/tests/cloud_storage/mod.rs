@@ -0,0 +1,3 @@
+mod spawner_test;
+mod update_size_test;
+mod webhook_test;
@@ -0,0 +1,47 @@
+use std::time::Duration;
+
+use collab_plugins::cloud_storage::{default_spawner, Spawner, TokioSpawner};
+use tokio::sync::oneshot;
+
+#[tokio::test]
+async fn tokio_spawner_run_task_test() {
+  let spawner = TokioSpawner;
+  let (tx, rx) = oneshot::channel();
+  spawner.spawn(Box::pin(async move {
+    let _ = tx.send(1);
+  }));
+  assert_eq!(rx.await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn spawner_sleep_in_spawned_task_test() {
+  let spawner = default_spawner();
+  let cloned_spawner = spawner.clone();
+  let (tx, mut rx) = oneshot::channel();
+  spawner.spawn(Box::pin(async move {
+    cloned_spawner.sleep(Duration::from_millis(200)).await;
+    let _ = tx.send(());
+  }));
+
+  tokio::time::sleep(Duration::from_millis(50)).await;
+  assert!(rx.try_recv().is_err());
+  tokio::time::timeout(Duration::from_secs(2), rx)
+    .await
+    .unwrap()
+    .unwrap();
+}
+
+#[cfg(feature = "smol_runtime")]
+#[test]
+fn smol_spawner_sleep_test() {
+  use collab_plugins::cloud_storage::SmolSpawner;
+
+  let spawner = SmolSpawner;
+  let (tx, rx) = oneshot::channel();
+  let cloned_spawner = spawner;
+  spawner.spawn(Box::pin(async move {
+    cloned_spawner.sleep(Duration::from_millis(50)).await;
+    let _ = tx.send(1);
+  }));
+  assert_eq!(smol::block_on(rx).unwrap(), 1);
+}
@@ -0,0 +1,227 @@
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use anyhow::Error;
+use async_trait::async_trait;
+use collab::core::collab::DataSource;
+use collab::core::origin::CollabOrigin;
+use collab::preclude::{Collab, CollabPlugin};
+use collab_entity::{CollabObject, CollabType};
+use collab_plugins::cloud_storage::postgres::SupabaseDBPlugin;
+use collab_plugins::cloud_storage::{
+  default_spawner, PayloadChunk, RemoteCollabSnapshot, RemoteCollabState, RemoteCollabStorage,
+  RemoteUpdateReceiver, SyncError, UpdateSizePolicy,
+};
+use yrs::{Doc, Text, Transact};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Sent {
+  Update(Vec<u8>),
+  Chunk(PayloadChunk),
+}
+
+#[derive(Default)]
+struct RecordingStorage {
+  supports_chunks: bool,
+  sent: Mutex<Vec<Sent>>,
+}
+
+impl RecordingStorage {
+  fn sent(&self) -> Vec<Sent> {
+    self.sent.lock().unwrap().clone()
+  }
+}
+
+#[async_trait]
+impl RemoteCollabStorage for RecordingStorage {
+  fn is_enable(&self) -> bool {
+    true
+  }
+
+  async fn get_doc_state(&self, _object: &CollabObject) -> Result<DataSource, Error> {
+    Ok(DataSource::Disk(None))
+  }
+
+  async fn get_snapshots(&self, _object_id: &str, _limit: usize) -> Vec<RemoteCollabSnapshot> {
+    vec![]
+  }
+
+  async fn get_collab_state(&self, _object_id: &str) -> Result<Option<RemoteCollabState>, Error> {
+    Ok(None)
+  }
+
+  async fn create_snapshot(
+    &self,
+    _object: &CollabObject,
+    _snapshot: Vec<u8>,
+  ) -> Result<i64, Error> {
+    Ok(0)
+  }
+
+  async fn send_update(
+    &self,
+    _object: &CollabObject,
+    _id: u64,
+    update: Vec<u8>,
+  ) -> Result<(), Error> {
+    self.sent.lock().unwrap().push(Sent::Update(update));
+    Ok(())
+  }
+
+  fn supports_chunks(&self) -> bool {
+    self.supports_chunks
+  }
+
+  async fn send_chunk(
+    &self,
+    _object: &CollabObject,
+    _id: u64,
+    chunk: PayloadChunk,
+  ) -> Result<(), Error> {
+    self.sent.lock().unwrap().push(Sent::Chunk(chunk));
+    Ok(())
+  }
+
+  async fn send_init_sync(
+    &self,
+    _object: &CollabObject,
+    _id: u64,
+    _init_update: Vec<u8>,
+  ) -> Result<(), Error> {
+    Ok(())
+  }
+
+  fn subscribe_remote_updates(&self, _object: &CollabObject) -> Option<RemoteUpdateReceiver> {
+    None
+  }
+}
+
+fn text_update(len: usize) -> Vec<u8> {
+  let doc = Doc::new();
+  let text = doc.get_or_insert_text("text");
+  let mut txn = doc.transact_mut();
+  text.push(&mut txn, &"a".repeat(len));
+  txn.encode_update_v1()
+}
+
+/// Push the update like a local transaction would, once the plugin finished its first sync.
+async fn push_local_update(
+  storage: RecordingStorage,
+  update_size: UpdateSizePolicy,
+  update: Vec<u8>,
+) -> Vec<Sent> {
+  let storage = Arc::new(storage);
+  let object = CollabObject::new(
+    1,
+    "doc_1".to_string(),
+    CollabType::Document,
+    "w1".to_string(),
+    "1".to_string(),
+  );
+  let plugin = SupabaseDBPlugin::new(
+    1,
+    object,
+    Weak::new(),
+    1,
+    storage.clone(),
+    Weak::new(),
+    update_size,
+    default_spawner(),
+  );
+  let collab = Collab::new(1, "doc_1", "1", vec![], false);
+  plugin.did_init(&collab, "doc_1");
+  tokio::time::sleep(Duration::from_millis(100)).await;
+
+  let plugin = tokio::task::spawn_blocking(move || {
+    plugin.receive_local_update(&CollabOrigin::Empty, "doc_1", &update);
+    plugin
+  })
+  .await
+  .unwrap();
+  // The sink sends the pending messages every second.
+  tokio::time::sleep(Duration::from_millis(1500)).await;
+  drop(plugin);
+  storage.sent()
+}
+
+#[test]
+fn payload_chunk_test() {
+  let payload = (0..10u8).collect::<Vec<_>>();
+  let chunks = PayloadChunk::split(&payload, 4, false);
+  assert_eq!(chunks.len(), 3);
+  assert_eq!(chunks[2].data, vec![8, 9]);
+  assert!(chunks.iter().all(|chunk| chunk.total == 3));
+
+  let mut shuffled = chunks.clone();
+  shuffled.reverse();
+  assert_eq!(PayloadChunk::reassemble(shuffled).unwrap(), payload);
+  let missing = chunks[..2].to_vec();
+  assert!(matches!(
+    PayloadChunk::reassemble(missing),
+    Err(SyncError::IncompletePayload {
+      received: 2,
+      total: 3
+    })
+  ));
+}
+
+#[test]
+fn update_size_policy_test() {
+  let policy = UpdateSizePolicy::new(4);
+  assert!(!policy.is_oversized(4));
+  assert!(policy.is_oversized(5));
+}
+
+fn chunking_storage() -> RecordingStorage {
+  RecordingStorage {
+    supports_chunks: true,
+    ..Default::default()
+  }
+}
+
+#[tokio::test]
+async fn split_oversized_local_update_test() {
+  let update = text_update(100);
+  let sent = push_local_update(
+    chunking_storage(),
+    UpdateSizePolicy::new(32),
+    update.clone(),
+  )
+  .await;
+  let chunks = sent
+    .into_iter()
+    .map(|sent| match sent {
+      Sent::Chunk(chunk) => chunk,
+      Sent::Update(_) => panic!("the update should be split"),
+    })
+    .collect::<Vec<_>>();
+  assert!(chunks.len() > 1);
+  assert!(chunks
+    .iter()
+    .all(|chunk| chunk.data.len() <= 32 && !chunk.is_init));
+  assert_eq!(PayloadChunk::reassemble(chunks).unwrap(), update);
+
+  // The updates under the max size are sent as they are.
+  let update = text_update(4);
+  let sent = push_local_update(
+    chunking_storage(),
+    UpdateSizePolicy::default(),
+    update.clone(),
+  )
+  .await;
+  assert_eq!(sent, vec![Sent::Update(update)]);
+}
+
+#[tokio::test]
+async fn oversized_update_without_chunk_support_test() {
+  // The committed update is never dropped, it's sent as a whole if the remote can't
+  // reassemble the chunks.
+  let update = text_update(100);
+  let sent = push_local_update(
+    RecordingStorage::default(),
+    UpdateSizePolicy::new(32),
+    update.clone(),
+  )
+  .await;
+  assert_eq!(sent, vec![Sent::Update(update)]);
+}
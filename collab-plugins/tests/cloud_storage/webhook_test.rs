@@ -0,0 +1,214 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use collab::core::clock::system_clock;
+use collab::core::clock::ManualClock;
+use collab::core::collab_webhook::{WebhookEvent, WebhookPayload, WEBHOOK_SCHEMA_VERSION};
+use collab_plugins::cloud_storage::{
+  default_spawner, EventEmitter, QueueOverflow, QueuePolicy, RetryPolicy, WebhookSink,
+};
+use serde_json::json;
+use tokio::sync::{broadcast, mpsc, Semaphore};
+
+#[derive(Debug, Clone)]
+struct TestEvent(&'static str);
+
+impl WebhookEvent for TestEvent {
+  fn event_type(&self) -> String {
+    "test.changed".to_string()
+  }
+
+  fn data(&self) -> serde_json::Value {
+    json!({ "name": self.0 })
+  }
+}
+
+/// Fails the first `failures` attempts of each payload.
+struct TestSink {
+  failures: u32,
+  attempts: Arc<AtomicU32>,
+  sent: Arc<Mutex<Vec<u64>>>,
+  delivered_tx: mpsc::UnboundedSender<WebhookPayload>,
+}
+
+#[async_trait]
+impl WebhookSink for TestSink {
+  async fn send(&self, payload: &WebhookPayload) -> Result<(), anyhow::Error> {
+    self.sent.lock().unwrap().push(payload.sequence);
+    if self.attempts.fetch_add(1, Ordering::SeqCst) < self.failures {
+      return Err(anyhow::anyhow!("unavailable"));
+    }
+    self.attempts.store(0, Ordering::SeqCst);
+    let _ = self.delivered_tx.send(payload.clone());
+    Ok(())
+  }
+}
+
+fn test_sink(
+  failures: u32,
+) -> (
+  TestSink,
+  Arc<Mutex<Vec<u64>>>,
+  mpsc::UnboundedReceiver<WebhookPayload>,
+) {
+  let (delivered_tx, delivered_rx) = mpsc::unbounded_channel();
+  let sent = Arc::new(Mutex::new(vec![]));
+  let sink = TestSink {
+    failures,
+    attempts: Arc::new(AtomicU32::new(0)),
+    sent: sent.clone(),
+    delivered_tx,
+  };
+  (sink, sent, delivered_rx)
+}
+
+/// Delivers a payload for each permit added to the gate.
+struct GatedSink {
+  gate: Arc<Semaphore>,
+  delivered_tx: mpsc::UnboundedSender<WebhookPayload>,
+}
+
+#[async_trait]
+impl WebhookSink for GatedSink {
+  async fn send(&self, payload: &WebhookPayload) -> Result<(), anyhow::Error> {
+    self.gate.acquire().await?.forget();
+    let _ = self.delivered_tx.send(payload.clone());
+    Ok(())
+  }
+}
+
+fn fast_retry(max_retries: u32) -> RetryPolicy {
+  RetryPolicy::new(
+    max_retries,
+    Duration::from_millis(1),
+    Duration::from_millis(4),
+  )
+}
+
+#[test]
+fn retry_delay_backoff_test() {
+  let policy = RetryPolicy::new(5, Duration::from_millis(100), Duration::from_millis(500));
+  assert_eq!(policy.delay(0), Duration::from_millis(100));
+  assert_eq!(policy.delay(1), Duration::from_millis(200));
+  assert_eq!(policy.delay(2), Duration::from_millis(400));
+  assert_eq!(policy.delay(3), Duration::from_millis(500));
+  assert_eq!(policy.delay(40), Duration::from_millis(500));
+}
+
+#[tokio::test]
+async fn emit_payload_test() {
+  let (sink, _, mut delivered_rx) = test_sink(0);
+  let clock = Arc::new(ManualClock::new(1000));
+  let emitter = EventEmitter::new_with_clock(sink, fast_retry(0), default_spawner(), clock.clone());
+
+  let payload = emitter.emit("d1", &TestEvent("a"));
+  assert_eq!(payload.schema_version, WEBHOOK_SCHEMA_VERSION);
+  assert_eq!(payload.sequence, 0);
+  assert_eq!(payload.event_type, "test.changed");
+  assert_eq!(payload.object_id, "d1");
+  assert_eq!(payload.timestamp, 1000);
+  assert_eq!(payload.data, json!({ "name": "a" }));
+  assert_eq!(delivered_rx.recv().await.unwrap(), payload);
+
+  let json = serde_json::to_value(&payload).unwrap();
+  assert_eq!(json["schema_version"], json!(WEBHOOK_SCHEMA_VERSION));
+  assert_eq!(json["event_type"], json!("test.changed"));
+}
+
+#[tokio::test]
+async fn retry_failed_delivery_in_order_test() {
+  let (sink, sent, mut delivered_rx) = test_sink(2);
+  let emitter = EventEmitter::new(sink, fast_retry(3), default_spawner());
+  emitter.emit("d1", &TestEvent("a"));
+  emitter.emit("d1", &TestEvent("b"));
+
+  let first = delivered_rx.recv().await.unwrap();
+  let second = delivered_rx.recv().await.unwrap();
+  assert_eq!(first.data, json!({ "name": "a" }));
+  assert_eq!(second.data, json!({ "name": "b" }));
+  assert_eq!(*sent.lock().unwrap(), vec![0, 0, 0, 1, 1, 1]);
+}
+
+#[tokio::test]
+async fn drop_payload_after_max_retries_test() {
+  let (sink, sent, mut delivered_rx) = test_sink(3);
+  let emitter = EventEmitter::new(sink, fast_retry(1), default_spawner());
+  emitter.emit("d1", &TestEvent("a"));
+  emitter.emit("d1", &TestEvent("b"));
+
+  // The first payload is dropped after 2 attempts, the second one succeeds on its second attempt
+  // because the sink keeps counting the failures.
+  let delivered = delivered_rx.recv().await.unwrap();
+  assert_eq!(delivered.sequence, 1);
+  assert_eq!(*sent.lock().unwrap(), vec![0, 0, 1, 1]);
+}
+
+#[tokio::test]
+async fn forward_stream_test() {
+  let (sink, _, mut delivered_rx) = test_sink(0);
+  let emitter = EventEmitter::new(sink, fast_retry(0), default_spawner());
+  let events = tokio_stream::iter(vec![TestEvent("a"), TestEvent("b")]);
+  emitter.forward("d1".to_string(), events);
+
+  assert_eq!(delivered_rx.recv().await.unwrap().sequence, 0);
+  assert_eq!(
+    delivered_rx.recv().await.unwrap().data,
+    json!({ "name": "b" })
+  );
+}
+
+#[tokio::test]
+async fn queue_overflow_test() {
+  for (overflow, expected) in [
+    (QueueOverflow::DropOldest, [2, 3]),
+    (QueueOverflow::DropNewest, [0, 1]),
+  ] {
+    let gate = Arc::new(Semaphore::new(0));
+    let (delivered_tx, mut delivered_rx) = mpsc::unbounded_channel();
+    let sink = GatedSink {
+      gate: gate.clone(),
+      delivered_tx,
+    };
+    let emitter = EventEmitter::new_with_queue_policy(
+      sink,
+      fast_retry(0),
+      QueuePolicy::new(2, overflow),
+      default_spawner(),
+      system_clock(),
+    );
+    for name in ["a", "b", "c", "d"] {
+      emitter.emit("d1", &TestEvent(name));
+    }
+    assert_eq!(emitter.dropped_count(), 2);
+
+    gate.add_permits(2);
+    assert_eq!(delivered_rx.recv().await.unwrap().sequence, expected[0]);
+    assert_eq!(delivered_rx.recv().await.unwrap().sequence, expected[1]);
+  }
+}
+
+#[tokio::test]
+async fn forward_lagged_broadcast_test() {
+  let (sink, _, mut delivered_rx) = test_sink(0);
+  let emitter = EventEmitter::new(sink, fast_retry(0), default_spawner());
+  let (event_tx, event_rx) = broadcast::channel(2);
+  for name in ["a", "b", "c"] {
+    event_tx.send(TestEvent(name)).unwrap();
+  }
+  emitter.forward_broadcast("d1".to_string(), event_rx);
+  drop(event_tx);
+
+  let lagged = delivered_rx.recv().await.unwrap();
+  assert_eq!(lagged.event_type, "webhook.lagged");
+  assert_eq!(lagged.data, json!({ "missed": 1 }));
+  assert_eq!(
+    delivered_rx.recv().await.unwrap().data,
+    json!({ "name": "b" })
+  );
+  assert_eq!(
+    delivered_rx.recv().await.unwrap().data,
+    json!({ "name": "c" })
+  );
+}
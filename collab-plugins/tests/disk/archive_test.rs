@@ -0,0 +1,89 @@
+use crate::disk::util::rocks_db;
+use collab_plugins::local_storage::kv::doc::CollabKVAction;
+use collab_plugins::local_storage::kv::snapshot::SnapshotAction;
+use collab_plugins::local_storage::kv::{KVTransactionDB, PersistenceError};
+use collab_plugins::CollabKVDB;
+use tempfile::TempDir;
+use uuid::Uuid;
+use yrs::{Doc, GetString, Text, Transact};
+
+fn write_doc(db: &CollabKVDB, uid: i64, workspace_id: &str, oid: &str, content: &str) {
+  let doc = Doc::new();
+  {
+    let txn = doc.transact();
+    db.with_write_txn(|w| w.create_new_doc(uid, workspace_id, oid, &txn))
+      .unwrap();
+  }
+  let text = doc.get_or_insert_text("text");
+  let mut txn = doc.transact_mut();
+  text.push(&mut txn, content);
+  let update = txn.encode_update_v1();
+  db.with_write_txn(|w| w.push_update(uid, workspace_id, oid, &update))
+    .unwrap();
+}
+
+fn read_doc(db: &CollabKVDB, uid: i64, workspace_id: &str, oid: &str) -> String {
+  let doc = Doc::new();
+  {
+    let mut txn = doc.transact_mut();
+    db.read_txn()
+      .load_doc_with_txn(uid, workspace_id, oid, &mut txn)
+      .unwrap();
+  }
+  let text = doc.get_or_insert_text("text");
+  let txn = doc.transact();
+  text.get_string(&txn)
+}
+
+#[tokio::test]
+async fn export_and_import_archive_test() {
+  let workspace_id = Uuid::new_v4().to_string();
+  let workspace_id = workspace_id.as_str();
+  let (_, source) = rocks_db();
+  write_doc(&source, 1, workspace_id, "doc_1", "hello");
+  write_doc(&source, 1, workspace_id, "doc_2", "world");
+  write_doc(&source, 2, workspace_id, "doc_3", "other user");
+  source
+    .with_write_txn(|w| w.create_snapshot_with_data(1, "doc_1", vec![1, 2, 3]))
+    .unwrap();
+
+  let archive_path = TempDir::new().unwrap().into_path().join("backup.collab");
+  assert_eq!(source.export_archive(1, &archive_path).unwrap(), 2);
+
+  let (_, target) = rocks_db();
+  // The existing document is replaced by the archived version
+  write_doc(&target, 1, workspace_id, "doc_2", "local");
+  // Safety: the archive isn't written while it's imported.
+  assert_eq!(unsafe { target.import_archive(&archive_path) }.unwrap(), 2);
+
+  let read = target.read_txn();
+  assert_eq!(read_doc(&target, 1, workspace_id, "doc_1"), "hello");
+  assert_eq!(read_doc(&target, 1, workspace_id, "doc_2"), "world");
+  assert_eq!(read.number_of_updates(1, workspace_id, "doc_1"), 1);
+  assert!(!read.is_exist(2, workspace_id, "doc_3"));
+  let snapshots = read.get_snapshots(1, "doc_1");
+  assert_eq!(snapshots.len(), 1);
+  assert_eq!(snapshots[0].data, vec![1, 2, 3]);
+}
+
+#[tokio::test]
+async fn import_corrupted_archive_test() {
+  let workspace_id = Uuid::new_v4().to_string();
+  let (_, source) = rocks_db();
+  write_doc(&source, 1, &workspace_id, "doc_1", "hello");
+  let archive_path = TempDir::new().unwrap().into_path().join("backup.collab");
+  source.export_archive(1, &archive_path).unwrap();
+
+  let mut data = std::fs::read(&archive_path).unwrap();
+  let last = data.len() - 1;
+  data[last] ^= 0xFF;
+  std::fs::write(&archive_path, data).unwrap();
+
+  let (_, target) = rocks_db();
+  // Safety: the archive isn't written while it's imported.
+  let err = unsafe { target.import_archive(&archive_path) }.unwrap_err();
+  assert!(matches!(err, PersistenceError::InvalidData(_)));
+  assert!(!target
+    .read_txn()
+    .is_exist(1, workspace_id.as_str(), "doc_1"));
+}
@@ -0,0 +1,78 @@
+use crate::disk::util::rocks_db;
+use collab::entity::EncodedCollab;
+use collab_plugins::local_storage::kv::batch::CollabWriteBatch;
+use collab_plugins::local_storage::kv::doc::CollabKVAction;
+use collab_plugins::local_storage::kv::{KVTransactionDB, PersistenceError};
+use uuid::Uuid;
+use yrs::updates::encoder::Encode;
+use yrs::{Doc, ReadTxn, StateVector, Text, Transact};
+
+fn encoded_doc(content: &str) -> EncodedCollab {
+  let doc = Doc::new();
+  let text = doc.get_or_insert_text("text");
+  let mut txn = doc.transact_mut();
+  text.push(&mut txn, content);
+  EncodedCollab::new_v1(
+    txn.state_vector().encode_v1(),
+    txn.encode_state_as_update_v1(&StateVector::default()),
+  )
+}
+
+#[tokio::test]
+async fn write_batch_commit_all_docs_test() {
+  let workspace_id = Uuid::new_v4().to_string();
+  let (_, db) = rocks_db();
+  let mut batch = CollabWriteBatch::new(1, &workspace_id);
+  batch
+    .create_doc("database", encoded_doc("database"))
+    .flush_doc("row_1", encoded_doc("row 1"))
+    .flush_doc("row_2", encoded_doc("row 2"));
+  db.write_batch(&batch).unwrap();
+
+  let read = db.read_txn();
+  for oid in ["database", "row_1", "row_2"] {
+    assert!(read.is_exist(1, workspace_id.as_str(), oid));
+  }
+}
+
+#[tokio::test]
+async fn write_batch_rollback_on_error_test() {
+  let workspace_id = Uuid::new_v4().to_string();
+  let (_, db) = rocks_db();
+  let mut batch = CollabWriteBatch::new(1, &workspace_id);
+  batch.create_doc("database", encoded_doc("database"));
+  db.write_batch(&batch).unwrap();
+
+  // The second batch fails when creating the existing database, so the rows must not be written.
+  let mut batch = CollabWriteBatch::new(1, &workspace_id);
+  batch
+    .flush_doc("row_1", encoded_doc("row 1"))
+    .flush_doc("row_2", encoded_doc("row 2"))
+    .create_doc("database", encoded_doc("database"));
+  let err = db.write_batch(&batch).unwrap_err();
+  assert!(matches!(err, PersistenceError::DocumentAlreadyExist));
+
+  let read = db.read_txn();
+  assert!(read.is_exist(1, workspace_id.as_str(), "database"));
+  assert!(!read.is_exist(1, workspace_id.as_str(), "row_1"));
+  assert!(!read.is_exist(1, workspace_id.as_str(), "row_2"));
+}
+
+#[tokio::test]
+async fn write_batch_delete_docs_test() {
+  let workspace_id = Uuid::new_v4().to_string();
+  let (_, db) = rocks_db();
+  let mut batch = CollabWriteBatch::new(1, &workspace_id);
+  batch
+    .flush_doc("row_1", encoded_doc("row 1"))
+    .flush_doc("row_2", encoded_doc("row 2"));
+  db.write_batch(&batch).unwrap();
+
+  let mut batch = CollabWriteBatch::new(1, &workspace_id);
+  batch.delete_doc("row_1").delete_doc("row_2");
+  db.write_batch(&batch).unwrap();
+
+  let read = db.read_txn();
+  assert!(!read.is_exist(1, workspace_id.as_str(), "row_1"));
+  assert!(!read.is_exist(1, workspace_id.as_str(), "row_2"));
+}
@@ -0,0 +1,116 @@
+use std::sync::Mutex;
+
+use crate::disk::util::rocks_db;
+use collab::preclude::Collab;
+use collab_plugins::local_storage::kv::coordinator::CollabOperationGroup;
+use collab_plugins::local_storage::kv::doc::CollabKVAction;
+use collab_plugins::local_storage::kv::{KVTransactionDB, PersistenceError};
+use collab_plugins::CollabKVDB;
+use uuid::Uuid;
+
+fn new_collab(object_id: &str, key: &str, value: &str) -> Collab {
+  let mut collab = Collab::new(1, object_id, "1", vec![], false);
+  collab.insert(key, value);
+  collab
+}
+
+fn read_collab(db: &CollabKVDB, workspace_id: &str, object_id: &str) -> Collab {
+  let mut collab = Collab::new(1, object_id, "1", vec![], false);
+  db.read_txn()
+    .load_doc_with_txn(1, workspace_id, object_id, &mut collab.transact_mut())
+    .unwrap();
+  collab
+}
+
+fn save_collab(db: &CollabKVDB, workspace_id: &str, object_id: &str, collab: &Collab) {
+  let mut group = CollabOperationGroup::new(1, workspace_id);
+  group.create_collab(object_id, collab);
+  group.commit(db, &|_: &str, _: &[u8]| {}).unwrap();
+}
+
+#[tokio::test]
+async fn commit_operation_group_test() {
+  let workspace_id = Uuid::new_v4().to_string();
+  let workspace_id = workspace_id.as_str();
+  let (_, db) = rocks_db();
+  let mut folder = new_collab("folder", "name", "workspace");
+  save_collab(&db, workspace_id, "folder", &folder);
+
+  let mut group = CollabOperationGroup::new(1, workspace_id);
+  group
+    .create_collab("database", &new_collab("database", "name", "grid"))
+    .create_collab("row_document", &new_collab("row_document", "text", "row"));
+  group.update_collab("folder", &mut folder, |collab| {
+    collab.insert("view", "database");
+  });
+  let sent = Mutex::new(vec![]);
+  group
+    .commit(&db, &|object_id: &str, _: &[u8]| {
+      sent.lock().unwrap().push(object_id.to_string())
+    })
+    .unwrap();
+
+  assert_eq!(
+    sent.into_inner().unwrap(),
+    vec!["database", "row_document", "folder"]
+  );
+  let folder = read_collab(&db, workspace_id, "folder");
+  assert_eq!(folder.get::<String>("name").unwrap(), "workspace");
+  assert_eq!(folder.get::<String>("view").unwrap(), "database");
+  let row_document = read_collab(&db, workspace_id, "row_document");
+  assert_eq!(row_document.get::<String>("text").unwrap(), "row");
+}
+
+#[tokio::test]
+async fn operation_group_rollback_on_error_test() {
+  let workspace_id = Uuid::new_v4().to_string();
+  let workspace_id = workspace_id.as_str();
+  let (_, db) = rocks_db();
+  let mut folder = new_collab("folder", "name", "workspace");
+  save_collab(&db, workspace_id, "folder", &folder);
+  save_collab(
+    &db,
+    workspace_id,
+    "database",
+    &new_collab("database", "name", "grid"),
+  );
+
+  // Creating the existing database fails, so the folder and the row document must not be changed.
+  let mut group = CollabOperationGroup::new(1, workspace_id);
+  group.create_collab("row_document", &new_collab("row_document", "text", "row"));
+  group.update_collab("folder", &mut folder, |collab| {
+    collab.insert("view", "database");
+  });
+  group.create_collab("database", &new_collab("database", "name", "board"));
+  let sent = Mutex::new(vec![]);
+  let err = group
+    .commit(&db, &|object_id: &str, _: &[u8]| {
+      sent.lock().unwrap().push(object_id.to_string())
+    })
+    .unwrap_err();
+
+  assert!(matches!(err, PersistenceError::DocumentAlreadyExist));
+  assert!(sent.into_inner().unwrap().is_empty());
+  let read = db.read_txn();
+  assert!(!read.is_exist(1, workspace_id, "row_document"));
+  let folder = read_collab(&db, workspace_id, "folder");
+  assert_eq!(folder.get::<String>("view"), None);
+}
+
+#[tokio::test]
+async fn operation_group_update_missing_collab_test() {
+  let workspace_id = Uuid::new_v4().to_string();
+  let workspace_id = workspace_id.as_str();
+  let (_, db) = rocks_db();
+  let mut folder = new_collab("folder", "name", "workspace");
+
+  let mut group = CollabOperationGroup::new(1, workspace_id);
+  group.create_collab("database", &new_collab("database", "name", "grid"));
+  group.update_collab("folder", &mut folder, |collab| {
+    collab.insert("view", "database");
+  });
+  let err = group.commit(&db, &|_: &str, _: &[u8]| {}).unwrap_err();
+
+  assert!(matches!(err, PersistenceError::RecordNotFound(_)));
+  assert!(!db.read_txn().is_exist(1, workspace_id, "database"));
+}
@@ -0,0 +1,148 @@
+use std::sync::Arc;
+
+use collab::core::origin::CollabOrigin;
+use collab::preclude::{Collab, ReadTxn, StateVector};
+use collab_entity::CollabType;
+use collab_plugins::local_storage::kv::doc::CollabKVAction;
+use collab_plugins::local_storage::kv::snapshot::SnapshotAction;
+use collab_plugins::local_storage::kv::KVTransactionDB;
+use collab_plugins::local_storage::rocksdb::fast_open::{open_collab_from_snapshot, OpenState};
+use serde_json::json;
+
+use crate::disk::script::disk_plugin_with_db;
+use crate::disk::util::rocks_db;
+
+fn insert_with_update(collab: &mut Collab, key: &str, value: &str) -> Vec<u8> {
+  let sv = collab.transact().state_vector();
+  collab.insert(key, value.to_string());
+  collab.transact().encode_state_as_update_v1(&sv)
+}
+
+/// Persist a document whose doc state and snapshot contain the key "1", with the updates
+/// inserting the keys "2" to "4" stored after them.
+fn persist_edited_doc(db: &Arc<collab_plugins::CollabKVDB>) {
+  let mut collab = Collab::new(1, "doc_1", "1", vec![], false);
+  collab.insert("1", "a".to_string());
+  db.with_write_txn(|w| w.create_new_doc(1, "w1", "doc_1", &collab.transact()))
+    .unwrap();
+  let snapshot = collab
+    .transact()
+    .encode_state_as_update_v1(&StateVector::default());
+  db.with_write_txn(|w| w.create_snapshot_with_data(1, "doc_1", snapshot))
+    .unwrap();
+  for (key, value) in [("2", "b"), ("3", "c"), ("4", "d")] {
+    let update = insert_with_update(&mut collab, key, value);
+    db.with_write_txn(|w| w.push_update(1, "w1", "doc_1", &update))
+      .unwrap();
+  }
+}
+
+#[tokio::test]
+async fn open_from_snapshot_then_replay_updates_test() {
+  let (_, db) = rocks_db();
+  let db = Arc::new(db);
+  persist_edited_doc(&db);
+
+  // The test runtime is single threaded, so the replay task doesn't run before the test awaits.
+  let fast_open = open_collab_from_snapshot(
+    db.clone(),
+    1,
+    "w1",
+    CollabOrigin::Empty,
+    "doc_1",
+    vec![],
+    false,
+  )
+  .unwrap();
+  assert_eq!(fast_open.state(), OpenState::ReadOnly);
+  assert_eq!(fast_open.read().await.to_json_value(), json!({"1": "a"}));
+
+  fast_open.wait_until_writable().await.unwrap();
+  assert!(fast_open.is_writable());
+  let (collab, num_of_updates) = fast_open.into_collab().await.unwrap();
+  assert_eq!(num_of_updates, 3);
+  assert_eq!(
+    collab.read().await.to_json_value(),
+    json!({"1": "a", "2": "b", "3": "c", "4": "d"})
+  );
+}
+
+#[tokio::test]
+async fn write_after_replay_is_persisted_test() {
+  let (_, db) = rocks_db();
+  let db = Arc::new(db);
+  persist_edited_doc(&db);
+
+  let disk_plugin = disk_plugin_with_db(
+    1,
+    "w1".to_string(),
+    db.clone(),
+    "doc_1",
+    CollabType::Unknown,
+  );
+  let fast_open = open_collab_from_snapshot(
+    db.clone(),
+    1,
+    "w1",
+    CollabOrigin::Empty,
+    "doc_1",
+    vec![disk_plugin],
+    false,
+  )
+  .unwrap();
+  fast_open
+    .write()
+    .await
+    .unwrap()
+    .insert("5", "e".to_string());
+  assert!(fast_open.is_writable());
+  drop(fast_open);
+
+  let mut collab = Collab::new(1, "doc_1", "1", vec![], false);
+  db.read_txn()
+    .load_doc_with_txn(1, "w1", "doc_1", &mut collab.transact_mut())
+    .unwrap();
+  assert_eq!(
+    collab.to_json_value(),
+    json!({"1": "a", "2": "b", "3": "c", "4": "d", "5": "e"})
+  );
+}
+
+#[tokio::test]
+async fn failed_replay_stays_read_only_test() {
+  let (_, db) = rocks_db();
+  let db = Arc::new(db);
+  persist_edited_doc(&db);
+  db.with_write_txn(|w| w.push_update(1, "w1", "doc_1", &[1, 2, 3]))
+    .unwrap();
+
+  let fast_open = open_collab_from_snapshot(
+    db.clone(),
+    1,
+    "w1",
+    CollabOrigin::Empty,
+    "doc_1",
+    vec![],
+    false,
+  )
+  .unwrap();
+  assert!(fast_open.write().await.is_err());
+  assert!(fast_open.wait_until_writable().await.is_err());
+  assert_eq!(fast_open.state(), OpenState::ReadOnly);
+  assert!(fast_open.into_collab().await.is_err());
+}
+
+#[tokio::test]
+async fn open_missing_doc_from_snapshot_test() {
+  let (_, db) = rocks_db();
+  let result = open_collab_from_snapshot(
+    Arc::new(db),
+    1,
+    "w1",
+    CollabOrigin::Empty,
+    "doc_1",
+    vec![],
+    false,
+  );
+  assert!(result.is_err());
+}
@@ -0,0 +1,134 @@
+use std::sync::Arc;
+
+use crate::disk::util::rocks_db;
+use collab::core::origin::{CollabClient, CollabOrigin};
+use collab::preclude::Collab;
+use collab_plugins::local_storage::kv::doc::CollabKVAction;
+use collab_plugins::local_storage::kv::history::HistoryAction;
+use collab_plugins::local_storage::kv::KVTransactionDB;
+use collab_plugins::local_storage::rocksdb::history_plugin::RocksdbHistoryPlugin;
+use uuid::Uuid;
+use yrs::{Doc, Text, Transact};
+
+fn text_updates(num_of_updates: usize) -> Vec<Vec<u8>> {
+  let doc = Doc::new();
+  let text = doc.get_or_insert_text("text");
+  (0..num_of_updates)
+    .map(|_| {
+      let mut txn = doc.transact_mut();
+      text.push(&mut txn, "abc");
+      txn.encode_update_v1()
+    })
+    .collect()
+}
+
+#[tokio::test]
+async fn query_history_by_time_range_test() {
+  let (_, db) = rocks_db();
+  let origin = CollabOrigin::Client(CollabClient::new(1, "1"));
+  let updates = text_updates(4);
+  db.with_write_txn(|w| {
+    for (i, update) in updates.iter().enumerate() {
+      w.push_history_at(1, "doc_1", 1000 * (i as i64 + 1), &origin, update)?;
+    }
+    Ok(())
+  })
+  .unwrap();
+
+  let read_txn = db.read_txn();
+  assert_eq!(read_txn.number_of_history_updates(1, "doc_1"), 4);
+  let history = read_txn.query_history(1, "doc_1", 0..i64::MAX).unwrap();
+  assert_eq!(
+    history.iter().map(|s| s.timestamp).collect::<Vec<_>>(),
+    vec![1000, 2000, 3000, 4000]
+  );
+  for summary in &history {
+    assert_eq!(summary.origin, origin);
+    assert_eq!(summary.inserted, 3);
+    assert_eq!(summary.deleted, 0);
+    assert_eq!(summary.clients.len(), 1);
+  }
+
+  // The start of the range is inclusive and the end is exclusive
+  let history = read_txn.query_history(1, "doc_1", 2000..4000).unwrap();
+  assert_eq!(
+    history.iter().map(|s| s.timestamp).collect::<Vec<_>>(),
+    vec![2000, 3000]
+  );
+
+  // The history is scoped by user and object
+  assert!(read_txn
+    .query_history(2, "doc_1", 0..i64::MAX)
+    .unwrap()
+    .is_empty());
+  assert!(read_txn
+    .query_history(1, "doc_2", 0..i64::MAX)
+    .unwrap()
+    .is_empty());
+}
+
+#[tokio::test]
+async fn history_keeps_updates_of_same_timestamp_test() {
+  let (_, db) = rocks_db();
+  let updates = text_updates(3);
+  db.with_write_txn(|w| {
+    for update in &updates {
+      w.push_history_at(1, "doc_1", 1000, &CollabOrigin::Server, update)?;
+    }
+    Ok(())
+  })
+  .unwrap();
+
+  let history = db.read_txn().query_history(1, "doc_1", 1000..1001).unwrap();
+  assert_eq!(history.len(), 3);
+  let sizes = updates
+    .iter()
+    .map(|update| update.len())
+    .collect::<Vec<_>>();
+  assert_eq!(history.iter().map(|s| s.size).collect::<Vec<_>>(), sizes);
+}
+
+#[tokio::test]
+async fn delete_doc_deletes_history_test() {
+  let workspace_id = Uuid::new_v4().to_string();
+  let (_, db) = rocks_db();
+  let doc = Doc::new();
+  db.with_write_txn(|w| w.create_new_doc(1, workspace_id.as_str(), "doc_1", &doc.transact()))
+    .unwrap();
+  let updates = text_updates(2);
+  db.with_write_txn(|w| {
+    for update in &updates {
+      w.push_history(1, "doc_1", &CollabOrigin::Empty, update)?;
+    }
+    Ok(())
+  })
+  .unwrap();
+  assert_eq!(db.read_txn().number_of_history_updates(1, "doc_1"), 2);
+
+  db.with_write_txn(|w| w.delete_doc(1, workspace_id.as_str(), "doc_1"))
+    .unwrap();
+  assert_eq!(db.read_txn().number_of_history_updates(1, "doc_1"), 0);
+}
+
+#[tokio::test]
+async fn history_plugin_test() {
+  let (_, db) = rocks_db();
+  let db = Arc::new(db);
+  let plugin = RocksdbHistoryPlugin::new(1, "doc_1".to_string(), Arc::downgrade(&db));
+  let mut collab = Collab::new(1, "doc_1", "1", vec![Box::new(plugin.clone())], false);
+  collab.initialize();
+
+  let start = chrono::Utc::now().timestamp_millis();
+  collab.insert("1", "a");
+  collab.insert("2", "b");
+  collab.remove("1");
+  let end = chrono::Utc::now().timestamp_millis() + 1;
+
+  let history = plugin.query_history(start..end).unwrap();
+  assert_eq!(history.len(), 3);
+  let origin = CollabOrigin::Client(CollabClient::new(1, "1"));
+  assert!(history.iter().all(|summary| summary.origin == origin));
+  assert_eq!(history[0].inserted, 1);
+  assert_eq!(history[2].deleted, 1);
+  assert!(plugin.query_history(0..start).unwrap().is_empty());
+}
@@ -0,0 +1,73 @@
+use crate::disk::util::rocks_db;
+use collab_plugins::local_storage::kv::doc::CollabKVAction;
+use collab_plugins::local_storage::kv::keys::make_doc_id_key_v0;
+use collab_plugins::local_storage::kv::snapshot::SnapshotAction;
+use collab_plugins::local_storage::kv::{KVStore, KVTransactionDB};
+use uuid::Uuid;
+use yrs::{Doc, Text, Transact};
+
+#[tokio::test]
+async fn list_objects_with_metadata_test() {
+  let workspace_id = Uuid::new_v4().to_string();
+  let workspace_id = workspace_id.as_str();
+  let (_, db) = rocks_db();
+  let now = chrono::Utc::now().timestamp();
+  for (oid, num_of_updates) in [("doc_1", 0), ("doc_2", 3)] {
+    let doc = Doc::new();
+    {
+      let txn = doc.transact();
+      db.with_write_txn(|w| w.create_new_doc(1, workspace_id, oid, &txn))
+        .unwrap();
+    }
+    let text = doc.get_or_insert_text("text");
+    for i in 0..num_of_updates {
+      let mut txn = doc.transact_mut();
+      text.push(&mut txn, &i.to_string());
+      let update = txn.encode_update_v1();
+      db.with_write_txn(|w| w.push_update(1, workspace_id, oid, &update))
+        .unwrap();
+    }
+  }
+  // Objects of other users are not listed
+  db.with_write_txn(|w| w.create_new_doc(2, workspace_id, "doc_3", &Doc::new().transact()))
+    .unwrap();
+
+  let mut objects = db.read_txn().list_objects(1).unwrap();
+  objects.sort_by(|a, b| a.object_id.cmp(&b.object_id));
+  assert_eq!(objects.len(), 2);
+  assert_eq!(objects[0].object_id, "doc_1");
+  assert_eq!(objects[0].workspace_id.as_deref(), Some(workspace_id));
+  assert_eq!(objects[0].update_count, 0);
+  assert_eq!(objects[1].object_id, "doc_2");
+  assert_eq!(objects[1].update_count, 3);
+  assert!(objects[1].size > objects[0].size);
+  for object in &objects {
+    assert!(object.last_modified.unwrap() >= now);
+  }
+
+  // Snapshots are counted in the size
+  let size_before = objects[1].size;
+  db.with_write_txn(|w| w.create_snapshot_with_data(1, "doc_2", vec![1, 2, 3]))
+    .unwrap();
+  let objects = db.read_txn().list_objects(1).unwrap();
+  let doc_2 = objects.iter().find(|o| o.object_id == "doc_2").unwrap();
+  assert!(doc_2.size > size_before);
+}
+
+#[tokio::test]
+async fn list_objects_with_legacy_key_test() {
+  let (_, db) = rocks_db();
+  db.with_write_txn(|w| {
+    w.insert(
+      make_doc_id_key_v0(&1_i64.to_be_bytes(), b"legacy_doc"),
+      42_u64.to_be_bytes(),
+    )
+  })
+  .unwrap();
+
+  let objects = db.read_txn().list_objects(1).unwrap();
+  assert_eq!(objects.len(), 1);
+  assert_eq!(objects[0].object_id, "legacy_doc");
+  assert!(objects[0].workspace_id.is_none());
+  assert!(objects[0].last_modified.is_none());
+}
@@ -0,0 +1,158 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use collab::preclude::Collab;
+use collab_plugins::local_storage::kv::doc::CollabKVAction;
+use collab_plugins::local_storage::kv::KVTransactionDB;
+use collab_plugins::local_storage::rocksdb::maintenance::{
+  MaintenancePolicy, MaintenanceReport, MaintenanceService,
+};
+use collab_plugins::CollabKVDB;
+use uuid::Uuid;
+use yrs::{Doc, GetString, Text, Transact};
+
+use crate::disk::util::rocks_db;
+
+fn create_doc_with_updates(db: &CollabKVDB, workspace_id: &str, oid: &str, n: usize) {
+  let doc = Doc::new();
+  {
+    let txn = doc.transact();
+    db.with_write_txn(|w| w.create_new_doc(1, workspace_id, oid, &txn))
+      .unwrap();
+  }
+  let text = doc.get_or_insert_text("text");
+  for i in 0..n {
+    let mut txn = doc.transact_mut();
+    text.push(&mut txn, &i.to_string());
+    let update = txn.encode_update_v1();
+    db.with_write_txn(|w| w.push_update(1, workspace_id, oid, &update))
+      .unwrap();
+  }
+}
+
+fn load_text(db: &CollabKVDB, workspace_id: &str, oid: &str) -> String {
+  let doc = Doc::new();
+  {
+    let mut txn = doc.transact_mut();
+    db.read_txn()
+      .load_doc_with_txn(1, workspace_id, oid, &mut txn)
+      .unwrap();
+  }
+  let text = doc.get_or_insert_text("text");
+  let txn = doc.transact();
+  text.get_string(&txn)
+}
+
+#[tokio::test]
+async fn compact_objects_with_many_updates_test() {
+  let workspace_id = Uuid::new_v4().to_string();
+  let workspace_id = workspace_id.as_str();
+  let (_, db) = rocks_db();
+  let db = Arc::new(db);
+  create_doc_with_updates(&db, workspace_id, "doc_1", 30);
+  create_doc_with_updates(&db, workspace_id, "doc_2", 5);
+  // The objects of the other workspaces are left untouched.
+  let other_workspace_id = Uuid::new_v4().to_string();
+  create_doc_with_updates(&db, &other_workspace_id, "doc_3", 30);
+  let expected = load_text(&db, workspace_id, "doc_1");
+
+  let policy = MaintenancePolicy::new()
+    .min_updates(10)
+    .idle_seconds(0)
+    .safety_margin(5);
+  let service = MaintenanceService::new(1, workspace_id, Arc::downgrade(&db), policy);
+  let report = service.run_once().unwrap();
+  assert_eq!(report.num_of_objects, 2);
+  assert_eq!(report.num_of_compacted, 1);
+  assert_eq!(report.reclaimed.num_of_updates, 25);
+  assert!(report.reclaimed.bytes > 0);
+  assert!(!report.interrupted);
+
+  let read = db.read_txn();
+  assert_eq!(read.number_of_updates(1, workspace_id, "doc_1"), 5);
+  assert_eq!(read.number_of_updates(1, workspace_id, "doc_2"), 5);
+  assert_eq!(read.number_of_updates(1, &other_workspace_id, "doc_3"), 30);
+  assert_eq!(load_text(&db, workspace_id, "doc_1"), expected);
+}
+
+#[tokio::test]
+async fn skip_recently_edited_objects_test() {
+  let workspace_id = Uuid::new_v4().to_string();
+  let workspace_id = workspace_id.as_str();
+  let (_, db) = rocks_db();
+  let db = Arc::new(db);
+  create_doc_with_updates(&db, workspace_id, "doc", 30);
+
+  let policy = MaintenancePolicy::new().min_updates(10).idle_seconds(600);
+  let service = MaintenanceService::new(1, workspace_id, Arc::downgrade(&db), policy);
+  let report = service.run_once().unwrap();
+  assert_eq!(report.num_of_compacted, 0);
+  assert_eq!(db.read_txn().number_of_updates(1, workspace_id, "doc"), 30);
+}
+
+#[tokio::test]
+async fn pause_maintenance_test() {
+  let workspace_id = Uuid::new_v4().to_string();
+  let workspace_id = workspace_id.as_str();
+  let (_, db) = rocks_db();
+  let db = Arc::new(db);
+  create_doc_with_updates(&db, workspace_id, "doc", 30);
+
+  let policy = MaintenancePolicy::new()
+    .min_updates(10)
+    .idle_seconds(0)
+    .interval(Duration::from_millis(50));
+  let service = MaintenanceService::new(1, workspace_id, Arc::downgrade(&db), policy);
+  let pause = service.pause();
+  assert!(service.is_paused());
+  let report = service.run_once().unwrap();
+  assert!(report.interrupted);
+  assert_eq!(report.num_of_compacted, 0);
+
+  let reports: Arc<Mutex<Vec<MaintenanceReport>>> = Default::default();
+  let cloned_reports = reports.clone();
+  let _handle = service.clone().spawn(move |report| {
+    cloned_reports.lock().unwrap().push(report);
+  });
+  tokio::time::sleep(Duration::from_millis(200)).await;
+  assert!(reports.lock().unwrap().is_empty());
+
+  drop(pause);
+  assert!(!service.is_paused());
+  tokio::time::timeout(Duration::from_secs(5), async {
+    while reports.lock().unwrap().is_empty() {
+      tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+  })
+  .await
+  .unwrap();
+  assert_eq!(reports.lock().unwrap()[0].num_of_compacted, 1);
+  assert_eq!(db.read_txn().number_of_updates(1, workspace_id, "doc"), 10);
+}
+
+#[tokio::test]
+async fn pause_maintenance_while_editing_test() {
+  let workspace_id = Uuid::new_v4().to_string();
+  let workspace_id = workspace_id.as_str();
+  let (_, db) = rocks_db();
+  let db = Arc::new(db);
+  create_doc_with_updates(&db, workspace_id, "doc", 30);
+
+  let policy = MaintenancePolicy::new().min_updates(10).idle_seconds(600);
+  let service = MaintenanceService::new(1, workspace_id, Arc::downgrade(&db), policy);
+  let mut collab = Collab::new(
+    1,
+    "doc",
+    "1",
+    vec![Box::new(service.editing_plugin())],
+    false,
+  );
+  collab.initialize();
+  assert!(!service.is_paused());
+
+  collab.insert("1", "a");
+  assert!(service.is_paused());
+  let report = service.run_once().unwrap();
+  assert!(report.interrupted);
+  assert_eq!(db.read_txn().number_of_updates(1, workspace_id, "doc"), 30);
+}
@@ -1,7 +1,17 @@
+mod archive_test;
+mod batch_test;
+mod coordinator_test;
 mod delete_test;
+mod fast_open_test;
+mod history_test;
 mod insert_test;
+mod list_objects_test;
+mod maintenance_test;
+mod prune_test;
 mod range_test;
 mod restore_test;
 mod script;
 mod undo_test;
 mod util;
+mod verify_test;
+mod workspace_test;
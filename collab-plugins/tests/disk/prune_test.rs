@@ -0,0 +1,160 @@
+use crate::disk::util::rocks_db;
+use collab_plugins::local_storage::kv::doc::CollabKVAction;
+use collab_plugins::local_storage::kv::prune::PruneAction;
+use collab_plugins::local_storage::kv::KVTransactionDB;
+use collab_plugins::local_storage::UpdatePrunePolicy;
+use collab_plugins::CollabKVDB;
+use uuid::Uuid;
+use yrs::{Doc, GetString, ReadTxn, StateVector, Text, Transact};
+
+fn create_doc_with_updates(db: &CollabKVDB, workspace_id: &str, oid: &str, n: usize) -> Doc {
+  let doc = Doc::new();
+  {
+    let txn = doc.transact();
+    db.with_write_txn(|w| w.create_new_doc(1, workspace_id, oid, &txn))
+      .unwrap();
+  }
+  let text = doc.get_or_insert_text("text");
+  for i in 0..n {
+    let mut txn = doc.transact_mut();
+    text.push(&mut txn, &i.to_string());
+    let update = txn.encode_update_v1();
+    db.with_write_txn(|w| w.push_update(1, workspace_id, oid, &update))
+      .unwrap();
+  }
+  doc
+}
+
+fn snapshot_data(doc: &Doc) -> Vec<u8> {
+  doc
+    .transact()
+    .encode_state_as_update_v1(&StateVector::default())
+}
+
+fn load_text(db: &CollabKVDB, workspace_id: &str, oid: &str) -> String {
+  let doc = Doc::new();
+  {
+    let mut txn = doc.transact_mut();
+    db.read_txn()
+      .load_doc_with_txn(1, workspace_id, oid, &mut txn)
+      .unwrap();
+  }
+  let text = doc.get_or_insert_text("text");
+  let txn = doc.transact();
+  text.get_string(&txn)
+}
+
+#[tokio::test]
+async fn prune_updates_after_snapshot_test() {
+  let workspace_id = Uuid::new_v4().to_string();
+  let workspace_id = workspace_id.as_str();
+  let (_, db) = rocks_db();
+  let oid = "doc";
+  let doc = create_doc_with_updates(&db, workspace_id, oid, 20);
+  let expected = load_text(&db, workspace_id, oid);
+
+  let reclaimed = db
+    .with_write_txn(|w| {
+      w.create_snapshot_with_policy(
+        1,
+        workspace_id,
+        oid,
+        snapshot_data(&doc),
+        &UpdatePrunePolicy::new(5),
+      )
+    })
+    .unwrap();
+  assert_eq!(reclaimed.num_of_updates, 15);
+  assert!(reclaimed.bytes > 0);
+
+  let read = db.read_txn();
+  assert_eq!(read.number_of_updates(1, workspace_id, oid), 5);
+  assert_eq!(load_text(&db, workspace_id, oid), expected);
+}
+
+#[tokio::test]
+async fn prune_disabled_keeps_updates_test() {
+  let workspace_id = Uuid::new_v4().to_string();
+  let workspace_id = workspace_id.as_str();
+  let (_, db) = rocks_db();
+  let oid = "doc";
+  let doc = create_doc_with_updates(&db, workspace_id, oid, 10);
+
+  let reclaimed = db
+    .with_write_txn(|w| {
+      w.create_snapshot_with_policy(
+        1,
+        workspace_id,
+        oid,
+        snapshot_data(&doc),
+        &UpdatePrunePolicy::default(),
+      )
+    })
+    .unwrap();
+  assert_eq!(reclaimed.num_of_updates, 0);
+
+  let read = db.read_txn();
+  assert_eq!(read.number_of_updates(1, workspace_id, oid), 10);
+  let space = read.reclaimable_space(1, workspace_id, oid, 4).unwrap();
+  assert_eq!(space.num_of_updates, 6);
+
+  // Updates after the snapshot are not reclaimable
+  drop(read);
+  let text = doc.get_or_insert_text("text");
+  let mut txn = doc.transact_mut();
+  text.push(&mut txn, "new");
+  let update = txn.encode_update_v1();
+  db.with_write_txn(|w| w.push_update(1, workspace_id, oid, &update))
+    .unwrap();
+  let space = db
+    .read_txn()
+    .reclaimable_space(1, workspace_id, oid, 4)
+    .unwrap();
+  assert_eq!(space.num_of_updates, 6);
+}
+
+#[tokio::test]
+async fn prune_all_covered_updates_test() {
+  let workspace_id = Uuid::new_v4().to_string();
+  let workspace_id = workspace_id.as_str();
+  let (_, db) = rocks_db();
+  let oid = "doc";
+  let doc = create_doc_with_updates(&db, workspace_id, oid, 10);
+  db.with_write_txn(|w| {
+    w.create_snapshot_with_policy(
+      1,
+      workspace_id,
+      oid,
+      snapshot_data(&doc),
+      &UpdatePrunePolicy::new(0),
+    )
+  })
+  .unwrap();
+
+  let read = db.read_txn();
+  assert_eq!(read.number_of_updates(1, workspace_id, oid), 0);
+  assert!(read.get_snapshot_clock(1, workspace_id, oid).is_none());
+  drop(read);
+
+  // The new updates must not be treated as covered by the previous snapshot.
+  let text = doc.get_or_insert_text("text");
+  let mut txn = doc.transact_mut();
+  text.push(&mut txn, "new");
+  let update = txn.encode_update_v1();
+  drop(txn);
+  db.with_write_txn(|w| w.push_update(1, workspace_id, oid, &update))
+    .unwrap();
+  let read = db.read_txn();
+  assert_eq!(
+    read
+      .reclaimable_space(1, workspace_id, oid, 0)
+      .unwrap()
+      .num_of_updates,
+    0
+  );
+  drop(read);
+  assert_eq!(
+    load_text(&db, workspace_id, oid),
+    text.get_string(&doc.transact())
+  );
+}
@@ -9,3 +9,13 @@ pub fn rocks_db() -> (PathBuf, CollabKVDB) {
   let cloned_path = path.clone();
   (path, CollabKVDB::open(cloned_path).unwrap())
 }
+
+pub fn rocks_db_with_record_checksums() -> (PathBuf, CollabKVDB) {
+  let tempdir = TempDir::new().unwrap();
+  let path = tempdir.into_path();
+  let cloned_path = path.clone();
+  (
+    path,
+    CollabKVDB::open_with_record_checksums(cloned_path, true).unwrap(),
+  )
+}
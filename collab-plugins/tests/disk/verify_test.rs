@@ -0,0 +1,152 @@
+use crate::disk::util::{rocks_db, rocks_db_with_record_checksums};
+use collab_plugins::local_storage::kv::doc::CollabKVAction;
+use collab_plugins::local_storage::kv::snapshot::SnapshotAction;
+use collab_plugins::local_storage::kv::verify::{VerifyAction, VerifyIssueKind};
+use collab_plugins::local_storage::kv::{KVStore, KVTransactionDB};
+use collab_plugins::CollabKVDB;
+use uuid::Uuid;
+use yrs::{Doc, GetString, Text, Transact};
+
+fn write_doc(db: &CollabKVDB, uid: i64, workspace_id: &str, oid: &str, content: &str) {
+  let doc = Doc::new();
+  {
+    let txn = doc.transact();
+    db.with_write_txn(|w| w.create_new_doc(uid, workspace_id, oid, &txn))
+      .unwrap();
+  }
+  let text = doc.get_or_insert_text("text");
+  let mut txn = doc.transact_mut();
+  text.push(&mut txn, content);
+  let update = txn.encode_update_v1();
+  db.with_write_txn(|w| w.push_update(uid, workspace_id, oid, &update))
+    .unwrap();
+}
+
+#[tokio::test]
+async fn verify_valid_data_test() {
+  let workspace_id = Uuid::new_v4().to_string();
+  let workspace_id = workspace_id.as_str();
+  let (_, db) = rocks_db();
+  write_doc(&db, 1, workspace_id, "doc_1", "hello");
+  write_doc(&db, 1, workspace_id, "doc_2", "world");
+
+  let report = db.verify(1, false).unwrap();
+  assert!(report.is_ok(), "{:?}", report.issues);
+  assert_eq!(report.num_of_objects, 2);
+  // doc state, state vector and one update for each document
+  assert_eq!(report.num_of_records, 6);
+}
+
+#[tokio::test]
+async fn verify_and_quarantine_bad_records_test() {
+  let workspace_id = Uuid::new_v4().to_string();
+  let workspace_id = workspace_id.as_str();
+  let (_, db) = rocks_db();
+  write_doc(&db, 1, workspace_id, "doc_1", "hello");
+  db.with_write_txn(|w| w.push_update(1, workspace_id, "doc_1", &[255, 255, 255]))
+    .unwrap();
+  // A valid update written after the bad one may depend on it, so it's moved out with it.
+  let update = {
+    let doc = Doc::new();
+    let text = doc.get_or_insert_text("text");
+    let mut txn = doc.transact_mut();
+    text.push(&mut txn, "world");
+    txn.encode_update_v1()
+  };
+  db.with_write_txn(|w| w.push_update(1, workspace_id, "doc_1", &update))
+    .unwrap();
+  db.with_write_txn(|w| w.create_snapshot_with_data(1, "doc_1", vec![1, 2, 3]))
+    .unwrap();
+
+  let report = db.verify(1, false).unwrap();
+  assert_eq!(report.issues.len(), 3);
+  assert!(matches!(
+    report.issues[0].kind,
+    VerifyIssueKind::InvalidUpdate(_)
+  ));
+  assert_eq!(report.issues[1].kind, VerifyIssueKind::FollowsBadUpdate);
+  assert!(matches!(
+    report.issues[2].kind,
+    VerifyIssueKind::InvalidSnapshot(_)
+  ));
+  assert_eq!(report.num_of_quarantined(), 0);
+  assert_eq!(db.read_txn().number_of_updates(1, workspace_id, "doc_1"), 3);
+
+  let report = db.verify(1, true).unwrap();
+  assert_eq!(report.num_of_quarantined(), 3);
+  assert_eq!(db.read_txn().number_of_updates(1, workspace_id, "doc_1"), 1);
+  assert!(db.read_txn().get_snapshots(1, "doc_1").is_empty());
+
+  let records = db.read_txn().get_quarantined_records(1).unwrap();
+  assert_eq!(records.len(), 3);
+  assert_eq!(records[0].key, report.issues[0].key);
+  assert_eq!(records[0].value, vec![255, 255, 255]);
+
+  // The remaining data is valid and the document can be opened.
+  assert!(db.verify(1, false).unwrap().is_ok());
+  let doc = Doc::new();
+  {
+    let mut txn = doc.transact_mut();
+    db.read_txn()
+      .load_doc_with_txn(1, workspace_id, "doc_1", &mut txn)
+      .unwrap();
+  }
+  let text = doc.get_or_insert_text("text");
+  assert_eq!(text.get_string(&doc.transact()), "hello");
+}
+
+#[tokio::test]
+async fn verify_record_checksum_test() {
+  let workspace_id = Uuid::new_v4().to_string();
+  let workspace_id = workspace_id.as_str();
+  let (_, db) = rocks_db_with_record_checksums();
+  write_doc(&db, 1, workspace_id, "doc_1", "hello");
+
+  let doc = Doc::new();
+  let update = {
+    let text = doc.get_or_insert_text("text");
+    let mut txn = doc.transact_mut();
+    text.push(&mut txn, "world");
+    txn.encode_update_v1()
+  };
+  let update_key = db
+    .with_write_txn(|w| w.push_update(1, workspace_id, "doc_1", &update))
+    .unwrap();
+  // An empty update still decodes and applies, so only the checksum can tell it was changed.
+  db.with_write_txn(|w| w.insert(&update_key, [0, 0]))
+    .unwrap();
+
+  let report = db.verify(1, true).unwrap();
+  assert_eq!(report.issues.len(), 1);
+  assert_eq!(report.issues[0].kind, VerifyIssueKind::ChecksumMismatch);
+  assert_eq!(report.issues[0].key, update_key);
+  assert!(report.issues[0].quarantined);
+  assert!(db.verify(1, false).unwrap().is_ok());
+}
+
+#[tokio::test]
+async fn record_checksums_are_opt_in_test() {
+  let workspace_id = Uuid::new_v4().to_string();
+  let workspace_id = workspace_id.as_str();
+  let (path, db) = rocks_db_with_record_checksums();
+  write_doc(&db, 1, workspace_id, "doc_1", "hello");
+  let update_key = db
+    .with_write_txn(|w| w.push_update(1, workspace_id, "doc_1", &[0, 0]))
+    .unwrap();
+  drop(db);
+
+  // Opened without the checksums, the ones of the previous session are removed, so a record
+  // overwritten in this session isn't reported as a mismatch.
+  let db = CollabKVDB::open(&path).unwrap();
+  let update = {
+    let doc = Doc::new();
+    let text = doc.get_or_insert_text("text");
+    let mut txn = doc.transact_mut();
+    text.push(&mut txn, "world");
+    txn.encode_update_v1()
+  };
+  db.with_write_txn(|w| w.insert(&update_key, &update))
+    .unwrap();
+  let report = db.verify(1, false).unwrap();
+  assert!(report.is_ok(), "{:?}", report.issues);
+}
@@ -0,0 +1,171 @@
+use crate::disk::util::rocks_db;
+use collab_plugins::local_storage::kv::doc::CollabKVAction;
+use collab_plugins::local_storage::kv::snapshot::SnapshotAction;
+use collab_plugins::local_storage::kv::KVTransactionDB;
+use collab_plugins::CollabKVDB;
+use tempfile::TempDir;
+use uuid::Uuid;
+use yrs::{Doc, Text, Transact};
+
+fn write_doc(db: &CollabKVDB, uid: i64, workspace_id: &str, oid: &str) {
+  let doc = Doc::new();
+  {
+    let txn = doc.transact();
+    db.with_write_txn(|w| w.create_new_doc(uid, workspace_id, oid, &txn))
+      .unwrap();
+  }
+  let text = doc.get_or_insert_text("text");
+  let mut txn = doc.transact_mut();
+  text.push(&mut txn, oid);
+  let update = txn.encode_update_v1();
+  db.with_write_txn(|w| w.push_update(uid, workspace_id, oid, &update))
+    .unwrap();
+}
+
+#[tokio::test]
+async fn list_workspace_objects_test() {
+  let workspace_1 = Uuid::new_v4().to_string();
+  let workspace_2 = Uuid::new_v4().to_string();
+  let (_, db) = rocks_db();
+  write_doc(&db, 1, &workspace_1, "doc_1");
+  write_doc(&db, 1, &workspace_1, "doc_2");
+  write_doc(&db, 1, &workspace_2, "doc_3");
+  write_doc(&db, 2, &workspace_1, "doc_4");
+
+  let read_txn = db.read_txn();
+  let mut objects = read_txn.list_workspace_objects(1, &workspace_1).unwrap();
+  objects.sort_by(|a, b| a.object_id.cmp(&b.object_id));
+  assert_eq!(objects.len(), 2);
+  assert_eq!(objects[0].object_id, "doc_1");
+  assert_eq!(objects[1].object_id, "doc_2");
+
+  let mut object_ids = read_txn
+    .get_all_object_ids(1, &workspace_1)
+    .unwrap()
+    .collect::<Vec<_>>();
+  object_ids.sort();
+  assert_eq!(object_ids, vec!["doc_1".to_string(), "doc_2".to_string()]);
+
+  let objects = read_txn.list_workspace_objects(1, &workspace_2).unwrap();
+  assert_eq!(objects.len(), 1);
+  assert_eq!(objects[0].object_id, "doc_3");
+}
+
+#[tokio::test]
+async fn non_uuid_workspace_id_test() {
+  let (_, db) = rocks_db();
+  write_doc(&db, 1, "w1", "doc_1");
+  write_doc(&db, 1, "w1", "doc_2");
+  write_doc(&db, 1, "w2", "doc_3");
+
+  let read_txn = db.read_txn();
+  let mut object_ids = read_txn
+    .get_all_object_ids(1, "w1")
+    .unwrap()
+    .collect::<Vec<_>>();
+  object_ids.sort();
+  assert_eq!(object_ids, vec!["doc_1".to_string(), "doc_2".to_string()]);
+
+  let objects = read_txn.list_workspace_objects(1, "w2").unwrap();
+  assert_eq!(objects.len(), 1);
+  assert_eq!(objects[0].workspace_id.as_deref(), Some("w2"));
+  assert_eq!(objects[0].object_id, "doc_3");
+  assert!(read_txn.workspace_size(1, "w1").unwrap() > 0);
+  drop(read_txn);
+
+  let deleted = db.with_write_txn(|w| w.delete_workspace(1, "w1")).unwrap();
+  assert_eq!(deleted, 2);
+  let read_txn = db.read_txn();
+  assert!(!read_txn.is_exist(1, "w1", "doc_1"));
+  assert!(read_txn.is_exist(1, "w2", "doc_3"));
+}
+
+#[tokio::test]
+async fn workspace_size_test() {
+  let workspace_1 = Uuid::new_v4().to_string();
+  let workspace_2 = Uuid::new_v4().to_string();
+  let (_, db) = rocks_db();
+  assert_eq!(db.read_txn().workspace_size(1, &workspace_1).unwrap(), 0);
+
+  write_doc(&db, 1, &workspace_1, "doc_1");
+  write_doc(&db, 1, &workspace_1, "doc_2");
+  write_doc(&db, 1, &workspace_2, "doc_3");
+  db.with_write_txn(|w| w.create_snapshot_with_data(1, "doc_1", vec![1, 2, 3]))
+    .unwrap();
+
+  let read_txn = db.read_txn();
+  let size_1 = read_txn.workspace_size(1, &workspace_1).unwrap();
+  let size_2 = read_txn.workspace_size(1, &workspace_2).unwrap();
+  assert!(size_1 > size_2);
+  let total: u64 = read_txn
+    .list_objects(1)
+    .unwrap()
+    .iter()
+    .map(|object| object.size)
+    .sum();
+  assert_eq!(size_1 + size_2, total);
+}
+
+#[tokio::test]
+async fn delete_workspace_test() {
+  let workspace_1 = Uuid::new_v4().to_string();
+  let workspace_2 = Uuid::new_v4().to_string();
+  let (_, db) = rocks_db();
+  write_doc(&db, 1, &workspace_1, "doc_1");
+  write_doc(&db, 1, &workspace_1, "doc_2");
+  write_doc(&db, 1, &workspace_2, "doc_3");
+  write_doc(&db, 2, &workspace_1, "doc_4");
+  db.with_write_txn(|w| w.create_snapshot_with_data(1, "doc_1", vec![1, 2, 3]))
+    .unwrap();
+
+  let deleted = db
+    .with_write_txn(|w| w.delete_workspace(1, &workspace_1))
+    .unwrap();
+  assert_eq!(deleted, 2);
+
+  let read_txn = db.read_txn();
+  assert!(!read_txn.is_exist(1, workspace_1.as_str(), "doc_1"));
+  assert!(!read_txn.is_exist(1, workspace_1.as_str(), "doc_2"));
+  assert!(read_txn.get_snapshots(1, "doc_1").is_empty());
+  assert_eq!(read_txn.workspace_size(1, &workspace_1).unwrap(), 0);
+
+  // The other workspaces of the user and the same workspace of other users are kept
+  assert!(read_txn.is_exist(1, workspace_2.as_str(), "doc_3"));
+  assert!(read_txn.is_exist(2, workspace_1.as_str(), "doc_4"));
+  assert_eq!(
+    read_txn.number_of_updates(1, workspace_2.as_str(), "doc_3"),
+    1
+  );
+}
+
+#[tokio::test]
+async fn migrate_workspace_test() {
+  let workspace_1 = Uuid::new_v4().to_string();
+  let workspace_2 = Uuid::new_v4().to_string();
+  let (_, source) = rocks_db();
+  write_doc(&source, 1, &workspace_1, "doc_1");
+  write_doc(&source, 1, &workspace_2, "doc_2");
+
+  let archive_dir = TempDir::new().unwrap();
+  let archive_path = archive_dir.path().join("workspace.archive");
+  assert_eq!(
+    source
+      .export_workspace_archive(1, &workspace_1, &archive_path)
+      .unwrap(),
+    1
+  );
+  source
+    .with_write_txn(|w| w.delete_workspace(1, &workspace_1))
+    .unwrap();
+
+  let (_, target) = rocks_db();
+  // Safety: the archive isn't written while it's imported.
+  assert_eq!(unsafe { target.import_archive(&archive_path) }.unwrap(), 1);
+  let read_txn = target.read_txn();
+  assert!(read_txn.is_exist(1, workspace_1.as_str(), "doc_1"));
+  assert!(!read_txn.is_exist(1, workspace_2.as_str(), "doc_2"));
+
+  let read_txn = source.read_txn();
+  assert!(!read_txn.is_exist(1, workspace_1.as_str(), "doc_1"));
+  assert!(read_txn.is_exist(1, workspace_2.as_str(), "doc_2"));
+}
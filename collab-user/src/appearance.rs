@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+
+use collab::preclude::{
+  Any, DeepObservable, EntryChange, Event, Map, MapExt, MapRef, Out, PathSegment, ReadTxn,
+  Subscription, TransactionMut,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+const THEME: &str = "theme";
+const THEME_MODE: &str = "theme_mode";
+const FONT: &str = "font";
+const LOCALE: &str = "locale";
+const IS_SIDEBAR_COLLAPSED: &str = "is_sidebar_collapsed";
+const SIDEBAR_WIDTH: &str = "sidebar_width";
+const FEATURE_FLAGS: &str = "feature_flags";
+
+pub type AppearanceSettingsChangeSender = broadcast::Sender<AppearanceSettingsChange>;
+pub type AppearanceSettingsChangeReceiver = broadcast::Receiver<AppearanceSettingsChange>;
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ThemeMode {
+  Light = 0,
+  Dark = 1,
+  #[default]
+  System = 2,
+}
+
+impl From<i64> for ThemeMode {
+  fn from(value: i64) -> Self {
+    match value {
+      0 => ThemeMode::Light,
+      1 => ThemeMode::Dark,
+      _ => ThemeMode::System,
+    }
+  }
+}
+
+/// The appearance settings of the user. The values that were never set are None, so the client
+/// can use its own defaults.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AppearanceSettingsData {
+  pub theme: Option<String>,
+  pub theme_mode: ThemeMode,
+  pub font: Option<String>,
+  pub locale: Option<String>,
+  pub is_sidebar_collapsed: bool,
+  pub sidebar_width: Option<i64>,
+  pub feature_flags: HashMap<String, bool>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum AppearanceSettingsChange {
+  DidUpdateTheme {
+    theme: Option<String>,
+  },
+  DidUpdateThemeMode {
+    theme_mode: ThemeMode,
+  },
+  DidUpdateFont {
+    font: Option<String>,
+  },
+  DidUpdateLocale {
+    locale: Option<String>,
+  },
+  DidUpdateSidebar {
+    is_collapsed: bool,
+    width: Option<i64>,
+  },
+  /// The flag was set, or removed if `enabled` is None.
+  DidUpdateFeatureFlag {
+    flag: String,
+    enabled: Option<bool>,
+  },
+}
+
+/// The appearance settings stored in the user awareness, so they roam across the devices of the
+/// user. Each setting is stored under its own key, so updating different settings on different
+/// devices doesn't conflict.
+pub struct AppearanceSettings {
+  container: MapRef,
+  #[allow(dead_code)]
+  subscription: Option<Subscription>,
+}
+
+impl AppearanceSettings {
+  pub fn new(mut container: MapRef, change_tx: Option<AppearanceSettingsChangeSender>) -> Self {
+    let subscription =
+      change_tx.map(|change_tx| subscribe_appearance_settings_change(&mut container, change_tx));
+    Self {
+      container,
+      subscription,
+    }
+  }
+
+  pub fn get_settings<T: ReadTxn>(&self, txn: &T) -> AppearanceSettingsData {
+    settings_from_map(txn, &self.container)
+  }
+
+  pub fn is_feature_enabled<T: ReadTxn>(&self, txn: &T, flag: &str) -> Option<bool> {
+    let flags: MapRef = self.container.get_with_txn(txn, FEATURE_FLAGS)?;
+    flags.get_with_txn(txn, flag)
+  }
+
+  /// Returns the values that were set, as strings. The feature flags are prefixed with
+  /// `feature_flags.`.
+  pub fn to_string_map<T: ReadTxn>(&self, txn: &T) -> HashMap<String, String> {
+    let mut map = HashMap::new();
+    for (key, value) in self.container.iter(txn) {
+      match value {
+        Out::Any(any) => {
+          map.insert(key.to_string(), any_to_string(&any));
+        },
+        Out::YMap(flags) if key == FEATURE_FLAGS => {
+          for (flag, value) in flags.iter(txn) {
+            if let Out::Any(any) = value {
+              map.insert(format!("{}.{}", FEATURE_FLAGS, flag), any_to_string(&any));
+            }
+          }
+        },
+        _ => {},
+      }
+    }
+    map
+  }
+
+  pub fn update<'a, 'b: 'a>(
+    &'a self,
+    txn: &'a mut TransactionMut<'b>,
+  ) -> AppearanceSettingsUpdate<'a, 'b> {
+    AppearanceSettingsUpdate {
+      container: &self.container,
+      txn,
+    }
+  }
+}
+
+/// Updates the appearance settings. Only the values that are set are written.
+pub struct AppearanceSettingsUpdate<'a, 'b> {
+  container: &'a MapRef,
+  txn: &'a mut TransactionMut<'b>,
+}
+
+impl<'a, 'b> AppearanceSettingsUpdate<'a, 'b> {
+  pub fn set_theme(self, theme: &str) -> Self {
+    self.container.insert(self.txn, THEME, theme);
+    self
+  }
+
+  pub fn set_theme_mode(self, theme_mode: ThemeMode) -> Self {
+    self
+      .container
+      .insert(self.txn, THEME_MODE, Any::BigInt(theme_mode as i64));
+    self
+  }
+
+  pub fn set_font(self, font: &str) -> Self {
+    self.container.insert(self.txn, FONT, font);
+    self
+  }
+
+  pub fn set_locale(self, locale: &str) -> Self {
+    self.container.insert(self.txn, LOCALE, locale);
+    self
+  }
+
+  pub fn set_sidebar_collapsed(self, is_collapsed: bool) -> Self {
+    self
+      .container
+      .insert(self.txn, IS_SIDEBAR_COLLAPSED, is_collapsed);
+    self
+  }
+
+  pub fn set_sidebar_width(self, width: i64) -> Self {
+    self
+      .container
+      .insert(self.txn, SIDEBAR_WIDTH, Any::BigInt(width));
+    self
+  }
+
+  pub fn set_feature_flag(self, flag: &str, enabled: bool) -> Self {
+    let flags = self.container.get_or_init_map(self.txn, FEATURE_FLAGS);
+    flags.insert(self.txn, flag, enabled);
+    self
+  }
+
+  pub fn remove_feature_flag(self, flag: &str) -> Self {
+    if let Some(flags) = self
+      .container
+      .get_with_txn::<_, MapRef>(self.txn, FEATURE_FLAGS)
+    {
+      flags.remove(self.txn, flag);
+    }
+    self
+  }
+
+  pub fn done(self) {}
+}
+
+fn settings_from_map<T: ReadTxn>(txn: &T, map: &MapRef) -> AppearanceSettingsData {
+  let feature_flags = map
+    .get_with_txn::<_, MapRef>(txn, FEATURE_FLAGS)
+    .map(|flags| {
+      flags
+        .iter(txn)
+        .flat_map(|(flag, value)| match value {
+          Out::Any(Any::Bool(enabled)) => Some((flag.to_string(), enabled)),
+          _ => None,
+        })
+        .collect()
+    })
+    .unwrap_or_default();
+  AppearanceSettingsData {
+    theme: map.get_with_txn(txn, THEME),
+    theme_mode: map
+      .get_with_txn::<_, i64>(txn, THEME_MODE)
+      .map(ThemeMode::from)
+      .unwrap_or_default(),
+    font: map.get_with_txn(txn, FONT),
+    locale: map.get_with_txn(txn, LOCALE),
+    is_sidebar_collapsed: map
+      .get_with_txn(txn, IS_SIDEBAR_COLLAPSED)
+      .unwrap_or_default(),
+    sidebar_width: map.get_with_txn(txn, SIDEBAR_WIDTH),
+    feature_flags,
+  }
+}
+
+fn any_to_string(any: &Any) -> String {
+  match any {
+    Any::String(s) => s.to_string(),
+    _ => any.to_string(),
+  }
+}
+
+fn subscribe_appearance_settings_change(
+  root: &mut MapRef,
+  change_tx: AppearanceSettingsChangeSender,
+) -> Subscription {
+  let container = root.clone();
+  root.observe_deep(move |txn, events| {
+    let settings = settings_from_map(txn, &container);
+    for event in events.iter() {
+      let event = match event {
+        Event::Map(event) => event,
+        _ => continue,
+      };
+      let path = event.path();
+      if path.is_empty() {
+        let mut is_sidebar_changed = false;
+        for key in event.keys(txn).keys() {
+          let change = match key.as_ref() {
+            THEME => AppearanceSettingsChange::DidUpdateTheme {
+              theme: settings.theme.clone(),
+            },
+            THEME_MODE => AppearanceSettingsChange::DidUpdateThemeMode {
+              theme_mode: settings.theme_mode,
+            },
+            FONT => AppearanceSettingsChange::DidUpdateFont {
+              font: settings.font.clone(),
+            },
+            LOCALE => AppearanceSettingsChange::DidUpdateLocale {
+              locale: settings.locale.clone(),
+            },
+            IS_SIDEBAR_COLLAPSED | SIDEBAR_WIDTH => {
+              is_sidebar_changed = true;
+              continue;
+            },
+            // The flags map is created along with its first flags, so no nested event is
+            // emitted for them.
+            FEATURE_FLAGS => {
+              for (flag, enabled) in &settings.feature_flags {
+                let _ = change_tx.send(AppearanceSettingsChange::DidUpdateFeatureFlag {
+                  flag: flag.clone(),
+                  enabled: Some(*enabled),
+                });
+              }
+              continue;
+            },
+            _ => continue,
+          };
+          let _ = change_tx.send(change);
+        }
+        if is_sidebar_changed {
+          let _ = change_tx.send(AppearanceSettingsChange::DidUpdateSidebar {
+            is_collapsed: settings.is_sidebar_collapsed,
+            width: settings.sidebar_width,
+          });
+        }
+      } else if matches!(path.front(), Some(PathSegment::Key(key)) if key.as_ref() == FEATURE_FLAGS)
+      {
+        for (flag, change) in event.keys(txn).iter() {
+          let enabled = match change {
+            EntryChange::Removed(_) => None,
+            _ => settings.feature_flags.get(flag.as_ref()).copied(),
+          };
+          let _ = change_tx.send(AppearanceSettingsChange::DidUpdateFeatureFlag {
+            flag: flag.to_string(),
+            enabled,
+          });
+        }
+      }
+    }
+  })
+}
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use collab::core::awareness::Awareness;
+use collab::preclude::block::ClientID;
+use collab::preclude::Subscription;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+pub type DeviceSessionChangeSender = broadcast::Sender<DeviceSessionChange>;
+pub type DeviceSessionChangeReceiver = broadcast::Receiver<DeviceSessionChange>;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DevicePlatform {
+  MacOS,
+  Windows,
+  Linux,
+  IOS,
+  Android,
+  Web,
+  #[serde(other)]
+  Unknown,
+}
+
+/// The awareness state of a device on which the user awareness is opened.
+///
+/// The state is not persisted. It is shared with the other devices of the user while the device
+/// is connected, and removed when the device leaves or stops sending its state.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DeviceSession {
+  pub uid: i64,
+  pub device_id: String,
+  pub platform: DevicePlatform,
+  pub app_version: String,
+  /// The last time the user interacted with the app on the device, in seconds.
+  pub last_active: i64,
+}
+
+impl DeviceSession {
+  pub fn new(
+    uid: i64,
+    device_id: String,
+    platform: DevicePlatform,
+    app_version: String,
+    last_active: i64,
+  ) -> Self {
+    Self {
+      uid,
+      device_id,
+      platform,
+      app_version,
+      last_active,
+    }
+  }
+
+  /// Whether the device was active within `timeout` seconds before `now`.
+  pub fn is_active(&self, now: i64, timeout: i64) -> bool {
+    now - self.last_active <= timeout
+  }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum DeviceSessionChange {
+  DidJoin {
+    client_id: ClientID,
+    session: DeviceSession,
+  },
+  DidUpdate {
+    client_id: ClientID,
+    session: DeviceSession,
+  },
+  /// The device left. `session` is the last known state of the device.
+  DidLeave {
+    client_id: ClientID,
+    session: Option<DeviceSession>,
+  },
+}
+
+/// Returns the sessions of all the devices, including the local one, keyed by client id.
+pub fn device_sessions(awareness: &Awareness) -> HashMap<ClientID, DeviceSession> {
+  awareness
+    .iter()
+    .flat_map(|(client_id, state)| {
+      let session = serde_json::from_str::<DeviceSession>(state.data.as_deref()?).ok()?;
+      Some((client_id, session))
+    })
+    .collect()
+}
+
+pub(crate) fn subscribe_device_session_change(
+  awareness: &Awareness,
+  change_tx: DeviceSessionChangeSender,
+) -> Subscription {
+  // The state of a device is gone when it leaves, so the last known states are kept to be sent
+  // along with the leave event. The changes of the local device are not sent.
+  let sessions = Arc::new(Mutex::new(device_sessions(awareness)));
+  awareness.on_change(move |awareness, event, _| {
+    let mut sessions = match sessions.lock() {
+      Ok(sessions) => sessions,
+      Err(_) => return,
+    };
+    let local_client_id = awareness.client_id();
+    let changed = event
+      .added()
+      .iter()
+      .chain(event.updated())
+      .filter(|client_id| **client_id != local_client_id);
+    for &client_id in changed {
+      let session = match awareness.state::<DeviceSession>(client_id) {
+        Some(session) => session,
+        None => continue,
+      };
+      let change = match sessions.insert(client_id, session.clone()) {
+        None => DeviceSessionChange::DidJoin { client_id, session },
+        Some(_) => DeviceSessionChange::DidUpdate { client_id, session },
+      };
+      let _ = change_tx.send(change);
+    }
+    for &client_id in event
+      .removed()
+      .iter()
+      .filter(|client_id| **client_id != local_client_id)
+    {
+      let session = sessions.remove(&client_id);
+      let _ = change_tx.send(DeviceSessionChange::DidLeave { client_id, session });
+    }
+  })
+}
@@ -1,7 +1,13 @@
+mod appearance;
+mod device_session;
+mod notification;
 mod reminder;
 mod user_awareness;
 
 pub mod core {
+  pub use crate::appearance::*;
+  pub use crate::device_session::*;
+  pub use crate::notification::*;
   pub use crate::reminder::*;
   pub use crate::user_awareness::*;
 }
@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use collab::preclude::encoding::serde::{from_any, to_any};
+use collab::preclude::{
+  deserialize_i64_from_numeric, Any, Map, MapExt, MapRef, Out, ReadTxn, TransactionMut,
+};
+use serde::{Deserialize, Serialize};
+
+const NOTIFICATION_SETTINGS: &str = "notification_settings";
+const MUTED_WORKSPACES: &str = "muted_workspaces";
+const EVENT_TYPES: &str = "event_types";
+const QUIET_HOURS: &str = "quiet_hours";
+
+const MINUTES_PER_DAY: i64 = 24 * 60;
+
+/// The kinds of events the user can be notified about.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationEventType {
+  Mention,
+  Comment,
+  Reminder,
+  Invitation,
+  PageUpdate,
+}
+
+impl NotificationEventType {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      NotificationEventType::Mention => "mention",
+      NotificationEventType::Comment => "comment",
+      NotificationEventType::Reminder => "reminder",
+      NotificationEventType::Invitation => "invitation",
+      NotificationEventType::PageUpdate => "page_update",
+    }
+  }
+}
+
+impl FromStr for NotificationEventType {
+  type Err = anyhow::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "mention" => Ok(NotificationEventType::Mention),
+      "comment" => Ok(NotificationEventType::Comment),
+      "reminder" => Ok(NotificationEventType::Reminder),
+      "invitation" => Ok(NotificationEventType::Invitation),
+      "page_update" => Ok(NotificationEventType::PageUpdate),
+      _ => Err(anyhow::anyhow!("Unknown notification event type: {}", s)),
+    }
+  }
+}
+
+/// The time of the day during which no notification is pushed. The minutes are counted from the
+/// midnight of the user's timezone, which is `utc_offset_minutes` away from UTC, so the server
+/// can evaluate the quiet hours without knowing the user's device.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub struct QuietHours {
+  #[serde(deserialize_with = "deserialize_i64_from_numeric")]
+  pub start_minute: i64,
+  #[serde(deserialize_with = "deserialize_i64_from_numeric")]
+  pub end_minute: i64,
+  #[serde(default, deserialize_with = "deserialize_i64_from_numeric")]
+  pub utc_offset_minutes: i64,
+}
+
+impl QuietHours {
+  pub fn new(start_minute: i64, end_minute: i64, utc_offset_minutes: i64) -> Self {
+    Self {
+      start_minute: start_minute.rem_euclid(MINUTES_PER_DAY),
+      end_minute: end_minute.rem_euclid(MINUTES_PER_DAY),
+      utc_offset_minutes,
+    }
+  }
+
+  /// Whether the timestamp, in seconds, falls in the quiet hours. The quiet hours wrap around
+  /// midnight when the end is before the start, and are empty when the start is the end.
+  pub fn contains(&self, timestamp: i64) -> bool {
+    let minute = (timestamp.div_euclid(60) + self.utc_offset_minutes).rem_euclid(MINUTES_PER_DAY);
+    if self.start_minute <= self.end_minute {
+      self.start_minute <= minute && minute < self.end_minute
+    } else {
+      minute >= self.start_minute || minute < self.end_minute
+    }
+  }
+}
+
+/// A snapshot of the notification settings of the user.
+///
+/// The server reads the same settings from the user awareness collab and calls
+/// [NotificationSettingsData::should_notify] before pushing a notification, so the client and
+/// the server agree on which notifications are shown.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct NotificationSettingsData {
+  pub muted_workspaces: Vec<String>,
+  /// The event types that were turned on or off. The event types that are not in the map are
+  /// enabled.
+  pub event_types: HashMap<NotificationEventType, bool>,
+  pub quiet_hours: Option<QuietHours>,
+}
+
+impl NotificationSettingsData {
+  pub fn is_workspace_muted(&self, workspace_id: &str) -> bool {
+    self.muted_workspaces.iter().any(|id| id == workspace_id)
+  }
+
+  pub fn is_event_enabled(&self, event_type: NotificationEventType) -> bool {
+    self.event_types.get(&event_type).copied().unwrap_or(true)
+  }
+
+  /// Whether a notification of the event type, sent from the workspace at `timestamp` in seconds,
+  /// should be pushed to the user.
+  pub fn should_notify(
+    &self,
+    workspace_id: &str,
+    event_type: NotificationEventType,
+    timestamp: i64,
+  ) -> bool {
+    !self.is_workspace_muted(workspace_id)
+      && self.is_event_enabled(event_type)
+      && !self
+        .quiet_hours
+        .map(|quiet_hours| quiet_hours.contains(timestamp))
+        .unwrap_or(false)
+  }
+}
+
+/// The notification settings stored in the user awareness.
+///
+/// The settings map is created the first time a setting is written, so the user awareness
+/// created before the settings existed can still be opened.
+pub struct NotificationSettings {
+  container: MapRef,
+}
+
+impl NotificationSettings {
+  pub fn new(container: MapRef) -> Self {
+    Self { container }
+  }
+
+  pub fn get_settings<T: ReadTxn>(&self, txn: &T) -> NotificationSettingsData {
+    let settings = match self.settings_map(txn) {
+      None => return NotificationSettingsData::default(),
+      Some(settings) => settings,
+    };
+    let mut muted_workspaces = settings
+      .get_with_txn::<_, MapRef>(txn, MUTED_WORKSPACES)
+      .map(|map| {
+        map
+          .iter(txn)
+          .map(|(workspace_id, _)| workspace_id.to_string())
+          .collect::<Vec<_>>()
+      })
+      .unwrap_or_default();
+    muted_workspaces.sort();
+    let event_types = settings
+      .get_with_txn::<_, MapRef>(txn, EVENT_TYPES)
+      .map(|map| {
+        map
+          .iter(txn)
+          .flat_map(|(event_type, value)| match value {
+            Out::Any(Any::Bool(enabled)) => Some((event_type.parse().ok()?, enabled)),
+            _ => None,
+          })
+          .collect()
+      })
+      .unwrap_or_default();
+    let quiet_hours = match settings.get(txn, QUIET_HOURS) {
+      Some(Out::Any(any)) => from_any(&any).ok(),
+      _ => None,
+    };
+    NotificationSettingsData {
+      muted_workspaces,
+      event_types,
+      quiet_hours,
+    }
+  }
+
+  pub fn update<'a, 'b: 'a>(
+    &'a self,
+    txn: &'a mut TransactionMut<'b>,
+  ) -> NotificationSettingsUpdate<'a, 'b> {
+    let settings = self.container.get_or_init_map(txn, NOTIFICATION_SETTINGS);
+    NotificationSettingsUpdate { settings, txn }
+  }
+
+  fn settings_map<T: ReadTxn>(&self, txn: &T) -> Option<MapRef> {
+    self.container.get_with_txn(txn, NOTIFICATION_SETTINGS)
+  }
+}
+
+/// Updates the notification settings. Each workspace and event type is stored under its own key,
+/// so changing them on different devices doesn't conflict.
+pub struct NotificationSettingsUpdate<'a, 'b> {
+  settings: MapRef,
+  txn: &'a mut TransactionMut<'b>,
+}
+
+impl<'a, 'b> NotificationSettingsUpdate<'a, 'b> {
+  pub fn mute_workspace(self, workspace_id: &str) -> Self {
+    let map = self.settings.get_or_init_map(self.txn, MUTED_WORKSPACES);
+    map.insert(self.txn, workspace_id, true);
+    self
+  }
+
+  pub fn unmute_workspace(self, workspace_id: &str) -> Self {
+    if let Some(map) = self
+      .settings
+      .get_with_txn::<_, MapRef>(self.txn, MUTED_WORKSPACES)
+    {
+      map.remove(self.txn, workspace_id);
+    }
+    self
+  }
+
+  pub fn set_event_enabled(self, event_type: NotificationEventType, enabled: bool) -> Self {
+    let map = self.settings.get_or_init_map(self.txn, EVENT_TYPES);
+    map.insert(self.txn, event_type.as_str(), enabled);
+    self
+  }
+
+  /// Sets the quiet hours, or removes them if `quiet_hours` is None.
+  pub fn set_quiet_hours(self, quiet_hours: Option<QuietHours>) -> Self {
+    match quiet_hours.and_then(|quiet_hours| to_any(&quiet_hours).ok()) {
+      None => {
+        self.settings.remove(self.txn, QUIET_HOURS);
+      },
+      Some(any) => {
+        self.settings.insert(self.txn, QUIET_HOURS, any);
+      },
+    }
+    self
+  }
+
+  pub fn done(self) {}
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn quiet_hours_test() {
+    // 22:00 to 07:00
+    let quiet_hours = QuietHours::new(22 * 60, 7 * 60, 0);
+    assert!(quiet_hours.contains(23 * 3600));
+    assert!(quiet_hours.contains(3 * 3600));
+    assert!(!quiet_hours.contains(7 * 3600));
+    assert!(!quiet_hours.contains(12 * 3600));
+
+    // 09:00 to 17:00 in UTC+2 is 07:00 to 15:00 in UTC
+    let quiet_hours = QuietHours::new(9 * 60, 17 * 60, 120);
+    assert!(quiet_hours.contains(8 * 3600));
+    assert!(!quiet_hours.contains(16 * 3600));
+
+    assert!(!QuietHours::new(60, 60, 0).contains(60 * 60));
+  }
+}
@@ -1,9 +1,13 @@
 use collab::preclude::encoding::serde::{from_any, to_any};
 use collab::preclude::{
-  Any, Array, ArrayRef, Change, DeepObservable, Event, Map, MapPrelim, Out, ReadTxn, Subscription,
-  ToJson, TransactionMut, YrsValue,
+  Any, Array, ArrayRef, Change, DeepObservable, Event, Map, MapPrelim, MapRef, Out, ReadTxn,
+  Subscription, ToJson, TransactionMut, YrsValue,
+};
+use collab_entity::reminder::{
+  Reminder, REMINDER_BLOCK_ID, REMINDER_ID, REMINDER_IS_ACK, REMINDER_IS_READ, REMINDER_MESSAGE,
+  REMINDER_META, REMINDER_OBJECT_ID, REMINDER_REPEAT, REMINDER_SCHEDULED_AT, REMINDER_TITLE,
+  REMINDER_TY,
 };
-use collab_entity::reminder::{Reminder, REMINDER_ID};
 use tokio::sync::broadcast;
 
 pub type RemindersChangeSender = broadcast::Sender<ReminderChange>;
@@ -12,6 +16,7 @@ pub type RemindersChangeReceiver = broadcast::Receiver<ReminderChange>;
 #[derive(Debug, Clone)]
 pub enum ReminderChange {
   DidCreateReminders { reminders: Vec<Reminder> },
+  DidUpdateReminder { reminder: Reminder },
   DidDeleteReminder { index: u32 },
 }
 
@@ -44,9 +49,20 @@ impl Reminders {
     None
   }
 
-  pub fn remove(&self, txn: &mut TransactionMut, id: &str) {
-    let (i, _) = self.find(txn, id).unwrap();
-    self.container.remove(txn, i);
+  /// Removes the reminder. Returns false if the reminder doesn't exist.
+  pub fn remove(&self, txn: &mut TransactionMut, id: &str) -> bool {
+    match self.find(txn, id) {
+      None => false,
+      Some((i, _)) => {
+        self.container.remove(txn, i);
+        true
+      },
+    }
+  }
+
+  pub fn get_reminder<T: ReadTxn>(&self, txn: &T, reminder_id: &str) -> Option<Reminder> {
+    let (_, value) = self.find(txn, reminder_id)?;
+    from_any::<Reminder>(&value.to_json(txn)).ok()
   }
 
   pub fn add(&self, txn: &mut TransactionMut, reminder: Reminder) {
@@ -54,22 +70,70 @@ impl Reminders {
     self.container.push_back(txn, map);
   }
 
-  pub fn update_reminder<F>(&self, txn: &mut TransactionMut, reminder_id: &str, f: F)
+  /// Updates the reminder and returns the updated reminder. Only the changed fields are written,
+  /// so concurrent updates of different fields on other devices are kept.
+  pub fn update_reminder<F>(
+    &self,
+    txn: &mut TransactionMut,
+    reminder_id: &str,
+    f: F,
+  ) -> Option<Reminder>
   where
     F: FnOnce(&mut Reminder),
   {
-    if let Some((i, value)) = self.find(txn, reminder_id) {
-      if let Ok(mut reminder) = from_any::<Reminder>(&value.to_json(txn)) {
-        // FIXME: this is wrong. This doesn't "update" the reminder, instead it replaces it,
-        //   with all of the unchanged fields included. That means, that if two people will be
-        //   updating the same reminder at the same time, the last one will overwrite the changes
-        //   of the first one, even if there was no edit collisions.
-        f(&mut reminder);
+    let (i, value) = self.find(txn, reminder_id)?;
+    let old = from_any::<Reminder>(&value.to_json(txn)).ok()?;
+    let mut reminder = old.clone();
+    f(&mut reminder);
+    // The id is used to find the reminder, so it can't be changed.
+    reminder.id = old.id.clone();
+    if reminder == old {
+      return Some(reminder);
+    }
+
+    match value {
+      Out::YMap(map) => update_reminder_map(txn, &map, &old, &reminder),
+      _ => {
+        // The reminders updated by the previous versions were stored as a single value.
         self.container.remove(txn, i);
-        let any = to_any(&reminder).unwrap();
-        self.container.insert(txn, i, any);
-      }
+        self
+          .container
+          .insert(txn, i, MapPrelim::from(reminder.clone()));
+      },
     }
+    Some(reminder)
+  }
+
+  /// Returns the reminders that should fire at `now`, see [Reminder::is_due].
+  pub fn get_due_reminders<T: ReadTxn>(&self, txn: &T, now: i64) -> Vec<Reminder> {
+    self
+      .get_all_reminders(txn)
+      .into_iter()
+      .filter(|reminder| reminder.is_due(now))
+      .collect()
+  }
+
+  /// Acknowledges the reminder. A repeating reminder is moved to its next occurrence after `now`
+  /// instead, and is only acknowledged when there are no more occurrences.
+  pub fn ack_reminder(
+    &self,
+    txn: &mut TransactionMut,
+    reminder_id: &str,
+    now: i64,
+  ) -> Option<Reminder> {
+    self.update_reminder(txn, reminder_id, |reminder| {
+      let next = reminder
+        .repeat
+        .as_ref()
+        .and_then(|repeat| repeat.next_after(reminder.scheduled_at, now));
+      match next {
+        Some(next) => {
+          reminder.scheduled_at = next;
+          reminder.is_read = false;
+        },
+        None => reminder.is_ack = true,
+      }
+    })
   }
 
   pub fn get_all_reminders<T: ReadTxn>(&self, txn: &T) -> Vec<Reminder> {
@@ -88,11 +152,58 @@ impl Reminders {
   }
 }
 
+fn update_reminder_map(txn: &mut TransactionMut, map: &MapRef, old: &Reminder, new: &Reminder) {
+  if old.object_id != new.object_id {
+    map.insert(txn, REMINDER_OBJECT_ID, new.object_id.clone());
+  }
+  if old.scheduled_at != new.scheduled_at {
+    map.insert(txn, REMINDER_SCHEDULED_AT, Any::BigInt(new.scheduled_at));
+  }
+  if old.is_ack != new.is_ack {
+    map.insert(txn, REMINDER_IS_ACK, new.is_ack);
+  }
+  if old.is_read != new.is_read {
+    map.insert(txn, REMINDER_IS_READ, new.is_read);
+  }
+  if old.ty != new.ty {
+    map.insert(txn, REMINDER_TY, Any::BigInt(new.ty.clone() as i64));
+  }
+  if old.title != new.title {
+    map.insert(txn, REMINDER_TITLE, new.title.clone());
+  }
+  if old.message != new.message {
+    map.insert(txn, REMINDER_MESSAGE, new.message.clone());
+  }
+  if old.meta != new.meta {
+    map.insert(txn, REMINDER_META, Any::from(new.meta.clone()));
+  }
+  if old.block_id != new.block_id {
+    match &new.block_id {
+      None => {
+        map.remove(txn, REMINDER_BLOCK_ID);
+      },
+      Some(block_id) => {
+        map.insert(txn, REMINDER_BLOCK_ID, block_id.clone());
+      },
+    }
+  }
+  if old.repeat != new.repeat {
+    match new.repeat.as_ref().and_then(|repeat| to_any(repeat).ok()) {
+      None => {
+        map.remove(txn, REMINDER_REPEAT);
+      },
+      Some(repeat) => {
+        map.insert(txn, REMINDER_REPEAT, repeat);
+      },
+    }
+  }
+}
+
 /// Subscribes to changes in the reminders array and dispatches relevant notifications.
 ///
 /// The function subscribes to deep changes in the provided `ArrayRefWrapper`, filtering
-/// for events specific to the reminders array. When reminders are added or removed, appropriate
-/// messages are sent to the `change_tx` channel.
+/// for events specific to the reminders array. When reminders are added, updated or removed,
+/// appropriate messages are sent to the `change_tx` channel.
 ///
 /// # Arguments
 ///
@@ -109,6 +220,13 @@ fn subscribe_reminder_change(
 ) -> Subscription {
   root.observe_deep(move |txn, events| {
     for event in events.iter() {
+      if let Event::Map(map_event) = event {
+        // The fields of a reminder were updated
+        if let Ok(reminder) = Reminder::try_from((txn, map_event.target())) {
+          let _ = change_tx.send(ReminderChange::DidUpdateReminder { reminder });
+        }
+        continue;
+      }
       if let Event::Array(array_event) = event {
         for change in array_event.delta(txn) {
           let change_tx = change_tx.clone();
@@ -2,11 +2,21 @@ use std::borrow::{Borrow, BorrowMut};
 use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
 
+use crate::appearance::{
+  AppearanceSettings, AppearanceSettingsChangeSender, AppearanceSettingsData,
+  AppearanceSettingsUpdate,
+};
+use crate::device_session::{
+  device_sessions, subscribe_device_session_change, DeviceSession, DeviceSessionChangeSender,
+};
+use crate::notification::{
+  NotificationSettings, NotificationSettingsData, NotificationSettingsUpdate,
+};
 use crate::reminder::{Reminders, RemindersChangeSender};
 use anyhow::{Error, Result};
 use collab::core::origin::CollabOrigin;
 use collab::entity::EncodedCollab;
-use collab::preclude::{ArrayRef, Collab, Map, MapExt, MapRef};
+use collab::preclude::{ArrayRef, Collab, Map, MapExt, MapRef, Subscription};
 use collab_entity::define::USER_AWARENESS;
 use collab_entity::reminder::Reminder;
 use collab_entity::CollabType;
@@ -93,13 +103,106 @@ impl UserAwareness {
     let txn = self.collab.transact();
     let reminders = self.body.reminders.get_all_reminders(&txn);
     let data = UserAwarenessData {
-      appearance_settings: Default::default(),
+      appearance_settings: self.body.appearance_settings.to_string_map(&txn),
       reminders,
     };
     let value = serde_json::to_value(data)?;
     Ok(value)
   }
 
+  pub fn get_appearance_settings(&self) -> AppearanceSettingsData {
+    let txn = self.collab.transact();
+    self.body.appearance_settings.get_settings(&txn)
+  }
+
+  /// Updates the appearance settings. The settings roam to the other devices of the user.
+  ///
+  /// # Arguments
+  ///
+  /// * `f` - A function or closure that takes `AppearanceSettingsUpdate` as its argument and
+  ///   sets the values to be changed.
+  pub fn update_appearance_settings<F>(&mut self, f: F)
+  where
+    F: FnOnce(AppearanceSettingsUpdate),
+  {
+    let mut txn = self.collab.transact_mut();
+    f(self.body.appearance_settings.update(&mut txn));
+  }
+
+  /// Returns whether the feature flag is enabled, or None if the flag was never set.
+  pub fn is_feature_enabled(&self, flag: &str) -> Option<bool> {
+    let txn = self.collab.transact();
+    self.body.appearance_settings.is_feature_enabled(&txn, flag)
+  }
+
+  pub fn get_notification_settings(&self) -> NotificationSettingsData {
+    let txn = self.collab.transact();
+    self.body.notification_settings.get_settings(&txn)
+  }
+
+  /// Updates the notification settings.
+  ///
+  /// # Arguments
+  ///
+  /// * `f` - A function or closure that takes `NotificationSettingsUpdate` as its argument and
+  ///   sets the values to be changed.
+  pub fn update_notification_settings<F>(&mut self, f: F)
+  where
+    F: FnOnce(NotificationSettingsUpdate),
+  {
+    let mut txn = self.collab.transact_mut();
+    f(self.body.notification_settings.update(&mut txn));
+  }
+
+  /// Sets the session of the local device, so the other devices of the user know it is online.
+  /// It overrides the previous session.
+  pub fn set_device_session(&mut self, session: DeviceSession) {
+    if let Err(e) = self.collab.get_mut_awareness().set_local_state(session) {
+      tracing::error!("Failed to serialize DeviceSession: {}", e);
+    }
+  }
+
+  pub fn get_device_session(&self) -> Option<DeviceSession> {
+    self.collab.get_awareness().local_state()
+  }
+
+  /// Updates the last active time of the local device session, in seconds.
+  pub fn mark_device_active(&mut self, now: i64) {
+    if let Some(mut session) = self.get_device_session() {
+      session.last_active = now;
+      self.set_device_session(session);
+    }
+  }
+
+  /// Removes the session of the local device.
+  /// It should be called when the user awareness is closed.
+  pub fn clean_device_session(&mut self) {
+    self.collab.get_mut_awareness().clean_local_state()
+  }
+
+  /// Returns the sessions of all the devices of the user, the most recently active first.
+  pub fn get_device_sessions(&self) -> Vec<DeviceSession> {
+    let mut sessions = device_sessions(self.collab.get_awareness())
+      .into_values()
+      .collect::<Vec<_>>();
+    sessions.sort_by(|a, b| {
+      b.last_active
+        .cmp(&a.last_active)
+        .then_with(|| a.device_id.cmp(&b.device_id))
+    });
+    sessions
+  }
+
+  /// Returns the sessions of the other devices of the user, the most recently active first.
+  pub fn get_other_device_sessions(&self) -> Vec<DeviceSession> {
+    let local_device_id = self.get_device_session().map(|session| session.device_id);
+    self
+      .get_device_sessions()
+      .into_iter()
+      .filter(|session| Some(&session.device_id) != local_device_id.as_ref())
+      .collect()
+  }
+
   pub fn get_all_reminders(&self) -> Vec<Reminder> {
     let txn = self.collab.transact();
     self.body.reminders.get_all_reminders(&txn)
@@ -120,9 +223,11 @@ impl UserAwareness {
   /// # Arguments
   ///
   /// * `reminder_id` - A string reference to the ID of the reminder to be removed.
-  pub fn remove_reminder(&mut self, reminder_id: &str) {
+  ///
+  /// Returns false if the reminder doesn't exist.
+  pub fn remove_reminder(&mut self, reminder_id: &str) -> bool {
     let mut txn = self.collab.transact_mut();
-    self.body.reminders.remove(&mut txn, reminder_id);
+    self.body.reminders.remove(&mut txn, reminder_id)
   }
 
   /// Updates an existing reminder in the `UserAwareness` object.
@@ -131,7 +236,9 @@ impl UserAwareness {
   ///
   /// * `reminder_id` - A string reference to the ID of the reminder to be updated.
   /// * `f` - A function or closure that takes `ReminderUpdate` as its argument and implements the changes to the reminder.
-  pub fn update_reminder<F>(&mut self, reminder_id: &str, f: F)
+  ///
+  /// Returns the updated reminder, or None if the reminder doesn't exist.
+  pub fn update_reminder<F>(&mut self, reminder_id: &str, f: F) -> Option<Reminder>
   where
     F: FnOnce(&mut Reminder),
   {
@@ -139,7 +246,30 @@ impl UserAwareness {
     self
       .body
       .reminders
-      .update_reminder(&mut txn, reminder_id, f);
+      .update_reminder(&mut txn, reminder_id, f)
+  }
+
+  pub fn get_reminder(&self, reminder_id: &str) -> Option<Reminder> {
+    let txn = self.collab.transact();
+    self.body.reminders.get_reminder(&txn, reminder_id)
+  }
+
+  /// Returns the reminders that should fire at `now`, in seconds.
+  pub fn get_due_reminders(&self, now: i64) -> Vec<Reminder> {
+    let txn = self.collab.transact();
+    self.body.reminders.get_due_reminders(&txn, now)
+  }
+
+  /// Acknowledges the reminder so it doesn't fire again on any device. A repeating reminder is
+  /// moved to its next occurrence after `now` instead.
+  ///
+  /// # Arguments
+  ///
+  /// * `reminder_id` - The ID of the reminder to be acknowledged.
+  /// * `now` - The current time, in seconds.
+  pub fn ack_reminder(&mut self, reminder_id: &str, now: i64) -> Option<Reminder> {
+    let mut txn = self.collab.transact_mut();
+    self.body.reminders.ack_reminder(&mut txn, reminder_id, now)
   }
 }
 
@@ -181,10 +311,12 @@ impl BorrowMut<Collab> for UserAwareness {
 pub struct UserAwarenessBody {
   #[allow(dead_code)]
   container: MapRef,
-  #[allow(dead_code)]
-  appearance_settings: MapRef,
+  appearance_settings: AppearanceSettings,
+  notification_settings: NotificationSettings,
   reminders: Reminders,
   #[allow(dead_code)]
+  device_session_subscription: Option<Subscription>,
+  #[allow(dead_code)]
   notifier: Option<UserAwarenessNotifier>,
 }
 
@@ -193,7 +325,12 @@ impl UserAwarenessBody {
     let mut txn = collab.context.transact_mut();
     let container = collab.data.get_or_init_map(&mut txn, USER_AWARENESS);
 
-    let appearance_settings = container.get_or_init_map(&mut txn, APPEARANCE_SETTINGS);
+    let appearance_settings = AppearanceSettings::new(
+      container.get_or_init_map(&mut txn, APPEARANCE_SETTINGS),
+      notifier
+        .as_ref()
+        .map(|notifier| notifier.appearance_change_tx.clone()),
+    );
 
     let reminder_container: ArrayRef = container.get_or_init(&mut txn, REMINDERS);
     let reminders = Reminders::new(
@@ -202,10 +339,15 @@ impl UserAwarenessBody {
         .as_ref()
         .map(|notifier| notifier.reminder_change_tx.clone()),
     );
+    let notification_settings = NotificationSettings::new(container.clone());
+    drop(txn);
+    let device_session_subscription = subscribe_device_session(collab, notifier.as_ref());
     Self {
       container,
       appearance_settings,
+      notification_settings,
       reminders,
+      device_session_subscription,
       notifier,
     }
   }
@@ -213,7 +355,12 @@ impl UserAwarenessBody {
   pub fn try_open(collab: &Collab, notifier: Option<UserAwarenessNotifier>) -> Option<Self> {
     let txn = collab.context.transact();
     let awareness: MapRef = collab.data.get_with_txn(&txn, USER_AWARENESS)?;
-    let appearance_settings = awareness.get_with_txn(&txn, APPEARANCE_SETTINGS)?;
+    let appearance_settings = AppearanceSettings::new(
+      awareness.get_with_txn(&txn, APPEARANCE_SETTINGS)?,
+      notifier
+        .as_ref()
+        .map(|notifier| notifier.appearance_change_tx.clone()),
+    );
 
     let reminders = Reminders::new(
       awareness.get_with_txn(&txn, REMINDERS)?,
@@ -221,18 +368,35 @@ impl UserAwarenessBody {
         .as_ref()
         .map(|notifier| notifier.reminder_change_tx.clone()),
     );
+    drop(txn);
+    let device_session_subscription = subscribe_device_session(collab, notifier.as_ref());
     Some(Self {
+      notification_settings: NotificationSettings::new(awareness.clone()),
       container: awareness,
       appearance_settings,
       reminders,
+      device_session_subscription,
       notifier,
     })
   }
 }
 
+fn subscribe_device_session(
+  collab: &Collab,
+  notifier: Option<&UserAwarenessNotifier>,
+) -> Option<Subscription> {
+  let change_tx = notifier?.device_session_change_tx.clone();
+  Some(subscribe_device_session_change(
+    collab.get_awareness(),
+    change_tx,
+  ))
+}
+
 #[derive(Clone)]
 pub struct UserAwarenessNotifier {
   pub reminder_change_tx: RemindersChangeSender,
+  pub appearance_change_tx: AppearanceSettingsChangeSender,
+  pub device_session_change_tx: DeviceSessionChangeSender,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use collab::core::collab::DataSource;
+use collab::core::origin::CollabOrigin;
+use collab::preclude::updates::decoder::Decode;
+use collab::preclude::{Collab, ReadTxn, Update};
+use collab_user::core::{
+  AppearanceSettingsChange, ThemeMode, UserAwareness, UserAwarenessNotifier,
+};
+
+use crate::util::{receive_with_timeout, UserAwarenessTest};
+
+#[tokio::test]
+async fn update_appearance_settings_test() {
+  let mut test = UserAwarenessTest::new(1);
+  let settings = test.get_appearance_settings();
+  assert_eq!(settings.theme, None);
+  assert_eq!(settings.theme_mode, ThemeMode::System);
+  assert!(!settings.is_sidebar_collapsed);
+
+  test.update_appearance_settings(|update| {
+    update
+      .set_theme("Dandelion")
+      .set_theme_mode(ThemeMode::Dark)
+      .set_font("Poppins")
+      .set_locale("en-US")
+      .set_sidebar_collapsed(true)
+      .set_sidebar_width(280)
+      .set_feature_flag("ai_chat", true)
+      .done();
+  });
+  let settings = test.get_appearance_settings();
+  assert_eq!(settings.theme.as_deref(), Some("Dandelion"));
+  assert_eq!(settings.theme_mode, ThemeMode::Dark);
+  assert_eq!(settings.font.as_deref(), Some("Poppins"));
+  assert_eq!(settings.locale.as_deref(), Some("en-US"));
+  assert!(settings.is_sidebar_collapsed);
+  assert_eq!(settings.sidebar_width, Some(280));
+  assert_eq!(test.is_feature_enabled("ai_chat"), Some(true));
+  assert_eq!(test.is_feature_enabled("calendar"), None);
+
+  test.update_appearance_settings(|update| update.remove_feature_flag("ai_chat").done());
+  assert_eq!(test.is_feature_enabled("ai_chat"), None);
+
+  let json = test.to_json().unwrap();
+  assert_eq!(json["appearance_settings"]["theme"], "Dandelion");
+  assert_eq!(json["appearance_settings"]["theme_mode"], "1");
+}
+
+#[tokio::test]
+async fn appearance_settings_roam_to_remote_test() {
+  let mut local = UserAwarenessTest::new(1);
+  let doc_state = local
+    .encode_collab_v1(|_| Ok::<_, anyhow::Error>(()))
+    .unwrap();
+  let collab = Collab::new_with_source(
+    CollabOrigin::Empty,
+    "1",
+    DataSource::DocStateV1(doc_state.doc_state.to_vec()),
+    vec![],
+    false,
+  )
+  .unwrap();
+  let (appearance_change_tx, mut rx) = tokio::sync::broadcast::channel(100);
+  let notifier = UserAwarenessNotifier {
+    reminder_change_tx: tokio::sync::broadcast::channel(100).0,
+    appearance_change_tx,
+    device_session_change_tx: tokio::sync::broadcast::channel(100).0,
+  };
+  let mut remote = UserAwareness::open(collab, Some(notifier)).unwrap();
+
+  local.update_appearance_settings(|update| {
+    update
+      .set_theme_mode(ThemeMode::Light)
+      .set_feature_flag("calendar", false)
+      .done();
+  });
+  apply_updates(&local.user_awareness, &mut remote);
+  assert_eq!(
+    remote.get_appearance_settings().theme_mode,
+    ThemeMode::Light
+  );
+  assert_eq!(remote.is_feature_enabled("calendar"), Some(false));
+
+  let mut changes = vec![];
+  for _ in 0..2 {
+    changes.push(
+      receive_with_timeout(&mut rx, Duration::from_secs(2))
+        .await
+        .unwrap(),
+    );
+  }
+  assert!(
+    changes.contains(&AppearanceSettingsChange::DidUpdateThemeMode {
+      theme_mode: ThemeMode::Light
+    })
+  );
+  assert!(
+    changes.contains(&AppearanceSettingsChange::DidUpdateFeatureFlag {
+      flag: "calendar".to_string(),
+      enabled: Some(false),
+    })
+  );
+}
+
+fn apply_updates(from: &UserAwareness, to: &mut UserAwareness) {
+  let sv = to.transact().state_vector();
+  let update = from.transact().encode_state_as_update_v1(&sv);
+  to.apply_update(Update::decode_v1(&update).unwrap())
+    .unwrap();
+}
@@ -0,0 +1,91 @@
+use std::time::Duration;
+
+use collab::core::origin::CollabOrigin;
+use collab::preclude::Collab;
+use collab_user::core::{
+  DevicePlatform, DeviceSession, DeviceSessionChange, DeviceSessionChangeReceiver, UserAwareness,
+  UserAwarenessNotifier,
+};
+
+use crate::util::receive_with_timeout;
+
+#[tokio::test]
+async fn device_session_join_and_leave_test() {
+  let (mut desktop, _) = create_user_awareness();
+  let (mut ipad, mut ipad_rx) = create_user_awareness();
+  let desktop_session = DeviceSession::new(
+    1,
+    "desktop".to_string(),
+    DevicePlatform::MacOS,
+    "0.7.0".to_string(),
+    100,
+  );
+  desktop.set_device_session(desktop_session.clone());
+  ipad.set_device_session(DeviceSession::new(
+    1,
+    "ipad".to_string(),
+    DevicePlatform::IOS,
+    "0.7.0".to_string(),
+    50,
+  ));
+  assert_eq!(desktop.get_device_session(), Some(desktop_session.clone()));
+
+  sync_awareness(&desktop, &ipad);
+  match receive_with_timeout(&mut ipad_rx, Duration::from_secs(2))
+    .await
+    .unwrap()
+  {
+    DeviceSessionChange::DidJoin { session, .. } => assert_eq!(session, desktop_session),
+    change => panic!("unexpected change: {:?}", change),
+  }
+  let device_ids = ipad
+    .get_device_sessions()
+    .into_iter()
+    .map(|session| session.device_id)
+    .collect::<Vec<_>>();
+  assert_eq!(device_ids, vec!["desktop", "ipad"]);
+  assert_eq!(ipad.get_other_device_sessions(), vec![desktop_session]);
+
+  desktop.mark_device_active(200);
+  sync_awareness(&desktop, &ipad);
+  match receive_with_timeout(&mut ipad_rx, Duration::from_secs(2))
+    .await
+    .unwrap()
+  {
+    DeviceSessionChange::DidUpdate { session, .. } => assert_eq!(session.last_active, 200),
+    change => panic!("unexpected change: {:?}", change),
+  }
+
+  desktop.clean_device_session();
+  sync_awareness(&desktop, &ipad);
+  match receive_with_timeout(&mut ipad_rx, Duration::from_secs(2))
+    .await
+    .unwrap()
+  {
+    DeviceSessionChange::DidLeave { session, .. } => {
+      assert_eq!(session.unwrap().device_id, "desktop")
+    },
+    change => panic!("unexpected change: {:?}", change),
+  }
+  assert!(ipad.get_other_device_sessions().is_empty());
+}
+
+fn create_user_awareness() -> (UserAwareness, DeviceSessionChangeReceiver) {
+  let (device_session_change_tx, rx) = tokio::sync::broadcast::channel(100);
+  let notifier = UserAwarenessNotifier {
+    reminder_change_tx: tokio::sync::broadcast::channel(100).0,
+    appearance_change_tx: tokio::sync::broadcast::channel(100).0,
+    device_session_change_tx,
+  };
+  let collab = Collab::new_with_origin(CollabOrigin::Empty, "1", vec![], false);
+  (UserAwareness::create(collab, Some(notifier)).unwrap(), rx)
+}
+
+/// Sends the local state of `from` to `to`, including a removed state.
+fn sync_awareness(from: &UserAwareness, to: &UserAwareness) {
+  let awareness = from.get_awareness();
+  let update = awareness
+    .update_with_clients([awareness.client_id()])
+    .unwrap();
+  to.get_awareness().apply_update(update).unwrap();
+}
@@ -1,2 +1,5 @@
+mod appearance_test;
+mod device_session_test;
+mod notification_test;
 mod reminder_test;
 mod util;
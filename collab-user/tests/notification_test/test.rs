@@ -0,0 +1,49 @@
+use collab_user::core::{NotificationEventType, QuietHours};
+
+use crate::util::UserAwarenessTest;
+
+#[tokio::test]
+async fn notification_settings_default_test() {
+  let test = UserAwarenessTest::new(1);
+  let settings = test.get_notification_settings();
+  assert!(settings.muted_workspaces.is_empty());
+  assert!(settings.quiet_hours.is_none());
+  assert!(settings.should_notify("w1", NotificationEventType::Mention, 0));
+}
+
+#[tokio::test]
+async fn update_notification_settings_test() {
+  let mut test = UserAwarenessTest::new(1);
+  test.update_notification_settings(|update| {
+    update
+      .mute_workspace("w1")
+      .mute_workspace("w2")
+      .set_event_enabled(NotificationEventType::PageUpdate, false)
+      .set_event_enabled(NotificationEventType::Mention, true)
+      .set_quiet_hours(Some(QuietHours::new(22 * 60, 7 * 60, 0)))
+      .done();
+  });
+  let settings = test.get_notification_settings();
+  assert_eq!(settings.muted_workspaces, vec!["w1", "w2"]);
+  assert!(!settings.is_event_enabled(NotificationEventType::PageUpdate));
+  assert!(settings.is_event_enabled(NotificationEventType::Mention));
+  assert!(settings.is_event_enabled(NotificationEventType::Comment));
+  assert_eq!(
+    settings.quiet_hours,
+    Some(QuietHours::new(22 * 60, 7 * 60, 0))
+  );
+
+  let noon = 12 * 3600;
+  let midnight = 24 * 3600;
+  assert!(!settings.should_notify("w1", NotificationEventType::Mention, noon));
+  assert!(!settings.should_notify("w3", NotificationEventType::PageUpdate, noon));
+  assert!(!settings.should_notify("w3", NotificationEventType::Mention, midnight));
+  assert!(settings.should_notify("w3", NotificationEventType::Mention, noon));
+
+  test.update_notification_settings(|update| {
+    update.unmute_workspace("w1").set_quiet_hours(None).done();
+  });
+  let settings = test.get_notification_settings();
+  assert_eq!(settings.muted_workspaces, vec!["w2"]);
+  assert!(settings.should_notify("w1", NotificationEventType::Mention, midnight));
+}
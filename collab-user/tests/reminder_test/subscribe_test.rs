@@ -1,6 +1,10 @@
+use collab::core::collab::DataSource;
+use collab::core::origin::CollabOrigin;
 use collab::lock::Mutex;
+use collab::preclude::updates::decoder::Decode;
+use collab::preclude::{Collab, ReadTxn, Update};
 use collab_entity::reminder::{ObjectType, Reminder};
-use collab_user::core::ReminderChange;
+use collab_user::core::{ReminderChange, UserAwareness, UserAwarenessNotifier};
 use std::sync::Arc;
 
 use crate::util::{receive_with_timeout, UserAwarenessTest};
@@ -59,3 +63,54 @@ async fn subscribe_delete_reminder_test() {
     }
   }
 }
+
+#[tokio::test]
+async fn subscribe_update_reminder_from_remote_test() {
+  let mut local = UserAwarenessTest::new(1);
+  local.add_reminder(Reminder::new(
+    "1".to_string(),
+    "o1".to_string(),
+    123,
+    ObjectType::Document,
+  ));
+  // Open the same user awareness on another device
+  let (reminder_change_tx, mut rx) = tokio::sync::broadcast::channel(100);
+  let doc_state = local
+    .encode_collab_v1(|_| Ok::<_, anyhow::Error>(()))
+    .unwrap();
+  let collab = Collab::new_with_source(
+    CollabOrigin::Empty,
+    "1",
+    DataSource::DocStateV1(doc_state.doc_state.to_vec()),
+    vec![],
+    false,
+  )
+  .unwrap();
+  let notifier = UserAwarenessNotifier {
+    reminder_change_tx,
+    appearance_change_tx: tokio::sync::broadcast::channel(100).0,
+    device_session_change_tx: tokio::sync::broadcast::channel(100).0,
+  };
+  let mut remote = UserAwareness::open(collab, Some(notifier)).unwrap();
+  assert!(remote.get_reminder("1").is_some());
+
+  local.update_reminder("1", |reminder| reminder.title = "title".to_string());
+  apply_updates(&local.user_awareness, &mut remote);
+  assert_eq!(remote.get_reminder("1").unwrap().title, "title");
+  loop {
+    let change = receive_with_timeout(&mut rx, std::time::Duration::from_secs(2))
+      .await
+      .unwrap();
+    if let ReminderChange::DidUpdateReminder { reminder } = change {
+      assert_eq!(reminder.title, "title");
+      break;
+    }
+  }
+}
+
+fn apply_updates(from: &UserAwareness, to: &mut UserAwareness) {
+  let sv = to.transact().state_vector();
+  let update = from.transact().encode_state_as_update_v1(&sv);
+  to.apply_update(Update::decode_v1(&update).unwrap())
+    .unwrap();
+}
@@ -1,4 +1,4 @@
-use collab_entity::reminder::{ObjectType, Reminder};
+use collab_entity::reminder::{ObjectType, Reminder, RepeatFrequency, RepeatRule};
 
 use crate::util::UserAwarenessTest;
 use assert_json_diff::assert_json_eq;
@@ -121,3 +121,65 @@ fn delete_reminder_test() {
     })
   )
 }
+
+#[test]
+fn reminder_block_id_and_repeat_test() {
+  let mut test = UserAwarenessTest::new(1);
+  let reminder = Reminder::new("1".to_string(), "o1".to_string(), 100, ObjectType::Document)
+    .with_block_id("b1".to_string())
+    .with_repeat(RepeatRule::new(RepeatFrequency::Daily));
+  test.add_reminder(reminder.clone());
+  assert_eq!(test.get_reminder("1"), Some(reminder));
+
+  let reminder = test
+    .update_reminder("1", |reminder| {
+      reminder.block_id = None;
+      reminder.repeat = Some(RepeatRule::new(RepeatFrequency::Weekly));
+    })
+    .unwrap();
+  assert_eq!(test.get_reminder("1"), Some(reminder));
+  assert!(test.update_reminder("2", |_| {}).is_none());
+  assert!(!test.remove_reminder("2"));
+  assert!(test.remove_reminder("1"));
+  assert!(test.get_reminder("1").is_none());
+}
+
+#[test]
+fn ack_reminder_test() {
+  let mut test = UserAwarenessTest::new(1);
+  test.add_reminder(Reminder::new(
+    "once".to_string(),
+    "o1".to_string(),
+    100,
+    ObjectType::Document,
+  ));
+  test.add_reminder(
+    Reminder::new(
+      "daily".to_string(),
+      "o1".to_string(),
+      100,
+      ObjectType::Document,
+    )
+    .with_repeat(RepeatRule::new(RepeatFrequency::Daily)),
+  );
+  test.add_reminder(Reminder::new(
+    "later".to_string(),
+    "o1".to_string(),
+    1000,
+    ObjectType::Document,
+  ));
+
+  let due = test
+    .get_due_reminders(200)
+    .into_iter()
+    .map(|reminder| reminder.id)
+    .collect::<Vec<_>>();
+  assert_eq!(due, vec!["once".to_string(), "daily".to_string()]);
+
+  assert!(test.ack_reminder("once", 200).unwrap().is_ack);
+  // The repeating reminder is moved to its next occurrence after now
+  let daily = test.ack_reminder("daily", 86400 * 2).unwrap();
+  assert!(!daily.is_ack);
+  assert_eq!(daily.scheduled_at, 100 + 86400 * 2);
+  assert!(test.get_due_reminders(200).is_empty());
+}
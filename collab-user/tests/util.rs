@@ -8,7 +8,9 @@ use collab::preclude::CollabBuilder;
 use collab_entity::CollabType;
 use collab_plugins::local_storage::rocksdb::rocksdb_plugin::RocksdbDiskPlugin;
 use collab_plugins::CollabKVDB;
-use collab_user::core::{RemindersChangeSender, UserAwareness, UserAwarenessNotifier};
+use collab_user::core::{
+  AppearanceSettingsChangeSender, RemindersChangeSender, UserAwareness, UserAwarenessNotifier,
+};
 use tempfile::TempDir;
 use tokio::sync::broadcast::Receiver;
 use tokio::time::timeout;
@@ -18,6 +20,8 @@ pub struct UserAwarenessTest {
   pub user_awareness: UserAwareness,
   #[allow(dead_code)]
   pub reminder_change_tx: RemindersChangeSender,
+  #[allow(dead_code)]
+  pub appearance_change_tx: AppearanceSettingsChangeSender,
 }
 
 impl Deref for UserAwarenessTest {
@@ -58,13 +62,17 @@ impl UserAwarenessTest {
     collab.initialize();
 
     let (reminder_change_tx, _) = tokio::sync::broadcast::channel(100);
+    let (appearance_change_tx, _) = tokio::sync::broadcast::channel(100);
     let notifier = UserAwarenessNotifier {
       reminder_change_tx: reminder_change_tx.clone(),
+      appearance_change_tx: appearance_change_tx.clone(),
+      device_session_change_tx: tokio::sync::broadcast::channel(100).0,
     };
     let user_awareness = UserAwareness::create(collab, Some(notifier)).unwrap();
     Self {
       user_awareness,
       reminder_change_tx,
+      appearance_change_tx,
     }
   }
 }
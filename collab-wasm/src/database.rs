@@ -0,0 +1,199 @@
+use std::sync::Arc;
+
+use collab::lock::RwLock;
+use collab_database::database::{gen_row_id, Database, DatabaseContext};
+use collab_database::rows::{Cells, CreateRowParams, Row, RowId};
+use js_sys::Promise;
+use wasm_bindgen::prelude::*;
+
+use crate::error::WasmError;
+use crate::store::{CollabStore, StoreCollabService};
+use crate::util::to_promise;
+
+/// A database opened by the web client.
+///
+/// The database and its rows are loaded from the [CollabStore] it was opened with, and written
+/// back to it by `save`. Fields, views, rows and cells are exchanged as JSON strings, using the
+/// same shapes as the [Field](collab_database::fields::Field),
+/// [DatabaseView](collab_database::entity::DatabaseView), [Row](collab_database::rows::Row) and
+/// [Cells] types.
+///
+/// All the methods return a promise, since the rows are loaded lazily.
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct WasmDatabase {
+  database_id: String,
+  database: Arc<RwLock<Database>>,
+}
+
+impl WasmDatabase {
+  pub async fn open(store: &CollabStore, database_id: &str) -> Result<Self, WasmError> {
+    if !store.contains(database_id) {
+      return Err(WasmError::NotFound(database_id.to_string()));
+    }
+    let context = DatabaseContext::new(Arc::new(StoreCollabService::new(store.clone())));
+    let database = Database::open(database_id, context).await?;
+    Ok(Self {
+      database_id: database_id.to_string(),
+      database: Arc::new(RwLock::new(database)),
+    })
+  }
+
+  pub async fn get_fields(&self) -> Result<String, WasmError> {
+    let fields = self.database.read().await.get_all_fields();
+    Ok(serde_json::to_string(&fields)?)
+  }
+
+  pub async fn get_views(&self) -> Result<String, WasmError> {
+    let views = self.database.read().await.get_all_views();
+    Ok(serde_json::to_string(&views)?)
+  }
+
+  /// Returns the rows of the view, in the order of the view.
+  pub async fn get_rows(&self, view_id: &str) -> Result<String, WasmError> {
+    let database = self.database.read().await;
+    let mut rows = vec![];
+    for row_order in database.get_row_orders_for_view(view_id) {
+      rows.extend(load_row(&database, &row_order.id).await);
+    }
+    Ok(serde_json::to_string(&rows)?)
+  }
+
+  pub async fn get_row(&self, row_id: &str) -> Result<Option<String>, WasmError> {
+    let database = self.database.read().await;
+    load_row(&database, &RowId::from(row_id.to_string()))
+      .await
+      .map(|row| serde_json::to_string(&row))
+      .transpose()
+      .map_err(Into::into)
+  }
+
+  /// Creates a row with the cells, given as a JSON object keyed by field id, at the end of all
+  /// the views. Returns the id of the row.
+  pub async fn create_row(&self, cells: &str) -> Result<String, WasmError> {
+    let cells = serde_json::from_str::<Cells>(cells)?;
+    let row_id = gen_row_id();
+    let params = CreateRowParams::new(row_id.clone(), self.database_id.clone()).with_cells(cells);
+    self.database.write().await.create_row(params).await?;
+    Ok(row_id.to_string())
+  }
+
+  /// Replaces the given cells of the row. The cells of the other fields are left untouched.
+  pub async fn update_cells(&self, row_id: &str, cells: &str) -> Result<(), WasmError> {
+    let cells = serde_json::from_str::<Cells>(cells)?;
+    let row_id = RowId::from(row_id.to_string());
+    let mut database = self.database.write().await;
+    if load_row(&database, &row_id).await.is_none() {
+      return Err(WasmError::NotFound(row_id.to_string()));
+    }
+    database
+      .update_row(row_id, |update| {
+        update.update_cells(|update| {
+          cells.into_iter().fold(update, |update, (field_id, cell)| {
+            update.insert_cell(&field_id, cell)
+          });
+        });
+      })
+      .await;
+    Ok(())
+  }
+
+  /// Removes the row from the database. Returns false if the database has no such row.
+  pub async fn remove_row(&self, row_id: &str) -> bool {
+    let row_id = RowId::from(row_id.to_string());
+    let mut database = self.database.write().await;
+    if load_row(&database, &row_id).await.is_none() {
+      return false;
+    }
+    database.remove_row(&row_id).await.is_some()
+  }
+
+  /// Writes the database and its rows to the store it was opened with.
+  pub async fn save(&self) -> Result<(), WasmError> {
+    let database = self.database.read().await;
+    let encoded = database.encode_database_collabs().await?;
+    if let Some(persistence) = database.collab_service.persistence() {
+      let collabs = std::iter::once(encoded.encoded_database_collab)
+        .chain(encoded.encoded_row_collabs)
+        .map(|collab| (collab.object_id, collab.encoded_collab))
+        .collect();
+      persistence.flush_collabs(collabs)?;
+    }
+    Ok(())
+  }
+}
+
+/// Returns the row, loading it from the store if it's not loaded yet.
+async fn load_row(database: &Database, row_id: &RowId) -> Option<Row> {
+  let database_row = database.get_or_init_database_row(row_id).await?;
+  let read_guard = database_row.read().await;
+  read_guard.get_row()
+}
+
+#[wasm_bindgen]
+impl WasmDatabase {
+  /// Opens the database from the collabs in the store.
+  #[wasm_bindgen(js_name = open)]
+  pub fn open_js(store: &CollabStore, database_id: String) -> Promise {
+    let store = store.clone();
+    to_promise(async move { WasmDatabase::open(&store, &database_id).await })
+  }
+
+  #[wasm_bindgen(getter, js_name = databaseId)]
+  pub fn database_id(&self) -> String {
+    self.database_id.clone()
+  }
+
+  #[wasm_bindgen(js_name = getFields)]
+  pub fn get_fields_js(&self) -> Promise {
+    let this = self.clone();
+    to_promise(async move { this.get_fields().await })
+  }
+
+  #[wasm_bindgen(js_name = getViews)]
+  pub fn get_views_js(&self) -> Promise {
+    let this = self.clone();
+    to_promise(async move { this.get_views().await })
+  }
+
+  #[wasm_bindgen(js_name = getRows)]
+  pub fn get_rows_js(&self, view_id: String) -> Promise {
+    let this = self.clone();
+    to_promise(async move { this.get_rows(&view_id).await })
+  }
+
+  #[wasm_bindgen(js_name = getRow)]
+  pub fn get_row_js(&self, row_id: String) -> Promise {
+    let this = self.clone();
+    to_promise(async move { this.get_row(&row_id).await })
+  }
+
+  #[wasm_bindgen(js_name = createRow)]
+  pub fn create_row_js(&self, cells: String) -> Promise {
+    let this = self.clone();
+    to_promise(async move { this.create_row(&cells).await })
+  }
+
+  #[wasm_bindgen(js_name = updateCells)]
+  pub fn update_cells_js(&self, row_id: String, cells: String) -> Promise {
+    let this = self.clone();
+    to_promise(async move {
+      this
+        .update_cells(&row_id, &cells)
+        .await
+        .map(|_| JsValue::UNDEFINED)
+    })
+  }
+
+  #[wasm_bindgen(js_name = removeRow)]
+  pub fn remove_row_js(&self, row_id: String) -> Promise {
+    let this = self.clone();
+    to_promise(async move { Ok(this.remove_row(&row_id).await) })
+  }
+
+  #[wasm_bindgen(js_name = save)]
+  pub fn save_js(&self) -> Promise {
+    let this = self.clone();
+    to_promise(async move { this.save().await.map(|_| JsValue::UNDEFINED) })
+  }
+}
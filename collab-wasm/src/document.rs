@@ -0,0 +1,159 @@
+use collab::core::collab::DataSource;
+use collab::core::origin::CollabOrigin;
+use collab::preclude::updates::decoder::Decode;
+use collab::preclude::updates::encoder::Encode;
+use collab::preclude::{Collab, ReadTxn, StateVector, Update};
+use collab_document::blocks::BlockAction;
+use collab_document::document::Document;
+use collab_document::document_data::default_document_data;
+use wasm_bindgen::prelude::*;
+
+use crate::error::WasmError;
+use crate::store::CollabStore;
+
+/// A document opened by the web client.
+///
+/// Blocks, document data and actions are exchanged as JSON strings, using the same shapes as the
+/// [Block], [DocumentData](collab_document::blocks::DocumentData) and [BlockAction] types.
+///
+/// [Block]: collab_document::blocks::Block
+#[wasm_bindgen]
+pub struct WasmDocument {
+  document: Document,
+}
+
+#[wasm_bindgen]
+impl WasmDocument {
+  /// Creates a new document with an empty paragraph.
+  pub fn create(document_id: &str) -> Result<WasmDocument, WasmError> {
+    let document = Document::create(document_id, default_document_data(document_id))?;
+    Ok(Self { document })
+  }
+
+  /// Opens the document from its doc state, encoded as a yrs v1 update.
+  pub fn open(document_id: &str, doc_state: Vec<u8>) -> Result<WasmDocument, WasmError> {
+    let collab = Collab::new_with_source(
+      CollabOrigin::Empty,
+      document_id,
+      DataSource::DocStateV1(doc_state),
+      vec![],
+      false,
+    )?;
+    let document = Document::open(collab)?;
+    Ok(Self { document })
+  }
+
+  /// Opens the document from the collab in the store.
+  #[wasm_bindgen(js_name = openFromStore)]
+  pub fn open_from_store(
+    store: &CollabStore,
+    document_id: &str,
+  ) -> Result<WasmDocument, WasmError> {
+    let encoded_collab = store
+      .get_encoded(document_id)
+      .ok_or_else(|| WasmError::NotFound(document_id.to_string()))?;
+    let collab = Collab::new_with_source(
+      CollabOrigin::Empty,
+      document_id,
+      encoded_collab.into(),
+      vec![],
+      false,
+    )?;
+    let document = Document::open(collab)?;
+    Ok(Self { document })
+  }
+
+  #[wasm_bindgen(getter, js_name = documentId)]
+  pub fn document_id(&self) -> String {
+    self.document.object_id().to_string()
+  }
+
+  #[wasm_bindgen(js_name = pageId)]
+  pub fn page_id(&self) -> Option<String> {
+    self.document.get_page_id()
+  }
+
+  /// Returns the blocks, children and texts of the document as JSON.
+  #[wasm_bindgen(js_name = getDocumentData)]
+  pub fn get_document_data(&self) -> Result<String, WasmError> {
+    let data = self.document.get_document_data()?;
+    Ok(serde_json::to_string(&data)?)
+  }
+
+  /// Returns the block as JSON, or undefined if the document has no such block.
+  #[wasm_bindgen(js_name = getBlock)]
+  pub fn get_block(&self, block_id: &str) -> Result<Option<String>, WasmError> {
+    self
+      .document
+      .get_block(block_id)
+      .map(|block| serde_json::to_string(&block))
+      .transpose()
+      .map_err(Into::into)
+  }
+
+  #[wasm_bindgen(js_name = getBlockChildrenIds)]
+  pub fn get_block_children_ids(&self, block_id: &str) -> Vec<String> {
+    self.document.get_block_children_ids(block_id)
+  }
+
+  /// Applies a JSON array of block actions, in order.
+  #[wasm_bindgen(js_name = applyActions)]
+  pub fn apply_actions(&mut self, actions: &str) -> Result<(), WasmError> {
+    let actions = serde_json::from_str::<Vec<BlockAction>>(actions)?;
+    self.document.apply_action(actions)?;
+    Ok(())
+  }
+
+  /// Applies a text delta, in the quill delta JSON format, to the text of a block.
+  #[wasm_bindgen(js_name = applyTextDelta)]
+  pub fn apply_text_delta(&mut self, text_id: &str, delta: String) {
+    self.document.apply_text_delta(text_id, delta);
+  }
+
+  #[wasm_bindgen(js_name = toPlainText)]
+  pub fn to_plain_text(&self) -> Result<String, WasmError> {
+    Ok(self.document.to_plain_text()?)
+  }
+
+  /// Returns the doc state of the document, encoded as a yrs v1 update.
+  #[wasm_bindgen(js_name = encodeDocState)]
+  pub fn encode_doc_state(&self) -> Result<Vec<u8>, WasmError> {
+    Ok(self.document.encode_collab()?.doc_state.to_vec())
+  }
+
+  /// Returns the state vector of the document, encoded with yrs v1.
+  #[wasm_bindgen(js_name = stateVector)]
+  pub fn state_vector(&self) -> Vec<u8> {
+    self.document.transact().state_vector().encode_v1()
+  }
+
+  /// Returns the changes that are missing from the given state vector, encoded as a yrs v1
+  /// update. Used to send the local changes to a peer.
+  #[wasm_bindgen(js_name = encodeUpdateSince)]
+  pub fn encode_update_since(&self, state_vector: &[u8]) -> Result<Vec<u8>, WasmError> {
+    let state_vector = StateVector::decode_v1(state_vector)
+      .map_err(|err| WasmError::InvalidUpdate(err.to_string()))?;
+    Ok(
+      self
+        .document
+        .transact()
+        .encode_state_as_update_v1(&state_vector),
+    )
+  }
+
+  /// Applies an update received from a peer, encoded with yrs v1.
+  #[wasm_bindgen(js_name = applyUpdate)]
+  pub fn apply_update(&mut self, update: &[u8]) -> Result<(), WasmError> {
+    let update =
+      Update::decode_v1(update).map_err(|err| WasmError::InvalidUpdate(err.to_string()))?;
+    self.document.apply_update(update)?;
+    Ok(())
+  }
+
+  /// Writes the doc state of the document to the store.
+  #[wasm_bindgen(js_name = saveToStore)]
+  pub fn save_to_store(&self, store: &CollabStore) -> Result<(), WasmError> {
+    store.insert_encoded(self.document_id(), self.document.encode_collab()?);
+    Ok(())
+  }
+}
@@ -0,0 +1,34 @@
+use collab::error::CollabError;
+use collab_database::error::DatabaseError;
+use collab_document::error::DocumentError;
+use wasm_bindgen::{JsError, JsValue};
+
+#[derive(Debug, thiserror::Error)]
+pub enum WasmError {
+  #[error(transparent)]
+  Collab(#[from] CollabError),
+
+  #[error(transparent)]
+  Document(#[from] DocumentError),
+
+  #[error(transparent)]
+  Database(#[from] DatabaseError),
+
+  #[error(transparent)]
+  Json(#[from] serde_json::Error),
+
+  #[error("{0} is not found")]
+  NotFound(String),
+
+  #[error("Invalid update: {0}")]
+  InvalidUpdate(String),
+
+  #[error("Internal failure: {0}")]
+  Internal(#[from] anyhow::Error),
+}
+
+impl From<WasmError> for JsValue {
+  fn from(err: WasmError) -> Self {
+    JsError::new(&err.to_string()).into()
+  }
+}
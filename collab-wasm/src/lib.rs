@@ -0,0 +1,15 @@
+//! The JavaScript bindings of the document and database collabs, used by AppFlowy Web.
+//!
+//! The structured values are exchanged as JSON strings and the collab data as `Uint8Array`s of
+//! yrs v1 updates, so the web client never has to know about the yrs types.
+
+mod database;
+mod document;
+mod error;
+mod store;
+mod util;
+
+pub use database::*;
+pub use document::*;
+pub use error::*;
+pub use store::*;
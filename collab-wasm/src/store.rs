@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use collab::core::collab::DataSource;
+use collab::core::origin::CollabOrigin;
+use collab::entity::EncodedCollab;
+use collab::preclude::Collab;
+use collab_database::error::DatabaseError;
+use collab_database::workspace_database::{
+  CollabPersistenceImpl, DatabaseCollabPersistenceService, DatabaseCollabService, EncodeCollabByOid,
+};
+use collab_entity::CollabType;
+use wasm_bindgen::prelude::*;
+
+use crate::util::apply_encoded_collab;
+
+/// The collabs loaded by the web client, keyed by object id.
+///
+/// The store is shared by the documents and databases opened from it. The client fills it with
+/// the doc states fetched from the server, or from IndexedDB with `loadFromIndexeddb`, and the
+/// databases write their collabs back to it on `save`.
+#[wasm_bindgen]
+#[derive(Clone, Default)]
+pub struct CollabStore {
+  collabs: Arc<RwLock<HashMap<String, EncodedCollab>>>,
+}
+
+#[wasm_bindgen]
+impl CollabStore {
+  #[wasm_bindgen(constructor)]
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Inserts the doc state of the collab, encoded as a yrs v1 update.
+  pub fn insert(&self, object_id: String, doc_state: Vec<u8>) {
+    self.insert_encoded(object_id, EncodedCollab::new_v1(Vec::new(), doc_state));
+  }
+
+  /// Returns the doc state of the collab, encoded as a yrs v1 update.
+  pub fn get(&self, object_id: &str) -> Option<Vec<u8>> {
+    self
+      .get_encoded(object_id)
+      .map(|encoded| encoded.doc_state.to_vec())
+  }
+
+  pub fn remove(&self, object_id: &str) -> bool {
+    self.collabs.write().unwrap().remove(object_id).is_some()
+  }
+
+  pub fn contains(&self, object_id: &str) -> bool {
+    self.collabs.read().unwrap().contains_key(object_id)
+  }
+
+  #[wasm_bindgen(js_name = objectIds)]
+  pub fn object_ids(&self) -> Vec<String> {
+    self.collabs.read().unwrap().keys().cloned().collect()
+  }
+}
+
+impl CollabStore {
+  pub fn insert_encoded(&self, object_id: impl Into<String>, encoded_collab: EncodedCollab) {
+    self
+      .collabs
+      .write()
+      .unwrap()
+      .insert(object_id.into(), encoded_collab);
+  }
+
+  pub fn get_encoded(&self, object_id: &str) -> Option<EncodedCollab> {
+    self.collabs.read().unwrap().get(object_id).cloned()
+  }
+}
+
+impl DatabaseCollabPersistenceService for CollabStore {
+  fn load_collab(&self, collab: &mut Collab) {
+    if let Some(encoded_collab) = self.get_encoded(collab.object_id()) {
+      let _ = apply_encoded_collab(collab, &encoded_collab);
+    }
+  }
+
+  fn get_encoded_collab(&self, object_id: &str, _collab_type: CollabType) -> Option<EncodedCollab> {
+    self.get_encoded(object_id)
+  }
+
+  fn delete_collab(&self, object_id: &str) -> Result<(), DatabaseError> {
+    self.remove(object_id);
+    Ok(())
+  }
+
+  fn save_collab(
+    &self,
+    object_id: &str,
+    encoded_collab: EncodedCollab,
+  ) -> Result<(), DatabaseError> {
+    self.insert_encoded(object_id, encoded_collab);
+    Ok(())
+  }
+
+  fn is_collab_exist(&self, object_id: &str) -> bool {
+    self.contains(object_id)
+  }
+
+  fn flush_collabs(
+    &self,
+    encoded_collabs: Vec<(String, EncodedCollab)>,
+  ) -> Result<(), DatabaseError> {
+    let mut collabs = self.collabs.write().unwrap();
+    collabs.extend(encoded_collabs);
+    Ok(())
+  }
+}
+
+/// Builds the collabs of a database from a [CollabStore].
+pub(crate) struct StoreCollabService {
+  store: CollabStore,
+}
+
+impl StoreCollabService {
+  pub(crate) fn new(store: CollabStore) -> Self {
+    Self { store }
+  }
+}
+
+#[async_trait]
+impl DatabaseCollabService for StoreCollabService {
+  async fn build_collab(
+    &self,
+    object_id: &str,
+    _object_type: CollabType,
+    encoded_collab: Option<(EncodedCollab, bool)>,
+  ) -> Result<Collab, DatabaseError> {
+    let data_source = match encoded_collab {
+      None => CollabPersistenceImpl {
+        persistence: self.persistence(),
+      }
+      .into(),
+      Some((encoded_collab, _)) => DataSource::from(encoded_collab),
+    };
+    Collab::new_with_source(CollabOrigin::Empty, object_id, data_source, vec![], false)
+      .map_err(|err| DatabaseError::Internal(err.into()))
+  }
+
+  async fn get_collabs(
+    &self,
+    object_ids: Vec<String>,
+    _collab_type: CollabType,
+  ) -> Result<EncodeCollabByOid, DatabaseError> {
+    Ok(
+      object_ids
+        .into_iter()
+        .filter_map(|object_id| {
+          let encoded_collab = self.store.get_encoded(&object_id)?;
+          Some((object_id, encoded_collab))
+        })
+        .collect(),
+    )
+  }
+
+  fn persistence(&self) -> Option<Arc<dyn DatabaseCollabPersistenceService>> {
+    Some(Arc::new(self.store.clone()))
+  }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod indexeddb {
+  use collab_plugins::local_storage::indexeddb::CollabIndexeddb;
+  use js_sys::{Array, Promise};
+  use wasm_bindgen::prelude::*;
+
+  use super::CollabStore;
+  use crate::error::WasmError;
+  use crate::util::to_promise;
+
+  #[wasm_bindgen]
+  impl CollabStore {
+    /// Loads the given collabs of the user from IndexedDB. The collabs that were never saved are
+    /// skipped. Returns the ids of the loaded collabs.
+    #[wasm_bindgen(js_name = loadFromIndexeddb)]
+    pub fn load_from_indexeddb(&self, uid: i64, object_ids: Vec<String>) -> Promise {
+      let store = self.clone();
+      to_promise(async move {
+        let db = open_indexeddb().await?;
+        let mut loaded_ids = vec![];
+        for object_id in object_ids {
+          if !db.is_exist(uid, &object_id).await.unwrap_or(false) {
+            continue;
+          }
+          let encoded_collab = db
+            .get_encoded_collab(uid, &object_id)
+            .await
+            .map_err(|err| WasmError::Internal(err.into()))?;
+          store.insert_encoded(object_id.clone(), encoded_collab);
+          loaded_ids.push(object_id);
+        }
+        Ok(loaded_ids.into_iter().map(JsValue::from).collect::<Array>())
+      })
+    }
+
+    /// Writes all the collabs of the store to IndexedDB, replacing the saved doc states.
+    #[wasm_bindgen(js_name = saveToIndexeddb)]
+    pub fn save_to_indexeddb(&self, uid: i64) -> Promise {
+      let collabs = self.collabs.read().unwrap().clone();
+      to_promise(async move {
+        let db = open_indexeddb().await?;
+        for (object_id, encoded_collab) in collabs {
+          let result = if db.is_exist(uid, &object_id).await.unwrap_or(false) {
+            db.flush_doc(uid, &object_id, &encoded_collab).await
+          } else {
+            db.create_doc(uid, &object_id, &encoded_collab).await
+          };
+          result.map_err(|err| WasmError::Internal(err.into()))?;
+        }
+        Ok(())
+      })
+    }
+  }
+
+  async fn open_indexeddb() -> Result<CollabIndexeddb, WasmError> {
+    CollabIndexeddb::new()
+      .await
+      .map_err(|err| WasmError::Internal(err.into()))
+  }
+}
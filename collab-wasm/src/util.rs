@@ -0,0 +1,37 @@
+use std::future::Future;
+
+use collab::entity::{EncodedCollab, EncoderVersion};
+use collab::error::CollabError;
+use collab::preclude::updates::decoder::Decode;
+use collab::preclude::{Collab, Update};
+use js_sys::Promise;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_futures::future_to_promise;
+
+use crate::error::WasmError;
+
+pub(crate) fn apply_encoded_collab(
+  collab: &mut Collab,
+  encoded_collab: &EncodedCollab,
+) -> Result<(), CollabError> {
+  if encoded_collab.doc_state.is_empty() {
+    return Ok(());
+  }
+  let update = match encoded_collab.version {
+    EncoderVersion::V1 => Update::decode_v1(&encoded_collab.doc_state)?,
+    EncoderVersion::V2 => Update::decode_v2(&encoded_collab.doc_state)?,
+  };
+  collab.apply_update(update)
+}
+
+/// Runs the future on the JavaScript event loop and resolves the promise with its output.
+///
+/// The exported methods return a promise instead of being `async`, since an async export can't
+/// borrow `self`. The method clones the handle it needs and moves it into the future.
+pub(crate) fn to_promise<F, T>(future: F) -> Promise
+where
+  F: Future<Output = Result<T, WasmError>> + 'static,
+  T: Into<JsValue>,
+{
+  future_to_promise(async move { future.await.map(Into::into).map_err(Into::into) })
+}
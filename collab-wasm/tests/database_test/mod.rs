@@ -0,0 +1,102 @@
+use std::sync::Arc;
+
+use collab_database::database::{gen_database_id, Database, DatabaseContext};
+use collab_database::entity::{CreateDatabaseParams, CreateViewParams, FieldType};
+use collab_database::fields::Field;
+use collab_database::rows::{new_cell_builder, Cells, CreateRowParams};
+use collab_database::template::entity::CELL_DATA;
+use collab_database::workspace_database::NoPersistenceDatabaseCollabService;
+use collab_wasm::{CollabStore, WasmDatabase, WasmError};
+use serde_json::Value;
+
+fn text_cells(field_id: &str, text: &str) -> Cells {
+  let mut cell = new_cell_builder(FieldType::RichText);
+  cell.insert(CELL_DATA.to_string(), text.into());
+  Cells::from([(field_id.to_string(), cell)])
+}
+
+/// Creates a database with one text field and one row, and puts its collabs in a store.
+async fn database_store(database_id: &str) -> CollabStore {
+  let params = CreateDatabaseParams {
+    database_id: database_id.to_string(),
+    views: vec![CreateViewParams {
+      database_id: database_id.to_string(),
+      view_id: "v1".to_string(),
+      ..Default::default()
+    }],
+    rows: vec![
+      CreateRowParams::new("r1".to_string(), database_id.to_string())
+        .with_cells(text_cells("f1", "first row")),
+    ],
+    fields: vec![Field::new(
+      "f1".to_string(),
+      "Name".to_string(),
+      FieldType::RichText.into(),
+      true,
+    )],
+  };
+  let context = DatabaseContext::new(Arc::new(NoPersistenceDatabaseCollabService));
+  let database = Database::create_with_view(params, context).await.unwrap();
+  let encoded = database.encode_database_collabs().await.unwrap();
+
+  let store = CollabStore::new();
+  for collab in std::iter::once(encoded.encoded_database_collab).chain(encoded.encoded_row_collabs)
+  {
+    store.insert_encoded(collab.object_id, collab.encoded_collab);
+  }
+  store
+}
+
+fn row_text(row: &Value) -> &str {
+  row["cells"]["f1"][CELL_DATA].as_str().unwrap()
+}
+
+#[tokio::test]
+async fn read_database_from_store_test() {
+  let database_id = gen_database_id();
+  let store = database_store(&database_id).await;
+  let database = WasmDatabase::open(&store, &database_id).await.unwrap();
+  assert_eq!(database.database_id(), database_id);
+
+  let fields = serde_json::from_str::<Value>(&database.get_fields().await.unwrap()).unwrap();
+  assert_eq!(fields[0]["name"], "Name");
+  let views = serde_json::from_str::<Value>(&database.get_views().await.unwrap()).unwrap();
+  assert_eq!(views[0]["id"], "v1");
+
+  let rows = serde_json::from_str::<Value>(&database.get_rows("v1").await.unwrap()).unwrap();
+  assert_eq!(rows.as_array().unwrap().len(), 1);
+  assert_eq!(row_text(&rows[0]), "first row");
+  assert!(database.get_row("r2").await.unwrap().is_none());
+
+  let result = WasmDatabase::open(&store, &gen_database_id()).await;
+  assert!(matches!(result, Err(WasmError::NotFound(_))));
+}
+
+#[tokio::test]
+async fn mutate_and_save_database_test() {
+  let database_id = gen_database_id();
+  let store = database_store(&database_id).await;
+  let database = WasmDatabase::open(&store, &database_id).await.unwrap();
+
+  let cells = serde_json::to_string(&text_cells("f1", "second row")).unwrap();
+  let row_id = database.create_row(&cells).await.unwrap();
+  let cells = serde_json::to_string(&text_cells("f1", "renamed row")).unwrap();
+  database.update_cells("r1", &cells).await.unwrap();
+  database.save().await.unwrap();
+  assert!(store.contains(&row_id));
+
+  let reopened = WasmDatabase::open(&store, &database_id).await.unwrap();
+  let rows = serde_json::from_str::<Value>(&reopened.get_rows("v1").await.unwrap()).unwrap();
+  let texts = rows
+    .as_array()
+    .unwrap()
+    .iter()
+    .map(row_text)
+    .collect::<Vec<_>>();
+  assert_eq!(texts, vec!["renamed row", "second row"]);
+
+  assert!(reopened.remove_row(&row_id).await);
+  assert!(!reopened.remove_row(&row_id).await);
+  let rows = serde_json::from_str::<Value>(&reopened.get_rows("v1").await.unwrap()).unwrap();
+  assert_eq!(rows.as_array().unwrap().len(), 1);
+}
@@ -0,0 +1,86 @@
+use collab_document::blocks::DocumentData;
+use collab_wasm::{CollabStore, WasmDocument, WasmError};
+use serde_json::json;
+
+fn insert_paragraph_actions(document: &WasmDocument, text: &str) -> String {
+  let page_id = document.page_id().unwrap();
+  json!([
+    {
+      "action": "InsertText",
+      "payload": { "text_id": "text_1", "delta": json!([{ "insert": text }]).to_string() },
+    },
+    {
+      "action": "Insert",
+      "payload": {
+        "block": {
+          "id": "block_1",
+          "ty": "paragraph",
+          "parent": page_id,
+          "children": "children_1",
+          "external_id": "text_1",
+          "external_type": "text",
+          "data": {},
+        },
+        "parent_id": page_id,
+      },
+    },
+  ])
+  .to_string()
+}
+
+#[tokio::test]
+async fn apply_actions_from_json_test() {
+  let mut document = WasmDocument::create("d1").unwrap();
+  let actions = insert_paragraph_actions(&document, "Hello web");
+  document.apply_actions(&actions).unwrap();
+
+  let block =
+    serde_json::from_str::<serde_json::Value>(&document.get_block("block_1").unwrap().unwrap())
+      .unwrap();
+  assert_eq!(block["ty"], "paragraph");
+  assert!(document.to_plain_text().unwrap().contains("Hello web"));
+
+  let page_id = document.page_id().unwrap();
+  assert!(document
+    .get_block_children_ids(&page_id)
+    .contains(&"block_1".to_string()));
+}
+
+#[tokio::test]
+async fn reopen_document_from_store_test() {
+  let mut document = WasmDocument::create("d1").unwrap();
+  let actions = insert_paragraph_actions(&document, "Saved text");
+  document.apply_actions(&actions).unwrap();
+
+  let store = CollabStore::new();
+  document.save_to_store(&store).unwrap();
+  assert_eq!(store.object_ids(), vec!["d1".to_string()]);
+
+  let reopened = WasmDocument::open_from_store(&store, "d1").unwrap();
+  assert_eq!(reopened.document_id(), "d1");
+  let data = |document: &WasmDocument| {
+    serde_json::from_str::<DocumentData>(&document.get_document_data().unwrap()).unwrap()
+  };
+  assert_eq!(data(&reopened), data(&document));
+
+  let reopened = WasmDocument::open("d1", store.get("d1").unwrap()).unwrap();
+  assert!(reopened.to_plain_text().unwrap().contains("Saved text"));
+
+  let result = WasmDocument::open_from_store(&store, "d2");
+  assert!(matches!(result, Err(WasmError::NotFound(_))));
+}
+
+#[tokio::test]
+async fn sync_documents_with_updates_test() {
+  let mut local = WasmDocument::create("d1").unwrap();
+  let mut remote = WasmDocument::open("d1", local.encode_doc_state().unwrap()).unwrap();
+
+  let actions = insert_paragraph_actions(&local, "From local");
+  local.apply_actions(&actions).unwrap();
+  let update = local.encode_update_since(&remote.state_vector()).unwrap();
+  remote.apply_update(&update).unwrap();
+  assert!(remote.to_plain_text().unwrap().contains("From local"));
+
+  let result = remote.apply_update(&[1, 2, 3]);
+  assert!(matches!(result, Err(WasmError::InvalidUpdate(_))));
+}
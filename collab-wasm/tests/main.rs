@@ -0,0 +1,2 @@
+mod database_test;
+mod document_test;
@@ -0,0 +1,112 @@
+//! Compares opening a collab from an encoded collab file by reading and copying the doc state
+//! with opening it from the memory-mapped file, see [Collab::new_with_encoded_file].
+//!
+//! Run with `cargo bench -p collab --bench encoded_collab_decode`. The size of the doc state in
+//! MB can be passed as the first argument.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use collab::core::collab::DataSource;
+use collab::core::origin::CollabOrigin;
+use collab::entity::EncodedCollab;
+use collab::preclude::Collab;
+use yrs::{Map, Text, TextPrelim};
+
+/// Keeps track of the peak of the allocated bytes.
+struct PeakAlloc;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for PeakAlloc {
+  unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    let ptr = System.alloc(layout);
+    if !ptr.is_null() {
+      let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+      PEAK.fetch_max(allocated, Ordering::Relaxed);
+    }
+    ptr
+  }
+
+  unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+    System.dealloc(ptr, layout);
+    ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+  }
+}
+
+#[global_allocator]
+static GLOBAL: PeakAlloc = PeakAlloc;
+
+/// Run the function and return its duration and the peak of the bytes it allocated.
+fn measure<F: FnOnce() -> Collab>(f: F) -> (Duration, usize) {
+  let baseline = ALLOCATED.load(Ordering::Relaxed);
+  PEAK.store(baseline, Ordering::Relaxed);
+  let start = Instant::now();
+  let collab = f();
+  let elapsed = start.elapsed();
+  let peak = PEAK.load(Ordering::Relaxed) - baseline;
+  drop(collab);
+  (elapsed, peak)
+}
+
+fn encoded_collab_file(size_mb: usize) -> tempfile::NamedTempFile {
+  let mut collab = Collab::new_with_origin(CollabOrigin::Empty, "bench", vec![], false);
+  {
+    let mut txn = collab.context.transact_mut();
+    let text = collab.data.insert(&mut txn, "text", TextPrelim::new(""));
+    // One chunk per insert, so the doc state holds many blocks like a real document.
+    let chunk = "x".repeat(1024);
+    for _ in 0..size_mb * 1024 {
+      text.push(&mut txn, &chunk);
+    }
+  }
+  let encoded = collab
+    .encode_collab_v1(|_| Ok::<_, collab::error::CollabError>(()))
+    .unwrap()
+    .encode_to_bytes()
+    .unwrap();
+  let file = tempfile::NamedTempFile::new().unwrap();
+  std::fs::write(file.path(), encoded).unwrap();
+  file
+}
+
+fn main() {
+  let size_mb = std::env::args()
+    .skip(1)
+    .find_map(|arg| arg.parse::<usize>().ok())
+    .unwrap_or(16);
+  let file = encoded_collab_file(size_mb);
+  let file_len = std::fs::metadata(file.path()).unwrap().len() as usize;
+
+  let (read_elapsed, read_peak) = measure(|| {
+    let bytes = std::fs::read(file.path()).unwrap();
+    let encoded_collab = EncodedCollab::decode_from_bytes(&bytes).unwrap();
+    Collab::new_with_source(
+      CollabOrigin::Empty,
+      "bench",
+      DataSource::from(encoded_collab),
+      vec![],
+      false,
+    )
+    .unwrap()
+  });
+  // Safety: the file is only written before it's mapped.
+  let (mapped_elapsed, mapped_peak) = measure(|| unsafe {
+    Collab::new_with_encoded_file(CollabOrigin::Empty, "bench", file.path(), vec![], false).unwrap()
+  });
+
+  let mb = |bytes: usize| bytes as f64 / (1024.0 * 1024.0);
+  println!("encoded collab file: {:.1}MB", mb(file_len));
+  println!(
+    "read + decode_from_bytes: {:?}, peak allocated {:.1}MB",
+    read_elapsed,
+    mb(read_peak)
+  );
+  println!(
+    "new_with_encoded_file:    {:?}, peak allocated {:.1}MB",
+    mapped_elapsed,
+    mb(mapped_peak)
+  );
+}
@@ -0,0 +1,128 @@
+use std::borrow::BorrowMut;
+use std::future::Future;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use yrs::block::ClientID;
+use yrs::sync::{Awareness, Timestamp};
+
+use crate::core::clock::{system_clock, ClockProvider};
+use crate::core::collab::Collab;
+use crate::lock::RwLock;
+
+/// When the awareness states of the peers expire. A peer that disconnects without removing its
+/// state would otherwise be shown as present until its state is replaced.
+#[derive(Debug, Clone)]
+pub struct AwarenessConfig {
+  /// A remote state that was not updated for this long is removed, as if the peer had left. The
+  /// local state is renewed after half of it, so the other peers don't remove it.
+  pub timeout: Duration,
+  /// How often the states are checked by [awareness_cleanup].
+  pub check_interval: Duration,
+  /// The clock the states are checked with. It must agree with the clock that stamps the
+  /// awareness states, the time of the system.
+  pub clock: Arc<dyn ClockProvider>,
+}
+
+impl AwarenessConfig {
+  /// Check the states ten times per timeout.
+  pub fn new(timeout: Duration) -> Self {
+    Self {
+      timeout,
+      check_interval: timeout / 10,
+      clock: system_clock(),
+    }
+  }
+
+  /// Set the clock the states are checked with.
+  pub fn with_clock(mut self, clock: Arc<dyn ClockProvider>) -> Self {
+    self.clock = clock;
+    self
+  }
+}
+
+impl Default for AwarenessConfig {
+  fn default() -> Self {
+    Self::new(Duration::from_secs(30))
+  }
+}
+
+/// The current time of the clock, in the milliseconds of the [Awareness] clock.
+pub fn awareness_now(clock: &dyn ClockProvider) -> Timestamp {
+  clock.timestamp_millis() as Timestamp
+}
+
+/// Remove the remote states that were not updated for the timeout at `now`, in milliseconds, and
+/// renew the local state if it's older than half of it. Each removed state is reported in the
+/// removed clients of the awareness events, like a peer that left. Return the removed clients.
+pub fn remove_outdated_states(
+  awareness: &Awareness,
+  config: &AwarenessConfig,
+  now: Timestamp,
+) -> Vec<ClientID> {
+  let timeout = config.timeout.as_millis() as Timestamp;
+  let local_client_id = awareness.client_id();
+  let mut outdated = vec![];
+  let mut renew_local_state = None;
+  for (client_id, state) in awareness.iter() {
+    let data = match state.data {
+      Some(data) => data,
+      None => continue,
+    };
+    let elapsed = now.saturating_sub(state.last_updated);
+    if client_id == local_client_id {
+      if elapsed >= timeout / 2 {
+        renew_local_state = Some(data);
+      }
+    } else if elapsed >= timeout {
+      outdated.push(client_id);
+    }
+  }
+
+  if let Some(data) = renew_local_state {
+    awareness.set_local_state_raw(data);
+  }
+  for client_id in &outdated {
+    awareness.remove_state(*client_id);
+  }
+  outdated
+}
+
+impl Collab {
+  /// See [remove_outdated_states].
+  pub fn remove_outdated_awareness_states(&self, config: &AwarenessConfig) -> Vec<ClientID> {
+    remove_outdated_states(
+      self.get_awareness(),
+      config,
+      awareness_now(config.clock.as_ref()),
+    )
+  }
+}
+
+/// Remove the outdated awareness states of the collab every [AwarenessConfig::check_interval],
+/// until the collab is dropped. The caller runs the returned future on its runtime and provides
+/// the timer: `sleep` returns a future that completes after the given duration, like
+/// `tokio::time::sleep`.
+pub async fn awareness_cleanup<T, S, F>(collab: Weak<RwLock<T>>, config: AwarenessConfig, sleep: S)
+where
+  T: BorrowMut<Collab> + Send + Sync + ?Sized + 'static,
+  S: Fn(Duration) -> F,
+  F: Future<Output = ()>,
+{
+  let check_interval = config.check_interval.max(Duration::from_millis(1));
+  loop {
+    sleep(check_interval).await;
+    let collab = match collab.upgrade() {
+      None => break,
+      Some(collab) => collab,
+    };
+    let removed = collab
+      .read()
+      .await
+      .borrow()
+      .remove_outdated_awareness_states(&config);
+    if !removed.is_empty() {
+      tracing::trace!("remove the outdated awareness states: {:?}", removed);
+    }
+  }
+}
@@ -0,0 +1,53 @@
+use std::cell::RefCell;
+
+use yrs::block::ClientID;
+
+thread_local! {
+  static DETERMINISTIC_CLIENT_IDS: RefCell<Option<ClientID>> = const { RefCell::new(None) };
+}
+
+/// While the returned guard is alive, the yrs documents created on the current thread get the
+/// client ids `first`, `first + 1`, ... instead of random ones.
+///
+/// The order of the concurrent edits merged by yrs depends on the client ids, so a test that
+/// creates its collabs in the same order gets byte-identical merged states across runs. The
+/// `#[tokio::test]` runtime runs on a single thread, so the collabs created by the spawned tasks
+/// get deterministic ids too. Dropping the guard restores the previous mode.
+///
+/// ```
+/// use collab::core::client_id::deterministic_client_ids;
+/// use collab::core::origin::CollabOrigin;
+/// use collab::preclude::Collab;
+///
+/// let _guard = deterministic_client_ids(1);
+/// let collab = Collab::new_with_origin(CollabOrigin::Empty, "1", vec![], false);
+/// assert_eq!(collab.client_id(), 1);
+/// ```
+#[must_use = "the client ids are random again once the guard is dropped"]
+pub fn deterministic_client_ids(first: ClientID) -> DeterministicClientIdsGuard {
+  let previous = DETERMINISTIC_CLIENT_IDS.with(|next| next.replace(Some(first)));
+  DeterministicClientIdsGuard { previous }
+}
+
+/// Restores the previous client id mode of the thread when dropped, see
+/// [deterministic_client_ids].
+#[derive(Debug)]
+pub struct DeterministicClientIdsGuard {
+  previous: Option<ClientID>,
+}
+
+impl Drop for DeterministicClientIdsGuard {
+  fn drop(&mut self) {
+    DETERMINISTIC_CLIENT_IDS.with(|next| *next.borrow_mut() = self.previous);
+  }
+}
+
+/// Return the next deterministic client id of the thread, or None if the client ids are random.
+pub(crate) fn next_client_id() -> Option<ClientID> {
+  DETERMINISTIC_CLIENT_IDS.with(|next| {
+    let mut next = next.borrow_mut();
+    let client_id = (*next)?;
+    *next = Some(client_id + 1);
+    Some(client_id)
+  })
+}
@@ -0,0 +1,67 @@
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, OnceLock};
+
+/// Provides the current time to the collabs that stamp their data, for example the created and
+/// modified times of the rows of a database.
+///
+/// The applications use the [SystemClock]. The tests can use a [ManualClock] to get deterministic
+/// timestamps, and a server can provide a trusted time instead of the time of the client.
+pub trait ClockProvider: Debug + Send + Sync + 'static {
+  /// Return the number of seconds since the unix epoch.
+  fn timestamp(&self) -> i64;
+
+  /// Return the number of milliseconds since the unix epoch.
+  fn timestamp_millis(&self) -> i64 {
+    self.timestamp() * 1000
+  }
+}
+
+/// The clock of the system.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl ClockProvider for SystemClock {
+  fn timestamp(&self) -> i64 {
+    chrono::Utc::now().timestamp()
+  }
+
+  fn timestamp_millis(&self) -> i64 {
+    chrono::Utc::now().timestamp_millis()
+  }
+}
+
+/// Return the shared instance of the [SystemClock].
+pub fn system_clock() -> Arc<dyn ClockProvider> {
+  static SYSTEM_CLOCK: OnceLock<Arc<dyn ClockProvider>> = OnceLock::new();
+  SYSTEM_CLOCK.get_or_init(|| Arc::new(SystemClock)).clone()
+}
+
+/// A clock that only moves when it's told to.
+#[derive(Debug, Default)]
+pub struct ManualClock {
+  timestamp: AtomicI64,
+}
+
+impl ManualClock {
+  pub fn new(timestamp: i64) -> Self {
+    Self {
+      timestamp: AtomicI64::new(timestamp),
+    }
+  }
+
+  pub fn set(&self, timestamp: i64) {
+    self.timestamp.store(timestamp, Ordering::SeqCst);
+  }
+
+  /// Moves the clock forward by the given number of seconds.
+  pub fn advance(&self, seconds: i64) {
+    self.timestamp.fetch_add(seconds, Ordering::SeqCst);
+  }
+}
+
+impl ClockProvider for ManualClock {
+  fn timestamp(&self) -> i64 {
+    self.timestamp.load(Ordering::SeqCst)
+  }
+}
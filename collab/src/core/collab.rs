@@ -22,12 +22,14 @@ use yrs::{
 };
 
 use crate::core::awareness::Awareness;
+use crate::core::client_id::next_client_id;
 use crate::core::collab_plugin::{CollabPersistence, CollabPlugin, CollabPluginType, Plugins};
 use crate::core::collab_state::{InitState, SnapshotState, State, SyncState};
+use crate::core::mapped_file::MappedFile;
 use crate::core::origin::{CollabClient, CollabOrigin};
 use crate::core::transaction::DocTransactionExtension;
 
-use crate::entity::{EncodedCollab, EncoderVersion};
+use crate::entity::{EncodedCollab, EncodedCollabRef, EncoderVersion};
 use crate::error::CollabError;
 use crate::preclude::JsonValue;
 
@@ -226,11 +228,21 @@ impl CollabContext {
 }
 
 pub fn make_yrs_doc(skp_gc: bool) -> Doc {
-  Doc::with_options(Options {
+  make_yrs_doc_with_client_id(skp_gc, None)
+}
+
+/// Creates a yrs document with the given client id. Without a client id, the document gets the
+/// next id of [deterministic_client_ids](crate::core::client_id::deterministic_client_ids) if it's enabled, or a random one.
+pub fn make_yrs_doc_with_client_id(skp_gc: bool, client_id: Option<ClientID>) -> Doc {
+  let mut options = Options {
     skip_gc: skp_gc,
     offset_kind: OffsetKind::Utf16,
     ..Options::default()
-  })
+  };
+  if let Some(client_id) = client_id.or_else(next_client_id) {
+    options.client_id = client_id;
+  }
+  Doc::with_options(options)
 }
 
 impl Collab {
@@ -253,29 +265,78 @@ impl Collab {
     skip_gc: bool,
   ) -> Result<Self, CollabError> {
     let mut collab = Self::new_with_origin(origin, object_id, plugins, skip_gc);
+    collab.load_data_source(data_source)?;
+    Ok(collab)
+  }
+
+  fn load_data_source(&mut self, data_source: DataSource) -> Result<(), CollabError> {
     match data_source {
       DataSource::Disk(disk) => {
         if let Some(disk) = disk {
-          disk.load_collab_from_disk(&mut collab)?;
+          disk.load_collab_from_disk(self)?;
         }
       },
       DataSource::DocStateV1(doc_state) => {
-        if !doc_state.is_empty() {
-          let update = Update::decode_v1(&doc_state)?;
-          collab.context.apply_update(update)?;
-        }
+        self.apply_doc_state(&doc_state, EncoderVersion::V1)?;
       },
       DataSource::DocStateV2(doc_state) => {
-        if !doc_state.is_empty() {
-          let update = Update::decode_v2(&doc_state)?;
-          collab.context.apply_update(update)?;
-        }
+        self.apply_doc_state(&doc_state, EncoderVersion::V2)?;
       },
     }
+    Ok(())
+  }
 
+  /// Creates the collab from a borrowed [EncodedCollabRef], for example one decoded from a
+  /// memory-mapped snapshot file. The update is decoded directly from the borrowed doc state,
+  /// without copying it into the `Vec` of a [DataSource] first.
+  pub fn new_with_encoded_ref(
+    origin: CollabOrigin,
+    object_id: &str,
+    encoded_collab: EncodedCollabRef,
+    plugins: Vec<Box<dyn CollabPlugin>>,
+    skip_gc: bool,
+  ) -> Result<Self, CollabError> {
+    let mut collab = Self::new_with_origin(origin, object_id, plugins, skip_gc);
+    collab.apply_doc_state(encoded_collab.doc_state, encoded_collab.version)?;
     Ok(collab)
   }
 
+  /// Creates the collab from a file written with [EncodedCollab::encode_to_bytes], like a
+  /// snapshot file. The file is memory-mapped and the update is decoded from the mapped bytes,
+  /// see [MappedFile], so a large doc state is never held in memory twice.
+  ///
+  /// # Safety
+  ///
+  /// The file must not be modified or truncated while the collab is created, see
+  /// [MappedFile::map].
+  pub unsafe fn new_with_encoded_file(
+    origin: CollabOrigin,
+    object_id: &str,
+    path: impl AsRef<std::path::Path>,
+    plugins: Vec<Box<dyn CollabPlugin>>,
+    skip_gc: bool,
+  ) -> Result<Self, CollabError> {
+    let file = MappedFile::open(path).map_err(|err| CollabError::Internal(err.into()))?;
+    let encoded_collab = EncodedCollabRef::decode_from_bytes(&file)
+      .map_err(|err| CollabError::Internal(err.into()))?;
+    Self::new_with_encoded_ref(origin, object_id, encoded_collab, plugins, skip_gc)
+  }
+
+  fn apply_doc_state(
+    &mut self,
+    doc_state: &[u8],
+    version: EncoderVersion,
+  ) -> Result<(), CollabError> {
+    if doc_state.is_empty() {
+      return Ok(());
+    }
+    let update = match version {
+      EncoderVersion::V1 => Update::decode_v1(doc_state)?,
+      EncoderVersion::V2 => Update::decode_v2(doc_state)?,
+    };
+    self.context.apply_update(update)
+  }
+
   /// Each collab can have only one cloud plugin
   pub fn has_cloud_plugin(&self) -> bool {
     self.plugins.has_cloud_plugin()
@@ -299,9 +360,21 @@ impl Collab {
     object_id: T,
     plugins: Vec<Box<dyn CollabPlugin>>,
     skip_gc: bool,
+  ) -> Collab {
+    Self::new_with_client_id(origin, object_id, None, plugins, skip_gc)
+  }
+
+  /// Creates the collab with the given yrs client id. Two collabs editing the same object must not
+  /// share a client id, the ids are only injected to get reproducible merged states in the tests.
+  pub fn new_with_client_id<T: AsRef<str>>(
+    origin: CollabOrigin,
+    object_id: T,
+    client_id: Option<ClientID>,
+    plugins: Vec<Box<dyn CollabPlugin>>,
+    skip_gc: bool,
   ) -> Collab {
     let object_id = object_id.as_ref().to_string();
-    let doc = make_yrs_doc(skip_gc);
+    let doc = make_yrs_doc_with_client_id(skip_gc, client_id);
     let data = doc.get_or_insert_map(DATA_SECTION);
     let meta = doc.get_or_insert_map(META_SECTION);
     let plugins = Plugins::new(plugins);
@@ -489,6 +562,46 @@ impl Collab {
     self.context.undo_manager = Some(undo_manager);
   }
 
+  /// Apply the changes in a transaction, unless the update they produce exceeds
+  /// `max_update_size` bytes. The update of the transaction is measured once it's committed, and
+  /// an oversized change, like a giant paste, is rolled back with an [UndoManager] and rejected
+  /// with [CollabError::UpdateTooLarge]. The plugins receive the oversized update followed by
+  /// the update that reverts it. The changes receive the data section of the document.
+  pub fn transact_mut_with_max_update_size<F, T>(
+    &mut self,
+    max_update_size: usize,
+    f: F,
+  ) -> Result<T, CollabError>
+  where
+    F: FnOnce(&mut TransactionMut, &MapRef) -> T,
+  {
+    // Only the changes of this transaction are captured, so the rollback doesn't revert the
+    // previous changes.
+    let mut undo_manager = UndoManager::with_scope_and_options(
+      self.context.doc(),
+      &self.data,
+      yrs::undo::Options {
+        capture_timeout_millis: 0,
+        ..Default::default()
+      },
+    );
+    undo_manager.include_origin(self.origin().clone());
+
+    let data = self.data.clone();
+    let (value, size) = self.context.with_txn(|txn| {
+      let value = f(txn, &data);
+      (value, txn.encode_update_v1().len())
+    })?;
+    if size > max_update_size {
+      undo_manager.undo_blocking();
+      return Err(CollabError::UpdateTooLarge {
+        size,
+        max_size: max_update_size,
+      });
+    }
+    Ok(value)
+  }
+
   /// Returns the doc state and the state vector.
   pub fn encode_collab_v1<F, E>(&self, validate: F) -> Result<EncodedCollab, E>
   where
@@ -596,6 +709,7 @@ pub struct CollabBuilder {
   object_id: String,
   source: DataSource,
   skip_gc: bool,
+  client_id: Option<ClientID>,
 }
 
 /// The raw data of a collab document. It is a list of updates. Each of them can be parsed by
@@ -647,6 +761,7 @@ impl CollabBuilder {
       device_id: "".to_string(),
       source: data_source,
       skip_gc: true,
+      client_id: None,
     }
   }
 
@@ -671,15 +786,22 @@ impl CollabBuilder {
     self
   }
 
+  /// Set the yrs client id of the collab, see [Collab::new_with_client_id].
+  pub fn with_client_id(mut self, client_id: ClientID) -> Self {
+    self.client_id = Some(client_id);
+    self
+  }
+
   pub fn build(self) -> Result<Collab, CollabError> {
     let origin = CollabOrigin::Client(CollabClient::new(self.uid, self.device_id));
-    let collab = Collab::new_with_source(
+    let mut collab = Collab::new_with_client_id(
       origin,
       &self.object_id,
-      self.source,
+      self.client_id,
       self.plugins,
       self.skip_gc,
-    )?;
+    );
+    collab.load_data_source(self.source)?;
     Ok(collab)
   }
 }
@@ -0,0 +1,185 @@
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, RwLock};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum BacklinkType {
+  /// A block of a document mentions the target.
+  Mention,
+  /// A relation cell of a database row links to the target row.
+  Relation,
+  /// A todo block of a document was turned into the target row, and their checked states are
+  /// kept in sync.
+  Task,
+}
+
+/// The block or row that links to another object.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct LinkSource {
+  /// The id of the document or database.
+  pub object_id: String,
+  /// The id of the block or row within the object.
+  pub id: String,
+}
+
+impl LinkSource {
+  pub fn new(object_id: impl ToString, id: impl ToString) -> Self {
+    Self {
+      object_id: object_id.to_string(),
+      id: id.to_string(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Backlink {
+  pub source: LinkSource,
+  /// The id of the linked object, a view, a document or a row.
+  pub target_id: String,
+  pub link_type: BacklinkType,
+}
+
+#[derive(Default)]
+struct BacklinkTable {
+  /// The links of every source, keyed by the object id of the source.
+  links_by_object: HashMap<String, HashMap<LinkSource, BTreeSet<Backlink>>>,
+  /// The links to every target, keyed by the target id.
+  backlinks: HashMap<String, BTreeSet<Backlink>>,
+}
+
+impl BacklinkTable {
+  /// Removes the links of the source, only the ones of the given type if any.
+  fn remove_source(&mut self, source: &LinkSource, link_type: Option<BacklinkType>) {
+    let sources = match self.links_by_object.get_mut(&source.object_id) {
+      None => return,
+      Some(sources) => sources,
+    };
+    let removed_links = match (sources.get_mut(source), link_type) {
+      (None, _) => return,
+      (Some(links), Some(link_type)) => {
+        let (removed, kept) = std::mem::take(links)
+          .into_iter()
+          .partition::<BTreeSet<_>, _>(|link| link.link_type == link_type);
+        *links = kept;
+        removed
+      },
+      (Some(links), None) => std::mem::take(links),
+    };
+    if sources.get(source).map_or(false, |links| links.is_empty()) {
+      sources.remove(source);
+    }
+    if sources.is_empty() {
+      self.links_by_object.remove(&source.object_id);
+    }
+    for link in removed_links {
+      if let Some(backlinks) = self.backlinks.get_mut(&link.target_id) {
+        backlinks.remove(&link);
+        if backlinks.is_empty() {
+          self.backlinks.remove(&link.target_id);
+        }
+      }
+    }
+  }
+}
+
+/// Records which blocks and rows link to which objects, so the objects that link to a given one
+/// can be found without opening every collab.
+///
+/// The registry is filled by the documents and databases that subscribe to it, which keep their
+/// links up to date from their change events. The registry is cheap to clone, all the clones share
+/// the same links.
+#[derive(Clone, Default)]
+pub struct BacklinkRegistry {
+  table: Arc<RwLock<BacklinkTable>>,
+}
+
+impl BacklinkRegistry {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Replaces the links of the given type of the source. The links of the other types are kept.
+  pub fn set_links<T: ToString>(
+    &self,
+    source: LinkSource,
+    link_type: BacklinkType,
+    target_ids: impl IntoIterator<Item = T>,
+  ) {
+    let links = target_ids
+      .into_iter()
+      .map(|target_id| Backlink {
+        source: source.clone(),
+        target_id: target_id.to_string(),
+        link_type,
+      })
+      .collect::<BTreeSet<_>>();
+    let mut table = self.table.write().unwrap_or_else(|err| err.into_inner());
+    table.remove_source(&source, Some(link_type));
+    if links.is_empty() {
+      return;
+    }
+    for link in &links {
+      table
+        .backlinks
+        .entry(link.target_id.clone())
+        .or_default()
+        .insert(link.clone());
+    }
+    table
+      .links_by_object
+      .entry(source.object_id.clone())
+      .or_default()
+      .entry(source)
+      .or_default()
+      .extend(links);
+  }
+
+  pub fn remove_source(&self, source: &LinkSource) {
+    let mut table = self.table.write().unwrap_or_else(|err| err.into_inner());
+    table.remove_source(source, None);
+  }
+
+  /// Removes the links of all the blocks or rows of the object.
+  pub fn remove_object(&self, object_id: &str) {
+    self.remove_links_of_object(object_id, None);
+  }
+
+  /// Removes the links of the given type of all the blocks or rows of the object. The links of
+  /// the other types are kept.
+  pub fn remove_object_links(&self, object_id: &str, link_type: BacklinkType) {
+    self.remove_links_of_object(object_id, Some(link_type));
+  }
+
+  fn remove_links_of_object(&self, object_id: &str, link_type: Option<BacklinkType>) {
+    let mut table = self.table.write().unwrap_or_else(|err| err.into_inner());
+    let sources = table
+      .links_by_object
+      .get(object_id)
+      .map(|sources| sources.keys().cloned().collect::<Vec<_>>())
+      .unwrap_or_default();
+    for source in sources {
+      table.remove_source(&source, link_type);
+    }
+  }
+
+  /// Returns the links to the object, ordered by source.
+  pub fn backlinks_for(&self, object_id: &str) -> Vec<Backlink> {
+    let table = self.table.read().unwrap_or_else(|err| err.into_inner());
+    table
+      .backlinks
+      .get(object_id)
+      .map(|backlinks| backlinks.iter().cloned().collect())
+      .unwrap_or_default()
+  }
+
+  /// Returns the links of all the blocks or rows of the object.
+  pub fn links_from(&self, object_id: &str) -> Vec<Backlink> {
+    let table = self.table.read().unwrap_or_else(|err| err.into_inner());
+    let mut links = table
+      .links_by_object
+      .get(object_id)
+      .map(|sources| sources.values().flatten().cloned().collect::<Vec<_>>())
+      .unwrap_or_default();
+    links.sort();
+    links
+  }
+}
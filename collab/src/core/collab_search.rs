@@ -1 +1,207 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
 
+/// The kind of object an [IndexedContent] comes from.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum IndexContentType {
+  /// A text block of a document. The id of the content is the block id.
+  Document,
+  /// A row of a database. The id of the content is the row id.
+  DatabaseRow,
+}
+
+/// A piece of text of a collab that can be searched.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct IndexedContent {
+  /// The id of the document or database.
+  pub object_id: String,
+  /// The id of the block or row within the object.
+  pub id: String,
+  pub text: String,
+  pub content_type: IndexContentType,
+}
+
+impl IndexedContent {
+  pub fn new(
+    object_id: impl ToString,
+    id: impl ToString,
+    text: impl ToString,
+    content_type: IndexContentType,
+  ) -> Self {
+    Self {
+      object_id: object_id.to_string(),
+      id: id.to_string(),
+      text: text.to_string(),
+      content_type,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum IndexChange {
+  /// Insert the content, or replace the content with the same object id and id.
+  Upsert(IndexedContent),
+  Remove {
+    object_id: String,
+    id: String,
+  },
+  /// Remove all the contents of the object.
+  RemoveObject {
+    object_id: String,
+  },
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+  pub text: String,
+  pub content_type: Option<IndexContentType>,
+  pub limit: Option<usize>,
+}
+
+impl SearchQuery {
+  pub fn new(text: impl ToString) -> Self {
+    Self {
+      text: text.to_string(),
+      content_type: None,
+      limit: None,
+    }
+  }
+
+  pub fn with_content_type(mut self, content_type: IndexContentType) -> Self {
+    self.content_type = Some(content_type);
+    self
+  }
+
+  pub fn with_limit(mut self, limit: usize) -> Self {
+    self.limit = Some(limit);
+    self
+  }
+}
+
+struct IndexEntry {
+  content: IndexedContent,
+  lowercase_text: String,
+}
+
+/// A searchable index of the text of the documents and databases of a workspace.
+///
+/// The index is filled by the collabs that subscribe to it, which keep it up to date from their
+/// change events, so a search doesn't need to decode the collabs. It only lives in memory and is
+/// rebuilt when the collabs are opened. The index is cheap to clone, all the clones share the same
+/// contents.
+#[derive(Clone, Default)]
+pub struct WorkspaceSearchIndex {
+  objects: Arc<RwLock<HashMap<String, HashMap<String, IndexEntry>>>>,
+}
+
+impl WorkspaceSearchIndex {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn apply_changes(&self, changes: Vec<IndexChange>) {
+    if changes.is_empty() {
+      return;
+    }
+    let mut objects = self.objects.write().unwrap_or_else(|err| err.into_inner());
+    for change in changes {
+      match change {
+        IndexChange::Upsert(content) => {
+          let entry = IndexEntry {
+            lowercase_text: content.text.to_lowercase(),
+            content,
+          };
+          objects
+            .entry(entry.content.object_id.clone())
+            .or_default()
+            .insert(entry.content.id.clone(), entry);
+        },
+        IndexChange::Remove { object_id, id } => {
+          if let Some(entries) = objects.get_mut(&object_id) {
+            entries.remove(&id);
+            if entries.is_empty() {
+              objects.remove(&object_id);
+            }
+          }
+        },
+        IndexChange::RemoveObject { object_id } => {
+          objects.remove(&object_id);
+        },
+      }
+    }
+  }
+
+  /// Replaces all the contents of the object with the given ones.
+  pub fn index_object(&self, object_id: &str, contents: Vec<IndexedContent>) {
+    let mut changes = vec![IndexChange::RemoveObject {
+      object_id: object_id.to_string(),
+    }];
+    changes.extend(contents.into_iter().map(IndexChange::Upsert));
+    self.apply_changes(changes);
+  }
+
+  pub fn remove_object(&self, object_id: &str) {
+    self.apply_changes(vec![IndexChange::RemoveObject {
+      object_id: object_id.to_string(),
+    }]);
+  }
+
+  /// Returns the ids of the contents of the object.
+  pub fn content_ids(&self, object_id: &str) -> Vec<String> {
+    let objects = self.objects.read().unwrap_or_else(|err| err.into_inner());
+    objects
+      .get(object_id)
+      .map(|entries| entries.keys().cloned().collect())
+      .unwrap_or_default()
+  }
+
+  pub fn num_of_contents(&self) -> usize {
+    let objects = self.objects.read().unwrap_or_else(|err| err.into_inner());
+    objects.values().map(|entries| entries.len()).sum()
+  }
+
+  /// Returns the contents that contain every word of the query, ignoring the case. The contents
+  /// that contain the whole query come first, then the ones where a word appears earlier,
+  /// then the shorter ones.
+  pub fn search(&self, query: &SearchQuery) -> Vec<IndexedContent> {
+    let text = query.text.trim().to_lowercase();
+    let words = text.split_whitespace().collect::<Vec<_>>();
+    if words.is_empty() {
+      return vec![];
+    }
+
+    let objects = self.objects.read().unwrap_or_else(|err| err.into_inner());
+    let mut matches = objects
+      .values()
+      .flat_map(|entries| entries.values())
+      .filter(|entry| {
+        query.content_type.map_or(true, |content_type| {
+          entry.content.content_type == content_type
+        })
+      })
+      .flat_map(|entry| {
+        let mut first_position = usize::MAX;
+        for word in &words {
+          let position = entry.lowercase_text.find(word)?;
+          first_position = first_position.min(position);
+        }
+        let is_phrase = entry.lowercase_text.contains(&text);
+        Some((
+          (!is_phrase, first_position, entry.lowercase_text.len()),
+          entry,
+        ))
+      })
+      .collect::<Vec<_>>();
+    matches.sort_by(|(left, left_entry), (right, right_entry)| {
+      left.cmp(right).then_with(|| {
+        (&left_entry.content.object_id, &left_entry.content.id)
+          .cmp(&(&right_entry.content.object_id, &right_entry.content.id))
+      })
+    });
+    matches
+      .into_iter()
+      .take(query.limit.unwrap_or(usize::MAX))
+      .map(|(_, entry)| entry.content.clone())
+      .collect()
+  }
+}
@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+
+use crate::core::collab::Collab;
+use crate::error::CollabError;
+
+/// The edit of one collab within an [UndoGroup].
+#[derive(Debug, Clone)]
+struct GroupedEdit {
+  object_id: String,
+  /// The length of the undo stack of the collab right after the edit, or the length of its redo
+  /// stack once the group is undone. It's used to check that the stack item of the edit is still
+  /// at the top of the stack.
+  depth: usize,
+}
+
+/// The edits made across several collabs within one user action, for example, creating a page in
+/// the folder and inserting a link to it into the parent document.
+///
+/// Each edit becomes its own item in the undo manager of its collab, so the group can be undone
+/// without undoing the changes made before or after it.
+#[derive(Debug, Clone, Default)]
+pub struct UndoGroup {
+  edits: Vec<GroupedEdit>,
+}
+
+impl UndoGroup {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Runs `f` on the collab and adds the changes it made to the group. The undo manager of the
+  /// collab must be enabled. Nothing is added if `f` didn't change the collab.
+  pub fn edit<F, T>(&mut self, collab: &mut Collab, f: F) -> Result<T, CollabError>
+  where
+    F: FnOnce(&mut Collab) -> T,
+  {
+    let undo_manager = collab.undo_manager_mut()?;
+    // Stop merging the changes into the last stack item, so the edit gets its own item.
+    undo_manager.reset();
+    let depth = undo_manager.undo_stack().len();
+
+    let output = f(collab);
+
+    let undo_manager = collab.undo_manager_mut()?;
+    undo_manager.reset();
+    let new_depth = undo_manager.undo_stack().len();
+    if new_depth > depth {
+      self.edits.push(GroupedEdit {
+        object_id: collab.object_id().to_string(),
+        depth: new_depth,
+      });
+    }
+    Ok(output)
+  }
+
+  /// Returns the ids of the edited collabs, in the order they were edited.
+  pub fn object_ids(&self) -> Vec<&str> {
+    self
+      .edits
+      .iter()
+      .map(|edit| edit.object_id.as_str())
+      .collect()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.edits.is_empty()
+  }
+}
+
+/// Undoes and redoes the [UndoGroup]s of a workspace, built on the undo managers of the collabs.
+///
+/// All the undoable edits of the collabs must go through the groups. When the undo stack of a
+/// collab was changed outside of the groups, undoing or redoing the group that edited it fails
+/// with [CollabError::UndoGroupOutOfSync] and nothing is changed.
+#[derive(Debug, Default)]
+pub struct WorkspaceUndoManager {
+  undo_stack: Vec<UndoGroup>,
+  redo_stack: Vec<UndoGroup>,
+}
+
+impl WorkspaceUndoManager {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Adds the group to the undo stack and clears the redo stack. An empty group is ignored.
+  pub fn push(&mut self, group: UndoGroup) {
+    if group.is_empty() {
+      return;
+    }
+    self.undo_stack.push(group);
+    self.redo_stack.clear();
+  }
+
+  pub fn can_undo(&self) -> bool {
+    !self.undo_stack.is_empty()
+  }
+
+  pub fn can_redo(&self) -> bool {
+    !self.redo_stack.is_empty()
+  }
+
+  /// Returns the group that will be undone next.
+  pub fn last_group(&self) -> Option<&UndoGroup> {
+    self.undo_stack.last()
+  }
+
+  /// Undoes the edits of the last group, in the reverse order. `collabs` must contain all the
+  /// collabs edited by the group. Returns false if there is nothing to undo.
+  pub fn undo(&mut self, collabs: &mut [&mut Collab]) -> Result<bool, CollabError> {
+    let mut group = match self.undo_stack.pop() {
+      None => return Ok(false),
+      Some(group) => group,
+    };
+    let result = apply_group(&mut group, collabs, true);
+    match result {
+      Ok(()) => {
+        self.redo_stack.push(group);
+        Ok(true)
+      },
+      Err(err) => {
+        self.undo_stack.push(group);
+        Err(err)
+      },
+    }
+  }
+
+  /// Redoes the edits of the last undone group, in the original order. `collabs` must contain all
+  /// the collabs edited by the group. Returns false if there is nothing to redo.
+  pub fn redo(&mut self, collabs: &mut [&mut Collab]) -> Result<bool, CollabError> {
+    let mut group = match self.redo_stack.pop() {
+      None => return Ok(false),
+      Some(group) => group,
+    };
+    let result = apply_group(&mut group, collabs, false);
+    match result {
+      Ok(()) => {
+        self.undo_stack.push(group);
+        Ok(true)
+      },
+      Err(err) => {
+        self.redo_stack.push(group);
+        Err(err)
+      },
+    }
+  }
+
+  pub fn clear(&mut self) {
+    self.undo_stack.clear();
+    self.redo_stack.clear();
+  }
+}
+
+/// Undoes or redoes the edits of the group. All the collabs are checked before any of them is
+/// changed, the depth of each edit is then updated for the opposite operation.
+fn apply_group(
+  group: &mut UndoGroup,
+  collabs: &mut [&mut Collab],
+  is_undo: bool,
+) -> Result<(), CollabError> {
+  // A collab edited several times within the group has one stack item per edit. The item of the
+  // edit that is applied first is at the top of the stack, and it has the largest depth.
+  let mut expected_depths = HashMap::new();
+  for edit in &group.edits {
+    let depth = expected_depths.entry(edit.object_id.as_str()).or_insert(0);
+    *depth = edit.depth.max(*depth);
+  }
+  for (object_id, expected_depth) in expected_depths {
+    let collab = collabs
+      .iter()
+      .find(|collab| collab.object_id() == object_id)
+      .ok_or_else(|| CollabError::UndoGroupOutOfSync(object_id.to_string()))?;
+    let undo_manager = collab.undo_manager()?;
+    let depth = if is_undo {
+      undo_manager.undo_stack().len()
+    } else {
+      undo_manager.redo_stack().len()
+    };
+    if depth != expected_depth {
+      return Err(CollabError::UndoGroupOutOfSync(object_id.to_string()));
+    }
+  }
+
+  let edits: Box<dyn Iterator<Item = &mut GroupedEdit>> = if is_undo {
+    Box::new(group.edits.iter_mut().rev())
+  } else {
+    Box::new(group.edits.iter_mut())
+  };
+  for edit in edits {
+    let collab = collabs
+      .iter_mut()
+      .find(|collab| collab.object_id() == edit.object_id)
+      .ok_or_else(|| CollabError::UndoGroupOutOfSync(edit.object_id.clone()))?;
+    if is_undo {
+      collab.undo()?;
+      edit.depth = collab.undo_manager()?.redo_stack().len();
+    } else {
+      collab.redo()?;
+      edit.depth = collab.undo_manager()?.undo_stack().len();
+    }
+  }
+  Ok(())
+}
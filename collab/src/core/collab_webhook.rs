@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+/// The version of the schema of [WebhookPayload]. It's increased when a field of the payload is
+/// removed or changes its meaning, the new fields don't change the version.
+pub const WEBHOOK_SCHEMA_VERSION: u32 = 1;
+
+/// A change event that can be sent to a webhook. It's implemented by the typed change events of
+/// the collab crates, like the block events of a document or the journal entries of a database.
+pub trait WebhookEvent {
+  /// The type of the event, like `database.row_created`. The receivers dispatch on it.
+  fn event_type(&self) -> String;
+
+  /// The content of the event. Its schema depends on the event type.
+  fn data(&self) -> serde_json::Value;
+}
+
+/// The body of a webhook request. The payloads are delivered by the `EventEmitter` of
+/// collab-plugins.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookPayload {
+  pub schema_version: u32,
+  /// The position of the payload among the payloads of the emitter. The payloads are delivered
+  /// in this order, so a receiver can drop the duplicates of a retried delivery.
+  pub sequence: u64,
+  pub event_type: String,
+  /// The id of the document or database the event comes from.
+  pub object_id: String,
+  /// The time, in seconds, when the event was emitted.
+  pub timestamp: i64,
+  pub data: serde_json::Value,
+}
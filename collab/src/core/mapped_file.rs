@@ -0,0 +1,76 @@
+use std::fs::File;
+use std::io;
+use std::ops::Deref;
+use std::path::Path;
+
+/// The read-only content of a file, like a snapshot file written with
+/// [EncodedCollab::encode_to_bytes](crate::entity::EncodedCollab::encode_to_bytes).
+///
+/// On unix the file is memory-mapped, so its pages are loaded on demand and shared with the page
+/// cache instead of being copied into a `Vec` first. On the other targets the file is read into
+/// memory.
+pub struct MappedFile {
+  #[cfg(unix)]
+  mmap: memmap2::Mmap,
+  #[cfg(not(unix))]
+  data: Vec<u8>,
+}
+
+impl MappedFile {
+  /// Open and map the file at the given path, see [Self::map].
+  ///
+  /// # Safety
+  ///
+  /// Same as [Self::map].
+  pub unsafe fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+    let file = File::open(path)?;
+    Self::map(&file)
+  }
+
+  /// Map the content of the file.
+  ///
+  /// # Safety
+  ///
+  /// The file must not be modified or truncated, by this process or another one, while the
+  /// returned value is alive. Otherwise the content of the returned slice may change under it, and
+  /// reading the truncated pages raises a SIGBUS.
+  #[cfg(unix)]
+  pub unsafe fn map(file: &File) -> io::Result<Self> {
+    let mmap = memmap2::Mmap::map(file)?;
+    Ok(Self { mmap })
+  }
+
+  /// Read the content of the file.
+  ///
+  /// # Safety
+  ///
+  /// Always safe on this target, it's `unsafe` to match the memory-mapped version.
+  #[cfg(not(unix))]
+  pub unsafe fn map(mut file: &File) -> io::Result<Self> {
+    use std::io::Read;
+
+    let mut data = vec![];
+    file.read_to_end(&mut data)?;
+    Ok(Self { data })
+  }
+}
+
+impl Deref for MappedFile {
+  type Target = [u8];
+
+  #[cfg(unix)]
+  fn deref(&self) -> &[u8] {
+    &self.mmap
+  }
+
+  #[cfg(not(unix))]
+  fn deref(&self) -> &[u8] {
+    &self.data
+  }
+}
+
+impl AsRef<[u8]> for MappedFile {
+  fn as_ref(&self) -> &[u8] {
+    self
+  }
+}
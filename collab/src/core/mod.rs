@@ -1,9 +1,16 @@
 pub use yrs::sync::awareness;
+pub mod awareness_expiry;
+pub mod client_id;
+pub mod clock;
 pub mod collab;
+pub mod collab_backlink;
 pub mod collab_plugin;
-mod collab_search;
+pub mod collab_search;
 pub mod collab_state;
+pub mod collab_undo;
+pub mod collab_webhook;
 pub mod fill;
+pub mod mapped_file;
 pub mod origin;
 pub mod transaction;
 pub mod value;
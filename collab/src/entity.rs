@@ -39,6 +39,17 @@ impl EncodedCollab {
     bincode::serialize(self)
   }
 
+  /// Decodes the [EncodedCollab] from shared bytes. The state vector and the doc state are slices
+  /// of `encoded` instead of copies, unlike [EncodedCollab::decode_from_bytes].
+  pub fn decode_from_shared_bytes(encoded: Bytes) -> Result<EncodedCollab, bincode::Error> {
+    let encoded_ref = EncodedCollabRef::decode_from_bytes(&encoded)?;
+    Ok(EncodedCollab {
+      state_vector: encoded.slice_ref(encoded_ref.state_vector),
+      doc_state: encoded.slice_ref(encoded_ref.doc_state),
+      version: encoded_ref.version,
+    })
+  }
+
   pub fn decode_from_bytes(encoded: &[u8]) -> Result<EncodedCollab, bincode::Error> {
     // The deserialize_encoded_collab function first tries to deserialize the data as EncodedCollab.
     // If it fails (presumably because the data was serialized with EncodedCollabV0), it then tries to deserialize as EncodedCollabV0.
@@ -64,6 +75,52 @@ pub struct EncodedCollabV0 {
   pub doc_state: Bytes,
 }
 
+/// A borrowed [EncodedCollab]. Decoding it doesn't copy the state vector and the doc state, they
+/// point into the encoded bytes. The bytes can be a memory-mapped snapshot file, so a large collab
+/// can be opened without reading the whole file into memory first.
+#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct EncodedCollabRef<'a> {
+  #[serde(borrow)]
+  pub state_vector: &'a [u8],
+  #[serde(borrow)]
+  pub doc_state: &'a [u8],
+  #[serde(default)]
+  pub version: EncoderVersion,
+}
+
+#[derive(Deserialize)]
+struct EncodedCollabV0Ref<'a> {
+  #[serde(borrow)]
+  state_vector: &'a [u8],
+  #[serde(borrow)]
+  doc_state: &'a [u8],
+}
+
+impl<'a> EncodedCollabRef<'a> {
+  /// Same as [EncodedCollab::decode_from_bytes], without copying the data.
+  pub fn decode_from_bytes(encoded: &'a [u8]) -> Result<EncodedCollabRef<'a>, bincode::Error> {
+    match bincode::deserialize::<EncodedCollabRef>(encoded) {
+      Ok(new_collab) => Ok(new_collab),
+      Err(_) => {
+        let old_collab: EncodedCollabV0Ref = bincode::deserialize(encoded)?;
+        Ok(EncodedCollabRef {
+          state_vector: old_collab.state_vector,
+          doc_state: old_collab.doc_state,
+          version: EncoderVersion::V1,
+        })
+      },
+    }
+  }
+
+  pub fn to_owned(&self) -> EncodedCollab {
+    EncodedCollab {
+      state_vector: Bytes::copy_from_slice(self.state_vector),
+      doc_state: Bytes::copy_from_slice(self.doc_state),
+      version: self.version.clone(),
+    }
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -105,4 +162,42 @@ mod tests {
       new_encoded_collab.state_vector
     );
   }
+
+  #[test]
+  fn encoded_collab_ref_points_into_encoded_bytes() {
+    let encoded_collab = EncodedCollab::new_v2(vec![1, 2, 3], vec![4, 5, 6, 7]);
+    let encoded = encoded_collab.encode_to_bytes().unwrap();
+
+    let encoded_ref = EncodedCollabRef::decode_from_bytes(&encoded).unwrap();
+    assert_eq!(encoded_ref.state_vector, &[1, 2, 3]);
+    assert_eq!(encoded_ref.doc_state, &[4, 5, 6, 7]);
+    assert_eq!(encoded_ref.version, EncoderVersion::V2);
+    let range = encoded.as_ptr_range();
+    assert!(range.contains(&encoded_ref.doc_state.as_ptr()));
+    assert_eq!(encoded_ref.to_owned(), encoded_collab);
+  }
+
+  #[test]
+  fn old_encoded_collab_decoded_into_encoded_collab_ref() {
+    let old_encoded_collab = EncodedCollabV0 {
+      state_vector: Bytes::from(vec![1, 2, 3]),
+      doc_state: Bytes::from(vec![4, 5, 6]),
+    };
+    let old_encoded_collab_bytes = bincode::serialize(&old_encoded_collab).unwrap();
+    let encoded_ref = EncodedCollabRef::decode_from_bytes(&old_encoded_collab_bytes).unwrap();
+    assert_eq!(encoded_ref.state_vector, &[1, 2, 3]);
+    assert_eq!(encoded_ref.doc_state, &[4, 5, 6]);
+    assert_eq!(encoded_ref.version, EncoderVersion::V1);
+  }
+
+  #[test]
+  fn encoded_collab_decoded_from_shared_bytes() {
+    let encoded_collab = EncodedCollab::new_v1(vec![1, 2, 3], vec![4, 5, 6]);
+    let encoded = Bytes::from(encoded_collab.encode_to_bytes().unwrap());
+    let decoded = EncodedCollab::decode_from_shared_bytes(encoded.clone()).unwrap();
+    assert_eq!(decoded, encoded_collab);
+    let range = encoded.as_ptr_range();
+    assert!(range.contains(&decoded.doc_state.as_ptr()));
+    assert!(range.contains(&decoded.state_vector.as_ptr()));
+  }
 }
@@ -1,4 +1,7 @@
+use std::fmt::Display;
+
 use anyhow::anyhow;
+use serde_repr::{Deserialize_repr, Serialize_repr};
 use yrs::TransactionAcqError;
 
 #[derive(Debug, thiserror::Error)]
@@ -24,6 +27,9 @@ pub enum CollabError {
   #[error("UndoManager is not enabled")]
   UndoManagerNotEnabled,
 
+  #[error("The undo stack of {0} was changed outside of the undo group")]
+  UndoGroupOutOfSync(String),
+
   #[error(transparent)]
   DecodeUpdate(#[from] yrs::encoding::read::Error),
 
@@ -36,8 +42,17 @@ pub enum CollabError {
   #[error("Failed to apply update: {0}")]
   UpdateFailed(#[from] yrs::error::UpdateError),
 
+  #[error("The update of {size} bytes exceeds the max update size of {max_size} bytes")]
+  UpdateTooLarge { size: usize, max_size: usize },
+
   #[error("Internal failure: {0}")]
   Internal(#[from] anyhow::Error),
+
+  #[error("{context}: {source}")]
+  Context {
+    context: String,
+    source: Box<CollabError>,
+  },
 }
 
 impl From<TransactionAcqError> for CollabError {
@@ -49,3 +64,171 @@ impl From<TransactionAcqError> for CollabError {
     }
   }
 }
+
+impl ClassifiedError for CollabError {
+  fn code(&self) -> ErrorCode {
+    match self {
+      CollabError::SerdeJson(_) => ErrorCode::InvalidData,
+      CollabError::UnexpectedEmpty(_) => ErrorCode::UnexpectedEmpty,
+      CollabError::AcquiredWriteTxnFail | CollabError::AcquiredReadTxnFail => {
+        ErrorCode::AcquireTxnFailed
+      },
+      CollabError::YrsTransactionError(_) | CollabError::UpdateFailed(_) => ErrorCode::ApplyUpdate,
+      CollabError::YrsEncodeStateError(_) => ErrorCode::EncodeState,
+      CollabError::UndoManagerNotEnabled | CollabError::UndoGroupOutOfSync(_) => {
+        ErrorCode::UndoManager
+      },
+      CollabError::DecodeUpdate(_) => ErrorCode::DecodeUpdate,
+      CollabError::NoRequiredData(_) => ErrorCode::NoRequiredData,
+      CollabError::Awareness(_) => ErrorCode::Awareness,
+      CollabError::UpdateTooLarge { .. } => ErrorCode::SyncPayloadTooLarge,
+      CollabError::Internal(_) => ErrorCode::Internal,
+      CollabError::Context { source, .. } => source.code(),
+    }
+  }
+}
+
+impl ErrorContext for CollabError {
+  fn context<C: Display>(self, context: C) -> Self {
+    CollabError::Context {
+      context: context.to_string(),
+      source: Box::new(self),
+    }
+  }
+}
+
+/// The stable code of an error. The codes are shared by the errors of all the collab crates, so
+/// the applications can map a failure to a user-facing message without knowing which crate
+/// produced it. A code is never renumbered or reused once it's released.
+///
+/// The codes are grouped by the layer that produces them:
+/// - 1xxx: general failures
+/// - 2xxx: the collab core
+/// - 3xxx: the persistence
+/// - 4xxx: the document
+/// - 5xxx: the database
+/// - 6xxx: the sync
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize_repr, Deserialize_repr)]
+#[repr(u32)]
+pub enum ErrorCode {
+  Internal = 1000,
+  InvalidData = 1001,
+  NoRequiredData = 1002,
+  RecordNotFound = 1003,
+  RecordAlreadyExist = 1004,
+  UnexpectedEmpty = 1005,
+  Cancelled = 1006,
+
+  AcquireTxnFailed = 2000,
+  DecodeUpdate = 2001,
+  ApplyUpdate = 2002,
+  EncodeState = 2003,
+  UndoManager = 2004,
+  Awareness = 2005,
+
+  StorageCorruption = 3000,
+  StorageRepairFailed = 3001,
+  StorageBusy = 3002,
+  StorageIO = 3003,
+  StorageEncoding = 3004,
+  DuplicateUpdateKey = 3005,
+  LatestUpdateKeyNotExist = 3006,
+
+  BlockNotFound = 4000,
+  BlockAlreadyExists = 4001,
+  InvalidBlockOperation = 4002,
+  ParseDocument = 4003,
+
+  DatabaseNotExist = 5000,
+  DatabaseViewNotExist = 5001,
+  DatabaseRowNotFound = 5002,
+  InvalidId = 5003,
+  ImportData = 5004,
+
+  SyncDecode = 6000,
+  SyncIO = 6001,
+  SyncTask = 6002,
+  SyncPayloadTooLarge = 6003,
+}
+
+impl ErrorCode {
+  pub fn value(&self) -> u32 {
+    *self as u32
+  }
+
+  /// Return true if the same operation may succeed when it's performed again later, for example
+  /// when the storage was busy or the transaction couldn't be acquired in time.
+  pub fn is_retryable(&self) -> bool {
+    matches!(
+      self,
+      ErrorCode::AcquireTxnFailed
+        | ErrorCode::StorageBusy
+        | ErrorCode::StorageIO
+        | ErrorCode::SyncIO
+        | ErrorCode::SyncTask
+    )
+  }
+
+  /// Return true if the error means that some of the persisted or received data can't be read
+  /// anymore. The applications should offer to restore the data from a snapshot or the server.
+  pub fn is_data_loss(&self) -> bool {
+    matches!(
+      self,
+      ErrorCode::DecodeUpdate | ErrorCode::StorageCorruption | ErrorCode::StorageRepairFailed
+    )
+  }
+}
+
+impl Display for ErrorCode {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{:?}({})", self, self.value())
+  }
+}
+
+/// Implemented by the errors of the collab crates to expose their [ErrorCode]. An error that
+/// wraps another error of the collab crates returns the code of the wrapped error.
+pub trait ClassifiedError: std::error::Error {
+  fn code(&self) -> ErrorCode;
+
+  fn is_retryable(&self) -> bool {
+    self.code().is_retryable()
+  }
+
+  fn is_data_loss(&self) -> bool {
+    self.code().is_data_loss()
+  }
+}
+
+/// Wraps an error with a message that describes what was being done when the error happened.
+/// Unlike [anyhow::Context], the wrapped error keeps its type and its [ErrorCode].
+pub trait ErrorContext: Sized {
+  fn context<C: Display>(self, context: C) -> Self;
+}
+
+pub trait ResultExt<T, E> {
+  /// Wraps the error, if any, with the given context.
+  fn err_context<C: Display>(self, context: C) -> Result<T, E>;
+
+  /// Wraps the error, if any, with the context returned by `f`. The closure is only called when
+  /// the result is an error.
+  fn with_err_context<C: Display, F: FnOnce() -> C>(self, f: F) -> Result<T, E>;
+}
+
+impl<T, E: ErrorContext> ResultExt<T, E> for Result<T, E> {
+  fn err_context<C: Display>(self, context: C) -> Result<T, E> {
+    self.map_err(|err| err.context(context))
+  }
+
+  fn with_err_context<C: Display, F: FnOnce() -> C>(self, f: F) -> Result<T, E> {
+    self.map_err(|err| err.context(f()))
+  }
+}
+
+/// Return the code of the collab error wrapped by the [anyhow::Error], or [ErrorCode::Internal]
+/// if it wraps something else.
+pub fn anyhow_error_code(error: &anyhow::Error) -> ErrorCode {
+  error
+    .downcast_ref::<CollabError>()
+    .map(|err| err.code())
+    .unwrap_or(ErrorCode::Internal)
+}
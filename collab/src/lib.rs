@@ -19,6 +19,8 @@ pub mod core;
 pub mod entity;
 pub mod error;
 pub mod lock;
+#[cfg(feature = "test_utils")]
+pub mod test_utils;
 pub mod util;
 
 pub mod preclude {
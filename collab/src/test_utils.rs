@@ -0,0 +1,177 @@
+//! Helpers to generate collabs and compare their JSON in the tests of the collab crates and of
+//! the downstream projects. Only available with the `test_utils` feature.
+
+use std::fmt::Write;
+use std::ops::Range;
+
+use serde_json::Value;
+
+const WORDS: &[&str] = &[
+  "apple", "breeze", "canvas", "delta", "ember", "forest", "garden", "harbor", "island", "jungle",
+  "kettle", "lantern", "meadow", "nectar", "orbit", "pebble", "quartz", "river", "summit",
+  "timber", "umbrella", "valley", "willow", "yonder", "zephyr",
+];
+
+/// A small deterministic random number generator (SplitMix64). The same seed always generates the
+/// same values, on every platform and with every version of the crate, so a failure reported with
+/// its seed can be reproduced.
+#[derive(Debug, Clone)]
+pub struct SeededRng {
+  state: u64,
+}
+
+impl SeededRng {
+  pub fn new(seed: u64) -> Self {
+    Self { state: seed }
+  }
+
+  pub fn next_u64(&mut self) -> u64 {
+    self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = self.state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+  }
+
+  /// Return a value in the given range. Return the start of the range if it's empty.
+  pub fn gen_range(&mut self, range: Range<usize>) -> usize {
+    if range.is_empty() {
+      return range.start;
+    }
+    range.start + (self.next_u64() % (range.end - range.start) as u64) as usize
+  }
+
+  /// Return true with the given probability, between 0.0 and 1.0.
+  pub fn gen_bool(&mut self, probability: f64) -> bool {
+    ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < probability
+  }
+
+  pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+    &items[self.gen_range(0..items.len())]
+  }
+
+  /// Return a random version 4 uuid.
+  pub fn gen_uuid(&mut self) -> String {
+    let mut bytes = [0u8; 16];
+    bytes[..8].copy_from_slice(&self.next_u64().to_le_bytes());
+    bytes[8..].copy_from_slice(&self.next_u64().to_le_bytes());
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let mut hex = String::with_capacity(32);
+    for byte in bytes {
+      let _ = write!(hex, "{:02x}", byte);
+    }
+    format!(
+      "{}-{}-{}-{}-{}",
+      &hex[..8],
+      &hex[8..12],
+      &hex[12..16],
+      &hex[16..20],
+      &hex[20..]
+    )
+  }
+
+  pub fn gen_word(&mut self) -> &'static str {
+    WORDS[self.gen_range(0..WORDS.len())]
+  }
+
+  /// Return the given number of words separated by spaces.
+  pub fn gen_sentence(&mut self, num_words: usize) -> String {
+    (0..num_words)
+      .map(|_| self.gen_word())
+      .collect::<Vec<_>>()
+      .join(" ")
+  }
+}
+
+/// Asserts that all the values of `expected` are in `actual`. The objects of `actual` may contain
+/// more keys than the objects of `expected`, the arrays must have the same length. Panics with
+/// the path of every difference.
+pub fn assert_json_include(actual: &Value, expected: &Value) {
+  let mut differences = vec![];
+  diff_json(actual, expected, "$", &[], true, &mut differences);
+  assert_no_differences(actual, differences);
+}
+
+/// Asserts that `actual` and `expected` are equal, ignoring the values of the given keys at any
+/// depth, for example the generated ids or the timestamps.
+pub fn assert_json_eq_ignoring(actual: &Value, expected: &Value, ignored_keys: &[&str]) {
+  let mut differences = vec![];
+  diff_json(actual, expected, "$", ignored_keys, false, &mut differences);
+  assert_no_differences(actual, differences);
+}
+
+fn assert_no_differences(actual: &Value, differences: Vec<String>) {
+  if !differences.is_empty() {
+    panic!(
+      "json mismatch:\n{}\nactual: {}",
+      differences.join("\n"),
+      serde_json::to_string_pretty(actual).unwrap_or_default()
+    );
+  }
+}
+
+fn diff_json(
+  actual: &Value,
+  expected: &Value,
+  path: &str,
+  ignored_keys: &[&str],
+  include: bool,
+  differences: &mut Vec<String>,
+) {
+  match (actual, expected) {
+    (Value::Object(actual), Value::Object(expected)) => {
+      for (key, expected_value) in expected {
+        if ignored_keys.contains(&key.as_str()) {
+          continue;
+        }
+        let path = format!("{}.{}", path, key);
+        match actual.get(key) {
+          None => differences.push(format!("{}: missing", path)),
+          Some(actual_value) => diff_json(
+            actual_value,
+            expected_value,
+            &path,
+            ignored_keys,
+            include,
+            differences,
+          ),
+        }
+      }
+      if !include {
+        for key in actual.keys() {
+          if !expected.contains_key(key) && !ignored_keys.contains(&key.as_str()) {
+            differences.push(format!("{}.{}: unexpected", path, key));
+          }
+        }
+      }
+    },
+    (Value::Array(actual), Value::Array(expected)) => {
+      if actual.len() != expected.len() {
+        differences.push(format!(
+          "{}: expected {} items, found {}",
+          path,
+          expected.len(),
+          actual.len()
+        ));
+        return;
+      }
+      for (index, (actual_value, expected_value)) in actual.iter().zip(expected).enumerate() {
+        let path = format!("{}[{}]", path, index);
+        diff_json(
+          actual_value,
+          expected_value,
+          &path,
+          ignored_keys,
+          include,
+          differences,
+        );
+      }
+    },
+    (actual, expected) => {
+      if actual != expected {
+        differences.push(format!("{}: expected {}, found {}", path, expected, actual));
+      }
+    },
+  }
+}
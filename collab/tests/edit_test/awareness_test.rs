@@ -1,3 +1,7 @@
+use collab::core::awareness_expiry::{
+  awareness_cleanup, awareness_now, remove_outdated_states, AwarenessConfig,
+};
+use collab::lock::RwLock;
 use collab::preclude::Collab;
 use serde_json::json;
 use std::collections::{HashMap, HashSet};
@@ -139,3 +143,75 @@ async fn clean_awareness_state_sync_test() {
     .count();
   assert_eq!(states, 1);
 }
+
+#[tokio::test]
+async fn remove_outdated_awareness_states_test() {
+  let mut c1 = Collab::new(1, "1", "1", vec![], true);
+  c1.emit_awareness_state();
+  let mut c2 = Collab::new(2, "1", "2", vec![], true);
+  c2.emit_awareness_state();
+  let u2 = c2.get_awareness().update().unwrap();
+  c1.get_mut_awareness().apply_update(u2).unwrap();
+  let (tx, rx) = mpsc::sync_channel(2);
+  let _update = c1.get_awareness().on_update(move |_, event, _| {
+    tx.send(event.clone()).unwrap();
+  });
+
+  let config = AwarenessConfig::new(Duration::from_secs(30));
+  let now = awareness_now(config.clock.as_ref());
+  assert!(remove_outdated_states(c1.get_awareness(), &config, now).is_empty());
+
+  // The local state is renewed instead of being removed.
+  let local_clock = c1.get_awareness().meta(c1.client_id()).unwrap().0;
+  let later = now + 31_000;
+  let removed = remove_outdated_states(c1.get_awareness(), &config, later);
+  assert_eq!(removed, vec![c2.client_id()]);
+  let renewed = rx.recv().unwrap();
+  assert_eq!(renewed.updated(), &[c1.client_id()]);
+  let left = rx.recv().unwrap();
+  assert_eq!(left.removed(), &[c2.client_id()]);
+  assert!(c1.get_awareness().meta(c1.client_id()).unwrap().0 > local_clock);
+  assert!(c1
+    .get_awareness()
+    .state::<serde_json::Value>(c2.client_id())
+    .is_none());
+  assert_eq!(
+    c1.get_awareness().local_state::<serde_json::Value>(),
+    Some(json!({"uid": 1}))
+  );
+}
+
+#[tokio::test]
+async fn awareness_cleanup_task_test() {
+  let collab = Arc::new(RwLock::new(Collab::new(1, "1", "1", vec![], true)));
+  let mut c2 = Collab::new(2, "1", "2", vec![], true);
+  c2.emit_awareness_state();
+  let u2 = c2.get_awareness().update().unwrap();
+  collab
+    .write()
+    .await
+    .get_mut_awareness()
+    .apply_update(u2)
+    .unwrap();
+
+  let config = AwarenessConfig::new(Duration::from_millis(100));
+  let task = tokio::spawn(awareness_cleanup(
+    Arc::downgrade(&collab),
+    config,
+    tokio::time::sleep,
+  ));
+  sleep(Duration::from_millis(300)).await;
+  assert!(collab
+    .read()
+    .await
+    .get_awareness()
+    .state::<serde_json::Value>(c2.client_id())
+    .is_none());
+
+  // The task stops once the collab is dropped.
+  drop(collab);
+  tokio::time::timeout(Duration::from_secs(1), task)
+    .await
+    .unwrap()
+    .unwrap();
+}
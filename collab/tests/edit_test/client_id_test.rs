@@ -0,0 +1,82 @@
+use collab::core::client_id::deterministic_client_ids;
+use collab::core::collab::DataSource;
+use collab::core::origin::CollabOrigin;
+use collab::preclude::{Collab, CollabBuilder, Map, MapRef, Text, TextRef};
+use yrs::updates::decoder::Decode;
+use yrs::{ReadTxn, StateVector, Update};
+
+/// Two collabs edit the same text and map key concurrently, then exchange their updates. Return
+/// the merged state of both collabs.
+fn concurrent_edit_merged_states() -> (Vec<u8>, Vec<u8>) {
+  let mut collab_1 = Collab::new_with_origin(CollabOrigin::Empty, "1", vec![], false);
+  let mut collab_2 = Collab::new_with_origin(CollabOrigin::Empty, "1", vec![], false);
+  for (collab, value) in [(&mut collab_1, "one"), (&mut collab_2, "two")] {
+    let mut txn = collab.context.transact_mut();
+    let text: TextRef = collab.data.get_or_init(&mut txn, "text");
+    text.insert(&mut txn, 0, value);
+    let map: MapRef = collab.data.get_or_init(&mut txn, "map");
+    map.insert(&mut txn, "key", value);
+  }
+
+  let update_1 = encode_state(&collab_1);
+  let update_2 = encode_state(&collab_2);
+  collab_1
+    .apply_update(Update::decode_v1(&update_2).unwrap())
+    .unwrap();
+  collab_2
+    .apply_update(Update::decode_v1(&update_1).unwrap())
+    .unwrap();
+  (encode_state(&collab_1), encode_state(&collab_2))
+}
+
+fn encode_state(collab: &Collab) -> Vec<u8> {
+  collab
+    .transact()
+    .encode_state_as_update_v1(&StateVector::default())
+}
+
+#[test]
+fn deterministic_client_ids_test() {
+  let guard = deterministic_client_ids(10);
+  let collab_1 = Collab::new_with_origin(CollabOrigin::Empty, "1", vec![], false);
+  let collab_2 = Collab::new_with_origin(CollabOrigin::Empty, "1", vec![], false);
+  assert_eq!(collab_1.client_id(), 10);
+  assert_eq!(collab_2.client_id(), 11);
+
+  {
+    let _nested = deterministic_client_ids(100);
+    let collab = Collab::new_with_origin(CollabOrigin::Empty, "1", vec![], false);
+    assert_eq!(collab.client_id(), 100);
+  }
+  let collab_3 = Collab::new_with_origin(CollabOrigin::Empty, "1", vec![], false);
+  assert_eq!(collab_3.client_id(), 12);
+
+  drop(guard);
+  let collab_4 = Collab::new_with_origin(CollabOrigin::Empty, "1", vec![], false);
+  let collab_5 = Collab::new_with_origin(CollabOrigin::Empty, "1", vec![], false);
+  assert_ne!(collab_4.client_id(), collab_5.client_id());
+}
+
+#[test]
+fn concurrent_edits_merge_to_identical_states_test() {
+  let first_run = {
+    let _guard = deterministic_client_ids(1);
+    concurrent_edit_merged_states()
+  };
+  let second_run = {
+    let _guard = deterministic_client_ids(1);
+    concurrent_edit_merged_states()
+  };
+  assert_eq!(first_run.0, first_run.1);
+  assert_eq!(first_run, second_run);
+}
+
+#[test]
+fn collab_builder_client_id_test() {
+  let collab = CollabBuilder::new(1, "1", DataSource::Disk(None))
+    .with_device_id("1")
+    .with_client_id(42)
+    .build()
+    .unwrap();
+  assert_eq!(collab.client_id(), 42);
+}
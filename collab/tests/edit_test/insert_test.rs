@@ -178,3 +178,27 @@ async fn undo_second_insert_text() {
 
   assert!(!collab.can_undo());
 }
+
+#[tokio::test]
+async fn reject_oversized_update_test() {
+  let mut collab = Collab::new(1, "1", "1", vec![], false);
+  collab
+    .transact_mut_with_max_update_size(1024, |txn, data| {
+      data.insert(txn, "text", "hello world");
+    })
+    .unwrap();
+
+  let result = collab.transact_mut_with_max_update_size(32, |txn, data| {
+    data.insert(txn, "text", "a".repeat(100));
+  });
+  assert_matches!(
+    result,
+    Err(CollabError::UpdateTooLarge { max_size: 32, .. })
+  );
+  // The rejected change is rolled back.
+  let s: String = collab
+    .data
+    .get_with_path(&collab.transact(), ["text"])
+    .unwrap();
+  assert_eq!(s, "hello world".to_string());
+}
@@ -1,5 +1,7 @@
 mod awareness_test;
+mod client_id_test;
 mod insert_test;
 mod observer_test;
 mod restore_test;
 mod state_vec_test;
+mod undo_group_test;
@@ -2,7 +2,10 @@
 
 use assert_json_diff::assert_json_eq;
 use collab::core::collab::{CollabBuilder, DataSource};
+use collab::core::mapped_file::MappedFile;
 use collab::core::origin::CollabOrigin;
+use collab::entity::{EncodedCollabRef, EncoderVersion};
+use collab::error::CollabError;
 
 use collab::preclude::{Collab, CollabPlugin, MapExt};
 use serde_json::json;
@@ -489,3 +492,59 @@ impl CollabPlugin for ReceiveUpdatesPlugin {
     CollabPluginType::Other("ReceiveUpdatesPlugin".to_string())
   }
 }
+
+#[tokio::test]
+async fn restore_from_encoded_collab_ref_test() {
+  let mut collab = Collab::new_with_origin(CollabOrigin::Empty, "test", vec![], false);
+  collab.insert("1", "a");
+  collab.insert("2", "b");
+  for version in [EncoderVersion::V1, EncoderVersion::V2] {
+    let encoded_collab = match version {
+      EncoderVersion::V1 => collab
+        .encode_collab_v1(|_| Ok::<_, CollabError>(()))
+        .unwrap(),
+      EncoderVersion::V2 => collab.encode_collab_v2(),
+    };
+    let encoded = encoded_collab.encode_to_bytes().unwrap();
+
+    let encoded_ref = EncodedCollabRef::decode_from_bytes(&encoded).unwrap();
+    assert_eq!(encoded_ref.version, version);
+    let restored =
+      Collab::new_with_encoded_ref(CollabOrigin::Empty, "test", encoded_ref, vec![], false)
+        .unwrap();
+    assert_eq!(restored.to_json(), collab.to_json());
+  }
+}
+
+#[tokio::test]
+async fn restore_from_encoded_collab_file_test() {
+  let mut collab = Collab::new_with_origin(CollabOrigin::Empty, "test", vec![], false);
+  collab.insert("1", "a");
+  collab.insert("2", "b");
+  let encoded = collab
+    .encode_collab_v1(|_| Ok::<_, CollabError>(()))
+    .unwrap()
+    .encode_to_bytes()
+    .unwrap();
+  let file = tempfile::NamedTempFile::new().unwrap();
+  std::fs::write(file.path(), &encoded).unwrap();
+
+  // Safety: the temporary files are only written before they're mapped.
+  let mapped = unsafe { MappedFile::open(file.path()) }.unwrap();
+  assert_eq!(&*mapped, encoded.as_slice());
+  let restored = unsafe {
+    Collab::new_with_encoded_file(CollabOrigin::Empty, "test", file.path(), vec![], false)
+  }
+  .unwrap();
+  assert_eq!(restored.to_json(), collab.to_json());
+
+  // An empty file is not an encoded collab.
+  let empty = tempfile::NamedTempFile::new().unwrap();
+  assert!(unsafe { MappedFile::open(empty.path()) }
+    .unwrap()
+    .is_empty());
+  assert!(unsafe {
+    Collab::new_with_encoded_file(CollabOrigin::Empty, "test", empty.path(), vec![], false)
+  }
+  .is_err());
+}
@@ -0,0 +1,139 @@
+use assert_matches2::assert_matches;
+use collab::core::collab_undo::{UndoGroup, WorkspaceUndoManager};
+use collab::error::CollabError;
+use collab::preclude::Collab;
+
+fn undoable_collab(object_id: &str) -> Collab {
+  let mut collab = Collab::new(1, object_id, "1", vec![], false);
+  collab.enable_undo_redo();
+  collab
+}
+
+#[tokio::test]
+async fn undo_group_across_collabs_test() {
+  let mut folder = undoable_collab("folder");
+  let mut document = undoable_collab("document");
+  folder.insert("before", "1");
+
+  let mut manager = WorkspaceUndoManager::new();
+  let mut group = UndoGroup::new();
+  group
+    .edit(&mut folder, |collab| collab.insert("page", "new page"))
+    .unwrap();
+  group
+    .edit(&mut document, |collab| collab.insert("link", "new page"))
+    .unwrap();
+  assert_eq!(group.object_ids(), vec!["folder", "document"]);
+  manager.push(group);
+
+  assert!(manager.undo(&mut [&mut folder, &mut document]).unwrap());
+  assert_json_diff::assert_json_eq!(folder.to_json(), serde_json::json!({ "before": "1" }));
+  assert_json_diff::assert_json_eq!(document.to_json(), serde_json::json!({}));
+  assert!(!manager.can_undo());
+  assert!(manager.can_redo());
+
+  assert!(manager.redo(&mut [&mut document, &mut folder]).unwrap());
+  assert_json_diff::assert_json_eq!(
+    folder.to_json(),
+    serde_json::json!({ "before": "1", "page": "new page" })
+  );
+  assert_json_diff::assert_json_eq!(
+    document.to_json(),
+    serde_json::json!({ "link": "new page" })
+  );
+  assert!(!manager.redo(&mut [&mut folder, &mut document]).unwrap());
+}
+
+#[tokio::test]
+async fn undo_group_with_several_edits_of_one_collab_test() {
+  let mut folder = undoable_collab("folder");
+  let mut document = undoable_collab("document");
+
+  let mut manager = WorkspaceUndoManager::new();
+  let mut group = UndoGroup::new();
+  group
+    .edit(&mut folder, |collab| collab.insert("1", "a"))
+    .unwrap();
+  group
+    .edit(&mut document, |collab| collab.insert("2", "b"))
+    .unwrap();
+  group
+    .edit(&mut folder, |collab| collab.insert("3", "c"))
+    .unwrap();
+  manager.push(group);
+
+  manager.undo(&mut [&mut folder, &mut document]).unwrap();
+  assert_json_diff::assert_json_eq!(folder.to_json(), serde_json::json!({}));
+  assert_json_diff::assert_json_eq!(document.to_json(), serde_json::json!({}));
+
+  manager.redo(&mut [&mut folder, &mut document]).unwrap();
+  assert_json_diff::assert_json_eq!(folder.to_json(), serde_json::json!({ "1": "a", "3": "c" }));
+  assert_json_diff::assert_json_eq!(document.to_json(), serde_json::json!({ "2": "b" }));
+}
+
+#[tokio::test]
+async fn undo_groups_in_order_test() {
+  let mut collab = undoable_collab("document");
+  let mut manager = WorkspaceUndoManager::new();
+  for (key, value) in [("1", "a"), ("2", "b")] {
+    let mut group = UndoGroup::new();
+    group
+      .edit(&mut collab, |collab| collab.insert(key, value))
+      .unwrap();
+    manager.push(group);
+  }
+
+  manager.undo(&mut [&mut collab]).unwrap();
+  assert_json_diff::assert_json_eq!(collab.to_json(), serde_json::json!({ "1": "a" }));
+  manager.undo(&mut [&mut collab]).unwrap();
+  assert_json_diff::assert_json_eq!(collab.to_json(), serde_json::json!({}));
+  assert!(!manager.undo(&mut [&mut collab]).unwrap());
+}
+
+#[tokio::test]
+async fn empty_undo_group_is_ignored_test() {
+  let mut collab = undoable_collab("document");
+  let mut manager = WorkspaceUndoManager::new();
+  let mut group = UndoGroup::new();
+  group.edit(&mut collab, |_| {}).unwrap();
+  assert!(group.is_empty());
+  manager.push(group);
+  assert!(!manager.can_undo());
+}
+
+#[tokio::test]
+async fn undo_group_out_of_sync_test() {
+  let mut folder = undoable_collab("folder");
+  let mut document = undoable_collab("document");
+
+  let mut manager = WorkspaceUndoManager::new();
+  let mut group = UndoGroup::new();
+  group
+    .edit(&mut folder, |collab| collab.insert("page", "new page"))
+    .unwrap();
+  group
+    .edit(&mut document, |collab| collab.insert("link", "new page"))
+    .unwrap();
+  manager.push(group);
+
+  // An edit made outside of the groups is on top of the undo stack of the document.
+  document.undo_manager_mut().unwrap().reset();
+  document.insert("text", "hello");
+  let result = manager.undo(&mut [&mut folder, &mut document]);
+  assert_matches!(result, Err(CollabError::UndoGroupOutOfSync(object_id)));
+  assert_eq!(object_id, "document");
+
+  // Nothing was undone and the group can still be undone.
+  assert_json_diff::assert_json_eq!(folder.to_json(), serde_json::json!({ "page": "new page" }));
+  assert!(manager.can_undo());
+
+  // A collab of the group is missing.
+  document.undo().unwrap();
+  let result = manager.undo(&mut [&mut folder]);
+  assert_matches!(result, Err(CollabError::UndoGroupOutOfSync(object_id)));
+  assert_eq!(object_id, "document");
+
+  assert!(manager.undo(&mut [&mut folder, &mut document]).unwrap());
+  assert_json_diff::assert_json_eq!(folder.to_json(), serde_json::json!({}));
+  assert_json_diff::assert_json_eq!(document.to_json(), serde_json::json!({}));
+}
@@ -0,0 +1,66 @@
+use std::error::Error;
+
+use anyhow::anyhow;
+use collab::error::{
+  anyhow_error_code, ClassifiedError, CollabError, ErrorCode, ErrorContext, ResultExt,
+};
+
+#[test]
+fn error_code_value_test() {
+  assert_eq!(ErrorCode::Internal.value(), 1000);
+  assert_eq!(ErrorCode::AcquireTxnFailed.value(), 2000);
+  assert_eq!(ErrorCode::StorageCorruption.value(), 3000);
+
+  let json = serde_json::to_string(&ErrorCode::DecodeUpdate).unwrap();
+  assert_eq!(json, "2001");
+  let code: ErrorCode = serde_json::from_str(&json).unwrap();
+  assert_eq!(code, ErrorCode::DecodeUpdate);
+  assert!(serde_json::from_str::<ErrorCode>("1").is_err());
+}
+
+#[test]
+fn classify_collab_error_test() {
+  let error = CollabError::AcquiredWriteTxnFail;
+  assert_eq!(error.code(), ErrorCode::AcquireTxnFailed);
+  assert!(error.is_retryable());
+  assert!(!error.is_data_loss());
+
+  let error = CollabError::from(yrs::encoding::read::Error::EndOfBuffer(1));
+  assert_eq!(error.code(), ErrorCode::DecodeUpdate);
+  assert!(!error.is_retryable());
+  assert!(error.is_data_loss());
+
+  let error = CollabError::NoRequiredData("data".to_string());
+  assert!(!error.is_retryable());
+  assert!(!error.is_data_loss());
+}
+
+#[test]
+fn error_context_test() {
+  let result: Result<(), CollabError> = Err(CollabError::AcquiredReadTxnFail);
+  let error = result
+    .err_context("read document")
+    .with_err_context(|| format!("open {}", "object_1"))
+    .unwrap_err();
+
+  // The context doesn't change the code of the error
+  assert_eq!(error.code(), ErrorCode::AcquireTxnFailed);
+  assert_eq!(
+    error.to_string(),
+    "open object_1: read document: Get read txn failed"
+  );
+
+  let source = error.source().unwrap();
+  assert_eq!(source.to_string(), "read document: Get read txn failed");
+  assert_eq!(source.source().unwrap().to_string(), "Get read txn failed");
+
+  let error = CollabError::UndoManagerNotEnabled.context("undo");
+  assert_eq!(error.code(), ErrorCode::UndoManager);
+}
+
+#[test]
+fn anyhow_error_code_test() {
+  let error = anyhow::Error::from(CollabError::AcquiredWriteTxnFail);
+  assert_eq!(anyhow_error_code(&error), ErrorCode::AcquireTxnFailed);
+  assert_eq!(anyhow_error_code(&anyhow!("unknown")), ErrorCode::Internal);
+}
@@ -1,2 +1,4 @@
 mod edit_test;
+mod error_test;
+mod test_utils_test;
 mod util;
@@ -0,0 +1 @@
+mod seeded_rng_test;
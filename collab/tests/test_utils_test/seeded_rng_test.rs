@@ -0,0 +1,51 @@
+use collab::test_utils::{assert_json_eq_ignoring, assert_json_include, SeededRng};
+use serde_json::json;
+
+#[test]
+fn seeded_rng_is_deterministic_test() {
+  let mut rng_1 = SeededRng::new(42);
+  let mut rng_2 = SeededRng::new(42);
+  for _ in 0..100 {
+    assert_eq!(rng_1.next_u64(), rng_2.next_u64());
+  }
+  assert_eq!(rng_1.gen_uuid(), rng_2.gen_uuid());
+  assert_ne!(SeededRng::new(1).gen_uuid(), SeededRng::new(2).gen_uuid());
+
+  let uuid = SeededRng::new(42).gen_uuid();
+  assert_eq!(uuid.len(), 36);
+  assert_eq!(&uuid[14..15], "4");
+}
+
+#[test]
+fn seeded_rng_range_test() {
+  let mut rng = SeededRng::new(7);
+  for _ in 0..100 {
+    let value = rng.gen_range(3..8);
+    assert!((3..8).contains(&value));
+  }
+  assert_eq!(rng.gen_range(5..5), 5);
+  assert!(!rng.gen_bool(0.0));
+  assert!(rng.gen_bool(1.0));
+}
+
+#[test]
+fn assert_json_include_test() {
+  let actual = json!({"id": "1", "name": "a", "children": [{"id": "2", "name": "b"}]});
+  assert_json_include(&actual, &json!({"name": "a", "children": [{"name": "b"}]}));
+
+  let result = std::panic::catch_unwind(|| {
+    assert_json_include(&actual, &json!({"children": [{"name": "c"}]}));
+  });
+  let message = *result.unwrap_err().downcast::<String>().unwrap();
+  assert!(message.contains("$.children[0].name: expected \"c\", found \"b\""));
+}
+
+#[test]
+fn assert_json_eq_ignoring_test() {
+  let actual = json!({"id": "1", "created_at": 10, "views": [{"id": "2", "name": "b"}]});
+  let expected = json!({"id": "3", "created_at": 20, "views": [{"id": "4", "name": "b"}]});
+  assert_json_eq_ignoring(&actual, &expected, &["id", "created_at"]);
+
+  let result = std::panic::catch_unwind(|| assert_json_eq_ignoring(&actual, &expected, &["id"]));
+  assert!(result.is_err());
+}